@@ -21,7 +21,10 @@ use aptos_crypto::HashValue;
 use aptos_db_indexer::table_info_reader::TableInfoReader;
 use aptos_gas_schedule::{AptosGasParameters, FromOnChainGasSchedule};
 use aptos_logger::{error, info, Schema};
-use aptos_mempool::{MempoolClientRequest, MempoolClientSender, SubmissionStatus};
+use aptos_mempool::{
+    GasPriceEstimate, MempoolClientRequest, MempoolClientSender, SubmissionSource,
+    SubmissionStatus, TransactionEviction,
+};
 use aptos_storage_interface::{
     state_view::{DbStateView, DbStateViewAtVersion, LatestDbStateCheckpointView},
     DbReader, Order, MAX_REQUEST_LIMIT,
@@ -205,11 +208,35 @@ impl Context {
         self.node_config.api.max_submit_transaction_batch_size
     }
 
-    pub async fn submit_transaction(&self, txn: SignedTransaction) -> Result<SubmissionStatus> {
+    pub async fn submit_transaction(
+        &self,
+        txn: SignedTransaction,
+        source: SubmissionSource,
+    ) -> Result<SubmissionStatus> {
         let (req_sender, callback) = oneshot::channel();
         self.mp_sender
             .clone()
-            .send(MempoolClientRequest::SubmitTransaction(txn, req_sender))
+            .send(MempoolClientRequest::SubmitTransaction(
+                txn, source, req_sender,
+            ))
+            .await?;
+
+        callback.await?
+    }
+
+    /// Submits a group of sequential transactions of a single account, which
+    /// mempool accepts all-or-nothing (and keeps together for block inclusion).
+    pub async fn submit_transaction_group(
+        &self,
+        txns: Vec<SignedTransaction>,
+        source: SubmissionSource,
+    ) -> Result<SubmissionStatus> {
+        let (req_sender, callback) = oneshot::channel();
+        self.mp_sender
+            .clone()
+            .send(MempoolClientRequest::SubmitTransactionGroup(
+                txns, source, req_sender,
+            ))
             .await?;
 
         callback.await?
@@ -805,6 +832,28 @@ impl Context {
         callback.await.map_err(anyhow::Error::from)
     }
 
+    /// Subscribes to the eviction of the transaction with the given sender and sequence number
+    /// from mempool. The returned receiver is notified if the transaction is removed from
+    /// mempool without being committed.
+    pub async fn subscribe_transaction_eviction(
+        &self,
+        sender: AccountAddress,
+        sequence_number: u64,
+    ) -> Result<oneshot::Receiver<TransactionEviction>> {
+        let (req_sender, callback) = oneshot::channel();
+
+        self.mp_sender
+            .clone()
+            .send(MempoolClientRequest::SubscribeTransactionEviction(
+                sender,
+                sequence_number,
+                req_sender,
+            ))
+            .await?;
+
+        Ok(callback)
+    }
+
     pub fn get_transaction_by_version(
         &self,
         version: u64,
@@ -1153,6 +1202,75 @@ impl Context {
         Ok(estimation)
     }
 
+    /// Estimates the gas unit price at the given percentile, based on the ready transactions
+    /// in mempool and the transactions committed in (at most) the last `horizon_blocks` blocks.
+    pub async fn estimate_gas_price_from_mempool(
+        &self,
+        percentile: u8,
+        horizon_blocks: usize,
+    ) -> Result<GasPriceEstimate> {
+        let (req_sender, callback) = oneshot::channel();
+        self.mp_sender
+            .clone()
+            .send(MempoolClientRequest::EstimateGasPrice(
+                percentile,
+                horizon_blocks,
+                req_sender,
+            ))
+            .await?;
+
+        callback.await?
+    }
+
+    /// Refines the given gas estimation using the gas price distribution tracked by mempool (if
+    /// configured). Estimates are never lower than the deprioritized (i.e., minimum inclusion)
+    /// estimate, and the given estimation is kept if mempool has no prices to sample.
+    pub async fn refine_gas_estimation_with_mempool(
+        &self,
+        gas_estimation: GasEstimation,
+    ) -> GasEstimation {
+        let config = &self.node_config.api.gas_estimation;
+        let mempool_percentiles = match &config.mempool_percentiles {
+            Some(mempool_percentiles) if config.enabled && config.static_override.is_none() => {
+                mempool_percentiles
+            },
+            _ => return gas_estimation,
+        };
+
+        let mut estimates = vec![];
+        for percentile in [
+            mempool_percentiles.low,
+            mempool_percentiles.market,
+            mempool_percentiles.aggressive,
+        ] {
+            match self
+                .estimate_gas_price_from_mempool(percentile, mempool_percentiles.horizon_blocks)
+                .await
+            {
+                Ok(estimate) => match estimate.gas_unit_price {
+                    Some(gas_unit_price) => estimates.push(gas_unit_price),
+                    None => return gas_estimation,
+                },
+                Err(error) => {
+                    error!("Failed to estimate the gas price from mempool: {:?}", error);
+                    return gas_estimation;
+                },
+            }
+        }
+
+        let min_price = gas_estimation
+            .deprioritized_gas_estimate
+            .unwrap_or(gas_estimation.gas_estimate);
+        let low_price = estimates[0].max(min_price);
+        let market_price = estimates[1].max(low_price);
+        let aggressive_price = self.next_bucket(estimates[2].max(market_price));
+        GasEstimation {
+            deprioritized_gas_estimate: Some(low_price),
+            gas_estimate: market_price,
+            prioritized_gas_estimate: Some(aggressive_price),
+        }
+    }
+
     fn min_gas_unit_price<E: InternalError>(&self, ledger_info: &LedgerInfo) -> Result<u64, E> {
         let (_, gas_schedule) = self.get_gas_schedule(ledger_info)?;
         Ok(gas_schedule.vm.txn.min_price_per_gas_unit.into())
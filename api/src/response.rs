@@ -487,6 +487,7 @@ generate_error_traits!(
     NotFound,
     Forbidden,
     PayloadTooLarge,
+    TooManyRequests,
     Internal,
     InsufficientStorage,
     ServiceUnavailable
@@ -604,6 +605,21 @@ pub fn transaction_not_found_by_hash<E: NotFoundError>(
     )
 }
 
+pub fn transaction_evicted<S: Display, E: NotFoundError>(
+    hash: HashValue,
+    reason: S,
+    ledger_info: &LedgerInfo,
+) -> E {
+    E::not_found_with_code(
+        format!(
+            "Transaction hash({}) was evicted from mempool without being committed: {}",
+            hash, reason
+        ),
+        AptosErrorCode::TransactionEvicted,
+        ledger_info,
+    )
+}
+
 pub fn version_pruned<E: GoneError>(ledger_version: u64, ledger_info: &LedgerInfo) -> E {
     E::gone_with_code(
         format!("Ledger version({}) has been pruned", ledger_version),
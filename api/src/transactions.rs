@@ -12,10 +12,10 @@ use crate::{
     metrics::WAIT_TRANSACTION_GAUGE,
     page::Page,
     response::{
-        api_disabled, api_forbidden, transaction_not_found_by_hash,
+        api_disabled, api_forbidden, transaction_evicted, transaction_not_found_by_hash,
         transaction_not_found_by_version, version_pruned, BadRequestError, BasicError,
         BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResult, BasicResultWith404,
-        ForbiddenError, InsufficientStorageError, InternalError,
+        ForbiddenError, InsufficientStorageError, InternalError, TooManyRequestsError,
     },
     ApiTags,
 };
@@ -26,9 +26,10 @@ use aptos_api_types::{
     HexEncodedBytes, LedgerInfo, MoveType, PendingTransaction, SubmitTransactionRequest,
     Transaction, TransactionData, TransactionOnChainData, TransactionsBatchSingleSubmissionFailure,
     TransactionsBatchSubmissionResult, UserTransaction, VerifyInput, VerifyInputWithRecursion,
-    MAX_RECURSIVE_TYPES_ALLOWED, U64,
+    MAX_RECURSIVE_TYPES_ALLOWED, U64, X_APTOS_API_KEY,
 };
 use aptos_crypto::{hash::CryptoHash, signing_message};
+use aptos_mempool::SubmissionSource;
 use aptos_types::{
     account_config::CoinStoreResource,
     mempool_status::MempoolStatusCode,
@@ -40,6 +41,7 @@ use aptos_types::{
 };
 use aptos_vm::{data_cache::AsMoveResolver, AptosSimulationVM};
 use move_core_types::vm_status::VMStatus;
+use poem::http::HeaderMap;
 use poem_openapi::{
     param::{Path, Query},
     payload::Json,
@@ -55,6 +57,7 @@ generate_error_response!(
     (403, Forbidden),
     (404, NotFound),
     (413, PayloadTooLarge),
+    (429, TooManyRequests),
     (500, Internal),
     (503, ServiceUnavailable),
     (507, InsufficientStorage)
@@ -371,6 +374,7 @@ impl TransactionsApi {
     async fn submit_transaction(
         &self,
         accept_type: AcceptType,
+        headers: &HeaderMap,
         data: SubmitTransactionPost,
     ) -> SubmitTransactionResult<PendingTransaction> {
         data.verify()
@@ -389,7 +393,8 @@ impl TransactionsApi {
             .check_api_output_enabled("Submit transaction", &accept_type)?;
         let ledger_info = self.context.get_latest_ledger_info()?;
         let signed_transaction = self.get_signed_transaction(&ledger_info, data)?;
-        self.create(&accept_type, &ledger_info, signed_transaction)
+        let source = get_submission_source(headers);
+        self.create(&accept_type, &ledger_info, signed_transaction, source)
             .await
     }
 
@@ -424,6 +429,7 @@ impl TransactionsApi {
     async fn submit_transactions_batch(
         &self,
         accept_type: AcceptType,
+        headers: &HeaderMap,
         data: SubmitTransactionsBatchPost,
     ) -> SubmitTransactionsBatchResult<TransactionsBatchSubmissionResult> {
         data.verify()
@@ -453,8 +459,14 @@ impl TransactionsApi {
                 &ledger_info,
             ));
         }
-        self.create_batch(&accept_type, &ledger_info, signed_transactions_batch)
-            .await
+        let source = get_submission_source(headers);
+        self.create_batch(
+            &accept_type,
+            &ledger_info,
+            signed_transactions_batch,
+            source,
+        )
+        .await
     }
 
     /// Simulate transaction
@@ -689,30 +701,35 @@ impl TransactionsApi {
             .check_api_output_enabled("Estimate gas price", &accept_type)?;
 
         let context = self.context.clone();
-        api_spawn_blocking(move || {
-            let latest_ledger_info = context.get_latest_ledger_info()?;
-            let gas_estimation = context.estimate_gas_price(&latest_ledger_info)?;
-            Self::log_gas_estimation(&gas_estimation);
+        let (gas_estimation, latest_ledger_info) = api_spawn_blocking(move || {
+            let latest_ledger_info = context.get_latest_ledger_info::<BasicError>()?;
+            let gas_estimation = context.estimate_gas_price::<BasicError>(&latest_ledger_info)?;
+            Ok((gas_estimation, latest_ledger_info))
+        })
+        .await?;
+        let gas_estimation = self
+            .context
+            .refine_gas_estimation_with_mempool(gas_estimation)
+            .await;
+        Self::log_gas_estimation(&gas_estimation);
 
-            match accept_type {
-                AcceptType::Json => BasicResponse::try_from_json((
-                    gas_estimation,
+        match accept_type {
+            AcceptType::Json => BasicResponse::try_from_json((
+                gas_estimation,
+                &latest_ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+            AcceptType::Bcs => {
+                let gas_estimation_bcs = GasEstimationBcs {
+                    gas_estimate: gas_estimation.gas_estimate,
+                };
+                BasicResponse::try_from_bcs((
+                    gas_estimation_bcs,
                     &latest_ledger_info,
                     BasicResponseStatus::Ok,
-                )),
-                AcceptType::Bcs => {
-                    let gas_estimation_bcs = GasEstimationBcs {
-                        gas_estimate: gas_estimation.gas_estimate,
-                    };
-                    BasicResponse::try_from_bcs((
-                        gas_estimation_bcs,
-                        &latest_ledger_info,
-                        BasicResponseStatus::Ok,
-                    ))
-                },
-            }
-        })
-        .await
+                ))
+            },
+        }
     }
 }
 
@@ -765,6 +782,7 @@ impl TransactionsApi {
         wait_by_hash_poll_interval_ms: u64,
     ) -> BasicResultWith404<Transaction> {
         let start_time = std::time::Instant::now();
+        let mut eviction_receiver = None;
         loop {
             let context = self.context.clone();
             let accept_type = accept_type.clone();
@@ -785,9 +803,37 @@ impl TransactionsApi {
                 .context(format!("Failed to find transaction with hash: {}", hash))
                 .map_err(|_| transaction_not_found_by_hash(hash, &ledger_info))?;
 
-            if let TransactionData::Pending(_) = txn_data {
+            if let TransactionData::Pending(txn) = &txn_data {
                 if (start_time.elapsed().as_millis() as u64) < wait_by_hash_timeout_ms {
-                    tokio::time::sleep(Duration::from_millis(wait_by_hash_poll_interval_ms)).await;
+                    // Subscribe to the eviction of the pending transaction, so that the wait
+                    // ends as soon as mempool drops the transaction (instead of on timeout)
+                    if eviction_receiver.is_none() {
+                        eviction_receiver = self
+                            .context
+                            .subscribe_transaction_eviction(txn.sender(), txn.sequence_number())
+                            .await
+                            .ok();
+                    }
+                    let poll_interval = Duration::from_millis(wait_by_hash_poll_interval_ms);
+                    let eviction = match eviction_receiver.as_mut() {
+                        Some(receiver) => tokio::select! {
+                            eviction = receiver => Some(eviction),
+                            _ = tokio::time::sleep(poll_interval) => None,
+                        },
+                        None => {
+                            tokio::time::sleep(poll_interval).await;
+                            None
+                        },
+                    };
+                    match eviction {
+                        Some(Ok(eviction)) if HashValue::from(eviction.hash) == hash => {
+                            return Err(transaction_evicted(hash, eviction.reason, &ledger_info));
+                        },
+                        // A different transaction (with the same sender and sequence number)
+                        // was evicted, so the subscription has to be renewed.
+                        Some(_) => eviction_receiver = None,
+                        None => {},
+                    }
                     continue;
                 }
             }
@@ -1168,16 +1214,21 @@ impl TransactionsApi {
     }
 
     /// Submits a single transaction, and converts mempool codes to errors
-    async fn create_internal(&self, txn: SignedTransaction) -> Result<(), AptosError> {
+    async fn create_internal(
+        &self,
+        txn: SignedTransaction,
+        source: SubmissionSource,
+    ) -> Result<(), AptosError> {
         let (mempool_status, vm_status_opt) = self
             .context
-            .submit_transaction(txn)
+            .submit_transaction(txn, source)
             .await
             .context("Mempool failed to initially evaluate submitted transaction")
             .map_err(|err| {
                 aptos_api_types::AptosError::new_with_error_code(err, AptosErrorCode::InternalError)
             })?;
-        match mempool_status.code {
+        let mempool_rejection_reason = mempool_status.rejection_reason.map(Into::into);
+        let result = match mempool_status.code {
             MempoolStatusCode::Accepted => Ok(()),
             MempoolStatusCode::MempoolIsFull | MempoolStatusCode::TooManyTransactions => {
                 Err(AptosError::new_with_error_code(
@@ -1216,7 +1267,18 @@ impl TransactionsApi {
                 format!("Transaction was rejected with status {}", mempool_status,),
                 AptosErrorCode::InternalError,
             )),
-        }
+            MempoolStatusCode::SubmissionQuotaExceeded => Err(AptosError::new_with_error_code(
+                mempool_status.message,
+                AptosErrorCode::SubmissionQuotaExceeded,
+            )),
+            MempoolStatusCode::InvalidTransactionGroup => Err(AptosError::new_with_error_code(
+                mempool_status.message,
+                AptosErrorCode::InvalidInput,
+            )),
+        };
+
+        // Attach the structured rejection reason (if any) so clients can retry smartly
+        result.map_err(|error| error.with_mempool_rejection_reason(mempool_rejection_reason))
     }
 
     /// Submits a single transaction
@@ -1225,8 +1287,9 @@ impl TransactionsApi {
         accept_type: &AcceptType,
         ledger_info: &LedgerInfo,
         txn: SignedTransaction,
+        source: SubmissionSource,
     ) -> SubmitTransactionResult<PendingTransaction> {
-        match self.create_internal(txn.clone()).await {
+        match self.create_internal(txn.clone(), source).await {
             Ok(()) => match accept_type {
                 AcceptType::Json => {
                     let state_view = self
@@ -1282,6 +1345,9 @@ impl TransactionsApi {
                         ledger_info,
                     ),
                 ),
+                AptosErrorCode::SubmissionQuotaExceeded => Err(
+                    SubmitTransactionError::too_many_requests_from_aptos_error(error, ledger_info),
+                ),
                 _ => Err(SubmitTransactionError::internal_from_aptos_error(
                     error,
                     ledger_info,
@@ -1296,11 +1362,12 @@ impl TransactionsApi {
         accept_type: &AcceptType,
         ledger_info: &LedgerInfo,
         txns: Vec<SignedTransaction>,
+        source: SubmissionSource,
     ) -> SubmitTransactionsBatchResult<TransactionsBatchSubmissionResult> {
         // Iterate through transactions keeping track of failures
         let mut txn_failures = Vec::new();
         for (idx, txn) in txns.iter().enumerate() {
-            if let Err(error) = self.create_internal(txn.clone()).await {
+            if let Err(error) = self.create_internal(txn.clone(), source.clone()).await {
                 txn_failures.push(TransactionsBatchSingleSubmissionFailure {
                     error,
                     transaction_index: idx,
@@ -1543,6 +1610,16 @@ fn override_gas_parameters(
     SignedTransaction::new_with_authenticator(raw_txn, signed_txn.authenticator())
 }
 
+/// Returns the submission source for a request (based on the API key header, if any)
+fn get_submission_source(headers: &HeaderMap) -> SubmissionSource {
+    headers
+        .get(X_APTOS_API_KEY)
+        .and_then(|api_key| api_key.to_str().ok())
+        .filter(|api_key| !api_key.is_empty())
+        .map(|api_key| SubmissionSource::ApiKey(api_key.to_string()))
+        .unwrap_or(SubmissionSource::Anonymous)
+}
+
 enum GetByVersionResponse {
     VersionTooNew,
     VersionTooOld,
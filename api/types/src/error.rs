@@ -2,7 +2,7 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_types::vm_status::StatusCode;
+use aptos_types::{mempool_status, vm_status::StatusCode};
 use poem_openapi::{Enum, Object};
 use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
@@ -16,6 +16,8 @@ pub struct AptosError {
     pub error_code: AptosErrorCode,
     /// A code providing VM error details when submitting transactions to the VM
     pub vm_error_code: Option<u64>,
+    /// A reason providing mempool rejection details when submitting transactions
+    pub mempool_rejection_reason: Option<MempoolRejectionReason>,
 }
 
 impl std::fmt::Display for AptosError {
@@ -35,6 +37,7 @@ impl AptosError {
             message: format!("{:#}", error),
             error_code,
             vm_error_code: None,
+            mempool_rejection_reason: None,
         }
     }
 
@@ -47,8 +50,18 @@ impl AptosError {
             message: format!("{:#}", error),
             error_code,
             vm_error_code: Some(vm_error_code as u64),
+            mempool_rejection_reason: None,
         }
     }
+
+    /// Adds the mempool rejection reason to the error
+    pub fn with_mempool_rejection_reason(
+        mut self,
+        mempool_rejection_reason: Option<MempoolRejectionReason>,
+    ) -> AptosError {
+        self.mempool_rejection_reason = mempool_rejection_reason;
+        self
+    }
 }
 
 /// These codes provide more granular error information beyond just the HTTP
@@ -81,6 +94,8 @@ pub enum AptosErrorCode {
     BlockNotFound = 108,
     ///  StateValue not found at the requested version
     StateValueNotFound = 109,
+    /// Transaction was evicted from mempool without being committed
+    TransactionEvicted = 110,
 
     /// Ledger version is pruned
     VersionPruned = 200,
@@ -101,6 +116,8 @@ pub enum AptosErrorCode {
     HealthCheckFailed = 500,
     /// The mempool is full, no new transactions can be submitted.
     MempoolIsFull = 501,
+    /// The submission quota for the client was exceeded, try again later.
+    SubmissionQuotaExceeded = 502,
 
     /// Internal server error
     InternalError = 600,
@@ -118,6 +135,40 @@ impl AptosErrorCode {
     }
 }
 
+/// These reasons explain why mempool rejected a submitted transaction, so
+/// that clients can decide how (and if) the transaction should be retried.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Enum)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolRejectionReason {
+    /// The mempool is full and the transaction fee is too low for the current load.
+    FeeTooLowForLoad,
+    /// The account has too many transactions in mempool.
+    AccountQuotaExceeded,
+    /// The mempool is full and the transaction is not ready (i.e., it would be parked).
+    ParkingLotFull,
+    /// A different transaction with the same sequence number is already in mempool.
+    Duplicate,
+    /// The transaction replaces one in mempool, but doesn't increase the gas unit price.
+    ReplacementUnderpriced,
+}
+
+impl From<mempool_status::MempoolRejectionReason> for MempoolRejectionReason {
+    fn from(reason: mempool_status::MempoolRejectionReason) -> Self {
+        match reason {
+            mempool_status::MempoolRejectionReason::FeeTooLowForLoad => Self::FeeTooLowForLoad,
+            mempool_status::MempoolRejectionReason::AccountQuotaExceeded => {
+                Self::AccountQuotaExceeded
+            },
+            mempool_status::MempoolRejectionReason::ParkingLotFull => Self::ParkingLotFull,
+            mempool_status::MempoolRejectionReason::Duplicate => Self::Duplicate,
+            mempool_status::MempoolRejectionReason::ReplacementUnderpriced => {
+                Self::ReplacementUnderpriced
+            },
+        }
+    }
+}
+
 #[test]
 fn test_serialize_deserialize() {
     let with_code = AptosError::new_with_vm_status(
@@ -133,4 +184,12 @@ fn test_serialize_deserialize() {
     let _: AptosError = bcs::from_bytes(&bcs::to_bytes(&without_code).unwrap()).unwrap();
     let _: AptosError =
         serde_json::from_str(&serde_json::to_string(&without_code).unwrap()).unwrap();
+
+    let with_rejection_reason =
+        AptosError::new_with_error_code("some message", AptosErrorCode::MempoolIsFull)
+            .with_mempool_rejection_reason(Some(MempoolRejectionReason::ParkingLotFull));
+    let _: AptosError = bcs::from_bytes(&bcs::to_bytes(&with_rejection_reason).unwrap()).unwrap();
+    let json = serde_json::to_string(&with_rejection_reason).unwrap();
+    assert!(json.contains("\"mempool_rejection_reason\":\"parking_lot_full\""));
+    let _: AptosError = serde_json::from_str(&json).unwrap();
 }
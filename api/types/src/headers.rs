@@ -19,3 +19,5 @@ pub const X_APTOS_LEDGER_TIMESTAMP: &str = "X-Aptos-Ledger-TimestampUsec";
 pub const X_APTOS_CURSOR: &str = "X-Aptos-Cursor";
 /// Provided by the client to identify what client it is.
 pub const X_APTOS_CLIENT: &str = "x-aptos-client";
+/// Provided by the client to identify the API key used for transaction submission quotas.
+pub const X_APTOS_API_KEY: &str = "x-aptos-api-key";
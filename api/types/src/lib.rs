@@ -28,7 +28,7 @@ pub use address::Address;
 pub use block::{BcsBlock, Block};
 pub use bytecode::Bytecode;
 pub use convert::{new_vm_utf8_string, AsConverter, ExplainVMStatus, MoveConverter};
-pub use error::{AptosError, AptosErrorCode};
+pub use error::{AptosError, AptosErrorCode, MempoolRejectionReason};
 pub use hash::HashValue;
 pub use headers::*;
 pub use index::{IndexResponse, IndexResponseBcs};
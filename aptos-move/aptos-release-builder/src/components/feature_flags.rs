@@ -106,6 +106,10 @@ pub enum FeatureFlag {
     MaxObjectNestingCheck,
     KeylessAccountsWithPasskeys,
     MultisigV2Enhancement,
+    ConsensusBatchResponseV2,
+    QuorumStoreQueueLengthHints,
+    ConsensusBlockRangeRetrieval,
+    ConsensusEpochStarterKit,
 }
 
 fn generate_features_blob(writer: &CodeWriter, data: &[u64]) {
@@ -266,6 +270,14 @@ impl From<FeatureFlag> for AptosFeatureFlag {
                 AptosFeatureFlag::KEYLESS_ACCOUNTS_WITH_PASSKEYS
             },
             FeatureFlag::MultisigV2Enhancement => AptosFeatureFlag::MULTISIG_V2_ENHANCEMENT,
+            FeatureFlag::ConsensusBatchResponseV2 => AptosFeatureFlag::CONSENSUS_BATCH_RESPONSE_V2,
+            FeatureFlag::QuorumStoreQueueLengthHints => {
+                AptosFeatureFlag::QUORUM_STORE_QUEUE_LENGTH_HINTS
+            },
+            FeatureFlag::ConsensusBlockRangeRetrieval => {
+                AptosFeatureFlag::CONSENSUS_BLOCK_RANGE_RETRIEVAL
+            },
+            FeatureFlag::ConsensusEpochStarterKit => AptosFeatureFlag::CONSENSUS_EPOCH_STARTER_KIT,
         }
     }
 }
@@ -355,6 +367,14 @@ impl From<AptosFeatureFlag> for FeatureFlag {
                 FeatureFlag::KeylessAccountsWithPasskeys
             },
             AptosFeatureFlag::MULTISIG_V2_ENHANCEMENT => FeatureFlag::MultisigV2Enhancement,
+            AptosFeatureFlag::CONSENSUS_BATCH_RESPONSE_V2 => FeatureFlag::ConsensusBatchResponseV2,
+            AptosFeatureFlag::QUORUM_STORE_QUEUE_LENGTH_HINTS => {
+                FeatureFlag::QuorumStoreQueueLengthHints
+            },
+            AptosFeatureFlag::CONSENSUS_BLOCK_RANGE_RETRIEVAL => {
+                FeatureFlag::ConsensusBlockRangeRetrieval
+            },
+            AptosFeatureFlag::CONSENSUS_EPOCH_STARTER_KIT => FeatureFlag::ConsensusEpochStarterKit,
         }
     }
 }
@@ -43,7 +43,10 @@ use aptos_types::{
     account_config,
     account_config::{new_block_event_key, AccountResource},
     block_executor::{
-        config::{BlockExecutorConfig, BlockExecutorConfigFromOnchain, BlockExecutorLocalConfig},
+        config::{
+            BlockExecutorConfig, BlockExecutorConfigFromOnchain, BlockExecutorLocalConfig,
+            ResourceGroupSerializationConfig,
+        },
         partitioner::PartitionedTransactions,
     },
     block_metadata::BlockMetadata,
@@ -113,7 +116,11 @@ static NUM_EXECUTION_SHARD: OnceCell<usize> = OnceCell::new();
 static NUM_PROOF_READING_THREADS: OnceCell<usize> = OnceCell::new();
 static PARANOID_TYPE_CHECKS: OnceCell<bool> = OnceCell::new();
 static DISCARD_FAILED_BLOCKS: OnceCell<bool> = OnceCell::new();
+static TXN_EXECUTION_TIME_BUDGET_MS: OnceCell<u64> = OnceCell::new();
+static RESOURCE_GROUP_SERIALIZATION_CONFIG: OnceCell<ResourceGroupSerializationConfig> =
+    OnceCell::new();
 static PROCESSED_TRANSACTIONS_DETAILED_COUNTERS: OnceCell<bool> = OnceCell::new();
+static EXECUTION_STATS_DIGEST: OnceCell<bool> = OnceCell::new();
 static TIMED_FEATURE_OVERRIDE: OnceCell<TimedFeatureOverride> = OnceCell::new();
 
 // TODO: Don't expose this in AptosVM, and use only in BlockAptosVM!
@@ -333,6 +340,31 @@ impl AptosVM {
         }
     }
 
+    /// Sets runtime config when invoked the first time.
+    pub fn set_txn_execution_time_budget_ms(budget_ms: u64) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        TXN_EXECUTION_TIME_BUDGET_MS.set(budget_ms).ok();
+    }
+
+    /// Get the per-transaction execution time budget if already set, otherwise return None
+    pub fn get_txn_execution_time_budget_ms() -> Option<u64> {
+        TXN_EXECUTION_TIME_BUDGET_MS.get().copied()
+    }
+
+    /// Sets runtime config when invoked the first time.
+    pub fn set_resource_group_serialization_config(config: ResourceGroupSerializationConfig) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        RESOURCE_GROUP_SERIALIZATION_CONFIG.set(config).ok();
+    }
+
+    /// Get the resource group serialization thresholds if already set, otherwise return default
+    pub fn get_resource_group_serialization_config() -> ResourceGroupSerializationConfig {
+        RESOURCE_GROUP_SERIALIZATION_CONFIG
+            .get()
+            .cloned()
+            .unwrap_or_default()
+    }
+
     // Set the override profile for timed features.
     pub fn set_timed_feature_override(profile: TimedFeatureOverride) {
         TIMED_FEATURE_OVERRIDE.set(profile).ok();
@@ -373,6 +405,20 @@ impl AptosVM {
         }
     }
 
+    /// Sets the computation of execution stats digests when invoked the first time.
+    pub fn set_execution_stats_digest() {
+        // Only the first call succeeds, due to OnceCell semantics.
+        EXECUTION_STATS_DIGEST.set(true).ok();
+    }
+
+    /// Get whether we should compute the digests of the execution stats of blocks
+    pub fn get_execution_stats_digest() -> bool {
+        match EXECUTION_STATS_DIGEST.get() {
+            Some(value) => *value,
+            None => false,
+        }
+    }
+
     /// Returns the internal gas schedule if it has been loaded, or an error if it hasn't.
     #[cfg(any(test, feature = "testing"))]
     pub fn gas_params(&self) -> Result<&AptosGasParameters, VMStatus> {
@@ -2310,6 +2356,8 @@ impl VMExecutor for AptosVM {
                     concurrency_level: Self::get_concurrency_level(),
                     allow_fallback: true,
                     discard_failed_blocks: Self::get_discard_failed_blocks(),
+                    txn_execution_time_budget_ms: Self::get_txn_execution_time_budget_ms(),
+                    resource_group_serialization: Self::get_resource_group_serialization_config(),
                 },
                 onchain: onchain_config,
             },
@@ -2348,6 +2396,38 @@ impl VMExecutor for AptosVM {
         }
         ret
     }
+
+    fn execute_block_sharded_streaming<
+        S: StateView + Sync + Send + 'static,
+        C: ExecutorClient<S>,
+    >(
+        sharded_block_executor: &ShardedBlockExecutor<S, C>,
+        transactions: PartitionedTransactions,
+        state_view: Arc<S>,
+        onchain_config: BlockExecutorConfigFromOnchain,
+        output_sink: &mut dyn FnMut(Vec<TransactionOutput>),
+    ) -> Result<(), VMStatus> {
+        let log_context = AdapterLogSchema::new(state_view.id(), 0);
+        info!(
+            log_context,
+            "Executing block (streaming), transaction count: {}",
+            transactions.num_txns()
+        );
+
+        let count = transactions.num_txns();
+        let ret = sharded_block_executor.execute_block_streaming(
+            state_view,
+            transactions,
+            AptosVM::get_concurrency_level(),
+            onchain_config,
+            output_sink,
+        );
+        if ret.is_ok() {
+            // Record the histogram count for transactions per block.
+            BLOCK_TRANSACTION_COUNT.observe(count as f64);
+        }
+        ret
+    }
 }
 
 // VMValidator external API
@@ -181,4 +181,26 @@ pub trait VMExecutor: Send + Sync {
         state_view: Arc<S>,
         onchain_config: BlockExecutorConfigFromOnchain,
     ) -> Result<Vec<TransactionOutput>, VMStatus>;
+
+    /// Executes a block of transactions using a sharded block executor, and streams the output
+    /// to the given sink in chunks (see `ShardedBlockExecutor::execute_block_streaming`). By
+    /// default, the output is streamed as a single chunk once the whole block is executed.
+    fn execute_block_sharded_streaming<
+        S: StateView + Sync + Send + 'static,
+        E: ExecutorClient<S>,
+    >(
+        sharded_block_executor: &ShardedBlockExecutor<S, E>,
+        transactions: PartitionedTransactions,
+        state_view: Arc<S>,
+        onchain_config: BlockExecutorConfigFromOnchain,
+        output_sink: &mut dyn FnMut(Vec<TransactionOutput>),
+    ) -> Result<(), VMStatus> {
+        output_sink(Self::execute_block_sharded(
+            sharded_block_executor,
+            transactions,
+            state_view,
+            onchain_config,
+        )?);
+        Ok(())
+    }
 }
@@ -9,5 +9,8 @@ use move_core_types::vm_status::VMStatus;
 pub trait CoordinatorClient<S: StateView + Sync + Send + 'static>: Send + Sync {
     fn receive_execute_command(&self) -> ExecutorShardCommand<S>;
 
-    fn send_execution_result(&self, result: Result<Vec<Vec<TransactionOutput>>, VMStatus>);
+    // Sends the output of the next sub-block (in the round order) to the coordinator, as soon as
+    // it is executed. If a sub-block fails to execute, the error is sent and the remaining
+    // sub-blocks are skipped.
+    fn send_sub_block_result(&self, result: Result<Vec<TransactionOutput>, VMStatus>);
 }
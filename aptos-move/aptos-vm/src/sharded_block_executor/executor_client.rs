@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::sharded_block_executor::counters::SHARDED_EXECUTION_RESULT_AGGREGATION_SECONDS;
 use aptos_types::{
     block_executor::{
         config::BlockExecutorConfigFromOnchain, partitioner::PartitionedTransactions,
@@ -46,5 +47,48 @@ pub trait ExecutorClient<S: StateView + Sync + Send + 'static>: Send + Sync {
         onchain_config: BlockExecutorConfigFromOnchain,
     ) -> Result<ShardedExecutionOutput, VMStatus>;
 
+    // A blocking call that executes the transactions in the block and streams the output to the
+    // given sink in chunks, i.e., the output of each sub-block in the round order (and in the shard
+    // order within a round), followed by the global output. A chunk is passed to the sink as soon
+    // as it (and all preceding chunks) are available, so that the consumer can process the output
+    // while the remaining sub-blocks are still executing. By default, the chunks are only streamed
+    // once the whole block has been executed.
+    fn execute_block_streaming(
+        &self,
+        state_view: Arc<S>,
+        transactions: PartitionedTransactions,
+        concurrency_level_per_shard: usize,
+        onchain_config: BlockExecutorConfigFromOnchain,
+        output_sink: &mut dyn FnMut(Vec<TransactionOutput>),
+    ) -> Result<(), VMStatus> {
+        let num_executor_shards = self.num_shards();
+        let (sharded_output, global_output) = self
+            .execute_block(
+                state_view,
+                transactions,
+                concurrency_level_per_shard,
+                onchain_config,
+            )?
+            .into_inner();
+        let _aggregation_timer = SHARDED_EXECUTION_RESULT_AGGREGATION_SECONDS.start_timer();
+        let num_rounds = sharded_output[0].len();
+        let mut ordered_results = vec![vec![]; num_executor_shards * num_rounds];
+        // Order the output from individual shards in the round order
+        for (shard_id, results_from_shard) in sharded_output.into_iter().enumerate() {
+            for (round, result) in results_from_shard.into_iter().enumerate() {
+                ordered_results[round * num_executor_shards + shard_id] = result;
+            }
+        }
+
+        for result in ordered_results.into_iter() {
+            output_sink(result);
+        }
+
+        // Lastly stream the global output
+        output_sink(global_output);
+
+        Ok(())
+    }
+
     fn shutdown(&mut self);
 }
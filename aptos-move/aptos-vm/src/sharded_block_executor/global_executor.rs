@@ -7,7 +7,10 @@ use crate::sharded_block_executor::{
 use aptos_logger::trace;
 use aptos_types::{
     block_executor::{
-        config::{BlockExecutorConfig, BlockExecutorConfigFromOnchain, BlockExecutorLocalConfig},
+        config::{
+            BlockExecutorConfig, BlockExecutorConfigFromOnchain, BlockExecutorLocalConfig,
+            ResourceGroupSerializationConfig,
+        },
         partitioner::{TransactionWithDependencies, GLOBAL_ROUND_ID},
     },
     state_store::StateView,
@@ -64,6 +67,8 @@ impl<S: StateView + Sync + Send + 'static> GlobalExecutor<S> {
                     concurrency_level: self.concurrency_level,
                     allow_fallback: true,
                     discard_failed_blocks: false,
+                    txn_execution_time_budget_ms: None,
+                    resource_group_serialization: ResourceGroupSerializationConfig::default(),
                 },
                 onchain: onchain_config,
             },
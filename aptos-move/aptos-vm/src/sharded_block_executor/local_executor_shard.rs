@@ -8,7 +8,7 @@ use crate::sharded_block_executor::{
     executor_client::{ExecutorClient, ShardedExecutionOutput},
     global_executor::GlobalExecutor,
     messages::CrossShardMsg,
-    sharded_aggregator_service,
+    sharded_aggregator_service::{self, TotalSupplyAggregator},
     sharded_executor_service::ShardedExecutorService,
     ExecutorShardCommand, ShardedBlockExecutor,
 };
@@ -17,12 +17,12 @@ use aptos_types::{
     block_executor::{
         config::BlockExecutorConfigFromOnchain,
         partitioner::{
-            PartitionedTransactions, RoundId, ShardId, GLOBAL_ROUND_ID,
+            PartitionedTransactions, RoundId, ShardId, SubBlocksForShard, GLOBAL_ROUND_ID,
             MAX_ALLOWED_PARTITIONING_ROUNDS,
         },
     },
     state_store::StateView,
-    transaction::TransactionOutput,
+    transaction::{analyzed_transaction::AnalyzedTransaction, TransactionOutput},
 };
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use move_core_types::vm_status::VMStatus;
@@ -41,7 +41,7 @@ impl<S: StateView + Sync + Send + 'static> LocalExecutorService<S> {
         num_shards: usize,
         num_threads: usize,
         command_rx: Receiver<ExecutorShardCommand<S>>,
-        result_tx: Sender<Result<Vec<Vec<TransactionOutput>>, VMStatus>>,
+        result_tx: Sender<Result<Vec<TransactionOutput>, VMStatus>>,
         cross_shard_client: LocalCrossShardClient,
     ) -> Self {
         let coordinator_client = Arc::new(LocalCoordinatorClient::new(command_rx, result_tx));
@@ -86,8 +86,8 @@ impl<S: StateView + Sync + Send + 'static> LocalExecutorService<S> {
             Vec<Receiver<ExecutorShardCommand<S>>>,
         ) = (0..num_shards).map(|_| unbounded()).unzip();
         let (result_txs, result_rxs): (
-            Vec<Sender<Result<Vec<Vec<TransactionOutput>>, VMStatus>>>,
-            Vec<Receiver<Result<Vec<Vec<TransactionOutput>>, VMStatus>>>,
+            Vec<Sender<Result<Vec<TransactionOutput>, VMStatus>>>,
+            Vec<Receiver<Result<Vec<TransactionOutput>, VMStatus>>>,
         ) = (0..num_shards).map(|_| unbounded()).unzip();
         // We need to create channels for each shard and each round. This is needed because individual
         // shards might send cross shard messages to other shards that will be consumed in different rounds.
@@ -130,8 +130,9 @@ impl<S: StateView + Sync + Send + 'static> LocalExecutorService<S> {
 pub struct LocalExecutorClient<S: StateView + Sync + Send + 'static> {
     // Channels to send execute block commands to the executor shards.
     command_txs: Vec<Sender<ExecutorShardCommand<S>>>,
-    // Channels to receive execution results from the executor shards.
-    result_rxs: Vec<Receiver<Result<Vec<Vec<TransactionOutput>>, VMStatus>>>,
+    // Channels to receive execution results from the executor shards (i.e., the output of each
+    // sub-block, in the round order).
+    result_rxs: Vec<Receiver<Result<Vec<TransactionOutput>, VMStatus>>>,
     executor_services: Vec<LocalExecutorService<S>>,
     global_executor: GlobalExecutor<S>,
 }
@@ -139,7 +140,7 @@ pub struct LocalExecutorClient<S: StateView + Sync + Send + 'static> {
 impl<S: StateView + Sync + Send + 'static> LocalExecutorClient<S> {
     pub fn new(
         command_tx: Vec<Sender<ExecutorShardCommand<S>>>,
-        result_rx: Vec<Receiver<Result<Vec<Vec<TransactionOutput>>, VMStatus>>>,
+        result_rx: Vec<Receiver<Result<Vec<TransactionOutput>, VMStatus>>>,
         executor_shards: Vec<LocalExecutorService<S>>,
         global_executor: GlobalExecutor<S>,
     ) -> Self {
@@ -161,18 +162,72 @@ impl<S: StateView + Sync + Send + 'static> LocalExecutorClient<S> {
         ))
     }
 
-    fn get_output_from_shards(&self) -> Result<Vec<Vec<Vec<TransactionOutput>>>, VMStatus> {
+    fn send_execute_commands(
+        &self,
+        state_view: Arc<S>,
+        sub_blocks: Vec<SubBlocksForShard<AnalyzedTransaction>>,
+        concurrency_level_per_shard: usize,
+        onchain_config: BlockExecutorConfigFromOnchain,
+    ) {
+        for (i, sub_blocks_for_shard) in sub_blocks.into_iter().enumerate() {
+            self.command_txs[i]
+                .send(ExecutorShardCommand::ExecuteSubBlocks(
+                    state_view.clone(),
+                    sub_blocks_for_shard,
+                    concurrency_level_per_shard,
+                    onchain_config.clone(),
+                ))
+                .unwrap();
+        }
+    }
+
+    fn receive_sub_block_output(
+        &self,
+        shard_id: ShardId,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        self.result_rxs[shard_id]
+            .recv()
+            .unwrap_or_else(|_| panic!("Did not receive output from shard {}", shard_id))
+    }
+
+    fn get_output_from_shards(
+        &self,
+        num_rounds: usize,
+    ) -> Result<Vec<Vec<Vec<TransactionOutput>>>, VMStatus> {
         let _timer = WAIT_FOR_SHARDED_OUTPUT_SECONDS.start_timer();
         trace!("LocalExecutorClient Waiting for results");
         let mut results = vec![];
-        for (i, rx) in self.result_rxs.iter().enumerate() {
-            results.push(
-                rx.recv()
-                    .unwrap_or_else(|_| panic!("Did not receive output from shard {}", i))?,
-            );
+        for shard_id in 0..self.num_shards() {
+            let mut results_from_shard = vec![];
+            for _ in 0..num_rounds {
+                results_from_shard.push(self.receive_sub_block_output(shard_id)?);
+            }
+            results.push(results_from_shard);
         }
         Ok(results)
     }
+
+    /// Streams the output of the sub-blocks to the sink in the merge order (i.e., in the round
+    /// order, and in the shard order within a round). Because the shards send the output of
+    /// each sub-block as soon as it is executed, a chunk only waits for the preceding chunks.
+    fn stream_output_from_shards(
+        &self,
+        num_rounds: usize,
+        total_supply_aggregator: &mut TotalSupplyAggregator,
+        output_sink: &mut dyn FnMut(Vec<TransactionOutput>),
+    ) -> Result<(), VMStatus> {
+        trace!("LocalExecutorClient Streaming results");
+        let executor_thread_pool = self.global_executor.get_executor_thread_pool();
+        for _ in 0..num_rounds {
+            for shard_id in 0..self.num_shards() {
+                let mut sub_block_output = self.receive_sub_block_output(shard_id)?;
+                total_supply_aggregator
+                    .update_next_chunk(&mut sub_block_output, executor_thread_pool.as_ref());
+                output_sink(sub_block_output);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for LocalExecutorClient<S> {
@@ -189,16 +244,13 @@ impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for LocalExecutorCl
     ) -> Result<ShardedExecutionOutput, VMStatus> {
         assert_eq!(transactions.num_shards(), self.num_shards());
         let (sub_blocks, global_txns) = transactions.into();
-        for (i, sub_blocks_for_shard) in sub_blocks.into_iter().enumerate() {
-            self.command_txs[i]
-                .send(ExecutorShardCommand::ExecuteSubBlocks(
-                    state_view.clone(),
-                    sub_blocks_for_shard,
-                    concurrency_level_per_shard,
-                    onchain_config.clone(),
-                ))
-                .unwrap();
-        }
+        let num_rounds = sub_blocks[0].num_sub_blocks();
+        self.send_execute_commands(
+            state_view.clone(),
+            sub_blocks,
+            concurrency_level_per_shard,
+            onchain_config.clone(),
+        );
 
         // This means that we are executing the global transactions concurrently with the individual shards but the
         // global transactions will be blocked for cross shard transaction results. This hopefully will help with
@@ -210,7 +262,7 @@ impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for LocalExecutorCl
             onchain_config,
         )?;
 
-        let mut sharded_output = self.get_output_from_shards()?;
+        let mut sharded_output = self.get_output_from_shards(num_rounds)?;
 
         sharded_aggregator_service::aggregate_and_update_total_supply(
             &mut sharded_output,
@@ -222,6 +274,56 @@ impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for LocalExecutorCl
         Ok(ShardedExecutionOutput::new(sharded_output, global_output))
     }
 
+    fn execute_block_streaming(
+        &self,
+        state_view: Arc<S>,
+        transactions: PartitionedTransactions,
+        concurrency_level_per_shard: usize,
+        onchain_config: BlockExecutorConfigFromOnchain,
+        output_sink: &mut dyn FnMut(Vec<TransactionOutput>),
+    ) -> Result<(), VMStatus> {
+        assert_eq!(transactions.num_shards(), self.num_shards());
+        let (sub_blocks, global_txns) = transactions.into();
+        let num_rounds = sub_blocks[0].num_sub_blocks();
+        self.send_execute_commands(
+            state_view.clone(),
+            sub_blocks,
+            concurrency_level_per_shard,
+            onchain_config.clone(),
+        );
+
+        let state_view = state_view.as_ref();
+        let mut total_supply_aggregator = TotalSupplyAggregator::new(state_view);
+        thread::scope(|scope| {
+            // The global transactions are executed concurrently with the individual shards (as in
+            // execute_block), but on a separate thread, so that the output of the shards can be
+            // streamed while the global transactions are blocked on cross shard results.
+            let global_output_handle = scope.spawn(move || {
+                self.global_executor
+                    .execute_global_txns(global_txns, state_view, onchain_config)
+            });
+
+            let sharded_result = self.stream_output_from_shards(
+                num_rounds,
+                &mut total_supply_aggregator,
+                output_sink,
+            );
+            let global_result = global_output_handle
+                .join()
+                .expect("Failed to join the global executor thread!");
+            sharded_result?;
+
+            // Lastly stream the global output
+            let mut global_output = global_result?;
+            total_supply_aggregator.update_next_chunk(
+                &mut global_output,
+                self.global_executor.get_executor_thread_pool().as_ref(),
+            );
+            output_sink(global_output);
+            Ok(())
+        })
+    }
+
     fn shutdown(&mut self) {}
 }
 
@@ -240,14 +342,14 @@ impl<S: StateView + Sync + Send + 'static> Drop for LocalExecutorClient<S> {
 
 pub struct LocalCoordinatorClient<S> {
     command_rx: Receiver<ExecutorShardCommand<S>>,
-    // Channel to send execution results to the coordinator.
-    result_tx: Sender<Result<Vec<Vec<TransactionOutput>>, VMStatus>>,
+    // Channel to send execution results to the coordinator (i.e., the output of each sub-block).
+    result_tx: Sender<Result<Vec<TransactionOutput>, VMStatus>>,
 }
 
 impl<S> LocalCoordinatorClient<S> {
     pub fn new(
         command_rx: Receiver<ExecutorShardCommand<S>>,
-        result_tx: Sender<Result<Vec<Vec<TransactionOutput>>, VMStatus>>,
+        result_tx: Sender<Result<Vec<TransactionOutput>, VMStatus>>,
     ) -> Self {
         Self {
             command_rx,
@@ -261,7 +363,7 @@ impl<S: StateView + Sync + Send + 'static> CoordinatorClient<S> for LocalCoordin
         self.command_rx.recv().unwrap()
     }
 
-    fn send_execution_result(&self, result: Result<Vec<Vec<TransactionOutput>>, VMStatus>) {
+    fn send_sub_block_result(&self, result: Result<Vec<TransactionOutput>, VMStatus>) {
         self.result_tx.send(result).unwrap()
     }
 }
@@ -3,10 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::sharded_block_executor::{
-    counters::{
-        NUM_EXECUTOR_SHARDS, SHARDED_BLOCK_EXECUTION_SECONDS,
-        SHARDED_EXECUTION_RESULT_AGGREGATION_SECONDS,
-    },
+    counters::{NUM_EXECUTOR_SHARDS, SHARDED_BLOCK_EXECUTION_SECONDS},
     executor_client::ExecutorClient,
 };
 use aptos_logger::info;
@@ -75,6 +72,31 @@ impl<S: StateView + Sync + Send + 'static, C: ExecutorClient<S>> ShardedBlockExe
         concurrency_level_per_shard: usize,
         onchain_config: BlockExecutorConfigFromOnchain,
     ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let mut aggregated_results = vec![];
+        self.execute_block_streaming(
+            state_view,
+            transactions,
+            concurrency_level_per_shard,
+            onchain_config,
+            &mut |output_chunk| aggregated_results.extend(output_chunk),
+        )?;
+        Ok(aggregated_results)
+    }
+
+    /// Execute a block of transactions in parallel (see `execute_block`), and stream the output
+    /// to the given sink in chunks as soon as they can be merged, i.e., the output of each
+    /// sub-block in the round order (and in the shard order within a round), followed by the
+    /// output of the global transactions. Concatenating the chunks yields the output of
+    /// `execute_block`. Note: if the execution fails, some chunks might have been streamed
+    /// already (and must be discarded by the consumer).
+    pub fn execute_block_streaming(
+        &self,
+        state_view: Arc<S>,
+        transactions: PartitionedTransactions,
+        concurrency_level_per_shard: usize,
+        onchain_config: BlockExecutorConfigFromOnchain,
+        output_sink: &mut dyn FnMut(Vec<TransactionOutput>),
+    ) -> Result<(), VMStatus> {
         let _timer = SHARDED_BLOCK_EXECUTION_SECONDS.start_timer();
         let num_executor_shards = self.executor_client.num_shards();
         NUM_EXECUTOR_SHARDS.set(num_executor_shards as i64);
@@ -84,36 +106,15 @@ impl<S: StateView + Sync + Send + 'static, C: ExecutorClient<S>> ShardedBlockExe
             "Block must be partitioned into {} sub-blocks",
             num_executor_shards
         );
-        let (sharded_output, global_output) = self
-            .executor_client
-            .execute_block(
-                state_view,
-                transactions,
-                concurrency_level_per_shard,
-                onchain_config,
-            )?
-            .into_inner();
-        // wait for all remote executors to send the result back and append them in order by shard id
+        self.executor_client.execute_block_streaming(
+            state_view,
+            transactions,
+            concurrency_level_per_shard,
+            onchain_config,
+            output_sink,
+        )?;
         info!("ShardedBlockExecutor Received all results");
-        let _aggregation_timer = SHARDED_EXECUTION_RESULT_AGGREGATION_SECONDS.start_timer();
-        let num_rounds = sharded_output[0].len();
-        let mut aggregated_results = vec![];
-        let mut ordered_results = vec![vec![]; num_executor_shards * num_rounds];
-        // Append the output from individual shards in the round order
-        for (shard_id, results_from_shard) in sharded_output.into_iter().enumerate() {
-            for (round, result) in results_from_shard.into_iter().enumerate() {
-                ordered_results[round * num_executor_shards + shard_id] = result;
-            }
-        }
-
-        for result in ordered_results.into_iter() {
-            aggregated_results.extend(result);
-        }
-
-        // Lastly append the global output
-        aggregated_results.extend(global_output);
-
-        Ok(aggregated_results)
+        Ok(())
     }
 
     pub fn shutdown(&mut self) {
@@ -183,18 +183,7 @@ pub fn aggregate_and_update_total_supply<S: StateView>(
     let mut aggr_ts_idx = 1;
     for round in 0..num_rounds {
         sharded_output.iter().for_each(|shard_output| {
-            let mut curr_delta = DeltaU128::default();
-            // Though we expect all the txn_outputs to have total_supply, there can be
-            // exceptions like 'block meta' (first txn in the block) and 'chkpt info' (last txn
-            // in the block) which may not have total supply. Hence we iterate till we find the
-            // last txn with total supply.
-            for txn in shard_output[round].iter().rev() {
-                if let Some(last_txn_total_supply) = txn.write_set().get_total_supply() {
-                    curr_delta =
-                        DeltaU128::get_delta(last_txn_total_supply, TOTAL_SUPPLY_AGGR_BASE_VAL);
-                    break;
-                }
-            }
+            let curr_delta = get_total_supply_delta(&shard_output[round]);
             aggr_total_supply_delta[aggr_ts_idx] =
                 curr_delta + aggr_total_supply_delta[aggr_ts_idx - 1];
             aggr_ts_idx += 1;
@@ -222,36 +211,79 @@ pub fn aggregate_and_update_total_supply<S: StateView>(
                 for (round, txn_outputs) in shard_output.iter_mut().enumerate() {
                     let delta_for_round =
                         aggr_total_supply_delta_ref[round * num_shards + shard_id] + base_val_delta;
-                    let num_txn_outputs = txn_outputs.len();
-                    txn_outputs
-                        .par_iter_mut()
-                        .with_min_len(optimal_min_len(num_txn_outputs, 32))
-                        .for_each(|txn_output| {
-                            if let Some(txn_total_supply) =
-                                txn_output.write_set().get_total_supply()
-                            {
-                                txn_output.update_total_supply(
-                                    delta_for_round.add_delta(txn_total_supply),
-                                );
-                            }
-                        });
+                    update_total_supply(txn_outputs, delta_for_round);
                 }
             });
     });
 
     let delta_for_global_shard = aggr_total_supply_delta[num_shards * num_rounds] + base_val_delta;
-    let delta_for_global_shard_ref = &delta_for_global_shard;
     executor_thread_pool.scope(|_| {
-        let num_txn_outputs = global_output.len();
-        global_output
-            .par_iter_mut()
-            .with_min_len(optimal_min_len(num_txn_outputs, 32))
-            .for_each(|txn_output| {
-                if let Some(txn_total_supply) = txn_output.write_set().get_total_supply() {
-                    txn_output.update_total_supply(
-                        delta_for_global_shard_ref.add_delta(txn_total_supply),
-                    );
-                }
-            });
+        update_total_supply(global_output, delta_for_global_shard);
     });
 }
+
+/// Updates the total supply of the sharded execution output incrementally, i.e., chunk by chunk
+/// in the merge order: the output of each sub-block in the round order (and in the shard order
+/// within a round), followed by the global output. The delta of a chunk only depends on the
+/// preceding chunks, so each chunk can be updated (and consumed) as soon as it is available,
+/// instead of waiting for the whole block (see `aggregate_and_update_total_supply`).
+pub struct TotalSupplyAggregator {
+    // The delta to apply to the next chunk, i.e., the aggregated delta of all the preceding chunks
+    // plus the delta between the actual total supply base value and the aggregator base value.
+    next_chunk_delta: DeltaU128,
+}
+
+impl TotalSupplyAggregator {
+    pub fn new<S: StateView>(state_view: &S) -> Self {
+        let total_supply_base_val: u128 =
+            get_state_value(&TOTAL_SUPPLY_STATE_KEY, state_view).unwrap();
+        Self {
+            next_chunk_delta: DeltaU128::get_delta(
+                total_supply_base_val,
+                TOTAL_SUPPLY_AGGR_BASE_VAL,
+            ),
+        }
+    }
+
+    /// Updates the total supply of the next chunk in the merge order
+    pub fn update_next_chunk(
+        &mut self,
+        txn_outputs: &mut [TransactionOutput],
+        executor_thread_pool: &rayon::ThreadPool,
+    ) {
+        // The delta of the chunk must be calculated before its total supply is updated
+        let chunk_delta = get_total_supply_delta(txn_outputs);
+        let delta_for_chunk = self.next_chunk_delta;
+        executor_thread_pool.scope(|_| {
+            update_total_supply(txn_outputs, delta_for_chunk);
+        });
+        self.next_chunk_delta = self.next_chunk_delta + chunk_delta;
+    }
+}
+
+/// Returns the total supply delta of the given txn_outputs (relative to the aggregator base value)
+fn get_total_supply_delta(txn_outputs: &[TransactionOutput]) -> DeltaU128 {
+    // Though we expect all the txn_outputs to have total_supply, there can be
+    // exceptions like 'block meta' (first txn in the block) and 'chkpt info' (last txn
+    // in the block) which may not have total supply. Hence we iterate till we find the
+    // last txn with total supply.
+    for txn in txn_outputs.iter().rev() {
+        if let Some(last_txn_total_supply) = txn.write_set().get_total_supply() {
+            return DeltaU128::get_delta(last_txn_total_supply, TOTAL_SUPPLY_AGGR_BASE_VAL);
+        }
+    }
+    DeltaU128::default()
+}
+
+/// Adds the given delta to the total supply of all txn_outputs (that have a total supply)
+fn update_total_supply(txn_outputs: &mut [TransactionOutput], delta: DeltaU128) {
+    let num_txn_outputs = txn_outputs.len();
+    txn_outputs
+        .par_iter_mut()
+        .with_min_len(optimal_min_len(num_txn_outputs, 32))
+        .for_each(|txn_output| {
+            if let Some(txn_total_supply) = txn_output.write_set().get_total_supply() {
+                txn_output.update_total_supply(delta.add_delta(txn_total_supply));
+            }
+        });
+}
@@ -19,7 +19,7 @@ use crate::{
 use aptos_logger::{info, trace};
 use aptos_types::{
     block_executor::{
-        config::{BlockExecutorConfig, BlockExecutorLocalConfig},
+        config::{BlockExecutorConfig, BlockExecutorLocalConfig, ResourceGroupSerializationConfig},
         partitioner::{ShardId, SubBlock, SubBlocksForShard, TransactionWithDependencies},
     },
     state_store::StateView,
@@ -171,13 +171,15 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
         block_on(callback_receiver).unwrap()
     }
 
+    /// Executes the sub-blocks in the round order, and passes the output of each
+    /// sub-block to the given callback as soon as the sub-block is executed.
     fn execute_block(
         &self,
         transactions: SubBlocksForShard<AnalyzedTransaction>,
         state_view: &S,
         config: BlockExecutorConfig,
-    ) -> Result<Vec<Vec<TransactionOutput>>, VMStatus> {
-        let mut result = vec![];
+        mut on_sub_block_executed: impl FnMut(Vec<TransactionOutput>),
+    ) -> Result<(), VMStatus> {
         for (round, sub_block) in transactions.into_sub_blocks().into_iter().enumerate() {
             let _timer = SHARDED_BLOCK_EXECUTION_BY_ROUNDS_SECONDS
                 .with_label_values(&[&self.shard_id.to_string(), &round.to_string()])
@@ -191,14 +193,19 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                 round,
                 sub_block.transactions.len()
             );
-            result.push(self.execute_sub_block(sub_block, round, state_view, config.clone())?);
+            on_sub_block_executed(self.execute_sub_block(
+                sub_block,
+                round,
+                state_view,
+                config.clone(),
+            )?);
             trace!(
                 "Finished executing sub block for shard {} and round {}",
                 self.shard_id,
                 round
             );
         }
-        Ok(result)
+        Ok(())
     }
 
     pub fn start(&self) {
@@ -234,9 +241,16 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                                 concurrency_level: concurrency_level_per_shard,
                                 allow_fallback: true,
                                 discard_failed_blocks: false,
+                                txn_execution_time_budget_ms: None,
+                                resource_group_serialization:
+                                    ResourceGroupSerializationConfig::default(),
                             },
                             onchain: onchain_config,
                         },
+                        |sub_block_output| {
+                            self.coordinator_client
+                                .send_sub_block_result(Ok(sub_block_output));
+                        },
                     );
                     drop(state_view);
                     drop(exe_timer);
@@ -244,7 +258,10 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                     let _result_tx_timer = SHARDED_EXECUTOR_SERVICE_SECONDS
                         .with_label_values(&[&self.shard_id.to_string(), "result_tx"])
                         .start_timer();
-                    self.coordinator_client.send_execution_result(ret);
+                    // The outputs of the executed sub-blocks have already been sent
+                    if let Err(error) = ret {
+                        self.coordinator_client.send_sub_block_result(Err(error));
+                    }
                 },
                 ExecutorShardCommand::Stop => {
                     break;
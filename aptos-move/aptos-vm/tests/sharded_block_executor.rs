@@ -185,6 +185,17 @@ fn test_partitioner_v2_connected_component_sharded_block_executor_with_random_tr
     }
 }
 
+#[test]
+fn test_partitioner_v2_connected_component_sharded_block_executor_streaming() {
+    let num_shards = 4;
+    let client = LocalExecutorService::setup_local_executor_shards(num_shards, Some(2));
+    let sharded_block_executor = ShardedBlockExecutor::new(client);
+    let partitioner = PartitionerV2Config::default()
+        .pre_partitioner_config(Box::<ConnectedComponentPartitionerConfig>::default())
+        .build();
+    test_utils::sharded_block_executor_streaming(partitioner, sharded_block_executor, 2);
+}
+
 mod test_utils {
     use aptos_block_partitioner::BlockPartitioner;
     use aptos_language_e2e_tests::{
@@ -415,4 +426,57 @@ mod test_utils {
                 .unwrap();
         compare_txn_outputs(unsharded_txn_output, sharded_txn_output);
     }
+
+    pub fn sharded_block_executor_streaming<E: ExecutorClient<FakeDataStore>>(
+        partitioner: Box<dyn BlockPartitioner>,
+        sharded_block_executor: ShardedBlockExecutor<FakeDataStore, E>,
+        concurrency: usize,
+    ) {
+        let num_txns = 400;
+        let num_accounts = 40;
+        let num_shards = sharded_block_executor.num_shards();
+        let mut executor = FakeExecutor::from_head_genesis();
+        let mut accounts = Vec::new();
+        for _ in 0..num_accounts {
+            let account = generate_account_at(&mut executor, AccountAddress::random());
+            accounts.push(Mutex::new(account));
+        }
+        let mut transactions = Vec::new();
+        for i in 0..num_txns {
+            let sender = &mut accounts[i % num_accounts].lock().unwrap();
+            let receiver = &accounts[(i + 1) % num_accounts].lock().unwrap();
+            transactions.push(generate_p2p_txn(sender, receiver, 1_000));
+        }
+
+        let partitioned_txns = partitioner.partition(transactions, num_shards);
+        let num_rounds = partitioned_txns.sharded_txns()[0].num_sub_blocks();
+        let execution_ordered_txns: Vec<SignatureVerifiedTransaction> =
+            PartitionedTransactions::flatten(partitioned_txns.clone())
+                .into_iter()
+                .map(|t| t.into_txn())
+                .collect();
+
+        // Stream the output and verify that a chunk is received for each sub-block
+        // (and for the global transactions).
+        let mut output_chunks = vec![];
+        sharded_block_executor
+            .execute_block_streaming(
+                Arc::new(executor.data_store().clone()),
+                partitioned_txns,
+                concurrency,
+                BlockExecutorConfigFromOnchain::new_no_block_limit(),
+                &mut |output_chunk| output_chunks.push(output_chunk),
+            )
+            .unwrap();
+        assert_eq!(output_chunks.len(), num_shards * num_rounds + 1);
+
+        // Verify that the merged chunks match the unsharded output
+        let unsharded_txn_output =
+            AptosVM::execute_block_no_limit(&execution_ordered_txns, executor.data_store())
+                .unwrap();
+        compare_txn_outputs(
+            unsharded_txn_output,
+            output_chunks.into_iter().flatten().collect(),
+        );
+    }
 }
@@ -1,7 +1,11 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{types::InputOutputKey, value_exchange::filter_value_for_exchange};
+use crate::{
+    trace::{TracedRead, TracedReadVersion},
+    types::InputOutputKey,
+    value_exchange::filter_value_for_exchange,
+};
 use anyhow::bail;
 use aptos_aggregator::{
     delta_math::DeltaHistory,
@@ -35,6 +39,7 @@ use std::{
         BTreeMap, HashMap, HashSet,
     },
     sync::Arc,
+    time::Duration,
 };
 
 /// The enum variants should not be re-ordered, as it defines a relation
@@ -313,6 +318,8 @@ pub(crate) struct CapturedReads<T: Transaction> {
     /// Set if the invarint on CapturedReads intended use is violated. Leads to an alert
     /// and sequential execution fallback.
     incorrect_use: bool,
+    /// The total time spent waiting on read dependencies during the execution.
+    dependency_wait_time: Duration,
 }
 
 #[derive(Debug)]
@@ -707,6 +714,37 @@ impl<T: Transaction> CapturedReads<T> {
         ret
     }
 
+    // Returns the captured data, group and module reads, with versions when available.
+    pub(crate) fn get_traced_reads(&self) -> Vec<TracedRead<T::Key>> {
+        let data_reads = self.data_reads.iter().map(|(key, read)| {
+            let version = match read {
+                DataRead::Versioned(Ok((txn_idx, incarnation)), _, _) => {
+                    TracedReadVersion::Txn(*txn_idx, *incarnation)
+                },
+                DataRead::Versioned(Err(StorageVersion), _, _) => TracedReadVersion::Storage,
+                DataRead::Metadata(_) | DataRead::Exists(_) | DataRead::Resolved(_) => {
+                    TracedReadVersion::Unversioned
+                },
+            };
+            TracedRead {
+                key: key.clone(),
+                version,
+            }
+        });
+
+        data_reads
+            .chain(
+                self.group_reads
+                    .keys()
+                    .chain(self.module_reads.iter())
+                    .map(|key| TracedRead {
+                        key: key.clone(),
+                        version: TracedReadVersion::Unversioned,
+                    }),
+            )
+            .collect()
+    }
+
     pub(crate) fn mark_failure(&mut self) {
         self.speculative_failure = true;
     }
@@ -714,6 +752,14 @@ impl<T: Transaction> CapturedReads<T> {
     pub(crate) fn mark_incorrect_use(&mut self) {
         self.incorrect_use = true;
     }
+
+    pub(crate) fn add_dependency_wait_time(&mut self, wait_time: Duration) {
+        self.dependency_wait_time += wait_time;
+    }
+
+    pub(crate) fn dependency_wait_time(&self) -> Duration {
+        self.dependency_wait_time
+    }
 }
 
 #[derive(Derivative)]
@@ -0,0 +1,139 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-transaction conflict statistics of a parallel (Block-STM) execution.
+//!
+//! Every parallel execution records, for each transaction, the number of times it was executed,
+//! the number of failed validations of its read-set, and the time it spent waiting on read
+//! dependencies. After the block, the statistics are aggregated into the conflict counters and,
+//! if enabled via `BlockExecutor::with_conflict_stats_capture`, kept as a serializable
+//! `BlockConflictStats` that can be exported for offline analysis (e.g. for tuning the
+//! transaction shuffler and the block partitioner).
+
+use crate::counters;
+use aptos_infallible::Mutex;
+use aptos_mvhashmap::types::TxnIndex;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::Duration,
+};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TxnConflictStats {
+    /// The number of (speculative) executions, including the first execution.
+    pub num_executions: u32,
+    /// The number of validations of the transaction's read-set that failed.
+    pub num_validation_failures: u32,
+    /// The total time (in microseconds) spent waiting on read dependencies.
+    pub dependency_wait_micros: u64,
+}
+
+impl TxnConflictStats {
+    /// Returns the number of executions in addition to the first one.
+    pub fn num_re_executions(&self) -> u32 {
+        self.num_executions.saturating_sub(1)
+    }
+}
+
+/// The conflict statistics of a single parallel block execution, indexed by transaction.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlockConflictStats {
+    pub concurrency_level: usize,
+    pub succeeded: bool,
+    pub txn_stats: Vec<TxnConflictStats>,
+}
+
+impl BlockConflictStats {
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bcs::to_bytes(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(bcs::from_bytes(bytes)?)
+    }
+}
+
+/// Holds the conflict statistics of the latest parallel execution of a block executor with
+/// conflict statistics capture enabled.
+#[derive(Default)]
+pub struct ConflictStatsCapture {
+    last_block_stats: Mutex<Option<BlockConflictStats>>,
+}
+
+impl ConflictStatsCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns (and clears) the statistics of the latest parallel execution, if any.
+    pub fn take_last_block_stats(&self) -> Option<BlockConflictStats> {
+        self.last_block_stats.lock().take()
+    }
+
+    pub(crate) fn store_block_stats(&self, block_stats: BlockConflictStats) {
+        *self.last_block_stats.lock() = Some(block_stats);
+    }
+}
+
+#[derive(Default)]
+struct TxnConflictCounters {
+    num_executions: AtomicU32,
+    num_validation_failures: AtomicU32,
+    dependency_wait_micros: AtomicU64,
+}
+
+/// Collects the conflict statistics of an ongoing parallel execution, shared by all workers.
+pub(crate) struct ConflictStatsRecorder {
+    txn_counters: Vec<TxnConflictCounters>,
+}
+
+impl ConflictStatsRecorder {
+    pub(crate) fn new(num_txns: TxnIndex) -> Self {
+        Self {
+            txn_counters: (0..num_txns)
+                .map(|_| TxnConflictCounters::default())
+                .collect(),
+        }
+    }
+
+    /// Records an execution of the transaction, and the time the execution spent
+    /// waiting on read dependencies.
+    pub(crate) fn record_execution(&self, txn_idx: TxnIndex, dependency_wait_time: Duration) {
+        let txn_counters = &self.txn_counters[txn_idx as usize];
+        txn_counters.num_executions.fetch_add(1, Ordering::Relaxed);
+        txn_counters
+            .dependency_wait_micros
+            .fetch_add(dependency_wait_time.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_validation_failure(&self, txn_idx: TxnIndex) {
+        self.txn_counters[txn_idx as usize]
+            .num_validation_failures
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Updates the conflict counters and returns the recorded statistics.
+    pub(crate) fn into_block_stats(
+        self,
+        concurrency_level: usize,
+        succeeded: bool,
+    ) -> BlockConflictStats {
+        let txn_stats: Vec<_> = self
+            .txn_counters
+            .into_iter()
+            .map(|txn_counters| TxnConflictStats {
+                num_executions: txn_counters.num_executions.into_inner(),
+                num_validation_failures: txn_counters.num_validation_failures.into_inner(),
+                dependency_wait_micros: txn_counters.dependency_wait_micros.into_inner(),
+            })
+            .collect();
+        counters::update_txn_conflict_counters(&txn_stats);
+
+        BlockConflictStats {
+            concurrency_level,
+            succeeded,
+            txn_stats,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::conflict_stats::TxnConflictStats;
 use aptos_metrics_core::{
     exponential_buckets, register_avg_counter_vec, register_histogram, register_histogram_vec,
     register_int_counter, register_int_counter_vec, Histogram, HistogramVec, IntCounter,
@@ -87,6 +88,17 @@ pub static EXCEED_PER_BLOCK_OUTPUT_LIMIT_COUNT: Lazy<IntCounterVec> = Lazy::new(
     .unwrap()
 });
 
+/// Count of transactions that exceeded the per-transaction execution time budget during parallel
+/// execution, and of blocks that were re-executed sequentially as a result.
+pub static TXN_EXECUTION_TIME_BUDGET_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_txn_time_budget_count",
+        "Count of transactions exceeding the per-transaction execution time budget (and resulting sequential fallbacks)",
+        &["outcome"]
+    )
+    .unwrap()
+});
+
 pub static PARALLEL_EXECUTION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -162,6 +174,25 @@ pub static DEPENDENCY_WAIT_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static TXN_CONFLICTS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_execution_txn_conflicts",
+        "Histogram for the per-txn number of validation failures and re-executions in Block STM",
+        &["type"],
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 2.0, /*count=*/ 16).unwrap(),
+    )
+    .unwrap()
+});
+
+pub static TXN_DEPENDENCY_WAIT_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_execution_txn_dependency_wait",
+        "The per-txn total time spent in waiting for dependencies in Block STM",
+        time_buckets(),
+    )
+    .unwrap()
+});
+
 pub static BLOCK_GAS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "aptos_execution_block_gas",
@@ -294,6 +325,18 @@ pub(crate) fn update_txn_gas_counters(txn_fee_statements: &Vec<FeeStatement>, is
     }
 }
 
+pub(crate) fn update_txn_conflict_counters(txn_stats: &[TxnConflictStats]) {
+    for stats in txn_stats {
+        TXN_CONFLICTS
+            .with_label_values(&["validation_failures"])
+            .observe(stats.num_validation_failures as f64);
+        TXN_CONFLICTS
+            .with_label_values(&["re_executions"])
+            .observe(stats.num_re_executions() as f64);
+        TXN_DEPENDENCY_WAIT_SECONDS.observe(stats.dependency_wait_micros as f64 / 1_000_000.0);
+    }
+}
+
 pub(crate) fn update_state_counters(block_state_stats: BlockStateStats, is_parallel: bool) {
     let mode_str = if is_parallel {
         Mode::PARALLEL
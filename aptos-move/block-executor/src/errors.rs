@@ -11,6 +11,10 @@ pub(crate) enum ParallelBlockExecutionError {
     // aborting the parallel execution pipeline and falling back to the sequential execution.
     // TODO: provide proper multi-versioning for code (like data) for the cache.
     ModulePathReadWriteError,
+    // A transaction incarnation exceeded the configured wall-clock execution budget. The parallel
+    // execution is aborted so that a pathological transaction does not stall the other workers,
+    // and the block is re-executed sequentially.
+    TxnExecutionTimeBudgetExceeded,
     /// unrecoverable VM error
     FatalVMError,
 }
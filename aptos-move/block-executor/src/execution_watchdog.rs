@@ -0,0 +1,153 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Enforces the per-transaction execution time budget of a parallel (Block-STM) execution.
+//!
+//! Each worker publishes the start time of its ongoing transaction execution, so that the other
+//! workers can detect (from the worker loop) an execution exceeding the budget while it is still
+//! running, and halt the parallel execution without waiting for the transaction to finish.
+
+use crate::{counters, errors::ParallelBlockExecutionError};
+use aptos_aggregator::types::PanicOr;
+use aptos_logger::info;
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+use std::{
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+// Marks a worker without an ongoing execution.
+const IDLE: u64 = u64::MAX;
+
+struct OngoingExecution {
+    // The start time (in nanoseconds since the start of the block execution), or IDLE.
+    start_nanos: AtomicU64,
+    txn_idx: AtomicU32,
+    incarnation: AtomicU32,
+}
+
+pub(crate) struct ExecutionWatchdog {
+    budget: Duration,
+    block_start: Instant,
+    ongoing_executions: Vec<OngoingExecution>,
+    // Set once the budget is exceeded, so that it is only logged and counted once per block.
+    exceeded: AtomicBool,
+}
+
+impl ExecutionWatchdog {
+    pub(crate) fn new(budget_ms: u64, num_workers: usize) -> Self {
+        Self {
+            budget: Duration::from_millis(budget_ms),
+            block_start: Instant::now(),
+            ongoing_executions: (0..num_workers)
+                .map(|_| OngoingExecution {
+                    start_nanos: AtomicU64::new(IDLE),
+                    txn_idx: AtomicU32::new(0),
+                    incarnation: AtomicU32::new(0),
+                })
+                .collect(),
+            exceeded: AtomicBool::new(false),
+        }
+    }
+
+    fn elapsed_nanos(&self) -> u64 {
+        self.block_start.elapsed().as_nanos() as u64
+    }
+
+    /// Records that the given worker started executing the given incarnation.
+    pub(crate) fn start_execution(
+        &self,
+        worker_id: usize,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+    ) {
+        let ongoing_execution = &self.ongoing_executions[worker_id];
+        ongoing_execution.txn_idx.store(txn_idx, Ordering::Relaxed);
+        ongoing_execution
+            .incarnation
+            .store(incarnation, Ordering::Relaxed);
+        ongoing_execution
+            .start_nanos
+            .store(self.elapsed_nanos(), Ordering::Release);
+    }
+
+    /// Records that the given worker finished its ongoing execution. Returns an error if the
+    /// execution took longer than the budget (e.g., if no other worker was idle to detect it).
+    pub(crate) fn finish_execution(
+        &self,
+        worker_id: usize,
+    ) -> Result<(), PanicOr<ParallelBlockExecutionError>> {
+        let ongoing_execution = &self.ongoing_executions[worker_id];
+        let start_nanos = ongoing_execution.start_nanos.swap(IDLE, Ordering::AcqRel);
+        self.check_execution(ongoing_execution, start_nanos)
+    }
+
+    /// Called from the worker loop: returns an error if the ongoing execution of any worker
+    /// already exceeded the budget, so that the parallel execution is halted right away.
+    pub(crate) fn check_ongoing_executions(
+        &self,
+    ) -> Result<(), PanicOr<ParallelBlockExecutionError>> {
+        for ongoing_execution in &self.ongoing_executions {
+            let start_nanos = ongoing_execution.start_nanos.load(Ordering::Acquire);
+            self.check_execution(ongoing_execution, start_nanos)?;
+        }
+        Ok(())
+    }
+
+    fn check_execution(
+        &self,
+        ongoing_execution: &OngoingExecution,
+        start_nanos: u64,
+    ) -> Result<(), PanicOr<ParallelBlockExecutionError>> {
+        if start_nanos == IDLE {
+            return Ok(());
+        }
+
+        let elapsed = Duration::from_nanos(self.elapsed_nanos().saturating_sub(start_nanos));
+        if elapsed <= self.budget {
+            return Ok(());
+        }
+
+        if !self.exceeded.swap(true, Ordering::Relaxed) {
+            // Exceeding the budget is an expected fallback behavior, no alert is required.
+            info!(
+                "[Execution] Txn {} incarnation {} exceeded execution time budget: {:?} > {:?}",
+                ongoing_execution.txn_idx.load(Ordering::Relaxed),
+                ongoing_execution.incarnation.load(Ordering::Relaxed),
+                elapsed,
+                self.budget
+            );
+            counters::TXN_EXECUTION_TIME_BUDGET_COUNT
+                .with_label_values(&["exceeded"])
+                .inc();
+        }
+        Err(PanicOr::Or(
+            ParallelBlockExecutionError::TxnExecutionTimeBudgetExceeded,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+    use std::thread::sleep;
+
+    #[test]
+    fn test_ongoing_execution_exceeding_budget() {
+        let watchdog = ExecutionWatchdog::new(10, 2);
+        assert_ok!(watchdog.check_ongoing_executions());
+
+        // An ongoing execution is detected by the other workers before it finishes
+        watchdog.start_execution(0, 5, 0);
+        assert_ok!(watchdog.check_ongoing_executions());
+        sleep(Duration::from_millis(20));
+        assert_err!(watchdog.check_ongoing_executions());
+        assert_err!(watchdog.finish_execution(0));
+
+        // Finished executions are not checked anymore
+        assert_ok!(watchdog.check_ongoing_executions());
+        watchdog.start_execution(1, 6, 0);
+        assert_ok!(watchdog.finish_execution(1));
+    }
+}
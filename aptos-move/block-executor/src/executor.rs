@@ -3,17 +3,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    conflict_stats::{ConflictStatsCapture, ConflictStatsRecorder},
     counters,
     counters::{
         PARALLEL_EXECUTION_SECONDS, RAYON_EXECUTION_SECONDS, TASK_EXECUTE_SECONDS,
         TASK_VALIDATE_SECONDS, VM_INIT_SECONDS, WORK_WITH_TASK_SECONDS,
     },
     errors::*,
+    execution_watchdog::ExecutionWatchdog,
     executor_utilities::*,
     explicit_sync_wrapper::ExplicitSyncWrapper,
     limit_processor::BlockGasLimitProcessor,
     scheduler::{DependencyStatus, ExecutionTaskType, Scheduler, SchedulerTask, Wave},
     task::{ExecutionStatus, ExecutorTask, TransactionOutput},
+    trace::{ExecutionTraceCapture, TraceEvent, TraceRecorder},
     txn_commit_hook::TransactionCommitHook,
     txn_last_input_output::{KeyKind, TxnLastInputOutput},
     types::ReadWriteSummary,
@@ -58,14 +61,19 @@ use std::{
         atomic::{AtomicBool, AtomicU32, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
-pub struct BlockExecutor<T, E, S, L, X> {
+pub struct BlockExecutor<T: Transaction, E, S, L, X> {
     // Number of active concurrent tasks, corresponding to the maximum number of rayon
     // threads that may be concurrently participating in parallel execution.
     config: BlockExecutorConfig,
     executor_thread_pool: Arc<ThreadPool>,
     transaction_commit_hook: Option<L>,
+    // If set, the interleaving of failed parallel executions is captured for replay.
+    trace_capture: Option<Arc<ExecutionTraceCapture<T::Key>>>,
+    // If set, the conflict statistics of parallel executions are kept for export.
+    conflict_stats_capture: Option<Arc<ConflictStatsCapture>>,
     phantom: PhantomData<(T, E, S, L, X)>,
 }
 
@@ -93,10 +101,41 @@ where
             config,
             executor_thread_pool,
             transaction_commit_hook,
+            trace_capture: None,
+            conflict_stats_capture: None,
             phantom: PhantomData,
         }
     }
 
+    /// Enables capturing the trace of parallel executions. If a parallel execution fails,
+    /// its trace is stored in the given capture (replacing any previously stored trace).
+    pub fn with_trace_capture(mut self, trace_capture: Arc<ExecutionTraceCapture<T::Key>>) -> Self {
+        self.trace_capture = Some(trace_capture);
+        self
+    }
+
+    /// Enables capturing the per-transaction conflict statistics of parallel executions. The
+    /// statistics of each parallel execution are stored in the given capture (replacing any
+    /// previously stored statistics).
+    pub fn with_conflict_stats_capture(
+        mut self,
+        conflict_stats_capture: Arc<ConflictStatsCapture>,
+    ) -> Self {
+        self.conflict_stats_capture = Some(conflict_stats_capture);
+        self
+    }
+
+    fn record_execution_conflict_stats(
+        txn_idx: TxnIndex,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        conflict_stats_recorder: &ConflictStatsRecorder,
+    ) {
+        let dependency_wait_time = last_input_output
+            .read_set(txn_idx)
+            .map_or(Duration::ZERO, |read_set| read_set.dependency_wait_time());
+        conflict_stats_recorder.record_execution(txn_idx, dependency_wait_time);
+    }
+
     fn execute(
         idx_to_execute: TxnIndex,
         incarnation: Incarnation,
@@ -440,18 +479,26 @@ where
         shared_counter: &AtomicU32,
         executor: &E,
         block: &[T],
+        trace_recorder: Option<&TraceRecorder<T::Key>>,
+        conflict_stats_recorder: &ConflictStatsRecorder,
+        execution_watchdog: Option<&ExecutionWatchdog>,
+        worker_id: usize,
     ) -> Result<(), PanicOr<ParallelBlockExecutionError>> {
         let mut block_limit_processor = shared_commit_state.acquire();
 
         while let Some((txn_idx, incarnation)) = scheduler.try_commit() {
             if !Self::validate_commit_ready(txn_idx, versioned_cache, last_input_output)? {
                 // Transaction needs to be re-executed, one final time.
+                conflict_stats_recorder.record_validation_failure(txn_idx);
 
                 Self::update_transaction_on_abort(txn_idx, last_input_output, versioned_cache);
                 // We are going to skip reducing validation index here, as we
                 // are executing immediately, and will reduce it unconditionally
                 // after execution, inside finish_execution_during_commit.
                 // Because of that, we can also ignore _updates_outside result.
+                if let Some(execution_watchdog) = execution_watchdog {
+                    execution_watchdog.start_execution(worker_id, txn_idx, incarnation + 1);
+                }
                 let _updates_outside = Self::execute(
                     txn_idx,
                     incarnation + 1,
@@ -466,12 +513,36 @@ where
                         start_shared_counter,
                         shared_counter,
                     ),
-                )?;
+                )
+                .map_err(|err| {
+                    if let Some(trace_recorder) = trace_recorder {
+                        trace_recorder.record_execution_failure(txn_idx, incarnation + 1, &err);
+                    }
+                    err
+                })?;
+                if let Some(trace_recorder) = trace_recorder {
+                    trace_recorder.record_execution(txn_idx, incarnation + 1, last_input_output);
+                }
+                Self::record_execution_conflict_stats(
+                    txn_idx,
+                    last_input_output,
+                    conflict_stats_recorder,
+                );
+                if let Some(execution_watchdog) = execution_watchdog {
+                    execution_watchdog.finish_execution(worker_id)?;
+                }
 
                 scheduler.finish_execution_during_commit(txn_idx)?;
 
                 let validation_result =
                     Self::validate(txn_idx, last_input_output, versioned_cache)?;
+                if let Some(trace_recorder) = trace_recorder {
+                    trace_recorder.record(TraceEvent::Validation {
+                        txn_idx,
+                        incarnation: incarnation + 1,
+                        valid: validation_result,
+                    });
+                }
                 if !validation_result
                     || !Self::validate_commit_ready(txn_idx, versioned_cache, last_input_output)
                         .unwrap_or(false)
@@ -545,6 +616,9 @@ where
                 .collect::<Result<Vec<_>, _>>()?;
 
             last_input_output.record_finalized_group(txn_idx, finalized_groups);
+            if let Some(trace_recorder) = trace_recorder {
+                trace_recorder.record(TraceEvent::Commit { txn_idx });
+            }
             defer! {
                 scheduler.add_to_commit_queue(txn_idx);
             }
@@ -657,10 +731,11 @@ where
         let materialized_finalized_groups =
             map_id_to_values_in_group_writes(finalized_groups, &latest_view)?;
 
-        let serialized_groups =
-            serialize_groups::<T>(materialized_finalized_groups).map_err(|e| {
-                code_invariant_error(format!("Panic error in serializing groups {e:?}"))
-            })?;
+        let serialized_groups = serialize_groups::<T>(
+            materialized_finalized_groups,
+            &self.config.local.resource_group_serialization,
+        )
+        .map_err(|e| code_invariant_error(format!("Panic error in serializing groups {e:?}")))?;
 
         let resource_write_set = last_input_output.take_resource_write_set(txn_idx);
         let resource_writes_to_materialize = resource_writes_to_materialize!(
@@ -732,6 +807,10 @@ where
         shared_counter: &AtomicU32,
         shared_commit_state: &ExplicitSyncWrapper<BlockGasLimitProcessor<T>>,
         final_results: &ExplicitSyncWrapper<Vec<E::Output>>,
+        trace_recorder: Option<&TraceRecorder<T::Key>>,
+        conflict_stats_recorder: &ConflictStatsRecorder,
+        execution_watchdog: Option<&ExecutionWatchdog>,
+        worker_id: usize,
     ) -> Result<(), PanicOr<ParallelBlockExecutionError>> {
         // Make executor for each task. TODO: fast concurrent executor.
         let init_timer = VM_INIT_SECONDS.start_timer();
@@ -771,15 +850,35 @@ where
                     shared_counter,
                     &executor,
                     block,
+                    trace_recorder,
+                    conflict_stats_recorder,
+                    execution_watchdog,
+                    worker_id,
                 )?;
                 scheduler.queueing_commits_mark_done();
             }
 
             drain_commit_queue()?;
 
+            // Halt as soon as an ongoing execution (e.g., of another worker) exceeds the budget,
+            // instead of waiting for the pathological transaction to finish.
+            if let Some(execution_watchdog) = execution_watchdog {
+                execution_watchdog.check_ongoing_executions()?;
+            }
+
             scheduler_task = match scheduler_task {
                 SchedulerTask::ValidationTask(txn_idx, incarnation, wave) => {
                     let valid = Self::validate(txn_idx, last_input_output, versioned_cache)?;
+                    if let Some(trace_recorder) = trace_recorder {
+                        trace_recorder.record(TraceEvent::Validation {
+                            txn_idx,
+                            incarnation,
+                            valid,
+                        });
+                    }
+                    if !valid {
+                        conflict_stats_recorder.record_validation_failure(txn_idx);
+                    }
                     Self::update_on_validation(
                         txn_idx,
                         incarnation,
@@ -795,6 +894,9 @@ where
                     incarnation,
                     ExecutionTaskType::Execution,
                 ) => {
+                    if let Some(execution_watchdog) = execution_watchdog {
+                        execution_watchdog.start_execution(worker_id, txn_idx, incarnation);
+                    }
                     let updates_outside = Self::execute(
                         txn_idx,
                         incarnation,
@@ -809,7 +911,24 @@ where
                             start_shared_counter,
                             shared_counter,
                         ),
-                    )?;
+                    )
+                    .map_err(|err| {
+                        if let Some(trace_recorder) = trace_recorder {
+                            trace_recorder.record_execution_failure(txn_idx, incarnation, &err);
+                        }
+                        err
+                    })?;
+                    if let Some(trace_recorder) = trace_recorder {
+                        trace_recorder.record_execution(txn_idx, incarnation, last_input_output);
+                    }
+                    Self::record_execution_conflict_stats(
+                        txn_idx,
+                        last_input_output,
+                        conflict_stats_recorder,
+                    );
+                    if let Some(execution_watchdog) = execution_watchdog {
+                        execution_watchdog.finish_execution(worker_id)?;
+                    }
                     scheduler.finish_execution(txn_idx, incarnation, updates_outside)?
                 },
                 SchedulerTask::ExecutionTask(_, _, ExecutionTaskType::Wakeup(condvar)) => {
@@ -865,6 +984,7 @@ where
             num_txns,
         ));
         let shared_maybe_error = AtomicBool::new(false);
+        let shared_budget_exceeded = AtomicBool::new(false);
 
         let final_results = ExplicitSyncWrapper::new(Vec::with_capacity(num_txns));
 
@@ -878,23 +998,61 @@ where
 
         let last_input_output = TxnLastInputOutput::new(num_txns);
         let scheduler = Scheduler::new(num_txns);
+        let trace_recorder = self.trace_capture.as_ref().map(|_| TraceRecorder::new());
+        let conflict_stats_recorder = ConflictStatsRecorder::new(num_txns);
+        let execution_watchdog = self
+            .config
+            .local
+            .txn_execution_time_budget_ms
+            .map(|budget_ms| {
+                ExecutionWatchdog::new(budget_ms, self.config.local.concurrency_level)
+            });
 
         let timer = RAYON_EXECUTION_SECONDS.start_timer();
         self.executor_thread_pool.scope(|s| {
-            for _ in 0..self.config.local.concurrency_level {
-                s.spawn(|_| {
+            for worker_id in 0..self.config.local.concurrency_level {
+                let execution_watchdog = execution_watchdog.as_ref();
+                let scheduler = &scheduler;
+                let last_input_output = &last_input_output;
+                let versioned_cache = &versioned_cache;
+                let shared_counter = &shared_counter;
+                let shared_commit_state = &shared_commit_state;
+                let final_results = &final_results;
+                let trace_recorder = &trace_recorder;
+                let conflict_stats_recorder = &conflict_stats_recorder;
+                let executor_initial_arguments = &executor_initial_arguments;
+                let shared_budget_exceeded = &shared_budget_exceeded;
+                let shared_maybe_error = &shared_maybe_error;
+                s.spawn(move |_| {
                     if let Err(err) = self.worker_loop(
-                        &executor_initial_arguments,
+                        executor_initial_arguments,
                         signature_verified_block,
-                        &last_input_output,
-                        &versioned_cache,
-                        &scheduler,
+                        last_input_output,
+                        versioned_cache,
+                        scheduler,
                         base_view,
                         start_shared_counter,
-                        &shared_counter,
-                        &shared_commit_state,
-                        &final_results,
+                        shared_counter,
+                        shared_commit_state,
+                        final_results,
+                        trace_recorder.as_ref(),
+                        conflict_stats_recorder,
+                        execution_watchdog,
+                        worker_id,
                     ) {
+                        if let Some(trace_recorder) = trace_recorder {
+                            trace_recorder.record(TraceEvent::Halt {
+                                error: format!("{:?}", err),
+                            });
+                        }
+                        if matches!(
+                            err,
+                            PanicOr::Or(
+                                ParallelBlockExecutionError::TxnExecutionTimeBudgetExceeded
+                            )
+                        ) {
+                            shared_budget_exceeded.store(true, Ordering::SeqCst);
+                        }
                         // If there are multiple errors, they all get logged:
                         // ModulePathReadWriteError and FatalVMErrorvariant is logged at construction,
                         // and below we log CodeInvariantErrors.
@@ -916,10 +1074,33 @@ where
         // Explicit async drops.
         DEFAULT_DROPPER.schedule_drop((last_input_output, scheduler, versioned_cache));
 
+        let has_error = shared_maybe_error.load(Ordering::SeqCst);
+        if has_error && shared_budget_exceeded.load(Ordering::SeqCst) {
+            counters::TXN_EXECUTION_TIME_BUDGET_COUNT
+                .with_label_values(&["block_fallback"])
+                .inc();
+        }
+        if let (Some(trace_capture), Some(trace_recorder)) = (&self.trace_capture, trace_recorder) {
+            if has_error {
+                let trace =
+                    trace_recorder.into_trace(num_txns, self.config.local.concurrency_level);
+                info!(
+                    "[BlockSTM]: captured trace of failed parallel execution ({} events)",
+                    trace.records.len()
+                );
+                trace_capture.store_failed_trace(trace);
+            }
+        }
+        let conflict_stats = conflict_stats_recorder
+            .into_block_stats(self.config.local.concurrency_level, !has_error);
+        if let Some(conflict_stats_capture) = &self.conflict_stats_capture {
+            conflict_stats_capture.store_block_stats(conflict_stats);
+        }
+
         // TODO add block end info to output.
         // block_limit_processor.is_block_limit_reached();
 
-        (!shared_maybe_error.load(Ordering::SeqCst))
+        (!has_error)
             .then(|| BlockOutput::new(final_results.into_inner()))
             .ok_or(())
     }
@@ -1211,10 +1392,13 @@ where
                             .collect::<Result<Vec<_>, _>>()?;
                         let materialized_finalized_groups =
                             map_id_to_values_in_group_writes(finalized_groups, &latest_view)?;
-                        let serialized_groups =
-                            serialize_groups::<T>(materialized_finalized_groups).map_err(|_| {
-                                SequentialBlockExecutionError::ResourceGroupSerializationError
-                            })?;
+                        let serialized_groups = serialize_groups::<T>(
+                            materialized_finalized_groups,
+                            &self.config.local.resource_group_serialization,
+                        )
+                        .map_err(|_| {
+                            SequentialBlockExecutionError::ResourceGroupSerializationError
+                        })?;
 
                         let resource_writes_to_materialize = resource_writes_to_materialize!(
                             resource_write_set,
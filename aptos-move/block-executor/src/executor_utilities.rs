@@ -6,15 +6,17 @@ use aptos_aggregator::types::code_invariant_error;
 use aptos_logger::error;
 use aptos_mvhashmap::types::ValueWithLayout;
 use aptos_types::{
-    contract_event::TransactionEvent, delayed_fields::PanicError, executable::Executable,
-    state_store::TStateView, transaction::BlockExecutableTransaction as Transaction,
-    write_set::TransactionWrite,
+    block_executor::config::ResourceGroupSerializationConfig, contract_event::TransactionEvent,
+    delayed_fields::PanicError, executable::Executable, state_store::TStateView,
+    transaction::BlockExecutableTransaction as Transaction, write_set::TransactionWrite,
 };
 use aptos_vm_logging::{alert, prelude::*};
 use bytes::Bytes;
 use fail::fail_point;
 use move_core_types::value::MoveTypeLayout;
 use rand::{thread_rng, Rng};
+use rayon::prelude::*;
+use serde::Serialize;
 use std::{collections::BTreeMap, sync::Arc};
 
 // TODO(clean-up): refactor & replace these macros with functions for code clarity. Currently
@@ -118,6 +120,7 @@ pub(crate) fn map_finalized_group<T: Transaction>(
 
 pub(crate) fn serialize_groups<T: Transaction>(
     finalized_groups: Vec<(T::Key, T::Value, Vec<(T::Tag, Arc<T::Value>)>)>,
+    config: &ResourceGroupSerializationConfig,
 ) -> Result<Vec<(T::Key, T::Value)>, ResourceGroupSerializationError> {
     fail_point!(
         "fail-point-resource-group-serialization",
@@ -125,9 +128,9 @@ pub(crate) fn serialize_groups<T: Transaction>(
         |_| Err(ResourceGroupSerializationError)
     );
 
-    finalized_groups
+    let finalized_groups: Vec<(T::Key, T::Value, BTreeMap<T::Tag, Bytes>)> = finalized_groups
         .into_iter()
-        .map(|(group_key, mut metadata_op, finalized_group)| {
+        .map(|(group_key, metadata_op, finalized_group)| {
             let btree: BTreeMap<T::Tag, Bytes> = finalized_group
                 .into_iter()
                 .map(|(resource_tag, arc_v)| {
@@ -137,18 +140,117 @@ pub(crate) fn serialize_groups<T: Transaction>(
                     (resource_tag, bytes)
                 })
                 .collect();
+            (group_key, metadata_op, btree)
+        })
+        .collect();
 
-            bcs::to_bytes(&btree)
-                .map_err(|e| {
-                    alert!("Unexpected resource group error {:?}", e);
-                    ResourceGroupSerializationError
-                })
-                .map(|group_bytes| {
-                    metadata_op.set_bytes(group_bytes.into());
-                    (group_key, metadata_op)
-                })
+    // Oversized groups are split into chunks (serialized in parallel), while small groups
+    // are merged into a single buffer. Either way, the bytes match the bcs serialization.
+    let mut serialized_groups: Vec<Option<Bytes>> = vec![None; finalized_groups.len()];
+    let mut merged_group_indices = vec![];
+    for (idx, (_, _, btree)) in finalized_groups.iter().enumerate() {
+        let group_size = btree.values().map(Bytes::len).sum::<usize>();
+        let group_bytes = match (config.split_threshold_bytes, config.merge_threshold_bytes) {
+            (Some(split_threshold), _) if group_size > split_threshold => {
+                serialize_group_in_chunks(btree, split_threshold)
+            },
+            (_, Some(merge_threshold)) if group_size < merge_threshold => {
+                merged_group_indices.push(idx);
+                continue;
+            },
+            _ => bcs::to_bytes(btree),
+        };
+        serialized_groups[idx] = Some(group_bytes.map_err(map_group_serialization_error)?.into());
+    }
+    if !merged_group_indices.is_empty() {
+        let mut buffer = vec![];
+        let mut ranges = Vec::with_capacity(merged_group_indices.len());
+        for idx in merged_group_indices.iter() {
+            let start = buffer.len();
+            bcs::serialize_into(&mut buffer, &finalized_groups[*idx].2)
+                .map_err(map_group_serialization_error)?;
+            ranges.push(start..buffer.len());
+        }
+        let buffer = Bytes::from(buffer);
+        for (idx, range) in merged_group_indices.into_iter().zip(ranges) {
+            serialized_groups[idx] = Some(buffer.slice(range));
+        }
+    }
+
+    Ok(finalized_groups
+        .into_iter()
+        .zip(serialized_groups)
+        .map(|((group_key, mut metadata_op, _), group_bytes)| {
+            metadata_op.set_bytes(group_bytes.expect("All groups must be serialized"));
+            (group_key, metadata_op)
         })
-        .collect()
+        .collect())
+}
+
+fn map_group_serialization_error(e: bcs::Error) -> ResourceGroupSerializationError {
+    alert!("Unexpected resource group error {:?}", e);
+    ResourceGroupSerializationError
+}
+
+/// Serializes the group in chunks of (roughly) the given number of resource bytes, in parallel.
+/// The result is identical to bcs::to_bytes(group): bcs serializes a map as its length followed
+/// by the entries, sorted by their serialized keys.
+pub(crate) fn serialize_group_in_chunks<Tag: Serialize + Sync>(
+    group: &BTreeMap<Tag, Bytes>,
+    chunk_size_bytes: usize,
+) -> bcs::Result<Vec<u8>> {
+    let mut entries = group
+        .iter()
+        .map(|(tag, bytes)| Ok((bcs::to_bytes(tag)?, bytes)))
+        .collect::<bcs::Result<Vec<_>>>()?;
+    entries.sort_by(|(tag_a, _), (tag_b, _)| tag_a.cmp(tag_b));
+
+    let mut chunks = vec![];
+    let mut chunk_start = 0;
+    let mut chunk_size = 0;
+    for (idx, (_, bytes)) in entries.iter().enumerate() {
+        chunk_size += bytes.len();
+        if chunk_size >= chunk_size_bytes {
+            chunks.push(&entries[chunk_start..=idx]);
+            chunk_start = idx + 1;
+            chunk_size = 0;
+        }
+    }
+    if chunk_start < entries.len() {
+        chunks.push(&entries[chunk_start..]);
+    }
+
+    let serialized_chunks = chunks
+        .par_iter()
+        .map(|chunk| -> bcs::Result<Vec<u8>> {
+            let mut chunk_bytes = vec![];
+            for (tag_bytes, bytes) in chunk.iter() {
+                chunk_bytes.extend_from_slice(tag_bytes);
+                bcs::serialize_into(&mut chunk_bytes, bytes)?;
+            }
+            Ok(chunk_bytes)
+        })
+        .collect::<bcs::Result<Vec<_>>>()?;
+
+    let mut group_bytes = Vec::with_capacity(
+        MAX_ULEB128_BYTES + serialized_chunks.iter().map(Vec::len).sum::<usize>(),
+    );
+    write_uleb128(&mut group_bytes, entries.len());
+    for chunk_bytes in serialized_chunks {
+        group_bytes.extend(chunk_bytes);
+    }
+    Ok(group_bytes)
+}
+
+// The max number of bytes of an uleb128 encoded length (bcs lengths fit in 32 bits)
+const MAX_ULEB128_BYTES: usize = 5;
+
+fn write_uleb128(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
 }
 
 pub(crate) fn gen_id_start_value(sequential: bool) -> u32 {
@@ -140,8 +140,10 @@ subsequent incarnation to finish.
 extern crate scopeguard;
 
 mod captured_reads;
+pub mod conflict_stats;
 pub mod counters;
 pub mod errors;
+mod execution_watchdog;
 pub mod executor;
 mod executor_utilities;
 pub mod explicit_sync_wrapper;
@@ -150,6 +152,7 @@ mod limit_processor;
 pub mod proptest_types;
 mod scheduler;
 pub mod task;
+pub mod trace;
 pub mod txn_commit_hook;
 pub mod txn_last_input_output;
 pub mod types;
@@ -25,7 +25,13 @@ use aptos_vm_types::resource_group_adapter::group_size_as_sum;
 use bytes::Bytes;
 use claims::{assert_matches, assert_none, assert_some, assert_some_eq};
 use itertools::izip;
-use std::{collections::HashMap, fmt::Debug, hash::Hash, result::Result, sync::atomic::Ordering};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    hash::Hash,
+    result::Result,
+    sync::atomic::Ordering,
+};
 
 // TODO: extend to derived values, and code.
 #[derive(Clone)]
@@ -89,6 +95,17 @@ pub(crate) struct BaselineOutput<K> {
     read_values: Vec<Result<Vec<BaselineValue>, ()>>,
     resolved_deltas: Vec<Result<HashMap<K, u128>, ()>>,
     group_reads: Vec<Result<Vec<(K, u32)>, ()>>,
+    /// Gas charged by each of the committed transactions.
+    gas_used: Vec<u64>,
+}
+
+/// The first committed transaction for which the gas charged by the block executor differs
+/// from the baseline. None for the gas means that the transaction was not committed.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct GasDivergence {
+    pub(crate) txn_idx: usize,
+    pub(crate) baseline_gas: Option<u64>,
+    pub(crate) output_gas: Option<u64>,
 }
 
 impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
@@ -105,6 +122,7 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
         let mut read_values = vec![];
         let mut resolved_deltas = vec![];
         let mut group_reads = vec![];
+        let mut gas_used = vec![];
 
         for txn in txns.iter() {
             match txn {
@@ -117,6 +135,7 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
                     // transaction, so create a successful empty reads and deltas.
                     read_values.push(Ok(vec![]));
                     resolved_deltas.push(Ok(HashMap::new()));
+                    gas_used.push(*gas);
 
                     // gas in SkipRest is used for unit tests for now (can generalize when needed).
                     assert_eq!(*gas, 0);
@@ -133,6 +152,8 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
                     // the last mock execution, and is >= 1 because there is at least one execution.
                     let last_incarnation = (incarnation_counter.load(Ordering::SeqCst) - 1)
                         % incarnation_behaviors.len();
+                    // The mock output charges the gas of the incarnation even if it fails.
+                    gas_used.push(incarnation_behaviors[last_incarnation].gas);
 
                     match incarnation_behaviors[last_incarnation]
                         .deltas
@@ -219,9 +240,33 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
             read_values,
             resolved_deltas,
             group_reads,
+            gas_used,
         }
     }
 
+    /// Compares the gas charged by the transactions in the block output with the baseline, and
+    /// returns the first divergence, if any. Transactions that are not committed (according to
+    /// the baseline) must be skipped in the block output.
+    pub(crate) fn find_gas_divergence<E: Debug>(
+        &self,
+        block_output: &BlockOutput<MockOutput<K, E>>,
+    ) -> Option<GasDivergence> {
+        let results = block_output.get_transaction_outputs_forced();
+        let committed = self.gas_used.len();
+
+        results.iter().enumerate().find_map(|(txn_idx, output)| {
+            let baseline_gas = self.gas_used.get(txn_idx).copied();
+            // Outputs after the committed prefix are only expected to be skipped.
+            let output_gas = (txn_idx < committed || !output.skipped).then_some(output.total_gas);
+
+            (baseline_gas != output_gas).then_some(GasDivergence {
+                txn_idx,
+                baseline_gas,
+                output_gas,
+            })
+        })
+    }
+
     fn assert_success<E: Debug>(&self, block_output: &BlockOutput<MockOutput<K, E>>) {
         let base_map: HashMap<u32, Bytes> = HashMap::from([(RESERVED_TAG, vec![0].into())]);
         let mut group_world = HashMap::new();
@@ -229,6 +274,8 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
         let results = block_output.get_transaction_outputs_forced();
         let committed = self.read_values.len();
         assert_eq!(self.resolved_deltas.len(), committed);
+        assert_eq!(self.gas_used.len(), committed);
+        assert_none!(self.find_gas_divergence(block_output));
 
         // Check read values & delta writes.
         izip!(
@@ -300,6 +347,27 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
                 }
             }
 
+            // Test the serialized group writes: each must contain the whole group (regardless
+            // of the resource group serialization thresholds), i.e. the group_world contents.
+            let materialized_group_writes = output
+                .materialized_group_writes
+                .get()
+                .expect("Group writes must be set");
+            assert_eq!(materialized_group_writes.len(), output.group_writes.len());
+            for (group_key, group_write) in materialized_group_writes.iter() {
+                let group: BTreeMap<u32, Bytes> =
+                    bcs::from_bytes(group_write.bytes().expect("Groups are never deleted"))
+                        .expect("Group must deserialize");
+                let baseline_group_map = group_world.entry(group_key).or_insert(base_map.clone());
+                assert_eq!(
+                    group,
+                    baseline_group_map
+                        .iter()
+                        .map(|(tag, v)| (*tag, v.clone()))
+                        .collect::<BTreeMap<_, _>>()
+                );
+            }
+
             // Test recorded finalized group writes: it should contain the whole group, and
             // as such, correspond to the contents of the group_world.
             // TODO: figure out what can still be tested here, e.g. RESERVED_TAG
@@ -4,6 +4,7 @@
 
 pub(crate) mod baseline;
 pub mod bencher;
+pub mod replay;
 #[cfg(test)]
 mod tests;
 pub(crate) mod types;
@@ -0,0 +1,161 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deterministic replay of captured parallel execution traces.
+//!
+//! The replay processes the recorded events in order on a single thread, tracking the latest
+//! incarnation (and its reads and writes) of each transaction, and checks that the recorded
+//! interleaving is consistent with Block-STM:
+//! - the incarnations of each transaction are executed in order, and validations refer to
+//!   incarnations that were executed,
+//! - transactions are committed in order, each exactly once, and
+//! - every versioned read of a committed incarnation observes the latest write of a lower
+//!   committed transaction to the same key (or storage, if there is no such write).
+//!
+//! Reads that were not resolved from a single version (see `TracedReadVersion::Unversioned`)
+//! are not checked.
+
+use crate::trace::{BlockExecutionTrace, TraceEvent, TracedRead, TracedReadVersion};
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+use std::{collections::BTreeMap, fmt::Debug};
+
+/// The summary of a successfully replayed trace.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReplaySummary {
+    pub num_executions: usize,
+    pub num_failed_validations: usize,
+    pub num_committed: TxnIndex,
+    /// The block-level errors that halted the execution, in the recorded order.
+    pub halt_errors: Vec<String>,
+}
+
+/// The latest recorded incarnation of a transaction.
+struct ReplayedIncarnation<K> {
+    incarnation: Incarnation,
+    reads: Vec<TracedRead<K>>,
+    writes: Vec<K>,
+}
+
+/// Replays the trace and returns its summary, or a description of the first event that
+/// diverges from the expected Block-STM behavior.
+pub fn replay_trace<K: Clone + Debug + Ord>(
+    trace: &BlockExecutionTrace<K>,
+) -> Result<ReplaySummary, String> {
+    let mut summary = ReplaySummary::default();
+    let mut latest_incarnations: BTreeMap<TxnIndex, ReplayedIncarnation<K>> = BTreeMap::new();
+    let mut executed_incarnations: BTreeMap<TxnIndex, Incarnation> = BTreeMap::new();
+    // The latest committed write (txn index and incarnation) to each key.
+    let mut committed_writes: BTreeMap<K, (TxnIndex, Incarnation)> = BTreeMap::new();
+
+    for (idx, record) in trace.records.iter().enumerate() {
+        match &record.event {
+            TraceEvent::Execution {
+                txn_idx,
+                incarnation,
+                reads,
+                writes,
+                ..
+            } => {
+                check_next_incarnation(idx, *txn_idx, *incarnation, trace, &executed_incarnations)?;
+                executed_incarnations.insert(*txn_idx, *incarnation);
+                latest_incarnations.insert(*txn_idx, ReplayedIncarnation {
+                    incarnation: *incarnation,
+                    reads: reads.clone(),
+                    writes: writes.clone(),
+                });
+                summary.num_executions += 1;
+            },
+            TraceEvent::ExecutionFailure {
+                txn_idx,
+                incarnation,
+                ..
+            } => {
+                check_next_incarnation(idx, *txn_idx, *incarnation, trace, &executed_incarnations)?;
+                executed_incarnations.insert(*txn_idx, *incarnation);
+            },
+            TraceEvent::Validation {
+                txn_idx,
+                incarnation,
+                valid,
+            } => {
+                if executed_incarnations
+                    .get(txn_idx)
+                    .map_or(true, |executed| incarnation > executed)
+                {
+                    return Err(format!(
+                        "Event {}: validation of txn {} incarnation {} before its execution",
+                        idx, txn_idx, incarnation
+                    ));
+                }
+                if !valid {
+                    summary.num_failed_validations += 1;
+                }
+            },
+            TraceEvent::Commit { txn_idx } => {
+                if *txn_idx != summary.num_committed {
+                    return Err(format!(
+                        "Event {}: commit of txn {}, expected txn {}",
+                        idx, txn_idx, summary.num_committed
+                    ));
+                }
+                let committed = latest_incarnations.get(txn_idx).ok_or_else(|| {
+                    format!(
+                        "Event {}: commit of txn {} that was never executed",
+                        idx, txn_idx
+                    )
+                })?;
+
+                for read in &committed.reads {
+                    let expected_version = match committed_writes.get(&read.key) {
+                        Some((writer_idx, writer_incarnation)) => {
+                            TracedReadVersion::Txn(*writer_idx, *writer_incarnation)
+                        },
+                        None => TracedReadVersion::Storage,
+                    };
+                    if read.version != TracedReadVersion::Unversioned
+                        && read.version != expected_version
+                    {
+                        return Err(format!(
+                            "Event {}: committed txn {} read {:?} at {:?}, expected {:?}",
+                            idx, txn_idx, read.key, read.version, expected_version
+                        ));
+                    }
+                }
+                for key in &committed.writes {
+                    committed_writes.insert(key.clone(), (*txn_idx, committed.incarnation));
+                }
+                summary.num_committed += 1;
+            },
+            TraceEvent::Halt { error } => summary.halt_errors.push(error.clone()),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Checks that the given incarnation is the next incarnation of the transaction.
+fn check_next_incarnation(
+    idx: usize,
+    txn_idx: TxnIndex,
+    incarnation: Incarnation,
+    trace: &BlockExecutionTrace<impl Debug>,
+    executed_incarnations: &BTreeMap<TxnIndex, Incarnation>,
+) -> Result<(), String> {
+    if txn_idx >= trace.num_txns {
+        return Err(format!(
+            "Event {}: execution of txn {} in a block of {} txns",
+            idx, txn_idx, trace.num_txns
+        ));
+    }
+
+    let expected_incarnation = executed_incarnations
+        .get(&txn_idx)
+        .map_or(0, |executed| executed + 1);
+    if incarnation != expected_incarnation {
+        return Err(format!(
+            "Event {}: execution of txn {} incarnation {}, expected incarnation {}",
+            idx, txn_idx, incarnation, expected_incarnation
+        ));
+    }
+    Ok(())
+}
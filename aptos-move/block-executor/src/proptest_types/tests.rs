@@ -3,23 +3,28 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    conflict_stats::{BlockConflictStats, ConflictStatsCapture},
     errors::SequentialBlockExecutionError,
     executor::BlockExecutor,
     proptest_types::{
-        baseline::BaselineOutput,
+        baseline::{BaselineOutput, GasDivergence},
+        replay::replay_trace,
         types::{
-            DeltaDataView, EmptyDataView, KeyType, MockEvent, MockOutput, MockTask,
-            MockTransaction, NonEmptyGroupDataView, TransactionGen, TransactionGenParams,
-            MAX_GAS_PER_TXN,
+            DeltaDataView, EmptyDataView, GasPerturbation, GasPerturbingMockTask, KeyType,
+            MockEvent, MockOutput, MockTask, MockTransaction, NonEmptyGroupDataView,
+            TransactionGen, TransactionGenParams, MAX_GAS_PER_TXN,
         },
     },
+    trace::{BlockExecutionTrace, ExecutionTraceCapture, TraceEvent},
     txn_commit_hook::NoOpTransactionCommitHook,
 };
+use aptos_mvhashmap::types::TxnIndex;
 use aptos_types::{
-    block_executor::config::BlockExecutorConfig, contract_event::TransactionEvent,
+    block_executor::config::{BlockExecutorConfig, ResourceGroupSerializationConfig},
+    contract_event::TransactionEvent,
     executable::ExecutableTestType,
 };
-use claims::{assert_matches, assert_ok};
+use claims::{assert_matches, assert_ok, assert_some};
 use num_cpus;
 use proptest::{
     collection::vec,
@@ -475,6 +480,231 @@ fn publishing_fixed_params_with_block_gas_limit(
     }
 }
 
+#[test]
+fn module_publishing_fallback_trace_replay() {
+    let mut runner = TestRunner::default();
+
+    let universe = vec(any::<[u8; 32]>(), 50)
+        .new_tree(&mut runner)
+        .expect("creating a new value should succeed")
+        .current();
+    let transaction_gen = vec(
+        any_with::<TransactionGen<[u8; 32]>>(TransactionGenParams::new_dynamic()),
+        300,
+    )
+    .new_tree(&mut runner)
+    .expect("creating a new value should succeed")
+    .current();
+
+    // First 12 keys are normal paths, next 14 are module reads, then writes.
+    let mut transactions: Vec<_> = transaction_gen
+        .into_iter()
+        .map(|txn_gen| txn_gen.materialize_disjoint_module_rw(&universe[0..40], 12, 26))
+        .collect();
+
+    let data_view = DeltaDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+    let trace_capture = Arc::new(ExecutionTraceCapture::new());
+    let block_executor = BlockExecutor::<
+        MockTransaction<KeyType<[u8; 32]>, MockEvent>,
+        MockTask<KeyType<[u8; 32]>, MockEvent>,
+        DeltaDataView<KeyType<[u8; 32]>>,
+        NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>,
+        ExecutableTestType,
+    >::new(
+        BlockExecutorConfig::new_no_block_limit(num_cpus::get()),
+        executor_thread_pool,
+        None,
+    )
+    .with_trace_capture(trace_capture.clone());
+
+    // Successful parallel executions should not store a trace.
+    assert_ok!(block_executor.execute_transactions_parallel((), &transactions, &data_view));
+    assert!(trace_capture.take_last_failed_trace().is_none());
+
+    // Make txn 10 write and txn 20 read the same module path, forcing a fallback.
+    for (txn_idx, is_write) in [(10, true), (20, false)] {
+        match transactions.get_mut(txn_idx).unwrap() {
+            MockTransaction::Write {
+                incarnation_counter: _,
+                incarnation_behaviors,
+            } => {
+                incarnation_behaviors.iter_mut().for_each(|behavior| {
+                    if is_write {
+                        let val = behavior.writes[0].1.clone();
+                        behavior.writes.push((KeyType(universe[42], true), val));
+                    } else {
+                        behavior.reads.push(KeyType(universe[42], true));
+                    }
+                });
+            },
+            _ => {
+                unreachable!();
+            },
+        };
+    }
+    assert_matches!(
+        block_executor.execute_transactions_parallel((), &transactions, &data_view),
+        Err(())
+    );
+
+    // The captured trace must survive serialization and replay deterministically.
+    let trace = trace_capture
+        .take_last_failed_trace()
+        .expect("Trace of the failed execution must be captured");
+    assert_eq!(trace.num_txns, 300);
+    let decoded_trace: BlockExecutionTrace<KeyType<[u8; 32]>> =
+        BlockExecutionTrace::from_bytes(&trace.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded_trace, trace);
+
+    let summary = replay_trace(&trace).unwrap();
+    assert_eq!(replay_trace(&trace).unwrap(), summary);
+    assert!(summary.num_executions > 0);
+    assert!(summary.num_committed < 300);
+    assert!(summary
+        .halt_errors
+        .iter()
+        .any(|error| error.contains("ModulePathReadWriteError")));
+    assert!(trace.records.iter().any(|record| matches!(
+        record.event,
+        TraceEvent::ExecutionFailure { txn_idx, .. } if txn_idx == 10 || txn_idx == 20
+    )));
+}
+
+#[test]
+fn txn_execution_time_budget_fallback() {
+    let mut runner = TestRunner::default();
+
+    let universe = vec(any::<[u8; 32]>(), 50)
+        .new_tree(&mut runner)
+        .expect("creating a new value should succeed")
+        .current();
+    let transaction_gen = vec(
+        any_with::<TransactionGen<[u8; 32]>>(TransactionGenParams::new_dynamic()),
+        300,
+    )
+    .new_tree(&mut runner)
+    .expect("creating a new value should succeed")
+    .current();
+    let transactions: Vec<_> = transaction_gen
+        .into_iter()
+        .map(|txn_gen| txn_gen.materialize(&universe, (false, false)))
+        .collect();
+
+    let data_view = EmptyDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+    let baseline = BaselineOutput::generate(&transactions, None);
+
+    for (budget_ms, expect_parallel_success) in [(u64::MAX, true), (0, false)] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get());
+        config.local.txn_execution_time_budget_ms = Some(budget_ms);
+        let block_executor = BlockExecutor::<
+            MockTransaction<KeyType<[u8; 32]>, MockEvent>,
+            MockTask<KeyType<[u8; 32]>, MockEvent>,
+            EmptyDataView<KeyType<[u8; 32]>>,
+            NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>,
+            ExecutableTestType,
+        >::new(config, executor_thread_pool.clone(), None);
+
+        let parallel_output =
+            block_executor.execute_transactions_parallel((), &transactions, &data_view);
+        assert_eq!(parallel_output.is_ok(), expect_parallel_success);
+        baseline.assert_parallel_output(&parallel_output);
+
+        // Exceeding the budget must fall back to the sequential execution.
+        baseline.assert_output(&block_executor.execute_block((), &transactions, &data_view));
+    }
+}
+
+#[test]
+fn conflict_stats_capture() {
+    let mut runner = TestRunner::default();
+
+    // A small key universe, for the transactions to conflict.
+    let universe = vec(any::<[u8; 32]>(), 10)
+        .new_tree(&mut runner)
+        .expect("creating a new value should succeed")
+        .current();
+    let transaction_gen = vec(
+        any_with::<TransactionGen<[u8; 32]>>(TransactionGenParams::new_dynamic()),
+        300,
+    )
+    .new_tree(&mut runner)
+    .expect("creating a new value should succeed")
+    .current();
+    let transactions: Vec<_> = transaction_gen
+        .into_iter()
+        .map(|txn_gen| txn_gen.materialize(&universe, (false, false)))
+        .collect();
+
+    let data_view = EmptyDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+    let conflict_stats_capture = Arc::new(ConflictStatsCapture::new());
+    let block_executor = BlockExecutor::<
+        MockTransaction<KeyType<[u8; 32]>, MockEvent>,
+        MockTask<KeyType<[u8; 32]>, MockEvent>,
+        EmptyDataView<KeyType<[u8; 32]>>,
+        NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>,
+        ExecutableTestType,
+    >::new(
+        BlockExecutorConfig::new_no_block_limit(num_cpus::get()),
+        executor_thread_pool,
+        None,
+    )
+    .with_conflict_stats_capture(conflict_stats_capture.clone());
+
+    let output = block_executor.execute_transactions_parallel((), &transactions, &data_view);
+    BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
+
+    // Every transaction must be executed, and can only be re-executed after a failed validation.
+    let block_stats = conflict_stats_capture
+        .take_last_block_stats()
+        .expect("Conflict statistics of the execution must be captured");
+    assert!(block_stats.succeeded);
+    assert_eq!(block_stats.txn_stats.len(), 300);
+    assert!(block_stats
+        .txn_stats
+        .iter()
+        .all(|txn_stats| txn_stats.num_executions > 0));
+    let num_re_executions: u32 = block_stats
+        .txn_stats
+        .iter()
+        .map(|txn_stats| txn_stats.num_re_executions())
+        .sum();
+    let num_validation_failures: u32 = block_stats
+        .txn_stats
+        .iter()
+        .map(|txn_stats| txn_stats.num_validation_failures)
+        .sum();
+    assert!(num_re_executions <= num_validation_failures);
+
+    // The statistics must survive serialization.
+    let decoded_block_stats = BlockConflictStats::from_bytes(&block_stats.to_bytes().unwrap());
+    assert_eq!(decoded_block_stats.unwrap(), block_stats);
+    assert!(conflict_stats_capture.take_last_block_stats().is_none());
+}
+
 #[test_case(1000, 100, 30, 15, 0)]
 #[test_case(1000, 50, 20, 10, 0)]
 #[test_case(1000, 15, 5, 5, 0)]
@@ -577,6 +807,95 @@ fn non_empty_group(
     }
 }
 
+// Tests resource group conflicts: many txns reading, writing and deleting a small number of
+// tags across multiple groups, compared against the baseline. The serialized groups must not
+// depend on the resource group serialization (split, merge) thresholds.
+#[test_case(1000, 50, 1, 4, false, None, None)]
+#[test_case(1000, 50, 2, 8, true, None, None)]
+#[test_case(1000, 50, 5, 3, false, None, None)]
+#[test_case(1000, 50, 5, 16, true, None, None)]
+#[test_case(1000, 50, 2, 8, false, Some(40), None)]
+#[test_case(1000, 50, 5, 16, true, None, Some(100))]
+#[test_case(1000, 50, 5, 16, false, Some(40), Some(100))]
+#[test_case(1000, 50, 5, 16, true, Some(1), Some(usize::MAX))]
+fn multi_group_conflicts(
+    num_txns: usize,
+    key_universe_len: usize,
+    num_groups: usize,
+    num_tags: u32,
+    query_group_sizes: bool,
+    split_threshold_bytes: Option<usize>,
+    merge_threshold_bytes: Option<usize>,
+) {
+    let mut runner = TestRunner::default();
+
+    let key_universe = vec(any::<[u8; 32]>(), key_universe_len)
+        .new_tree(&mut runner)
+        .expect("creating a new value should succeed")
+        .current();
+
+    let transaction_gen = vec(
+        any_with::<TransactionGen<[u8; 32]>>(TransactionGenParams::new_dynamic()),
+        num_txns,
+    )
+    .new_tree(&mut runner)
+    .expect("creating a new value should succeed")
+    .current();
+
+    let transactions: Vec<_> = transaction_gen
+        .into_iter()
+        .map(|txn_gen| {
+            txn_gen.materialize_group_conflicts::<[u8; 32], MockEvent>(
+                &key_universe,
+                num_groups,
+                num_tags,
+                query_group_sizes,
+            )
+        })
+        .collect();
+
+    let data_view = NonEmptyGroupDataView::<KeyType<[u8; 32]>> {
+        group_keys: key_universe[(key_universe_len - num_groups)..key_universe_len]
+            .iter()
+            .map(|k| KeyType(*k, false))
+            .collect(),
+    };
+
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get());
+    config.local.resource_group_serialization = ResourceGroupSerializationConfig {
+        split_threshold_bytes,
+        merge_threshold_bytes,
+    };
+    let block_executor = BlockExecutor::<
+        MockTransaction<KeyType<[u8; 32]>, MockEvent>,
+        MockTask<KeyType<[u8; 32]>, MockEvent>,
+        NonEmptyGroupDataView<KeyType<[u8; 32]>>,
+        NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>,
+        ExecutableTestType,
+    >::new(config, executor_thread_pool, None);
+    let baseline = BaselineOutput::generate(&transactions, None);
+
+    for _ in 0..10 {
+        let output = block_executor.execute_transactions_parallel((), &transactions, &data_view);
+        baseline.assert_parallel_output(&output);
+    }
+
+    let output =
+        block_executor.execute_transactions_sequential((), &transactions, &data_view, false);
+    baseline.assert_output(&output.map_err(|e| match e {
+        SequentialBlockExecutionError::ResourceGroupSerializationError => {
+            panic!("Unexpected error")
+        },
+        SequentialBlockExecutionError::ErrorToReturn(err) => err,
+    }));
+}
+
 #[test]
 fn dynamic_read_writes() {
     dynamic_read_writes_with_block_gas_limit(3000, None);
@@ -722,3 +1041,95 @@ fn module_publishing_races_with_block_gas_limit_test() {
         );
     }
 }
+
+// Executes the transactions in parallel, with the gas charged by one of the transactions perturbed
+// (emulating non-deterministic gas metering), and checks that comparing the output against the
+// baseline surfaces the divergence, i.e. the perturbed gas is never committed unnoticed.
+fn run_transactions_with_gas_perturbation(
+    universe: &[[u8; 32]],
+    transaction_gen: Vec<TransactionGen<[u8; 32]>>,
+    perturbed_txn: Index,
+    extra_gas: u64,
+    maybe_block_gas_limit: Option<u64>,
+) {
+    let transactions: Vec<_> = transaction_gen
+        .into_iter()
+        .map(|txn_gen| txn_gen.materialize(universe, (false, false)))
+        .collect();
+    let perturbation = GasPerturbation {
+        txn_idx: perturbed_txn.index(transactions.len()) as TxnIndex,
+        extra_gas,
+    };
+
+    let data_view = EmptyDataView::<KeyType<[u8; 32]>> {
+        phantom: PhantomData,
+    };
+
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+
+    let output = BlockExecutor::<
+        MockTransaction<KeyType<[u8; 32]>, MockEvent>,
+        GasPerturbingMockTask<KeyType<[u8; 32]>, MockEvent>,
+        EmptyDataView<KeyType<[u8; 32]>>,
+        NoOpTransactionCommitHook<MockOutput<KeyType<[u8; 32]>, MockEvent>, usize>,
+        ExecutableTestType,
+    >::new(
+        BlockExecutorConfig::new_maybe_block_limit(num_cpus::get(), maybe_block_gas_limit),
+        executor_thread_pool,
+        None,
+    )
+    .execute_transactions_parallel(perturbation, &transactions, &data_view);
+    let block_output = assert_ok!(output);
+
+    // The baseline follows the (deterministic) incarnation behaviors.
+    let baseline = BaselineOutput::generate(&transactions, maybe_block_gas_limit);
+    let divergence = baseline.find_gas_divergence(&block_output);
+
+    // The transactions before the perturbed one are not affected, so the perturbed transaction is
+    // committed iff it is committed by the baseline, in which case it must be the first divergence.
+    let perturbed_idx = perturbation.txn_idx as usize;
+    match divergence {
+        Some(GasDivergence {
+            txn_idx,
+            baseline_gas: Some(baseline_gas),
+            output_gas: Some(output_gas),
+        }) => {
+            assert_eq!(txn_idx, perturbed_idx);
+            assert_eq!(output_gas, baseline_gas + extra_gas);
+        },
+        Some(divergence) => panic!("Unexpected gas divergence {:?}", divergence),
+        None => {
+            // Only possible if the perturbed transaction was skipped due to the block gas limit.
+            assert_some!(maybe_block_gas_limit);
+            assert!(block_output.get_transaction_outputs_forced()[perturbed_idx].skipped);
+        },
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+    #[test]
+    fn gas_perturbation_divergence_detected(
+        universe in vec(any::<[u8; 32]>(), 100),
+        transaction_gen in vec(any_with::<TransactionGen<[u8;32]>>(TransactionGenParams::new_dynamic()), 1000).no_shrink(),
+        perturbed_txn in any::<Index>(),
+        extra_gas in 1..=MAX_GAS_PER_TXN,
+    ) {
+        run_transactions_with_gas_perturbation(&universe, transaction_gen, perturbed_txn, extra_gas, None);
+    }
+
+    #[test]
+    fn gas_perturbation_divergence_detected_with_block_gas_limit(
+        universe in vec(any::<[u8; 32]>(), 100),
+        transaction_gen in vec(any_with::<TransactionGen<[u8;32]>>(TransactionGenParams::new_dynamic()), 1000).no_shrink(),
+        perturbed_txn in any::<Index>(),
+        extra_gas in 1..=MAX_GAS_PER_TXN,
+    ) {
+        run_transactions_with_gas_perturbation(&universe, transaction_gen, perturbed_txn, extra_gas, Some(rand::thread_rng().gen_range(0, 1000 * MAX_GAS_PER_TXN / 2)));
+    }
+}
@@ -31,12 +31,13 @@ use aptos_types::{
 };
 use aptos_vm_types::resolver::{TExecutorView, TResourceGroupView};
 use bytes::Bytes;
-use claims::{assert_ge, assert_le, assert_ok};
+use claims::{assert_ge, assert_gt, assert_le, assert_ok};
 use move_core_types::value::MoveTypeLayout;
 use move_vm_types::delayed_values::delayed_field_id::DelayedFieldID;
 use once_cell::sync::OnceCell;
 use proptest::{arbitrary::Arbitrary, collection::vec, prelude::*, proptest, sample::Index};
 use proptest_derive::Arbitrary;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap, HashSet},
     convert::TryInto,
@@ -142,7 +143,7 @@ where
 // Generation of transactions
 ///////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, Copy, Hash, Debug, PartialEq, PartialOrd, Ord, Eq)]
+#[derive(Clone, Copy, Hash, Debug, PartialEq, PartialOrd, Ord, Eq, Serialize, Deserialize)]
 pub(crate) struct KeyType<K: Hash + Clone + Debug + PartialOrd + Ord + Eq>(
     /// Wrapping the types used for testing to add ModulePath trait implementation (below).
     pub K,
@@ -736,6 +737,93 @@ impl<V: Into<Vec<u8>> + Arbitrary + Clone + Debug + Eq + Sync + Send> Transactio
         MockTransaction::from_behaviors(behaviors)
     }
 
+    // Generates a mock txn in which all reads and writes are converted to resource group
+    // operations. The last num_groups keys of the universe are used as group keys, and the
+    // resource tags are chosen from 0..num_tags, so that transactions frequently conflict on the
+    // same (group, tag) pairs, as well as on the same groups via different tags (which share a
+    // storage slot). Unlike materialize_groups, generated writes may be deletions, and if
+    // query_group_sizes is set, the size of each group that is read is also queried.
+    pub(crate) fn materialize_group_conflicts<
+        K: Clone + Hash + Debug + Eq + Ord,
+        E: Send + Sync + Debug + Clone + TransactionEvent,
+    >(
+        self,
+        universe: &[K],
+        num_groups: usize,
+        num_tags: u32,
+        query_group_sizes: bool,
+    ) -> MockTransaction<KeyType<K>, E> {
+        let universe_len = universe.len();
+        assert_gt!(num_groups, 0, "Must have at least one group");
+        assert_gt!(num_tags, 0, "Must have at least one resource tag");
+        assert_gt!(
+            universe_len,
+            num_groups,
+            "Universe must have keys besides group keys"
+        );
+
+        let is_module_read = |_| -> bool { false };
+        let is_module_write = |_| -> bool { false };
+        let is_delta = |_, _: &V| -> Option<DeltaOp> { None };
+
+        let mut behaviors = self
+            .new_mock_write_txn(
+                &universe[0..universe_len - num_groups],
+                &is_module_read,
+                &is_module_write,
+                &is_delta,
+                true,
+            )
+            .into_behaviors();
+
+        let group_key = |group_idx: usize| -> KeyType<K> {
+            KeyType(universe[universe_len - 1 - group_idx].clone(), false)
+        };
+        let key_to_group = |key: &KeyType<K>| -> (usize, u32) {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            let hash = hasher.finish();
+            (
+                (hash % num_groups as u64) as usize,
+                ((hash >> 32) % num_tags as u64) as u32,
+            )
+        };
+
+        for behavior in behaviors.iter_mut() {
+            let mut group_reads = vec![];
+            let mut groups_read = BTreeSet::new();
+            for read_key in behavior.reads.drain(..) {
+                let (group_idx, tag) = key_to_group(&read_key);
+                group_reads.push((group_key(group_idx), tag));
+                groups_read.insert(group_idx);
+            }
+
+            let mut inner_ops = vec![HashMap::new(); num_groups];
+            for (write_key, value) in behavior.writes.drain(..) {
+                let (group_idx, tag) = key_to_group(&write_key);
+                // Groups must never become empty, hence RESERVED_TAG is never deleted.
+                if tag != RESERVED_TAG || !value.is_deletion() {
+                    inner_ops[group_idx].insert(tag, value);
+                }
+            }
+
+            // Group test does not handle deltas (different view, no default storage value).
+            assert!(behavior.deltas.is_empty());
+            behavior.group_reads = group_reads;
+            behavior.group_writes = inner_ops
+                .into_iter()
+                .enumerate()
+                .filter(|(_, inner_ops)| !inner_ops.is_empty())
+                .map(|(group_idx, inner_ops)| (group_key(group_idx), inner_ops))
+                .collect();
+            if query_group_sizes {
+                behavior.group_sizes = groups_read.into_iter().map(group_key).collect();
+            }
+        }
+
+        MockTransaction::from_behaviors(behaviors)
+    }
+
     pub(crate) fn materialize_with_deltas<
         K: Clone + Hash + Debug + Eq + Ord,
         E: Send + Sync + Debug + Clone + TransactionEvent,
@@ -958,6 +1046,8 @@ where
                     read_results,
                     read_group_sizes,
                     materialized_delta_writes: OnceCell::new(),
+                    materialized_group_writes: OnceCell::new(),
+                    materialized_group_writes: OnceCell::new(),
                     total_gas: behavior.gas,
                     skipped: false,
                 })
@@ -976,6 +1066,63 @@ where
     }
 }
 
+/// Test-only hook for perturbing the gas charged by a transaction during parallel execution, to
+/// emulate non-deterministic gas metering. Every execution of the transaction at `txn_idx` charges
+/// `extra_gas` on top of the gas prescribed by its incarnation behavior, so the committed gas
+/// diverges from the (sequential) baseline, which only follows the incarnation behaviors.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct GasPerturbation {
+    pub(crate) txn_idx: TxnIndex,
+    pub(crate) extra_gas: u64,
+}
+
+/// Wraps MockTask, perturbing the gas charged by transactions according to GasPerturbation.
+pub(crate) struct GasPerturbingMockTask<K, E> {
+    task: MockTask<K, E>,
+    perturbation: GasPerturbation,
+}
+
+impl<K, E> ExecutorTask for GasPerturbingMockTask<K, E>
+where
+    K: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + Debug + 'static,
+    E: Send + Sync + Debug + Clone + TransactionEvent + 'static,
+{
+    type Argument = GasPerturbation;
+    type Error = usize;
+    type Output = MockOutput<K, E>;
+    type Txn = MockTransaction<K, E>;
+
+    fn init(argument: Self::Argument) -> Self {
+        Self {
+            task: MockTask::new(),
+            perturbation: argument,
+        }
+    }
+
+    fn execute_transaction(
+        &self,
+        view: &(impl TExecutorView<K, u32, MoveTypeLayout, DelayedFieldID, ValueType>
+              + TResourceGroupView<GroupKey = K, ResourceTag = u32, Layout = MoveTypeLayout>),
+        txn: &Self::Txn,
+        txn_idx: TxnIndex,
+    ) -> ExecutionStatus<Self::Output, Self::Error> {
+        let mut status = self.task.execute_transaction(view, txn, txn_idx);
+        if txn_idx == self.perturbation.txn_idx {
+            match &mut status {
+                ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+                    output.total_gas += self.perturbation.extra_gas;
+                },
+                _ => (),
+            }
+        }
+        status
+    }
+
+    fn is_transaction_dynamic_change_set_capable(txn: &Self::Txn) -> bool {
+        MockTask::<K, E>::is_transaction_dynamic_change_set_capable(txn)
+    }
+}
+
 pub(crate) fn raw_metadata(v: u64) -> StateValueMetadata {
     StateValueMetadata::legacy(v, &CurrentTimeMicroseconds { microseconds: v })
 }
@@ -990,6 +1137,9 @@ pub(crate) struct MockOutput<K, E> {
     pub(crate) read_results: Vec<Option<Vec<u8>>>,
     pub(crate) read_group_sizes: Vec<(K, u64)>,
     pub(crate) materialized_delta_writes: OnceCell<Vec<(K, WriteOp)>>,
+    // The serialized group writes (the only patched resource writes in tests, as
+    // the resource writes do not contain delayed fields).
+    pub(crate) materialized_group_writes: OnceCell<Vec<(K, ValueType)>>,
     pub(crate) total_gas: u64,
     pub(crate) skipped: bool,
 }
@@ -1095,6 +1245,7 @@ where
             read_results: vec![],
             read_group_sizes: vec![],
             materialized_delta_writes: OnceCell::new(),
+            materialized_group_writes: OnceCell::new(),
             total_gas: 0,
             skipped: true,
         }
@@ -1109,6 +1260,7 @@ where
             read_results: vec![],
             read_group_sizes: vec![],
             materialized_delta_writes: OnceCell::new(),
+            materialized_group_writes: OnceCell::new(),
             total_gas: 0,
             skipped: true,
         }
@@ -1125,14 +1277,17 @@ where
     fn incorporate_materialized_txn_output(
         &self,
         aggregator_v1_writes: Vec<(<Self::Txn as Transaction>::Key, WriteOp)>,
-        _patched_resource_write_set: Vec<(
+        patched_resource_write_set: Vec<(
             <Self::Txn as Transaction>::Key,
             <Self::Txn as Transaction>::Value,
         )>,
         _patched_events: Vec<<Self::Txn as Transaction>::Event>,
     ) -> Result<(), PanicError> {
         assert_ok!(self.materialized_delta_writes.set(aggregator_v1_writes));
-        // TODO[agg_v2](tests): Set the patched resource write set and events. But that requires the function
+        assert_ok!(self
+            .materialized_group_writes
+            .set(patched_resource_write_set));
+        // TODO[agg_v2](tests): Set the patched events. But that requires the function
         // to take &mut self as input
         Ok(())
     }
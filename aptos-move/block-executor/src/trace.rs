@@ -0,0 +1,230 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optional capture of the interleaving of a parallel (Block-STM) execution.
+//!
+//! When enabled via `BlockExecutor::with_trace_capture`, every worker records the read and
+//! write sets of each incarnation it executes, the outcome of each validation, and the order
+//! in which transactions are committed. If the parallel execution fails (and the executor falls
+//! back to sequential execution), the recorded events are kept as a serializable
+//! `BlockExecutionTrace`, which can be persisted and replayed deterministically in tests (see
+//! `proptest_types::replay`). Traces of successful executions are discarded.
+
+use crate::{
+    task::{ExecutionStatus, TransactionOutput},
+    txn_last_input_output::TxnLastInputOutput,
+};
+use aptos_infallible::Mutex;
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+use aptos_types::transaction::BlockExecutableTransaction as Transaction;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// The version that a captured read was resolved from.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TracedReadVersion {
+    /// The value was read from the base state view (storage).
+    Storage,
+    /// The value was written by the given incarnation of a lower transaction.
+    Txn(TxnIndex, Incarnation),
+    /// The read did not observe a single version (e.g. resolved aggregator v1 deltas,
+    /// group and module reads), and is not replayed.
+    Unversioned,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TracedRead<K> {
+    pub key: K,
+    pub version: TracedReadVersion,
+}
+
+/// The status of a speculative execution, without the (non-serializable) output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TracedExecutionStatus {
+    Success,
+    Abort,
+    SkipRest,
+    SpeculativeExecutionAbortError,
+    DelayedFieldsCodeInvariantError,
+}
+
+impl<O, E> From<&ExecutionStatus<O, E>> for TracedExecutionStatus {
+    fn from(status: &ExecutionStatus<O, E>) -> Self {
+        match status {
+            ExecutionStatus::Success(_) => TracedExecutionStatus::Success,
+            ExecutionStatus::Abort(_) => TracedExecutionStatus::Abort,
+            ExecutionStatus::SkipRest(_) => TracedExecutionStatus::SkipRest,
+            ExecutionStatus::SpeculativeExecutionAbortError(_) => {
+                TracedExecutionStatus::SpeculativeExecutionAbortError
+            },
+            ExecutionStatus::DelayedFieldsCodeInvariantError(_) => {
+                TracedExecutionStatus::DelayedFieldsCodeInvariantError
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TraceEvent<K> {
+    /// An incarnation of a transaction finished executing. Reads and writes are sorted by key.
+    Execution {
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        reads: Vec<TracedRead<K>>,
+        writes: Vec<K>,
+        status: TracedExecutionStatus,
+    },
+    /// An incarnation of a transaction failed to execute with a block-level error.
+    ExecutionFailure {
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        error: String,
+    },
+    /// The read-set of an incarnation was validated.
+    Validation {
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        valid: bool,
+    },
+    /// The latest incarnation of a transaction was committed.
+    Commit { txn_idx: TxnIndex },
+    /// A worker halted the parallel execution with the given error.
+    Halt { error: String },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord<K> {
+    /// The index of the rayon thread that recorded the event, if any.
+    pub thread_index: Option<usize>,
+    pub event: TraceEvent<K>,
+}
+
+/// The events of a single parallel block execution, in the order they were recorded.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlockExecutionTrace<K> {
+    pub num_txns: TxnIndex,
+    pub concurrency_level: usize,
+    pub records: Vec<TraceRecord<K>>,
+}
+
+impl<K: Serialize + DeserializeOwned> BlockExecutionTrace<K> {
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bcs::to_bytes(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(bcs::from_bytes(bytes)?)
+    }
+}
+
+/// Holds the trace of the latest failed parallel execution of a block executor with trace
+/// capture enabled.
+pub struct ExecutionTraceCapture<K> {
+    last_failed_trace: Mutex<Option<BlockExecutionTrace<K>>>,
+}
+
+impl<K> Default for ExecutionTraceCapture<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> ExecutionTraceCapture<K> {
+    pub fn new() -> Self {
+        Self {
+            last_failed_trace: Mutex::new(None),
+        }
+    }
+
+    /// Returns (and clears) the trace of the latest failed parallel execution, if any.
+    pub fn take_last_failed_trace(&self) -> Option<BlockExecutionTrace<K>> {
+        self.last_failed_trace.lock().take()
+    }
+
+    pub(crate) fn store_failed_trace(&self, trace: BlockExecutionTrace<K>) {
+        *self.last_failed_trace.lock() = Some(trace);
+    }
+}
+
+/// Collects the events of an ongoing parallel execution, shared by all workers.
+pub(crate) struct TraceRecorder<K> {
+    records: Mutex<Vec<TraceRecord<K>>>,
+}
+
+impl<K: Clone + Ord> TraceRecorder<K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            records: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn record(&self, event: TraceEvent<K>) {
+        let record = TraceRecord {
+            thread_index: rayon::current_thread_index(),
+            event,
+        };
+        self.records.lock().push(record);
+    }
+
+    /// Records the execution of the given incarnation. Must be called after the outputs of the
+    /// incarnation are recorded in last_input_output, and before the execution is finished in
+    /// the scheduler (so that no other incarnation of the transaction can be recorded meanwhile).
+    pub(crate) fn record_execution<T, O, E>(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        last_input_output: &TxnLastInputOutput<T, O, E>,
+    ) where
+        T: Transaction<Key = K>,
+        O: TransactionOutput<Txn = T>,
+        E: Debug + Send + Clone,
+    {
+        let mut reads = last_input_output
+            .read_set(txn_idx)
+            .map_or(vec![], |read_set| read_set.get_traced_reads());
+        reads.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let mut writes: Vec<K> = last_input_output
+            .modified_keys(txn_idx)
+            .map_or(vec![], |keys| keys.map(|(k, _)| k).collect());
+        writes.sort();
+
+        let status = last_input_output.txn_output(txn_idx).map_or(
+            TracedExecutionStatus::SpeculativeExecutionAbortError,
+            |output| output.as_ref().into(),
+        );
+
+        self.record(TraceEvent::Execution {
+            txn_idx,
+            incarnation,
+            reads,
+            writes,
+            status,
+        });
+    }
+
+    pub(crate) fn record_execution_failure(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        error: &impl Debug,
+    ) {
+        self.record(TraceEvent::ExecutionFailure {
+            txn_idx,
+            incarnation,
+            error: format!("{:?}", error),
+        });
+    }
+
+    pub(crate) fn into_trace(
+        self,
+        num_txns: TxnIndex,
+        concurrency_level: usize,
+    ) -> BlockExecutionTrace<K> {
+        BlockExecutionTrace {
+            num_txns,
+            concurrency_level,
+            records: self.records.into_inner(),
+        }
+    }
+}
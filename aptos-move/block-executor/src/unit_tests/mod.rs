@@ -5,6 +5,7 @@
 use crate::{
     errors::SequentialBlockExecutionError,
     executor::BlockExecutor,
+    executor_utilities::{serialize_group_in_chunks, serialize_groups},
     proptest_types::{
         baseline::BaselineOutput,
         types::{
@@ -24,10 +25,13 @@ use aptos_aggregator::{
 };
 use aptos_mvhashmap::types::TxnIndex;
 use aptos_types::{
-    block_executor::config::BlockExecutorConfig,
+    block_executor::config::{BlockExecutorConfig, ResourceGroupSerializationConfig},
     contract_event::TransactionEvent,
     executable::{ExecutableTestType, ModulePath},
+    state_store::state_value::StateValueMetadata,
+    write_set::{TransactionWrite, WriteOpKind},
 };
+use bytes::Bytes;
 use claims::assert_matches;
 use fail::FailScenario;
 use rand::{prelude::*, random};
@@ -152,6 +156,86 @@ fn resource_group_bcs_fallback() {
     scenario.teardown();
 }
 
+#[test]
+fn resource_group_serialization_thresholds() {
+    let mut rng = thread_rng();
+    // Note: u32 tags are serialized in little endian, so the order of the serialized tags
+    // (i.e., the bcs order of the map entries) differs from the order of the tags.
+    let groups: Vec<BTreeMap<u32, Bytes>> = [1, 2, 10, 300]
+        .into_iter()
+        .map(|num_tags| {
+            (0..num_tags)
+                .map(|_| {
+                    let len = rng.gen_range(1, 50);
+                    (
+                        rng.gen::<u32>(),
+                        (0..len).map(|_| rng.gen::<u8>()).collect(),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+
+    for group in groups.iter() {
+        for chunk_size_bytes in [1, 10, 100, 1_000_000] {
+            assert_eq!(
+                serialize_group_in_chunks(group, chunk_size_bytes).unwrap(),
+                bcs::to_bytes(group).unwrap()
+            );
+        }
+    }
+
+    let finalized_groups: Vec<_> = groups
+        .iter()
+        .enumerate()
+        .map(|(idx, group)| {
+            (
+                KeyType::<u32>(idx as u32, false),
+                ValueType::from_value(vec![0], true),
+                group
+                    .iter()
+                    .map(|(tag, bytes)| {
+                        let value = ValueType::new(
+                            Some(bytes.clone()),
+                            StateValueMetadata::none(),
+                            WriteOpKind::Creation,
+                        );
+                        (*tag, Arc::new(value))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    for (split_threshold_bytes, merge_threshold_bytes) in [
+        (None, None),
+        (Some(100), None),
+        (None, Some(1_000)),
+        (Some(100), Some(1_000)),
+        (Some(1), Some(usize::MAX)),
+    ] {
+        let config = ResourceGroupSerializationConfig {
+            split_threshold_bytes,
+            merge_threshold_bytes,
+        };
+        let serialized_groups = serialize_groups::<MockTransaction<KeyType<u32>, MockEvent>>(
+            finalized_groups.clone(),
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(serialized_groups.len(), groups.len());
+        for ((group_key, metadata_op), (idx, group)) in
+            serialized_groups.iter().zip(groups.iter().enumerate())
+        {
+            assert_eq!(*group_key, KeyType::<u32>(idx as u32, false));
+            assert_eq!(
+                metadata_op.bytes().unwrap().as_ref(),
+                bcs::to_bytes(group).unwrap().as_slice()
+            );
+        }
+    }
+}
+
 #[test]
 fn block_output_err_precedence() {
     let incarnation: MockIncarnation<KeyType<u32>, MockEvent> = MockIncarnation::new(
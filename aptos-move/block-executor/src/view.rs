@@ -70,6 +70,7 @@ use std::{
         atomic::{AtomicU32, Ordering},
         Arc,
     },
+    time::Instant,
 };
 
 /// A struct which describes the result of the read from the proxy. The client
@@ -204,7 +205,7 @@ fn get_delayed_field_value_impl<T: Transaction>(
                 return Ok(value);
             },
             Err(PanicOr::Or(MVDelayedFieldsError::Dependency(dep_idx))) => {
-                if !wait_for_dependency(wait_for, txn_idx, dep_idx)? {
+                if !wait_for_dependency(wait_for, captured_reads, txn_idx, dep_idx)? {
                     // TODO[agg_v2](cleanup): think of correct return type
                     return Err(PanicOr::Or(DelayedFieldsSpeculativeError::InconsistentRead));
                 }
@@ -370,7 +371,7 @@ fn delayed_field_try_add_delta_outcome_impl<T: Transaction>(
                 ) {
                     Ok(v) => break v,
                     Err(MVDelayedFieldsError::Dependency(dep_idx)) => {
-                        if !wait_for_dependency(wait_for, txn_idx, dep_idx)? {
+                        if !wait_for_dependency(wait_for, captured_reads, txn_idx, dep_idx)? {
                             // TODO[agg_v2](cleanup): think of correct return type
                             return Err(PanicOr::Or(
                                 DelayedFieldsSpeculativeError::InconsistentRead,
@@ -401,15 +402,18 @@ fn delayed_field_try_add_delta_outcome_impl<T: Transaction>(
 
 // txn_idx is estimated to have a r/w dependency on dep_idx.
 // Returns after the dependency has been resolved, the returned indicator is true if
-// it is safe to continue, and false if the execution has been halted.
-fn wait_for_dependency(
+// it is safe to continue, and false if the execution has been halted. The time spent
+// waiting is accumulated in the captured reads (for the conflict statistics).
+fn wait_for_dependency<T: Transaction>(
     wait_for: &dyn TWaitForDependency,
+    captured_reads: &RefCell<CapturedReads<T>>,
     txn_idx: TxnIndex,
     dep_idx: TxnIndex,
 ) -> Result<bool, PanicError> {
     match wait_for.wait_for_dependency(txn_idx, dep_idx)? {
         DependencyResult::Dependency(dep_condition) => {
             let _timer = counters::DEPENDENCY_WAIT_SECONDS.start_timer();
+            let wait_start = Instant::now();
             // Wait on a condition variable corresponding to the encountered
             // read dependency. Once the dep_idx finishes re-execution, scheduler
             // will mark the dependency as resolved, and then the txn_idx will be
@@ -429,6 +433,9 @@ fn wait_for_dependency(
             while matches!(*dep_resolved, DependencyStatus::Unresolved) {
                 dep_resolved = cvar.wait(dep_resolved).unwrap();
             }
+            captured_reads
+                .borrow_mut()
+                .add_dependency_wait_time(wait_start.elapsed());
             // dep resolved status is either resolved or execution halted.
             Ok(matches!(*dep_resolved, DependencyStatus::Resolved))
         },
@@ -508,7 +515,8 @@ impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
                     unreachable!("Reading group size does not require a specific tag look-up");
                 },
                 Err(Dependency(dep_idx)) => {
-                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx)? {
+                    if !wait_for_dependency(self.scheduler, &self.captured_reads, txn_idx, dep_idx)?
+                    {
                         return Err(PartialVMError::new(
                             StatusCode::SPECULATIVE_EXECUTION_ABORT_ERROR,
                         )
@@ -633,7 +641,12 @@ impl<'a, T: Transaction, X: Executable> ResourceState<T> for ParallelState<'a, T
                     return ReadResult::Uninitialized;
                 },
                 Err(Dependency(dep_idx)) => {
-                    match wait_for_dependency(self.scheduler, txn_idx, dep_idx) {
+                    match wait_for_dependency(
+                        self.scheduler,
+                        &self.captured_reads,
+                        txn_idx,
+                        dep_idx,
+                    ) {
                         Err(e) => {
                             error!("Error {:?} in wait for dependency", e);
                             return ReadResult::HaltSpeculativeExecution(format!(
@@ -749,7 +762,8 @@ impl<'a, T: Transaction, X: Executable> ResourceGroupState<T> for ParallelState<
                     return Ok(GroupReadResult::Value(None, None));
                 },
                 Err(Dependency(dep_idx)) => {
-                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx)? {
+                    if !wait_for_dependency(self.scheduler, &self.captured_reads, txn_idx, dep_idx)?
+                    {
                         // TODO[agg_v2](cleanup): consider changing from PartialVMResult<GroupReadResult> to GroupReadResult
                         // like in ReadResult for resources.
                         return Err(PartialVMError::new(
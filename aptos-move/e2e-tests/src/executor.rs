@@ -34,6 +34,7 @@ use aptos_types::{
     },
     block_executor::config::{
         BlockExecutorConfig, BlockExecutorConfigFromOnchain, BlockExecutorLocalConfig,
+        ResourceGroupSerializationConfig,
     },
     block_metadata::BlockMetadata,
     chain_id::ChainId,
@@ -499,6 +500,8 @@ impl FakeExecutor {
                 },
                 allow_fallback: self.allow_block_executor_fallback,
                 discard_failed_blocks: false,
+                txn_execution_time_budget_ms: None,
+                resource_group_serialization: ResourceGroupSerializationConfig::default(),
             },
             onchain: onchain_config,
         };
@@ -436,6 +436,50 @@ module std::features {
         is_enabled(MULTISIG_V2_ENHANCEMENT)
     }
 
+    /// Whether consensus responds to batch requests with `BatchResponseV2` messages.
+    ///
+    /// Lifetime: transient
+    const CONSENSUS_BATCH_RESPONSE_V2: u64 = 56;
+
+    public fun get_consensus_batch_response_v2_feature(): u64 { CONSENSUS_BATCH_RESPONSE_V2 }
+
+    public fun consensus_batch_response_v2_enabled(): bool acquires Features {
+        is_enabled(CONSENSUS_BATCH_RESPONSE_V2)
+    }
+
+    /// Whether the quorum store gossips batch queue length hints for load-aware batch creation.
+    ///
+    /// Lifetime: transient
+    const QUORUM_STORE_QUEUE_LENGTH_HINTS: u64 = 57;
+
+    public fun get_quorum_store_queue_length_hints_feature(): u64 { QUORUM_STORE_QUEUE_LENGTH_HINTS }
+
+    public fun quorum_store_queue_length_hints_enabled(): bool acquires Features {
+        is_enabled(QUORUM_STORE_QUEUE_LENGTH_HINTS)
+    }
+
+    /// Whether consensus retrieves missing blocks with `BlockRangeRetrievalRequest` messages.
+    ///
+    /// Lifetime: transient
+    const CONSENSUS_BLOCK_RANGE_RETRIEVAL: u64 = 58;
+
+    public fun get_consensus_block_range_retrieval_feature(): u64 { CONSENSUS_BLOCK_RANGE_RETRIEVAL }
+
+    public fun consensus_block_range_retrieval_enabled(): bool acquires Features {
+        is_enabled(CONSENSUS_BLOCK_RANGE_RETRIEVAL)
+    }
+
+    /// Whether validators joining mid-epoch bootstrap with `EpochStarterKit` messages.
+    ///
+    /// Lifetime: transient
+    const CONSENSUS_EPOCH_STARTER_KIT: u64 = 59;
+
+    public fun get_consensus_epoch_starter_kit_feature(): u64 { CONSENSUS_EPOCH_STARTER_KIT }
+
+    public fun consensus_epoch_starter_kit_enabled(): bool acquires Features {
+        is_enabled(CONSENSUS_EPOCH_STARTER_KIT)
+    }
+
 
     // ============================================================================================
     // Feature Flag Implementation
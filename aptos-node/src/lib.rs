@@ -27,6 +27,7 @@ use aptos_dkg_runtime::start_dkg_runtime;
 use aptos_framework::ReleaseBundle;
 use aptos_jwk_consensus::start_jwk_consensus_runtime;
 use aptos_logger::{prelude::*, telemetry_log_writer::TelemetryLog, Level, LoggerFilterUpdater};
+use aptos_mempool::MempoolPersistence;
 use aptos_safety_rules::safety_rules_manager::load_consensus_key_from_secure_storage;
 use aptos_state_sync_driver::driver_factory::StateSyncRuntimes;
 use aptos_types::chain_id::ChainId;
@@ -192,12 +193,14 @@ pub struct AptosHandle {
     _api_runtime: Option<Runtime>,
     _backup_runtime: Option<Runtime>,
     _consensus_runtime: Option<Runtime>,
+    _consensus_observer_runtime: Option<Runtime>,
     _dkg_runtime: Option<Runtime>,
     _indexer_grpc_runtime: Option<Runtime>,
     _indexer_runtime: Option<Runtime>,
     _indexer_table_info_runtime: Option<Runtime>,
     _jwk_consensus_runtime: Option<Runtime>,
     _mempool_runtime: Runtime,
+    mempool_persistence: MempoolPersistence,
     _network_runtimes: Vec<Runtime>,
     _peer_monitoring_service_runtime: Runtime,
     _state_sync_runtimes: StateSyncRuntimes,
@@ -247,8 +250,24 @@ pub fn start(
     }
 
     // Set up the node environment and start it
-    let _node_handle =
+    let node_handle =
         setup_environment_and_start_node(config, remote_log_receiver, Some(logger_filter_update))?;
+
+    // If mempool persistence is enabled, take a final snapshot of the mempool
+    // transactions on shutdown (e.g., before a restart). We exit immediately after,
+    // instead of waiting for all runtimes to shut down.
+    if node_handle.mempool_persistence.is_enabled() {
+        utils::wait_for_shutdown_signal();
+        if let Err(error) = node_handle.mempool_persistence.persist_transactions() {
+            error!(
+                "Failed to persist the mempool transactions on shutdown: {:?}",
+                error
+            );
+        }
+        aptos_logger::flush();
+        std::process::exit(0);
+    }
+
     let term = Arc::new(AtomicBool::new(false));
     while !term.load(Ordering::Acquire) {
         thread::park();
@@ -604,6 +623,7 @@ pub fn setup_environment_and_start_node(
     let (
         network_runtimes,
         consensus_network_interfaces,
+        consensus_observer_network_interfaces,
         dkg_network_interfaces,
         jwk_consensus_network_interfaces,
         mempool_network_interfaces,
@@ -650,16 +670,23 @@ pub fn setup_environment_and_start_node(
     ) = services::bootstrap_api_and_indexer(&node_config, db_rw.clone(), chain_id)?;
 
     // Create mempool and get the consensus to mempool sender
-    let (mempool_runtime, consensus_to_mempool_sender) =
-        services::start_mempool_runtime_and_get_consensus_sender(
-            &mut node_config,
-            &db_rw,
-            mempool_reconfig_subscription,
-            mempool_network_interfaces,
-            mempool_listener,
-            mempool_client_receiver,
-            peers_and_metadata,
-        );
+    let (
+        mempool_runtime,
+        consensus_to_mempool_sender,
+        mempool_submission_quotas,
+        mempool_capacity_reporter,
+        mempool_persistence,
+    ) = services::start_mempool_runtime_and_get_consensus_sender(
+        &mut node_config,
+        &db_rw,
+        mempool_reconfig_subscription,
+        mempool_network_interfaces,
+        mempool_listener,
+        mempool_client_receiver,
+        peers_and_metadata,
+    );
+    admin_service.set_mempool_submission_quotas(mempool_submission_quotas);
+    admin_service.set_mempool_capacity_reporter(mempool_capacity_reporter);
 
     // Ensure consensus key in secure DB.
     if !matches!(
@@ -732,38 +759,55 @@ pub fn setup_environment_and_start_node(
         _ => None,
     };
 
-    // Create the consensus runtime (this blocks on state sync first)
-    let consensus_runtime = consensus_network_interfaces.map(|consensus_network_interfaces| {
-        // Wait until state sync has been initialized
-        debug!("Waiting until state sync is initialized!");
-        state_sync_runtimes.block_until_initialized();
-        debug!("State sync initialization complete.");
-
-        // Initialize and start consensus
-        let (runtime, consensus_db, quorum_store_db) = services::start_consensus_runtime(
-            &mut node_config,
-            db_rw,
-            consensus_reconfig_subscription,
-            consensus_network_interfaces,
-            consensus_notifier,
-            consensus_to_mempool_sender,
-            vtxn_pool,
+    // Create the consensus publisher and start the consensus observer (if enabled)
+    let (consensus_observer_runtime, consensus_publisher) =
+        services::start_consensus_observer_runtime(
+            &node_config,
+            consensus_observer_network_interfaces,
+            consensus_notifier.clone(),
+            db_rw.clone(),
         );
-        admin_service.set_consensus_dbs(consensus_db, quorum_store_db);
-        runtime
-    });
+
+    // Create the consensus runtime (this blocks on state sync first)
+    let consensus_runtime = match consensus_network_interfaces {
+        Some(consensus_network_interfaces) => {
+            // Wait until state sync has been initialized
+            debug!("Waiting until state sync is initialized!");
+            state_sync_runtimes.block_until_initialized();
+            debug!("State sync initialization complete.");
+
+            // Initialize and start consensus
+            let (runtime, consensus_db, quorum_store_db, leader_reputation_reporter) =
+                services::start_consensus_runtime(
+                    &mut node_config,
+                    db_rw,
+                    consensus_reconfig_subscription,
+                    consensus_network_interfaces,
+                    consensus_notifier,
+                    consensus_to_mempool_sender,
+                    vtxn_pool,
+                    consensus_publisher,
+                )?;
+            admin_service.set_consensus_dbs(consensus_db, quorum_store_db);
+            admin_service.set_leader_reputation_reporter(leader_reputation_reporter);
+            Some(runtime)
+        },
+        None => None,
+    };
 
     Ok(AptosHandle {
         _admin_service: admin_service,
         _api_runtime: api_runtime,
         _backup_runtime: backup_service,
         _consensus_runtime: consensus_runtime,
+        _consensus_observer_runtime: consensus_observer_runtime,
         _dkg_runtime: dkg_runtime,
         _indexer_grpc_runtime: indexer_grpc_runtime,
         _indexer_runtime: indexer_runtime,
         _indexer_table_info_runtime: indexer_table_info_runtime,
         _jwk_consensus_runtime: jwk_consensus_runtime,
         _mempool_runtime: mempool_runtime,
+        mempool_persistence,
         _network_runtimes: network_runtimes,
         _peer_monitoring_service_runtime: peer_monitoring_service_runtime,
         _state_sync_runtimes: state_sync_runtimes,
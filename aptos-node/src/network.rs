@@ -7,7 +7,9 @@ use aptos_config::{
     config::{NetworkConfig, NodeConfig},
     network_id::NetworkId,
 };
-use aptos_consensus::network_interface::ConsensusMsg;
+use aptos_consensus::{
+    consensus_observer::network_message::ConsensusObserverMessage, network_interface::ConsensusMsg,
+};
 use aptos_dkg_runtime::DKGMessage;
 use aptos_event_notifications::EventSubscriptionService;
 use aptos_jwk_consensus::types::JWKConsensusMsg;
@@ -68,6 +70,26 @@ pub fn consensus_network_configuration(node_config: &NodeConfig) -> NetworkAppli
     NetworkApplicationConfig::new(network_client_config, network_service_config)
 }
 
+/// Returns the network application config for the consensus observer and publisher
+pub fn consensus_observer_network_configuration(
+    node_config: &NodeConfig,
+) -> NetworkApplicationConfig {
+    let direct_send_protocols: Vec<ProtocolId> =
+        aptos_consensus::consensus_observer::network_message::DIRECT_SEND.into();
+    let rpc_protocols: Vec<ProtocolId> =
+        aptos_consensus::consensus_observer::network_message::RPC.into();
+    let max_network_channel_size = node_config.consensus_observer.max_network_channel_size as usize;
+
+    let network_client_config =
+        NetworkClientConfig::new(direct_send_protocols.clone(), rpc_protocols.clone());
+    let network_service_config = NetworkServiceConfig::new(
+        direct_send_protocols,
+        rpc_protocols,
+        aptos_channel::Config::new(max_network_channel_size).queue_style(QueueStyle::FIFO),
+    );
+    NetworkApplicationConfig::new(network_client_config, network_service_config)
+}
+
 pub fn dkg_network_configuration(node_config: &NodeConfig) -> NetworkApplicationConfig {
     let direct_send_protocols: Vec<ProtocolId> =
         aptos_dkg_runtime::network_interface::DIRECT_SEND.into();
@@ -102,7 +124,14 @@ pub fn jwk_consensus_network_configuration(node_config: &NodeConfig) -> NetworkA
 
 /// Returns the network application config for the mempool client and service
 pub fn mempool_network_configuration(node_config: &NodeConfig) -> NetworkApplicationConfig {
-    let direct_send_protocols = vec![ProtocolId::MempoolDirectSend];
+    // The newest protocols are preferred (they support digest announcements and
+    // transaction groups), but the older protocols are still used for peers that
+    // don't support them.
+    let direct_send_protocols = vec![
+        ProtocolId::MempoolGroupDirectSend,
+        ProtocolId::MempoolDigestDirectSend,
+        ProtocolId::MempoolDirectSend,
+    ];
     let rpc_protocols = vec![]; // Mempool does not use RPC
 
     let network_client_config =
@@ -140,7 +169,7 @@ pub fn peer_monitoring_network_configuration(node_config: &NodeConfig) -> Networ
 
 /// Returns the network application config for the storage service client and server
 pub fn storage_service_network_configuration(node_config: &NodeConfig) -> NetworkApplicationConfig {
-    let direct_send_protocols = vec![]; // The storage service does not use direct send
+    let direct_send_protocols = vec![ProtocolId::StateSyncDirectSend]; // For commit notifications
     let rpc_protocols = vec![ProtocolId::StorageServiceRpc];
     let max_network_channel_size = node_config
         .state_sync
@@ -225,6 +254,7 @@ pub fn setup_networks_and_get_interfaces(
 ) -> (
     Vec<Runtime>,
     Option<ApplicationNetworkInterfaces<ConsensusMsg>>,
+    Option<ApplicationNetworkInterfaces<ConsensusObserverMessage>>,
     Option<ApplicationNetworkInterfaces<DKGMessage>>,
     Option<ApplicationNetworkInterfaces<JWKConsensusMsg>>,
     ApplicationNetworkInterfaces<MempoolSyncMsg>,
@@ -237,6 +267,7 @@ pub fn setup_networks_and_get_interfaces(
     // Create each network and register the application handles
     let mut network_runtimes = vec![];
     let mut consensus_network_handle = None;
+    let mut consensus_observer_network_handles = vec![];
     let mut dkg_network_handle = None;
     let mut jwk_consensus_network_handle = None;
     let mut mempool_network_handles = vec![];
@@ -298,6 +329,17 @@ pub fn setup_networks_and_get_interfaces(
             }
         }
 
+        // Register the consensus observer (both client and server) with the non-validator networks
+        if !network_id.is_validator_network() && node_config.consensus_observer.is_enabled() {
+            let consensus_observer_network_handle = register_client_and_service_with_network(
+                &mut network_builder,
+                network_id,
+                &network_config,
+                consensus_observer_network_configuration(node_config),
+            );
+            consensus_observer_network_handles.push(consensus_observer_network_handle);
+        }
+
         // Register mempool (both client and server) with the network
         let mempool_network_handle = register_client_and_service_with_network(
             &mut network_builder,
@@ -349,6 +391,7 @@ pub fn setup_networks_and_get_interfaces(
     // Transform all network handles into application interfaces
     let (
         consensus_interfaces,
+        consensus_observer_interfaces,
         dkg_interfaces,
         jwk_consensus_interfaces,
         mempool_interfaces,
@@ -357,6 +400,7 @@ pub fn setup_networks_and_get_interfaces(
     ) = transform_network_handles_into_interfaces(
         node_config,
         consensus_network_handle,
+        consensus_observer_network_handles,
         dkg_network_handle,
         jwk_consensus_network_handle,
         mempool_network_handles,
@@ -381,6 +425,7 @@ pub fn setup_networks_and_get_interfaces(
     (
         network_runtimes,
         consensus_interfaces,
+        consensus_observer_interfaces,
         dkg_interfaces,
         jwk_consensus_interfaces,
         mempool_interfaces,
@@ -427,6 +472,7 @@ fn register_client_and_service_with_network<
 fn transform_network_handles_into_interfaces(
     node_config: &NodeConfig,
     consensus_network_handle: Option<ApplicationNetworkHandle<ConsensusMsg>>,
+    consensus_observer_network_handles: Vec<ApplicationNetworkHandle<ConsensusObserverMessage>>,
     dkg_network_handle: Option<ApplicationNetworkHandle<DKGMessage>>,
     jwk_consensus_network_handle: Option<ApplicationNetworkHandle<JWKConsensusMsg>>,
     mempool_network_handles: Vec<ApplicationNetworkHandle<MempoolSyncMsg>>,
@@ -437,6 +483,7 @@ fn transform_network_handles_into_interfaces(
     peers_and_metadata: Arc<PeersAndMetadata>,
 ) -> (
     Option<ApplicationNetworkInterfaces<ConsensusMsg>>,
+    Option<ApplicationNetworkInterfaces<ConsensusObserverMessage>>,
     Option<ApplicationNetworkInterfaces<DKGMessage>>,
     Option<ApplicationNetworkInterfaces<JWKConsensusMsg>>,
     ApplicationNetworkInterfaces<MempoolSyncMsg>,
@@ -451,6 +498,16 @@ fn transform_network_handles_into_interfaces(
         )
     });
 
+    let consensus_observer_interfaces = if consensus_observer_network_handles.is_empty() {
+        None
+    } else {
+        Some(create_network_interfaces(
+            consensus_observer_network_handles,
+            consensus_observer_network_configuration(node_config),
+            peers_and_metadata.clone(),
+        ))
+    };
+
     let dkg_interfaces = dkg_network_handle.map(|handle| {
         create_network_interfaces(
             vec![handle],
@@ -485,6 +542,7 @@ fn transform_network_handles_into_interfaces(
 
     (
         consensus_interfaces,
+        consensus_observer_interfaces,
         dkg_interfaces,
         jwk_consensus_interfaces,
         mempool_interfaces,
@@ -6,17 +6,25 @@ use aptos_admin_service::AdminService;
 use aptos_build_info::build_information;
 use aptos_config::config::NodeConfig;
 use aptos_consensus::{
-    network_interface::ConsensusMsg, persistent_liveness_storage::StorageWriteProxy,
+    consensus_observer::{
+        network_message::ConsensusObserverMessage, publisher::ConsensusPublisher,
+    },
+    network_interface::ConsensusMsg,
+    persistent_liveness_storage::StorageWriteProxy,
     quorum_store::quorum_store_db::QuorumStoreDB,
+    LeaderReputationReporter,
 };
-use aptos_consensus_notifications::ConsensusNotifier;
+use aptos_consensus_notifications::{ConsensusNotificationSender, ConsensusNotifier};
 use aptos_data_client::client::AptosDataClient;
 use aptos_db_indexer::table_info_reader::TableInfoReader;
 use aptos_event_notifications::{DbBackedOnChainConfig, ReconfigNotificationListener};
 use aptos_indexer_grpc_fullnode::runtime::bootstrap as bootstrap_indexer_grpc;
 use aptos_indexer_grpc_table_info::runtime::bootstrap as bootstrap_indexer_table_info;
 use aptos_logger::{debug, telemetry_log_writer::TelemetryLog, LoggerFilterUpdater};
-use aptos_mempool::{network::MempoolSyncMsg, MempoolClientRequest, QuorumStoreRequest};
+use aptos_mempool::{
+    network::MempoolSyncMsg, MempoolCapacityReporter, MempoolClientRequest, MempoolPersistence,
+    QuorumStoreRequest, SubmissionQuotas,
+};
 use aptos_mempool_notifications::MempoolNotificationListener;
 use aptos_network::application::{interface::NetworkClientInterface, storage::PeersAndMetadata};
 use aptos_network_benchmark::{run_netbench_service, NetbenchMessage};
@@ -115,7 +123,13 @@ pub fn start_consensus_runtime(
     consensus_notifier: ConsensusNotifier,
     consensus_to_mempool_sender: Sender<QuorumStoreRequest>,
     vtxn_pool: VTxnPoolState,
-) -> (Runtime, Arc<StorageWriteProxy>, Arc<QuorumStoreDB>) {
+    consensus_publisher: Option<ConsensusPublisher>,
+) -> anyhow::Result<(
+    Runtime,
+    Arc<StorageWriteProxy>,
+    Arc<QuorumStoreDB>,
+    LeaderReputationReporter,
+)> {
     let instant = Instant::now();
     let consensus = aptos_consensus::consensus_provider::start_consensus(
         node_config,
@@ -127,9 +141,58 @@ pub fn start_consensus_runtime(
         consensus_reconfig_subscription
             .expect("Consensus requires a reconfiguration subscription!"),
         vtxn_pool,
-    );
+        consensus_publisher,
+    )?;
     debug!("Consensus started in {} ms", instant.elapsed().as_millis());
-    consensus
+    Ok(consensus)
+}
+
+/// Starts the consensus observer runtime (if enabled) and returns the consensus
+/// publisher (if enabled). Only full nodes observe consensus, but any node can
+/// publish (e.g., a validator to its VFNs, or a VFN to its public fullnodes).
+pub fn start_consensus_observer_runtime(
+    node_config: &NodeConfig,
+    consensus_observer_network_interfaces: Option<
+        ApplicationNetworkInterfaces<ConsensusObserverMessage>,
+    >,
+    consensus_notifier: ConsensusNotifier,
+    db_rw: DbReaderWriter,
+) -> (Option<Runtime>, Option<ConsensusPublisher>) {
+    // If the observer and publisher are disabled, there's nothing to start
+    let consensus_observer_network_interfaces = match consensus_observer_network_interfaces {
+        Some(network_interfaces) => network_interfaces,
+        None => return (None, None),
+    };
+    let consensus_observer_config = &node_config.consensus_observer;
+
+    // Create the consensus publisher
+    let consensus_publisher = if consensus_observer_config.publisher_enabled {
+        Some(ConsensusPublisher::new(
+            consensus_observer_config.clone(),
+            consensus_observer_network_interfaces.network_client.clone(),
+        ))
+    } else {
+        None
+    };
+
+    // Only full nodes forward the observed updates to state sync
+    let state_sync_notifier: Option<Arc<dyn ConsensusNotificationSender>> =
+        if consensus_observer_config.observer_enabled && !node_config.base.role.is_validator() {
+            Some(Arc::new(consensus_notifier))
+        } else {
+            None
+        };
+
+    // Start the consensus observer
+    let runtime = aptos_consensus::consensus_provider::start_consensus_observer(
+        node_config,
+        consensus_observer_network_interfaces.network_client,
+        consensus_observer_network_interfaces.network_service_events,
+        consensus_publisher.clone(),
+        state_sync_notifier,
+        db_rw,
+    );
+    (Some(runtime), consensus_publisher)
 }
 
 /// Create the mempool runtime and start mempool
@@ -141,27 +204,40 @@ pub fn start_mempool_runtime_and_get_consensus_sender(
     mempool_listener: MempoolNotificationListener,
     mempool_client_receiver: Receiver<MempoolClientRequest>,
     peers_and_metadata: Arc<PeersAndMetadata>,
-) -> (Runtime, Sender<QuorumStoreRequest>) {
+) -> (
+    Runtime,
+    Sender<QuorumStoreRequest>,
+    Arc<SubmissionQuotas>,
+    MempoolCapacityReporter,
+    MempoolPersistence,
+) {
     // Create a communication channel between consensus and mempool
     let (consensus_to_mempool_sender, consensus_to_mempool_receiver) =
         mpsc::channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
 
     // Bootstrap and start mempool
     let instant = Instant::now();
-    let mempool = aptos_mempool::bootstrap(
-        node_config,
-        Arc::clone(&db_rw.reader),
-        network_interfaces.network_client,
-        network_interfaces.network_service_events,
-        mempool_client_receiver,
-        consensus_to_mempool_receiver,
-        mempool_listener,
-        mempool_reconfig_subscription,
-        peers_and_metadata,
-    );
+    let (mempool, submission_quotas, capacity_reporter, mempool_persistence) =
+        aptos_mempool::bootstrap(
+            node_config,
+            Arc::clone(&db_rw.reader),
+            network_interfaces.network_client,
+            network_interfaces.network_service_events,
+            mempool_client_receiver,
+            consensus_to_mempool_receiver,
+            mempool_listener,
+            mempool_reconfig_subscription,
+            peers_and_metadata,
+        );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
 
-    (mempool, consensus_to_mempool_sender)
+    (
+        mempool,
+        consensus_to_mempool_sender,
+        submission_quotas,
+        capacity_reporter,
+        mempool_persistence,
+    )
 }
 
 /// Spawns a new thread for the admin service
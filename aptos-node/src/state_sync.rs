@@ -26,7 +26,7 @@ use aptos_state_sync_driver::{
 };
 use aptos_storage_interface::{DbReader, DbReaderWriter};
 use aptos_storage_service_client::StorageServiceClient;
-use aptos_storage_service_notifications::StorageServiceNotificationListener;
+use aptos_storage_service_notifications::{PeerCommitNotifier, StorageServiceNotificationListener};
 use aptos_storage_service_server::{
     network::StorageServiceNetworkEvents, storage::StorageReader, StorageServiceServer,
 };
@@ -128,7 +128,7 @@ pub fn start_state_sync_and_get_notification_handles(
     // Start the data client
     let peers_and_metadata = network_client.get_peers_and_metadata();
     let (aptos_data_client, aptos_data_client_runtime) =
-        setup_aptos_data_client(node_config, network_client, db_rw.reader.clone())?;
+        setup_aptos_data_client(node_config, network_client.clone(), db_rw.reader.clone())?;
 
     // Start the data streaming service
     let state_sync_config = node_config.state_sync;
@@ -154,14 +154,18 @@ pub fn start_state_sync_and_get_notification_handles(
         );
     let (storage_service_notifier, storage_service_listener) =
         aptos_storage_service_notifications::new_storage_service_notifier_listener_pair();
+    let (peer_commit_notifier, peer_commit_listener) =
+        aptos_storage_service_notifications::new_peer_commit_notifier_listener_pair();
 
     // Start the state sync storage service
     let storage_service_runtime = setup_state_sync_storage_service(
         state_sync_config,
         peers_and_metadata,
         network_service_events,
+        network_client,
         &db_rw,
         storage_service_listener,
+        peer_commit_notifier,
     )?;
 
     // Create the state sync driver factory
@@ -174,6 +178,7 @@ pub fn start_state_sync_and_get_notification_handles(
         mempool_notifier,
         storage_service_notifier,
         metadata_storage,
+        peer_commit_listener,
         consensus_listener,
         event_subscription_service,
         aptos_data_client.clone(),
@@ -251,8 +256,10 @@ fn setup_state_sync_storage_service(
     config: StateSyncConfig,
     peers_and_metadata: Arc<PeersAndMetadata>,
     network_service_events: NetworkServiceEvents<StorageServiceMessage>,
+    network_client: NetworkClient<StorageServiceMessage>,
     db_rw: &DbReaderWriter,
     storage_service_listener: StorageServiceNotificationListener,
+    peer_commit_notifier: PeerCommitNotifier,
 ) -> anyhow::Result<Runtime> {
     // Create a new state sync storage service runtime
     let storage_service_runtime = aptos_runtimes::spawn_named_runtime("stor-server".into(), None);
@@ -265,8 +272,9 @@ fn setup_state_sync_storage_service(
         storage_reader,
         TimeService::real(),
         peers_and_metadata,
-        StorageServiceNetworkEvents::new(network_service_events),
+        StorageServiceNetworkEvents::new(network_service_events, Some(peer_commit_notifier)),
         storage_service_listener,
+        Some(network_client),
     );
     storage_service_runtime.spawn(service.start());
 
@@ -5,7 +5,8 @@ use anyhow::anyhow;
 use aptos_config::config::{NodeConfig, DEFAULT_CONCURRENCY_LEVEL};
 use aptos_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
 use aptos_types::{
-    account_config::CORE_CODE_ADDRESS, account_view::AccountView, chain_id::ChainId,
+    account_config::CORE_CODE_ADDRESS, account_view::AccountView,
+    block_executor::config::ResourceGroupSerializationConfig, chain_id::ChainId,
     state_store::account_with_state_view::AsAccountWithStateView,
 };
 use aptos_vm::AptosVM;
@@ -33,6 +34,31 @@ pub fn create_global_rayon_pool(create_global_rayon_pool: bool) {
     }
 }
 
+/// Blocks the current thread until the process receives a shutdown signal
+/// (i.e., SIGINT, or SIGTERM on unix platforms).
+pub fn wait_for_shutdown_signal() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build the shutdown signal runtime!");
+    runtime.block_on(async {
+        #[cfg(unix)]
+        {
+            let mut sigterm =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                    .expect("Failed to install the SIGTERM handler!");
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {},
+                _ = sigterm.recv() => {},
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    });
+}
+
 /// Fetches the chain ID from on-chain resources
 pub fn fetch_chain_id(db: &DbReaderWriter) -> anyhow::Result<ChainId> {
     let db_state_view = db
@@ -57,6 +83,13 @@ pub fn set_aptos_vm_configurations(node_config: &NodeConfig) {
     };
     AptosVM::set_concurrency_level_once(effective_concurrency_level as usize);
     AptosVM::set_discard_failed_blocks(node_config.execution.discard_failed_blocks);
+    if let Some(budget_ms) = node_config.execution.txn_execution_time_budget_ms {
+        AptosVM::set_txn_execution_time_budget_ms(budget_ms);
+    }
+    AptosVM::set_resource_group_serialization_config(ResourceGroupSerializationConfig {
+        split_threshold_bytes: node_config.execution.resource_group_split_threshold_bytes,
+        merge_threshold_bytes: node_config.execution.resource_group_merge_threshold_bytes,
+    });
     AptosVM::set_num_proof_reading_threads_once(
         node_config.execution.num_proof_reading_threads as usize,
     );
@@ -67,4 +100,7 @@ pub fn set_aptos_vm_configurations(node_config: &NodeConfig) {
     {
         AptosVM::set_processed_transactions_detailed_counters();
     }
+    if node_config.execution.execution_stats_digest {
+        AptosVM::set_execution_stats_digest();
+    }
 }
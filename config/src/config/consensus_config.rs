@@ -60,6 +60,12 @@ pub struct ConsensusConfig {
     pub intra_consensus_channel_buffer_size: usize,
     pub quorum_store: QuorumStoreConfig,
     pub vote_back_pressure_limit: u64,
+    // When the local execution lags behind ordering by more than vote_back_pressure_limit
+    // rounds, the validator keeps voting on ordering (order-only mode) as long as the lag is
+    // within this limit, instead of withholding its votes. In order-only mode, the validator
+    // doesn't vote on execution results (i.e., commit votes) until it catches up.
+    // 0 disables the order-only mode.
+    pub order_only_max_execution_lag: u64,
     pub pipeline_backpressure: Vec<PipelineBackpressureValues>,
     // Used to decide if backoff is needed.
     // must match one of the CHAIN_HEALTH_WINDOW_SIZES values.
@@ -74,6 +80,248 @@ pub struct ConsensusConfig {
     pub broadcast_vote: bool,
     pub proof_cache_capacity: u64,
     pub rand_rb_config: ReliableBroadcastConfig,
+    // Number of recent rounds for which the round manager remembers the proposals and votes it
+    // has processed, so that duplicates (e.g., after recovery or network flaps) are not reprocessed.
+    pub processed_msgs_window_rounds: u64,
+    // Number of recent proposal timestamps kept per peer to estimate its clock offset (relative
+    // to the local clock). Setting this to 0 disables clock skew estimation.
+    pub clock_skew_window_size: usize,
+    // Minimum number of samples required before a peer's clock offset is used in the estimates.
+    pub clock_skew_min_samples: usize,
+    // Upper bound on the extra time (beyond the round deadline) that proposal timestamps may be
+    // ahead of the local clock, when the estimated fleet clock skew requires it (in milliseconds).
+    pub max_proposal_timestamp_tolerance_ms: u64,
+    pub consensusdb_pruner: ConsensusDBPrunerConfig,
+    pub optimistic_execution: OptimisticExecutionConfig,
+    pub state_prefetch: StatePrefetchConfig,
+    pub external_block_builder: ExternalBlockBuilderConfig,
+    pub startup_checks: ConsensusStartupChecksConfig,
+    pub block_range_retrieval: BlockRangeRetrievalConfig,
+    pub epoch_starter_kit: EpochStarterKitConfig,
+    pub rand_share_backfill: RandShareBackfillConfig,
+    pub byzantine_behavior: ByzantineBehaviorConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusDBPrunerConfig {
+    // Whether stale blocks and quorum certs are pruned from the consensus db in the background
+    pub enable: bool,
+    // Number of rounds (below the latest committed round) for which blocks and quorum certs
+    // are retained. Blocks and quorum certs from previous epochs are always pruned.
+    pub prune_window_rounds: u64,
+    // Maximum number of blocks (and their quorum certs) deleted in a single write batch
+    pub batch_size: usize,
+    // Delay between consecutive write batches, to limit the impact on the consensus db
+    pub batch_interval_ms: u64,
+    // Interval between pruning passes
+    pub pruning_interval_ms: u64,
+    // Whether each pruning pass blocks the creation of consensus db checkpoints, so that
+    // checkpoints never observe a partially pruned database.
+    pub checkpoint_consistent: bool,
+}
+
+impl Default for ConsensusDBPrunerConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            prune_window_rounds: 1_000,
+            batch_size: 100,
+            batch_interval_ms: 10,
+            pruning_interval_ms: 60_000,
+            checkpoint_consistent: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OptimisticExecutionConfig {
+    // Whether proposals are speculatively executed as soon as they pass verification (i.e.,
+    // before a QC is formed). The results are discarded if the block is never ordered.
+    pub enable: bool,
+    // Maximum number of speculatively executed blocks whose results are retained (in flight or
+    // waiting to be ordered). This bounds the memory used by speculative execution.
+    pub max_speculative_blocks: usize,
+    // Proposals with more transactions than this are not speculatively executed
+    pub max_speculative_block_txns: usize,
+}
+
+impl Default for OptimisticExecutionConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_speculative_blocks: 4,
+            max_speculative_block_txns: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatePrefetchConfig {
+    // Whether the state read by the transactions of an ordered block (e.g., the sender accounts
+    // and the called modules) is prefetched from storage while earlier blocks are executing
+    pub enable: bool,
+    // Maximum number of state keys prefetched for a single block
+    pub max_prefetch_keys_per_block: usize,
+}
+
+impl Default for StatePrefetchConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_prefetch_keys_per_block: 20_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExternalBlockBuilderConfig {
+    // Whether proposal payloads are requested from an external (out-of-process) block builder.
+    // If the builder fails to respond in time, the payload is pulled from quorum store instead.
+    pub enable: bool,
+    // Path of the local (unix domain) socket the external block builder listens on
+    pub socket_path: PathBuf,
+    // Deadline for the external block builder to respond with a payload
+    pub request_timeout_ms: u64,
+    // Maximum size of a single response from the external block builder
+    pub max_response_bytes: usize,
+}
+
+impl Default for ExternalBlockBuilderConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            socket_path: PathBuf::from("/tmp/aptos-block-builder.sock"),
+            request_timeout_ms: 100,
+            max_response_bytes: 64 * 1024 * 1024, // 64 MiB
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusStartupChecksConfig {
+    // Whether to validate the consensus key, consensus dbs and local clock before starting
+    // consensus. If any check fails, the node refuses to start consensus.
+    pub enable: bool,
+    // Maximum time the local clock can be behind the latest committed block timestamp
+    pub max_clock_lag_behind_ledger_ms: u64,
+}
+
+impl Default for ConsensusStartupChecksConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            max_clock_lag_behind_ledger_ms: 60_000, // 1 minute
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlockRangeRetrievalConfig {
+    // Whether missing blocks are retrieved using ranged requests (i.e., all blocks down to a
+    // target round, in responses capped by bytes), instead of requests for a fixed number of
+    // blocks. Note: all peers must support ranged requests (otherwise the requests will fail).
+    pub enable: bool,
+    // Maximum number of bytes of the blocks in a ranged retrieval response. A response always
+    // contains at least one block (if found), even if the block exceeds this limit.
+    pub max_response_bytes: u64,
+}
+
+impl Default for BlockRangeRetrievalConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_response_bytes: 20 * 1024 * 1024, // 20 MiB
+        }
+    }
+}
+
+impl BlockRangeRetrievalConfig {
+    /// Returns the max bytes of ranged retrieval responses (if ranged retrieval is enabled)
+    pub fn max_request_response_bytes(&self) -> Option<u64> {
+        self.enable.then_some(self.max_response_bytes)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EpochStarterKitConfig {
+    // Whether nodes without consensus data (e.g., joining mid-epoch with an empty consensus db)
+    // request an epoch starter kit (i.e., the epoch change proof, latest sync info and recent
+    // blocks) from peers, instead of waiting for consensus messages to trigger recovery. Note:
+    // peers that don't support epoch starter kits will ignore the requests.
+    pub enable: bool,
+    // Maximum number of recent blocks requested in (and served with) an epoch starter kit
+    pub max_num_blocks: u64,
+    // Number of (random) peers the epoch starter kit is requested from
+    pub num_peers_to_request: usize,
+}
+
+impl Default for EpochStarterKitConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            max_num_blocks: 100,
+            num_peers_to_request: 3,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RandShareBackfillConfig {
+    // Whether validators request all known randomness shares of the blocks that are pending
+    // randomness for too long (e.g., after missing share broadcasts) from peers. Note: backfill
+    // requests are always served (subject to rate limiting), even if this is disabled.
+    pub enable: bool,
+    // Time that a block must be pending randomness before its shares are backfilled (in ms)
+    pub backfill_delay_ms: u64,
+    // Interval between the checks for blocks to backfill (in milliseconds)
+    pub backfill_interval_ms: u64,
+    // Maximum number of rounds in a backfill request. Larger requests are not served.
+    pub max_rounds_per_request: usize,
+    // Number of (random) peers the shares are requested from
+    pub num_peers_to_request: usize,
+    // Timeout for the backfill requests (in milliseconds)
+    pub rpc_timeout_ms: u64,
+    // Minimum time between two backfill requests served to the same peer (in milliseconds).
+    // Requests that arrive earlier are dropped.
+    pub min_request_interval_per_peer_ms: u64,
+}
+
+impl Default for RandShareBackfillConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            backfill_delay_ms: 1_000,
+            backfill_interval_ms: 1_000,
+            max_rounds_per_request: 20,
+            num_peers_to_request: 2,
+            rpc_timeout_ms: 2_000,
+            min_request_interval_per_peer_ms: 200,
+        }
+    }
+}
+
+/// Byzantine behaviors injected into the round manager, used to validate the reactions of
+/// the honest validators (and their safety rules) in tests. Note: the behaviors are only
+/// applied if consensus is built with the `byzantine` feature, and the config sanitizer
+/// rejects any non-default behaviors otherwise.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ByzantineBehaviorConfig {
+    // Whether to send two conflicting (signed) proposals for the same round, each to a
+    // different half of the validators, when the node is the proposer
+    pub equivocate_proposals: bool,
+    // Whether to withhold the votes for proposals (i.e., sign the votes, but not send them)
+    pub withhold_votes: bool,
+    // Delay before broadcasting a timeout vote after a local timeout (in milliseconds)
+    pub timeout_vote_delay_ms: u64,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
@@ -181,6 +429,7 @@ impl Default for ConsensusConfig {
             // Considering block gas limit and pipeline backpressure should keep number of blocks
             // in the pipline very low, we can keep this limit pretty low, too.
             vote_back_pressure_limit: 7,
+            order_only_max_execution_lag: 0,
             pipeline_backpressure: vec![
                 PipelineBackpressureValues {
                     // pipeline_latency looks how long has the oldest block still in pipeline
@@ -320,6 +569,19 @@ impl Default for ConsensusConfig {
                 backoff_policy_max_delay_ms: 10000,
                 rpc_timeout_ms: 10000,
             },
+            processed_msgs_window_rounds: 20,
+            clock_skew_window_size: 50,
+            clock_skew_min_samples: 10,
+            max_proposal_timestamp_tolerance_ms: 10_000,
+            consensusdb_pruner: ConsensusDBPrunerConfig::default(),
+            optimistic_execution: OptimisticExecutionConfig::default(),
+            state_prefetch: StatePrefetchConfig::default(),
+            external_block_builder: ExternalBlockBuilderConfig::default(),
+            startup_checks: ConsensusStartupChecksConfig::default(),
+            block_range_retrieval: BlockRangeRetrievalConfig::default(),
+            epoch_starter_kit: EpochStarterKitConfig::default(),
+            rand_share_backfill: RandShareBackfillConfig::default(),
+            byzantine_behavior: ByzantineBehaviorConfig::default(),
         }
     }
 }
@@ -470,10 +732,47 @@ impl ConfigSanitizer for ConsensusConfig {
         // Quorum store batches must be <= consensus blocks
         Self::sanitize_batch_block_limits(&sanitizer_name, &node_config.consensus)?;
 
+        // The order-only execution lag (if enabled) must exceed the vote back pressure limit
+        let consensus_config = &node_config.consensus;
+        if consensus_config.order_only_max_execution_lag > 0
+            && consensus_config.order_only_max_execution_lag
+                <= consensus_config.vote_back_pressure_limit
+        {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                format!(
+                    "The order-only lag ({}) must exceed the vote back pressure limit ({})!",
+                    consensus_config.order_only_max_execution_lag,
+                    consensus_config.vote_back_pressure_limit
+                ),
+            ));
+        }
+
+        // Byzantine behaviors can only be configured if the byzantine feature is enabled
+        if !is_byzantine_enabled()
+            && consensus_config.byzantine_behavior != ByzantineBehaviorConfig::default()
+        {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "Byzantine behaviors require the byzantine feature to be enabled!".to_string(),
+            ));
+        }
+
         Ok(())
     }
 }
 
+/// Returns true iff the byzantine feature is enabled
+fn is_byzantine_enabled() -> bool {
+    cfg_if! {
+        if #[cfg(feature = "byzantine")] {
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// Returns true iff consensus-only-perf-test is enabled
 fn is_consensus_only_perf_test_enabled() -> bool {
     cfg_if! {
@@ -738,4 +1037,47 @@ mod test {
             ConsensusConfig::sanitize(&node_config, NodeType::ValidatorFullnode, None).unwrap_err();
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
+
+    #[test]
+    fn test_invalid_order_only_max_execution_lag() {
+        // Create a node config with an order-only execution lag below the vote back pressure limit
+        let node_config = NodeConfig {
+            consensus: ConsensusConfig {
+                vote_back_pressure_limit: 10,
+                order_only_max_execution_lag: 5,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error = ConsensusConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
+    #[test]
+    fn test_byzantine_behavior() {
+        // Create a node config with a byzantine behavior
+        let node_config = NodeConfig {
+            consensus: ConsensusConfig {
+                byzantine_behavior: ByzantineBehaviorConfig {
+                    withhold_votes: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it only succeeds with the byzantine feature
+        let result = ConsensusConfig::sanitize(&node_config, NodeType::Validator, None);
+        if is_byzantine_enabled() {
+            assert!(result.is_ok());
+        } else {
+            assert!(matches!(
+                result.unwrap_err(),
+                Error::ConfigSanitizerFailed(_, _)
+            ));
+        }
+    }
 }
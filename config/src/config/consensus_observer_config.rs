@@ -0,0 +1,121 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::{
+    config_sanitizer::ConfigSanitizer, node_config_loader::NodeType, Error, NodeConfig,
+};
+use aptos_types::{chain_id::ChainId, PeerId};
+use serde::{Deserialize, Serialize};
+
+/// The configuration of the consensus observer, which allows fullnodes to execute
+/// the ordered blocks and follow the commit decisions published by upstream nodes
+/// (e.g., a VFN following its validator), instead of waiting for state sync to
+/// discover new data.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusObserverConfig {
+    /// Whether the node subscribes to and follows consensus updates
+    pub observer_enabled: bool,
+    /// Whether the node publishes consensus updates to its subscribers
+    pub publisher_enabled: bool,
+
+    /// Maximum number of pending network messages
+    pub max_network_channel_size: u64,
+    /// Interval (in milliseconds) to check the observation progress
+    pub progress_check_interval_ms: u64,
+    /// Maximum time (in milliseconds) without a verified commit decision,
+    /// after which the observer falls back to state sync.
+    pub max_observation_gap_ms: u64,
+    /// Maximum number of block payloads buffered by the observer (for execution)
+    pub max_pending_blocks: u64,
+
+    /// Maximum number of subscribers served by the publisher
+    pub max_subscribers: u64,
+    /// The peers (in addition to those on the VFN network) that may subscribe to the publisher
+    pub allowed_subscriber_peers: Vec<PeerId>,
+}
+
+impl Default for ConsensusObserverConfig {
+    fn default() -> Self {
+        Self {
+            observer_enabled: false,
+            publisher_enabled: false,
+            max_network_channel_size: 1000,
+            progress_check_interval_ms: 5_000,
+            max_observation_gap_ms: 10_000,
+            max_pending_blocks: 100,
+            max_subscribers: 10,
+            allowed_subscriber_peers: vec![],
+        }
+    }
+}
+
+impl ConsensusObserverConfig {
+    /// Returns true iff the observer or the publisher is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.observer_enabled || self.publisher_enabled
+    }
+}
+
+impl ConfigSanitizer for ConsensusObserverConfig {
+    fn sanitize(
+        node_config: &NodeConfig,
+        node_type: NodeType,
+        _chain_id: Option<ChainId>,
+    ) -> Result<(), Error> {
+        let sanitizer_name = Self::get_sanitizer_name();
+        let consensus_observer_config = &node_config.consensus_observer;
+
+        // Verify that validators do not enable the observer (they participate in consensus)
+        if node_type.is_validator() && consensus_observer_config.observer_enabled {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "Validators should not enable the consensus observer!".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_observer_on_validator() {
+        // Create a node config with the observer enabled
+        let node_config = NodeConfig {
+            consensus_observer: ConsensusObserverConfig {
+                observer_enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Verify that the config fails sanitization for validators
+        let error =
+            ConsensusObserverConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Verify that the config passes sanitization for fullnodes
+        for node_type in [NodeType::ValidatorFullnode, NodeType::PublicFullnode] {
+            ConsensusObserverConfig::sanitize(&node_config, node_type, None).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_sanitize_publisher_on_validator() {
+        // Create a node config with only the publisher enabled
+        let node_config = NodeConfig {
+            consensus_observer: ConsensusObserverConfig {
+                publisher_enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Verify that the config passes sanitization for validators
+        ConsensusObserverConfig::sanitize(&node_config, NodeType::Validator, None).unwrap();
+    }
+}
@@ -35,10 +35,22 @@ pub struct ExecutionConfig {
     pub paranoid_type_verification: bool,
     /// Enabled discarding blocks that fail execution due to BlockSTM/VM issue.
     pub discard_failed_blocks: bool,
+    /// If set, parallel execution of a block falls back to sequential execution when a
+    /// transaction takes longer than the given number of milliseconds to execute.
+    pub txn_execution_time_budget_ms: Option<u64>,
+    /// If set, resource groups with more bytes are serialized in parallel chunks
+    /// (the serialized groups are identical for any threshold).
+    pub resource_group_split_threshold_bytes: Option<usize>,
+    /// If set, resource groups with fewer bytes are serialized into a shared buffer
+    /// (the serialized groups are identical for any threshold).
+    pub resource_group_merge_threshold_bytes: Option<usize>,
     /// Enables paranoid mode for hot potatoes, which adds extra runtime VM checks
     pub paranoid_hot_potato_verification: bool,
     /// Enables enhanced metrics around processed transactions
     pub processed_transactions_detailed_counters: bool,
+    /// Enables computing a digest of the (deterministic) execution statistics of each block,
+    /// which can be compared across validators to detect non-deterministic execution
+    pub execution_stats_digest: bool,
     /// Enables filtering of transactions before they are sent to execution
     pub transaction_filter: Filter,
     /// Used during DB bootstrapping
@@ -72,7 +84,11 @@ impl Default for ExecutionConfig {
             paranoid_type_verification: true,
             paranoid_hot_potato_verification: true,
             discard_failed_blocks: false,
+            txn_execution_time_budget_ms: None,
+            resource_group_split_threshold_bytes: None,
+            resource_group_merge_threshold_bytes: None,
             processed_transactions_detailed_counters: false,
+            execution_stats_digest: false,
             transaction_filter: Filter::empty(),
             genesis_waypoint: None,
         }
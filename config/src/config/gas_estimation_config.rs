@@ -15,6 +15,17 @@ pub struct GasEstimationStaticOverride {
     pub aggressive: u64,
 }
 
+/// The percentiles of the gas price distribution (of the ready transactions in mempool and the
+/// transactions committed in recent blocks) used to refine the gas estimation.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct GasEstimationMempoolPercentiles {
+    pub low: u8,
+    pub market: u8,
+    pub aggressive: u8,
+    /// The number of recently committed blocks considered by the estimation
+    pub horizon_blocks: usize,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct GasEstimationConfig {
@@ -22,6 +33,9 @@ pub struct GasEstimationConfig {
     pub enabled: bool,
     /// Static values to override. If set, use these values instead of computing a GasEstimation.
     pub static_override: Option<GasEstimationStaticOverride>,
+    /// Mempool percentiles to refine the estimation with. If set, the computed GasEstimation
+    /// is refined with the gas price distribution tracked by mempool.
+    pub mempool_percentiles: Option<GasEstimationMempoolPercentiles>,
     /// Number of transactions for blocks to be classified as full for gas estimation
     pub full_block_txns: usize,
     /// Maximum number of blocks read for low gas estimation
@@ -39,6 +53,7 @@ impl Default for GasEstimationConfig {
         GasEstimationConfig {
             enabled: true,
             static_override: None,
+            mempool_percentiles: None,
             full_block_txns: 250,
             low_block_history: 10,
             market_block_history: 30,
@@ -88,6 +103,24 @@ impl ConfigSanitizer for GasEstimationConfig {
             ));
         }
 
+        // Validate the mempool percentiles are increasing and at most 100
+        if let Some(mempool_percentiles) = &gas_estimation_config.mempool_percentiles {
+            if mempool_percentiles.low > mempool_percentiles.market
+                || mempool_percentiles.market > mempool_percentiles.aggressive
+                || mempool_percentiles.aggressive > 100
+            {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    format!(
+                        "invalid mempool percentiles: low {}, market {}, aggressive {}",
+                        mempool_percentiles.low,
+                        mempool_percentiles.market,
+                        mempool_percentiles.aggressive
+                    ),
+                ));
+            }
+        }
+
         Ok(())
     }
 }
@@ -214,4 +247,29 @@ mod tests {
             GasEstimationConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
+
+    #[test]
+    fn test_sanitize_invalid_mempool_percentiles() {
+        // Create a node config with a market percentile that is higher than the aggressive one
+        let node_config = NodeConfig {
+            api: ApiConfig {
+                gas_estimation: GasEstimationConfig {
+                    mempool_percentiles: Some(GasEstimationMempoolPercentiles {
+                        low: 10,
+                        market: 95,
+                        aggressive: 90,
+                        horizon_blocks: 30,
+                    }),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error =
+            GasEstimationConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
 }
@@ -2,18 +2,24 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::config::{
-    config_optimizer::ConfigOptimizer, config_sanitizer::ConfigSanitizer,
-    node_config_loader::NodeType, Error, NodeConfig, MAX_APPLICATION_MESSAGE_SIZE,
+use crate::{
+    config::{
+        config_optimizer::ConfigOptimizer, config_sanitizer::ConfigSanitizer,
+        node_config_loader::NodeType, Error, NodeConfig, MAX_APPLICATION_MESSAGE_SIZE,
+    },
+    network_id::NetworkId,
 };
 use aptos_global_constants::DEFAULT_BUCKETS;
 use aptos_types::chain_id::ChainId;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct MempoolConfig {
+    /// Adaptive broadcast windows (i.e., batch sizes and intervals) per peer
+    pub adaptive_broadcast: AdaptiveBroadcastConfig,
     /// Maximum number of transactions allowed in the Mempool
     pub capacity: usize,
     /// Maximum number of bytes allowed in the Mempool
@@ -22,14 +28,32 @@ pub struct MempoolConfig {
     pub capacity_per_user: usize,
     /// Number of failover peers to broadcast to when the primary network is alive
     pub default_failovers: usize,
+    /// The networks on which broadcasts announce transaction digests first (so that peers
+    /// only request the transactions they are missing), instead of sending the full
+    /// transactions. This only applies to peers that support digest announcements.
+    pub digest_broadcast_networks: Vec<NetworkId>,
+    /// Whether or not the batches pulled by consensus prefer complete chains of sequential
+    /// account transactions (by their fee per transaction), instead of independent transactions
+    pub enable_chain_aware_batch_packing: bool,
+    /// Whether or not consensus backpressure (i.e., when the validator cannot drain the
+    /// mempool anyway) slows down broadcasts and peer intake
+    pub enable_consensus_backpressure: bool,
     /// Whether or not to enable intelligent peer prioritization
     pub enable_intelligent_peer_prioritization: bool,
+    /// The number of recently committed blocks whose gas prices are tracked for gas price
+    /// estimation (i.e., the maximum estimation horizon)
+    pub fee_estimation_history_blocks: usize,
     /// The maximum number of broadcasts sent to a single peer that are pending a response ACK at any point.
     pub max_broadcasts_per_peer: usize,
     /// Maximum number of inbound network messages to the Mempool application
     pub max_network_channel_size: usize,
+    /// The maximum number of sequential transactions in a transaction group (i.e.,
+    /// transactions of an account that are submitted and kept together)
+    pub max_transaction_group_size: usize,
     /// The interval to take a snapshot of the mempool to logs, only used when trace logging is enabled
     pub mempool_snapshot_interval_secs: u64,
+    /// Persistence of the mempool transactions across restarts
+    pub persistence: MempoolPersistenceConfig,
     /// The maximum amount of time to wait for an ACK of Mempool submission to an upstream node.
     pub shared_mempool_ack_timeout_ms: u64,
     /// The amount of time to backoff between retries of Mempool submission to an upstream node.
@@ -58,6 +82,8 @@ pub struct MempoolConfig {
     pub broadcast_buckets: Vec<u64>,
     pub eager_expire_threshold_ms: Option<u64>,
     pub eager_expire_time_ms: u64,
+    /// Per-source quotas for transactions submitted to the node (e.g., via the API)
+    pub submission_quotas: SubmissionQuotasConfig,
 }
 
 impl Default for MempoolConfig {
@@ -71,12 +97,19 @@ impl Default for MempoolConfig {
             shared_mempool_max_concurrent_inbound_syncs: 4,
             max_broadcasts_per_peer: 20,
             max_network_channel_size: 1024,
+            max_transaction_group_size: 16,
             mempool_snapshot_interval_secs: 180,
+            persistence: MempoolPersistenceConfig::default(),
+            adaptive_broadcast: AdaptiveBroadcastConfig::default(),
             capacity: 2_000_000,
             capacity_bytes: 2 * 1024 * 1024 * 1024,
             capacity_per_user: 100,
             default_failovers: 1,
+            digest_broadcast_networks: vec![],
+            enable_chain_aware_batch_packing: false,
+            enable_consensus_backpressure: true,
             enable_intelligent_peer_prioritization: true,
+            fee_estimation_history_blocks: 120,
             shared_mempool_peer_update_interval_ms: 1_000,
             shared_mempool_priority_update_interval_secs: 600, // 10 minutes (frequent reprioritization is expensive)
             system_transaction_timeout_secs: 600,
@@ -84,17 +117,147 @@ impl Default for MempoolConfig {
             broadcast_buckets: DEFAULT_BUCKETS.to_vec(),
             eager_expire_threshold_ms: Some(10_000),
             eager_expire_time_ms: 3_000,
+            submission_quotas: SubmissionQuotasConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MempoolPersistenceConfig {
+    /// Whether or not to persist the mempool transactions to a local DB (periodically,
+    /// and on shutdown), and restore them on startup
+    pub enabled: bool,
+    /// Maximum number of transaction bytes to persist. The highest priority
+    /// (ready) transactions are persisted first.
+    pub max_persisted_bytes: u64,
+    /// Maximum age (since mempool insertion) of the transactions to restore.
+    /// Older transactions are discarded on startup.
+    pub max_transaction_age_secs: u64,
+    /// Whether or not to also persist the parked (i.e., non-ready) transactions
+    pub persist_parked_transactions: bool,
+    /// The interval (in seconds) between mempool snapshots. If this is
+    /// 0, the transactions are only persisted on shutdown.
+    pub snapshot_interval_secs: u64,
+}
+
+impl Default for MempoolPersistenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_persisted_bytes: 100 * 1024 * 1024, // 100 MiB
+            max_transaction_age_secs: 600,          // 10 minutes
+            persist_parked_transactions: true,
+            snapshot_interval_secs: 60,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveBroadcastConfig {
+    /// Whether or not the broadcast windows adapt to the bandwidth class of each peer, as
+    /// observed from its broadcast ACKs (i.e., fast peers get larger and more frequent
+    /// batches, and slow peers get smaller and less frequent batches)
+    pub enabled: bool,
+    /// Peers with a smoothed ACK round trip time below this are considered fast
+    pub fast_peer_max_rtt_ms: u64,
+    /// Peers with a smoothed ACK round trip time above this are considered slow
+    pub slow_peer_min_rtt_ms: u64,
+    /// Peers with a smoothed broadcast error rate (i.e., expired or failed broadcasts)
+    /// above this percentage are considered slow, regardless of their round trip time
+    pub slow_peer_min_error_rate_percent: u64,
+    /// The factor by which the batch size is scaled up (and the broadcast interval is
+    /// scaled down) for fast peers, and vice versa for slow peers
+    pub window_scaling_factor: u64,
+}
+
+impl Default for AdaptiveBroadcastConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fast_peer_max_rtt_ms: 50,
+            slow_peer_min_rtt_ms: 500,
+            slow_peer_min_error_rate_percent: 10,
+            window_scaling_factor: 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubmissionQuotasConfig {
+    /// Whether or not to enforce submission quotas
+    pub enabled: bool,
+    /// The quota for submissions without an API key (or with an unknown API key).
+    /// If this is not set, these submissions are not limited.
+    pub default_quota: Option<SubmissionQuota>,
+    /// The quotas for submissions with specific API keys (keyed by API key)
+    pub api_key_quotas: BTreeMap<String, SubmissionQuota>,
+}
+
+/// A limit on the number of transactions a single source can submit per time window
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubmissionQuota {
+    /// Maximum number of transactions that can be submitted in a single window
+    pub max_submissions_per_window: u64,
+    /// The duration of a single window (in seconds)
+    pub window_secs: u64,
+}
+
+impl SubmissionQuota {
+    pub fn new(max_submissions_per_window: u64, window_secs: u64) -> Self {
+        Self {
+            max_submissions_per_window,
+            window_secs,
         }
     }
 }
 
 impl ConfigSanitizer for MempoolConfig {
     fn sanitize(
-        _node_config: &NodeConfig,
+        node_config: &NodeConfig,
         _node_type: NodeType,
         _chain_id: Option<ChainId>,
     ) -> Result<(), Error> {
-        Ok(()) // TODO: add reasonable verifications
+        let sanitizer_name = Self::get_sanitizer_name();
+        let submission_quotas_config = &node_config.mempool.submission_quotas;
+
+        // Verify that all submission quotas have a non-zero window
+        let default_quota = submission_quotas_config.default_quota.iter();
+        let api_key_quotas = submission_quotas_config.api_key_quotas.values();
+        if default_quota
+            .chain(api_key_quotas)
+            .any(|quota| quota.window_secs == 0)
+        {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "Submission quotas must have a non-zero window!".to_string(),
+            ));
+        }
+
+        // Verify that the adaptive broadcast config is consistent
+        let adaptive_broadcast_config = &node_config.mempool.adaptive_broadcast;
+        if adaptive_broadcast_config.enabled {
+            if adaptive_broadcast_config.fast_peer_max_rtt_ms
+                > adaptive_broadcast_config.slow_peer_min_rtt_ms
+            {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "The fast peer RTT must not exceed the slow peer RTT for adaptive broadcasts!"
+                        .to_string(),
+                ));
+            }
+            if adaptive_broadcast_config.window_scaling_factor == 0 {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "The adaptive broadcast window scaling factor must be non-zero!".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -144,6 +307,69 @@ impl ConfigOptimizer for MempoolConfig {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sanitize_submission_quotas() {
+        // Create a node config with a zero window submission quota
+        let mut node_config = NodeConfig::default();
+        node_config
+            .mempool
+            .submission_quotas
+            .api_key_quotas
+            .insert("test_key".into(), SubmissionQuota::new(10, 0));
+
+        // Verify that the config sanitizer fails
+        let error = MempoolConfig::sanitize(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::mainnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Fix the window and verify that the config sanitizer passes
+        node_config
+            .mempool
+            .submission_quotas
+            .api_key_quotas
+            .insert("test_key".into(), SubmissionQuota::new(10, 1));
+        MempoolConfig::sanitize(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::mainnet()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_adaptive_broadcast() {
+        // Create a node config with inconsistent adaptive broadcast RTTs
+        let mut node_config = NodeConfig::default();
+        node_config.mempool.adaptive_broadcast = AdaptiveBroadcastConfig {
+            enabled: true,
+            fast_peer_max_rtt_ms: 1_000,
+            slow_peer_min_rtt_ms: 100,
+            ..Default::default()
+        };
+
+        // Verify that the config sanitizer fails
+        let error = MempoolConfig::sanitize(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::mainnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Fix the RTTs and verify that the config sanitizer passes
+        node_config.mempool.adaptive_broadcast.slow_peer_min_rtt_ms = 1_000;
+        MempoolConfig::sanitize(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::mainnet()),
+        )
+        .unwrap();
+    }
+
     #[test]
     fn test_optimize_vfn_configs() {
         // Create the default VFN config
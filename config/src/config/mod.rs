@@ -9,6 +9,7 @@ mod base_config;
 mod config_optimizer;
 mod config_sanitizer;
 mod consensus_config;
+mod consensus_observer_config;
 mod dag_consensus_config;
 mod dkg_config;
 mod error;
@@ -43,6 +44,7 @@ pub use admin_service_config::*;
 pub use api_config::*;
 pub use base_config::*;
 pub use consensus_config::*;
+pub use consensus_observer_config::*;
 pub use dag_consensus_config::*;
 pub use error::*;
 pub use execution_config::*;
@@ -7,10 +7,10 @@ use crate::{
         dkg_config::DKGConfig, jwk_consensus_config::JWKConsensusConfig,
         netbench_config::NetbenchConfig, node_config_loader::NodeConfigLoader,
         node_startup_config::NodeStartupConfig, persistable_config::PersistableConfig,
-        utils::RootPath, AdminServiceConfig, ApiConfig, BaseConfig, ConsensusConfig, Error,
-        ExecutionConfig, IndexerConfig, IndexerGrpcConfig, InspectionServiceConfig, LoggerConfig,
-        MempoolConfig, NetworkConfig, PeerMonitoringServiceConfig, SafetyRulesTestConfig,
-        StateSyncConfig, StorageConfig,
+        utils::RootPath, AdminServiceConfig, ApiConfig, BaseConfig, ConsensusConfig,
+        ConsensusObserverConfig, Error, ExecutionConfig, IndexerConfig, IndexerGrpcConfig,
+        InspectionServiceConfig, LoggerConfig, MempoolConfig, NetworkConfig,
+        PeerMonitoringServiceConfig, SafetyRulesTestConfig, StateSyncConfig, StorageConfig,
     },
     network_id::NetworkId,
 };
@@ -42,6 +42,8 @@ pub struct NodeConfig {
     #[serde(default)]
     pub consensus: ConsensusConfig,
     #[serde(default)]
+    pub consensus_observer: ConsensusObserverConfig,
+    #[serde(default)]
     pub dag_consensus: DagConsensusConfig,
     #[serde(default)]
     pub dkg: DKGConfig,
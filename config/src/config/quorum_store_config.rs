@@ -41,6 +41,37 @@ impl Default for QuorumStoreBackPressureConfig {
     }
 }
 
+/// Load-aware batch creation: validators gossip the number of their batched but uncommitted
+/// txns, and a validator whose queue is much longer than its peers' defers creating new
+/// batches for a bounded time, so that forwarded txns are batched by less loaded validators.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuorumStoreLoadAwareBatchingConfig {
+    pub enabled: bool,
+    pub hint_broadcast_interval_ms: u64,
+    pub hint_expiration_ms: u64,
+    pub min_queue_txns: u64,
+    pub imbalance_factor: f64,
+    pub max_deferral_ms: u64,
+}
+
+impl Default for QuorumStoreLoadAwareBatchingConfig {
+    fn default() -> QuorumStoreLoadAwareBatchingConfig {
+        QuorumStoreLoadAwareBatchingConfig {
+            enabled: false,
+            hint_broadcast_interval_ms: 500,
+            // Hints older than this are ignored, e.g., if the peer went offline
+            hint_expiration_ms: 3000,
+            // Batch creation is never deferred below this queue length
+            min_queue_txns: 1000,
+            // Defer only if the queue is longer than this factor times the median of the peers
+            imbalance_factor: 2.0,
+            // Bounds the latency added to the txns waiting to be batched
+            max_deferral_ms: 500,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct QuorumStoreConfig {
@@ -63,12 +94,23 @@ pub struct QuorumStoreConfig {
     pub batch_request_retry_limit: usize,
     pub batch_request_retry_interval_ms: usize,
     pub batch_request_rpc_timeout_ms: usize,
+    /// The time after which a pending batch prefetch is abandoned (so that it can be retried).
+    pub batch_prefetch_timeout_ms: usize,
     /// Used when setting up the expiration time for the batch initation.
     pub batch_expiry_gap_when_init_usecs: u64,
     pub memory_quota: usize,
     pub db_quota: usize,
     pub batch_quota: usize,
+    /// The max size (in bytes) of the cache of recently persisted batches that exceed the
+    /// memory quota (and would otherwise have to be read back from the db).
+    pub recent_batch_cache_bytes: usize,
+    /// The interval between compactions of the db, which remove the expired batches that were
+    /// left behind in the db. If 0, the db is only cleaned up when the quorum store starts.
+    pub batch_db_compaction_interval_ms: u64,
+    /// The time past their expiration after which batches are removed by the db compaction.
+    pub batch_db_compaction_grace_period_ms: u64,
     pub back_pressure: QuorumStoreBackPressureConfig,
+    pub load_aware_batching: QuorumStoreLoadAwareBatchingConfig,
     pub num_workers_for_remote_batches: usize,
     pub batch_buckets: Vec<u64>,
     pub allow_batches_without_pos_in_proposal: bool,
@@ -100,11 +142,16 @@ impl Default for QuorumStoreConfig {
             batch_request_retry_limit: 10,
             batch_request_retry_interval_ms: 1000,
             batch_request_rpc_timeout_ms: 5000,
+            batch_prefetch_timeout_ms: 10000,
             batch_expiry_gap_when_init_usecs: Duration::from_secs(60).as_micros() as u64,
             memory_quota: 120_000_000,
             db_quota: 300_000_000,
             batch_quota: 300_000,
+            recent_batch_cache_bytes: 50_000_000,
+            batch_db_compaction_interval_ms: 600_000,
+            batch_db_compaction_grace_period_ms: 60_000,
             back_pressure: QuorumStoreBackPressureConfig::default(),
+            load_aware_batching: QuorumStoreLoadAwareBatchingConfig::default(),
             // number of batch coordinators to handle QS batch messages, should be >= 1
             num_workers_for_remote_batches: 10,
             batch_buckets: DEFAULT_BUCKETS.to_vec(),
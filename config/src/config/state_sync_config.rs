@@ -147,10 +147,20 @@ impl Default for StateSyncDriverConfig {
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StorageServiceConfig {
+    /// The config for adaptively compressing responses
+    pub adaptive_compression: AdaptiveCompressionConfig,
+    /// The config for re-verifying responses before they are served
+    pub data_integrity_check: DataIntegrityCheckConfig,
+    /// Whether to send commit notifications to downstream peers
+    pub enable_commit_notifications: bool,
+    /// Maximum number of epoch changes in a single epoch-skipping proof
+    pub max_epoch_change_proof_length: u64,
     /// Maximum number of epoch ending ledger infos per chunk
     pub max_epoch_chunk_size: u64,
     /// Maximum number of invalid requests per peer
     pub max_invalid_requests_per_peer: u64,
+    /// Maximum number of bytes in the lru cache before eviction
+    pub max_lru_cache_bytes: u64,
     /// Maximum number of items in the lru cache before eviction
     pub max_lru_cache_size: u64,
     /// Maximum number of pending network messages
@@ -173,6 +183,8 @@ pub struct StorageServiceConfig {
     pub min_time_to_ignore_peers_secs: u64,
     /// The interval (ms) to refresh the request moderator state
     pub request_moderator_refresh_interval_ms: u64,
+    /// The config for pre-serializing the responses for the newest data
+    pub response_pre_serialization: ResponsePreSerializationConfig,
     /// The interval (ms) to refresh the storage summary
     pub storage_summary_refresh_interval_ms: u64,
 }
@@ -180,8 +192,13 @@ pub struct StorageServiceConfig {
 impl Default for StorageServiceConfig {
     fn default() -> Self {
         Self {
+            adaptive_compression: AdaptiveCompressionConfig::default(),
+            data_integrity_check: DataIntegrityCheckConfig::default(),
+            enable_commit_notifications: true,
+            max_epoch_change_proof_length: 100, // Matches the max epoch ending ledger infos per DB read
             max_epoch_chunk_size: MAX_EPOCH_CHUNK_SIZE,
             max_invalid_requests_per_peer: 500,
+            max_lru_cache_bytes: 512 * 1024 * 1024, // 512 MiB
             max_lru_cache_size: 500, // At ~0.6MiB per chunk, this should take no more than 0.5GiB
             max_network_channel_size: 4000,
             max_network_chunk_bytes: MAX_MESSAGE_SIZE as u64,
@@ -193,11 +210,108 @@ impl Default for StorageServiceConfig {
             max_transaction_output_chunk_size: MAX_TRANSACTION_OUTPUT_CHUNK_SIZE,
             min_time_to_ignore_peers_secs: 300, // 5 minutes
             request_moderator_refresh_interval_ms: 1000, // 1 second
+            response_pre_serialization: ResponsePreSerializationConfig::default(),
             storage_summary_refresh_interval_ms: 100, // Optimal for <= 10 blocks per second
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveCompressionConfig {
+    /// Whether or not to skip compression (for clients that requested it) when the
+    /// measured cost of compressing responses outweighs the bandwidth savings.
+    /// Note: this should only be enabled once all clients accept uncompressed responses.
+    pub enable_adaptive_compression: bool,
+
+    /// Whether or not to compress responses with zstd (for clients that accept it).
+    /// Otherwise, responses are only compressed with LZ4.
+    pub enable_zstd_compression: bool,
+
+    /// The maximum number of concurrent response compressions. If exceeded, the
+    /// server CPU is considered saturated and new responses are not compressed.
+    pub max_concurrent_compressions: u64,
+
+    /// The minimum number of compression samples (per peer and request type)
+    /// required before compression can be skipped.
+    pub min_compression_samples: u64,
+
+    /// The number of uncompressed responses (per peer and request type) after
+    /// which a response is compressed again, to refresh the measurements.
+    pub compression_probe_interval: u64,
+
+    /// The estimated link bandwidth (bytes per second) to peers on the public network
+    pub public_network_bandwidth_bytes_per_sec: u64,
+
+    /// The estimated link bandwidth (bytes per second) to peers on the validator network
+    pub validator_network_bandwidth_bytes_per_sec: u64,
+
+    /// The estimated link bandwidth (bytes per second) to peers on the VFN network.
+    /// VFNs are usually co-located with their validators, so the link is fast.
+    pub vfn_network_bandwidth_bytes_per_sec: u64,
+}
+
+impl Default for AdaptiveCompressionConfig {
+    fn default() -> Self {
+        Self {
+            enable_adaptive_compression: false,
+            enable_zstd_compression: true,
+            max_concurrent_compressions: 16,
+            min_compression_samples: 10,
+            compression_probe_interval: 50,
+            public_network_bandwidth_bytes_per_sec: 10 * 1024 * 1024, // 10 MiB/s
+            validator_network_bandwidth_bytes_per_sec: 100 * 1024 * 1024, // 100 MiB/s
+            vfn_network_bandwidth_bytes_per_sec: 1024 * 1024 * 1024,  // 1 GiB/s
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataIntegrityCheckConfig {
+    /// Whether or not to re-verify the proofs of responses against the latest
+    /// synced ledger info before sending them. This guards against silent local
+    /// DB corruption propagating to syncing peers.
+    pub enable_integrity_checks: bool,
+
+    /// The sampling interval for the checks, i.e., only one in every
+    /// `sampling_interval` verifiable responses is re-verified.
+    pub sampling_interval: u64,
+}
+
+impl Default for DataIntegrityCheckConfig {
+    fn default() -> Self {
+        Self {
+            enable_integrity_checks: false,
+            sampling_interval: 100,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResponsePreSerializationConfig {
+    /// Whether or not to serialize (and compress) the responses for newly committed
+    /// transactions and outputs once, in the background, so that all peers following
+    /// the chain are served the cached bytes (instead of re-serializing per request).
+    /// Note: pre-serialization is skipped if data integrity checks are enabled.
+    pub enable_pre_serialization: bool,
+
+    /// The maximum number of versions (behind the highest synced version) for
+    /// which responses are pre-serialized and tracked. Older responses are no
+    /// longer considered hot, and are left to the LRU cache eviction policy.
+    pub max_hot_versions: u64,
+}
+
+impl Default for ResponsePreSerializationConfig {
+    fn default() -> Self {
+        Self {
+            enable_pre_serialization: false,
+            max_hot_versions: 500,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct DataStreamingServiceConfig {
@@ -310,6 +424,16 @@ impl Default for DynamicPrefetchingConfig {
 pub struct AptosDataPollerConfig {
     /// The additional number of polls to send per peer bucket (per second)
     pub additional_polls_per_peer_bucket: u64,
+    /// Whether or not to poll peers for storage summary updates (i.e., only the
+    /// data that changed since the last known summary) instead of full summaries.
+    pub enable_storage_summary_updates: bool,
+    /// The number of consecutive storage summary updates to poll from a peer before
+    /// polling a full storage summary (to refresh the locally reconstructed summary).
+    pub full_storage_summary_refresh_interval: u64,
+    /// The number of consecutive storage summary updates that can fail (e.g., time out)
+    /// before updates are disabled for the peer. Peers that don't support updates drop
+    /// the requests, so this bounds the number of wasted polls.
+    pub max_storage_summary_update_failures: u64,
     /// The minimum number of polls that should be sent per second
     pub min_polls_per_second: u64,
     /// The maximum number of in-flight polls for priority peers
@@ -328,6 +452,9 @@ impl Default for AptosDataPollerConfig {
     fn default() -> Self {
         Self {
             additional_polls_per_peer_bucket: 1,
+            enable_storage_summary_updates: false,
+            full_storage_summary_refresh_interval: 10,
+            max_storage_summary_update_failures: 3,
             min_polls_per_second: 5,
             max_num_in_flight_priority_polls: 30,
             max_num_in_flight_regular_polls: 30,
@@ -389,13 +516,46 @@ impl Default for AptosLatencyFilteringConfig {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AptosBandwidthSelectionConfig {
+    /// Whether or not to bias data requests towards peers with high observed throughput
+    pub enable_bandwidth_selection: bool,
+    /// The percentage of data requests sent to peers chosen without throughput weights
+    /// (to keep exploring peers whose throughput is unknown or may have improved)
+    pub exploration_percentage: u64,
+    /// Minimum number of throughput samples before a peer is selected by throughput
+    pub min_samples_for_bandwidth_selection: u64,
+    /// The weight (percentage) of each new sample in the throughput and error rate averages
+    pub sample_weight_percentage: u64,
+}
+
+impl Default for AptosBandwidthSelectionConfig {
+    fn default() -> Self {
+        Self {
+            enable_bandwidth_selection: true,
+            exploration_percentage: 10, // Explore on 10% of requests
+            min_samples_for_bandwidth_selection: 3,
+            sample_weight_percentage: 20,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct AptosDataClientConfig {
+    /// The aptos bandwidth selection config for the data client
+    pub bandwidth_selection_config: AptosBandwidthSelectionConfig,
     /// The aptos data poller config for the data client
     pub data_poller_config: AptosDataPollerConfig,
     /// The aptos data multi-fetch config for the data client
     pub data_multi_fetch_config: AptosDataMultiFetchConfig,
+    /// Whether or not to request incremental proofs for consecutive state value chunks
+    pub enable_incremental_state_value_proofs: bool,
+    /// Whether or not to accept zstd compressed data (in addition to LZ4). Zstd
+    /// achieves better compression ratios, but is more expensive to decompress.
+    /// Note: this only applies if `use_compression` is enabled.
+    pub enable_zstd_compression: bool,
     /// The aptos latency filtering config for the data client
     pub latency_filtering_config: AptosLatencyFilteringConfig,
     /// The interval (milliseconds) at which to refresh the latency monitor
@@ -429,8 +589,11 @@ pub struct AptosDataClientConfig {
 impl Default for AptosDataClientConfig {
     fn default() -> Self {
         Self {
+            bandwidth_selection_config: AptosBandwidthSelectionConfig::default(),
             data_poller_config: AptosDataPollerConfig::default(),
             data_multi_fetch_config: AptosDataMultiFetchConfig::default(),
+            enable_incremental_state_value_proofs: false,
+            enable_zstd_compression: false,
             latency_filtering_config: AptosLatencyFilteringConfig::default(),
             latency_monitor_loop_interval_ms: 100,
             max_epoch_chunk_size: MAX_EPOCH_CHUNK_SIZE,
@@ -2,7 +2,7 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::block::Block;
+use crate::{block::Block, common::Round};
 use anyhow::ensure;
 use aptos_crypto::hash::HashValue;
 use aptos_short_hex_str::AsShortHexStr;
@@ -71,6 +71,54 @@ impl fmt::Display for BlockRetrievalRequest {
     }
 }
 
+/// RPC to get a range of chained blocks, starting from the given block id and going back (through
+/// the parents) until the first block with a round less than or equal to the target round. The
+/// blocks in the response are capped by bytes, so large ranges are retrieved in multiple chunks
+/// (each chunk starts from the parent of the last block in the previous chunk).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockRangeRetrievalRequest {
+    block_id: HashValue,
+    target_round: Round,
+    max_response_bytes: u64,
+}
+
+impl BlockRangeRetrievalRequest {
+    pub fn new(block_id: HashValue, target_round: Round, max_response_bytes: u64) -> Self {
+        Self {
+            block_id,
+            target_round,
+            max_response_bytes,
+        }
+    }
+
+    pub fn block_id(&self) -> HashValue {
+        self.block_id
+    }
+
+    pub fn target_round(&self) -> Round {
+        self.target_round
+    }
+
+    pub fn max_response_bytes(&self) -> u64 {
+        self.max_response_bytes
+    }
+
+    /// Returns true iff the given block is the last block in the range
+    pub fn is_target_reached(&self, block: &Block) -> bool {
+        block.round() <= self.target_round
+    }
+}
+
+impl fmt::Display for BlockRangeRetrievalRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[BlockRangeRetrievalRequest starting from id {} to round {}, max bytes {}]",
+            self.block_id, self.target_round, self.max_response_bytes
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum BlockRetrievalStatus {
     // Successfully fill in the request.
@@ -137,6 +185,55 @@ impl BlockRetrievalResponse {
             })
             .map(|_| ())
     }
+
+    /// Verifies the response to a ranged retrieval request. A successful response contains
+    /// a non-empty chain of blocks starting from the requested block id. Only the last block
+    /// may reach the target round, and it must do so iff the status is `SucceededWithTarget`.
+    pub fn verify_range(
+        &self,
+        retrieval_request: &BlockRangeRetrievalRequest,
+        sig_verifier: &ValidatorVerifier,
+    ) -> anyhow::Result<()> {
+        let reached_target = match self.status {
+            BlockRetrievalStatus::Succeeded => false,
+            BlockRetrievalStatus::SucceededWithTarget => true,
+            _ => return Ok(()),
+        };
+        ensure!(
+            !self.blocks.is_empty(),
+            "no blocks returned for status {:?}",
+            self.status
+        );
+        let num_blocks = self.blocks.len();
+        self.blocks
+            .iter()
+            .enumerate()
+            .try_fold(
+                retrieval_request.block_id(),
+                |expected_id, (index, block)| {
+                    block.validate_signature(sig_verifier)?;
+                    block.verify_well_formed()?;
+                    ensure!(
+                        block.id() == expected_id,
+                        "blocks doesn't form a chain: expect {}, get {}",
+                        expected_id,
+                        block.id()
+                    );
+                    let is_last_block = index == num_blocks - 1;
+                    ensure!(
+                        retrieval_request.is_target_reached(block)
+                            == (is_last_block && reached_target),
+                        "unexpected block round {} for target round {} (index {}, status {:?})",
+                        block.round(),
+                        retrieval_request.target_round(),
+                        index,
+                        self.status
+                    );
+                    Ok(block.parent_id())
+                },
+            )
+            .map(|_| ())
+    }
 }
 
 impl fmt::Display for BlockRetrievalResponse {
@@ -2,6 +2,9 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::{block::Block, sync_info::SyncInfo};
+use anyhow::ensure;
+use aptos_types::{epoch_change::EpochChangeProof, validator_verifier::ValidatorVerifier};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -21,3 +24,112 @@ impl fmt::Display for EpochRetrievalRequest {
         )
     }
 }
+
+/// Request to get an EpochStarterKit (i.e., everything required to join the
+/// latest epoch of the responder), sent by nodes without consensus data.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EpochStarterKitRequest {
+    /// The current epoch of the requester
+    pub epoch: u64,
+    /// The maximum number of recent blocks to return
+    pub max_num_blocks: u64,
+}
+
+impl fmt::Display for EpochStarterKitRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "EpochStarterKitRequest: epoch {}, max_num_blocks {}",
+            self.epoch, self.max_num_blocks
+        )
+    }
+}
+
+/// A bundle that allows a node to join the latest epoch of the responder with a single
+/// round trip: the epoch change proof (if the requester is in an older epoch), the
+/// latest sync info (i.e., the latest commit cert), and the recent blocks required to
+/// fast forward sync to the sync info.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EpochStarterKit {
+    /// The epoch change proof from the requested epoch (if the responder is in a later epoch)
+    epoch_change_proof: Option<EpochChangeProof>,
+    /// The sync info of the responder (in the latest epoch)
+    sync_info: SyncInfo,
+    /// The chain of blocks from the highest ordered block back to the highest committed block.
+    /// The chain may be incomplete (e.g., if it exceeds the requested number of blocks).
+    blocks: Vec<Block>,
+}
+
+impl EpochStarterKit {
+    pub fn new(
+        epoch_change_proof: Option<EpochChangeProof>,
+        sync_info: SyncInfo,
+        blocks: Vec<Block>,
+    ) -> Self {
+        Self {
+            epoch_change_proof,
+            sync_info,
+            blocks,
+        }
+    }
+
+    /// Returns the epoch of the sync info and blocks
+    pub fn epoch(&self) -> u64 {
+        self.sync_info.epoch()
+    }
+
+    pub fn epoch_change_proof(&self) -> Option<&EpochChangeProof> {
+        self.epoch_change_proof.as_ref()
+    }
+
+    pub fn sync_info(&self) -> &SyncInfo {
+        &self.sync_info
+    }
+
+    pub fn blocks(&self) -> &Vec<Block> {
+        &self.blocks
+    }
+
+    pub fn into_parts(self) -> (SyncInfo, Vec<Block>) {
+        (self.sync_info, self.blocks)
+    }
+
+    /// Verifies that the blocks are valid and form a chain (starting from the block certified
+    /// by the highest ordered cert). Note: the sync info and the epoch change proof are
+    /// verified separately (i.e., against the epoch state they belong to).
+    pub fn verify_blocks(&self, sig_verifier: &ValidatorVerifier) -> anyhow::Result<()> {
+        let highest_ordered_block_id = self.sync_info.highest_ordered_cert().certified_block().id();
+        self.blocks
+            .iter()
+            .try_fold(highest_ordered_block_id, |expected_id, block| {
+                ensure!(
+                    block.epoch() == self.epoch(),
+                    "block is in a different epoch: expect {}, get {}",
+                    self.epoch(),
+                    block.epoch()
+                );
+                block.validate_signature(sig_verifier)?;
+                block.verify_well_formed()?;
+                ensure!(
+                    block.id() == expected_id,
+                    "blocks doesn't form a chain: expect {}, get {}",
+                    expected_id,
+                    block.id()
+                );
+                Ok(block.parent_id())
+            })
+            .map(|_| ())
+    }
+}
+
+impl fmt::Display for EpochStarterKit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "EpochStarterKit: epoch {}, has_epoch_change_proof {}, num_blocks {}",
+            self.epoch(),
+            self.epoch_change_proof.is_some(),
+            self.blocks.len()
+        )
+    }
+}
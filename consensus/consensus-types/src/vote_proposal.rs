@@ -57,6 +57,10 @@ impl VoteProposal {
         self.next_epoch_state.as_ref()
     }
 
+    pub fn decoupled_execution(&self) -> bool {
+        self.decoupled_execution
+    }
+
     /// This function returns the vote data with a dummy executed_state_id and version
     fn vote_data_ordering_only(&self) -> VoteData {
         VoteData::new(
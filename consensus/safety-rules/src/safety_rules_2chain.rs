@@ -10,7 +10,11 @@ use aptos_consensus_types::{
     vote::Vote,
     vote_proposal::VoteProposal,
 };
-use aptos_crypto::{bls12381, hash::CryptoHash, HashValue};
+use aptos_crypto::{
+    bls12381,
+    hash::{CryptoHash, ACCUMULATOR_PLACEHOLDER_HASH},
+    HashValue,
+};
 use aptos_types::{block_info::BlockInfo, ledger_info::LedgerInfo};
 
 /// 2-chain safety rules implementation
@@ -81,6 +85,9 @@ impl SafetyRules {
         // Construct and sign vote
         let author = self.signer()?.author();
         let ledger_info = self.construct_ledger_info_2chain(proposed_block, vote_data.hash())?;
+        if vote_proposal.decoupled_execution() {
+            Self::verify_order_only_ledger_info(&ledger_info)?;
+        }
         let signature = self.sign(&ledger_info)?;
         let vote = Vote::new_with_signature(vote_data, author, ledger_info, signature);
 
@@ -150,6 +157,24 @@ impl SafetyRules {
     /// prefixes can be committed if there exist certified block B1 that satisfy:
     /// 1) B0 <- B1 <--
     /// 2) round(B0) + 1 = round(B1)
+    /// With decoupled execution, votes only certify the ordering of the blocks, and a validator
+    /// may vote before executing them (e.g., in order-only mode, while it lags behind in
+    /// execution). Thus, the votes must not commit to any execution results. The only exception
+    /// is the genesis block of the epoch, which carries the (already committed) execution
+    /// results of the previous epoch.
+    fn verify_order_only_ledger_info(ledger_info: &LedgerInfo) -> Result<(), Error> {
+        let commit_info = ledger_info.commit_info();
+        if commit_info.is_empty()
+            || commit_info.round() == 0
+            || (commit_info.executed_state_id() == *ACCUMULATOR_PLACEHOLDER_HASH
+                && commit_info.version() == 0)
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidOrderedLedgerInfo(ledger_info.to_string()))
+        }
+    }
+
     fn construct_ledger_info_2chain(
         &self,
         proposed_block: &Block,
@@ -69,6 +69,7 @@ pub fn run_test_suite(safety_rules: &Callback) {
     test_2chain_timeout(safety_rules);
     test_sign_commit_vote(safety_rules);
     test_bad_execution_output(safety_rules);
+    test_order_only_vote(safety_rules);
 }
 
 fn test_bad_execution_output(safety_rules: &Callback) {
@@ -123,6 +124,54 @@ fn test_bad_execution_output(safety_rules: &Callback) {
     a3_block.unwrap();
 }
 
+fn test_order_only_vote(safety_rules: &Callback) {
+    // build two chains of the following form:
+    //
+    // genesis---a1--a2--a3
+    //        \
+    //         --b1--b2--b3
+    //
+    // a1 carries actual execution results, so the vote for a3 (which commits a2 and
+    // thereby the execution results) is rejected when voting on ordering only. The vote
+    // for b3 (which only commits ordering) succeeds.
+    let (mut safety_rules, signer) = safety_rules();
+
+    let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+    let round = genesis_qc.certified_block().round();
+    safety_rules.initialize(&proof).unwrap();
+
+    let executed_proof = Proof::new(vec![], 0, vec![HashValue::random()]);
+    let a1 =
+        make_proposal_with_qc_and_proof(round + 1, executed_proof, genesis_qc.clone(), &signer);
+    let a2 = make_proposal_with_parent(round + 2, &a1, None, &signer);
+    let a3 = make_proposal_with_parent(round + 3, &a2, None, &signer);
+    let order_only_a3 = VoteProposal::new(
+        a3.accumulator_extension_proof().clone(),
+        a3.block().clone(),
+        None,
+        true,
+    );
+    assert!(matches!(
+        safety_rules
+            .construct_and_sign_vote_two_chain(&order_only_a3, None)
+            .unwrap_err(),
+        Error::InvalidOrderedLedgerInfo(_)
+    ));
+
+    let b1 = test_utils::make_proposal_with_qc(round + 1, genesis_qc, &signer);
+    let b2 = make_proposal_with_parent(round + 2, &b1, None, &signer);
+    let b3 = make_proposal_with_parent(round + 3, &b2, None, &signer);
+    let order_only_b3 = VoteProposal::new(
+        b3.accumulator_extension_proof().clone(),
+        b3.block().clone(),
+        None,
+        true,
+    );
+    safety_rules
+        .construct_and_sign_vote_two_chain(&order_only_b3, None)
+        .unwrap();
+}
+
 fn test_end_to_end(safety_rules: &Callback) {
     let (mut safety_rules, signer) = safety_rules();
 
@@ -219,6 +219,11 @@ impl BlockStore {
         block_store
     }
 
+    /// Speculatively execute a verified proposal before it gathers a QC (if enabled)
+    pub async fn speculatively_execute(&self, block: &Block) {
+        self.execution_client.speculatively_execute(block).await;
+    }
+
     /// Send an ordered block id with the proof for execution, returns () on success or error
     pub async fn send_for_execution(&self, finality_proof: QuorumCert) -> anyhow::Result<()> {
         let block_id_to_commit = finality_proof.commit_info().id();
@@ -448,6 +453,11 @@ impl BlockStore {
         ordered_round > self.vote_back_pressure_limit + commit_round
     }
 
+    /// Returns the number of rounds by which the commit root lags behind the ordered root
+    pub fn execution_lag(&self) -> Round {
+        self.ordered_root().round() - self.commit_root().round()
+    }
+
     pub fn pipeline_pending_latency(&self, proposal_timestamp: Duration) -> Duration {
         let ordered_root = self.ordered_root();
         let commit_root = self.commit_root();
@@ -4,36 +4,39 @@
 
 use crate::{
     block_storage::{BlockReader, BlockStore},
+    counters,
     epoch_manager::LivenessStorageData,
     logging::{LogEvent, LogSchema},
     monitor,
-    network::{IncomingBlockRetrievalRequest, NetworkSender},
+    network::{IncomingBlockRangeRetrievalRequest, IncomingBlockRetrievalRequest, NetworkSender},
     network_interface::ConsensusMsg,
     payload_manager::PayloadManager,
     persistent_liveness_storage::{LedgerRecoveryData, PersistentLivenessStorage, RecoveryData},
     pipeline::execution_client::TExecutionClient,
 };
-use anyhow::{bail, Context};
+use anyhow::{bail, ensure, Context};
 use aptos_consensus_types::{
     block::Block,
     block_retrieval::{
-        BlockRetrievalRequest, BlockRetrievalResponse, BlockRetrievalStatus, NUM_PEERS_PER_RETRY,
-        NUM_RETRIES, RETRY_INTERVAL_MSEC, RPC_TIMEOUT_MSEC,
+        BlockRangeRetrievalRequest, BlockRetrievalRequest, BlockRetrievalResponse,
+        BlockRetrievalStatus, NUM_PEERS_PER_RETRY, NUM_RETRIES, RETRY_INTERVAL_MSEC,
+        RPC_TIMEOUT_MSEC,
     },
     common::Author,
+    epoch_retrieval::EpochStarterKit,
     quorum_cert::QuorumCert,
     sync_info::SyncInfo,
 };
 use aptos_crypto::HashValue;
 use aptos_logger::prelude::*;
 use aptos_types::{
-    account_address::AccountAddress, epoch_change::EpochChangeProof,
+    account_address::AccountAddress, block_info::BlockInfo, epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
 };
 use fail::fail_point;
 use futures::{stream::FuturesUnordered, StreamExt};
 use rand::{prelude::*, Rng};
-use std::{clone::Clone, cmp::min, sync::Arc, time::Duration};
+use std::{clone::Clone, cmp::min, collections::HashMap, fmt, sync::Arc, time::Duration};
 use tokio::time;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -148,7 +151,7 @@ impl BlockStore {
                 break;
             }
             let mut blocks = retriever
-                .retrieve_block_for_qc(&retrieve_qc, 1, retrieve_qc.certified_block().id())
+                .retrieve_block_for_qc(&retrieve_qc, 1, retrieve_qc.certified_block())
                 .await?;
             // retrieve_block_for_qc guarantees that blocks has exactly 1 element
             let block = blocks.remove(0);
@@ -237,7 +240,7 @@ impl BlockStore {
             .retrieve_block_for_qc(
                 highest_ordered_cert,
                 num_blocks,
-                highest_commit_cert.commit_info().id(),
+                highest_commit_cert.commit_info(),
             )
             .await?;
 
@@ -277,7 +280,7 @@ impl BlockStore {
                 .retrieve_block_for_qc(
                     highest_commit_cert,
                     1,
-                    highest_commit_cert.certified_block().id(),
+                    highest_commit_cert.certified_block(),
                 )
                 .await?;
 
@@ -400,6 +403,115 @@ impl BlockStore {
             .send(Ok(response_bytes.into()))
             .map_err(|_| anyhow::anyhow!("Failed to send block retrieval response"))
     }
+
+    /// Creates an epoch starter kit with the current sync info and the chain of blocks from the
+    /// highest ordered block back to the highest committed block (i.e., the blocks required to
+    /// fast forward sync to the sync info). The chain is truncated at max_num_blocks blocks.
+    pub fn create_epoch_starter_kit(
+        &self,
+        epoch_change_proof: Option<EpochChangeProof>,
+        max_num_blocks: u64,
+    ) -> EpochStarterKit {
+        let sync_info = self.sync_info();
+        let commit_block_id = sync_info.highest_commit_cert().commit_info().id();
+        let mut blocks = vec![];
+        let mut id = sync_info.highest_ordered_cert().certified_block().id();
+        while (blocks.len() as u64) < max_num_blocks {
+            // Note: the genesis block of the epoch is never sent to peers
+            let Some(executed_block) = self.get_block(id) else {
+                break;
+            };
+            if executed_block.block().is_genesis_block() {
+                break;
+            }
+            blocks.push(executed_block.block().clone());
+            if id == commit_block_id {
+                break;
+            }
+            id = executed_block.parent_id();
+        }
+        EpochStarterKit::new(epoch_change_proof, sync_info, blocks)
+    }
+
+    /// Retrieve the chained blocks from the block store starting from the requested id and
+    /// going back until the target round (inclusive). The response is capped by the given
+    /// number of bytes (and the requested max bytes), but always contains at least one block
+    /// (if found). If the cap is reached, the requester continues from the last block's parent.
+    pub async fn process_block_range_retrieval(
+        &self,
+        request: IncomingBlockRangeRetrievalRequest,
+        max_response_bytes: u64,
+    ) -> anyhow::Result<()> {
+        fail_point!("consensus::process_block_range_retrieval", |_| {
+            Err(anyhow::anyhow!(
+                "Injected error in process_block_range_retrieval"
+            ))
+        });
+        let max_response_bytes = min(max_response_bytes, request.req.max_response_bytes());
+        let mut blocks = vec![];
+        let mut total_block_bytes = 0;
+        let mut status = BlockRetrievalStatus::Succeeded;
+        let mut id = request.req.block_id();
+        loop {
+            let Some(executed_block) = self.get_block(id) else {
+                status = BlockRetrievalStatus::NotEnoughBlocks;
+                break;
+            };
+            let block = executed_block.block();
+            let block_bytes = bcs::serialized_size(block)? as u64;
+            if !blocks.is_empty() && total_block_bytes + block_bytes > max_response_bytes {
+                break;
+            }
+            blocks.push(block.clone());
+            total_block_bytes += block_bytes;
+            if request.req.is_target_reached(block) {
+                status = BlockRetrievalStatus::SucceededWithTarget;
+                break;
+            }
+            id = executed_block.parent_id();
+        }
+
+        if blocks.is_empty() {
+            status = BlockRetrievalStatus::IdNotFound;
+        }
+        counters::BLOCK_RANGE_RETRIEVAL_RESPONSE_BLOCKS.observe(blocks.len() as f64);
+
+        let response = Box::new(BlockRetrievalResponse::new(status, blocks));
+        let response_bytes = request
+            .protocol
+            .to_bytes(&ConsensusMsg::BlockRetrievalResponse(response))?;
+        request
+            .response_sender
+            .send(Ok(response_bytes.into()))
+            .map_err(|_| anyhow::anyhow!("Failed to send block range retrieval response"))
+    }
+}
+
+/// The request used to retrieve a chunk of blocks from peers
+#[derive(Clone)]
+enum ChunkRetrievalRequest {
+    // A chain of a fixed number of blocks (or until the target block)
+    Chain(BlockRetrievalRequest),
+    // A range of blocks down to the target round (capped by bytes)
+    Range(BlockRangeRetrievalRequest),
+}
+
+impl ChunkRetrievalRequest {
+    fn block_id(&self) -> HashValue {
+        match self {
+            ChunkRetrievalRequest::Chain(request) => request.block_id(),
+            ChunkRetrievalRequest::Range(request) => request.block_id(),
+        }
+    }
+}
+
+impl fmt::Display for ChunkRetrievalRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChunkRetrievalRequest::Chain(request) => write!(f, "{}", request),
+            ChunkRetrievalRequest::Range(request) => write!(f, "{}", request),
+        }
+    }
 }
 
 /// BlockRetriever is used internally to retrieve blocks
@@ -408,6 +520,10 @@ pub struct BlockRetriever {
     preferred_peer: Author,
     validator_addresses: Vec<AccountAddress>,
     max_blocks_to_request: u64,
+    // The max bytes of each ranged retrieval response (if ranged retrieval is enabled)
+    max_range_response_bytes: Option<u64>,
+    // Verified blocks that are already available locally (e.g., from an epoch starter kit)
+    prefetched_blocks: HashMap<HashValue, Block>,
 }
 
 impl BlockRetriever {
@@ -416,22 +532,69 @@ impl BlockRetriever {
         preferred_peer: Author,
         validator_addresses: Vec<AccountAddress>,
         max_blocks_to_request: u64,
+        max_range_response_bytes: Option<u64>,
     ) -> Self {
         Self {
             network,
             preferred_peer,
             validator_addresses,
             max_blocks_to_request,
+            max_range_response_bytes,
+            prefetched_blocks: HashMap::new(),
         }
     }
 
+    /// Adds the given (verified) blocks to the retriever. Chains of blocks that are
+    /// fully prefetched are returned without requesting them from peers.
+    pub fn with_prefetched_blocks(mut self, blocks: Vec<Block>) -> Self {
+        self.prefetched_blocks
+            .extend(blocks.into_iter().map(|block| (block.id(), block)));
+        self
+    }
+
+    /// Returns the chain of (at most n) prefetched blocks for the given block_id, down to the
+    /// target block. Returns None if the chain is not fully prefetched.
+    fn retrieve_prefetched_blocks(
+        &self,
+        block_id: HashValue,
+        target_block_id: HashValue,
+        num_blocks: u64,
+    ) -> Option<Vec<Block>> {
+        let mut blocks = vec![];
+        let mut id = block_id;
+        while (blocks.len() as u64) < num_blocks {
+            let block = self.prefetched_blocks.get(&id)?;
+            blocks.push(block.clone());
+            if id == target_block_id {
+                return Some(blocks);
+            }
+            id = block.parent_id();
+        }
+        None
+    }
+
     async fn retrieve_block_for_id_chunk(
         &mut self,
         block_id: HashValue,
         target_block_id: HashValue,
         retrieve_batch_size: u64,
+        peers: Vec<AccountAddress>,
+    ) -> anyhow::Result<BlockRetrievalResponse> {
+        let request = BlockRetrievalRequest::new_with_target_block_id(
+            block_id,
+            retrieve_batch_size,
+            target_block_id,
+        );
+        self.retrieve_chunk(ChunkRetrievalRequest::Chain(request), peers)
+            .await
+    }
+
+    async fn retrieve_chunk(
+        &mut self,
+        request: ChunkRetrievalRequest,
         mut peers: Vec<AccountAddress>,
     ) -> anyhow::Result<BlockRetrievalResponse> {
+        let block_id = request.block_id();
         let mut failed_attempt = 0_u32;
         let mut cur_retry = 0;
 
@@ -443,11 +606,6 @@ impl BlockRetriever {
         monitor!("retrieve_block_for_id_chunk", {
             let mut interval = time::interval(retry_interval);
             let mut futures = FuturesUnordered::new();
-            let request = BlockRetrievalRequest::new_with_target_block_id(
-                block_id,
-                retrieve_batch_size,
-                target_block_id,
-            );
             loop {
                 tokio::select! {
                     _ = interval.tick() => {
@@ -472,18 +630,27 @@ impl BlockRetriever {
                             debug!(
                                 LogSchema::new(LogEvent::RetrieveBlock).remote_peer(peer),
                                 block_id = block_id,
-                                "Fetching {} blocks, retry {}, failed attempts {}",
-                                retrieve_batch_size,
+                                "Fetching {}, retry {}, failed attempts {}",
+                                request,
                                 cur_retry,
                                 failed_attempt
                             );
                             let remote_peer = peer;
-                            let future = self.network.request_block(
-                                request.clone(),
-                                peer,
-                                rpc_timeout,
-                            );
-                            futures.push(async move { (remote_peer, future.await) });
+                            let network = self.network.clone();
+                            let request = request.clone();
+                            futures.push(async move {
+                                let response = match request {
+                                    ChunkRetrievalRequest::Chain(request) => {
+                                        network.request_block(request, peer, rpc_timeout).await
+                                    },
+                                    ChunkRetrievalRequest::Range(request) => {
+                                        network
+                                            .request_block_range(request, peer, rpc_timeout)
+                                            .await
+                                    },
+                                };
+                                (remote_peer, response)
+                            });
                         }
                     }
                     Some((peer, response)) = futures.next() => {
@@ -517,14 +684,44 @@ impl BlockRetriever {
     async fn retrieve_block_for_id(
         &mut self,
         block_id: HashValue,
-        target_block_id: HashValue,
+        target_block: &BlockInfo,
         peers: Vec<AccountAddress>,
         num_blocks: u64,
     ) -> anyhow::Result<Vec<Block>> {
+        let target_block_id = target_block.id();
         info!(
             "Retrieving {} blocks starting from {}",
             num_blocks, block_id
         );
+        if let Some(blocks) = self.retrieve_prefetched_blocks(block_id, target_block_id, num_blocks)
+        {
+            info!(
+                "Retrieved {} prefetched blocks starting from {}",
+                blocks.len(),
+                block_id
+            );
+            return Ok(blocks);
+        }
+        if let Some(max_response_bytes) = self.max_range_response_bytes {
+            // Only use ranged requests if the blocks don't fit into a single chunk
+            // (and ranged requests are enabled in the current epoch).
+            if num_blocks > self.max_blocks_to_request
+                && self
+                    .network
+                    .message_gating()
+                    .is_block_range_retrieval_enabled()
+            {
+                return self
+                    .retrieve_block_range_for_id(
+                        block_id,
+                        target_block,
+                        peers,
+                        num_blocks,
+                        max_response_bytes,
+                    )
+                    .await;
+            }
+        }
         let mut progress = 0;
         let mut last_block_id = block_id;
         let mut result_blocks: Vec<Block> = vec![];
@@ -578,21 +775,95 @@ impl BlockRetriever {
         Ok(result_blocks)
     }
 
+    /// Retrieve the chain of (at most n) blocks for the given block_id, down to the target block,
+    /// using ranged requests. Each response contains as many blocks as fit into the byte limit,
+    /// so the number of requests depends on the size of the blocks (instead of their number).
+    async fn retrieve_block_range_for_id(
+        &mut self,
+        block_id: HashValue,
+        target_block: &BlockInfo,
+        peers: Vec<AccountAddress>,
+        num_blocks: u64,
+        max_response_bytes: u64,
+    ) -> anyhow::Result<Vec<Block>> {
+        let mut last_block_id = block_id;
+        let mut result_blocks: Vec<Block> = vec![];
+        if peers.is_empty() {
+            bail!("Failed to fetch block {}: no peers available", block_id);
+        }
+        loop {
+            info!(
+                "Retrieving range chunk: blocks starting from {} to round {}, original start {}",
+                last_block_id,
+                target_block.round(),
+                block_id
+            );
+
+            let request = BlockRangeRetrievalRequest::new(
+                last_block_id,
+                target_block.round(),
+                max_response_bytes,
+            );
+            let response = self
+                .retrieve_chunk(ChunkRetrievalRequest::Range(request), peers.clone())
+                .await;
+            match response {
+                Ok(result) if matches!(result.status(), BlockRetrievalStatus::Succeeded) => {
+                    // extend the result blocks and continue from the parent of the last block
+                    let batch = result.blocks().clone();
+                    last_block_id = batch.last().unwrap().parent_id();
+                    result_blocks.extend(batch);
+                },
+                Ok(result)
+                    if matches!(result.status(), BlockRetrievalStatus::SucceededWithTarget) =>
+                {
+                    // if we reached the target round, end the loop
+                    let batch = result.blocks().clone();
+                    result_blocks.extend(batch);
+                    break;
+                },
+                _e => {
+                    bail!(
+                        "Failed to fetch block range from {}, for original start {}",
+                        last_block_id,
+                        block_id,
+                    );
+                },
+            }
+            if result_blocks.len() as u64 >= num_blocks {
+                bail!(
+                    "Failed to reach round {} within {} blocks, for original start {}",
+                    target_block.round(),
+                    num_blocks,
+                    block_id,
+                );
+            }
+        }
+        ensure!(
+            result_blocks.len() as u64 <= num_blocks,
+            "Retrieved {} blocks, but expected at most {}",
+            result_blocks.len(),
+            num_blocks
+        );
+        ensure!(
+            result_blocks.last().unwrap().id() == target_block.id(),
+            "The retrieved range ends at block {}, but expected the target block {}",
+            result_blocks.last().unwrap().id(),
+            target_block.id()
+        );
+        Ok(result_blocks)
+    }
+
     /// Retrieve chain of n blocks for given QC
     async fn retrieve_block_for_qc<'a>(
         &'a mut self,
         qc: &'a QuorumCert,
         num_blocks: u64,
-        target_block_id: HashValue,
+        target_block: &'a BlockInfo,
     ) -> anyhow::Result<Vec<Block>> {
         let peers = qc.ledger_info().get_voters(&self.validator_addresses);
-        self.retrieve_block_for_id(
-            qc.certified_block().id(),
-            target_block_id,
-            peers,
-            num_blocks,
-        )
-        .await
+        self.retrieve_block_for_id(qc.certified_block().id(), target_block, peers, num_blocks)
+            .await
     }
 
     fn pick_peer(&self, first_atempt: bool, peers: &mut Vec<AccountAddress>) -> AccountAddress {
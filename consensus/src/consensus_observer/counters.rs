@@ -0,0 +1,97 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_metrics_core::{
+    register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+};
+use once_cell::sync::Lazy;
+
+// Useful observer labels
+pub const ACCEPTED_LABEL: &str = "accepted";
+pub const FUTURE_EPOCH_LABEL: &str = "future_epoch";
+pub const INVALID_PROOF_LABEL: &str = "invalid_proof";
+pub const REJECTED_LABEL: &str = "rejected";
+pub const STALE_LABEL: &str = "stale";
+pub const UNEXPECTED_PEER_LABEL: &str = "unexpected_peer";
+
+pub const EXECUTION_FAILURE_LABEL: &str = "execution_failure";
+pub const EXECUTION_MISMATCH_LABEL: &str = "execution_mismatch";
+pub const MISSING_BLOCKS_LABEL: &str = "missing_blocks";
+pub const OBSERVATION_GAP_LABEL: &str = "observation_gap";
+
+pub const SYNC_SUCCESS_LABEL: &str = "success";
+pub const SYNC_FAILURE_LABEL: &str = "failure";
+
+/// Counter for the messages received by the consensus observer (by type and result)
+pub static OBSERVER_RECEIVED_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_observer_received_messages",
+        "Counters for the messages received by the consensus observer",
+        &["message_type", "result"]
+    )
+    .unwrap()
+});
+
+/// Counter for the messages sent by the consensus observer and publisher (by type)
+pub static OBSERVER_SENT_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_observer_sent_messages",
+        "Counters for the messages sent by the consensus observer and publisher",
+        &["message_type"]
+    )
+    .unwrap()
+});
+
+/// Counter for the state sync requests issued by the consensus observer (by result)
+pub static OBSERVER_SYNC_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_observer_sync_requests",
+        "Counters for the sync requests issued by the consensus observer",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Counter for the times the observer fell back to state sync (by reason)
+pub static OBSERVER_FALLBACKS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_observer_fallbacks",
+        "Counters for the times the consensus observer fell back to state sync",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+/// Counter for the blocks executed and committed by the consensus observer
+pub static OBSERVER_COMMITTED_BLOCKS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_consensus_observer_committed_blocks",
+        "Counter for the blocks executed and committed by the consensus observer"
+    )
+    .unwrap()
+});
+
+/// Gauge for the highest verified rounds (ordered and committed) seen by the observer
+pub static OBSERVER_HIGHEST_ROUNDS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_consensus_observer_highest_rounds",
+        "The highest ordered and committed rounds verified by the consensus observer",
+        &["round_type"]
+    )
+    .unwrap()
+});
+
+/// Gauge for the number of subscribers of the consensus publisher
+pub static PUBLISHER_NUM_SUBSCRIBERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_consensus_publisher_num_subscribers",
+        "The number of active subscribers of the consensus publisher"
+    )
+    .unwrap()
+});
+
+/// Increments the given counter with the provided label values
+pub fn increment_counter(counter: &Lazy<IntCounterVec>, labels: &[&str]) {
+    counter.with_label_values(labels).inc();
+}
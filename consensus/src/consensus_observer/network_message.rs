@@ -0,0 +1,95 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_consensus_types::common::Round;
+use aptos_crypto::HashValue;
+use aptos_network::ProtocolId;
+use aptos_types::{
+    block_executor::config::BlockExecutorConfigFromOnchain, ledger_info::LedgerInfoWithSignatures,
+    transaction::Transaction,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Supported protocols in preferred order (from highest priority to lowest).
+pub const DIRECT_SEND: &[ProtocolId] = &[ProtocolId::ConsensusObserver];
+
+/// Supported protocols in preferred order (from highest priority to lowest).
+pub const RPC: &[ProtocolId] = &[];
+
+/// The messages exchanged between consensus publishers and observers
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ConsensusObserverMessage {
+    /// Subscribes the sender to the consensus updates of the receiver
+    Subscribe,
+    /// Unsubscribes the sender from the consensus updates of the receiver
+    Unsubscribe,
+    /// Notifies the subscriber that the blocks up to the proof were ordered
+    /// (i.e., that the payloads of the blocks can be executed).
+    OrderedBlock {
+        ordered_proof: LedgerInfoWithSignatures,
+    },
+    /// Notifies the subscriber of the payload of an ordered block (as executed by the publisher)
+    BlockPayload { block_payload: BlockPayload },
+    /// Notifies the subscriber that the blocks up to the proof were committed
+    CommitDecision {
+        commit_proof: LedgerInfoWithSignatures,
+    },
+}
+
+impl ConsensusObserverMessage {
+    /// Returns a summary label for the message
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            ConsensusObserverMessage::Subscribe => "subscribe",
+            ConsensusObserverMessage::Unsubscribe => "unsubscribe",
+            ConsensusObserverMessage::OrderedBlock { .. } => "ordered_block",
+            ConsensusObserverMessage::BlockPayload { .. } => "block_payload",
+            ConsensusObserverMessage::CommitDecision { .. } => "commit_decision",
+        }
+    }
+}
+
+impl Display for ConsensusObserverMessage {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ConsensusObserverMessage::OrderedBlock { ordered_proof } => write!(
+                f,
+                "OrderedBlock [epoch: {}, round: {}, version: {}]",
+                ordered_proof.commit_info().epoch(),
+                ordered_proof.commit_info().round(),
+                ordered_proof.commit_info().version(),
+            ),
+            ConsensusObserverMessage::BlockPayload { block_payload } => write!(
+                f,
+                "BlockPayload [epoch: {}, round: {}, id: {}, transactions: {}]",
+                block_payload.epoch,
+                block_payload.round,
+                block_payload.block_id,
+                block_payload.transactions.len(),
+            ),
+            ConsensusObserverMessage::CommitDecision { commit_proof } => write!(
+                f,
+                "CommitDecision [epoch: {}, round: {}, version: {}]",
+                commit_proof.commit_info().epoch(),
+                commit_proof.commit_info().round(),
+                commit_proof.commit_info().version(),
+            ),
+            message => write!(f, "{}", message.get_label()),
+        }
+    }
+}
+
+/// The payload of an ordered block, i.e., the transactions (including the block
+/// metadata and validator transactions) and the config used to execute the block.
+/// The payload is not signed: the execution results of the observer are verified
+/// against the commit decisions before anything is committed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BlockPayload {
+    pub block_id: HashValue,
+    pub parent_block_id: HashValue,
+    pub epoch: u64,
+    pub round: Round,
+    pub transactions: Vec<Transaction>,
+    pub block_executor_onchain_config: BlockExecutorConfigFromOnchain,
+}
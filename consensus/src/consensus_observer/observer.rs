@@ -0,0 +1,789 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::consensus_observer::{
+    counters,
+    network_message::{BlockPayload, ConsensusObserverMessage},
+    publisher::ConsensusPublisher,
+};
+use aptos_config::{
+    config::ConsensusObserverConfig,
+    network_id::{NetworkId, PeerNetworkId},
+};
+use aptos_consensus_notifications::{ConsensusNotificationSender, Error as SyncError};
+use aptos_consensus_types::common::Round;
+use aptos_executor_types::{BlockExecutorTrait, StateComputeResult};
+use aptos_logger::prelude::*;
+use aptos_network::{
+    application::interface::{NetworkClient, NetworkClientInterface, NetworkServiceEvents},
+    protocols::network::Event,
+};
+use aptos_storage_interface::DbReader;
+use aptos_types::{
+    block_executor::partitioner::ExecutableBlock, epoch_change::Verifier, epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::signature_verified_transaction::into_signature_verified_block,
+};
+use futures::{stream::select_all, StreamExt};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// The subscription to the consensus updates of a single upstream peer
+struct ActiveSubscription {
+    peer_network_id: PeerNetworkId,
+    last_commit_time: Instant,
+}
+
+/// A block executed by the observer (but not yet committed)
+struct ExecutedBlock {
+    block_payload: BlockPayload,
+    compute_result: StateComputeResult,
+}
+
+/// The consensus observer follows the consensus updates published by an upstream
+/// peer (e.g., the validator of a VFN), without participating in consensus.
+///
+/// Once the node has caught up (i.e., state sync has satisfied a sync request
+/// to a verified commit decision), the observer takes over from state sync: it
+/// executes the payloads of the verified ordered blocks, and commits the executed
+/// blocks once their results match the (verified) commit decisions. If the results
+/// don't match, or a block is missing, the observer hands the commit decision to
+/// state sync as the sync target instead. If the observation stalls (e.g., the
+/// upstream is slow, or the epoch changed), the observer unsubscribes and the node
+/// keeps syncing through state sync, until the observer can resubscribe.
+///
+/// The observer also handles the subscriptions of the local consensus publisher (if
+/// any), and forwards all verified updates to its own subscribers.
+pub struct ConsensusObserver {
+    config: ConsensusObserverConfig,
+    network_client: NetworkClient<ConsensusObserverMessage>,
+    db_reader: Arc<dyn DbReader>,
+    // The state sync notifier is only set if the observer is enabled
+    state_sync_notifier: Option<Arc<dyn ConsensusNotificationSender>>,
+    consensus_publisher: Option<ConsensusPublisher>,
+    block_executor: Arc<dyn BlockExecutorTrait>,
+
+    epoch_state: Option<EpochState>,
+    active_subscription: Option<ActiveSubscription>,
+    last_failed_peer: Option<PeerNetworkId>,
+    highest_ordered_proof: Option<LedgerInfoWithSignatures>,
+    highest_commit_proof: Option<LedgerInfoWithSignatures>,
+
+    // Whether the observer executes (and commits) blocks, i.e., whether state
+    // sync has handed over control. Otherwise, the node is synced by state sync.
+    executing: bool,
+    // The received block payloads (by epoch and round) that are not yet executed
+    block_payloads: BTreeMap<(u64, Round), BlockPayload>,
+    // The executed blocks that are not yet committed (in execution order)
+    executed_blocks: Vec<ExecutedBlock>,
+
+    // The latest verified commit proof that has not yet been sent to state sync.
+    // Only a single sync request is in flight at a time, so newer commit proofs
+    // simply replace the pending target.
+    pending_sync_target: Option<LedgerInfoWithSignatures>,
+    sync_in_progress: bool,
+    sync_result_sender: UnboundedSender<(LedgerInfoWithSignatures, Result<(), SyncError>)>,
+    sync_result_receiver: UnboundedReceiver<(LedgerInfoWithSignatures, Result<(), SyncError>)>,
+}
+
+impl ConsensusObserver {
+    pub fn new(
+        config: ConsensusObserverConfig,
+        network_client: NetworkClient<ConsensusObserverMessage>,
+        db_reader: Arc<dyn DbReader>,
+        state_sync_notifier: Option<Arc<dyn ConsensusNotificationSender>>,
+        consensus_publisher: Option<ConsensusPublisher>,
+        block_executor: Arc<dyn BlockExecutorTrait>,
+    ) -> Self {
+        let (sync_result_sender, sync_result_receiver) = unbounded_channel();
+        Self {
+            config,
+            network_client,
+            db_reader,
+            state_sync_notifier,
+            consensus_publisher,
+            block_executor,
+            epoch_state: None,
+            active_subscription: None,
+            last_failed_peer: None,
+            highest_ordered_proof: None,
+            highest_commit_proof: None,
+            executing: false,
+            block_payloads: BTreeMap::new(),
+            executed_blocks: vec![],
+            pending_sync_target: None,
+            sync_in_progress: false,
+            sync_result_sender,
+            sync_result_receiver,
+        }
+    }
+
+    /// Returns true iff the observer is enabled (i.e., it follows consensus updates)
+    fn observer_enabled(&self) -> bool {
+        self.state_sync_notifier.is_some()
+    }
+
+    /// Starts the observer loop that handles network events, sync results and progress checks
+    pub async fn start(
+        mut self,
+        network_service_events: NetworkServiceEvents<ConsensusObserverMessage>,
+    ) {
+        // Transform the network events to also include the network id
+        let network_events: Vec<_> = network_service_events
+            .into_network_and_events()
+            .into_iter()
+            .map(|(network_id, events)| events.map(move |event| (network_id, event)))
+            .collect();
+        let mut network_events = select_all(network_events).fuse();
+
+        // Create the progress check interval
+        let mut progress_check_interval = tokio::time::interval(Duration::from_millis(
+            self.config.progress_check_interval_ms,
+        ));
+
+        info!("Starting the consensus observer loop!");
+        loop {
+            tokio::select! {
+                Some((network_id, event)) = network_events.next() => {
+                    self.handle_network_event(network_id, event).await;
+                }
+                Some((sync_target, result)) = self.sync_result_receiver.recv() => {
+                    self.handle_sync_result(sync_target, result).await;
+                }
+                _ = progress_check_interval.tick() => {
+                    self.check_progress().await;
+                }
+                else => {
+                    break; // Exit the consensus observer loop
+                }
+            }
+        }
+        error!("The consensus observer loop exited unexpectedly!");
+    }
+
+    /// Handles a single network event
+    async fn handle_network_event(
+        &mut self,
+        network_id: NetworkId,
+        event: Event<ConsensusObserverMessage>,
+    ) {
+        match event {
+            Event::Message(peer_id, message) => {
+                let peer_network_id = PeerNetworkId::new(network_id, peer_id);
+                self.handle_message(peer_network_id, message).await;
+            },
+            Event::LostPeer(connection_metadata) => {
+                let peer_network_id =
+                    PeerNetworkId::new(network_id, connection_metadata.remote_peer_id);
+                if let Some(consensus_publisher) = &self.consensus_publisher {
+                    consensus_publisher.remove_subscriber(&peer_network_id);
+                }
+                if self.is_subscribed_to(&peer_network_id) {
+                    info!(
+                        "Lost the connection to the consensus observer subscription peer: {}",
+                        peer_network_id
+                    );
+                    self.active_subscription = None;
+                    self.stop_executing().await;
+                }
+            },
+            _ => {}, // Other events are not used by the consensus observer
+        }
+    }
+
+    /// Handles a message received from the given peer
+    async fn handle_message(
+        &mut self,
+        peer_network_id: PeerNetworkId,
+        message: ConsensusObserverMessage,
+    ) {
+        let message_label = message.get_label();
+        let result_label = match message {
+            ConsensusObserverMessage::Subscribe => match &self.consensus_publisher {
+                Some(consensus_publisher) => {
+                    if consensus_publisher.add_subscriber(peer_network_id) {
+                        counters::ACCEPTED_LABEL
+                    } else {
+                        counters::REJECTED_LABEL
+                    }
+                },
+                None => counters::UNEXPECTED_PEER_LABEL,
+            },
+            ConsensusObserverMessage::Unsubscribe => {
+                if let Some(consensus_publisher) = &self.consensus_publisher {
+                    consensus_publisher.remove_subscriber(&peer_network_id);
+                }
+                counters::ACCEPTED_LABEL
+            },
+            ConsensusObserverMessage::OrderedBlock { ordered_proof } => {
+                self.process_ordered_block(peer_network_id, ordered_proof)
+                    .await
+            },
+            ConsensusObserverMessage::BlockPayload { block_payload } => {
+                self.process_block_payload(peer_network_id, block_payload)
+                    .await
+            },
+            ConsensusObserverMessage::CommitDecision { commit_proof } => {
+                self.process_commit_decision(peer_network_id, commit_proof)
+                    .await
+            },
+        };
+        counters::increment_counter(&counters::OBSERVER_RECEIVED_MESSAGES, &[
+            message_label,
+            result_label,
+        ]);
+    }
+
+    /// Processes an ordered block message and returns the result label
+    async fn process_ordered_block(
+        &mut self,
+        peer_network_id: PeerNetworkId,
+        ordered_proof: LedgerInfoWithSignatures,
+    ) -> &'static str {
+        if !self.is_subscribed_to(&peer_network_id) {
+            return counters::UNEXPECTED_PEER_LABEL;
+        }
+        if let Err(label) = self.verify_proof(&ordered_proof) {
+            return label;
+        }
+
+        counters::OBSERVER_HIGHEST_ROUNDS
+            .with_label_values(&["ordered"])
+            .set(ordered_proof.commit_info().round() as i64);
+        self.highest_ordered_proof = Some(ordered_proof.clone());
+        self.publish_message(ConsensusObserverMessage::OrderedBlock { ordered_proof });
+
+        // The payloads of the newly ordered blocks can now be executed
+        self.execute_ordered_blocks().await;
+        counters::ACCEPTED_LABEL
+    }
+
+    /// Processes a block payload message and returns the result label
+    async fn process_block_payload(
+        &mut self,
+        peer_network_id: PeerNetworkId,
+        block_payload: BlockPayload,
+    ) -> &'static str {
+        if !self.is_subscribed_to(&peer_network_id) {
+            return counters::UNEXPECTED_PEER_LABEL;
+        }
+
+        // Ignore payloads of blocks that are already committed
+        let payload_round = (block_payload.epoch, block_payload.round);
+        if let Some(highest_commit_proof) = &self.highest_commit_proof {
+            let highest_commit_info = highest_commit_proof.commit_info();
+            if payload_round <= (highest_commit_info.epoch(), highest_commit_info.round()) {
+                return counters::STALE_LABEL;
+            }
+        }
+
+        // Drop the oldest payload if too many payloads are pending (e.g., while syncing)
+        if self.block_payloads.len() as u64 >= self.config.max_pending_blocks {
+            if let Some((_, dropped_payload)) = self.block_payloads.pop_first() {
+                warn!(
+                    "Dropped the block payload: {}. There are too many pending payloads!",
+                    dropped_payload.block_id
+                );
+            }
+        }
+
+        // Forward the payload and execute it (once the block is ordered)
+        self.block_payloads
+            .insert(payload_round, block_payload.clone());
+        self.publish_message(ConsensusObserverMessage::BlockPayload { block_payload });
+        self.execute_ordered_blocks().await;
+        counters::ACCEPTED_LABEL
+    }
+
+    /// Processes a commit decision message and returns the result label
+    async fn process_commit_decision(
+        &mut self,
+        peer_network_id: PeerNetworkId,
+        commit_proof: LedgerInfoWithSignatures,
+    ) -> &'static str {
+        if !self.is_subscribed_to(&peer_network_id) {
+            return counters::UNEXPECTED_PEER_LABEL;
+        }
+        if let Err(label) = self.verify_proof(&commit_proof) {
+            return label;
+        }
+
+        // Ignore commit decisions that are not newer than the highest seen
+        let commit_info = commit_proof.commit_info();
+        if let Some(highest_commit_proof) = &self.highest_commit_proof {
+            let highest_commit_info = highest_commit_proof.commit_info();
+            if (commit_info.epoch(), commit_info.round())
+                <= (highest_commit_info.epoch(), highest_commit_info.round())
+            {
+                return counters::STALE_LABEL;
+            }
+        }
+
+        // Update the observation progress
+        if let Some(active_subscription) = self.active_subscription.as_mut() {
+            active_subscription.last_commit_time = Instant::now();
+        }
+        counters::OBSERVER_HIGHEST_ROUNDS
+            .with_label_values(&["committed"])
+            .set(commit_info.round() as i64);
+
+        // Forward the commit decision, and commit the executed blocks (if the
+        // observer is executing). Otherwise, sync to the commit decision.
+        self.highest_commit_proof = Some(commit_proof.clone());
+        self.publish_message(ConsensusObserverMessage::CommitDecision {
+            commit_proof: commit_proof.clone(),
+        });
+        if self.executing {
+            self.commit_executed_blocks(commit_proof).await;
+        } else {
+            self.pending_sync_target = Some(commit_proof);
+        }
+        self.sync_to_pending_target();
+        counters::ACCEPTED_LABEL
+    }
+
+    /// Executes the pending block payloads that extend the executed blocks (or the
+    /// committed block), as long as the blocks are ordered (by the highest verified
+    /// ordered proof). Falls back to state sync if the execution fails.
+    async fn execute_ordered_blocks(&mut self) {
+        while self.executing {
+            let highest_ordered_round = match &self.highest_ordered_proof {
+                Some(ordered_proof) => (
+                    ordered_proof.commit_info().epoch(),
+                    ordered_proof.commit_info().round(),
+                ),
+                None => return,
+            };
+
+            // Find the next payload to execute (if it has been ordered)
+            let parent_block_id = match self.executed_blocks.last() {
+                Some(executed_block) => executed_block.block_payload.block_id,
+                None => self.block_executor.committed_block_id(),
+            };
+            let payload_round = match self
+                .block_payloads
+                .iter()
+                .find(|(_, block_payload)| block_payload.parent_block_id == parent_block_id)
+            {
+                Some((payload_round, _)) if *payload_round <= highest_ordered_round => {
+                    *payload_round
+                },
+                _ => return, // The next block is not yet received (or ordered)
+            };
+            let block_payload = self
+                .block_payloads
+                .remove(&payload_round)
+                .expect("The block payload must exist!");
+
+            // Execute the block
+            let block_executor = self.block_executor.clone();
+            let executable_block = ExecutableBlock::from((
+                block_payload.block_id,
+                into_signature_verified_block(block_payload.transactions.clone()),
+            ));
+            let onchain_config = block_payload.block_executor_onchain_config.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                block_executor.execute_block(executable_block, parent_block_id, onchain_config)
+            })
+            .await;
+            match result {
+                Ok(Ok(compute_result)) => self.executed_blocks.push(ExecutedBlock {
+                    block_payload,
+                    compute_result,
+                }),
+                result => {
+                    warn!(
+                        "Failed to execute the block: {}! Falling back to state sync. Result: {:?}",
+                        block_payload.block_id,
+                        result.map(|result| result.map(|_| ()))
+                    );
+                    counters::increment_counter(&counters::OBSERVER_FALLBACKS, &[
+                        counters::EXECUTION_FAILURE_LABEL,
+                    ]);
+                    self.stop_executing().await;
+                },
+            }
+        }
+    }
+
+    /// Commits the executed blocks up to the given commit decision, and notifies
+    /// state sync of the commit. If the block wasn't executed (or its execution
+    /// results don't match), the commit decision becomes the pending sync target.
+    async fn commit_executed_blocks(&mut self, commit_proof: LedgerInfoWithSignatures) {
+        let commit_info = commit_proof.commit_info().clone();
+
+        // Verify the execution results against the commit decision
+        let fallback_label =
+            match self.executed_blocks.iter().position(|executed_block| {
+                executed_block.block_payload.block_id == commit_info.id()
+            }) {
+                Some(index) => {
+                    let compute_result = &self.executed_blocks[index].compute_result;
+                    if compute_result.root_hash() == commit_info.executed_state_id()
+                        && compute_result.version() == commit_info.version()
+                    {
+                        self.commit_blocks(index, commit_proof.clone()).await
+                    } else {
+                        Err(counters::EXECUTION_MISMATCH_LABEL)
+                    }
+                },
+                None => Err(counters::MISSING_BLOCKS_LABEL),
+            };
+
+        // Fall back to state sync (if required)
+        if let Err(fallback_label) = fallback_label {
+            warn!(
+                "Failed to commit the executed blocks for: {}! Falling back to state sync.",
+                commit_info
+            );
+            counters::increment_counter(&counters::OBSERVER_FALLBACKS, &[fallback_label]);
+            self.stop_executing().await;
+            self.pending_sync_target = Some(commit_proof);
+        }
+    }
+
+    /// Commits the executed blocks up to (and including) the given index, and
+    /// notifies state sync of the committed transactions and events.
+    async fn commit_blocks(
+        &mut self,
+        index: usize,
+        commit_proof: LedgerInfoWithSignatures,
+    ) -> Result<(), &'static str> {
+        let committed_blocks: Vec<_> = self.executed_blocks.drain(..=index).collect();
+
+        // Commit the blocks to storage
+        let block_executor = self.block_executor.clone();
+        let block_ids = committed_blocks
+            .iter()
+            .map(|executed_block| executed_block.block_payload.block_id)
+            .collect();
+        let proof = commit_proof.clone();
+        let result =
+            tokio::task::spawn_blocking(move || block_executor.commit_blocks(block_ids, proof))
+                .await;
+        if !matches!(result, Ok(Ok(()))) {
+            error!(
+                "Failed to commit the executed blocks! Result: {:?}",
+                result.map(|result| result.map(|_| ()))
+            );
+            return Err(counters::EXECUTION_FAILURE_LABEL);
+        }
+        counters::OBSERVER_COMMITTED_BLOCKS.inc_by(committed_blocks.len() as u64);
+
+        // Notify state sync of the committed transactions and events
+        let mut transactions = vec![];
+        let mut subscribable_events = vec![];
+        for executed_block in committed_blocks {
+            let compute_result = executed_block.compute_result;
+            if compute_result.has_reconfiguration()
+                && compute_result.compute_status_for_input_txns().is_empty()
+            {
+                continue; // Reconfiguration suffix blocks don't commit any transactions
+            }
+            subscribable_events.extend_from_slice(compute_result.subscribable_events());
+            transactions.extend(compute_result.transactions_to_commit(
+                executed_block.block_payload.transactions,
+                executed_block.block_payload.block_id,
+            ));
+        }
+        if let Some(state_sync_notifier) = &self.state_sync_notifier {
+            if let Err(error) = state_sync_notifier
+                .notify_new_commit(transactions, subscribable_events)
+                .await
+            {
+                error!(
+                    "Failed to notify state sync of the committed blocks! Error: {:?}",
+                    error
+                );
+            }
+        }
+
+        // The epoch state must be updated to verify the proofs of the next epoch. The
+        // executor is also reset, as the next epoch starts from a new (genesis) block.
+        if commit_proof.ledger_info().ends_epoch() {
+            self.refresh_epoch_state();
+            self.block_executor.finish();
+            self.executed_blocks.clear();
+        }
+
+        // Remove the payloads that are now committed
+        let commit_round = (
+            commit_proof.commit_info().epoch(),
+            commit_proof.commit_info().round(),
+        );
+        self.block_payloads = self
+            .block_payloads
+            .split_off(&(commit_round.0, commit_round.1 + 1));
+        Ok(())
+    }
+
+    /// Stops executing blocks (if the observer is executing), and notifies state
+    /// sync that it must sync the node again (e.g., through continuous syncing).
+    async fn stop_executing(&mut self) {
+        if !self.executing {
+            return;
+        }
+        self.executing = false;
+        self.block_executor.finish();
+        self.block_payloads.clear();
+        self.executed_blocks.clear();
+
+        if let Some(state_sync_notifier) = &self.state_sync_notifier {
+            if let Err(error) = state_sync_notifier.notify_observer_fallback().await {
+                error!(
+                    "Failed to notify state sync of the observer fallback! Error: {:?}",
+                    error
+                );
+            }
+        }
+    }
+
+    /// Starts executing blocks, once state sync has handed over control
+    /// (i.e., once state sync satisfied a sync request of the observer).
+    async fn start_executing(&mut self) {
+        if let Err(error) = self.block_executor.reset() {
+            error!("Failed to reset the block executor! Error: {:?}", error);
+            return;
+        }
+        info!("The consensus observer started executing blocks!");
+        self.executing = true;
+        self.executed_blocks.clear();
+
+        // Remove the payloads that were already committed by state sync
+        if let Ok(latest_ledger_info) = self.db_reader.get_latest_ledger_info() {
+            let commit_info = latest_ledger_info.commit_info();
+            self.block_payloads = self
+                .block_payloads
+                .split_off(&(commit_info.epoch(), commit_info.round() + 1));
+        }
+        self.execute_ordered_blocks().await;
+    }
+
+    /// Verifies the given proof against the current epoch state.
+    /// On failure, the result label is returned as the error.
+    fn verify_proof(&mut self, proof: &LedgerInfoWithSignatures) -> Result<(), &'static str> {
+        if self.epoch_state.is_none() {
+            self.refresh_epoch_state();
+        }
+        let epoch_state = self
+            .epoch_state
+            .as_ref()
+            .ok_or(counters::FUTURE_EPOCH_LABEL)?;
+
+        // Proofs for future epochs cannot be verified until state sync reaches the epoch
+        let proof_epoch = proof.commit_info().epoch();
+        if proof_epoch > epoch_state.epoch {
+            return Err(counters::FUTURE_EPOCH_LABEL);
+        } else if proof_epoch < epoch_state.epoch {
+            return Err(counters::STALE_LABEL);
+        }
+
+        epoch_state.verify(proof).map_err(|error| {
+            warn!(
+                "Failed to verify the consensus observer proof! Error: {:?}",
+                error
+            );
+            counters::INVALID_PROOF_LABEL
+        })
+    }
+
+    /// Sends the pending sync target to state sync (if no sync is in flight)
+    fn sync_to_pending_target(&mut self) {
+        if self.sync_in_progress {
+            return; // The pending target will be synced once the current sync completes
+        }
+        let (sync_target, state_sync_notifier) =
+            match (self.pending_sync_target.take(), &self.state_sync_notifier) {
+                (Some(sync_target), Some(state_sync_notifier)) => {
+                    (sync_target, state_sync_notifier.clone())
+                },
+                _ => return,
+            };
+
+        // If storage has already synced past the target, sync to the latest ledger
+        // info instead. The request is satisfied right away, but state sync must
+        // still hand over control (so that the observer can execute blocks).
+        let sync_target = match self.db_reader.get_latest_ledger_info() {
+            Ok(latest_ledger_info)
+                if latest_ledger_info.commit_info().version()
+                    >= sync_target.commit_info().version() =>
+            {
+                latest_ledger_info
+            },
+            _ => sync_target,
+        };
+
+        self.sync_in_progress = true;
+        let sync_result_sender = self.sync_result_sender.clone();
+        tokio::spawn(async move {
+            let result = state_sync_notifier
+                .sync_to_target(sync_target.clone())
+                .await;
+            let _ = sync_result_sender.send((sync_target, result));
+        });
+    }
+
+    /// Handles the result of a sync request sent to state sync
+    async fn handle_sync_result(
+        &mut self,
+        sync_target: LedgerInfoWithSignatures,
+        result: Result<(), SyncError>,
+    ) {
+        self.sync_in_progress = false;
+        let synced = result.is_ok();
+        match result {
+            Ok(()) => {
+                counters::increment_counter(&counters::OBSERVER_SYNC_REQUESTS, &[
+                    counters::SYNC_SUCCESS_LABEL,
+                ]);
+
+                // The epoch state must be updated to verify the proofs of the next epoch
+                if sync_target.ledger_info().ends_epoch() {
+                    self.refresh_epoch_state();
+                }
+            },
+            Err(error) => {
+                warn!(
+                    "Failed to sync to the consensus observer target: {}. Error: {:?}",
+                    sync_target, error
+                );
+                counters::increment_counter(&counters::OBSERVER_SYNC_REQUESTS, &[
+                    counters::SYNC_FAILURE_LABEL,
+                ]);
+            },
+        }
+
+        // Sync to any newer target received in the meantime
+        self.sync_to_pending_target();
+        if self.sync_in_progress || !synced {
+            return;
+        }
+
+        // State sync has handed over control, so start executing blocks (if
+        // the observer is still subscribed). Otherwise, hand control back.
+        if self.active_subscription.is_some() {
+            self.start_executing().await;
+        } else if let Some(state_sync_notifier) = &self.state_sync_notifier {
+            if let Err(error) = state_sync_notifier.notify_observer_fallback().await {
+                error!(
+                    "Failed to notify state sync of the observer fallback! Error: {:?}",
+                    error
+                );
+            }
+        }
+    }
+
+    /// Checks the observation progress, and falls back to state sync if the
+    /// observation has stalled. Also (re)subscribes to a peer if required.
+    async fn check_progress(&mut self) {
+        if !self.observer_enabled() {
+            return;
+        }
+
+        // State sync may have moved to a new epoch without the observer (e.g., after a fallback)
+        self.refresh_epoch_state();
+
+        match &self.active_subscription {
+            Some(active_subscription) => {
+                let max_observation_gap = Duration::from_millis(self.config.max_observation_gap_ms);
+                if active_subscription.last_commit_time.elapsed() > max_observation_gap {
+                    warn!(
+                        "No commits observed from peer {} for {:?}! Falling back to state sync.",
+                        active_subscription.peer_network_id, max_observation_gap
+                    );
+                    counters::increment_counter(&counters::OBSERVER_FALLBACKS, &[
+                        counters::OBSERVATION_GAP_LABEL,
+                    ]);
+                    self.unsubscribe().await;
+                }
+            },
+            None => self.subscribe(),
+        }
+    }
+
+    /// Subscribes to the most suitable upstream peer (preferring the VFN network)
+    fn subscribe(&mut self) {
+        let mut peers = match self.network_client.get_available_peers() {
+            Ok(peers) => peers,
+            Err(error) => {
+                warn!(
+                    "Failed to get the available consensus observer peers! Error: {:?}",
+                    error
+                );
+                return;
+            },
+        };
+        peers.sort_by_key(|peer| (!peer.network_id().is_vfn_network(), *peer));
+
+        // Avoid the last failed peer, unless it is the only option
+        let peer_network_id = match peers
+            .iter()
+            .find(|peer| Some(**peer) != self.last_failed_peer)
+            .or_else(|| peers.first())
+        {
+            Some(peer_network_id) => *peer_network_id,
+            None => return, // There are no peers to subscribe to
+        };
+
+        info!(
+            "Subscribing to consensus updates from peer: {}",
+            peer_network_id
+        );
+        self.send_message(peer_network_id, ConsensusObserverMessage::Subscribe);
+        self.active_subscription = Some(ActiveSubscription {
+            peer_network_id,
+            last_commit_time: Instant::now(),
+        });
+    }
+
+    /// Unsubscribes from the current peer, stops executing blocks and drops any pending
+    /// sync target. Until the next subscription, the node is synced by state sync alone.
+    async fn unsubscribe(&mut self) {
+        if let Some(active_subscription) = self.active_subscription.take() {
+            let peer_network_id = active_subscription.peer_network_id;
+            self.send_message(peer_network_id, ConsensusObserverMessage::Unsubscribe);
+            self.last_failed_peer = Some(peer_network_id);
+        }
+        self.stop_executing().await;
+        self.pending_sync_target = None;
+    }
+
+    /// Returns true iff the observer is subscribed to the given peer
+    fn is_subscribed_to(&self, peer_network_id: &PeerNetworkId) -> bool {
+        self.active_subscription
+            .as_ref()
+            .map_or(false, |subscription| {
+                &subscription.peer_network_id == peer_network_id
+            })
+    }
+
+    /// Refreshes the epoch state using the latest state in storage
+    fn refresh_epoch_state(&mut self) {
+        match self.db_reader.get_latest_epoch_state() {
+            Ok(epoch_state) => self.epoch_state = Some(epoch_state),
+            Err(error) => warn!("Failed to read the latest epoch state! Error: {:?}", error),
+        }
+    }
+
+    /// Forwards the given message to the subscribers of the local publisher (if any)
+    fn publish_message(&self, message: ConsensusObserverMessage) {
+        if let Some(consensus_publisher) = &self.consensus_publisher {
+            consensus_publisher.publish_message(message);
+        }
+    }
+
+    /// Sends the given message to the specified peer
+    fn send_message(&self, peer_network_id: PeerNetworkId, message: ConsensusObserverMessage) {
+        counters::increment_counter(&counters::OBSERVER_SENT_MESSAGES, &[message.get_label()]);
+        if let Err(error) = self.network_client.send_to_peer(message, peer_network_id) {
+            warn!(
+                "Failed to send the consensus observer message to peer {}! Error: {:?}",
+                peer_network_id, error
+            );
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::consensus_observer::{counters, network_message::ConsensusObserverMessage};
+use aptos_config::{config::ConsensusObserverConfig, network_id::PeerNetworkId};
+use aptos_infallible::RwLock;
+use aptos_logger::prelude::*;
+use aptos_network::application::interface::{NetworkClient, NetworkClientInterface};
+use std::{collections::HashSet, sync::Arc};
+
+/// The consensus publisher forwards the ordered blocks (and their payloads) and
+/// commit decisions (seen by the local node) to all subscribed observers.
+#[derive(Clone)]
+pub struct ConsensusPublisher {
+    config: ConsensusObserverConfig,
+    network_client: NetworkClient<ConsensusObserverMessage>,
+    subscribers: Arc<RwLock<HashSet<PeerNetworkId>>>,
+}
+
+impl ConsensusPublisher {
+    pub fn new(
+        config: ConsensusObserverConfig,
+        network_client: NetworkClient<ConsensusObserverMessage>,
+    ) -> Self {
+        Self {
+            config,
+            network_client,
+            subscribers: Arc::new(RwLock::new(HashSet::new())),
+        }
+    }
+
+    /// Adds the given peer to the set of subscribers. Only peers on the VFN network
+    /// (or explicitly allowed peers) may subscribe, up to the maximum number of
+    /// subscribers. Returns true iff the peer is subscribed.
+    pub fn add_subscriber(&self, peer_network_id: PeerNetworkId) -> bool {
+        if !peer_network_id.network_id().is_vfn_network()
+            && !self
+                .config
+                .allowed_subscriber_peers
+                .contains(&peer_network_id.peer_id())
+        {
+            warn!(
+                "Rejected the subscription of a peer that isn't allowed: {}",
+                peer_network_id
+            );
+            return false;
+        }
+
+        let mut subscribers = self.subscribers.write();
+        if !subscribers.contains(&peer_network_id)
+            && subscribers.len() as u64 >= self.config.max_subscribers
+        {
+            warn!(
+                "Rejected the subscription of peer {}. There are too many subscribers!",
+                peer_network_id
+            );
+            return false;
+        }
+        if subscribers.insert(peer_network_id) {
+            info!("New consensus observer subscriber: {}", peer_network_id);
+        }
+        counters::PUBLISHER_NUM_SUBSCRIBERS.set(subscribers.len() as i64);
+        true
+    }
+
+    /// Removes the given peer from the set of subscribers
+    pub fn remove_subscriber(&self, peer_network_id: &PeerNetworkId) {
+        let mut subscribers = self.subscribers.write();
+        if subscribers.remove(peer_network_id) {
+            info!("Removed consensus observer subscriber: {}", peer_network_id);
+        }
+        counters::PUBLISHER_NUM_SUBSCRIBERS.set(subscribers.len() as i64);
+    }
+
+    /// Returns the current set of subscribers
+    pub fn get_subscribers(&self) -> Vec<PeerNetworkId> {
+        self.subscribers.read().iter().cloned().collect()
+    }
+
+    /// Returns true iff there are any subscribers (i.e., messages should be published)
+    pub fn has_subscribers(&self) -> bool {
+        !self.subscribers.read().is_empty()
+    }
+
+    /// Publishes the given message to all subscribers. Delivery is best effort.
+    pub fn publish_message(&self, message: ConsensusObserverMessage) {
+        let subscribers = self.get_subscribers();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        counters::increment_counter(&counters::OBSERVER_SENT_MESSAGES, &[message.get_label()]);
+        if let Err(error) = self.network_client.send_to_peers(message, &subscribers) {
+            warn!(
+                "Failed to publish the consensus observer message to subscribers! Error: {:?}",
+                error
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_config::network_id::NetworkId;
+    use aptos_network::application::storage::PeersAndMetadata;
+    use aptos_types::PeerId;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_add_and_remove_subscribers() {
+        // Create a consensus publisher that allows a single public peer
+        let allowed_peer_id = PeerId::random();
+        let consensus_publisher = create_consensus_publisher(ConsensusObserverConfig {
+            allowed_subscriber_peers: vec![allowed_peer_id],
+            ..Default::default()
+        });
+        assert!(!consensus_publisher.has_subscribers());
+
+        // Add several subscribers (including a duplicate)
+        let peer_1 = PeerNetworkId::new(NetworkId::Vfn, PeerId::random());
+        let peer_2 = PeerNetworkId::new(NetworkId::Public, allowed_peer_id);
+        assert!(consensus_publisher.add_subscriber(peer_1));
+        assert!(consensus_publisher.add_subscriber(peer_2));
+        assert!(consensus_publisher.add_subscriber(peer_1));
+        assert!(consensus_publisher.has_subscribers());
+        let mut subscribers = consensus_publisher.get_subscribers();
+        subscribers.sort();
+        let mut expected_subscribers = vec![peer_1, peer_2];
+        expected_subscribers.sort();
+        assert_eq!(subscribers, expected_subscribers);
+
+        // Remove the subscribers and verify the set is updated
+        consensus_publisher.remove_subscriber(&peer_1);
+        assert_eq!(consensus_publisher.get_subscribers(), vec![peer_2]);
+        consensus_publisher.remove_subscriber(&peer_2);
+        consensus_publisher.remove_subscriber(&peer_2);
+        assert!(consensus_publisher.get_subscribers().is_empty());
+    }
+
+    #[test]
+    fn test_subscriber_restrictions() {
+        // Create a consensus publisher with a single subscriber slot
+        let consensus_publisher = create_consensus_publisher(ConsensusObserverConfig {
+            max_subscribers: 1,
+            ..Default::default()
+        });
+
+        // Verify that public peers (that aren't explicitly allowed) are rejected
+        let public_peer = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+        assert!(!consensus_publisher.add_subscriber(public_peer));
+
+        // Verify that subscribers are rejected once the maximum is reached
+        let peer_1 = PeerNetworkId::new(NetworkId::Vfn, PeerId::random());
+        let peer_2 = PeerNetworkId::new(NetworkId::Vfn, PeerId::random());
+        assert!(consensus_publisher.add_subscriber(peer_1));
+        assert!(!consensus_publisher.add_subscriber(peer_2));
+        assert!(consensus_publisher.add_subscriber(peer_1));
+        assert_eq!(consensus_publisher.get_subscribers(), vec![peer_1]);
+
+        // Verify that a slot is freed once a subscriber is removed
+        consensus_publisher.remove_subscriber(&peer_1);
+        assert!(consensus_publisher.add_subscriber(peer_2));
+        assert_eq!(consensus_publisher.get_subscribers(), vec![peer_2]);
+    }
+
+    /// Creates a consensus publisher with the given config
+    fn create_consensus_publisher(config: ConsensusObserverConfig) -> ConsensusPublisher {
+        let network_client = NetworkClient::new(
+            vec![],
+            vec![],
+            HashMap::new(),
+            PeersAndMetadata::new(&[NetworkId::Vfn, NetworkId::Public]),
+        );
+        ConsensusPublisher::new(config, network_client)
+    }
+}
@@ -3,19 +3,26 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    consensus_observer::{
+        network_message::ConsensusObserverMessage, observer::ConsensusObserver,
+        publisher::ConsensusPublisher,
+    },
     counters,
     epoch_manager::EpochManager,
+    liveness::leader_reputation_report::LeaderReputationReporter,
     network::NetworkTask,
     network_interface::{ConsensusMsg, ConsensusNetworkClient},
-    persistent_liveness_storage::StorageWriteProxy,
+    persistent_liveness_storage::{PersistentLivenessStorage, StorageWriteProxy},
     pipeline::execution_client::ExecutionProxyClient,
     quorum_store::quorum_store_db::QuorumStoreDB,
     rand::rand_gen::storage::db::RandDb,
+    startup_checks,
     state_computer::ExecutionProxy,
     transaction_filter::TransactionFilter,
     txn_notifier::MempoolNotifier,
     util::time_service::ClockTimeService,
 };
+use anyhow::bail;
 use aptos_bounded_executor::BoundedExecutor;
 use aptos_config::config::NodeConfig;
 use aptos_consensus_notifications::ConsensusNotificationSender;
@@ -31,7 +38,8 @@ use futures::channel::mpsc;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
-/// Helper function to start consensus based on configuration and return the runtime
+/// Helper function to start consensus based on configuration and return the runtime.
+/// Returns an error (instead of starting consensus) if any startup check fails.
 pub fn start_consensus(
     node_config: &NodeConfig,
     network_client: NetworkClient<ConsensusMsg>,
@@ -41,11 +49,42 @@ pub fn start_consensus(
     aptos_db: DbReaderWriter,
     reconfig_events: ReconfigNotificationListener<DbBackedOnChainConfig>,
     vtxn_pool: VTxnPoolState,
-) -> (Runtime, Arc<StorageWriteProxy>, Arc<QuorumStoreDB>) {
-    let runtime = aptos_runtimes::spawn_named_runtime("consensus".into(), None);
+    consensus_publisher: Option<ConsensusPublisher>,
+) -> anyhow::Result<(
+    Runtime,
+    Arc<StorageWriteProxy>,
+    Arc<QuorumStoreDB>,
+    LeaderReputationReporter,
+)> {
     let storage = Arc::new(StorageWriteProxy::new(node_config, aptos_db.reader.clone()));
     let quorum_store_db = Arc::new(QuorumStoreDB::new(node_config.storage.dir()));
 
+    // Verify the node is able to participate before joining consensus
+    if node_config.consensus.startup_checks.enable {
+        let errors = startup_checks::run_startup_checks(
+            node_config,
+            aptos_db.reader.as_ref(),
+            &storage.consensus_db(),
+            &quorum_store_db,
+        );
+        if !errors.is_empty() {
+            for error in &errors {
+                error!("Consensus startup check failed: {}", error);
+            }
+            bail!(
+                "Refusing to start consensus, {} startup check(s) failed: {}",
+                errors.len(),
+                errors
+                    .iter()
+                    .map(|error| error.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+    }
+
+    let runtime = aptos_runtimes::spawn_named_runtime("consensus".into(), None);
+
     let txn_notifier = Arc::new(MempoolNotifier::new(
         consensus_to_mempool_sender.clone(),
         node_config.consensus.mempool_executed_txn_timeout_ms,
@@ -57,6 +96,9 @@ pub fn start_consensus(
         state_sync_notifier,
         runtime.handle(),
         TransactionFilter::new(node_config.execution.transaction_filter.clone()),
+        node_config.consensus.optimistic_execution.clone(),
+        node_config.consensus.state_prefetch.clone(),
+        consensus_publisher.clone(),
     );
 
     let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
@@ -77,6 +119,7 @@ pub fn start_consensus(
         consensus_network_client.clone(),
         bounded_executor.clone(),
         rand_storage.clone(),
+        consensus_publisher,
     ));
 
     let epoch_mgr = EpochManager::new(
@@ -96,11 +139,44 @@ pub fn start_consensus(
         rand_storage,
     );
 
+    let leader_reputation_reporter = epoch_mgr.leader_reputation_reporter();
     let (network_task, network_receiver) = NetworkTask::new(network_service_events, self_receiver);
 
     runtime.spawn(network_task.start());
     runtime.spawn(epoch_mgr.start(timeout_receiver, network_receiver));
 
     debug!("Consensus started.");
-    (runtime, storage, quorum_store_db)
+    Ok((
+        runtime,
+        storage,
+        quorum_store_db,
+        leader_reputation_reporter,
+    ))
+}
+
+/// Helper function to start the consensus observer and return the runtime. The
+/// observer follows (and executes) consensus updates only if a state sync notifier
+/// is given, and serves subscribers only if a consensus publisher is given.
+pub fn start_consensus_observer(
+    node_config: &NodeConfig,
+    network_client: NetworkClient<ConsensusObserverMessage>,
+    network_service_events: NetworkServiceEvents<ConsensusObserverMessage>,
+    consensus_publisher: Option<ConsensusPublisher>,
+    state_sync_notifier: Option<Arc<dyn ConsensusNotificationSender>>,
+    aptos_db: DbReaderWriter,
+) -> Runtime {
+    let runtime = aptos_runtimes::spawn_named_runtime("observer".into(), None);
+    let block_executor = Arc::new(BlockExecutor::<AptosVM>::new(aptos_db.clone()));
+    let consensus_observer = ConsensusObserver::new(
+        node_config.consensus_observer.clone(),
+        network_client,
+        aptos_db.reader,
+        state_sync_notifier,
+        consensus_publisher,
+        block_executor,
+    );
+    runtime.spawn(consensus_observer.start(network_service_events));
+
+    debug!("Consensus observer started.");
+    runtime
 }
@@ -5,14 +5,15 @@
 use self::schema::dag::NodeSchema;
 use super::*;
 use crate::dag::{CertifiedNode, Extensions, Node, Vote};
+use aptos_config::config::ConsensusDBPrunerConfig;
 use aptos_consensus_types::{
-    block::block_test_utils::certificate_for_genesis,
+    block::block_test_utils::{certificate_for_genesis, placeholder_certificate_for_block},
     common::{Author, Payload},
 };
 use aptos_crypto::bls12381::Signature;
 use aptos_temppath::TempPath;
-use aptos_types::aggregate_signature::AggregateSignature;
-use std::{collections::HashMap, hash::Hash};
+use aptos_types::{aggregate_signature::AggregateSignature, validator_signer::ValidatorSigner};
+use std::{collections::HashMap, hash::Hash, sync::atomic::AtomicBool};
 
 #[test]
 fn test_put_get() {
@@ -79,6 +80,72 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_all::<QCSchema>().unwrap().len(), 0);
 }
 
+#[test]
+fn test_prune_stale_blocks() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    // Save blocks (and their quorum certs) for rounds 1 to 10
+    let signer = ValidatorSigner::random(None);
+    let genesis_qc = certificate_for_genesis();
+    let genesis_id = genesis_qc.certified_block().id();
+    let blocks: Vec<_> = (1..=10)
+        .map(|round| Block::new_nil(round, genesis_qc.clone(), vec![]))
+        .collect();
+    let qcs = blocks
+        .iter()
+        .map(|block| {
+            placeholder_certificate_for_block(
+                &[signer.clone()],
+                block.id(),
+                block.round(),
+                genesis_id,
+                0,
+            )
+        })
+        .collect();
+    db.save_blocks_and_quorum_certificates(blocks.clone(), qcs)
+        .unwrap();
+
+    // Prune with a committed round of 8 and a window of 3 rounds (in small batches)
+    let config = ConsensusDBPrunerConfig {
+        prune_window_rounds: 3,
+        batch_size: 3,
+        batch_interval_ms: 0,
+        ..Default::default()
+    };
+    let epoch = blocks[0].epoch();
+    let quit = AtomicBool::new(false);
+    let num_pruned = pruner::prune_stale_blocks(&db, &config, epoch, 8, &quit).unwrap();
+
+    // Verify that only the blocks and quorum certs below round 5 were pruned
+    assert_eq!(num_pruned, 4);
+    let mut remaining_rounds: Vec<_> = db
+        .get_all::<BlockSchema>()
+        .unwrap()
+        .into_iter()
+        .map(|(_, block)| block.round())
+        .collect();
+    remaining_rounds.sort_unstable();
+    assert_eq!(remaining_rounds, (5..=10).collect::<Vec<_>>());
+    assert!(db
+        .get_all::<QCSchema>()
+        .unwrap()
+        .iter()
+        .all(|(_, qc)| qc.certified_block().round() >= 5));
+    assert_eq!(db.get_all::<QCSchema>().unwrap().len(), 6);
+
+    // Verify that pruning again is a no-op
+    let num_pruned = pruner::prune_stale_blocks(&db, &config, epoch, 8, &quit).unwrap();
+    assert_eq!(num_pruned, 0);
+
+    // Verify that all blocks and quorum certs from previous epochs are pruned
+    let num_pruned = pruner::prune_stale_blocks(&db, &config, epoch + 1, 1, &quit).unwrap();
+    assert_eq!(num_pruned, 6);
+    assert_eq!(db.get_all::<BlockSchema>().unwrap().len(), 0);
+    assert_eq!(db.get_all::<QCSchema>().unwrap().len(), 0);
+}
+
 fn test_dag_type<S: Schema<Key = K>, K: Eq + Hash>(key: S::Key, value: S::Value, db: &ConsensusDB) {
     db.put::<S>(&key, &value).unwrap();
     let mut from_db: HashMap<K, S::Value> = db.get_all::<S>().unwrap().into_iter().collect();
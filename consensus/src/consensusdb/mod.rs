@@ -4,17 +4,20 @@
 
 #[cfg(test)]
 mod consensusdb_test;
+mod pruner;
 mod schema;
 
 use crate::error::DbError;
 use anyhow::Result;
 use aptos_consensus_types::{block::Block, quorum_cert::QuorumCert};
 use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use aptos_schemadb::{
     schema::Schema, Options, ReadOptions, SchemaBatch, DB, DEFAULT_COLUMN_FAMILY_NAME,
 };
 use aptos_storage_interface::AptosDbError;
+pub use pruner::ConsensusDBPruner;
 pub use schema::{
     block::BlockSchema,
     dag::{CertifiedNodeSchema, DagVoteSchema, NodeSchema},
@@ -32,22 +35,14 @@ pub const CONSENSUS_DB_NAME: &str = "consensus_db";
 
 /// Creates new physical DB checkpoint in directory specified by `checkpoint_path`.
 pub fn create_checkpoint<P: AsRef<Path> + Clone>(db_path: P, checkpoint_path: P) -> Result<()> {
-    let start = Instant::now();
-    let consensus_db_checkpoint_path = checkpoint_path.as_ref().join(CONSENSUS_DB_NAME);
-    std::fs::remove_dir_all(&consensus_db_checkpoint_path).unwrap_or(());
-    ConsensusDB::new(db_path)
-        .db
-        .create_checkpoint(&consensus_db_checkpoint_path)?;
-    info!(
-        path = consensus_db_checkpoint_path,
-        time_ms = %start.elapsed().as_millis(),
-        "Made ConsensusDB checkpoint."
-    );
-    Ok(())
+    ConsensusDB::new(db_path).create_checkpoint(checkpoint_path)
 }
 
 pub struct ConsensusDB {
     db: DB,
+    // Held by checkpoints (and by checkpoint consistent pruning passes), so that
+    // checkpoints never observe a partially pruned database.
+    pruning_lock: Mutex<()>,
 }
 
 impl ConsensusDB {
@@ -77,7 +72,27 @@ impl ConsensusDB {
             instant.elapsed().as_millis()
         );
 
-        Self { db }
+        Self {
+            db,
+            pruning_lock: Mutex::new(()),
+        }
+    }
+
+    /// Creates new physical DB checkpoint in directory specified by `checkpoint_path`.
+    /// Unlike the free-standing `create_checkpoint`, this can be used on a live DB.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, checkpoint_path: P) -> Result<()> {
+        let _pruning_guard = self.pruning_lock.lock();
+
+        let start = Instant::now();
+        let consensus_db_checkpoint_path = checkpoint_path.as_ref().join(CONSENSUS_DB_NAME);
+        std::fs::remove_dir_all(&consensus_db_checkpoint_path).unwrap_or(());
+        self.db.create_checkpoint(&consensus_db_checkpoint_path)?;
+        info!(
+            path = consensus_db_checkpoint_path,
+            time_ms = %start.elapsed().as_millis(),
+            "Made ConsensusDB checkpoint."
+        );
+        Ok(())
     }
 
     pub fn get_data(
@@ -0,0 +1,170 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensusdb::{BlockSchema, ConsensusDB, QCSchema},
+    counters,
+};
+use anyhow::Result;
+use aptos_config::config::ConsensusDBPrunerConfig;
+use aptos_consensus_types::common::Round;
+use aptos_crypto::HashValue;
+use aptos_logger::prelude::*;
+use aptos_storage_interface::DbReader;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+/// Periodically prunes the blocks and quorum certs that are no longer needed
+/// by consensus (i.e., those from previous epochs, and those more than
+/// `prune_window_rounds` below the latest committed round).
+pub struct ConsensusDBPruner {
+    quit: Arc<AtomicBool>,
+    worker_thread: Option<JoinHandle<()>>,
+}
+
+impl ConsensusDBPruner {
+    pub fn new(
+        config: ConsensusDBPrunerConfig,
+        consensus_db: Arc<ConsensusDB>,
+        aptos_db: Arc<dyn DbReader>,
+    ) -> Self {
+        let quit = Arc::new(AtomicBool::new(false));
+        let worker_quit = quit.clone();
+        let worker_thread = std::thread::Builder::new()
+            .name("consensusdb_pruner".into())
+            .spawn(move || run_pruner(config, consensus_db, aptos_db, worker_quit))
+            .expect("Creating consensusdb pruner thread should succeed.");
+
+        Self {
+            quit,
+            worker_thread: Some(worker_thread),
+        }
+    }
+}
+
+impl Drop for ConsensusDBPruner {
+    fn drop(&mut self) {
+        self.quit.store(true, Ordering::Relaxed);
+        if let Some(worker_thread) = self.worker_thread.take() {
+            worker_thread
+                .join()
+                .expect("Consensusdb pruner thread should join peacefully.");
+        }
+    }
+}
+
+/// Runs pruning passes until asked to quit
+fn run_pruner(
+    config: ConsensusDBPrunerConfig,
+    consensus_db: Arc<ConsensusDB>,
+    aptos_db: Arc<dyn DbReader>,
+    quit: Arc<AtomicBool>,
+) {
+    let pruning_interval = Duration::from_millis(config.pruning_interval_ms);
+    let mut last_pass = None;
+    while !quit.load(Ordering::Relaxed) {
+        // Sleep in short intervals so that the pruner can quit promptly
+        if last_pass.map_or(false, |last_pass: Instant| {
+            last_pass.elapsed() < pruning_interval
+        }) {
+            std::thread::sleep(Duration::from_millis(100).min(pruning_interval));
+            continue;
+        }
+        last_pass = Some(Instant::now());
+
+        // Identify the latest committed block
+        let ledger_info = match aptos_db.get_latest_ledger_info() {
+            Ok(ledger_info) => ledger_info,
+            Err(error) => {
+                warn!(
+                    "Failed to get the latest ledger info for pruning: {:?}",
+                    error
+                );
+                continue;
+            },
+        };
+        let committed_epoch = ledger_info.ledger_info().epoch();
+        let committed_round = ledger_info.ledger_info().round();
+
+        // Prune the stale blocks and quorum certs
+        match prune_stale_blocks(
+            &consensus_db,
+            &config,
+            committed_epoch,
+            committed_round,
+            &quit,
+        ) {
+            Ok(num_pruned) if num_pruned > 0 => info!(
+                epoch = committed_epoch,
+                round = committed_round,
+                "Pruned {} stale blocks from ConsensusDB.",
+                num_pruned
+            ),
+            Ok(_) => {},
+            Err(error) => warn!("Failed to prune ConsensusDB: {:?}", error),
+        }
+    }
+}
+
+/// Deletes (in batches) all blocks and quorum certs that are older than the
+/// prune window, oldest first. Returns the number of pruned blocks.
+pub(crate) fn prune_stale_blocks(
+    consensus_db: &ConsensusDB,
+    config: &ConsensusDBPrunerConfig,
+    committed_epoch: u64,
+    committed_round: Round,
+    quit: &AtomicBool,
+) -> Result<usize> {
+    // Hold the pruning lock for the entire pass, if checkpoints must be consistent
+    let _pruning_guard = config
+        .checkpoint_consistent
+        .then(|| consensus_db.pruning_lock.lock());
+
+    // Identify the stale blocks and quorum certs (keyed by the certified block id)
+    let min_retained_round = committed_round.saturating_sub(config.prune_window_rounds);
+    let is_stale =
+        |epoch: u64, round: Round| (epoch, round) < (committed_epoch, min_retained_round);
+    let mut stale_blocks = HashMap::new();
+    for (block_id, block) in consensus_db.get_all::<BlockSchema>()? {
+        if is_stale(block.epoch(), block.round()) {
+            stale_blocks.insert(block_id, (block.epoch(), block.round()));
+        }
+    }
+    for (block_id, qc) in consensus_db.get_all::<QCSchema>()? {
+        let certified_block = qc.certified_block();
+        if is_stale(certified_block.epoch(), certified_block.round()) {
+            stale_blocks.insert(block_id, (certified_block.epoch(), certified_block.round()));
+        }
+    }
+
+    // Delete the oldest blocks first, so that a partial pass never leaves gaps
+    let mut stale_block_ids: Vec<(u64, Round, HashValue)> = stale_blocks
+        .into_iter()
+        .map(|(block_id, (epoch, round))| (epoch, round, block_id))
+        .collect();
+    stale_block_ids.sort_unstable();
+
+    let mut num_pruned = 0;
+    for (index, batch) in stale_block_ids.chunks(config.batch_size.max(1)).enumerate() {
+        if quit.load(Ordering::Relaxed) {
+            break;
+        }
+        if index > 0 && config.batch_interval_ms > 0 {
+            std::thread::sleep(Duration::from_millis(config.batch_interval_ms));
+        }
+
+        let block_ids = batch.iter().map(|(_, _, block_id)| *block_id).collect();
+        consensus_db.delete_blocks_and_quorum_certificates(block_ids)?;
+        num_pruned += batch.len();
+        counters::CONSENSUSDB_PRUNED_BLOCKS.inc_by(batch.len() as u64);
+    }
+
+    Ok(num_pruned)
+}
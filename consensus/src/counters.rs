@@ -240,6 +240,14 @@ pub static CONSENSUS_WITHOLD_VOTE_BACKPRESSURE_TRIGGERED: Lazy<Histogram> = Lazy
     )
 });
 
+/// Counts when a lagging validator keeps voting on ordering only (instead of withholding its vote)
+pub static CONSENSUS_ORDER_ONLY_VOTE_TRIGGERED: Lazy<Histogram> = Lazy::new(|| {
+    register_avg_counter(
+        "aptos_consensus_order_only_vote_triggered",
+        "Counts when consensus votes in order-only mode due to execution lag",
+    )
+});
+
 /// Counts when chain_health backoff is triggered
 pub static CHAIN_HEALTH_BACKOFF_TRIGGERED: Lazy<Histogram> = Lazy::new(|| {
     register_avg_counter(
@@ -750,6 +758,17 @@ pub static CONSENSUS_SENT_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counters for consensus messages that were not sent or processed because their
+/// type is not enabled in the current epoch, broken down by direction and type
+pub static CONSENSUS_GATED_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_gated_msgs_count",
+        "Counters for gated consensus messages broken down by direction and type",
+        &["direction", "type"]
+    )
+    .unwrap()
+});
+
 /// Counters(queued,dequeued,dropped) related to consensus round manager channel
 pub static ROUND_MANAGER_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -790,6 +809,16 @@ pub static BLOCK_RETRIEVAL_TASK_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of blocks in the responses to ranged block retrieval requests
+pub static BLOCK_RANGE_RETRIEVAL_RESPONSE_BLOCKS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_consensus_block_range_retrieval_response_blocks",
+        "Number of blocks in the responses to ranged block retrieval requests",
+        exponential_buckets(/*start=*/ 1.0, /*factor=*/ 2.0, /*count=*/ 12).unwrap(),
+    )
+    .unwrap()
+});
+
 /// Count of the buffer manager retry requests since last restart.
 pub static BUFFER_MANAGER_RETRY_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -823,6 +852,16 @@ pub static QUORUM_BATCH_READY_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the batch prefetch requests, by result (e.g., requested, deduplicated or timed out).
+pub static BATCH_PREFETCH_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_batch_prefetch_requests",
+        "Count of the batch prefetch requests, by result",
+        &["result"]
+    )
+    .unwrap()
+});
+
 /// Histogram of the time durations waiting for batch when executing.
 pub static BATCH_WAIT_DURATION: Lazy<DurationHistogram> = Lazy::new(|| {
     DurationHistogram::new(
@@ -849,6 +888,57 @@ pub static BUFFER_MANAGER_PHASE_PROCESS_SECONDS: Lazy<HistogramVec> = Lazy::new(
     .unwrap()
 });
 
+/// Count of the speculative (optimistic) block executions, by outcome.
+pub static SPECULATIVE_EXECUTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_speculative_executions",
+        "Count of the speculative block executions, by outcome",
+        &["outcome"]
+    )
+    .unwrap()
+});
+
+/// Count of the block state prefetches, by outcome. The prefetch hit rate is the
+/// fraction of prefetches that completed before the block's execution started.
+pub static STATE_PREFETCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_state_prefetches",
+        "Count of the block state prefetches, by outcome",
+        &["outcome"]
+    )
+    .unwrap()
+});
+
+/// Count of the state keys prefetched for ordered blocks
+pub static STATE_PREFETCHED_KEYS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_consensus_state_prefetched_keys",
+        "Count of the state keys prefetched for ordered blocks"
+    )
+    .unwrap()
+});
+
+/// Count of the payload requests sent to the external block builder, by outcome
+pub static EXTERNAL_BLOCK_BUILDER_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_external_block_builder_requests",
+        "Count of the payload requests sent to the external block builder, by outcome",
+        &["outcome"]
+    )
+    .unwrap()
+});
+
+/// Latency of the payload requests sent to the external block builder
+pub static EXTERNAL_BLOCK_BUILDER_LATENCY: Lazy<DurationHistogram> = Lazy::new(|| {
+    DurationHistogram::new(
+        register_histogram!(
+            "aptos_consensus_external_block_builder_latency",
+            "Latency of the payload requests sent to the external block builder"
+        )
+        .unwrap(),
+    )
+});
+
 /// Count of the number of `ProposalExt` blocks received while the feature is disabled.
 pub static UNEXPECTED_PROPOSAL_EXT_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -967,3 +1057,121 @@ pub static RAND_QUEUE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Count of the lookups in the WVUF derivation caches (by cache and result).
+pub static RAND_WVUF_CACHE_LOOKUPS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_rand_wvuf_cache_lookups_count",
+        "Count of the lookups in the WVUF derivation caches, by cache and result (hit or miss)",
+        &["cache", "result"]
+    )
+    .unwrap()
+});
+
+/// Count of the rounds for which each validator contributed (or missed) a randomness share.
+pub static RAND_SHARE_PARTICIPATION_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_rand_share_participation_count",
+        "Count of the rounds for which each validator contributed or missed a randomness share",
+        &["peer_id", "status"]
+    )
+    .unwrap()
+});
+
+/// Emits the randomness share participation status of each validator in the latest finalized
+/// round, 0 means the share was missed and 1 otherwise.
+pub static RAND_SHARE_PARTICIPATION_STATUS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_consensus_rand_share_participation_status",
+        "Randomness share participation status in the latest finalized round, 0 means missed and 1 otherwise",
+        &["peer_id"]
+    )
+    .unwrap()
+});
+
+/// Latency of the randomness shares of each validator, since the block timestamp.
+pub static RAND_SHARE_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_consensus_rand_share_latency_seconds",
+        "Latency of the randomness shares of each validator, since the block timestamp",
+        &["peer_id"]
+    )
+    .unwrap()
+});
+
+/// Count of the randomness share backfill requests (sent and served), by result.
+pub static RAND_SHARE_BACKFILL_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_rand_share_backfill_requests_count",
+        "Count of the randomness share backfill requests (sent and served), by result",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Count of the randomness shares received in backfill responses, by result.
+pub static RAND_BACKFILLED_SHARES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_rand_backfilled_shares_count",
+        "Count of the randomness shares received in backfill responses, by result",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Count of the duplicate proposals and votes that the round manager did not reprocess.
+pub static ROUND_MANAGER_SUPPRESSED_DUPLICATE_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_round_manager_suppressed_duplicate_msgs_count",
+        "Count of the duplicate proposals and votes that the round manager did not reprocess",
+        &["msg_type"]
+    )
+    .unwrap()
+});
+
+/// Median of the estimated clock offsets of peers relative to the local clock (in ms).
+pub static FLEET_CLOCK_SKEW_MS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_consensus_fleet_clock_skew_ms",
+        "Median of the estimated clock offsets of peers relative to the local clock (in ms)"
+    )
+    .unwrap()
+});
+
+/// Largest (absolute) estimated clock offset of a peer relative to the local clock (in ms).
+pub static MAX_PEER_CLOCK_SKEW_MS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_consensus_max_peer_clock_skew_ms",
+        "Largest (absolute) estimated clock offset of a peer relative to the local clock (in ms)"
+    )
+    .unwrap()
+});
+
+/// Extra time that proposal timestamps may be ahead of the local clock (in ms).
+pub static PROPOSAL_TIMESTAMP_TOLERANCE_MS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_consensus_proposal_timestamp_tolerance_ms",
+        "Extra time that proposal timestamps may be ahead of the local clock (in ms)"
+    )
+    .unwrap()
+});
+
+/// Count of the stale blocks (and their quorum certs) pruned from the consensus db.
+pub static CONSENSUSDB_PRUNED_BLOCKS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_consensus_db_pruned_blocks_count",
+        "Count of the stale blocks (and their quorum certs) pruned from the consensus db"
+    )
+    .unwrap()
+});
+
+/// Count of the byzantine behaviors injected by the round manager (only with the byzantine
+/// feature), by behavior
+pub static BYZANTINE_BEHAVIORS_INJECTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_byzantine_behaviors_injected",
+        "Count of the byzantine behaviors injected by the round manager, by behavior",
+        &["behavior"]
+    )
+    .unwrap()
+});
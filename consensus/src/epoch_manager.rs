@@ -16,6 +16,8 @@ use crate::{
             extract_epoch_to_proposers, AptosDBBackend, LeaderReputation,
             ProposerAndVoterHeuristic, ReputationHeuristic,
         },
+        leader_reputation_report::LeaderReputationReporter,
+        processed_message_tracker::ProcessedMessageTracker,
         proposal_generator::{
             ChainHealthBackoffConfig, PipelineBackpressureConfig, ProposalGenerator,
         },
@@ -25,15 +27,22 @@ use crate::{
         round_state::{ExponentialTimeInterval, RoundState},
     },
     logging::{LogEvent, LogSchema},
+    message_gating::ConsensusMsgGating,
     metrics_safety_rules::MetricsSafetyRules,
     monitor,
     network::{
-        IncomingBatchRetrievalRequest, IncomingBlockRetrievalRequest, IncomingDAGRequest,
-        IncomingRandGenRequest, IncomingRpcRequest, NetworkReceivers, NetworkSender,
+        IncomingBatchRetrievalRequest, IncomingBlockRangeRetrievalRequest,
+        IncomingBlockRetrievalRequest, IncomingDAGRequest, IncomingRandGenRequest,
+        IncomingRpcRequest, NetworkReceivers, NetworkSender,
     },
     network_interface::{ConsensusMsg, ConsensusNetworkClient},
     payload_client::{
-        mixed::MixedPayloadClient, user::quorum_store_client::QuorumStoreClient, PayloadClient,
+        mixed::MixedPayloadClient,
+        user::{
+            external_builder_client::ExternalBuilderClient, quorum_store_client::QuorumStoreClient,
+            UserPayloadClient,
+        },
+        PayloadClient,
     },
     payload_manager::PayloadManager,
     persistent_liveness_storage::{LedgerRecoveryData, PersistentLivenessStorage, RecoveryData},
@@ -61,7 +70,7 @@ use aptos_config::config::{
 use aptos_consensus_types::{
     common::{Author, Round},
     delayed_qc_msg::DelayedQcMsg,
-    epoch_retrieval::EpochRetrievalRequest,
+    epoch_retrieval::{EpochRetrievalRequest, EpochStarterKit, EpochStarterKitRequest},
     proof_of_store::ProofCache,
 };
 use aptos_crypto::bls12381;
@@ -103,7 +112,7 @@ use futures::{
 };
 use itertools::Itertools;
 use mini_moka::sync::Cache;
-use rand::{prelude::StdRng, thread_rng, SeedableRng};
+use rand::{prelude::StdRng, seq::IteratorRandom, thread_rng, SeedableRng};
 use std::{
     cmp::Ordering,
     collections::HashMap,
@@ -138,6 +147,7 @@ pub struct EpochManager<P: OnChainConfigProvider> {
     network_sender: ConsensusNetworkClient<NetworkClient<ConsensusMsg>>,
     timeout_sender: aptos_channels::Sender<Round>,
     quorum_store_enabled: bool,
+    message_gating: Arc<ConsensusMsgGating>,
     quorum_store_to_mempool_sender: Sender<QuorumStoreRequest>,
     execution_client: Arc<dyn TExecutionClient>,
     storage: Arc<dyn PersistentLivenessStorage>,
@@ -152,9 +162,13 @@ pub struct EpochManager<P: OnChainConfigProvider> {
     >,
     buffered_proposal_tx: Option<aptos_channel::Sender<Author, VerifiedEvent>>,
     round_manager_close_tx: Option<oneshot::Sender<oneshot::Sender<()>>>,
+    // the proposals and votes processed by the current round manager (used to drop duplicates)
+    processed_msgs: Option<Arc<Mutex<ProcessedMessageTracker>>>,
     epoch_state: Option<Arc<EpochState>>,
     block_retrieval_tx:
         Option<aptos_channel::Sender<AccountAddress, IncomingBlockRetrievalRequest>>,
+    block_range_retrieval_tx:
+        Option<aptos_channel::Sender<AccountAddress, IncomingBlockRangeRetrievalRequest>>,
     quorum_store_msg_tx: Option<aptos_channel::Sender<AccountAddress, VerifiedEvent>>,
     quorum_store_coordinator_tx: Option<Sender<CoordinatorCommand>>,
     quorum_store_storage: Arc<dyn QuorumStoreStorage>,
@@ -171,6 +185,11 @@ pub struct EpochManager<P: OnChainConfigProvider> {
     payload_manager: Arc<PayloadManager>,
     rand_storage: Arc<dyn RandStorage<AugmentedData>>,
     proof_cache: ProofCache,
+    leader_reputation_reporter: LeaderReputationReporter,
+    // the block store of the current round manager (used to serve epoch starter kits)
+    block_store: Option<Arc<BlockStore>>,
+    // an epoch starter kit received for the next epoch (applied once the epoch starts)
+    pending_epoch_starter_kit: Option<(Author, EpochStarterKit)>,
 }
 
 impl<P: OnChainConfigProvider> EpochManager<P> {
@@ -206,6 +225,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             timeout_sender,
             // This default value is updated at epoch start
             quorum_store_enabled: false,
+            message_gating: Arc::new(ConsensusMsgGating::allow_all()),
             quorum_store_to_mempool_sender,
             execution_client,
             storage,
@@ -215,9 +235,11 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             rand_manager_msg_tx: None,
             round_manager_tx: None,
             round_manager_close_tx: None,
+            processed_msgs: None,
             buffered_proposal_tx: None,
             epoch_state: None,
             block_retrieval_tx: None,
+            block_range_retrieval_tx: None,
             quorum_store_msg_tx: None,
             quorum_store_coordinator_tx: None,
             quorum_store_storage,
@@ -235,9 +257,17 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                 .initial_capacity(1_000)
                 .time_to_live(Duration::from_secs(20))
                 .build(),
+            leader_reputation_reporter: LeaderReputationReporter::new(),
+            block_store: None,
+            pending_epoch_starter_kit: None,
         }
     }
 
+    /// Returns a handle that generates leader reputation reports for the current epoch
+    pub(crate) fn leader_reputation_reporter(&self) -> LeaderReputationReporter {
+        self.leader_reputation_reporter.clone()
+    }
+
     fn epoch_state(&self) -> &EpochState {
         self.epoch_state
             .as_ref()
@@ -275,6 +305,9 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         epoch_state: &EpochState,
         onchain_config: &OnChainConsensusConfig,
     ) -> Arc<dyn ProposerElection + Send + Sync> {
+        // Leader reputation reports are only available for leader reputation epochs
+        self.leader_reputation_reporter.clear_context();
+
         let proposers = epoch_state
             .verifier
             .get_ordered_account_addresses_iter()
@@ -294,6 +327,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                     window_size,
                     weight_by_voting_power,
                     use_history_from_previous_epoch_max_count,
+                    proposer_and_voter_config,
                 ) = match &leader_reputation_type {
                     LeaderReputationType::ProposerAndVoter(proposer_and_voter_config)
                     | LeaderReputationType::ProposerAndVoterV2(proposer_and_voter_config) => {
@@ -317,6 +351,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                             std::cmp::max(proposer_window_size, voter_window_size),
                             proposer_and_voter_config.weight_by_voting_power,
                             proposer_and_voter_config.use_history_from_previous_epoch_max_count,
+                            *proposer_and_voter_config,
                         )
                     },
                 };
@@ -330,11 +365,12 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                     seek_len,
                     self.storage.aptos_db(),
                 ));
-                let voting_powers: Vec<_> = if weight_by_voting_power {
-                    proposers
-                        .iter()
-                        .map(|p| epoch_state.verifier.get_voting_power(p).unwrap())
-                        .collect()
+                let proposer_voting_powers: Vec<_> = proposers
+                    .iter()
+                    .map(|p| epoch_state.verifier.get_voting_power(p).unwrap())
+                    .collect();
+                let voting_powers = if weight_by_voting_power {
+                    proposer_voting_powers.clone()
                 } else {
                     vec![1; proposers.len()]
                 };
@@ -356,6 +392,16 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                         .collect::<Vec<_>>()
                 );
 
+                self.leader_reputation_reporter.set_context(
+                    self.author,
+                    epoch_state.epoch,
+                    epoch_to_proposers.clone(),
+                    proposer_voting_powers,
+                    proposer_and_voter_config,
+                    leader_reputation_type.use_reputation_window_from_stale_end(),
+                    self.storage.aptos_db(),
+                );
+
                 let proposer_election = Box::new(LeaderReputation::new(
                     epoch_state.epoch,
                     epoch_to_proposers,
@@ -520,6 +566,160 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         }
     }
 
+    fn process_epoch_starter_kit_request(
+        &mut self,
+        request: EpochStarterKitRequest,
+        peer_id: AccountAddress,
+    ) -> anyhow::Result<()> {
+        debug!(
+            LogSchema::new(LogEvent::ReceiveEpochStarterKitRequest)
+                .remote_peer(peer_id)
+                .epoch(self.epoch()),
+            "[EpochManager] receive {}", request,
+        );
+        let block_store = self.block_store.as_ref().context(
+            "[EpochManager] Unable to serve epoch starter kit, round manager not started",
+        )?;
+        let epoch_change_proof = if request.epoch < self.epoch() {
+            let proof = self
+                .storage
+                .aptos_db()
+                .get_epoch_ending_ledger_infos(request.epoch, self.epoch())
+                .map_err(DbError::from)
+                .context("[EpochManager] Failed to get epoch proof")?;
+            Some(proof)
+        } else {
+            None
+        };
+        let max_num_blocks = std::cmp::min(
+            request.max_num_blocks,
+            self.config.epoch_starter_kit.max_num_blocks,
+        );
+        let starter_kit = block_store.create_epoch_starter_kit(epoch_change_proof, max_num_blocks);
+        let msg = ConsensusMsg::EpochStarterKit(Box::new(starter_kit));
+        if let Err(err) = self.network_sender.send_to(peer_id, msg) {
+            warn!(
+                "[EpochManager] Failed to send epoch starter kit to {}, with error: {:?}",
+                peer_id, err,
+            );
+        }
+        Ok(())
+    }
+
+    async fn process_epoch_starter_kit(
+        &mut self,
+        starter_kit: EpochStarterKit,
+        peer_id: AccountAddress,
+    ) -> anyhow::Result<()> {
+        info!(
+            LogSchema::new(LogEvent::ReceiveEpochStarterKit)
+                .remote_peer(peer_id)
+                .epoch(self.epoch()),
+            "[EpochManager] receive {}", starter_kit,
+        );
+        // Epoch starter kits are only requested (and used) in recovery mode
+        if !self.recovery_mode {
+            counters::EPOCH_MANAGER_ISSUES_DETAILS
+                .with_label_values(&["unexpected_epoch_starter_kit"])
+                .inc();
+            return Ok(());
+        }
+
+        match starter_kit.epoch_change_proof() {
+            Some(proof) => {
+                // Join the latest epoch first, and apply the kit once the epoch starts
+                let proof = proof.clone();
+                ensure!(
+                    proof.epoch()? == self.epoch(),
+                    "[EpochManager] Unexpected starter kit proof from epoch {}, local epoch {}",
+                    proof.epoch()?,
+                    self.epoch()
+                );
+                self.pending_epoch_starter_kit = Some((peer_id, starter_kit));
+                monitor!("process_epoch_proof", self.initiate_new_epoch(proof).await)
+            },
+            None => {
+                ensure!(
+                    starter_kit.epoch() == self.epoch(),
+                    "[EpochManager] Unexpected epoch starter kit from epoch {}, local epoch {}",
+                    starter_kit.epoch(),
+                    self.epoch()
+                );
+                self.forward_epoch_starter_kit(peer_id, starter_kit)
+            },
+        }
+    }
+
+    /// Bootstraps the recovery manager using an epoch starter kit. If a kit was already
+    /// received for the current epoch, it is forwarded to the recovery manager. Otherwise,
+    /// the kit is requested from (random) peers in the epoch.
+    fn bootstrap_with_epoch_starter_kit(
+        &self,
+        pending_epoch_starter_kit: Option<(Author, EpochStarterKit)>,
+    ) {
+        if !self.config.epoch_starter_kit.enable
+            || !self.message_gating.is_epoch_starter_kit_enabled()
+        {
+            return;
+        }
+
+        if let Some((peer_id, starter_kit)) = pending_epoch_starter_kit {
+            if starter_kit.epoch() == self.epoch() {
+                match self.forward_epoch_starter_kit(peer_id, starter_kit) {
+                    Ok(()) => return,
+                    Err(error) => {
+                        warn!(
+                            "[EpochManager] Failed to forward epoch starter kit: {}",
+                            error
+                        )
+                    },
+                }
+            }
+        }
+
+        let request = EpochStarterKitRequest {
+            epoch: self.epoch(),
+            max_num_blocks: self.config.epoch_starter_kit.max_num_blocks,
+        };
+        let peers = self
+            .epoch_state()
+            .verifier
+            .get_ordered_account_addresses_iter()
+            .filter(|peer_id| *peer_id != self.author)
+            .choose_multiple(
+                &mut thread_rng(),
+                self.config.epoch_starter_kit.num_peers_to_request,
+            );
+        info!(
+            epoch = self.epoch(),
+            "[EpochManager] Requesting epoch starter kit from {:?}", peers
+        );
+        let msg = ConsensusMsg::EpochStarterKitRequest(Box::new(request));
+        if let Err(err) = self.network_sender.send_to_many(peers.into_iter(), msg) {
+            warn!(
+                "[EpochManager] Failed to send epoch starter kit request, {:?}",
+                err
+            );
+            counters::EPOCH_MANAGER_ISSUES_DETAILS
+                .with_label_values(&["failed_to_send_epoch_starter_kit_request"])
+                .inc();
+        }
+    }
+
+    fn forward_epoch_starter_kit(
+        &self,
+        peer_id: AccountAddress,
+        starter_kit: EpochStarterKit,
+    ) -> anyhow::Result<()> {
+        let event = VerifiedEvent::UnverifiedEpochStarterKit(Box::new(starter_kit));
+        Self::forward_event_to(
+            self.round_manager_tx.clone(),
+            (peer_id, discriminant(&event)),
+            (peer_id, event),
+        )
+        .context("recovery manager sender")
+    }
+
     async fn initiate_new_epoch(&mut self, proof: EpochChangeProof) -> anyhow::Result<()> {
         let ledger_info = proof
             .verify(self.epoch_state())
@@ -557,26 +757,48 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             1,
             Some(&counters::BLOCK_RETRIEVAL_TASK_MSGS),
         );
+        let (range_request_tx, mut range_request_rx) =
+            aptos_channel::new::<_, IncomingBlockRangeRetrievalRequest>(
+                QueueStyle::LIFO,
+                1,
+                Some(&counters::BLOCK_RETRIEVAL_TASK_MSGS),
+            );
+        let max_range_response_bytes = self.config.block_range_retrieval.max_response_bytes;
         let task = async move {
             info!(epoch = epoch, "Block retrieval task starts");
-            while let Some(request) = request_rx.next().await {
-                if request.req.num_blocks() > max_blocks_allowed {
-                    warn!(
-                        "Ignore block retrieval with too many blocks: {}",
-                        request.req.num_blocks()
-                    );
-                    continue;
-                }
-                if let Err(e) = monitor!(
-                    "process_block_retrieval",
-                    block_store.process_block_retrieval(request).await
-                ) {
+            loop {
+                let result = tokio::select! {
+                    Some(request) = request_rx.next() => {
+                        if request.req.num_blocks() > max_blocks_allowed {
+                            warn!(
+                                "Ignore block retrieval with too many blocks: {}",
+                                request.req.num_blocks()
+                            );
+                            continue;
+                        }
+                        monitor!(
+                            "process_block_retrieval",
+                            block_store.process_block_retrieval(request).await
+                        )
+                    },
+                    Some(request) = range_request_rx.next() => {
+                        monitor!(
+                            "process_block_range_retrieval",
+                            block_store
+                                .process_block_range_retrieval(request, max_range_response_bytes)
+                                .await
+                        )
+                    },
+                    else => break,
+                };
+                if let Err(e) = result {
                     warn!(epoch = epoch, error = ?e, kind = error_kind(&e));
                 }
             }
             info!(epoch = epoch, "Block retrieval task stops");
         };
         self.block_retrieval_tx = Some(request_tx);
+        self.block_range_retrieval_tx = Some(range_request_tx);
         tokio::spawn(task);
     }
 
@@ -592,6 +814,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                 .expect("[EpochManager] Fail to drop round manager");
         }
         self.round_manager_tx = None;
+        self.processed_msgs = None;
 
         if let Some(close_tx) = self.dag_shutdown_tx.take() {
             // Release the previous RoundManager, especially the SafetyRule client
@@ -612,7 +835,9 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         self.execution_client.end_epoch().await;
 
         // Shutdown the block retrieval task by dropping the sender
+        self.block_store = None;
         self.block_retrieval_tx = None;
+        self.block_range_retrieval_tx = None;
         self.batch_retrieval_tx = None;
 
         if let Some(mut quorum_store_coordinator_tx) = self.quorum_store_coordinator_tx.take() {
@@ -648,6 +873,9 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             ledger_data.committed_round(),
             self.config
                 .max_blocks_per_sending_request(onchain_consensus_config.quorum_store_enabled()),
+            self.config
+                .block_range_retrieval
+                .max_request_response_bytes(),
             self.payload_manager.clone(),
         );
         tokio::spawn(recovery_manager.start(recovery_manager_rx, close_rx));
@@ -868,6 +1096,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         );
 
         round_manager.init(last_vote).await;
+        self.processed_msgs = Some(round_manager.processed_msgs());
 
         let (close_tx, close_rx) = oneshot::channel();
         self.round_manager_close_tx = Some(close_tx);
@@ -878,6 +1107,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             close_rx,
         ));
 
+        self.block_store = Some(block_store.clone());
         self.spawn_block_retrieval_task(epoch, block_store, max_blocks_allowed);
     }
 
@@ -897,6 +1127,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             self.self_sender.clone(),
             epoch_state.verifier.clone(),
         )
+        .with_message_gating(self.message_gating.clone())
     }
 
     fn try_get_rand_config_for_new_epoch(
@@ -987,7 +1218,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             self.rand_storage
                 .save_key_pair_bytes(
                     new_epoch,
-                    bcs::to_bytes(&(augmented_key_pair.clone(), fast_augmented_key_pair.clone()))
+                    bcs::to_bytes(&(&augmented_key_pair, &fast_augmented_key_pair))
                         .map_err(NoRandomnessReason::KeyPairSerializationError)?,
                 )
                 .map_err(NoRandomnessReason::KeyPairPersistError)?;
@@ -1100,8 +1331,14 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             epoch_state.epoch, rand_config, fast_rand_config
         );
 
+        let features = payload.get::<Features>().unwrap_or_default();
         let (network_sender, payload_client, payload_manager) = self
-            .initialize_shared_component(&epoch_state, &consensus_config)
+            .initialize_shared_component(
+                &epoch_state,
+                &consensus_config,
+                &onchain_randomness_config,
+                &features,
+            )
             .await;
 
         let (rand_msg_tx, rand_msg_rx) = aptos_channel::new::<AccountAddress, IncomingRandGenRequest>(
@@ -1149,19 +1386,39 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         &mut self,
         epoch_state: &EpochState,
         consensus_config: &OnChainConsensusConfig,
+        onchain_randomness_config: &OnChainRandomnessConfig,
+        features: &Features,
     ) -> (NetworkSender, Arc<dyn PayloadClient>, Arc<PayloadManager>) {
         self.set_epoch_start_metrics(epoch_state);
         self.quorum_store_enabled = self.enable_quorum_store(consensus_config);
+        self.message_gating = Arc::new(ConsensusMsgGating::new(
+            self.quorum_store_enabled,
+            consensus_config,
+            onchain_randomness_config,
+            features,
+        ));
         let network_sender = self.create_network_sender(epoch_state);
         let (payload_manager, quorum_store_client, quorum_store_builder) = self
             .init_payload_provider(epoch_state, network_sender.clone(), consensus_config)
             .await;
         let effective_vtxn_config = consensus_config.effective_validator_txn_config();
         debug!("effective_vtxn_config={:?}", effective_vtxn_config);
+        let user_payload_client: Arc<dyn UserPayloadClient> =
+            if self.config.external_block_builder.enable {
+                Arc::new(ExternalBuilderClient::new_with_ipc_builder(
+                    &self.config.external_block_builder,
+                    Arc::new(quorum_store_client),
+                    self.quorum_store_enabled,
+                    epoch_state.verifier.clone(),
+                    self.proof_cache.clone(),
+                ))
+            } else {
+                Arc::new(quorum_store_client)
+            };
         let mixed_payload_client = MixedPayloadClient::new(
             effective_vtxn_config,
             Arc::new(self.vtxn_pool.clone()),
-            Arc::new(quorum_store_client),
+            user_payload_client,
         );
         self.start_quorum_store(quorum_store_builder);
         (
@@ -1185,6 +1442,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         fast_rand_config: Option<RandConfig>,
         rand_msg_rx: aptos_channel::Receiver<AccountAddress, IncomingRandGenRequest>,
     ) {
+        let pending_epoch_starter_kit = self.pending_epoch_starter_kit.take();
         match self.storage.start() {
             LivenessStorageData::FullRecoveryData(initial_data) => {
                 self.recovery_mode = false;
@@ -1212,7 +1470,8 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                     epoch_state,
                     Arc::new(network_sender),
                 )
-                .await
+                .await;
+                self.bootstrap_with_epoch_starter_kit(pending_epoch_starter_kit);
             },
         }
     }
@@ -1325,6 +1584,9 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                 BlockStage::EPOCH_MANAGER_RECEIVED,
             );
         }
+        // only messages of the current epoch are gated (i.e., after the epoch check)
+        let msg_enabled = self.message_gating.is_enabled(&consensus_msg);
+        let msg_name = consensus_msg.name();
         // we can't verify signatures from a different epoch
         let maybe_unverified_event = self.check_epoch(peer_id, consensus_msg).await?;
 
@@ -1335,6 +1597,20 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                 Ok(false) => return Ok(()), // This occurs when the quorum store is not enabled, but the recovery mode is enabled. We filter out the messages, but don't raise any error.
                 Err(err) => return Err(err),
             }
+            // filter out messages that are not enabled in the current epoch
+            if !msg_enabled {
+                self.message_gating.record_gated_receive(&msg_name);
+                return Ok(());
+            }
+            // drop the proposals and votes that were already processed, before paying for
+            // their signature verification
+            if let (Some(processed_msgs), Some(processed_msg_key)) =
+                (&self.processed_msgs, unverified_event.processed_msg_key())
+            {
+                if processed_msgs.lock().is_duplicate(&processed_msg_key) {
+                    return Ok(());
+                }
+            }
             // same epoch -> run well-formedness + signature check
             let epoch_state = self.epoch_state.clone().unwrap();
             let proof_cache = self.proof_cache.clone();
@@ -1400,7 +1676,8 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             | ConsensusMsg::BatchMsg(_)
             | ConsensusMsg::BatchRequestMsg(_)
             | ConsensusMsg::SignedBatchInfo(_)
-            | ConsensusMsg::ProofOfStoreMsg(_) => {
+            | ConsensusMsg::ProofOfStoreMsg(_)
+            | ConsensusMsg::BatchQueueLengthHintMsg(_) => {
                 let event: UnverifiedEvent = msg.into();
                 if event.epoch()? == self.epoch() {
                     return Ok(Some(event));
@@ -1443,6 +1720,31 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                     self.process_epoch_retrieval(*request, peer_id)
                 )?;
             },
+            ConsensusMsg::EpochStarterKitRequest(request) => {
+                if !self.message_gating.is_epoch_starter_kit_enabled() {
+                    self.message_gating
+                        .record_gated_receive("EpochStarterKitRequest");
+                    return Ok(None);
+                }
+                ensure!(
+                    request.epoch <= self.epoch(),
+                    "[EpochManager] Received EpochStarterKitRequest beyond what we have locally"
+                );
+                monitor!(
+                    "process_epoch_starter_kit_request",
+                    self.process_epoch_starter_kit_request(*request, peer_id)
+                )?;
+            },
+            ConsensusMsg::EpochStarterKit(starter_kit) => {
+                if !self.message_gating.is_epoch_starter_kit_enabled() {
+                    self.message_gating.record_gated_receive("EpochStarterKit");
+                    return Ok(None);
+                }
+                monitor!(
+                    "process_epoch_starter_kit",
+                    self.process_epoch_starter_kit(*starter_kit, peer_id).await
+                )?;
+            },
             _ => {
                 bail!("[EpochManager] Unexpected messages: {:?}", msg);
             },
@@ -1458,7 +1760,8 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         match event {
             UnverifiedEvent::BatchMsg(_)
             | UnverifiedEvent::SignedBatchInfo(_)
-            | UnverifiedEvent::ProofOfStoreMsg(_) => {
+            | UnverifiedEvent::ProofOfStoreMsg(_)
+            | UnverifiedEvent::BatchQueueLengthHintMsg(_) => {
                 if self.quorum_store_enabled {
                     Ok(true) // This states that we shouldn't filter out the event
                 } else if self.recovery_mode {
@@ -1505,7 +1808,8 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
         if let Err(e) = match event {
             quorum_store_event @ (VerifiedEvent::SignedBatchInfo(_)
             | VerifiedEvent::ProofOfStoreMsg(_)
-            | VerifiedEvent::BatchMsg(_)) => {
+            | VerifiedEvent::BatchMsg(_)
+            | VerifiedEvent::BatchQueueLengthHintMsg(_)) => {
                 Self::forward_event_to(quorum_store_msg_tx, peer_id, quorum_store_event)
                     .context("quorum store sender")
             },
@@ -1548,11 +1852,19 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                 return Ok(());
             },
             None => {
-                ensure!(matches!(request, IncomingRpcRequest::BlockRetrieval(_)));
+                ensure!(matches!(
+                    request,
+                    IncomingRpcRequest::BlockRetrieval(_)
+                        | IncomingRpcRequest::BlockRangeRetrieval(_)
+                ));
             },
             _ => {},
         }
 
+        if !self.message_gating.check_receive_rpc(&request) {
+            return Ok(());
+        }
+
         match request {
             IncomingRpcRequest::BlockRetrieval(request) => {
                 if let Some(tx) = &self.block_retrieval_tx {
@@ -1562,6 +1874,14 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
                     Ok(())
                 }
             },
+            IncomingRpcRequest::BlockRangeRetrieval(request) => {
+                if let Some(tx) = &self.block_range_retrieval_tx {
+                    tx.push(peer_id, request)
+                } else {
+                    error!("Round manager not started");
+                    Ok(())
+                }
+            },
             IncomingRpcRequest::BatchRetrieval(request) => {
                 if let Some(tx) = &self.batch_retrieval_tx {
                     tx.push(peer_id, request)
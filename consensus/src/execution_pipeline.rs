@@ -5,28 +5,41 @@
 
 use crate::{
     block_preparer::BlockPreparer,
-    monitor,
+    counters, monitor,
     state_computer::{PipelineExecutionResult, StateComputeResultFut},
 };
-use aptos_consensus_types::block::Block;
+use aptos_config::config::{OptimisticExecutionConfig, StatePrefetchConfig};
+use aptos_consensus_types::{block::Block, common::Round};
 use aptos_crypto::HashValue;
 use aptos_executor_types::{
     state_checkpoint_output::StateCheckpointOutput, BlockExecutorTrait, ExecutorError,
     ExecutorResult,
 };
 use aptos_experimental_runtimes::thread_manager::optimal_min_len;
-use aptos_logger::{debug, error};
+use aptos_infallible::Mutex;
+use aptos_logger::{debug, error, warn};
 use aptos_types::{
+    access_path::AccessPath,
+    account_config::{AccountResource, CoinStoreResource},
     block_executor::{config::BlockExecutorConfigFromOnchain, partitioner::ExecutableBlock},
     block_metadata_ext::BlockMetadataExt,
+    state_store::state_key::StateKey,
     transaction::{
         signature_verified_transaction::SignatureVerifiedTransaction, SignedTransaction,
+        TransactionPayload,
     },
 };
 use fail::fail_point;
+use move_core_types::{account_address::AccountAddress, move_resource::MoveStructType};
 use once_cell::sync::Lazy;
 use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use tokio::sync::{mpsc, oneshot};
 
 pub static SIG_VERIFY_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
@@ -39,18 +52,49 @@ pub static SIG_VERIFY_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
     )
 });
 
+// Labels for the speculative execution outcomes
+const SPECULATIVE_EXECUTION_QUEUED: &str = "queued";
+const SPECULATIVE_EXECUTION_SKIPPED_TOO_LARGE: &str = "skipped_too_large";
+const SPECULATIVE_EXECUTION_SKIPPED_LIMIT: &str = "skipped_limit";
+const SPECULATIVE_EXECUTION_USED: &str = "used";
+const SPECULATIVE_EXECUTION_FAILED: &str = "failed";
+const SPECULATIVE_EXECUTION_DISCARDED: &str = "discarded";
+
+// Labels for the state prefetch outcomes
+const STATE_PREFETCH_COMPLETED: &str = "completed"; // Completed before the block's execution
+const STATE_PREFETCH_LATE: &str = "late"; // Completed after the block's execution started
+const STATE_PREFETCH_SKIPPED_LATE: &str = "skipped_late";
+const STATE_PREFETCH_FAILED: &str = "failed";
+
 pub struct ExecutionPipeline {
     prepare_block_tx: mpsc::UnboundedSender<PrepareBlockCommand>,
+    optimistic_execution_config: OptimisticExecutionConfig,
+    // The (pending) results of the blocks that were executed before being ordered
+    speculative_executions: Mutex<HashMap<HashValue, SpeculativeExecution>>,
 }
 
 impl ExecutionPipeline {
-    pub fn spawn(executor: Arc<dyn BlockExecutorTrait>, runtime: &tokio::runtime::Handle) -> Self {
+    pub fn spawn(
+        executor: Arc<dyn BlockExecutorTrait>,
+        runtime: &tokio::runtime::Handle,
+        optimistic_execution_config: OptimisticExecutionConfig,
+        state_prefetch_config: StatePrefetchConfig,
+    ) -> Self {
         let (prepare_block_tx, prepare_block_rx) = mpsc::unbounded_channel();
         let (execute_block_tx, execute_block_rx) = mpsc::unbounded_channel();
         let (ledger_apply_tx, ledger_apply_rx) = mpsc::unbounded_channel();
+        let prefetch_tx = if state_prefetch_config.enable {
+            let (prefetch_tx, prefetch_rx) = mpsc::unbounded_channel();
+            runtime.spawn(Self::prefetch_stage(prefetch_rx, executor.clone()));
+            Some(prefetch_tx)
+        } else {
+            None
+        };
         runtime.spawn(Self::prepare_block_stage(
             prepare_block_rx,
             execute_block_tx,
+            prefetch_tx,
+            state_prefetch_config.max_prefetch_keys_per_block,
         ));
         runtime.spawn(Self::execute_stage(
             execute_block_rx,
@@ -58,7 +102,112 @@ impl ExecutionPipeline {
             executor.clone(),
         ));
         runtime.spawn(Self::ledger_apply_stage(ledger_apply_rx, executor));
-        Self { prepare_block_tx }
+        Self {
+            prepare_block_tx,
+            optimistic_execution_config,
+            speculative_executions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Speculatively executes the given (verified) proposal before it is ordered, so that the
+    /// execution latency is taken off the critical path. The result is used if the block is
+    /// later ordered on top of the same parent, and discarded otherwise.
+    pub fn queue_speculative(
+        &self,
+        block: Block,
+        metadata: BlockMetadataExt,
+        parent_block_id: HashValue,
+        txn_generator: BlockPreparer,
+        block_executor_onchain_config: BlockExecutorConfigFromOnchain,
+    ) {
+        let config = &self.optimistic_execution_config;
+        if !config.enable {
+            return;
+        }
+
+        // Bound the memory used by speculative execution
+        let num_txns = block.payload().map_or(0, |payload| payload.len());
+        if num_txns > config.max_speculative_block_txns {
+            Self::observe_speculative_execution(SPECULATIVE_EXECUTION_SKIPPED_TOO_LARGE);
+            return;
+        }
+        let mut speculative_executions = self.speculative_executions.lock();
+        if speculative_executions.contains_key(&block.id()) {
+            return; // The block is already being executed
+        }
+        if speculative_executions.len() >= config.max_speculative_blocks {
+            Self::observe_speculative_execution(SPECULATIVE_EXECUTION_SKIPPED_LIMIT);
+            return;
+        }
+
+        // Send the block through the pipeline, and keep the receiver for the result
+        let (result_tx, result_rx) = oneshot::channel();
+        let block_id = block.id();
+        let round = block.round();
+        self.prepare_block_tx
+            .send(PrepareBlockCommand {
+                block,
+                metadata,
+                block_executor_onchain_config,
+                parent_block_id,
+                block_preparer: txn_generator,
+                speculative_result_rx: None,
+                result_tx,
+            })
+            .expect("Failed to send block to execution pipeline.");
+        speculative_executions.insert(block_id, SpeculativeExecution {
+            round,
+            parent_block_id,
+            result_rx,
+        });
+        Self::observe_speculative_execution(SPECULATIVE_EXECUTION_QUEUED);
+    }
+
+    /// Removes and returns the speculative execution result receiver for the given ordered
+    /// block (if the block was speculatively executed on top of the same parent). Speculative
+    /// executions of other blocks at the same or lower rounds can no longer be ordered, so
+    /// they are discarded.
+    fn take_speculative_execution(
+        &self,
+        block: &Block,
+        parent_block_id: HashValue,
+    ) -> Option<oneshot::Receiver<ExecutorResult<PipelineExecutionResult>>> {
+        let mut speculative_executions = self.speculative_executions.lock();
+        let speculative_execution = speculative_executions.remove(&block.id());
+        speculative_executions.retain(|_, speculative_execution| {
+            let retain = speculative_execution.round > block.round();
+            if !retain {
+                Self::observe_speculative_execution(SPECULATIVE_EXECUTION_DISCARDED);
+            }
+            retain
+        });
+
+        match speculative_execution {
+            Some(speculative_execution)
+                if speculative_execution.parent_block_id == parent_block_id =>
+            {
+                Some(speculative_execution.result_rx)
+            },
+            Some(_) => {
+                Self::observe_speculative_execution(SPECULATIVE_EXECUTION_DISCARDED);
+                None
+            },
+            None => None,
+        }
+    }
+
+    /// Discards all speculative execution results (e.g., on epoch change or state sync)
+    pub fn discard_speculative_executions(&self) {
+        let mut speculative_executions = self.speculative_executions.lock();
+        for _ in speculative_executions.drain() {
+            Self::observe_speculative_execution(SPECULATIVE_EXECUTION_DISCARDED);
+        }
+    }
+
+    fn observe_speculative_execution(outcome: &str) {
+        counters::SPECULATIVE_EXECUTIONS
+            .with_label_values(&[outcome])
+            .inc();
     }
 
     pub async fn queue(
@@ -71,6 +220,7 @@ impl ExecutionPipeline {
     ) -> StateComputeResultFut {
         let (result_tx, result_rx) = oneshot::channel();
         let block_id = block.id();
+        let speculative_result_rx = self.take_speculative_execution(&block, parent_block_id);
         self.prepare_block_tx
             .send(PrepareBlockCommand {
                 block,
@@ -78,6 +228,7 @@ impl ExecutionPipeline {
                 block_executor_onchain_config,
                 parent_block_id,
                 block_preparer: txn_generator,
+                speculative_result_rx,
                 result_tx,
             })
             .expect("Failed to send block to execution pipeline.");
@@ -96,6 +247,8 @@ impl ExecutionPipeline {
 
     async fn prepare_block(
         execute_block_tx: mpsc::UnboundedSender<ExecuteBlockCommand>,
+        prefetch_tx: Option<mpsc::UnboundedSender<PrefetchCommand>>,
+        max_prefetch_keys: usize,
         command: PrepareBlockCommand,
     ) {
         let PrepareBlockCommand {
@@ -104,9 +257,38 @@ impl ExecutionPipeline {
             block_executor_onchain_config,
             parent_block_id,
             block_preparer,
+            speculative_result_rx,
             result_tx,
         } = command;
 
+        // If the block was speculatively executed, use the result (if it succeeded). Note: the
+        // speculative execution was queued earlier, so waiting for it here preserves the order
+        // of the blocks in the pipeline (in case the block must be re-executed).
+        if let Some(speculative_result_rx) = speculative_result_rx {
+            match speculative_result_rx.await {
+                Ok(Ok(result)) => {
+                    Self::observe_speculative_execution(SPECULATIVE_EXECUTION_USED);
+                    result_tx.send(Ok(result)).unwrap_or_else(|err| {
+                        error!(
+                            block_id = block.id(),
+                            "Failed to send back execution result for block {}: {:?}.",
+                            block.id(),
+                            err,
+                        );
+                    });
+                    return;
+                },
+                result => {
+                    Self::observe_speculative_execution(SPECULATIVE_EXECUTION_FAILED);
+                    debug!(
+                        "Speculative execution of block {} failed: {:?}. Re-executing.",
+                        block.id(),
+                        result.map(|result| result.map(|_| ())),
+                    );
+                },
+            }
+        }
+
         debug!("prepare_block received block {}.", block.id());
         let input_txns = block_preparer.prepare_block(&block).await;
         if let Err(e) = input_txns {
@@ -122,6 +304,19 @@ impl ExecutionPipeline {
         }
         let validator_txns = block.validator_txns().cloned().unwrap_or_default();
         let input_txns = input_txns.unwrap();
+
+        // Prefetch the state read by the transactions (while the earlier blocks are executing)
+        let execution_started = Arc::new(AtomicBool::new(false));
+        if let Some(prefetch_tx) = prefetch_tx {
+            let state_keys = get_prefetch_state_keys(&input_txns, max_prefetch_keys);
+            if !state_keys.is_empty() {
+                let _ = prefetch_tx.send(PrefetchCommand {
+                    block_id: block.id(),
+                    state_keys,
+                    execution_started: execution_started.clone(),
+                });
+            }
+        }
         tokio::task::spawn_blocking(move || {
             let txns_to_execute =
                 Block::combine_to_input_transactions(validator_txns, input_txns.clone(), metadata);
@@ -140,6 +335,7 @@ impl ExecutionPipeline {
                     block: (block.id(), sig_verified_txns).into(),
                     parent_block_id,
                     block_executor_onchain_config,
+                    execution_started,
                     result_tx,
                 })
                 .expect("Failed to send block to execution pipeline.");
@@ -151,16 +347,75 @@ impl ExecutionPipeline {
     async fn prepare_block_stage(
         mut prepare_block_rx: mpsc::UnboundedReceiver<PrepareBlockCommand>,
         execute_block_tx: mpsc::UnboundedSender<ExecuteBlockCommand>,
+        prefetch_tx: Option<mpsc::UnboundedSender<PrefetchCommand>>,
+        max_prefetch_keys: usize,
     ) {
         while let Some(command) = prepare_block_rx.recv().await {
             monitor!(
                 "prepare_block",
-                Self::prepare_block(execute_block_tx.clone(), command).await
+                Self::prepare_block(
+                    execute_block_tx.clone(),
+                    prefetch_tx.clone(),
+                    max_prefetch_keys,
+                    command
+                )
+                .await
             );
         }
         debug!("prepare_block_stage quitting.");
     }
 
+    /// Prefetches the state of the blocks (in order) until each block's execution starts.
+    /// Prefetches that would only start after the block's execution are skipped.
+    async fn prefetch_stage(
+        mut prefetch_rx: mpsc::UnboundedReceiver<PrefetchCommand>,
+        executor: Arc<dyn BlockExecutorTrait>,
+    ) {
+        while let Some(PrefetchCommand {
+            block_id,
+            state_keys,
+            execution_started,
+        }) = prefetch_rx.recv().await
+        {
+            if execution_started.load(Ordering::Acquire) {
+                Self::observe_state_prefetch(STATE_PREFETCH_SKIPPED_LATE);
+                continue;
+            }
+
+            let executor = executor.clone();
+            let result = monitor!(
+                "prefetch_state",
+                tokio::task::spawn_blocking(move || executor.prefetch_state_values(state_keys))
+                    .await
+            )
+            .expect("Failed to spawn_blocking.");
+            match result {
+                Ok(num_keys) => {
+                    counters::STATE_PREFETCHED_KEYS.inc_by(num_keys as u64);
+                    if execution_started.load(Ordering::Acquire) {
+                        Self::observe_state_prefetch(STATE_PREFETCH_LATE);
+                    } else {
+                        Self::observe_state_prefetch(STATE_PREFETCH_COMPLETED);
+                    }
+                },
+                Err(error) => {
+                    Self::observe_state_prefetch(STATE_PREFETCH_FAILED);
+                    warn!(
+                        "Failed to prefetch the state for block {}: {:?}",
+                        block_id, error
+                    );
+                },
+            }
+        }
+        debug!("prefetch_stage quitting.");
+    }
+
+    fn observe_state_prefetch(outcome: &str) {
+        counters::STATE_PREFETCHES
+            .with_label_values(&[outcome])
+            .inc();
+    }
+
     async fn execute_stage(
         mut block_rx: mpsc::UnboundedReceiver<ExecuteBlockCommand>,
         ledger_apply_tx: mpsc::UnboundedSender<LedgerApplyCommand>,
@@ -171,11 +426,13 @@ impl ExecutionPipeline {
             block,
             parent_block_id,
             block_executor_onchain_config,
+            execution_started,
             result_tx,
         }) = block_rx.recv().await
         {
             let block_id = block.block_id;
             debug!("execute_stage received block {}.", block_id);
+            execution_started.store(true, Ordering::Release);
             let executor = executor.clone();
             let state_checkpoint_output = monitor!(
                 "execute_block",
@@ -252,17 +509,33 @@ struct PrepareBlockCommand {
     // The parent block id.
     parent_block_id: HashValue,
     block_preparer: BlockPreparer,
+    // The pending result of the speculative execution of the block (if any)
+    speculative_result_rx: Option<oneshot::Receiver<ExecutorResult<PipelineExecutionResult>>>,
     result_tx: oneshot::Sender<ExecutorResult<PipelineExecutionResult>>,
 }
 
+struct SpeculativeExecution {
+    round: Round,
+    parent_block_id: HashValue,
+    result_rx: oneshot::Receiver<ExecutorResult<PipelineExecutionResult>>,
+}
+
 struct ExecuteBlockCommand {
     input_txns: Vec<SignedTransaction>,
     block: ExecutableBlock,
     parent_block_id: HashValue,
     block_executor_onchain_config: BlockExecutorConfigFromOnchain,
+    // Set once the execution of the block starts (i.e., prefetching is no longer useful)
+    execution_started: Arc<AtomicBool>,
     result_tx: oneshot::Sender<ExecutorResult<PipelineExecutionResult>>,
 }
 
+struct PrefetchCommand {
+    block_id: HashValue,
+    state_keys: Vec<StateKey>,
+    execution_started: Arc<AtomicBool>,
+}
+
 struct LedgerApplyCommand {
     input_txns: Vec<SignedTransaction>,
     block_id: HashValue,
@@ -270,3 +543,64 @@ struct LedgerApplyCommand {
     state_checkpoint_output: ExecutorResult<StateCheckpointOutput>,
     result_tx: oneshot::Sender<ExecutorResult<PipelineExecutionResult>>,
 }
+
+/// Returns the (deduplicated) state keys that the given transactions are expected to read,
+/// i.e., the sender accounts and the modules called by entry functions. At most
+/// `max_keys` keys are returned.
+fn get_prefetch_state_keys(txns: &[SignedTransaction], max_keys: usize) -> Vec<StateKey> {
+    let mut state_keys = HashSet::new();
+    let mut ordered_state_keys = vec![];
+    let mut add_state_key = |state_key: StateKey| {
+        if ordered_state_keys.len() < max_keys && state_keys.insert(state_key.clone()) {
+            ordered_state_keys.push(state_key);
+        }
+    };
+
+    for txn in txns {
+        let sender = txn.sender();
+        for state_key in [
+            get_resource_state_key::<AccountResource>(sender),
+            get_resource_state_key::<CoinStoreResource>(sender),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            add_state_key(state_key);
+        }
+        if let TransactionPayload::EntryFunction(entry_function) = txn.payload() {
+            add_state_key(StateKey::access_path(AccessPath::code_access_path(
+                entry_function.module().clone(),
+            )));
+        }
+    }
+    ordered_state_keys
+}
+
+/// Returns the state key of the given resource type under the given account
+fn get_resource_state_key<T: MoveStructType>(address: AccountAddress) -> Option<StateKey> {
+    AccessPath::resource_access_path(address, T::struct_tag())
+        .ok()
+        .map(StateKey::access_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        execution_pipeline::get_prefetch_state_keys, test_utils::create_vec_signed_transactions,
+    };
+
+    #[test]
+    fn test_get_prefetch_state_keys() {
+        // Each sender has an account and a coin store resource
+        let mut txns = create_vec_signed_transactions(10);
+        assert_eq!(get_prefetch_state_keys(&txns, 1000).len(), 20);
+
+        // Duplicate senders are only prefetched once
+        txns.extend(txns.clone());
+        assert_eq!(get_prefetch_state_keys(&txns, 1000).len(), 20);
+
+        // The number of keys is bounded
+        assert_eq!(get_prefetch_state_keys(&txns, 5).len(), 5);
+        assert!(get_prefetch_state_keys(&txns, 0).is_empty());
+    }
+}
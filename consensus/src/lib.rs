@@ -18,12 +18,14 @@ extern crate scopeguard;
 extern crate core;
 
 mod block_storage;
+pub mod consensus_observer;
 mod consensusdb;
 mod dag;
 mod epoch_manager;
 mod error;
 mod liveness;
 mod logging;
+mod message_gating;
 mod metrics_safety_rules;
 mod network;
 #[cfg(test)]
@@ -36,6 +38,9 @@ pub mod quorum_store;
 mod rand;
 mod recovery_manager;
 mod round_manager;
+mod startup_checks;
+#[cfg(test)]
+mod startup_checks_test;
 mod state_computer;
 #[cfg(test)]
 mod state_computer_tests;
@@ -56,6 +61,8 @@ mod execution_pipeline;
 /// AptosNet interface.
 pub mod network_interface;
 mod payload_manager;
+#[cfg(test)]
+mod payload_manager_test;
 mod qc_aggregator;
 mod transaction_deduper;
 mod transaction_filter;
@@ -66,6 +73,10 @@ use aptos_metrics_core::IntGauge;
 pub use consensusdb::create_checkpoint;
 /// Required by the smoke tests
 pub use consensusdb::CONSENSUS_DB_NAME;
+/// Required by the admin service
+pub use liveness::leader_reputation_report::{
+    LeaderReputationOverrides, LeaderReputationReport, LeaderReputationReporter, ProposerReputation,
+};
 pub use quorum_store::quorum_store_db::QUORUM_STORE_DB_NAME;
 #[cfg(feature = "fuzzing")]
 pub use round_manager::round_manager_fuzzing;
@@ -0,0 +1,128 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters;
+use aptos_config::config::ConsensusConfig;
+use aptos_consensus_types::common::Author;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+// Estimates the clock offsets of peers relative to the local clock, using the timestamps of
+// the proposals they author. Each sample is the difference between the proposal timestamp
+// and the local time at which the proposal was received, so it also includes the network
+// delay (i.e., the offsets of peers that are ahead of us are slightly underestimated).
+//
+// Votes are not used as they carry the timestamp of the proposed block, not the voter's clock.
+//
+// To be robust to outliers (and to byzantine proposers), the offset of each peer is the
+// median of its recent samples, and the fleet skew is the median of the peer offsets.
+pub struct ClockSkewEstimator {
+    window_size: usize,
+    min_samples: usize,
+    max_timestamp_tolerance: Duration,
+    // Recent clock offsets (in microseconds) of each peer, oldest first
+    peer_offsets: HashMap<Author, VecDeque<i64>>,
+}
+
+impl ClockSkewEstimator {
+    pub fn new(window_size: usize, min_samples: usize, max_timestamp_tolerance: Duration) -> Self {
+        Self {
+            window_size,
+            min_samples: min_samples.clamp(1, window_size.max(1)),
+            max_timestamp_tolerance,
+            peer_offsets: HashMap::new(),
+        }
+    }
+
+    pub fn from_config(config: &ConsensusConfig) -> Self {
+        Self::new(
+            config.clock_skew_window_size,
+            config.clock_skew_min_samples,
+            Duration::from_millis(config.max_proposal_timestamp_tolerance_ms),
+        )
+    }
+
+    // Records the timestamp of a message authored by the given peer, and the local time at
+    // which the message was received.
+    pub fn record_timestamp(
+        &mut self,
+        author: Author,
+        timestamp_usecs: u64,
+        local_timestamp: Duration,
+    ) {
+        if self.window_size == 0 {
+            return;
+        }
+
+        let offset_usecs = (timestamp_usecs as i128 - local_timestamp.as_micros() as i128)
+            .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+        let offsets = self.peer_offsets.entry(author).or_default();
+        if offsets.len() == self.window_size {
+            offsets.pop_front();
+        }
+        offsets.push_back(offset_usecs);
+
+        self.update_metrics();
+    }
+
+    // Returns the estimated clock offset of the given peer (in microseconds). A positive
+    // offset means that the peer's clock is ahead of the local clock.
+    pub fn peer_skew_usecs(&self, author: &Author) -> Option<i64> {
+        self.peer_offsets
+            .get(author)
+            .filter(|offsets| offsets.len() >= self.min_samples)
+            .map(|offsets| median(offsets.iter().copied().collect()))
+    }
+
+    // Returns the median of the estimated peer clock offsets (in microseconds)
+    pub fn fleet_skew_usecs(&self) -> Option<i64> {
+        let peer_skews = self.peer_skews_usecs();
+        if peer_skews.is_empty() {
+            None
+        } else {
+            Some(median(peer_skews))
+        }
+    }
+
+    // Returns the largest (absolute) estimated peer clock offset (in microseconds)
+    pub fn max_peer_skew_usecs(&self) -> Option<i64> {
+        self.peer_skews_usecs()
+            .into_iter()
+            .max_by_key(|skew| skew.unsigned_abs())
+    }
+
+    // Returns the extra time that proposal timestamps may be ahead of the local clock. This
+    // covers the fleet skew when the local clock is behind the majority of the peers (e.g.,
+    // during an NTP incident), and is bounded by the configured maximum tolerance.
+    pub fn timestamp_tolerance(&self) -> Duration {
+        let fleet_skew_usecs = self.fleet_skew_usecs().unwrap_or(0).max(0) as u64;
+        Duration::from_micros(fleet_skew_usecs).min(self.max_timestamp_tolerance)
+    }
+
+    fn peer_skews_usecs(&self) -> Vec<i64> {
+        self.peer_offsets
+            .keys()
+            .filter_map(|author| self.peer_skew_usecs(author))
+            .collect()
+    }
+
+    fn update_metrics(&self) {
+        if let Some(fleet_skew_usecs) = self.fleet_skew_usecs() {
+            counters::FLEET_CLOCK_SKEW_MS.set(fleet_skew_usecs / 1000);
+        }
+        if let Some(max_peer_skew_usecs) = self.max_peer_skew_usecs() {
+            counters::MAX_PEER_CLOCK_SKEW_MS.set(max_peer_skew_usecs / 1000);
+        }
+        counters::PROPOSAL_TIMESTAMP_TOLERANCE_MS
+            .set(self.timestamp_tolerance().as_millis() as i64);
+    }
+}
+
+// Returns the median of the given (non-empty) values. For an even number
+// of values, the lower of the two middle values is returned.
+fn median(mut values: Vec<i64>) -> i64 {
+    values.sort_unstable();
+    values[(values.len() - 1) / 2]
+}
@@ -0,0 +1,98 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::liveness::clock_skew_estimator::ClockSkewEstimator;
+use aptos_consensus_types::common::Author;
+use std::time::Duration;
+
+const LOCAL_TIMESTAMP: Duration = Duration::from_secs(1_000_000);
+
+// Records a message from the given peer with the given clock offset (in ms)
+fn record_offset(estimator: &mut ClockSkewEstimator, author: Author, offset_ms: i64) {
+    let timestamp_usecs = (LOCAL_TIMESTAMP.as_micros() as i64 + offset_ms * 1000) as u64;
+    estimator.record_timestamp(author, timestamp_usecs, LOCAL_TIMESTAMP);
+}
+
+#[test]
+fn test_peer_skew_requires_min_samples() {
+    let mut estimator = ClockSkewEstimator::new(10, 3, Duration::from_secs(10));
+    let author = Author::random();
+
+    // Not enough samples have been recorded
+    record_offset(&mut estimator, author, 500);
+    record_offset(&mut estimator, author, 500);
+    assert_eq!(estimator.peer_skew_usecs(&author), None);
+    assert_eq!(estimator.fleet_skew_usecs(), None);
+    assert_eq!(estimator.timestamp_tolerance(), Duration::ZERO);
+
+    // The skew is estimated once enough samples exist
+    record_offset(&mut estimator, author, 500);
+    assert_eq!(estimator.peer_skew_usecs(&author), Some(500_000));
+    assert_eq!(estimator.fleet_skew_usecs(), Some(500_000));
+}
+
+#[test]
+fn test_peer_skew_is_robust_to_outliers() {
+    let mut estimator = ClockSkewEstimator::new(5, 1, Duration::from_secs(10));
+    let author = Author::random();
+
+    // A single outlier does not affect the estimate
+    for offset_ms in [100, 120, 60_000, 110, 90] {
+        record_offset(&mut estimator, author, offset_ms);
+    }
+    assert_eq!(estimator.peer_skew_usecs(&author), Some(110_000));
+
+    // Old samples fall out of the window
+    for _ in 0..3 {
+        record_offset(&mut estimator, author, -200);
+    }
+    assert_eq!(estimator.peer_skew_usecs(&author), Some(-200_000));
+}
+
+#[test]
+fn test_fleet_skew_and_tolerance() {
+    let mut estimator = ClockSkewEstimator::new(10, 1, Duration::from_secs(10));
+
+    // Peers are behind the local clock (e.g., due to network delays)
+    let peers: Vec<_> = (0..4).map(|_| Author::random()).collect();
+    for peer in &peers {
+        record_offset(&mut estimator, *peer, -50);
+    }
+    assert_eq!(estimator.fleet_skew_usecs(), Some(-50_000));
+    assert_eq!(estimator.timestamp_tolerance(), Duration::ZERO);
+
+    // A single byzantine peer far in the future does not affect the tolerance
+    let byzantine_peer = Author::random();
+    record_offset(&mut estimator, byzantine_peer, 200_000);
+    assert_eq!(estimator.fleet_skew_usecs(), Some(-50_000));
+    assert_eq!(estimator.max_peer_skew_usecs(), Some(200_000_000));
+    assert_eq!(estimator.timestamp_tolerance(), Duration::ZERO);
+
+    // The local clock falls behind the majority of the peers (e.g., an NTP incident)
+    for peer in &peers[..3] {
+        for _ in 0..10 {
+            record_offset(&mut estimator, *peer, 2_000);
+        }
+    }
+    assert_eq!(estimator.fleet_skew_usecs(), Some(2_000_000));
+    assert_eq!(estimator.timestamp_tolerance(), Duration::from_secs(2));
+
+    // The tolerance is bounded by the configured maximum
+    for peer in &peers {
+        for _ in 0..10 {
+            record_offset(&mut estimator, *peer, 30_000);
+        }
+    }
+    assert_eq!(estimator.timestamp_tolerance(), Duration::from_secs(10));
+}
+
+#[test]
+fn test_disabled_estimator() {
+    let mut estimator = ClockSkewEstimator::new(0, 1, Duration::from_secs(10));
+    let author = Author::random();
+    for _ in 0..10 {
+        record_offset(&mut estimator, author, 5_000);
+    }
+    assert_eq!(estimator.peer_skew_usecs(&author), None);
+    assert_eq!(estimator.timestamp_tolerance(), Duration::ZERO);
+}
@@ -511,6 +511,24 @@ impl ProposerAndVoterHeuristic {
             ),
         }
     }
+
+    /// Returns the weight of a candidate with the given votes and (failed) proposals
+    pub(crate) fn get_weight(
+        &self,
+        num_votes: u32,
+        num_proposals: u32,
+        num_failed_proposals: u32,
+    ) -> u64 {
+        if num_failed_proposals * 100
+            > (num_proposals + num_failed_proposals) * self.failure_threshold_percent
+        {
+            self.failed_weight
+        } else if num_proposals > 0 || num_votes > 0 {
+            self.active_weight
+        } else {
+            self.inactive_weight
+        }
+    }
 }
 
 impl ReputationHeuristic for ProposerAndVoterHeuristic {
@@ -532,16 +550,7 @@ impl ReputationHeuristic for ProposerAndVoterHeuristic {
                 let cur_votes = *votes.get(author).unwrap_or(&0);
                 let cur_proposals = *proposals.get(author).unwrap_or(&0);
                 let cur_failed_proposals = *failed_proposals.get(author).unwrap_or(&0);
-
-                if cur_failed_proposals * 100
-                    > (cur_proposals + cur_failed_proposals) * self.failure_threshold_percent
-                {
-                    self.failed_weight
-                } else if cur_proposals > 0 || cur_votes > 0 {
-                    self.active_weight
-                } else {
-                    self.inactive_weight
-                }
+                self.get_weight(cur_votes, cur_proposals, cur_failed_proposals)
             })
             .collect()
     }
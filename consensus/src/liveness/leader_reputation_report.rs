@@ -0,0 +1,255 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! On-demand reports of the leader reputation state. A report contains the current reputation
+//! weights (and election probabilities) of all proposers in the epoch. Reports can also simulate
+//! a different window and weighting configuration, so that operators can tune proposer election
+//! without redeploying. Simulations never affect the actual proposer election.
+
+use crate::liveness::leader_reputation::{NewBlockEventAggregation, ProposerAndVoterHeuristic};
+use anyhow::{bail, ensure, Result};
+use aptos_consensus_types::common::{Author, Round};
+use aptos_infallible::RwLock;
+use aptos_storage_interface::DbReader;
+use aptos_types::{account_config::NewBlockEvent, on_chain_config::ProposerAndVoterConfig};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+/// The leader reputation state of the current epoch (required to generate reports)
+struct LeaderReputationContext {
+    author: Author,
+    epoch: u64,
+    epoch_to_proposers: HashMap<u64, Vec<Author>>,
+    voting_powers: Vec<u64>, // The voting powers of the proposers (in the current epoch)
+    config: ProposerAndVoterConfig,
+    reputation_window_from_stale_end: bool,
+    aptos_db: Arc<dyn DbReader>,
+}
+
+/// Overrides of the leader reputation configuration (used to simulate window
+/// and weighting changes). Fields that are not set use the on-chain value.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LeaderReputationOverrides {
+    pub proposer_window_num_validators_multiplier: Option<usize>,
+    pub voter_window_num_validators_multiplier: Option<usize>,
+    pub active_weight: Option<u64>,
+    pub inactive_weight: Option<u64>,
+    pub failed_weight: Option<u64>,
+    pub failure_threshold_percent: Option<u32>,
+    pub weight_by_voting_power: Option<bool>,
+}
+
+impl LeaderReputationOverrides {
+    /// Returns true iff no configuration values are overridden
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns the given config with the overrides applied
+    fn apply(&self, config: &ProposerAndVoterConfig) -> ProposerAndVoterConfig {
+        ProposerAndVoterConfig {
+            active_weight: self.active_weight.unwrap_or(config.active_weight),
+            inactive_weight: self.inactive_weight.unwrap_or(config.inactive_weight),
+            failed_weight: self.failed_weight.unwrap_or(config.failed_weight),
+            failure_threshold_percent: self
+                .failure_threshold_percent
+                .unwrap_or(config.failure_threshold_percent),
+            proposer_window_num_validators_multiplier: self
+                .proposer_window_num_validators_multiplier
+                .unwrap_or(config.proposer_window_num_validators_multiplier),
+            voter_window_num_validators_multiplier: self
+                .voter_window_num_validators_multiplier
+                .unwrap_or(config.voter_window_num_validators_multiplier),
+            weight_by_voting_power: self
+                .weight_by_voting_power
+                .unwrap_or(config.weight_by_voting_power),
+            use_history_from_previous_epoch_max_count: config
+                .use_history_from_previous_epoch_max_count,
+        }
+    }
+}
+
+/// A report of the leader reputation weights of all proposers in the epoch
+#[derive(Clone, Debug, Serialize)]
+pub struct LeaderReputationReport {
+    pub epoch: u64,
+    pub simulated: bool,
+    pub config: ProposerAndVoterConfig,
+    pub proposer_window_size: usize,
+    pub voter_window_size: usize,
+    /// The number of committed blocks considered by the report
+    pub num_history_blocks: usize,
+    /// The (epoch, round) of the latest committed block considered by the report
+    pub latest_block: Option<(u64, Round)>,
+    pub proposers: Vec<ProposerReputation>,
+}
+
+/// The reputation of a single proposer (computed over the history window)
+#[derive(Clone, Debug, Serialize)]
+pub struct ProposerReputation {
+    pub author: Author,
+    pub voting_power: u64,
+    pub num_proposals: u32,
+    pub num_failed_proposals: u32,
+    pub num_votes: u32,
+    pub reputation_weight: u64,
+    /// The probability of being elected as the proposer of a round
+    pub election_probability: f64,
+}
+
+/// A cheaply cloneable handle that generates leader reputation reports for the current epoch
+#[derive(Clone, Default)]
+pub struct LeaderReputationReporter {
+    context: Arc<RwLock<Option<LeaderReputationContext>>>,
+}
+
+impl LeaderReputationReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the leader reputation state for a new epoch
+    pub(crate) fn set_context(
+        &self,
+        author: Author,
+        epoch: u64,
+        epoch_to_proposers: HashMap<u64, Vec<Author>>,
+        voting_powers: Vec<u64>,
+        config: ProposerAndVoterConfig,
+        reputation_window_from_stale_end: bool,
+        aptos_db: Arc<dyn DbReader>,
+    ) {
+        *self.context.write() = Some(LeaderReputationContext {
+            author,
+            epoch,
+            epoch_to_proposers,
+            voting_powers,
+            config,
+            reputation_window_from_stale_end,
+            aptos_db,
+        });
+    }
+
+    /// Clears the leader reputation state (e.g., if the epoch doesn't use leader reputation)
+    pub(crate) fn clear_context(&self) {
+        *self.context.write() = None;
+    }
+
+    /// Generates a report using the latest committed history. If any overrides
+    /// are given, the report simulates the weights under the overridden config.
+    pub fn generate_report(
+        &self,
+        overrides: &LeaderReputationOverrides,
+    ) -> Result<LeaderReputationReport> {
+        let context = self.context.read();
+        let context = match context.as_ref() {
+            Some(context) => context,
+            None => bail!("Leader reputation is not used for proposer election!"),
+        };
+
+        // Calculate the window sizes
+        let config = overrides.apply(&context.config);
+        let proposers = &context.epoch_to_proposers[&context.epoch];
+        let proposer_window_size =
+            proposers.len() * config.proposer_window_num_validators_multiplier;
+        let voter_window_size = proposers.len() * config.voter_window_num_validators_multiplier;
+        ensure!(
+            proposer_window_size > 0 && voter_window_size > 0,
+            "The proposer and voter windows must be non-empty!"
+        );
+
+        // Fetch the latest history from storage
+        let history = fetch_latest_block_events(
+            context.aptos_db.as_ref(),
+            std::cmp::max(proposer_window_size, voter_window_size),
+        )?;
+
+        // Count the votes and proposals in the windows. Note: history from epochs
+        // that are not in the epoch to proposers map is ignored.
+        let aggregation = NewBlockEventAggregation::new(
+            voter_window_size,
+            proposer_window_size,
+            context.reputation_window_from_stale_end,
+        );
+        let votes = aggregation.count_votes(&context.epoch_to_proposers, &history);
+        let proposals = aggregation.count_proposals(&context.epoch_to_proposers, &history);
+        let failed_proposals =
+            aggregation.count_failed_proposals(&context.epoch_to_proposers, &history);
+
+        // Calculate the reputation weights of all proposers
+        let heuristic = ProposerAndVoterHeuristic::new(
+            context.author,
+            config.active_weight,
+            config.inactive_weight,
+            config.failed_weight,
+            config.failure_threshold_percent,
+            voter_window_size,
+            proposer_window_size,
+            context.reputation_window_from_stale_end,
+        );
+        let mut proposer_reputations: Vec<_> = proposers
+            .iter()
+            .zip(context.voting_powers.iter())
+            .map(|(author, voting_power)| {
+                let num_votes = *votes.get(author).unwrap_or(&0);
+                let num_proposals = *proposals.get(author).unwrap_or(&0);
+                let num_failed_proposals = *failed_proposals.get(author).unwrap_or(&0);
+                ProposerReputation {
+                    author: *author,
+                    voting_power: *voting_power,
+                    num_proposals,
+                    num_failed_proposals,
+                    num_votes,
+                    reputation_weight: heuristic.get_weight(
+                        num_votes,
+                        num_proposals,
+                        num_failed_proposals,
+                    ),
+                    election_probability: 0.0,
+                }
+            })
+            .collect();
+
+        // Calculate the election probabilities (i.e., the normalized stake weights)
+        let stake_weights: Vec<u128> = proposer_reputations
+            .iter()
+            .map(|reputation| {
+                let voting_power = if config.weight_by_voting_power {
+                    reputation.voting_power
+                } else {
+                    1
+                };
+                reputation.reputation_weight as u128 * voting_power as u128
+            })
+            .collect();
+        let total_stake_weight: u128 = stake_weights.iter().sum();
+        if total_stake_weight > 0 {
+            for (reputation, stake_weight) in proposer_reputations.iter_mut().zip(stake_weights) {
+                reputation.election_probability = stake_weight as f64 / total_stake_weight as f64;
+            }
+        }
+
+        Ok(LeaderReputationReport {
+            epoch: context.epoch,
+            simulated: !overrides.is_empty(),
+            config,
+            proposer_window_size,
+            voter_window_size,
+            num_history_blocks: history.len(),
+            latest_block: history.first().map(|event| (event.epoch(), event.round())),
+            proposers: proposer_reputations,
+        })
+    }
+}
+
+/// Fetches the latest block events from storage (ordered from newest to oldest)
+fn fetch_latest_block_events(
+    aptos_db: &dyn DbReader,
+    num_events: usize,
+) -> Result<Vec<NewBlockEvent>> {
+    aptos_db
+        .get_latest_block_events(num_events)?
+        .into_iter()
+        .map(|event| Ok(bcs::from_bytes::<NewBlockEvent>(event.event.event_data())?))
+        .collect()
+}
@@ -9,6 +9,9 @@ use crate::liveness::{
     leader_reputation::{
         LeaderReputation, MetadataBackend, NewBlockEventAggregation, ReputationHeuristic,
     },
+    leader_reputation_report::{
+        LeaderReputationOverrides, LeaderReputationReport, LeaderReputationReporter,
+    },
     proposer_election::{choose_index, ProposerElection},
 };
 use aptos_bitvec::BitVec;
@@ -22,6 +25,7 @@ use aptos_types::{
     account_config::{new_block_event_key, NewBlockEvent},
     contract_event::{ContractEvent, EventWithVersion},
     epoch_state::EpochState,
+    on_chain_config::ProposerAndVoterConfig,
     transaction::Version,
     validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
 };
@@ -275,6 +279,108 @@ fn test_proposer_and_voter_heuristic() {
     );
 }
 
+#[test]
+fn test_leader_reputation_report() {
+    let mut example1 = Example1::new(5);
+    let validators0 = example1.validators0.clone();
+    let config = ProposerAndVoterConfig {
+        active_weight: 100,
+        inactive_weight: 10,
+        failed_weight: 1,
+        failure_threshold_percent: 49,
+        proposer_window_num_validators_multiplier: 2,
+        voter_window_num_validators_multiplier: 1,
+        weight_by_voting_power: false,
+        use_history_from_previous_epoch_max_count: 0,
+    };
+
+    // Verify that reports can't be generated without leader reputation
+    let reporter = LeaderReputationReporter::new();
+    assert_err!(reporter.generate_report(&LeaderReputationOverrides::default()));
+
+    // Set the leader reputation context and add history
+    reporter.set_context(
+        validators0[0],
+        0,
+        HashMap::from([(0u64, validators0.clone())]),
+        vec![1, 2, 3, 4],
+        config,
+        false,
+        example1.aptos_db.clone(),
+    );
+    example1.step1();
+
+    // Verify the current reputation weights
+    let report = reporter
+        .generate_report(&LeaderReputationOverrides::default())
+        .unwrap();
+    assert!(!report.simulated);
+    assert_eq!(report.proposer_window_size, 8);
+    assert_eq!(report.voter_window_size, 4);
+    assert_eq!(report.num_history_blocks, 4);
+    assert_eq!(
+        report
+            .proposers
+            .iter()
+            .map(|proposer| proposer.author)
+            .collect::<Vec<_>>(),
+        validators0
+    );
+    assert_eq!(get_reputation_weights(&report), vec![100, 100, 1, 1]);
+    assert_eq!(get_election_probabilities(&report), vec![
+        100.0 / 202.0,
+        100.0 / 202.0,
+        1.0 / 202.0,
+        1.0 / 202.0
+    ]);
+
+    // Verify that a simulated config change is reflected in the weights
+    let overrides = LeaderReputationOverrides {
+        failure_threshold_percent: Some(50),
+        weight_by_voting_power: Some(true),
+        ..Default::default()
+    };
+    let report = reporter.generate_report(&overrides).unwrap();
+    assert!(report.simulated);
+    assert_eq!(report.config.failure_threshold_percent, 50);
+    assert_eq!(get_reputation_weights(&report), vec![100, 100, 100, 1]);
+    assert_eq!(get_election_probabilities(&report), vec![
+        100.0 / 604.0,
+        200.0 / 604.0,
+        300.0 / 604.0,
+        4.0 / 604.0
+    ]);
+
+    // Verify that the simulation doesn't modify the current config
+    let report = reporter
+        .generate_report(&LeaderReputationOverrides::default())
+        .unwrap();
+    assert_eq!(report.config, config);
+
+    // Verify that empty windows are rejected
+    let overrides = LeaderReputationOverrides {
+        voter_window_num_validators_multiplier: Some(0),
+        ..Default::default()
+    };
+    assert_err!(reporter.generate_report(&overrides));
+}
+
+fn get_reputation_weights(report: &LeaderReputationReport) -> Vec<u64> {
+    report
+        .proposers
+        .iter()
+        .map(|proposer| proposer.reputation_weight)
+        .collect()
+}
+
+fn get_election_probabilities(report: &LeaderReputationReport) -> Vec<f64> {
+    report
+        .proposers
+        .iter()
+        .map(|proposer| proposer.election_probability)
+        .collect()
+}
+
 /// #### LeaderReputation test ####
 
 #[test]
@@ -3,7 +3,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub(crate) mod cached_proposer_election;
+pub(crate) mod clock_skew_estimator;
 pub(crate) mod leader_reputation;
+pub(crate) mod leader_reputation_report;
+pub(crate) mod processed_message_tracker;
 pub(crate) mod proposal_generator;
 pub(crate) mod proposer_election;
 pub(crate) mod rotating_proposer_election;
@@ -14,8 +17,12 @@ pub(crate) mod unequivocal_proposer_election;
 #[cfg(test)]
 mod cached_proposer_election_test;
 #[cfg(test)]
+mod clock_skew_estimator_test;
+#[cfg(test)]
 mod leader_reputation_test;
 #[cfg(test)]
+mod processed_message_tracker_test;
+#[cfg(test)]
 mod rotating_proposer_test;
 #[cfg(test)]
 mod round_proposer_test;
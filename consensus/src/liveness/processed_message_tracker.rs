@@ -0,0 +1,147 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    logging::{LogEvent, LogSchema},
+};
+use aptos_consensus_types::{
+    common::{Author, Round},
+    proposal_msg::ProposalMsg,
+    vote_msg::VoteMsg,
+};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_logger::debug;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProcessedMessageType {
+    Proposal,
+    Vote,
+    TimeoutVote,
+}
+
+impl ProcessedMessageType {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            ProcessedMessageType::Proposal => "proposal",
+            ProcessedMessageType::Vote => "vote",
+            ProcessedMessageType::TimeoutVote => "timeout_vote",
+        }
+    }
+}
+
+/// Identifies a processed message by its type, round, author and digest.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProcessedMessageKey {
+    pub msg_type: ProcessedMessageType,
+    pub round: Round,
+    pub author: Author,
+    pub digest: HashValue,
+}
+
+impl ProcessedMessageKey {
+    pub fn from_proposal_msg(proposal_msg: &ProposalMsg) -> Self {
+        Self {
+            msg_type: ProcessedMessageType::Proposal,
+            round: proposal_msg.proposal().round(),
+            author: proposal_msg.proposer(),
+            digest: proposal_msg.proposal().id(),
+        }
+    }
+
+    pub fn from_vote_msg(vote_msg: &VoteMsg) -> Self {
+        let vote = vote_msg.vote();
+        // A vote can be re-sent with a timeout, which must not be treated as a duplicate
+        // of the original vote.
+        let (msg_type, digest) = match vote.two_chain_timeout() {
+            Some((timeout, _)) => (
+                ProcessedMessageType::TimeoutVote,
+                timeout.signing_format().hash(),
+            ),
+            None => (ProcessedMessageType::Vote, vote.ledger_info().hash()),
+        };
+        Self {
+            msg_type,
+            round: vote.vote_data().proposed().round(),
+            author: vote.author(),
+            digest,
+        }
+    }
+}
+
+// Remembers the proposals and votes that the round manager has successfully processed
+// in a bounded window of recent rounds, so that duplicates can be short-circuited.
+//
+// Only messages that were processed successfully should be recorded, so that messages
+// whose processing failed (e.g., due to missing dependencies) can be retried.
+pub struct ProcessedMessageTracker {
+    window_rounds: u64,
+    highest_round: Round,
+    processed: BTreeMap<Round, HashSet<ProcessedMessageKey>>,
+}
+
+impl ProcessedMessageTracker {
+    pub fn new(window_rounds: u64) -> Self {
+        Self {
+            window_rounds,
+            highest_round: 0,
+            processed: BTreeMap::new(),
+        }
+    }
+
+    // Returns true iff the given message was already processed.
+    pub fn is_processed(&self, key: &ProcessedMessageKey) -> bool {
+        self.processed
+            .get(&key.round)
+            .map_or(false, |keys| keys.contains(key))
+    }
+
+    // Returns true (and updates the counters) if the given message was already processed, in
+    // which case it should be dropped. This is checked both before the signature verification
+    // of the message (so that duplicates do not pay for it), and before processing.
+    pub fn is_duplicate(&self, key: &ProcessedMessageKey) -> bool {
+        if !self.is_processed(key) {
+            return false;
+        }
+
+        let log_event = match key.msg_type {
+            ProcessedMessageType::Proposal => LogEvent::ReceiveProposal,
+            ProcessedMessageType::Vote | ProcessedMessageType::TimeoutVote => LogEvent::ReceiveVote,
+        };
+        debug!(
+            LogSchema::new(log_event).remote_peer(key.author),
+            "Ignoring duplicate {} for round {} with digest {}",
+            key.msg_type.get_label(),
+            key.round,
+            key.digest,
+        );
+        counters::ROUND_MANAGER_SUPPRESSED_DUPLICATE_MSGS
+            .with_label_values(&[key.msg_type.get_label()])
+            .inc();
+        true
+    }
+
+    // Records the given message as processed, and prunes the rounds that fall out of the window.
+    // Messages for rounds below the window are not recorded.
+    pub fn mark_processed(&mut self, key: ProcessedMessageKey) {
+        if self.window_rounds == 0 || key.round < self.lowest_tracked_round() {
+            return;
+        }
+
+        self.processed.entry(key.round).or_default().insert(key);
+        if key.round > self.highest_round {
+            self.highest_round = key.round;
+            self.processed = self.processed.split_off(&self.lowest_tracked_round());
+        }
+    }
+
+    // Returns the number of processed messages that are currently tracked.
+    pub fn num_tracked(&self) -> usize {
+        self.processed.values().map(|keys| keys.len()).sum()
+    }
+
+    fn lowest_tracked_round(&self) -> Round {
+        self.highest_round.saturating_sub(self.window_rounds - 1)
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::liveness::processed_message_tracker::{
+    ProcessedMessageKey, ProcessedMessageTracker, ProcessedMessageType,
+};
+use aptos_consensus_types::common::{Author, Round};
+use aptos_crypto::HashValue;
+
+fn create_key(msg_type: ProcessedMessageType, round: Round, author: Author) -> ProcessedMessageKey {
+    ProcessedMessageKey {
+        msg_type,
+        round,
+        author,
+        digest: HashValue::random(),
+    }
+}
+
+#[test]
+fn test_duplicate_detection() {
+    let mut tracker = ProcessedMessageTracker::new(10);
+    let author = Author::random();
+
+    // Only processed messages are detected as duplicates
+    let proposal = create_key(ProcessedMessageType::Proposal, 1, author);
+    assert!(!tracker.is_processed(&proposal));
+    assert!(!tracker.is_duplicate(&proposal));
+    tracker.mark_processed(proposal);
+    assert!(tracker.is_processed(&proposal));
+    assert!(tracker.is_duplicate(&proposal));
+
+    // Messages with a different type, round, author or digest are not duplicates
+    let vote = ProcessedMessageKey {
+        msg_type: ProcessedMessageType::Vote,
+        ..proposal
+    };
+    assert!(!tracker.is_processed(&vote));
+    let next_round = ProcessedMessageKey {
+        round: 2,
+        ..proposal
+    };
+    assert!(!tracker.is_processed(&next_round));
+    let other_author = ProcessedMessageKey {
+        author: Author::random(),
+        ..proposal
+    };
+    assert!(!tracker.is_processed(&other_author));
+    let other_digest = ProcessedMessageKey {
+        digest: HashValue::random(),
+        ..proposal
+    };
+    assert!(!tracker.is_processed(&other_digest));
+}
+
+#[test]
+fn test_bounded_window() {
+    let mut tracker = ProcessedMessageTracker::new(3);
+    let author = Author::random();
+
+    let keys: Vec<_> = (1..=5)
+        .map(|round| create_key(ProcessedMessageType::Vote, round, author))
+        .collect();
+    for key in &keys {
+        tracker.mark_processed(*key);
+    }
+
+    // Only the last 3 rounds are tracked
+    assert_eq!(tracker.num_tracked(), 3);
+    assert!(!tracker.is_processed(&keys[0]));
+    assert!(!tracker.is_processed(&keys[1]));
+    for key in &keys[2..] {
+        assert!(tracker.is_processed(key));
+    }
+
+    // Messages below the window are not recorded
+    tracker.mark_processed(keys[0]);
+    assert!(!tracker.is_processed(&keys[0]));
+
+    // Messages within the window (but not for the highest round) are recorded
+    let timeout_vote = create_key(ProcessedMessageType::TimeoutVote, 3, author);
+    tracker.mark_processed(timeout_vote);
+    assert!(tracker.is_processed(&timeout_vote));
+    assert_eq!(tracker.num_tracked(), 4);
+}
+
+#[test]
+fn test_disabled_tracker() {
+    let mut tracker = ProcessedMessageTracker::new(0);
+
+    let proposal = create_key(ProcessedMessageType::Proposal, 1, Author::random());
+    tracker.mark_processed(proposal);
+    assert!(!tracker.is_processed(&proposal));
+    assert_eq!(tracker.num_tracked(), 0);
+}
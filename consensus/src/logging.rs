@@ -33,6 +33,8 @@ pub enum LogEvent {
     ReceiveBlockRetrieval,
     ReceiveEpochChangeProof,
     ReceiveEpochRetrieval,
+    ReceiveEpochStarterKit,
+    ReceiveEpochStarterKitRequest,
     ReceiveMessageFromDifferentEpoch,
     ReceiveNewCertificate,
     ReceiveProposal,
@@ -0,0 +1,307 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{counters, network::IncomingRpcRequest, network_interface::ConsensusMsg};
+use aptos_types::on_chain_config::{
+    FeatureFlag, Features, OnChainConsensusConfig, OnChainRandomnessConfig,
+};
+
+const SEND_LABEL: &str = "send";
+const RECEIVE_LABEL: &str = "receive";
+
+/// Determines which consensus message variants are enabled in the current epoch,
+/// based on the epoch's on-chain configs. Variants that are not enabled are neither
+/// sent nor processed, so that new message types can be rolled out safely in mixed
+/// version networks (i.e., only once enabled on-chain, after all validators upgrade).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConsensusMsgGating {
+    quorum_store_enabled: bool,
+    dag_enabled: bool,
+    randomness_enabled: bool,
+    batch_response_v2_enabled: bool,
+    batch_queue_length_hints_enabled: bool,
+    block_range_retrieval_enabled: bool,
+    epoch_starter_kit_enabled: bool,
+}
+
+impl ConsensusMsgGating {
+    pub fn new(
+        quorum_store_enabled: bool,
+        consensus_config: &OnChainConsensusConfig,
+        randomness_config: &OnChainRandomnessConfig,
+        features: &Features,
+    ) -> Self {
+        Self {
+            quorum_store_enabled,
+            dag_enabled: consensus_config.is_dag_enabled(),
+            randomness_enabled: randomness_config.randomness_enabled(),
+            batch_response_v2_enabled: features
+                .is_enabled(FeatureFlag::CONSENSUS_BATCH_RESPONSE_V2),
+            batch_queue_length_hints_enabled: features
+                .is_enabled(FeatureFlag::QUORUM_STORE_QUEUE_LENGTH_HINTS),
+            block_range_retrieval_enabled: features
+                .is_enabled(FeatureFlag::CONSENSUS_BLOCK_RANGE_RETRIEVAL),
+            epoch_starter_kit_enabled: features
+                .is_enabled(FeatureFlag::CONSENSUS_EPOCH_STARTER_KIT),
+        }
+    }
+
+    /// Returns a gating that enables all message variants (e.g., before the first epoch starts)
+    pub fn allow_all() -> Self {
+        Self {
+            quorum_store_enabled: true,
+            dag_enabled: true,
+            randomness_enabled: true,
+            batch_response_v2_enabled: true,
+            batch_queue_length_hints_enabled: true,
+            block_range_retrieval_enabled: true,
+            epoch_starter_kit_enabled: true,
+        }
+    }
+
+    /// Returns true iff the given message variant is enabled in the current epoch
+    pub fn is_enabled(&self, msg: &ConsensusMsg) -> bool {
+        match msg {
+            ConsensusMsg::BatchMsg(_)
+            | ConsensusMsg::BatchRequestMsg(_)
+            | ConsensusMsg::BatchResponse(_)
+            | ConsensusMsg::SignedBatchInfo(_)
+            | ConsensusMsg::ProofOfStoreMsg(_) => self.quorum_store_enabled,
+            ConsensusMsg::BatchResponseV2(_) => {
+                self.quorum_store_enabled && self.batch_response_v2_enabled
+            },
+            ConsensusMsg::BatchQueueLengthHintMsg(_) => {
+                self.quorum_store_enabled && self.batch_queue_length_hints_enabled
+            },
+            ConsensusMsg::DAGMessage(_) => self.dag_enabled,
+            ConsensusMsg::RandGenMessage(_) => self.randomness_enabled,
+            ConsensusMsg::BlockRangeRetrievalRequest(_) => self.block_range_retrieval_enabled,
+            ConsensusMsg::EpochStarterKitRequest(_) | ConsensusMsg::EpochStarterKit(_) => {
+                self.epoch_starter_kit_enabled
+            },
+            ConsensusMsg::BlockRetrievalRequest(_)
+            | ConsensusMsg::BlockRetrievalResponse(_)
+            | ConsensusMsg::EpochRetrievalRequest(_)
+            | ConsensusMsg::ProposalMsg(_)
+            | ConsensusMsg::SyncInfo(_)
+            | ConsensusMsg::EpochChangeProof(_)
+            | ConsensusMsg::VoteMsg(_)
+            | ConsensusMsg::CommitVoteMsg(_)
+            | ConsensusMsg::CommitDecisionMsg(_)
+            | ConsensusMsg::CommitMessage(_) => true,
+        }
+    }
+
+    /// Returns true iff the message is enabled and can be sent. Otherwise,
+    /// the gated send is counted.
+    pub fn check_send(&self, msg: &ConsensusMsg) -> bool {
+        self.check(msg.name(), self.is_enabled(msg), SEND_LABEL)
+    }
+
+    /// Returns true iff BatchResponseV2 is enabled and can be sent (i.e., the same as
+    /// `check_send`, for responses that are only constructed once the version is known).
+    pub fn check_send_batch_response_v2(&self) -> bool {
+        self.check(
+            "BatchResponseV2",
+            self.quorum_store_enabled && self.batch_response_v2_enabled,
+            SEND_LABEL,
+        )
+    }
+
+    /// Returns true iff blocks can be retrieved with ranged requests in the current
+    /// epoch. Otherwise, requesters fall back to the (chained) block retrieval requests.
+    pub fn is_block_range_retrieval_enabled(&self) -> bool {
+        self.block_range_retrieval_enabled
+    }
+
+    /// Returns true iff validators joining mid-epoch can bootstrap with epoch starter
+    /// kits in the current epoch. Note: starter kit messages are processed before the
+    /// epoch check (as the requester may lag behind), so they are gated by the caller.
+    pub fn is_epoch_starter_kit_enabled(&self) -> bool {
+        self.epoch_starter_kit_enabled
+    }
+
+    /// Counts a received message that is not processed because it is not enabled.
+    /// Note: this is separate from `is_enabled`, as only messages of the current
+    /// epoch are gated (and the epoch is only known after the message is consumed).
+    pub fn record_gated_receive(&self, msg_name: &str) {
+        self.check(msg_name, false, RECEIVE_LABEL);
+    }
+
+    /// Returns true iff the (already deserialized) rpc request is enabled and can
+    /// be processed. Otherwise, the gated receive is counted.
+    pub fn check_receive_rpc(&self, request: &IncomingRpcRequest) -> bool {
+        let (name, enabled) = match request {
+            IncomingRpcRequest::BatchRetrieval(_) => ("BatchRequestMsg", self.quorum_store_enabled),
+            IncomingRpcRequest::DAGRequest(_) => ("DAGMessage", self.dag_enabled),
+            IncomingRpcRequest::RandGenRequest(_) => ("RandGenMessage", self.randomness_enabled),
+            IncomingRpcRequest::BlockRangeRetrieval(_) => (
+                "BlockRangeRetrievalRequest",
+                self.block_range_retrieval_enabled,
+            ),
+            IncomingRpcRequest::BlockRetrieval(_) | IncomingRpcRequest::CommitRequest(_) => {
+                return true;
+            },
+        };
+        self.check(name, enabled, RECEIVE_LABEL)
+    }
+
+    fn check(&self, msg_name: &str, enabled: bool, direction: &str) -> bool {
+        if !enabled {
+            counters::CONSENSUS_GATED_MSGS
+                .with_label_values(&[direction, msg_name])
+                .inc();
+        }
+        enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dag::DAGNetworkMessage, message_gating::ConsensusMsgGating,
+        network_interface::ConsensusMsg, quorum_store::types::BatchQueueLengthHintMsg,
+        rand::rand_gen::network_messages::RandGenMessage,
+    };
+    use aptos_consensus_types::{
+        block_retrieval::BlockRangeRetrievalRequest,
+        epoch_retrieval::{EpochRetrievalRequest, EpochStarterKitRequest},
+    };
+    use aptos_crypto::HashValue;
+    use aptos_types::{
+        on_chain_config::{FeatureFlag, Features, OnChainConsensusConfig, OnChainRandomnessConfig},
+        PeerId,
+    };
+
+    fn create_test_messages() -> (ConsensusMsg, ConsensusMsg, ConsensusMsg, ConsensusMsg) {
+        (
+            ConsensusMsg::EpochRetrievalRequest(Box::new(EpochRetrievalRequest {
+                start_epoch: 1,
+                end_epoch: 2,
+            })),
+            ConsensusMsg::BatchQueueLengthHintMsg(Box::new(BatchQueueLengthHintMsg::new(
+                1,
+                PeerId::random(),
+                100,
+            ))),
+            ConsensusMsg::DAGMessage(DAGNetworkMessage::new(1, vec![])),
+            ConsensusMsg::RandGenMessage(RandGenMessage::new(1, vec![])),
+        )
+    }
+
+    #[test]
+    fn test_gating_follows_on_chain_configs() {
+        let (epoch_retrieval_msg, hint_msg, dag_msg, rand_msg) = create_test_messages();
+
+        // Disable the queue length hints, randomness and DAG
+        let mut features = Features::default();
+        features.disable(FeatureFlag::QUORUM_STORE_QUEUE_LENGTH_HINTS);
+        let gating = ConsensusMsgGating::new(
+            true,
+            &OnChainConsensusConfig::default(),
+            &OnChainRandomnessConfig::default_disabled(),
+            &features,
+        );
+        assert!(gating.is_enabled(&epoch_retrieval_msg));
+        assert!(!gating.is_enabled(&hint_msg));
+        assert!(!gating.is_enabled(&dag_msg));
+        assert!(!gating.is_enabled(&rand_msg));
+        assert!(!gating.check_send(&hint_msg));
+
+        // Enable the queue length hints and randomness
+        features.enable(FeatureFlag::QUORUM_STORE_QUEUE_LENGTH_HINTS);
+        let gating = ConsensusMsgGating::new(
+            true,
+            &OnChainConsensusConfig::default(),
+            &OnChainRandomnessConfig::default_enabled(),
+            &features,
+        );
+        assert!(gating.is_enabled(&hint_msg));
+        assert!(gating.is_enabled(&rand_msg));
+        assert!(gating.check_send(&hint_msg));
+        assert!(gating.check_send_batch_response_v2());
+
+        // Quorum store messages are gated if the quorum store is disabled
+        let gating = ConsensusMsgGating::new(
+            false,
+            &OnChainConsensusConfig::default(),
+            &OnChainRandomnessConfig::default_enabled(),
+            &features,
+        );
+        assert!(!gating.is_enabled(&hint_msg));
+        assert!(!gating.check_send_batch_response_v2());
+    }
+
+    #[test]
+    fn test_block_range_retrieval_gating() {
+        let range_request_msg = ConsensusMsg::BlockRangeRetrievalRequest(Box::new(
+            BlockRangeRetrievalRequest::new(HashValue::random(), 0, u64::MAX),
+        ));
+
+        // Ranged block retrieval is gated if the feature is disabled
+        let mut features = Features::default();
+        features.disable(FeatureFlag::CONSENSUS_BLOCK_RANGE_RETRIEVAL);
+        let gating = ConsensusMsgGating::new(
+            true,
+            &OnChainConsensusConfig::default(),
+            &OnChainRandomnessConfig::default_disabled(),
+            &features,
+        );
+        assert!(!gating.is_enabled(&range_request_msg));
+        assert!(!gating.check_send(&range_request_msg));
+        assert!(!gating.is_block_range_retrieval_enabled());
+
+        // Ranged block retrieval is allowed once the feature is enabled
+        features.enable(FeatureFlag::CONSENSUS_BLOCK_RANGE_RETRIEVAL);
+        let gating = ConsensusMsgGating::new(
+            true,
+            &OnChainConsensusConfig::default(),
+            &OnChainRandomnessConfig::default_disabled(),
+            &features,
+        );
+        assert!(gating.is_enabled(&range_request_msg));
+        assert!(gating.check_send(&range_request_msg));
+        assert!(gating.is_block_range_retrieval_enabled());
+    }
+
+    #[test]
+    fn test_epoch_starter_kit_gating() {
+        let starter_kit_request_msg =
+            ConsensusMsg::EpochStarterKitRequest(Box::new(EpochStarterKitRequest {
+                epoch: 1,
+                max_num_blocks: 10,
+            }));
+
+        // Epoch starter kits are gated if the feature is disabled
+        let mut features = Features::default();
+        features.disable(FeatureFlag::CONSENSUS_EPOCH_STARTER_KIT);
+        let gating = ConsensusMsgGating::new(
+            true,
+            &OnChainConsensusConfig::default(),
+            &OnChainRandomnessConfig::default_disabled(),
+            &features,
+        );
+        assert!(!gating.is_enabled(&starter_kit_request_msg));
+        assert!(!gating.is_epoch_starter_kit_enabled());
+
+        // Epoch starter kits are allowed once the feature is enabled
+        features.enable(FeatureFlag::CONSENSUS_EPOCH_STARTER_KIT);
+        let gating = ConsensusMsgGating::new(
+            true,
+            &OnChainConsensusConfig::default(),
+            &OnChainRandomnessConfig::default_disabled(),
+            &features,
+        );
+        assert!(gating.is_enabled(&starter_kit_request_msg));
+        assert!(gating.is_epoch_starter_kit_enabled());
+    }
+
+    #[test]
+    fn test_allow_all() {
+        let (epoch_retrieval_msg, hint_msg, dag_msg, rand_msg) = create_test_messages();
+        let gating = ConsensusMsgGating::allow_all();
+        for msg in [epoch_retrieval_msg, hint_msg, dag_msg, rand_msg] {
+            assert!(gating.check_send(&msg));
+        }
+    }
+}
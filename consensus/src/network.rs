@@ -10,10 +10,11 @@ use crate::{
         TDAGNetworkSender,
     },
     logging::{LogEvent, LogSchema},
+    message_gating::ConsensusMsgGating,
     monitor,
     network_interface::{ConsensusMsg, ConsensusNetworkClient, RPC},
     pipeline::commit_reliable_broadcast::CommitMessage,
-    quorum_store::types::{Batch, BatchMsg, BatchRequest, BatchResponse},
+    quorum_store::types::{Batch, BatchMsg, BatchQueueLengthHintMsg, BatchRequest, BatchResponse},
     rand::rand_gen::{
         network_messages::{RandGenMessage, RandMessage},
         types::{AugmentedData, FastShare, Share},
@@ -23,7 +24,7 @@ use anyhow::{anyhow, bail, ensure};
 use aptos_channels::{self, aptos_channel, message_queues::QueueStyle};
 use aptos_config::network_id::NetworkId;
 use aptos_consensus_types::{
-    block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse},
+    block_retrieval::{BlockRangeRetrievalRequest, BlockRetrievalRequest, BlockRetrievalResponse},
     common::Author,
     pipeline::{commit_decision::CommitDecision, commit_vote::CommitVote},
     proof_of_store::{ProofOfStore, ProofOfStoreMsg, SignedBatchInfo, SignedBatchInfoMsg},
@@ -98,6 +99,15 @@ pub struct IncomingBlockRetrievalRequest {
     pub response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
 }
 
+/// The ranged block retrieval request is used internally for implementing RPC: the callback is
+/// executed for carrying the response
+#[derive(Debug)]
+pub struct IncomingBlockRangeRetrievalRequest {
+    pub req: BlockRangeRetrievalRequest,
+    pub protocol: ProtocolId,
+    pub response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+}
+
 #[derive(Debug)]
 pub struct IncomingBatchRetrievalRequest {
     pub req: BatchRequest,
@@ -130,6 +140,7 @@ pub struct IncomingRandGenRequest {
 #[derive(Debug)]
 pub enum IncomingRpcRequest {
     BlockRetrieval(IncomingBlockRetrievalRequest),
+    BlockRangeRetrieval(IncomingBlockRangeRetrievalRequest),
     BatchRetrieval(IncomingBatchRetrievalRequest),
     DAGRequest(IncomingDAGRequest),
     CommitRequest(IncomingCommitRequest),
@@ -143,7 +154,9 @@ impl IncomingRpcRequest {
             IncomingRpcRequest::DAGRequest(req) => Some(req.req.epoch()),
             IncomingRpcRequest::RandGenRequest(req) => Some(req.req.epoch()),
             IncomingRpcRequest::CommitRequest(req) => req.req.epoch(),
-            IncomingRpcRequest::BlockRetrieval(_) => None,
+            IncomingRpcRequest::BlockRetrieval(_) | IncomingRpcRequest::BlockRangeRetrieval(_) => {
+                None
+            },
         }
     }
 }
@@ -190,6 +203,8 @@ pub trait QuorumStoreSender: Send + Clone {
     async fn broadcast_proof_of_store_msg(&mut self, proof_of_stores: Vec<ProofOfStore>);
 
     async fn send_proof_of_store_msg_to_self(&mut self, proof_of_stores: Vec<ProofOfStore>);
+
+    async fn broadcast_batch_queue_length_hint_msg(&mut self, hint: BatchQueueLengthHintMsg);
 }
 
 /// Implements the actual networking support for all consensus messaging.
@@ -202,6 +217,8 @@ pub struct NetworkSender {
     self_sender: aptos_channels::UnboundedSender<Event<ConsensusMsg>>,
     validators: ValidatorVerifier,
     time_service: aptos_time_service::TimeService,
+    // Message variants that are not enabled in the current epoch are not sent
+    message_gating: Arc<ConsensusMsgGating>,
 }
 
 impl NetworkSender {
@@ -217,9 +234,19 @@ impl NetworkSender {
             self_sender,
             validators,
             time_service: aptos_time_service::TimeService::real(),
+            message_gating: Arc::new(ConsensusMsgGating::allow_all()),
         }
     }
 
+    pub fn with_message_gating(mut self, message_gating: Arc<ConsensusMsgGating>) -> Self {
+        self.message_gating = message_gating;
+        self
+    }
+
+    pub fn message_gating(&self) -> Arc<ConsensusMsgGating> {
+        self.message_gating.clone()
+    }
+
     /// Tries to retrieve num of blocks backwards starting from id from the given peer: the function
     /// returns a future that is fulfilled with BlockRetrievalResponse.
     pub async fn request_block(
@@ -264,6 +291,56 @@ impl NetworkSender {
         Ok(response)
     }
 
+    /// Tries to retrieve a range of blocks backwards (down to the target round) starting from id
+    /// from the given peer: the function returns a future that is fulfilled with a
+    /// BlockRetrievalResponse containing (a chunk of) the range.
+    pub async fn request_block_range(
+        &self,
+        retrieval_request: BlockRangeRetrievalRequest,
+        from: Author,
+        timeout: Duration,
+    ) -> anyhow::Result<BlockRetrievalResponse> {
+        fail_point!("consensus::send::any", |_| {
+            Err(anyhow::anyhow!("Injected error in request_block_range"))
+        });
+        fail_point!("consensus::send::block_range_retrieval", |_| {
+            Err(anyhow::anyhow!("Injected error in request_block_range"))
+        });
+
+        ensure!(from != self.author, "Retrieve block range from self");
+        let msg = ConsensusMsg::BlockRangeRetrievalRequest(Box::new(retrieval_request.clone()));
+        ensure!(
+            self.message_gating.check_send(&msg),
+            "{} is not enabled in the current epoch",
+            msg.name()
+        );
+        counters::CONSENSUS_SENT_MSGS
+            .with_label_values(&[msg.name()])
+            .inc();
+        let response_msg = monitor!(
+            "block_range_retrieval",
+            self.consensus_network_client
+                .send_rpc(from, msg, timeout)
+                .await
+        )?;
+        let response = match response_msg {
+            ConsensusMsg::BlockRetrievalResponse(resp) => *resp,
+            _ => return Err(anyhow!("Invalid response to request")),
+        };
+        response
+            .verify_range(&retrieval_request, &self.validators)
+            .map_err(|e| {
+                error!(
+                    SecurityEvent::InvalidRetrievedBlock,
+                    request_block_response = response,
+                    error = ?e,
+                );
+                e
+            })?;
+
+        Ok(response)
+    }
+
     pub async fn send_rpc(
         &self,
         receiver: Author,
@@ -273,6 +350,11 @@ impl NetworkSender {
         fail_point!("consensus::send::any", |_| {
             Err(anyhow::anyhow!("Injected error in send_rpc"))
         });
+        ensure!(
+            self.message_gating.check_send(&msg),
+            "{} is not enabled in the current epoch",
+            msg.name()
+        );
         counters::CONSENSUS_SENT_MSGS
             .with_label_values(&[msg.name()])
             .inc();
@@ -303,6 +385,9 @@ impl NetworkSender {
     /// out.
     async fn broadcast(&self, msg: ConsensusMsg) {
         fail_point!("consensus::send::any", |_| ());
+        if !self.message_gating.check_send(&msg) {
+            return;
+        }
         // Directly send the message to ourself without going through network.
         let self_msg = Event::Message(self.author, msg.clone());
         let mut self_sender = self.self_sender.clone();
@@ -332,6 +417,9 @@ impl NetworkSender {
     }
 
     pub fn broadcast_without_self(&self, msg: ConsensusMsg) {
+        if !self.message_gating.check_send(&msg) {
+            return;
+        }
         let self_author = self.author;
         let other_validators: Vec<_> = self
             .validators
@@ -354,6 +442,9 @@ impl NetworkSender {
     /// Tries to send msg to given recipients.
     async fn send(&self, msg: ConsensusMsg, recipients: Vec<Author>) {
         fail_point!("consensus::send::any", |_| ());
+        if !self.message_gating.check_send(&msg) {
+            return;
+        }
         let network_sender = self.consensus_network_client.clone();
         let mut self_sender = self.self_sender.clone();
         for peer in recipients {
@@ -487,6 +578,8 @@ impl QuorumStoreSender for NetworkSender {
             .consensus_network_client
             .send_rpc(recipient, msg, timeout)
             .await?;
+        // Note: both response versions are accepted (regardless of the message gating),
+        // as responders running an older version may not gate BatchResponseV2.
         match response {
             // TODO: deprecated, remove after another release (likely v1.11)
             ConsensusMsg::BatchResponse(batch) => {
@@ -538,6 +631,12 @@ impl QuorumStoreSender for NetworkSender {
         let msg = ConsensusMsg::ProofOfStoreMsg(Box::new(ProofOfStoreMsg::new(proofs)));
         self.send(msg, vec![self.author]).await
     }
+
+    async fn broadcast_batch_queue_length_hint_msg(&mut self, hint: BatchQueueLengthHintMsg) {
+        fail_point!("consensus::send::batch_queue_length_hint", |_| ());
+        let msg = ConsensusMsg::BatchQueueLengthHintMsg(Box::new(hint));
+        self.broadcast(msg).await
+    }
 }
 
 #[async_trait]
@@ -696,7 +795,8 @@ impl NetworkTask {
                     match msg {
                         quorum_store_msg @ (ConsensusMsg::SignedBatchInfo(_)
                         | ConsensusMsg::BatchMsg(_)
-                        | ConsensusMsg::ProofOfStoreMsg(_)) => {
+                        | ConsensusMsg::ProofOfStoreMsg(_)
+                        | ConsensusMsg::BatchQueueLengthHintMsg(_)) => {
                             Self::push_msg(
                                 peer_id,
                                 quorum_store_msg,
@@ -738,7 +838,9 @@ impl NetworkTask {
                         | ConsensusMsg::VoteMsg(_)
                         | ConsensusMsg::SyncInfo(_)
                         | ConsensusMsg::EpochRetrievalRequest(_)
-                        | ConsensusMsg::EpochChangeProof(_)) => {
+                        | ConsensusMsg::EpochChangeProof(_)
+                        | ConsensusMsg::EpochStarterKitRequest(_)
+                        | ConsensusMsg::EpochStarterKit(_)) => {
                             if let ConsensusMsg::ProposalMsg(proposal) = &consensus_msg {
                                 observe_block(
                                     proposal.proposal().timestamp_usecs(),
@@ -794,6 +896,21 @@ impl NetworkTask {
                                 response_sender: callback,
                             })
                         },
+                        ConsensusMsg::BlockRangeRetrievalRequest(request) => {
+                            debug!(
+                                remote_peer = peer_id,
+                                event = LogEvent::ReceiveBlockRetrieval,
+                                "{}",
+                                request
+                            );
+                            IncomingRpcRequest::BlockRangeRetrieval(
+                                IncomingBlockRangeRetrievalRequest {
+                                    req: *request,
+                                    protocol,
+                                    response_sender: callback,
+                                },
+                            )
+                        },
                         ConsensusMsg::BatchRequestMsg(request) => {
                             debug!(
                                 remote_peer = peer_id,
@@ -7,13 +7,13 @@
 use crate::{
     dag::DAGNetworkMessage,
     pipeline,
-    quorum_store::types::{Batch, BatchMsg, BatchRequest, BatchResponse},
+    quorum_store::types::{Batch, BatchMsg, BatchQueueLengthHintMsg, BatchRequest, BatchResponse},
     rand::rand_gen::network_messages::RandGenMessage,
 };
 use aptos_config::network_id::{NetworkId, PeerNetworkId};
 use aptos_consensus_types::{
-    block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse},
-    epoch_retrieval::EpochRetrievalRequest,
+    block_retrieval::{BlockRangeRetrievalRequest, BlockRetrievalRequest, BlockRetrievalResponse},
+    epoch_retrieval::{EpochRetrievalRequest, EpochStarterKit, EpochStarterKitRequest},
     pipeline::{commit_decision::CommitDecision, commit_vote::CommitVote},
     proof_of_store::{ProofOfStoreMsg, SignedBatchInfoMsg},
     proposal_msg::ProposalMsg,
@@ -75,13 +75,22 @@ pub enum ConsensusMsg {
     RandGenMessage(RandGenMessage),
     /// Quorum Store: Response to the batch request.
     BatchResponseV2(Box<BatchResponse>),
+    /// Quorum Store: Gossip the number of txns in the sender's batches that are not yet committed.
+    BatchQueueLengthHintMsg(Box<BatchQueueLengthHintMsg>),
+    /// RPC to get a range of chained blocks (down to a target round) starting from the given
+    /// block id. The response is a BlockRetrievalResponse (capped by bytes).
+    BlockRangeRetrievalRequest(Box<BlockRangeRetrievalRequest>),
+    /// Request to get an EpochStarterKit (sent by nodes joining an epoch without consensus data).
+    EpochStarterKitRequest(Box<EpochStarterKitRequest>),
+    /// The epoch change proof, latest sync info and recent blocks required to join the epoch.
+    EpochStarterKit(Box<EpochStarterKit>),
 }
 
 /// Network type for consensus
 impl ConsensusMsg {
     /// ConsensusMsg type in string
     ///
-    pub fn name(&self) -> &str {
+    pub fn name(&self) -> &'static str {
         match self {
             ConsensusMsg::BlockRetrievalRequest(_) => "BlockRetrievalRequest",
             ConsensusMsg::BlockRetrievalResponse(_) => "BlockRetrievalResponse",
@@ -101,6 +110,10 @@ impl ConsensusMsg {
             ConsensusMsg::CommitMessage(_) => "CommitMessage",
             ConsensusMsg::RandGenMessage(_) => "RandGenMessage",
             ConsensusMsg::BatchResponseV2(_) => "BatchResponseV2",
+            ConsensusMsg::BatchQueueLengthHintMsg(_) => "BatchQueueLengthHintMsg",
+            ConsensusMsg::BlockRangeRetrievalRequest(_) => "BlockRangeRetrievalRequest",
+            ConsensusMsg::EpochStarterKitRequest(_) => "EpochStarterKitRequest",
+            ConsensusMsg::EpochStarterKit(_) => "EpochStarterKit",
         }
     }
 }
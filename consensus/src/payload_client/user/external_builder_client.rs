@@ -0,0 +1,621 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters::{EXTERNAL_BLOCK_BUILDER_LATENCY, EXTERNAL_BLOCK_BUILDER_REQUESTS},
+    error::QuorumStoreError,
+    payload_client::user::UserPayloadClient,
+};
+use anyhow::{bail, ensure};
+use aptos_config::config::ExternalBlockBuilderConfig;
+use aptos_consensus_types::{
+    common::{Payload, PayloadFilter, ProofWithDataWithTxnLimit, TransactionSummary},
+    proof_of_store::ProofCache,
+};
+use aptos_logger::{info, warn};
+use aptos_types::validator_verifier::ValidatorVerifier;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::min,
+    collections::HashSet,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::UnixStream,
+    time::timeout,
+};
+
+// Useful labels for the external block builder metrics
+const ERROR_LABEL: &str = "error";
+const INVALID_LABEL: &str = "invalid";
+const NO_PAYLOAD_LABEL: &str = "no_payload";
+const SUCCESS_LABEL: &str = "success";
+const TIMEOUT_LABEL: &str = "timeout";
+
+/// A request for a proposal payload, sent to the external block builder
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ExternalPayloadRequest {
+    pub max_items: u64,
+    pub max_bytes: u64,
+    pub max_inline_items: u64,
+    pub max_inline_bytes: u64,
+    /// The payloads of the pending (uncommitted) blocks, which must not be proposed again
+    pub exclude: PayloadFilter,
+    /// The time (in milliseconds) the builder has to respond, before the request is abandoned
+    pub deadline_ms: u64,
+}
+
+/// The response of the external block builder to a payload request
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ExternalPayloadResponse {
+    Payload(Payload),
+    /// The builder has nothing to propose, and defers to quorum store
+    NoPayload,
+}
+
+/// An external (out-of-process) service that builds the proposal payloads
+#[async_trait::async_trait]
+pub trait ExternalBlockBuilder: Send + Sync {
+    async fn request_payload(
+        &self,
+        request: ExternalPayloadRequest,
+    ) -> anyhow::Result<ExternalPayloadResponse>;
+}
+
+/// An external block builder reachable over a local (unix domain) socket. Each request
+/// opens a new connection, and the messages are BCS encoded with a u32 length prefix.
+pub struct IpcBlockBuilder {
+    socket_path: PathBuf,
+    max_response_bytes: usize,
+}
+
+impl IpcBlockBuilder {
+    pub fn new(socket_path: PathBuf, max_response_bytes: usize) -> Self {
+        Self {
+            socket_path,
+            max_response_bytes,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ExternalBlockBuilder for IpcBlockBuilder {
+    async fn request_payload(
+        &self,
+        request: ExternalPayloadRequest,
+    ) -> anyhow::Result<ExternalPayloadResponse> {
+        let mut stream = UnixStream::connect(&self.socket_path).await?;
+        write_message(&mut stream, &bcs::to_bytes(&request)?).await?;
+        let response = read_message(&mut stream, self.max_response_bytes).await?;
+        Ok(bcs::from_bytes(&response)?)
+    }
+}
+
+/// Writes the given message to the stream, prefixed by its length
+async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &[u8],
+) -> anyhow::Result<()> {
+    let length = u32::try_from(message.len())?;
+    writer.write_u32(length).await?;
+    writer.write_all(message).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads a length prefixed message from the stream (of at most `max_bytes` bytes)
+async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_bytes: usize,
+) -> anyhow::Result<Vec<u8>> {
+    let length = reader.read_u32().await? as usize;
+    if length > max_bytes {
+        bail!(
+            "Message is too large! Length: {}, max: {}",
+            length,
+            max_bytes
+        );
+    }
+    let mut message = vec![0; length];
+    reader.read_exact(&mut message).await?;
+    Ok(message)
+}
+
+/// Client that requests the proposal payloads from an external block builder, and falls
+/// back to the given client (i.e., quorum store) if the builder fails to respond with a
+/// valid payload before the deadline.
+pub struct ExternalBuilderClient {
+    builder: Arc<dyn ExternalBlockBuilder>,
+    fallback_client: Arc<dyn UserPayloadClient>,
+    request_timeout: Duration,
+    quorum_store_enabled: bool,
+    validator_verifier: ValidatorVerifier,
+    proof_cache: ProofCache,
+}
+
+impl ExternalBuilderClient {
+    pub fn new(
+        builder: Arc<dyn ExternalBlockBuilder>,
+        fallback_client: Arc<dyn UserPayloadClient>,
+        request_timeout: Duration,
+        quorum_store_enabled: bool,
+        validator_verifier: ValidatorVerifier,
+        proof_cache: ProofCache,
+    ) -> Self {
+        Self {
+            builder,
+            fallback_client,
+            request_timeout,
+            quorum_store_enabled,
+            validator_verifier,
+            proof_cache,
+        }
+    }
+
+    /// Creates a client for the external block builder on the configured local socket
+    pub fn new_with_ipc_builder(
+        config: &ExternalBlockBuilderConfig,
+        fallback_client: Arc<dyn UserPayloadClient>,
+        quorum_store_enabled: bool,
+        validator_verifier: ValidatorVerifier,
+        proof_cache: ProofCache,
+    ) -> Self {
+        info!(
+            "Requesting proposal payloads from the external block builder at {:?}",
+            config.socket_path
+        );
+        let builder = IpcBlockBuilder::new(config.socket_path.clone(), config.max_response_bytes);
+        Self::new(
+            Arc::new(builder),
+            fallback_client,
+            Duration::from_millis(config.request_timeout_ms),
+            quorum_store_enabled,
+            validator_verifier,
+            proof_cache,
+        )
+    }
+
+    /// Requests a payload from the external block builder. Returns None if the builder
+    /// fails to respond in time, has nothing to propose or responds with an invalid payload.
+    async fn request_external_payload(
+        &self,
+        request: ExternalPayloadRequest,
+        request_timeout: Duration,
+    ) -> Option<Payload> {
+        let start_time = Instant::now();
+        let response = timeout(
+            request_timeout,
+            self.builder.request_payload(request.clone()),
+        )
+        .await;
+        EXTERNAL_BLOCK_BUILDER_LATENCY.observe_duration(start_time.elapsed());
+
+        let (label, payload) = match response {
+            Ok(Ok(ExternalPayloadResponse::Payload(payload))) => {
+                match self.check_payload(&payload, &request) {
+                    Ok(()) => (SUCCESS_LABEL, Some(payload)),
+                    Err(error) => {
+                        warn!("Invalid payload from the external block builder: {}", error);
+                        (INVALID_LABEL, None)
+                    },
+                }
+            },
+            Ok(Ok(ExternalPayloadResponse::NoPayload)) => (NO_PAYLOAD_LABEL, None),
+            Ok(Err(error)) => {
+                warn!(
+                    "Failed to request a payload from the external block builder: {}",
+                    error
+                );
+                (ERROR_LABEL, None)
+            },
+            Err(_) => (TIMEOUT_LABEL, None),
+        };
+        EXTERNAL_BLOCK_BUILDER_REQUESTS
+            .with_label_values(&[label])
+            .inc();
+        payload
+    }
+
+    /// Verifies that the payload from the external block builder can be proposed (see
+    /// `check_payload_limits`), and that the other validators will accept it, i.e., the
+    /// proofs of store are signed by a quorum of the current epoch and the inline batches
+    /// match their digests.
+    fn check_payload(
+        &self,
+        payload: &Payload,
+        request: &ExternalPayloadRequest,
+    ) -> anyhow::Result<()> {
+        check_payload_limits(payload, request, self.quorum_store_enabled)?;
+        payload.verify(
+            &self.validator_verifier,
+            &self.proof_cache,
+            self.quorum_store_enabled,
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl UserPayloadClient for ExternalBuilderClient {
+    async fn pull(
+        &self,
+        max_poll_time: Duration,
+        max_items: u64,
+        max_bytes: u64,
+        max_inline_items: u64,
+        max_inline_bytes: u64,
+        exclude: PayloadFilter,
+        wait_callback: BoxFuture<'static, ()>,
+        pending_ordering: bool,
+        pending_uncommitted_blocks: usize,
+        recent_max_fill_fraction: f32,
+    ) -> anyhow::Result<Payload, QuorumStoreError> {
+        // The builder is never given more time than the proposal can wait for
+        let start_time = Instant::now();
+        let request_timeout = min(self.request_timeout, max_poll_time);
+        let request = ExternalPayloadRequest {
+            max_items,
+            max_bytes,
+            max_inline_items,
+            max_inline_bytes,
+            exclude: exclude.clone(),
+            deadline_ms: request_timeout.as_millis() as u64,
+        };
+        if let Some(payload) = self
+            .request_external_payload(request, request_timeout)
+            .await
+        {
+            return Ok(payload);
+        }
+
+        // Otherwise, fall back to pulling the payload (for the remaining time)
+        self.fallback_client
+            .pull(
+                max_poll_time.saturating_sub(start_time.elapsed()),
+                max_items,
+                max_bytes,
+                max_inline_items,
+                max_inline_bytes,
+                exclude,
+                wait_callback,
+                pending_ordering,
+                pending_uncommitted_blocks,
+                recent_max_fill_fraction,
+            )
+            .await
+    }
+}
+
+/// Verifies that the payload from the external block builder has the expected type,
+/// respects the block limits and doesn't repeat any excluded payloads. These checks
+/// are cheap, so they are done before verifying the signatures.
+fn check_payload_limits(
+    payload: &Payload,
+    request: &ExternalPayloadRequest,
+    quorum_store_enabled: bool,
+) -> anyhow::Result<()> {
+    ensure!(
+        payload.is_direct() != quorum_store_enabled,
+        "Unexpected payload type! Quorum store enabled: {}",
+        quorum_store_enabled
+    );
+    ensure!(
+        payload.len() as u64 <= request.max_items,
+        "Too many transactions! Count: {}, max: {}",
+        payload.len(),
+        request.max_items
+    );
+    ensure!(
+        payload.size() as u64 <= request.max_bytes,
+        "Payload is too large! Size: {}, max: {}",
+        payload.size(),
+        request.max_bytes
+    );
+
+    match (payload, &request.exclude) {
+        (Payload::DirectMempool(txns), PayloadFilter::DirectMempool(excluded_txns)) => {
+            let excluded_txns: HashSet<_> = excluded_txns.iter().collect();
+            for txn in txns {
+                let summary = TransactionSummary::new(txn.sender(), txn.sequence_number());
+                ensure!(
+                    !excluded_txns.contains(&summary),
+                    "Transaction is already in a pending block: {:?}",
+                    summary
+                );
+            }
+        },
+        (Payload::QuorumStoreInlineHybrid(inline_batches, proofs, _), exclude) => {
+            let num_inline_txns: usize = inline_batches.iter().map(|(_, txns)| txns.len()).sum();
+            let inline_bytes: u64 = inline_batches
+                .iter()
+                .map(|(batch_info, _)| batch_info.num_bytes())
+                .sum();
+            ensure!(
+                num_inline_txns as u64 <= request.max_inline_items
+                    && inline_bytes <= request.max_inline_bytes,
+                "Inline batches exceed the limits! Count: {}, bytes: {}",
+                num_inline_txns,
+                inline_bytes
+            );
+            if let PayloadFilter::InQuorumStore(excluded_batches) = exclude {
+                let batches = inline_batches
+                    .iter()
+                    .map(|(batch_info, _)| batch_info)
+                    .chain(proofs.proofs.iter().map(|proof| proof.info()));
+                for batch in batches {
+                    ensure!(
+                        !excluded_batches.contains(batch),
+                        "Batch is already in a pending block: {}",
+                        batch
+                    );
+                }
+            }
+        },
+        (Payload::InQuorumStore(proofs), PayloadFilter::InQuorumStore(excluded_batches))
+        | (
+            Payload::InQuorumStoreWithLimit(ProofWithDataWithTxnLimit {
+                proof_with_data: proofs,
+                ..
+            }),
+            PayloadFilter::InQuorumStore(excluded_batches),
+        ) => {
+            for proof in &proofs.proofs {
+                ensure!(
+                    !excluded_batches.contains(proof.info()),
+                    "Batch is already in a pending block: {}",
+                    proof.info()
+                );
+            }
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{payload_client::user::DummyClient, test_utils::create_vec_signed_transactions};
+    use aptos_consensus_types::{
+        common::{BatchPayload, ProofWithData},
+        proof_of_store::{BatchId, BatchInfo, ProofOfStore},
+    };
+    use aptos_crypto::{hash::CryptoHash, HashValue};
+    use aptos_types::{
+        aggregate_signature::AggregateSignature, transaction::SignedTransaction,
+        validator_verifier::random_validator_verifier, PeerId,
+    };
+    use mini_moka::sync::Cache;
+
+    /// An external block builder that responds with the given payload after a delay
+    struct MockBlockBuilder {
+        response_delay: Duration,
+        response: ExternalPayloadResponse,
+    }
+
+    #[async_trait::async_trait]
+    impl ExternalBlockBuilder for MockBlockBuilder {
+        async fn request_payload(
+            &self,
+            _request: ExternalPayloadRequest,
+        ) -> anyhow::Result<ExternalPayloadResponse> {
+            tokio::time::sleep(self.response_delay).await;
+            Ok(self.response.clone())
+        }
+    }
+
+    async fn pull_payload(client: &ExternalBuilderClient, exclude: PayloadFilter) -> Payload {
+        client
+            .pull(
+                Duration::from_secs(1), // max_poll_time
+                100,                    // max_items
+                1048576,                // size limit: 1MB
+                50,
+                500000, // inline limit: 500KB
+                exclude,
+                Box::pin(async {}),
+                false,
+                0,
+                0.,
+            )
+            .await
+            .unwrap()
+    }
+
+    fn create_client(
+        response_delay: Duration,
+        builder_txns: Vec<SignedTransaction>,
+        fallback_txns: Vec<SignedTransaction>,
+    ) -> ExternalBuilderClient {
+        create_client_with_payload(
+            response_delay,
+            Payload::DirectMempool(builder_txns),
+            fallback_txns,
+            false,
+        )
+    }
+
+    fn create_client_with_payload(
+        response_delay: Duration,
+        builder_payload: Payload,
+        fallback_txns: Vec<SignedTransaction>,
+        quorum_store_enabled: bool,
+    ) -> ExternalBuilderClient {
+        let builder = MockBlockBuilder {
+            response_delay,
+            response: ExternalPayloadResponse::Payload(builder_payload),
+        };
+        let (_, validator_verifier) = random_validator_verifier(4, None, false);
+        ExternalBuilderClient::new(
+            Arc::new(builder),
+            Arc::new(DummyClient::new(fallback_txns)),
+            Duration::from_millis(100),
+            quorum_store_enabled,
+            validator_verifier,
+            Cache::builder().build(),
+        )
+    }
+
+    fn create_batch_info(
+        author: PeerId,
+        digest: HashValue,
+        txns: &[SignedTransaction],
+    ) -> BatchInfo {
+        let num_bytes = txns.iter().map(|txn| txn.raw_txn_bytes_len() as u64).sum();
+        BatchInfo::new(
+            author,
+            BatchId::new_for_test(1),
+            1,
+            u64::MAX,
+            digest,
+            txns.len() as u64,
+            num_bytes,
+            0,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_external_payload() {
+        let builder_txns = create_vec_signed_transactions(5);
+        let fallback_txns = create_vec_signed_transactions(3);
+        let client = create_client(Duration::ZERO, builder_txns.clone(), fallback_txns);
+
+        // The payload of the builder is proposed
+        let payload = pull_payload(&client, PayloadFilter::Empty).await;
+        assert_eq!(payload, Payload::DirectMempool(builder_txns));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_on_timeout() {
+        let builder_txns = create_vec_signed_transactions(5);
+        let fallback_txns = create_vec_signed_transactions(3);
+        let client = create_client(Duration::from_secs(5), builder_txns, fallback_txns.clone());
+
+        // The builder misses the deadline, so the payload is pulled from the fallback client
+        let start_time = Instant::now();
+        let payload = pull_payload(&client, PayloadFilter::Empty).await;
+        assert_eq!(payload, Payload::DirectMempool(fallback_txns));
+        assert!(start_time.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_on_invalid_payload() {
+        let builder_txns = create_vec_signed_transactions(5);
+        let fallback_txns = create_vec_signed_transactions(3);
+        let client = create_client(Duration::ZERO, builder_txns.clone(), fallback_txns.clone());
+
+        // The builder proposes a transaction of a pending block, so the payload is rejected
+        let exclude = PayloadFilter::DirectMempool(vec![TransactionSummary::new(
+            builder_txns[2].sender(),
+            builder_txns[2].sequence_number(),
+        )]);
+        let payload = pull_payload(&client, exclude).await;
+        assert_eq!(payload, Payload::DirectMempool(fallback_txns));
+
+        // A direct mempool payload is rejected when quorum store is enabled
+        let client = ExternalBuilderClient::new(
+            client.builder.clone(),
+            client.fallback_client.clone(),
+            Duration::from_millis(100),
+            true,
+            client.validator_verifier.clone(),
+            client.proof_cache.clone(),
+        );
+        let payload = pull_payload(&client, PayloadFilter::Empty).await;
+        assert_eq!(payload, Payload::DirectMempool(fallback_txns));
+    }
+
+    #[tokio::test]
+    async fn test_payload_verification() {
+        let author = PeerId::random();
+        let inline_txns = create_vec_signed_transactions(5);
+        let fallback_txns = create_vec_signed_transactions(3);
+
+        // An inline batch that matches its digest is proposed
+        let digest = BatchPayload::new(author, inline_txns.clone()).hash();
+        let inline_batch = (
+            create_batch_info(author, digest, &inline_txns),
+            inline_txns.clone(),
+        );
+        let valid_payload = Payload::QuorumStoreInlineHybrid(
+            vec![inline_batch.clone()],
+            ProofWithData::new(vec![]),
+            None,
+        );
+        let client = create_client_with_payload(
+            Duration::ZERO,
+            valid_payload.clone(),
+            fallback_txns.clone(),
+            true,
+        );
+        assert_eq!(
+            pull_payload(&client, PayloadFilter::Empty).await,
+            valid_payload
+        );
+
+        // An inline batch that doesn't match its digest is rejected
+        let invalid_batch = (
+            create_batch_info(author, HashValue::random(), &inline_txns),
+            inline_txns.clone(),
+        );
+        let invalid_payload =
+            Payload::QuorumStoreInlineHybrid(vec![invalid_batch], ProofWithData::new(vec![]), None);
+        let client = create_client_with_payload(
+            Duration::ZERO,
+            invalid_payload,
+            fallback_txns.clone(),
+            true,
+        );
+        assert_eq!(
+            pull_payload(&client, PayloadFilter::Empty).await,
+            Payload::DirectMempool(fallback_txns.clone())
+        );
+
+        // A proof of store that isn't signed by a quorum of the epoch is rejected
+        let unsigned_proof = ProofOfStore::new(inline_batch.0, AggregateSignature::empty());
+        let invalid_payload = Payload::QuorumStoreInlineHybrid(
+            vec![],
+            ProofWithData::new(vec![unsigned_proof]),
+            None,
+        );
+        let client = create_client_with_payload(
+            Duration::ZERO,
+            invalid_payload,
+            fallback_txns.clone(),
+            true,
+        );
+        assert_eq!(
+            pull_payload(&client, PayloadFilter::Empty).await,
+            Payload::DirectMempool(fallback_txns)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_message_framing() {
+        let (mut writer, mut reader) = tokio::io::duplex(1024);
+        let request = ExternalPayloadRequest {
+            max_items: 10,
+            max_bytes: 1024,
+            max_inline_items: 5,
+            max_inline_bytes: 512,
+            exclude: PayloadFilter::Empty,
+            deadline_ms: 100,
+        };
+
+        // A message round trips through the stream
+        let message = bcs::to_bytes(&request).unwrap();
+        write_message(&mut writer, &message).await.unwrap();
+        let received = read_message(&mut reader, 1024).await.unwrap();
+        assert_eq!(
+            bcs::from_bytes::<ExternalPayloadRequest>(&received).unwrap(),
+            request
+        );
+
+        // Messages larger than the limit are rejected
+        write_message(&mut writer, &message).await.unwrap();
+        assert!(read_message(&mut reader, message.len() - 1).await.is_err());
+    }
+}
@@ -81,4 +81,5 @@ impl UserPayloadClient for DummyClient {
     }
 }
 
+pub mod external_builder_client;
 pub mod quorum_store_client;
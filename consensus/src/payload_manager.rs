@@ -12,16 +12,136 @@ use aptos_consensus_types::{
 };
 use aptos_crypto::HashValue;
 use aptos_executor_types::{ExecutorError::DataNotFound, *};
+use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
-use aptos_types::transaction::SignedTransaction;
+use aptos_types::{transaction::SignedTransaction, PeerId};
 use futures::channel::mpsc::Sender;
-use std::sync::Arc;
-use tokio::sync::oneshot;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::oneshot, time::timeout};
+
+// Useful labels for the batch prefetch requests
+const PREFETCH_REQUESTED_LABEL: &str = "requested";
+const PREFETCH_DEDUPLICATED_LABEL: &str = "deduplicated";
+const PREFETCH_FAILED_LABEL: &str = "failed";
+const PREFETCH_TIMEOUT_LABEL: &str = "timeout";
+
+type BatchResultSender = oneshot::Sender<ExecutorResult<Vec<SignedTransaction>>>;
 
 pub trait TPayloadManager: Send + Sync {
     fn prefetch_payload_data(&self, payload: &Payload, timestamp: u64);
 }
 
+/// Prefetches the batches behind the proofs in proposals (e.g., as soon as a proposal is
+/// received from the leader), so that the batches are available by the time the block
+/// is executed. Missing batches are requested from the batch author and the proof signers
+/// in parallel (see `BatchRequester`). Concurrent requests for the same batch (e.g., when
+/// a proposal is received, and later when the block is inserted) are deduplicated, and
+/// requests that don't complete before the prefetch timeout are abandoned, so that the
+/// batch can be requested again.
+pub struct BatchPrefetcher {
+    batch_reader: Arc<dyn BatchReader>,
+    prefetch_timeout: Duration,
+
+    // The pending batch requests (by batch digest), and the senders waiting on each request
+    pending_requests: Arc<Mutex<HashMap<HashValue, Vec<BatchResultSender>>>>,
+}
+
+impl BatchPrefetcher {
+    pub fn new(batch_reader: Arc<dyn BatchReader>, prefetch_timeout: Duration) -> Self {
+        Self {
+            batch_reader,
+            prefetch_timeout,
+            pending_requests: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the number of pending batch requests
+    #[cfg(test)]
+    pub fn num_pending_requests(&self) -> usize {
+        self.pending_requests.lock().len()
+    }
+
+    /// Notifies all senders waiting on the given batch request
+    fn notify_waiters(
+        pending_requests: &Mutex<HashMap<HashValue, Vec<BatchResultSender>>>,
+        digest: &HashValue,
+        result: Option<Vec<SignedTransaction>>,
+    ) {
+        let waiters = pending_requests.lock().remove(digest).unwrap_or_default();
+        for waiter in waiters {
+            let waiter_result = match &result {
+                Some(payload) => Ok(payload.clone()),
+                None => Err(ExecutorError::CouldNotGetData),
+            };
+            if waiter.send(waiter_result).is_err() {
+                debug!(
+                    "Receiver of prefetched batch not available for digest {}",
+                    digest
+                );
+            }
+        }
+    }
+}
+
+impl BatchReader for BatchPrefetcher {
+    fn exists(&self, digest: &HashValue) -> Option<PeerId> {
+        self.batch_reader.exists(digest)
+    }
+
+    fn get_batch(
+        &self,
+        proof: ProofOfStore,
+    ) -> oneshot::Receiver<ExecutorResult<Vec<SignedTransaction>>> {
+        let (tx, rx) = oneshot::channel();
+        let digest = *proof.digest();
+
+        // If the batch is already being requested, wait on the existing request
+        {
+            let mut pending_requests = self.pending_requests.lock();
+            if let Some(waiters) = pending_requests.get_mut(&digest) {
+                counters::BATCH_PREFETCH_REQUESTS
+                    .with_label_values(&[PREFETCH_DEDUPLICATED_LABEL])
+                    .inc();
+                waiters.push(tx);
+                return rx;
+            }
+            pending_requests.insert(digest, vec![tx]);
+        }
+        counters::BATCH_PREFETCH_REQUESTS
+            .with_label_values(&[PREFETCH_REQUESTED_LABEL])
+            .inc();
+
+        // Otherwise, request the batch and notify all waiters once it's available
+        let batch_rx = self.batch_reader.get_batch(proof);
+        let prefetch_timeout = self.prefetch_timeout;
+        let pending_requests = self.pending_requests.clone();
+        tokio::spawn(async move {
+            let result = match timeout(prefetch_timeout, batch_rx).await {
+                Ok(Ok(Ok(payload))) => Some(payload),
+                Ok(_) => {
+                    counters::BATCH_PREFETCH_REQUESTS
+                        .with_label_values(&[PREFETCH_FAILED_LABEL])
+                        .inc();
+                    None
+                },
+                Err(_) => {
+                    counters::BATCH_PREFETCH_REQUESTS
+                        .with_label_values(&[PREFETCH_TIMEOUT_LABEL])
+                        .inc();
+                    debug!("QS: batch prefetch timed out, digest: {}", digest);
+                    None
+                },
+            };
+            Self::notify_waiters(&pending_requests, &digest, result);
+        });
+        rx
+    }
+
+    fn update_certified_timestamp(&self, certified_time: u64) {
+        self.batch_reader.update_certified_timestamp(certified_time);
+    }
+}
+
 /// Responsible to extract the transactions out of the payload and notify QuorumStore about commits.
 /// If QuorumStore is enabled, has to ask BatchReader for the transaction behind the proofs of availability in the payload.
 pub enum PayloadManager {
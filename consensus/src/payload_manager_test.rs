@@ -0,0 +1,93 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    payload_manager::BatchPrefetcher,
+    quorum_store::{batch_store::BatchReader, types::Batch},
+};
+use aptos_consensus_types::proof_of_store::{BatchId, ProofOfStore};
+use aptos_crypto::HashValue;
+use aptos_executor_types::{ExecutorError, ExecutorResult};
+use aptos_infallible::Mutex;
+use aptos_types::{
+    aggregate_signature::AggregateSignature, transaction::SignedTransaction, PeerId,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::oneshot;
+
+/// A mock batch reader that holds on to all batch requests (so that
+/// the tests can decide when, and how, the requests are completed).
+#[derive(Default)]
+struct MockBatchReader {
+    requests: Mutex<Vec<oneshot::Sender<ExecutorResult<Vec<SignedTransaction>>>>>,
+}
+
+impl MockBatchReader {
+    fn num_requests(&self) -> usize {
+        self.requests.lock().len()
+    }
+}
+
+impl BatchReader for MockBatchReader {
+    fn exists(&self, _digest: &HashValue) -> Option<PeerId> {
+        None
+    }
+
+    fn get_batch(
+        &self,
+        _proof: ProofOfStore,
+    ) -> oneshot::Receiver<ExecutorResult<Vec<SignedTransaction>>> {
+        let (tx, rx) = oneshot::channel();
+        self.requests.lock().push(tx);
+        rx
+    }
+
+    fn update_certified_timestamp(&self, _certified_time: u64) {}
+}
+
+#[tokio::test]
+async fn test_batch_prefetch_deduplication() {
+    // Create a batch prefetcher
+    let batch_reader = Arc::new(MockBatchReader::default());
+    let batch_prefetcher = BatchPrefetcher::new(batch_reader.clone(), Duration::from_secs(60));
+
+    // Request the same batch twice, and verify that only a single request is sent
+    let proof = create_proof_of_store();
+    let first_rx = batch_prefetcher.get_batch(proof.clone());
+    let second_rx = batch_prefetcher.get_batch(proof);
+    assert_eq!(batch_reader.num_requests(), 1);
+    assert_eq!(batch_prefetcher.num_pending_requests(), 1);
+
+    // Complete the request, and verify that both receivers are notified
+    let request = batch_reader.requests.lock().pop().unwrap();
+    request.send(Ok(vec![])).unwrap();
+    assert_eq!(first_rx.await.unwrap(), Ok(vec![]));
+    assert_eq!(second_rx.await.unwrap(), Ok(vec![]));
+    assert_eq!(batch_prefetcher.num_pending_requests(), 0);
+}
+
+#[tokio::test]
+async fn test_batch_prefetch_timeout() {
+    // Create a batch prefetcher with a short prefetch timeout
+    let batch_reader = Arc::new(MockBatchReader::default());
+    let batch_prefetcher = BatchPrefetcher::new(batch_reader.clone(), Duration::from_millis(10));
+
+    // Request a batch (that is never served), and verify that the request times out
+    let proof = create_proof_of_store();
+    let rx = batch_prefetcher.get_batch(proof.clone());
+    assert_eq!(rx.await.unwrap(), Err(ExecutorError::CouldNotGetData));
+    assert_eq!(batch_prefetcher.num_pending_requests(), 0);
+
+    // Verify that the batch can be requested again
+    let _rx = batch_prefetcher.get_batch(proof);
+    assert_eq!(batch_reader.num_requests(), 2);
+}
+
+/// Creates a proof of store for a test batch
+fn create_proof_of_store() -> ProofOfStore {
+    let batch = Batch::new(BatchId::new_for_test(1), vec![], 1, 1, PeerId::random(), 0);
+    ProofOfStore::new(
+        batch.batch_info().clone(),
+        AggregateSignature::new(vec![u8::MAX].into(), None),
+    )
+}
@@ -2,7 +2,11 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{consensusdb::ConsensusDB, epoch_manager::LivenessStorageData, error::DbError};
+use crate::{
+    consensusdb::{ConsensusDB, ConsensusDBPruner},
+    epoch_manager::LivenessStorageData,
+    error::DbError,
+};
 use anyhow::{format_err, Context, Result};
 use aptos_config::config::NodeConfig;
 use aptos_consensus_types::{
@@ -312,12 +316,22 @@ impl RecoveryData {
 pub struct StorageWriteProxy {
     db: Arc<ConsensusDB>,
     aptos_db: Arc<dyn DbReader>,
+    // Prunes stale blocks and quorum certs in the background (if enabled)
+    _pruner: Option<ConsensusDBPruner>,
 }
 
 impl StorageWriteProxy {
     pub fn new(config: &NodeConfig, aptos_db: Arc<dyn DbReader>) -> Self {
         let db = Arc::new(ConsensusDB::new(config.storage.dir()));
-        StorageWriteProxy { db, aptos_db }
+        let pruner_config = &config.consensus.consensusdb_pruner;
+        let pruner = pruner_config
+            .enable
+            .then(|| ConsensusDBPruner::new(pruner_config.clone(), db.clone(), aptos_db.clone()));
+        StorageWriteProxy {
+            db,
+            aptos_db,
+            _pruner: pruner,
+        }
     }
 }
 
@@ -43,7 +43,6 @@ impl<T: Hashable> Buffer<T> {
         &self.head
     }
 
-    #[cfg(test)]
     pub fn tail_cursor(&self) -> &Cursor {
         &self.tail
     }
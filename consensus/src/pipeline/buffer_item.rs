@@ -5,7 +5,9 @@
 use crate::{pipeline::hashable::Hashable, state_replication::StateComputerCommitCallBackType};
 use anyhow::anyhow;
 use aptos_consensus_types::{
-    common::Author, pipeline::commit_vote::CommitVote, pipelined_block::PipelinedBlock,
+    common::{Author, Round},
+    pipeline::commit_vote::CommitVote,
+    pipelined_block::PipelinedBlock,
 };
 use aptos_crypto::{bls12381, HashValue};
 use aptos_executor_types::ExecutorResult;
@@ -384,6 +386,10 @@ impl BufferItem {
         self.get_blocks().last().unwrap().id()
     }
 
+    pub fn round(&self) -> Round {
+        self.get_blocks().last().unwrap().round()
+    }
+
     pub fn add_signature_if_matched(&mut self, vote: CommitVote) -> anyhow::Result<()> {
         let target_commit_info = vote.commit_info();
         let author = vote.author();
@@ -21,7 +21,9 @@ use crate::{
 };
 use aptos_bounded_executor::BoundedExecutor;
 use aptos_consensus_types::{
-    common::Author, pipeline::commit_decision::CommitDecision, pipelined_block::PipelinedBlock,
+    common::{Author, Round},
+    pipeline::commit_decision::CommitDecision,
+    pipelined_block::PipelinedBlock,
 };
 use aptos_crypto::HashValue;
 use aptos_executor_types::ExecutorError;
@@ -130,6 +132,9 @@ pub struct BufferManager {
     previous_commit_time: Instant,
     reset_flag: Arc<AtomicBool>,
     bounded_executor: BoundedExecutor,
+    // In order-only mode, the commit votes are skipped for the executed blocks that lag behind
+    // the latest ordered block by more than this number of rounds (None disables the mode).
+    order_only_execution_lag: Option<Round>,
 }
 
 impl BufferManager {
@@ -154,6 +159,7 @@ impl BufferManager {
         ongoing_tasks: Arc<AtomicU64>,
         reset_flag: Arc<AtomicBool>,
         executor: BoundedExecutor,
+        order_only_execution_lag: Option<Round>,
     ) -> Self {
         let buffer = Buffer::<BufferItem>::new();
 
@@ -199,6 +205,7 @@ impl BufferManager {
             previous_commit_time: Instant::now(),
             reset_flag,
             bounded_executor: executor,
+            order_only_execution_lag,
         }
     }
 
@@ -294,14 +301,31 @@ impl BufferManager {
         // come in.
     }
 
+    /// Returns the lowest round of the executed items that are signed (i.e., commit voted).
+    /// In order-only mode, the validator doesn't vote on the execution results of the items
+    /// that lag too far behind the latest ordered block, as it's still catching up. These
+    /// items can still be committed with the votes of the other validators, or by committing
+    /// a later item, and the validator resumes commit voting once it catches up.
+    fn lowest_commit_vote_round(&self) -> Round {
+        match (self.order_only_execution_lag, self.buffer.tail_cursor()) {
+            (Some(execution_lag), Some(_)) => self
+                .buffer
+                .get(self.buffer.tail_cursor())
+                .round()
+                .saturating_sub(execution_lag),
+            _ => 0,
+        }
+    }
+
     /// Set the signing root to the first not signed item (Executed) and send execution request
     /// Set to None if not exist
     async fn advance_signing_root(&mut self) {
         let cursor = self.signing_root;
+        let lowest_commit_vote_round = self.lowest_commit_vote_round();
         self.signing_root = self
             .buffer
             .find_elem_from(cursor.or_else(|| *self.buffer.head_cursor()), |item| {
-                item.is_executed()
+                item.is_executed() && item.round() >= lowest_commit_vote_round
             });
         info!(
             "Advance signing root from {:?} to {:?}",
@@ -669,18 +693,24 @@ impl BufferManager {
 
     fn update_buffer_manager_metrics(&self) {
         let mut cursor = *self.buffer.head_cursor();
+        let lowest_commit_vote_round = self.lowest_commit_vote_round();
         let mut pending_ordered = 0;
         let mut pending_executed = 0;
+        let mut pending_order_only = 0;
         let mut pending_signed = 0;
         let mut pending_aggregated = 0;
 
         while cursor.is_some() {
-            match self.buffer.get(&cursor) {
+            let item = self.buffer.get(&cursor);
+            match item {
                 BufferItem::Ordered(_) => {
                     pending_ordered += 1;
                 },
                 BufferItem::Executed(_) => {
                     pending_executed += 1;
+                    if item.round() < lowest_commit_vote_round {
+                        pending_order_only += 1;
+                    }
                 },
                 BufferItem::Signed(_) => {
                     pending_signed += 1;
@@ -698,6 +728,9 @@ impl BufferManager {
         counters::NUM_BLOCKS_IN_PIPELINE
             .with_label_values(&["executed"])
             .set(pending_executed as i64);
+        counters::NUM_BLOCKS_IN_PIPELINE
+            .with_label_values(&["executed_order_only"])
+            .set(pending_order_only as i64);
         counters::NUM_BLOCKS_IN_PIPELINE
             .with_label_values(&["signed"])
             .set(pending_signed as i64);
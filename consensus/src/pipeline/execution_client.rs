@@ -3,6 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    consensus_observer::{
+        network_message::ConsensusObserverMessage, publisher::ConsensusPublisher,
+    },
     counters,
     error::StateSyncError,
     network::{IncomingCommitRequest, IncomingRandGenRequest, NetworkSender},
@@ -28,7 +31,7 @@ use anyhow::Result;
 use aptos_bounded_executor::BoundedExecutor;
 use aptos_channels::{aptos_channel, message_queues::QueueStyle};
 use aptos_config::config::ConsensusConfig;
-use aptos_consensus_types::{common::Author, pipelined_block::PipelinedBlock};
+use aptos_consensus_types::{block::Block, common::Author, pipelined_block::PipelinedBlock};
 use aptos_executor_types::ExecutorResult;
 use aptos_infallible::RwLock;
 use aptos_logger::prelude::*;
@@ -76,6 +79,9 @@ pub trait TExecutionClient: Send + Sync {
         callback: StateComputerCommitCallBackType,
     ) -> ExecutorResult<()>;
 
+    /// Speculatively execute a verified proposal before it is ordered (if enabled).
+    async fn speculatively_execute(&self, block: &Block);
+
     fn send_commit_msg(
         &self,
         peer_id: AccountAddress,
@@ -143,6 +149,8 @@ pub struct ExecutionProxyClient {
     // channels to buffer manager
     handle: Arc<RwLock<BufferManagerHandle>>,
     rand_storage: Arc<dyn RandStorage<AugmentedData>>,
+    // publishes the ordered blocks and commit decisions to consensus observers
+    consensus_publisher: Option<ConsensusPublisher>,
 }
 
 impl ExecutionProxyClient {
@@ -154,6 +162,7 @@ impl ExecutionProxyClient {
         network_sender: ConsensusNetworkClient<NetworkClient<ConsensusMsg>>,
         bounded_executor: BoundedExecutor,
         rand_storage: Arc<dyn RandStorage<AugmentedData>>,
+        consensus_publisher: Option<ConsensusPublisher>,
     ) -> Self {
         Self {
             consensus_config,
@@ -164,6 +173,7 @@ impl ExecutionProxyClient {
             bounded_executor,
             handle: Arc::new(RwLock::new(BufferManagerHandle::new())),
             rand_storage,
+            consensus_publisher,
         }
     }
 
@@ -213,6 +223,7 @@ impl ExecutionProxyClient {
                     self.rand_storage.clone(),
                     self.bounded_executor.clone(),
                     &self.consensus_config.rand_rb_config,
+                    &self.consensus_config.rand_share_backfill,
                 );
 
                 tokio::spawn(rand_manager.start(
@@ -256,6 +267,10 @@ impl ExecutionProxyClient {
             reset_buffer_manager_rx,
             epoch_state,
             self.bounded_executor.clone(),
+            // In order-only mode, commit votes are skipped for the blocks that lag behind
+            // ordering by more than the vote back pressure limit
+            (self.consensus_config.order_only_max_execution_lag > 0)
+                .then_some(self.consensus_config.vote_back_pressure_limit),
         );
 
         tokio::spawn(execution_schedule_phase.start());
@@ -330,6 +345,22 @@ impl TExecutionClient for ExecutionProxyClient {
             block.set_insertion_time();
         }
 
+        // Publish the ordered block, and the commit decision once the blocks are committed
+        let callback: StateComputerCommitCallBackType = match self.consensus_publisher.clone() {
+            Some(consensus_publisher) => {
+                consensus_publisher.publish_message(ConsensusObserverMessage::OrderedBlock {
+                    ordered_proof: ordered_proof.clone(),
+                });
+                Box::new(move |committed_blocks, commit_proof| {
+                    consensus_publisher.publish_message(ConsensusObserverMessage::CommitDecision {
+                        commit_proof: commit_proof.clone(),
+                    });
+                    callback(committed_blocks, commit_proof);
+                })
+            },
+            None => callback,
+        };
+
         if execute_tx
             .unwrap()
             .send(OrderedBlocks {
@@ -348,6 +379,15 @@ impl TExecutionClient for ExecutionProxyClient {
         Ok(())
     }
 
+    async fn speculatively_execute(&self, block: &Block) {
+        if !self.consensus_config.optimistic_execution.enable || block.is_nil_block() {
+            return;
+        }
+        self.execution_proxy
+            .schedule_speculative_compute(block, block.parent_id())
+            .await;
+    }
+
     fn send_commit_msg(
         &self,
         peer_id: AccountAddress,
@@ -474,6 +514,8 @@ impl TExecutionClient for DummyExecutionClient {
         Ok(())
     }
 
+    async fn speculatively_execute(&self, _: &Block) {}
+
     fn send_commit_msg(&self, _: AccountAddress, _: IncomingCommitRequest) -> Result<()> {
         Ok(())
     }
@@ -11,6 +11,7 @@ use crate::{
             create_channel, BufferManager, OrderedBlocks, Receiver, ResetAck, ResetRequest,
             ResetSignal, Sender,
         },
+        commit_reliable_broadcast::CommitMessage,
         decoupled_execution_utils::prepare_phases_and_buffer_manager,
         execution_schedule_phase::ExecutionSchedulePhase,
         execution_wait_phase::ExecutionWaitPhase,
@@ -20,16 +21,16 @@ use crate::{
         tests::test_utils::prepare_executed_blocks_with_ledger_info,
     },
     test_utils::{
-        consensus_runtime, timed_block_on, EmptyStateComputer, MockStorage,
-        RandomComputeResultStateComputer,
+        consensus_runtime, timed_block_on, DelayedExecutionStateComputer, EmptyStateComputer,
+        MockStorage,
     },
 };
 use aptos_bounded_executor::BoundedExecutor;
 use aptos_channels::{aptos_channel, message_queues::QueueStyle};
 use aptos_config::network_id::NetworkId;
 use aptos_consensus_types::{
-    block::block_test_utils::certificate_for_genesis, pipelined_block::PipelinedBlock,
-    vote_proposal::VoteProposal,
+    block::block_test_utils::certificate_for_genesis, common::Round,
+    pipelined_block::PipelinedBlock, vote_proposal::VoteProposal,
 };
 use aptos_crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
 use aptos_infallible::Mutex;
@@ -59,6 +60,8 @@ use tokio::runtime::Runtime;
 
 pub fn prepare_buffer_manager(
     bounded_executor: BoundedExecutor,
+    order_only_execution_lag: Option<Round>,
+    num_delayed_blocks: usize,
 ) -> (
     BufferManager,
     Sender<OrderedBlocks>,
@@ -133,7 +136,7 @@ pub fn prepare_buffer_manager(
     let (block_tx, block_rx) = create_channel::<OrderedBlocks>();
     let (buffer_reset_tx, buffer_reset_rx) = create_channel::<ResetRequest>();
 
-    let mocked_execution_proxy = Arc::new(RandomComputeResultStateComputer::new());
+    let mocked_execution_proxy = Arc::new(DelayedExecutionStateComputer::new(num_delayed_blocks));
     let hash_val = mocked_execution_proxy.get_root_hash();
 
     let (
@@ -156,6 +159,7 @@ pub fn prepare_buffer_manager(
             verifier: validators.clone(),
         }),
         bounded_executor,
+        order_only_execution_lag,
     );
 
     (
@@ -175,7 +179,10 @@ pub fn prepare_buffer_manager(
     )
 }
 
-pub fn launch_buffer_manager() -> (
+pub fn launch_buffer_manager(
+    order_only_execution_lag: Option<Round>,
+    num_delayed_blocks: usize,
+) -> (
     Sender<OrderedBlocks>,
     Sender<ResetRequest>,
     aptos_channel::Sender<AccountAddress, IncomingCommitRequest>,
@@ -203,7 +210,11 @@ pub fn launch_buffer_manager() -> (
         signers,
         result_rx,
         validators,
-    ) = prepare_buffer_manager(bounded_executor);
+    ) = prepare_buffer_manager(
+        bounded_executor,
+        order_only_execution_lag,
+        num_delayed_blocks,
+    );
 
     runtime.spawn(execution_schedule_phase_pipeline.start());
     runtime.spawn(execution_wait_phase_pipeline.start());
@@ -248,6 +259,29 @@ async fn loopback_commit_vote(
     };
 }
 
+/// Loops back the messages sent by the buffer manager until a commit vote is found, and
+/// returns the id of the block it votes for.
+async fn loopback_until_commit_vote(
+    self_loop_rx: &mut aptos_channels::UnboundedReceiver<Event<ConsensusMsg>>,
+    msg_tx: &aptos_channel::Sender<AccountAddress, IncomingCommitRequest>,
+    verifier: &ValidatorVerifier,
+) -> HashValue {
+    loop {
+        let msg = self_loop_rx.next().await.unwrap();
+        let voted_block_id = match &msg {
+            Event::RpcRequest(_, ConsensusMsg::CommitMessage(msg), _, _) => match msg.as_ref() {
+                CommitMessage::Vote(vote) => Some(vote.commit_info().id()),
+                _ => None,
+            },
+            _ => None,
+        };
+        loopback_commit_vote(msg, msg_tx, verifier).await;
+        if let Some(block_id) = voted_block_id {
+            return block_id;
+        }
+    }
+}
+
 async fn assert_results(
     batches: Vec<Vec<PipelinedBlock>>,
     result_rx: &mut Receiver<OrderedBlocks>,
@@ -278,7 +312,7 @@ fn buffer_manager_happy_path_test() {
         signers,
         mut result_rx,
         verifier,
-    ) = launch_buffer_manager();
+    ) = launch_buffer_manager(None, 0);
 
     let genesis_qc = certificate_for_genesis();
     let num_batches = 3;
@@ -342,7 +376,7 @@ fn buffer_manager_sync_test() {
         signers,
         mut result_rx,
         verifier,
-    ) = launch_buffer_manager();
+    ) = launch_buffer_manager(None, 0);
 
     let genesis_qc = certificate_for_genesis();
     let num_batches = 100;
@@ -419,3 +453,100 @@ fn buffer_manager_sync_test() {
         assert!(result_rx.next().now_or_never().is_none());
     });
 }
+
+#[test]
+fn buffer_manager_order_only_test() {
+    let num_batches = 4;
+    let blocks_per_batch = 5;
+    // The execution is held back until the first three batches are ordered, i.e., the
+    // validator lags behind ordering in execution
+    let num_lagging_batches = 3;
+    let (
+        mut block_tx,
+        _reset_tx,
+        msg_tx,
+        mut self_loop_rx,
+        _hash_val,
+        runtime,
+        signers,
+        mut result_rx,
+        verifier,
+    ) = launch_buffer_manager(
+        Some(blocks_per_batch - 1),
+        num_lagging_batches * blocks_per_batch as usize,
+    );
+
+    let genesis_qc = certificate_for_genesis();
+    let mut init_round = 0;
+
+    let mut batches = vec![];
+    let mut proofs = vec![];
+    let mut last_proposal: Option<VoteProposal> = None;
+
+    for _ in 0..num_batches {
+        let (vecblocks, li_sig, proposal) = prepare_executed_blocks_with_ledger_info(
+            &signers[0],
+            blocks_per_batch,
+            *ACCUMULATOR_PLACEHOLDER_HASH,
+            *ACCUMULATOR_PLACEHOLDER_HASH,
+            last_proposal,
+            Some(genesis_qc.clone()),
+            init_round,
+        );
+        init_round += blocks_per_batch;
+        batches.push(vecblocks);
+        proofs.push(li_sig);
+        last_proposal = Some(proposal.last().unwrap().clone());
+    }
+
+    timed_block_on(&runtime, async move {
+        for i in 0..num_lagging_batches {
+            block_tx
+                .send(OrderedBlocks {
+                    ordered_blocks: batches[i].clone(),
+                    ordered_proof: proofs[i].clone(),
+                    callback: Box::new(move |_, _| {}),
+                })
+                .await
+                .ok();
+        }
+
+        // The commit votes are skipped for the batches that lag behind the latest ordered
+        // batch by more than the execution lag, so only the latest one is voted for
+        let last_lagging_batch = &batches[num_lagging_batches - 1];
+        assert_eq!(
+            loopback_until_commit_vote(&mut self_loop_rx, &msg_tx, &verifier).await,
+            last_lagging_batch.last().unwrap().id()
+        );
+
+        // The skipped batches are committed together with the latest one
+        let OrderedBlocks { ordered_blocks, .. } = result_rx.next().await.unwrap();
+        assert_eq!(
+            ordered_blocks.len(),
+            num_lagging_batches * blocks_per_batch as usize
+        );
+        assert_eq!(
+            ordered_blocks.last().unwrap().id(),
+            last_lagging_batch.last().unwrap().id()
+        );
+
+        // Once caught up, the validator resumes commit voting
+        block_tx
+            .send(OrderedBlocks {
+                ordered_blocks: batches[num_lagging_batches].clone(),
+                ordered_proof: proofs[num_lagging_batches].clone(),
+                callback: Box::new(move |_, _| {}),
+            })
+            .await
+            .ok();
+        assert_eq!(
+            loopback_until_commit_vote(&mut self_loop_rx, &msg_tx, &verifier).await,
+            batches[num_lagging_batches].last().unwrap().id()
+        );
+        assert_results(
+            batches.drain(num_lagging_batches..).collect(),
+            &mut result_rx,
+        )
+        .await;
+    });
+}
@@ -6,8 +6,9 @@ use crate::{
     quorum_store::{
         batch_store::BatchWriter,
         counters,
+        load_aware_batching::LoadAwareBatching,
         quorum_store_db::QuorumStoreStorage,
-        types::Batch,
+        types::{Batch, BatchQueueLengthHintMsg},
         utils::{MempoolProxy, TimeExpirations},
     },
 };
@@ -31,6 +32,7 @@ use tokio::time::Interval;
 pub enum BatchGeneratorCommand {
     CommitNotification(u64, Vec<BatchInfo>),
     ProofExpiration(Vec<BatchId>),
+    PeerQueueLengthHint(PeerId, u64),
     Shutdown(tokio::sync::oneshot::Sender<()>),
 }
 
@@ -40,6 +42,14 @@ pub struct BackPressure {
     pub proof_count: bool,
 }
 
+impl BackPressure {
+    /// Returns true iff quorum store is back pressured, i.e., proposals are not
+    /// draining the local queue (e.g., because of execution pipeline back pressure).
+    pub fn is_back_pressured(&self) -> bool {
+        self.txn_count || self.proof_count
+    }
+}
+
 pub struct BatchGenerator {
     epoch: u64,
     my_peer_id: PeerId,
@@ -55,6 +65,8 @@ pub struct BatchGenerator {
     last_end_batch_time: Instant,
     // quorum store back pressure, get updated from proof manager
     back_pressure: BackPressure,
+    // defers batch creation if the local queue is much longer than the peers' queues
+    load_aware_batching: LoadAwareBatching,
 }
 
 impl BatchGenerator {
@@ -83,6 +95,8 @@ impl BatchGenerator {
         db.save_batch_id(epoch, incremented_batch_id)
             .expect("Could not save to db");
 
+        let load_aware_batching = LoadAwareBatching::new(config.load_aware_batching);
+
         Self {
             epoch,
             my_peer_id,
@@ -100,6 +114,7 @@ impl BatchGenerator {
                 txn_count: false,
                 proof_count: false,
             },
+            load_aware_batching,
         }
     }
 
@@ -359,7 +374,16 @@ impl BatchGenerator {
 
             tokio::select! {
                 Some(updated_back_pressure) = back_pressure_rx.recv() => {
+                    let was_back_pressured = self.back_pressure.is_back_pressured();
                     self.back_pressure = updated_back_pressure;
+
+                    // Let mempool know that it can slow down (or resume) broadcasts
+                    let is_back_pressured = self.back_pressure.is_back_pressured();
+                    if is_back_pressured != was_back_pressured {
+                        if let Err(e) = self.mempool_proxy.notify_back_pressure(is_back_pressured) {
+                            warn!("QS: failed to notify mempool of back pressure: {:?}", e);
+                        }
+                    }
                 },
                 _ = interval.tick() => monitor!("batch_generator_handle_tick", {
 
@@ -395,13 +419,23 @@ impl BatchGenerator {
                     } else {
                         counters::QS_BACKPRESSURE_PROOF_COUNT.observe(0.0);
                     }
+                    let num_txns_in_progress = self.txns_in_progress_sorted.len() as u64;
+                    if self.load_aware_batching.should_broadcast_hint(tick_start) {
+                        let hint = BatchQueueLengthHintMsg::new(
+                            self.epoch,
+                            self.my_peer_id,
+                            num_txns_in_progress,
+                        );
+                        network_sender.broadcast_batch_queue_length_hint_msg(hint).await;
+                    }
                     let since_last_non_empty_pull_ms = std::cmp::min(
                         tick_start.duration_since(last_non_empty_pull).as_millis(),
                         self.config.batch_generation_max_interval_ms as u128
                     ) as usize;
-                    if (!self.back_pressure.proof_count
+                    if ((!self.back_pressure.proof_count
                         && since_last_non_empty_pull_ms >= self.config.batch_generation_min_non_empty_interval_ms)
-                        || since_last_non_empty_pull_ms == self.config.batch_generation_max_interval_ms {
+                        || since_last_non_empty_pull_ms == self.config.batch_generation_max_interval_ms)
+                        && !self.load_aware_batching.should_defer(num_txns_in_progress, tick_start) {
 
                         let dynamic_pull_max_txn = std::cmp::max(
                             (since_last_non_empty_pull_ms as f64 / 1000.0 * dynamic_pull_txn_per_s as f64) as u64, 1);
@@ -478,6 +512,16 @@ impl BatchGenerator {
                                 self.remove_batch_in_progress(&batch_id);
                             }
                         }
+                        BatchGeneratorCommand::PeerQueueLengthHint(author, num_txns_in_progress) => {
+                            // Own hints are also delivered, as broadcast includes self.
+                            if author != self.my_peer_id {
+                                self.load_aware_batching.update_peer_queue_length(
+                                    author,
+                                    num_txns_in_progress,
+                                    Instant::now(),
+                                );
+                            }
+                        },
                         BatchGeneratorCommand::Shutdown(ack_tx) => {
                             ack_tx
                                 .send(())
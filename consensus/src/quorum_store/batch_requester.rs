@@ -20,6 +20,7 @@ use std::{sync::Arc, time::Duration};
 use tokio::{sync::oneshot, time};
 
 struct BatchRequesterState {
+    author: Option<PeerId>,
     signers: Vec<PeerId>,
     next_index: usize,
     ret_tx: oneshot::Sender<ExecutorResult<Vec<SignedTransaction>>>,
@@ -29,11 +30,13 @@ struct BatchRequesterState {
 
 impl BatchRequesterState {
     fn new(
+        author: Option<PeerId>,
         signers: Vec<PeerId>,
         ret_tx: oneshot::Sender<ExecutorResult<Vec<SignedTransaction>>>,
         retry_limit: usize,
     ) -> Self {
         Self {
+            author,
             signers,
             next_index: 0,
             ret_tx,
@@ -52,8 +55,7 @@ impl BatchRequesterState {
             counters::SENT_BATCH_REQUEST_RETRY_COUNT.inc_by(num_peers as u64);
         }
         if self.num_retries < self.retry_limit {
-            self.num_retries += 1;
-            let ret = self
+            let mut ret: Vec<_> = self
                 .signers
                 .iter()
                 .cycle()
@@ -61,6 +63,14 @@ impl BatchRequesterState {
                 .take(num_peers)
                 .cloned()
                 .collect();
+            // the batch author is the most likely to have the batch, so the
+            // first request is sent to the author (and the signers) in parallel
+            if self.num_retries == 0 {
+                if let Some(author) = self.author.filter(|author| !ret.contains(author)) {
+                    ret.push(author);
+                }
+            }
+            self.num_retries += 1;
             self.next_index = (self.next_index + num_peers) % self.signers.len();
             Some(ret)
         } else {
@@ -137,7 +147,9 @@ impl<T: QuorumStoreSender + Sync + 'static> BatchRequester<T> {
         let expiration = proof.expiration();
         let signers = proof.shuffled_signers(&self.validator_verifier);
         let validator_verifier = self.validator_verifier.clone();
-        let mut request_state = BatchRequesterState::new(signers, ret_tx, self.retry_limit);
+        // the batch author is skipped if it's this node (it doesn't have the batch anyway)
+        let author = Some(proof.author()).filter(|author| *author != self.my_peer_id);
+        let mut request_state = BatchRequesterState::new(author, signers, ret_tx, self.retry_limit);
         let network_sender = self.network_sender.clone();
         let request_num_peers = self.request_num_peers;
         let my_peer_id = self.my_peer_id;
@@ -22,11 +22,12 @@ use dashmap::{
     DashMap,
 };
 use fail::fail_point;
+use mini_moka::sync::Cache;
 use once_cell::sync::OnceCell;
 use std::{
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc, Mutex,
+        Arc, Mutex, Weak,
     },
     time::Duration,
 };
@@ -112,6 +113,9 @@ pub struct BatchStore {
     peer_quota: DashMap<PeerId, QuotaManager>,
     expirations: Mutex<TimeExpirations<HashValue>>,
     db: Arc<dyn QuorumStoreStorage>,
+    // Caches the payloads of recently persisted (or read) batches that are only
+    // stored in the db, to avoid redundant db reads on the proposal path.
+    recent_batch_cache: Cache<HashValue, PersistedValue>,
     memory_quota: usize,
     db_quota: usize,
     batch_quota: usize,
@@ -126,6 +130,7 @@ impl BatchStore {
         memory_quota: usize,
         db_quota: usize,
         batch_quota: usize,
+        recent_batch_cache_bytes: usize,
         validator_signer: ValidatorSigner,
     ) -> Self {
         let db_clone = db.clone();
@@ -136,6 +141,12 @@ impl BatchStore {
             peer_quota: DashMap::new(),
             expirations: Mutex::new(TimeExpirations::new()),
             db,
+            recent_batch_cache: Cache::builder()
+                .max_capacity(recent_batch_cache_bytes as u64)
+                .weigher(|_, value: &PersistedValue| {
+                    u32::try_from(value.num_bytes()).unwrap_or(u32::MAX)
+                })
+                .build(),
             memory_quota,
             db_quota,
             batch_quota,
@@ -293,6 +304,7 @@ impl BatchStore {
             };
             // No longer holding the lock on db_cache entry.
             if let Some(value) = removed_value {
+                self.recent_batch_cache.invalidate(&h);
                 self.free_quota(value);
                 ret.push(h);
             }
@@ -306,6 +318,12 @@ impl BatchStore {
                 let batch_info = persist_request.batch_info().clone();
                 trace!("QS: sign digest {}", persist_request.digest());
                 if needs_db {
+                    // If the payload exceeded the memory quota, it will have to be read
+                    // back from the db (e.g., when the batch is proposed). So, cache it.
+                    if self.is_persisted_only(persist_request.digest()) {
+                        self.recent_batch_cache
+                            .insert(*persist_request.digest(), persist_request.clone());
+                    }
                     self.db
                         .save_batch(persist_request)
                         .expect("Could not write to DB");
@@ -340,15 +358,66 @@ impl BatchStore {
         }
     }
 
+    /// Removes the batches from the db that expired at least the grace period ago, but were
+    /// left behind (e.g., due to failed deletions, or in previous epochs). Batches that are in
+    /// the cache are never removed, as they may still be referenced by (in-flight) proofs of
+    /// store. Returns the number of removed batches and their total size (in bytes).
+    ///
+    /// Only the expiration index is scanned, so the payloads are never read. Batches persisted
+    /// before the index existed are not indexed, but those are all read (and either deleted or
+    /// cached) on startup, see [`BatchStore::new`].
+    pub(crate) fn compact_db(&self, grace_period_usecs: u64) -> anyhow::Result<(usize, u64)> {
+        let expiration_cutoff = self
+            .last_certified_time()
+            .saturating_sub(grace_period_usecs);
+        let expired_batches: Vec<_> = self
+            .db
+            .get_all_batch_expirations()?
+            .into_iter()
+            .filter(|(_, value)| value.expiration <= expiration_cutoff)
+            .map(|(digest, value)| (digest, value.num_bytes))
+            .collect();
+
+        let (mut num_batches, mut num_bytes) = (0, 0);
+        for (digest, batch_bytes) in expired_batches {
+            // Hold the lock on the cache entry, so that the batch can't be persisted (again)
+            // concurrently: the cache insertion always precedes the db write.
+            let cache_entry = self.db_cache.entry(digest);
+            if let Vacant(_) = &cache_entry {
+                self.db.delete_batches(vec![digest])?;
+                num_batches += 1;
+                num_bytes += batch_bytes;
+            }
+        }
+
+        counters::QS_DB_COMPACTION_RECLAIMED_BATCHES.inc_by(num_batches as u64);
+        counters::QS_DB_COMPACTION_RECLAIMED_BYTES.inc_by(num_bytes);
+        Ok((num_batches, num_bytes))
+    }
+
     fn last_certified_time(&self) -> u64 {
         self.last_certified_time.load(Ordering::Relaxed)
     }
 
+    fn is_persisted_only(&self, digest: &HashValue) -> bool {
+        self.db_cache.get(digest).map_or(false, |value| {
+            value.payload_storage_mode() == StorageMode::PersistedOnly
+        })
+    }
+
     fn get_batch_from_db(&self, digest: &HashValue) -> ExecutorResult<PersistedValue> {
+        if let Some(value) = self.recent_batch_cache.get(digest) {
+            counters::inc_recent_batch_cache_request(counters::CACHE_HIT_LABEL);
+            return Ok(value);
+        }
+        counters::inc_recent_batch_cache_request(counters::CACHE_MISS_LABEL);
         counters::GET_BATCH_FROM_DB_COUNT.inc();
 
         match self.db.get_batch(digest) {
-            Ok(Some(value)) => Ok(value),
+            Ok(Some(value)) => {
+                self.recent_batch_cache.insert(*digest, value.clone());
+                Ok(value)
+            },
             Ok(None) | Err(_) => {
                 warn!("Could not get batch from db");
                 Err(ExecutorError::CouldNotGetData)
@@ -373,6 +442,37 @@ impl BatchStore {
     }
 }
 
+/// Periodically compacts the db of the batch store (see `BatchStore::compact_db`), until
+/// the batch store is dropped at the end of the epoch.
+pub(crate) async fn start_db_compaction(
+    batch_store: Weak<BatchStore>,
+    interval: Duration,
+    grace_period: Duration,
+) {
+    let mut interval = tokio::time::interval(interval);
+    // The first tick completes immediately (and the db was just cleaned upon recovery)
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let Some(batch_store) = batch_store.upgrade() else {
+            break;
+        };
+        let grace_period_usecs = grace_period.as_micros() as u64;
+        match tokio::task::spawn_blocking(move || batch_store.compact_db(grace_period_usecs)).await
+        {
+            Ok(Ok((num_batches, num_bytes))) => {
+                debug!(
+                    "QS: db compaction removed {} batches ({} bytes)",
+                    num_batches, num_bytes
+                );
+            },
+            Ok(Err(e)) => warn!("QS: db compaction failed: {:?}", e),
+            Err(e) => warn!("QS: db compaction task failed: {:?}", e),
+        }
+    }
+    info!("QS: db compaction stops");
+}
+
 impl BatchWriter for BatchStore {
     fn persist(&self, persist_requests: Vec<PersistedValue>) -> Vec<SignedBatchInfo> {
         let mut signed_infos = vec![];
@@ -244,6 +244,24 @@ pub static GAP_BETWEEN_BATCH_EXPIRATION_AND_CURRENT_TIME_WHEN_SAVE: Lazy<Histogr
     },
 );
 
+/// Count of the expired batches removed from the db by compaction.
+pub static QS_DB_COMPACTION_RECLAIMED_BATCHES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "quorum_store_db_compaction_reclaimed_batches",
+        "Count of the expired batches removed from the db by compaction"
+    )
+    .unwrap()
+});
+
+/// Count of the bytes (of expired batches) reclaimed from the db by compaction.
+pub static QS_DB_COMPACTION_RECLAIMED_BYTES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "quorum_store_db_compaction_reclaimed_bytes",
+        "Count of the bytes (of expired batches) reclaimed from the db by compaction"
+    )
+    .unwrap()
+});
+
 pub static NUM_BATCH_EXPIRED_WHEN_SAVE: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
         "quorum_store_num_batch_expired_when_save",
@@ -515,6 +533,26 @@ pub static EXCEEDED_BATCH_QUOTA_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Cache hit and miss labels
+pub const CACHE_HIT_LABEL: &str = "hit";
+pub const CACHE_MISS_LABEL: &str = "miss";
+
+/// Count of the lookups in the recent batch cache (for batches only held in the QS DB).
+static RECENT_BATCH_CACHE_REQUEST_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "quorum_store_recent_batch_cache_request_count",
+        "Count of the lookups in the recent batch cache, grouped by result (hit or miss).",
+        &["result"]
+    )
+    .unwrap()
+});
+
+pub fn inc_recent_batch_cache_request(result: &str) {
+    RECENT_BATCH_CACHE_REQUEST_COUNT
+        .with_label_values(&[result])
+        .inc();
+}
+
 /// Count of the number of batch request sent to other nodes.
 pub static GET_BATCH_FROM_DB_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -627,6 +665,28 @@ pub static QS_BACKPRESSURE_DYNAMIC_MAX: Lazy<Histogram> = Lazy::new(|| {
     )
 });
 
+pub static QS_LOAD_AWARE_BATCHING_DEFERRED_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "quorum_store_load_aware_batching_deferred_count",
+        "Number of batch pulls deferred because the local queue is longer than the peers' queues."
+    )
+    .unwrap()
+});
+
+pub static QS_LOAD_AWARE_BATCHING_QUEUE_RATIO: Lazy<Histogram> = Lazy::new(|| {
+    register_avg_counter(
+        "quorum_store_load_aware_batching_queue_ratio",
+        "Ratio of the local queue length to the median queue length of the peers",
+    )
+});
+
+pub static QS_LOAD_AWARE_BATCHING_NUM_PEER_HINTS: Lazy<Histogram> = Lazy::new(|| {
+    register_avg_counter(
+        "quorum_store_load_aware_batching_num_peer_hints",
+        "Number of peers with an unexpired queue length hint",
+    )
+});
+
 /// Latencies
 
 /// Histogram of the time durations for batch creation.
@@ -0,0 +1,116 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::quorum_store::counters;
+use aptos_config::config::QuorumStoreLoadAwareBatchingConfig;
+use aptos_types::PeerId;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Decides whether the batch generator should defer creating new batches, based on the
+/// queue length hints gossiped by the peers. The queue length of a validator is the number
+/// of txns in its batches that are not yet committed.
+///
+/// A validator defers the batch creation if its queue is long, and longer than the median
+/// queue of its peers by the configured factor, so that the less loaded validators batch
+/// the (VFN forwarded) txns they also have in mempool. The deferral is bounded, so that the
+/// txns are still batched if no peer picks them up.
+pub(crate) struct LoadAwareBatching {
+    config: QuorumStoreLoadAwareBatchingConfig,
+    peer_queue_lengths: HashMap<PeerId, (u64, Instant)>,
+    last_hint_broadcast: Option<Instant>,
+    deferred_since: Option<Instant>,
+}
+
+impl LoadAwareBatching {
+    pub(crate) fn new(config: QuorumStoreLoadAwareBatchingConfig) -> Self {
+        Self {
+            config,
+            peer_queue_lengths: HashMap::new(),
+            last_hint_broadcast: None,
+            deferred_since: None,
+        }
+    }
+
+    pub(crate) fn update_peer_queue_length(
+        &mut self,
+        peer_id: PeerId,
+        num_txns_in_progress: u64,
+        now: Instant,
+    ) {
+        self.peer_queue_lengths
+            .insert(peer_id, (num_txns_in_progress, now));
+    }
+
+    /// Returns true if the local queue length should be broadcast to the peers, i.e., if
+    /// the feature is enabled and the broadcast interval elapsed since the last broadcast.
+    pub(crate) fn should_broadcast_hint(&mut self, now: Instant) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        match self.last_hint_broadcast {
+            Some(last)
+                if now.duration_since(last)
+                    < Duration::from_millis(self.config.hint_broadcast_interval_ms) =>
+            {
+                false
+            },
+            _ => {
+                self.last_hint_broadcast = Some(now);
+                true
+            },
+        }
+    }
+
+    /// The median queue length of the peers with an unexpired hint, if any.
+    pub(crate) fn peer_median_queue_length(&mut self, now: Instant) -> Option<u64> {
+        let hint_expiration = Duration::from_millis(self.config.hint_expiration_ms);
+        self.peer_queue_lengths
+            .retain(|_, (_, received)| now.duration_since(*received) < hint_expiration);
+        counters::QS_LOAD_AWARE_BATCHING_NUM_PEER_HINTS
+            .observe(self.peer_queue_lengths.len() as f64);
+
+        let mut queue_lengths: Vec<_> = self
+            .peer_queue_lengths
+            .values()
+            .map(|(queue_length, _)| *queue_length)
+            .collect();
+        queue_lengths.sort_unstable();
+        queue_lengths.get(queue_lengths.len() / 2).copied()
+    }
+
+    /// Returns true if the batch creation should be deferred, given the local queue length.
+    /// Once deferred for max_deferral_ms, a pull is allowed and a new deferral period starts.
+    pub(crate) fn should_defer(&mut self, local_queue_length: u64, now: Instant) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        let peer_median = match self.peer_median_queue_length(now) {
+            Some(peer_median) => peer_median,
+            None => {
+                self.deferred_since = None;
+                return false;
+            },
+        };
+        counters::QS_LOAD_AWARE_BATCHING_QUEUE_RATIO
+            .observe(local_queue_length as f64 / std::cmp::max(peer_median, 1) as f64);
+
+        let overloaded = local_queue_length >= self.config.min_queue_txns
+            && local_queue_length as f64 > peer_median as f64 * self.config.imbalance_factor;
+        if !overloaded {
+            self.deferred_since = None;
+            return false;
+        }
+
+        let deferred_since = *self.deferred_since.get_or_insert(now);
+        if now.duration_since(deferred_since) < Duration::from_millis(self.config.max_deferral_ms) {
+            counters::QS_LOAD_AWARE_BATCHING_DEFERRED_COUNT.inc();
+            true
+        } else {
+            self.deferred_since = None;
+            false
+        }
+    }
+}
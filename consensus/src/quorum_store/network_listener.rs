@@ -4,8 +4,8 @@
 use crate::{
     monitor,
     quorum_store::{
-        batch_coordinator::BatchCoordinatorCommand, counters,
-        proof_coordinator::ProofCoordinatorCommand, proof_manager::ProofManagerCommand,
+        batch_coordinator::BatchCoordinatorCommand, batch_generator::BatchGeneratorCommand,
+        counters, proof_coordinator::ProofCoordinatorCommand, proof_manager::ProofManagerCommand,
     },
     round_manager::VerifiedEvent,
 };
@@ -20,6 +20,7 @@ pub(crate) struct NetworkListener {
     proof_coordinator_tx: Sender<ProofCoordinatorCommand>,
     remote_batch_coordinator_tx: Vec<Sender<BatchCoordinatorCommand>>,
     proof_manager_tx: Sender<ProofManagerCommand>,
+    batch_generator_tx: Sender<BatchGeneratorCommand>,
 }
 
 impl NetworkListener {
@@ -28,12 +29,14 @@ impl NetworkListener {
         proof_coordinator_tx: Sender<ProofCoordinatorCommand>,
         remote_batch_coordinator_tx: Vec<Sender<BatchCoordinatorCommand>>,
         proof_manager_tx: Sender<ProofManagerCommand>,
+        batch_generator_tx: Sender<BatchGeneratorCommand>,
     ) -> Self {
         Self {
             network_msg_rx,
             proof_coordinator_tx,
             remote_batch_coordinator_tx,
             proof_manager_tx,
+            batch_generator_tx,
         }
     }
 
@@ -82,6 +85,16 @@ impl NetworkListener {
                             .await
                             .expect("could not push Proof proof_of_store");
                     },
+                    VerifiedEvent::BatchQueueLengthHintMsg(hint) => {
+                        let cmd = BatchGeneratorCommand::PeerQueueLengthHint(
+                            hint.author(),
+                            hint.num_txns_in_progress(),
+                        );
+                        self.batch_generator_tx
+                            .send(cmd)
+                            .await
+                            .expect("Could not send queue length hint to batch_generator");
+                    },
                     _ => {
                         unreachable!()
                     },
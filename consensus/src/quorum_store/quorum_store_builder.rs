@@ -6,12 +6,12 @@ use crate::{
     error::error_kind,
     network::{IncomingBatchRetrievalRequest, NetworkSender},
     network_interface::ConsensusMsg,
-    payload_manager::PayloadManager,
+    payload_manager::{BatchPrefetcher, PayloadManager},
     quorum_store::{
         batch_coordinator::{BatchCoordinator, BatchCoordinatorCommand},
         batch_generator::{BackPressure, BatchGenerator, BatchGeneratorCommand},
         batch_requester::BatchRequester,
-        batch_store::{BatchReader, BatchReaderImpl, BatchStore},
+        batch_store::{start_db_compaction, BatchReader, BatchReaderImpl, BatchStore},
         counters,
         direct_mempool_quorum_store::DirectMempoolQuorumStore,
         network_listener::NetworkListener,
@@ -255,6 +255,7 @@ impl InnerBuilder {
             self.config.memory_quota,
             self.config.db_quota,
             self.config.batch_quota,
+            self.config.recent_batch_cache_bytes,
             signer,
         ));
         self.batch_store = Some(batch_store.clone());
@@ -375,10 +376,21 @@ impl InnerBuilder {
             self.proof_coordinator_cmd_tx.clone(),
             self.remote_batch_coordinator_cmd_tx.clone(),
             self.proof_manager_cmd_tx.clone(),
+            self.batch_generator_cmd_tx.clone(),
         );
         spawn_named!("network_listener", net.start());
 
         let batch_store = self.batch_store.clone().unwrap();
+        if self.config.batch_db_compaction_interval_ms > 0 {
+            spawn_named!(
+                "batch_db_compaction",
+                start_db_compaction(
+                    Arc::downgrade(&batch_store),
+                    Duration::from_millis(self.config.batch_db_compaction_interval_ms),
+                    Duration::from_millis(self.config.batch_db_compaction_grace_period_ms),
+                )
+            );
+        }
         let epoch = self.epoch;
         let (batch_retrieval_tx, mut batch_retrieval_rx) =
             aptos_channel::new::<AccountAddress, IncomingBatchRetrievalRequest>(
@@ -387,6 +399,7 @@ impl InnerBuilder {
                 Some(&counters::BATCH_RETRIEVAL_TASK_MSGS),
             );
         let aptos_db_clone = self.aptos_db.clone();
+        let message_gating = self.network_sender.message_gating();
         spawn_named!("batch_serve", async move {
             info!(epoch = epoch, "Batch retrieval task starts");
             while let Some(rpc_request) = batch_retrieval_rx.next().await {
@@ -407,7 +420,15 @@ impl InnerBuilder {
                     }
                 };
 
-                let msg = ConsensusMsg::BatchResponseV2(Box::new(response));
+                let msg = if message_gating.check_send_batch_response_v2() {
+                    ConsensusMsg::BatchResponseV2(Box::new(response))
+                } else {
+                    // Fall back to the deprecated response, which can't express NotFound
+                    match response {
+                        BatchResponse::Batch(batch) => ConsensusMsg::BatchResponse(Box::new(batch)),
+                        BatchResponse::NotFound(_) => continue,
+                    }
+                };
                 let bytes = rpc_request.protocol.to_bytes(&msg).unwrap();
                 if let Err(e) = rpc_request
                     .response_sender
@@ -430,10 +451,14 @@ impl InnerBuilder {
         Option<aptos_channel::Sender<AccountAddress, VerifiedEvent>>,
     ) {
         let batch_reader = self.create_batch_store();
+        let batch_prefetcher = BatchPrefetcher::new(
+            batch_reader,
+            Duration::from_millis(self.config.batch_prefetch_timeout_ms as u64),
+        );
 
         (
             Arc::from(PayloadManager::InQuorumStore(
-                batch_reader,
+                Arc::new(batch_prefetcher),
                 // TODO: remove after splitting out clean requests
                 self.coordinator_tx.clone(),
             )),
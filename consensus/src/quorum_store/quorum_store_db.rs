@@ -4,8 +4,11 @@
 use crate::{
     error::DbError,
     quorum_store::{
-        schema::{BatchIdSchema, BatchSchema, BATCH_CF_NAME, BATCH_ID_CF_NAME},
-        types::PersistedValue,
+        schema::{
+            BatchExpirationSchema, BatchIdSchema, BatchSchema, BATCH_CF_NAME,
+            BATCH_EXPIRATION_CF_NAME, BATCH_ID_CF_NAME,
+        },
+        types::{BatchExpiration, PersistedValue},
     },
 };
 use anyhow::Result;
@@ -20,6 +23,9 @@ pub trait QuorumStoreStorage: Sync + Send {
 
     fn get_all_batches(&self) -> Result<HashMap<HashValue, PersistedValue>>;
 
+    /// Returns the expiration and size of all persisted batches, without reading their payloads.
+    fn get_all_batch_expirations(&self) -> Result<HashMap<HashValue, BatchExpiration>>;
+
     fn save_batch(&self, batch: PersistedValue) -> Result<(), DbError>;
 
     fn get_batch(&self, digest: &HashValue) -> Result<Option<PersistedValue>, DbError>;
@@ -40,7 +46,7 @@ pub struct QuorumStoreDB {
 
 impl QuorumStoreDB {
     pub(crate) fn new<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
-        let column_families = vec![BATCH_CF_NAME, BATCH_ID_CF_NAME];
+        let column_families = vec![BATCH_CF_NAME, BATCH_ID_CF_NAME, BATCH_EXPIRATION_CF_NAME];
 
         // TODO: this fails twins tests because it assumes a unique path per process
         let path = db_root_path.as_ref().join(QUORUM_STORE_DB_NAME);
@@ -59,6 +65,16 @@ impl QuorumStoreDB {
 
         Self { db }
     }
+
+    /// Returns the epochs for which a batch id is persisted
+    pub(crate) fn get_batch_id_epochs(&self) -> Result<Vec<u64>, DbError> {
+        let mut iter = self.db.iter::<BatchIdSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        let epochs = iter
+            .map(|res| res.map(|(epoch, _)| epoch).map_err(Into::into))
+            .collect::<Result<Vec<u64>>>()?;
+        Ok(epochs)
+    }
 }
 
 impl QuorumStoreStorage for QuorumStoreDB {
@@ -67,6 +83,7 @@ impl QuorumStoreStorage for QuorumStoreDB {
         for digest in digests.iter() {
             trace!("QS: db delete digest {}", digest);
             batch.delete::<BatchSchema>(digest)?;
+            batch.delete::<BatchExpirationSchema>(digest)?;
         }
         self.db.write_schemas(batch)?;
         Ok(())
@@ -79,13 +96,31 @@ impl QuorumStoreStorage for QuorumStoreDB {
             .collect::<Result<HashMap<HashValue, PersistedValue>>>()
     }
 
+    fn get_all_batch_expirations(&self) -> Result<HashMap<HashValue, BatchExpiration>> {
+        let mut iter = self
+            .db
+            .iter::<BatchExpirationSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.map(|res| res.map_err(Into::into))
+            .collect::<Result<HashMap<HashValue, BatchExpiration>>>()
+    }
+
     fn save_batch(&self, batch: PersistedValue) -> Result<(), DbError> {
         trace!(
             "QS: db persists digest {} expiration {:?}",
             batch.digest(),
             batch.expiration()
         );
-        Ok(self.db.put::<BatchSchema>(batch.digest(), &batch)?)
+        // The batch and its expiration are written atomically, so that the index never
+        // misses a persisted batch.
+        let schema_batch = SchemaBatch::new();
+        schema_batch.put::<BatchSchema>(batch.digest(), &batch)?;
+        schema_batch.put::<BatchExpirationSchema>(batch.digest(), &BatchExpiration {
+            expiration: batch.expiration(),
+            num_bytes: batch.num_bytes(),
+        })?;
+        self.db.write_schemas(schema_batch)?;
+        Ok(())
     }
 
     fn get_batch(&self, digest: &HashValue) -> Result<Option<PersistedValue>, DbError> {
@@ -141,6 +176,10 @@ impl QuorumStoreStorage for MockQuorumStoreDB {
         Ok(HashMap::new())
     }
 
+    fn get_all_batch_expirations(&self) -> Result<HashMap<HashValue, BatchExpiration>> {
+        Ok(HashMap::new())
+    }
+
     fn save_batch(&self, _: PersistedValue) -> Result<(), DbError> {
         Ok(())
     }
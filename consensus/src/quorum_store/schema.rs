@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::quorum_store::types::PersistedValue;
+use crate::quorum_store::types::{BatchExpiration, PersistedValue};
 use anyhow::Result;
 use aptos_consensus_types::proof_of_store::BatchId;
 use aptos_crypto::HashValue;
@@ -12,6 +12,7 @@ use aptos_schemadb::{
 
 pub(crate) const BATCH_CF_NAME: ColumnFamilyName = "batch";
 pub(crate) const BATCH_ID_CF_NAME: ColumnFamilyName = "batch_ID";
+pub(crate) const BATCH_EXPIRATION_CF_NAME: ColumnFamilyName = "batch_expiration";
 
 #[derive(Debug)]
 pub(crate) struct BatchSchema;
@@ -43,6 +44,36 @@ impl ValueCodec<BatchSchema> for PersistedValue {
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct BatchExpirationSchema;
+
+impl Schema for BatchExpirationSchema {
+    type Key = HashValue;
+    type Value = BatchExpiration;
+
+    const COLUMN_FAMILY_NAME: aptos_schemadb::ColumnFamilyName = BATCH_EXPIRATION_CF_NAME;
+}
+
+impl KeyCodec<BatchExpirationSchema> for HashValue {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_vec())
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        Ok(HashValue::from_slice(data)?)
+    }
+}
+
+impl ValueCodec<BatchExpirationSchema> for BatchExpiration {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(bcs::from_bytes(data)?)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct BatchIdSchema;
 
@@ -5,7 +5,7 @@ use crate::{
     network::QuorumStoreSender,
     quorum_store::{
         batch_requester::BatchRequester,
-        types::{Batch, BatchRequest, BatchResponse},
+        types::{Batch, BatchQueueLengthHintMsg, BatchRequest, BatchResponse},
     },
 };
 use aptos_consensus_types::{
@@ -72,6 +72,10 @@ impl QuorumStoreSender for MockBatchRequester {
     async fn send_proof_of_store_msg_to_self(&mut self, _proof_of_stores: Vec<ProofOfStore>) {
         unimplemented!()
     }
+
+    async fn broadcast_batch_queue_length_hint_msg(&mut self, _hint: BatchQueueLengthHintMsg) {
+        unimplemented!()
+    }
 }
 
 #[tokio::test]
@@ -3,7 +3,7 @@
 
 use crate::quorum_store::{
     batch_store::{BatchStore, BatchWriter, QuotaManager},
-    quorum_store_db::QuorumStoreDB,
+    quorum_store_db::{QuorumStoreDB, QuorumStoreStorage},
     types::{PersistedValue, StorageMode},
 };
 use aptos_consensus_types::proof_of_store::{BatchId, BatchInfo};
@@ -35,6 +35,7 @@ pub fn batch_store_for_test(memory_quota: usize) -> Arc<BatchStore> {
         memory_quota, // memory_quota
         2001,         // db quota
         2001,         // batch quota
+        2001,         // recent batch cache bytes
         signers[0].clone(),
     ))
 }
@@ -281,3 +282,89 @@ fn test_get_local_batch() {
     assert_err!(store.get_batch_from_local(&digest_2));
     assert_err!(store.get_batch_from_local(&digest_3));
 }
+
+#[test]
+fn test_recent_batch_cache() {
+    let tmp_dir = TempPath::new();
+    let db = Arc::new(QuorumStoreDB::new(&tmp_dir));
+    let (signers, _validator_verifier) = random_validator_verifier(4, None, false);
+    let store = BatchStore::new(
+        10, // epoch
+        10, // last committed round
+        db.clone(),
+        0,    // memory_quota
+        2001, // db quota
+        2001, // batch quota
+        2001, // recent batch cache bytes
+        signers[0].clone(),
+    );
+
+    let digest = HashValue::random();
+    let request = request_for_test(&digest, 50, 20, Some(vec![]));
+    // Should be stored in DB only (and in the recent batch cache).
+    assert!(!store.persist(vec![request]).is_empty());
+
+    // The batch is served from the recent batch cache (even if missing from the DB).
+    assert_ok!(db.delete_batches(vec![digest]));
+    let mut value = store.get_batch_from_local(&digest).unwrap();
+    assert_eq!(value.take_payload(), Some(vec![]));
+
+    // Expired batches are removed from the recent batch cache.
+    store.update_certified_timestamp(50);
+    assert_err!(store.get_batch_from_local(&digest));
+}
+
+#[test]
+fn test_compact_db() {
+    let tmp_dir = TempPath::new();
+    let db = Arc::new(QuorumStoreDB::new(&tmp_dir));
+    let (signers, _validator_verifier) = random_validator_verifier(4, None, false);
+    let store = BatchStore::new(
+        10, // epoch
+        10, // last committed round
+        db.clone(),
+        2001, // memory_quota
+        2001, // db quota
+        2001, // batch quota
+        2001, // recent batch cache bytes
+        signers[0].clone(),
+    );
+
+    // A batch that is persisted (and cached)
+    let cached_digest = HashValue::random();
+    assert!(!store
+        .persist(vec![request_for_test(&cached_digest, 50, 20, Some(vec![]))])
+        .is_empty());
+    // Batches that were left behind in the db (e.g., by a previous epoch)
+    let expired_digest = HashValue::random();
+    assert_ok!(db.save_batch(request_for_test(&expired_digest, 20, 30, Some(vec![]))));
+    let recently_expired_digest = HashValue::random();
+    assert_ok!(db.save_batch(request_for_test(
+        &recently_expired_digest,
+        35,
+        40,
+        Some(vec![])
+    )));
+
+    // Only the batch that expired before the grace period is removed
+    store.update_certified_timestamp(40);
+    assert_ok_eq!(store.compact_db(10), (1, 30));
+    let batches = db.get_all_batches().unwrap();
+    assert!(!batches.contains_key(&expired_digest));
+    assert!(batches.contains_key(&recently_expired_digest));
+    assert!(batches.contains_key(&cached_digest));
+    assert!(!db
+        .get_all_batch_expirations()
+        .unwrap()
+        .contains_key(&expired_digest));
+
+    // Cached batches are never removed (even if expired), as proofs may still reference them
+    store
+        .insert_to_cache(&request_for_test(&recently_expired_digest, 60, 40, None))
+        .unwrap();
+    assert_ok_eq!(store.compact_db(0), (0, 0));
+    assert!(db
+        .get_all_batches()
+        .unwrap()
+        .contains_key(&recently_expired_digest));
+}
@@ -0,0 +1,100 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::quorum_store::load_aware_batching::LoadAwareBatching;
+use aptos_config::config::QuorumStoreLoadAwareBatchingConfig;
+use aptos_types::PeerId;
+use std::time::{Duration, Instant};
+
+fn enabled_config() -> QuorumStoreLoadAwareBatchingConfig {
+    QuorumStoreLoadAwareBatchingConfig {
+        enabled: true,
+        hint_broadcast_interval_ms: 100,
+        hint_expiration_ms: 1000,
+        min_queue_txns: 100,
+        imbalance_factor: 2.0,
+        max_deferral_ms: 200,
+    }
+}
+
+#[test]
+fn test_disabled_never_defers() {
+    let mut load_aware_batching =
+        LoadAwareBatching::new(QuorumStoreLoadAwareBatchingConfig::default());
+    let now = Instant::now();
+    load_aware_batching.update_peer_queue_length(PeerId::random(), 0, now);
+
+    assert!(!load_aware_batching.should_broadcast_hint(now));
+    assert!(!load_aware_batching.should_defer(10_000, now));
+}
+
+#[test]
+fn test_hint_broadcast_interval() {
+    let mut load_aware_batching = LoadAwareBatching::new(enabled_config());
+    let now = Instant::now();
+
+    assert!(load_aware_batching.should_broadcast_hint(now));
+    assert!(!load_aware_batching.should_broadcast_hint(now + Duration::from_millis(50)));
+    assert!(load_aware_batching.should_broadcast_hint(now + Duration::from_millis(100)));
+}
+
+#[test]
+fn test_peer_median_ignores_expired_hints() {
+    let mut load_aware_batching = LoadAwareBatching::new(enabled_config());
+    let now = Instant::now();
+    assert_eq!(load_aware_batching.peer_median_queue_length(now), None);
+
+    load_aware_batching.update_peer_queue_length(PeerId::random(), 1000, now);
+    let later = now + Duration::from_millis(600);
+    for queue_length in [10, 20, 30] {
+        load_aware_batching.update_peer_queue_length(PeerId::random(), queue_length, later);
+    }
+    assert_eq!(
+        load_aware_batching.peer_median_queue_length(later),
+        Some(30)
+    );
+
+    // The first hint expires
+    let expired = now + Duration::from_millis(1000);
+    assert_eq!(
+        load_aware_batching.peer_median_queue_length(expired),
+        Some(20)
+    );
+}
+
+#[test]
+fn test_defer_only_when_overloaded() {
+    let mut load_aware_batching = LoadAwareBatching::new(enabled_config());
+    let now = Instant::now();
+
+    // No hints
+    assert!(!load_aware_batching.should_defer(1000, now));
+
+    load_aware_batching.update_peer_queue_length(PeerId::random(), 40, now);
+    // Below min_queue_txns, even if imbalanced
+    assert!(!load_aware_batching.should_defer(99, now));
+    // Not imbalanced enough
+    load_aware_batching.update_peer_queue_length(PeerId::random(), 200, now);
+    load_aware_batching.update_peer_queue_length(PeerId::random(), 300, now);
+    assert!(!load_aware_batching.should_defer(400, now));
+    // Imbalanced
+    assert!(load_aware_batching.should_defer(401, now));
+}
+
+#[test]
+fn test_deferral_is_bounded() {
+    let mut load_aware_batching = LoadAwareBatching::new(enabled_config());
+    let now = Instant::now();
+    load_aware_batching.update_peer_queue_length(PeerId::random(), 0, now);
+
+    assert!(load_aware_batching.should_defer(1000, now));
+    assert!(load_aware_batching.should_defer(1000, now + Duration::from_millis(150)));
+    // After max_deferral_ms, a pull is allowed, and a new deferral period starts
+    assert!(!load_aware_batching.should_defer(1000, now + Duration::from_millis(200)));
+    assert!(load_aware_batching.should_defer(1000, now + Duration::from_millis(250)));
+
+    // The deferral period is reset once the queue is no longer overloaded
+    assert!(!load_aware_batching.should_defer(10, now + Duration::from_millis(300)));
+    assert!(load_aware_batching.should_defer(1000, now + Duration::from_millis(500)));
+    assert!(load_aware_batching.should_defer(1000, now + Duration::from_millis(650)));
+}
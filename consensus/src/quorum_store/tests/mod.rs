@@ -5,6 +5,7 @@ mod batch_generator_test;
 mod batch_requester_test;
 mod batch_store_test;
 mod direct_mempool_quorum_store_test;
+mod load_aware_batching_test;
 mod proof_coordinator_test;
 mod proof_manager_test;
 mod quorum_store_db_test;
@@ -4,7 +4,7 @@
 use crate::{
     quorum_store::{
         quorum_store_db::{QuorumStoreDB, QuorumStoreStorage},
-        types::{Batch, PersistedValue},
+        types::{Batch, BatchExpiration, PersistedValue},
     },
     test_utils::create_vec_signed_transactions,
 };
@@ -56,6 +56,21 @@ fn test_db_for_data() {
     assert_eq!(all_batches.len(), 2);
     assert!(all_batches.contains_key(persist_request_1.digest()));
     assert!(all_batches.contains_key(persist_request_2.digest()));
+
+    // The expiration index is kept in sync with the persisted batches
+    let all_expirations = db
+        .get_all_batch_expirations()
+        .expect("could not read from db");
+    assert_eq!(all_expirations.len(), 2);
+    assert_eq!(
+        all_expirations.get(persist_request_1.digest()),
+        Some(&BatchExpiration {
+            expiration: persist_request_1.expiration(),
+            num_bytes: persist_request_1.num_bytes(),
+        })
+    );
+    assert!(all_expirations.contains_key(persist_request_2.digest()));
+    assert!(!all_expirations.contains_key(persist_request_3.digest()));
 }
 
 #[test]
@@ -20,6 +20,14 @@ pub struct PersistedValue {
     maybe_payload: Option<Vec<SignedTransaction>>,
 }
 
+/// The expiration and size of a persisted batch, indexed separately from the batch itself,
+/// so that expired batches can be found without reading their payloads.
+#[derive(Clone, Copy, Eq, Deserialize, Serialize, PartialEq, Debug)]
+pub struct BatchExpiration {
+    pub expiration: u64,
+    pub num_bytes: u64,
+}
+
 #[derive(PartialEq, Debug)]
 pub(crate) enum StorageMode {
     PersistedOnly,
@@ -301,3 +309,39 @@ impl BatchMsg {
         self.batches
     }
 }
+
+/// Gossiped by each validator with the number of txns in its batches that are not yet
+/// committed, so that peers can balance the batch creation across the validator set.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BatchQueueLengthHintMsg {
+    epoch: u64,
+    author: PeerId,
+    num_txns_in_progress: u64,
+}
+
+impl BatchQueueLengthHintMsg {
+    pub fn new(epoch: u64, author: PeerId, num_txns_in_progress: u64) -> Self {
+        Self {
+            epoch,
+            author,
+            num_txns_in_progress,
+        }
+    }
+
+    pub fn verify(&self, peer_id: PeerId) -> anyhow::Result<()> {
+        ensure!(self.author == peer_id, "Hint author doesn't match sender");
+        Ok(())
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn author(&self) -> PeerId {
+        self.author
+    }
+
+    pub fn num_txns_in_progress(&self) -> u64 {
+        self.num_txns_in_progress
+    }
+}
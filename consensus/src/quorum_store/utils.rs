@@ -139,6 +139,16 @@ impl MempoolProxy {
             },
         }
     }
+
+    /// Notifies mempool whenever quorum store becomes (or stops being) back pressured,
+    /// so that mempool can slow down its broadcasts and intake accordingly.
+    pub fn notify_back_pressure(&self, back_pressured: bool) -> Result<(), anyhow::Error> {
+        let msg = QuorumStoreRequest::BackpressureNotification(back_pressured);
+        self.mempool_tx
+            .clone()
+            .try_send(msg)
+            .map_err(anyhow::Error::from)
+    }
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -8,7 +8,10 @@ use crate::{
 use aptos_consensus_types::{common::Round, pipelined_block::PipelinedBlock};
 use aptos_reliable_broadcast::DropGuard;
 use aptos_types::randomness::{RandMetadata, Randomness};
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
 
 /// Maintain the ordered blocks received from consensus and corresponding randomness
 pub struct QueueItem {
@@ -16,6 +19,7 @@ pub struct QueueItem {
     offsets_by_round: HashMap<Round, usize>,
     num_undecided_blocks: usize,
     broadcast_handle: Option<Vec<DropGuard>>,
+    enqueue_time: Instant,
 }
 
 impl QueueItem {
@@ -33,6 +37,7 @@ impl QueueItem {
             offsets_by_round,
             num_undecided_blocks: len,
             broadcast_handle,
+            enqueue_time: Instant::now(),
         }
     }
 
@@ -59,6 +64,15 @@ impl QueueItem {
             .collect()
     }
 
+    /// Returns the metadata of the blocks that don't have randomness yet
+    pub fn undecided_rand_metadata(&self) -> Vec<RandMetadata> {
+        self.blocks()
+            .iter()
+            .filter(|block| !block.has_randomness())
+            .map(|block| RandMetadata::from(block.block()))
+            .collect()
+    }
+
     pub fn set_randomness(&mut self, round: Round, rand: Randomness) -> bool {
         let offset = self.offset(round);
         if !self.blocks()[offset].has_randomness() {
@@ -136,6 +150,21 @@ impl BlockQueue {
             .filter(|item| item.offsets_by_round.contains_key(&round))
     }
 
+    /// Returns the metadata of (at most `max_blocks`) blocks that have been pending
+    /// randomness for at least the given duration (ordered by round)
+    pub fn get_stale_undecided_metadata(
+        &self,
+        min_pending_duration: Duration,
+        max_blocks: usize,
+    ) -> Vec<RandMetadata> {
+        self.queue
+            .values()
+            .filter(|item| item.enqueue_time.elapsed() >= min_pending_duration)
+            .flat_map(|item| item.undecided_rand_metadata())
+            .take(max_blocks)
+            .collect()
+    }
+
     /// Update the corresponding block's randomness, return true if updated successfully
     pub fn set_randomness(&mut self, round: Round, randomness: Randomness) -> bool {
         if let Some(item) = self.item_mut(round) {
@@ -153,7 +182,7 @@ mod tests {
         test_utils::create_ordered_blocks,
     };
     use aptos_types::randomness::Randomness;
-    use std::collections::HashSet;
+    use std::{collections::HashSet, time::Duration};
 
     #[test]
     fn test_queue_item() {
@@ -222,4 +251,34 @@ mod tests {
 
         assert_eq!(queue.queue.len(), 1);
     }
+
+    #[test]
+    fn test_stale_undecided_metadata() {
+        let mut queue = BlockQueue::new();
+        let all_rounds = vec![vec![1], vec![2, 3], vec![5, 8, 13]];
+        for rounds in &all_rounds {
+            queue.push_back(QueueItem::new(create_ordered_blocks(rounds.clone()), None));
+        }
+        let stale_rounds = |queue: &BlockQueue, max_blocks| {
+            queue
+                .get_stale_undecided_metadata(Duration::ZERO, max_blocks)
+                .iter()
+                .map(|metadata| metadata.round())
+                .collect::<Vec<_>>()
+        };
+
+        // all undecided blocks are returned (ordered by round)
+        assert_eq!(stale_rounds(&queue, 10), vec![1, 2, 3, 5, 8, 13]);
+        assert_eq!(stale_rounds(&queue, 4), vec![1, 2, 3, 5]);
+
+        // decided blocks are skipped
+        queue.set_randomness(2, Randomness::default());
+        queue.set_randomness(8, Randomness::default());
+        assert_eq!(stale_rounds(&queue, 10), vec![1, 3, 5, 13]);
+
+        // blocks that were recently enqueued are skipped
+        assert!(queue
+            .get_stale_undecided_metadata(Duration::from_secs(3600), 10)
+            .is_empty());
+    }
 }
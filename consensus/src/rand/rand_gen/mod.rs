@@ -6,10 +6,13 @@ mod test_utils;
 
 pub mod block_queue;
 pub mod network_messages;
+pub mod participation;
 pub mod rand_store;
 pub mod types;
 
 pub mod aug_data_store;
 pub mod rand_manager;
 pub mod reliable_broadcast_state;
+pub mod share_backfill;
 pub mod storage;
+pub mod wvuf_cache;
@@ -7,7 +7,7 @@ use crate::{
     network_interface::ConsensusMsg,
     rand::rand_gen::types::{
         AugData, AugDataSignature, CertifiedAugData, CertifiedAugDataAck, RandConfig, RandShare,
-        RequestShare, TAugmentedData, TShare,
+        RequestShare, RequestShareBackfill, ShareBackfill, TAugmentedData, TShare,
     },
 };
 use anyhow::bail;
@@ -30,6 +30,8 @@ pub enum RandMessage<S, D> {
     CertifiedAugData(CertifiedAugData<D>),
     CertifiedAugDataAck(CertifiedAugDataAck),
     FastShare(FastShare<S>),
+    RequestShareBackfill(RequestShareBackfill),
+    ShareBackfill(ShareBackfill<S>),
 }
 
 impl<S: TShare, D: TAugmentedData> RandMessage<S, D> {
@@ -42,6 +44,7 @@ impl<S: TShare, D: TAugmentedData> RandMessage<S, D> {
     ) -> anyhow::Result<()> {
         match self {
             RandMessage::RequestShare(_) => Ok(()),
+            RandMessage::RequestShareBackfill(request) => request.verify(epoch_state.epoch),
             RandMessage::Share(share) => share.verify(rand_config),
             RandMessage::AugData(aug_data) => {
                 aug_data.verify(rand_config, fast_rand_config, sender)
@@ -71,6 +74,8 @@ impl<S: TShare, D: TAugmentedData> TConsensusMsg for RandMessage<S, D> {
             RandMessage::CertifiedAugData(certified_aug_data) => certified_aug_data.epoch(),
             RandMessage::CertifiedAugDataAck(ack) => ack.epoch(),
             RandMessage::FastShare(share) => share.share.epoch(),
+            RandMessage::RequestShareBackfill(request) => request.epoch(),
+            RandMessage::ShareBackfill(backfill) => backfill.epoch(),
         }
     }
 
@@ -120,6 +125,7 @@ impl core::fmt::Debug for RandGenMessage {
 }
 
 pub struct RpcRequest<S, D> {
+    pub sender: Author,
     pub req: RandMessage<S, D>,
     pub protocol: ProtocolId,
     pub response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
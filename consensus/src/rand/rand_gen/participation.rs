@@ -0,0 +1,232 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters::{
+    RAND_SHARE_LATENCY, RAND_SHARE_PARTICIPATION_COUNT, RAND_SHARE_PARTICIPATION_STATUS,
+};
+use aptos_consensus_types::common::{Author, Round};
+use aptos_crypto::HashValue;
+use aptos_types::randomness::RandMetadata;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+/// Shares are accepted for this many rounds after the highest known round, before the
+/// participation of a round is finalized (i.e., late shares still count as contributed).
+pub const PARTICIPATION_GRACE_ROUNDS: u64 = 10;
+
+const CONTRIBUTED_LABEL: &str = "contributed";
+const MISSED_LABEL: &str = "missed";
+
+#[derive(Default)]
+struct RoundParticipation {
+    metadata: Option<RandMetadata>,
+    // The block id the share was for, and the time the share was received (since the epoch)
+    shares: HashMap<Author, (HashValue, Duration)>,
+}
+
+/// The finalized participation of a single round.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundParticipationSummary {
+    pub round: Round,
+    /// The validators that contributed a share, and the share latency (since the block timestamp)
+    pub contributed: Vec<(Author, Duration)>,
+    /// The validators that did not contribute a share
+    pub missed: Vec<Author>,
+}
+
+/// Tracks which validators contribute (slow path) randomness shares for each round, so that
+/// validators degrading the randomness beacon can be identified. Only rounds with a block
+/// (i.e., with rand metadata) are tracked.
+pub struct ShareParticipationTracker {
+    validators: Vec<Author>,
+    rounds: BTreeMap<Round, RoundParticipation>,
+    highest_finalized_round: Option<Round>,
+}
+
+impl ShareParticipationTracker {
+    pub fn new(validators: Vec<Author>) -> Self {
+        Self {
+            validators,
+            rounds: BTreeMap::new(),
+            highest_finalized_round: None,
+        }
+    }
+
+    fn is_finalized(&self, round: Round) -> bool {
+        self.highest_finalized_round
+            .map_or(false, |finalized_round| round <= finalized_round)
+    }
+
+    pub fn record_metadata(&mut self, metadata: &RandMetadata) {
+        if self.is_finalized(metadata.round()) {
+            return;
+        }
+        self.rounds.entry(metadata.round()).or_default().metadata = Some(metadata.clone());
+    }
+
+    /// Records a (valid) share, received at `now` (since the unix epoch)
+    pub fn record_share(&mut self, author: Author, metadata: &RandMetadata, now: Duration) {
+        if self.is_finalized(metadata.round()) {
+            return;
+        }
+        self.rounds
+            .entry(metadata.round())
+            .or_default()
+            .shares
+            .entry(author)
+            .and_modify(|share| {
+                // A share for a different block is replaced (e.g., once the block is known)
+                if share.0 != metadata.block_id {
+                    *share = (metadata.block_id, now);
+                }
+            })
+            .or_insert((metadata.block_id, now));
+    }
+
+    /// Finalizes the participation of the rounds that are older than the grace period,
+    /// updates the participation counters, and returns the summaries of the finalized rounds.
+    pub fn finalize_rounds(
+        &mut self,
+        highest_known_round: Round,
+    ) -> Vec<RoundParticipationSummary> {
+        let Some(finalize_up_to) = highest_known_round.checked_sub(PARTICIPATION_GRACE_ROUNDS)
+        else {
+            return vec![];
+        };
+        let remaining = self.rounds.split_off(&(finalize_up_to + 1));
+        let finalized = std::mem::replace(&mut self.rounds, remaining);
+        self.highest_finalized_round = Some(
+            self.highest_finalized_round
+                .map_or(finalize_up_to, |round| round.max(finalize_up_to)),
+        );
+
+        finalized
+            .into_iter()
+            .filter_map(|(round, participation)| {
+                let metadata = participation.metadata?;
+                let summary = self.summarize(round, &metadata, &participation.shares);
+                Self::update_counters(&summary);
+                Some(summary)
+            })
+            .collect()
+    }
+
+    fn summarize(
+        &self,
+        round: Round,
+        metadata: &RandMetadata,
+        shares: &HashMap<Author, (HashValue, Duration)>,
+    ) -> RoundParticipationSummary {
+        let block_timestamp = Duration::from_micros(metadata.timestamp);
+        let mut contributed = vec![];
+        let mut missed = vec![];
+        for validator in &self.validators {
+            match shares.get(validator) {
+                // Shares for a different block (e.g., before the block was known) don't count
+                Some((block_id, received_time)) if *block_id == metadata.block_id => {
+                    contributed.push((*validator, received_time.saturating_sub(block_timestamp)))
+                },
+                _ => missed.push(*validator),
+            }
+        }
+        RoundParticipationSummary {
+            round,
+            contributed,
+            missed,
+        }
+    }
+
+    fn update_counters(summary: &RoundParticipationSummary) {
+        for (author, latency) in &summary.contributed {
+            let peer_id = author.to_string();
+            RAND_SHARE_PARTICIPATION_COUNT
+                .with_label_values(&[&peer_id, CONTRIBUTED_LABEL])
+                .inc();
+            RAND_SHARE_PARTICIPATION_STATUS
+                .with_label_values(&[&peer_id])
+                .set(1);
+            RAND_SHARE_LATENCY
+                .with_label_values(&[&peer_id])
+                .observe(latency.as_secs_f64());
+        }
+        for author in &summary.missed {
+            let peer_id = author.to_string();
+            RAND_SHARE_PARTICIPATION_COUNT
+                .with_label_values(&[&peer_id, MISSED_LABEL])
+                .inc();
+            RAND_SHARE_PARTICIPATION_STATUS
+                .with_label_values(&[&peer_id])
+                .set(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rand::rand_gen::participation::{
+        RoundParticipationSummary, ShareParticipationTracker, PARTICIPATION_GRACE_ROUNDS,
+    };
+    use aptos_consensus_types::common::Author;
+    use aptos_crypto::HashValue;
+    use aptos_types::randomness::RandMetadata;
+    use std::time::Duration;
+
+    #[test]
+    fn test_share_participation() {
+        let validators: Vec<Author> = (0..3).map(|_| Author::random()).collect();
+        let mut tracker = ShareParticipationTracker::new(validators.clone());
+        let block_timestamp = Duration::from_secs(100);
+        let metadata = |round: u64| {
+            RandMetadata::new(
+                1,
+                round,
+                HashValue::sha3_256_of(&round.to_le_bytes()),
+                block_timestamp.as_micros() as u64,
+            )
+        };
+
+        // Round 1: validator 0 contributes before the block is known, validator 1 after it
+        tracker.record_share(validators[0], &metadata(1), block_timestamp);
+        tracker.record_metadata(&metadata(1));
+        tracker.record_share(
+            validators[1],
+            &metadata(1),
+            block_timestamp + Duration::from_millis(300),
+        );
+        // Validator 2 contributes a share for a different block of the round
+        let other_block_metadata = RandMetadata::new(
+            1,
+            1,
+            HashValue::random(),
+            block_timestamp.as_micros() as u64,
+        );
+        tracker.record_share(validators[2], &other_block_metadata, block_timestamp);
+        // Round 2 has no block, and is not tracked
+        tracker.record_share(validators[0], &metadata(2), block_timestamp);
+
+        // Nothing is finalized within the grace period
+        assert!(tracker
+            .finalize_rounds(PARTICIPATION_GRACE_ROUNDS)
+            .is_empty());
+        assert_eq!(
+            tracker.finalize_rounds(PARTICIPATION_GRACE_ROUNDS + 2),
+            vec![RoundParticipationSummary {
+                round: 1,
+                contributed: vec![
+                    (validators[0], Duration::ZERO),
+                    (validators[1], Duration::from_millis(300)),
+                ],
+                missed: vec![validators[2]],
+            }]
+        );
+
+        // Shares for finalized rounds are ignored
+        tracker.record_metadata(&metadata(1));
+        tracker.record_share(validators[2], &metadata(1), block_timestamp);
+        assert!(tracker
+            .finalize_rounds(PARTICIPATION_GRACE_ROUNDS + 2)
+            .is_empty());
+    }
+}
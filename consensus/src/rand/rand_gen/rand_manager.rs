@@ -14,13 +14,17 @@ use crate::{
         reliable_broadcast_state::{
             AugDataCertBuilder, CertifiedAugDataAckState, ShareAggregateState,
         },
+        share_backfill::{backfill_shares, serve_share_backfill_request, ShareBackfillRateLimiter},
         storage::interface::RandStorage,
-        types::{FastShare, PathType, RandConfig, RequestShare, TAugmentedData, TShare},
+        types::{
+            FastShare, PathType, RandConfig, RequestShare, RequestShareBackfill, ShareBackfill,
+            TAugmentedData, TShare,
+        },
     },
 };
 use aptos_bounded_executor::BoundedExecutor;
 use aptos_channels::aptos_channel;
-use aptos_config::config::ReliableBroadcastConfig;
+use aptos_config::config::{RandShareBackfillConfig, ReliableBroadcastConfig};
 use aptos_consensus_types::common::Author;
 use aptos_infallible::Mutex;
 use aptos_logger::{error, info, spawn_named, warn};
@@ -66,6 +70,10 @@ pub struct RandManager<S: TShare, D: TAugmentedData> {
 
     // for randomness fast path
     fast_config: Option<RandConfig>,
+
+    // for backfilling the shares of blocks that are pending for too long
+    backfill_config: RandShareBackfillConfig,
+    backfill_rate_limiter: ShareBackfillRateLimiter,
 }
 
 impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
@@ -80,6 +88,7 @@ impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
         db: Arc<dyn RandStorage<D>>,
         bounded_executor: BoundedExecutor,
         rb_config: &ReliableBroadcastConfig,
+        backfill_config: &RandShareBackfillConfig,
     ) -> Self {
         let rb_backoff_policy = ExponentialBackoff::from_millis(rb_config.backoff_policy_base_ms)
             .factor(rb_config.backoff_policy_factor)
@@ -124,6 +133,11 @@ impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
             block_queue: BlockQueue::new(),
 
             fast_config,
+
+            backfill_config: backfill_config.clone(),
+            backfill_rate_limiter: ShareBackfillRateLimiter::new(Duration::from_millis(
+                backfill_config.min_request_interval_per_peer_ms,
+            )),
         }
     }
 
@@ -238,6 +252,7 @@ impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
                                 .is_ok()
                             {
                                 let _ = tx.unbounded_send(RpcRequest {
+                                    sender: rand_gen_msg.sender,
                                     req: msg,
                                     protocol: rand_gen_msg.protocol,
                                     response_sender: rand_gen_msg.response_sender,
@@ -253,6 +268,57 @@ impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
         }
     }
 
+    fn process_share_backfill_request(
+        &mut self,
+        sender: Author,
+        request: RequestShareBackfill,
+        protocol: ProtocolId,
+        response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) {
+        match serve_share_backfill_request(
+            sender,
+            &request,
+            &self.rand_store,
+            &mut self.backfill_rate_limiter,
+            &self.backfill_config,
+        ) {
+            Ok(shares) => {
+                let backfill = ShareBackfill::new(self.epoch_state.epoch, shares);
+                self.process_response(
+                    protocol,
+                    response_sender,
+                    RandMessage::ShareBackfill(backfill),
+                );
+            },
+            Err(e) => {
+                warn!(
+                    "[RandManager] Failed to serve share backfill request: {}",
+                    e
+                );
+            },
+        }
+    }
+
+    /// Requests the shares of the blocks that have been pending randomness for too long
+    fn backfill_stale_shares(&self) {
+        let rand_metadata = self.block_queue.get_stale_undecided_metadata(
+            Duration::from_millis(self.backfill_config.backfill_delay_ms),
+            self.backfill_config.max_rounds_per_request,
+        );
+        if rand_metadata.is_empty() {
+            return;
+        }
+        tokio::spawn(backfill_shares::<S, D>(
+            self.author,
+            self.epoch_state.clone(),
+            self.config.clone(),
+            rand_metadata,
+            self.network_sender.clone(),
+            self.rand_store.clone(),
+            self.backfill_config.clone(),
+        ));
+    }
+
     fn spawn_aggregate_shares_task(&self, metadata: RandMetadata) -> DropGuard {
         let rb = self.reliable_broadcast.clone();
         let aggregate_state = Arc::new(ShareAggregateState::new(
@@ -360,6 +426,9 @@ impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
 
         let _guard = self.broadcast_aug_data().await;
         let mut interval = tokio::time::interval(Duration::from_millis(5000));
+        let mut backfill_interval = tokio::time::interval(Duration::from_millis(
+            self.backfill_config.backfill_interval_ms.max(1),
+        ));
         while !self.stop {
             tokio::select! {
                 Some(blocks) = incoming_blocks.next() => {
@@ -374,6 +443,7 @@ impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
                 }
                 Some(request) = verified_msg_rx.next() => {
                     let RpcRequest {
+                        sender,
                         req: rand_gen_msg,
                         protocol,
                         response_sender,
@@ -396,6 +466,14 @@ impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
                                 }
                             }
                         }
+                        RandMessage::RequestShareBackfill(request) => {
+                            self.process_share_backfill_request(
+                                sender,
+                                request,
+                                protocol,
+                                response_sender,
+                            );
+                        }
                         RandMessage::Share(share) => {
                             info!(LogSchema::new(LogEvent::ReceiveProactiveRandShare)
                                 .author(self.author)
@@ -444,6 +522,11 @@ impl<S: TShare, D: TAugmentedData> RandManager<S, D> {
                 _ = interval.tick().fuse() => {
                     self.observe_queue();
                 },
+                _ = backfill_interval.tick().fuse() => {
+                    if self.backfill_config.enable {
+                        self.backfill_stale_shares();
+                    }
+                },
 
             }
             let maybe_ready_blocks = self.block_queue.dequeue_rand_ready_prefix();
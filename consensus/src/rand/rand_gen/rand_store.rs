@@ -4,12 +4,17 @@
 use crate::{
     block_storage::tracing::{observe_block, BlockStage},
     rand::rand_gen::{
+        participation::ShareParticipationTracker,
         rand_manager::Sender,
-        types::{PathType, RandConfig, RandShare, TShare, FUTURE_ROUNDS_TO_ACCEPT},
+        types::{
+            PathType, RandConfig, RandShare, TShare, FUTURE_ROUNDS_TO_ACCEPT,
+            PAST_ROUNDS_TO_BACKFILL,
+        },
     },
 };
 use anyhow::ensure;
 use aptos_consensus_types::common::{Author, Round};
+use aptos_infallible::duration_since_epoch;
 use aptos_types::randomness::{RandMetadata, Randomness};
 use itertools::Either;
 use std::collections::{BTreeMap, HashMap, HashSet};
@@ -42,7 +47,7 @@ impl<S: TShare> ShareAggregator<S> {
         rand_config: &RandConfig,
         rand_metadata: RandMetadata,
         decision_tx: Sender<Randomness>,
-    ) -> Either<Self, RandShare<S>> {
+    ) -> Either<Self, (RandShare<S>, Vec<RandShare<S>>)> {
         if self.total_weight < rand_config.threshold() {
             return Either::Left(self);
         }
@@ -65,6 +70,8 @@ impl<S: TShare> ShareAggregator<S> {
         let self_share = self
             .get_self_share()
             .expect("Aggregated item should have self share");
+        // Keep the aggregated shares (so that they can be backfilled to lagging peers)
+        let shares: Vec<_> = self.shares.values().cloned().collect();
         tokio::task::spawn_blocking(move || {
             decision_tx.unbounded_send(S::aggregate(
                 self.shares.values(),
@@ -72,7 +79,7 @@ impl<S: TShare> ShareAggregator<S> {
                 rand_metadata,
             ))
         });
-        Either::Right(self_share)
+        Either::Right((self_share, shares))
     }
 
     fn retain(&mut self, rand_config: &RandConfig, rand_metadata: &RandMetadata) {
@@ -102,6 +109,7 @@ enum RandItem<S> {
     },
     Decided {
         self_share: RandShare<S>,
+        shares: Vec<RandShare<S>>,
     },
 }
 
@@ -157,7 +165,7 @@ impl<S: TShare> RandItem<S> {
                     metadata,
                     share_aggregator,
                 },
-                Either::Right(self_share) => Self::Decided { self_share },
+                Either::Right((self_share, shares)) => Self::Decided { self_share, shares },
             },
             item @ (RandItem::Decided { .. } | RandItem::PendingMetadata(_)) => item,
         };
@@ -200,6 +208,28 @@ impl<S: TShare> RandItem<S> {
             RandItem::Decided { self_share, .. } => Some(self_share.clone()),
         }
     }
+
+    /// Returns all known shares with the given metadata
+    fn get_shares(&self, metadata: &RandMetadata) -> Vec<RandShare<S>> {
+        let shares = match self {
+            RandItem::PendingMetadata(share_aggregator)
+            | RandItem::PendingDecision {
+                share_aggregator, ..
+            } => Either::Left(share_aggregator.shares.values()),
+            RandItem::Decided { shares, .. } => Either::Right(shares.iter()),
+        };
+        shares
+            .filter(|share| share.metadata() == metadata)
+            .cloned()
+            .collect()
+    }
+
+    /// Drops the shares of a decided item (i.e., they can no longer be backfilled)
+    fn prune_shares(&mut self) {
+        if let RandItem::Decided { shares, .. } = self {
+            *shares = vec![];
+        }
+    }
 }
 
 pub struct RandStore<S> {
@@ -210,7 +240,10 @@ pub struct RandStore<S> {
     fast_rand_config: Option<RandConfig>,
     fast_rand_map: Option<BTreeMap<Round, RandItem<S>>>,
     highest_known_round: u64,
+    // All decided rounds below this round have had their shares pruned
+    shares_pruned_round: u64,
     decision_tx: Sender<Randomness>,
+    participation_tracker: ShareParticipationTracker,
 }
 
 impl<S: TShare> RandStore<S> {
@@ -221,6 +254,7 @@ impl<S: TShare> RandStore<S> {
         fast_rand_config: Option<RandConfig>,
         decision_tx: Sender<Randomness>,
     ) -> Self {
+        let participation_tracker = ShareParticipationTracker::new(rand_config.get_validators());
         Self {
             epoch,
             author,
@@ -229,15 +263,34 @@ impl<S: TShare> RandStore<S> {
             fast_rand_config: fast_rand_config.clone(),
             fast_rand_map: fast_rand_config.map(|_| BTreeMap::new()),
             highest_known_round: 0,
+            shares_pruned_round: 0,
             decision_tx,
+            participation_tracker,
         }
     }
 
     pub fn update_highest_known_round(&mut self, round: u64) {
         self.highest_known_round = std::cmp::max(self.highest_known_round, round);
+        self.participation_tracker
+            .finalize_rounds(self.highest_known_round);
+
+        // Prune the shares of the decided rounds that are too old to be backfilled
+        let prune_round = self
+            .highest_known_round
+            .saturating_sub(PAST_ROUNDS_TO_BACKFILL);
+        if prune_round > self.shares_pruned_round {
+            for (_, rand_item) in self
+                .rand_map
+                .range_mut(self.shares_pruned_round..prune_round)
+            {
+                rand_item.prune_shares();
+            }
+            self.shares_pruned_round = prune_round;
+        }
     }
 
     pub fn add_rand_metadata(&mut self, rand_metadata: RandMetadata) {
+        self.participation_tracker.record_metadata(&rand_metadata);
         let rand_item = self
             .rand_map
             .entry(rand_metadata.round())
@@ -286,9 +339,15 @@ impl<S: TShare> RandStore<S> {
             )
         };
 
+        let author = *share.author();
         rand_item.add_share(share, rand_config)?;
         rand_item.try_aggregate(rand_config, self.decision_tx.clone());
-        Ok(rand_item.has_decision())
+        let has_decision = rand_item.has_decision();
+        if path == PathType::Slow {
+            self.participation_tracker
+                .record_share(author, &rand_metadata, duration_since_epoch());
+        }
+        Ok(has_decision)
     }
 
     /// This should only be called after the block is added, returns None if already decided
@@ -315,6 +374,17 @@ impl<S: TShare> RandStore<S> {
             .and_then(|item| item.get_self_share())
             .filter(|share| share.metadata() == metadata))
     }
+
+    /// Returns all known (slow path) shares for the given metadata, to be backfilled to a peer
+    pub fn get_backfill_shares(&self, metadata: &RandMetadata) -> Vec<RandShare<S>> {
+        if metadata.round() > self.highest_known_round {
+            return vec![];
+        }
+        self.rand_map
+            .get(&metadata.round())
+            .map(|item| item.get_shares(metadata))
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -323,7 +393,7 @@ mod tests {
         block_queue::QueueItem,
         rand_store::{RandItem, RandStore, ShareAggregator},
         test_utils::{create_ordered_blocks, create_share, create_share_for_round},
-        types::{MockShare, PathType, RandConfig},
+        types::{MockShare, PathType, RandConfig, PAST_ROUNDS_TO_BACKFILL},
     };
     use aptos_consensus_types::common::Author;
     use aptos_crypto::{bls12381, HashValue, Uniform};
@@ -548,4 +618,56 @@ mod tests {
         }
         assert!(decision_rx.next().await.is_some());
     }
+
+    #[tokio::test]
+    async fn test_rand_store_backfill_shares() {
+        let ctxt = TestContext::new(vec![100; 7], 0);
+        let (decision_tx, mut decision_rx) = unbounded();
+        let mut rand_store = RandStore::new(
+            ctxt.target_epoch,
+            ctxt.authors[1],
+            ctxt.rand_config.clone(),
+            None,
+            decision_tx,
+        );
+
+        let blocks = QueueItem::new(create_ordered_blocks(vec![1, 2]), None);
+        let metadata = blocks.all_rand_metadata();
+        rand_store.update_highest_known_round(2);
+        for metadata in blocks.all_rand_metadata() {
+            rand_store.add_rand_metadata(metadata);
+        }
+
+        // pending shares are backfilled
+        for share in ctxt.authors[0..3]
+            .iter()
+            .map(|author| create_share(metadata[0].clone(), *author))
+        {
+            rand_store.add_share(share, PathType::Slow).unwrap();
+        }
+        assert_eq!(rand_store.get_backfill_shares(&metadata[0]).len(), 3);
+        assert!(rand_store.get_backfill_shares(&metadata[1]).is_empty());
+
+        // decided shares are backfilled (shares after the decision are not kept)
+        for share in ctxt.authors[3..5]
+            .iter()
+            .map(|author| create_share(metadata[0].clone(), *author))
+        {
+            rand_store.add_share(share, PathType::Slow).unwrap();
+        }
+        assert!(decision_rx.next().await.is_some());
+        assert_eq!(rand_store.get_backfill_shares(&metadata[0]).len(), 4);
+
+        // shares with mismatching metadata (or from future rounds) are not backfilled
+        let mut other_metadata = metadata[0].clone();
+        other_metadata.timestamp += 1;
+        assert!(rand_store.get_backfill_shares(&other_metadata).is_empty());
+        let mut future_metadata = metadata[0].clone();
+        future_metadata.metadata_to_sign.round = 3;
+        assert!(rand_store.get_backfill_shares(&future_metadata).is_empty());
+
+        // old decided shares are pruned
+        rand_store.update_highest_known_round(2 + PAST_ROUNDS_TO_BACKFILL);
+        assert!(rand_store.get_backfill_shares(&metadata[0]).is_empty());
+    }
 }
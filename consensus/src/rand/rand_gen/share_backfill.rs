@@ -0,0 +1,268 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters::{RAND_BACKFILLED_SHARES, RAND_SHARE_BACKFILL_REQUESTS},
+    network::NetworkSender,
+    rand::rand_gen::{
+        network_messages::RandMessage,
+        rand_store::RandStore,
+        types::{PathType, RandConfig, RandShare, RequestShareBackfill, TAugmentedData, TShare},
+    },
+};
+use anyhow::bail;
+use aptos_config::config::RandShareBackfillConfig;
+use aptos_consensus_types::common::Author;
+use aptos_infallible::Mutex;
+use aptos_logger::{info, warn};
+use aptos_reliable_broadcast::RBNetworkSender;
+use aptos_types::{epoch_state::EpochState, randomness::RandMetadata};
+use futures::future::join_all;
+use rand::seq::SliceRandom;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+// Useful labels for the share backfill metrics
+const ADDED_LABEL: &str = "added";
+const FAILED_LABEL: &str = "failed";
+const INVALID_LABEL: &str = "invalid";
+const RATE_LIMITED_LABEL: &str = "rate_limited";
+const REJECTED_LABEL: &str = "rejected";
+const SENT_LABEL: &str = "sent";
+const SERVED_LABEL: &str = "served";
+
+/// Rate limits the share backfill requests served to each peer
+pub struct ShareBackfillRateLimiter {
+    min_request_interval: Duration,
+    last_served_times: HashMap<Author, Instant>,
+}
+
+impl ShareBackfillRateLimiter {
+    pub fn new(min_request_interval: Duration) -> Self {
+        Self {
+            min_request_interval,
+            last_served_times: HashMap::new(),
+        }
+    }
+
+    /// Returns true iff a request from the given peer can be served now
+    pub fn try_acquire(&mut self, peer: Author) -> bool {
+        let now = Instant::now();
+        if let Some(last_served_time) = self.last_served_times.get(&peer) {
+            if now.duration_since(*last_served_time) < self.min_request_interval {
+                return false;
+            }
+        }
+        self.last_served_times.insert(peer, now);
+        true
+    }
+}
+
+/// Returns all known shares for the given backfill request (if the request can be served)
+pub fn serve_share_backfill_request<S: TShare>(
+    peer: Author,
+    request: &RequestShareBackfill,
+    rand_store: &Mutex<RandStore<S>>,
+    rate_limiter: &mut ShareBackfillRateLimiter,
+    config: &RandShareBackfillConfig,
+) -> anyhow::Result<Vec<RandShare<S>>> {
+    if request.rand_metadata().len() > config.max_rounds_per_request {
+        update_request_metrics(REJECTED_LABEL);
+        bail!(
+            "Too many rounds in the backfill request from {}: {}",
+            peer,
+            request.rand_metadata().len()
+        );
+    }
+    if !rate_limiter.try_acquire(peer) {
+        update_request_metrics(RATE_LIMITED_LABEL);
+        bail!("Backfill request from {} is rate limited", peer);
+    }
+
+    let rand_store = rand_store.lock();
+    let shares = request
+        .rand_metadata()
+        .iter()
+        .flat_map(|metadata| rand_store.get_backfill_shares(metadata))
+        .collect();
+    update_request_metrics(SERVED_LABEL);
+    Ok(shares)
+}
+
+/// Requests all known shares of the given blocks from (random) peers, and adds the
+/// valid shares to the rand store. Shares that are already known are not verified again.
+pub async fn backfill_shares<S: TShare, D: TAugmentedData>(
+    author: Author,
+    epoch_state: Arc<EpochState>,
+    rand_config: RandConfig,
+    rand_metadata: Vec<RandMetadata>,
+    network_sender: Arc<NetworkSender>,
+    rand_store: Arc<Mutex<RandStore<S>>>,
+    config: RandShareBackfillConfig,
+) {
+    // Choose the peers to request the shares from
+    let peers: Vec<_> = epoch_state
+        .verifier
+        .get_ordered_account_addresses_iter()
+        .filter(|peer| *peer != author)
+        .collect();
+    let peers: Vec<_> = peers
+        .choose_multiple(&mut rand::thread_rng(), config.num_peers_to_request)
+        .copied()
+        .collect();
+    let rounds: Vec<_> = rand_metadata
+        .iter()
+        .map(|metadata| metadata.round())
+        .collect();
+    info!(
+        epoch = epoch_state.epoch,
+        rounds = rounds,
+        "[RandManager] Requesting share backfill from {} peers",
+        peers.len(),
+    );
+
+    // Send the requests (concurrently) and process the responses
+    let request = RequestShareBackfill::new(epoch_state.epoch, rand_metadata.clone());
+    let timeout = Duration::from_millis(config.rpc_timeout_ms);
+    let responses = peers.into_iter().map(|peer| {
+        let network_sender = network_sender.clone();
+        let message = RandMessage::<S, D>::RequestShareBackfill(request.clone());
+        async move {
+            update_request_metrics(SENT_LABEL);
+            let response: anyhow::Result<RandMessage<S, D>> =
+                network_sender.send_rb_rpc(peer, message, timeout).await;
+            (peer, response)
+        }
+    });
+    let requested_metadata: HashSet<_> = rand_metadata.into_iter().collect();
+    for (peer, response) in join_all(responses).await {
+        let shares = match response {
+            Ok(RandMessage::ShareBackfill(backfill)) => backfill.into_shares(),
+            Ok(_) => {
+                update_request_metrics(FAILED_LABEL);
+                warn!(
+                    "[RandManager] Unexpected share backfill response from {}",
+                    peer
+                );
+                continue;
+            },
+            Err(error) => {
+                update_request_metrics(FAILED_LABEL);
+                warn!(
+                    "[RandManager] Failed to backfill shares from {}: {}",
+                    peer, error
+                );
+                continue;
+            },
+        };
+        add_backfilled_shares::<S>(&requested_metadata, shares, &rand_config, &rand_store).await;
+    }
+}
+
+/// Verifies the given backfilled shares, and adds the valid shares to the rand store
+async fn add_backfilled_shares<S: TShare>(
+    requested_metadata: &HashSet<RandMetadata>,
+    shares: Vec<RandShare<S>>,
+    rand_config: &RandConfig,
+    rand_store: &Arc<Mutex<RandStore<S>>>,
+) {
+    // Filter out the unrequested shares and the shares that are already known
+    let num_shares = shares.len();
+    let shares: Vec<_> = {
+        let rand_store = rand_store.lock();
+        shares
+            .into_iter()
+            .filter(|share| {
+                requested_metadata.contains(share.metadata())
+                    && rand_store
+                        .get_all_shares_authors(share.metadata())
+                        .map_or(false, |authors| !authors.contains(share.author()))
+            })
+            .collect()
+    };
+
+    // Verify the remaining shares (this is expensive, so it runs on a blocking thread)
+    let rand_config = rand_config.clone();
+    let num_new_shares = shares.len();
+    let valid_shares = match tokio::task::spawn_blocking(move || {
+        shares
+            .into_iter()
+            .filter(|share| share.verify(&rand_config).is_ok())
+            .collect::<Vec<_>>()
+    })
+    .await
+    {
+        Ok(valid_shares) => valid_shares,
+        Err(error) => {
+            warn!(
+                "[RandManager] Failed to verify backfilled shares: {}",
+                error
+            );
+            return;
+        },
+    };
+    update_share_metrics(INVALID_LABEL, num_new_shares - valid_shares.len());
+
+    // Add the valid shares
+    let num_valid_shares = valid_shares.len();
+    let mut rand_store = rand_store.lock();
+    for share in valid_shares {
+        if let Err(error) = rand_store.add_share(share, PathType::Slow) {
+            warn!("[RandManager] Failed to add backfilled share: {}", error);
+        }
+    }
+    update_share_metrics(ADDED_LABEL, num_valid_shares);
+    info!(
+        "[RandManager] Received {} backfilled shares ({} new, {} valid)",
+        num_shares, num_new_shares, num_valid_shares
+    );
+}
+
+/// Updates the share backfill request metrics with the given result
+fn update_request_metrics(result: &str) {
+    RAND_SHARE_BACKFILL_REQUESTS
+        .with_label_values(&[result])
+        .inc();
+}
+
+/// Updates the backfilled share metrics with the given result
+fn update_share_metrics(result: &str, num_shares: usize) {
+    RAND_BACKFILLED_SHARES
+        .with_label_values(&[result])
+        .inc_by(num_shares as u64);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rand::rand_gen::share_backfill::ShareBackfillRateLimiter;
+    use aptos_consensus_types::common::Author;
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut rate_limiter = ShareBackfillRateLimiter::new(Duration::from_millis(100));
+        let peer_1 = Author::random();
+        let peer_2 = Author::random();
+
+        // the first request of each peer is served
+        assert!(rate_limiter.try_acquire(peer_1));
+        assert!(rate_limiter.try_acquire(peer_2));
+
+        // requests within the interval are rate limited
+        assert!(!rate_limiter.try_acquire(peer_1));
+        assert!(!rate_limiter.try_acquire(peer_2));
+
+        // requests after the interval are served again
+        thread::sleep(Duration::from_millis(150));
+        assert!(rate_limiter.try_acquire(peer_1));
+        assert!(!rate_limiter.try_acquire(peer_1));
+
+        // a zero interval never rate limits
+        let mut rate_limiter = ShareBackfillRateLimiter::new(Duration::ZERO);
+        assert!(rate_limiter.try_acquire(peer_1));
+        assert!(rate_limiter.try_acquire(peer_1));
+    }
+}
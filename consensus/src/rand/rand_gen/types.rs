@@ -1,13 +1,14 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::rand::rand_gen::wvuf_cache::WvufDerivationCache;
 use anyhow::{bail, ensure};
 use aptos_consensus_types::common::{Author, Round};
 use aptos_crypto::bls12381::Signature;
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use aptos_dkg::{
     pvss::{Player, WeightedConfig},
-    weighted_vuf::traits::WeightedVUF,
+    weighted_vuf::{beacon::evaluation_to_randomness, traits::WeightedVUF},
 };
 use aptos_logger::debug;
 use aptos_runtimes::spawn_rayon_thread_pool;
@@ -19,11 +20,12 @@ use aptos_types::{
     validator_verifier::ValidatorVerifier,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use sha3::{Digest, Sha3_256};
 use std::{fmt::Debug, sync::Arc};
 
 pub const NUM_THREADS_FOR_WVUF_DERIVATION: usize = 8;
 pub const FUTURE_ROUNDS_TO_ACCEPT: u64 = 200;
+// Number of recent rounds for which the shares of decided rounds are kept (to serve backfills)
+pub const PAST_ROUNDS_TO_BACKFILL: u64 = 200;
 
 #[derive(PartialEq)]
 pub enum PathType {
@@ -97,6 +99,11 @@ impl TShare for Share {
         Self: Sized,
     {
         let timer = std::time::Instant::now();
+        let msg = rand_metadata.to_bytes();
+        if let Some(eval) = rand_config.wvuf_cache.get_eval(msg.as_slice()) {
+            return Randomness::new(rand_metadata, evaluation_to_randomness(&eval));
+        }
+
         let mut apks_and_proofs = vec![];
         for share in shares {
             let id = *rand_config
@@ -107,16 +114,24 @@ impl TShare for Share {
             let apk = rand_config.get_certified_apk(share.author()).unwrap(); // needs to have apk to verify the share
             apks_and_proofs.push((Player { id }, apk.clone(), share.share().share));
         }
+        // Order the shares by player, so that the Lagrange coefficients of a subset of players
+        // can be reused regardless of the order in which their shares were received
+        apks_and_proofs.sort_by_key(|(player, _, _)| player.id);
 
         let proof = WVUF::aggregate_shares(&rand_config.wconfig, &apks_and_proofs);
+        let player_ids = proof.iter().map(|(player, _)| player.id).collect();
+        let lagr = rand_config
+            .wvuf_cache
+            .get_or_compute_lagrange_coeffs(player_ids, || {
+                WVUF::lagrange_coeffs(&rand_config.wconfig, &proof)
+            });
         let pool =
             spawn_rayon_thread_pool("wvuf".to_string(), Some(NUM_THREADS_FOR_WVUF_DERIVATION));
-        let eval = WVUF::derive_eval(
+        let eval = WVUF::derive_eval_with_lagrange_coeffs(
             &rand_config.wconfig,
-            &rand_config.vuf_pp,
-            rand_metadata.to_bytes().as_slice(),
             &rand_config.get_all_certified_apk(),
             &proof,
+            &lagr,
             &pool,
         )
         .expect("All APK should exist");
@@ -125,8 +140,8 @@ impl TShare for Share {
             timer.elapsed().as_millis(),
             NUM_THREADS_FOR_WVUF_DERIVATION
         );
-        let eval_bytes = bcs::to_bytes(&eval).unwrap();
-        let rand_bytes = Sha3_256::digest(eval_bytes.as_slice()).to_vec();
+        let rand_bytes = evaluation_to_randomness(&eval);
+        rand_config.wvuf_cache.insert_eval(msg.as_slice(), eval);
         Randomness::new(rand_metadata.clone(), rand_bytes)
     }
 }
@@ -377,6 +392,70 @@ impl RequestShare {
     }
 }
 
+/// A request for all known (slow path) shares of the given blocks, e.g., from
+/// a validator that missed the share broadcasts for these blocks.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RequestShareBackfill {
+    epoch: u64,
+    rand_metadata: Vec<RandMetadata>,
+}
+
+impl RequestShareBackfill {
+    pub fn new(epoch: u64, rand_metadata: Vec<RandMetadata>) -> Self {
+        Self {
+            epoch,
+            rand_metadata,
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn rand_metadata(&self) -> &[RandMetadata] {
+        &self.rand_metadata
+    }
+
+    pub fn verify(&self, epoch: u64) -> anyhow::Result<()> {
+        ensure!(
+            self.epoch == epoch,
+            "[RequestShareBackfill] epoch mismatch: {} vs {}",
+            self.epoch,
+            epoch
+        );
+        ensure!(
+            self.rand_metadata
+                .iter()
+                .all(|metadata| metadata.epoch() == epoch),
+            "[RequestShareBackfill] metadata from different epoch"
+        );
+        Ok(())
+    }
+}
+
+/// The shares known for the blocks of a share backfill request. Note: the
+/// shares are not verified on deserialization, so the requester must verify
+/// each share before using it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShareBackfill<S> {
+    epoch: u64,
+    shares: Vec<RandShare<S>>,
+}
+
+impl<S: TShare> ShareBackfill<S> {
+    pub fn new(epoch: u64, shares: Vec<RandShare<S>>) -> Self {
+        Self { epoch, shares }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn into_shares(self) -> Vec<RandShare<S>> {
+        self.shares
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct FastShare<S> {
     pub share: RandShare<S>,
@@ -575,6 +654,8 @@ pub struct RandConfig {
     keys: Arc<RandKeys>,
     // weighted config for weighted VUF
     wconfig: WeightedConfig,
+    // caches for deriving the WVUF evaluations
+    wvuf_cache: Arc<WvufDerivationCache>,
 }
 
 impl Debug for RandConfig {
@@ -603,6 +684,7 @@ impl RandConfig {
             vuf_pp,
             keys: Arc::new(keys),
             wconfig,
+            wvuf_cache: Arc::new(WvufDerivationCache::new()),
         }
     }
 
@@ -614,6 +696,10 @@ impl RandConfig {
         self.author
     }
 
+    pub fn get_validators(&self) -> Vec<Author> {
+        self.validator.get_ordered_account_addresses()
+    }
+
     pub fn get_id(&self, peer: &Author) -> usize {
         *self
             .validator
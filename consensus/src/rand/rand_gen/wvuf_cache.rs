@@ -0,0 +1,104 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters::RAND_WVUF_CACHE_LOOKUPS;
+use aptos_crypto::HashValue;
+use aptos_dkg::weighted_vuf::pinkas::LagrangeCoeffs;
+use aptos_types::randomness::Evaluation;
+use mini_moka::sync::Cache;
+use std::sync::Arc;
+
+const EVAL_CACHE_CAPACITY: u64 = 1_000;
+const LAGRANGE_COEFFS_CACHE_CAPACITY: u64 = 100;
+
+const EVAL_CACHE_LABEL: &str = "eval";
+const LAGRANGE_COEFFS_CACHE_LABEL: &str = "lagrange_coeffs";
+const HIT_LABEL: &str = "hit";
+const MISS_LABEL: &str = "miss";
+
+/// Bounded caches for deriving WVUF evaluations, which is on the critical path of every block:
+/// - the derived evaluations, keyed by the hash of the message (i.e., the rand metadata), and
+/// - the Lagrange coefficients, keyed by the ids of the players whose shares were aggregated,
+///   since the same subset of validators usually aggregates the randomness of consecutive blocks.
+pub struct WvufDerivationCache {
+    evals: Cache<HashValue, Evaluation>,
+    lagrange_coeffs: Cache<Vec<usize>, Arc<LagrangeCoeffs>>,
+}
+
+impl WvufDerivationCache {
+    pub fn new() -> Self {
+        Self {
+            evals: Cache::new(EVAL_CACHE_CAPACITY),
+            lagrange_coeffs: Cache::new(LAGRANGE_COEFFS_CACHE_CAPACITY),
+        }
+    }
+
+    pub fn get_eval(&self, msg: &[u8]) -> Option<Evaluation> {
+        let eval = self.evals.get(&HashValue::sha3_256_of(msg));
+        observe_lookup(EVAL_CACHE_LABEL, eval.is_some());
+        eval
+    }
+
+    pub fn insert_eval(&self, msg: &[u8], eval: Evaluation) {
+        self.evals.insert(HashValue::sha3_256_of(msg), eval);
+    }
+
+    /// Returns the cached Lagrange coefficients of the given players (in the given order),
+    /// computing and caching them on a miss.
+    pub fn get_or_compute_lagrange_coeffs(
+        &self,
+        player_ids: Vec<usize>,
+        compute: impl FnOnce() -> LagrangeCoeffs,
+    ) -> Arc<LagrangeCoeffs> {
+        if let Some(lagr) = self.lagrange_coeffs.get(&player_ids) {
+            observe_lookup(LAGRANGE_COEFFS_CACHE_LABEL, true);
+            return lagr;
+        }
+        observe_lookup(LAGRANGE_COEFFS_CACHE_LABEL, false);
+
+        let lagr = Arc::new(compute());
+        self.lagrange_coeffs.insert(player_ids, lagr.clone());
+        lagr
+    }
+}
+
+impl Default for WvufDerivationCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn observe_lookup(cache: &str, hit: bool) {
+    let result = if hit { HIT_LABEL } else { MISS_LABEL };
+    RAND_WVUF_CACHE_LOOKUPS
+        .with_label_values(&[cache, result])
+        .inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lagrange_coeffs_are_computed_once_per_subset() {
+        let cache = WvufDerivationCache::new();
+        let lagr: LagrangeCoeffs = vec![1u64.into(), 2u64.into()];
+
+        let mut num_computations = 0;
+        for _ in 0..3 {
+            let cached_lagr = cache.get_or_compute_lagrange_coeffs(vec![0, 2], || {
+                num_computations += 1;
+                lagr.clone()
+            });
+            assert_eq!(cached_lagr.as_ref(), &lagr);
+        }
+        assert_eq!(num_computations, 1);
+
+        // A different order of the players is a different entry
+        cache.get_or_compute_lagrange_coeffs(vec![2, 0], || {
+            num_computations += 1;
+            lagr.iter().rev().cloned().collect()
+        });
+        assert_eq!(num_computations, 2);
+    }
+}
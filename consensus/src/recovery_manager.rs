@@ -15,7 +15,8 @@ use crate::{
 use anyhow::{anyhow, ensure, Context, Result};
 use aptos_channels::aptos_channel;
 use aptos_consensus_types::{
-    common::Author, proposal_msg::ProposalMsg, sync_info::SyncInfo, vote_msg::VoteMsg,
+    block::Block, common::Author, epoch_retrieval::EpochStarterKit, proposal_msg::ProposalMsg,
+    sync_info::SyncInfo, vote_msg::VoteMsg,
 };
 use aptos_logger::prelude::*;
 use aptos_types::{block_info::Round, epoch_state::EpochState};
@@ -32,6 +33,7 @@ pub struct RecoveryManager {
     execution_client: Arc<dyn TExecutionClient>,
     last_committed_round: Round,
     max_blocks_to_request: u64,
+    max_range_response_bytes: Option<u64>,
     payload_manager: Arc<PayloadManager>,
 }
 
@@ -43,6 +45,7 @@ impl RecoveryManager {
         execution_client: Arc<dyn TExecutionClient>,
         last_committed_round: Round,
         max_blocks_to_request: u64,
+        max_range_response_bytes: Option<u64>,
         payload_manager: Arc<PayloadManager>,
     ) -> Self {
         RecoveryManager {
@@ -52,6 +55,7 @@ impl RecoveryManager {
             execution_client,
             last_committed_round,
             max_blocks_to_request,
+            max_range_response_bytes,
             payload_manager,
         }
     }
@@ -71,7 +75,30 @@ impl RecoveryManager {
         self.sync_up(sync_info, author).await
     }
 
+    /// Processes an epoch starter kit: the (verified) blocks of the kit are used to fast
+    /// forward sync to the sync info, without retrieving the blocks from peers.
+    pub async fn process_epoch_starter_kit(
+        &mut self,
+        starter_kit: EpochStarterKit,
+        peer: Author,
+    ) -> Result<RecoveryData> {
+        starter_kit.verify_blocks(&self.epoch_state.verifier)?;
+        let (sync_info, blocks) = starter_kit.into_parts();
+        self.sync_up_with_prefetched_blocks(&sync_info, peer, blocks)
+            .await
+    }
+
     pub async fn sync_up(&mut self, sync_info: &SyncInfo, peer: Author) -> Result<RecoveryData> {
+        self.sync_up_with_prefetched_blocks(sync_info, peer, vec![])
+            .await
+    }
+
+    async fn sync_up_with_prefetched_blocks(
+        &mut self,
+        sync_info: &SyncInfo,
+        peer: Author,
+        prefetched_blocks: Vec<Block>,
+    ) -> Result<RecoveryData> {
         sync_info.verify(&self.epoch_state.verifier)?;
         ensure!(
             sync_info.highest_round() > self.last_committed_round,
@@ -89,7 +116,9 @@ impl RecoveryManager {
                 .get_ordered_account_addresses_iter()
                 .collect(),
             self.max_blocks_to_request,
-        );
+            self.max_range_response_bytes,
+        )
+        .with_prefetched_blocks(prefetched_blocks);
         let recovery_data = BlockStore::fast_forward_sync(
             sync_info.highest_ordered_cert(),
             sync_info.highest_commit_cert(),
@@ -132,6 +161,12 @@ impl RecoveryManager {
                                 self.sync_up(&sync_info, peer_id).await
                             )
                         }
+                        VerifiedEvent::UnverifiedEpochStarterKit(starter_kit) => {
+                            monitor!(
+                                "process_recovery",
+                                self.process_epoch_starter_kit(*starter_kit, peer_id).await
+                            )
+                        }
                         unexpected_event => Err(anyhow!("Unexpected event: {:?}", unexpected_event)),
                     }
                     .with_context(|| format!("from peer {}", peer_id));
@@ -10,6 +10,8 @@ use crate::{
     counters::{self, PROPOSED_VTXN_BYTES, PROPOSED_VTXN_COUNT},
     error::{error_kind, VerifyError},
     liveness::{
+        clock_skew_estimator::ClockSkewEstimator,
+        processed_message_tracker::{ProcessedMessageKey, ProcessedMessageTracker},
         proposal_generator::ProposalGenerator,
         proposer_election::ProposerElection,
         round_state::{NewRoundEvent, NewRoundReason, RoundState, RoundStateLogSchema},
@@ -22,7 +24,7 @@ use crate::{
     network_interface::ConsensusMsg,
     pending_votes::VoteReceptionResult,
     persistent_liveness_storage::PersistentLivenessStorage,
-    quorum_store::types::BatchMsg,
+    quorum_store::types::{BatchMsg, BatchQueueLengthHintMsg},
     rand::rand_gen::types::{FastShare, RandConfig, Share, TShare},
     util::is_vtxn_expected,
 };
@@ -34,6 +36,7 @@ use aptos_consensus_types::{
     block_data::BlockType,
     common::{Author, Round},
     delayed_qc_msg::DelayedQcMsg,
+    epoch_retrieval::EpochStarterKit,
     proof_of_store::{ProofCache, ProofOfStoreMsg, SignedBatchInfoMsg},
     proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert,
@@ -42,7 +45,7 @@ use aptos_consensus_types::{
     vote::Vote,
     vote_msg::VoteMsg,
 };
-use aptos_infallible::{checked, Mutex};
+use aptos_infallible::{checked, duration_since_epoch, Mutex};
 use aptos_logger::prelude::*;
 #[cfg(test)]
 use aptos_safety_rules::ConsensusState;
@@ -75,6 +78,7 @@ pub enum UnverifiedEvent {
     BatchMsg(Box<BatchMsg>),
     SignedBatchInfo(Box<SignedBatchInfoMsg>),
     ProofOfStoreMsg(Box<ProofOfStoreMsg>),
+    BatchQueueLengthHintMsg(Box<BatchQueueLengthHintMsg>),
 }
 
 pub const BACK_PRESSURE_POLLING_INTERVAL_MS: u64 = 10;
@@ -145,6 +149,15 @@ impl UnverifiedEvent {
                 }
                 VerifiedEvent::ProofOfStoreMsg(p)
             },
+            UnverifiedEvent::BatchQueueLengthHintMsg(h) => {
+                if !self_message {
+                    h.verify(peer_id)?;
+                    counters::VERIFY_MSG
+                        .with_label_values(&["batch_queue_length_hint"])
+                        .observe(start_time.elapsed().as_secs_f64());
+                }
+                VerifiedEvent::BatchQueueLengthHintMsg(h)
+            },
         })
     }
 
@@ -156,6 +169,17 @@ impl UnverifiedEvent {
             UnverifiedEvent::BatchMsg(b) => b.epoch(),
             UnverifiedEvent::SignedBatchInfo(sd) => sd.epoch(),
             UnverifiedEvent::ProofOfStoreMsg(p) => p.epoch(),
+            UnverifiedEvent::BatchQueueLengthHintMsg(h) => Ok(h.epoch()),
+        }
+    }
+
+    /// Returns the key identifying the message for duplicate detection (only proposals and
+    /// votes are tracked).
+    pub fn processed_msg_key(&self) -> Option<ProcessedMessageKey> {
+        match self {
+            UnverifiedEvent::ProposalMsg(p) => Some(ProcessedMessageKey::from_proposal_msg(p)),
+            UnverifiedEvent::VoteMsg(v) => Some(ProcessedMessageKey::from_vote_msg(v)),
+            _ => None,
         }
     }
 }
@@ -169,6 +193,7 @@ impl From<ConsensusMsg> for UnverifiedEvent {
             ConsensusMsg::BatchMsg(m) => UnverifiedEvent::BatchMsg(m),
             ConsensusMsg::SignedBatchInfo(m) => UnverifiedEvent::SignedBatchInfo(m),
             ConsensusMsg::ProofOfStoreMsg(m) => UnverifiedEvent::ProofOfStoreMsg(m),
+            ConsensusMsg::BatchQueueLengthHintMsg(m) => UnverifiedEvent::BatchQueueLengthHintMsg(m),
             _ => unreachable!("Unexpected conversion"),
         }
     }
@@ -181,15 +206,32 @@ pub enum VerifiedEvent {
     VerifiedProposalMsg(Box<Block>),
     VoteMsg(Box<VoteMsg>),
     UnverifiedSyncInfo(Box<SyncInfo>),
+    UnverifiedEpochStarterKit(Box<EpochStarterKit>),
     BatchMsg(Box<BatchMsg>),
     SignedBatchInfo(Box<SignedBatchInfoMsg>),
     ProofOfStoreMsg(Box<ProofOfStoreMsg>),
+    BatchQueueLengthHintMsg(Box<BatchQueueLengthHintMsg>),
     // local messages
     LocalTimeout(Round),
     // Shutdown the NetworkListener
     Shutdown(TokioOneshot::Sender<()>),
 }
 
+/// Returns true iff the votes are withheld due to back pressure, i.e., the local execution
+/// lags behind ordering by more than the vote back pressure limit. In order-only mode (i.e.,
+/// if `order_only_max_execution_lag` is non-zero), the validator keeps voting on ordering
+/// while the lag is within the given limit. With decoupled execution, votes only certify the
+/// ordering, so voting doesn't require the proposed blocks (or their ancestors) to be executed.
+fn is_vote_back_pressured(block_store: &BlockStore, order_only_max_execution_lag: Round) -> bool {
+    if !block_store.vote_back_pressure() {
+        return false;
+    }
+
+    let order_only = order_only_max_execution_lag > 0
+        && block_store.execution_lag() <= order_only_max_execution_lag;
+    !order_only
+}
+
 #[cfg(test)]
 #[path = "round_manager_test.rs"]
 mod round_manager_test;
@@ -219,6 +261,8 @@ pub struct RoundManager {
     randomness_config: OnChainRandomnessConfig,
     jwk_consensus_config: OnChainJWKConsensusConfig,
     fast_rand_config: Option<RandConfig>,
+    processed_msgs: Arc<Mutex<ProcessedMessageTracker>>,
+    clock_skew_estimator: ClockSkewEstimator,
 }
 
 impl RoundManager {
@@ -249,6 +293,10 @@ impl RoundManager {
             .set(onchain_config.decoupled_execution() as i64);
         let vtxn_config = onchain_config.effective_validator_txn_config();
         debug!("vtxn_config={:?}", vtxn_config);
+        let processed_msgs = Arc::new(Mutex::new(ProcessedMessageTracker::new(
+            local_config.processed_msgs_window_rounds,
+        )));
+        let clock_skew_estimator = ClockSkewEstimator::from_config(&local_config);
         Self {
             epoch_state,
             block_store,
@@ -265,6 +313,8 @@ impl RoundManager {
             randomness_config,
             jwk_consensus_config,
             fast_rand_config,
+            processed_msgs,
+            clock_skew_estimator,
         }
     }
 
@@ -279,6 +329,9 @@ impl RoundManager {
                 .collect(),
             self.local_config
                 .max_blocks_per_sending_request(self.onchain_config.quorum_store_enabled()),
+            self.local_config
+                .block_range_retrieval
+                .max_request_response_bytes(),
         )
     }
 
@@ -327,6 +380,14 @@ impl RoundManager {
                         .await?;
                 }
             }
+            #[cfg(feature = "byzantine")]
+            {
+                if self.local_config.byzantine_behavior.equivocate_proposals {
+                    self.send_equivocating_proposals(proposal_msg).await?;
+                    counters::PROPOSALS_COUNT.inc();
+                    return Ok(());
+                }
+            }
             self.network.broadcast_proposal(proposal_msg).await;
             counters::PROPOSALS_COUNT.inc();
         }
@@ -449,6 +510,22 @@ impl RoundManager {
             block_parent_hash = proposal_msg.proposal().quorum_cert().certified_block().id(),
         );
 
+        let processed_msg_key = ProcessedMessageKey::from_proposal_msg(&proposal_msg);
+        if self.processed_msgs.lock().is_duplicate(&processed_msg_key) {
+            return Ok(());
+        }
+
+        // Record the proposal timestamp before validating the proposal, so that the clock
+        // skew of peers is still estimated when their proposals are rejected for being
+        // too far in the future.
+        if proposal_msg.proposer() != self.proposal_generator.author() {
+            self.clock_skew_estimator.record_timestamp(
+                proposal_msg.proposer(),
+                proposal_msg.proposal().timestamp_usecs(),
+                duration_since_epoch(),
+            );
+        }
+
         if self
             .ensure_round_and_sync_up(
                 proposal_msg.proposal().round(),
@@ -458,7 +535,9 @@ impl RoundManager {
             .await
             .context("[RoundManager] Process proposal")?
         {
-            self.process_proposal(proposal_msg.take_proposal()).await
+            self.process_proposal(proposal_msg.take_proposal()).await?;
+            self.processed_msgs.lock().mark_processed(processed_msg_key);
+            Ok(())
         } else {
             bail!(
                 "Stale proposal {}, current round {}",
@@ -579,7 +658,7 @@ impl RoundManager {
     }
 
     fn sync_only(&self) -> bool {
-        let sync_or_not = self.local_config.sync_only || self.block_store.vote_back_pressure();
+        let sync_or_not = self.local_config.sync_only || self.vote_back_pressure();
         counters::OP_COUNTERS
             .gauge("sync_only")
             .set(sync_or_not as i64);
@@ -587,6 +666,15 @@ impl RoundManager {
         sync_or_not
     }
 
+    /// Returns true iff the votes are withheld due to back pressure (i.e., the local execution
+    /// lags behind ordering). See `is_vote_back_pressured` for the order-only mode.
+    fn vote_back_pressure(&self) -> bool {
+        is_vote_back_pressured(
+            &self.block_store,
+            self.local_config.order_only_max_execution_lag,
+        )
+    }
+
     /// The replica broadcasts a "timeout vote message", which includes the round signature, which
     /// can be aggregated to a TimeoutCertificate.
     /// The timeout vote message can be one of the following three options:
@@ -642,6 +730,8 @@ impl RoundManager {
 
         self.round_state.record_vote(timeout_vote.clone());
         let timeout_vote_msg = VoteMsg::new(timeout_vote, self.block_store.sync_info());
+        #[cfg(feature = "byzantine")]
+        self.delay_timeout_vote().await;
         self.network.broadcast_timeout_vote(timeout_vote_msg).await;
         warn!(
             round = round,
@@ -772,16 +862,21 @@ impl RoundManager {
 
         let block_time_since_epoch = Duration::from_micros(proposal.timestamp_usecs());
 
+        // Allow for the estimated clock skew of the fleet (e.g., if the local clock is behind)
+        let timestamp_tolerance = self.clock_skew_estimator.timestamp_tolerance();
         ensure!(
-            block_time_since_epoch < self.round_state.current_round_deadline(),
+            block_time_since_epoch
+                < self.round_state.current_round_deadline() + timestamp_tolerance,
             "[RoundManager] Waiting until proposal block timestamp usecs {:?} \
-            would exceed the round duration {:?}, hence will not vote for this round",
+            would exceed the round duration {:?} (with clock skew tolerance {:?}), \
+            hence will not vote for this round",
             block_time_since_epoch,
             self.round_state.current_round_deadline(),
+            timestamp_tolerance,
         );
 
         observe_block(proposal.timestamp_usecs(), BlockStage::SYNCED);
-        if self.block_store.vote_back_pressure() {
+        if self.vote_back_pressure() {
             counters::CONSENSUS_WITHOLD_VOTE_BACKPRESSURE_TRIGGERED.observe(1.0);
             // In case of back pressure, we delay processing proposal. This is done by resending the
             // same proposal to self after some time. Even if processing proposal is delayed, we add
@@ -806,6 +901,9 @@ impl RoundManager {
             Ok(())
         } else {
             counters::CONSENSUS_WITHOLD_VOTE_BACKPRESSURE_TRIGGERED.observe(0.0);
+            // Back pressure without withholding the vote means voting in order-only mode
+            counters::CONSENSUS_ORDER_ONLY_VOTE_TRIGGERED
+                .observe(self.block_store.vote_back_pressure() as u8 as f64);
             self.process_verified_proposal(proposal).await
         }
     }
@@ -819,11 +917,12 @@ impl RoundManager {
     ) {
         let start = Instant::now();
         let block_store = self.block_store.clone();
+        let order_only_max_execution_lag = self.local_config.order_only_max_execution_lag;
         let self_sender = self.buffered_proposal_tx.clone();
         let event = VerifiedEvent::VerifiedProposalMsg(Box::new(proposal));
         tokio::spawn(async move {
             while start.elapsed() < Duration::from_millis(timeout_ms) {
-                if !block_store.vote_back_pressure() {
+                if !is_vote_back_pressured(&block_store, order_only_max_execution_lag) {
                     if let Err(e) = self_sender.push(author, event) {
                         warn!("Failed to send event to round manager {:?}", e);
                     }
@@ -836,6 +935,7 @@ impl RoundManager {
 
     pub async fn process_verified_proposal(&mut self, proposal: Block) -> anyhow::Result<()> {
         let proposal_round = proposal.round();
+        self.block_store.speculatively_execute(&proposal).await;
         let vote = self
             .execute_and_vote(proposal)
             .await
@@ -862,6 +962,17 @@ impl RoundManager {
             }
         }
 
+        #[cfg(feature = "byzantine")]
+        {
+            if self.local_config.byzantine_behavior.withhold_votes {
+                warn!(self.new_log(LogEvent::Vote), "Withholding vote {}", vote);
+                counters::BYZANTINE_BEHAVIORS_INJECTED
+                    .with_label_values(&["withhold_vote"])
+                    .inc();
+                return Ok(());
+            }
+        }
+
         if self.local_config.broadcast_vote {
             info!(self.new_log(LogEvent::Vote), "{}", vote);
             self.network.broadcast_vote(vote_msg).await;
@@ -932,6 +1043,11 @@ impl RoundManager {
         fail_point!("consensus::process_vote_msg", |_| {
             Err(anyhow::anyhow!("Injected error in process_vote_msg"))
         });
+        let processed_msg_key = ProcessedMessageKey::from_vote_msg(&vote_msg);
+        if self.processed_msgs.lock().is_duplicate(&processed_msg_key) {
+            return Ok(());
+        }
+
         // Check whether this validator is a valid recipient of the vote.
         if self
             .ensure_round_and_sync_up(
@@ -945,10 +1061,17 @@ impl RoundManager {
             self.process_vote(vote_msg.vote())
                 .await
                 .context("[RoundManager] Add a new vote")?;
+            self.processed_msgs.lock().mark_processed(processed_msg_key);
         }
         Ok(())
     }
 
+    /// Returns the tracker of the processed proposals and votes. This is shared with the epoch
+    /// manager, so that duplicates can be dropped before their signatures are verified.
+    pub fn processed_msgs(&self) -> Arc<Mutex<ProcessedMessageTracker>> {
+        self.processed_msgs.clone()
+    }
+
     /// Add a vote to the pending votes.
     /// If a new QC / TC is formed then
     /// 1) fetch missing dependencies if required, and then
@@ -1242,4 +1365,89 @@ impl RoundManager {
             Ok(())
         }
     }
+
+    /// Sends two conflicting proposals for the round: the generated proposal to one half of the
+    /// validators, and a copy with a different timestamp (and thus a different id) to the other
+    /// half. Both proposals are signed by safety rules, as it doesn't track signed proposals. The
+    /// honest validators are expected to vote for at most one proposal per round.
+    ///
+    /// It's only enabled with byzantine behavior injection (byzantine feature).
+    #[cfg(feature = "byzantine")]
+    async fn send_equivocating_proposals(&self, proposal_msg: ProposalMsg) -> anyhow::Result<()> {
+        use aptos_consensus_types::block_data::BlockData;
+
+        let block_data = proposal_msg.proposal().block_data();
+        let payload = block_data
+            .payload()
+            .cloned()
+            .context("[RoundManager] Proposal to equivocate has no payload")?;
+        let author = block_data
+            .author()
+            .context("[RoundManager] Proposal to equivocate has no author")?;
+        let failed_authors = block_data.failed_authors().cloned().unwrap_or_default();
+        let timestamp_usecs = block_data.timestamp_usecs() + 1;
+        let conflicting_block_data = match block_data.validator_txns() {
+            Some(validator_txns) => BlockData::new_proposal_ext(
+                validator_txns.clone(),
+                payload,
+                author,
+                failed_authors,
+                block_data.round(),
+                timestamp_usecs,
+                block_data.quorum_cert().clone(),
+            ),
+            None => BlockData::new_proposal(
+                payload,
+                author,
+                failed_authors,
+                block_data.round(),
+                timestamp_usecs,
+                block_data.quorum_cert().clone(),
+            ),
+        };
+        let signature = self
+            .safety_rules
+            .lock()
+            .sign_proposal(&conflicting_block_data)?;
+        let conflicting_proposal_msg = ProposalMsg::new(
+            Block::new_proposal_from_block_data_and_signature(conflicting_block_data, signature),
+            proposal_msg.sync_info().clone(),
+        );
+        warn!(
+            self.new_log(LogEvent::Propose),
+            "Equivocating with proposals {} and {}",
+            proposal_msg.proposal(),
+            conflicting_proposal_msg.proposal()
+        );
+
+        let mut half_peers: Vec<_> = self
+            .epoch_state
+            .verifier
+            .get_ordered_account_addresses_iter()
+            .collect();
+        let other_half_peers = half_peers.split_off(half_peers.len() / 2);
+        self.network.send_proposal(proposal_msg, half_peers).await;
+        self.network
+            .send_proposal(conflicting_proposal_msg, other_half_peers)
+            .await;
+        counters::BYZANTINE_BEHAVIORS_INJECTED
+            .with_label_values(&["equivocate_proposal"])
+            .inc();
+        Ok(())
+    }
+
+    /// Delays the broadcast of a timeout vote by the configured delay. Note: this also stalls
+    /// the processing of all other events by the round manager, as a slow validator would.
+    ///
+    /// It's only enabled with byzantine behavior injection (byzantine feature).
+    #[cfg(feature = "byzantine")]
+    async fn delay_timeout_vote(&self) {
+        let delay_ms = self.local_config.byzantine_behavior.timeout_vote_delay_ms;
+        if delay_ms > 0 {
+            counters::BYZANTINE_BEHAVIORS_INJECTED
+                .with_label_values(&["delay_timeout_vote"])
+                .inc();
+            sleep(Duration::from_millis(delay_ms)).await;
+        }
+    }
 }
@@ -13,7 +13,7 @@ use crate::{
         round_state::{ExponentialTimeInterval, RoundState},
     },
     metrics_safety_rules::MetricsSafetyRules,
-    network::{IncomingBlockRetrievalRequest, NetworkSender},
+    network::{IncomingBlockRangeRetrievalRequest, IncomingBlockRetrievalRequest, NetworkSender},
     network_interface::{CommitMessage, ConsensusMsg, ConsensusNetworkClient, DIRECT_SEND, RPC},
     network_tests::{NetworkPlayground, TwinId},
     payload_manager::PayloadManager,
@@ -37,8 +37,12 @@ use aptos_consensus_types::{
         block_test_utils::{certificate_for_genesis, gen_test_certificate},
         Block,
     },
-    block_retrieval::{BlockRetrievalRequest, BlockRetrievalStatus},
+    block_retrieval::{
+        BlockRangeRetrievalRequest, BlockRetrievalRequest, BlockRetrievalResponse,
+        BlockRetrievalStatus,
+    },
     common::{Author, Payload, Round},
+    epoch_retrieval::EpochStarterKit,
     pipeline::commit_decision::CommitDecision,
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
@@ -694,6 +698,51 @@ fn new_round_on_quorum_cert() {
     });
 }
 
+#[test]
+/// Duplicate proposals and votes that were already processed should be ignored
+fn duplicate_proposal_not_reprocessed() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(
+        &mut playground,
+        runtime.handle().clone(),
+        1,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    let node = &mut nodes[0];
+    timed_block_on(&runtime, async {
+        let proposal_msg = node.next_proposal().await;
+        node.round_manager
+            .process_proposal_msg(proposal_msg.clone())
+            .await
+            .unwrap();
+        let vote_msg = node.next_vote().await;
+
+        // The duplicate proposal is ignored, so no additional vote is sent
+        node.round_manager
+            .process_proposal_msg(proposal_msg)
+            .await
+            .unwrap();
+        node.no_next_msg();
+
+        // Adding the vote forms a QC and round 2 should start
+        node.round_manager
+            .process_vote_msg(vote_msg.clone())
+            .await
+            .unwrap();
+        let proposal_msg = node.next_proposal().await;
+        assert_eq!(proposal_msg.proposal().round(), 2);
+
+        // The duplicate vote is ignored
+        node.round_manager.process_vote_msg(vote_msg).await.unwrap();
+        node.no_next_msg();
+    });
+}
+
 #[test]
 /// If the proposal is valid, a vote should be sent
 fn vote_on_successful_proposal() {
@@ -1329,6 +1378,174 @@ fn response_on_block_retrieval() {
     });
 }
 
+#[test]
+fn response_on_block_range_retrieval() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut node = NodeSetup::create_nodes(
+        &mut playground,
+        runtime.handle().clone(),
+        1,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .pop()
+    .unwrap();
+
+    let genesis_qc = certificate_for_genesis();
+    let block = Block::new_proposal(
+        Payload::empty(false, true),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+        Vec::new(),
+    )
+    .unwrap();
+    let block_id = block.id();
+    let proposal = ProposalMsg::new(block, SyncInfo::new(genesis_qc.clone(), genesis_qc, None));
+
+    timed_block_on(&runtime, async {
+        node.round_manager
+            .process_proposal_msg(proposal)
+            .await
+            .unwrap();
+        let genesis_id = node.block_store.ordered_root().id();
+
+        // verify that the whole range is returned if it fits into the response
+        let response = process_block_range_retrieval(
+            &node,
+            BlockRangeRetrievalRequest::new(block_id, 0, u64::MAX),
+            u64::MAX,
+        )
+        .await;
+        assert_eq!(response.status(), BlockRetrievalStatus::SucceededWithTarget);
+        let block_ids: Vec<_> = response.blocks().iter().map(|block| block.id()).collect();
+        assert_eq!(block_ids, vec![block_id, genesis_id]);
+
+        // verify that the response is capped by the requested bytes (but has at least one block)
+        let response = process_block_range_retrieval(
+            &node,
+            BlockRangeRetrievalRequest::new(block_id, 0, 1),
+            u64::MAX,
+        )
+        .await;
+        assert_eq!(response.status(), BlockRetrievalStatus::Succeeded);
+        assert_eq!(response.blocks().len(), 1);
+        assert_eq!(response.blocks()[0].id(), block_id);
+
+        // verify that the response is capped by the local max bytes
+        let response = process_block_range_retrieval(
+            &node,
+            BlockRangeRetrievalRequest::new(block_id, 0, u64::MAX),
+            1,
+        )
+        .await;
+        assert_eq!(response.status(), BlockRetrievalStatus::Succeeded);
+        assert_eq!(response.blocks().len(), 1);
+
+        // verify that the range ends at the first block with a round <= the target round
+        let response = process_block_range_retrieval(
+            &node,
+            BlockRangeRetrievalRequest::new(block_id, 1, u64::MAX),
+            u64::MAX,
+        )
+        .await;
+        assert_eq!(response.status(), BlockRetrievalStatus::SucceededWithTarget);
+        assert_eq!(response.blocks().len(), 1);
+
+        // verify that if a block is not there, return ID_NOT_FOUND
+        let response = process_block_range_retrieval(
+            &node,
+            BlockRangeRetrievalRequest::new(HashValue::random(), 0, u64::MAX),
+            u64::MAX,
+        )
+        .await;
+        assert_eq!(response.status(), BlockRetrievalStatus::IdNotFound);
+        assert!(response.blocks().is_empty());
+    });
+}
+
+#[test]
+fn create_and_verify_epoch_starter_kit() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut node = NodeSetup::create_nodes(
+        &mut playground,
+        runtime.handle().clone(),
+        1,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .pop()
+    .unwrap();
+
+    let genesis_qc = certificate_for_genesis();
+    let block = Block::new_proposal(
+        Payload::empty(false, true),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+        Vec::new(),
+    )
+    .unwrap();
+    let proposal = ProposalMsg::new(
+        block.clone(),
+        SyncInfo::new(genesis_qc.clone(), genesis_qc, None),
+    );
+    let verifier = generate_validator_verifier(&[node.signer.clone()]);
+
+    timed_block_on(&runtime, async {
+        node.round_manager
+            .process_proposal_msg(proposal)
+            .await
+            .unwrap();
+
+        // verify that the kit contains the sync info, but not the genesis block
+        let starter_kit = node.block_store.create_epoch_starter_kit(None, 10);
+        assert!(starter_kit.epoch_change_proof().is_none());
+        assert_eq!(starter_kit.sync_info(), &node.block_store.sync_info());
+        assert!(starter_kit.blocks().is_empty());
+        starter_kit.verify_blocks(&verifier).unwrap();
+
+        // verify that blocks that don't chain to the highest ordered block are rejected
+        let starter_kit = EpochStarterKit::new(None, node.block_store.sync_info(), vec![block]);
+        assert!(starter_kit.verify_blocks(&verifier).is_err());
+    });
+}
+
+/// Processes the ranged block retrieval request and returns the response
+async fn process_block_range_retrieval(
+    node: &NodeSetup,
+    request: BlockRangeRetrievalRequest,
+    max_response_bytes: u64,
+) -> BlockRetrievalResponse {
+    let (tx, rx) = oneshot::channel();
+    let request = IncomingBlockRangeRetrievalRequest {
+        req: request,
+        protocol: ProtocolId::ConsensusRpcBcs,
+        response_sender: tx,
+    };
+    node.block_store
+        .process_block_range_retrieval(request, max_response_bytes)
+        .await
+        .unwrap();
+    match rx.await {
+        Ok(Ok(bytes)) => match bcs::from_bytes(&bytes) {
+            Ok(ConsensusMsg::BlockRetrievalResponse(response)) => *response,
+            _ => panic!("block range retrieval failure"),
+        },
+        _ => panic!("block range retrieval failure"),
+    }
+}
+
 #[test]
 /// rebuild a node from previous storage without violating safety guarantees.
 fn recover_on_restart() {
@@ -0,0 +1,235 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{consensusdb::ConsensusDB, quorum_store::quorum_store_db::QuorumStoreDB};
+use aptos_config::config::NodeConfig;
+use aptos_consensus_types::{common::Author, vote::Vote};
+use aptos_crypto::bls12381;
+use aptos_infallible::duration_since_epoch;
+use aptos_logger::prelude::*;
+use aptos_safety_rules::safety_rules_manager::load_consensus_key_from_secure_storage;
+use aptos_storage_interface::DbReader;
+use aptos_types::epoch_state::EpochState;
+use std::time::Duration;
+use thiserror::Error;
+
+/// An inconsistency found by the consensus startup checks. Each error
+/// describes what is wrong, and what the operator should do about it.
+#[derive(Debug, Error)]
+pub enum StartupCheckError {
+    #[error(
+        "Unable to load the consensus key from secure storage: {0}. Check that the \
+        consensus.safety_rules.backend config points to the initialized secure storage."
+    )]
+    ConsensusKeyUnavailable(String),
+    #[error(
+        "The local consensus key ({local_public_key}) doesn't match the on-chain consensus key \
+        ({on_chain_public_key}) of validator {author}. Restore the correct key to secure storage, \
+        or rotate the on-chain consensus key."
+    )]
+    ConsensusKeyMismatch {
+        author: Author,
+        local_public_key: bls12381::PublicKey,
+        on_chain_public_key: bls12381::PublicKey,
+    },
+    #[error("Unable to read the latest ledger state from storage: {0}")]
+    LedgerUnavailable(String),
+    #[error(
+        "The consensus db is unreadable or corrupted: {0}. Stop the node, remove the consensus db \
+        (it will be rebuilt from the network) and restart."
+    )]
+    ConsensusDbCorrupted(String),
+    #[error(
+        "The consensus db contains data for epoch {db_epoch}, but the ledger is only at epoch \
+        {ledger_epoch}. The consensus db likely belongs to a different chain or a newer snapshot. \
+        Stop the node, remove the consensus db and restart."
+    )]
+    ConsensusDbAheadOfLedger { db_epoch: u64, ledger_epoch: u64 },
+    #[error(
+        "The quorum store db is unreadable or corrupted: {0}. Stop the node, remove the quorum \
+        store db and restart."
+    )]
+    QuorumStoreDbCorrupted(String),
+    #[error(
+        "The quorum store db contains a batch id for epoch {db_epoch}, but the ledger is only at \
+        epoch {ledger_epoch}. Stop the node, remove the quorum store db and restart."
+    )]
+    QuorumStoreDbAheadOfLedger { db_epoch: u64, ledger_epoch: u64 },
+    #[error(
+        "The local clock is {lag_ms} ms behind the timestamp of the latest committed block. \
+        Fix the system clock (e.g., check the NTP configuration) and restart."
+    )]
+    ClockBehindLedger { lag_ms: u64 },
+}
+
+/// Runs all consensus startup checks, and returns the errors found (if any).
+/// Consensus should refuse to start if any check fails, instead of failing
+/// (or panicking) later on, e.g., in the middle of a round.
+pub fn run_startup_checks(
+    node_config: &NodeConfig,
+    aptos_db: &dyn DbReader,
+    consensus_db: &ConsensusDB,
+    quorum_store_db: &QuorumStoreDB,
+) -> Vec<StartupCheckError> {
+    let mut errors = vec![];
+
+    // Fetch the latest ledger state (all other checks depend on it)
+    let (epoch_state, ledger_info) = match aptos_db
+        .get_latest_epoch_state()
+        .and_then(|epoch_state| Ok((epoch_state, aptos_db.get_latest_ledger_info()?)))
+    {
+        Ok(ledger_state) => ledger_state,
+        Err(error) => {
+            errors.push(StartupCheckError::LedgerUnavailable(error.to_string()));
+            return errors;
+        },
+    };
+
+    // Verify the consensus key
+    if let Some(author) = node_config
+        .validator_network
+        .as_ref()
+        .map(|network_config| network_config.peer_id())
+    {
+        match load_consensus_key_from_secure_storage(&node_config.consensus.safety_rules) {
+            Ok(consensus_key) => {
+                errors.extend(check_consensus_key(
+                    author,
+                    &consensus_key.public_key(),
+                    &epoch_state,
+                ));
+            },
+            Err(error) => {
+                errors.push(StartupCheckError::ConsensusKeyUnavailable(
+                    error.to_string(),
+                ));
+            },
+        }
+    }
+
+    // Verify the consensus and quorum store dbs
+    errors.extend(check_consensus_db(consensus_db, epoch_state.epoch));
+    errors.extend(check_quorum_store_db(quorum_store_db, epoch_state.epoch));
+
+    // Verify the local clock
+    let max_clock_lag = Duration::from_millis(
+        node_config
+            .consensus
+            .startup_checks
+            .max_clock_lag_behind_ledger_ms,
+    );
+    errors.extend(check_clock(
+        duration_since_epoch(),
+        Duration::from_micros(ledger_info.ledger_info().timestamp_usecs()),
+        max_clock_lag,
+    ));
+
+    errors
+}
+
+/// Verifies that the local consensus key matches the on-chain consensus key
+/// (if the node is a validator in the current epoch).
+pub fn check_consensus_key(
+    author: Author,
+    local_public_key: &bls12381::PublicKey,
+    epoch_state: &EpochState,
+) -> Option<StartupCheckError> {
+    match epoch_state.verifier.get_public_key(&author) {
+        Some(on_chain_public_key) if &on_chain_public_key != local_public_key => {
+            Some(StartupCheckError::ConsensusKeyMismatch {
+                author,
+                local_public_key: local_public_key.clone(),
+                on_chain_public_key,
+            })
+        },
+        Some(_) => None,
+        None => {
+            info!(
+                "Validator {} is not in the validator set of epoch {}. \
+                Skipping the consensus key check.",
+                author, epoch_state.epoch
+            );
+            None
+        },
+    }
+}
+
+/// Verifies that the consensus db is readable, and doesn't contain data
+/// for epochs after the current ledger epoch.
+pub fn check_consensus_db(
+    consensus_db: &ConsensusDB,
+    ledger_epoch: u64,
+) -> Option<StartupCheckError> {
+    let (last_vote, _, blocks, quorum_certs) = match consensus_db.get_data() {
+        Ok(data) => data,
+        Err(error) => return Some(StartupCheckError::ConsensusDbCorrupted(error.to_string())),
+    };
+    let last_vote = match last_vote
+        .map(|bytes| bcs::from_bytes::<Vote>(&bytes))
+        .transpose()
+    {
+        Ok(last_vote) => last_vote,
+        Err(error) => {
+            return Some(StartupCheckError::ConsensusDbCorrupted(format!(
+                "unable to deserialize the last vote: {}",
+                error
+            )))
+        },
+    };
+
+    // Find the highest epoch in the db
+    let db_epoch = blocks
+        .iter()
+        .map(|block| block.epoch())
+        .chain(
+            quorum_certs
+                .iter()
+                .map(|quorum_cert| quorum_cert.certified_block().epoch()),
+        )
+        .chain(last_vote.map(|vote| vote.vote_data().proposed().epoch()))
+        .max();
+    match db_epoch {
+        Some(db_epoch) if db_epoch > ledger_epoch => {
+            Some(StartupCheckError::ConsensusDbAheadOfLedger {
+                db_epoch,
+                ledger_epoch,
+            })
+        },
+        _ => None,
+    }
+}
+
+/// Verifies that the quorum store db is readable, and doesn't contain
+/// batch ids for epochs after the current ledger epoch.
+pub fn check_quorum_store_db(
+    quorum_store_db: &QuorumStoreDB,
+    ledger_epoch: u64,
+) -> Option<StartupCheckError> {
+    let db_epochs = match quorum_store_db.get_batch_id_epochs() {
+        Ok(db_epochs) => db_epochs,
+        Err(error) => return Some(StartupCheckError::QuorumStoreDbCorrupted(error.to_string())),
+    };
+    match db_epochs.into_iter().max() {
+        Some(db_epoch) if db_epoch > ledger_epoch => {
+            Some(StartupCheckError::QuorumStoreDbAheadOfLedger {
+                db_epoch,
+                ledger_epoch,
+            })
+        },
+        _ => None,
+    }
+}
+
+/// Verifies that the local clock is not (too far) behind the timestamp
+/// of the latest committed block. Otherwise, the node would reject all
+/// new proposals as being too far in the future.
+pub fn check_clock(
+    now: Duration,
+    ledger_timestamp: Duration,
+    max_clock_lag: Duration,
+) -> Option<StartupCheckError> {
+    let clock_lag = ledger_timestamp.saturating_sub(now);
+    (clock_lag > max_clock_lag).then(|| StartupCheckError::ClockBehindLedger {
+        lag_ms: clock_lag.as_millis() as u64,
+    })
+}
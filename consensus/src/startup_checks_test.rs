@@ -0,0 +1,130 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensusdb::ConsensusDB,
+    quorum_store::quorum_store_db::{QuorumStoreDB, QuorumStoreStorage},
+    startup_checks::{
+        check_clock, check_consensus_db, check_consensus_key, check_quorum_store_db,
+        StartupCheckError,
+    },
+};
+use aptos_consensus_types::{
+    block::{block_test_utils::certificate_for_genesis, Block},
+    common::Payload,
+    proof_of_store::BatchId,
+};
+use aptos_temppath::TempPath;
+use aptos_types::{
+    epoch_state::EpochState, validator_signer::ValidatorSigner,
+    validator_verifier::ValidatorVerifier,
+};
+use std::time::Duration;
+
+#[test]
+fn test_check_consensus_key() {
+    let signer = ValidatorSigner::random(None);
+    let epoch_state = EpochState {
+        epoch: 1,
+        verifier: ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+    };
+
+    // The local key matches the on-chain key
+    assert!(check_consensus_key(signer.author(), &signer.public_key(), &epoch_state).is_none());
+
+    // The local key doesn't match the on-chain key
+    let other_signer = ValidatorSigner::random([1; 32]);
+    assert!(matches!(
+        check_consensus_key(signer.author(), &other_signer.public_key(), &epoch_state),
+        Some(StartupCheckError::ConsensusKeyMismatch { .. })
+    ));
+
+    // The node is not in the validator set
+    assert!(check_consensus_key(
+        other_signer.author(),
+        &other_signer.public_key(),
+        &epoch_state
+    )
+    .is_none());
+}
+
+#[test]
+fn test_check_consensus_db() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    // An empty db is consistent
+    assert!(check_consensus_db(&db, 0).is_none());
+
+    // Save a block (and its quorum cert)
+    let signer = ValidatorSigner::random(None);
+    let genesis_qc = certificate_for_genesis();
+    let block = Block::new_proposal(
+        Payload::empty(false, true),
+        1,
+        1,
+        genesis_qc.clone(),
+        &signer,
+        vec![],
+    )
+    .unwrap();
+    let block_epoch = block.epoch();
+    db.save_blocks_and_quorum_certificates(vec![block], vec![genesis_qc])
+        .unwrap();
+
+    // The db is consistent with the same (or a later) ledger epoch
+    assert!(check_consensus_db(&db, block_epoch).is_none());
+    assert!(check_consensus_db(&db, block_epoch + 1).is_none());
+
+    // The db is ahead of an earlier ledger epoch
+    if let Some(ledger_epoch) = block_epoch.checked_sub(1) {
+        assert!(matches!(
+            check_consensus_db(&db, ledger_epoch),
+            Some(StartupCheckError::ConsensusDbAheadOfLedger { .. })
+        ));
+    }
+
+    // A corrupted last vote is detected
+    db.save_vote(vec![0xFF; 10]).unwrap();
+    assert!(matches!(
+        check_consensus_db(&db, block_epoch),
+        Some(StartupCheckError::ConsensusDbCorrupted(_))
+    ));
+}
+
+#[test]
+fn test_check_quorum_store_db() {
+    let tmp_dir = TempPath::new();
+    let db = QuorumStoreDB::new(&tmp_dir);
+
+    // An empty db is consistent
+    assert!(check_quorum_store_db(&db, 0).is_none());
+
+    // Save batch ids for epochs 5 and 6
+    db.save_batch_id(5, BatchId::new_for_test(1)).unwrap();
+    db.save_batch_id(6, BatchId::new_for_test(2)).unwrap();
+    assert!(check_quorum_store_db(&db, 6).is_none());
+    assert!(matches!(
+        check_quorum_store_db(&db, 5),
+        Some(StartupCheckError::QuorumStoreDbAheadOfLedger {
+            db_epoch: 6,
+            ledger_epoch: 5
+        })
+    ));
+}
+
+#[test]
+fn test_check_clock() {
+    let now = Duration::from_secs(1_000);
+    let max_clock_lag = Duration::from_secs(60);
+
+    // The clock is ahead of the ledger, or only slightly behind it
+    assert!(check_clock(now, now - Duration::from_secs(10), max_clock_lag).is_none());
+    assert!(check_clock(now, now + Duration::from_secs(10), max_clock_lag).is_none());
+
+    // The clock is too far behind the ledger
+    assert!(matches!(
+        check_clock(now, now + Duration::from_secs(120), max_clock_lag),
+        Some(StartupCheckError::ClockBehindLedger { lag_ms: 120_000 })
+    ));
+}
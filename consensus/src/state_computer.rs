@@ -5,6 +5,10 @@
 use crate::{
     block_preparer::BlockPreparer,
     block_storage::tracing::{observe_block, BlockStage},
+    consensus_observer::{
+        network_message::{BlockPayload, ConsensusObserverMessage},
+        publisher::ConsensusPublisher,
+    },
     counters,
     error::StateSyncError,
     execution_pipeline::ExecutionPipeline,
@@ -17,6 +21,7 @@ use crate::{
     txn_notifier::TxnNotifier,
 };
 use anyhow::Result;
+use aptos_config::config::{OptimisticExecutionConfig, StatePrefetchConfig};
 use aptos_consensus_notifications::ConsensusNotificationSender;
 use aptos_consensus_types::{block::Block, common::Round, pipelined_block::PipelinedBlock};
 use aptos_crypto::HashValue;
@@ -90,6 +95,8 @@ pub struct ExecutionProxy {
     transaction_filter: Arc<TransactionFilter>,
     execution_pipeline: ExecutionPipeline,
     state: RwLock<Option<MutableState>>,
+    // publishes the payloads of the executed blocks to consensus observers
+    consensus_publisher: Option<ConsensusPublisher>,
 }
 
 impl ExecutionProxy {
@@ -99,6 +106,9 @@ impl ExecutionProxy {
         state_sync_notifier: Arc<dyn ConsensusNotificationSender>,
         handle: &tokio::runtime::Handle,
         txn_filter: TransactionFilter,
+        optimistic_execution_config: OptimisticExecutionConfig,
+        state_prefetch_config: StatePrefetchConfig,
+        consensus_publisher: Option<ConsensusPublisher>,
     ) -> Self {
         let (tx, mut rx) =
             aptos_channels::new::<NotificationType>(10, &counters::PENDING_STATE_SYNC_NOTIFICATION);
@@ -115,7 +125,12 @@ impl ExecutionProxy {
                 callback();
             }
         });
-        let execution_pipeline = ExecutionPipeline::spawn(executor.clone(), handle);
+        let execution_pipeline = ExecutionPipeline::spawn(
+            executor.clone(),
+            handle,
+            optimistic_execution_config,
+            state_prefetch_config,
+        );
         Self {
             executor,
             txn_notifier,
@@ -125,7 +140,48 @@ impl ExecutionProxy {
             transaction_filter: Arc::new(txn_filter),
             execution_pipeline,
             state: RwLock::new(None),
+            consensus_publisher,
+        }
+    }
+
+    /// Speculatively executes the given (verified) proposal before it is ordered. The result
+    /// is only used if the block is later ordered (on top of the same parent).
+    pub async fn schedule_speculative_compute(&self, block: &Block, parent_block_id: HashValue) {
+        let MutableState {
+            validators,
+            payload_manager,
+            transaction_shuffler,
+            block_executor_onchain_config,
+            transaction_deduper,
+            is_randomness_enabled,
+        } = match self.state.read().as_ref().cloned() {
+            Some(state) => state,
+            None => return, // We're between epochs
+        };
+
+        // The randomness for the block is only known once the block is ordered
+        if is_randomness_enabled {
+            return;
         }
+
+        debug!(
+            block = %block,
+            parent_id = parent_block_id,
+            "Speculatively executing block",
+        );
+        let transaction_generator = BlockPreparer::new(
+            payload_manager,
+            self.transaction_filter.clone(),
+            transaction_deduper,
+            transaction_shuffler,
+        );
+        self.execution_pipeline.queue_speculative(
+            block.clone(),
+            block.new_block_metadata(&validators).into(),
+            parent_block_id,
+            transaction_generator,
+            block_executor_onchain_config,
+        );
     }
 
     fn transactions_to_commit(
@@ -205,6 +261,30 @@ impl StateComputer for ExecutionProxy {
             block.new_block_metadata(&validators).into()
         };
 
+        // Observers can only execute the block once its transactions are known (i.e.,
+        // once its payload is resolved), so the payload is published after execution.
+        let block_payload_publisher = self
+            .consensus_publisher
+            .clone()
+            .filter(|consensus_publisher| consensus_publisher.has_subscribers())
+            .map(|consensus_publisher| {
+                let block_payload = BlockPayload {
+                    block_id,
+                    parent_block_id,
+                    epoch: block.epoch(),
+                    round: block.round(),
+                    transactions: vec![],
+                    block_executor_onchain_config: block_executor_onchain_config.clone(),
+                };
+                let validator_txns = block.validator_txns().cloned().unwrap_or_default();
+                (
+                    consensus_publisher,
+                    block_payload,
+                    validator_txns,
+                    metadata.clone(),
+                )
+            });
+
         let fut = self
             .execution_pipeline
             .queue(
@@ -222,6 +302,17 @@ impl StateComputer for ExecutionProxy {
                 "Got state compute result, post processing."
             );
             let pipeline_execution_result = fut.await?;
+            if let Some((consensus_publisher, mut block_payload, validator_txns, metadata)) =
+                block_payload_publisher
+            {
+                block_payload.transactions = Block::combine_to_input_transactions(
+                    validator_txns,
+                    pipeline_execution_result.input_txns.clone(),
+                    metadata,
+                );
+                consensus_publisher
+                    .publish_message(ConsensusObserverMessage::BlockPayload { block_payload });
+            }
             let user_txns = &pipeline_execution_result.input_txns;
             let result = &pipeline_execution_result.result;
 
@@ -336,6 +427,7 @@ impl StateComputer for ExecutionProxy {
         // Before the state synchronization, we have to call finish() to free the in-memory SMT
         // held by BlockExecutor to prevent memory leak.
         self.executor.finish();
+        self.execution_pipeline.discard_speculative_executions();
 
         // The pipeline phase already committed beyond the target block timestamp, just return.
         if *latest_logical_time >= logical_time {
@@ -388,6 +480,7 @@ impl StateComputer for ExecutionProxy {
         transaction_deduper: Arc<dyn TransactionDeduper>,
         randomness_enabled: bool,
     ) {
+        self.execution_pipeline.discard_speculative_executions();
         *self.state.write() = Some(MutableState {
             validators: epoch_state
                 .verifier
@@ -405,6 +498,7 @@ impl StateComputer for ExecutionProxy {
     // Clears the epoch-specific state. Only a sync_to call is expected before calling new_epoch
     // on the next epoch.
     fn end_epoch(&self) {
+        self.execution_pipeline.discard_speculative_executions();
         self.state.write().take();
     }
 }
@@ -519,6 +613,10 @@ async fn test_commit_sync_race() {
             *self.time.lock() = logical_time;
             Ok(())
         }
+
+        async fn notify_observer_fallback(&self) -> std::result::Result<(), Error> {
+            Ok(())
+        }
     }
 
     let callback = Box::new(move |_a: &[Arc<PipelinedBlock>], _b: LedgerInfoWithSignatures| {});
@@ -540,6 +638,9 @@ async fn test_commit_sync_race() {
         recorded_commit.clone(),
         &tokio::runtime::Handle::current(),
         TransactionFilter::new(Filter::empty()),
+        OptimisticExecutionConfig::default(),
+        StatePrefetchConfig::default(),
+        None,
     );
 
     executor.new_epoch(
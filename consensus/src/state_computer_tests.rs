@@ -7,7 +7,9 @@ use crate::{
     transaction_filter::TransactionFilter, transaction_shuffler::NoOpShuffler,
     txn_notifier::TxnNotifier,
 };
-use aptos_config::config::transaction_filter_type::Filter;
+use aptos_config::config::{
+    transaction_filter_type::Filter, OptimisticExecutionConfig, StatePrefetchConfig,
+};
 use aptos_consensus_notifications::{ConsensusNotificationSender, Error};
 use aptos_consensus_types::{block::Block, block_data::BlockData, pipelined_block::PipelinedBlock};
 use aptos_crypto::HashValue;
@@ -57,6 +59,10 @@ impl ConsensusNotificationSender for DummyStateSyncNotifier {
     async fn sync_to_target(&self, _target: LedgerInfoWithSignatures) -> Result<(), Error> {
         unreachable!()
     }
+
+    async fn notify_observer_fallback(&self) -> Result<(), Error> {
+        unreachable!()
+    }
 }
 
 struct DummyTxnNotifier {}
@@ -144,6 +150,9 @@ async fn schedule_compute_should_discover_validator_txns() {
         Arc::new(DummyStateSyncNotifier::new()),
         &Handle::current(),
         TransactionFilter::new(Filter::empty()),
+        OptimisticExecutionConfig::default(),
+        StatePrefetchConfig::default(),
+        None,
     );
 
     let validator_txn_0 = ValidatorTransaction::dummy(vec![0xFF; 99]);
@@ -187,6 +196,70 @@ async fn schedule_compute_should_discover_validator_txns() {
     assert_eq!(&validator_txn_1, supposed_validator_txn_1);
 }
 
+#[tokio::test]
+async fn schedule_compute_should_reuse_speculative_execution() {
+    let executor = Arc::new(DummyBlockExecutor::new());
+
+    let execution_policy = ExecutionProxy::new(
+        executor.clone(),
+        Arc::new(DummyTxnNotifier {}),
+        Arc::new(DummyStateSyncNotifier::new()),
+        &Handle::current(),
+        TransactionFilter::new(Filter::empty()),
+        OptimisticExecutionConfig {
+            enable: true,
+            ..OptimisticExecutionConfig::default()
+        },
+        StatePrefetchConfig::default(),
+        None,
+    );
+
+    execution_policy.new_epoch(
+        &EpochState::empty(),
+        Arc::new(PayloadManager::DirectMempool),
+        Arc::new(NoOpShuffler {}),
+        BlockExecutorConfigFromOnchain::new_no_block_limit(),
+        Arc::new(NoOpDeduper {}),
+        false,
+    );
+
+    // Speculatively execute a block, and then order it on top of the same parent
+    let block = Block::new_for_testing(
+        HashValue::zero(),
+        BlockData::dummy_with_validator_txns(vec![]),
+        None,
+    );
+    execution_policy
+        .schedule_speculative_compute(&block, HashValue::zero())
+        .await;
+    execution_policy
+        .schedule_compute(&block, HashValue::zero(), None)
+        .await
+        .await
+        .unwrap();
+
+    // Ensure the block was only executed once
+    assert_eq!(executor.blocks_received.lock().len(), 1);
+
+    // Speculatively execute another block, and then order it on top of a different parent
+    let block = Block::new_for_testing(
+        HashValue::random(),
+        BlockData::dummy_with_validator_txns(vec![ValidatorTransaction::dummy(vec![0xFF])]),
+        None,
+    );
+    execution_policy
+        .schedule_speculative_compute(&block, HashValue::random())
+        .await;
+    execution_policy
+        .schedule_compute(&block, HashValue::zero(), None)
+        .await
+        .await
+        .unwrap();
+
+    // Ensure the speculative result was discarded, and the block was re-executed
+    assert_eq!(executor.blocks_received.lock().len(), 3);
+}
+
 #[tokio::test]
 async fn commit_should_discover_validator_txns() {
     let state_sync_notifier = Arc::new(DummyStateSyncNotifier::new());
@@ -197,6 +270,9 @@ async fn commit_should_discover_validator_txns() {
         state_sync_notifier.clone(),
         &tokio::runtime::Handle::current(),
         TransactionFilter::new(Filter::empty()),
+        OptimisticExecutionConfig::default(),
+        StatePrefetchConfig::default(),
+        None,
     );
 
     let validator_txn_0 = ValidatorTransaction::dummy(vec![0xFF; 99]);
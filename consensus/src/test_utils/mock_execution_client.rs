@@ -16,7 +16,7 @@ use crate::{
 };
 use anyhow::{format_err, Result};
 use aptos_channels::aptos_channel;
-use aptos_consensus_types::{common::Payload, pipelined_block::PipelinedBlock};
+use aptos_consensus_types::{block::Block, common::Payload, pipelined_block::PipelinedBlock};
 use aptos_crypto::HashValue;
 use aptos_executor_types::ExecutorResult;
 use aptos_infallible::Mutex;
@@ -149,6 +149,8 @@ impl TExecutionClient for MockExecutionClient {
         Ok(())
     }
 
+    async fn speculatively_execute(&self, _block: &Block) {}
+
     fn send_commit_msg(
         &self,
         _peer_id: AccountAddress,
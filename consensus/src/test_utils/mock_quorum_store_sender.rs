@@ -4,7 +4,7 @@
 use crate::{
     network::QuorumStoreSender,
     network_interface::ConsensusMsg,
-    quorum_store::types::{Batch, BatchRequest, BatchResponse},
+    quorum_store::types::{Batch, BatchQueueLengthHintMsg, BatchRequest, BatchResponse},
 };
 use aptos_consensus_types::{
     common::Author,
@@ -82,4 +82,8 @@ impl QuorumStoreSender for MockQuorumStoreSender {
     async fn send_proof_of_store_msg_to_self(&mut self, _proof_of_stores: Vec<ProofOfStore>) {
         unimplemented!()
     }
+
+    async fn broadcast_batch_queue_length_hint_msg(&mut self, _hint: BatchQueueLengthHintMsg) {
+        unimplemented!()
+    }
 }
@@ -22,7 +22,11 @@ use aptos_types::{
 };
 use futures::SinkExt;
 use futures_channel::mpsc::UnboundedSender;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::sync::watch;
 
 pub struct EmptyStateComputer {
     executor_channel: UnboundedSender<OrderedBlocks>,
@@ -159,3 +163,89 @@ impl StateComputer for RandomComputeResultStateComputer {
 
     fn end_epoch(&self) {}
 }
+
+/// Holds back the execution results until the given number of blocks are scheduled, to
+/// simulate a validator that lags behind ordering in execution. The results are computed by
+/// a `RandomComputeResultStateComputer`.
+pub struct DelayedExecutionStateComputer {
+    inner: RandomComputeResultStateComputer,
+    num_blocks_to_delay: usize,
+    num_scheduled_blocks: AtomicUsize,
+    released_tx: watch::Sender<bool>,
+}
+
+impl DelayedExecutionStateComputer {
+    pub fn new(num_blocks_to_delay: usize) -> Self {
+        let (released_tx, _) = watch::channel(num_blocks_to_delay == 0);
+        Self {
+            inner: RandomComputeResultStateComputer::new(),
+            num_blocks_to_delay,
+            num_scheduled_blocks: AtomicUsize::new(0),
+            released_tx,
+        }
+    }
+
+    pub fn get_root_hash(&self) -> HashValue {
+        self.inner.get_root_hash()
+    }
+}
+
+#[async_trait::async_trait]
+impl StateComputer for DelayedExecutionStateComputer {
+    async fn schedule_compute(
+        &self,
+        block: &Block,
+        parent_block_id: HashValue,
+        randomness: Option<Randomness>,
+    ) -> StateComputeResultFut {
+        let mut released_rx = self.released_tx.subscribe();
+        if self.num_scheduled_blocks.fetch_add(1, Ordering::SeqCst) + 1 >= self.num_blocks_to_delay
+        {
+            self.released_tx.send_replace(true);
+        }
+        let fut = self
+            .inner
+            .schedule_compute(block, parent_block_id, randomness)
+            .await;
+        Box::pin(async move {
+            let _ = released_rx.wait_for(|released| *released).await;
+            fut.await
+        })
+    }
+
+    async fn commit(
+        &self,
+        blocks: &[Arc<PipelinedBlock>],
+        finality_proof: LedgerInfoWithSignatures,
+        callback: StateComputerCommitCallBackType,
+    ) -> ExecutorResult<()> {
+        self.inner.commit(blocks, finality_proof, callback).await
+    }
+
+    async fn sync_to(&self, target: LedgerInfoWithSignatures) -> Result<(), StateSyncError> {
+        self.inner.sync_to(target).await
+    }
+
+    fn new_epoch(
+        &self,
+        epoch_state: &EpochState,
+        payload_manager: Arc<PayloadManager>,
+        transaction_shuffler: Arc<dyn TransactionShuffler>,
+        block_executor_onchain_config: BlockExecutorConfigFromOnchain,
+        transaction_deduper: Arc<dyn TransactionDeduper>,
+        randomness_enabled: bool,
+    ) {
+        self.inner.new_epoch(
+            epoch_state,
+            payload_manager,
+            transaction_shuffler,
+            block_executor_onchain_config,
+            transaction_deduper,
+            randomness_enabled,
+        )
+    }
+
+    fn end_epoch(&self) {
+        self.inner.end_epoch()
+    }
+}
@@ -37,6 +37,8 @@ use aptos_types::{
 };
 pub use mock_payload_manager::MockPayloadManager;
 #[cfg(test)]
+pub use mock_state_computer::DelayedExecutionStateComputer;
+#[cfg(test)]
 pub use mock_state_computer::EmptyStateComputer;
 #[cfg(test)]
 pub use mock_state_computer::RandomComputeResultStateComputer;
@@ -0,0 +1,197 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::consensusdb::ConsensusDB;
+use anyhow::{ensure, Result};
+use aptos_consensus_types::{
+    block::Block,
+    common::{Author, Payload, Round},
+    quorum_cert::QuorumCert,
+    timeout_2chain::TwoChainTimeoutCertificate,
+    vote::Vote,
+};
+use aptos_crypto::HashValue;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The default number of rounds returned per page
+pub const DEFAULT_ROUNDS_PER_PAGE: usize = 10;
+/// The maximum number of rounds returned per page
+pub const MAX_ROUNDS_PER_PAGE: usize = 100;
+
+/// A query for the consensus artifacts retained (in the consensus db) for
+/// the rounds of a past epoch. Useful for post-incident analysis.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoundQuery {
+    /// The epoch to query
+    pub epoch: u64,
+    /// The first round to query (inclusive)
+    pub start_round: Round,
+    /// The last round to query (inclusive). If none, all later rounds are queried.
+    pub end_round: Option<Round>,
+    /// The maximum number of rounds to return (per page)
+    pub limit: usize,
+    /// Whether to include the block payloads (these are redacted by default)
+    pub include_payloads: bool,
+}
+
+impl RoundQuery {
+    pub fn new(epoch: u64) -> Self {
+        Self {
+            epoch,
+            start_round: 0,
+            end_round: None,
+            limit: DEFAULT_ROUNDS_PER_PAGE,
+            include_payloads: false,
+        }
+    }
+}
+
+/// A single page of query results, ordered by round
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoundQueryResponse {
+    pub rounds: Vec<RoundArtifacts>,
+    /// The start round of the next page (if there are more results)
+    pub next_start_round: Option<Round>,
+}
+
+/// All retained consensus artifacts for a single round
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoundArtifacts {
+    pub epoch: u64,
+    pub round: Round,
+    /// The proposed blocks (more than one indicates equivocation)
+    pub blocks: Vec<BlockArtifact>,
+    /// The quorum certs that certify the blocks of this round
+    pub quorum_certs: Vec<QuorumCert>,
+    /// The timeout certificate for this round (only the highest one is retained)
+    pub timeout_certificate: Option<TwoChainTimeoutCertificate>,
+    /// The votes for this round (only the last vote is retained)
+    pub votes: Vec<Vote>,
+}
+
+impl RoundArtifacts {
+    fn new(epoch: u64, round: Round) -> Self {
+        Self {
+            epoch,
+            round,
+            blocks: vec![],
+            quorum_certs: vec![],
+            timeout_certificate: None,
+            votes: vec![],
+        }
+    }
+}
+
+/// A summary of a proposed block. The payload is only included if requested.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockArtifact {
+    pub id: HashValue,
+    pub author: Option<Author>,
+    pub parent_id: HashValue,
+    pub timestamp_usecs: u64,
+    pub is_nil_block: bool,
+    pub quorum_cert: QuorumCert,
+    pub num_payload_txns: usize,
+    pub payload: Option<Payload>,
+}
+
+impl BlockArtifact {
+    fn new(block: Block, include_payloads: bool) -> Self {
+        Self {
+            id: block.id(),
+            author: block.author(),
+            parent_id: block.parent_id(),
+            timestamp_usecs: block.timestamp_usecs(),
+            is_nil_block: block.is_nil_block(),
+            quorum_cert: block.quorum_cert().clone(),
+            num_payload_txns: block.payload().map_or(0, Payload::len),
+            payload: if include_payloads {
+                block.payload().cloned()
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Returns all consensus artifacts retained for the queried rounds. Only the
+/// rounds that have at least one artifact are returned.
+pub fn query_rounds(consensus_db: &ConsensusDB, query: &RoundQuery) -> Result<RoundQueryResponse> {
+    ensure!(
+        query.limit > 0 && query.limit <= MAX_ROUNDS_PER_PAGE,
+        "The limit must be between 1 and {}, but got: {}",
+        MAX_ROUNDS_PER_PAGE,
+        query.limit
+    );
+    if let Some(end_round) = query.end_round {
+        ensure!(
+            query.start_round <= end_round,
+            "The start round ({}) must not be greater than the end round ({})",
+            query.start_round,
+            end_round
+        );
+    }
+
+    let (last_vote, highest_timeout_certificate, blocks, quorum_certs) = consensus_db.get_data()?;
+    let is_queried = |epoch: u64, round: Round| {
+        epoch == query.epoch
+            && round >= query.start_round
+            && query.end_round.map_or(true, |end_round| round <= end_round)
+    };
+
+    // Group the artifacts by round
+    let mut rounds: BTreeMap<Round, RoundArtifacts> = BTreeMap::new();
+    for block in blocks {
+        if is_queried(block.epoch(), block.round()) {
+            artifacts_for_round(&mut rounds, query.epoch, block.round())
+                .blocks
+                .push(BlockArtifact::new(block, query.include_payloads));
+        }
+    }
+    for quorum_cert in quorum_certs {
+        let certified_block = quorum_cert.certified_block();
+        if is_queried(certified_block.epoch(), certified_block.round()) {
+            artifacts_for_round(&mut rounds, query.epoch, certified_block.round())
+                .quorum_certs
+                .push(quorum_cert);
+        }
+    }
+    if let Some(timeout_certificate) = highest_timeout_certificate {
+        let timeout_certificate: TwoChainTimeoutCertificate =
+            bcs::from_bytes(&timeout_certificate)?;
+        if is_queried(timeout_certificate.epoch(), timeout_certificate.round()) {
+            artifacts_for_round(&mut rounds, query.epoch, timeout_certificate.round())
+                .timeout_certificate = Some(timeout_certificate);
+        }
+    }
+    if let Some(last_vote) = last_vote {
+        let last_vote: Vote = bcs::from_bytes(&last_vote)?;
+        let proposed_block = last_vote.vote_data().proposed();
+        if is_queried(proposed_block.epoch(), proposed_block.round()) {
+            artifacts_for_round(&mut rounds, query.epoch, proposed_block.round())
+                .votes
+                .push(last_vote);
+        }
+    }
+
+    // Paginate the results
+    let mut rounds = rounds.into_values();
+    let page = rounds.by_ref().take(query.limit).collect();
+    let next_start_round = rounds.next().map(|artifacts| artifacts.round);
+    Ok(RoundQueryResponse {
+        rounds: page,
+        next_start_round,
+    })
+}
+
+/// Returns the artifacts for the given round (creating them if they don't exist)
+fn artifacts_for_round(
+    rounds: &mut BTreeMap<Round, RoundArtifacts>,
+    epoch: u64,
+    round: Round,
+) -> &mut RoundArtifacts {
+    rounds
+        .entry(round)
+        .or_insert_with(|| RoundArtifacts::new(epoch, round))
+}
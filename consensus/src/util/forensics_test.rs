@@ -0,0 +1,148 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensusdb::ConsensusDB,
+    util::forensics::{query_rounds, RoundQuery, MAX_ROUNDS_PER_PAGE},
+};
+use aptos_consensus_types::{
+    block::{
+        block_test_utils::{certificate_for_genesis, placeholder_certificate_for_block},
+        Block,
+    },
+    common::Payload,
+    timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
+    vote::Vote,
+    vote_data::VoteData,
+};
+use aptos_crypto::HashValue;
+use aptos_temppath::TempPath;
+use aptos_types::{
+    block_info::BlockInfo, ledger_info::LedgerInfo, validator_signer::ValidatorSigner,
+};
+
+#[test]
+fn test_query_rounds() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+    let signer = ValidatorSigner::random(None);
+
+    // Save proposals for rounds 1 to 4, and quorum certs for rounds 1 and 2
+    let genesis_qc = certificate_for_genesis();
+    let blocks: Vec<_> = (1..=4)
+        .map(|round| {
+            Block::new_proposal(
+                Payload::empty(false, true),
+                round,
+                round * 1000,
+                genesis_qc.clone(),
+                &signer,
+                vec![],
+            )
+            .unwrap()
+        })
+        .collect();
+    let quorum_certs = blocks[..2]
+        .iter()
+        .map(|block| {
+            placeholder_certificate_for_block(
+                &[signer.clone()],
+                block.id(),
+                block.round(),
+                genesis_qc.certified_block().id(),
+                0,
+            )
+        })
+        .collect();
+    db.save_blocks_and_quorum_certificates(blocks.clone(), quorum_certs)
+        .unwrap();
+    let epoch = blocks[0].epoch();
+
+    // Save a vote for round 4, and a timeout certificate for round 5
+    let vote = Vote::new(
+        VoteData::new(BlockInfo::random(4), BlockInfo::random(3)),
+        signer.author(),
+        LedgerInfo::new(BlockInfo::empty(), HashValue::zero()),
+        &signer,
+    )
+    .unwrap();
+    db.save_vote(bcs::to_bytes(&vote).unwrap()).unwrap();
+    let timeout_certificate =
+        TwoChainTimeoutCertificate::new(TwoChainTimeout::new(epoch, 5, genesis_qc));
+    db.save_highest_2chain_timeout_certificate(bcs::to_bytes(&timeout_certificate).unwrap())
+        .unwrap();
+
+    // Query the first page, and verify the payloads are redacted
+    let query = RoundQuery {
+        limit: 2,
+        ..RoundQuery::new(epoch)
+    };
+    let response = query_rounds(&db, &query).unwrap();
+    let rounds: Vec<_> = response.rounds.iter().map(|round| round.round).collect();
+    assert_eq!(rounds, vec![1, 2]);
+    assert_eq!(response.next_start_round, Some(3));
+    for artifacts in &response.rounds {
+        assert_eq!(artifacts.blocks.len(), 1);
+        assert_eq!(artifacts.quorum_certs.len(), 1);
+        assert!(artifacts.blocks[0].payload.is_none());
+    }
+
+    // Query the next page (with payloads)
+    let query = RoundQuery {
+        start_round: 3,
+        include_payloads: true,
+        ..query
+    };
+    let response = query_rounds(&db, &query).unwrap();
+    let rounds: Vec<_> = response.rounds.iter().map(|round| round.round).collect();
+    assert_eq!(rounds, vec![3, 4]);
+    assert_eq!(response.next_start_round, Some(5));
+    assert_eq!(response.rounds[0].blocks[0].id, blocks[2].id());
+    assert!(response.rounds[0].blocks[0].payload.is_some());
+    assert!(response.rounds[0].votes.is_empty());
+    assert_eq!(response.rounds[1].votes, vec![vote]);
+
+    // Query the last page, and verify it only contains the timeout certificate
+    let query = RoundQuery {
+        start_round: 5,
+        ..query
+    };
+    let response = query_rounds(&db, &query).unwrap();
+    assert_eq!(response.rounds.len(), 1);
+    assert!(response.rounds[0].blocks.is_empty());
+    assert_eq!(
+        response.rounds[0].timeout_certificate,
+        Some(timeout_certificate)
+    );
+    assert_eq!(response.next_start_round, None);
+
+    // Verify a bounded query, and a query for a different epoch
+    let query = RoundQuery {
+        start_round: 2,
+        end_round: Some(3),
+        limit: MAX_ROUNDS_PER_PAGE,
+        ..RoundQuery::new(epoch)
+    };
+    assert_eq!(query_rounds(&db, &query).unwrap().rounds.len(), 2);
+    let response = query_rounds(&db, &RoundQuery::new(epoch + 1)).unwrap();
+    assert!(response.rounds.is_empty());
+
+    // Verify that invalid queries are rejected
+    for query in [
+        RoundQuery {
+            limit: 0,
+            ..RoundQuery::new(epoch)
+        },
+        RoundQuery {
+            limit: MAX_ROUNDS_PER_PAGE + 1,
+            ..RoundQuery::new(epoch)
+        },
+        RoundQuery {
+            start_round: 3,
+            end_round: Some(2),
+            ..RoundQuery::new(epoch)
+        },
+    ] {
+        assert!(query_rounds(&db, &query).is_err());
+    }
+}
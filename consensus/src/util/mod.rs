@@ -8,6 +8,9 @@ use aptos_types::{
 };
 
 pub mod db_tool;
+pub mod forensics;
+#[cfg(test)]
+mod forensics_test;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod mock_time_service;
 pub mod time_service;
@@ -2,17 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::server::utils::{reply_with, reply_with_status, spawn_blocking};
-use anyhow::{bail, Error};
+use anyhow::{anyhow, bail, Error};
 use aptos_consensus::{
     persistent_liveness_storage::PersistentLivenessStorage,
-    quorum_store::quorum_store_db::QuorumStoreStorage, util::db_tool::extract_txns_from_block,
+    quorum_store::quorum_store_db::QuorumStoreStorage,
+    util::{
+        db_tool::extract_txns_from_block,
+        forensics::{self, RoundQuery},
+    },
+    LeaderReputationOverrides, LeaderReputationReporter,
 };
 use aptos_crypto::HashValue;
 use aptos_logger::info;
 use aptos_types::transaction::Transaction;
-use http::header::{HeaderValue, CONTENT_LENGTH};
+use http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
 use hyper::{Body, Request, Response, StatusCode};
-use std::{collections::HashMap, sync::Arc};
+use serde_json::json;
+use std::{borrow::Cow, collections::HashMap, str::FromStr, sync::Arc};
 
 pub async fn handle_dump_consensus_db_request(
     _req: Request<Body>,
@@ -127,6 +133,162 @@ pub async fn handle_dump_block_request(
     }
 }
 
+/// Handles requests for the consensus artifacts retained for the rounds of a past epoch
+/// (returned as JSON). The `epoch` query parameter is required. The rounds can be bounded
+/// with the `start_round` and `end_round` query parameters, and paginated with the `limit`
+/// query parameter. Block payloads are only included if `include_payloads` is true.
+pub async fn handle_query_rounds_request(
+    req: Request<Body>,
+    consensus_db: Arc<dyn PersistentLivenessStorage>,
+) -> hyper::Result<Response<Body>> {
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+    let round_query = match parse_round_query(&query_pairs) {
+        Ok(round_query) => round_query,
+        Err(err) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, err.to_string())),
+    };
+
+    info!("Querying consensus db rounds: {:?}.", round_query);
+
+    let result = spawn_blocking(move || {
+        let response = forensics::query_rounds(&consensus_db.consensus_db(), &round_query)?;
+        serde_json::to_string_pretty(&response).map_err(Error::msg)
+    })
+    .await;
+
+    match result {
+        Ok(body) => {
+            info!("Finished querying consensus db rounds.");
+            let headers: Vec<(_, HeaderValue)> = vec![
+                (CONTENT_LENGTH, HeaderValue::from(body.len())),
+                (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+            ];
+            Ok(reply_with(headers, body))
+        },
+        Err(e) => {
+            info!("Failed to query consensus db rounds: {e:?}");
+            Ok(reply_with_status(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                e.to_string(),
+            ))
+        },
+    }
+}
+
+/// Handles requests for the leader reputation weights of the current epoch (returned as JSON).
+/// A "what if" simulation is also returned if any of the window or weighting configuration
+/// values are overridden with query parameters (e.g., `proposer_window_num_validators_multiplier`,
+/// `voter_window_num_validators_multiplier`, `active_weight`, `inactive_weight`, `failed_weight`,
+/// `failure_threshold_percent` and `weight_by_voting_power`).
+pub async fn handle_leader_reputation_request(
+    req: Request<Body>,
+    leader_reputation_reporter: LeaderReputationReporter,
+) -> hyper::Result<Response<Body>> {
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+    let overrides = match parse_leader_reputation_overrides(&query_pairs) {
+        Ok(overrides) => overrides,
+        Err(err) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, err.to_string())),
+    };
+
+    info!("Generating leader reputation report: {:?}.", overrides);
+
+    let result = spawn_blocking(move || {
+        let current =
+            leader_reputation_reporter.generate_report(&LeaderReputationOverrides::default())?;
+        let simulated = if overrides.is_empty() {
+            None
+        } else {
+            Some(leader_reputation_reporter.generate_report(&overrides)?)
+        };
+        serde_json::to_string_pretty(&json!({
+            "current": current,
+            "simulated": simulated,
+        }))
+        .map_err(Error::msg)
+    })
+    .await;
+
+    match result {
+        Ok(body) => {
+            info!("Finished generating leader reputation report.");
+            let headers: Vec<(_, HeaderValue)> = vec![
+                (CONTENT_LENGTH, HeaderValue::from(body.len())),
+                (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+            ];
+            Ok(reply_with(headers, body))
+        },
+        Err(e) => {
+            info!("Failed to generate leader reputation report: {e:?}");
+            Ok(reply_with_status(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                e.to_string(),
+            ))
+        },
+    }
+}
+
+/// Parses the leader reputation overrides from the given query parameters
+fn parse_leader_reputation_overrides(
+    query_pairs: &HashMap<Cow<str>, Cow<str>>,
+) -> anyhow::Result<LeaderReputationOverrides> {
+    Ok(LeaderReputationOverrides {
+        proposer_window_num_validators_multiplier: parse_query_param(
+            query_pairs,
+            "proposer_window_num_validators_multiplier",
+        )?,
+        voter_window_num_validators_multiplier: parse_query_param(
+            query_pairs,
+            "voter_window_num_validators_multiplier",
+        )?,
+        active_weight: parse_query_param(query_pairs, "active_weight")?,
+        inactive_weight: parse_query_param(query_pairs, "inactive_weight")?,
+        failed_weight: parse_query_param(query_pairs, "failed_weight")?,
+        failure_threshold_percent: parse_query_param(query_pairs, "failure_threshold_percent")?,
+        weight_by_voting_power: parse_query_param(query_pairs, "weight_by_voting_power")?,
+    })
+}
+
+/// Parses the round query from the given query parameters
+fn parse_round_query(query_pairs: &HashMap<Cow<str>, Cow<str>>) -> anyhow::Result<RoundQuery> {
+    let epoch = match parse_query_param(query_pairs, "epoch")? {
+        Some(epoch) => epoch,
+        None => bail!("Missing query parameter epoch"),
+    };
+
+    let mut round_query = RoundQuery::new(epoch);
+    if let Some(start_round) = parse_query_param(query_pairs, "start_round")? {
+        round_query.start_round = start_round;
+    }
+    round_query.end_round = parse_query_param(query_pairs, "end_round")?;
+    if let Some(limit) = parse_query_param(query_pairs, "limit")? {
+        round_query.limit = limit;
+    }
+    if let Some(include_payloads) = parse_query_param(query_pairs, "include_payloads")? {
+        round_query.include_payloads = include_payloads;
+    }
+
+    Ok(round_query)
+}
+
+/// Parses the (optional) query parameter with the given name
+fn parse_query_param<T: FromStr>(
+    query_pairs: &HashMap<Cow<str>, Cow<str>>,
+    name: &str,
+) -> anyhow::Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    query_pairs
+        .get(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|err| anyhow!("Invalid query parameter {}: {}", name, err))
+        })
+        .transpose()
+}
+
 fn dump_consensus_db(consensus_db: &dyn PersistentLivenessStorage) -> anyhow::Result<String> {
     let mut body = String::new();
 
@@ -0,0 +1,149 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::server::utils::{reply_with, reply_with_status, spawn_blocking};
+use anyhow::{anyhow, Error};
+use aptos_config::config::SubmissionQuota;
+use aptos_logger::info;
+use aptos_mempool::{MempoolCapacityReporter, SubmissionQuotas, DEFAULT_MAX_TOP_ACCOUNTS};
+use http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
+
+/// Handles requests to view (GET), set (POST) or remove (DELETE) mempool submission quotas.
+/// Quotas are identified by the `api_key` query parameter. If no API key is given, the
+/// request applies to the default quota.
+pub async fn handle_submission_quotas_request(
+    req: Request<Body>,
+    submission_quotas: Arc<SubmissionQuotas>,
+) -> hyper::Result<Response<Body>> {
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+    let api_key = query_pairs
+        .get("api_key")
+        .map(|api_key| api_key.to_string());
+
+    match *req.method() {
+        Method::GET => Ok(reply_with_status(
+            StatusCode::OK,
+            dump_submission_quotas(&submission_quotas),
+        )),
+        Method::POST => {
+            let quota = match parse_submission_quota(&query_pairs) {
+                Ok(quota) => quota,
+                Err(err) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, err.to_string())),
+            };
+
+            info!(
+                "Setting mempool submission quota (default quota: {}): {:?}",
+                api_key.is_none(),
+                quota
+            );
+            submission_quotas.set_quota(api_key, quota);
+            Ok(reply_with_status(
+                StatusCode::OK,
+                "Submission quota updated.",
+            ))
+        },
+        Method::DELETE => {
+            info!(
+                "Removing mempool submission quota (default quota: {})",
+                api_key.is_none()
+            );
+            submission_quotas.remove_quota(api_key.as_deref());
+            Ok(reply_with_status(
+                StatusCode::OK,
+                "Submission quota removed.",
+            ))
+        },
+        _ => Ok(reply_with_status(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Unsupported method.",
+        )),
+    }
+}
+
+/// Handles requests for a mempool capacity report (returned as JSON). The number of
+/// accounts listed in the report can be set with the `top_accounts` query parameter.
+pub async fn handle_capacity_report_request(
+    req: Request<Body>,
+    capacity_reporter: MempoolCapacityReporter,
+) -> hyper::Result<Response<Body>> {
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+    let max_top_accounts = match query_pairs.get("top_accounts") {
+        Some(top_accounts) => match top_accounts.parse::<usize>() {
+            Ok(top_accounts) => top_accounts,
+            Err(err) => {
+                return Ok(reply_with_status(
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid query parameter top_accounts: {}", err),
+                ))
+            },
+        },
+        None => DEFAULT_MAX_TOP_ACCOUNTS,
+    };
+
+    info!(
+        "Generating mempool capacity report (top accounts: {}).",
+        max_top_accounts
+    );
+    let result = spawn_blocking(move || {
+        let report = capacity_reporter.generate_report(max_top_accounts);
+        serde_json::to_string_pretty(&report).map_err(Error::msg)
+    })
+    .await;
+
+    match result {
+        Ok(body) => {
+            let headers: Vec<(_, HeaderValue)> = vec![
+                (CONTENT_LENGTH, HeaderValue::from(body.len())),
+                (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+            ];
+            Ok(reply_with(headers, body))
+        },
+        Err(err) => {
+            info!("Failed to generate the mempool capacity report: {err:?}");
+            Ok(reply_with_status(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                err.to_string(),
+            ))
+        },
+    }
+}
+
+/// Returns a human-readable dump of the submission quotas and their usage
+fn dump_submission_quotas(submission_quotas: &SubmissionQuotas) -> String {
+    let mut body = format!(
+        "Submission quotas enabled: {}\n",
+        submission_quotas.is_enabled()
+    );
+    for summary in submission_quotas.get_summaries() {
+        body.push_str(&format!("{:?}\n", summary));
+    }
+    body
+}
+
+/// Parses a submission quota from the given query parameters
+fn parse_submission_quota(
+    query_pairs: &HashMap<Cow<'_, str>, Cow<'_, str>>,
+) -> Result<SubmissionQuota, Error> {
+    let parse_u64 = |name: &str| -> Result<u64, Error> {
+        query_pairs
+            .get(name)
+            .ok_or_else(|| anyhow!("Missing query parameter: {}", name))?
+            .parse()
+            .map_err(|err| anyhow!("Invalid query parameter {}: {}", name, err))
+    };
+
+    let max_submissions_per_window = parse_u64("max_submissions_per_window")?;
+    let window_secs = parse_u64("window_secs")?;
+    if window_secs == 0 {
+        return Err(anyhow!("The quota window must be non-zero!"));
+    }
+
+    Ok(SubmissionQuota::new(
+        max_submissions_per_window,
+        window_secs,
+    ))
+}
@@ -5,9 +5,11 @@ use crate::server::utils::reply_with_status;
 use aptos_config::config::{AuthenticationConfig, NodeConfig};
 use aptos_consensus::{
     persistent_liveness_storage::StorageWriteProxy, quorum_store::quorum_store_db::QuorumStoreDB,
+    LeaderReputationReporter,
 };
 use aptos_infallible::RwLock;
 use aptos_logger::info;
+use aptos_mempool::{MempoolCapacityReporter, SubmissionQuotas};
 use aptos_storage_interface::DbReaderWriter;
 use hyper::{
     service::{make_service_fn, service_fn},
@@ -22,6 +24,7 @@ use std::{
 use tokio::runtime::Runtime;
 
 mod consensus;
+mod mempool;
 #[cfg(target_os = "linux")]
 pub mod profiling;
 #[cfg(target_os = "linux")]
@@ -35,6 +38,9 @@ pub struct Context {
     aptos_db: RwLock<Option<Arc<DbReaderWriter>>>,
     consensus_db: RwLock<Option<Arc<StorageWriteProxy>>>,
     quorum_store_db: RwLock<Option<Arc<QuorumStoreDB>>>,
+    leader_reputation_reporter: RwLock<Option<LeaderReputationReporter>>,
+    mempool_submission_quotas: RwLock<Option<Arc<SubmissionQuotas>>>,
+    mempool_capacity_reporter: RwLock<Option<MempoolCapacityReporter>>,
 }
 
 impl Context {
@@ -50,6 +56,18 @@ impl Context {
         *self.consensus_db.write() = Some(consensus_db);
         *self.quorum_store_db.write() = Some(quorum_store_db);
     }
+
+    fn set_leader_reputation_reporter(&self, leader_reputation_reporter: LeaderReputationReporter) {
+        *self.leader_reputation_reporter.write() = Some(leader_reputation_reporter);
+    }
+
+    fn set_mempool_submission_quotas(&self, submission_quotas: Arc<SubmissionQuotas>) {
+        *self.mempool_submission_quotas.write() = Some(submission_quotas);
+    }
+
+    fn set_mempool_capacity_reporter(&self, capacity_reporter: MempoolCapacityReporter) {
+        *self.mempool_capacity_reporter.write() = Some(capacity_reporter);
+    }
 }
 
 pub struct AdminService {
@@ -108,6 +126,24 @@ impl AdminService {
             .set_consensus_dbs(consensus_db, quorum_store_db)
     }
 
+    pub fn set_leader_reputation_reporter(
+        &self,
+        leader_reputation_reporter: LeaderReputationReporter,
+    ) {
+        self.context
+            .set_leader_reputation_reporter(leader_reputation_reporter)
+    }
+
+    pub fn set_mempool_submission_quotas(&self, submission_quotas: Arc<SubmissionQuotas>) {
+        self.context
+            .set_mempool_submission_quotas(submission_quotas)
+    }
+
+    pub fn set_mempool_capacity_reporter(&self, capacity_reporter: MempoolCapacityReporter) {
+        self.context
+            .set_mempool_capacity_reporter(capacity_reporter)
+    }
+
     fn start(&self, address: SocketAddr, enabled: bool) {
         let context = self.context.clone();
         self.runtime.spawn(async move {
@@ -183,6 +219,17 @@ impl AdminService {
                     ))
                 }
             },
+            (hyper::Method::GET, "/debug/consensus/rounds") => {
+                let consensus_db = context.consensus_db.read().clone();
+                if let Some(consensus_db) = consensus_db {
+                    consensus::handle_query_rounds_request(req, consensus_db).await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "Consensus db is not available.",
+                    ))
+                }
+            },
             (hyper::Method::GET, "/debug/consensus/quorumstoredb") => {
                 let quorum_store_db = context.quorum_store_db.read().clone();
                 if let Some(quorum_store_db) = quorum_store_db {
@@ -211,6 +258,43 @@ impl AdminService {
                     ))
                 }
             },
+            (hyper::Method::GET, "/debug/consensus/leader_reputation") => {
+                let leader_reputation_reporter = context.leader_reputation_reporter.read().clone();
+                if let Some(leader_reputation_reporter) = leader_reputation_reporter {
+                    consensus::handle_leader_reputation_request(req, leader_reputation_reporter)
+                        .await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "Leader reputation reports are not available.",
+                    ))
+                }
+            },
+            (
+                hyper::Method::GET | hyper::Method::POST | hyper::Method::DELETE,
+                "/mempool/submission_quotas",
+            ) => {
+                let submission_quotas = context.mempool_submission_quotas.read().clone();
+                if let Some(submission_quotas) = submission_quotas {
+                    mempool::handle_submission_quotas_request(req, submission_quotas).await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "Mempool submission quotas are not available.",
+                    ))
+                }
+            },
+            (hyper::Method::GET, "/mempool/capacity_report") => {
+                let capacity_reporter = context.mempool_capacity_reporter.read().clone();
+                if let Some(capacity_reporter) = capacity_reporter {
+                    mempool::handle_capacity_report_request(req, capacity_reporter).await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "Mempool capacity reports are not available.",
+                    ))
+                }
+            },
             _ => Ok(reply_with_status(StatusCode::NOT_FOUND, "Not found.")),
         }
     }
@@ -0,0 +1,27 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// The compression codecs offered by the crate. The codecs trade off the
+/// compression ratio against the cost of compression and decompression:
+/// LZ4 is fast and cheap to decompress, while zstd achieves better
+/// compression ratios at a higher CPU cost (for both sides).
+///
+/// Note: new codecs must only be appended to the end of the enum, as
+/// the codecs are serialized and sent across the network.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum CompressionCodec {
+    Lz4,
+    Zstd,
+}
+
+impl CompressionCodec {
+    /// Returns a summary label for the codec
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Self::Lz4 => "lz4",
+            Self::Zstd => "zstd",
+        }
+    }
+}
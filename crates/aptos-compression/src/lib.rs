@@ -3,11 +3,12 @@
 
 use crate::{
     client::CompressionClient,
+    codec::CompressionCodec,
     Error::{CompressionError, DecompressionError},
 };
 use aptos_logger::prelude::*;
 use lz4::block::CompressionMode;
-use std::time::Instant;
+use std::{io::Read, time::Instant};
 use thiserror::Error;
 
 /// This crate provides a simple library interface for data compression.
@@ -15,11 +16,14 @@ use thiserror::Error;
 /// sent across the network (e.g., by state sync and consensus).
 /// Internally, it uses LZ4 in fast mode to compress the data.
 /// See <https://github.com/10xGenomics/lz4-rs> for more information.
+/// Clients that can afford more expensive (de)compression can
+/// also use zstd (see `CompressionCodec`) for better ratios.
 ///
 /// Note: the crate also exposes some basic compression metrics
 /// that can be used to track the cumulative compression ratio
 /// and compression/decompression durations during the runtime.
 pub mod client;
+pub mod codec;
 mod metrics;
 #[cfg(test)]
 mod tests;
@@ -28,6 +32,10 @@ mod tests;
 /// This was determined anecdotally.
 const ACCELERATION_PARAMETER: i32 = 1;
 
+/// The compression level to use for zstd compression (i.e.,
+/// the zstd default, which balances speed and compression ratio).
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
 /// A useful wrapper for representing compressed data
 pub type CompressedData = Vec<u8>;
 
@@ -120,6 +128,123 @@ pub fn decompress(
     Ok(raw_data)
 }
 
+/// Compresses the raw data stream using the given codec
+pub fn compress_with_codec(
+    raw_data: Vec<u8>,
+    codec: CompressionCodec,
+    client: CompressionClient,
+    max_bytes: usize,
+) -> Result<CompressedData, Error> {
+    match codec {
+        CompressionCodec::Lz4 => compress(raw_data, client, max_bytes),
+        CompressionCodec::Zstd => compress_zstd(raw_data, client, max_bytes),
+    }
+}
+
+/// Decompresses the compressed data stream using the given codec
+pub fn decompress_with_codec(
+    compressed_data: &CompressedData,
+    codec: CompressionCodec,
+    client: CompressionClient,
+    max_size: usize,
+) -> Result<Vec<u8>, Error> {
+    match codec {
+        CompressionCodec::Lz4 => decompress(compressed_data, client, max_size),
+        CompressionCodec::Zstd => decompress_zstd(compressed_data, client, max_size),
+    }
+}
+
+/// Compresses the raw data stream using zstd
+fn compress_zstd(
+    raw_data: Vec<u8>,
+    client: CompressionClient,
+    max_bytes: usize,
+) -> Result<CompressedData, Error> {
+    // Start the compression timer
+    let start_time = Instant::now();
+
+    // Ensure that the raw data size is not greater than the max bytes limit
+    if raw_data.len() > max_bytes {
+        let error_string = format!(
+            "Raw data size greater than max bytes limit: {}, max: {}",
+            raw_data.len(),
+            max_bytes
+        );
+        return create_compression_error(&client, error_string);
+    }
+
+    // Compress the data
+    let compressed_data = match zstd::bulk::compress(&raw_data, ZSTD_COMPRESSION_LEVEL) {
+        Ok(compressed_data) => compressed_data,
+        Err(error) => {
+            let error_string = format!("Failed to compress the data: {}", error);
+            return create_compression_error(&client, error_string);
+        },
+    };
+
+    // Ensure that the compressed data size is not greater than the max byte limit
+    if compressed_data.len() > max_bytes {
+        let error_string = format!(
+            "Compressed size greater than max bytes limit: {}, max: {}",
+            compressed_data.len(),
+            max_bytes
+        );
+        return create_compression_error(&client, error_string);
+    }
+
+    // Stop the timer and update the metrics
+    metrics::observe_compression_operation_time(&client, start_time);
+    metrics::update_compression_metrics(&client, &raw_data, &compressed_data);
+
+    Ok(compressed_data)
+}
+
+/// Decompresses the zstd compressed data stream. The data is decompressed
+/// incrementally, so no more than the max size is ever allocated (even if
+/// the compressed data is malicious).
+fn decompress_zstd(
+    compressed_data: &CompressedData,
+    client: CompressionClient,
+    max_size: usize,
+) -> Result<Vec<u8>, Error> {
+    // Start the decompression timer
+    let start_time = Instant::now();
+
+    // Create the decoder
+    let decoder = match zstd::stream::read::Decoder::new(compressed_data.as_slice()) {
+        Ok(decoder) => decoder,
+        Err(error) => {
+            let error_string = format!("Failed to create the decoder: {}", error);
+            return create_decompression_error(&client, error_string);
+        },
+    };
+
+    // Decompress the data (reading at most one byte beyond the max size)
+    let mut raw_data = vec![];
+    if let Err(error) = decoder
+        .take((max_size as u64).saturating_add(1))
+        .read_to_end(&mut raw_data)
+    {
+        let error_string = format!("Failed to decompress the data: {}", error);
+        return create_decompression_error(&client, error_string);
+    }
+
+    // Ensure that the decompressed data size is not greater than the max size limit
+    if raw_data.len() > max_size {
+        let error_string = format!(
+            "Decompressed size greater than max size limit! Max: {}",
+            max_size
+        );
+        return create_decompression_error(&client, error_string);
+    }
+
+    // Stop the timer and update the metrics
+    metrics::observe_decompression_operation_time(&client, start_time);
+    metrics::update_decompression_metrics(&client, compressed_data, &raw_data);
+
+    Ok(raw_data)
+}
+
 /// A simple utility function that wraps the given error string in a compression error
 fn create_compression_error(
     client: &CompressionClient,
@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::CompressionClient;
+use crate::{codec::CompressionCodec, CompressionClient};
 use aptos_crypto::{ed25519::Ed25519PrivateKey, hash::HashValue, PrivateKey, SigningKey, Uniform};
 use aptos_types::{
     account_address::AccountAddress,
@@ -74,6 +74,71 @@ fn test_compression_limits() {
     assert!(maybe_decompressed_bytes.is_err());
 }
 
+#[test]
+fn test_codec_compression() {
+    for codec in [CompressionCodec::Lz4, CompressionCodec::Zstd] {
+        // Test compress random bytes
+        let raw_bytes: Vec<_> = (0..MIB).map(|_| rand::thread_rng().gen::<u8>()).collect();
+        test_compress_and_decompress_with_codec(raw_bytes, codec);
+
+        // Test transaction outputs with proof
+        let outputs_with_proof = create_output_list_with_proof(13434, 17000, 19000);
+        test_compress_and_decompress_with_codec(outputs_with_proof, codec);
+
+        // Test transactions with proof
+        let transactions_with_proof = create_transaction_list_with_proof(1000, 1999, 1999, true);
+        test_compress_and_decompress_with_codec(transactions_with_proof, codec);
+    }
+}
+
+#[test]
+fn test_codec_compression_limits() {
+    for codec in [CompressionCodec::Lz4, CompressionCodec::Zstd] {
+        // Create test data
+        let too_small_bytes = 1;
+        let transactions_with_proof = create_transaction_list_with_proof(1000, 1999, 1999, true);
+        let bcs_encoded_bytes = bcs::to_bytes(&transactions_with_proof).unwrap();
+
+        // Test compression limit
+        let maybe_compressed_bytes = crate::compress_with_codec(
+            bcs_encoded_bytes.clone(),
+            codec,
+            CompressionClient::StateSync,
+            too_small_bytes,
+        );
+        assert!(maybe_compressed_bytes.is_err());
+
+        // Test decompression limit
+        let compressed_bytes = crate::compress_with_codec(
+            bcs_encoded_bytes.clone(),
+            codec,
+            CompressionClient::StateSync,
+            MAX_COMPRESSION_SIZE,
+        )
+        .unwrap();
+        let maybe_decompressed_bytes = crate::decompress_with_codec(
+            &compressed_bytes,
+            codec,
+            CompressionClient::StateSync,
+            bcs_encoded_bytes.len() - 1,
+        );
+        assert!(maybe_decompressed_bytes.is_err());
+
+        // Verify that data compressed with one codec can't be decompressed with the other
+        let other_codec = match codec {
+            CompressionCodec::Lz4 => CompressionCodec::Zstd,
+            CompressionCodec::Zstd => CompressionCodec::Lz4,
+        };
+        let maybe_decompressed_bytes = crate::decompress_with_codec(
+            &compressed_bytes,
+            other_codec,
+            CompressionClient::StateSync,
+            MAX_COMPRESSION_SIZE,
+        );
+        assert!(maybe_decompressed_bytes.is_err());
+    }
+}
+
 /// Ensures that the given object can be compressed and decompressed successfully
 /// when BCS encoded.
 fn test_compress_and_decompress<T: Debug + DeserializeOwned + PartialEq + Serialize>(object: T) {
@@ -95,6 +160,32 @@ fn test_compress_and_decompress<T: Debug + DeserializeOwned + PartialEq + Serial
     assert_eq!(object, decoded_object);
 }
 
+/// Ensures that the given object can be compressed and decompressed successfully
+/// with the given codec when BCS encoded.
+fn test_compress_and_decompress_with_codec<T: Debug + DeserializeOwned + PartialEq + Serialize>(
+    object: T,
+    codec: CompressionCodec,
+) {
+    let bcs_encoded_bytes = bcs::to_bytes(&object).unwrap();
+    let compressed_bytes = crate::compress_with_codec(
+        bcs_encoded_bytes,
+        codec,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    let decompressed_bytes = crate::decompress_with_codec(
+        &compressed_bytes,
+        codec,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    let decoded_object = bcs::from_bytes::<T>(&decompressed_bytes).unwrap();
+
+    assert_eq!(object, decoded_object);
+}
+
 /// Creates a test epoch change proof
 fn create_epoch_ending_ledger_infos(
     start_epoch: u64,
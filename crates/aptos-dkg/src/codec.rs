@@ -0,0 +1,199 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Explicit, versioned byte encodings for the WVUF types sent over the wire.
+//!
+//! Unlike the serde encodings derived from the `blstrs` types, these encodings are fully specified
+//! here, so they cannot silently change when dependencies are bumped. Every encoding starts with a
+//! version byte, and decoding checks the lengths as well as the prime-order subgroup membership of
+//! all group elements.
+
+use crate::{
+    utils::serialization::{g1_proj_from_bytes, g2_proj_from_bytes},
+    weighted_vuf::pinkas::RandomizedPKs,
+    G1_PROJ_NUM_BYTES, G2_PROJ_NUM_BYTES,
+};
+use aptos_crypto::CryptoMaterialError;
+use blstrs::{G1Projective, G2Projective};
+
+/// The first (and currently only) version of the encodings.
+pub const CODEC_VERSION_V1: u8 = 1;
+
+/// The size in bytes of the version prefix.
+const VERSION_NUM_BYTES: usize = 1;
+
+/// The size in bytes of the length prefix of a list.
+const LENGTH_NUM_BYTES: usize = 4;
+
+/// A type with an explicit, versioned byte encoding.
+pub trait VersionedCodec: Sized {
+    /// Encodes the value using the latest version of the encoding.
+    fn to_versioned_bytes(&self) -> Vec<u8>;
+
+    /// Decodes a value, failing on unknown versions, unexpected lengths and invalid group elements.
+    fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, CryptoMaterialError>;
+}
+
+/// Strips the version prefix, failing if the version is not supported.
+fn strip_version(bytes: &[u8]) -> Result<&[u8], CryptoMaterialError> {
+    match bytes.split_first() {
+        Some((&CODEC_VERSION_V1, rest)) => Ok(rest),
+        Some(_) => Err(CryptoMaterialError::DeserializationError),
+        None => Err(CryptoMaterialError::WrongLengthError),
+    }
+}
+
+/// Version 1 layout: `version || pi || len(rks) as u32 (LE) || rks[0] || ... || rks[len - 1]`,
+/// where all G1 elements are compressed.
+impl VersionedCodec for RandomizedPKs {
+    fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            VERSION_NUM_BYTES
+                + G1_PROJ_NUM_BYTES
+                + LENGTH_NUM_BYTES
+                + self.rks.len() * G1_PROJ_NUM_BYTES,
+        );
+        bytes.push(CODEC_VERSION_V1);
+        bytes.extend_from_slice(&self.pi.to_compressed());
+        bytes.extend_from_slice(&(self.rks.len() as u32).to_le_bytes());
+        for rk in &self.rks {
+            bytes.extend_from_slice(&rk.to_compressed());
+        }
+        bytes
+    }
+
+    fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, CryptoMaterialError> {
+        let bytes = strip_version(bytes)?;
+        if bytes.len() < G1_PROJ_NUM_BYTES + LENGTH_NUM_BYTES {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        let (pi_bytes, bytes) = bytes.split_at(G1_PROJ_NUM_BYTES);
+        let (len_bytes, rks_bytes) = bytes.split_at(LENGTH_NUM_BYTES);
+
+        // Check the length before parsing, so that a bogus length cannot cause a large allocation
+        let num_rks = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        if num_rks.checked_mul(G1_PROJ_NUM_BYTES) != Some(rks_bytes.len()) {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+
+        let pi = g1_proj_from_bytes(pi_bytes)?;
+        let rks = rks_bytes
+            .chunks_exact(G1_PROJ_NUM_BYTES)
+            .map(g1_proj_from_bytes)
+            .collect::<Result<Vec<G1Projective>, _>>()?;
+
+        Ok(RandomizedPKs { pi, rks })
+    }
+}
+
+/// Version 1 layout: `version || share`, where the G2 share is compressed. This is the encoding of
+/// the Pinkas WVUF proof shares.
+impl VersionedCodec for G2Projective {
+    fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(VERSION_NUM_BYTES + G2_PROJ_NUM_BYTES);
+        bytes.push(CODEC_VERSION_V1);
+        bytes.extend_from_slice(&self.to_compressed());
+        bytes
+    }
+
+    fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, CryptoMaterialError> {
+        g2_proj_from_bytes(strip_version(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        codec::{VersionedCodec, CODEC_VERSION_V1},
+        utils::random::{random_g1_point, random_g1_points, random_g2_point},
+        weighted_vuf::pinkas::RandomizedPKs,
+        G1_PROJ_NUM_BYTES,
+    };
+    use aptos_crypto::CryptoMaterialError;
+    use blstrs::G2Projective;
+    use proptest::prelude::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    proptest! {
+        #[test]
+        fn test_randomized_pks_round_trip(seed in any::<[u8; 32]>(), num_rks in 0usize..8) {
+            let mut rng = StdRng::from_seed(seed);
+            let rpks = RandomizedPKs {
+                pi: random_g1_point(&mut rng),
+                rks: random_g1_points(num_rks, &mut rng),
+            };
+
+            let bytes = rpks.to_versioned_bytes();
+            prop_assert_eq!(bytes[0], CODEC_VERSION_V1);
+            prop_assert_eq!(RandomizedPKs::from_versioned_bytes(&bytes).unwrap(), rpks);
+
+            // Truncated or extended encodings are rejected
+            prop_assert!(RandomizedPKs::from_versioned_bytes(&bytes[..bytes.len() - 1]).is_err());
+            let mut extended_bytes = bytes.clone();
+            extended_bytes.push(0);
+            prop_assert!(RandomizedPKs::from_versioned_bytes(&extended_bytes).is_err());
+        }
+
+        #[test]
+        fn test_proof_share_round_trip(seed in any::<[u8; 32]>()) {
+            let mut rng = StdRng::from_seed(seed);
+            let share = random_g2_point(&mut rng);
+
+            let bytes = share.to_versioned_bytes();
+            prop_assert_eq!(bytes[0], CODEC_VERSION_V1);
+            prop_assert_eq!(G2Projective::from_versioned_bytes(&bytes).unwrap(), share);
+            prop_assert!(G2Projective::from_versioned_bytes(&bytes[1..]).is_err());
+        }
+
+        #[test]
+        fn test_decoding_arbitrary_bytes_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+            let _ = RandomizedPKs::from_versioned_bytes(&bytes);
+            let _ = G2Projective::from_versioned_bytes(&bytes);
+        }
+    }
+
+    #[test]
+    fn test_decoding_rejects_bad_inputs() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let rpks = RandomizedPKs {
+            pi: random_g1_point(&mut rng),
+            rks: random_g1_points(2, &mut rng),
+        };
+        let bytes = rpks.to_versioned_bytes();
+
+        // Empty input and unknown versions
+        assert_eq!(
+            RandomizedPKs::from_versioned_bytes(&[]),
+            Err(CryptoMaterialError::WrongLengthError)
+        );
+        let mut unknown_version = bytes.clone();
+        unknown_version[0] = CODEC_VERSION_V1 + 1;
+        assert_eq!(
+            RandomizedPKs::from_versioned_bytes(&unknown_version),
+            Err(CryptoMaterialError::DeserializationError)
+        );
+
+        // A length prefix that does not match the number of RKs
+        let mut bad_length = bytes.clone();
+        bad_length[1 + G1_PROJ_NUM_BYTES] = 3;
+        assert_eq!(
+            RandomizedPKs::from_versioned_bytes(&bad_length),
+            Err(CryptoMaterialError::WrongLengthError)
+        );
+        let mut huge_length = bytes.clone();
+        huge_length[1 + G1_PROJ_NUM_BYTES..1 + G1_PROJ_NUM_BYTES + 4]
+            .copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            RandomizedPKs::from_versioned_bytes(&huge_length),
+            Err(CryptoMaterialError::WrongLengthError)
+        );
+
+        // Points that are not on the curve (or not in the subgroup) are rejected
+        let mut bad_point = bytes;
+        bad_point[1 + G1_PROJ_NUM_BYTES + 4..1 + 2 * G1_PROJ_NUM_BYTES + 4].fill(0xFF);
+        assert_eq!(
+            RandomizedPKs::from_versioned_bytes(&bad_point),
+            Err(CryptoMaterialError::DeserializationError)
+        );
+    }
+}
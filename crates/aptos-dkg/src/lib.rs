@@ -22,6 +22,7 @@ pub use constants::{G1_PROJ_NUM_BYTES, G2_PROJ_NUM_BYTES, SCALAR_NUM_BYTES};
 pub use utils::random::DST_RAND_CORE_HELL;
 
 pub mod algebra;
+pub mod codec;
 pub mod constants;
 pub mod pvss;
 pub mod utils;
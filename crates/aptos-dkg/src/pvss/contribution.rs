@@ -11,7 +11,7 @@ use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use blstrs::Scalar;
 use group::Group;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, ops::Mul};
+use std::{fmt::Display, mem, ops::Mul};
 
 /// A PVSS contribution, which is signed as part of the PVSS transcript
 /// TODO(TechDebt): CryptoHasher does not work with lifetimes. So we cannot make this struct store
@@ -102,3 +102,159 @@ where
     sig.verify_aggregate(&msgs_refs[..], &pks[..])?;
     Ok(())
 }
+
+/// Verifies the SoKs of a (possibly aggregated) transcript incrementally, in chunks of at most
+/// `chunk_size` dealer contributions, rather than materializing the PoKs, messages and signatures
+/// of all dealers at once as `batch_verify_soks` does. The peak memory used is thus proportional
+/// to `chunk_size` instead of the number of dealers.
+///
+/// The result is the same as that of `batch_verify_soks`: a contribution is accepted iff all chunks
+/// verify and the committed secrets add up to the dealt public key (checked in `finish`).
+pub struct StreamingSoKVerifier<Gr, A> {
+    pk_base: Gr,
+    tau: Scalar,
+    chunk_size: usize,
+    /// The sum of the commitments of all contributions added so far.
+    comm_sum: Gr,
+    num_contributions: usize,
+    /// The contributions added since the last chunk was verified.
+    chunk: Vec<(SoK<Gr>, bls12381::PublicKey, A)>,
+}
+
+impl<Gr, A> StreamingSoKVerifier<Gr, A>
+where
+    Gr: Serialize + HasMultiExp + Display + Copy + Group + for<'a> Mul<&'a Scalar>,
+    A: Serialize + Clone,
+{
+    pub fn new(pk_base: &Gr, tau: &Scalar, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "expected a non-zero chunk size");
+
+        StreamingSoKVerifier {
+            pk_base: *pk_base,
+            tau: *tau,
+            chunk_size,
+            comm_sum: Gr::identity(),
+            num_contributions: 0,
+            chunk: Vec::with_capacity(chunk_size),
+        }
+    }
+
+    /// Adds the next dealer contribution, verifying the current chunk once it is full.
+    pub fn add_contribution(
+        &mut self,
+        sok: &SoK<Gr>,
+        spk: bls12381::PublicKey,
+        aux: A,
+    ) -> anyhow::Result<()> {
+        self.comm_sum.add_assign(&sok.1);
+        self.num_contributions += 1;
+        self.chunk.push((sok.clone(), spk, aux));
+
+        if self.chunk.len() == self.chunk_size {
+            self.verify_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Verifies the remaining contributions and checks that the committed secrets add up to `pk`.
+    /// Returns the number of verified contributions.
+    pub fn finish(mut self, pk: &Gr) -> anyhow::Result<usize> {
+        self.verify_chunk()?;
+
+        if self.comm_sum.ne(pk) {
+            bail!(
+                "The PoK does not correspond to the dealt secret. Expected {} but got {}",
+                pk,
+                self.comm_sum
+            );
+        }
+
+        Ok(self.num_contributions)
+    }
+
+    fn verify_chunk(&mut self) -> anyhow::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+
+        // Reuse the chunk's allocation for the next chunk
+        let mut chunk = mem::take(&mut self.chunk);
+
+        // First, the PoKs
+        let poks = chunk
+            .iter()
+            .map(|((_, c, _, pok), _, _)| (*c, *pok))
+            .collect::<Vec<(Gr, schnorr::PoK<Gr>)>>();
+        schnorr::pok_batch_verify::<Gr>(&poks, &self.pk_base, &self.tau)?;
+
+        // Second, the signatures
+        let msgs = chunk
+            .iter()
+            .map(|((player, comm, _, _), _, aux)| Contribution::<Gr, A> {
+                comm: *comm,
+                player: *player,
+                aux: aux.clone(),
+            })
+            .collect::<Vec<Contribution<Gr, A>>>();
+        let msgs_refs = msgs.iter().collect::<Vec<&Contribution<Gr, A>>>();
+        let pks = chunk
+            .iter()
+            .map(|(_, spk, _)| spk)
+            .collect::<Vec<&bls12381::PublicKey>>();
+        let sig = bls12381::Signature::aggregate(
+            chunk
+                .iter()
+                .map(|((_, _, sig, _), _, _)| sig.clone())
+                .collect::<Vec<bls12381::Signature>>(),
+        )?;
+        sig.verify_aggregate(&msgs_refs[..], &pks[..])?;
+
+        chunk.clear();
+        self.chunk = chunk;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        pvss::{
+            contribution::batch_verify_soks,
+            test_utils::{setup_soks, streaming_verify_soks},
+        },
+        utils::random::random_scalar,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_streaming_verification_matches_batch_verification() {
+        let mut rng = StdRng::from_seed([1u8; 32]);
+        let (soks, spks, aux, g, pk) = setup_soks(10, &mut rng);
+        let tau = random_scalar(&mut rng);
+
+        batch_verify_soks(&soks, &g, &pk, &spks, &aux, &tau).unwrap();
+        for chunk_size in [1, 3, 10, 32] {
+            assert_eq!(
+                streaming_verify_soks(&soks, &spks, &aux, &g, &pk, &tau, chunk_size).unwrap(),
+                soks.len()
+            );
+        }
+
+        // A wrong dealt public key
+        let wrong_pk = pk + g;
+        assert!(batch_verify_soks(&soks, &g, &wrong_pk, &spks, &aux, &tau).is_err());
+        assert!(streaming_verify_soks(&soks, &spks, &aux, &g, &wrong_pk, &tau, 3).is_err());
+
+        // A signature over the wrong auxiliary info (in the last, partial chunk)
+        let mut wrong_aux = aux.clone();
+        wrong_aux[9].0 += 1;
+        assert!(batch_verify_soks(&soks, &g, &pk, &spks, &wrong_aux, &tau).is_err());
+        assert!(streaming_verify_soks(&soks, &spks, &wrong_aux, &g, &pk, &tau, 3).is_err());
+
+        // A PoK for the wrong commitment (in the first chunk)
+        let mut wrong_soks = soks.clone();
+        wrong_soks[0].3 = wrong_soks[1].3;
+        assert!(batch_verify_soks(&wrong_soks, &g, &pk, &spks, &aux, &tau).is_err());
+        assert!(streaming_verify_soks(&wrong_soks, &spks, &aux, &g, &pk, &tau, 3).is_err());
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     algebra::polynomials::shamir_secret_share,
     pvss,
     pvss::{
-        contribution::{batch_verify_soks, Contribution, SoK},
+        contribution::{batch_verify_soks, Contribution, SoK, StreamingSoKVerifier},
         das, encryption_dlog, fiat_shamir, schnorr, traits,
         traits::{transcript::MalleableTranscript, HasEncryptionPublicParams, SecretSharingConfig},
         LowDegreeTest, Player, WeightedConfig,
@@ -22,7 +22,7 @@ use crate::{
 use anyhow::bail;
 use aptos_crypto::{bls12381, CryptoMaterialError, Genesis, SigningKey, ValidCryptoMaterial};
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
-use blstrs::{pairing, G1Affine, G1Projective, G2Affine, G2Projective, Gt};
+use blstrs::{pairing, G1Affine, G1Projective, G2Affine, G2Projective, Gt, Scalar};
 use group::{Curve, Group};
 use rand::thread_rng;
 use serde::{Deserialize, Serialize};
@@ -201,7 +201,6 @@ impl traits::Transcript for Transcript {
             2,
         );
 
-        let g_2 = pp.get_commitment_base();
         let g_1 = pp.get_encryption_public_params().pubkey_base();
         let W = sc.get_total_weight();
         batch_verify_soks::<G1Projective, A>(
@@ -213,73 +212,7 @@ impl traits::Transcript for Transcript {
             &extra[0],
         )?;
 
-        let ldt = LowDegreeTest::new(
-            f,
-            sc.get_threshold_weight(),
-            W + 1,
-            true,
-            sc.get_batch_evaluation_domain(),
-        )?;
-        ldt.low_degree_test_on_g1(&self.V)?;
-
-        //
-        // Correctness of encryptions check
-        //
-
-        // TODO: 128-bit scalars from Merlin transcript
-        let alphas_betas_and_gammas = random_scalars(3 * W + 1, &mut thread_rng());
-        let (alphas_and_betas, gammas) = alphas_betas_and_gammas.split_at(2 * W + 1);
-        let (alphas, betas) = alphas_and_betas.split_at(W + 1);
-        assert_eq!(alphas.len(), W + 1);
-        assert_eq!(betas.len(), W);
-        assert_eq!(gammas.len(), W);
-
-        let lc_VR_hat = G2Projective::multi_exp_iter(
-            self.V_hat.iter().chain(self.R_hat.iter()),
-            alphas_and_betas.iter(),
-        );
-        let lc_VRC = G1Projective::multi_exp_iter(
-            self.V.iter().chain(self.R.iter()).chain(self.C.iter()),
-            alphas_betas_and_gammas.iter(),
-        );
-        let lc_V_hat = G2Projective::multi_exp_iter(self.V_hat.iter().take(W), gammas.iter());
-        let mut lc_R_hat = Vec::with_capacity(n);
-
-        for i in 0..n {
-            let p = sc.get_player(i);
-            let weight = sc.get_player_weight(&p);
-            let s_i = sc.get_player_starting_index(&p);
-
-            lc_R_hat.push(g2_multi_exp(
-                &self.R_hat[s_i..s_i + weight],
-                &gammas[s_i..s_i + weight],
-            ));
-        }
-
-        let h = pp.get_encryption_public_params().message_base();
-        let g_2_neg = g_2.neg();
-        let eks = eks
-            .iter()
-            .map(Into::<G1Projective>::into)
-            .collect::<Vec<G1Projective>>();
-        // The vector of left-hand-side ($\mathbb{G}_2$) inputs to each pairing in the multi-pairing.
-        let lhs = [g_1, &lc_VRC, h].into_iter().chain(&eks);
-        // The vector of right-hand-side ($\mathbb{G}_2$) inputs to each pairing in the multi-pairing.
-        let rhs = [&lc_VR_hat, &g_2_neg, &lc_V_hat]
-            .into_iter()
-            .chain(&lc_R_hat);
-
-        let res = multi_pairing(lhs, rhs);
-        if res != Gt::identity() {
-            bail!(
-                "Expected zero during multi-pairing check for {} {}, but got {}",
-                sc,
-                Self::scheme_name(),
-                res
-            );
-        }
-
-        return Ok(());
+        self.verify_encryptions(sc, pp, eks, f)
     }
 
     fn get_dealers(&self) -> Vec<Player> {
@@ -389,6 +322,151 @@ impl traits::Transcript for Transcript {
 }
 
 impl Transcript {
+    /// Like `verify`, but verifies the dealer contributions (i.e., the SoKs) incrementally, in
+    /// chunks of at most `chunk_size` dealers. The signing PKs and auxiliary infos of the dealers
+    /// are pulled from `spks_and_aux` as needed, so the caller can produce them lazily instead of
+    /// collecting them upfront. This bounds the peak memory needed to verify transcripts that were
+    /// aggregated from many dealers (e.g., during an epoch change).
+    #[allow(non_snake_case)]
+    pub fn verify_streaming<A, I>(
+        &self,
+        sc: &WeightedConfig,
+        pp: &das::PublicParameters,
+        eks: &Vec<encryption_dlog::g1::EncryptPubKey>,
+        spks_and_aux: I,
+        chunk_size: usize,
+    ) -> anyhow::Result<()>
+    where
+        A: Serialize + Clone,
+        I: IntoIterator<Item = (bls12381::PublicKey, A)>,
+    {
+        self.check_sizes(sc)?;
+        let n = sc.get_total_num_players();
+        if eks.len() != n {
+            bail!("Expected {} encryption keys, but got {}", n, eks.len());
+        }
+        if chunk_size == 0 {
+            bail!("Expected a non-zero chunk size");
+        }
+
+        // Same challenges as in `verify`
+        let (f, extra) = fiat_shamir::fiat_shamir(
+            self,
+            sc.get_threshold_config(),
+            pp,
+            eks,
+            &DAS_WEIGHTED_PVSS_FIAT_SHAMIR_DST[..],
+            2,
+        );
+
+        let g_1 = pp.get_encryption_public_params().pubkey_base();
+        let W = sc.get_total_weight();
+        let mut verifier = StreamingSoKVerifier::<G1Projective, A>::new(g_1, &extra[0], chunk_size);
+        let mut spks_and_aux = spks_and_aux.into_iter();
+        for sok in &self.soks {
+            let Some((spk, aux)) = spks_and_aux.next() else {
+                bail!(
+                    "Expected {} signing PKs and auxiliary infos, but got fewer",
+                    self.soks.len()
+                );
+            };
+            verifier.add_contribution(sok, spk, aux)?;
+        }
+        if spks_and_aux.next().is_some() {
+            bail!(
+                "Expected {} signing PKs and auxiliary infos, but got more",
+                self.soks.len()
+            );
+        }
+        verifier.finish(&self.V[W])?;
+
+        self.verify_encryptions(sc, pp, eks, f)
+    }
+
+    /// Checks that the committed polynomial has low degree and that the encryptions are correct,
+    /// given the Fiat-Shamir challenge `f` for the low-degree test.
+    #[allow(non_snake_case)]
+    fn verify_encryptions(
+        &self,
+        sc: &WeightedConfig,
+        pp: &das::PublicParameters,
+        eks: &Vec<encryption_dlog::g1::EncryptPubKey>,
+        f: Vec<Scalar>,
+    ) -> anyhow::Result<()> {
+        let n = sc.get_total_num_players();
+        let g_2 = pp.get_commitment_base();
+        let g_1 = pp.get_encryption_public_params().pubkey_base();
+        let W = sc.get_total_weight();
+
+        let ldt = LowDegreeTest::new(
+            f,
+            sc.get_threshold_weight(),
+            W + 1,
+            true,
+            sc.get_batch_evaluation_domain(),
+        )?;
+        ldt.low_degree_test_on_g1(&self.V)?;
+
+        //
+        // Correctness of encryptions check
+        //
+
+        // TODO: 128-bit scalars from Merlin transcript
+        let alphas_betas_and_gammas = random_scalars(3 * W + 1, &mut thread_rng());
+        let (alphas_and_betas, gammas) = alphas_betas_and_gammas.split_at(2 * W + 1);
+        let (alphas, betas) = alphas_and_betas.split_at(W + 1);
+        assert_eq!(alphas.len(), W + 1);
+        assert_eq!(betas.len(), W);
+        assert_eq!(gammas.len(), W);
+
+        let lc_VR_hat = G2Projective::multi_exp_iter(
+            self.V_hat.iter().chain(self.R_hat.iter()),
+            alphas_and_betas.iter(),
+        );
+        let lc_VRC = G1Projective::multi_exp_iter(
+            self.V.iter().chain(self.R.iter()).chain(self.C.iter()),
+            alphas_betas_and_gammas.iter(),
+        );
+        let lc_V_hat = G2Projective::multi_exp_iter(self.V_hat.iter().take(W), gammas.iter());
+        let mut lc_R_hat = Vec::with_capacity(n);
+
+        for i in 0..n {
+            let p = sc.get_player(i);
+            let weight = sc.get_player_weight(&p);
+            let s_i = sc.get_player_starting_index(&p);
+
+            lc_R_hat.push(g2_multi_exp(
+                &self.R_hat[s_i..s_i + weight],
+                &gammas[s_i..s_i + weight],
+            ));
+        }
+
+        let h = pp.get_encryption_public_params().message_base();
+        let g_2_neg = g_2.neg();
+        let eks = eks
+            .iter()
+            .map(Into::<G1Projective>::into)
+            .collect::<Vec<G1Projective>>();
+        // The vector of left-hand-side ($\mathbb{G}_2$) inputs to each pairing in the multi-pairing.
+        let lhs = [g_1, &lc_VRC, h].into_iter().chain(&eks);
+        // The vector of right-hand-side ($\mathbb{G}_2$) inputs to each pairing in the multi-pairing.
+        let rhs = [&lc_VR_hat, &g_2_neg, &lc_V_hat]
+            .into_iter()
+            .chain(&lc_R_hat);
+
+        let res = multi_pairing(lhs, rhs);
+        if res != Gt::identity() {
+            bail!(
+                "Expected zero during multi-pairing check for {} {}, but got {}",
+                sc,
+                <Self as traits::Transcript>::scheme_name(),
+                res
+            );
+        }
+
+        Ok(())
+    }
+
     #[allow(non_snake_case)]
     fn check_sizes(&self, sc: &WeightedConfig) -> anyhow::Result<()> {
         let W = sc.get_total_weight();
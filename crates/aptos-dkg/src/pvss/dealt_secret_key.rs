@@ -16,7 +16,11 @@ macro_rules! dealt_secret_key_impl {
                 dealt_secret_key_share::$gt::DealtSecretKeyShare, player::Player,
                 threshold_config::ThresholdConfig, traits, traits::SecretSharingConfig,
             },
-            utils::{serialization::$gt_proj_from_bytes, $gt_multi_exp},
+            utils::{
+                secret::{group_element_ct_eq, zeroize_group_element},
+                serialization::$gt_proj_from_bytes,
+                $gt_multi_exp,
+            },
         };
         use aptos_crypto::CryptoMaterialError;
         use aptos_crypto_derive::{SilentDebug, SilentDisplay};
@@ -38,7 +42,9 @@ macro_rules! dealt_secret_key_impl {
         /// materialized in our protocol. Instead, we always use some form of efficient multi-party computation
         /// MPC protocol to materialize a function of `sk`, such as `f(sk, m)` where `f` is a verifiable random
         /// function (VRF), for example.
-        #[derive(SilentDebug, SilentDisplay, PartialEq, Clone)]
+        ///
+        /// NOTE: The key is zeroized on drop, and compared in constant time.
+        #[derive(SilentDebug, SilentDisplay, Clone)]
         pub struct DealtSecretKey {
             /// A group element $\hat{h}^a \in G$, where $G$ is $G_1$, $G_2$ or $G_T$
             h_hat: $GTProjective,
@@ -63,6 +69,23 @@ macro_rules! dealt_secret_key_impl {
             pub fn as_group_element(&self) -> &$GTProjective {
                 &self.h_hat
             }
+
+            /// Overwrites the key with the identity (e.g., before its memory is released).
+            pub(crate) fn zeroize(&mut self) {
+                zeroize_group_element(&mut self.h_hat);
+            }
+        }
+
+        impl PartialEq for DealtSecretKey {
+            fn eq(&self, other: &Self) -> bool {
+                group_element_ct_eq(&self.h_hat, &other.h_hat).into()
+            }
+        }
+
+        impl Drop for DealtSecretKey {
+            fn drop(&mut self) {
+                self.zeroize();
+            }
         }
 
         // impl fmt::Debug for DealtSecretKey {
@@ -14,6 +14,9 @@ macro_rules! dealt_secret_key_share_impl {
         const DEALT_SK_SHARE_NUM_BYTES: usize = DEALT_SK_NUM_BYTES;
 
         /// A player's *share* of the secret key that was dealt via the PVSS transcript.
+        ///
+        /// NOTE: Like the `DealtSecretKey` it wraps, the share is zeroized on drop, and compared in
+        /// constant time.
         #[derive(DeserializeKey, SerializeKey, SilentDisplay, SilentDebug, PartialEq, Clone)]
         pub struct DealtSecretKeyShare(pub(crate) DealtSecretKey);
 
@@ -36,6 +39,11 @@ macro_rules! dealt_secret_key_share_impl {
             pub fn as_group_element(&self) -> &$GTProjective {
                 self.0.as_group_element()
             }
+
+            /// Overwrites the share with the identity. Note: the share is also zeroized on drop.
+            pub(crate) fn zeroize(&mut self) {
+                self.0.zeroize()
+            }
         }
 
         impl ValidCryptoMaterial for DealtSecretKeyShare {
@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-mod contribution;
+pub mod contribution;
 pub mod das;
 pub(crate) mod dealt_pub_key;
 pub(crate) mod dealt_pub_key_share;
@@ -15,7 +15,7 @@ pub mod insecure_field;
 mod low_degree_test;
 mod player;
 pub mod scalar_secret_key;
-mod schnorr;
+pub mod schnorr;
 pub mod test_utils;
 mod threshold_config;
 pub mod traits;
@@ -1,13 +1,20 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::pvss::{
-    traits::{transcript::Transcript, Convert, HasEncryptionPublicParams, SecretSharingConfig},
-    Player, ThresholdConfig, WeightedConfig,
+use crate::{
+    pvss::{
+        contribution::{Contribution, SoK, StreamingSoKVerifier},
+        schnorr,
+        traits::{transcript::Transcript, Convert, HasEncryptionPublicParams, SecretSharingConfig},
+        Player, ThresholdConfig, WeightedConfig,
+    },
+    utils::random::random_scalar,
 };
-use aptos_crypto::{hash::CryptoHash, SigningKey, Uniform};
+use aptos_crypto::{bls12381, hash::CryptoHash, SigningKey, Uniform};
+use blstrs::{G1Projective, Scalar};
+use group::Group;
 use num_traits::Zero;
-use rand::{prelude::ThreadRng, thread_rng};
+use rand::{prelude::ThreadRng, rngs::StdRng, thread_rng};
 use serde::Serialize;
 use std::ops::AddAssign;
 
@@ -109,6 +116,69 @@ pub fn get_threshold_config_and_rng(t: usize, n: usize) -> (ThresholdConfig, Thr
     (sc, thread_rng())
 }
 
+/// The auxiliary info signed by the dealers in `setup_soks`.
+pub type SoKAux = (u64, u64);
+
+/// Returns the SoKs, signing PKs and auxiliary infos of `num_dealers` dealers, as well as the
+/// PoK base and the dealt public key. Useful for testing SoK verification.
+pub fn setup_soks(
+    num_dealers: usize,
+    rng: &mut StdRng,
+) -> (
+    Vec<SoK<G1Projective>>,
+    Vec<bls12381::PublicKey>,
+    Vec<SoKAux>,
+    G1Projective,
+    G1Projective,
+) {
+    let g = G1Projective::generator();
+    let mut soks = Vec::with_capacity(num_dealers);
+    let mut spks = Vec::with_capacity(num_dealers);
+    let mut aux = Vec::with_capacity(num_dealers);
+    let mut pk = G1Projective::identity();
+
+    for i in 0..num_dealers {
+        let ssk = bls12381::PrivateKey::generate(rng);
+        let player = Player { id: i };
+        let a = random_scalar(rng);
+        let comm = g * a;
+        let pok = schnorr::pok_prove(&a, &g, &comm, rng);
+        let aux_i = (7, i as u64);
+        let sig = ssk
+            .sign(&Contribution::<G1Projective, SoKAux> {
+                comm,
+                player,
+                aux: aux_i,
+            })
+            .unwrap();
+
+        pk += comm;
+        soks.push((player, comm, sig, pok));
+        spks.push(ssk.verifying_key());
+        aux.push(aux_i);
+    }
+
+    (soks, spks, aux, g, pk)
+}
+
+/// Verifies the SoKs returned by `setup_soks` with a `StreamingSoKVerifier` that buffers
+/// `chunk_size` SoKs at a time. Returns the number of verified SoKs.
+pub fn streaming_verify_soks(
+    soks: &[SoK<G1Projective>],
+    spks: &[bls12381::PublicKey],
+    aux: &[SoKAux],
+    g: &G1Projective,
+    pk: &G1Projective,
+    tau: &Scalar,
+    chunk_size: usize,
+) -> anyhow::Result<usize> {
+    let mut verifier = StreamingSoKVerifier::<G1Projective, SoKAux>::new(g, tau, chunk_size);
+    for ((sok, spk), aux) in soks.iter().zip(spks).zip(aux) {
+        verifier.add_contribution(sok, spk.clone(), *aux)?;
+    }
+    verifier.finish(pk)
+}
+
 #[allow(unused)]
 macro_rules! vec_to_str {
     ($vec:ident) => {
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod generic_weighting;
+pub mod rounding;
 mod weighted_config;
 
 pub use generic_weighting::GenericWeighting;
@@ -0,0 +1,175 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rounds a stake distribution to a *weighted* threshold configuration.
+//!
+//! Given the players' stakes and a target total weight $W$, each player's *ideal* weight is
+//! $W \cdot s_i / S$, where $S$ is the total stake. Since weights are integers, every player's
+//! weight deviates from its ideal weight, and these rounding errors translate into a gap between
+//! the stake ratio that can never reconstruct (the secrecy threshold) and the stake ratio that can
+//! always reconstruct (the effective reconstruction threshold).
+//! See https://eprint.iacr.org/2024/198.
+//!
+//! Weights are computed via the largest remainder method, which minimizes the total rounding error
+//! $\sum_i |w_i - W \cdot s_i / S|$ for the target total weight. Since zero weights are not
+//! supported, players whose weight would round to zero get weight 1 (i.e., the total weight
+//! may slightly exceed the target).
+//!
+//! All computations use fixed-point arithmetic, so that every validator computes the same config.
+
+use crate::pvss::WeightedConfig;
+use anyhow::{anyhow, ensure};
+use fixed::types::U64F64;
+
+/// The outcome of rounding a stake distribution to weights.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightRoundingProfile {
+    /// The weight of each player.
+    pub weights: Vec<usize>,
+    /// The minimum weight needed to reconstruct the secret.
+    pub reconstruct_threshold: usize,
+    /// Any subset of players with at most this ratio of the stake cannot reconstruct.
+    pub secrecy_threshold_in_stake_ratio: U64F64,
+    /// Any subset of players with at least this ratio of the stake can reconstruct.
+    pub reconstruct_threshold_in_stake_ratio: U64F64,
+    /// The total rounding error (in weights), i.e., $\sum_i |w_i - W \cdot s_i / S|$.
+    pub rounding_error: U64F64,
+}
+
+impl WeightRoundingProfile {
+    pub fn total_weight(&self) -> usize {
+        self.weights.iter().sum()
+    }
+
+    /// The security loss due to rounding, i.e., the gap between the (effective) reconstruction
+    /// threshold and the secrecy threshold, as a ratio of the stake.
+    pub fn stake_ratio_gap(&self) -> U64F64 {
+        self.reconstruct_threshold_in_stake_ratio - self.secrecy_threshold_in_stake_ratio
+    }
+}
+
+/// Computes the per-player weights and the reconstruction threshold (with the least rounding
+/// error for the target total weight), such that any subset of players with at most
+/// `secrecy_threshold_in_stake_ratio` of the stake cannot reconstruct the secret.
+///
+/// Returns an error if, due to the rounding error, a subset with
+/// `max_reconstruct_threshold_in_stake_ratio` of the stake might not be able to reconstruct (i.e.,
+/// the security loss is too high). In this case, a larger target total weight reduces the
+/// rounding error.
+pub fn compute_weighted_config(
+    stakes: &[u64],
+    target_total_weight: usize,
+    secrecy_threshold_in_stake_ratio: U64F64,
+    max_reconstruct_threshold_in_stake_ratio: U64F64,
+) -> anyhow::Result<(WeightedConfig, WeightRoundingProfile)> {
+    let profile = compute_weight_rounding_profile(
+        stakes,
+        target_total_weight,
+        secrecy_threshold_in_stake_ratio,
+    )?;
+    ensure!(
+        profile.reconstruct_threshold_in_stake_ratio <= max_reconstruct_threshold_in_stake_ratio,
+        "the reconstruction threshold {} exceeds the maximum {} due to rounding; consider increasing the target total weight {}",
+        profile.reconstruct_threshold_in_stake_ratio,
+        max_reconstruct_threshold_in_stake_ratio,
+        target_total_weight
+    );
+
+    let wc = WeightedConfig::new(profile.reconstruct_threshold, profile.weights.clone())?;
+    Ok((wc, profile))
+}
+
+/// Computes the per-player weights and the reconstruction threshold for the target total
+/// weight, without bounding the security loss. See `compute_weighted_config`.
+pub fn compute_weight_rounding_profile(
+    stakes: &[u64],
+    target_total_weight: usize,
+    secrecy_threshold_in_stake_ratio: U64F64,
+) -> anyhow::Result<WeightRoundingProfile> {
+    ensure!(!stakes.is_empty(), "expected a non-empty vector of stakes");
+    ensure!(
+        target_total_weight > 0,
+        "expected the target total weight to be > 0"
+    );
+    ensure!(
+        secrecy_threshold_in_stake_ratio < U64F64::from_num(1),
+        "expected the secrecy threshold to be < 1"
+    );
+    let stake_sum = stakes
+        .iter()
+        .try_fold(0u64, |sum, stake| sum.checked_add(*stake))
+        .ok_or_else(|| anyhow!("the total stake overflows"))?;
+    ensure!(stake_sum > 0, "expected the total stake to be > 0");
+
+    // The ideal weight of each player is `target_total_weight * stake / stake_sum`. Start with
+    // the ideal weights rounded down, and distribute the remaining weight to the players with
+    // the largest remainders (breaking ties by player index, for determinism).
+    let total_weight = target_total_weight as u128;
+    let mut rounded_down_weights = Vec::with_capacity(stakes.len());
+    let mut remainders = Vec::with_capacity(stakes.len());
+    for stake in stakes {
+        let scaled_stake = *stake as u128 * total_weight;
+        rounded_down_weights.push((scaled_stake / stake_sum as u128) as usize);
+        remainders.push((scaled_stake % stake_sum as u128) as u64);
+    }
+    let mut weights = rounded_down_weights.clone();
+    let num_rounded_up = target_total_weight - weights.iter().sum::<usize>();
+    let mut players_by_remainder: Vec<usize> = (0..stakes.len()).collect();
+    players_by_remainder.sort_by(|a, b| remainders[*b].cmp(&remainders[*a]).then(a.cmp(b)));
+    for player in players_by_remainder.into_iter().take(num_rounded_up) {
+        weights[player] += 1;
+    }
+    // TODO: remove once zero weights are supported
+    for weight in weights.iter_mut() {
+        if *weight == 0 {
+            *weight = 1;
+        }
+    }
+
+    // Compute the rounding errors of the players rounded up and down
+    let stake_sum_fixed = U64F64::from_num(stake_sum);
+    let mut delta_up = U64F64::from_num(0);
+    let mut delta_down = U64F64::from_num(0);
+    for ((weight, rounded_down_weight), remainder) in
+        weights.iter().zip(&rounded_down_weights).zip(&remainders)
+    {
+        let ideal_weight =
+            U64F64::from_num(*rounded_down_weight) + U64F64::from_num(*remainder) / stake_sum_fixed;
+        let weight = U64F64::from_num(*weight);
+        if weight > ideal_weight {
+            delta_up += weight - ideal_weight;
+        } else {
+            delta_down += ideal_weight - weight;
+        }
+    }
+
+    // A subset with at most the secrecy threshold of the stake has an ideal weight of at most
+    // `secrecy_threshold * W`, and hence a weight of at most `secrecy_threshold * W + delta_up`.
+    let target_total_weight_fixed = U64F64::from_num(target_total_weight);
+    let reconstruct_threshold = (secrecy_threshold_in_stake_ratio * target_total_weight_fixed
+        + delta_up)
+        .floor()
+        .to_num::<usize>()
+        + 1;
+    let actual_total_weight = weights.iter().sum::<usize>();
+    ensure!(
+        reconstruct_threshold <= actual_total_weight,
+        "the reconstruction threshold {} exceeds the total weight {} due to rounding; consider increasing the target total weight {}",
+        reconstruct_threshold,
+        actual_total_weight,
+        target_total_weight
+    );
+
+    // Conversely, a subset with a ratio `r` of the stake has a weight of at least
+    // `r * W - delta_down`, so it can reconstruct if `r >= (threshold + delta_down) / W`.
+    let reconstruct_threshold_in_stake_ratio =
+        (U64F64::from_num(reconstruct_threshold) + delta_down) / target_total_weight_fixed;
+
+    Ok(WeightRoundingProfile {
+        weights,
+        reconstruct_threshold,
+        secrecy_threshold_in_stake_ratio,
+        reconstruct_threshold_in_stake_ratio,
+        rounding_error: delta_up + delta_down,
+    })
+}
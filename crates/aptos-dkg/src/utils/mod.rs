@@ -16,6 +16,7 @@ use std::ops::Mul;
 pub(crate) mod biguint;
 pub mod parallel_multi_pairing;
 pub mod random;
+pub mod secret;
 pub mod serialization;
 
 #[inline]
@@ -0,0 +1,184 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for handling secret key material: zeroization (on drop), constant-time comparisons and
+//! (optionally, via the `mlock` feature) locking the memory of secrets so that it is never swapped
+//! out to disk.
+
+use blstrs::Scalar;
+use ff::Field;
+use group::{Group, GroupEncoding};
+#[cfg(feature = "mlock")]
+use once_cell::sync::Lazy;
+#[cfg(feature = "mlock")]
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{Mutex, PoisonError},
+};
+use std::{
+    ptr,
+    sync::atomic::{compiler_fence, Ordering},
+};
+use subtle::{Choice, ConstantTimeEq};
+
+/// The size of a memory page, i.e., the granularity of memory locks.
+#[cfg(feature = "mlock")]
+static PAGE_SIZE: Lazy<usize> = Lazy::new(|| {
+    // SAFETY: `sysconf` has no preconditions.
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    usize::try_from(page_size).unwrap_or(4096)
+});
+
+/// The number of secrets holding a lock on each locked page (indexed by the page address).
+/// Memory locks do not stack, so a page is only unlocked once all of its secrets are unlocked.
+#[cfg(feature = "mlock")]
+static PAGE_LOCKS: Lazy<Mutex<HashMap<usize, usize>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Overwrites the given value with `zero`. The write is volatile (and fenced), so that it cannot
+/// be optimized away by the compiler, even though the value is never read again (e.g., on drop).
+#[inline]
+fn zeroize_with<T: Copy>(value: &mut T, zero: T) {
+    // SAFETY: `value` is a valid, aligned and exclusive reference, and `T` is `Copy` (so there
+    // is no destructor to skip when overwriting the old value).
+    unsafe { ptr::write_volatile(value, zero) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Overwrites the given scalar with zero.
+pub fn zeroize_scalar(scalar: &mut Scalar) {
+    zeroize_with(scalar, Scalar::ZERO);
+}
+
+/// Overwrites the given group element with the identity.
+pub fn zeroize_group_element<G: Group>(element: &mut G) {
+    zeroize_with(element, G::identity());
+}
+
+/// Compares two scalars in constant time.
+pub fn scalar_ct_eq(a: &Scalar, b: &Scalar) -> Choice {
+    a.ct_eq(b)
+}
+
+/// Compares two group elements in constant time (via their canonical encodings).
+pub fn group_element_ct_eq<G: GroupEncoding>(a: &G, b: &G) -> Choice {
+    a.to_bytes().as_ref().ct_eq(b.to_bytes().as_ref())
+}
+
+/// Returns the addresses of the pages backing the given slice (with a step of `PAGE_SIZE`).
+#[cfg(feature = "mlock")]
+fn page_addresses<T>(slice: &[T]) -> std::iter::StepBy<Range<usize>> {
+    let start = slice.as_ptr() as usize;
+    let end = start + std::mem::size_of_val(slice);
+    let first_page = start - start % *PAGE_SIZE;
+    let pages_end = if start == end { first_page } else { end };
+    (first_page..pages_end).step_by(*PAGE_SIZE)
+}
+
+/// Locks the memory backing the given slice, so that it is never swapped out to disk. Returns an
+/// error if the lock fails (e.g., if `RLIMIT_MEMLOCK` is exceeded).
+///
+/// Every successful call must eventually be matched by a `munlock_slice` call on the same slice.
+/// The locks are counted per page, so pages shared with other locked secrets stay locked until
+/// all of these secrets are unlocked.
+#[cfg(feature = "mlock")]
+pub fn mlock_slice<T>(slice: &[T]) -> anyhow::Result<()> {
+    let len = std::mem::size_of_val(slice);
+    if len == 0 {
+        return Ok(());
+    }
+
+    let mut page_locks = PAGE_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    // SAFETY: the pointer and length describe the (valid) memory of the slice.
+    if unsafe { libc::mlock(slice.as_ptr() as *const libc::c_void, len) } != 0 {
+        anyhow::bail!(
+            "Failed to lock the memory of the secret: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    for page in page_addresses(slice) {
+        *page_locks.entry(page).or_insert(0) += 1;
+    }
+
+    Ok(())
+}
+
+/// Releases the locks taken by `mlock_slice` on the memory backing the given slice. Each page is
+/// only unlocked once no other locked secret shares it. Pages that were never locked (via
+/// `mlock_slice`) are left untouched.
+#[cfg(feature = "mlock")]
+pub fn munlock_slice<T>(slice: &[T]) {
+    let mut page_locks = PAGE_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+    for page in page_addresses(slice) {
+        let Some(count) = page_locks.get_mut(&page) else {
+            continue;
+        };
+        *count -= 1;
+        if *count == 0 {
+            page_locks.remove(&page);
+
+            // SAFETY: the page backs (part of) the valid memory of the slice.
+            unsafe { libc::munlock(page as *const libc::c_void, *PAGE_SIZE) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random::{random_g1_point, random_scalar};
+    use blstrs::G1Projective;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_zeroize_and_ct_eq() {
+        let mut rng = thread_rng();
+
+        let mut scalar = random_scalar(&mut rng);
+        let copy = scalar;
+        assert!(bool::from(scalar_ct_eq(&scalar, &copy)));
+        zeroize_scalar(&mut scalar);
+        assert_eq!(scalar, Scalar::ZERO);
+        assert!(!bool::from(scalar_ct_eq(&scalar, &copy)));
+
+        let mut element = random_g1_point(&mut rng);
+        let copy = element;
+        assert!(bool::from(group_element_ct_eq(&element, &copy)));
+        zeroize_group_element(&mut element);
+        assert_eq!(element, G1Projective::identity());
+        assert!(!bool::from(group_element_ct_eq(&element, &copy)));
+    }
+
+    #[cfg(feature = "mlock")]
+    #[test]
+    fn test_mlock_shared_page() {
+        let page_count = |slice: &[Scalar]| {
+            let page = page_addresses(slice).next().unwrap();
+            PAGE_LOCKS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&page)
+                .copied()
+                .unwrap_or(0)
+        };
+
+        // Two secrets that share a page
+        let mut rng = thread_rng();
+        let secrets = Box::new([random_scalar(&mut rng), random_scalar(&mut rng)]);
+        let (first, second) = secrets.split_at(1);
+        if page_addresses(first).next() != page_addresses(second).next() {
+            return;
+        }
+        let initial_count = page_count(first);
+
+        // The page stays locked until both secrets are unlocked
+        mlock_slice(first).unwrap();
+        mlock_slice(second).unwrap();
+        assert_eq!(page_count(first), initial_count + 2);
+        munlock_slice(first);
+        assert_eq!(page_count(second), initial_count + 1);
+        munlock_slice(second);
+        assert_eq!(page_count(second), initial_count);
+    }
+}
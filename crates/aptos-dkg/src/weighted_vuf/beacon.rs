@@ -0,0 +1,436 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A randomness beacon built on top of a weighted VUF.
+//!
+//! For every round of an epoch, players create proof shares on the round's `BeaconMessage`. A
+//! `ShareCollector` verifies and collects these shares until enough weight has contributed, and
+//! then aggregates them into a `BeaconOutput`: the round's randomness, together with a proof that
+//! anyone with the epoch's `BeaconConfig` can verify via `verify_beacon_output`.
+//!
+//! Outputs are appended to a `BeaconHistory`, which hash-chains the outputs of an epoch so that the
+//! full history can be verified via `verify_beacon_history`. The history is persisted via the
+//! `BeaconStorage` hooks (e.g., a node's DB, or an external service's own storage).
+
+use crate::{
+    pvss::{traits::SecretSharingConfig, Player, WeightedConfig},
+    weighted_vuf::{
+        misbehavior::{verify_share_or_report, WvufMisbehaviorReport},
+        traits::WeightedVUF,
+    },
+};
+use anyhow::{anyhow, bail, ensure};
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::{collections::BTreeMap, marker::PhantomData, sync::Mutex};
+
+/// Domain separator for the beacon messages and the history hash chain.
+pub const BEACON_DOMAIN_SEPARATOR: &[u8] = b"APTOS_WVUF_RANDOMNESS_BEACON";
+
+/// Identifies the beacon output of a single round.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BeaconMessage {
+    pub epoch: u64,
+    pub round: u64,
+}
+
+impl BeaconMessage {
+    pub fn new(epoch: u64, round: u64) -> Self {
+        Self { epoch, round }
+    }
+
+    /// Returns the (domain-separated) message that players create proof shares on.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = BEACON_DOMAIN_SEPARATOR.to_vec();
+        bytes.extend(bcs::to_bytes(self).expect("BeaconMessage serialization should not fail"));
+        bytes
+    }
+}
+
+/// Hashes a WVUF evaluation into the randomness bytes handed out to consumers.
+pub fn evaluation_to_randomness<E: Serialize>(eval: &E) -> Vec<u8> {
+    let eval_bytes = bcs::to_bytes(eval).expect("WVUF evaluation serialization should not fail");
+    Sha3_256::digest(eval_bytes.as_slice()).to_vec()
+}
+
+/// Everything needed to verify the proof shares and beacon outputs of an epoch.
+pub struct BeaconConfig<WVUF: WeightedVUF> {
+    pub epoch: u64,
+    pub wc: WeightedConfig,
+    pub pp: WVUF::PublicParameters,
+    pub pk: WVUF::PubKey,
+    /// The augmented public key shares of all players (if agreed upon), indexed by player ID.
+    pub apks: Vec<Option<WVUF::AugmentedPubKeyShare>>,
+}
+
+impl<WVUF: WeightedVUF> BeaconConfig<WVUF> {
+    fn get_apk(&self, player: &Player) -> anyhow::Result<&WVUF::AugmentedPubKeyShare> {
+        self.apks
+            .get(player.id)
+            .ok_or_else(|| anyhow!("Unknown beacon player {}", player.id))?
+            .as_ref()
+            .ok_or_else(|| anyhow!("No augmented public key for beacon player {}", player.id))
+    }
+}
+
+/// The randomness of a single round, with a proof that it was derived correctly.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconOutput<Proof> {
+    pub message: BeaconMessage,
+    pub randomness: Vec<u8>,
+    pub proof: Proof,
+}
+
+/// The beacon output type for a specific WVUF scheme.
+pub type WvufBeaconOutput<WVUF> = BeaconOutput<<WVUF as WeightedVUF>::Proof>;
+
+/// Verifies that the beacon output was correctly derived for its message under the given config.
+pub fn verify_beacon_output<WVUF: WeightedVUF>(
+    config: &BeaconConfig<WVUF>,
+    output: &WvufBeaconOutput<WVUF>,
+    thread_pool: &ThreadPool,
+) -> anyhow::Result<()> {
+    ensure!(
+        output.message.epoch == config.epoch,
+        "Beacon output is for epoch {}, but the config is for epoch {}",
+        output.message.epoch,
+        config.epoch
+    );
+
+    let msg = output.message.to_bytes();
+    WVUF::verify_proof(&config.pp, &config.pk, &config.apks, &msg, &output.proof)?;
+    let eval = WVUF::derive_eval(
+        &config.wc,
+        &config.pp,
+        &msg,
+        &config.apks,
+        &output.proof,
+        thread_pool,
+    )?;
+    ensure!(
+        evaluation_to_randomness(&eval) == output.randomness,
+        "Beacon output for {:?} does not match its proof",
+        output.message
+    );
+    Ok(())
+}
+
+/// The state of a `ShareCollector`.
+enum CollectorState<WVUF: WeightedVUF> {
+    /// Still collecting (verified) proof shares.
+    Collecting(Vec<(Player, WVUF::AugmentedPubKeyShare, WVUF::ProofShare)>),
+    /// The shares have been aggregated into the round's output.
+    Decided(WvufBeaconOutput<WVUF>),
+}
+
+/// Collects the proof shares for a single round, and aggregates them into the round's beacon
+/// output once enough weight has contributed. Invalid shares are rejected, and a misbehavior
+/// report is kept for each of them (see `take_misbehavior_reports`).
+pub struct ShareCollector<'a, WVUF: WeightedVUF> {
+    config: &'a BeaconConfig<WVUF>,
+    message: BeaconMessage,
+    msg_bytes: Vec<u8>,
+    contributed: Vec<bool>,
+    contributed_weight: usize,
+    misbehavior_reports: Vec<WvufMisbehaviorReport<WVUF>>,
+    state: CollectorState<WVUF>,
+}
+
+impl<'a, WVUF: WeightedVUF> ShareCollector<'a, WVUF>
+where
+    WVUF::ProofShare: Clone,
+{
+    pub fn new(config: &'a BeaconConfig<WVUF>, round: u64) -> Self {
+        let message = BeaconMessage::new(config.epoch, round);
+        Self {
+            config,
+            message,
+            msg_bytes: message.to_bytes(),
+            contributed: vec![false; config.wc.get_total_num_players()],
+            contributed_weight: 0,
+            misbehavior_reports: vec![],
+            state: CollectorState::Collecting(vec![]),
+        }
+    }
+
+    pub fn get_message(&self) -> &BeaconMessage {
+        &self.message
+    }
+
+    /// Verifies and adds the proof share of the given player. Duplicate shares are ignored.
+    pub fn add_share(&mut self, player: Player, share: WVUF::ProofShare) -> anyhow::Result<()> {
+        let apks_and_proofs = match &mut self.state {
+            CollectorState::Collecting(apks_and_proofs) => apks_and_proofs,
+            CollectorState::Decided(_) => return Ok(()), // The output is already known
+        };
+        let apk = self.config.get_apk(&player)?;
+        if self.contributed[player.id] {
+            return Ok(());
+        }
+
+        if let Err(report) =
+            verify_share_or_report::<WVUF>(&self.config.pp, &player, apk, &self.msg_bytes, &share)
+        {
+            let reason = report.reason.clone();
+            self.misbehavior_reports.push(*report);
+            bail!(
+                "Invalid beacon share from player {} for {:?}: {}",
+                player.id,
+                self.message,
+                reason
+            );
+        }
+
+        apks_and_proofs.push((player, apk.clone(), share));
+        self.contributed[player.id] = true;
+        self.contributed_weight += self.config.wc.get_player_weight(&player);
+        Ok(())
+    }
+
+    /// Returns true iff enough weight has contributed to aggregate the output.
+    pub fn has_enough_weight(&self) -> bool {
+        self.contributed_weight >= self.config.wc.get_threshold_weight()
+    }
+
+    /// Aggregates the collected shares into the round's output, if enough weight has contributed.
+    /// Once aggregated, the output is returned on all subsequent calls.
+    pub fn try_aggregate(
+        &mut self,
+        thread_pool: &ThreadPool,
+    ) -> anyhow::Result<Option<&WvufBeaconOutput<WVUF>>> {
+        let has_enough_weight = self.has_enough_weight();
+        if let CollectorState::Collecting(apks_and_proofs) = &mut self.state {
+            if !has_enough_weight {
+                return Ok(None);
+            }
+
+            // Order the shares by player, so the output doesn't depend on the arrival order
+            apks_and_proofs.sort_by_key(|(player, _, _)| player.id);
+            let proof = WVUF::aggregate_shares(&self.config.wc, apks_and_proofs);
+            let eval = WVUF::derive_eval(
+                &self.config.wc,
+                &self.config.pp,
+                &self.msg_bytes,
+                &self.config.apks,
+                &proof,
+                thread_pool,
+            )?;
+            self.state = CollectorState::Decided(BeaconOutput {
+                message: self.message,
+                randomness: evaluation_to_randomness(&eval),
+                proof,
+            });
+        }
+
+        Ok(self.get_output())
+    }
+
+    /// Returns the round's output (if the shares have been aggregated).
+    pub fn get_output(&self) -> Option<&WvufBeaconOutput<WVUF>> {
+        match &self.state {
+            CollectorState::Collecting(_) => None,
+            CollectorState::Decided(output) => Some(output),
+        }
+    }
+
+    /// Returns (and clears) the misbehavior reports for the invalid shares received so far.
+    pub fn take_misbehavior_reports(&mut self) -> Vec<WvufMisbehaviorReport<WVUF>> {
+        std::mem::take(&mut self.misbehavior_reports)
+    }
+}
+
+/// A single entry in the beacon history of an epoch. Each entry commits to the previous entry (or,
+/// for the first entry, to the epoch), so the history can't be rewritten without detection.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconHistoryEntry<Proof> {
+    pub output: BeaconOutput<Proof>,
+    pub prev_entry_hash: Vec<u8>,
+}
+
+impl<Proof: Serialize> BeaconHistoryEntry<Proof> {
+    pub fn hash(&self) -> Vec<u8> {
+        let entry_bytes =
+            bcs::to_bytes(self).expect("BeaconHistoryEntry serialization should not fail");
+        Sha3_256::digest(entry_bytes.as_slice()).to_vec()
+    }
+}
+
+/// The beacon history entry type for a specific WVUF scheme.
+pub type WvufBeaconHistoryEntry<WVUF> = BeaconHistoryEntry<<WVUF as WeightedVUF>::Proof>;
+
+/// Returns the hash that the first history entry of the given epoch commits to.
+pub fn genesis_entry_hash(epoch: u64) -> Vec<u8> {
+    let mut bytes = BEACON_DOMAIN_SEPARATOR.to_vec();
+    bytes.extend(epoch.to_le_bytes());
+    Sha3_256::digest(bytes.as_slice()).to_vec()
+}
+
+/// Persistence hooks for the beacon history.
+pub trait BeaconStorage<Proof>: Send + Sync {
+    /// Persists the given history entry.
+    fn save_entry(&self, entry: &BeaconHistoryEntry<Proof>) -> anyhow::Result<()>;
+
+    /// Returns all persisted history entries of the given epoch, ordered by round.
+    fn get_entries(&self, epoch: u64) -> anyhow::Result<Vec<BeaconHistoryEntry<Proof>>>;
+}
+
+/// A simple in-memory beacon storage (e.g., for tests, or for consumers that don't persist).
+pub struct InMemoryBeaconStorage<Proof> {
+    entries: Mutex<BTreeMap<BeaconMessage, BeaconHistoryEntry<Proof>>>,
+}
+
+impl<Proof> InMemoryBeaconStorage<Proof> {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl<Proof> Default for InMemoryBeaconStorage<Proof> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Proof: Clone + Send> BeaconStorage<Proof> for InMemoryBeaconStorage<Proof> {
+    fn save_entry(&self, entry: &BeaconHistoryEntry<Proof>) -> anyhow::Result<()> {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(entry.output.message, entry.clone());
+        Ok(())
+    }
+
+    fn get_entries(&self, epoch: u64) -> anyhow::Result<Vec<BeaconHistoryEntry<Proof>>> {
+        let entries = self.entries.lock().unwrap();
+        let epoch_entries = entries
+            .range(BeaconMessage::new(epoch, 0)..=BeaconMessage::new(epoch, u64::MAX))
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        Ok(epoch_entries)
+    }
+}
+
+/// The (append-only) beacon history of a single epoch.
+pub struct BeaconHistory<Proof, S: BeaconStorage<Proof>> {
+    epoch: u64,
+    storage: S,
+    // The round and hash of the last entry (if any)
+    last_entry: Option<(u64, Vec<u8>)>,
+    _proof: PhantomData<Proof>,
+}
+
+impl<Proof: Serialize, S: BeaconStorage<Proof>> BeaconHistory<Proof, S> {
+    /// Opens the history of the given epoch, checking the hash chain of the persisted entries.
+    pub fn new(epoch: u64, storage: S) -> anyhow::Result<Self> {
+        let entries = storage.get_entries(epoch)?;
+        check_hash_chain(epoch, &entries)?;
+        let last_entry = entries
+            .last()
+            .map(|entry| (entry.output.message.round, entry.hash()));
+        Ok(Self {
+            epoch,
+            storage,
+            last_entry,
+            _proof: PhantomData,
+        })
+    }
+
+    /// Appends the given output to the history. Outputs must be appended in increasing round order
+    /// (rounds may be skipped). Note: the output itself is not verified here.
+    pub fn append(&mut self, output: BeaconOutput<Proof>) -> anyhow::Result<()> {
+        ensure!(
+            output.message.epoch == self.epoch,
+            "Beacon output is for epoch {}, but the history is for epoch {}",
+            output.message.epoch,
+            self.epoch
+        );
+        let prev_entry_hash = match &self.last_entry {
+            Some((last_round, last_hash)) => {
+                ensure!(
+                    output.message.round > *last_round,
+                    "Beacon output for round {} is not after the last round {}",
+                    output.message.round,
+                    last_round
+                );
+                last_hash.clone()
+            },
+            None => genesis_entry_hash(self.epoch),
+        };
+
+        let entry = BeaconHistoryEntry {
+            output,
+            prev_entry_hash,
+        };
+        self.storage.save_entry(&entry)?;
+        self.last_entry = Some((entry.output.message.round, entry.hash()));
+        Ok(())
+    }
+
+    /// Returns all entries of the history, ordered by round.
+    pub fn get_entries(&self) -> anyhow::Result<Vec<BeaconHistoryEntry<Proof>>> {
+        self.storage.get_entries(self.epoch)
+    }
+
+    /// Returns the hash of the last entry, which commits to the entire history so far.
+    pub fn get_head_hash(&self) -> Vec<u8> {
+        match &self.last_entry {
+            Some((_, last_hash)) => last_hash.clone(),
+            None => genesis_entry_hash(self.epoch),
+        }
+    }
+}
+
+/// Verifies the given beacon history of an epoch: the entries must form a hash chain (in
+/// increasing round order), and every output must be valid under the given config.
+pub fn verify_beacon_history<WVUF: WeightedVUF>(
+    config: &BeaconConfig<WVUF>,
+    entries: &[WvufBeaconHistoryEntry<WVUF>],
+    thread_pool: &ThreadPool,
+) -> anyhow::Result<()>
+where
+    WVUF::Proof: Serialize,
+{
+    check_hash_chain(config.epoch, entries)?;
+    for entry in entries {
+        verify_beacon_output(config, &entry.output, thread_pool)?;
+    }
+    Ok(())
+}
+
+/// Checks that the entries form a hash chain for the given epoch, in increasing round order.
+fn check_hash_chain<Proof: Serialize>(
+    epoch: u64,
+    entries: &[BeaconHistoryEntry<Proof>],
+) -> anyhow::Result<()> {
+    let mut prev_entry: Option<&BeaconHistoryEntry<Proof>> = None;
+    for entry in entries {
+        let message = &entry.output.message;
+        ensure!(
+            message.epoch == epoch,
+            "Beacon history entry for {:?} is not in epoch {}",
+            message,
+            epoch
+        );
+        let expected_prev_entry_hash = match prev_entry {
+            Some(prev_entry) => {
+                ensure!(
+                    message.round > prev_entry.output.message.round,
+                    "Beacon history entry for {:?} is out of order",
+                    message
+                );
+                prev_entry.hash()
+            },
+            None => genesis_entry_hash(epoch),
+        };
+        ensure!(
+            entry.prev_entry_hash == expected_prev_entry_hash,
+            "Beacon history entry for {:?} does not commit to the previous entry",
+            message
+        );
+        prev_entry = Some(entry);
+    }
+    Ok(())
+}
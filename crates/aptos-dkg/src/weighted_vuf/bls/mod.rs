@@ -55,6 +55,16 @@ impl WeightedVUF for BlsWUF {
         (sk, pk)
     }
 
+    fn rotate_augmentation<R: rand_core::RngCore + rand_core::CryptoRng>(
+        _pp: &Self::PublicParameters,
+        ask: Self::AugmentedSecretKeyShare,
+        apk: Self::AugmentedPubKeyShare,
+        _rng: &mut R,
+    ) -> (Self::AugmentedSecretKeyShare, Self::AugmentedPubKeyShare) {
+        // There is no augmentation to rotate
+        (ask, apk)
+    }
+
     fn get_public_delta(_apk: &Self::AugmentedPubKeyShare) -> &Self::Delta {
         &()
     }
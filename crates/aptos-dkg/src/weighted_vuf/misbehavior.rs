@@ -0,0 +1,156 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured misbehavior reports for WVUF participants.
+//!
+//! When a player's proof share fails `verify_share`, or a player's delta fails `augment_pubkey`,
+//! we produce a serializable `MisbehaviorReport` containing the offending data and enough context
+//! to re-run the failed check. A report can then be embedded in, e.g., a validator transaction and
+//! verified independently by anyone who has an authenticated view of the offender's (augmented)
+//! public key share via `verify_misbehavior_report`.
+
+use crate::{pvss::Player, weighted_vuf::traits::WeightedVUF};
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+/// The class of check that a player failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureClass {
+    /// The player's proof share did not verify against its augmented public key share.
+    InvalidProofShare,
+    /// The player's delta could not be used to augment its public key share.
+    InvalidDelta,
+}
+
+/// The offending data, together with the context needed to re-run the failed check.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MisbehaviorEvidence<Delta, ProofShare> {
+    /// A proof share that failed verification on the message `msg`.
+    ProofShare {
+        msg: Vec<u8>,
+        proof_share: ProofShare,
+    },
+    /// A delta that failed public key augmentation.
+    Delta { delta: Delta },
+}
+
+impl<Delta, ProofShare> MisbehaviorEvidence<Delta, ProofShare> {
+    pub fn get_failure_class(&self) -> FailureClass {
+        match self {
+            MisbehaviorEvidence::ProofShare { .. } => FailureClass::InvalidProofShare,
+            MisbehaviorEvidence::Delta { .. } => FailureClass::InvalidDelta,
+        }
+    }
+}
+
+/// A report of a player that sent invalid WVUF data.
+///
+/// `reason` is the error returned by the failed check. It is only meant for diagnostics and is
+/// never relied upon when verifying the report.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MisbehaviorReport<Delta, ProofShare> {
+    pub player: Player,
+    pub failure_class: FailureClass,
+    pub evidence: MisbehaviorEvidence<Delta, ProofShare>,
+    pub reason: String,
+}
+
+impl<Delta, ProofShare> MisbehaviorReport<Delta, ProofShare> {
+    pub fn get_player(&self) -> &Player {
+        &self.player
+    }
+
+    pub fn get_failure_class(&self) -> FailureClass {
+        self.failure_class
+    }
+}
+
+/// The misbehavior report type for a specific WVUF scheme.
+pub type WvufMisbehaviorReport<WVUF> =
+    MisbehaviorReport<<WVUF as WeightedVUF>::Delta, <WVUF as WeightedVUF>::ProofShare>;
+
+/// Like `WeightedVUF::verify_share`, but returns a `MisbehaviorReport` on failure.
+pub fn verify_share_or_report<WVUF: WeightedVUF>(
+    pp: &WVUF::PublicParameters,
+    player: &Player,
+    apk: &WVUF::AugmentedPubKeyShare,
+    msg: &[u8],
+    proof_share: &WVUF::ProofShare,
+) -> Result<(), Box<WvufMisbehaviorReport<WVUF>>>
+where
+    WVUF::ProofShare: Clone,
+{
+    WVUF::verify_share(pp, apk, msg, proof_share).map_err(|e| {
+        Box::new(MisbehaviorReport {
+            player: *player,
+            failure_class: FailureClass::InvalidProofShare,
+            evidence: MisbehaviorEvidence::ProofShare {
+                msg: msg.to_vec(),
+                proof_share: proof_share.clone(),
+            },
+            reason: e.to_string(),
+        })
+    })
+}
+
+/// Like `WeightedVUF::augment_pubkey`, but returns a `MisbehaviorReport` on failure.
+pub fn augment_pubkey_or_report<WVUF: WeightedVUF>(
+    pp: &WVUF::PublicParameters,
+    player: &Player,
+    pk: WVUF::PubKeyShare,
+    delta: WVUF::Delta,
+) -> Result<WVUF::AugmentedPubKeyShare, Box<WvufMisbehaviorReport<WVUF>>> {
+    WVUF::augment_pubkey(pp, pk, delta.clone()).map_err(|e| {
+        Box::new(MisbehaviorReport {
+            player: *player,
+            failure_class: FailureClass::InvalidDelta,
+            evidence: MisbehaviorEvidence::Delta { delta },
+            reason: e.to_string(),
+        })
+    })
+}
+
+/// Verifies a misbehavior report by re-running the failed check on the evidence. Returns `Ok(())`
+/// if and only if the report is well-formed and the evidence indeed fails the check.
+///
+/// The caller must supply its own authenticated view of the offending player's public key share
+/// `pk` (e.g., from the DKG transcript) and, for proof share reports, of the player's augmented
+/// public key share `apk`. The report itself is never trusted to carry these.
+pub fn verify_misbehavior_report<WVUF: WeightedVUF>(
+    pp: &WVUF::PublicParameters,
+    report: &WvufMisbehaviorReport<WVUF>,
+    pk: &WVUF::PubKeyShare,
+    apk: Option<&WVUF::AugmentedPubKeyShare>,
+) -> anyhow::Result<()> {
+    if report.failure_class != report.evidence.get_failure_class() {
+        bail!(
+            "Misbehavior report for player {} claims failure class {:?} but carries evidence for {:?}",
+            report.player.id,
+            report.failure_class,
+            report.evidence.get_failure_class()
+        );
+    }
+
+    let check_passed = match &report.evidence {
+        MisbehaviorEvidence::ProofShare { msg, proof_share } => {
+            let apk = apk.ok_or(anyhow!(
+                "Missing APK for player {} when verifying proof share misbehavior report",
+                report.player.id
+            ))?;
+            WVUF::verify_share(pp, apk, msg.as_slice(), proof_share).is_ok()
+        },
+        MisbehaviorEvidence::Delta { delta } => {
+            WVUF::augment_pubkey(pp, pk.clone(), delta.clone()).is_ok()
+        },
+    };
+
+    if check_passed {
+        bail!(
+            "Misbehavior report for player {} is invalid: the evidence passes the {:?} check",
+            report.player.id,
+            report.failure_class
+        );
+    }
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod beacon;
 pub mod bls;
+pub mod misbehavior;
 pub mod pinkas;
 pub mod traits;
@@ -5,29 +5,36 @@ use crate::{
     algebra::{lagrange::lagrange_coefficients, polynomials::get_powers_of_tau},
     pvss,
     pvss::{
-        dealt_pub_key_share::g2::DealtPubKeyShare, traits::HasEncryptionPublicParams, Player,
-        WeightedConfig,
+        dealt_pub_key_share::g2::DealtPubKeyShare, dealt_secret_key_share::g1::DealtSecretKeyShare,
+        traits::HasEncryptionPublicParams, Player, WeightedConfig,
     },
     utils::{
-        g1_multi_exp, g2_multi_exp, multi_pairing, parallel_multi_pairing,
-        random::{random_nonzero_scalar, random_scalar},
+        g1_multi_exp, g2_multi_exp, hash_to_scalar, multi_pairing, parallel_multi_pairing,
+        random::random_nonzero_scalar,
+        secret::{group_element_ct_eq, scalar_ct_eq, zeroize_scalar},
     },
     weighted_vuf::traits::WeightedVUF,
 };
 use anyhow::{anyhow, bail};
+use aptos_crypto_derive::SilentDebug;
 use blstrs::{pairing, G1Projective, G2Projective, Gt, Scalar};
 use ff::Field;
 use group::{Curve, Group};
-use rand::thread_rng;
 use rayon::{
     iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator},
     ThreadPool,
 };
 use serde::{Deserialize, Serialize};
 use std::ops::{Mul, Neg, Range};
+#[cfg(feature = "mlock")]
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub const PINKAS_WVUF_DST: &[u8; 21] = b"APTOS_PINKAS_WVUF_DST";
 
+/// Domain-separator tag (DST) for the Fiat-Shamir hashing used to derive the randomness that
+/// combines the checks in `augment_pubkey` and `verify_proof`.
+pub const PINKAS_WVUF_FIAT_SHAMIR_DST: &[u8; 33] = b"APTOS_PINKAS_WVUF_FIAT_SHAMIR_DST";
+
 // For the worst-case (higher number of players, with fewer shares each), setting to 1 or 4 is not good, so using 2.
 pub const MIN_MULTIEXP_NUM_JOBS: usize = 2;
 
@@ -37,10 +44,95 @@ pub const MIN_MULTIPAIR_NUM_JOBS: usize = 4;
 
 pub struct PinkasWUF;
 
+/// The Lagrange coefficients (at zero) of the virtual players of a subset of players. These only
+/// depend on the subset, so they can be reused across evaluations derived from the same subset.
+pub type LagrangeCoeffs = Vec<Scalar>;
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RandomizedPKs {
-    pi: G1Projective,       // \hat{g}^{r}
-    rks: Vec<G1Projective>, // g^{r \sk_i}, for all shares i
+    pub(crate) pi: G1Projective,       // \hat{g}^{r}
+    pub(crate) rks: Vec<G1Projective>, // g^{r \sk_i}, for all shares i
+}
+
+/// A player's augmented secret key share: the inverse of the player's augmentation scalar $r$,
+/// together with the player's dealt secret key shares.
+///
+/// NOTE: This (de)serializes like an `(r_inv, sk)` tuple, so it is compatible with previously
+/// persisted augmented key pairs. The secrets are never printed, are compared in constant time,
+/// and are zeroized on drop. With the `mlock` feature, their memory can also be locked via
+/// `lock_memory` (`r_inv` is boxed, so that it does not move along with the share).
+///
+/// The share is deliberately not `Clone`, so that the secrets are never implicitly copied into
+/// memory that is neither locked nor zeroized.
+#[derive(SilentDebug, Serialize, Deserialize)]
+pub struct AugmentedSecretKeyShare {
+    r_inv: Box<Scalar>,           // r^{-1}
+    sk: Vec<DealtSecretKeyShare>, // the dealt secret key shares
+    #[cfg(feature = "mlock")]
+    #[serde(skip)]
+    locked: AtomicBool, // whether the memory of the secrets is locked
+}
+
+impl AugmentedSecretKeyShare {
+    /// Locks the memory of the secrets, so that it is never swapped out to disk. The memory is
+    /// unlocked (after being zeroized) when the share is dropped. Locking an already locked share
+    /// is a no-op.
+    #[cfg(feature = "mlock")]
+    pub fn lock_memory(&self) -> anyhow::Result<()> {
+        if self.locked.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let r_inv = std::slice::from_ref(&*self.r_inv);
+        if let Err(error) = crate::utils::secret::mlock_slice(r_inv) {
+            self.locked.store(false, Ordering::SeqCst);
+            return Err(error);
+        }
+        if let Err(error) = crate::utils::secret::mlock_slice(&self.sk) {
+            crate::utils::secret::munlock_slice(r_inv);
+            self.locked.store(false, Ordering::SeqCst);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Unlocks the memory of the secrets, if it was locked via `lock_memory`.
+    #[cfg(feature = "mlock")]
+    fn unlock_memory(&mut self) {
+        if std::mem::take(self.locked.get_mut()) {
+            crate::utils::secret::munlock_slice(std::slice::from_ref(&*self.r_inv));
+            crate::utils::secret::munlock_slice(&self.sk);
+        }
+    }
+}
+
+impl PartialEq for AugmentedSecretKeyShare {
+    fn eq(&self, other: &Self) -> bool {
+        // The number of shares is public (i.e., it is the weight of the player)
+        if self.sk.len() != other.sk.len() {
+            return false;
+        }
+
+        let mut eq = scalar_ct_eq(&self.r_inv, &other.r_inv);
+        for (sk, other_sk) in self.sk.iter().zip(other.sk.iter()) {
+            eq &= group_element_ct_eq(sk.as_group_element(), other_sk.as_group_element());
+        }
+        eq.into()
+    }
+}
+
+impl Drop for AugmentedSecretKeyShare {
+    fn drop(&mut self) {
+        // Zeroize the shares in place, before their memory is (potentially) unlocked
+        zeroize_scalar(&mut self.r_inv);
+        for sk in self.sk.iter_mut() {
+            sk.zeroize();
+        }
+
+        #[cfg(feature = "mlock")]
+        self.unlock_memory();
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -65,7 +157,7 @@ impl From<&pvss::das::PublicParameters> for PublicParameters {
 /// of secret key and public key.
 impl WeightedVUF for PinkasWUF {
     type AugmentedPubKeyShare = (RandomizedPKs, Self::PubKeyShare);
-    type AugmentedSecretKeyShare = (Scalar, Self::SecretKeyShare);
+    type AugmentedSecretKeyShare = AugmentedSecretKeyShare;
     // /// Note: Our BLS PKs are currently in G_1.
     // type BlsPubKey = bls12381::PublicKey;
     // type BlsSecretKey = bls12381::PrivateKey;
@@ -98,7 +190,30 @@ impl WeightedVUF for PinkasWUF {
                 .collect::<Vec<G1Projective>>(),
         };
 
-        ((r.invert().unwrap(), sk), (rpks, pk))
+        let ask = AugmentedSecretKeyShare {
+            r_inv: Box::new(r.invert().unwrap()),
+            sk,
+            #[cfg(feature = "mlock")]
+            locked: AtomicBool::new(false),
+        };
+
+        (ask, (rpks, pk))
+    }
+
+    fn rotate_augmentation<R: rand_core::RngCore + rand_core::CryptoRng>(
+        pp: &Self::PublicParameters,
+        mut ask: Self::AugmentedSecretKeyShare,
+        apk: Self::AugmentedPubKeyShare,
+        rng: &mut R,
+    ) -> (Self::AugmentedSecretKeyShare, Self::AugmentedPubKeyShare) {
+        // The old r^{-1} is zeroized when `ask` is dropped. The shares are moved out of `ask`, so
+        // their memory is unlocked first (the new share can be locked again via `lock_memory`).
+        #[cfg(feature = "mlock")]
+        ask.unlock_memory();
+        let sk = std::mem::take(&mut ask.sk);
+        let (_, pk) = apk;
+
+        Self::augment_key_pair(pp, sk, pk, rng)
     }
 
     fn get_public_delta(apk: &Self::AugmentedPubKeyShare) -> &Self::Delta {
@@ -121,13 +236,12 @@ impl WeightedVUF for PinkasWUF {
             );
         }
 
-        // TODO: Fiat-Shamir transform instead of RNG
-        let tau = random_scalar(&mut thread_rng());
-
         let pks = pk
             .iter()
             .map(|pk| *pk.as_group_element())
             .collect::<Vec<G2Projective>>();
+
+        let tau = Self::augment_pubkey_challenge(pp, &pks, &delta);
         let taus = get_powers_of_tau(&tau, pks.len());
 
         let pks_combined = g2_multi_exp(&pks[..], &taus[..]);
@@ -145,11 +259,9 @@ impl WeightedVUF for PinkasWUF {
     }
 
     fn create_share(ask: &Self::AugmentedSecretKeyShare, msg: &[u8]) -> Self::ProofShare {
-        let (r_inv, _) = ask;
-
         let hash = Self::hash_to_curve(msg);
 
-        hash.mul(r_inv)
+        hash.mul(&*ask.r_inv)
     }
 
     fn verify_share(
@@ -171,6 +283,40 @@ impl WeightedVUF for PinkasWUF {
         Ok(())
     }
 
+    /// Verifies all the proof shares in a single multi-pairing, by combining their checks with
+    /// Fiat-Shamir randomness. Only if the combined check fails, the shares are verified one by
+    /// one to identify the culprits.
+    fn verify_shares_batch(
+        pp: &Self::PublicParameters,
+        msg: &[u8],
+        apks_and_proofs: &[(Player, Self::AugmentedPubKeyShare, Self::ProofShare)],
+    ) -> Vec<Player> {
+        if apks_and_proofs.is_empty() {
+            return vec![];
+        }
+
+        let pis = apks_and_proofs
+            .iter()
+            .map(|(_, apk, _)| Self::get_public_delta(apk).pi)
+            .collect::<Vec<G1Projective>>();
+        let proof = apks_and_proofs
+            .iter()
+            .map(|(player, _, share)| (*player, *share))
+            .collect::<Self::Proof>();
+
+        let tau = Self::verify_shares_batch_challenge(pp, msg, &proof, &pis);
+        if Self::batch_pairing_check(pp, msg, &proof, &pis, &tau) {
+            return vec![];
+        }
+
+        // Fall back to verifying each share, to identify the culprits
+        apks_and_proofs
+            .iter()
+            .filter(|(_, apk, share)| Self::verify_share(pp, apk, msg, share).is_err())
+            .map(|(player, _, _)| *player)
+            .collect()
+    }
+
     fn aggregate_shares(
         _wc: &WeightedConfig,
         apks_and_proofs: &[(Player, Self::AugmentedPubKeyShare, Self::ProofShare)],
@@ -221,18 +367,6 @@ impl WeightedVUF for PinkasWUF {
             bail!("Number of proof shares ({}) exceeds number of APKs ({}) when verifying aggregated WVUF proof", proof.len(), apks.len());
         }
 
-        // TODO: Fiat-Shamir transform instead of RNG
-        let tau = random_scalar(&mut thread_rng());
-        let taus = get_powers_of_tau(&tau, proof.len());
-
-        // [share_i^{\tau^i}]_{i \in [0, n)}
-        let shares = proof
-            .iter()
-            .map(|(_, share)| share)
-            .zip(taus.iter())
-            .map(|(share, tau)| share.mul(tau))
-            .collect::<Vec<G2Projective>>();
-
         let mut pis = Vec::with_capacity(proof.len());
         for (player, _) in proof {
             if player.id >= apks.len() {
@@ -252,14 +386,8 @@ impl WeightedVUF for PinkasWUF {
             );
         }
 
-        let h = Self::hash_to_curve(msg);
-        let sum_of_taus: Scalar = taus.iter().sum();
-
-        if multi_pairing(
-            pis.iter().chain([pp.g_neg].iter()),
-            shares.iter().chain([h.mul(sum_of_taus)].iter()),
-        ) != Gt::identity()
-        {
+        let tau = Self::verify_proof_challenge(pp, msg, proof, &pis);
+        if !Self::batch_pairing_check(pp, msg, proof, &pis, &tau) {
             bail!("Multipairing check in batched aggregate verification failed");
         }
 
@@ -272,6 +400,82 @@ impl PinkasWUF {
         G2Projective::hash_to_curve(msg, &PINKAS_WVUF_DST[..], b"H(m)")
     }
 
+    /// Derives the Fiat-Shamir challenge $\tau$ used to combine the pairing checks of all the RKs
+    /// in `augment_pubkey`, by hashing the public parameters, the PKs and the randomized PKs.
+    pub fn augment_pubkey_challenge(
+        pp: &PublicParameters,
+        pks: &[G2Projective],
+        delta: &RandomizedPKs,
+    ) -> Scalar {
+        let mut input = FiatShamirInput::new(b"augment_pubkey", pp);
+        input.append_g2s(pks.iter());
+        input.append_g1s([&delta.pi].into_iter());
+        input.append_g1s(delta.rks.iter());
+        input.challenge()
+    }
+
+    /// Derives the Fiat-Shamir challenge $\tau$ used to combine the pairing checks of all the
+    /// proof shares in `verify_proof`, by hashing the public parameters, the message, the proof
+    /// shares and the $\pi$'s of their players.
+    pub fn verify_proof_challenge(
+        pp: &PublicParameters,
+        msg: &[u8],
+        proof: &<Self as WeightedVUF>::Proof,
+        pis: &[G1Projective],
+    ) -> Scalar {
+        let mut input = FiatShamirInput::new(b"verify_proof", pp);
+        input.append_bytes(msg);
+        input.append_players(proof.iter().map(|(player, _)| player));
+        input.append_g1s(pis.iter());
+        input.append_g2s(proof.iter().map(|(_, share)| share));
+        input.challenge()
+    }
+
+    /// Derives the Fiat-Shamir challenge $\tau$ used to combine the pairing checks of the proof
+    /// shares in `verify_shares_batch`. Same inputs as `verify_proof_challenge`, but a different
+    /// label, so that the challenges of the two checks are independent.
+    pub fn verify_shares_batch_challenge(
+        pp: &PublicParameters,
+        msg: &[u8],
+        proof: &<Self as WeightedVUF>::Proof,
+        pis: &[G1Projective],
+    ) -> Scalar {
+        let mut input = FiatShamirInput::new(b"verify_shares_batch", pp);
+        input.append_bytes(msg);
+        input.append_players(proof.iter().map(|(player, _)| player));
+        input.append_g1s(pis.iter());
+        input.append_g2s(proof.iter().map(|(_, share)| share));
+        input.challenge()
+    }
+
+    /// Checks $\prod_i e(\pi_i, share_i^{\tau^i}) = e(g, H(m)^{\sum_i \tau^i})$ in a single
+    /// multi-pairing, which holds (w.h.p.) iff every share verifies against its player's $\pi_i$.
+    fn batch_pairing_check(
+        pp: &PublicParameters,
+        msg: &[u8],
+        proof: &<Self as WeightedVUF>::Proof,
+        pis: &[G1Projective],
+        tau: &Scalar,
+    ) -> bool {
+        let taus = get_powers_of_tau(tau, proof.len());
+
+        // [share_i^{\tau^i}]_{i \in [0, n)}
+        let shares = proof
+            .iter()
+            .map(|(_, share)| share)
+            .zip(taus.iter())
+            .map(|(share, tau)| share.mul(tau))
+            .collect::<Vec<G2Projective>>();
+
+        let h = Self::hash_to_curve(msg);
+        let sum_of_taus: Scalar = taus.iter().sum();
+
+        multi_pairing(
+            pis.iter().chain([pp.g_neg].iter()),
+            shares.iter().chain([h.mul(sum_of_taus)].iter()),
+        ) == Gt::identity()
+    }
+
     pub fn collect_lagrange_coeffs_shares_and_rks<'a>(
         wc: &WeightedConfig,
         apks: &'a [Option<(RandomizedPKs, Vec<DealtPubKeyShare>)>],
@@ -282,8 +486,65 @@ impl PinkasWUF {
         Vec<Scalar>,
         Vec<Range<usize>>,
     )> {
+        let (shares, rks, ranges) = Self::collect_shares_and_rks(wc, apks, proof)?;
+        let lagr = Self::lagrange_coeffs(wc, proof);
+        Ok((shares, rks, lagr, ranges))
+    }
+
+    /// Computes the Lagrange coefficients associated with the evaluation points of the players'
+    /// sub shares, in the order of the players in `proof`.
+    pub fn lagrange_coeffs(
+        wc: &WeightedConfig,
+        proof: &<Self as WeightedVUF>::Proof,
+    ) -> LagrangeCoeffs {
         // Collect all the evaluation points associated with each player's augmented pubkey sub shares.
         let mut sub_player_ids = Vec::with_capacity(wc.get_total_weight());
+        for (player, _) in proof {
+            for j in 0..wc.get_player_weight(player) {
+                sub_player_ids.push(wc.get_virtual_player(player, j).id);
+            }
+        }
+
+        let batch_dom = wc.get_batch_evaluation_domain();
+        lagrange_coefficients(batch_dom, &sub_player_ids[..], &Scalar::ZERO)
+    }
+
+    /// Same as `derive_eval`, but reuses the Lagrange coefficients `lagr` previously returned by
+    /// `lagrange_coeffs` for a proof with the same players (in the same order).
+    pub fn derive_eval_with_lagrange_coeffs(
+        wc: &WeightedConfig,
+        apks: &[Option<<Self as WeightedVUF>::AugmentedPubKeyShare>],
+        proof: &<Self as WeightedVUF>::Proof,
+        lagr: &LagrangeCoeffs,
+        thread_pool: &ThreadPool,
+    ) -> anyhow::Result<<Self as WeightedVUF>::Evaluation> {
+        let (rhs, rks, ranges) = Self::collect_shares_and_rks(wc, apks, proof)?;
+
+        let num_sub_players = ranges.last().map_or(0, |range| range.end);
+        if lagr.len() != num_sub_players {
+            bail!(
+                "Expected {} Lagrange coefficients for the players in the proof, but got {}",
+                num_sub_players,
+                lagr.len()
+            );
+        }
+
+        // Compute the RK multiexps in parallel
+        let lhs = Self::rk_multiexps(proof, rks, lagr, &ranges, thread_pool);
+
+        // Interpolate the WVUF evaluation in parallel
+        Ok(Self::multi_pairing(lhs, rhs, thread_pool))
+    }
+
+    fn collect_shares_and_rks<'a>(
+        wc: &WeightedConfig,
+        apks: &'a [Option<(RandomizedPKs, Vec<DealtPubKeyShare>)>],
+        proof: &'a Vec<(Player, <Self as WeightedVUF>::ProofShare)>,
+    ) -> anyhow::Result<(
+        Vec<&'a G2Projective>,
+        Vec<&'a Vec<G1Projective>>,
+        Vec<Range<usize>>,
+    )> {
         // The G2 shares
         let mut shares = Vec::with_capacity(proof.len());
         // The RKs of each player
@@ -293,10 +554,6 @@ impl PinkasWUF {
 
         let mut k = 0;
         for (player, share) in proof {
-            for j in 0..wc.get_player_weight(player) {
-                sub_player_ids.push(wc.get_virtual_player(player, j).id);
-            }
-
             let apk = apks[player.id]
                 .as_ref()
                 .ok_or(anyhow!("Missing APK for player {}", player.get_id()))?;
@@ -309,10 +566,7 @@ impl PinkasWUF {
             k += w;
         }
 
-        // Compute the Lagrange coefficients associated with those evaluation points
-        let batch_dom = wc.get_batch_evaluation_domain();
-        let lagr = lagrange_coefficients(batch_dom, &sub_player_ids[..], &Scalar::ZERO);
-        Ok((shares, rks, lagr, ranges))
+        Ok((shares, rks, ranges))
     }
 
     pub fn rk_multiexps(
@@ -349,3 +603,122 @@ impl PinkasWUF {
         )
     }
 }
+
+/// The public inputs hashed into a Fiat-Shamir challenge. Group elements are appended in compressed
+/// form, and every list is prefixed by its length, so that distinct inputs have distinct encodings.
+struct FiatShamirInput {
+    bytes: Vec<u8>,
+}
+
+impl FiatShamirInput {
+    fn new(label: &[u8], pp: &PublicParameters) -> Self {
+        let mut input = FiatShamirInput { bytes: vec![] };
+        input.append_bytes(label);
+        input.append_g1s([&pp.g].into_iter());
+        input.append_g2s([&pp.g_hat].into_iter());
+        input
+    }
+
+    fn append_len(&mut self, len: usize) {
+        self.bytes.extend_from_slice(&(len as u64).to_le_bytes());
+    }
+
+    fn append_bytes(&mut self, bytes: &[u8]) {
+        self.append_len(bytes.len());
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn append_players<'a>(&mut self, players: impl ExactSizeIterator<Item = &'a Player>) {
+        self.append_len(players.len());
+        for player in players {
+            self.append_len(player.id);
+        }
+    }
+
+    fn append_g1s<'a>(&mut self, elems: impl ExactSizeIterator<Item = &'a G1Projective>) {
+        self.append_len(elems.len());
+        for elem in elems {
+            self.bytes.extend_from_slice(&elem.to_compressed());
+        }
+    }
+
+    fn append_g2s<'a>(&mut self, elems: impl ExactSizeIterator<Item = &'a G2Projective>) {
+        self.append_len(elems.len());
+        for elem in elems {
+            self.bytes.extend_from_slice(&elem.to_compressed());
+        }
+    }
+
+    fn challenge(self) -> Scalar {
+        hash_to_scalar(&self.bytes, &PINKAS_WVUF_FIAT_SHAMIR_DST[..])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        pvss::Player,
+        weighted_vuf::pinkas::{PinkasWUF, PublicParameters, RandomizedPKs},
+    };
+    use blstrs::{G1Projective, G2Projective};
+    use group::Group;
+    use std::ops::Neg;
+
+    fn test_public_parameters() -> PublicParameters {
+        PublicParameters {
+            g: G1Projective::generator(),
+            g_neg: G1Projective::generator().neg(),
+            g_hat: G2Projective::generator(),
+        }
+    }
+
+    /// Locks in the Fiat-Shamir challenges, which must be the same across all validators.
+    #[test]
+    fn test_fiat_shamir_challenge_vectors() {
+        let pp = test_public_parameters();
+
+        let pks = vec![G2Projective::generator(), G2Projective::identity()];
+        let delta = RandomizedPKs {
+            pi: G1Projective::generator(),
+            rks: vec![G1Projective::identity(), G1Projective::generator()],
+        };
+        let tau = PinkasWUF::augment_pubkey_challenge(&pp, &pks, &delta);
+        assert_eq!(
+            hex::encode(tau.to_bytes_le()),
+            "ae80b793cd1de0c76460e848e8981b645a6ba82434f2be74255084f23259f53b"
+        );
+
+        let proof = vec![
+            (Player { id: 0 }, G2Projective::generator()),
+            (Player { id: 2 }, G2Projective::identity()),
+        ];
+        let pis = vec![G1Projective::generator(), G1Projective::identity()];
+        let tau = PinkasWUF::verify_proof_challenge(&pp, b"some msg", &proof, &pis);
+        assert_eq!(
+            hex::encode(tau.to_bytes_le()),
+            "143eb5912cf4670b75f7d9e7bc405e2cb7ba1db774e64c4a7a0e24f168bec424"
+        );
+    }
+
+    #[test]
+    fn test_fiat_shamir_challenge_binds_inputs() {
+        let pp = test_public_parameters();
+        let proof = vec![(Player { id: 0 }, G2Projective::generator())];
+        let pis = vec![G1Projective::generator()];
+        let tau = PinkasWUF::verify_proof_challenge(&pp, b"some msg", &proof, &pis);
+
+        assert_eq!(
+            tau,
+            PinkasWUF::verify_proof_challenge(&pp, b"some msg", &proof, &pis)
+        );
+        assert_ne!(
+            tau,
+            PinkasWUF::verify_proof_challenge(&pp, b"other msg", &proof, &pis)
+        );
+        let other_proof = vec![(Player { id: 1 }, G2Projective::generator())];
+        assert_ne!(
+            tau,
+            PinkasWUF::verify_proof_challenge(&pp, b"some msg", &other_proof, &pis)
+        );
+    }
+}
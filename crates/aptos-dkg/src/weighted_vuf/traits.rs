@@ -38,6 +38,18 @@ pub trait WeightedVUF {
         rng: &mut R,
     ) -> (Self::AugmentedSecretKeyShare, Self::AugmentedPubKeyShare);
 
+    /// Rotates the augmentation of a key pair (e.g., periodically, or if the augmentation may
+    /// have leaked) without redoing the DKG: the dealt secret and public key shares are kept, and
+    /// only the new `Delta` has to be re-broadcast to (and verified via `augment_pubkey` by) the
+    /// other players. Shares created with the old augmented key pair no longer verify against the
+    /// new one.
+    fn rotate_augmentation<R: rand_core::RngCore + rand_core::CryptoRng>(
+        pp: &Self::PublicParameters,
+        ask: Self::AugmentedSecretKeyShare,
+        apk: Self::AugmentedPubKeyShare,
+        rng: &mut R,
+    ) -> (Self::AugmentedSecretKeyShare, Self::AugmentedPubKeyShare);
+
     fn get_public_delta(pk: &Self::AugmentedPubKeyShare) -> &Self::Delta;
 
     fn augment_pubkey(
@@ -55,6 +67,21 @@ pub trait WeightedVUF {
         proof: &Self::ProofShare,
     ) -> anyhow::Result<()>;
 
+    /// Verifies the proof shares of many players for the same message, returning the players
+    /// whose shares failed to verify (i.e., an empty vector iff all shares are valid). The
+    /// default implementation verifies each share individually.
+    fn verify_shares_batch(
+        pp: &Self::PublicParameters,
+        msg: &[u8],
+        apks_and_proofs: &[(Player, Self::AugmentedPubKeyShare, Self::ProofShare)],
+    ) -> Vec<Player> {
+        apks_and_proofs
+            .iter()
+            .filter(|(_, apk, proof)| Self::verify_share(pp, apk, msg, proof).is_err())
+            .map(|(player, _, _)| *player)
+            .collect()
+    }
+
     fn aggregate_shares(
         wc: &WeightedConfig,
         apks_and_proofs: &[(Player, Self::AugmentedPubKeyShare, Self::ProofShare)],
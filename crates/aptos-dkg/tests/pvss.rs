@@ -78,6 +78,98 @@ fn test_pvss_all_weighted() {
     }
 }
 
+#[test]
+fn test_pvss_weighted_streaming_verification() {
+    let mut rng = thread_rng();
+
+    for wc in test_utils::get_weighted_configs_for_testing() {
+        println!("\nTesting {wc} streaming verification");
+        let seed = random_scalar(&mut rng);
+        let mut rng = StdRng::from_seed(seed.to_bytes_le());
+        let d = test_utils::setup_dealing::<das::WeightedTranscript, StdRng>(&wc, &mut rng);
+
+        // Every player deals, and the transcripts are aggregated
+        let n = wc.get_total_num_players();
+        let mut trx = das::WeightedTranscript::deal(
+            &wc,
+            &d.pp,
+            &d.ssks[0],
+            &d.eks,
+            &d.iss[0],
+            &NoAux,
+            &wc.get_player(0),
+            &mut rng,
+        );
+        for i in 1..n {
+            let other = das::WeightedTranscript::deal(
+                &wc,
+                &d.pp,
+                &d.ssks[i],
+                &d.eks,
+                &d.iss[i],
+                &NoAux,
+                &wc.get_player(i),
+                &mut rng,
+            );
+            trx.aggregate_with(&wc, &other);
+        }
+        let aux = vec![NoAux; n];
+
+        trx.verify(&wc, &d.pp, &d.spks, &d.eks, &aux)
+            .expect("aggregated PVSS transcript failed verification");
+        for chunk_size in [1, 2, n] {
+            trx.verify_streaming(
+                &wc,
+                &d.pp,
+                &d.eks,
+                d.spks.iter().cloned().zip(aux.iter().cloned()),
+                chunk_size,
+            )
+            .expect("aggregated PVSS transcript failed streaming verification");
+        }
+
+        // Too few or too many signing PKs are rejected
+        assert!(trx
+            .verify_streaming(
+                &wc,
+                &d.pp,
+                &d.eks,
+                d.spks.iter().cloned().zip(aux.iter().cloned()).take(n - 1),
+                1
+            )
+            .is_err());
+        assert!(trx
+            .verify_streaming(
+                &wc,
+                &d.pp,
+                &d.eks,
+                d.spks
+                    .iter()
+                    .cloned()
+                    .chain(d.spks.iter().cloned())
+                    .zip(vec![NoAux; 2 * n]),
+                1
+            )
+            .is_err());
+
+        // Signing PKs in the wrong order are rejected (whenever there is more than one dealer)
+        if n > 1 {
+            let mut spks = d.spks.clone();
+            spks.swap(0, 1);
+            assert!(trx.verify(&wc, &d.pp, &spks, &d.eks, &aux).is_err());
+            assert!(trx
+                .verify_streaming(
+                    &wc,
+                    &d.pp,
+                    &d.eks,
+                    spks.into_iter().zip(aux.iter().cloned()),
+                    1
+                )
+                .is_err());
+        }
+    }
+}
+
 #[test]
 fn test_pvss_transcript_size() {
     for sc in get_threshold_configs_for_benchmarking() {
@@ -0,0 +1,99 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_dkg::pvss::weighted::rounding::{
+    compute_weight_rounding_profile, compute_weighted_config,
+};
+use fixed::types::U64F64;
+
+fn ratio(numerator: u64, denominator: u64) -> U64F64 {
+    U64F64::from_num(numerator) / U64F64::from_num(denominator)
+}
+
+#[test]
+fn test_equal_stakes_have_equal_weights() {
+    let stakes = vec![100; 10];
+    let (wc, profile) = compute_weighted_config(&stakes, 50, ratio(1, 2), ratio(2, 3)).unwrap();
+
+    assert_eq!(profile.weights, vec![5; 10]);
+    assert_eq!(profile.rounding_error, U64F64::from_num(0));
+    // Without rounding errors, the threshold is just above the secrecy threshold
+    assert_eq!(profile.reconstruct_threshold, 26);
+    assert_eq!(wc.get_total_weight(), 50);
+    assert_eq!(wc.get_threshold_weight(), 26);
+}
+
+#[test]
+fn test_rounding_bounds() {
+    let stakes = vec![
+        7_000_000, 3_141_592, 2_718_281, 1_000_000, 999_999, 123_456, 654_321, 42, 500_000,
+    ];
+    let stake_sum: u64 = stakes.iter().sum();
+    let secrecy_threshold = ratio(1, 2);
+
+    for target_total_weight in [20, 100, 1000] {
+        let profile =
+            compute_weight_rounding_profile(&stakes, target_total_weight, secrecy_threshold)
+                .unwrap();
+        assert!(profile.total_weight() >= target_total_weight);
+        assert!(profile.weights.iter().all(|weight| *weight > 0));
+        assert!(profile.reconstruct_threshold_in_stake_ratio > secrecy_threshold);
+
+        // Check the bounds against all subsets of players
+        for subset in 0u32..(1 << stakes.len()) {
+            let (stake, weight) = (0..stakes.len())
+                .filter(|i| subset & (1 << i) != 0)
+                .fold((0, 0), |(stake, weight), i| {
+                    (stake + stakes[i], weight + profile.weights[i])
+                });
+            let stake_ratio = ratio(stake, stake_sum);
+            if stake_ratio <= secrecy_threshold {
+                assert!(weight < profile.reconstruct_threshold);
+            }
+            if stake_ratio >= profile.reconstruct_threshold_in_stake_ratio {
+                assert!(weight >= profile.reconstruct_threshold);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_larger_total_weight_reduces_gap() {
+    let stakes: Vec<u64> = (1..=50).map(|i| i * i * 1_000).collect();
+    let secrecy_threshold = ratio(1, 2);
+
+    let small = compute_weight_rounding_profile(&stakes, 100, secrecy_threshold).unwrap();
+    let large = compute_weight_rounding_profile(&stakes, 10_000, secrecy_threshold).unwrap();
+    assert!(large.stake_ratio_gap() < small.stake_ratio_gap());
+
+    // The security loss bound can only be met with the larger total weight
+    let max_reconstruct_threshold = ratio(6, 10);
+    assert!(
+        compute_weighted_config(&stakes, 100, secrecy_threshold, max_reconstruct_threshold)
+            .is_err()
+    );
+    assert!(compute_weighted_config(
+        &stakes,
+        10_000,
+        secrecy_threshold,
+        max_reconstruct_threshold
+    )
+    .is_ok());
+}
+
+#[test]
+fn test_zero_stakes_get_weight_one() {
+    let stakes = vec![1_000_000, 0, 1];
+    let profile = compute_weight_rounding_profile(&stakes, 10, ratio(1, 2)).unwrap();
+    assert_eq!(profile.weights, vec![10, 1, 1]);
+    assert_eq!(profile.total_weight(), 12);
+}
+
+#[test]
+fn test_invalid_inputs() {
+    assert!(compute_weight_rounding_profile(&[], 10, ratio(1, 2)).is_err());
+    assert!(compute_weight_rounding_profile(&[1, 2], 0, ratio(1, 2)).is_err());
+    assert!(compute_weight_rounding_profile(&[0, 0], 10, ratio(1, 2)).is_err());
+    assert!(compute_weight_rounding_profile(&[1, 2], 10, ratio(1, 1)).is_err());
+    assert!(compute_weight_rounding_profile(&[u64::MAX, 1], 10, ratio(1, 2)).is_err());
+}
@@ -0,0 +1,112 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measures the peak memory of SoK verification. This lives in its own test binary, since it
+//! installs a global allocator.
+use aptos_dkg::{
+    pvss::{
+        contribution::{batch_verify_soks, Contribution},
+        schnorr,
+        test_utils::{setup_soks, streaming_verify_soks, SoKAux},
+    },
+    utils::random::random_scalar,
+};
+use blstrs::G1Projective;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// An allocator that tracks the peak number of bytes allocated by the current thread, so that
+/// the measurements are not affected by allocations made concurrently by other tests.
+struct PeakTrackingAllocator;
+
+thread_local! {
+    static CURRENT_BYTES: Cell<usize> = const { Cell::new(0) };
+    static PEAK_BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for PeakTrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let _ = CURRENT_BYTES.try_with(|current| {
+                let bytes = current.get() + layout.size();
+                current.set(bytes);
+                let _ = PEAK_BYTES.try_with(|peak| peak.set(peak.get().max(bytes)));
+            });
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        let _ = CURRENT_BYTES
+            .try_with(|current| current.set(current.get().saturating_sub(layout.size())));
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakTrackingAllocator = PeakTrackingAllocator;
+
+/// Returns the result of `f` and the peak number of bytes it allocated (on top of the bytes
+/// that were already allocated when it was called).
+fn measure_peak_bytes<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = CURRENT_BYTES.with(|current| current.get());
+    PEAK_BYTES.with(|peak| peak.set(start));
+    let result = f();
+    let peak = PEAK_BYTES.with(|peak| peak.get());
+    (result, peak.saturating_sub(start))
+}
+
+#[test]
+fn test_streaming_verification_peak_memory() {
+    const NUM_DEALERS: usize = 256;
+    const CHUNK_SIZE: usize = 4;
+
+    let mut rng = StdRng::from_seed([2u8; 32]);
+    let (soks, spks, aux, g, pk) = setup_soks(NUM_DEALERS, &mut rng);
+    let tau = random_scalar(&mut rng);
+
+    let (result, batch_peak_bytes) =
+        measure_peak_bytes(|| batch_verify_soks(&soks, &g, &pk, &spks, &aux, &tau));
+    result.unwrap();
+
+    let (result, streaming_peak_bytes) =
+        measure_peak_bytes(|| streaming_verify_soks(&soks, &spks, &aux, &g, &pk, &tau, CHUNK_SIZE));
+    assert_eq!(result.unwrap(), NUM_DEALERS);
+
+    // The batch verifier materializes the PoKs, messages and signatures of all dealers
+    let min_batch_peak_bytes = NUM_DEALERS
+        * (std::mem::size_of::<schnorr::PoK<G1Projective>>()
+            + std::mem::size_of::<Contribution<G1Projective, SoKAux>>());
+    assert!(
+        batch_peak_bytes >= min_batch_peak_bytes,
+        "batch verification peaked at {} bytes, expected at least {}",
+        batch_peak_bytes,
+        min_batch_peak_bytes
+    );
+
+    // ...while the streaming verifier only materializes a single chunk at a time
+    assert!(
+        streaming_peak_bytes * 8 < batch_peak_bytes,
+        "streaming verification peaked at {} bytes, but batch verification at {} bytes",
+        streaming_peak_bytes,
+        batch_peak_bytes
+    );
+
+    // Doubling the number of dealers should not affect the streaming verifier's peak memory
+    let (soks, spks, aux, g, pk) = setup_soks(2 * NUM_DEALERS, &mut rng);
+    let (result, streaming_peak_bytes_2x) =
+        measure_peak_bytes(|| streaming_verify_soks(&soks, &spks, &aux, &g, &pk, &tau, CHUNK_SIZE));
+    assert_eq!(result.unwrap(), 2 * NUM_DEALERS);
+    assert!(
+        streaming_peak_bytes_2x <= streaming_peak_bytes + streaming_peak_bytes / 2,
+        "streaming verification peaked at {} bytes for {} dealers, but at {} bytes for {}",
+        streaming_peak_bytes_2x,
+        2 * NUM_DEALERS,
+        streaming_peak_bytes,
+        NUM_DEALERS
+    );
+}
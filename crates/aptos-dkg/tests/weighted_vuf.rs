@@ -13,7 +13,18 @@ use aptos_dkg::{
         Player, WeightedConfig,
     },
     utils::random::random_scalar,
-    weighted_vuf::{pinkas::PinkasWUF, traits::WeightedVUF},
+    weighted_vuf::{
+        beacon::{
+            verify_beacon_history, verify_beacon_output, BeaconConfig, BeaconHistory,
+            BeaconMessage, InMemoryBeaconStorage, ShareCollector,
+        },
+        misbehavior::{
+            augment_pubkey_or_report, verify_misbehavior_report, verify_share_or_report,
+            FailureClass, MisbehaviorEvidence, WvufMisbehaviorReport,
+        },
+        pinkas::PinkasWUF,
+        traits::WeightedVUF,
+    },
 };
 use aptos_runtimes::spawn_rayon_thread_pool;
 use rand::{rngs::StdRng, thread_rng};
@@ -25,6 +36,323 @@ fn test_wvuf_basic_viability() {
     weighted_wvuf_bvt::<pvss::das::WeightedTranscript, PinkasWUF>();
 }
 
+#[test]
+fn test_wvuf_misbehavior_reports() {
+    type T = pvss::das::WeightedTranscript;
+    type WVUF = PinkasWUF;
+
+    let mut rng = thread_rng();
+    let (wc, d, trx) = weighted_pvss::<T>(&mut StdRng::from_seed(
+        random_scalar(&mut rng).to_bytes_le(),
+    ));
+    let vuf_pp = <WVUF as WeightedVUF>::PublicParameters::from(&d.pp);
+
+    // Players 0 and 2 have the same weight, so their deltas have the same length
+    let players = [wc.get_player(0), wc.get_player(2)];
+    let (asks, apks_and_pks): (Vec<_>, Vec<_>) = players
+        .iter()
+        .map(|p| {
+            let (sk, pk) = trx.decrypt_own_share(&wc, p, &d.dks[p.id]);
+            let (ask, apk) = WVUF::augment_key_pair(&vuf_pp, sk, pk.clone(), &mut rng);
+            (ask, (apk, pk))
+        })
+        .unzip();
+    let (apk, pk) = &apks_and_pks[0];
+
+    // A valid share produces no report, and a fabricated report about it does not verify
+    let msg = b"some msg";
+    let share = WVUF::create_share(&asks[0], msg);
+    verify_share_or_report::<WVUF>(&vuf_pp, &players[0], apk, msg, &share)
+        .expect("Valid WVUF proof share should not be reported");
+    let other_msg = b"other msg";
+    let report = verify_share_or_report::<WVUF>(&vuf_pp, &players[0], apk, other_msg, &share)
+        .expect_err("WVUF proof share for the wrong message should be reported");
+    let mut fabricated_report = report.as_ref().clone();
+    fabricated_report.evidence = match fabricated_report.evidence {
+        MisbehaviorEvidence::ProofShare { proof_share, .. } => MisbehaviorEvidence::ProofShare {
+            msg: msg.to_vec(),
+            proof_share,
+        },
+        evidence => evidence,
+    };
+    assert!(verify_misbehavior_report::<WVUF>(&vuf_pp, &fabricated_report, pk, Some(apk)).is_err());
+
+    // An invalid share produces a report that verifies independently, even after serialization
+    assert_eq!(report.get_failure_class(), FailureClass::InvalidProofShare);
+    assert_eq!(report.get_player(), &players[0]);
+    let report_bytes = bcs::to_bytes(report.as_ref()).unwrap();
+    let report: WvufMisbehaviorReport<WVUF> = bcs::from_bytes(&report_bytes).unwrap();
+    verify_misbehavior_report::<WVUF>(&vuf_pp, &report, pk, Some(apk))
+        .expect("Misbehavior report for invalid proof share should verify");
+    assert!(verify_misbehavior_report::<WVUF>(&vuf_pp, &report, pk, None).is_err());
+
+    // Using another player's delta fails augmentation and produces a verifiable report
+    let other_delta = WVUF::get_public_delta(&apks_and_pks[1].0).clone();
+    let report = augment_pubkey_or_report::<WVUF>(&vuf_pp, &players[0], pk.clone(), other_delta)
+        .expect_err("Augmenting with another player's delta should be reported");
+    assert_eq!(report.get_failure_class(), FailureClass::InvalidDelta);
+    verify_misbehavior_report::<WVUF>(&vuf_pp, &report, pk, None)
+        .expect("Misbehavior report for invalid delta should verify");
+
+    // A report with a mismatching failure class does not verify
+    let mut mislabeled_report = report.as_ref().clone();
+    mislabeled_report.failure_class = FailureClass::InvalidProofShare;
+    assert!(verify_misbehavior_report::<WVUF>(&vuf_pp, &mislabeled_report, pk, Some(apk)).is_err());
+
+    // The player's own delta does not produce a report
+    let own_delta = WVUF::get_public_delta(apk).clone();
+    assert_eq!(
+        apk,
+        &augment_pubkey_or_report::<WVUF>(&vuf_pp, &players[0], pk.clone(), own_delta).unwrap()
+    );
+}
+
+#[test]
+fn test_wvuf_beacon() {
+    type T = pvss::das::WeightedTranscript;
+    type WVUF = PinkasWUF;
+
+    let mut rng = StdRng::from_seed(random_scalar(&mut thread_rng()).to_bytes_le());
+    let (wc, d, trx) = weighted_pvss::<T>(&mut rng);
+    let vuf_pp = <WVUF as WeightedVUF>::PublicParameters::from(&d.pp);
+    let pool = spawn_rayon_thread_pool("test-beacon".to_string(), Some(4));
+
+    let (asks, apks): (Vec<_>, Vec<_>) = (0..wc.get_total_num_players())
+        .map(|p| {
+            let player = wc.get_player(p);
+            let (sk, pk) = trx.decrypt_own_share(&wc, &player, &d.dks[p]);
+            let (ask, apk) = WVUF::augment_key_pair(&vuf_pp, sk, pk, &mut rng);
+            (ask, Some(apk))
+        })
+        .unzip();
+    let epoch = 5;
+    let config = BeaconConfig::<WVUF> {
+        epoch,
+        wc: wc.clone(),
+        pp: vuf_pp,
+        pk: d.dpk.clone(),
+        apks,
+    };
+
+    let mut history = BeaconHistory::new(epoch, InMemoryBeaconStorage::new()).unwrap();
+    for round in [1, 2, 4] {
+        let mut collector = ShareCollector::new(&config, round);
+        let msg = collector.get_message().to_bytes();
+
+        // An invalid share (for another round) is rejected and reported
+        let other_msg = BeaconMessage::new(epoch, round + 1).to_bytes();
+        let player = wc.get_player(0);
+        assert!(collector
+            .add_share(player, WVUF::create_share(&asks[0], &other_msg))
+            .is_err());
+        assert_eq!(collector.take_misbehavior_reports().len(), 1);
+
+        // The output can only be aggregated once enough weight has contributed
+        let players = wc.get_random_eligible_subset_of_players(&mut rng);
+        for (i, p) in players.iter().enumerate() {
+            assert!(collector.try_aggregate(&pool).unwrap().is_none());
+            collector
+                .add_share(*p, WVUF::create_share(&asks[p.id], &msg))
+                .unwrap();
+            if i == 0 {
+                // Duplicate shares are ignored
+                collector
+                    .add_share(*p, WVUF::create_share(&asks[p.id], &msg))
+                    .unwrap();
+            }
+        }
+        assert!(collector.has_enough_weight());
+        let output = collector.try_aggregate(&pool).unwrap().unwrap().clone();
+        assert_eq!(output.message, BeaconMessage::new(epoch, round));
+        assert_eq!(
+            output.randomness,
+            Sha3_256::digest(bcs::to_bytes(&WVUF::eval(&d.dsk, &msg)).unwrap().as_slice()).to_vec()
+        );
+        verify_beacon_output(&config, &output, &pool).unwrap();
+
+        // A tampered output does not verify
+        let mut tampered_output = output.clone();
+        tampered_output.randomness[0] ^= 1;
+        assert!(verify_beacon_output(&config, &tampered_output, &pool).is_err());
+
+        history.append(output).unwrap();
+    }
+
+    // Outputs can't be appended out of order, or for another epoch
+    let entries = history.get_entries().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert!(history.append(entries[1].output.clone()).is_err());
+    let mut other_epoch_output = entries[2].output.clone();
+    other_epoch_output.message = BeaconMessage::new(epoch + 1, 10);
+    assert!(history.append(other_epoch_output).is_err());
+
+    // The history verifies, and is committed to by its head
+    verify_beacon_history(&config, &entries, &pool).unwrap();
+    assert_eq!(history.get_head_hash(), entries[2].hash());
+
+    // A history with a missing or reordered entry does not verify
+    assert!(verify_beacon_history(&config, &entries[1..], &pool).is_err());
+    let reordered_entries = vec![entries[1].clone(), entries[0].clone()];
+    assert!(verify_beacon_history(&config, &reordered_entries, &pool).is_err());
+}
+
+#[test]
+fn test_pinkas_wvuf_derive_eval_with_lagrange_coeffs() {
+    type T = pvss::das::WeightedTranscript;
+    type WVUF = PinkasWUF;
+
+    let mut rng = StdRng::from_seed(random_scalar(&mut thread_rng()).to_bytes_le());
+    let (wc, d, trx) = weighted_pvss::<T>(&mut rng);
+    let vuf_pp = <WVUF as WeightedVUF>::PublicParameters::from(&d.pp);
+    let pool = spawn_rayon_thread_pool("test-wvuf".to_string(), Some(4));
+
+    let (asks, apks): (Vec<_>, Vec<_>) = (0..wc.get_total_num_players())
+        .map(|p| {
+            let player = wc.get_player(p);
+            let (sk, pk) = trx.decrypt_own_share(&wc, &player, &d.dks[p]);
+            let (ask, apk) = WVUF::augment_key_pair(&vuf_pp, sk, pk, &mut rng);
+            (ask, Some(apk))
+        })
+        .unzip();
+
+    // The same subset of players aggregates proofs for different messages, reusing the
+    // Lagrange coefficients computed for the first proof
+    let players = wc.get_random_eligible_subset_of_players(&mut rng);
+    let mut lagr = None;
+    for msg in [b"first msg".as_slice(), b"second msg".as_slice()] {
+        let apks_and_proofs = players
+            .iter()
+            .map(|p| {
+                let apk = apks[p.id].clone().unwrap();
+                (*p, apk, WVUF::create_share(&asks[p.id], msg))
+            })
+            .collect::<Vec<_>>();
+        let proof = WVUF::aggregate_shares(&wc, &apks_and_proofs);
+        let lagr = lagr.get_or_insert_with(|| WVUF::lagrange_coeffs(&wc, &proof));
+
+        let eval = WVUF::derive_eval_with_lagrange_coeffs(&wc, &apks, &proof, lagr, &pool)
+            .expect("WVUF derivation was expected to succeed");
+        assert_eq!(eval, WVUF::eval(&d.dsk, msg));
+        assert_eq!(
+            eval,
+            WVUF::derive_eval(&wc, &vuf_pp, msg, &apks, &proof, &pool).unwrap()
+        );
+    }
+
+    // Lagrange coefficients for a different subset of players are rejected
+    let apks_and_proofs = vec![(
+        players[0],
+        apks[players[0].id].clone().unwrap(),
+        WVUF::create_share(&asks[players[0].id], b"msg"),
+    )];
+    let proof = WVUF::aggregate_shares(&wc, &apks_and_proofs);
+    assert!(WVUF::derive_eval_with_lagrange_coeffs(
+        &wc,
+        &apks,
+        &proof,
+        lagr.as_ref().unwrap(),
+        &pool
+    )
+    .is_err());
+}
+
+#[test]
+fn test_pinkas_wvuf_verify_shares_batch() {
+    type T = pvss::das::WeightedTranscript;
+    type WVUF = PinkasWUF;
+
+    let mut rng = StdRng::from_seed(random_scalar(&mut thread_rng()).to_bytes_le());
+    let (wc, d, trx) = weighted_pvss::<T>(&mut rng);
+    let vuf_pp = <WVUF as WeightedVUF>::PublicParameters::from(&d.pp);
+
+    let msg = b"some msg";
+    let mut apks_and_proofs = (0..wc.get_total_num_players())
+        .map(|p| {
+            let player = wc.get_player(p);
+            let (sk, pk) = trx.decrypt_own_share(&wc, &player, &d.dks[p]);
+            let (ask, apk) = WVUF::augment_key_pair(&vuf_pp, sk, pk, &mut rng);
+            (player, apk, WVUF::create_share(&ask, msg))
+        })
+        .collect::<Vec<_>>();
+
+    // All shares are valid
+    assert!(WVUF::verify_shares_batch(&vuf_pp, msg, &[]).is_empty());
+    assert!(WVUF::verify_shares_batch(&vuf_pp, msg, &apks_and_proofs).is_empty());
+
+    // The shares are not valid for another message
+    let culprits = WVUF::verify_shares_batch(&vuf_pp, b"other msg", &apks_and_proofs);
+    assert_eq!(culprits.len(), apks_and_proofs.len());
+
+    // Swapping the shares of two players makes exactly these two players culprits
+    let (first, last) = (0, apks_and_proofs.len() - 1);
+    let first_share = apks_and_proofs[first].2;
+    apks_and_proofs[first].2 = apks_and_proofs[last].2;
+    apks_and_proofs[last].2 = first_share;
+    assert_eq!(
+        WVUF::verify_shares_batch(&vuf_pp, msg, &apks_and_proofs),
+        vec![apks_and_proofs[first].0, apks_and_proofs[last].0]
+    );
+}
+
+#[test]
+fn test_pinkas_wvuf_rotate_augmentation() {
+    type T = pvss::das::WeightedTranscript;
+    type WVUF = PinkasWUF;
+
+    let mut rng = StdRng::from_seed(random_scalar(&mut thread_rng()).to_bytes_le());
+    let (wc, d, trx) = weighted_pvss::<T>(&mut rng);
+    let vuf_pp = <WVUF as WeightedVUF>::PublicParameters::from(&d.pp);
+    let pool = spawn_rayon_thread_pool("test-wvuf".to_string(), Some(4));
+
+    let msg = b"some msg";
+    let (asks, apks): (Vec<_>, Vec<_>) = (0..wc.get_total_num_players())
+        .map(|p| {
+            let player = wc.get_player(p);
+            let (sk, pk) = trx.decrypt_own_share(&wc, &player, &d.dks[p]);
+            let (ask, apk) = WVUF::augment_key_pair(&vuf_pp, sk, pk.clone(), &mut rng);
+            let old_share = WVUF::create_share(&ask, msg);
+
+            // The secrets are never printed, and survive a serialization round-trip
+            assert_eq!(
+                format!("{:?}", ask),
+                "<elided secret for AugmentedSecretKeyShare>"
+            );
+            let ask_bytes = bcs::to_bytes(&ask).unwrap();
+            assert!(ask == bcs::from_bytes(&ask_bytes).unwrap());
+
+            // The rotated key pair is re-augmented from the same (dealt) key shares
+            let (ask, apk) = WVUF::rotate_augmentation(&vuf_pp, ask, apk, &mut rng);
+            let delta = WVUF::get_public_delta(&apk);
+            assert_eq!(
+                apk,
+                WVUF::augment_pubkey(&vuf_pp, pk, delta.clone()).unwrap()
+            );
+            assert_ne!(bcs::to_bytes(&ask).unwrap(), ask_bytes);
+
+            // Shares created with the old augmented key pair no longer verify
+            assert!(WVUF::verify_share(&vuf_pp, &apk, msg, &old_share).is_err());
+            let share = WVUF::create_share(&ask, msg);
+            WVUF::verify_share(&vuf_pp, &apk, msg, &share).expect("WVUF proof share should verify");
+
+            (ask, Some(apk))
+        })
+        .unzip();
+
+    // The rotated key pairs evaluate to the same VUF output
+    let apks_and_proofs = wc
+        .get_random_eligible_subset_of_players(&mut rng)
+        .into_iter()
+        .map(|p| {
+            let apk = apks[p.id].clone().unwrap();
+            (p, apk, WVUF::create_share(&asks[p.id], msg))
+        })
+        .collect::<Vec<_>>();
+    let proof = WVUF::aggregate_shares(&wc, &apks_and_proofs);
+    let eval = WVUF::derive_eval(&wc, &vuf_pp, msg, &apks, &proof, &pool)
+        .expect("WVUF derivation was expected to succeed");
+    assert_eq!(eval, WVUF::eval(&d.dsk, msg));
+}
+
 fn weighted_wvuf_bvt<
     T: Transcript<SecretSharingConfig = WeightedConfig>,
     WVUF: WeightedVUF<
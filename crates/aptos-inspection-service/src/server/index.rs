@@ -3,7 +3,7 @@
 
 use crate::{
     server::utils::CONTENT_TYPE_TEXT, CONFIGURATION_PATH, FORGE_METRICS_PATH, JSON_METRICS_PATH,
-    METRICS_PATH, PEER_INFORMATION_PATH, SYSTEM_INFORMATION_PATH,
+    METRICS_PATH, PEER_INFORMATION_PATH, RANDOMNESS_PARTICIPATION_PATH, SYSTEM_INFORMATION_PATH,
 };
 use hyper::{Body, StatusCode};
 
@@ -29,6 +29,7 @@ fn get_index_response() -> String {
     index_response.push(format!("\t- {}", JSON_METRICS_PATH));
     index_response.push(format!("\t- {}", METRICS_PATH));
     index_response.push(format!("\t- {}", PEER_INFORMATION_PATH));
+    index_response.push(format!("\t- {}", RANDOMNESS_PARTICIPATION_PATH));
     index_response.push(format!("\t- {}", SYSTEM_INFORMATION_PATH));
 
     index_response.join("\n") // Separate each entry with a newline
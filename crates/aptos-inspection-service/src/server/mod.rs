@@ -22,6 +22,7 @@ mod index;
 mod json_encoder;
 mod metrics;
 mod peer_information;
+mod randomness_participation;
 mod system_information;
 pub mod utils;
 
@@ -35,6 +36,7 @@ pub const INDEX_PATH: &str = "/";
 pub const JSON_METRICS_PATH: &str = "/json_metrics";
 pub const METRICS_PATH: &str = "/metrics";
 pub const PEER_INFORMATION_PATH: &str = "/peer_information";
+pub const RANDOMNESS_PARTICIPATION_PATH: &str = "/randomness_participation";
 pub const SYSTEM_INFORMATION_PATH: &str = "/system_information";
 
 // Useful string constants
@@ -140,6 +142,11 @@ async fn serve_requests(
                 peers_and_metadata,
             )
         },
+        RANDOMNESS_PARTICIPATION_PATH => {
+            // /randomness_participation
+            // Exposes the randomness share participation of each validator
+            randomness_participation::handle_randomness_participation_request()
+        },
         SYSTEM_INFORMATION_PATH => {
             // /system_information
             // Exposes the system and build information
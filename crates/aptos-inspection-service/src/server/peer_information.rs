@@ -253,6 +253,16 @@ fn display_state_sync_metadata(
                 peer_storage_summary
             ));
 
+            // Display the peer's bandwidth stats (used for peer selection)
+            let bandwidth_stats = peer_state_entry.get_bandwidth_stats();
+            peer_information_output.push(format!(
+                "\t\t- Bandwidth score: {:.2}, throughput (bytes/sec): {:.2}, error rate: {:.4}, samples: {}",
+                bandwidth_stats.get_bandwidth_score(),
+                bandwidth_stats.get_throughput_bytes_per_sec(),
+                bandwidth_stats.get_error_rate(),
+                bandwidth_stats.get_num_samples()
+            ));
+
             // Get the peer's request/response counts
             let sent_requests_by_type = peer_state_entry.get_sent_requests_by_type();
             let received_responses_by_type = peer_state_entry.get_received_responses_by_type();
@@ -0,0 +1,135 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::server::utils::{self, CONTENT_TYPE_JSON};
+use hyper::{Body, StatusCode};
+use prometheus::proto::{Histogram, Metric, MetricFamily};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+// The randomness share participation metrics (exported by consensus)
+const RAND_SHARE_PARTICIPATION_COUNT_METRIC: &str =
+    "aptos_consensus_rand_share_participation_count";
+const RAND_SHARE_LATENCY_METRIC: &str = "aptos_consensus_rand_share_latency_seconds";
+
+// The labels of the randomness share participation metrics
+const PEER_ID_LABEL: &str = "peer_id";
+const STATUS_LABEL: &str = "status";
+const CONTRIBUTED_STATUS: &str = "contributed";
+const MISSED_STATUS: &str = "missed";
+
+/// Per-validator randomness share participation (as observed by this node)
+#[derive(Default)]
+struct ValidatorParticipation {
+    contributed: u64,
+    missed: u64,
+    latency_p50_secs: Option<f64>,
+    latency_p99_secs: Option<f64>,
+}
+
+impl ValidatorParticipation {
+    fn to_json(&self) -> Value {
+        let total = self.contributed + self.missed;
+        let participation_ratio = if total == 0 {
+            None
+        } else {
+            Some(self.contributed as f64 / total as f64)
+        };
+        json!({
+            "contributed_rounds": self.contributed,
+            "missed_rounds": self.missed,
+            "participation_ratio": participation_ratio,
+            "latency_p50_secs": self.latency_p50_secs,
+            "latency_p99_secs": self.latency_p99_secs,
+        })
+    }
+}
+
+/// Handles a new randomness participation request
+pub fn handle_randomness_participation_request() -> (StatusCode, Body, String) {
+    let participation = get_randomness_participation(utils::get_metric_families());
+    let encoded_participation = match serde_json::to_string_pretty(&participation) {
+        Ok(encoded_participation) => encoded_participation,
+        Err(error) => format!(
+            "Failed to get the randomness participation! Error: {}",
+            error
+        ),
+    };
+
+    (
+        StatusCode::OK,
+        Body::from(encoded_participation),
+        CONTENT_TYPE_JSON.into(),
+    )
+}
+
+/// Returns the randomness share participation (contributed and missed rounds, and
+/// latency percentiles) of each validator, as JSON (keyed by peer ID)
+pub fn get_randomness_participation(metric_families: Vec<MetricFamily>) -> Value {
+    let mut participation: BTreeMap<String, ValidatorParticipation> = BTreeMap::new();
+    for metric_family in metric_families {
+        match metric_family.get_name() {
+            RAND_SHARE_PARTICIPATION_COUNT_METRIC => {
+                for metric in metric_family.get_metric() {
+                    let (Some(peer_id), Some(status)) = (
+                        get_label_value(metric, PEER_ID_LABEL),
+                        get_label_value(metric, STATUS_LABEL),
+                    ) else {
+                        continue;
+                    };
+                    let validator_participation = participation.entry(peer_id).or_default();
+                    let count = metric.get_counter().get_value() as u64;
+                    match status.as_str() {
+                        CONTRIBUTED_STATUS => validator_participation.contributed = count,
+                        MISSED_STATUS => validator_participation.missed = count,
+                        _ => {},
+                    }
+                }
+            },
+            RAND_SHARE_LATENCY_METRIC => {
+                for metric in metric_family.get_metric() {
+                    let Some(peer_id) = get_label_value(metric, PEER_ID_LABEL) else {
+                        continue;
+                    };
+                    let validator_participation = participation.entry(peer_id).or_default();
+                    let histogram = metric.get_histogram();
+                    validator_participation.latency_p50_secs = estimate_percentile(histogram, 0.5);
+                    validator_participation.latency_p99_secs = estimate_percentile(histogram, 0.99);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    Value::Object(
+        participation
+            .into_iter()
+            .map(|(peer_id, validator_participation)| (peer_id, validator_participation.to_json()))
+            .collect(),
+    )
+}
+
+/// Returns the value of the given label (if any)
+fn get_label_value(metric: &Metric, label_name: &str) -> Option<String> {
+    metric
+        .get_label()
+        .iter()
+        .find(|label| label.get_name() == label_name)
+        .map(|label| label.get_value().to_string())
+}
+
+/// Estimates the given percentile of the histogram, as the upper bound of the first
+/// bucket that contains it. Returns None if the histogram is empty, or if the
+/// percentile is beyond the largest bucket.
+fn estimate_percentile(histogram: &Histogram, percentile: f64) -> Option<f64> {
+    let sample_count = histogram.get_sample_count();
+    if sample_count == 0 {
+        return None;
+    }
+    let target_count = (sample_count as f64 * percentile).ceil() as u64;
+    histogram
+        .get_bucket()
+        .iter()
+        .find(|bucket| bucket.get_cumulative_count() >= target_count)
+        .map(|bucket| bucket.get_upper_bound())
+}
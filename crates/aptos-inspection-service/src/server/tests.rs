@@ -4,11 +4,12 @@
 use crate::{
     server::{
         configuration::CONFIGURATION_DISABLED_MESSAGE,
-        peer_information::PEER_INFO_DISABLED_MESSAGE, serve_requests,
+        peer_information::PEER_INFO_DISABLED_MESSAGE,
+        randomness_participation::get_randomness_participation, serve_requests,
         system_information::SYS_INFO_DISABLED_MESSAGE, utils::get_all_metrics,
     },
     CONFIGURATION_PATH, FORGE_METRICS_PATH, INDEX_PATH, JSON_METRICS_PATH, METRICS_PATH,
-    PEER_INFORMATION_PATH, SYSTEM_INFORMATION_PATH,
+    PEER_INFORMATION_PATH, RANDOMNESS_PARTICIPATION_PATH, SYSTEM_INFORMATION_PATH,
 };
 use aptos_config::config::{AptosDataClientConfig, BaseConfig, NodeConfig};
 use aptos_data_client::client::AptosDataClient;
@@ -20,7 +21,10 @@ use assert_approx_eq::assert_approx_eq;
 use futures::executor::block_on;
 use hyper::{body, Body, Method, Request, Response, StatusCode};
 use once_cell::sync::Lazy;
-use prometheus::{proto::MetricFamily, register_int_counter, Counter, IntCounter, Opts, Registry};
+use prometheus::{
+    proto::MetricFamily, register_int_counter, Counter, HistogramOpts, HistogramVec, IntCounter,
+    IntCounterVec, Opts, Registry,
+};
 use rusty_fork::rusty_fork_test;
 use std::{collections::HashMap, io::read_to_string, string::String, sync::Arc};
 
@@ -90,6 +94,7 @@ async fn test_inspect_index() {
     assert!(response_body_string.contains(JSON_METRICS_PATH));
     assert!(response_body_string.contains(METRICS_PATH));
     assert!(response_body_string.contains(PEER_INFORMATION_PATH));
+    assert!(response_body_string.contains(RANDOMNESS_PARTICIPATION_PATH));
     assert!(response_body_string.contains(SYSTEM_INFORMATION_PATH));
 }
 
@@ -246,6 +251,63 @@ fn test_publish_metrics() {
     assert_approx_eq!(1.0, metrics.first().unwrap().get_counter().get_value());
 }
 
+#[test]
+fn test_randomness_participation() {
+    // Create the randomness participation metrics (as exported by consensus)
+    let participation_count = IntCounterVec::new(
+        Opts::new(
+            "aptos_consensus_rand_share_participation_count",
+            "participation count help",
+        ),
+        &["peer_id", "status"],
+    )
+    .unwrap();
+    let share_latency = HistogramVec::new(
+        HistogramOpts::new(
+            "aptos_consensus_rand_share_latency_seconds",
+            "share latency help",
+        )
+        .buckets(vec![0.1, 0.5, 1.0]),
+        &["peer_id"],
+    )
+    .unwrap();
+    let register = Registry::new();
+    register
+        .register(Box::new(participation_count.clone()))
+        .unwrap();
+    register.register(Box::new(share_latency.clone())).unwrap();
+
+    // Record the participation of two validators
+    participation_count
+        .with_label_values(&["validator_1", "contributed"])
+        .inc_by(3);
+    participation_count
+        .with_label_values(&["validator_1", "missed"])
+        .inc();
+    participation_count
+        .with_label_values(&["validator_2", "missed"])
+        .inc_by(2);
+    for latency in [0.05, 0.2, 0.3, 0.8] {
+        share_latency
+            .with_label_values(&["validator_1"])
+            .observe(latency);
+    }
+
+    // Verify the participation of each validator
+    let participation = get_randomness_participation(register.gather());
+    let validator_1 = &participation["validator_1"];
+    assert_eq!(validator_1["contributed_rounds"], 3);
+    assert_eq!(validator_1["missed_rounds"], 1);
+    assert_approx_eq!(validator_1["participation_ratio"].as_f64().unwrap(), 0.75);
+    assert_approx_eq!(validator_1["latency_p50_secs"].as_f64().unwrap(), 0.5);
+    assert_approx_eq!(validator_1["latency_p99_secs"].as_f64().unwrap(), 1.0);
+    let validator_2 = &participation["validator_2"];
+    assert_eq!(validator_2["contributed_rounds"], 0);
+    assert_eq!(validator_2["missed_rounds"], 2);
+    assert_approx_eq!(validator_2["participation_ratio"].as_f64().unwrap(), 0.0);
+    assert!(validator_2["latency_p50_secs"].is_null());
+}
+
 // Exercise the serve_requests() handler with a GET request to the given path
 async fn send_get_request_to_path(config: &NodeConfig, endpoint: &str) -> Response<Body> {
     // Build the URI
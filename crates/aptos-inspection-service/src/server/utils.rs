@@ -47,7 +47,7 @@ pub fn get_encoded_metrics(encoder: impl Encoder) -> Vec<u8> {
 }
 
 /// A simple utility function that returns all metric families
-fn get_metric_families() -> Vec<MetricFamily> {
+pub fn get_metric_families() -> Vec<MetricFamily> {
     let metric_families = aptos_metrics_core::gather();
     let mut total: u64 = 0;
     let mut families_over_1000: u64 = 0;
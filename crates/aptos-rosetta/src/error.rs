@@ -49,6 +49,7 @@ pub enum ApiError {
     SequenceNumberTooOld(Option<String>),
     VmError(Option<String>),
     MempoolIsFull(Option<String>),
+    SubmissionQuotaExceeded(Option<String>),
 }
 
 impl std::fmt::Display for ApiError {
@@ -97,6 +98,7 @@ impl ApiError {
             SequenceNumberTooOld(None),
             VmError(None),
             MempoolIsFull(None),
+            SubmissionQuotaExceeded(None),
         ]
     }
 
@@ -137,6 +139,7 @@ impl ApiError {
             MempoolIsFull(_) => 32,
             CoinTypeFailedToBeFetched(_) => 33,
             StateValueNotFound(_) => 34,
+            SubmissionQuotaExceeded(_) => 35,
         }
     }
 
@@ -147,6 +150,7 @@ impl ApiError {
             AccountNotFound(_)
                 | BlockNotFound(_)
                 | MempoolIsFull(_)
+                | SubmissionQuotaExceeded(_)
                 | GasEstimationFailed(_)
                 | CoinTypeFailedToBeFetched(_)
         )
@@ -193,6 +197,7 @@ impl ApiError {
             ApiError::SequenceNumberTooOld(_) => "Sequence number too old.  Please create a new transaction with an updated sequence number",
             ApiError::VmError(_) => "Transaction submission failed due to VM error",
             ApiError::MempoolIsFull(_) => "Mempool is full all accounts",
+            ApiError::SubmissionQuotaExceeded(_) => "Submission quota exceeded, please retry later",
             ApiError::GasEstimationFailed(_) => "Gas estimation failed",
         }
     }
@@ -222,6 +227,7 @@ impl ApiError {
             ApiError::SequenceNumberTooOld(inner) => inner,
             ApiError::VmError(inner) => inner,
             ApiError::MempoolIsFull(inner) => inner,
+            ApiError::SubmissionQuotaExceeded(inner) => inner,
             ApiError::GasEstimationFailed(inner) => inner,
             ApiError::MaxGasFeeTooLow(inner) => inner,
             _ => None,
@@ -294,6 +300,9 @@ impl From<RestError> for ApiError {
                     ApiError::InternalError(Some(err.error.message))
                 },
                 AptosErrorCode::MempoolIsFull => ApiError::MempoolIsFull(Some(err.error.message)),
+                AptosErrorCode::SubmissionQuotaExceeded => {
+                    ApiError::SubmissionQuotaExceeded(Some(err.error.message))
+                },
                 AptosErrorCode::WebFrameworkError => {
                     ApiError::InternalError(Some(err.error.message))
                 },
@@ -1,11 +1,16 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+//! Streaming aggregation of DKG transcripts. Transcripts are verified as they arrive from the
+//! dealers (concurrently, without holding the aggregation lock) and aggregated immediately, so
+//! that only a single aggregated transcript is kept in memory. Dealers of invalid transcripts
+//! are rejected early, and the aggregation state can be checkpointed and resumed.
+
 use crate::{counters::DKG_STAGE_SECONDS, types::DKGTranscriptRequest, DKGMessage};
 use anyhow::{anyhow, ensure};
 use aptos_consensus_types::common::Author;
 use aptos_infallible::{duration_since_epoch, Mutex};
-use aptos_logger::info;
+use aptos_logger::{info, warn};
 use aptos_reliable_broadcast::BroadcastStatus;
 use aptos_types::{
     dkg::{DKGTrait, DKGTranscript},
@@ -13,11 +18,19 @@ use aptos_types::{
     validator_verifier::VerifyError,
 };
 use move_core_types::account_address::AccountAddress;
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 pub struct TranscriptAggregator<S: DKGTrait> {
     pub contributors: HashSet<AccountAddress>,
     pub trx: Option<S::Transcript>,
+    /// Dealers whose transcripts failed verification. Their later contributions
+    /// are rejected early (i.e., without deserialization and verification).
+    pub rejected_dealers: HashSet<AccountAddress>,
 }
 
 impl<S: DKGTrait> Default for TranscriptAggregator<S> {
@@ -25,10 +38,21 @@ impl<S: DKGTrait> Default for TranscriptAggregator<S> {
         Self {
             contributors: HashSet::new(),
             trx: None,
+            rejected_dealers: HashSet::new(),
         }
     }
 }
 
+/// A snapshot of the (partial) transcript aggregation of an epoch. Checkpoints can
+/// be persisted mid-DKG, so that the aggregation can be resumed (e.g., after a
+/// restart) without collecting and verifying the contributed transcripts again.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TranscriptAggregationCheckpoint {
+    pub epoch: u64,
+    pub contributors: BTreeSet<AccountAddress>,
+    pub transcript_bytes: Option<Vec<u8>>, // The aggregated transcript (if any)
+}
+
 pub struct TranscriptAggregationState<DKG: DKGTrait> {
     start_time: Duration,
     my_addr: AccountAddress,
@@ -55,6 +79,80 @@ impl<DKG: DKGTrait> TranscriptAggregationState<DKG> {
             epoch_state,
         }
     }
+
+    /// Returns a checkpoint of the current aggregation state
+    pub fn checkpoint(&self) -> anyhow::Result<TranscriptAggregationCheckpoint> {
+        let trx_aggregator = self.trx_aggregator.lock();
+        let transcript_bytes = trx_aggregator
+            .trx
+            .as_ref()
+            .map(bcs::to_bytes)
+            .transpose()
+            .map_err(|e| anyhow!("[DKG] checkpoint failed with trx serialization error: {e}"))?;
+        Ok(TranscriptAggregationCheckpoint {
+            epoch: self.epoch_state.epoch,
+            contributors: trx_aggregator.contributors.iter().copied().collect(),
+            transcript_bytes,
+        })
+    }
+
+    /// Resumes the aggregation from the given checkpoint. The aggregated transcript
+    /// is verified (once) and must contain exactly the dealings of the contributors.
+    /// Transcripts aggregated before resuming are discarded.
+    pub fn resume(&self, checkpoint: TranscriptAggregationCheckpoint) -> anyhow::Result<()> {
+        let TranscriptAggregationCheckpoint {
+            epoch,
+            contributors,
+            transcript_bytes,
+        } = checkpoint;
+        ensure!(
+            epoch == self.epoch_state.epoch,
+            "[DKG] resuming aggregation failed with invalid checkpoint epoch",
+        );
+
+        let trx = match transcript_bytes {
+            Some(transcript_bytes) => {
+                let trx: DKG::Transcript =
+                    bcs::from_bytes(transcript_bytes.as_slice()).map_err(|e| {
+                        anyhow!(
+                            "[DKG] resuming aggregation failed with trx deserialization error: {e}"
+                        )
+                    })?;
+                DKG::verify_transcript(&self.dkg_pub_params, &trx).map_err(|e| {
+                    anyhow!("[DKG] resuming aggregation failed with trx verification failure: {e}")
+                })?;
+                Some(trx)
+            },
+            None => None,
+        };
+
+        // Verify the contributors match the dealers of the aggregated transcript
+        let address_to_validator_index = self.epoch_state.verifier.address_to_validator_index();
+        let contributor_indices = contributors
+            .iter()
+            .map(|contributor| {
+                address_to_validator_index
+                    .get(contributor)
+                    .map(|index| *index as u64)
+                    .ok_or_else(|| anyhow!("[DKG] resuming aggregation failed with illegal dealer"))
+            })
+            .collect::<anyhow::Result<BTreeSet<_>>>()?;
+        let dealers = trx.as_ref().map(DKG::get_dealers).unwrap_or_default();
+        ensure!(
+            contributor_indices == dealers,
+            "[DKG] resuming aggregation failed with contributor and dealer mismatch"
+        );
+
+        let mut trx_aggregator = self.trx_aggregator.lock();
+        trx_aggregator.contributors = contributors.into_iter().collect();
+        trx_aggregator.trx = trx;
+        info!(
+            epoch = self.epoch_state.epoch,
+            num_contributors = trx_aggregator.contributors.len(),
+            "[DKG] resumed transcript aggregation from checkpoint."
+        );
+        Ok(())
+    }
 }
 
 impl<S: DKGTrait> BroadcastStatus<DKGMessage> for Arc<TranscriptAggregationState<S>> {
@@ -85,18 +183,51 @@ impl<S: DKGTrait> BroadcastStatus<DKGMessage> for Arc<TranscriptAggregationState
             metadata.author == sender,
             "[DKG] adding peer transcript failed with node author mismatch"
         );
-        let transcript = bcs::from_bytes(transcript_bytes.as_slice()).map_err(|e| {
-            anyhow!("[DKG] adding peer transcript failed with trx deserialization error: {e}")
-        })?;
+
+        // Skip (or reject) the transcript early if the dealer has already been processed
+        {
+            let trx_aggregator = self.trx_aggregator.lock();
+            if trx_aggregator.contributors.contains(&metadata.author) {
+                return Ok(None);
+            }
+            ensure!(
+                !trx_aggregator.rejected_dealers.contains(&metadata.author),
+                "[DKG] adding peer transcript failed with previously rejected dealer"
+            );
+        }
+
+        // Verify the transcript without holding the lock (so that transcripts
+        // from different dealers can be verified concurrently).
+        let transcript = bcs::from_bytes(transcript_bytes.as_slice())
+            .map_err(|e| {
+                anyhow!("[DKG] adding peer transcript failed with trx deserialization error: {e}")
+            })
+            .and_then(|transcript| {
+                S::verify_transcript(&self.dkg_pub_params, &transcript).map_err(|e| {
+                    anyhow!(
+                        "[DKG] adding peer transcript failed with trx verification failure: {e}"
+                    )
+                })?;
+                Ok(transcript)
+            });
         let mut trx_aggregator = self.trx_aggregator.lock();
+        let transcript = match transcript {
+            Ok(transcript) => transcript,
+            Err(error) => {
+                warn!(
+                    epoch = self.epoch_state.epoch,
+                    peer = sender,
+                    "[DKG] rejecting dealer after invalid transcript: {}",
+                    error
+                );
+                trx_aggregator.rejected_dealers.insert(metadata.author);
+                return Err(error);
+            },
+        };
         if trx_aggregator.contributors.contains(&metadata.author) {
-            return Ok(None);
+            return Ok(None); // The dealer was aggregated concurrently
         }
 
-        S::verify_transcript(&self.dkg_pub_params, &transcript).map_err(|e| {
-            anyhow!("[DKG] adding peer transcript failed with trx verification failure: {e}")
-        })?;
-
         // All checks passed. Aggregating.
         let is_self = self.my_addr == sender;
         if !is_self && !self.valid_peer_transcript_seen {
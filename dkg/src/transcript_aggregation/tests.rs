@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::transcript_aggregation::TranscriptAggregationState;
+use crate::transcript_aggregation::{TranscriptAggregationCheckpoint, TranscriptAggregationState};
 use aptos_crypto::{bls12381::bls12381_keys, Uniform};
 use aptos_infallible::duration_since_epoch;
 use aptos_reliable_broadcast::BroadcastStatus;
@@ -17,7 +17,8 @@ use aptos_types::{
     },
 };
 use move_core_types::account_address::AccountAddress;
-use std::sync::Arc;
+use rand::thread_rng;
+use std::{collections::BTreeSet, sync::Arc};
 
 #[test]
 fn test_transcript_aggregation_state() {
@@ -132,3 +133,105 @@ fn test_transcript_aggregation_state() {
     });
     assert!(matches!(result, Ok(Some(_))));
 }
+
+#[test]
+fn test_transcript_aggregation_streaming_and_checkpoint() {
+    let num_validators = 5;
+    let epoch = 999;
+    let addrs: Vec<AccountAddress> = (0..num_validators)
+        .map(|_| AccountAddress::random())
+        .collect();
+    let private_keys: Vec<bls12381_keys::PrivateKey> = (0..num_validators)
+        .map(|_| bls12381_keys::PrivateKey::generate_for_testing())
+        .collect();
+    let voting_powers = [1, 1, 1, 6, 6]; // total voting power: 15, default threshold: 11
+    let validator_infos: Vec<ValidatorConsensusInfo> = (0..num_validators)
+        .map(|i| {
+            let public_key = bls12381_keys::PublicKey::from(&private_keys[i]);
+            ValidatorConsensusInfo::new(addrs[i], public_key, voting_powers[i])
+        })
+        .collect();
+    let validator_consensus_info_move_structs = validator_infos
+        .clone()
+        .into_iter()
+        .map(ValidatorConsensusInfoMoveStruct::from)
+        .collect::<Vec<_>>();
+    let verifier = ValidatorVerifier::new(validator_infos);
+    let pub_params = DummyDKG::new_public_params(&DKGSessionMetadata {
+        dealer_epoch: 999,
+        randomness_config: OnChainRandomnessConfig::default_enabled().into(),
+        dealer_validator_set: validator_consensus_info_move_structs.clone(),
+        target_validator_set: validator_consensus_info_move_structs,
+    });
+    let epoch_state = Arc::new(EpochState { epoch, verifier });
+    let new_trx_agg_state = || {
+        Arc::new(TranscriptAggregationState::<DummyDKG>::new(
+            duration_since_epoch(),
+            addrs[0],
+            pub_params.clone(),
+            epoch_state.clone(),
+        ))
+    };
+
+    // Deal a transcript for each validator
+    let mut rng = thread_rng();
+    let transcripts: Vec<DKGTranscript> = (0..num_validators)
+        .map(|i| {
+            let input_secret = <DummyDKG as DKGTrait>::InputSecret::generate(&mut rng);
+            let trx = DummyDKG::generate_transcript(
+                &mut rng,
+                &pub_params,
+                &input_secret,
+                i as u64,
+                &private_keys[i],
+            );
+            DKGTranscript::new(epoch, addrs[i], bcs::to_bytes(&trx).unwrap())
+        })
+        .collect();
+
+    // An invalid transcript should be rejected, and so should later contributions of the dealer
+    let trx_agg_state = new_trx_agg_state();
+    let result = trx_agg_state.add(addrs[1], DKGTranscript::new(epoch, addrs[1], vec![0xAB]));
+    assert!(result.is_err());
+    let result = trx_agg_state.add(addrs[1], transcripts[1].clone());
+    assert!(result.is_err());
+
+    // Valid transcripts should be aggregated as they arrive
+    let result = trx_agg_state.add(addrs[0], transcripts[0].clone());
+    assert!(matches!(result, Ok(None)));
+    let result = trx_agg_state.add(addrs[3], transcripts[3].clone());
+    assert!(matches!(result, Ok(None)));
+
+    // Checkpoint the aggregation state
+    let checkpoint = trx_agg_state.checkpoint().unwrap();
+    assert_eq!(checkpoint.epoch, epoch);
+    assert_eq!(
+        checkpoint.contributors,
+        BTreeSet::from([addrs[0], addrs[3]])
+    );
+    assert!(checkpoint.transcript_bytes.is_some());
+
+    // Checkpoints of another epoch, or with mismatching contributors, should be rejected
+    let resumed_trx_agg_state = new_trx_agg_state();
+    let result = resumed_trx_agg_state.resume(TranscriptAggregationCheckpoint {
+        epoch: 998,
+        ..checkpoint.clone()
+    });
+    assert!(result.is_err());
+    let result = resumed_trx_agg_state.resume(TranscriptAggregationCheckpoint {
+        contributors: BTreeSet::from([addrs[0], addrs[4]]),
+        ..checkpoint.clone()
+    });
+    assert!(result.is_err());
+
+    // The resumed aggregation should skip the checkpointed contributors and
+    // return the aggregated transcript once the threshold is exceeded.
+    resumed_trx_agg_state.resume(checkpoint).unwrap();
+    let result = resumed_trx_agg_state.add(addrs[3], transcripts[3].clone());
+    assert!(matches!(result, Ok(None)));
+    let agg_trx = resumed_trx_agg_state
+        .add(addrs[4], transcripts[4].clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(DummyDKG::get_dealers(&agg_trx), BTreeSet::from([0, 3, 4]));
+}
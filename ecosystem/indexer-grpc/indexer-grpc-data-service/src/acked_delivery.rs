@@ -0,0 +1,614 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Acknowledged delivery for the data service streams. A client opts in by naming a session
+//! (per tenant), and the server buffers the batches sent on the session until the client acks
+//! them. Acks are piggybacked on the (re)connect requests of the session: the client passes the
+//! last version it has durably processed, the server drops the acked batches, replays the unacked
+//! ones, and then continues the stream right after them. This allows processors that checkpoint
+//! asynchronously to reconnect without missing or re-processing any transactions, i.e., with
+//! exactly-once semantics relative to their checkpoint.
+//!
+//! The buffer of each session is bounded. Once it is full, depending on the eviction policy,
+//! either the stream ends (until the client reconnects with an ack), or the oldest unacked
+//! batches are evicted (and later replayed from the cache and file store instead).
+
+use crate::metrics::{ACK_SESSIONS_COUNT, ACK_SESSION_EVENTS_COUNT};
+use aptos_protos::indexer::v1::TransactionsResponse;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tonic::Status;
+
+// By default, a session buffers up to this many unacked batches (i.e., responses).
+const DEFAULT_MAX_UNACKED_BATCHES: usize = 100;
+// By default, a session buffers up to this many (protobuf encoded) unacked bytes.
+const DEFAULT_MAX_UNACKED_BYTES: usize = 200 * 1024 * 1024;
+// By default, sessions without a stream are dropped after this duration.
+const DEFAULT_SESSION_TTL_SECS: u64 = 300;
+// By default, up to this many sessions are kept (across all tenants).
+const DEFAULT_MAX_SESSIONS: usize = 1_000;
+
+// Useful labels for the session event metrics
+const ACKED_LABEL: &str = "acked";
+const CREATED_LABEL: &str = "created";
+const EVICTED_LABEL: &str = "evicted";
+const EXPIRED_LABEL: &str = "expired";
+const FULL_LABEL: &str = "full";
+const REPLAY_MISS_LABEL: &str = "replay_miss";
+const REPLAYED_LABEL: &str = "replayed";
+const SUPERSEDED_LABEL: &str = "superseded";
+
+/// What to do once the unacked buffer of a session is full.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnackedBufferEvictionPolicy {
+    /// End the stream with `RESOURCE_EXHAUSTED`, until the client reconnects with an ack. No
+    /// batch is evicted, so the client can never fall further behind than the buffer allows.
+    #[default]
+    EndStream,
+    /// Evict the oldest unacked batches. If the client reconnects with an ack older than the
+    /// evicted batches, the stream resumes from the cache and file store instead of the buffer.
+    EvictOldest,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AckedDeliveryConfig {
+    /// Whether clients can open acknowledged delivery sessions. Disabled by default.
+    pub enabled: bool,
+    /// The max number of unacked batches buffered per session.
+    pub max_unacked_batches: usize,
+    /// The max number of (protobuf encoded) unacked bytes buffered per session.
+    pub max_unacked_bytes: usize,
+    /// What to do once the unacked buffer of a session is full.
+    pub eviction_policy: UnackedBufferEvictionPolicy,
+    /// Sessions without a stream are dropped after this duration.
+    pub session_ttl_secs: u64,
+    /// The max number of sessions, across all tenants.
+    pub max_sessions: usize,
+}
+
+impl AckedDeliveryConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.enabled
+            && (self.max_unacked_batches == 0
+                || self.max_unacked_bytes == 0
+                || self.max_sessions == 0)
+        {
+            anyhow::bail!(
+                "max_unacked_batches, max_unacked_bytes and max_sessions must be > 0, if enabled"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for AckedDeliveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_unacked_batches: DEFAULT_MAX_UNACKED_BATCHES,
+            max_unacked_bytes: DEFAULT_MAX_UNACKED_BYTES,
+            eviction_policy: UnackedBufferEvictionPolicy::default(),
+            session_ttl_secs: DEFAULT_SESSION_TTL_SECS,
+            max_sessions: DEFAULT_MAX_SESSIONS,
+        }
+    }
+}
+
+/// A batch that was sent to the client, but is not acked yet.
+#[derive(Debug)]
+struct UnackedBatch {
+    response: TransactionsResponse,
+    num_bytes: usize,
+}
+
+impl UnackedBatch {
+    fn new(response: TransactionsResponse) -> Self {
+        let num_bytes = response.encoded_len();
+        Self {
+            response,
+            num_bytes,
+        }
+    }
+
+    fn end_version(&self) -> u64 {
+        self.response
+            .transactions
+            .last()
+            .map_or(0, |transaction| transaction.version)
+    }
+}
+
+/// The state of a single session, shared by its (consecutive) streams.
+#[derive(Debug)]
+struct AckSession {
+    tenant: String,
+    // The version the next stream of the session resumes from (after replaying the buffer).
+    next_version: u64,
+    // The last version acked by the client, if any.
+    acked_version: Option<u64>,
+    unacked_batches: VecDeque<UnackedBatch>,
+    unacked_bytes: usize,
+    // The end version of the last evicted batch, if any batch was evicted since the last ack.
+    evicted_version: Option<u64>,
+    // Incremented by every new stream of the session, so that superseded streams end.
+    stream_generation: u64,
+    has_stream: bool,
+    last_active_time: Instant,
+}
+
+impl AckSession {
+    fn new(tenant: &str, next_version: u64) -> Self {
+        Self {
+            tenant: tenant.to_string(),
+            next_version,
+            acked_version: None,
+            unacked_batches: VecDeque::new(),
+            unacked_bytes: 0,
+            evicted_version: None,
+            stream_generation: 0,
+            has_stream: false,
+            last_active_time: Instant::now(),
+        }
+    }
+
+    fn is_expired(&self, ttl: Duration, now: Instant) -> bool {
+        !self.has_stream && now.saturating_duration_since(self.last_active_time) > ttl
+    }
+
+    fn pop_oldest_batch(&mut self) -> Option<UnackedBatch> {
+        let batch = self.unacked_batches.pop_front()?;
+        self.unacked_bytes -= batch.num_bytes;
+        Some(batch)
+    }
+
+    /// Acks all transactions up to (and including) the given version.
+    fn ack(&mut self, version: u64) -> Result<(), Status> {
+        if version >= self.next_version {
+            return Err(Status::invalid_argument(format!(
+                "Acked version {} was never sent on the session (next version: {})",
+                version, self.next_version
+            )));
+        }
+
+        // If the acked version is not covered by the buffer (i.e., it is older than the evicted
+        // batches or the previous ack), the batches after it can't be replayed from the buffer.
+        let is_covered = self
+            .evicted_version
+            .map_or(true, |evicted| version >= evicted)
+            && self.acked_version.map_or(true, |acked| version >= acked);
+        if !is_covered {
+            ACK_SESSION_EVENTS_COUNT
+                .with_label_values(&[&self.tenant, REPLAY_MISS_LABEL])
+                .inc();
+            self.unacked_batches.clear();
+            self.unacked_bytes = 0;
+            self.next_version = version + 1;
+        }
+
+        let mut num_acked = 0;
+        while self
+            .unacked_batches
+            .front()
+            .map_or(false, |batch| batch.end_version() <= version)
+        {
+            self.pop_oldest_batch();
+            num_acked += 1;
+        }
+        // The oldest remaining batch may be partially acked, in which case it's trimmed.
+        if let Some(batch) = self.unacked_batches.front_mut() {
+            batch
+                .response
+                .transactions
+                .retain(|transaction| transaction.version > version);
+            let num_bytes = batch.response.encoded_len();
+            self.unacked_bytes = self.unacked_bytes - batch.num_bytes + num_bytes;
+            batch.num_bytes = num_bytes;
+        }
+        ACK_SESSION_EVENTS_COUNT
+            .with_label_values(&[&self.tenant, ACKED_LABEL])
+            .inc_by(num_acked);
+
+        self.acked_version = Some(version);
+        self.evicted_version = None;
+        Ok(())
+    }
+}
+
+/// Tracks the acknowledged delivery sessions of all tenants.
+#[derive(Debug)]
+pub struct AckSessionManager {
+    config: AckedDeliveryConfig,
+    sessions: Mutex<HashMap<(String, String), Arc<Mutex<AckSession>>>>,
+}
+
+impl AckSessionManager {
+    pub fn new(config: AckedDeliveryConfig) -> Self {
+        Self {
+            config,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Attaches a new stream to the given session (creating the session if needed), after
+    /// applying the piggybacked ack (if any). The returned stream replays the unacked batches
+    /// of the session first, and supersedes any previous stream of the session.
+    ///
+    /// Note: the starting version only applies to new sessions without an ack. Otherwise, the
+    /// stream resumes from the session (or right after the acked version, if the session expired).
+    pub fn attach(
+        &self,
+        tenant: &str,
+        session_id: &str,
+        starting_version: Option<u64>,
+        acked_version: Option<u64>,
+    ) -> Result<AckSessionStream, Status> {
+        if !self.config.enabled {
+            return Err(Status::invalid_argument(
+                "Acknowledged delivery is not enabled on this data service",
+            ));
+        }
+
+        let session = {
+            let mut sessions = self.sessions.lock().unwrap();
+            self.remove_expired_sessions(&mut sessions);
+            let key = (tenant.to_string(), session_id.to_string());
+            match sessions.get(&key) {
+                Some(session) => session.clone(),
+                None => {
+                    if sessions.len() >= self.config.max_sessions {
+                        return Err(Status::resource_exhausted(
+                            "Too many acknowledged delivery sessions; please retry later.",
+                        ));
+                    }
+                    let next_version = match (acked_version, starting_version) {
+                        (Some(acked_version), _) => acked_version + 1,
+                        (None, Some(starting_version)) => starting_version,
+                        (None, None) => return Err(Status::aborted("Starting version is not set")),
+                    };
+                    let session = Arc::new(Mutex::new(AckSession::new(tenant, next_version)));
+                    sessions.insert(key, session.clone());
+                    ACK_SESSIONS_COUNT.set(sessions.len() as i64);
+                    ACK_SESSION_EVENTS_COUNT
+                        .with_label_values(&[tenant, CREATED_LABEL])
+                        .inc();
+                    // A new session has nothing to ack
+                    return self.attach_stream(session, None);
+                },
+            }
+        };
+        self.attach_stream(session, acked_version)
+    }
+
+    fn attach_stream(
+        &self,
+        session: Arc<Mutex<AckSession>>,
+        acked_version: Option<u64>,
+    ) -> Result<AckSessionStream, Status> {
+        let (stream_generation, replay, start_version) = {
+            let mut state = session.lock().unwrap();
+            if let Some(acked_version) = acked_version {
+                state.ack(acked_version)?;
+            }
+            state.stream_generation += 1;
+            state.has_stream = true;
+            state.last_active_time = Instant::now();
+
+            let replay: Vec<_> = state
+                .unacked_batches
+                .iter()
+                .map(|batch| batch.response.clone())
+                .collect();
+            ACK_SESSION_EVENTS_COUNT
+                .with_label_values(&[&state.tenant, REPLAYED_LABEL])
+                .inc_by(replay.len() as u64);
+            (state.stream_generation, replay, state.next_version)
+        };
+        Ok(AckSessionStream {
+            config: self.config.clone(),
+            session,
+            stream_generation,
+            replay,
+            start_version,
+        })
+    }
+
+    fn remove_expired_sessions(
+        &self,
+        sessions: &mut HashMap<(String, String), Arc<Mutex<AckSession>>>,
+    ) {
+        let ttl = Duration::from_secs(self.config.session_ttl_secs);
+        let now = Instant::now();
+        sessions.retain(|(tenant, _), session| {
+            let is_expired = session.lock().unwrap().is_expired(ttl, now);
+            if is_expired {
+                ACK_SESSION_EVENTS_COUNT
+                    .with_label_values(&[tenant, EXPIRED_LABEL])
+                    .inc();
+            }
+            !is_expired
+        });
+        ACK_SESSIONS_COUNT.set(sessions.len() as i64);
+    }
+}
+
+/// A stream attached to an acknowledged delivery session. All batches sent on the stream must
+/// be buffered via the session first.
+#[derive(Debug)]
+pub struct AckSessionStream {
+    config: AckedDeliveryConfig,
+    session: Arc<Mutex<AckSession>>,
+    stream_generation: u64,
+    replay: Vec<TransactionsResponse>,
+    start_version: u64,
+}
+
+impl AckSessionStream {
+    /// The version the stream continues from, after the replayed batches.
+    pub fn start_version(&self) -> u64 {
+        self.start_version
+    }
+
+    /// Takes the unacked batches to be replayed at the start of the stream.
+    pub fn take_replay(&mut self) -> Vec<TransactionsResponse> {
+        std::mem::take(&mut self.replay)
+    }
+
+    /// Buffers the given batches before they are sent. Fails if the stream is superseded by
+    /// a newer stream of the session, or if the buffer is full (and can't be evicted).
+    pub fn buffer(&self, responses: &[TransactionsResponse]) -> Result<(), Status> {
+        let mut session = self.session.lock().unwrap();
+        if session.stream_generation != self.stream_generation {
+            ACK_SESSION_EVENTS_COUNT
+                .with_label_values(&[&session.tenant, SUPERSEDED_LABEL])
+                .inc();
+            return Err(Status::aborted(
+                "The stream is superseded by a newer stream of the same session.",
+            ));
+        }
+
+        let batches: Vec<_> = responses
+            .iter()
+            .filter(|response| !response.transactions.is_empty())
+            .map(|response| UnackedBatch::new(response.clone()))
+            .collect();
+        let num_bytes: usize = batches.iter().map(|batch| batch.num_bytes).sum();
+        let is_full = |session: &AckSession| {
+            session.unacked_batches.len() + batches.len() > self.config.max_unacked_batches
+                || session.unacked_bytes + num_bytes > self.config.max_unacked_bytes
+        };
+        // Note: an empty buffer always accepts the batches, so that the stream can progress.
+        if !session.unacked_batches.is_empty() && is_full(&session) {
+            match self.config.eviction_policy {
+                UnackedBufferEvictionPolicy::EndStream => {
+                    ACK_SESSION_EVENTS_COUNT
+                        .with_label_values(&[&session.tenant, FULL_LABEL])
+                        .inc();
+                    return Err(Status::resource_exhausted(format!(
+                        "Too many unacked batches; reconnect the session with an ack (the last \
+                         processed version). Next version: {}.",
+                        session.next_version
+                    )));
+                },
+                UnackedBufferEvictionPolicy::EvictOldest => {
+                    while !session.unacked_batches.is_empty() && is_full(&session) {
+                        if let Some(batch) = session.pop_oldest_batch() {
+                            session.evicted_version = Some(batch.end_version());
+                            ACK_SESSION_EVENTS_COUNT
+                                .with_label_values(&[&session.tenant, EVICTED_LABEL])
+                                .inc();
+                        }
+                    }
+                },
+            }
+        }
+
+        if let Some(batch) = batches.last() {
+            session.next_version = batch.end_version() + 1;
+        }
+        session.unacked_bytes += num_bytes;
+        session.unacked_batches.extend(batches);
+        session.last_active_time = Instant::now();
+        Ok(())
+    }
+}
+
+impl Drop for AckSessionStream {
+    fn drop(&mut self) {
+        let mut session = self.session.lock().unwrap();
+        if session.stream_generation == self.stream_generation {
+            session.has_stream = false;
+            session.last_active_time = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::Transaction;
+
+    fn create_response(versions: std::ops::RangeInclusive<u64>) -> TransactionsResponse {
+        TransactionsResponse {
+            transactions: versions
+                .map(|version| Transaction {
+                    version,
+                    ..Transaction::default()
+                })
+                .collect(),
+            chain_id: Some(1),
+        }
+    }
+
+    fn create_manager(
+        max_unacked_batches: usize,
+        eviction_policy: UnackedBufferEvictionPolicy,
+    ) -> AckSessionManager {
+        AckSessionManager::new(AckedDeliveryConfig {
+            enabled: true,
+            max_unacked_batches,
+            eviction_policy,
+            ..AckedDeliveryConfig::default()
+        })
+    }
+
+    fn get_versions(responses: &[TransactionsResponse]) -> Vec<u64> {
+        responses
+            .iter()
+            .flat_map(|response| response.transactions.iter().map(|txn| txn.version))
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_unacked_batches() {
+        let manager = create_manager(10, UnackedBufferEvictionPolicy::EndStream);
+
+        // A new session starts from the starting version, without any replay
+        let mut stream = manager.attach("tenant", "session", Some(10), None).unwrap();
+        assert_eq!(stream.start_version(), 10);
+        assert!(stream.take_replay().is_empty());
+        stream
+            .buffer(&[create_response(10..=14), create_response(15..=19)])
+            .unwrap();
+        stream.buffer(&[create_response(20..=24)]).unwrap();
+        drop(stream);
+
+        // Reconnecting with a partial ack replays the unacked transactions only
+        let mut stream = manager.attach("tenant", "session", None, Some(16)).unwrap();
+        assert_eq!(stream.start_version(), 25);
+        assert_eq!(
+            get_versions(&stream.take_replay()),
+            (17..=24).collect::<Vec<_>>()
+        );
+
+        // Reconnecting without an ack replays the same transactions again
+        let mut stream = manager.attach("tenant", "session", None, None).unwrap();
+        assert_eq!(
+            get_versions(&stream.take_replay()),
+            (17..=24).collect::<Vec<_>>()
+        );
+
+        // Acking everything leaves nothing to replay
+        let mut stream = manager.attach("tenant", "session", None, Some(24)).unwrap();
+        assert!(stream.take_replay().is_empty());
+        assert_eq!(stream.start_version(), 25);
+
+        // Acks beyond the sent transactions are rejected
+        assert!(manager.attach("tenant", "session", None, Some(25)).is_err());
+
+        // Sessions are scoped by tenant
+        let stream = manager.attach("other", "session", Some(0), None).unwrap();
+        assert_eq!(stream.start_version(), 0);
+    }
+
+    #[test]
+    fn test_superseded_stream() {
+        let manager = create_manager(10, UnackedBufferEvictionPolicy::EndStream);
+        let old_stream = manager.attach("tenant", "session", Some(0), None).unwrap();
+        old_stream.buffer(&[create_response(0..=9)]).unwrap();
+
+        // A new stream of the session supersedes the old one
+        let new_stream = manager.attach("tenant", "session", None, None).unwrap();
+        assert!(old_stream.buffer(&[create_response(10..=19)]).is_err());
+        new_stream.buffer(&[create_response(10..=19)]).unwrap();
+
+        // Dropping the old stream doesn't detach the new one
+        drop(old_stream);
+        assert!(manager.sessions.lock().unwrap().values().all(|session| {
+            let session = session.lock().unwrap();
+            session.has_stream && session.stream_generation == 2
+        }));
+    }
+
+    #[test]
+    fn test_end_stream_when_full() {
+        let manager = create_manager(2, UnackedBufferEvictionPolicy::EndStream);
+        let stream = manager.attach("tenant", "session", Some(0), None).unwrap();
+        stream
+            .buffer(&[create_response(0..=9), create_response(10..=19)])
+            .unwrap();
+
+        // The buffer is full, so the stream ends
+        let status = stream.buffer(&[create_response(20..=29)]).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        drop(stream);
+
+        // Once acked, the stream continues
+        let mut stream = manager.attach("tenant", "session", None, Some(9)).unwrap();
+        assert_eq!(
+            get_versions(&stream.take_replay()),
+            (10..=19).collect::<Vec<_>>()
+        );
+        assert_eq!(stream.start_version(), 20);
+        stream.buffer(&[create_response(20..=29)]).unwrap();
+    }
+
+    #[test]
+    fn test_evict_oldest_when_full() {
+        let manager = create_manager(2, UnackedBufferEvictionPolicy::EvictOldest);
+        let stream = manager.attach("tenant", "session", Some(0), None).unwrap();
+        stream
+            .buffer(&[create_response(0..=9), create_response(10..=19)])
+            .unwrap();
+        stream.buffer(&[create_response(20..=29)]).unwrap();
+        drop(stream);
+
+        // The oldest batch is evicted, so an older ack resumes from the stores
+        let mut stream = manager.attach("tenant", "session", None, Some(4)).unwrap();
+        assert!(stream.take_replay().is_empty());
+        assert_eq!(stream.start_version(), 5);
+        stream.buffer(&[create_response(5..=9)]).unwrap();
+        drop(stream);
+
+        // Acks covered by the buffer replay from the buffer again
+        let mut stream = manager.attach("tenant", "session", None, Some(7)).unwrap();
+        assert_eq!(get_versions(&stream.take_replay()), vec![8, 9]);
+        assert_eq!(stream.start_version(), 10);
+    }
+
+    #[test]
+    fn test_session_expiration() {
+        let manager = AckSessionManager::new(AckedDeliveryConfig {
+            enabled: true,
+            session_ttl_secs: 0,
+            max_sessions: 1,
+            ..AckedDeliveryConfig::default()
+        });
+        let stream = manager
+            .attach("tenant", "session_1", Some(0), None)
+            .unwrap();
+        stream.buffer(&[create_response(0..=9)]).unwrap();
+
+        // Sessions with a stream never expire
+        assert!(manager
+            .attach("tenant", "session_2", Some(0), None)
+            .is_err());
+
+        // Once the stream ends, the session expires
+        drop(stream);
+        std::thread::sleep(Duration::from_millis(10));
+        drop(
+            manager
+                .attach("tenant", "session_2", Some(0), None)
+                .unwrap(),
+        );
+
+        // An expired session resumes right after the ack
+        std::thread::sleep(Duration::from_millis(10));
+        let mut stream = manager
+            .attach("tenant", "session_1", Some(0), Some(5))
+            .unwrap();
+        assert!(stream.take_replay().is_empty());
+        assert_eq!(stream.start_version(), 6);
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Historical backfills from the file store. Requests that start far behind the head of the
+//! file store are split into shards (i.e., contiguous version ranges of whole files), which are
+//! read from the file store in parallel (with bounded concurrency) and merged back into a single
+//! ordered stream. Each shard tracks its progress, so that a failed read is retried from the
+//! last file read (instead of the start of the shard).
+
+use crate::{
+    metrics::{BACKFILL_SHARDS_COUNT, BACKFILL_SHARD_RETRIES_COUNT},
+    service::NUM_DATA_FETCH_RETRIES,
+};
+use anyhow::{ensure, Context, Result};
+use aptos_indexer_grpc_utils::{
+    compression_util::FILE_ENTRY_TRANSACTION_COUNT, file_store_operator::FileStoreOperator,
+};
+use aptos_protos::transaction::v1::Transaction;
+use futures::{stream::BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tracing::{info, warn};
+
+// Failed shard reads are retried after this duration (multiplied by the number of retries).
+const SHARD_RETRY_SLEEP_DURATION_MS: u64 = 100;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackfillConfig {
+    /// Whether requests far behind the file store head are backfilled in parallel.
+    pub enabled: bool,
+    /// The min number of versions between the request and the file store head for the request
+    /// to be backfilled. Requests closer to the head are served sequentially.
+    pub min_backfill_versions: u64,
+    /// The number of versions per shard. This has to be a multiple of the file size.
+    pub shard_size: u64,
+    /// The max number of shards read concurrently (per stream).
+    pub max_concurrent_shards: usize,
+    /// The max number of times a shard is retried (from its last checkpoint) before the
+    /// stream is terminated.
+    pub max_shard_retries: u64,
+}
+
+impl BackfillConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.shard_size == 0 || self.shard_size % FILE_ENTRY_TRANSACTION_COUNT != 0 {
+            anyhow::bail!(
+                "shard_size must be a positive multiple of {}",
+                FILE_ENTRY_TRANSACTION_COUNT
+            );
+        }
+        if self.max_concurrent_shards == 0 {
+            anyhow::bail!("max_concurrent_shards must be > 0");
+        }
+        Ok(())
+    }
+
+    /// Returns the shards to backfill for a request starting at the given version (for the
+    /// given number of transactions, if set). Only versions below the file store head are
+    /// backfilled. Returns no shards if the request is not far enough behind the head.
+    pub fn get_shards(
+        &self,
+        start_version: u64,
+        transactions_count: Option<u64>,
+        file_store_version: u64,
+    ) -> Vec<BackfillShard> {
+        if !self.enabled {
+            return vec![];
+        }
+        let end_version = match transactions_count {
+            Some(transactions_count) => {
+                file_store_version.min(start_version.saturating_add(transactions_count))
+            },
+            None => file_store_version,
+        };
+        if end_version.saturating_sub(start_version) < self.min_backfill_versions.max(1) {
+            return vec![];
+        }
+        split_into_shards(start_version, end_version, self.shard_size)
+    }
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_backfill_versions: 100_000,
+            shard_size: 10_000,
+            max_concurrent_shards: 4,
+            max_shard_retries: 3,
+        }
+    }
+}
+
+/// A shard of a backfill, i.e., the versions [start_version, end_version).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BackfillShard {
+    pub start_version: u64,
+    pub end_version: u64,
+}
+
+/// Splits the versions [start_version, end_version) into shards. The shards (except the
+/// first and last) are aligned to the shard size, so that they consist of whole files.
+fn split_into_shards(start_version: u64, end_version: u64, shard_size: u64) -> Vec<BackfillShard> {
+    let mut shards = vec![];
+    let mut shard_start_version = start_version;
+    while shard_start_version < end_version {
+        let shard_end_version = (shard_start_version / shard_size + 1)
+            .saturating_mul(shard_size)
+            .min(end_version);
+        shards.push(BackfillShard {
+            start_version: shard_start_version,
+            end_version: shard_end_version,
+        });
+        shard_start_version = shard_end_version;
+    }
+    shards
+}
+
+/// A backfill in progress: the shards are read concurrently, but returned in version order.
+pub struct Backfill {
+    shards: BoxStream<'static, (BackfillShard, Result<Vec<Transaction>>)>,
+    num_shards: usize,
+    num_completed_shards: usize,
+}
+
+impl Backfill {
+    pub fn new(
+        file_store_operator: Arc<Box<dyn FileStoreOperator>>,
+        shards: Vec<BackfillShard>,
+        config: &BackfillConfig,
+    ) -> Self {
+        let num_shards = shards.len();
+        let max_shard_retries = config.max_shard_retries;
+        let shards = futures::stream::iter(shards)
+            .map(move |shard| {
+                let file_store_operator = file_store_operator.clone();
+                async move {
+                    let result = read_shard(
+                        file_store_operator.as_ref().as_ref(),
+                        shard,
+                        max_shard_retries,
+                    )
+                    .await;
+                    (shard, result)
+                }
+            })
+            .buffered(config.max_concurrent_shards)
+            .boxed();
+        Self {
+            shards,
+            num_shards,
+            num_completed_shards: 0,
+        }
+    }
+
+    /// Returns the transactions of the next shard (in version order), or None if the
+    /// backfill is complete. A shard that fails (after all retries) fails the backfill.
+    pub async fn next_shard(&mut self) -> Option<Result<Vec<Transaction>>> {
+        let (shard, result) = self.shards.next().await?;
+        match &result {
+            Ok(_) => {
+                BACKFILL_SHARDS_COUNT.with_label_values(&["success"]).inc();
+                self.num_completed_shards += 1;
+                info!(
+                    start_version = shard.start_version,
+                    end_version = shard.end_version,
+                    num_completed_shards = self.num_completed_shards,
+                    num_shards = self.num_shards,
+                    "[Data Service] Backfill shard completed."
+                );
+            },
+            Err(e) => {
+                BACKFILL_SHARDS_COUNT.with_label_values(&["failed"]).inc();
+                warn!(
+                    start_version = shard.start_version,
+                    end_version = shard.end_version,
+                    error = e.to_string(),
+                    "[Data Service] Backfill shard failed."
+                );
+            },
+        }
+        Some(result)
+    }
+}
+
+/// Reads the transactions of the shard from the file store. The next version to read is
+/// checkpointed after each file, and failed reads are retried from the checkpoint.
+async fn read_shard(
+    file_store_operator: &dyn FileStoreOperator,
+    shard: BackfillShard,
+    max_shard_retries: u64,
+) -> Result<Vec<Transaction>> {
+    let mut transactions = vec![];
+    let mut next_version = shard.start_version;
+    let mut num_retries = 0;
+    while next_version < shard.end_version {
+        match file_store_operator
+            .get_transactions(next_version, NUM_DATA_FETCH_RETRIES)
+            .await
+        {
+            Ok(mut file_transactions) => {
+                file_transactions.retain(|transaction| transaction.version < shard.end_version);
+                let first_version = file_transactions
+                    .first()
+                    .map(|transaction| transaction.version);
+                ensure!(
+                    first_version == Some(next_version),
+                    "Unexpected first version {:?} in the file store, expected {}",
+                    first_version,
+                    next_version
+                );
+                next_version = file_transactions.last().unwrap().version + 1;
+                transactions.extend(file_transactions);
+            },
+            Err(e) => {
+                if num_retries >= max_shard_retries {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Failed to read version {} of shard [{}, {}) after {} retries",
+                            next_version, shard.start_version, shard.end_version, num_retries
+                        )
+                    });
+                }
+                num_retries += 1;
+                BACKFILL_SHARD_RETRIES_COUNT.inc();
+                tokio::time::sleep(Duration::from_millis(
+                    SHARD_RETRY_SLEEP_DURATION_MS * num_retries,
+                ))
+                .await;
+            },
+        }
+    }
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_indexer_grpc_utils::compression_util::{FileEntry, FileStoreMetadata, StorageFormat};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// A file store that fails the first reads of the file at the given version
+    #[derive(Clone)]
+    struct MockFileStoreOperator {
+        failing_version: u64,
+        num_failures_left: Arc<AtomicU64>,
+    }
+
+    #[async_trait::async_trait]
+    impl FileStoreOperator for MockFileStoreOperator {
+        async fn verify_storage_bucket_existence(&self) {}
+
+        fn storage_format(&self) -> StorageFormat {
+            StorageFormat::GzipCompressedProto
+        }
+
+        fn store_name(&self) -> &str {
+            "Mock"
+        }
+
+        async fn get_raw_file(&self, version: u64) -> Result<Vec<u8>> {
+            let starting_version =
+                version / FILE_ENTRY_TRANSACTION_COUNT * FILE_ENTRY_TRANSACTION_COUNT;
+            if starting_version == self.failing_version
+                && self
+                    .num_failures_left
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok()
+            {
+                anyhow::bail!("Injected failure for version {}", version);
+            }
+            let transactions = (starting_version..starting_version + FILE_ENTRY_TRANSACTION_COUNT)
+                .map(|version| Transaction {
+                    version,
+                    ..Default::default()
+                })
+                .collect();
+            Ok(FileEntry::from_transactions(transactions, self.storage_format()).into_inner())
+        }
+
+        async fn try_get_file_store_metadata(&self) -> Result<Option<FileStoreMetadata>> {
+            unimplemented!()
+        }
+
+        async fn update_file_store_metadata_with_timeout(
+            &mut self,
+            _expected_chain_id: u64,
+            _version: u64,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn update_file_store_metadata_internal(
+            &mut self,
+            _chain_id: u64,
+            _version: u64,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn upload_transaction_batch(
+            &mut self,
+            _chain_id: u64,
+            _batch: Vec<Transaction>,
+        ) -> Result<(u64, u64)> {
+            unimplemented!()
+        }
+
+        fn clone_box(&self) -> Box<dyn FileStoreOperator> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn create_file_store(
+        failing_version: u64,
+        num_failures: u64,
+    ) -> Arc<Box<dyn FileStoreOperator>> {
+        Arc::new(Box::new(MockFileStoreOperator {
+            failing_version,
+            num_failures_left: Arc::new(AtomicU64::new(num_failures)),
+        }))
+    }
+
+    fn create_config(shard_size: u64) -> BackfillConfig {
+        BackfillConfig {
+            enabled: true,
+            min_backfill_versions: 1,
+            shard_size,
+            max_concurrent_shards: 3,
+            max_shard_retries: 1,
+        }
+    }
+
+    #[test]
+    fn test_get_shards() {
+        let config = create_config(10_000);
+        let shards = config.get_shards(5_500, None, 25_000);
+        assert_eq!(shards, vec![
+            BackfillShard {
+                start_version: 5_500,
+                end_version: 10_000
+            },
+            BackfillShard {
+                start_version: 10_000,
+                end_version: 20_000
+            },
+            BackfillShard {
+                start_version: 20_000,
+                end_version: 25_000
+            },
+        ]);
+
+        // The transactions count limits the backfill
+        let shards = config.get_shards(5_500, Some(1_000), 25_000);
+        assert_eq!(shards, vec![BackfillShard {
+            start_version: 5_500,
+            end_version: 6_500
+        }]);
+
+        // Requests at (or ahead of) the file store head are not backfilled
+        assert!(config.get_shards(25_000, None, 25_000).is_empty());
+        assert!(config.get_shards(30_000, None, 25_000).is_empty());
+
+        // Requests close to the head (or with backfills disabled) are not backfilled
+        let config = BackfillConfig {
+            min_backfill_versions: 20_000,
+            ..create_config(10_000)
+        };
+        assert!(config.get_shards(5_500, None, 25_000).is_empty());
+        assert!(BackfillConfig::default()
+            .get_shards(0, None, 25_000)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(create_config(10_000).validate().is_ok());
+        assert!(create_config(0).validate().is_err());
+        assert!(create_config(1_500).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backfill_is_ordered_and_retried() {
+        // Each read of the file at version 4_000 fails once (after the inner retries)
+        let file_store = create_file_store(4_000, NUM_DATA_FETCH_RETRIES as u64 + 1);
+        let config = create_config(2_000);
+        let shards = config.get_shards(1_500, Some(6_000), 100_000);
+        assert_eq!(shards.len(), 4);
+
+        let mut backfill = Backfill::new(file_store, shards, &config);
+        let mut versions = vec![];
+        while let Some(transactions) = backfill.next_shard().await {
+            versions.extend(transactions.unwrap().iter().map(|txn| txn.version));
+        }
+        assert_eq!(versions, (1_500..7_500).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_backfill_fails_after_max_retries() {
+        // The file at version 2_000 fails more often than the shard retries allow
+        let file_store = create_file_store(2_000, u64::MAX);
+        let config = create_config(2_000);
+        let shards = config.get_shards(0, Some(6_000), 100_000);
+
+        let mut backfill = Backfill::new(file_store, shards, &config);
+        assert_eq!(backfill.next_shard().await.unwrap().unwrap().len(), 2_000);
+        assert!(backfill.next_shard().await.unwrap().is_err());
+    }
+}
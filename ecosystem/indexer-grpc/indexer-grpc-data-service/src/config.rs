@@ -1,11 +1,20 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::service::RawDataServerWrapper;
+use crate::{
+    acked_delivery::{AckSessionManager, AckedDeliveryConfig},
+    backfill::BackfillConfig,
+    quota::{TenantQuotaConfig, TenantQuotaManager},
+    service::RawDataServerWrapper,
+};
 use anyhow::{bail, Result};
 use aptos_indexer_grpc_server_framework::RunnableConfig;
 use aptos_indexer_grpc_utils::{
-    compression_util::StorageFormat, config::IndexerGrpcFileStoreConfig, types::RedisUrl,
+    compression_util::StorageFormat,
+    config::IndexerGrpcFileStoreConfig,
+    health::{serve_health_checks, HealthCheckConfig, HealthChecker},
+    in_memory_cache::{InMemoryCache, InMemoryCacheConfig},
+    types::RedisUrl,
 };
 use aptos_protos::{
     indexer::v1::FILE_DESCRIPTOR_SET as INDEXER_V1_FILE_DESCRIPTOR_SET,
@@ -69,6 +78,21 @@ pub struct IndexerGrpcDataServiceConfig {
     /// Sender addresses to ignore. Transactions from these addresses will not be indexed.
     #[serde(default = "IndexerGrpcDataServiceConfig::default_sender_addresses_to_ignore")]
     pub sender_addresses_to_ignore: Vec<String>,
+    /// If given, we will serve the liveness and readiness of the cache and the file store.
+    #[serde(default)]
+    pub health_check_config: Option<HealthCheckConfig>,
+    /// The memory budget of the in-memory transaction cache.
+    #[serde(default)]
+    pub in_memory_cache_config: InMemoryCacheConfig,
+    /// The per-tenant (i.e., per API key) quotas. By default, no quotas are enforced.
+    #[serde(default)]
+    pub tenant_quota_config: TenantQuotaConfig,
+    /// Parallel backfills of historical ranges from the file store. Disabled by default.
+    #[serde(default)]
+    pub backfill_config: BackfillConfig,
+    /// Acknowledged delivery sessions, with replay of the unacked batches. Disabled by default.
+    #[serde(default)]
+    pub acked_delivery_config: AckedDeliveryConfig,
 }
 
 impl IndexerGrpcDataServiceConfig {
@@ -81,6 +105,11 @@ impl IndexerGrpcDataServiceConfig {
         redis_read_replica_address: RedisUrl,
         enable_cache_compression: bool,
         sender_addresses_to_ignore: Vec<String>,
+        health_check_config: Option<HealthCheckConfig>,
+        in_memory_cache_config: InMemoryCacheConfig,
+        tenant_quota_config: TenantQuotaConfig,
+        backfill_config: BackfillConfig,
+        acked_delivery_config: AckedDeliveryConfig,
     ) -> Self {
         Self {
             data_service_grpc_tls_config,
@@ -93,6 +122,11 @@ impl IndexerGrpcDataServiceConfig {
             redis_read_replica_address,
             enable_cache_compression,
             sender_addresses_to_ignore,
+            health_check_config,
+            in_memory_cache_config,
+            tenant_quota_config,
+            backfill_config,
+            acked_delivery_config,
         }
     }
 
@@ -117,6 +151,10 @@ impl RunnableConfig for IndexerGrpcDataServiceConfig {
         {
             bail!("At least one of data_service_grpc_non_tls_config and data_service_grpc_tls_config must be set");
         }
+        self.in_memory_cache_config.validate()?;
+        self.tenant_quota_config.validate()?;
+        self.backfill_config.validate()?;
+        self.acked_delivery_config.validate()?;
         Ok(())
     }
 
@@ -143,13 +181,25 @@ impl RunnableConfig for IndexerGrpcDataServiceConfig {
             .get_tokio_connection_manager()
             .await?;
 
-        // InMemoryCache.
-        let in_memory_cache =
-            aptos_indexer_grpc_utils::in_memory_cache::InMemoryCache::new_with_redis_connection(
-                redis_conn,
-                cache_storage_format,
+        let health_checker = self.health_check_config.as_ref().map(|config| {
+            (
+                Arc::new(HealthChecker::new(
+                    redis_conn.clone(),
+                    cache_storage_format,
+                    self.file_store_config.create(),
+                    config.max_lag_secs,
+                )),
+                config.listen_address,
             )
-            .await?;
+        });
+
+        // InMemoryCache.
+        let in_memory_cache = InMemoryCache::new_with_redis_connection(
+            redis_conn,
+            cache_storage_format,
+            self.in_memory_cache_config,
+        )
+        .await?;
         // Add authentication interceptor.
         let server = RawDataServerWrapper::new(
             self.redis_read_replica_address.clone(),
@@ -161,6 +211,9 @@ impl RunnableConfig for IndexerGrpcDataServiceConfig {
                 .collect::<HashSet<_>>(),
             cache_storage_format,
             Arc::new(in_memory_cache),
+            Arc::new(TenantQuotaManager::new(self.tenant_quota_config.clone())),
+            self.backfill_config.clone(),
+            Arc::new(AckSessionManager::new(self.acked_delivery_config.clone())),
         )?;
         let svc = aptos_protos::indexer::v1::raw_data_server::RawDataServer::new(server)
             .send_compressed(CompressionEncoding::Gzip)
@@ -209,6 +262,13 @@ impl RunnableConfig for IndexerGrpcDataServiceConfig {
             }));
         }
 
+        if let Some((health_checker, listen_address)) = health_checker {
+            tasks.push(tokio::spawn(async move {
+                serve_health_checks(health_checker, listen_address).await;
+                Ok(())
+            }));
+        }
+
         futures::future::try_join_all(tasks).await?;
         Ok(())
     }
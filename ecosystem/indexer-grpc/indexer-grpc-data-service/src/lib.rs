@@ -1,10 +1,16 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+mod acked_delivery;
+mod backfill;
 mod config;
 mod grpc_response_stream;
 mod metrics;
+mod quota;
 mod response_dispatcher;
 mod service;
 
+pub use acked_delivery::{AckedDeliveryConfig, UnackedBufferEvictionPolicy};
+pub use backfill::BackfillConfig;
 pub use config::{IndexerGrpcDataServiceConfig, NonTlsConfig, SERVER_NAME};
+pub use quota::{TenantQuota, TenantQuotaConfig};
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_metrics_core::{
-    register_gauge_vec, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec,
-    GaugeVec, HistogramVec, IntCounterVec, IntGaugeVec,
+    register_gauge_vec, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, register_int_gauge_vec, GaugeVec, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -97,3 +98,71 @@ pub static BYTES_READY_TO_TRANSFER_FROM_SERVER: Lazy<IntCounterVec> = Lazy::new(
     )
     .unwrap()
 });
+
+/// Number of active streams per tenant.
+pub static TENANT_ACTIVE_STREAMS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "indexer_grpc_data_service_tenant_active_streams",
+        "Number of active streams per tenant",
+        &["request_api_key_name"],
+    )
+    .unwrap()
+});
+
+/// Number of requests or streams rejected because the tenant exceeded its quota.
+pub static TENANT_OVER_QUOTA_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_grpc_data_service_tenant_over_quota_count",
+        "Number of requests or streams rejected because the tenant exceeded its quota",
+        &["request_api_key_name", "quota_type"],
+    )
+    .unwrap()
+});
+
+/// Total duration that the streams of a tenant were throttled, due to the rate quotas.
+pub static TENANT_THROTTLED_DURATION_IN_MS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_grpc_data_service_tenant_throttled_duration_in_ms",
+        "Total duration that the streams of a tenant were throttled",
+        &["request_api_key_name"],
+    )
+    .unwrap()
+});
+
+/// Number of backfill shards read from the file store, by result.
+pub static BACKFILL_SHARDS_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_grpc_data_service_backfill_shards_count",
+        "Number of backfill shards read from the file store",
+        &["result"],
+    )
+    .unwrap()
+});
+
+/// Number of backfill shard reads retried (from the last checkpoint).
+pub static BACKFILL_SHARD_RETRIES_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "indexer_grpc_data_service_backfill_shard_retries_count",
+        "Number of backfill shard reads retried",
+    )
+    .unwrap()
+});
+
+/// Number of acknowledged delivery sessions.
+pub static ACK_SESSIONS_COUNT: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "indexer_grpc_data_service_ack_sessions_count",
+        "Number of acknowledged delivery sessions",
+    )
+    .unwrap()
+});
+
+/// Number of acknowledged delivery session events (e.g., acked or replayed batches), by event.
+pub static ACK_SESSION_EVENTS_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_grpc_data_service_ack_session_events_count",
+        "Number of acknowledged delivery session events",
+        &["request_api_key_name", "event"],
+    )
+    .unwrap()
+});
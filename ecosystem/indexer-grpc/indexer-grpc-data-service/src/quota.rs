@@ -0,0 +1,469 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-tenant quotas for the data service. A tenant is identified by its API key name,
+//! and each tenant is limited in the number of concurrent streams, and in the rate of
+//! transactions and bytes streamed (via a token bucket per tenant). This prevents a single
+//! heavy consumer from degrading the service for the others.
+
+use crate::metrics::{
+    TENANT_ACTIVE_STREAMS, TENANT_OVER_QUOTA_COUNT, TENANT_THROTTLED_DURATION_IN_MS,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tonic::{metadata::MetadataMap, Code, Status};
+
+// The time (in milliseconds) the client should wait before retrying an over-quota request.
+const RESPONSE_HEADER_APTOS_RETRY_AFTER_MS_HEADER: &str = "x-aptos-retry-after-ms";
+// The quota (see `QuotaType`) that is exceeded.
+const RESPONSE_HEADER_APTOS_QUOTA_EXCEEDED_HEADER: &str = "x-aptos-quota-exceeded";
+
+// Clients over the stream quota are asked to retry after this duration.
+const DEFAULT_STREAM_QUOTA_RETRY_AFTER_MS: u64 = 5_000;
+// By default, streams over the rate quotas are throttled for up to this duration before the
+// stream is terminated with an over-quota error.
+const DEFAULT_MAX_THROTTLE_DURATION_MS: u64 = 10_000;
+// By default, the token buckets can hold this many seconds worth of the rate.
+const DEFAULT_BURST_DURATION_SECS: u64 = 10;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QuotaType {
+    Streams,
+    Transactions,
+    Bytes,
+}
+
+impl QuotaType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaType::Streams => "streams",
+            QuotaType::Transactions => "transactions",
+            QuotaType::Bytes => "bytes",
+        }
+    }
+}
+
+impl fmt::Display for QuotaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The quota of a single tenant. Unset limits are not enforced.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantQuota {
+    /// The max number of concurrent streams.
+    pub max_concurrent_streams: Option<u64>,
+    /// The max number of transactions streamed per second.
+    pub max_transactions_per_sec: Option<u64>,
+    /// The max number of (protobuf encoded) bytes streamed per second.
+    pub max_bytes_per_sec: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantQuotaConfig {
+    /// The quota of the tenants without an override.
+    pub default_quota: TenantQuota,
+    /// The quota overrides, keyed by the API key name of the tenant.
+    pub tenant_quota_overrides: HashMap<String, TenantQuota>,
+    /// The number of seconds worth of the rate that a tenant can burst.
+    pub burst_duration_secs: u64,
+    /// Streams over the rate quotas are throttled for up to this duration. Beyond this,
+    /// the stream is terminated with an over-quota error.
+    pub max_throttle_duration_ms: u64,
+}
+
+impl TenantQuotaConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.burst_duration_secs == 0 {
+            anyhow::bail!("burst_duration_secs must be > 0");
+        }
+        for (tenant, quota) in std::iter::once(("default", &self.default_quota)).chain(
+            self.tenant_quota_overrides
+                .iter()
+                .map(|(tenant, quota)| (tenant.as_str(), quota)),
+        ) {
+            if quota.max_transactions_per_sec == Some(0) || quota.max_bytes_per_sec == Some(0) {
+                anyhow::bail!("The rate quotas of tenant {} must be > 0, if set", tenant);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_quota(&self, tenant: &str) -> &TenantQuota {
+        self.tenant_quota_overrides
+            .get(tenant)
+            .unwrap_or(&self.default_quota)
+    }
+}
+
+impl Default for TenantQuotaConfig {
+    fn default() -> Self {
+        Self {
+            default_quota: TenantQuota::default(),
+            tenant_quota_overrides: HashMap::new(),
+            burst_duration_secs: DEFAULT_BURST_DURATION_SECS,
+            max_throttle_duration_ms: DEFAULT_MAX_THROTTLE_DURATION_MS,
+        }
+    }
+}
+
+/// The error returned when a tenant exceeds its quota.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuotaExceeded {
+    pub quota_type: QuotaType,
+    pub retry_after: Duration,
+}
+
+impl QuotaExceeded {
+    /// Converts the error into a `RESOURCE_EXHAUSTED` status, with the retry-after
+    /// and the exceeded quota as metadata.
+    pub fn into_status(self) -> Status {
+        let mut metadata = MetadataMap::new();
+        metadata.insert(
+            RESPONSE_HEADER_APTOS_RETRY_AFTER_MS_HEADER,
+            tonic::metadata::MetadataValue::from(self.retry_after.as_millis() as u64),
+        );
+        metadata.insert(
+            RESPONSE_HEADER_APTOS_QUOTA_EXCEEDED_HEADER,
+            tonic::metadata::MetadataValue::from_static(self.quota_type.as_str()),
+        );
+        Status::with_metadata(
+            Code::ResourceExhausted,
+            format!(
+                "Quota exceeded: {}. Retry after {} ms.",
+                self.quota_type,
+                self.retry_after.as_millis()
+            ),
+            metadata,
+        )
+    }
+}
+
+/// A token bucket that refills at a constant rate, up to its capacity. Consuming
+/// always succeeds, but may leave the bucket in debt, which must be repaid (i.e.,
+/// waited for) before the next consumption. This allows consuming more than the
+/// capacity at once (e.g., a large batch of transactions).
+#[derive(Debug)]
+struct TokenBucket {
+    rate_per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill_time: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate_per_sec: u64, burst_duration_secs: u64, now: Instant) -> Self {
+        let capacity = (rate_per_sec * burst_duration_secs) as f64;
+        Self {
+            rate_per_sec: rate_per_sec as f64,
+            capacity,
+            tokens: capacity,
+            last_refill_time: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill_time);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate_per_sec).min(self.capacity);
+        self.last_refill_time = now;
+    }
+
+    /// Returns the duration to wait until the bucket is out of debt.
+    fn time_to_repay(&self) -> Duration {
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate_per_sec)
+        }
+    }
+
+    /// Consumes the given amount of tokens, and returns the duration to wait before the
+    /// tokens can be used (i.e., until the bucket is out of debt).
+    fn consume(&mut self, amount: u64, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= amount as f64;
+        self.time_to_repay()
+    }
+}
+
+/// The usage of a single tenant, shared by all of its streams.
+#[derive(Debug)]
+struct TenantState {
+    tenant: String,
+    active_streams: u64,
+    transactions_bucket: Option<TokenBucket>,
+    bytes_bucket: Option<TokenBucket>,
+}
+
+/// Tracks and enforces the quotas of all tenants.
+#[derive(Debug)]
+pub struct TenantQuotaManager {
+    config: TenantQuotaConfig,
+    tenants: Mutex<HashMap<String, Arc<Mutex<TenantState>>>>,
+}
+
+impl TenantQuotaManager {
+    pub fn new(config: TenantQuotaConfig) -> Self {
+        Self {
+            config,
+            tenants: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Acquires a stream for the given tenant. The returned permit must be held for the
+    /// duration of the stream, and is used to enforce the rate quotas of the tenant.
+    pub fn acquire_stream(self: &Arc<Self>, tenant: &str) -> Result<StreamPermit, QuotaExceeded> {
+        let quota = self.config.get_quota(tenant);
+        let state = self
+            .tenants
+            .lock()
+            .unwrap()
+            .entry(tenant.to_string())
+            .or_insert_with(|| {
+                let now = Instant::now();
+                Arc::new(Mutex::new(TenantState {
+                    tenant: tenant.to_string(),
+                    active_streams: 0,
+                    transactions_bucket: quota
+                        .max_transactions_per_sec
+                        .map(|rate| TokenBucket::new(rate, self.config.burst_duration_secs, now)),
+                    bytes_bucket: quota
+                        .max_bytes_per_sec
+                        .map(|rate| TokenBucket::new(rate, self.config.burst_duration_secs, now)),
+                }))
+            })
+            .clone();
+
+        {
+            let mut state = state.lock().unwrap();
+            if let Some(max_concurrent_streams) = quota.max_concurrent_streams {
+                if state.active_streams >= max_concurrent_streams {
+                    TENANT_OVER_QUOTA_COUNT
+                        .with_label_values(&[tenant, QuotaType::Streams.as_str()])
+                        .inc();
+                    return Err(QuotaExceeded {
+                        quota_type: QuotaType::Streams,
+                        retry_after: Duration::from_millis(DEFAULT_STREAM_QUOTA_RETRY_AFTER_MS),
+                    });
+                }
+            }
+            state.active_streams += 1;
+            TENANT_ACTIVE_STREAMS
+                .with_label_values(&[tenant])
+                .set(state.active_streams as i64);
+        }
+
+        Ok(StreamPermit {
+            state,
+            max_throttle_duration: Duration::from_millis(self.config.max_throttle_duration_ms),
+        })
+    }
+}
+
+/// A stream of a tenant; the stream is released on drop.
+#[derive(Debug)]
+pub struct StreamPermit {
+    state: Arc<Mutex<TenantState>>,
+    max_throttle_duration: Duration,
+}
+
+impl StreamPermit {
+    /// Consumes the rate quotas for the given number of transactions and bytes, and returns
+    /// the duration to throttle the stream before sending them. If the stream would have
+    /// to be throttled for longer than the max throttle duration, an error is returned.
+    pub fn consume(
+        &self,
+        num_transactions: u64,
+        num_bytes: u64,
+    ) -> Result<Duration, QuotaExceeded> {
+        self.consume_at(num_transactions, num_bytes, Instant::now())
+    }
+
+    fn consume_at(
+        &self,
+        num_transactions: u64,
+        num_bytes: u64,
+        now: Instant,
+    ) -> Result<Duration, QuotaExceeded> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let mut buckets = [
+            (
+                state.transactions_bucket.as_mut(),
+                num_transactions,
+                QuotaType::Transactions,
+            ),
+            (state.bytes_bucket.as_mut(), num_bytes, QuotaType::Bytes),
+        ];
+
+        // Reject before consuming, so that terminated streams don't accumulate more debt
+        for (bucket, _, quota_type) in buckets.iter_mut() {
+            let Some(bucket) = bucket else {
+                continue;
+            };
+            bucket.refill(now);
+            let retry_after = bucket.time_to_repay();
+            if retry_after > self.max_throttle_duration {
+                TENANT_OVER_QUOTA_COUNT
+                    .with_label_values(&[state.tenant.as_str(), quota_type.as_str()])
+                    .inc();
+                return Err(QuotaExceeded {
+                    quota_type: *quota_type,
+                    retry_after,
+                });
+            }
+        }
+        let throttle_duration = buckets
+            .into_iter()
+            .filter_map(|(bucket, amount, _)| bucket.map(|bucket| bucket.consume(amount, now)))
+            .max()
+            .unwrap_or(Duration::ZERO);
+
+        if !throttle_duration.is_zero() {
+            TENANT_THROTTLED_DURATION_IN_MS
+                .with_label_values(&[state.tenant.as_str()])
+                .inc_by(throttle_duration.as_millis() as u64);
+        }
+        Ok(throttle_duration)
+    }
+}
+
+impl Drop for StreamPermit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.active_streams -= 1;
+        TENANT_ACTIVE_STREAMS
+            .with_label_values(&[state.tenant.as_str()])
+            .set(state.active_streams as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_quota_manager(quota: TenantQuota) -> Arc<TenantQuotaManager> {
+        Arc::new(TenantQuotaManager::new(TenantQuotaConfig {
+            default_quota: quota,
+            burst_duration_secs: 1,
+            max_throttle_duration_ms: 1_000,
+            ..TenantQuotaConfig::default()
+        }))
+    }
+
+    #[test]
+    fn test_concurrent_streams_quota() {
+        let quota_manager = create_quota_manager(TenantQuota {
+            max_concurrent_streams: Some(2),
+            ..TenantQuota::default()
+        });
+
+        let first_permit = quota_manager.acquire_stream("tenant_1").unwrap();
+        let _second_permit = quota_manager.acquire_stream("tenant_1").unwrap();
+        let error = quota_manager.acquire_stream("tenant_1").unwrap_err();
+        assert_eq!(error.quota_type, QuotaType::Streams);
+
+        // Other tenants are not affected
+        let _other_permit = quota_manager.acquire_stream("tenant_2").unwrap();
+
+        // Dropping a permit releases the stream
+        drop(first_permit);
+        let _third_permit = quota_manager.acquire_stream("tenant_1").unwrap();
+    }
+
+    #[test]
+    fn test_tenant_quota_overrides() {
+        let mut config = TenantQuotaConfig {
+            default_quota: TenantQuota {
+                max_concurrent_streams: Some(0),
+                ..TenantQuota::default()
+            },
+            ..TenantQuotaConfig::default()
+        };
+        config
+            .tenant_quota_overrides
+            .insert("tenant_1".to_string(), TenantQuota::default());
+        let quota_manager = Arc::new(TenantQuotaManager::new(config));
+
+        assert!(quota_manager.acquire_stream("tenant_1").is_ok());
+        assert!(quota_manager.acquire_stream("tenant_2").is_err());
+    }
+
+    #[test]
+    fn test_rate_quotas_throttle_then_reject() {
+        let quota_manager = create_quota_manager(TenantQuota {
+            max_transactions_per_sec: Some(100),
+            max_bytes_per_sec: Some(1_000),
+            ..TenantQuota::default()
+        });
+        let permit = quota_manager.acquire_stream("tenant_1").unwrap();
+        let now = Instant::now();
+
+        // Within the burst, no throttling is needed
+        assert_eq!(permit.consume_at(100, 1_000, now).unwrap(), Duration::ZERO);
+        // Beyond the burst, the stream is throttled until the debt is repaid
+        assert_eq!(
+            permit.consume_at(50, 1_000, now).unwrap(),
+            Duration::from_secs(1)
+        );
+        // Once the debt exceeds the max throttle duration, the stream is rejected
+        let _ = permit.consume_at(0, 1_000, now).unwrap();
+        let error = permit.consume_at(1, 1, now).unwrap_err();
+        assert_eq!(error.quota_type, QuotaType::Bytes);
+        assert_eq!(error.retry_after, Duration::from_secs(2));
+
+        // The bucket refills over time, and is shared by the streams of the tenant
+        let other_permit = quota_manager.acquire_stream("tenant_1").unwrap();
+        assert_eq!(
+            other_permit
+                .consume_at(1, 1, now + Duration::from_secs(3))
+                .unwrap(),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_quota_exceeded_status() {
+        let status = QuotaExceeded {
+            quota_type: QuotaType::Transactions,
+            retry_after: Duration::from_millis(1_500),
+        }
+        .into_status();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(
+            status
+                .metadata()
+                .get(RESPONSE_HEADER_APTOS_RETRY_AFTER_MS_HEADER)
+                .unwrap(),
+            "1500"
+        );
+        assert_eq!(
+            status
+                .metadata()
+                .get(RESPONSE_HEADER_APTOS_QUOTA_EXCEEDED_HEADER)
+                .unwrap(),
+            "transactions"
+        );
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert!(TenantQuotaConfig::default().validate().is_ok());
+        let config = TenantQuotaConfig {
+            default_quota: TenantQuota {
+                max_bytes_per_sec: Some(0),
+                ..TenantQuota::default()
+            },
+            ..TenantQuotaConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
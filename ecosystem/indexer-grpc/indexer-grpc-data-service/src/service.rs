@@ -1,11 +1,16 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::metrics::{
-    BYTES_READY_TO_TRANSFER_FROM_SERVER, CONNECTION_COUNT, ERROR_COUNT,
-    LATEST_PROCESSED_VERSION as LATEST_PROCESSED_VERSION_OLD, PROCESSED_BATCH_SIZE,
-    PROCESSED_LATENCY_IN_SECS, PROCESSED_LATENCY_IN_SECS_ALL, PROCESSED_VERSIONS_COUNT,
-    SHORT_CONNECTION_COUNT,
+use crate::{
+    acked_delivery::{AckSessionManager, AckSessionStream},
+    backfill::{Backfill, BackfillConfig},
+    metrics::{
+        BYTES_READY_TO_TRANSFER_FROM_SERVER, CONNECTION_COUNT, ERROR_COUNT,
+        LATEST_PROCESSED_VERSION as LATEST_PROCESSED_VERSION_OLD, PROCESSED_BATCH_SIZE,
+        PROCESSED_LATENCY_IN_SECS, PROCESSED_LATENCY_IN_SECS_ALL, PROCESSED_VERSIONS_COUNT,
+        SHORT_CONNECTION_COUNT,
+    },
+    quota::{StreamPermit, TenantQuotaManager},
 };
 use anyhow::{Context, Result};
 use aptos_indexer_grpc_utils::{
@@ -20,7 +25,10 @@ use aptos_indexer_grpc_utils::{
     counters::{log_grpc_step, IndexerGrpcStep, NUM_MULTI_FETCH_OVERLAPPED_VERSIONS},
     file_store_operator::FileStoreOperator,
     in_memory_cache::InMemoryCache,
+    stream_cursor::{StreamCursor, StreamCursorError},
     time_diff_since_pb_timestamp_in_secs,
+    transaction_filter::TransactionFilter,
+    transaction_projection::TransactionProjection,
     types::RedisUrl,
 };
 use aptos_moving_average::MovingAverage;
@@ -40,7 +48,7 @@ use std::{
 };
 use tokio::sync::mpsc::{channel, error::SendTimeoutError};
 use tokio_stream::wrappers::ReceiverStream;
-use tonic::{Request, Response, Status};
+use tonic::{metadata::MetadataMap, Code, Request, Response, Status};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
@@ -65,7 +73,21 @@ const SHORT_CONNECTION_DURATION_IN_SECS: u64 = 10;
 const REQUEST_HEADER_APTOS_EMAIL_HEADER: &str = "x-aptos-email";
 const REQUEST_HEADER_APTOS_USER_CLASSIFICATION_HEADER: &str = "x-aptos-user-classification";
 const REQUEST_HEADER_APTOS_API_KEY_NAME: &str = "x-aptos-api-key-name";
+// Optional; a filter expression (see `TransactionFilter`) for the transactions to be streamed.
+const REQUEST_HEADER_APTOS_TRANSACTION_FILTER_HEADER: &str = "x-aptos-transaction-filter";
+// Optional; the transaction fields (see `TransactionProjection`) to exclude from the stream.
+const REQUEST_HEADER_APTOS_TRANSACTION_PROJECTION_HEADER: &str = "x-aptos-transaction-projection";
+// Optional; a stream cursor (see `StreamCursor`) to resume a previous stream from.
+const REQUEST_HEADER_APTOS_STREAM_CURSOR_HEADER: &str = "x-aptos-stream-cursor";
+// Optional; the (client chosen) id of an acknowledged delivery session (see `AckSessionManager`).
+const REQUEST_HEADER_APTOS_ACK_SESSION_ID_HEADER: &str = "x-aptos-ack-session-id";
+// Optional; the last version processed by the client, acked on the acknowledged delivery session.
+const REQUEST_HEADER_APTOS_ACKED_VERSION_HEADER: &str = "x-aptos-acked-version";
 const RESPONSE_HEADER_APTOS_CONNECTION_ID_HEADER: &str = "x-aptos-connection-id";
+// The stream epoch of the cache, for clients to build stream cursors from the responses.
+const RESPONSE_HEADER_APTOS_STREAM_EPOCH_HEADER: &str = "x-aptos-stream-epoch";
+// The code (see `StreamCursorError::code`) of the reason a stream cursor was rejected.
+const RESPONSE_HEADER_APTOS_STREAM_CURSOR_ERROR_HEADER: &str = "x-aptos-stream-cursor-error";
 const SERVICE_TYPE: &str = "data_service";
 
 // Number of times to retry fetching a given txn block from the stores
@@ -83,6 +105,9 @@ pub struct RawDataServerWrapper {
     pub sender_addresses_to_ignore: HashSet<String>,
     pub cache_storage_format: StorageFormat,
     in_memory_cache: Arc<InMemoryCache>,
+    tenant_quota_manager: Arc<TenantQuotaManager>,
+    backfill_config: BackfillConfig,
+    ack_session_manager: Arc<AckSessionManager>,
 }
 
 impl RawDataServerWrapper {
@@ -93,6 +118,9 @@ impl RawDataServerWrapper {
         sender_addresses_to_ignore: HashSet<String>,
         cache_storage_format: StorageFormat,
         in_memory_cache: Arc<InMemoryCache>,
+        tenant_quota_manager: Arc<TenantQuotaManager>,
+        backfill_config: BackfillConfig,
+        ack_session_manager: Arc<AckSessionManager>,
     ) -> anyhow::Result<Self> {
         Ok(Self {
             redis_client: Arc::new(
@@ -105,6 +133,9 @@ impl RawDataServerWrapper {
             sender_addresses_to_ignore,
             cache_storage_format,
             in_memory_cache,
+            tenant_quota_manager,
+            backfill_config,
+            ack_session_manager,
         })
     }
 }
@@ -128,8 +159,17 @@ impl RawData for RawDataServerWrapper {
     ///    1.2. If the data is not in cache, fetch the data from file store.
     ///    1.3. If the data is not in file store, stream connection will break.
     ///    1.4  If error happens, retry after a short sleep.
+    ///    1.5. If the stream is resumed from a stream cursor, the cursor is validated first, and
+    ///         stale cursors are rejected with `FAILED_PRECONDITION`.
+    ///    1.6. If the stream is attached to an acknowledged delivery session, the unacked batches
+    ///         of the session are replayed first, and all sent batches are buffered until acked.
     /// 2. Push data into channel to stream to the client.
     ///    2.1. If the channel is full, do not fetch and retry after a short sleep.
+    ///    2.2. If the tenant is over its rate quotas, the stream is throttled; if throttled for
+    ///         too long, the stream ends with `RESOURCE_EXHAUSTED` (with retry-after metadata).
+    ///
+    /// Requests beyond the concurrent streams quota of the tenant are rejected with
+    /// `RESOURCE_EXHAUSTED`.
     async fn get_transactions(
         &self,
         req: Request<GetTransactionsRequest>,
@@ -146,18 +186,71 @@ impl RawData for RawDataServerWrapper {
                 &request_metadata.processor_name,
             ])
             .inc();
+        let transaction_filter = match get_transaction_filter(&req) {
+            Ok(transaction_filter) => transaction_filter,
+            Err(e) => {
+                return Result::Err(Status::invalid_argument(format!(
+                    "Invalid transaction filter: {}",
+                    e
+                )))
+            },
+        };
+        let transaction_projection = match get_transaction_projection(&req) {
+            Ok(transaction_projection) => transaction_projection,
+            Err(e) => {
+                return Result::Err(Status::invalid_argument(format!(
+                    "Invalid transaction projection: {}",
+                    e
+                )))
+            },
+        };
+        let stream_cursor = match get_stream_cursor(&req) {
+            Ok(stream_cursor) => stream_cursor,
+            Err(e) => return Result::Err(stream_cursor_error_status(e)),
+        };
+        let (ack_session_id, acked_version) = get_ack_session(&req)?;
+        if ack_session_id.is_some() && stream_cursor.is_some() {
+            return Result::Err(Status::invalid_argument(
+                "Stream cursors are not supported by acknowledged delivery sessions",
+            ));
+        }
+        // Note: the tenant is identified by the API key name; the permit is held by the stream.
+        let stream_permit = self
+            .tenant_quota_manager
+            .acquire_stream(&request_metadata.request_api_key_name)
+            .map_err(|e| e.into_status())?;
         let request = req.into_inner();
 
         let transactions_count = request.transactions_count;
 
         // Response channel to stream the data to the client.
         let (tx, rx) = channel(self.data_service_response_channel_size);
-        let current_version = match &request.starting_version {
-            Some(version) => *version,
-            None => {
+        let ack_session = match &ack_session_id {
+            Some(ack_session_id) => Some(self.ack_session_manager.attach(
+                &request_metadata.request_api_key_name,
+                ack_session_id,
+                request.starting_version,
+                acked_version,
+            )?),
+            None => None,
+        };
+        let current_version = match (&ack_session, &stream_cursor, &request.starting_version) {
+            (Some(ack_session), _, _) => ack_session.start_version(),
+            (None, Some(stream_cursor), Some(version)) if stream_cursor.version != *version => {
+                return Result::Err(Status::invalid_argument(format!(
+                    "Starting version {} does not match the stream cursor version {}",
+                    version, stream_cursor.version
+                )));
+            },
+            (None, Some(stream_cursor), _) => stream_cursor.version,
+            (None, None, Some(version)) => *version,
+            (None, None, None) => {
                 return Result::Err(Status::aborted("Starting version is not set"));
             },
         };
+        let stream_epoch =
+            get_stream_epoch_and_validate_cursor(&self.redis_client, stream_cursor.as_ref())
+                .await?;
 
         let file_store_operator: Box<dyn FileStoreOperator> = self.file_store_config.create();
         let file_store_operator = Arc::new(file_store_operator);
@@ -181,6 +274,7 @@ impl RawData for RawDataServerWrapper {
         let request_metadata = Arc::new(request_metadata);
         let sender_addresses_to_ignore = self.sender_addresses_to_ignore.clone();
         let in_memory_cache = self.in_memory_cache.clone();
+        let backfill_config = self.backfill_config.clone();
         tokio::spawn({
             let request_metadata = request_metadata.clone();
             async move {
@@ -194,6 +288,11 @@ impl RawData for RawDataServerWrapper {
                     sender_addresses_to_ignore,
                     current_version,
                     in_memory_cache,
+                    transaction_filter,
+                    transaction_projection,
+                    stream_permit,
+                    backfill_config,
+                    ack_session,
                 )
                 .await;
             }
@@ -207,6 +306,12 @@ impl RawData for RawDataServerWrapper {
             tonic::metadata::MetadataValue::from_str(&request_metadata.request_connection_id)
                 .unwrap(),
         );
+        if let Some(stream_epoch) = stream_epoch {
+            response.metadata_mut().insert(
+                RESPONSE_HEADER_APTOS_STREAM_EPOCH_HEADER,
+                tonic::metadata::MetadataValue::from(stream_epoch),
+            );
+        }
         Ok(response)
     }
 }
@@ -385,6 +490,11 @@ async fn data_fetcher_task(
     sender_addresses_to_ignore: HashSet<String>,
     mut current_version: u64,
     in_memory_cache: Arc<InMemoryCache>,
+    transaction_filter: Option<TransactionFilter>,
+    transaction_projection: Option<TransactionProjection>,
+    stream_permit: StreamPermit,
+    backfill_config: BackfillConfig,
+    mut ack_session: Option<AckSessionStream>,
 ) {
     let mut connection_start_time = Some(std::time::Instant::now());
     let mut transactions_count = transactions_count;
@@ -428,7 +538,8 @@ async fn data_fetcher_task(
         .await;
     }
 
-    let metadata_chain_id = metadata.unwrap().chain_id;
+    let metadata = metadata.unwrap();
+    let metadata_chain_id = metadata.chain_id;
 
     // Validate redis chain id. Must be present by the time it gets here
     let chain_id = match cache_operator.get_chain_id().await {
@@ -465,28 +576,99 @@ async fn data_fetcher_task(
         return;
     }
 
+    // Replay the unacked batches of the acknowledged delivery session (if any) first.
+    if let Some(ack_session) = ack_session.as_mut() {
+        let replay = ack_session.take_replay();
+        if !replay.is_empty() {
+            info!(
+                num_batches = replay.len(),
+                connection_id = request_metadata.request_connection_id.as_str(),
+                "[Data Service] Replaying the unacked batches of the session."
+            );
+            if channel_send_multiple_with_timeout(replay, tx.clone(), request_metadata.clone())
+                .await
+                .is_err()
+            {
+                warn!("[Data Service] Failed to replay the unacked batches; exiting.");
+                return;
+            }
+        }
+    }
+
     // Data service metrics.
     let mut tps_calculator = MovingAverage::new(MOVING_AVERAGE_WINDOW_SIZE);
 
-    loop {
-        // 1. Fetch data from cache and file store.
-        let transaction_data = match get_data_with_tasks(
-            current_version,
-            transactions_count,
-            chain_id,
-            &mut cache_operator,
+    // Requests far behind the file store head are backfilled from the file store in parallel.
+    let backfill_shards =
+        backfill_config.get_shards(current_version, transactions_count, metadata.version);
+    let mut backfill = if backfill_shards.is_empty() {
+        None
+    } else {
+        info!(
+            start_version = current_version,
+            file_store_version = metadata.version,
+            num_shards = backfill_shards.len(),
+            connection_id = request_metadata.request_connection_id.as_str(),
+            "[Data Service] Starting a file store backfill."
+        );
+        Some(Backfill::new(
             file_store_operator.clone(),
-            request_metadata.clone(),
-            cache_storage_format,
-            in_memory_cache.clone(),
-        )
-        .await
-        {
-            DataFetchSubTaskResult::BatchSuccess(txns) => txns,
-            DataFetchSubTaskResult::Success(_) => {
-                unreachable!("Fetching from multiple tasks will never return a single vector")
+            backfill_shards,
+            &backfill_config,
+        ))
+    };
+
+    loop {
+        // 1. Fetch data from the backfill (if any), or the cache and file store.
+        let backfill_shard = match backfill.as_mut() {
+            Some(backfill) => backfill.next_shard().await,
+            None => None,
+        };
+        let transaction_data = match backfill_shard {
+            Some(Ok(txns)) => vec![txns],
+            Some(Err(e)) => {
+                ERROR_COUNT.with_label_values(&["backfill_failed"]).inc();
+                // Connection will be dropped anyway, so we ignore the error here.
+                let _result = tx
+                    .send_timeout(
+                        Err(Status::unavailable(format!(
+                            "[Data Service] Failed to backfill from the file store; please retry \
+                            from version {}.",
+                            current_version
+                        ))),
+                        RESPONSE_CHANNEL_SEND_TIMEOUT,
+                    )
+                    .await;
+                error!(
+                    error = e.to_string(),
+                    current_version = current_version,
+                    "[Data Service] Failed to backfill from the file store."
+                );
+                break;
+            },
+            None => {
+                backfill = None;
+                match get_data_with_tasks(
+                    current_version,
+                    transactions_count,
+                    chain_id,
+                    &mut cache_operator,
+                    file_store_operator.clone(),
+                    request_metadata.clone(),
+                    cache_storage_format,
+                    in_memory_cache.clone(),
+                )
+                .await
+                {
+                    DataFetchSubTaskResult::BatchSuccess(txns) => txns,
+                    DataFetchSubTaskResult::Success(_) => {
+                        unreachable!(
+                            "Fetching from multiple tasks will never return a single vector"
+                        )
+                    },
+                    DataFetchSubTaskResult::NoResults => continue,
+                }
             },
-            DataFetchSubTaskResult::NoResults => continue,
         };
 
         let mut transaction_data = ensure_sequential_transactions(transaction_data);
@@ -506,6 +688,23 @@ async fn data_fetcher_task(
                 transactions_count = Some(count - transaction_data.len() as u64);
             }
         };
+        // Note: the progress is tracked before filtering, so that the stream moves forward even
+        // if none of the transactions in the batch match the filter.
+        let current_batch_size = transaction_data.as_slice().len();
+        let end_of_batch_version = transaction_data.as_slice().last().unwrap().version;
+        // Filter the transactions before serialization, so that only the matching ones are sent.
+        if let Some(transaction_filter) = &transaction_filter {
+            transaction_data = transaction_filter.filter(transaction_data);
+            if transaction_data.is_empty() {
+                tps_calculator.tick_now(current_batch_size as u64);
+                current_version = end_of_batch_version + 1;
+                continue;
+            }
+        }
+        // Strip the excluded fields before serialization (and compression).
+        if let Some(transaction_projection) = &transaction_projection {
+            transaction_data = transaction_projection.project(transaction_data);
+        }
         // Note: this is the protobuf encoded transaction size.
         let bytes_ready_to_transfer = transaction_data
             .iter()
@@ -518,9 +717,31 @@ async fn data_fetcher_task(
                 &request_metadata.processor_name,
             ])
             .inc_by(bytes_ready_to_transfer as u64);
+        // Enforce the rate quotas of the tenant before sending the data.
+        match stream_permit.consume(
+            transaction_data.len() as u64,
+            bytes_ready_to_transfer as u64,
+        ) {
+            Ok(throttle_duration) => {
+                if !throttle_duration.is_zero() {
+                    tokio::time::sleep(throttle_duration).await;
+                }
+            },
+            Err(e) => {
+                warn!(
+                    request_api_key_name = request_metadata.request_api_key_name.as_str(),
+                    connection_id = request_metadata.request_connection_id.as_str(),
+                    quota_type = e.quota_type.as_str(),
+                    "[Data Service] Tenant is over quota; ending the stream."
+                );
+                // Connection will be dropped anyway, so we ignore the error here.
+                let _result = tx
+                    .send_timeout(Err(e.into_status()), RESPONSE_CHANNEL_SEND_TIMEOUT)
+                    .await;
+                break;
+            },
+        }
         // 2. Push the data to the response channel, i.e. stream the data to the client.
-        let current_batch_size = transaction_data.as_slice().len();
-        let end_of_batch_version = transaction_data.as_slice().last().unwrap().version;
         let resp_items = get_transactions_responses_builder(
             transaction_data,
             chain_id as u32,
@@ -535,6 +756,21 @@ async fn data_fetcher_task(
             .timestamp
             .as_ref()
             .map(time_diff_since_pb_timestamp_in_secs);
+        // Buffer the batches on the acknowledged delivery session (if any) before sending them.
+        if let Some(ack_session) = &ack_session {
+            if let Err(status) = ack_session.buffer(&resp_items) {
+                warn!(
+                    connection_id = request_metadata.request_connection_id.as_str(),
+                    error = status.message(),
+                    "[Data Service] Failed to buffer the batches on the session; ending the stream."
+                );
+                // Connection will be dropped anyway, so we ignore the error here.
+                let _result = tx
+                    .send_timeout(Err(status), RESPONSE_CHANNEL_SEND_TIMEOUT)
+                    .await;
+                break;
+            }
+        }
 
         match channel_send_multiple_with_timeout(resp_items, tx.clone(), request_metadata.clone())
             .await
@@ -900,6 +1136,179 @@ fn get_request_metadata(
     Ok(request_metadata)
 }
 
+/// Parses the (optional) transaction filter of the request.
+fn get_transaction_filter(
+    req: &Request<GetTransactionsRequest>,
+) -> anyhow::Result<Option<TransactionFilter>> {
+    req.metadata()
+        .get(REQUEST_HEADER_APTOS_TRANSACTION_FILTER_HEADER)
+        .map(|value| value.to_str()?.parse::<TransactionFilter>())
+        .transpose()
+}
+
+/// Parses the (optional) transaction projection of the request.
+fn get_transaction_projection(
+    req: &Request<GetTransactionsRequest>,
+) -> anyhow::Result<Option<TransactionProjection>> {
+    req.metadata()
+        .get(REQUEST_HEADER_APTOS_TRANSACTION_PROJECTION_HEADER)
+        .map(|value| value.to_str()?.parse::<TransactionProjection>())
+        .transpose()
+}
+
+fn get_stream_cursor(
+    req: &Request<GetTransactionsRequest>,
+) -> Result<Option<StreamCursor>, StreamCursorError> {
+    req.metadata()
+        .get(REQUEST_HEADER_APTOS_STREAM_CURSOR_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|_| StreamCursorError::Malformed)
+                .and_then(StreamCursor::decode)
+        })
+        .transpose()
+}
+
+/// Parses the (optional) acknowledged delivery session id and the piggybacked ack of the request.
+fn get_ack_session(
+    req: &Request<GetTransactionsRequest>,
+) -> Result<(Option<String>, Option<u64>), Status> {
+    let ack_session_id = req
+        .metadata()
+        .get(REQUEST_HEADER_APTOS_ACK_SESSION_ID_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .filter(|id| !id.is_empty())
+                .map(|id| id.to_string())
+                .ok_or_else(|| Status::invalid_argument("Invalid acknowledged delivery session id"))
+        })
+        .transpose()?;
+    let acked_version = req
+        .metadata()
+        .get(REQUEST_HEADER_APTOS_ACKED_VERSION_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|version| version.parse::<u64>().ok())
+                .ok_or_else(|| Status::invalid_argument("Invalid acked version"))
+        })
+        .transpose()?;
+    if acked_version.is_some() && ack_session_id.is_none() {
+        return Err(Status::invalid_argument(
+            "The acked version requires an acknowledged delivery session id",
+        ));
+    }
+    Ok((ack_session_id, acked_version))
+}
+
+/// Returns the status for a rejected stream cursor. Apart from malformed cursors, the client is
+/// instructed to fall back to the file store, i.e., to request the cursor version without a cursor.
+fn stream_cursor_error_status(error: StreamCursorError) -> Status {
+    let mut metadata = MetadataMap::new();
+    metadata.insert(
+        RESPONSE_HEADER_APTOS_STREAM_CURSOR_ERROR_HEADER,
+        tonic::metadata::MetadataValue::from_static(error.code()),
+    );
+    match error {
+        StreamCursorError::Malformed => {
+            Status::with_metadata(Code::InvalidArgument, "Invalid stream cursor", metadata)
+        },
+        _ => Status::with_metadata(
+            Code::FailedPrecondition,
+            format!(
+                "Stream cursor rejected: {}. Fall back to the file store by requesting from the \
+                 cursor version without a stream cursor.",
+                error
+            ),
+            metadata,
+        ),
+    }
+}
+
+/// Returns the current stream epoch of the cache (if set), after checking that the stream can be
+/// resumed from the given cursor.
+async fn get_stream_epoch_and_validate_cursor(
+    redis_client: &Client,
+    stream_cursor: Option<&StreamCursor>,
+) -> Result<Option<u64>, Status> {
+    let conn = redis_client
+        .get_tokio_connection_manager()
+        .await
+        .map_err(|e| {
+            ERROR_COUNT
+                .with_label_values(&["redis_connection_failed"])
+                .inc();
+            error!(
+                error = e.to_string(),
+                "[Data Service] Failed to get redis connection."
+            );
+            Status::unavailable("[Data Service] Cannot connect to Redis; please retry.")
+        })?;
+    // The storage format is irrelevant, since no transactions are read.
+    let mut cache_operator = CacheOperator::new(conn, StorageFormat::Base64UncompressedProto);
+    let stream_state = async {
+        let stream_epoch = cache_operator.get_stream_epoch().await?;
+        let Some(stream_cursor) = stream_cursor else {
+            return Ok(stream_epoch);
+        };
+        let chain_id = cache_operator.get_chain_id().await?;
+        let cache_latest_version = cache_operator.get_latest_version().await?;
+        let file_store_latest_version = cache_operator.get_file_store_latest_version().await?;
+        match (
+            stream_epoch,
+            chain_id,
+            cache_latest_version,
+            file_store_latest_version,
+        ) {
+            (
+                Some(stream_epoch),
+                Some(chain_id),
+                Some(cache_latest_version),
+                Some(file_store_latest_version),
+            ) => Ok(stream_cursor
+                .validate(
+                    chain_id,
+                    stream_epoch,
+                    cache_latest_version,
+                    file_store_latest_version,
+                )
+                .map(|_| Some(stream_epoch))),
+            _ => anyhow::bail!("Cache is not initialized."),
+        }
+    }
+    .await;
+
+    match stream_state {
+        Ok(Ok(stream_epoch)) => Ok(stream_epoch),
+        Ok(Err(e)) => {
+            ERROR_COUNT
+                .with_label_values(&["stream_cursor_rejected"])
+                .inc();
+            warn!(
+                error = e.to_string(),
+                "[Data Service] Stream cursor rejected."
+            );
+            Err(stream_cursor_error_status(e))
+        },
+        Err(e) => {
+            ERROR_COUNT
+                .with_label_values(&["redis_get_stream_state_failed"])
+                .inc();
+            error!(
+                error = e.to_string(),
+                "[Data Service] Failed to get the stream state from redis."
+            );
+            Err(Status::unavailable(
+                "[Data Service] Cannot get the stream state from redis; please retry.",
+            ))
+        },
+    }
+}
+
 async fn channel_send_multiple_with_timeout(
     resp_items: Vec<TransactionsResponse>,
     tx: tokio::sync::mpsc::Sender<Result<TransactionsResponse, Status>>,
@@ -999,12 +1408,24 @@ fn filter_transactions_for_sender_addresses(
 
 #[cfg(test)]
 mod tests {
-    use super::{ensure_sequential_transactions, filter_transactions_for_sender_addresses};
-    use aptos_protos::transaction::v1::{
-        transaction::TxnData, Event, Signature, Transaction, TransactionInfo, TransactionPayload,
-        UserTransaction, UserTransactionRequest, WriteSetChange,
+    use super::{
+        ensure_sequential_transactions, filter_transactions_for_sender_addresses, get_ack_session,
+        get_stream_cursor, get_transaction_filter, get_transaction_projection,
+        stream_cursor_error_status, REQUEST_HEADER_APTOS_TRANSACTION_PROJECTION_HEADER,
+        REQUEST_HEADER_APTOS_ACKED_VERSION_HEADER, REQUEST_HEADER_APTOS_ACK_SESSION_ID_HEADER,
+        REQUEST_HEADER_APTOS_STREAM_CURSOR_HEADER, REQUEST_HEADER_APTOS_TRANSACTION_FILTER_HEADER,
+        RESPONSE_HEADER_APTOS_STREAM_CURSOR_ERROR_HEADER,
+    };
+    use aptos_indexer_grpc_utils::stream_cursor::{StreamCursor, StreamCursorError};
+    use aptos_protos::{
+        indexer::v1::GetTransactionsRequest,
+        transaction::v1::{
+            transaction::TxnData, Event, Signature, Transaction, TransactionInfo,
+            TransactionPayload, UserTransaction, UserTransactionRequest, WriteSetChange,
+        },
     };
     use std::collections::HashSet;
+    use tonic::{Code, Request};
 
     #[test]
     fn test_ensure_sequential_transactions_merges_and_sorts() {
@@ -1087,4 +1508,119 @@ mod tests {
         assert_eq!(user_transaction.events.len(), 0);
         assert_eq!(txn.info.as_ref().unwrap().changes.len(), 0);
     }
+
+    #[test]
+    fn test_transaction_filter_is_parsed_from_request_header() {
+        let mut req = Request::new(GetTransactionsRequest::default());
+        assert_eq!(get_transaction_filter(&req).unwrap(), None);
+
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_TRANSACTION_FILTER_HEADER,
+            "success=true & entry_function=0x1::coin".parse().unwrap(),
+        );
+        assert_eq!(
+            get_transaction_filter(&req).unwrap().unwrap().to_string(),
+            "success=true & entry_function=0x1::coin"
+        );
+
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_TRANSACTION_FILTER_HEADER,
+            "success=yes".parse().unwrap(),
+        );
+        assert!(get_transaction_filter(&req).is_err());
+    }
+
+    #[test]
+    fn test_transaction_projection_is_parsed_from_request_header() {
+        let mut req = Request::new(GetTransactionsRequest::default());
+        assert_eq!(get_transaction_projection(&req).unwrap(), None);
+
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_TRANSACTION_PROJECTION_HEADER,
+            "event_data,changes".parse().unwrap(),
+        );
+        assert_eq!(
+            get_transaction_projection(&req)
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "changes,event_data"
+        );
+
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_TRANSACTION_PROJECTION_HEADER,
+            "write_set".parse().unwrap(),
+        );
+        assert!(get_transaction_projection(&req).is_err());
+    }
+
+    #[test]
+    fn test_get_stream_cursor() {
+        let mut req = Request::new(GetTransactionsRequest::default());
+        assert_eq!(get_stream_cursor(&req), Ok(None));
+
+        let stream_cursor = StreamCursor::new(100, 1, 42);
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_STREAM_CURSOR_HEADER,
+            stream_cursor.encode().parse().unwrap(),
+        );
+        assert_eq!(get_stream_cursor(&req), Ok(Some(stream_cursor)));
+
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_STREAM_CURSOR_HEADER,
+            "garbage".parse().unwrap(),
+        );
+        assert_eq!(get_stream_cursor(&req), Err(StreamCursorError::Malformed));
+    }
+
+    #[test]
+    fn test_get_ack_session() {
+        let mut req = Request::new(GetTransactionsRequest::default());
+        assert_eq!(get_ack_session(&req).unwrap(), (None, None));
+
+        // The acked version requires a session
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_ACKED_VERSION_HEADER,
+            "100".parse().unwrap(),
+        );
+        assert_eq!(
+            get_ack_session(&req).unwrap_err().code(),
+            Code::InvalidArgument
+        );
+
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_ACK_SESSION_ID_HEADER,
+            "session".parse().unwrap(),
+        );
+        assert_eq!(
+            get_ack_session(&req).unwrap(),
+            (Some("session".to_string()), Some(100))
+        );
+
+        req.metadata_mut().insert(
+            REQUEST_HEADER_APTOS_ACKED_VERSION_HEADER,
+            "garbage".parse().unwrap(),
+        );
+        assert!(get_ack_session(&req).is_err());
+    }
+
+    #[test]
+    fn test_stream_cursor_error_status() {
+        let status = stream_cursor_error_status(StreamCursorError::Malformed);
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let status = stream_cursor_error_status(StreamCursorError::StaleStreamEpoch {
+            cursor_stream_epoch: 1,
+            stream_epoch: 2,
+        });
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert!(status.message().contains("Fall back to the file store"));
+        assert_eq!(
+            status
+                .metadata()
+                .get(RESPONSE_HEADER_APTOS_STREAM_CURSOR_ERROR_HEADER)
+                .unwrap(),
+            "stale_stream_epoch"
+        );
+    }
 }
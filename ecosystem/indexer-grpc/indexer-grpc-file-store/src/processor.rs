@@ -83,7 +83,15 @@ impl Processor {
         ensure!(metadata.chain_id == chain_id, "Chain ID mismatch.");
         let batch_start_version = metadata.version;
         // Cache config in the cache
-        cache_operator.cache_setup_if_needed().await?;
+        let cache_initialized = cache_operator.cache_setup_if_needed().await?;
+        // A (re)initialized cache starts a new stream epoch, which invalidates all stream cursors.
+        let stream_epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("SystemTime before UNIX EPOCH!")
+            .as_micros() as u64;
+        cache_operator
+            .set_stream_epoch(stream_epoch, cache_initialized)
+            .await?;
         match cache_operator.get_chain_id().await? {
             Some(id) => {
                 ensure!(id == chain_id, "Chain ID mismatch.");
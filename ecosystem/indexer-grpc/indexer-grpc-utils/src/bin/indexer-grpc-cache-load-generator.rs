@@ -0,0 +1,52 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Context, Result};
+use aptos_indexer_grpc_utils::{
+    load_generator::{InMemoryRedisConnection, LoadGenerator, LoadGeneratorConfig},
+    types::RedisUrl,
+};
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+pub struct Args {
+    /// The path to the load generator config (YAML). If not set, the default config is used.
+    #[clap(long)]
+    config_path: Option<PathBuf>,
+
+    /// The Redis instance to write to and read from. If not set, an in-process mock Redis
+    /// is used. Note: this should not be a Redis instance used by a live cache worker.
+    #[clap(long)]
+    redis_address: Option<RedisUrl>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Parse the commandline args and the config
+    let args = Args::parse();
+    let config: LoadGeneratorConfig = match &args.config_path {
+        Some(config_path) => {
+            let config = std::fs::read_to_string(config_path)
+                .with_context(|| format!("Failed to read config file {}", config_path.display()))?;
+            serde_yaml::from_str(&config)?
+        },
+        None => LoadGeneratorConfig::default(),
+    };
+    let load_generator = LoadGenerator::new(config)?;
+
+    // Run the load generator against the cache backend
+    let report = match args.redis_address {
+        Some(redis_address) => {
+            let conn = redis::Client::open(redis_address.0.clone())
+                .with_context(|| format!("Failed to create redis client for {}", redis_address))?
+                .get_tokio_connection_manager()
+                .await?;
+            load_generator.run(conn).await?
+        },
+        None => load_generator.run(InMemoryRedisConnection::new()).await?,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -25,6 +25,7 @@ const CACHE_SIZE_EVICTION_LOWER_BOUND: u64 = 300_000_u64;
 // Keys for cache.
 const CACHE_KEY_LATEST_VERSION: &str = "latest_version";
 const CACHE_KEY_CHAIN_ID: &str = "chain_id";
+const CACHE_KEY_STREAM_EPOCH: &str = "stream_epoch";
 // 9999-12-31 23:59:59. UTC.
 const BASE_EXPIRATION_EPOCH_TIME_IN_SECONDS: u64 = 253_402_300_799;
 
@@ -141,6 +142,31 @@ impl<T: redis::aio::ConnectionLike + Send + Clone> CacheOperator<T> {
         self.get_config_by_key(CACHE_KEY_CHAIN_ID).await
     }
 
+    /// Sets the stream epoch, which identifies the current incarnation of the cache. It should be
+    /// overwritten whenever the cache is (re)initialized, so that stream cursors issued before
+    /// are rejected; otherwise, it is only set if missing.
+    pub async fn set_stream_epoch(
+        &mut self,
+        stream_epoch: u64,
+        overwrite: bool,
+    ) -> anyhow::Result<()> {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(CACHE_KEY_STREAM_EPOCH).arg(stream_epoch);
+        if !overwrite {
+            cmd.arg("NX");
+        }
+        // SET NX returns nil if the key already exists.
+        let _: Option<String> = cmd
+            .query_async(&mut self.conn)
+            .await
+            .context("Redis stream epoch update failed.")?;
+        Ok(())
+    }
+
+    pub async fn get_stream_epoch(&mut self) -> anyhow::Result<Option<u64>> {
+        self.get_config_by_key(CACHE_KEY_STREAM_EPOCH).await
+    }
+
     pub async fn get_latest_version(&mut self) -> anyhow::Result<Option<u64>> {
         self.get_config_by_key(CACHE_KEY_LATEST_VERSION).await
     }
@@ -475,6 +501,44 @@ mod tests {
         assert_eq!(cache_operator.get_chain_id().await.unwrap(), Some(123));
     }
 
+    // Cache stream epoch tests.
+    #[tokio::test]
+    async fn cache_stream_epoch_is_overwritten_on_setup() {
+        let cmds = vec![
+            MockCmd::new(
+                redis::cmd("SET").arg(CACHE_KEY_STREAM_EPOCH).arg(42_u64),
+                Ok("OK"),
+            ),
+            MockCmd::new(redis::cmd("GET").arg(CACHE_KEY_STREAM_EPOCH), Ok("42")),
+        ];
+        let mock_connection = MockRedisConnection::new(cmds);
+        let mut cache_operator: CacheOperator<MockRedisConnection> =
+            CacheOperator::new(mock_connection, StorageFormat::Base64UncompressedProto);
+
+        cache_operator.set_stream_epoch(42, true).await.unwrap();
+        assert_eq!(cache_operator.get_stream_epoch().await.unwrap(), Some(42));
+    }
+
+    #[tokio::test]
+    async fn cache_stream_epoch_is_kept_if_present() {
+        let cmds = vec![
+            MockCmd::new(
+                redis::cmd("SET")
+                    .arg(CACHE_KEY_STREAM_EPOCH)
+                    .arg(43_u64)
+                    .arg("NX"),
+                Ok(redis::Value::Nil),
+            ),
+            MockCmd::new(redis::cmd("GET").arg(CACHE_KEY_STREAM_EPOCH), Ok("42")),
+        ];
+        let mock_connection = MockRedisConnection::new(cmds);
+        let mut cache_operator: CacheOperator<MockRedisConnection> =
+            CacheOperator::new(mock_connection, StorageFormat::Base64UncompressedProto);
+
+        cache_operator.set_stream_epoch(43, false).await.unwrap();
+        assert_eq!(cache_operator.get_stream_epoch().await.unwrap(), Some(42));
+    }
+
     // Cache latest version tests.
     #[tokio::test]
     async fn cache_latest_version_ok() {
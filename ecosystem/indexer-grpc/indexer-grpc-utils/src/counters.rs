@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{constants::IndexerGrpcRequestMetadata, timestamp_to_iso, timestamp_to_unixtime};
-use aptos_metrics_core::{register_gauge_vec, register_int_gauge_vec, GaugeVec, IntGaugeVec};
+use aptos_metrics_core::{
+    register_gauge_vec, register_int_counter, register_int_gauge, register_int_gauge_vec, GaugeVec,
+    IntCounter, IntGauge, IntGaugeVec,
+};
 use aptos_protos::util::timestamp::Timestamp;
 use once_cell::sync::Lazy;
 use prometheus::{register_int_counter_vec, IntCounterVec};
@@ -210,6 +213,42 @@ pub static TRANSACTION_UNIX_TIMESTAMP: Lazy<GaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Total size in bytes of the transactions resident in the in-memory cache
+pub static IN_MEMORY_CACHE_RESIDENT_SIZE_IN_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "indexer_grpc_in_memory_cache_resident_size_in_bytes",
+        "Total size in bytes of the transactions resident in the in-memory cache",
+    )
+    .unwrap()
+});
+
+/// Number of transactions resident in the in-memory cache
+pub static IN_MEMORY_CACHE_RESIDENT_TRANSACTIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "indexer_grpc_in_memory_cache_resident_transactions",
+        "Number of transactions resident in the in-memory cache",
+    )
+    .unwrap()
+});
+
+/// Number of transactions evicted from the in-memory cache
+pub static IN_MEMORY_CACHE_EVICTED_TRANSACTIONS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "indexer_grpc_in_memory_cache_evicted_transactions",
+        "Number of transactions evicted from the in-memory cache",
+    )
+    .unwrap()
+});
+
+/// Total size in bytes of the transactions evicted from the in-memory cache
+pub static IN_MEMORY_CACHE_EVICTED_BYTES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "indexer_grpc_in_memory_cache_evicted_bytes",
+        "Total size in bytes of the transactions evicted from the in-memory cache",
+    )
+    .unwrap()
+});
+
 pub fn log_grpc_step(
     service_type: &str,
     step: IndexerGrpcStep,
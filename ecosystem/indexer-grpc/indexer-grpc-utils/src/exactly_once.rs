@@ -0,0 +1,381 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for processors to achieve effectively-exactly-once processing of the transaction stream.
+//!
+//! The stream itself is at-least-once: after a reconnect, a processor may receive transactions
+//! it has already processed. To process each transaction exactly once, the processor must write
+//! its output and its checkpoint (i.e., the idempotency key of the last processed transaction)
+//! atomically, e.g., in the same database transaction. `ExactlyOnceProcessor` tracks the
+//! checkpoint, drops the replayed transactions, detects gaps, and only advances the checkpoint
+//! once the commit callback succeeds.
+
+use anyhow::{bail, ensure, Context};
+use aptos_protos::{indexer::v1::TransactionsResponse, transaction::v1::Transaction};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use std::{fmt, str::FromStr};
+
+/// Uniquely identifies a transaction across chains, e.g., to be stored alongside the output of
+/// a processor.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct IdempotencyKey {
+    pub chain_id: u64,
+    pub version: u64,
+}
+
+impl IdempotencyKey {
+    pub fn new(chain_id: u64, version: u64) -> Self {
+        Self { chain_id, version }
+    }
+}
+
+/// Formats the key as `<chain id>:<version>`.
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.chain_id, self.version)
+    }
+}
+
+impl FromStr for IdempotencyKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (chain_id, version) = s
+            .split_once(':')
+            .with_context(|| format!("Idempotency key '{}' is not <chain id>:<version>", s))?;
+        Ok(Self {
+            chain_id: chain_id
+                .parse()
+                .with_context(|| format!("Invalid chain id in idempotency key '{}'", s))?,
+            version: version
+                .parse()
+                .with_context(|| format!("Invalid version in idempotency key '{}'", s))?,
+        })
+    }
+}
+
+/// The processor-specific storage of the output and the checkpoint.
+#[async_trait]
+pub trait TransactionalCommitter: Send {
+    /// Returns the last committed checkpoint, if any.
+    async fn load_checkpoint(&mut self) -> anyhow::Result<Option<IdempotencyKey>>;
+
+    /// Processes the transactions and persists the output together with the checkpoint,
+    /// atomically: either both are persisted, or neither is (and an error is returned).
+    async fn commit(
+        &mut self,
+        transactions: Vec<Transaction>,
+        checkpoint: IdempotencyKey,
+    ) -> anyhow::Result<()>;
+}
+
+/// The result of processing a single response of the stream.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ProcessedBatch {
+    /// The number of transactions committed.
+    pub num_committed: usize,
+    /// The number of transactions dropped because they were already committed.
+    pub num_duplicates: usize,
+}
+
+/// Wraps a `TransactionalCommitter` to deduplicate and checkpoint the transaction stream.
+pub struct ExactlyOnceProcessor<C> {
+    committer: C,
+    chain_id: u64,
+    checkpoint: Option<IdempotencyKey>,
+}
+
+impl<C: TransactionalCommitter> ExactlyOnceProcessor<C> {
+    /// Creates the processor, resuming from the committed checkpoint (if any).
+    pub async fn new(mut committer: C, chain_id: u64) -> anyhow::Result<Self> {
+        let checkpoint = committer
+            .load_checkpoint()
+            .await
+            .context("Failed to load the checkpoint")?;
+        if let Some(checkpoint) = checkpoint {
+            ensure!(
+                checkpoint.chain_id == chain_id,
+                "Checkpoint {} is for a different chain than {}",
+                checkpoint,
+                chain_id
+            );
+        }
+        Ok(Self {
+            committer,
+            chain_id,
+            checkpoint,
+        })
+    }
+
+    /// Returns the last committed checkpoint, if any.
+    pub fn checkpoint(&self) -> Option<IdempotencyKey> {
+        self.checkpoint
+    }
+
+    /// Returns the version the stream should be (re)started from.
+    pub fn starting_version(&self) -> u64 {
+        self.checkpoint
+            .map_or(0, |checkpoint| checkpoint.version + 1)
+    }
+
+    /// Returns the underlying committer.
+    pub fn into_inner(self) -> C {
+        self.committer
+    }
+
+    /// Processes a single response of the stream. Transactions at or below the checkpoint are
+    /// dropped, and the rest are committed (with the new checkpoint) if they continue the
+    /// checkpoint without gaps. If the commit fails, the checkpoint is left unchanged, so the
+    /// same transactions can be processed again (e.g., after reconnecting).
+    pub async fn process_response(
+        &mut self,
+        response: TransactionsResponse,
+    ) -> anyhow::Result<ProcessedBatch> {
+        if let Some(chain_id) = response.chain_id {
+            ensure!(
+                chain_id == self.chain_id,
+                "Received transactions for chain {}, expected chain {}",
+                chain_id,
+                self.chain_id
+            );
+        }
+
+        let num_transactions = response.transactions.len();
+        let starting_version = self.starting_version();
+        let transactions: Vec<Transaction> = response
+            .transactions
+            .into_iter()
+            .filter(|transaction| transaction.version >= starting_version)
+            .collect();
+        let num_duplicates = num_transactions - transactions.len();
+
+        let Some(last_transaction) = transactions.last() else {
+            return Ok(ProcessedBatch {
+                num_committed: 0,
+                num_duplicates,
+            });
+        };
+        let last_version = last_transaction.version;
+        for (expected_version, transaction) in (starting_version..).zip(transactions.iter()) {
+            if transaction.version != expected_version {
+                bail!(
+                    "Gap detected: expected version {}, received version {}",
+                    expected_version,
+                    transaction.version
+                );
+            }
+        }
+
+        let checkpoint = IdempotencyKey::new(self.chain_id, last_version);
+        self.committer
+            .commit(transactions, checkpoint)
+            .await
+            .with_context(|| format!("Failed to commit up to checkpoint {}", checkpoint))?;
+        self.checkpoint = Some(checkpoint);
+
+        Ok(ProcessedBatch {
+            num_committed: (last_version - starting_version + 1) as usize,
+            num_duplicates,
+        })
+    }
+
+    /// Processes the stream until it ends or an error occurs. The stream should be started
+    /// from `starting_version`; replayed transactions are dropped.
+    pub async fn process_stream<S>(&mut self, mut stream: S) -> anyhow::Result<ProcessedBatch>
+    where
+        S: Stream<Item = Result<TransactionsResponse, tonic::Status>> + Unpin,
+    {
+        let mut total = ProcessedBatch::default();
+        while let Some(response) = stream.next().await {
+            let response = response.context("Failed to receive transactions from the stream")?;
+            let processed_batch = self.process_response(response).await?;
+            total.num_committed += processed_batch.num_committed;
+            total.num_duplicates += processed_batch.num_duplicates;
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN_ID: u64 = 1;
+
+    /// Stores the committed versions and checkpoint in memory, and fails the next commits on
+    /// demand.
+    #[derive(Default)]
+    struct InMemoryCommitter {
+        committed_versions: Vec<u64>,
+        checkpoint: Option<IdempotencyKey>,
+        num_commits_to_fail: usize,
+    }
+
+    #[async_trait]
+    impl TransactionalCommitter for InMemoryCommitter {
+        async fn load_checkpoint(&mut self) -> anyhow::Result<Option<IdempotencyKey>> {
+            Ok(self.checkpoint)
+        }
+
+        async fn commit(
+            &mut self,
+            transactions: Vec<Transaction>,
+            checkpoint: IdempotencyKey,
+        ) -> anyhow::Result<()> {
+            if self.num_commits_to_fail > 0 {
+                self.num_commits_to_fail -= 1;
+                bail!("Database unavailable");
+            }
+            self.committed_versions
+                .extend(transactions.iter().map(|transaction| transaction.version));
+            self.checkpoint = Some(checkpoint);
+            Ok(())
+        }
+    }
+
+    fn create_response(versions: std::ops::Range<u64>) -> TransactionsResponse {
+        TransactionsResponse {
+            transactions: versions
+                .map(|version| Transaction {
+                    version,
+                    ..Default::default()
+                })
+                .collect(),
+            chain_id: Some(CHAIN_ID),
+        }
+    }
+
+    #[test]
+    fn test_idempotency_key_round_trip() {
+        let key = IdempotencyKey::new(CHAIN_ID, 42);
+        assert_eq!(key.to_string(), "1:42");
+        assert_eq!("1:42".parse::<IdempotencyKey>().unwrap(), key);
+        assert!("42".parse::<IdempotencyKey>().is_err());
+        assert!("1:x".parse::<IdempotencyKey>().is_err());
+        assert!(IdempotencyKey::new(1, 43) > key);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_transactions_are_dropped() {
+        let mut processor = ExactlyOnceProcessor::new(InMemoryCommitter::default(), CHAIN_ID)
+            .await
+            .unwrap();
+        assert_eq!(processor.starting_version(), 0);
+
+        let processed = processor.process_response(create_response(0..10)).await;
+        assert_eq!(processed.unwrap(), ProcessedBatch {
+            num_committed: 10,
+            num_duplicates: 0,
+        });
+
+        // The stream is restarted from an earlier version, e.g., after a reconnect
+        let processed = processor.process_response(create_response(5..15)).await;
+        assert_eq!(processed.unwrap(), ProcessedBatch {
+            num_committed: 5,
+            num_duplicates: 5,
+        });
+        let processed = processor.process_response(create_response(0..15)).await;
+        assert_eq!(processed.unwrap(), ProcessedBatch {
+            num_committed: 0,
+            num_duplicates: 15,
+        });
+
+        assert_eq!(
+            processor.checkpoint(),
+            Some(IdempotencyKey::new(CHAIN_ID, 14))
+        );
+        assert_eq!(
+            processor.into_inner().committed_versions,
+            (0..15).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_commit_does_not_advance_checkpoint() {
+        let committer = InMemoryCommitter {
+            num_commits_to_fail: 1,
+            ..Default::default()
+        };
+        let mut processor = ExactlyOnceProcessor::new(committer, CHAIN_ID)
+            .await
+            .unwrap();
+
+        assert!(processor
+            .process_response(create_response(0..10))
+            .await
+            .is_err());
+        assert_eq!(processor.checkpoint(), None);
+        assert_eq!(processor.starting_version(), 0);
+
+        // The same transactions are redelivered and committed exactly once
+        processor
+            .process_response(create_response(0..10))
+            .await
+            .unwrap();
+        assert_eq!(
+            processor.into_inner().committed_versions,
+            (0..10).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_committed_checkpoint() {
+        let committer = InMemoryCommitter {
+            checkpoint: Some(IdempotencyKey::new(CHAIN_ID, 99)),
+            ..Default::default()
+        };
+        let mut processor = ExactlyOnceProcessor::new(committer, CHAIN_ID)
+            .await
+            .unwrap();
+        assert_eq!(processor.starting_version(), 100);
+
+        let stream = futures::stream::iter(vec![
+            Ok(create_response(90..110)),
+            Ok(create_response(110..120)),
+        ]);
+        let processed = processor.process_stream(stream).await.unwrap();
+        assert_eq!(processed, ProcessedBatch {
+            num_committed: 20,
+            num_duplicates: 10,
+        });
+        assert_eq!(processor.starting_version(), 120);
+
+        // A checkpoint from a different chain is rejected
+        let committer = InMemoryCommitter {
+            checkpoint: Some(IdempotencyKey::new(CHAIN_ID + 1, 99)),
+            ..Default::default()
+        };
+        assert!(ExactlyOnceProcessor::new(committer, CHAIN_ID)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gaps_and_chain_mismatches_are_rejected() {
+        let mut processor = ExactlyOnceProcessor::new(InMemoryCommitter::default(), CHAIN_ID)
+            .await
+            .unwrap();
+        processor
+            .process_response(create_response(0..10))
+            .await
+            .unwrap();
+
+        // Versions 10..12 are missing
+        assert!(processor
+            .process_response(create_response(12..20))
+            .await
+            .is_err());
+
+        // Transactions from another chain
+        let mut response = create_response(10..20);
+        response.chain_id = Some(CHAIN_ID + 1);
+        assert!(processor.process_response(response).await.is_err());
+
+        assert_eq!(processor.starting_version(), 10);
+        assert_eq!(
+            processor.into_inner().committed_versions,
+            (0..10).collect::<Vec<_>>()
+        );
+    }
+}
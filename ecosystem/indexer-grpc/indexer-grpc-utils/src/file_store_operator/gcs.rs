@@ -88,27 +88,26 @@ impl FileStoreOperator for GcsFileStoreOperator {
         }
     }
 
-    /// Gets the metadata from the file store. Operator will panic if error happens when accessing the metadata file(except not found).
-    async fn get_file_store_metadata(&self) -> Option<FileStoreMetadata> {
+    async fn try_get_file_store_metadata(&self) -> anyhow::Result<Option<FileStoreMetadata>> {
         match Object::download(&self.bucket_name, METADATA_FILE_NAME).await {
             Ok(metadata) => {
                 let metadata: FileStoreMetadata =
                     serde_json::from_slice(&metadata).expect("Expected metadata to be valid JSON.");
-                Some(metadata)
+                Ok(Some(metadata))
             },
             Err(cloud_storage::Error::Other(err)) => {
                 if err.contains("No such object: ") {
                     // Metadata is not found.
-                    None
+                    Ok(None)
                 } else {
-                    panic!(
+                    bail!(
                         "[Indexer File] Error happens when accessing metadata file. {}",
                         err
                     );
                 }
             },
             Err(e) => {
-                panic!(
+                bail!(
                     "[Indexer File] Error happens when accessing metadata file. {}",
                     e
                 );
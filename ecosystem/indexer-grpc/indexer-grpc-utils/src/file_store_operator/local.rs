@@ -73,16 +73,16 @@ impl FileStoreOperator for LocalFileStoreOperator {
         }
     }
 
-    async fn get_file_store_metadata(&self) -> Option<FileStoreMetadata> {
+    async fn try_get_file_store_metadata(&self) -> anyhow::Result<Option<FileStoreMetadata>> {
         let metadata_path = self.path.join(METADATA_FILE_NAME);
         match tokio::fs::read(metadata_path).await {
-            Ok(metadata) => Some(FileStoreMetadata::from_bytes(metadata)),
+            Ok(metadata) => Ok(Some(FileStoreMetadata::from_bytes(metadata))),
             Err(err) => {
                 if err.kind() == std::io::ErrorKind::NotFound {
                     // Metadata is not found.
-                    None
+                    Ok(None)
                 } else {
-                    panic!(
+                    anyhow::bail!(
                         "[Indexer File] Error happens when accessing metadata file. {}",
                         err
                     );
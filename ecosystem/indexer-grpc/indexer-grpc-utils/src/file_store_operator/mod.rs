@@ -85,7 +85,13 @@ pub trait FileStoreOperator: Send + Sync {
         ))
     }
     /// Gets the metadata from the file store. Operator will panic if error happens when accessing the metadata file(except not found).
-    async fn get_file_store_metadata(&self) -> Option<FileStoreMetadata>;
+    async fn get_file_store_metadata(&self) -> Option<FileStoreMetadata> {
+        self.try_get_file_store_metadata()
+            .await
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+    /// Gets the metadata from the file store. Returns an error if error happens when accessing the metadata file(except not found).
+    async fn try_get_file_store_metadata(&self) -> Result<Option<FileStoreMetadata>>;
     /// If the file store is empty, the metadata will be created; otherwise, return the existing metadata.
     async fn update_file_store_metadata_with_timeout(
         &mut self,
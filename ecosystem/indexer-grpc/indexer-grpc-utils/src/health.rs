@@ -0,0 +1,302 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Health and readiness reporting for cache-backed data services, so that orchestration
+//! systems can restart (liveness) or drain (readiness) unhealthy instances.
+
+use crate::{
+    cache_operator::CacheOperator, compression_util::StorageFormat,
+    file_store_operator::FileStoreOperator, time_diff_since_pb_timestamp_in_secs,
+};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{convert::Infallible, future::Future, net::SocketAddr, sync::Arc, time::Duration};
+use warp::{http::StatusCode, Filter};
+
+// Timeout of each dependency check; an unresponsive dependency is reported as unreachable.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+// Default max lag of the latest cached transaction for the service to be ready.
+const DEFAULT_MAX_LAG_SECS: u64 = 60;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HealthCheckConfig {
+    /// The address for the health check HTTP server to listen on.
+    pub listen_address: SocketAddr,
+    /// The max lag (in seconds) of the latest cached transaction for the service to be ready.
+    #[serde(default = "HealthCheckConfig::default_max_lag_secs")]
+    pub max_lag_secs: u64,
+}
+
+impl HealthCheckConfig {
+    pub const fn default_max_lag_secs() -> u64 {
+        DEFAULT_MAX_LAG_SECS
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HealthStatus {
+    /// Whether the latest version can be read from Redis.
+    pub redis_connected: bool,
+    /// Whether the file store metadata can be accessed.
+    pub file_store_reachable: bool,
+    /// The latest version in the cache (exclusive).
+    pub latest_cached_version: Option<u64>,
+    /// The latest version in the file store (exclusive).
+    pub file_store_version: Option<u64>,
+    /// How far (in seconds) the latest cached transaction is behind the wall clock, i.e., the
+    /// lag of the cache behind upstream.
+    pub lag_secs: Option<f64>,
+}
+
+impl HealthStatus {
+    /// Healthy if both Redis and the file store are reachable; otherwise no request can be served.
+    pub fn is_healthy(&self) -> bool {
+        self.redis_connected && self.file_store_reachable
+    }
+
+    /// Ready if healthy, both the cache and the file store have data, and the cache does not lag
+    /// behind upstream by more than max_lag_secs.
+    pub fn is_ready(&self, max_lag_secs: u64) -> bool {
+        self.is_healthy()
+            && self.latest_cached_version.is_some()
+            && self.file_store_version.is_some()
+            && self
+                .lag_secs
+                .map_or(false, |lag_secs| lag_secs <= max_lag_secs as f64)
+    }
+}
+
+pub struct HealthChecker<C: redis::aio::ConnectionLike + Send> {
+    cache_operator: CacheOperator<C>,
+    file_store_operator: Box<dyn FileStoreOperator>,
+    max_lag_secs: u64,
+}
+
+impl<C> HealthChecker<C>
+where
+    C: redis::aio::ConnectionLike + Send + Sync + Clone + 'static,
+{
+    pub fn new(
+        conn: C,
+        storage_format: StorageFormat,
+        file_store_operator: Box<dyn FileStoreOperator>,
+        max_lag_secs: u64,
+    ) -> Self {
+        Self {
+            cache_operator: CacheOperator::new(conn, storage_format),
+            file_store_operator,
+            max_lag_secs,
+        }
+    }
+
+    pub fn max_lag_secs(&self) -> u64 {
+        self.max_lag_secs
+    }
+
+    /// Checks the cache and the file store. Never fails; failures are reported in the status.
+    pub async fn check(&self) -> HealthStatus {
+        let mut cache_operator = self.cache_operator.clone();
+
+        let (redis_connected, latest_cached_version) =
+            match with_timeout(cache_operator.get_latest_version()).await {
+                Ok(version) => (true, version),
+                Err(err) => {
+                    tracing::warn!(
+                        "[Health Check] Failed to get latest version from Redis: {:?}",
+                        err
+                    );
+                    (false, None)
+                },
+            };
+
+        let lag_secs = match latest_cached_version {
+            Some(version) if version > 0 => {
+                match with_timeout(cache_operator.get_transactions(version - 1, 1)).await {
+                    Ok(transactions) => transactions
+                        .first()
+                        .and_then(|transaction| transaction.timestamp.as_ref())
+                        .map(time_diff_since_pb_timestamp_in_secs),
+                    Err(err) => {
+                        tracing::warn!(
+                            version = version - 1,
+                            "[Health Check] Failed to get latest transaction from Redis: {:?}",
+                            err
+                        );
+                        None
+                    },
+                }
+            },
+            _ => None,
+        };
+
+        let (file_store_reachable, file_store_version) =
+            match with_timeout(self.file_store_operator.try_get_file_store_metadata()).await {
+                Ok(metadata) => (true, metadata.map(|metadata| metadata.version)),
+                Err(err) => {
+                    tracing::warn!(
+                        store_name = self.file_store_operator.store_name(),
+                        "[Health Check] Failed to get file store metadata: {:?}",
+                        err
+                    );
+                    (false, None)
+                },
+            };
+
+        HealthStatus {
+            redis_connected,
+            file_store_reachable,
+            latest_cached_version,
+            file_store_version,
+            lag_secs,
+        }
+    }
+}
+
+async fn with_timeout<T>(future: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+    tokio::time::timeout(HEALTH_CHECK_TIMEOUT, future)
+        .await
+        .context("Health check timed out")?
+}
+
+/// Liveness and readiness endpoints, replying with the health status as JSON, and with
+/// SERVICE_UNAVAILABLE if the service is not healthy (respectively ready).
+pub fn health_check_routes<C>(
+    checker: Arc<HealthChecker<C>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+    C: redis::aio::ConnectionLike + Send + Sync + Clone + 'static,
+{
+    let liveness_checker = checker.clone();
+    let liveness = warp::path("liveness").and_then(move || {
+        let checker = liveness_checker.clone();
+        async move {
+            let status = checker.check().await;
+            let is_healthy = status.is_healthy();
+            Ok::<_, Infallible>(status_reply(&status, is_healthy))
+        }
+    });
+    let readiness = warp::path("readiness").and_then(move || {
+        let checker = checker.clone();
+        async move {
+            let status = checker.check().await;
+            let is_ready = status.is_ready(checker.max_lag_secs());
+            Ok::<_, Infallible>(status_reply(&status, is_ready))
+        }
+    });
+    liveness.or(readiness)
+}
+
+fn status_reply(status: &HealthStatus, ok: bool) -> impl warp::Reply {
+    let status_code = if ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    warp::reply::with_status(warp::reply::json(status), status_code)
+}
+
+/// Serves the liveness and readiness endpoints on the given address.
+pub async fn serve_health_checks<C>(checker: Arc<HealthChecker<C>>, listen_address: SocketAddr)
+where
+    C: redis::aio::ConnectionLike + Send + Sync + Clone + 'static,
+{
+    tracing::info!(
+        listen_address = listen_address.to_string().as_str(),
+        "[Health Check] Starting health check server."
+    );
+    warp::serve(health_check_routes(checker))
+        .run(listen_address)
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_store_operator::LocalFileStoreOperator;
+    use redis_test::{MockCmd, MockRedisConnection};
+
+    fn healthy_status() -> HealthStatus {
+        HealthStatus {
+            redis_connected: true,
+            file_store_reachable: true,
+            latest_cached_version: Some(100),
+            file_store_version: Some(50),
+            lag_secs: Some(1.0),
+        }
+    }
+
+    fn create_checker(cmds: Vec<MockCmd>) -> HealthChecker<MockRedisConnection> {
+        // The metadata file does not exist in an empty directory.
+        let file_store_path = std::env::temp_dir().join("indexer_grpc_health_check_test_empty");
+        HealthChecker::new(
+            MockRedisConnection::new(cmds),
+            StorageFormat::Base64UncompressedProto,
+            Box::new(LocalFileStoreOperator::new(file_store_path, false)),
+            DEFAULT_MAX_LAG_SECS,
+        )
+    }
+
+    #[test]
+    fn test_readiness_requires_data_and_bounded_lag() {
+        assert!(healthy_status().is_ready(10));
+        assert!(!HealthStatus {
+            lag_secs: Some(11.0),
+            ..healthy_status()
+        }
+        .is_ready(10));
+        assert!(!HealthStatus {
+            lag_secs: None,
+            ..healthy_status()
+        }
+        .is_ready(10));
+        assert!(!HealthStatus {
+            file_store_version: None,
+            ..healthy_status()
+        }
+        .is_ready(10));
+
+        let status = HealthStatus {
+            redis_connected: false,
+            ..healthy_status()
+        };
+        assert!(!status.is_healthy());
+        assert!(!status.is_ready(10));
+    }
+
+    #[tokio::test]
+    async fn test_redis_disconnected() {
+        let checker = create_checker(vec![MockCmd::new(
+            redis::cmd("GET").arg("latest_version"),
+            Err::<String, _>(redis::RedisError::from((
+                redis::ErrorKind::IoError,
+                "connection refused",
+            ))),
+        )]);
+
+        let status = checker.check().await;
+        assert!(!status.redis_connected);
+        assert!(status.file_store_reachable);
+        assert_eq!(status.latest_cached_version, None);
+        assert!(!status.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_empty_cache_is_healthy_but_not_ready() {
+        let checker = create_checker(vec![MockCmd::new(
+            redis::cmd("GET").arg("latest_version"),
+            Ok("0"),
+        )]);
+
+        let status = checker.check().await;
+        assert_eq!(status, HealthStatus {
+            redis_connected: true,
+            file_store_reachable: true,
+            latest_cached_version: Some(0),
+            file_store_version: None,
+            lag_secs: None,
+        });
+        assert!(status.is_healthy());
+        assert!(!status.is_ready(checker.max_lag_secs()));
+    }
+}
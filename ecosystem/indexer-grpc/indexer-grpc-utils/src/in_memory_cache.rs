@@ -1,29 +1,80 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::compression_util::{CacheEntry, StorageFormat};
+use crate::{
+    compression_util::{CacheEntry, StorageFormat},
+    counters::{
+        IN_MEMORY_CACHE_EVICTED_BYTES, IN_MEMORY_CACHE_EVICTED_TRANSACTIONS,
+        IN_MEMORY_CACHE_RESIDENT_SIZE_IN_BYTES, IN_MEMORY_CACHE_RESIDENT_TRANSACTIONS,
+    },
+};
 use anyhow::Context;
 use aptos_protos::transaction::v1::Transaction;
 use dashmap::DashMap;
 use itertools::Itertools;
 use prost::Message;
 use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 // Internal lookup retry interval for in-memory cache.
 const IN_MEMORY_CACHE_LOOKUP_RETRY_INTERVAL_MS: u64 = 10;
 const IN_MEMORY_CACHE_GC_INTERVAL_MS: u64 = 100;
-// Max cache size in bytes: 3 GB.
-const IN_MEMORY_CACHE_TARGET_MAX_CAPACITY_IN_BYTES: u64 = 3_000_000_000;
-// Eviction cache size in bytes: 3.5 GB. Evict the map to 3 GB.
-const IN_MEMORY_CACHE_EVICTION_TRIGGER_SIZE_IN_BYTES: u64 = 3_500_000_000;
+// Default target cache size in bytes after eviction: 3 GB.
+const DEFAULT_TARGET_RESIDENT_BYTES: u64 = 3_000_000_000;
+// Default max cache size in bytes: 3.5 GB. Once exceeded, evict the map to the target size.
+const DEFAULT_MAX_RESIDENT_BYTES: u64 = 3_500_000_000;
 // Max cache entry TTL: 30 seconds.
 // const MAX_IN_MEMORY_CACHE_ENTRY_TTL: u64 = 30;
 // Warm-up cache entries. Pre-fetch the cache entries to warm up the cache.
 const WARM_UP_CACHE_ENTRIES: u64 = 20_000;
 const MAX_REDIS_FETCH_BATCH_SIZE: usize = 500;
 
+/// The memory budget of the in-memory cache. The cache is bounded by the (encoded) size of the
+/// resident transactions rather than their count, as transaction sizes vary widely.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct InMemoryCacheConfig {
+    /// The max size (in bytes) of the resident transactions. Once exceeded, the oldest
+    /// versions are evicted until the size drops to `target_resident_bytes`.
+    #[serde(default = "InMemoryCacheConfig::default_max_resident_bytes")]
+    pub max_resident_bytes: u64,
+    /// The size (in bytes) of the resident transactions after an eviction.
+    #[serde(default = "InMemoryCacheConfig::default_target_resident_bytes")]
+    pub target_resident_bytes: u64,
+}
+
+impl InMemoryCacheConfig {
+    pub const fn default_max_resident_bytes() -> u64 {
+        DEFAULT_MAX_RESIDENT_BYTES
+    }
+
+    pub const fn default_target_resident_bytes() -> u64 {
+        DEFAULT_TARGET_RESIDENT_BYTES
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.target_resident_bytes > self.max_resident_bytes {
+            anyhow::bail!(
+                "target_resident_bytes ({}) must not exceed max_resident_bytes ({})",
+                self.target_resident_bytes,
+                self.max_resident_bytes
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for InMemoryCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_resident_bytes: Self::default_max_resident_bytes(),
+            target_resident_bytes: Self::default_target_resident_bytes(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct CacheMetadata {
     total_size_in_bytes: u64,
@@ -31,6 +82,15 @@ struct CacheMetadata {
     first_version: u64,
 }
 
+impl CacheMetadata {
+    /// Updates the resident size counters.
+    fn update_counters(&self) {
+        IN_MEMORY_CACHE_RESIDENT_SIZE_IN_BYTES.set(self.total_size_in_bytes as i64);
+        IN_MEMORY_CACHE_RESIDENT_TRANSACTIONS
+            .set(self.latest_version.saturating_sub(self.first_version) as i64);
+    }
+}
+
 /// InMemoryCache is a simple in-memory cache that stores the protobuf Transaction.
 pub struct InMemoryCache {
     /// Cache maps the cache key to the deserialized Transaction.
@@ -43,6 +103,7 @@ impl InMemoryCache {
     pub async fn new_with_redis_connection<C>(
         conn: C,
         storage_format: StorageFormat,
+        config: InMemoryCacheConfig,
     ) -> anyhow::Result<Self>
     where
         C: redis::aio::ConnectionLike + Send + Sync + Clone + 'static,
@@ -55,11 +116,13 @@ impl InMemoryCache {
             in_memory_latest_version
         );
         let cancellation_token = tokio_util::sync::CancellationToken::new();
-        let cache_metadata = Arc::new(RwLock::new(CacheMetadata {
+        let cache_metadata = CacheMetadata {
             first_version: in_memory_first_version,
             total_size_in_bytes,
             latest_version: in_memory_latest_version,
-        }));
+        };
+        cache_metadata.update_counters();
+        let cache_metadata = Arc::new(RwLock::new(cache_metadata));
         spawn_update_task(
             conn,
             cache.clone(),
@@ -70,6 +133,7 @@ impl InMemoryCache {
         spawn_cleanup_task(
             cache.clone(),
             cache_metadata.clone(),
+            config,
             cancellation_token.clone(),
         );
         tracing::info!("In-memory cache is created");
@@ -230,12 +294,13 @@ fn spawn_update_task<C>(
                 redis_waiting_duration,
                 "In-memory cache is updated"
             );
-            let mut current_cache_metadata = { *cache_metadata.read().await };
-            current_cache_metadata.latest_version = end_version;
-            current_cache_metadata.total_size_in_bytes += newly_added_bytes;
-            // Get the data available.
+            // Get the data available. The metadata is updated in place, so that concurrent
+            // evictions are not overwritten.
             {
-                *cache_metadata.write().await = current_cache_metadata;
+                let mut current_cache_metadata = cache_metadata.write().await;
+                current_cache_metadata.latest_version = end_version;
+                current_cache_metadata.total_size_in_bytes += newly_added_bytes;
+                current_cache_metadata.update_counters();
             }
             current_time = std::time::Instant::now();
         }
@@ -245,6 +310,7 @@ fn spawn_update_task<C>(
 fn spawn_cleanup_task(
     cache: Arc<DashMap<u64, Arc<Transaction>>>,
     cache_metadata: Arc<RwLock<CacheMetadata>>,
+    config: InMemoryCacheConfig,
     cancellation_token: tokio_util::sync::CancellationToken,
 ) {
     tokio::spawn(async move {
@@ -253,11 +319,8 @@ fn spawn_cleanup_task(
                 tracing::info!("In-memory cache cleanup task is cancelled.");
                 return;
             }
-            let mut current_cache_metadata = { *cache_metadata.read().await };
-            let should_evict = current_cache_metadata
-                .total_size_in_bytes
-                .saturating_sub(IN_MEMORY_CACHE_EVICTION_TRIGGER_SIZE_IN_BYTES)
-                > 0;
+            let should_evict =
+                { cache_metadata.read().await.total_size_in_bytes > config.max_resident_bytes };
             if !should_evict {
                 tokio::time::sleep(std::time::Duration::from_millis(
                     IN_MEMORY_CACHE_GC_INTERVAL_MS,
@@ -265,25 +328,55 @@ fn spawn_cleanup_task(
                 .await;
                 continue;
             }
-            let mut actual_bytes_removed = 0;
-            let mut bytes_to_remove = current_cache_metadata
-                .total_size_in_bytes
-                .saturating_sub(IN_MEMORY_CACHE_TARGET_MAX_CAPACITY_IN_BYTES);
-            while bytes_to_remove > 0 {
-                let key_to_remove = current_cache_metadata.first_version;
-                let (_k, v) = cache
-                    .remove(&key_to_remove)
-                    .expect("Failed to remove the key");
-                bytes_to_remove = bytes_to_remove.saturating_sub(v.encoded_len() as u64);
-                actual_bytes_removed += v.encoded_len() as u64;
-                current_cache_metadata.first_version += 1;
-            }
-            current_cache_metadata.total_size_in_bytes -= actual_bytes_removed;
-            *cache_metadata.write().await = current_cache_metadata;
+            // Hold the write lock while evicting, so that the update task can't race with us.
+            let mut current_cache_metadata = cache_metadata.write().await;
+            let (evicted_transactions, evicted_bytes) = evict_oldest_versions(
+                &cache,
+                &mut current_cache_metadata,
+                config.target_resident_bytes,
+            );
+            current_cache_metadata.update_counters();
+            tracing::info!(
+                evicted_transactions,
+                evicted_bytes,
+                in_memory_first_version = current_cache_metadata.first_version,
+                total_size_in_bytes = current_cache_metadata.total_size_in_bytes,
+                "In-memory cache is evicted"
+            );
         }
     });
 }
 
+/// Evicts the oldest versions until the resident size drops to `target_resident_bytes`.
+/// Versions are only ever appended in order, so the oldest version is also the least recently
+/// inserted one. Returns the number of evicted transactions and bytes.
+fn evict_oldest_versions(
+    cache: &DashMap<u64, Arc<Transaction>>,
+    cache_metadata: &mut CacheMetadata,
+    target_resident_bytes: u64,
+) -> (u64, u64) {
+    let mut evicted_transactions = 0;
+    let mut evicted_bytes = 0;
+    while cache_metadata.total_size_in_bytes > target_resident_bytes
+        && cache_metadata.first_version < cache_metadata.latest_version
+    {
+        let key_to_remove = cache_metadata.first_version;
+        let (_k, v) = cache
+            .remove(&key_to_remove)
+            .expect("Failed to remove the key");
+        let transaction_bytes = v.encoded_len() as u64;
+        cache_metadata.total_size_in_bytes = cache_metadata
+            .total_size_in_bytes
+            .saturating_sub(transaction_bytes);
+        cache_metadata.first_version += 1;
+        evicted_transactions += 1;
+        evicted_bytes += transaction_bytes;
+    }
+    IN_MEMORY_CACHE_EVICTED_TRANSACTIONS.inc_by(evicted_transactions);
+    IN_MEMORY_CACHE_EVICTED_BYTES.inc_by(evicted_bytes);
+    (evicted_transactions, evicted_bytes)
+}
+
 // TODO: move the following functions to cache operator.
 async fn get_config_by_key<C>(conn: &mut C, key: &str) -> anyhow::Result<Option<u64>>
 where
@@ -391,6 +484,7 @@ mod tests {
         let in_memory_cache = InMemoryCache::new_with_redis_connection(
             mock_connection.clone(),
             StorageFormat::Base64UncompressedProto,
+            InMemoryCacheConfig::default(),
         )
         .await
         .unwrap();
@@ -418,6 +512,7 @@ mod tests {
         let in_memory_cache = InMemoryCache::new_with_redis_connection(
             mock_connection.clone(),
             StorageFormat::Base64UncompressedProto,
+            InMemoryCacheConfig::default(),
         )
         .await
         .unwrap();
@@ -462,6 +557,7 @@ mod tests {
         let in_memory_cache = InMemoryCache::new_with_redis_connection(
             mock_connection.clone(),
             StorageFormat::Base64UncompressedProto,
+            InMemoryCacheConfig::default(),
         )
         .await
         .unwrap();
@@ -471,4 +567,64 @@ mod tests {
         assert_eq!(txns.len(), 1);
         assert_eq!(txns[0].version, 1);
     }
+
+    #[test]
+    fn test_in_memory_cache_config_validation() {
+        assert!(InMemoryCacheConfig::default().validate().is_ok());
+        let config = InMemoryCacheConfig {
+            max_resident_bytes: 100,
+            target_resident_bytes: 200,
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_evict_oldest_versions() {
+        // Populate the cache with transactions of different sizes.
+        let cache = DashMap::new();
+        let mut total_size_in_bytes = 0;
+        for version in 0..10 {
+            let txn = Transaction {
+                version,
+                block_height: 1,
+                epoch: version * 1_000_000,
+                ..Default::default()
+            };
+            total_size_in_bytes += txn.encoded_len() as u64;
+            cache.insert(version, Arc::new(txn));
+        }
+        let mut cache_metadata = CacheMetadata {
+            total_size_in_bytes,
+            latest_version: 10,
+            first_version: 0,
+        };
+
+        // Nothing is evicted if the cache is within the budget.
+        assert_eq!(
+            evict_oldest_versions(&cache, &mut cache_metadata, total_size_in_bytes),
+            (0, 0)
+        );
+        assert_eq!(cache.len(), 10);
+
+        // Evict down to half of the budget; the oldest versions go first.
+        let target_resident_bytes = total_size_in_bytes / 2;
+        let (evicted_transactions, evicted_bytes) =
+            evict_oldest_versions(&cache, &mut cache_metadata, target_resident_bytes);
+        assert!(evicted_transactions > 0);
+        assert!(cache_metadata.total_size_in_bytes <= target_resident_bytes);
+        assert_eq!(
+            cache_metadata.total_size_in_bytes,
+            total_size_in_bytes - evicted_bytes
+        );
+        assert_eq!(cache_metadata.first_version, evicted_transactions);
+        assert_eq!(cache.len() as u64, 10 - evicted_transactions);
+        assert!(!cache.contains_key(&0));
+        assert!(cache.contains_key(&9));
+
+        // A zero budget evicts everything, but never past the latest version.
+        evict_oldest_versions(&cache, &mut cache_metadata, 0);
+        assert!(cache.is_empty());
+        assert_eq!(cache_metadata.first_version, 10);
+        assert_eq!(cache_metadata.total_size_in_bytes, 0);
+    }
 }
@@ -6,8 +6,14 @@ pub mod compression_util;
 pub mod config;
 pub mod constants;
 pub mod counters;
+pub mod exactly_once;
 pub mod file_store_operator;
+pub mod health;
 pub mod in_memory_cache;
+pub mod load_generator;
+pub mod stream_cursor;
+pub mod transaction_filter;
+pub mod transaction_projection;
 pub mod types;
 
 use anyhow::{Context, Result};
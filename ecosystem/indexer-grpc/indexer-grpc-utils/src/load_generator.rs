@@ -0,0 +1,600 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Synthetic load generator for the transaction cache.
+//!
+//! The load generator writes a stream of synthetic transactions (with a configurable size
+//! distribution and burst pattern) into the cache, the same way the cache worker does, while
+//! a number of readers follow the stream through the same read path as the data service. This
+//! allows performance requirements to be validated against realistic load shapes, either with
+//! an in-process mock Redis or against a real Redis instance.
+
+use crate::{cache_operator::CacheOperator, compression_util::StorageFormat};
+use anyhow::{ensure, Context};
+use aptos_protos::{
+    transaction::v1::{Transaction, TransactionInfo},
+    util::timestamp::Timestamp,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use redis::{aio::ConnectionLike, Arg, Cmd, ErrorKind, Pipeline, RedisFuture, RedisResult, Value};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// The size distribution of the synthetic transactions.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSizeDistribution {
+    /// All transactions have the same payload size.
+    Fixed { size_in_bytes: usize },
+    /// Payload sizes are uniformly distributed in [min_size_in_bytes, max_size_in_bytes].
+    Uniform {
+        min_size_in_bytes: usize,
+        max_size_in_bytes: usize,
+    },
+    /// Payload sizes are mostly small, with occasional large transactions (e.g., module
+    /// publishing), where `large_ratio` is the fraction of large transactions.
+    Bimodal {
+        small_size_in_bytes: usize,
+        large_size_in_bytes: usize,
+        large_ratio: f64,
+    },
+}
+
+impl TransactionSizeDistribution {
+    fn sample(&self, rng: &mut StdRng) -> usize {
+        match self {
+            Self::Fixed { size_in_bytes } => *size_in_bytes,
+            Self::Uniform {
+                min_size_in_bytes,
+                max_size_in_bytes,
+            } => rng.gen_range(*min_size_in_bytes, *max_size_in_bytes + 1),
+            Self::Bimodal {
+                small_size_in_bytes,
+                large_size_in_bytes,
+                large_ratio,
+            } => {
+                if rng.gen_bool(*large_ratio) {
+                    *large_size_in_bytes
+                } else {
+                    *small_size_in_bytes
+                }
+            },
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Fixed { .. } => {},
+            Self::Uniform {
+                min_size_in_bytes,
+                max_size_in_bytes,
+            } => ensure!(
+                min_size_in_bytes <= max_size_in_bytes,
+                "The minimum transaction size must not exceed the maximum transaction size."
+            ),
+            Self::Bimodal { large_ratio, .. } => ensure!(
+                (0.0..=1.0).contains(large_ratio),
+                "The ratio of large transactions must be in [0, 1]."
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// The pattern in which the batches of synthetic transactions are written to the cache.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BurstPattern {
+    /// Batches are written as fast as possible.
+    Unthrottled,
+    /// Batches are written at a steady rate of transactions per second.
+    Steady { transactions_per_second: u64 },
+    /// Bursts of batches are written as fast as possible, separated by idle periods.
+    Bursty {
+        batches_per_burst: usize,
+        idle_duration_ms: u64,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadGeneratorConfig {
+    /// The number of batches written to the cache.
+    pub num_batches: usize,
+    /// The number of transactions per batch, i.e., per cache update.
+    pub batch_size: usize,
+    /// The size distribution of the synthetic transactions.
+    pub transaction_size_distribution: TransactionSizeDistribution,
+    /// The pattern in which the batches are written.
+    pub burst_pattern: BurstPattern,
+    /// The number of concurrent readers following the stream.
+    pub num_readers: usize,
+    /// The maximum number of transactions fetched by a reader in a single read.
+    pub max_read_batch_size: u64,
+    /// Whether the cache entries are compressed.
+    pub enable_cache_compression: bool,
+    /// The seed for generating the synthetic transactions (for reproducibility).
+    pub seed: u64,
+}
+
+impl Default for LoadGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            num_batches: 100,
+            batch_size: 1000,
+            transaction_size_distribution: TransactionSizeDistribution::Uniform {
+                min_size_in_bytes: 500,
+                max_size_in_bytes: 5000,
+            },
+            burst_pattern: BurstPattern::Unthrottled,
+            num_readers: 4,
+            max_read_batch_size: 1000,
+            enable_cache_compression: false,
+            seed: 0,
+        }
+    }
+}
+
+impl LoadGeneratorConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(self.batch_size > 0, "The batch size must be positive.");
+        ensure!(
+            self.max_read_batch_size > 0,
+            "The maximum read batch size must be positive."
+        );
+        if let BurstPattern::Steady {
+            transactions_per_second,
+        } = self.burst_pattern
+        {
+            ensure!(
+                transactions_per_second > 0,
+                "The number of transactions per second must be positive."
+            );
+        }
+        self.transaction_size_distribution.validate()
+    }
+
+    fn storage_format(&self) -> StorageFormat {
+        if self.enable_cache_compression {
+            StorageFormat::GzipCompressedProto
+        } else {
+            StorageFormat::Base64UncompressedProto
+        }
+    }
+}
+
+/// Latency statistics (in milliseconds) of a set of cache operations.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct LatencyReport {
+    pub count: usize,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyReport {
+    fn new(mut latencies: Vec<Duration>) -> Self {
+        if latencies.is_empty() {
+            return Self::default();
+        }
+        latencies.sort();
+        let to_ms = |latency: &Duration| latency.as_secs_f64() * 1000.0;
+        let percentile = |p: f64| to_ms(&latencies[((latencies.len() - 1) as f64 * p) as usize]);
+        Self {
+            count: latencies.len(),
+            mean_ms: latencies.iter().map(to_ms).sum::<f64>() / latencies.len() as f64,
+            p50_ms: percentile(0.5),
+            p99_ms: percentile(0.99),
+            max_ms: to_ms(latencies.last().unwrap()),
+        }
+    }
+}
+
+/// The results of a load generator run.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LoadReport {
+    pub num_transactions: u64,
+    pub total_size_in_bytes: u64,
+    pub duration_secs: f64,
+    pub write_transactions_per_second: f64,
+    /// The latency of writing a batch (including the latest version update).
+    pub write_latency: LatencyReport,
+    /// The number of transactions read, across all readers.
+    pub num_transactions_read: u64,
+    /// The latency of a single read (including decoding).
+    pub read_latency: LatencyReport,
+}
+
+/// Writes synthetic transactions to the cache (with the configured load shape) while
+/// concurrent readers follow the stream.
+pub struct LoadGenerator {
+    config: LoadGeneratorConfig,
+}
+
+impl LoadGenerator {
+    pub fn new(config: LoadGeneratorConfig) -> anyhow::Result<Self> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    pub async fn run<C>(&self, conn: C) -> anyhow::Result<LoadReport>
+    where
+        C: ConnectionLike + Send + Sync + Clone + 'static,
+    {
+        let storage_format = self.config.storage_format();
+        let mut cache_operator = CacheOperator::new(conn, storage_format);
+        cache_operator.cache_setup_if_needed().await?;
+        let starting_version = cache_operator
+            .get_latest_version()
+            .await?
+            .context("The cache latest version is not set.")?;
+        let num_transactions = (self.config.num_batches * self.config.batch_size) as u64;
+        let ending_version = starting_version + num_transactions;
+
+        // Start the readers, which follow the stream from the starting version
+        let start_time = Instant::now();
+        let readers: Vec<_> = (0..self.config.num_readers)
+            .map(|_| {
+                tokio::spawn(read_transactions(
+                    cache_operator.clone(),
+                    starting_version,
+                    ending_version,
+                    self.config.max_read_batch_size,
+                ))
+            })
+            .collect();
+
+        // Write the synthetic transactions
+        let (total_size_in_bytes, write_latencies) = self
+            .write_transactions(&mut cache_operator, starting_version)
+            .await?;
+        let write_duration = start_time.elapsed();
+
+        let mut num_transactions_read = 0;
+        let mut read_latencies = vec![];
+        for reader in readers {
+            let (num_read, latencies) = reader.await??;
+            num_transactions_read += num_read;
+            read_latencies.extend(latencies);
+        }
+
+        Ok(LoadReport {
+            num_transactions,
+            total_size_in_bytes,
+            duration_secs: start_time.elapsed().as_secs_f64(),
+            write_transactions_per_second: num_transactions as f64 / write_duration.as_secs_f64(),
+            write_latency: LatencyReport::new(write_latencies),
+            num_transactions_read,
+            read_latency: LatencyReport::new(read_latencies),
+        })
+    }
+
+    /// Writes the batches the way the cache worker does, i.e., the transactions followed
+    /// by the latest version update. Returns the total transaction size and the latencies.
+    async fn write_transactions<C>(
+        &self,
+        cache_operator: &mut CacheOperator<C>,
+        starting_version: u64,
+    ) -> anyhow::Result<(u64, Vec<Duration>)>
+    where
+        C: ConnectionLike + Send + Clone,
+    {
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+        let mut total_size_in_bytes = 0;
+        let mut latencies = vec![];
+        let start_time = Instant::now();
+        for batch_index in 0..self.config.num_batches {
+            self.wait_for_next_batch(batch_index, start_time).await;
+
+            let batch_start_version =
+                starting_version + (batch_index * self.config.batch_size) as u64;
+            let transactions: Vec<_> = (0..self.config.batch_size as u64)
+                .map(|offset| {
+                    let size_in_bytes = self.config.transaction_size_distribution.sample(&mut rng);
+                    total_size_in_bytes += size_in_bytes as u64;
+                    create_transaction(batch_start_version + offset, size_in_bytes, &mut rng)
+                })
+                .collect();
+
+            let write_start_time = Instant::now();
+            cache_operator
+                .update_cache_transactions(transactions)
+                .await?;
+            cache_operator
+                .update_cache_latest_version(
+                    self.config.batch_size as u64,
+                    batch_start_version + self.config.batch_size as u64,
+                )
+                .await?;
+            latencies.push(write_start_time.elapsed());
+        }
+        Ok((total_size_in_bytes, latencies))
+    }
+
+    /// Waits until the batch with the given index should be written, per the burst pattern
+    async fn wait_for_next_batch(&self, batch_index: usize, start_time: Instant) {
+        match self.config.burst_pattern {
+            BurstPattern::Unthrottled => {},
+            BurstPattern::Steady {
+                transactions_per_second,
+            } => {
+                // Pace against the start time, so that slow writes don't cause drift
+                let num_transactions_written = (batch_index * self.config.batch_size) as f64;
+                let target_elapsed = Duration::from_secs_f64(
+                    num_transactions_written / transactions_per_second as f64,
+                );
+                if let Some(wait_duration) = target_elapsed.checked_sub(start_time.elapsed()) {
+                    tokio::time::sleep(wait_duration).await;
+                }
+            },
+            BurstPattern::Bursty {
+                batches_per_burst,
+                idle_duration_ms,
+            } => {
+                if batch_index > 0 && batch_index % batches_per_burst.max(1) == 0 {
+                    tokio::time::sleep(Duration::from_millis(idle_duration_ms)).await;
+                }
+            },
+        }
+    }
+}
+
+/// Follows the stream from the starting version (until the ending version), the way the
+/// data service does. Returns the number of transactions read and the read latencies.
+async fn read_transactions<C>(
+    mut cache_operator: CacheOperator<C>,
+    starting_version: u64,
+    ending_version: u64,
+    max_read_batch_size: u64,
+) -> anyhow::Result<(u64, Vec<Duration>)>
+where
+    C: ConnectionLike + Send + Clone,
+{
+    let mut current_version = starting_version;
+    let mut latencies = vec![];
+    while current_version < ending_version {
+        let latest_version = cache_operator
+            .get_latest_version()
+            .await?
+            .context("The cache latest version is not set.")?;
+        if latest_version <= current_version {
+            // Data not ready yet. Wait and retry.
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            continue;
+        }
+
+        let transaction_count = (latest_version - current_version).min(max_read_batch_size);
+        let read_start_time = Instant::now();
+        let transactions = cache_operator
+            .get_transactions(current_version, transaction_count)
+            .await?;
+        latencies.push(read_start_time.elapsed());
+        ensure!(
+            transactions.first().map(|transaction| transaction.version) == Some(current_version),
+            "Unexpected transaction version read from cache."
+        );
+        current_version += transaction_count;
+    }
+    Ok((current_version - starting_version, latencies))
+}
+
+/// Creates a synthetic transaction, padded with random bytes to the given size
+fn create_transaction(version: u64, size_in_bytes: usize, rng: &mut StdRng) -> Transaction {
+    let mut padding = vec![0u8; size_in_bytes];
+    rng.fill(&mut padding[..]);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    Transaction {
+        version,
+        timestamp: Some(Timestamp {
+            seconds: now.as_secs() as i64,
+            nanos: now.subsec_nanos() as i32,
+        }),
+        info: Some(TransactionInfo {
+            hash: padding,
+            ..TransactionInfo::default()
+        }),
+        ..Transaction::default()
+    }
+}
+
+/// An in-process Redis, supporting (only) the commands used by the cache operator.
+/// Note: expirations are ignored, and EVALSHA always runs the latest version update script.
+#[derive(Clone, Default)]
+pub struct InMemoryRedisConnection {
+    entries: Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>,
+}
+
+impl InMemoryRedisConnection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn execute(&self, cmd: &Cmd) -> RedisResult<Value> {
+        let args: Vec<&[u8]> = cmd
+            .args_iter()
+            .filter_map(|arg| match arg {
+                Arg::Simple(arg) => Some(arg),
+                Arg::Cursor => None,
+            })
+            .collect();
+        let (name, args) = args
+            .split_first()
+            .ok_or((ErrorKind::ClientError, "Empty command"))?;
+        let get_arg = |index: usize| {
+            args.get(index)
+                .copied()
+                .ok_or((ErrorKind::ClientError, "Missing command argument"))
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        match name.to_ascii_uppercase().as_slice() {
+            b"GET" => Ok(entries
+                .get(get_arg(0)?)
+                .map_or(Value::Nil, |value| Value::Data(value.clone()))),
+            b"MGET" => Ok(Value::Bulk(
+                args.iter()
+                    .map(|key| {
+                        entries
+                            .get(*key)
+                            .map_or(Value::Nil, |value| Value::Data(value.clone()))
+                    })
+                    .collect(),
+            )),
+            b"SET" => {
+                let (key, value) = (get_arg(0)?, get_arg(1)?);
+                let only_if_absent = args[2..].iter().any(|arg| arg.eq_ignore_ascii_case(b"NX"));
+                if only_if_absent && entries.contains_key(key) {
+                    return Ok(Value::Nil);
+                }
+                entries.insert(key.to_vec(), value.to_vec());
+                Ok(Value::Okay)
+            },
+            b"DEL" => Ok(Value::Int(
+                args.iter()
+                    .filter(|key| entries.remove(**key).is_some())
+                    .count() as i64,
+            )),
+            // EVALSHA <sha> <num keys> <latest version key> <num of versions> <version>
+            b"EVALSHA" => {
+                let parse_u64 = |arg: &[u8]| {
+                    std::str::from_utf8(arg)
+                        .ok()
+                        .and_then(|arg| arg.parse::<u64>().ok())
+                        .ok_or((ErrorKind::TypeError, "Invalid integer argument"))
+                };
+                let key = get_arg(2)?;
+                let num_of_versions = parse_u64(get_arg(3)?)?;
+                let version = parse_u64(get_arg(4)?)?;
+                let latest_version = entries.get(key).map(|value| parse_u64(value)).transpose()?;
+                let (new_latest_version, result) = match latest_version {
+                    None => (num_of_versions, 0),
+                    Some(latest_version) if latest_version + num_of_versions < version => {
+                        return Ok(Value::Int(2))
+                    },
+                    Some(latest_version) if latest_version + num_of_versions == version => {
+                        (version, 0)
+                    },
+                    Some(latest_version) => (version.max(latest_version), 1),
+                };
+                entries.insert(key.to_vec(), new_latest_version.to_string().into_bytes());
+                Ok(Value::Int(result))
+            },
+            _ => Err((
+                ErrorKind::ClientError,
+                "Unsupported command",
+                String::from_utf8_lossy(name).to_string(),
+            )
+                .into()),
+        }
+    }
+}
+
+impl ConnectionLike for InMemoryRedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let result = self.execute(cmd);
+        Box::pin(async move { result })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        pipeline: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let result = pipeline
+            .cmd_iter()
+            .map(|cmd| self.execute(cmd))
+            .skip(offset)
+            .take(count)
+            .collect();
+        Box::pin(async move { result })
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_config(burst_pattern: BurstPattern) -> LoadGeneratorConfig {
+        LoadGeneratorConfig {
+            num_batches: 10,
+            batch_size: 50,
+            transaction_size_distribution: TransactionSizeDistribution::Bimodal {
+                small_size_in_bytes: 100,
+                large_size_in_bytes: 10_000,
+                large_ratio: 0.1,
+            },
+            burst_pattern,
+            num_readers: 3,
+            max_read_batch_size: 40,
+            ..LoadGeneratorConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn load_generator_readers_follow_stream() {
+        for burst_pattern in [
+            BurstPattern::Unthrottled,
+            BurstPattern::Steady {
+                transactions_per_second: 50_000,
+            },
+            BurstPattern::Bursty {
+                batches_per_burst: 3,
+                idle_duration_ms: 5,
+            },
+        ] {
+            let load_generator = LoadGenerator::new(create_config(burst_pattern)).unwrap();
+            let report = load_generator
+                .run(InMemoryRedisConnection::new())
+                .await
+                .unwrap();
+
+            assert_eq!(report.num_transactions, 500);
+            assert_eq!(report.write_latency.count, 10);
+            assert_eq!(report.num_transactions_read, 3 * 500);
+            assert!(report.read_latency.count >= 3 * 500 / 40);
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_redis_rejects_latest_version_gap() {
+        let mut cache_operator = CacheOperator::new(
+            InMemoryRedisConnection::new(),
+            StorageFormat::Base64UncompressedProto,
+        );
+        assert!(cache_operator.cache_setup_if_needed().await.unwrap());
+        assert!(!cache_operator.cache_setup_if_needed().await.unwrap());
+
+        cache_operator
+            .update_cache_latest_version(10, 10)
+            .await
+            .unwrap();
+        assert_eq!(cache_operator.get_latest_version().await.unwrap(), Some(10));
+        assert!(cache_operator
+            .update_cache_latest_version(10, 30)
+            .await
+            .is_err());
+        assert_eq!(cache_operator.get_latest_version().await.unwrap(), Some(10));
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        let config = LoadGeneratorConfig {
+            transaction_size_distribution: TransactionSizeDistribution::Uniform {
+                min_size_in_bytes: 10,
+                max_size_in_bytes: 1,
+            },
+            ..LoadGeneratorConfig::default()
+        };
+        assert!(LoadGenerator::new(config).is_err());
+    }
+}
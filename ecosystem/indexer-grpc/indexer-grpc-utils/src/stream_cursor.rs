@@ -0,0 +1,318 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resumable stream cursors.
+//!
+//! A cursor is an opaque token encoding the next version to stream, the chain id and the stream
+//! epoch of the cache the stream was served from. The stream epoch changes whenever the cache is
+//! (re)initialized, so a client reconnecting with a cursor can be told if the cache it was
+//! streaming from has been truncated in the meantime, instead of silently skipping data.
+
+use crate::cache_operator::CACHE_SIZE_ESTIMATION;
+use aptos_protos::indexer::v1::TransactionsResponse;
+use std::fmt;
+
+/// The first (and currently only) version of the cursor encoding.
+const STREAM_CURSOR_ENCODING_VERSION: u8 = 1;
+
+/// `encoding version || next version (LE) || chain id (LE) || stream epoch (LE)`
+const STREAM_CURSOR_NUM_BYTES: usize = 1 + 3 * 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamCursor {
+    /// The next version to stream.
+    pub version: u64,
+    pub chain_id: u64,
+    pub stream_epoch: u64,
+}
+
+/// The reasons for rejecting a cursor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamCursorError {
+    /// The cursor could not be decoded.
+    Malformed,
+    /// The cursor was issued for a different chain.
+    ChainIdMismatch { cursor_chain_id: u64, chain_id: u64 },
+    /// The cache has been reinitialized since the cursor was issued.
+    StaleStreamEpoch {
+        cursor_stream_epoch: u64,
+        stream_epoch: u64,
+    },
+    /// The cursor is beyond the latest version in the cache, i.e., the cache lost versions that
+    /// were already streamed.
+    AheadOfCache {
+        cursor_version: u64,
+        cache_latest_version: u64,
+    },
+    /// The version of the cursor was evicted from the cache, and is not in the file store yet.
+    Truncated {
+        cursor_version: u64,
+        file_store_latest_version: u64,
+    },
+}
+
+impl StreamCursor {
+    pub fn new(version: u64, chain_id: u64, stream_epoch: u64) -> Self {
+        Self {
+            version,
+            chain_id,
+            stream_epoch,
+        }
+    }
+
+    /// Returns the cursor for resuming the stream after the given response, if it is not empty.
+    pub fn after_response(response: &TransactionsResponse, stream_epoch: u64) -> Option<Self> {
+        let last_transaction = response.transactions.last()?;
+        Some(Self::new(
+            last_transaction.version + 1,
+            response.chain_id?,
+            stream_epoch,
+        ))
+    }
+
+    /// Encodes the cursor as an opaque (URL-safe) token.
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(STREAM_CURSOR_NUM_BYTES);
+        bytes.push(STREAM_CURSOR_ENCODING_VERSION);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&self.chain_id.to_le_bytes());
+        bytes.extend_from_slice(&self.stream_epoch.to_le_bytes());
+        base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    pub fn decode(token: &str) -> Result<Self, StreamCursorError> {
+        let bytes = base64::decode_config(token, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| StreamCursorError::Malformed)?;
+        if bytes.len() != STREAM_CURSOR_NUM_BYTES || bytes[0] != STREAM_CURSOR_ENCODING_VERSION {
+            return Err(StreamCursorError::Malformed);
+        }
+
+        let read_u64 =
+            |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        Ok(Self::new(read_u64(1), read_u64(9), read_u64(17)))
+    }
+
+    /// Checks that the stream can be resumed from the cursor, given the current state of the cache
+    /// and the file store. Note: the cache latest version is exclusive.
+    pub fn validate(
+        &self,
+        chain_id: u64,
+        stream_epoch: u64,
+        cache_latest_version: u64,
+        file_store_latest_version: u64,
+    ) -> Result<(), StreamCursorError> {
+        if self.chain_id != chain_id {
+            return Err(StreamCursorError::ChainIdMismatch {
+                cursor_chain_id: self.chain_id,
+                chain_id,
+            });
+        }
+        if self.stream_epoch != stream_epoch {
+            return Err(StreamCursorError::StaleStreamEpoch {
+                cursor_stream_epoch: self.stream_epoch,
+                stream_epoch,
+            });
+        }
+        if self.version > cache_latest_version {
+            return Err(StreamCursorError::AheadOfCache {
+                cursor_version: self.version,
+                cache_latest_version,
+            });
+        }
+        let evicted_from_cache = self.version + CACHE_SIZE_ESTIMATION < cache_latest_version;
+        if evicted_from_cache && self.version >= file_store_latest_version {
+            return Err(StreamCursorError::Truncated {
+                cursor_version: self.version,
+                file_store_latest_version,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl StreamCursorError {
+    /// A stable identifier of the error, e.g., to be returned in the response metadata.
+    pub fn code(&self) -> &'static str {
+        match self {
+            StreamCursorError::Malformed => "malformed",
+            StreamCursorError::ChainIdMismatch { .. } => "chain_id_mismatch",
+            StreamCursorError::StaleStreamEpoch { .. } => "stale_stream_epoch",
+            StreamCursorError::AheadOfCache { .. } => "ahead_of_cache",
+            StreamCursorError::Truncated { .. } => "truncated",
+        }
+    }
+}
+
+impl fmt::Display for StreamCursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamCursorError::Malformed => write!(f, "Malformed stream cursor"),
+            StreamCursorError::ChainIdMismatch {
+                cursor_chain_id,
+                chain_id,
+            } => write!(
+                f,
+                "Stream cursor is for chain id {}, but the chain id is {}",
+                cursor_chain_id, chain_id
+            ),
+            StreamCursorError::StaleStreamEpoch {
+                cursor_stream_epoch,
+                stream_epoch,
+            } => write!(
+                f,
+                "Stream cursor is from stream epoch {}, but the cache was reinitialized at \
+                 stream epoch {}",
+                cursor_stream_epoch, stream_epoch
+            ),
+            StreamCursorError::AheadOfCache {
+                cursor_version,
+                cache_latest_version,
+            } => write!(
+                f,
+                "Stream cursor version {} is ahead of the cache latest version {}",
+                cursor_version, cache_latest_version
+            ),
+            StreamCursorError::Truncated {
+                cursor_version,
+                file_store_latest_version,
+            } => write!(
+                f,
+                "Stream cursor version {} was evicted from the cache, and the file store is only \
+                 at version {}",
+                cursor_version, file_store_latest_version
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StreamCursorError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::Transaction;
+
+    const CHAIN_ID: u64 = 4;
+    const STREAM_EPOCH: u64 = 1_700_000_000_000_000;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = StreamCursor::new(12_345, CHAIN_ID, STREAM_EPOCH);
+        let token = cursor.encode();
+        assert_eq!(StreamCursor::decode(&token), Ok(cursor));
+
+        // Tokens are opaque, but tampering with them is detected
+        assert_eq!(
+            StreamCursor::decode(&token[1..]),
+            Err(StreamCursorError::Malformed)
+        );
+        assert_eq!(
+            StreamCursor::decode("not a cursor"),
+            Err(StreamCursorError::Malformed)
+        );
+        let mut bytes = base64::decode_config(&token, base64::URL_SAFE_NO_PAD).unwrap();
+        bytes[0] = STREAM_CURSOR_ENCODING_VERSION + 1;
+        assert_eq!(
+            StreamCursor::decode(&base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)),
+            Err(StreamCursorError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_cursor_after_response() {
+        let response = TransactionsResponse {
+            transactions: (10..20)
+                .map(|version| Transaction {
+                    version,
+                    ..Default::default()
+                })
+                .collect(),
+            chain_id: Some(CHAIN_ID),
+        };
+        assert_eq!(
+            StreamCursor::after_response(&response, STREAM_EPOCH),
+            Some(StreamCursor::new(20, CHAIN_ID, STREAM_EPOCH))
+        );
+        assert_eq!(
+            StreamCursor::after_response(&TransactionsResponse::default(), STREAM_EPOCH),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cursor_validation() {
+        let cursor = StreamCursor::new(1_000, CHAIN_ID, STREAM_EPOCH);
+        assert_eq!(cursor.validate(CHAIN_ID, STREAM_EPOCH, 1_000, 0), Ok(()));
+        assert_eq!(
+            cursor.validate(CHAIN_ID + 1, STREAM_EPOCH, 1_000, 0),
+            Err(StreamCursorError::ChainIdMismatch {
+                cursor_chain_id: CHAIN_ID,
+                chain_id: CHAIN_ID + 1,
+            })
+        );
+        assert_eq!(
+            cursor.validate(CHAIN_ID, STREAM_EPOCH, 999, 0),
+            Err(StreamCursorError::AheadOfCache {
+                cursor_version: 1_000,
+                cache_latest_version: 999,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cache_reinitialized_after_cursor_was_issued() {
+        // The client streams up to version 1_999 and crashes
+        let cursor = StreamCursor::new(2_000, CHAIN_ID, STREAM_EPOCH);
+        assert_eq!(
+            cursor.validate(CHAIN_ID, STREAM_EPOCH, 2_000, 1_000),
+            Ok(())
+        );
+
+        // Meanwhile, the cache is flushed and reinitialized, so it gets a new stream epoch. Even
+        // once the cache has caught up again, the cursor is stale.
+        let new_stream_epoch = STREAM_EPOCH + 1;
+        for cache_latest_version in [0, 1_000, 2_000, 10_000] {
+            assert_eq!(
+                cursor.validate(CHAIN_ID, new_stream_epoch, cache_latest_version, 1_000),
+                Err(StreamCursorError::StaleStreamEpoch {
+                    cursor_stream_epoch: STREAM_EPOCH,
+                    stream_epoch: new_stream_epoch,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_cache_evicted_cursor_before_file_store_caught_up() {
+        let cursor = StreamCursor::new(2_000, CHAIN_ID, STREAM_EPOCH);
+
+        // The cache moves on while the client is disconnected, and evicts the cursor version
+        // before the file store has uploaded it.
+        let cache_latest_version = 2_000 + CACHE_SIZE_ESTIMATION + 1;
+        for file_store_latest_version in [0, 1_000, 2_000] {
+            assert_eq!(
+                cursor.validate(
+                    CHAIN_ID,
+                    STREAM_EPOCH,
+                    cache_latest_version,
+                    file_store_latest_version
+                ),
+                Err(StreamCursorError::Truncated {
+                    cursor_version: 2_000,
+                    file_store_latest_version,
+                })
+            );
+        }
+
+        // Once the file store has caught up, the stream can be resumed from the file store
+        assert_eq!(
+            cursor.validate(CHAIN_ID, STREAM_EPOCH, cache_latest_version, 2_001),
+            Ok(())
+        );
+        // At the eviction boundary, the cursor version is still in the cache
+        assert_eq!(
+            cursor.validate(CHAIN_ID, STREAM_EPOCH, 2_000 + CACHE_SIZE_ESTIMATION, 0),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Server-side transaction filters, so that clients that are only interested in a subset of the
+//! transactions (e.g., the events of a few modules) do not have to receive the whole stream.
+//!
+//! A filter is a disjunction (`|`) of conjunctions (`&`) of predicates, e.g.,
+//! `success=true & entry_function=0x1::coin::transfer | event_type=0x1::coin`.
+//! The supported predicates are:
+//!  - `success=<true|false>`: whether the transaction was executed successfully.
+//!  - `entry_function=<address>::<module>[::<function>]`: the entry function called by a user
+//!    transaction (directly, or via a multisig payload).
+//!  - `event_type=<address>::<module>[::<struct>]`: the transaction emitted an event of the given
+//!    type. Generic type parameters are ignored.
+//!  - `account=<address>`: the sender of a user transaction.
+//!
+//! Addresses may be given in the short (e.g., `0x1`) or the long form.
+
+use anyhow::{bail, ensure, Result};
+use aptos_protos::transaction::v1::{
+    multisig_transaction_payload, transaction::TxnData, transaction_payload, EntryFunctionId,
+    Event, Transaction, TransactionPayload,
+};
+use std::{fmt, str::FromStr};
+
+/// The maximum number of predicates in a filter, to bound the cost of evaluating it.
+pub const MAX_TRANSACTION_FILTER_PREDICATES: usize = 64;
+
+const OR_SEPARATOR: char = '|';
+const AND_SEPARATOR: char = '&';
+const MOVE_ID_SEPARATOR: &str = "::";
+
+/// A filter over the transactions of a stream. See the module documentation for the syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionFilter {
+    // A transaction matches if all predicates of any of the clauses match.
+    clauses: Vec<Vec<TransactionPredicate>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionPredicate {
+    Success(bool),
+    EntryFunction(MoveIdFilter),
+    EventType(MoveIdFilter),
+    Account(String),
+}
+
+/// Matches Move identifiers of the form `<address>::<module>::<name>`, either exactly or by module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoveIdFilter {
+    address: String,
+    module: String,
+    name: Option<String>,
+}
+
+impl TransactionFilter {
+    /// Returns true iff the transaction matches the filter.
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        self.clauses.iter().any(|clause| {
+            clause
+                .iter()
+                .all(|predicate| predicate.matches(transaction))
+        })
+    }
+
+    /// Drops the transactions that do not match the filter.
+    pub fn filter(&self, mut transactions: Vec<Transaction>) -> Vec<Transaction> {
+        transactions.retain(|transaction| self.matches(transaction));
+        transactions
+    }
+}
+
+impl FromStr for TransactionFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let clauses = s
+            .split(OR_SEPARATOR)
+            .map(|clause| {
+                clause
+                    .split(AND_SEPARATOR)
+                    .map(TransactionPredicate::from_str)
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let num_predicates = clauses.iter().map(Vec::len).sum::<usize>();
+        ensure!(
+            num_predicates <= MAX_TRANSACTION_FILTER_PREDICATES,
+            "Transaction filter has {} predicates, but at most {} are allowed",
+            num_predicates,
+            MAX_TRANSACTION_FILTER_PREDICATES
+        );
+        Ok(Self { clauses })
+    }
+}
+
+impl fmt::Display for TransactionFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let clauses = self
+            .clauses
+            .iter()
+            .map(|clause| {
+                clause
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" & ")
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", clauses.join(" | "))
+    }
+}
+
+impl TransactionPredicate {
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        match self {
+            TransactionPredicate::Success(success) => transaction
+                .info
+                .as_ref()
+                .map_or(false, |info| info.success == *success),
+            TransactionPredicate::EntryFunction(filter) => get_entry_function(transaction)
+                .and_then(|function| {
+                    let module = function.module.as_ref()?;
+                    Some(filter.matches(&module.address, &module.name, &function.name))
+                })
+                .unwrap_or(false),
+            TransactionPredicate::EventType(filter) => get_events(transaction)
+                .iter()
+                .any(|event| event_type_matches(filter, event)),
+            TransactionPredicate::Account(address) => match &transaction.txn_data {
+                Some(TxnData::User(user_transaction)) => {
+                    user_transaction.request.as_ref().map_or(false, |request| {
+                        normalize_address(&request.sender) == *address
+                    })
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+impl FromStr for TransactionPredicate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((key, value)) = s.split_once('=') else {
+            bail!("Invalid predicate '{}', expected '<key>=<value>'", s.trim());
+        };
+        let value = value.trim();
+        match key.trim() {
+            "success" => match value {
+                "true" => Ok(TransactionPredicate::Success(true)),
+                "false" => Ok(TransactionPredicate::Success(false)),
+                _ => bail!("Invalid success value '{}', expected true or false", value),
+            },
+            "entry_function" => Ok(TransactionPredicate::EntryFunction(value.parse()?)),
+            "event_type" => Ok(TransactionPredicate::EventType(value.parse()?)),
+            "account" => Ok(TransactionPredicate::Account(parse_address(value)?)),
+            key => bail!("Unknown predicate '{}'", key),
+        }
+    }
+}
+
+impl fmt::Display for TransactionPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionPredicate::Success(success) => write!(f, "success={}", success),
+            TransactionPredicate::EntryFunction(filter) => write!(f, "entry_function={}", filter),
+            TransactionPredicate::EventType(filter) => write!(f, "event_type={}", filter),
+            TransactionPredicate::Account(address) => write!(f, "account={}", address),
+        }
+    }
+}
+
+impl MoveIdFilter {
+    pub fn matches(&self, address: &str, module: &str, name: &str) -> bool {
+        self.module == module
+            && self
+                .name
+                .as_deref()
+                .map_or(true, |expected| expected == name)
+            && self.address == normalize_address(address)
+    }
+}
+
+impl FromStr for MoveIdFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts = s.split(MOVE_ID_SEPARATOR).collect::<Vec<_>>();
+        let (address, module, name) = match parts.as_slice() {
+            [address, module] => (address, module, None),
+            [address, module, name] => (address, module, Some(name.to_string())),
+            _ => bail!(
+                "Invalid Move identifier '{}', expected '<address>::<module>[::<name>]'",
+                s
+            ),
+        };
+        ensure!(
+            is_identifier(module) && name.as_deref().map_or(true, is_identifier),
+            "Invalid Move identifier '{}'",
+            s
+        );
+
+        Ok(Self {
+            address: parse_address(address)?,
+            module: module.to_string(),
+            name,
+        })
+    }
+}
+
+impl fmt::Display for MoveIdFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.address, self.module)?;
+        if let Some(name) = &self.name {
+            write!(f, "::{}", name)?;
+        }
+        Ok(())
+    }
+}
+
+fn get_entry_function(transaction: &Transaction) -> Option<&EntryFunctionId> {
+    let Some(TxnData::User(user_transaction)) = &transaction.txn_data else {
+        return None;
+    };
+    let payload = user_transaction.request.as_ref()?.payload.as_ref()?;
+    match payload {
+        TransactionPayload {
+            payload: Some(transaction_payload::Payload::EntryFunctionPayload(payload)),
+            ..
+        } => payload.function.as_ref(),
+        TransactionPayload {
+            payload: Some(transaction_payload::Payload::MultisigPayload(payload)),
+            ..
+        } => match payload.transaction_payload.as_ref()?.payload.as_ref()? {
+            multisig_transaction_payload::Payload::EntryFunctionPayload(payload) => {
+                payload.function.as_ref()
+            },
+        },
+        _ => None,
+    }
+}
+
+fn get_events(transaction: &Transaction) -> &[Event] {
+    match &transaction.txn_data {
+        Some(TxnData::User(user_transaction)) => &user_transaction.events,
+        Some(TxnData::BlockMetadata(block_metadata)) => &block_metadata.events,
+        Some(TxnData::Genesis(genesis)) => &genesis.events,
+        _ => &[],
+    }
+}
+
+fn event_type_matches(filter: &MoveIdFilter, event: &Event) -> bool {
+    // Strip the generic type parameters, e.g., 0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>
+    let struct_tag = event
+        .type_str
+        .split_once('<')
+        .map_or(event.type_str.as_str(), |(struct_tag, _)| struct_tag);
+    match struct_tag
+        .split(MOVE_ID_SEPARATOR)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [address, module, name] => filter.matches(address, module, name),
+        _ => false,
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parses a hex address (with the 0x prefix), in its normalized form.
+fn parse_address(address: &str) -> Result<String> {
+    let hex = address.strip_prefix("0x").unwrap_or_default();
+    ensure!(
+        !hex.is_empty() && hex.len() <= 64 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        "Invalid address '{}', expected a hex string starting with 0x",
+        address
+    );
+    Ok(normalize_address(address))
+}
+
+/// Normalizes a hex address to its short form (lower case, without leading zeros), e.g., 0x1.
+fn normalize_address(address: &str) -> String {
+    let hex = address.strip_prefix("0x").unwrap_or(address);
+    let hex = hex.trim_start_matches('0');
+    if hex.is_empty() {
+        "0x0".to_string()
+    } else {
+        format!("0x{}", hex.to_ascii_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::{
+        EntryFunctionPayload, MoveModuleId, TransactionInfo, UserTransaction,
+        UserTransactionRequest,
+    };
+
+    fn user_transaction(
+        sender: &str,
+        entry_function: (&str, &str, &str),
+        event_types: &[&str],
+        success: bool,
+    ) -> Transaction {
+        let (address, module, name) = entry_function;
+        Transaction {
+            version: 1,
+            info: Some(TransactionInfo {
+                success,
+                ..Default::default()
+            }),
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    sender: sender.to_string(),
+                    payload: Some(TransactionPayload {
+                        payload: Some(transaction_payload::Payload::EntryFunctionPayload(
+                            EntryFunctionPayload {
+                                function: Some(EntryFunctionId {
+                                    module: Some(MoveModuleId {
+                                        address: address.to_string(),
+                                        name: module.to_string(),
+                                    }),
+                                    name: name.to_string(),
+                                }),
+                                ..Default::default()
+                            },
+                        )),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                events: event_types
+                    .iter()
+                    .map(|type_str| Event {
+                        type_str: type_str.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let filter: TransactionFilter =
+            "success=true & entry_function=0x01::coin::transfer | event_type = 0x1::coin"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            filter.to_string(),
+            "success=true & entry_function=0x1::coin::transfer | event_type=0x1::coin"
+        );
+        // The normalized form can be parsed again
+        assert_eq!(
+            filter.to_string().parse::<TransactionFilter>().unwrap(),
+            filter
+        );
+
+        for invalid_filter in [
+            "",
+            "success",
+            "success=maybe",
+            "sender=0x1",
+            "account=1",
+            "account=0xg",
+            "entry_function=0x1",
+            "entry_function=0x1::coin::transfer::extra",
+            "event_type=0x1::1coin",
+            "success=true &",
+            "success=true | | success=false",
+        ] {
+            assert!(
+                invalid_filter.parse::<TransactionFilter>().is_err(),
+                "{} should be rejected",
+                invalid_filter
+            );
+        }
+
+        let too_many_predicates = vec!["success=true"; MAX_TRANSACTION_FILTER_PREDICATES + 1];
+        assert!(too_many_predicates
+            .join("|")
+            .parse::<TransactionFilter>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_filter_transactions() {
+        let transfer = user_transaction(
+            "0x00ab",
+            ("0x1", "coin", "transfer"),
+            &["0x1::coin::WithdrawEvent", "0x1::coin::DepositEvent"],
+            true,
+        );
+        let failed_mint = user_transaction(
+            "0xcd",
+            ("0xcafe", "nft", "mint"),
+            &["0x000cafe::nft::MintEvent<0x1::aptos_coin::AptosCoin>"],
+            false,
+        );
+        let state_checkpoint = Transaction {
+            version: 2,
+            ..Default::default()
+        };
+        let transactions = vec![transfer.clone(), failed_mint.clone(), state_checkpoint];
+
+        let filter = |s: &str| {
+            s.parse::<TransactionFilter>()
+                .unwrap()
+                .filter(transactions.clone())
+        };
+        assert_eq!(filter("success=true"), vec![transfer.clone()]);
+        assert_eq!(filter("success=false"), vec![failed_mint.clone()]);
+        assert_eq!(filter("account=0xab"), vec![transfer.clone()]);
+        assert_eq!(filter("entry_function=0x1::coin"), vec![transfer.clone()]);
+        assert_eq!(filter("entry_function=0x1::coin::mint"), vec![]);
+        assert_eq!(filter("event_type=0x1::coin::DepositEvent"), vec![
+            transfer.clone()
+        ]);
+        // Generic type parameters are ignored, and addresses are normalized
+        assert_eq!(filter("event_type=0xCAFE::nft::MintEvent"), vec![
+            failed_mint.clone()
+        ]);
+        assert_eq!(filter("event_type=0x1::aptos_coin"), vec![]);
+        assert_eq!(filter("success=true & account=0xcd"), vec![]);
+        assert_eq!(filter("entry_function=0xcafe::nft | account=0xab"), vec![
+            transfer,
+            failed_mint
+        ]);
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Server-side transaction projections, so that clients that do not need some (potentially large)
+//! fields of the transactions do not have to receive them. The fields are stripped before the
+//! transactions are compressed and sent, which saves both bandwidth and client-side decoding.
+//!
+//! A projection is a comma-separated list of the fields to exclude, e.g., `changes,event_data`.
+//! The supported fields are:
+//!  - `changes`: the write set changes in the transaction info.
+//!  - `events`: the events emitted by the transaction.
+//!  - `event_data`: the (JSON) data of the events. The other event fields (e.g., the type) are
+//!    kept.
+//!  - `payload`: the payload of user transactions, and the write set of genesis transactions.
+//!  - `signature`: the signature of user transactions.
+
+use anyhow::{bail, Result};
+use aptos_protos::transaction::v1::{transaction::TxnData, Event, Transaction};
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+const FIELD_SEPARATOR: char = ',';
+
+/// A projection over the transactions of a stream. See the module documentation for the syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionProjection {
+    excluded_fields: BTreeSet<ProjectionField>,
+}
+
+/// A transaction field that can be excluded by a projection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProjectionField {
+    Changes,
+    Events,
+    EventData,
+    Payload,
+    Signature,
+}
+
+impl TransactionProjection {
+    /// Returns true iff the given field is excluded by the projection.
+    pub fn excludes(&self, field: ProjectionField) -> bool {
+        self.excluded_fields.contains(&field)
+    }
+
+    /// Strips the excluded fields from the transaction.
+    pub fn apply(&self, transaction: &mut Transaction) {
+        if self.excludes(ProjectionField::Changes) {
+            if let Some(info) = transaction.info.as_mut() {
+                info.changes.clear();
+            }
+        }
+
+        if let Some(events) = get_events_mut(transaction) {
+            if self.excludes(ProjectionField::Events) {
+                events.clear();
+            } else if self.excludes(ProjectionField::EventData) {
+                for event in events.iter_mut() {
+                    event.data.clear();
+                }
+            }
+        }
+
+        match transaction.txn_data.as_mut() {
+            Some(TxnData::User(user_transaction)) => {
+                if let Some(request) = user_transaction.request.as_mut() {
+                    if self.excludes(ProjectionField::Payload) {
+                        request.payload = None;
+                    }
+                    if self.excludes(ProjectionField::Signature) {
+                        request.signature = None;
+                    }
+                }
+            },
+            Some(TxnData::Genesis(genesis)) => {
+                if self.excludes(ProjectionField::Payload) {
+                    genesis.payload = None;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Strips the excluded fields from all the transactions.
+    pub fn project(&self, mut transactions: Vec<Transaction>) -> Vec<Transaction> {
+        for transaction in transactions.iter_mut() {
+            self.apply(transaction);
+        }
+        transactions
+    }
+}
+
+impl FromStr for TransactionProjection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let excluded_fields = s
+            .split(FIELD_SEPARATOR)
+            .map(ProjectionField::from_str)
+            .collect::<Result<BTreeSet<_>>>()?;
+        Ok(Self { excluded_fields })
+    }
+}
+
+impl fmt::Display for TransactionProjection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = self
+            .excluded_fields
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}", fields.join(","))
+    }
+}
+
+impl ProjectionField {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            ProjectionField::Changes => "changes",
+            ProjectionField::Events => "events",
+            ProjectionField::EventData => "event_data",
+            ProjectionField::Payload => "payload",
+            ProjectionField::Signature => "signature",
+        }
+    }
+}
+
+impl FromStr for ProjectionField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "changes" => Ok(ProjectionField::Changes),
+            "events" => Ok(ProjectionField::Events),
+            "event_data" => Ok(ProjectionField::EventData),
+            "payload" => Ok(ProjectionField::Payload),
+            "signature" => Ok(ProjectionField::Signature),
+            field => bail!("Unknown projection field '{}'", field),
+        }
+    }
+}
+
+impl fmt::Display for ProjectionField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+fn get_events_mut(transaction: &mut Transaction) -> Option<&mut Vec<Event>> {
+    match transaction.txn_data.as_mut()? {
+        TxnData::User(user_transaction) => Some(&mut user_transaction.events),
+        TxnData::BlockMetadata(block_metadata) => Some(&mut block_metadata.events),
+        TxnData::Genesis(genesis) => Some(&mut genesis.events),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::{
+        Signature, TransactionInfo, TransactionPayload, UserTransaction, UserTransactionRequest,
+        WriteSetChange,
+    };
+
+    fn user_transaction() -> Transaction {
+        Transaction {
+            version: 1,
+            info: Some(TransactionInfo {
+                success: true,
+                changes: vec![WriteSetChange::default(); 2],
+                ..Default::default()
+            }),
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    sender: "0x1".to_string(),
+                    payload: Some(TransactionPayload::default()),
+                    signature: Some(Signature::default()),
+                    ..Default::default()
+                }),
+                events: vec![Event {
+                    type_str: "0x1::coin::DepositEvent".to_string(),
+                    data: "{\"amount\":\"100\"}".to_string(),
+                    ..Default::default()
+                }],
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn get_request(transaction: &Transaction) -> &UserTransactionRequest {
+        match &transaction.txn_data {
+            Some(TxnData::User(user_transaction)) => user_transaction.request.as_ref().unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn get_events(transaction: &Transaction) -> &[Event] {
+        match &transaction.txn_data {
+            Some(TxnData::User(user_transaction)) => &user_transaction.events,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_projection() {
+        let projection: TransactionProjection =
+            "signature, event_data,changes,event_data".parse().unwrap();
+        assert_eq!(projection.to_string(), "changes,event_data,signature");
+        // The normalized form can be parsed again
+        assert_eq!(
+            projection
+                .to_string()
+                .parse::<TransactionProjection>()
+                .unwrap(),
+            projection
+        );
+        assert!(projection.excludes(ProjectionField::EventData));
+        assert!(!projection.excludes(ProjectionField::Payload));
+
+        for invalid_projection in ["", "changes,", "write_set", "changes;events"] {
+            assert!(
+                invalid_projection.parse::<TransactionProjection>().is_err(),
+                "{} should be rejected",
+                invalid_projection
+            );
+        }
+    }
+
+    #[test]
+    fn test_project_transactions() {
+        let transaction = user_transaction();
+        let state_checkpoint = Transaction {
+            version: 2,
+            ..Default::default()
+        };
+        let transactions = vec![transaction.clone(), state_checkpoint.clone()];
+        let project = |s: &str| {
+            s.parse::<TransactionProjection>()
+                .unwrap()
+                .project(transactions.clone())
+        };
+
+        let projected = project("changes,event_data");
+        assert!(projected[0].info.as_ref().unwrap().changes.is_empty());
+        assert!(projected[0].info.as_ref().unwrap().success);
+        let events = get_events(&projected[0]);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].data.is_empty());
+        assert_eq!(events[0].type_str, "0x1::coin::DepositEvent");
+        assert_eq!(get_request(&projected[0]), get_request(&transaction));
+        // Transactions without the excluded fields are left untouched
+        assert_eq!(projected[1], state_checkpoint);
+
+        let projected = project("events,payload,signature");
+        assert!(get_events(&projected[0]).is_empty());
+        let request = get_request(&projected[0]);
+        assert!(request.payload.is_none());
+        assert!(request.signature.is_none());
+        assert_eq!(request.sender, "0x1");
+        assert_eq!(projected[0].info, transaction.info);
+    }
+}
@@ -425,6 +425,7 @@ impl TransactionBlockExecutor for NativeExecutor {
         Ok(ChunkOutput {
             transactions: transactions.into_iter().map(|t| t.into_inner()).collect(),
             transaction_outputs,
+            output_hashes: None,
             state_cache: state_view.into_state_cache(),
         })
     }
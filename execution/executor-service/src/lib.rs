@@ -28,13 +28,14 @@ mod tests;
 #[cfg(test)]
 mod thread_executor_service;
 
+/// The output of a single sub-block, sent by a shard as soon as the sub-block is executed.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RemoteExecutionResult {
-    pub inner: Result<Vec<Vec<TransactionOutput>>, VMStatus>,
+    pub inner: Result<Vec<TransactionOutput>, VMStatus>,
 }
 
 impl RemoteExecutionResult {
-    pub fn new(inner: Result<Vec<Vec<TransactionOutput>>, VMStatus>) -> Self {
+    pub fn new(inner: Result<Vec<TransactionOutput>, VMStatus>) -> Self {
         Self { inner }
     }
 }
@@ -112,7 +112,7 @@ impl CoordinatorClient<RemoteStateViewClient> for RemoteCoordinatorClient {
         }
     }
 
-    fn send_execution_result(&self, result: Result<Vec<Vec<TransactionOutput>>, VMStatus>) {
+    fn send_sub_block_result(&self, result: Result<Vec<TransactionOutput>, VMStatus>) {
         let remote_execution_result = RemoteExecutionResult::new(result);
         let output_message = bcs::to_bytes(&remote_execution_result).unwrap();
         self.result_tx.send(Message::new(output_message)).unwrap();
@@ -161,36 +161,22 @@ impl<S: StateView + Sync + Send + 'static> RemoteExecutorClient<S> {
         ))
     }
 
-    fn get_output_from_shards(&self) -> Result<Vec<Vec<Vec<TransactionOutput>>>, VMStatus> {
-        trace!("RemoteExecutorClient Waiting for results");
-        let mut results = vec![];
-        for rx in self.result_rxs.iter() {
-            let received_bytes = rx.recv().unwrap().to_bytes();
-            let result: RemoteExecutionResult = bcs::from_bytes(&received_bytes).unwrap();
-            results.push(result.inner?);
-        }
-        Ok(results)
-    }
-}
-
-impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for RemoteExecutorClient<S> {
-    fn num_shards(&self) -> usize {
-        self.command_txs.len()
-    }
-
-    fn execute_block(
+    // Sends the sub-blocks to the shards, and returns the number of rounds (i.e., the number of
+    // sub-block outputs each shard sends back).
+    fn send_execute_commands(
         &self,
         state_view: Arc<S>,
         transactions: PartitionedTransactions,
         concurrency_level_per_shard: usize,
         onchain_config: BlockExecutorConfigFromOnchain,
-    ) -> Result<ShardedExecutionOutput, VMStatus> {
+    ) -> usize {
         trace!("RemoteExecutorClient Sending block to shards");
         self.state_view_service.set_state_view(state_view);
         let (sub_blocks, global_txns) = transactions.into();
         if !global_txns.is_empty() {
             panic!("Global transactions are not supported yet");
         }
+        let num_rounds = sub_blocks[0].num_sub_blocks();
         for (shard_id, sub_blocks) in sub_blocks.into_iter().enumerate() {
             let senders = self.command_txs.clone();
             let execution_request = RemoteExecutionRequest::ExecuteBlock(ExecuteBlockCommand {
@@ -205,13 +191,89 @@ impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for RemoteExecutorC
                 .send(Message::new(bcs::to_bytes(&execution_request).unwrap()))
                 .unwrap();
         }
+        num_rounds
+    }
+
+    fn receive_sub_block_output(
+        &self,
+        shard_id: usize,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let received_bytes = self.result_rxs[shard_id].recv().unwrap().to_bytes();
+        let result: RemoteExecutionResult = bcs::from_bytes(&received_bytes).unwrap();
+        result.inner
+    }
+
+    fn get_output_from_shards(
+        &self,
+        num_rounds: usize,
+    ) -> Result<Vec<Vec<Vec<TransactionOutput>>>, VMStatus> {
+        trace!("RemoteExecutorClient Waiting for results");
+        let mut results = vec![];
+        for shard_id in 0..self.num_shards() {
+            let mut results_from_shard = vec![];
+            for _ in 0..num_rounds {
+                results_from_shard.push(self.receive_sub_block_output(shard_id)?);
+            }
+            results.push(results_from_shard);
+        }
+        Ok(results)
+    }
+}
 
-        let execution_results = self.get_output_from_shards()?;
+impl<S: StateView + Sync + Send + 'static> ExecutorClient<S> for RemoteExecutorClient<S> {
+    fn num_shards(&self) -> usize {
+        self.command_txs.len()
+    }
+
+    fn execute_block(
+        &self,
+        state_view: Arc<S>,
+        transactions: PartitionedTransactions,
+        concurrency_level_per_shard: usize,
+        onchain_config: BlockExecutorConfigFromOnchain,
+    ) -> Result<ShardedExecutionOutput, VMStatus> {
+        let num_rounds = self.send_execute_commands(
+            state_view,
+            transactions,
+            concurrency_level_per_shard,
+            onchain_config,
+        );
+
+        let execution_results = self.get_output_from_shards(num_rounds)?;
 
         self.state_view_service.drop_state_view();
         Ok(ShardedExecutionOutput::new(execution_results, vec![]))
     }
 
+    fn execute_block_streaming(
+        &self,
+        state_view: Arc<S>,
+        transactions: PartitionedTransactions,
+        concurrency_level_per_shard: usize,
+        onchain_config: BlockExecutorConfigFromOnchain,
+        output_sink: &mut dyn FnMut(Vec<TransactionOutput>),
+    ) -> Result<(), VMStatus> {
+        let num_rounds = self.send_execute_commands(
+            state_view,
+            transactions,
+            concurrency_level_per_shard,
+            onchain_config,
+        );
+
+        // The shards send the output of each sub-block as soon as it is executed, so a chunk
+        // only waits for the preceding chunks (in the round order, and in the shard order within
+        // a round). There are no global transactions, i.e., no global output to stream.
+        trace!("RemoteExecutorClient Streaming results");
+        for _ in 0..num_rounds {
+            for shard_id in 0..self.num_shards() {
+                output_sink(self.receive_sub_block_output(shard_id)?);
+            }
+        }
+
+        self.state_view_service.drop_state_view();
+        Ok(())
+    }
+
     fn shutdown(&mut self) {
         self.network_controller.shutdown();
     }
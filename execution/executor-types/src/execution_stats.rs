@@ -0,0 +1,132 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_crypto::HashValue;
+use aptos_types::transaction::{TransactionInfo, TransactionStatus};
+use serde::{Deserialize, Serialize};
+
+/// A compact digest of the (deterministic) execution statistics of a block. All validators that
+/// execute the same block must compute the same digest, so the digests can be compared across
+/// validators (off-band) to detect non-deterministic execution, e.g., in custom deployments.
+///
+/// Only statistics that are deterministic are covered. Statistics that depend on the execution
+/// schedule (e.g., the number of speculative aborts during parallel execution) are excluded.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ExecutionStatsDigest {
+    /// The number of input transactions (including the BlockMetadata and validator txns)
+    pub num_input_txns: usize,
+    /// The number of input transactions that are kept, discarded and retried (respectively)
+    pub num_kept_txns: usize,
+    pub num_discarded_txns: usize,
+    pub num_retried_txns: usize,
+    /// The total gas used by the transactions to commit
+    pub total_gas_used: u64,
+    /// The hash of the statuses of the input transactions and the gas used by each
+    /// transaction to commit (in order)
+    pub digest: HashValue,
+}
+
+/// The statistics covered by the digest (in the order they are hashed)
+#[derive(Serialize)]
+struct DigestedExecutionStats<'a> {
+    statuses_for_input_txns: &'a [TransactionStatus],
+    gas_used_per_txn: Vec<u64>,
+}
+
+impl ExecutionStatsDigest {
+    /// Computes the digest from the statuses of the input transactions, and the
+    /// transaction infos of the transactions to commit.
+    pub fn new<'a>(
+        statuses_for_input_txns: &[TransactionStatus],
+        transaction_infos: impl Iterator<Item = &'a TransactionInfo>,
+    ) -> Self {
+        let gas_used_per_txn: Vec<u64> = transaction_infos.map(|info| info.gas_used()).collect();
+        let total_gas_used = gas_used_per_txn.iter().sum();
+
+        let (mut num_kept_txns, mut num_discarded_txns, mut num_retried_txns) = (0, 0, 0);
+        for status in statuses_for_input_txns {
+            match status {
+                TransactionStatus::Keep(_) => num_kept_txns += 1,
+                TransactionStatus::Discard(_) => num_discarded_txns += 1,
+                TransactionStatus::Retry => num_retried_txns += 1,
+            }
+        }
+
+        let stats = DigestedExecutionStats {
+            statuses_for_input_txns,
+            gas_used_per_txn,
+        };
+        let digest = HashValue::sha3_256_of(
+            &bcs::to_bytes(&stats).expect("Execution stats serialization must succeed"),
+        );
+
+        Self {
+            num_input_txns: statuses_for_input_txns.len(),
+            num_kept_txns,
+            num_discarded_txns,
+            num_retried_txns,
+            total_gas_used,
+            digest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::{transaction::ExecutionStatus, vm_status::StatusCode};
+
+    fn transaction_info(gas_used: u64) -> TransactionInfo {
+        TransactionInfo::new(
+            HashValue::zero(),
+            HashValue::zero(),
+            HashValue::zero(),
+            None,
+            gas_used,
+            ExecutionStatus::Success,
+        )
+    }
+
+    #[test]
+    fn test_execution_stats_digest() {
+        let statuses = vec![
+            TransactionStatus::Keep(ExecutionStatus::Success),
+            TransactionStatus::Discard(StatusCode::SEQUENCE_NUMBER_TOO_OLD),
+            TransactionStatus::Keep(ExecutionStatus::Success),
+            TransactionStatus::Retry,
+        ];
+        let infos = [
+            transaction_info(10),
+            transaction_info(20),
+            transaction_info(0),
+        ];
+        let digest = ExecutionStatsDigest::new(&statuses, infos.iter());
+        assert_eq!(digest.num_input_txns, 4);
+        assert_eq!(digest.num_kept_txns, 2);
+        assert_eq!(digest.num_discarded_txns, 1);
+        assert_eq!(digest.num_retried_txns, 1);
+        assert_eq!(digest.total_gas_used, 30);
+
+        // The digest is deterministic
+        assert_eq!(digest, ExecutionStatsDigest::new(&statuses, infos.iter()));
+
+        // The digest changes with the gas used by any transaction
+        let other_infos = [
+            transaction_info(10),
+            transaction_info(21),
+            transaction_info(0),
+        ];
+        assert_ne!(
+            digest.digest,
+            ExecutionStatsDigest::new(&statuses, other_infos.iter()).digest
+        );
+
+        // The digest changes with the statuses of the input transactions
+        let mut other_statuses = statuses.clone();
+        other_statuses[1] = TransactionStatus::Discard(StatusCode::SEQUENCE_NUMBER_TOO_NEW);
+        assert_ne!(
+            digest.digest,
+            ExecutionStatsDigest::new(&other_statuses, infos.iter()).digest
+        );
+    }
+}
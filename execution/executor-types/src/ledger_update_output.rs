@@ -3,7 +3,7 @@
 
 #![forbid(unsafe_code)]
 
-use crate::StateComputeResult;
+use crate::{ExecutionStatsDigest, StateComputeResult};
 use anyhow::{ensure, Result};
 use aptos_crypto::HashValue;
 use aptos_storage_interface::cached_state_view::ShardedStateCache;
@@ -176,6 +176,16 @@ impl LedgerUpdateOutput {
         )
     }
 
+    /// Computes the digest of the (deterministic) execution statistics of the block
+    pub fn execution_stats_digest(&self) -> ExecutionStatsDigest {
+        ExecutionStatsDigest::new(
+            &self.statuses_for_input_txns,
+            self.to_commit
+                .iter()
+                .map(|txn_to_commit| txn_to_commit.transaction_info()),
+        )
+    }
+
     pub fn combine(&mut self, rhs: Self) {
         let Self {
             statuses_for_input_txns,
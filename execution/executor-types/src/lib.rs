@@ -28,6 +28,7 @@ use aptos_types::{
 };
 pub use error::{ExecutorError, ExecutorResult};
 pub use executed_chunk::ExecutedChunk;
+pub use execution_stats::ExecutionStatsDigest;
 pub use ledger_update_output::LedgerUpdateOutput;
 pub use parsed_transaction_output::ParsedTransactionOutput;
 use serde::{Deserialize, Serialize};
@@ -45,6 +46,7 @@ use std::{
 mod error;
 mod executed_chunk;
 pub mod execution_output;
+mod execution_stats;
 mod ledger_update_output;
 pub mod parsed_transaction_output;
 pub mod state_checkpoint_output;
@@ -158,6 +160,13 @@ pub trait BlockExecutorTrait: Send + Sync {
         onchain_config: BlockExecutorConfigFromOnchain,
     ) -> ExecutorResult<StateCheckpointOutput>;
 
+    /// Reads the given state keys from storage (at the latest state checkpoint), so that the
+    /// storage caches are warm by the time the keys are read by execution. This is best-effort:
+    /// it doesn't affect the execution results. Returns the number of keys that were read.
+    fn prefetch_state_values(&self, _state_keys: Vec<StateKey>) -> ExecutorResult<usize> {
+        Ok(0)
+    }
+
     fn ledger_update(
         &self,
         block_id: HashValue,
@@ -329,6 +338,9 @@ pub struct StateComputeResult {
     transaction_info_hashes: Vec<HashValue>,
 
     subscribable_events: Vec<ContractEvent>,
+
+    /// The digest of the execution statistics of the block (only computed if enabled)
+    execution_stats_digest: Option<ExecutionStatsDigest>,
 }
 
 impl StateComputeResult {
@@ -353,6 +365,7 @@ impl StateComputeResult {
             compute_status_for_input_txns,
             transaction_info_hashes,
             subscribable_events,
+            execution_stats_digest: None,
         }
     }
 
@@ -370,6 +383,7 @@ impl StateComputeResult {
             compute_status_for_input_txns: vec![],
             transaction_info_hashes: vec![],
             subscribable_events: vec![],
+            execution_stats_digest: None,
         }
     }
 
@@ -387,6 +401,7 @@ impl StateComputeResult {
             ],
             transaction_info_hashes: vec![],
             subscribable_events: vec![],
+            execution_stats_digest: None,
         }
     }
 
@@ -506,6 +521,15 @@ impl StateComputeResult {
     pub fn subscribable_events(&self) -> &[ContractEvent] {
         &self.subscribable_events
     }
+
+    pub fn execution_stats_digest(&self) -> Option<&ExecutionStatsDigest> {
+        self.execution_stats_digest.as_ref()
+    }
+
+    pub fn with_execution_stats_digest(mut self, digest: ExecutionStatsDigest) -> Self {
+        self.execution_stats_digest = Some(digest);
+        self
+    }
 }
 
 pub struct ProofReader {
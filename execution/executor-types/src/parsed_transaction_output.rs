@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use aptos_crypto::HashValue;
 use aptos_types::{
     contract_event::ContractEvent,
     event::EventKey,
@@ -20,6 +21,9 @@ pub static NEW_EPOCH_EVENT_KEY: Lazy<EventKey> = Lazy::new(on_chain_config::new_
 pub struct ParsedTransactionOutput {
     output: TransactionOutput,
     reconfig_events: Vec<ContractEvent>,
+    // The event root hash and the write set hash of the output, if already calculated (e.g.,
+    // while the rest of the block was still being executed).
+    hashes: Option<(HashValue, HashValue)>,
 }
 
 impl ParsedTransactionOutput {
@@ -44,6 +48,7 @@ impl From<TransactionOutput> for ParsedTransactionOutput {
         Self {
             output,
             reconfig_events,
+            hashes: None,
         }
     }
 }
@@ -61,6 +66,16 @@ impl ParsedTransactionOutput {
         !self.reconfig_events.is_empty()
     }
 
+    pub fn with_hashes(mut self, event_root_hash: HashValue, write_set_hash: HashValue) -> Self {
+        self.hashes = Some((event_root_hash, write_set_hash));
+        self
+    }
+
+    /// Returns the event root hash and the write set hash, if they were already calculated.
+    pub fn hashes(&self) -> Option<(HashValue, HashValue)> {
+        self.hashes
+    }
+
     pub fn unpack(
         self,
     ) -> (
@@ -74,6 +89,7 @@ impl ParsedTransactionOutput {
         let Self {
             output,
             reconfig_events,
+            hashes: _,
         } = self;
         let (write_set, events, gas_used, status, auxiliary_data) = output.unpack();
 
@@ -20,7 +20,7 @@ use anyhow::Result;
 use aptos_crypto::HashValue;
 use aptos_executor_types::{
     execution_output::ExecutionOutput, state_checkpoint_output::StateCheckpointOutput,
-    BlockExecutorTrait, ExecutorError, ExecutorResult, StateComputeResult,
+    BlockExecutorTrait, ExecutorError, ExecutorResult, LedgerUpdateOutput, StateComputeResult,
 };
 use aptos_experimental_runtimes::thread_manager::THREAD_MANAGER;
 use aptos_infallible::RwLock;
@@ -34,11 +34,14 @@ use aptos_types::{
         config::BlockExecutorConfigFromOnchain,
         partitioner::{ExecutableBlock, ExecutableTransactions},
     },
+    epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
-    state_store::{state_value::StateValue, StateViewId},
+    proof::accumulator::InMemoryTransactionAccumulator,
+    state_store::{state_key::StateKey, state_value::StateValue, StateViewId},
 };
 use aptos_vm::AptosVM;
 use fail::fail_point;
+use rayon::prelude::*;
 use std::{marker::PhantomData, sync::Arc};
 
 pub trait TransactionBlockExecutor: Send + Sync {
@@ -123,6 +126,28 @@ where
             .execute_and_state_checkpoint(block, parent_block_id, onchain_config)
     }
 
+    fn prefetch_state_values(&self, state_keys: Vec<StateKey>) -> ExecutorResult<usize> {
+        let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
+            .with_label_values(&["prefetch_state_values"])
+            .start_timer();
+        let version = match self.db.reader.get_latest_state_checkpoint_version()? {
+            Some(version) => version,
+            None => return Ok(0), // There is no state to prefetch yet
+        };
+
+        // The values are dropped: reading them is enough to warm the storage caches
+        let num_keys = state_keys.len();
+        THREAD_MANAGER.get_io_pool().install(|| {
+            state_keys.par_iter().try_for_each(|state_key| {
+                self.db
+                    .reader
+                    .get_state_value_by_version(state_key, version)
+                    .map(|_| ())
+            })
+        })?;
+        Ok(num_keys)
+    }
+
     fn ledger_update(
         &self,
         block_id: HashValue,
@@ -177,6 +202,31 @@ where
     fn root_smt(&self) -> SparseMerkleTree<StateValue> {
         self.block_tree.root_block().output.state().current.clone()
     }
+
+    /// Returns the state compute result of the block, including the digest of the execution
+    /// stats (if enabled).
+    fn state_compute_result(
+        block_id: HashValue,
+        output: &LedgerUpdateOutput,
+        parent_accumulator: &Arc<InMemoryTransactionAccumulator>,
+        next_epoch_state: Option<EpochState>,
+    ) -> StateComputeResult {
+        let state_compute_result =
+            output.as_state_compute_result(parent_accumulator, next_epoch_state);
+        if !AptosVM::get_execution_stats_digest() {
+            return state_compute_result;
+        }
+
+        let execution_stats_digest = output.execution_stats_digest();
+        info!(
+            LogSchema::new(LogEntry::BlockExecutor).block_id(block_id),
+            num_input_txns = execution_stats_digest.num_input_txns,
+            total_gas_used = execution_stats_digest.total_gas_used,
+            digest = %execution_stats_digest.digest,
+            "execution_stats_digest"
+        );
+        state_compute_result.with_execution_stats_digest(execution_stats_digest)
+    }
 }
 
 impl<V> BlockExecutorInner<V>
@@ -291,13 +341,12 @@ where
         let current_output = block_vec.pop().expect("Must exist").unwrap();
         parent_block.ensure_has_child(block_id)?;
         if current_output.output.has_ledger_update() {
-            return Ok(current_output
-                .output
-                .get_ledger_update()
-                .as_state_compute_result(
-                    parent_accumulator,
-                    current_output.output.epoch_state().clone(),
-                ));
+            return Ok(Self::state_compute_result(
+                block_id,
+                current_output.output.get_ledger_update(),
+                parent_accumulator,
+                current_output.output.epoch_state().clone(),
+            ));
         }
 
         let output =
@@ -321,7 +370,9 @@ where
             output.ensure_ends_with_state_checkpoint()?;
         }
 
-        let state_compute_result = output.as_state_compute_result(
+        let state_compute_result = Self::state_compute_result(
+            block_id,
+            &output,
             parent_accumulator,
             current_output.output.epoch_state().clone(),
         );
@@ -50,6 +50,7 @@ impl ApplyChunkOutput {
             state_cache,
             transactions,
             transaction_outputs,
+            output_hashes,
         } = chunk_output;
         let (new_epoch, statuses_for_input_txns, to_commit, to_discard, to_retry) = {
             let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
@@ -60,6 +61,7 @@ impl ApplyChunkOutput {
             Self::sort_transactions_with_state_checkpoint(
                 transactions,
                 transaction_outputs,
+                output_hashes,
                 append_state_checkpoint_to_block,
             )?
         };
@@ -192,6 +194,7 @@ impl ApplyChunkOutput {
     fn sort_transactions_with_state_checkpoint(
         mut transactions: Vec<Transaction>,
         transaction_outputs: Vec<TransactionOutput>,
+        output_hashes: Option<Vec<(HashValue, HashValue)>>,
         append_state_checkpoint_to_block: Option<HashValue>,
     ) -> Result<(
         bool,
@@ -200,8 +203,15 @@ impl ApplyChunkOutput {
         TransactionsWithParsedOutput,
         TransactionsWithParsedOutput,
     )> {
-        let mut transaction_outputs: Vec<ParsedTransactionOutput> =
-            transaction_outputs.into_iter().map(Into::into).collect();
+        let mut transaction_outputs: Vec<ParsedTransactionOutput> = match output_hashes {
+            Some(output_hashes) => itertools::zip_eq(transaction_outputs, output_hashes)
+                .map(|(output, (event_root_hash, write_set_hash))| {
+                    ParsedTransactionOutput::from(output)
+                        .with_hashes(event_root_hash, write_set_hash)
+                })
+                .collect(),
+            None => transaction_outputs.into_iter().map(Into::into).collect(),
+        };
         // N.B. off-by-1 intentionally, for exclusive index
         let new_epoch_marker = transaction_outputs
             .iter()
@@ -304,15 +314,6 @@ impl ApplyChunkOutput {
         let mut txn_info_hashes = Vec::with_capacity(num_txns);
         let hashes_vec =
             Self::calculate_events_and_writeset_hashes(to_commit_from_execution.parsed_outputs());
-        let hashes_vec: Vec<(HashValue, HashValue)> = hashes_vec
-            .into_par_iter()
-            .map(|(event_hashes, write_set_hash)| {
-                (
-                    InMemoryEventAccumulator::from_leaves(&event_hashes).root_hash(),
-                    write_set_hash,
-                )
-            })
-            .collect();
 
         let mut all_subscribable_events = Vec::new();
         let (to_commit_txns, to_commit_outputs) = to_commit_from_execution.into_inner();
@@ -365,9 +366,11 @@ impl ApplyChunkOutput {
         (to_commit, txn_info_hashes, all_subscribable_events)
     }
 
+    // Returns the event root hash and the write set hash of each output, skipping the outputs
+    // with hashes calculated already (see `calculate_output_hashes`).
     fn calculate_events_and_writeset_hashes(
         to_commit_from_execution: &[ParsedTransactionOutput],
-    ) -> Vec<(Vec<HashValue>, HashValue)> {
+    ) -> Vec<(HashValue, HashValue)> {
         let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
             .with_label_values(&["calculate_events_and_writeset_hashes"])
             .start_timer();
@@ -376,17 +379,40 @@ impl ApplyChunkOutput {
             .par_iter()
             .with_min_len(optimal_min_len(num_txns, 64))
             .map(|txn_output| {
-                (
-                    txn_output
-                        .events()
-                        .iter()
-                        .map(CryptoHash::hash)
-                        .collect::<Vec<_>>(),
-                    CryptoHash::hash(txn_output.write_set()),
-                )
+                txn_output
+                    .hashes()
+                    .unwrap_or_else(|| Self::calculate_output_hash(txn_output))
             })
             .collect::<Vec<_>>()
     }
+
+    /// Calculates the event root hash and the write set hash of each output ahead of the ledger
+    /// update, e.g., for a chunk of the block output while the rest of the block is executing.
+    pub(crate) fn calculate_output_hashes(
+        transaction_outputs: &[TransactionOutput],
+    ) -> Vec<(HashValue, HashValue)> {
+        let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
+            .with_label_values(&["calculate_output_hashes"])
+            .start_timer();
+        let num_txns = transaction_outputs.len();
+        transaction_outputs
+            .par_iter()
+            .with_min_len(optimal_min_len(num_txns, 64))
+            .map(Self::calculate_output_hash)
+            .collect::<Vec<_>>()
+    }
+
+    fn calculate_output_hash(txn_output: &TransactionOutput) -> (HashValue, HashValue) {
+        let event_hashes = txn_output
+            .events()
+            .iter()
+            .map(CryptoHash::hash)
+            .collect::<Vec<_>>();
+        (
+            InMemoryEventAccumulator::from_leaves(&event_hashes).root_hash(),
+            CryptoHash::hash(txn_output.write_set()),
+        )
+    }
 }
 
 pub fn ensure_no_discard(to_discard: Vec<Transaction>) -> Result<()> {
@@ -441,3 +467,60 @@ fn assemble_ledger_diff_should_filter_subscribable_events() {
     );
     assert_eq!(vec![event_0, event_2], subscribable_events);
 }
+
+#[test]
+fn assemble_ledger_diff_should_use_calculated_output_hashes() {
+    let event = ContractEvent::new_v2_with_type_tag_str(
+        "0x2345::random_module::RandomEvent",
+        b"random_x".to_vec(),
+    );
+    let transaction_outputs = vec![
+        TransactionOutput::new(
+            WriteSet::default(),
+            vec![event.clone()],
+            0,
+            TransactionStatus::Keep(ExecutionStatus::Success),
+            TransactionAuxiliaryData::default(),
+        ),
+        TransactionOutput::new(
+            WriteSet::default(),
+            vec![event.clone(), event],
+            0,
+            TransactionStatus::Keep(ExecutionStatus::Success),
+            TransactionAuxiliaryData::default(),
+        ),
+    ];
+    let output_hashes = ApplyChunkOutput::calculate_output_hashes(&transaction_outputs);
+    let assemble_txn_info_hashes = |parsed_outputs: Vec<ParsedTransactionOutput>| {
+        let (_, txn_info_hashes, _) = ApplyChunkOutput::assemble_ledger_diff(
+            TransactionsWithParsedOutput::new(
+                vec![Transaction::dummy(), Transaction::dummy()],
+                parsed_outputs,
+            ),
+            vec![
+                ShardedStateUpdates::default(),
+                ShardedStateUpdates::default(),
+            ],
+            vec![Some(HashValue::zero()), Some(HashValue::zero())],
+        );
+        txn_info_hashes
+    };
+
+    // The ledger update yields the same transaction infos, whether the output hashes were
+    // calculated ahead or not
+    let expected_txn_info_hashes = assemble_txn_info_hashes(
+        transaction_outputs
+            .iter()
+            .cloned()
+            .map(ParsedTransactionOutput::from)
+            .collect(),
+    );
+    let txn_info_hashes = assemble_txn_info_hashes(
+        itertools::zip_eq(transaction_outputs, output_hashes)
+            .map(|(output, (event_root_hash, write_set_hash))| {
+                ParsedTransactionOutput::from(output).with_hashes(event_root_hash, write_set_hash)
+            })
+            .collect(),
+    );
+    assert_eq!(txn_info_hashes, expected_txn_info_hashes);
+}
@@ -42,6 +42,9 @@ pub struct ChunkOutput {
     pub transactions: Vec<Transaction>,
     /// Raw VM output.
     pub transaction_outputs: Vec<TransactionOutput>,
+    /// The event root hash and the write set hash of each output, if calculated during the
+    /// execution (i.e., for the chunks of a block streamed by the sharded executor).
+    pub output_hashes: Option<Vec<(HashValue, HashValue)>>,
     /// Carries the frozen base state view, so all in-mem nodes involved won't drop before the
     /// execution result is processed; as well as all the accounts touched during execution, together
     /// with their proofs.
@@ -76,6 +79,7 @@ impl ChunkOutput {
         Ok(Self {
             transactions: transactions.into_iter().map(|t| t.into_inner()).collect(),
             transaction_outputs,
+            output_hashes: None,
             state_cache: state_view.into_state_cache(),
         })
     }
@@ -86,10 +90,18 @@ impl ChunkOutput {
         onchain_config: BlockExecutorConfigFromOnchain,
     ) -> Result<Self> {
         let state_view_arc = Arc::new(state_view);
-        let transaction_outputs = Self::execute_block_sharded::<V>(
+        let mut transaction_outputs = vec![];
+        let mut output_hashes = vec![];
+        // The outputs are hashed for the ledger update as soon as they are streamed, while the
+        // remaining sub-blocks are still executing.
+        Self::execute_block_sharded::<V>(
             transactions.clone(),
             state_view_arc.clone(),
             onchain_config,
+            &mut |output_chunk| {
+                output_hashes.extend(ApplyChunkOutput::calculate_output_hashes(&output_chunk));
+                transaction_outputs.extend(output_chunk);
+            },
         )?;
 
         // TODO(skedia) add logic to emit counters per shard instead of doing it globally.
@@ -103,6 +115,7 @@ impl ChunkOutput {
                 .map(|t| t.into_txn().into_inner())
                 .collect(),
             transaction_outputs,
+            output_hashes: Some(output_hashes),
             state_cache: state_view.into_state_cache(),
         })
     }
@@ -128,6 +141,7 @@ impl ChunkOutput {
         Ok(Self {
             transactions,
             transaction_outputs,
+            output_hashes: None,
             state_cache: state_view.into_state_cache(),
         })
     }
@@ -169,20 +183,23 @@ impl ChunkOutput {
         partitioned_txns: PartitionedTransactions,
         state_view: Arc<CachedStateView>,
         onchain_config: BlockExecutorConfigFromOnchain,
-    ) -> Result<Vec<TransactionOutput>> {
+        output_sink: &mut dyn FnMut(Vec<TransactionOutput>),
+    ) -> Result<()> {
         if !get_remote_addresses().is_empty() {
-            Ok(V::execute_block_sharded(
+            Ok(V::execute_block_sharded_streaming(
                 REMOTE_SHARDED_BLOCK_EXECUTOR.lock().deref(),
                 partitioned_txns,
                 state_view,
                 onchain_config,
+                output_sink,
             )?)
         } else {
-            Ok(V::execute_block_sharded(
+            Ok(V::execute_block_sharded_streaming(
                 SHARDED_BLOCK_EXECUTOR.lock().deref(),
                 partitioned_txns,
                 state_view,
                 onchain_config,
+                output_sink,
             )?)
         }
     }
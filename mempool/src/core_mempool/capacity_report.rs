@@ -0,0 +1,257 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Capacity planning reports for the core mempool. A report summarizes the current composition
+//! of mempool (by bucket, account and age) together with the observed throughput, so that
+//! operators can choose capacity and bucket configurations based on real data.
+
+use crate::core_mempool::CoreMempool;
+use aptos_infallible::Mutex;
+use aptos_types::account_address::AccountAddress;
+use serde::Serialize;
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+/// The window over which insertion and commit throughput is measured
+pub const THROUGHPUT_WINDOW_SECS: u64 = 60;
+
+/// The (exclusive) upper bounds of the age buckets in the report. The last bucket is unbounded.
+pub const AGE_BUCKET_BOUNDS_SECS: &[u64] = &[1, 10, 60, 300, 600];
+
+/// The default number of accounts listed in the account concentration report
+pub const DEFAULT_MAX_TOP_ACCOUNTS: usize = 10;
+
+/// A structured capacity planning report for mempool
+#[derive(Clone, Debug, Serialize)]
+pub struct CapacityReport {
+    pub generated_at_usecs: u64,
+    pub utilization: UtilizationReport,
+    pub buckets: Vec<BucketReport>,
+    pub accounts: AccountConcentrationReport,
+    pub ages: Vec<AgeBucketReport>,
+    pub throughput: ThroughputReport,
+    pub eviction_horizon: EvictionHorizonReport,
+}
+
+/// The overall utilization of mempool (relative to the configured capacities)
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct UtilizationReport {
+    pub num_txns: usize,
+    pub num_parked_txns: usize,
+    pub capacity: usize,
+    pub txns_utilization: f64,
+    pub size_bytes: usize,
+    pub capacity_bytes: usize,
+    pub bytes_utilization: f64,
+}
+
+/// The composition of a single broadcast bucket (identified by its minimum ranking score)
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BucketReport {
+    pub bucket_min: String,
+    pub num_txns: usize,
+    pub num_parked_txns: usize,
+    pub size_bytes: usize,
+}
+
+/// The concentration of transactions across the accounts in mempool
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AccountConcentrationReport {
+    pub num_accounts: usize,
+    pub capacity_per_user: usize,
+    pub num_accounts_at_capacity: usize,
+    /// The fraction of all transactions that belong to the top accounts
+    pub top_accounts_txns_share: f64,
+    /// The accounts with the most transactions (ordered by descending transaction count)
+    pub top_accounts: Vec<AccountReport>,
+}
+
+/// The transactions of a single account in mempool
+#[derive(Clone, Debug, Serialize)]
+pub struct AccountReport {
+    pub address: AccountAddress,
+    pub num_txns: usize,
+    pub size_bytes: usize,
+}
+
+/// The transactions that have been in mempool for less than `max_age_secs`
+/// (but at least as long as the previous bucket). The last bucket has no upper bound.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AgeBucketReport {
+    pub max_age_secs: Option<u64>,
+    pub num_txns: usize,
+    pub size_bytes: usize,
+}
+
+/// The insertion and commit throughput observed over the last `window_secs`
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct ThroughputReport {
+    pub window_secs: u64,
+    pub insertion_tps: f64,
+    pub commit_tps: f64,
+}
+
+/// A projection of when mempool will fill up (and start rejecting or evicting transactions)
+/// assuming the currently observed throughput persists.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct EvictionHorizonReport {
+    /// The rate at which mempool is growing (negative if mempool is draining)
+    pub net_growth_tps: f64,
+    pub avg_txn_bytes: f64,
+    /// The projected time until either capacity is reached (None if mempool is not growing)
+    pub secs_until_full: Option<f64>,
+    /// The projected time to commit all transactions currently in mempool (None if nothing
+    /// is being committed).
+    pub secs_to_drain: Option<f64>,
+}
+
+impl EvictionHorizonReport {
+    pub(crate) fn new(utilization: &UtilizationReport, throughput: &ThroughputReport) -> Self {
+        let net_growth_tps = throughput.insertion_tps - throughput.commit_tps;
+        let avg_txn_bytes = if utilization.num_txns > 0 {
+            utilization.size_bytes as f64 / utilization.num_txns as f64
+        } else {
+            0.0
+        };
+
+        // Identify the capacity (count or bytes) that will be exhausted first
+        let secs_until_full = if net_growth_tps > 0.0 {
+            let remaining_txns = utilization.capacity.saturating_sub(utilization.num_txns);
+            let secs_until_txns_full = remaining_txns as f64 / net_growth_tps;
+            if avg_txn_bytes > 0.0 {
+                let remaining_bytes = utilization
+                    .capacity_bytes
+                    .saturating_sub(utilization.size_bytes);
+                let secs_until_bytes_full =
+                    remaining_bytes as f64 / (net_growth_tps * avg_txn_bytes);
+                Some(secs_until_txns_full.min(secs_until_bytes_full))
+            } else {
+                Some(secs_until_txns_full)
+            }
+        } else {
+            None
+        };
+
+        let secs_to_drain = if throughput.commit_tps > 0.0 {
+            Some(utilization.num_txns as f64 / throughput.commit_tps)
+        } else {
+            None
+        };
+
+        Self {
+            net_growth_tps,
+            avg_txn_bytes,
+            secs_until_full,
+            secs_to_drain,
+        }
+    }
+}
+
+/// Returns the index of the age bucket for the given age
+pub(crate) fn get_age_bucket_index(age: Duration) -> usize {
+    AGE_BUCKET_BOUNDS_SECS
+        .iter()
+        .position(|bound_secs| age < Duration::from_secs(*bound_secs))
+        .unwrap_or(AGE_BUCKET_BOUNDS_SECS.len())
+}
+
+/// Returns an empty report for each age bucket
+pub(crate) fn new_age_buckets() -> Vec<AgeBucketReport> {
+    AGE_BUCKET_BOUNDS_SECS
+        .iter()
+        .map(|bound_secs| Some(*bound_secs))
+        .chain(std::iter::once(None))
+        .map(|max_age_secs| AgeBucketReport {
+            max_age_secs,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Tracks the number of inserted and committed transactions over a sliding window
+/// (using one-second slots), to estimate the current mempool throughput.
+pub(crate) struct ThroughputTracker {
+    window_secs: u64,
+    // (second since epoch, num inserted, num committed)
+    slots: VecDeque<(u64, u64, u64)>,
+}
+
+impl ThroughputTracker {
+    pub(crate) fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs,
+            slots: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn record_insertion(&mut self, now: Duration) {
+        self.current_slot(now).1 += 1;
+    }
+
+    pub(crate) fn record_commit(&mut self, now: Duration) {
+        self.current_slot(now).2 += 1;
+    }
+
+    /// Returns the throughput measured over the window ending at `now`
+    pub(crate) fn get_throughput(&self, now: Duration) -> ThroughputReport {
+        let now_secs = now.as_secs();
+        let window_start_secs = now_secs.saturating_sub(self.window_secs - 1);
+        let (num_inserted, num_committed) = self
+            .slots
+            .iter()
+            .filter(|(slot_secs, _, _)| *slot_secs >= window_start_secs)
+            .fold(
+                (0, 0),
+                |(inserted, committed), (_, slot_inserted, slot_committed)| {
+                    (inserted + slot_inserted, committed + slot_committed)
+                },
+            );
+
+        // Only average over the part of the window that has been tracked (e.g., after startup)
+        let tracked_secs = match self.slots.front() {
+            Some((first_slot_secs, _, _)) => {
+                now_secs.saturating_sub(window_start_secs.max(*first_slot_secs)) + 1
+            },
+            None => self.window_secs,
+        };
+
+        ThroughputReport {
+            window_secs: self.window_secs,
+            insertion_tps: num_inserted as f64 / tracked_secs as f64,
+            commit_tps: num_committed as f64 / tracked_secs as f64,
+        }
+    }
+
+    /// Returns the slot for the current second (and drops any slots outside the window)
+    fn current_slot(&mut self, now: Duration) -> &mut (u64, u64, u64) {
+        let now_secs = now.as_secs();
+        while let Some((slot_secs, _, _)) = self.slots.front() {
+            if *slot_secs + self.window_secs > now_secs {
+                break;
+            }
+            self.slots.pop_front();
+        }
+
+        if self.slots.back().map(|(slot_secs, _, _)| *slot_secs) != Some(now_secs) {
+            self.slots.push_back((now_secs, 0, 0));
+        }
+        self.slots.back_mut().unwrap()
+    }
+}
+
+/// A handle for generating capacity reports of a running mempool (e.g., from the admin service)
+#[derive(Clone)]
+pub struct MempoolCapacityReporter {
+    mempool: Arc<Mutex<CoreMempool>>,
+}
+
+impl MempoolCapacityReporter {
+    pub(crate) fn new(mempool: Arc<Mutex<CoreMempool>>) -> Self {
+        Self { mempool }
+    }
+
+    /// Generates a capacity report, listing at most `max_top_accounts` accounts.
+    /// Note: this iterates over all transactions while holding the mempool lock.
+    pub fn generate_report(&self, max_top_accounts: usize) -> CapacityReport {
+        self.mempool.lock().gen_capacity_report(max_top_accounts)
+    }
+}
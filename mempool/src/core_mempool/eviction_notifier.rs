@@ -0,0 +1,119 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Eviction notifications for the core mempool. Clients (e.g., the API) can subscribe to the
+//! eviction of the transaction with a given sender and sequence number, and are notified as soon
+//! as the transaction is removed from mempool without being committed (e.g., on expiration).
+
+use crate::counters;
+use aptos_crypto::HashValue;
+use aptos_types::account_address::AccountAddress;
+use futures::channel::oneshot;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+
+/// The reason a transaction was evicted from mempool
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum EvictionReason {
+    /// The transaction expired (based on the client-specified expiration time)
+    Expired,
+    /// The transaction was in mempool for longer than the system TTL
+    SystemTtlExpired,
+    /// The transaction was evicted from the parking lot to make space for a ready transaction
+    ParkingLotEvicted,
+    /// The transaction was replaced by a transaction with a higher gas unit price
+    Replaced,
+    /// The transaction was rejected during block execution
+    Rejected,
+}
+
+impl EvictionReason {
+    /// Returns a summary label for the eviction reason
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Self::Expired => "expired",
+            Self::SystemTtlExpired => "system_ttl_expired",
+            Self::ParkingLotEvicted => "parking_lot_evicted",
+            Self::Replaced => "replaced",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+impl fmt::Display for EvictionReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// A notification for a transaction evicted from mempool
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionEviction {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    /// The committed hash of the evicted transaction
+    pub hash: HashValue,
+    pub reason: EvictionReason,
+}
+
+/// Tracks the subscriptions to transaction evictions, keyed by (sender, sequence number)
+#[derive(Default)]
+pub struct EvictionNotifier {
+    subscriptions: HashMap<(AccountAddress, u64), Vec<oneshot::Sender<TransactionEviction>>>,
+}
+
+impl EvictionNotifier {
+    /// Subscribes to the eviction of the transaction with the given sender and sequence number.
+    /// The subscription ends once a notification is sent, or the receiver is dropped.
+    pub fn subscribe(
+        &mut self,
+        sender: AccountAddress,
+        sequence_number: u64,
+        callback: oneshot::Sender<TransactionEviction>,
+    ) {
+        let callbacks = self
+            .subscriptions
+            .entry((sender, sequence_number))
+            .or_default();
+        callbacks.retain(|callback| !callback.is_canceled());
+        callbacks.push(callback);
+    }
+
+    /// Notifies all subscribers of the given transaction eviction
+    pub fn notify(
+        &mut self,
+        sender: AccountAddress,
+        sequence_number: u64,
+        hash: HashValue,
+        reason: EvictionReason,
+    ) {
+        counters::CORE_MEMPOOL_EVICTED_TXNS
+            .with_label_values(&[reason.get_label()])
+            .inc();
+        if let Some(callbacks) = self.subscriptions.remove(&(sender, sequence_number)) {
+            let eviction = TransactionEviction {
+                sender,
+                sequence_number,
+                hash,
+                reason,
+            };
+            for callback in callbacks {
+                // The receiver may have been dropped (e.g., the subscriber stopped waiting)
+                let _ = callback.send(eviction.clone());
+            }
+        }
+    }
+
+    /// Removes the subscriptions whose receivers have been dropped
+    pub fn remove_canceled_subscriptions(&mut self) {
+        self.subscriptions.retain(|_, callbacks| {
+            callbacks.retain(|callback| !callback.is_canceled());
+            !callbacks.is_empty()
+        });
+    }
+
+    /// Returns the number of (sender, sequence number) pairs with subscriptions
+    pub fn num_subscriptions(&self) -> usize {
+        self.subscriptions.len()
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gas price estimation for the core mempool. The estimator tracks the gas unit prices of the
+//! transactions committed in recent blocks. Together with the gas unit prices of the ready
+//! transactions in mempool, these are used to estimate the gas unit price required to get a
+//! transaction committed (at a given percentile of the price distribution).
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The maximum percentile that can be requested
+pub const MAX_PERCENTILE: u8 = 100;
+
+/// A gas unit price estimate (at the requested percentile)
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GasPriceEstimate {
+    pub percentile: u8,
+    /// The number of recently committed blocks considered by the estimate
+    pub horizon_blocks: usize,
+    pub num_ready_txns: usize,
+    pub num_committed_txns: usize,
+    /// The estimated gas unit price (None if there were no prices to sample)
+    pub gas_unit_price: Option<u64>,
+}
+
+/// Tracks the gas unit prices of the transactions in recently committed blocks. Note: only the
+/// prices of committed transactions that were in the local mempool are known.
+pub struct FeeEstimator {
+    max_history_blocks: usize,
+    committed_gas_prices: VecDeque<Vec<u64>>, // The gas prices per block (newest block first)
+}
+
+impl FeeEstimator {
+    pub fn new(max_history_blocks: usize) -> Self {
+        Self {
+            max_history_blocks,
+            committed_gas_prices: VecDeque::with_capacity(max_history_blocks),
+        }
+    }
+
+    /// Records the gas unit prices of the transactions in a newly committed block
+    pub fn record_committed_block(&mut self, gas_prices: Vec<u64>) {
+        if self.max_history_blocks == 0 {
+            return;
+        }
+        if self.committed_gas_prices.len() >= self.max_history_blocks {
+            self.committed_gas_prices.pop_back();
+        }
+        self.committed_gas_prices.push_front(gas_prices);
+    }
+
+    /// Estimates the gas unit price at the given percentile, using the given ready gas
+    /// prices and the gas prices committed in (at most) the last `horizon_blocks` blocks.
+    pub fn estimate_gas_price(
+        &self,
+        percentile: u8,
+        horizon_blocks: usize,
+        ready_gas_prices: Vec<u64>,
+    ) -> Result<GasPriceEstimate> {
+        ensure!(
+            percentile <= MAX_PERCENTILE,
+            "Invalid percentile: {}, the maximum is {}",
+            percentile,
+            MAX_PERCENTILE
+        );
+
+        let horizon_blocks = horizon_blocks.min(self.committed_gas_prices.len());
+        let num_ready_txns = ready_gas_prices.len();
+        let mut gas_prices = ready_gas_prices;
+        for block_gas_prices in self.committed_gas_prices.iter().take(horizon_blocks) {
+            gas_prices.extend_from_slice(block_gas_prices);
+        }
+        let num_committed_txns = gas_prices.len() - num_ready_txns;
+
+        Ok(GasPriceEstimate {
+            percentile,
+            horizon_blocks,
+            num_ready_txns,
+            num_committed_txns,
+            gas_unit_price: get_percentile(gas_prices, percentile),
+        })
+    }
+}
+
+/// Returns the value at the given percentile (using the nearest-rank method)
+fn get_percentile(mut values: Vec<u64>, percentile: u8) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    let rank = (values.len() * percentile as usize).div_ceil(MAX_PERCENTILE as usize);
+    let index = rank.saturating_sub(1);
+    let (_, value, _) = values.select_nth_unstable(index);
+    Some(*value)
+}
@@ -23,6 +23,11 @@ use std::{
 
 pub type AccountTransactions = BTreeMap<u64, MempoolTransaction>;
 
+/// The transaction groups of an account, i.e., the ranges of sequential transactions that
+/// were submitted together and are kept together (for broadcast and block inclusion).
+/// Maps the first sequence number of each group to its last sequence number (inclusive).
+pub type AccountTransactionGroups = BTreeMap<u64, u64>;
+
 /// PriorityIndex represents the main Priority Queue in Mempool.
 /// It's used to form the transaction block for Consensus.
 /// Transactions are ordered by gas price. Second level ordering is done by expiration time.
@@ -475,6 +480,16 @@ impl ParkingLotIndex {
         })
     }
 
+    /// Returns an iterator over all parked transactions (ordered by sequence number per account)
+    pub(crate) fn iter(&self) -> impl Iterator<Item = TxnPointer> + '_ {
+        self.data.iter().flat_map(|(sender, txns)| {
+            txns.iter().map(move |seq_num| TxnPointer {
+                sender: *sender,
+                sequence_number: *seq_num,
+            })
+        })
+    }
+
     pub(crate) fn size(&self) -> usize {
         self.size
     }
@@ -6,14 +6,19 @@
 //! agreed upon.
 use crate::{
     core_mempool::{
+        capacity_report::{CapacityReport, ThroughputTracker, THROUGHPUT_WINDOW_SECS},
+        eviction_notifier::TransactionEviction,
+        fee_estimator::{FeeEstimator, GasPriceEstimate},
         index::TxnPointer,
         transaction::{InsertionInfo, MempoolTransaction, TimelineState},
+        transaction_chain::{build_transaction_chains, pack_transaction_chains},
         transaction_store::TransactionStore,
     },
     counters,
     logging::{LogEntry, LogSchema, TxnsLog},
-    shared_mempool::types::MultiBucketTimelineIndexIds,
+    shared_mempool::types::{MultiBucketTimelineIndexIds, TransactionGroup},
 };
+use anyhow::Result;
 use aptos_config::config::NodeConfig;
 use aptos_consensus_types::common::{TransactionInProgress, TransactionSummary};
 use aptos_crypto::HashValue;
@@ -24,26 +29,47 @@ use aptos_types::{
     transaction::SignedTransaction,
     vm_status::DiscardedVMStatus,
 };
+use futures::channel::oneshot;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     sync::atomic::Ordering,
     time::{Duration, Instant, SystemTime},
 };
 
+// With chain-aware batch packing, up to this many times the requested number of transactions
+// are considered as candidates, so that complete chains can be preferred.
+const CHAIN_AWARE_CANDIDATE_MULTIPLIER: u64 = 2;
+
 pub struct Mempool {
     // Stores the metadata of all transactions in mempool (of all states).
     transactions: TransactionStore,
 
+    // Tracks the recent insertion and commit throughput (for capacity reports)
+    throughput_tracker: ThroughputTracker,
+
+    // Tracks the gas prices of recently committed transactions (for gas price estimation)
+    fee_estimator: FeeEstimator,
+
     pub system_transaction_timeout: Duration,
+
+    // Whether batches pack complete account chains by fee density (see `transaction_chain`)
+    chain_aware_batch_packing: bool,
+
+    // The maximum number of transactions in a transaction group
+    max_transaction_group_size: usize,
 }
 
 impl Mempool {
     pub fn new(config: &NodeConfig) -> Self {
         Mempool {
             transactions: TransactionStore::new(&config.mempool),
+            throughput_tracker: ThroughputTracker::new(THROUGHPUT_WINDOW_SECS),
+            fee_estimator: FeeEstimator::new(config.mempool.fee_estimation_history_blocks),
             system_transaction_timeout: Duration::from_secs(
                 config.mempool.system_transaction_timeout_secs,
             ),
+            chain_aware_batch_packing: config.mempool.enable_chain_aware_batch_packing,
+            max_transaction_group_size: config.mempool.max_transaction_group_size,
         }
     }
 
@@ -51,6 +77,23 @@ impl Mempool {
     pub(crate) fn commit_transaction(&mut self, sender: &AccountAddress, sequence_number: u64) {
         self.transactions
             .commit_transaction(sender, sequence_number);
+        self.throughput_tracker
+            .record_commit(aptos_infallible::duration_since_epoch());
+    }
+
+    /// Records the gas unit prices of the (locally known) transactions in a committed block
+    pub(crate) fn record_committed_gas_prices(&mut self, gas_prices: Vec<u64>) {
+        self.fee_estimator.record_committed_block(gas_prices);
+    }
+
+    /// Returns the gas unit price of the transaction (if it exists in mempool)
+    pub(crate) fn get_gas_unit_price(
+        &self,
+        sender: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<u64> {
+        self.transactions
+            .get_gas_unit_price(sender, sequence_number)
     }
 
     pub(crate) fn log_commit_transaction(
@@ -190,6 +233,56 @@ impl Mempool {
         self.transactions.get_by_hash(hash)
     }
 
+    /// Returns the transaction groups that are completely contained in the given
+    /// transactions (e.g., a broadcast batch), so that peers can keep them together.
+    pub(crate) fn get_transaction_groups(
+        &self,
+        transactions: &[SignedTransaction],
+    ) -> Vec<TransactionGroup> {
+        let txn_pointers: HashSet<_> = transactions
+            .iter()
+            .map(|txn| TxnPointer::new(txn.sender(), txn.sequence_number()))
+            .collect();
+        let mut transaction_groups = vec![];
+        for txn in transactions {
+            let (first_sequence_number, last_sequence_number) = match self
+                .transactions
+                .get_transaction_group(&txn.sender(), txn.sequence_number())
+            {
+                Some(group) => group,
+                None => continue,
+            };
+            let transaction_group = TransactionGroup {
+                sender: txn.sender(),
+                first_sequence_number,
+                last_sequence_number,
+            };
+            if transaction_groups.contains(&transaction_group) {
+                continue; // The group was already added
+            }
+            if (first_sequence_number..=last_sequence_number).all(|sequence_number| {
+                txn_pointers.contains(&TxnPointer::new(txn.sender(), sequence_number))
+            }) {
+                transaction_groups.push(transaction_group);
+            }
+        }
+        transaction_groups
+    }
+
+    /// Subscribes to the eviction of the transaction with the given sender and sequence
+    /// number. The callback is notified if the transaction is removed from mempool without
+    /// being committed (e.g., because it expired, was replaced or was evicted from the
+    /// parking lot). Note: the callback is not notified if the transaction is committed.
+    pub(crate) fn subscribe_eviction(
+        &mut self,
+        sender: AccountAddress,
+        sequence_number: u64,
+        callback: oneshot::Sender<TransactionEviction>,
+    ) {
+        self.transactions
+            .subscribe_eviction(sender, sequence_number, callback);
+    }
+
     /// Used to add a transaction to the Mempool.
     /// Performs basic validation: checks account's sequence number.
     pub(crate) fn add_txn(
@@ -230,6 +323,10 @@ impl Mempool {
         );
 
         let status = self.transactions.insert(txn_info);
+        if status.code == MempoolStatusCode::Accepted {
+            self.throughput_tracker
+                .record_insertion(aptos_infallible::duration_since_epoch_at(&now));
+        }
         counters::core_mempool_txn_ranking_score(
             counters::INSERT_LABEL,
             status.code.to_string().as_str(),
@@ -239,6 +336,108 @@ impl Mempool {
         status
     }
 
+    /// Used to add a group of sequential transactions of an account to the Mempool. The group
+    /// is added all-or-nothing, and its transactions are kept together (i.e., they are
+    /// broadcast in the same batch, and pulled by consensus in the same batch).
+    pub(crate) fn add_txn_group(
+        &mut self,
+        txns: Vec<(SignedTransaction, u64)>,
+        db_sequence_number: u64,
+        timeline_state: TimelineState,
+        client_submitted: bool,
+    ) -> MempoolStatus {
+        if let Err(error) = self.verify_txn_group(&txns) {
+            return MempoolStatus::new(MempoolStatusCode::InvalidTransactionGroup)
+                .with_message(error.to_string());
+        }
+        let (first_txn, _) = &txns[0];
+        trace!(
+            LogSchema::new(LogEntry::AddTxn).txns(TxnsLog::new_txn(
+                first_txn.sender(),
+                first_txn.sequence_number()
+            )),
+            committed_seq_number = db_sequence_number,
+            group_size = txns.len(),
+        );
+
+        // don't accept old transactions (e.g. seq is less than account's current seq_number)
+        if first_txn.sequence_number() < db_sequence_number {
+            return MempoolStatus::new(MempoolStatusCode::InvalidSeqNumber).with_message(format!(
+                "transaction group sequence number is {}, current sequence number is  {}",
+                first_txn.sequence_number(),
+                db_sequence_number,
+            ));
+        }
+
+        let now = SystemTime::now();
+        let expiration_time =
+            aptos_infallible::duration_since_epoch_at(&now) + self.system_transaction_timeout;
+        let ranking_scores: Vec<_> = txns
+            .iter()
+            .map(|(_, ranking_score)| *ranking_score)
+            .collect();
+        let txn_infos = txns
+            .into_iter()
+            .map(|(txn, ranking_score)| {
+                MempoolTransaction::new(
+                    txn,
+                    expiration_time,
+                    ranking_score,
+                    timeline_state,
+                    db_sequence_number,
+                    now,
+                    client_submitted,
+                )
+            })
+            .collect();
+
+        let status = self.transactions.insert_group(txn_infos);
+        if status.code == MempoolStatusCode::Accepted {
+            for _ in &ranking_scores {
+                self.throughput_tracker
+                    .record_insertion(aptos_infallible::duration_since_epoch_at(&now));
+            }
+        }
+        for ranking_score in ranking_scores {
+            counters::core_mempool_txn_ranking_score(
+                counters::INSERT_LABEL,
+                status.code.to_string().as_str(),
+                self.transactions.get_bucket(ranking_score),
+                ranking_score,
+            );
+        }
+        status
+    }
+
+    /// Verifies that the given transactions form a valid group, i.e., that they are
+    /// sequential transactions of the same account (and that the group isn't too large).
+    fn verify_txn_group(&self, txns: &[(SignedTransaction, u64)]) -> Result<()> {
+        let (first_txn, _) = match txns.first() {
+            Some(first_txn) => first_txn,
+            None => anyhow::bail!("Transaction group is empty"),
+        };
+        if txns.len() > self.max_transaction_group_size {
+            anyhow::bail!(
+                "Transaction group is too large. Number of transactions: {}, Max group size: {}",
+                txns.len(),
+                self.max_transaction_group_size
+            );
+        }
+        for (expected_sequence_number, (txn, _)) in (first_txn.sequence_number()..).zip(txns) {
+            if txn.sender() != first_txn.sender() {
+                anyhow::bail!("Transactions in a group must have the same sender");
+            }
+            if txn.sequence_number() != expected_sequence_number {
+                anyhow::bail!(
+                    "Transactions in a group must be sequential. Expected: {}, found: {}",
+                    expected_sequence_number,
+                    txn.sequence_number()
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn was_seen(
         txn_pointer: &TransactionSummary,
         seen: &HashMap<TransactionSummary, u64>,
@@ -291,6 +490,12 @@ impl Mempool {
         let mut skipped = HashSet::new();
         let mut total_bytes = 0;
         let mut txn_walked = 0usize;
+        // With chain-aware packing, more candidates are considered, and then packed by chain
+        let candidate_limit = if self.chain_aware_batch_packing {
+            max_txns.saturating_mul(CHAIN_AWARE_CANDIDATE_MULTIPLIER)
+        } else {
+            max_txns
+        };
         // iterate over the queue of transactions based on gas price
         'main: for txn in self.transactions.iter_queue() {
             txn_walked += 1;
@@ -317,7 +522,7 @@ impl Mempool {
                 let ptr = TxnPointer::from(txn);
                 seen.insert(ptr, txn.gas_ranking_score);
                 result.push(ptr);
-                if (result.len() as u64) == max_txns {
+                if (result.len() as u64) == candidate_limit {
                     break;
                 }
 
@@ -327,7 +532,7 @@ impl Mempool {
                 while skipped.contains(&skipped_txn) {
                     seen.insert(skipped_txn, txn.gas_ranking_score);
                     result.push(skipped_txn);
-                    if (result.len() as u64) == max_txns {
+                    if (result.len() as u64) == candidate_limit {
                         break 'main;
                     }
                     skipped_txn = TxnPointer::new(txn.address, skipped_txn.sequence_number + 1);
@@ -336,6 +541,22 @@ impl Mempool {
                 skipped.insert(TxnPointer::from(txn));
             }
         }
+        if self.chain_aware_batch_packing {
+            let candidates: Vec<_> = result
+                .into_iter()
+                .map(|txn_pointer| {
+                    let ranking_score = self
+                        .transactions
+                        .get_ranking_score(&txn_pointer.sender, txn_pointer.sequence_number)
+                        .unwrap_or_default();
+                    (txn_pointer, ranking_score)
+                })
+                .collect();
+            result =
+                pack_transaction_chains(build_transaction_chains(&candidates), max_txns as usize);
+        }
+        // Transaction groups are never split across batches
+        result = self.truncate_to_group_boundaries(result, &exclude_transactions);
         let result_size = result.len();
         let result_end_time = start_time.elapsed();
         let result_time = result_end_time.saturating_sub(gas_end_time);
@@ -365,6 +586,21 @@ impl Mempool {
                 );
             }
         }
+        if full_bytes {
+            // The byte limit may have split a transaction group
+            let block_pointers = block
+                .iter()
+                .map(|txn| TxnPointer::new(txn.sender(), txn.sequence_number()))
+                .collect();
+            let block_pointers: HashSet<_> = self
+                .truncate_to_group_boundaries(block_pointers, &exclude_transactions)
+                .into_iter()
+                .collect();
+            block.retain(|txn| {
+                block_pointers.contains(&TxnPointer::new(txn.sender(), txn.sequence_number()))
+            });
+            total_bytes = block.iter().map(|txn| txn.txn_bytes_len() as u64).sum();
+        }
         let block_end_time = start_time.elapsed();
         let block_time = block_end_time.saturating_sub(result_end_time);
 
@@ -409,6 +645,7 @@ impl Mempool {
             block.clear();
         }
 
+        self.record_batch_chain_completeness(&block, &exclude_transactions);
         counters::mempool_service_transactions(counters::GET_BLOCK_LABEL, block.len());
         counters::MEMPOOL_SERVICE_BYTES_GET_BLOCK.observe(total_bytes as f64);
         for transaction in &block {
@@ -417,6 +654,82 @@ impl Mempool {
         block
     }
 
+    /// Drops the transactions of the groups that are not completely included in the batch (the
+    /// group transactions that were already pulled, i.e., excluded, count as included). The
+    /// later transactions of the same accounts are dropped as well, as they follow the group.
+    fn truncate_to_group_boundaries(
+        &self,
+        batch: Vec<TxnPointer>,
+        exclude_transactions: &BTreeMap<TransactionSummary, TransactionInProgress>,
+    ) -> Vec<TxnPointer> {
+        let included: HashSet<_> = batch.iter().copied().collect();
+        let mut truncated_accounts: HashMap<AccountAddress, u64> = HashMap::new();
+        for txn_pointer in &batch {
+            let (first_sequence_number, last_sequence_number) = match self
+                .transactions
+                .get_transaction_group(&txn_pointer.sender, txn_pointer.sequence_number)
+            {
+                Some(group) => group,
+                None => continue,
+            };
+            let complete = (first_sequence_number..=last_sequence_number).all(|sequence_number| {
+                let group_txn_pointer = TxnPointer::new(txn_pointer.sender, sequence_number);
+                included.contains(&group_txn_pointer)
+                    || exclude_transactions.contains_key(&group_txn_pointer)
+            });
+            if !complete {
+                let truncated_sequence_number = truncated_accounts
+                    .entry(txn_pointer.sender)
+                    .or_insert(txn_pointer.sequence_number);
+                *truncated_sequence_number =
+                    (*truncated_sequence_number).min(txn_pointer.sequence_number);
+            }
+        }
+        if truncated_accounts.is_empty() {
+            return batch;
+        }
+
+        let num_txns = batch.len();
+        let batch: Vec<_> = batch
+            .into_iter()
+            .filter(|txn_pointer| {
+                truncated_accounts
+                    .get(&txn_pointer.sender)
+                    .map_or(true, |sequence_number| {
+                        txn_pointer.sequence_number < *sequence_number
+                    })
+            })
+            .collect();
+        counters::CORE_MEMPOOL_GROUP_TRUNCATED_TXNS.inc_by((num_txns - batch.len()) as u64);
+        batch
+    }
+
+    /// Records, for each account in the batch, the number of its transactions in the batch, and
+    /// whether its chain is complete (i.e., none of its ready transactions are left behind).
+    fn record_batch_chain_completeness(
+        &self,
+        batch: &[SignedTransaction],
+        exclude_transactions: &BTreeMap<TransactionSummary, TransactionInProgress>,
+    ) {
+        let mut chains: HashMap<AccountAddress, (u64, u64)> = HashMap::new();
+        for txn in batch {
+            let (length, last_sequence_number) = chains
+                .entry(txn.sender())
+                .or_insert((0, txn.sequence_number()));
+            *length += 1;
+            *last_sequence_number = (*last_sequence_number).max(txn.sequence_number());
+        }
+        for (sender, (length, last_sequence_number)) in chains {
+            let next_txn = TxnPointer::new(sender, last_sequence_number + 1);
+            let complete = exclude_transactions.contains_key(&next_txn)
+                || self
+                    .transactions
+                    .get_ranking_score(&sender, last_sequence_number + 1)
+                    .is_none();
+            counters::core_mempool_batch_chain(complete, length);
+        }
+    }
+
     /// Periodic core mempool garbage collection.
     /// Removes all expired transactions and clears expired entries in metrics
     /// cache and sequence number cache.
@@ -447,10 +760,42 @@ impl Mempool {
         self.transactions.timeline_range(start_end_pairs)
     }
 
+    /// Returns the ready transactions (in priority order) and their insertion times
+    pub(crate) fn get_ready_transactions(&self) -> Vec<(SignedTransaction, SystemTime)> {
+        self.transactions.get_ready_transactions()
+    }
+
+    /// Returns the parked transactions and their insertion times
+    pub(crate) fn get_parked_transactions(&self) -> Vec<(SignedTransaction, SystemTime)> {
+        self.transactions.get_parked_transactions()
+    }
+
+    /// Estimates the gas unit price at the given percentile, based on the ready transactions
+    /// and the transactions committed in (at most) the last `horizon_blocks` blocks.
+    pub fn estimate_gas_price(
+        &self,
+        percentile: u8,
+        horizon_blocks: usize,
+    ) -> Result<GasPriceEstimate> {
+        let ready_gas_prices = self.transactions.get_ready_gas_prices();
+        self.fee_estimator
+            .estimate_gas_price(percentile, horizon_blocks, ready_gas_prices)
+    }
+
     pub fn gen_snapshot(&self) -> TxnsLog {
         self.transactions.gen_snapshot()
     }
 
+    /// Generates a capacity report, listing at most `max_top_accounts` accounts
+    pub fn gen_capacity_report(&self, max_top_accounts: usize) -> CapacityReport {
+        let now = SystemTime::now();
+        let throughput = self
+            .throughput_tracker
+            .get_throughput(aptos_infallible::duration_since_epoch_at(&now));
+        self.transactions
+            .gen_capacity_report(now, throughput, max_top_accounts)
+    }
+
     #[cfg(test)]
     pub fn get_parking_lot_size(&self) -> usize {
         self.transactions.get_parking_lot_size()
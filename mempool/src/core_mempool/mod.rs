@@ -2,14 +2,30 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod capacity_report;
+mod eviction_notifier;
+mod fee_estimator;
 mod index;
 mod mempool;
 mod transaction;
+mod transaction_chain;
 mod transaction_store;
 
-#[cfg(test)]
-pub use self::transaction::{MempoolTransaction, SubmittedBy};
 pub use self::{
-    index::TxnPointer, mempool::Mempool as CoreMempool, transaction::TimelineState,
+    capacity_report::{
+        AccountConcentrationReport, AccountReport, AgeBucketReport, BucketReport, CapacityReport,
+        EvictionHorizonReport, MempoolCapacityReporter, ThroughputReport, UtilizationReport,
+        DEFAULT_MAX_TOP_ACCOUNTS,
+    },
+    eviction_notifier::{EvictionReason, TransactionEviction},
+    fee_estimator::GasPriceEstimate,
+    index::TxnPointer,
+    mempool::Mempool as CoreMempool,
+    transaction::TimelineState,
     transaction_store::TXN_INDEX_ESTIMATED_BYTES,
 };
+#[cfg(test)]
+pub use self::{
+    transaction::{MempoolTransaction, SubmittedBy},
+    transaction_chain::{build_transaction_chains, pack_transaction_chains, TransactionChain},
+};
@@ -0,0 +1,95 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Chain-aware packing of the transactions pulled by consensus. When several sequential
+//! transactions of an account are ready, they form a chain that can only be executed in
+//! order. Packing complete chains, by their fee per transaction (i.e., their price density),
+//! captures more fees than packing the transactions independently (which may leave a
+//! high-value chain partially included), without letting long chains of low-fee transactions
+//! displace short high-fee ones within the transaction budget.
+
+use crate::core_mempool::index::TxnPointer;
+use aptos_types::account_address::AccountAddress;
+use std::{cmp::Ordering, collections::HashMap};
+
+/// The contiguous ready transactions of an account, in sequence number order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionChain {
+    pub sender: AccountAddress,
+    pub sequence_numbers: Vec<u64>,
+    /// The sum of the ranking scores (i.e., gas unit prices) of the transactions in the chain.
+    pub aggregate_ranking_score: u64,
+}
+
+impl TransactionChain {
+    fn len(&self) -> usize {
+        self.sequence_numbers.len()
+    }
+
+    /// Compares the price densities (i.e., the average ranking scores) of the chains
+    fn cmp_density(&self, other: &Self) -> Ordering {
+        let density = self.aggregate_ranking_score as u128 * other.len() as u128;
+        let other_density = other.aggregate_ranking_score as u128 * self.len() as u128;
+        density.cmp(&other_density)
+    }
+
+    fn pointers(&self, count: usize) -> impl Iterator<Item = TxnPointer> + '_ {
+        self.sequence_numbers
+            .iter()
+            .take(count)
+            .map(|sequence_number| TxnPointer::new(self.sender, *sequence_number))
+    }
+}
+
+/// Groups the candidate transactions into chains per account. The candidates of each
+/// account must be contiguous and in sequence number order (as pulled from the priority
+/// queue). Chains are returned in the order of their first candidate.
+pub fn build_transaction_chains(candidates: &[(TxnPointer, u64)]) -> Vec<TransactionChain> {
+    let mut chains: Vec<TransactionChain> = vec![];
+    let mut chain_indices: HashMap<AccountAddress, usize> = HashMap::new();
+    for (txn_pointer, ranking_score) in candidates {
+        let index = *chain_indices.entry(txn_pointer.sender).or_insert_with(|| {
+            chains.push(TransactionChain {
+                sender: txn_pointer.sender,
+                sequence_numbers: vec![],
+                aggregate_ranking_score: 0,
+            });
+            chains.len() - 1
+        });
+        let chain = &mut chains[index];
+        chain.sequence_numbers.push(txn_pointer.sequence_number);
+        chain.aggregate_ranking_score =
+            chain.aggregate_ranking_score.saturating_add(*ranking_score);
+    }
+    chains
+}
+
+/// Packs up to `max_txns` transactions from the given chains. Chains are packed greedily in
+/// decreasing order of their price density (ties are broken by the given order), and only
+/// complete chains are packed at first. The remaining space is then filled with the prefixes
+/// of the chains that did not fit.
+pub fn pack_transaction_chains(
+    mut chains: Vec<TransactionChain>,
+    max_txns: usize,
+) -> Vec<TxnPointer> {
+    // Note: the sort is stable, so ties preserve the priority order
+    chains.sort_by(|a, b| b.cmp_density(a));
+
+    let mut packed = Vec::with_capacity(max_txns);
+    let mut partial_chains = vec![];
+    for chain in chains {
+        if packed.len() + chain.len() <= max_txns {
+            packed.extend(chain.pointers(chain.len()));
+        } else {
+            partial_chains.push(chain);
+        }
+    }
+    for chain in partial_chains {
+        let space = max_txns - packed.len();
+        if space == 0 {
+            break;
+        }
+        packed.extend(chain.pointers(space));
+    }
+    packed
+}
@@ -4,9 +4,15 @@
 
 use crate::{
     core_mempool::{
+        capacity_report::{
+            get_age_bucket_index, new_age_buckets, AccountConcentrationReport, AccountReport,
+            BucketReport, CapacityReport, EvictionHorizonReport, ThroughputReport,
+            UtilizationReport,
+        },
+        eviction_notifier::{EvictionNotifier, EvictionReason, TransactionEviction},
         index::{
-            AccountTransactions, MultiBucketTimelineIndex, ParkingLotIndex, PriorityIndex,
-            PriorityQueueIter, TTLIndex,
+            AccountTransactionGroups, AccountTransactions, MultiBucketTimelineIndex,
+            ParkingLotIndex, PriorityIndex, PriorityQueueIter, TTLIndex,
         },
         mempool::Mempool,
         transaction::{InsertionInfo, MempoolTransaction, TimelineState},
@@ -22,12 +28,13 @@ use aptos_crypto::HashValue;
 use aptos_logger::{prelude::*, Level};
 use aptos_types::{
     account_address::AccountAddress,
-    mempool_status::{MempoolStatus, MempoolStatusCode},
+    mempool_status::{MempoolRejectionReason, MempoolStatus, MempoolStatusCode},
     transaction::SignedTransaction,
 };
+use futures::channel::oneshot;
 use std::{
-    cmp::max,
-    collections::HashMap,
+    cmp::{max, min},
+    collections::{HashMap, HashSet},
     mem::size_of,
     ops::Bound,
     time::{Duration, SystemTime},
@@ -43,9 +50,15 @@ pub const TXN_INDEX_ESTIMATED_BYTES: usize = size_of::<crate::core_mempool::inde
 pub struct TransactionStore {
     // main DS
     transactions: HashMap<AccountAddress, AccountTransactions>,
+    // The groups of sequential transactions per account (that are kept together)
+    transaction_groups: HashMap<AccountAddress, AccountTransactionGroups>,
 
     // Sequence numbers for accounts with transactions
     sequence_numbers: HashMap<AccountAddress, u64>,
+    // The next expected sequence number for accounts with transactions, i.e., the first
+    // sequence number gap after the chain of ready transactions. Transactions before it
+    // are already ready, so filling the gap only needs to promote the transactions after it.
+    next_ready_sequence_numbers: HashMap<AccountAddress, u64>,
 
     // indexes
     priority_index: PriorityIndex,
@@ -69,6 +82,8 @@ pub struct TransactionStore {
     size_bytes: usize,
     // keeps track of txns that were resubmitted with higher gas
     gas_upgraded_index: HashMap<TxnPointer, u64>,
+    // notifies the subscribers of txns that are evicted (i.e., removed without being committed)
+    eviction_notifier: EvictionNotifier,
 
     // configuration
     capacity: usize,
@@ -86,7 +101,9 @@ impl TransactionStore {
         Self {
             // main DS
             transactions: HashMap::new(),
+            transaction_groups: HashMap::new(),
             sequence_numbers: HashMap::new(),
+            next_ready_sequence_numbers: HashMap::new(),
 
             // various indexes
             system_ttl_index: TTLIndex::new(Box::new(|t: &MempoolTransaction| t.expiration_time)),
@@ -101,6 +118,7 @@ impl TransactionStore {
             // estimated size in bytes
             size_bytes: 0,
             gas_upgraded_index: HashMap::new(),
+            eviction_notifier: EvictionNotifier::default(),
 
             // configuration
             capacity: config.capacity,
@@ -167,6 +185,16 @@ impl TransactionStore {
         None
     }
 
+    /// Returns the gas unit price of the transaction (if it exists)
+    pub(crate) fn get_gas_unit_price(
+        &self,
+        address: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<u64> {
+        self.get_mempool_txn(address, sequence_number)
+            .map(|txn| txn.txn.gas_unit_price())
+    }
+
     pub(crate) fn get_ranking_score(
         &self,
         address: &AccountAddress,
@@ -191,6 +219,98 @@ impl TransactionStore {
         &self.gas_upgraded_index
     }
 
+    /// Returns the (inclusive) sequence number range of the group that
+    /// contains the given transaction, if the transaction is in a group.
+    pub(crate) fn get_transaction_group(
+        &self,
+        address: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<(u64, u64)> {
+        self.transaction_groups
+            .get(address)?
+            .range(..=sequence_number)
+            .next_back()
+            .filter(|(_, last_sequence_number)| sequence_number <= **last_sequence_number)
+            .map(|(first_sequence_number, last_sequence_number)| {
+                (*first_sequence_number, *last_sequence_number)
+            })
+    }
+
+    /// Inserts the given sequential transactions of an account as a group. The insertion is
+    /// all-or-nothing: if any of the transactions is rejected, the transactions of the group
+    /// that were already inserted are removed again. The caller must ensure that the group
+    /// is non-empty, and that the transactions are sequential and of the same account.
+    pub(crate) fn insert_group(&mut self, txns: Vec<MempoolTransaction>) -> MempoolStatus {
+        let address = txns[0].get_sender();
+        let first_sequence_number = txns[0].sequence_info.transaction_sequence_number;
+        let last_sequence_number = first_sequence_number + txns.len() as u64 - 1;
+
+        // Groups can't contain transactions that are already in mempool
+        if self
+            .transactions
+            .get(&address)
+            .map_or(false, |account_txns| {
+                account_txns
+                    .range(first_sequence_number..=last_sequence_number)
+                    .next()
+                    .is_some()
+            })
+        {
+            return MempoolStatus::new(MempoolStatusCode::InvalidTransactionGroup)
+                .with_message("Transaction group overlaps transactions in mempool".to_string())
+                .with_rejection_reason(MempoolRejectionReason::Duplicate);
+        }
+
+        // Groups are broadcast in a single batch, so they must fit into one
+        let group_bytes: u64 = txns
+            .iter()
+            .map(|txn| txn.txn.raw_txn_bytes_len() as u64)
+            .sum();
+        if group_bytes > self.max_batch_bytes {
+            return MempoolStatus::new(MempoolStatusCode::InvalidTransactionGroup).with_message(
+                format!(
+                    "Transaction group is too large. Group size: {} bytes, Max size: {} bytes",
+                    group_bytes, self.max_batch_bytes,
+                ),
+            );
+        }
+
+        let mut inserted_sequence_numbers = vec![];
+        for txn in txns {
+            let sequence_number = txn.sequence_info.transaction_sequence_number;
+            let status = self.insert(txn);
+            if status.code != MempoolStatusCode::Accepted {
+                for sequence_number in inserted_sequence_numbers.into_iter().rev() {
+                    self.remove_transaction(&address, sequence_number);
+                }
+                return status;
+            }
+            inserted_sequence_numbers.push(sequence_number);
+        }
+        self.transaction_groups
+            .entry(address)
+            .or_default()
+            .insert(first_sequence_number, last_sequence_number);
+        counters::CORE_MEMPOOL_TRANSACTION_GROUP_SIZE
+            .observe(inserted_sequence_numbers.len() as f64);
+        MempoolStatus::new(MempoolStatusCode::Accepted)
+    }
+
+    /// Removes the transaction group that contains the given transaction (if any).
+    /// The remaining transactions of the group are no longer kept together.
+    fn remove_transaction_group(&mut self, address: &AccountAddress, sequence_number: u64) {
+        if let Some((first_sequence_number, _)) =
+            self.get_transaction_group(address, sequence_number)
+        {
+            if let Some(groups) = self.transaction_groups.get_mut(address) {
+                groups.remove(&first_sequence_number);
+                if groups.is_empty() {
+                    self.transaction_groups.remove(address);
+                }
+            }
+        }
+    }
+
     /// Insert transaction into TransactionStore. Performs validation checks and updates indexes.
     pub(crate) fn insert(&mut self, txn: MempoolTransaction) -> MempoolStatus {
         let address = txn.get_sender();
@@ -206,31 +326,41 @@ impl TransactionStore {
         if let Some(txns) = self.transactions.get_mut(&address) {
             if let Some(current_version) = txns.get_mut(&txn_seq_num) {
                 if current_version.txn.payload() != txn.txn.payload() {
-                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
-                        "Transaction already in mempool with a different payload".to_string(),
-                    );
+                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate)
+                        .with_message(
+                            "Transaction already in mempool with a different payload".to_string(),
+                        )
+                        .with_rejection_reason(MempoolRejectionReason::Duplicate);
                 } else if current_version.txn.expiration_timestamp_secs()
                     != txn.txn.expiration_timestamp_secs()
                 {
-                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
-                        "Transaction already in mempool with a different expiration timestamp"
-                            .to_string(),
-                    );
+                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate)
+                        .with_message(
+                            "Transaction already in mempool with a different expiration timestamp"
+                                .to_string(),
+                        )
+                        .with_rejection_reason(MempoolRejectionReason::Duplicate);
                 } else if current_version.txn.max_gas_amount() != txn.txn.max_gas_amount() {
-                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
-                        "Transaction already in mempool with a different max gas amount"
-                            .to_string(),
-                    );
+                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate)
+                        .with_message(
+                            "Transaction already in mempool with a different max gas amount"
+                                .to_string(),
+                        )
+                        .with_rejection_reason(MempoolRejectionReason::Duplicate);
                 } else if current_version.get_gas_price() < txn.get_gas_price() {
                     // Update txn if gas unit price is a larger value than before
                     if let Some(txn) = txns.remove(&txn_seq_num) {
                         self.index_remove(&txn);
+                        self.notify_eviction(&txn, EvictionReason::Replaced);
                     };
+                    self.lower_next_ready_sequence_number(&address, txn_seq_num);
                     gas_upgraded = true;
                 } else if current_version.get_gas_price() > txn.get_gas_price() {
-                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
-                        "Transaction already in mempool with a higher gas price".to_string(),
-                    );
+                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate)
+                        .with_message(
+                            "Transaction already in mempool with a higher gas price".to_string(),
+                        )
+                        .with_rejection_reason(MempoolRejectionReason::ReplacementUnderpriced);
                 } else {
                     // If the transaction is the same, it's an idempotent call
                     // Updating signers is not supported, the previous submission must fail
@@ -241,11 +371,20 @@ impl TransactionStore {
         }
 
         if self.check_is_full_after_eviction(&txn, acc_seq_num) {
-            return MempoolStatus::new(MempoolStatusCode::MempoolIsFull).with_message(format!(
-                "Mempool is full. Mempool size: {}, Capacity: {}",
-                self.system_ttl_index.size(),
-                self.capacity,
-            ));
+            // Non-ready transactions can't evict other transactions, so they
+            // are rejected because the parking lot is full.
+            let rejection_reason = if self.check_txn_ready(&txn, acc_seq_num) {
+                MempoolRejectionReason::FeeTooLowForLoad
+            } else {
+                MempoolRejectionReason::ParkingLotFull
+            };
+            return MempoolStatus::new(MempoolStatusCode::MempoolIsFull)
+                .with_message(format!(
+                    "Mempool is full. Mempool size: {}, Capacity: {}",
+                    self.system_ttl_index.size(),
+                    self.capacity,
+                ))
+                .with_rejection_reason(rejection_reason);
         }
 
         self.clean_committed_transactions(&address, acc_seq_num);
@@ -255,13 +394,13 @@ impl TransactionStore {
         if let Some(txns) = self.transactions.get_mut(&address) {
             // capacity check
             if txns.len() >= self.capacity_per_user {
-                return MempoolStatus::new(MempoolStatusCode::TooManyTransactions).with_message(
-                    format!(
+                return MempoolStatus::new(MempoolStatusCode::TooManyTransactions)
+                    .with_message(format!(
                         "Mempool over capacity for account. Number of transactions from account: {} Capacity per account: {}",
                         txns.len(),
                         self.capacity_per_user,
-                    ),
-                );
+                    ))
+                    .with_rejection_reason(MempoolRejectionReason::AccountQuotaExceeded);
             }
 
             // insert into storage and other indexes
@@ -279,6 +418,7 @@ impl TransactionStore {
             self.track_indices();
         }
         self.process_ready_transactions(&address, acc_seq_num);
+        self.park_transaction_if_not_ready(&address, txn_seq_num);
         MempoolStatus::new(MempoolStatusCode::Accepted)
     }
 
@@ -337,7 +477,12 @@ impl TransactionStore {
                             txn.sequence_info.transaction_sequence_number
                         ))
                     );
+                    self.lower_next_ready_sequence_number(
+                        &txn_pointer.sender,
+                        txn_pointer.sequence_number,
+                    );
                     self.index_remove(&txn);
+                    self.notify_eviction(&txn, EvictionReason::ParkingLotEvicted);
                 }
             }
         }
@@ -425,10 +570,18 @@ impl TransactionStore {
     /// - All transactions of a given account that are sequential to the current sequence number
     ///   should be included in both the PriorityIndex (ordering for Consensus) and
     ///   TimelineIndex (txns for SharedMempool).
-    /// - Other txns are considered to be "non-ready" and should be added to ParkingLotIndex.
+    /// - Other txns are considered to be "non-ready" and should be added to ParkingLotIndex
+    ///   (see `park_transaction_if_not_ready()`).
+    ///
+    /// Promotion starts at the next expected sequence number of the account (if the chain of
+    /// transactions before it is already ready), so promoting k transactions takes O(k).
     fn process_ready_transactions(&mut self, address: &AccountAddress, sequence_num: u64) {
         if let Some(txns) = self.transactions.get_mut(address) {
-            let mut min_seq = sequence_num;
+            let mut min_seq = match self.next_ready_sequence_numbers.get(address) {
+                Some(next_ready_seq) if *next_ready_seq > sequence_num => *next_ready_seq,
+                _ => sequence_num,
+            };
+            let mut num_promoted_txns = 0;
 
             while let Some(txn) = txns.get_mut(&min_seq) {
                 let process_ready = !self.priority_index.contains(txn);
@@ -450,32 +603,63 @@ impl TransactionStore {
 
                 // Remove txn from parking lot after it has been promoted to
                 // priority_index / timeline_index, i.e., txn status is ready.
-                self.parking_lot_index.remove(txn);
+                if self.parking_lot_index.contains(address, &min_seq) {
+                    self.parking_lot_index.remove(txn);
+                    if let Ok(time_delta) =
+                        SystemTime::now().duration_since(txn.insertion_info.insertion_time)
+                    {
+                        counters::CORE_MEMPOOL_PARKED_TXN_PROMOTION_LATENCY
+                            .observe(time_delta.as_secs_f64());
+                    }
+                    num_promoted_txns += 1;
+                }
                 min_seq += 1;
             }
+            self.next_ready_sequence_numbers.insert(*address, min_seq);
 
-            let mut parking_lot_txns = 0;
-            for (_, txn) in txns.range_mut((Bound::Excluded(min_seq), Bound::Unbounded)) {
-                match txn.timeline_state {
-                    TimelineState::Ready(_) => {},
-                    _ => {
-                        self.parking_lot_index.insert(txn);
-                        txn.was_parked = true;
-                        parking_lot_txns += 1;
-                    },
-                }
+            if num_promoted_txns > 0 {
+                counters::CORE_MEMPOOL_PARKED_TXNS_PROMOTED.observe(num_promoted_txns as f64);
             }
-
             trace!(
                 LogSchema::new(LogEntry::ProcessReadyTxns).account(*address),
                 first_ready_seq_num = sequence_num,
                 last_ready_seq_num = min_seq,
-                num_parked_txns = parking_lot_txns,
+                num_promoted_txns = num_promoted_txns,
             );
             self.track_indices();
         }
     }
 
+    /// Parks the given transaction if it is not ready, i.e., if it follows
+    /// a sequence number gap (and was not already ready before).
+    fn park_transaction_if_not_ready(&mut self, address: &AccountAddress, sequence_number: u64) {
+        let next_ready_seq = match self.next_ready_sequence_numbers.get(address) {
+            Some(next_ready_seq) if sequence_number >= *next_ready_seq => *next_ready_seq,
+            _ => return, // The transaction is ready
+        };
+        if let Some(txn) = self
+            .transactions
+            .get_mut(address)
+            .and_then(|txns| txns.get_mut(&sequence_number))
+        {
+            if !matches!(txn.timeline_state, TimelineState::Ready(_)) {
+                self.parking_lot_index.insert(txn);
+                txn.was_parked = true;
+                counters::CORE_MEMPOOL_SEQUENCE_NUMBER_GAP
+                    .observe((sequence_number - next_ready_seq) as f64);
+                self.track_indices();
+            }
+        }
+    }
+
+    /// Lowers the next expected sequence number of the account (if required),
+    /// when a transaction that is not committed is removed.
+    fn lower_next_ready_sequence_number(&mut self, address: &AccountAddress, sequence_number: u64) {
+        if let Some(next_ready_seq) = self.next_ready_sequence_numbers.get_mut(address) {
+            *next_ready_seq = min(*next_ready_seq, sequence_number);
+        }
+    }
+
     fn clean_committed_transactions(&mut self, address: &AccountAddress, sequence_number: u64) {
         // Remove all previous seq number transactions for this account.
         // This can happen if transactions are sent to multiple nodes and one of the
@@ -531,10 +715,22 @@ impl TransactionStore {
             }
         }
         if let Some(txn_to_remove) = txn_to_remove {
-            if let Some(txns) = self.transactions.get_mut(account) {
-                txns.remove(&sequence_number);
+            // The transactions of a group are kept together, so the rest of the group is
+            // removed as well (the transactions can only be committed all together).
+            let group = self.get_transaction_group(account, sequence_number);
+            let mut removed_txns = vec![];
+            removed_txns.extend(self.remove_transaction(account, sequence_number));
+            if let Some((first_sequence_number, last_sequence_number)) = group {
+                for group_sequence_number in first_sequence_number..=last_sequence_number {
+                    if group_sequence_number != sequence_number {
+                        removed_txns
+                            .extend(self.remove_transaction(account, group_sequence_number));
+                    }
+                }
+            }
+            for txn in &removed_txns {
+                self.notify_eviction(txn, EvictionReason::Rejected);
             }
-            self.index_remove(&txn_to_remove);
 
             if aptos_logger::enabled!(Level::Trace) {
                 let mut txns_log = TxnsLog::new();
@@ -547,6 +743,44 @@ impl TransactionStore {
         }
     }
 
+    /// Removes the given transaction from mempool (if it exists), and returns it
+    fn remove_transaction(
+        &mut self,
+        address: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<MempoolTransaction> {
+        let txn = self
+            .transactions
+            .get_mut(address)
+            .and_then(|txns| txns.remove(&sequence_number))?;
+        self.lower_next_ready_sequence_number(address, sequence_number);
+        self.index_remove(&txn);
+        Some(txn)
+    }
+
+    /// Subscribes to the eviction of the transaction with the given sender and sequence number
+    pub(crate) fn subscribe_eviction(
+        &mut self,
+        sender: AccountAddress,
+        sequence_number: u64,
+        callback: oneshot::Sender<TransactionEviction>,
+    ) {
+        self.eviction_notifier
+            .subscribe(sender, sequence_number, callback);
+        counters::CORE_MEMPOOL_EVICTION_SUBSCRIPTIONS
+            .set(self.eviction_notifier.num_subscriptions() as i64);
+    }
+
+    /// Notifies the eviction subscribers of the given (removed) transaction
+    fn notify_eviction(&mut self, txn: &MempoolTransaction, reason: EvictionReason) {
+        self.eviction_notifier.notify(
+            txn.get_sender(),
+            txn.sequence_info.transaction_sequence_number,
+            txn.get_committed_hash(),
+            reason,
+        );
+    }
+
     /// Removes transaction from all indexes. Only call after removing from main transactions DS.
     fn index_remove(&mut self, txn: &MempoolTransaction) {
         counters::CORE_MEMPOOL_REMOVED_TXNS.inc();
@@ -561,10 +795,13 @@ impl TransactionStore {
 
         // Remove account datastructures if there are no more transactions for the account.
         let address = &txn.get_sender();
+        self.remove_transaction_group(address, txn.sequence_info.transaction_sequence_number);
         if let Some(txns) = self.transactions.get(address) {
             if txns.is_empty() {
                 self.transactions.remove(address);
                 self.sequence_numbers.remove(address);
+                self.next_ready_sequence_numbers.remove(address);
+                self.transaction_groups.remove(address);
             }
         }
 
@@ -581,6 +818,7 @@ impl TransactionStore {
     ) -> (Vec<SignedTransaction>, MultiBucketTimelineIndexIds) {
         let mut batch = vec![];
         let mut batch_total_bytes: u64 = 0;
+        let mut batched_txns = HashSet::new();
         let mut last_timeline_id = timeline_id.id_per_bucket.clone();
 
         // Add as many transactions to the batch as possible
@@ -592,29 +830,51 @@ impl TransactionStore {
             .rev()
         {
             for (address, sequence_number) in bucket {
-                if let Some(txn) = self.get_mempool_txn(address, *sequence_number) {
-                    let transaction_bytes = txn.txn.raw_txn_bytes_len() as u64;
-                    if batch_total_bytes.saturating_add(transaction_bytes) > self.max_batch_bytes {
-                        break; // The batch is full
-                    } else {
-                        batch.push(txn.txn.clone());
-                        batch_total_bytes = batch_total_bytes.saturating_add(transaction_bytes);
-                        if let TimelineState::Ready(timeline_id) = txn.timeline_state {
-                            last_timeline_id[i] = timeline_id;
-                        }
-                        let bucket = self.timeline_index.get_bucket(txn.ranking_score);
-                        Mempool::log_txn_latency(
-                            &txn.insertion_info,
-                            bucket,
-                            BROADCAST_BATCHED_LABEL,
-                        );
-                        counters::core_mempool_txn_ranking_score(
-                            BROADCAST_BATCHED_LABEL,
-                            BROADCAST_BATCHED_LABEL,
-                            bucket,
-                            txn.ranking_score,
-                        );
-                    }
+                let txn = match self.get_mempool_txn(address, *sequence_number) {
+                    Some(txn) => txn,
+                    None => continue,
+                };
+
+                // The transactions of a group are broadcast together (in the same batch).
+                // Note: group transactions in other buckets may be broadcast again when
+                // their timeline is read, but re-broadcasts are idempotent for the peers.
+                let group_txns: Vec<_> = match self.get_transaction_group(address, *sequence_number)
+                {
+                    Some((first_sequence_number, last_sequence_number)) => (first_sequence_number
+                        ..=last_sequence_number)
+                        .filter_map(|sequence_number| {
+                            self.get_mempool_txn(address, sequence_number)
+                        })
+                        .collect(),
+                    None => vec![txn],
+                };
+                let group_txns: Vec<_> = group_txns
+                    .into_iter()
+                    .filter(|txn| !batched_txns.contains(&TxnPointer::from(*txn)))
+                    .collect();
+                let group_bytes: u64 = group_txns
+                    .iter()
+                    .map(|txn| txn.txn.raw_txn_bytes_len() as u64)
+                    .sum();
+                if batch_total_bytes.saturating_add(group_bytes) > self.max_batch_bytes {
+                    break; // The batch is full
+                }
+
+                for txn in group_txns {
+                    batch.push(txn.txn.clone());
+                    batched_txns.insert(TxnPointer::from(txn));
+                    let bucket = self.timeline_index.get_bucket(txn.ranking_score);
+                    Mempool::log_txn_latency(&txn.insertion_info, bucket, BROADCAST_BATCHED_LABEL);
+                    counters::core_mempool_txn_ranking_score(
+                        BROADCAST_BATCHED_LABEL,
+                        BROADCAST_BATCHED_LABEL,
+                        bucket,
+                        txn.ranking_score,
+                    );
+                }
+                batch_total_bytes = batch_total_bytes.saturating_add(group_bytes);
+                if let TimelineState::Ready(timeline_id) = txn.timeline_state {
+                    last_timeline_id[i] = timeline_id;
                 }
             }
         }
@@ -680,17 +940,19 @@ impl TransactionStore {
     }
 
     fn gc(&mut self, now: Duration, by_system_ttl: bool) {
-        let (metric_label, index, log_event) = if by_system_ttl {
+        let (metric_label, index, log_event, eviction_reason) = if by_system_ttl {
             (
                 counters::GC_SYSTEM_TTL_LABEL,
                 &mut self.system_ttl_index,
                 LogEvent::SystemTTLExpiration,
+                EvictionReason::SystemTtlExpired,
             )
         } else {
             (
                 counters::GC_CLIENT_EXP_LABEL,
                 &mut self.expiration_time_index,
                 LogEvent::ClientExpiration,
+                EvictionReason::Expired,
             )
         };
         counters::CORE_MEMPOOL_GC_EVENT_COUNT
@@ -707,6 +969,7 @@ impl TransactionStore {
             false => TxnsLog::new_with_max(10),
         };
         while let Some(key) = gc_iter.next() {
+            self.lower_next_ready_sequence_number(&key.address, key.sequence_number);
             if let Some(txns) = self.transactions.get_mut(&key.address) {
                 let park_range_start = Bound::Excluded(key.sequence_number);
                 let park_range_end = gc_iter
@@ -745,10 +1008,18 @@ impl TransactionStore {
 
                     // remove txn
                     self.index_remove(&txn);
+                    self.notify_eviction(&txn, eviction_reason);
                 }
             }
         }
 
+        // Clean up the eviction subscriptions that are no longer waited for
+        if by_system_ttl {
+            self.eviction_notifier.remove_canceled_subscriptions();
+            counters::CORE_MEMPOOL_EVICTION_SUBSCRIPTIONS
+                .set(self.eviction_notifier.num_subscriptions() as i64);
+        }
+
         if !gc_txns_log.is_empty() {
             debug!(LogSchema::event_log(LogEntry::GCRemoveTxns, log_event).txns(gc_txns_log));
         } else {
@@ -761,6 +1032,43 @@ impl TransactionStore {
         self.priority_index.iter()
     }
 
+    /// Returns the ready (i.e., non-parked) transactions in priority order,
+    /// along with their mempool insertion times.
+    pub(crate) fn get_ready_transactions(&self) -> Vec<(SignedTransaction, SystemTime)> {
+        self.iter_queue()
+            .filter_map(|key| {
+                self.get_mempool_txn(
+                    &key.address,
+                    key.sequence_number.transaction_sequence_number,
+                )
+                .map(|txn| (txn.txn.clone(), txn.insertion_info.insertion_time))
+            })
+            .collect()
+    }
+
+    /// Returns the parked (i.e., non-ready) transactions, along with their mempool insertion times
+    pub(crate) fn get_parked_transactions(&self) -> Vec<(SignedTransaction, SystemTime)> {
+        self.parking_lot_index
+            .iter()
+            .filter_map(|pointer| {
+                self.get_mempool_txn(&pointer.sender, pointer.sequence_number)
+                    .map(|txn| (txn.txn.clone(), txn.insertion_info.insertion_time))
+            })
+            .collect()
+    }
+
+    /// Returns the gas unit prices of the ready (i.e., non-parked) transactions
+    pub(crate) fn get_ready_gas_prices(&self) -> Vec<u64> {
+        self.iter_queue()
+            .filter_map(|key| {
+                self.get_gas_unit_price(
+                    &key.address,
+                    key.sequence_number.transaction_sequence_number,
+                )
+            })
+            .collect()
+    }
+
     pub(crate) fn gen_snapshot(&self) -> TxnsLog {
         let mut txns_log = TxnsLog::new();
         for (account, txns) in self.transactions.iter() {
@@ -781,6 +1089,100 @@ impl TransactionStore {
         txns_log
     }
 
+    /// Generates a capacity report for the transactions currently in the store
+    pub(crate) fn gen_capacity_report(
+        &self,
+        now: SystemTime,
+        throughput: ThroughputReport,
+        max_top_accounts: usize,
+    ) -> CapacityReport {
+        // Create an (initially empty) report for each bucket
+        let mut buckets: Vec<BucketReport> = self
+            .timeline_index
+            .get_sizes()
+            .into_iter()
+            .map(|(bucket_min, _)| BucketReport {
+                bucket_min: bucket_min.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let mut ages = new_age_buckets();
+        let mut accounts = Vec::with_capacity(self.transactions.len());
+        let mut num_parked_txns = 0;
+
+        // Aggregate the transactions by bucket, account and age
+        for (account, txns) in self.transactions.iter() {
+            let mut account_report = AccountReport {
+                address: *account,
+                num_txns: txns.len(),
+                size_bytes: 0,
+            };
+            for (seq_num, txn) in txns.iter() {
+                let txn_bytes = txn.get_estimated_bytes();
+                let is_parked = self.parking_lot_index.contains(account, seq_num);
+                if is_parked {
+                    num_parked_txns += 1;
+                }
+                account_report.size_bytes += txn_bytes;
+
+                let bucket_min = self.get_bucket(txn.ranking_score);
+                if let Some(bucket) = buckets.iter_mut().find(|b| b.bucket_min == bucket_min) {
+                    bucket.num_txns += 1;
+                    bucket.size_bytes += txn_bytes;
+                    if is_parked {
+                        bucket.num_parked_txns += 1;
+                    }
+                }
+
+                let age = now
+                    .duration_since(txn.insertion_info.insertion_time)
+                    .unwrap_or_default();
+                let age_bucket = &mut ages[get_age_bucket_index(age)];
+                age_bucket.num_txns += 1;
+                age_bucket.size_bytes += txn_bytes;
+            }
+            accounts.push(account_report);
+        }
+
+        // Identify the accounts with the most transactions
+        let num_txns = self.system_ttl_index.size();
+        let num_accounts = accounts.len();
+        let num_accounts_at_capacity = accounts
+            .iter()
+            .filter(|account| account.num_txns >= self.capacity_per_user)
+            .count();
+        accounts.sort_by(|a, b| b.num_txns.cmp(&a.num_txns).then(a.address.cmp(&b.address)));
+        accounts.truncate(max_top_accounts);
+        let num_top_accounts_txns: usize = accounts.iter().map(|account| account.num_txns).sum();
+
+        let utilization = UtilizationReport {
+            num_txns,
+            num_parked_txns,
+            capacity: self.capacity,
+            txns_utilization: num_txns as f64 / self.capacity.max(1) as f64,
+            size_bytes: self.size_bytes,
+            capacity_bytes: self.capacity_bytes,
+            bytes_utilization: self.size_bytes as f64 / self.capacity_bytes.max(1) as f64,
+        };
+        let eviction_horizon = EvictionHorizonReport::new(&utilization, &throughput);
+
+        CapacityReport {
+            generated_at_usecs: aptos_infallible::duration_since_epoch_at(&now).as_micros() as u64,
+            utilization,
+            buckets,
+            accounts: AccountConcentrationReport {
+                num_accounts,
+                capacity_per_user: self.capacity_per_user,
+                num_accounts_at_capacity,
+                top_accounts_txns_share: num_top_accounts_txns as f64 / num_txns.max(1) as f64,
+                top_accounts: accounts,
+            },
+            ages,
+            throughput,
+            eviction_horizon,
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn get_parking_lot_size(&self) -> usize {
         self.parking_lot_index.size()
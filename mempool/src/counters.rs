@@ -65,6 +65,9 @@ pub const SUCCESS_LABEL: &str = "success";
 // Bounded executor task labels
 pub const CLIENT_EVENT_LABEL: &str = "client_event";
 pub const CLIENT_EVENT_GET_TXN_LABEL: &str = "client_event_get_txn";
+pub const CLIENT_EVENT_SUBMIT_GROUP_LABEL: &str = "client_event_submit_group";
+pub const CLIENT_EVENT_ESTIMATE_GAS_PRICE_LABEL: &str = "client_event_estimate_gas_price";
+pub const CLIENT_EVENT_SUBSCRIBE_EVICTION_LABEL: &str = "client_event_subscribe_eviction";
 pub const RECONFIG_EVENT_LABEL: &str = "reconfig";
 pub const PEER_BROADCAST_EVENT_LABEL: &str = "peer_broadcast";
 
@@ -72,15 +75,31 @@ pub const PEER_BROADCAST_EVENT_LABEL: &str = "peer_broadcast";
 pub const SPAWN_LABEL: &str = "spawn";
 pub const START_LABEL: &str = "start";
 
+// Mempool persistence result labels
+pub const PERSISTED_LABEL: &str = "persisted";
+pub const RESTORED_LABEL: &str = "restored";
+pub const DISCARDED_EXPIRED_LABEL: &str = "discarded_expired";
+pub const DISCARDED_INVALID_LABEL: &str = "discarded_invalid";
+pub const DISCARDED_CORRUPTED_LABEL: &str = "discarded_corrupted";
+
 // Mempool network msg failure type labels:
 pub const BROADCAST_TXNS: &str = "broadcast_txns";
 pub const ACK_TXNS: &str = "ack_txns";
+pub const MISSING_TXNS_REQUEST: &str = "missing_txns_request";
 
 // Broadcast/ACK type labels
 pub const EXPIRED_BROADCAST_LABEL: &str = "expired";
 pub const RETRY_BROADCAST_LABEL: &str = "retry";
 pub const BACKPRESSURE_BROADCAST_LABEL: &str = "backpressure";
 
+// Digest broadcast labels
+pub const DIGEST_ANNOUNCED_LABEL: &str = "announced";
+pub const DIGEST_REQUESTED_LABEL: &str = "requested";
+
+// Adaptive broadcast batch labels
+pub const SCHEDULED_BATCH_LABEL: &str = "scheduled";
+pub const ACKED_BATCH_LABEL: &str = "acked";
+
 // ACK direction labels
 pub const RECEIVED_LABEL: &str = "received";
 pub const SENT_LABEL: &str = "sent";
@@ -163,6 +182,74 @@ pub static CORE_MEMPOOL_REMOVED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter tracking number of txns evicted from core mempool (without being committed), by reason
+pub static CORE_MEMPOOL_EVICTED_TXNS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_core_mempool_evicted_txns_count",
+        "Number of txns evicted from core mempool (without being committed), by reason",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+/// Gauge tracking the number of txns with eviction subscriptions in core mempool
+pub static CORE_MEMPOOL_EVICTION_SUBSCRIPTIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_core_mempool_eviction_subscriptions",
+        "Number of txns with eviction subscriptions in core mempool"
+    )
+    .unwrap()
+});
+
+/// Counter tracking the sequence number gap of txns that are parked upon insertion
+pub static CORE_MEMPOOL_SEQUENCE_NUMBER_GAP: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_core_mempool_sequence_number_gap",
+        "Sequence number gap (to the next expected sequence number) of txns parked upon insertion",
+        TRANSACTION_COUNT_BUCKETS.clone()
+    )
+    .unwrap()
+});
+
+/// Counter tracking number of parked txns promoted to ready when a sequence number gap is filled
+pub static CORE_MEMPOOL_PARKED_TXNS_PROMOTED: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_core_mempool_parked_txns_promoted",
+        "Number of parked txns promoted to ready when a sequence number gap is filled",
+        TRANSACTION_COUNT_BUCKETS.clone()
+    )
+    .unwrap()
+});
+
+/// Histogram tracking the number of transactions in the groups inserted into core mempool
+pub static CORE_MEMPOOL_TRANSACTION_GROUP_SIZE: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_core_mempool_transaction_group_size",
+        "Number of transactions in the transaction groups inserted into core mempool",
+        TRANSACTION_COUNT_BUCKETS.clone()
+    )
+    .unwrap()
+});
+
+/// Counter tracking the transactions dropped from the batches pulled by consensus, so that
+/// transaction groups are not split across batches
+pub static CORE_MEMPOOL_GROUP_TRUNCATED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_core_mempool_group_truncated_txns",
+        "Number of transactions dropped from pulled batches to keep transaction groups together"
+    )
+    .unwrap()
+});
+
+/// Counter tracking how long parked txns stayed in core mempool before being promoted to ready
+pub static CORE_MEMPOOL_PARKED_TXN_PROMOTION_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_core_mempool_parked_txn_promotion_latency",
+        "How long a parked txn stayed in core mempool before being promoted to ready"
+    )
+    .unwrap()
+});
+
 /// Counter tracking number of txns received that are idempotent duplicates
 pub static CORE_MEMPOOL_IDEMPOTENT_TXNS: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -195,6 +282,46 @@ static CORE_MEMPOOL_TXN_COMMIT_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub const CHAIN_COMPLETE_LABEL: &str = "complete";
+pub const CHAIN_PARTIAL_LABEL: &str = "partial";
+
+/// Counter tracking the chains (i.e., the transactions of an account) in the pulled batches,
+/// by whether the ready transactions of the account are completely included
+static CORE_MEMPOOL_BATCH_CHAINS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_core_mempool_batch_chains_count",
+        "Number of account chains in the batches pulled by consensus, by completeness",
+        &["completeness"]
+    )
+    .unwrap()
+});
+
+/// Histogram tracking the length of the chains (i.e., the transactions of an account) in
+/// the pulled batches
+static CORE_MEMPOOL_BATCH_CHAIN_LENGTH: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_core_mempool_batch_chain_length",
+        "Number of transactions of an account chain in the batches pulled by consensus",
+        &["completeness"],
+        TRANSACTION_COUNT_BUCKETS.clone()
+    )
+    .unwrap()
+});
+
+pub fn core_mempool_batch_chain(complete: bool, length: u64) {
+    let completeness = if complete {
+        CHAIN_COMPLETE_LABEL
+    } else {
+        CHAIN_PARTIAL_LABEL
+    };
+    CORE_MEMPOOL_BATCH_CHAINS
+        .with_label_values(&[completeness])
+        .inc();
+    CORE_MEMPOOL_BATCH_CHAIN_LENGTH
+        .with_label_values(&[completeness])
+        .observe(length as f64);
+}
+
 pub fn core_mempool_txn_ranking_score(
     stage: &'static str,
     status: &str,
@@ -461,6 +588,38 @@ pub fn shared_mempool_transactions_processed_inc(status: &str, network: &str) {
         .inc();
 }
 
+/// Counter for the results of client submission quota checks
+static SHARED_MEMPOOL_SUBMISSION_QUOTA_RESULTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_shared_mempool_submission_quota_results",
+        "Number of client submissions accepted or rejected by the submission quotas",
+        &["result"]
+    )
+    .unwrap()
+});
+
+pub fn submission_quota_result_inc(result: &str) {
+    SHARED_MEMPOOL_SUBMISSION_QUOTA_RESULTS
+        .with_label_values(&[result])
+        .inc();
+}
+
+/// Counter for the transactions persisted (by snapshots), and restored (or discarded) on startup
+static PERSISTED_TRANSACTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_mempool_persisted_transactions",
+        "Number of mempool transactions persisted across restarts, by result",
+        &["result"]
+    )
+    .unwrap()
+});
+
+pub fn persisted_transactions_inc_by(result: &str, num_txns: usize) {
+    PERSISTED_TRANSACTIONS
+        .with_label_values(&[result])
+        .inc_by(num_txns as u64);
+}
+
 /// Counter for number of transactions in each mempool broadcast sent
 static SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
@@ -492,6 +651,64 @@ pub fn shared_mempool_broadcast_type_inc(network_id: NetworkId, label: &str) {
         .inc();
 }
 
+/// Counter for number of broadcast batches scheduled (i.e., sent) and ACK'ed, by the
+/// bandwidth class of the peer (only tracked when adaptive broadcasts are enabled)
+static SHARED_MEMPOOL_ADAPTIVE_BROADCAST_BATCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_shared_mempool_adaptive_broadcast_batches",
+        "Number of broadcast batches scheduled and ACK'ed, by peer bandwidth class",
+        &["network", "bandwidth_class", "state"]
+    )
+    .unwrap()
+});
+
+pub fn shared_mempool_adaptive_broadcast_batches_inc(
+    network_id: NetworkId,
+    bandwidth_class: &'static str,
+    state: &'static str,
+) {
+    SHARED_MEMPOOL_ADAPTIVE_BROADCAST_BATCHES
+        .with_label_values(&[network_id.as_str(), bandwidth_class, state])
+        .inc();
+}
+
+/// Counter for number of transactions announced (and requested) in digest broadcasts received
+static SHARED_MEMPOOL_DIGEST_TRANSACTION_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_shared_mempool_digest_transaction_count",
+        "Number of transactions announced and requested in digest broadcasts received",
+        &["network", "type"]
+    )
+    .unwrap()
+});
+
+pub fn shared_mempool_digest_transactions_inc(
+    network_id: NetworkId,
+    label: &'static str,
+    num_txns: usize,
+) {
+    SHARED_MEMPOOL_DIGEST_TRANSACTION_COUNT
+        .with_label_values(&[network_id.as_str(), label])
+        .inc_by(num_txns as u64);
+}
+
+/// Counter for transaction bytes that were not received because digest
+/// broadcasts announced transactions that were already in mempool
+static SHARED_MEMPOOL_DIGEST_SAVED_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_shared_mempool_digest_saved_bytes",
+        "Number of transaction bytes saved by digest broadcasts",
+        &["network"]
+    )
+    .unwrap()
+});
+
+pub fn shared_mempool_digest_saved_bytes_inc(network_id: NetworkId, num_bytes: u64) {
+    SHARED_MEMPOOL_DIGEST_SAVED_BYTES
+        .with_label_values(&[network_id.as_str()])
+        .inc_by(num_bytes);
+}
+
 static SHARED_MEMPOOL_ACK_TYPE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "aptos_shared_mempool_ack_count",
@@ -617,6 +834,15 @@ pub fn active_upstream_peers(network_id: &NetworkId) -> IntGauge {
     ACTIVE_UPSTREAM_PEERS_COUNT.with_label_values(&[network_id.as_str()])
 }
 
+/// Whether consensus backpressure is currently slowing down broadcasts and peer intake
+pub static CONSENSUS_BACKPRESSURE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_mempool_consensus_backpressure",
+        "Whether consensus backpressure is slowing down broadcasts and peer intake (1 = yes)"
+    )
+    .unwrap()
+});
+
 /// Duration of each run of the event loop.
 pub static MAIN_LOOP: Lazy<DurationHistogram> = Lazy::new(|| {
     DurationHistogram::new(
@@ -58,12 +58,19 @@
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod tests;
+pub use core_mempool::{
+    AccountConcentrationReport, AccountReport, AgeBucketReport, BucketReport, CapacityReport,
+    EvictionHorizonReport, EvictionReason, GasPriceEstimate, MempoolCapacityReporter,
+    ThroughputReport, TransactionEviction, UtilizationReport, DEFAULT_MAX_TOP_ACCOUNTS,
+};
 pub use shared_mempool::{
     bootstrap, network,
     network::MempoolSyncMsg,
+    persistence::MempoolPersistence,
+    submission_quotas::{SubmissionQuotaRejection, SubmissionQuotaSummary, SubmissionQuotas},
     types::{
         MempoolClientRequest, MempoolClientSender, MempoolEventsReceiver, QuorumStoreRequest,
-        QuorumStoreResponse, SubmissionStatus,
+        QuorumStoreResponse, SubmissionSource, SubmissionStatus,
     },
 };
 #[cfg(any(test, feature = "fuzzing"))]
@@ -156,12 +156,14 @@ pub enum LogEntry {
     ReconfigUpdate,
     JsonRpc,
     GetTransaction,
+    EstimateGasPrice,
     GetBlock,
     QuorumStore,
     StateSyncCommit,
     BroadcastTransaction,
     BroadcastACK,
     ReceiveACK,
+    MissingTxnsRequest,
     InvariantViolated,
     AddTxn,
     RemoveTxn,
@@ -12,7 +12,10 @@ use crate::{
     shared_mempool::{
         tasks,
         tasks::process_committed_transactions,
-        types::{notify_subscribers, ScheduledBroadcast, SharedMempool, SharedMempoolNotification},
+        types::{
+            notify_subscribers, MultiBatchId, ScheduledBroadcast, SharedMempool,
+            SharedMempoolNotification, TransactionGroup,
+        },
     },
     MempoolEventsReceiver, QuorumStoreRequest,
 };
@@ -30,7 +33,10 @@ use aptos_network::{
     },
     protocols::network::Event,
 };
-use aptos_types::on_chain_config::{OnChainConfigPayload, OnChainConfigProvider};
+use aptos_types::{
+    on_chain_config::{OnChainConfigPayload, OnChainConfigProvider},
+    transaction::SignedTransaction,
+};
 use aptos_vm_validator::vm_validator::TransactionValidation;
 use futures::{
     channel::mpsc,
@@ -153,7 +159,13 @@ async fn handle_client_request<NetworkClient, TransactionValidator>(
     TransactionValidator: TransactionValidation + 'static,
 {
     match request {
-        MempoolClientRequest::SubmitTransaction(txn, callback) => {
+        MempoolClientRequest::SubmitTransaction(txn, source, callback) => {
+            // Reject the submission early if the source has exceeded its quota
+            if let Err(rejection) = smp.submission_quotas.check_and_record_submission(&source) {
+                tasks::reject_client_transaction_submission(rejection, callback);
+                return;
+            }
+
             // This timer measures how long it took for the bounded executor to *schedule* the
             // task.
             let _timer = counters::task_spawn_latency_timer(
@@ -174,6 +186,33 @@ async fn handle_client_request<NetworkClient, TransactionValidator>(
                 ))
                 .await;
         },
+        MempoolClientRequest::SubmitTransactionGroup(txns, source, callback) => {
+            // Reject the submission early if the source has exceeded its quota
+            if let Err(rejection) = smp.submission_quotas.check_and_record_submission(&source) {
+                tasks::reject_client_transaction_submission(rejection, callback);
+                return;
+            }
+
+            // This timer measures how long it took for the bounded executor to *schedule* the
+            // task.
+            let _timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_SUBMIT_GROUP_LABEL,
+                counters::SPAWN_LABEL,
+            );
+            // This timer measures how long it took for the task to go from scheduled to started.
+            let task_start_timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_SUBMIT_GROUP_LABEL,
+                counters::START_LABEL,
+            );
+            bounded_executor
+                .spawn(tasks::process_client_transaction_group_submission(
+                    smp.clone(),
+                    txns,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        },
         MempoolClientRequest::GetTransactionByHash(hash, callback) => {
             // This timer measures how long it took for the bounded executor to *schedule* the
             // task.
@@ -195,6 +234,50 @@ async fn handle_client_request<NetworkClient, TransactionValidator>(
                 ))
                 .await;
         },
+        MempoolClientRequest::EstimateGasPrice(percentile, horizon_blocks, callback) => {
+            // This timer measures how long it took for the bounded executor to *schedule* the
+            // task.
+            let _timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_ESTIMATE_GAS_PRICE_LABEL,
+                counters::SPAWN_LABEL,
+            );
+            // This timer measures how long it took for the task to go from scheduled to started.
+            let task_start_timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_ESTIMATE_GAS_PRICE_LABEL,
+                counters::START_LABEL,
+            );
+            bounded_executor
+                .spawn(tasks::process_client_estimate_gas_price(
+                    smp.clone(),
+                    percentile,
+                    horizon_blocks,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        },
+        MempoolClientRequest::SubscribeTransactionEviction(sender, sequence_number, callback) => {
+            // This timer measures how long it took for the bounded executor to *schedule* the
+            // task.
+            let _timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_SUBSCRIBE_EVICTION_LABEL,
+                counters::SPAWN_LABEL,
+            );
+            // This timer measures how long it took for the task to go from scheduled to started.
+            let task_start_timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_SUBSCRIBE_EVICTION_LABEL,
+                counters::START_LABEL,
+            );
+            bounded_executor
+                .spawn(tasks::process_client_subscribe_eviction(
+                    smp.clone(),
+                    sender,
+                    sequence_number,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        },
     }
 }
 
@@ -293,38 +376,32 @@ async fn handle_network_event<NetworkClient, TransactionValidator>(
                     request_id,
                     transactions,
                 } => {
-                    let smp_clone = smp.clone();
                     let peer = PeerNetworkId::new(network_id, peer_id);
-                    let ineligible_for_broadcast = (smp.network_interface.is_validator()
-                        && !smp.broadcast_within_validator_network())
-                        || smp.network_interface.is_upstream_peer(&peer, None);
-                    let timeline_state = if ineligible_for_broadcast {
-                        TimelineState::NonQualified
-                    } else {
-                        TimelineState::NotReady
-                    };
-                    // This timer measures how long it took for the bounded executor to
-                    // *schedule* the task.
-                    let _timer = counters::task_spawn_latency_timer(
-                        counters::PEER_BROADCAST_EVENT_LABEL,
-                        counters::SPAWN_LABEL,
-                    );
-                    // This timer measures how long it took for the task to go from scheduled
-                    // to started.
-                    let task_start_timer = counters::task_spawn_latency_timer(
-                        counters::PEER_BROADCAST_EVENT_LABEL,
-                        counters::START_LABEL,
-                    );
-                    bounded_executor
-                        .spawn(tasks::process_transaction_broadcast(
-                            smp_clone,
-                            transactions,
-                            request_id,
-                            timeline_state,
-                            peer,
-                            task_start_timer,
-                        ))
-                        .await;
+                    handle_transaction_broadcast(
+                        bounded_executor,
+                        smp,
+                        peer,
+                        request_id,
+                        transactions,
+                        vec![],
+                    )
+                    .await;
+                },
+                MempoolSyncMsg::BroadcastTransactionGroupsRequest {
+                    request_id,
+                    transactions,
+                    transaction_groups,
+                } => {
+                    let peer = PeerNetworkId::new(network_id, peer_id);
+                    handle_transaction_broadcast(
+                        bounded_executor,
+                        smp,
+                        peer,
+                        request_id,
+                        transactions,
+                        transaction_groups,
+                    )
+                    .await;
                 },
                 MempoolSyncMsg::BroadcastTransactionsResponse {
                     request_id,
@@ -340,6 +417,17 @@ async fn handle_network_event<NetworkClient, TransactionValidator>(
                         ack_timestamp,
                     );
                 },
+                MempoolSyncMsg::BroadcastTransactionDigestsRequest {
+                    request_id,
+                    digests,
+                } => {
+                    let peer = PeerNetworkId::new(network_id, peer_id);
+                    tasks::process_transaction_digests(smp, digests, request_id, peer);
+                },
+                MempoolSyncMsg::MissingTransactionsRequest { request_id, hashes } => {
+                    let peer = PeerNetworkId::new(network_id, peer_id);
+                    tasks::process_missing_transactions_request(smp, hashes, request_id, peer);
+                },
             }
         },
         Event::RpcRequest(peer_id, _msg, _, _res_tx) => {
@@ -353,6 +441,52 @@ async fn handle_network_event<NetworkClient, TransactionValidator>(
     }
 }
 
+/// Spawns a task to process the transactions (and transaction groups) broadcast by a peer
+async fn handle_transaction_broadcast<NetworkClient, TransactionValidator>(
+    bounded_executor: &BoundedExecutor,
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    peer: PeerNetworkId,
+    request_id: MultiBatchId,
+    transactions: Vec<SignedTransaction>,
+    transaction_groups: Vec<TransactionGroup>,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg> + 'static,
+    TransactionValidator: TransactionValidation + 'static,
+{
+    let smp_clone = smp.clone();
+    let ineligible_for_broadcast = (smp.network_interface.is_validator()
+        && !smp.broadcast_within_validator_network())
+        || smp.network_interface.is_upstream_peer(&peer, None);
+    let timeline_state = if ineligible_for_broadcast {
+        TimelineState::NonQualified
+    } else {
+        TimelineState::NotReady
+    };
+    // This timer measures how long it took for the bounded executor to
+    // *schedule* the task.
+    let _timer = counters::task_spawn_latency_timer(
+        counters::PEER_BROADCAST_EVENT_LABEL,
+        counters::SPAWN_LABEL,
+    );
+    // This timer measures how long it took for the task to go from scheduled
+    // to started.
+    let task_start_timer = counters::task_spawn_latency_timer(
+        counters::PEER_BROADCAST_EVENT_LABEL,
+        counters::START_LABEL,
+    );
+    bounded_executor
+        .spawn(tasks::process_transaction_broadcast(
+            smp_clone,
+            transactions,
+            transaction_groups,
+            request_id,
+            timeline_state,
+            peer,
+            task_start_timer,
+        ))
+        .await;
+}
+
 async fn handle_update_peers<NetworkClient, TransactionValidator>(
     peers_and_metadata: Arc<PeersAndMetadata>,
     smp: &mut SharedMempool<NetworkClient, TransactionValidator>,
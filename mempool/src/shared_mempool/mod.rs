@@ -3,8 +3,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod network;
+pub mod persistence;
 mod priority;
 mod runtime;
+pub mod submission_quotas;
 pub(crate) mod types;
 pub use runtime::bootstrap;
 #[cfg(any(test, feature = "fuzzing"))]
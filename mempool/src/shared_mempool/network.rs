@@ -5,14 +5,15 @@
 //! Interface between Mempool and Network layers.
 
 use crate::{
+    core_mempool::CoreMempool,
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
     shared_mempool::{
         priority::PrioritizedPeersState,
         tasks,
         types::{
-            notify_subscribers, MultiBatchId, PeerSyncState, SharedMempool,
-            SharedMempoolNotification,
+            notify_subscribers, BandwidthClass, MultiBatchId, PeerSyncState, SharedMempool,
+            SharedMempoolNotification, TransactionGroup,
         },
     },
 };
@@ -20,15 +21,17 @@ use aptos_config::{
     config::{MempoolConfig, RoleType},
     network_id::PeerNetworkId,
 };
-use aptos_infallible::RwLock;
+use aptos_crypto::HashValue;
+use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::prelude::*;
 use aptos_netcore::transport::ConnectionOrigin;
 use aptos_network::{
     application::{error::Error, interface::NetworkClientInterface, metadata::PeerMetadata},
     transport::ConnectionMetadata,
+    ProtocolId,
 };
 use aptos_time_service::TimeService;
-use aptos_types::transaction::SignedTransaction;
+use aptos_types::{account_address::AccountAddress, transaction::SignedTransaction};
 use aptos_vm_validator::vm_validator::TransactionValidation;
 use fail::fail_point;
 use serde::{Deserialize, Serialize};
@@ -58,6 +61,46 @@ pub enum MempoolSyncMsg {
         /// A backpressure signal from the recipient when it is overwhelmed (e.g., mempool is full).
         backoff: bool,
     },
+    /// Broadcast announcement issued by the sender. This is sent (instead of the
+    /// full transactions) to peers that support digest announcements.
+    BroadcastTransactionDigestsRequest {
+        request_id: MultiBatchId,
+        digests: Vec<TransactionDigest>,
+    },
+    /// Request issued by the receiver of a digest announcement, for the announced
+    /// transactions that are missing from its mempool. The sender responds with a
+    /// `BroadcastTransactionsRequest` (for the same request id) that is acked as usual.
+    MissingTransactionsRequest {
+        request_id: MultiBatchId,
+        hashes: Vec<HashValue>,
+    },
+    /// Broadcast request issued by the sender, for a batch that contains transaction groups.
+    /// This is sent (instead of `BroadcastTransactionsRequest`) to peers that support groups,
+    /// so that they keep the groups together too. It is acked as usual.
+    BroadcastTransactionGroupsRequest {
+        request_id: MultiBatchId,
+        transactions: Vec<SignedTransaction>,
+        transaction_groups: Vec<TransactionGroup>,
+    },
+}
+
+/// A compact summary of a transaction, used to announce broadcasts
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionDigest {
+    /// The committed hash of the transaction
+    pub hash: HashValue,
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+}
+
+impl TransactionDigest {
+    pub fn new(transaction: &SignedTransaction) -> Self {
+        Self {
+            hash: transaction.clone().committed_hash(),
+            sender: transaction.sender(),
+            sequence_number: transaction.sequence_number(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -256,6 +299,16 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
                 .with_label_values(&[network_id.as_str()])
                 .observe(rtt.as_secs_f64());
 
+            if self.mempool_config.adaptive_broadcast.enabled {
+                let bandwidth_class = self.get_bandwidth_class_for_state(sync_state);
+                counters::shared_mempool_adaptive_broadcast_batches_inc(
+                    network_id,
+                    bandwidth_class.get_label(),
+                    counters::ACKED_BATCH_LABEL,
+                );
+                sync_state.broadcast_info.window.record_ack(rtt);
+            }
+
             counters::shared_mempool_pending_broadcasts(&peer).dec();
         } else {
             trace!(
@@ -297,6 +350,39 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
         }
     }
 
+    /// Returns the bandwidth class of the given peer. All peers are considered
+    /// normal if adaptive broadcasts are disabled.
+    pub fn get_bandwidth_class(&self, peer: &PeerNetworkId) -> BandwidthClass {
+        self.sync_states
+            .read()
+            .get(peer)
+            .map_or(BandwidthClass::Normal, |state| {
+                self.get_bandwidth_class_for_state(state)
+            })
+    }
+
+    /// Returns the bandwidth class of the peer with the given sync state
+    fn get_bandwidth_class_for_state(&self, state: &PeerSyncState) -> BandwidthClass {
+        let adaptive_broadcast_config = &self.mempool_config.adaptive_broadcast;
+        if adaptive_broadcast_config.enabled {
+            state
+                .broadcast_info
+                .window
+                .bandwidth_class(adaptive_broadcast_config)
+        } else {
+            BandwidthClass::Normal
+        }
+    }
+
+    /// Records a broadcast error (i.e., an expired or failed broadcast) for the given peer
+    fn record_broadcast_error(&self, peer: &PeerNetworkId) {
+        if self.mempool_config.adaptive_broadcast.enabled {
+            if let Some(state) = self.sync_states.write().get_mut(peer) {
+                state.broadcast_info.window.record_error();
+            }
+        }
+    }
+
     /// Peers are prioritized when the local is a validator, or it's within the default failovers.
     /// One is added for the primary peer
     fn check_peer_prioritized(&self, peer: PeerNetworkId) -> Result<(), BroadcastError> {
@@ -388,6 +474,8 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
             match std::cmp::max(expired_batch_id, retry_batch_id) {
                 Some(id) => {
                     let metric_label = if Some(id) == expired_batch_id {
+                        // The peer didn't ACK the batch in time
+                        state.broadcast_info.window.record_error();
                         Some(counters::EXPIRED_BROADCAST_LABEL)
                     } else {
                         Some(counters::RETRY_BROADCAST_LABEL)
@@ -397,11 +485,13 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
                     (id.clone(), txns, metric_label)
                 },
                 None => {
-                    // Fresh broadcast
-                    let (txns, new_timeline_id) = mempool.read_timeline(
-                        &state.timeline_id,
+                    // Fresh broadcast (the batch size depends on the bandwidth class of the peer)
+                    let batch_size = self.get_bandwidth_class_for_state(state).batch_size(
                         self.mempool_config.shared_mempool_batch_size,
+                        self.mempool_config.adaptive_broadcast.window_scaling_factor,
                     );
+                    let (txns, new_timeline_id) =
+                        mempool.read_timeline(&state.timeline_id, batch_size);
                     (
                         MultiBatchId::from_timeline_ids(&state.timeline_id, &new_timeline_id),
                         txns,
@@ -417,16 +507,77 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
         Ok((batch_id, transactions, metric_label))
     }
 
+    /// Returns true iff broadcasts to the given peer should announce transaction
+    /// digests (instead of sending the full transactions).
+    fn use_digest_broadcast(&self, peer: &PeerNetworkId) -> bool {
+        if !self
+            .mempool_config
+            .digest_broadcast_networks
+            .contains(&peer.network_id())
+        {
+            return false;
+        }
+
+        // Only peers that negotiated the digest protocol can handle digest announcements
+        self.supports_protocol(peer, ProtocolId::MempoolDigestDirectSend)
+    }
+
+    /// Returns true iff the given peer negotiated the specified protocol
+    fn supports_protocol(&self, peer: &PeerNetworkId, protocol_id: ProtocolId) -> bool {
+        self.network_client
+            .get_peers_and_metadata()
+            .get_metadata_for_peer(*peer)
+            .map_or(false, |metadata| metadata.supports_protocol(protocol_id))
+    }
+
+    /// Returns the broadcast request for the given transactions. The transaction groups
+    /// in the batch are only sent to the peers that negotiated the group protocol (other
+    /// peers handle the transactions individually, so they may split the groups).
+    pub(crate) fn transactions_request(
+        &self,
+        peer: &PeerNetworkId,
+        request_id: MultiBatchId,
+        transactions: Vec<SignedTransaction>,
+        mempool: &Mutex<CoreMempool>,
+    ) -> MempoolSyncMsg {
+        if self.supports_protocol(peer, ProtocolId::MempoolGroupDirectSend) {
+            let transaction_groups = mempool.lock().get_transaction_groups(&transactions);
+            if !transaction_groups.is_empty() {
+                return MempoolSyncMsg::BroadcastTransactionGroupsRequest {
+                    request_id,
+                    transactions,
+                    transaction_groups,
+                };
+            }
+        }
+        MempoolSyncMsg::BroadcastTransactionsRequest {
+            request_id,
+            transactions,
+        }
+    }
+
+    /// Returns true iff the given batch was broadcast to the peer and is pending an ACK
+    pub fn is_pending_broadcast(&self, peer: &PeerNetworkId, batch_id: &MultiBatchId) -> bool {
+        self.sync_states.read().get(peer).map_or(false, |state| {
+            state.broadcast_info.sent_batches.contains_key(batch_id)
+        })
+    }
+
     /// Sends a batch to the given peer
     async fn send_batch_to_peer(
         &self,
         peer: PeerNetworkId,
         batch_id: MultiBatchId,
         transactions: Vec<SignedTransaction>,
+        mempool: &Mutex<CoreMempool>,
     ) -> Result<(), BroadcastError> {
-        let request = MempoolSyncMsg::BroadcastTransactionsRequest {
-            request_id: batch_id,
-            transactions,
+        let request = if self.use_digest_broadcast(&peer) {
+            MempoolSyncMsg::BroadcastTransactionDigestsRequest {
+                request_id: batch_id,
+                digests: transactions.iter().map(TransactionDigest::new).collect(),
+            }
+        } else {
+            self.transactions_request(&peer, batch_id, transactions, mempool)
         };
 
         if let Err(e) = self.network_client.send_to_peer(request, peer) {
@@ -486,8 +637,13 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
 
         let num_txns = transactions.len();
         let send_time = SystemTime::now();
-        self.send_batch_to_peer(peer, batch_id.clone(), transactions)
-            .await?;
+        if let Err(error) = self
+            .send_batch_to_peer(peer, batch_id.clone(), transactions, &smp.mempool)
+            .await
+        {
+            self.record_broadcast_error(&peer);
+            return Err(error);
+        }
         let num_pending_broadcasts =
             self.update_broadcast_state(peer, batch_id.clone(), send_time)?;
         notify_subscribers(SharedMempoolNotification::Broadcast, &smp.subscribers);
@@ -506,6 +662,13 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
         // TODO: Rethink if this metric is useful
         counters::shared_mempool_pending_broadcasts(&peer).set(num_pending_broadcasts as i64);
         counters::shared_mempool_broadcast_latency(network_id, latency);
+        if self.mempool_config.adaptive_broadcast.enabled {
+            counters::shared_mempool_adaptive_broadcast_batches_inc(
+                network_id,
+                self.get_bandwidth_class(&peer).get_label(),
+                counters::SCHEDULED_BATCH_LABEL,
+            );
+        }
         if let Some(label) = metric_label {
             counters::shared_mempool_broadcast_type_inc(network_id, label);
         }
@@ -0,0 +1,425 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    core_mempool::{CoreMempool, TimelineState},
+    counters,
+    network::MempoolSyncMsg,
+    shared_mempool::{tasks, types::SharedMempool},
+};
+use anyhow::{anyhow, ensure, Result};
+use aptos_config::config::MempoolPersistenceConfig;
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_network::application::interface::NetworkClientInterface;
+use aptos_schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+    ColumnFamilyName, Options, ReadOptions, SchemaBatch, DB,
+};
+use aptos_types::{
+    account_address::AccountAddress, mempool_status::MempoolStatusCode,
+    transaction::SignedTransaction,
+};
+use aptos_vm_validator::vm_validator::TransactionValidation;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use tokio_stream::wrappers::IntervalStream;
+
+// The name of the DB (in the storage directory) holding the persisted transactions
+pub const MEMPOOL_DB_NAME: &str = "mempool_db";
+
+// The column family holding the persisted transactions
+const PERSISTED_TRANSACTIONS_CF_NAME: ColumnFamilyName = "persisted_transactions";
+
+/// The key of a persisted transaction (i.e., the sender and sequence number)
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct PersistedTransactionKey {
+    sender: AccountAddress,
+    sequence_number: u64,
+}
+
+impl PersistedTransactionKey {
+    fn new(transaction: &SignedTransaction) -> Self {
+        Self {
+            sender: transaction.sender(),
+            sequence_number: transaction.sequence_number(),
+        }
+    }
+}
+
+// The values are stored as raw bytes (and decoded manually), so that
+// corrupted entries can be skipped when the transactions are loaded.
+define_schema!(
+    PersistedTransactionSchema,
+    PersistedTransactionKey,
+    Vec<u8>,
+    PERSISTED_TRANSACTIONS_CF_NAME
+);
+
+impl KeyCodec<PersistedTransactionSchema> for PersistedTransactionKey {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        // The sequence number is big endian encoded, so that the
+        // transactions of each sender are ordered by sequence number.
+        let mut encoded_key = self.sender.to_vec();
+        encoded_key.extend_from_slice(&self.sequence_number.to_be_bytes());
+        Ok(encoded_key)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure!(
+            data.len() == AccountAddress::LENGTH + 8,
+            "Unexpected key length: {}",
+            data.len()
+        );
+        let (sender, sequence_number) = data.split_at(AccountAddress::LENGTH);
+        Ok(Self {
+            sender: AccountAddress::try_from(sender)?,
+            sequence_number: u64::from_be_bytes(sequence_number.try_into()?),
+        })
+    }
+}
+
+impl ValueCodec<PersistedTransactionSchema> for Vec<u8> {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(self.clone())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(data.to_vec())
+    }
+}
+
+/// A single persisted transaction
+#[derive(Debug, Deserialize, Serialize)]
+struct PersistedTransaction {
+    transaction: SignedTransaction,
+    insertion_time: Duration, // The mempool insertion time (since the unix epoch)
+}
+
+/// A handle for persisting the transactions of a running mempool to a local
+/// DB (i.e., write-behind snapshots taken periodically and on shutdown), and
+/// for loading them again on startup. Loaded transactions must be revalidated
+/// before they are inserted back into mempool (see `restore_persisted_transactions()`).
+#[derive(Clone)]
+pub struct MempoolPersistence {
+    config: MempoolPersistenceConfig,
+    db: Option<Arc<DB>>, // The DB is only opened if persistence is enabled
+    mempool: Arc<Mutex<CoreMempool>>,
+
+    // The committed hashes of the transactions in the last snapshot. This is None if no
+    // snapshot has been taken yet (i.e., the contents of the DB are unknown). The lock
+    // also ensures that snapshots are never written concurrently.
+    snapshot_transactions: Arc<Mutex<Option<HashMap<PersistedTransactionKey, HashValue>>>>,
+}
+
+impl MempoolPersistence {
+    pub(crate) fn new(
+        config: MempoolPersistenceConfig,
+        storage_dir: &Path,
+        mempool: Arc<Mutex<CoreMempool>>,
+    ) -> Self {
+        let db = config
+            .enabled
+            .then(|| Arc::new(open_mempool_db(storage_dir)));
+        Self {
+            config,
+            db,
+            mempool,
+            snapshot_transactions: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns true iff persistence is enabled in the config
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Snapshots the ready (and parked) transactions to the DB, and returns the
+    /// number of transactions in the snapshot. The snapshot is bounded by the
+    /// configured max bytes (the ready transactions are added first, in priority
+    /// order). Only the differences to the previous snapshot are written.
+    pub fn persist_transactions(&self) -> Result<usize> {
+        let db = self
+            .db
+            .as_ref()
+            .ok_or_else(|| anyhow!("Mempool persistence is not enabled!"))?;
+        let mut snapshot_transactions = self.snapshot_transactions.lock();
+
+        // Fetch the transactions to persist
+        let (ready_transactions, parked_transactions) = {
+            let mempool = self.mempool.lock();
+            let parked_transactions = if self.config.persist_parked_transactions {
+                mempool.get_parked_transactions()
+            } else {
+                vec![]
+            };
+            (mempool.get_ready_transactions(), parked_transactions)
+        };
+
+        // Write the transactions that are new (or changed) since the last snapshot
+        // (skipping those that are already too old)
+        let now = SystemTime::now();
+        let max_age = Duration::from_secs(self.config.max_transaction_age_secs);
+        let batch = SchemaBatch::new();
+        let mut snapshot = HashMap::new();
+        let mut snapshot_bytes = 0;
+        let mut num_written_transactions = 0;
+        for (transaction, insertion_time) in
+            ready_transactions.into_iter().chain(parked_transactions)
+        {
+            if now.duration_since(insertion_time).unwrap_or_default() > max_age {
+                continue;
+            }
+            let transaction_bytes = transaction.txn_bytes_len() as u64;
+            if snapshot_bytes + transaction_bytes > self.config.max_persisted_bytes {
+                break;
+            }
+            snapshot_bytes += transaction_bytes;
+
+            let key = PersistedTransactionKey::new(&transaction);
+            let committed_hash = transaction.clone().committed_hash();
+            let previous_hash = snapshot_transactions
+                .as_ref()
+                .and_then(|snapshot_transactions| snapshot_transactions.get(&key));
+            if previous_hash != Some(&committed_hash) {
+                let persisted_transaction = PersistedTransaction {
+                    transaction,
+                    insertion_time: insertion_time
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default(),
+                };
+                batch.put::<PersistedTransactionSchema>(
+                    &key,
+                    &bcs::to_bytes(&persisted_transaction)?,
+                )?;
+                num_written_transactions += 1;
+            }
+            snapshot.insert(key, committed_hash);
+        }
+
+        // Delete the transactions that are no longer in the snapshot (e.g., committed)
+        let stale_keys: Vec<_> = match snapshot_transactions.as_ref() {
+            Some(snapshot_transactions) => snapshot_transactions.keys().copied().collect(),
+            None => get_persisted_entries(db)?
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect(),
+        };
+        let mut num_deleted_transactions = 0;
+        for key in stale_keys {
+            if !snapshot.contains_key(&key) {
+                batch.delete::<PersistedTransactionSchema>(&key)?;
+                num_deleted_transactions += 1;
+            }
+        }
+        db.write_schemas(batch)?;
+
+        let num_transactions = snapshot.len();
+        *snapshot_transactions = Some(snapshot);
+        counters::persisted_transactions_inc_by(
+            counters::PERSISTED_LABEL,
+            num_written_transactions,
+        );
+        debug!(
+            "Persisted a snapshot of {} mempool transactions ({} bytes). Written: {}, deleted: {}",
+            num_transactions, snapshot_bytes, num_written_transactions, num_deleted_transactions
+        );
+        Ok(num_transactions)
+    }
+
+    /// Loads the persisted transactions (ordered by sender and sequence number).
+    /// Transactions that are older than the configured max age are discarded, as
+    /// are the transactions that fail to decode. Note: the transactions are not
+    /// removed from the DB (the next snapshot will remove them if required).
+    pub(crate) fn load_persisted_transactions(&self) -> Vec<SignedTransaction> {
+        let db = match &self.db {
+            Some(db) => db,
+            None => return vec![],
+        };
+        let persisted_entries = match get_persisted_entries(db) {
+            Ok(persisted_entries) => persisted_entries,
+            Err(error) => {
+                error!(
+                    "Failed to read the persisted mempool transactions: {:?}",
+                    error
+                );
+                return vec![];
+            },
+        };
+
+        // Decode the transactions and discard the corrupted (and too old) transactions
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let max_age = Duration::from_secs(self.config.max_transaction_age_secs);
+        let mut transactions = vec![];
+        let mut num_corrupted = 0;
+        let mut num_expired = 0;
+        for (key, value) in persisted_entries {
+            match decode_persisted_transaction(&key, &value) {
+                Ok(persisted) => {
+                    if now.saturating_sub(persisted.insertion_time) <= max_age {
+                        transactions.push(persisted.transaction);
+                    } else {
+                        num_expired += 1;
+                    }
+                },
+                Err(error) => {
+                    warn!(
+                        "Discarding the corrupted persisted mempool transaction {:?}: {:?}",
+                        key, error
+                    );
+                    num_corrupted += 1;
+                },
+            }
+        }
+        counters::persisted_transactions_inc_by(counters::DISCARDED_EXPIRED_LABEL, num_expired);
+        counters::persisted_transactions_inc_by(counters::DISCARDED_CORRUPTED_LABEL, num_corrupted);
+
+        transactions
+    }
+
+    /// Writes the given raw value for the given transaction key (e.g., to corrupt entries)
+    #[cfg(test)]
+    pub(crate) fn put_raw_value(
+        &self,
+        sender: AccountAddress,
+        sequence_number: u64,
+        value: Vec<u8>,
+    ) {
+        let key = PersistedTransactionKey {
+            sender,
+            sequence_number,
+        };
+        let batch = SchemaBatch::new();
+        batch
+            .put::<PersistedTransactionSchema>(&key, &value)
+            .unwrap();
+        self.db.as_ref().unwrap().write_schemas(batch).unwrap();
+    }
+}
+
+/// Opens (or creates) the mempool DB in the given storage directory
+fn open_mempool_db(storage_dir: &Path) -> DB {
+    let path = storage_dir.join(MEMPOOL_DB_NAME);
+    let instant = Instant::now();
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    let db = DB::open(
+        path.clone(),
+        MEMPOOL_DB_NAME,
+        vec![PERSISTED_TRANSACTIONS_CF_NAME],
+        &opts,
+    )
+    .expect("MempoolDB open failed; unable to continue");
+
+    info!(
+        "Opened MempoolDB at {:?} in {} ms",
+        path,
+        instant.elapsed().as_millis()
+    );
+    db
+}
+
+/// Returns all persisted entries in the DB (ordered by key)
+fn get_persisted_entries(db: &DB) -> Result<Vec<(PersistedTransactionKey, Vec<u8>)>> {
+    let mut iter = db.iter::<PersistedTransactionSchema>(ReadOptions::default())?;
+    iter.seek_to_first();
+    Ok(iter.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Decodes the given persisted transaction, and verifies that it matches the key
+fn decode_persisted_transaction(
+    key: &PersistedTransactionKey,
+    value: &[u8],
+) -> Result<PersistedTransaction> {
+    let persisted_transaction: PersistedTransaction = bcs::from_bytes(value)?;
+    let transaction_key = PersistedTransactionKey::new(&persisted_transaction.transaction);
+    ensure!(
+        &transaction_key == key,
+        "Transaction key mismatch! Expected: {:?}, found: {:?}",
+        key,
+        transaction_key
+    );
+    Ok(persisted_transaction)
+}
+
+/// Restores the persisted transactions (if any) into mempool. All transactions
+/// are revalidated against the latest ledger state before they are inserted,
+/// so transactions that were committed (or became invalid) are discarded.
+pub(crate) fn restore_persisted_transactions<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    persistence: &MempoolPersistence,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    let transactions = persistence.load_persisted_transactions();
+    if transactions.is_empty() {
+        return;
+    }
+
+    let statuses =
+        tasks::process_incoming_transactions(smp, transactions, TimelineState::NotReady, false);
+    let num_restored = statuses
+        .iter()
+        .filter(|(_, (mempool_status, _))| mempool_status.code == MempoolStatusCode::Accepted)
+        .count();
+    let num_discarded = statuses.len() - num_restored;
+    counters::persisted_transactions_inc_by(counters::RESTORED_LABEL, num_restored);
+    counters::persisted_transactions_inc_by(counters::DISCARDED_INVALID_LABEL, num_discarded);
+    info!(
+        "Restored {} persisted mempool transactions (discarded {} invalid transactions)",
+        num_restored, num_discarded
+    );
+}
+
+/// Restores the persisted transactions (if any), and then periodically
+/// snapshots the transactions in mempool (if snapshots are enabled).
+pub(crate) async fn persistence_job<NetworkClient, TransactionValidator>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    persistence: MempoolPersistence,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg> + 'static,
+    TransactionValidator: TransactionValidation + 'static,
+{
+    // Restore the persisted transactions (revalidation is expensive, so it runs on
+    // a blocking thread). Snapshots only start once the restore has completed.
+    let restore_persistence = persistence.clone();
+    if let Err(error) = tokio::task::spawn_blocking(move || {
+        restore_persisted_transactions(&smp, &restore_persistence)
+    })
+    .await
+    {
+        error!(
+            "Failed to restore the persisted mempool transactions: {:?}",
+            error
+        );
+    }
+
+    let snapshot_interval_secs = persistence.config.snapshot_interval_secs;
+    if snapshot_interval_secs == 0 {
+        return;
+    }
+    let mut interval = IntervalStream::new(tokio::time::interval(Duration::from_secs(
+        snapshot_interval_secs,
+    )));
+    while let Some(_interval) = interval.next().await {
+        let snapshot_persistence = persistence.clone();
+        match tokio::task::spawn_blocking(move || snapshot_persistence.persist_transactions()).await
+        {
+            Ok(Ok(_)) => {},
+            Ok(Err(error)) => warn!("Failed to snapshot the mempool transactions: {:?}", error),
+            Err(error) => warn!("Failed to snapshot the mempool transactions: {:?}", error),
+        }
+    }
+}
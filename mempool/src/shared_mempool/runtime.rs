@@ -3,10 +3,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::CoreMempool,
+    core_mempool::{CoreMempool, MempoolCapacityReporter},
     network::MempoolSyncMsg,
     shared_mempool::{
         coordinator::{coordinator, gc_coordinator, snapshot_job},
+        persistence::{persistence_job, MempoolPersistence},
+        submission_quotas::SubmissionQuotas,
         types::{MempoolEventsReceiver, SharedMempool, SharedMempoolNotification},
     },
     QuorumStoreRequest,
@@ -32,6 +34,11 @@ use tokio::runtime::{Handle, Runtime};
 ///   - outbound_sync_task (task that periodically broadcasts transactions to peers).
 ///   - inbound_network_task (task that handles inbound mempool messages and network events).
 ///   - gc_task (task that performs GC of all expired transactions by SystemTTL).
+///
+/// If mempool persistence is enabled, the transactions persisted before the last
+/// restart are also revalidated and restored, and mempool is periodically snapshotted.
+///
+/// Returns the submission quotas enforced by the SharedMempool, and the persistence handle.
 pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
     executor: &Handle,
     config: &NodeConfig,
@@ -46,7 +53,8 @@ pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
     validator: Arc<RwLock<TransactionValidator>>,
     subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
     peers_and_metadata: Arc<PeersAndMetadata>,
-) where
+) -> (Arc<SubmissionQuotas>, MempoolPersistence)
+where
     TransactionValidator: TransactionValidation + 'static,
     ConfigProvider: OnChainConfigProvider,
 {
@@ -60,6 +68,17 @@ pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
             subscribers,
             config.base.role,
         );
+    let submission_quotas = smp.submission_quotas.clone();
+
+    // Restore the transactions persisted before the last restart, and start the snapshots
+    let persistence = MempoolPersistence::new(
+        config.mempool.persistence.clone(),
+        &config.storage.dir(),
+        mempool.clone(),
+    );
+    if persistence.is_enabled() {
+        executor.spawn(persistence_job(smp.clone(), persistence.clone()));
+    }
 
     executor.spawn(coordinator(
         smp,
@@ -84,6 +103,8 @@ pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
             config.mempool.mempool_snapshot_interval_secs,
         ));
     }
+
+    (submission_quotas, persistence)
 }
 
 pub fn bootstrap(
@@ -96,11 +117,17 @@ pub fn bootstrap(
     mempool_listener: MempoolNotificationListener,
     mempool_reconfig_events: ReconfigNotificationListener<DbBackedOnChainConfig>,
     peers_and_metadata: Arc<PeersAndMetadata>,
-) -> Runtime {
+) -> (
+    Runtime,
+    Arc<SubmissionQuotas>,
+    MempoolCapacityReporter,
+    MempoolPersistence,
+) {
     let runtime = aptos_runtimes::spawn_named_runtime("shared-mem".into(), None);
     let mempool = Arc::new(Mutex::new(CoreMempool::new(config)));
+    let capacity_reporter = MempoolCapacityReporter::new(mempool.clone());
     let vm_validator = Arc::new(RwLock::new(VMValidator::new(Arc::clone(&db))));
-    start_shared_mempool(
+    let (submission_quotas, persistence) = start_shared_mempool(
         runtime.handle(),
         config,
         mempool,
@@ -115,5 +142,5 @@ pub fn bootstrap(
         vec![],
         peers_and_metadata,
     );
-    runtime
+    (runtime, submission_quotas, capacity_reporter, persistence)
 }
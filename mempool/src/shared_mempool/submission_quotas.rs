@@ -0,0 +1,263 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-source quotas for transactions submitted to mempool by clients (e.g., the API).
+//!
+//! Each submission carries a `SubmissionSource`. Submissions with a known API key are
+//! counted against the quota for that key. All other submissions (i.e., anonymous
+//! submissions and submissions with an unknown API key) share the default quota. The
+//! quota table can be updated at runtime (e.g., via the admin service).
+
+use crate::{counters, shared_mempool::types::SubmissionSource};
+use aptos_config::config::{SubmissionQuota, SubmissionQuotasConfig};
+use aptos_infallible::Mutex;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The reason a submission was rejected by the quotas
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SubmissionQuotaRejection {
+    /// The quota for the submission's API key was exceeded
+    ApiKeyQuotaExceeded,
+    /// The default quota (shared by all submissions without a known API key) was exceeded
+    DefaultQuotaExceeded,
+}
+
+impl SubmissionQuotaRejection {
+    /// Returns a summary label for the rejection
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            SubmissionQuotaRejection::ApiKeyQuotaExceeded => "api_key_quota_exceeded",
+            SubmissionQuotaRejection::DefaultQuotaExceeded => "default_quota_exceeded",
+        }
+    }
+}
+
+impl fmt::Display for SubmissionQuotaRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmissionQuotaRejection::ApiKeyQuotaExceeded => {
+                write!(f, "The submission quota for the API key was exceeded")
+            },
+            SubmissionQuotaRejection::DefaultQuotaExceeded => {
+                write!(f, "The default submission quota was exceeded")
+            },
+        }
+    }
+}
+
+/// A snapshot of the quota and usage for a single quota bucket
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SubmissionQuotaSummary {
+    pub api_key: Option<String>,        // None for the default quota
+    pub quota: Option<SubmissionQuota>, // None if the bucket is unlimited
+    pub submissions_in_window: u64,
+    pub total_accepted: u64,
+    pub total_rejected: u64,
+}
+
+/// The submission usage for a single quota bucket
+#[derive(Clone, Debug, Default)]
+struct QuotaUsage {
+    window_start: Option<Instant>,
+    submissions_in_window: u64,
+    total_accepted: u64,
+    total_rejected: u64,
+}
+
+impl QuotaUsage {
+    /// Returns true iff the current window has elapsed for the given quota (at the given time)
+    fn window_elapsed(&self, quota: &SubmissionQuota, now: Instant) -> bool {
+        let window_duration = Duration::from_secs(quota.window_secs);
+        self.window_start
+            .map(|window_start| now.duration_since(window_start) >= window_duration)
+            .unwrap_or(true)
+    }
+
+    /// Returns the number of submissions in the current window (at the given time)
+    fn get_submissions_in_window(&self, quota: &Option<SubmissionQuota>, now: Instant) -> u64 {
+        match quota {
+            Some(quota) if self.window_elapsed(quota, now) => 0,
+            _ => self.submissions_in_window,
+        }
+    }
+
+    /// Records a submission against the given quota (at the given time)
+    /// and returns true iff the submission is within the quota.
+    fn record_submission(&mut self, quota: &Option<SubmissionQuota>, now: Instant) -> bool {
+        let within_quota = match quota {
+            Some(quota) => {
+                // Start a new window if the current one has elapsed
+                if self.window_elapsed(quota, now) {
+                    self.window_start = Some(now);
+                    self.submissions_in_window = 0;
+                }
+
+                self.submissions_in_window < quota.max_submissions_per_window
+            },
+            None => true, // The bucket is unlimited
+        };
+
+        if within_quota {
+            self.submissions_in_window += 1;
+            self.total_accepted += 1;
+        } else {
+            self.total_rejected += 1;
+        }
+        within_quota
+    }
+}
+
+/// The quota table and the usage of each quota bucket
+#[derive(Default)]
+struct QuotaState {
+    default_quota: Option<SubmissionQuota>,
+    api_key_quotas: HashMap<String, SubmissionQuota>,
+    default_usage: QuotaUsage,
+    api_key_usage: HashMap<String, QuotaUsage>,
+}
+
+/// Enforces per-source quotas on client transaction submissions
+pub struct SubmissionQuotas {
+    enabled: bool,
+    state: Mutex<QuotaState>,
+    time_service: TimeService,
+}
+
+impl SubmissionQuotas {
+    pub fn new(config: &SubmissionQuotasConfig, time_service: TimeService) -> Self {
+        let state = QuotaState {
+            default_quota: config.default_quota,
+            api_key_quotas: config
+                .api_key_quotas
+                .iter()
+                .map(|(api_key, quota)| (api_key.clone(), *quota))
+                .collect(),
+            ..Default::default()
+        };
+
+        Self {
+            enabled: config.enabled,
+            state: Mutex::new(state),
+            time_service,
+        }
+    }
+
+    /// Returns true iff quotas are enforced
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Records a submission from the given source and returns an error
+    /// if the submission exceeds the quota for the source.
+    pub fn check_and_record_submission(
+        &self,
+        source: &SubmissionSource,
+    ) -> Result<(), SubmissionQuotaRejection> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        // Record the submission against the quota bucket of the source
+        let now = self.time_service.now();
+        let mut state = self.state.lock();
+        let (within_quota, rejection) = match source.get_api_key() {
+            Some(api_key) if state.api_key_quotas.contains_key(api_key) => {
+                let quota = state.api_key_quotas.get(api_key).copied();
+                let within_quota = state
+                    .api_key_usage
+                    .entry(api_key.to_string())
+                    .or_default()
+                    .record_submission(&quota, now);
+                (within_quota, SubmissionQuotaRejection::ApiKeyQuotaExceeded)
+            },
+            _ => {
+                let quota = state.default_quota;
+                let within_quota = state.default_usage.record_submission(&quota, now);
+                (within_quota, SubmissionQuotaRejection::DefaultQuotaExceeded)
+            },
+        };
+
+        // Update the metrics and return the result
+        if within_quota {
+            counters::submission_quota_result_inc(counters::SUCCESS_LABEL);
+            Ok(())
+        } else {
+            counters::submission_quota_result_inc(rejection.get_label());
+            Err(rejection)
+        }
+    }
+
+    /// Sets the quota for the given API key (or the default quota, if no API key is given).
+    /// Existing usage for the quota bucket is preserved.
+    pub fn set_quota(&self, api_key: Option<String>, quota: SubmissionQuota) {
+        let mut state = self.state.lock();
+        match api_key {
+            Some(api_key) => {
+                state.api_key_quotas.insert(api_key, quota);
+            },
+            None => state.default_quota = Some(quota),
+        }
+    }
+
+    /// Removes the quota for the given API key (or the default quota, if no API key is given).
+    /// Submissions with a removed API key fall back to the default quota, and removing the
+    /// default quota makes these submissions unlimited.
+    pub fn remove_quota(&self, api_key: Option<&str>) {
+        let mut state = self.state.lock();
+        match api_key {
+            Some(api_key) => {
+                state.api_key_quotas.remove(api_key);
+                state.api_key_usage.remove(api_key);
+            },
+            None => state.default_quota = None,
+        }
+    }
+
+    /// Returns a summary of the quota and usage for each quota bucket
+    /// (sorted by API key, with the default quota first).
+    pub fn get_summaries(&self) -> Vec<SubmissionQuotaSummary> {
+        let now = self.time_service.now();
+        let state = self.state.lock();
+
+        let mut api_key_summaries: Vec<_> = state
+            .api_key_quotas
+            .iter()
+            .map(|(api_key, quota)| {
+                let usage = state
+                    .api_key_usage
+                    .get(api_key)
+                    .cloned()
+                    .unwrap_or_default();
+                create_summary(Some(api_key.clone()), Some(*quota), &usage, now)
+            })
+            .collect();
+        api_key_summaries.sort_by(|a, b| a.api_key.cmp(&b.api_key));
+
+        let default_summary = create_summary(None, state.default_quota, &state.default_usage, now);
+        std::iter::once(default_summary)
+            .chain(api_key_summaries)
+            .collect()
+    }
+}
+
+/// Creates a quota summary for the given bucket
+fn create_summary(
+    api_key: Option<String>,
+    quota: Option<SubmissionQuota>,
+    usage: &QuotaUsage,
+    now: Instant,
+) -> SubmissionQuotaSummary {
+    SubmissionQuotaSummary {
+        api_key,
+        quota,
+        submissions_in_window: usage.get_submissions_in_window(&quota, now),
+        total_accepted: usage.total_accepted,
+        total_rejected: usage.total_rejected,
+    }
+}
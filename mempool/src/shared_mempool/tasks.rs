@@ -4,13 +4,16 @@
 
 //! Tasks that are executed by coordinators (short-lived compared to coordinators)
 use crate::{
-    core_mempool::{CoreMempool, TimelineState},
+    core_mempool::{CoreMempool, GasPriceEstimate, TimelineState, TransactionEviction},
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
-    network::{BroadcastError, MempoolSyncMsg},
-    shared_mempool::types::{
-        notify_subscribers, MultiBatchId, ScheduledBroadcast, SharedMempool,
-        SharedMempoolNotification, SubmissionStatusBundle,
+    network::{BroadcastError, MempoolSyncMsg, TransactionDigest},
+    shared_mempool::{
+        submission_quotas::SubmissionQuotaRejection,
+        types::{
+            notify_subscribers, MultiBatchId, ScheduledBroadcast, SharedMempool,
+            SharedMempoolNotification, SubmissionStatusBundle, TransactionGroup,
+        },
     },
     thread_pool::IO_POOL,
     QuorumStoreRequest, QuorumStoreResponse, SubmissionStatus,
@@ -25,6 +28,7 @@ use aptos_metrics_core::HistogramTimer;
 use aptos_network::application::interface::NetworkClientInterface;
 use aptos_storage_interface::state_view::LatestDbStateCheckpointView;
 use aptos_types::{
+    account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     on_chain_config::{OnChainConfigPayload, OnChainConfigProvider, OnChainConsensusConfig},
     transaction::SignedTransaction,
@@ -89,10 +93,16 @@ pub(crate) async fn execute_broadcast<NetworkClient, TransactionValidator>(
     }
     let schedule_backoff = network_interface.is_backoff_mode(&peer);
 
-    let interval_ms = if schedule_backoff {
+    // Broadcast less frequently if the peer asked us to back off, or if consensus
+    // cannot drain our mempool anyway. Otherwise, the interval depends on the bandwidth
+    // class of the peer (if adaptive broadcasts are enabled).
+    let interval_ms = if schedule_backoff || *smp.consensus_backpressure.read() {
         smp.config.shared_mempool_backoff_interval_ms
     } else {
-        smp.config.shared_mempool_tick_interval_ms
+        network_interface.get_bandwidth_class(&peer).interval_ms(
+            smp.config.shared_mempool_tick_interval_ms,
+            smp.config.adaptive_broadcast.window_scaling_factor,
+        )
     };
 
     scheduled_broadcasts.push(ScheduledBroadcast::new(
@@ -140,6 +150,196 @@ pub(crate) async fn process_client_transaction_submission<NetworkClient, Transac
     }
 }
 
+/// Processes a group of sequential transactions directly submitted by a client. The group is
+/// only added to mempool if all of its transactions pass validation. Otherwise, the status of
+/// the first invalid transaction is returned.
+pub(crate) async fn process_client_transaction_group_submission<
+    NetworkClient,
+    TransactionValidator,
+>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    transactions: Vec<SignedTransaction>,
+    callback: oneshot::Sender<Result<SubmissionStatus>>,
+    timer: HistogramTimer,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation + 'static,
+{
+    timer.stop_and_record();
+    let _timer = counters::process_txn_submit_latency_timer_client();
+    let ineligible_for_broadcast =
+        smp.network_interface.is_validator() && !smp.broadcast_within_validator_network();
+    let timeline_state = if ineligible_for_broadcast {
+        TimelineState::NonQualified
+    } else {
+        TimelineState::NotReady
+    };
+    let status = validate_and_add_transaction_group(&smp, transactions, timeline_state, true);
+    counters::shared_mempool_transactions_processed_inc(
+        &status.0.code.to_string(),
+        counters::CLIENT_LABEL,
+    );
+    notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
+
+    if callback.send(Ok(status)).is_err() {
+        warn!(LogSchema::event_log(
+            LogEntry::JsonRpc,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
+/// Performs VM validation on the transactions of the group, and inserts the
+/// group into mempool iff all of its transactions pass validation.
+fn validate_and_add_transaction_group<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    transactions: Vec<SignedTransaction>,
+    timeline_state: TimelineState,
+    client_submitted: bool,
+) -> SubmissionStatus
+where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    // Fetch the account sequence number (the group has a single sender)
+    let sender = match transactions.first() {
+        Some(transaction) => transaction.sender(),
+        None => {
+            return (
+                MempoolStatus::new(MempoolStatusCode::InvalidTransactionGroup)
+                    .with_message("Transaction group is empty".to_string()),
+                None,
+            )
+        },
+    };
+    let state_view = smp
+        .db
+        .latest_state_checkpoint_view()
+        .expect("Failed to get latest state checkpoint view.");
+    let sequence_number = match get_account_sequence_number(&state_view, sender) {
+        Ok(sequence_number) => sequence_number,
+        Err(error) => {
+            error!(LogSchema::new(LogEntry::DBError).error(&error));
+            counters::DB_ERROR.inc();
+            return (
+                MempoolStatus::new(MempoolStatusCode::VmError),
+                Some(DiscardedVMStatus::RESOURCE_DOES_NOT_EXIST),
+            );
+        },
+    };
+
+    // Validate all transactions of the group
+    let vm_validation_timer = counters::PROCESS_TXN_BREAKDOWN_LATENCY
+        .with_label_values(&[counters::VM_VALIDATION_LABEL])
+        .start_timer();
+    let mut transactions_with_scores = Vec::with_capacity(transactions.len());
+    for transaction in transactions {
+        match smp
+            .validator
+            .read()
+            .validate_transaction(transaction.clone())
+        {
+            Ok(validation_result) => match validation_result.status() {
+                None => transactions_with_scores.push((transaction, validation_result.score())),
+                Some(validation_status) => {
+                    return (
+                        MempoolStatus::new(MempoolStatusCode::VmError),
+                        Some(validation_status),
+                    );
+                },
+            },
+            Err(_) => {
+                return (
+                    MempoolStatus::new(MempoolStatusCode::VmError),
+                    Some(DiscardedVMStatus::UNKNOWN_STATUS),
+                );
+            },
+        }
+    }
+    vm_validation_timer.stop_and_record();
+
+    let mempool_status = smp.mempool.lock().add_txn_group(
+        transactions_with_scores,
+        sequence_number,
+        timeline_state,
+        client_submitted,
+    );
+    (mempool_status, None)
+}
+
+/// Submits the given transaction groups (received from a peer) to the local mempool, so
+/// that the groups are kept together here too. Each group is validated and added all-or-
+/// nothing, and the status of the group is returned for each of its transactions.
+fn process_incoming_transaction_groups<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    transaction_groups: Vec<Vec<SignedTransaction>>,
+    timeline_state: TimelineState,
+) -> Vec<SubmissionStatusBundle>
+where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    let mut statuses = vec![];
+    for transactions in transaction_groups {
+        let status =
+            validate_and_add_transaction_group(smp, transactions.clone(), timeline_state, false);
+        statuses.extend(
+            transactions
+                .into_iter()
+                .map(|transaction| (transaction, status.clone())),
+        );
+    }
+    statuses
+}
+
+/// Splits the given transactions into the transactions of the given groups (in sequence
+/// number order) and the remaining transactions. Groups that are incomplete (i.e., that
+/// don't match the transactions) are ignored, and their transactions are handled individually.
+fn split_transaction_groups(
+    mut transactions: Vec<SignedTransaction>,
+    transaction_groups: &[TransactionGroup],
+) -> (Vec<SignedTransaction>, Vec<Vec<SignedTransaction>>) {
+    let mut grouped_transactions = vec![];
+    for transaction_group in transaction_groups {
+        let (mut group_transactions, remaining_transactions): (Vec<_>, Vec<_>) = transactions
+            .into_iter()
+            .partition(|transaction| transaction_group.contains(transaction));
+        group_transactions.sort_by_key(|transaction| transaction.sequence_number());
+        group_transactions.dedup_by_key(|transaction| transaction.sequence_number());
+        if group_transactions.len() == transaction_group.num_transactions() {
+            grouped_transactions.push(group_transactions);
+            transactions = remaining_transactions;
+        } else {
+            transactions = remaining_transactions
+                .into_iter()
+                .chain(group_transactions)
+                .collect();
+        }
+    }
+    (transactions, grouped_transactions)
+}
+
+/// Rejects a transaction submitted by a client that exceeded its submission quota.
+pub(crate) fn reject_client_transaction_submission(
+    rejection: SubmissionQuotaRejection,
+    callback: oneshot::Sender<Result<SubmissionStatus>>,
+) {
+    let mempool_status = MempoolStatus::new(MempoolStatusCode::SubmissionQuotaExceeded)
+        .with_message(rejection.to_string());
+    counters::shared_mempool_transactions_processed_inc(
+        &mempool_status.code.to_string(),
+        counters::CLIENT_LABEL,
+    );
+    if callback.send(Ok((mempool_status, None))).is_err() {
+        warn!(LogSchema::event_log(
+            LogEntry::JsonRpc,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
 /// Processes get transaction by hash request by client.
 pub(crate) async fn process_client_get_transaction<NetworkClient, TransactionValidator>(
     smp: SharedMempool<NetworkClient, TransactionValidator>,
@@ -163,10 +363,54 @@ pub(crate) async fn process_client_get_transaction<NetworkClient, TransactionVal
     }
 }
 
+/// Processes gas price estimation request by client.
+pub(crate) async fn process_client_estimate_gas_price<NetworkClient, TransactionValidator>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    percentile: u8,
+    horizon_blocks: usize,
+    callback: oneshot::Sender<Result<GasPriceEstimate>>,
+    timer: HistogramTimer,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    timer.stop_and_record();
+    let estimate = smp
+        .mempool
+        .lock()
+        .estimate_gas_price(percentile, horizon_blocks);
+
+    if callback.send(estimate).is_err() {
+        warn!(LogSchema::event_log(
+            LogEntry::EstimateGasPrice,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
+/// Processes a subscription to a transaction eviction by client.
+pub(crate) async fn process_client_subscribe_eviction<NetworkClient, TransactionValidator>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    sender: AccountAddress,
+    sequence_number: u64,
+    callback: oneshot::Sender<TransactionEviction>,
+    timer: HistogramTimer,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    timer.stop_and_record();
+    smp.mempool
+        .lock()
+        .subscribe_eviction(sender, sequence_number, callback);
+}
+
 /// Processes transactions from other nodes.
 pub(crate) async fn process_transaction_broadcast<NetworkClient, TransactionValidator>(
     smp: SharedMempool<NetworkClient, TransactionValidator>,
     transactions: Vec<SignedTransaction>,
+    transaction_groups: Vec<TransactionGroup>,
     request_id: MultiBatchId,
     timeline_state: TimelineState,
     peer: PeerNetworkId,
@@ -177,10 +421,22 @@ pub(crate) async fn process_transaction_broadcast<NetworkClient, TransactionVali
 {
     timer.stop_and_record();
     let _timer = counters::process_txn_submit_latency_timer(peer.network_id());
-    let results = process_incoming_transactions(&smp, transactions, timeline_state, false);
+    let (transactions, grouped_transactions) =
+        split_transaction_groups(transactions, &transaction_groups);
+    let mut results = process_incoming_transactions(&smp, transactions, timeline_state, false);
+    results.extend(process_incoming_transaction_groups(
+        &smp,
+        grouped_transactions,
+        timeline_state,
+    ));
     log_txn_process_results(&results, Some(peer));
 
-    let ack_response = gen_ack_response(request_id, results, &peer);
+    let ack_response = gen_ack_response(
+        request_id,
+        results,
+        &peer,
+        *smp.consensus_backpressure.read(),
+    );
 
     // Respond to the peer with an ack. Note: ack response messages should be
     // small enough that they always fit within the maximum network message
@@ -201,10 +457,12 @@ pub(crate) async fn process_transaction_broadcast<NetworkClient, TransactionVali
 }
 
 /// If `MempoolIsFull` on any of the transactions, provide backpressure to the downstream peer.
+/// Backpressure is also provided (without retries) if consensus is back pressured.
 fn gen_ack_response(
     request_id: MultiBatchId,
     results: Vec<SubmissionStatusBundle>,
     peer: &PeerNetworkId,
+    consensus_backpressure: bool,
 ) -> MempoolSyncMsg {
     let mut backoff_and_retry = false;
     for (_, (mempool_status, _)) in results.into_iter() {
@@ -213,17 +471,13 @@ fn gen_ack_response(
             break;
         }
     }
+    let backoff = backoff_and_retry || consensus_backpressure;
 
-    update_ack_counter(
-        peer,
-        counters::SENT_LABEL,
-        backoff_and_retry,
-        backoff_and_retry,
-    );
+    update_ack_counter(peer, counters::SENT_LABEL, backoff_and_retry, backoff);
     MempoolSyncMsg::BroadcastTransactionsResponse {
         request_id,
         retry: backoff_and_retry,
-        backoff: backoff_and_retry,
+        backoff,
     }
 }
 
@@ -249,6 +503,135 @@ pub(crate) fn update_ack_counter(
     }
 }
 
+/// Processes a digest announcement from another node, and requests the
+/// announced transactions that are missing from the local mempool.
+pub(crate) fn process_transaction_digests<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    digests: Vec<TransactionDigest>,
+    request_id: MultiBatchId,
+    peer: PeerNetworkId,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    // Identify the announced transactions that are missing from mempool
+    let num_announced = digests.len();
+    let mut missing_hashes = vec![];
+    let mut saved_bytes = 0;
+    {
+        let mempool = smp.mempool.lock();
+        for digest in digests {
+            match mempool.get_by_hash(digest.hash) {
+                Some(transaction) => saved_bytes += transaction.txn_bytes_len() as u64,
+                None => missing_hashes.push(digest.hash),
+            }
+        }
+    }
+
+    // Update the digest metrics
+    let network_id = peer.network_id();
+    counters::shared_mempool_digest_transactions_inc(
+        network_id,
+        counters::DIGEST_ANNOUNCED_LABEL,
+        num_announced,
+    );
+    counters::shared_mempool_digest_transactions_inc(
+        network_id,
+        counters::DIGEST_REQUESTED_LABEL,
+        missing_hashes.len(),
+    );
+    counters::shared_mempool_digest_saved_bytes_inc(network_id, saved_bytes);
+
+    // If all transactions are already in mempool, ack the broadcast immediately
+    if missing_hashes.is_empty() {
+        let ack_response = MempoolSyncMsg::BroadcastTransactionsResponse {
+            request_id,
+            retry: false,
+            backoff: false,
+        };
+        if let Err(e) = smp
+            .network_interface
+            .send_message_to_peer(peer, ack_response)
+        {
+            counters::network_send_fail_inc(counters::ACK_TXNS);
+            warn!(
+                LogSchema::event_log(LogEntry::BroadcastACK, LogEvent::NetworkSendFail)
+                    .peer(&peer)
+                    .error(&e.into())
+            );
+            return;
+        }
+        notify_subscribers(SharedMempoolNotification::ACK, &smp.subscribers);
+        return;
+    }
+
+    // Otherwise, request the missing transactions (the broadcast is acked once they arrive)
+    let missing_request = MempoolSyncMsg::MissingTransactionsRequest {
+        request_id,
+        hashes: missing_hashes,
+    };
+    if let Err(e) = smp
+        .network_interface
+        .send_message_to_peer(peer, missing_request)
+    {
+        counters::network_send_fail_inc(counters::MISSING_TXNS_REQUEST);
+        warn!(
+            LogSchema::event_log(LogEntry::MissingTxnsRequest, LogEvent::NetworkSendFail)
+                .peer(&peer)
+                .error(&e.into())
+        );
+    }
+}
+
+/// Processes a request from another node for transactions that were announced
+/// (by digest) in a pending broadcast, and sends the transactions to the node.
+pub(crate) fn process_missing_transactions_request<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    hashes: Vec<HashValue>,
+    request_id: MultiBatchId,
+    peer: PeerNetworkId,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    // Only respond to requests for pending broadcasts (the batch
+    // may have expired, or already been acked by the peer).
+    if !smp
+        .network_interface
+        .is_pending_broadcast(&peer, &request_id)
+    {
+        counters::unexpected_msg_count_inc(&peer.network_id());
+        return;
+    }
+
+    // Fetch the requested transactions. Transactions that are no longer in
+    // mempool (e.g., because they were committed) are skipped.
+    let transactions: Vec<_> = {
+        let mempool = smp.mempool.lock();
+        hashes
+            .into_iter()
+            .take(smp.config.shared_mempool_batch_size)
+            .filter_map(|hash| mempool.get_by_hash(hash))
+            .collect()
+    };
+    if transactions.is_empty() {
+        return;
+    }
+
+    // Send the transactions to the peer
+    let request =
+        smp.network_interface
+            .transactions_request(&peer, request_id, transactions, &smp.mempool);
+    if let Err(e) = smp.network_interface.send_message_to_peer(peer, request) {
+        counters::network_send_fail_inc(counters::BROADCAST_TXNS);
+        warn!(
+            LogSchema::event_log(LogEntry::BroadcastTransaction, LogEvent::NetworkSendFail)
+                .peer(&peer)
+                .error(&e.into())
+        );
+    }
+}
+
 /// Submits a list of SignedTransaction to the local mempool
 /// and returns a vector containing [SubmissionStatusBundle].
 pub(crate) fn process_incoming_transactions<NetworkClient, TransactionValidator>(
@@ -520,6 +903,11 @@ pub(crate) fn process_quorum_store_request<NetworkClient, TransactionValidator>(
                 counters::GET_BLOCK_LABEL,
             )
         },
+        QuorumStoreRequest::BackpressureNotification(back_pressured) => {
+            // There's no callback to respond to
+            process_consensus_backpressure(smp, back_pressured);
+            return;
+        },
         QuorumStoreRequest::RejectNotification(transactions, callback) => {
             counters::mempool_service_transactions(
                 counters::COMMIT_CONSENSUS_LABEL,
@@ -547,6 +935,20 @@ pub(crate) fn process_quorum_store_request<NetworkClient, TransactionValidator>(
     counters::mempool_service_latency(counter_label, result, latency);
 }
 
+/// Updates the consensus backpressure, which slows down broadcasts and peer intake
+/// while consensus cannot drain the mempool (if enabled via config).
+fn process_consensus_backpressure<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    back_pressured: bool,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    let consensus_backpressure = smp.config.enable_consensus_backpressure && back_pressured;
+    *smp.consensus_backpressure.write() = consensus_backpressure;
+    counters::CONSENSUS_BACKPRESSURE.set(consensus_backpressure as i64);
+}
+
 /// Remove transactions that are committed (or rejected) so that we can stop broadcasting them.
 pub(crate) fn process_committed_transactions(
     mempool: &Mutex<CoreMempool>,
@@ -556,7 +958,13 @@ pub(crate) fn process_committed_transactions(
     let mut pool = mempool.lock();
     let block_timestamp = Duration::from_micros(block_timestamp_usecs);
 
+    let mut committed_gas_prices = Vec::with_capacity(transactions.len());
     for transaction in transactions {
+        if let Some(gas_unit_price) =
+            pool.get_gas_unit_price(&transaction.sender, transaction.sequence_number)
+        {
+            committed_gas_prices.push(gas_unit_price);
+        }
         pool.log_commit_transaction(
             &transaction.sender,
             transaction.sequence_number,
@@ -564,6 +972,7 @@ pub(crate) fn process_committed_transactions(
         );
         pool.commit_transaction(&transaction.sender, transaction.sequence_number);
     }
+    pool.record_committed_gas_prices(committed_gas_prices);
 
     if block_timestamp_usecs > 0 {
         pool.gc_by_expiration_time(block_timestamp);
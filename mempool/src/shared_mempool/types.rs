@@ -4,12 +4,13 @@
 
 //! Objects used by/related to shared mempool
 use crate::{
-    core_mempool::CoreMempool,
+    core_mempool::{CoreMempool, GasPriceEstimate, TransactionEviction},
     network::{MempoolNetworkInterface, MempoolSyncMsg},
+    shared_mempool::submission_quotas::SubmissionQuotas,
 };
 use anyhow::Result;
 use aptos_config::{
-    config::{MempoolConfig, RoleType},
+    config::{AdaptiveBroadcastConfig, MempoolConfig, RoleType},
     network_id::PeerNetworkId,
 };
 use aptos_consensus_types::common::{
@@ -19,8 +20,10 @@ use aptos_crypto::HashValue;
 use aptos_infallible::{Mutex, RwLock};
 use aptos_network::application::interface::NetworkClientInterface;
 use aptos_storage_interface::DbReader;
+use aptos_time_service::TimeService;
 use aptos_types::{
-    mempool_status::MempoolStatus, transaction::SignedTransaction, vm_status::DiscardedVMStatus,
+    account_address::AccountAddress, mempool_status::MempoolStatus, transaction::SignedTransaction,
+    vm_status::DiscardedVMStatus,
 };
 use aptos_vm_validator::vm_validator::TransactionValidation;
 use futures::{
@@ -36,7 +39,7 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::Waker,
-    time::{Instant, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::runtime::Handle;
 
@@ -50,6 +53,9 @@ pub(crate) struct SharedMempool<NetworkClient, TransactionValidator> {
     pub validator: Arc<RwLock<TransactionValidator>>,
     pub subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
     pub broadcast_within_validator_network: Arc<RwLock<bool>>,
+    // Whether consensus is back pressured (only set if coupled with mempool via config)
+    pub consensus_backpressure: Arc<RwLock<bool>>,
+    pub submission_quotas: Arc<SubmissionQuotas>,
 }
 
 impl<
@@ -67,6 +73,10 @@ impl<
         role: RoleType,
     ) -> Self {
         let network_interface = MempoolNetworkInterface::new(network_client, role, config.clone());
+        let submission_quotas = Arc::new(SubmissionQuotas::new(
+            &config.submission_quotas,
+            TimeService::real(),
+        ));
         SharedMempool {
             mempool,
             config,
@@ -75,6 +85,8 @@ impl<
             validator,
             subscribers,
             broadcast_within_validator_network: Arc::new(RwLock::new(true)),
+            consensus_backpressure: Arc::new(RwLock::new(false)),
+            submission_quotas,
         }
     }
 
@@ -182,6 +194,11 @@ pub enum QuorumStoreRequest {
         // callback to respond to
         oneshot::Sender<Result<QuorumStoreResponse>>,
     ),
+    /// Notifications about changes to the consensus backpressure.
+    BackpressureNotification(
+        // whether consensus is back pressured (i.e., it cannot drain mempool)
+        bool,
+    ),
 }
 
 impl fmt::Display for QuorumStoreRequest {
@@ -210,6 +227,12 @@ impl fmt::Display for QuorumStoreRequest {
                     rejected_txns.len()
                 )
             },
+            QuorumStoreRequest::BackpressureNotification(back_pressured) => {
+                format!(
+                    "BackpressureNotification [back_pressured: {}]",
+                    back_pressured
+                )
+            },
         };
         write!(f, "{}", payload)
     }
@@ -228,8 +251,47 @@ pub type SubmissionStatus = (MempoolStatus, Option<DiscardedVMStatus>);
 pub type SubmissionStatusBundle = (SignedTransaction, SubmissionStatus);
 
 pub enum MempoolClientRequest {
-    SubmitTransaction(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>),
+    SubmitTransaction(
+        SignedTransaction,
+        SubmissionSource,
+        oneshot::Sender<Result<SubmissionStatus>>,
+    ),
+    /// Submits a group of sequential transactions of a single account. The group is added
+    /// to mempool all-or-nothing, and its transactions are kept together for broadcast and
+    /// block inclusion. The group counts as a single submission against the quotas.
+    SubmitTransactionGroup(
+        Vec<SignedTransaction>,
+        SubmissionSource,
+        oneshot::Sender<Result<SubmissionStatus>>,
+    ),
     GetTransactionByHash(HashValue, oneshot::Sender<Option<SignedTransaction>>),
+    /// Estimates the gas unit price at the given percentile (0 to 100), based
+    /// on the ready transactions in mempool and the transactions committed in
+    /// (at most) the given number of recent blocks.
+    EstimateGasPrice(u8, usize, oneshot::Sender<Result<GasPriceEstimate>>),
+    /// Subscribes to the eviction of the transaction with the given sender and sequence
+    /// number. The callback is notified if the transaction is removed from mempool without
+    /// being committed (e.g., on expiration, replacement or parking lot eviction).
+    SubscribeTransactionEviction(AccountAddress, u64, oneshot::Sender<TransactionEviction>),
+}
+
+/// The source of a transaction submitted by a client (used to enforce submission quotas)
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SubmissionSource {
+    /// The submission did not identify its source
+    Anonymous,
+    /// The submission was made with the given API key
+    ApiKey(String),
+}
+
+impl SubmissionSource {
+    /// Returns the API key of the source (if any)
+    pub fn get_api_key(&self) -> Option<&str> {
+        match self {
+            SubmissionSource::Anonymous => None,
+            SubmissionSource::ApiKey(api_key) => Some(api_key),
+        }
+    }
 }
 
 pub type MempoolClientSender = mpsc::Sender<MempoolClientRequest>;
@@ -305,6 +367,29 @@ impl From<Vec<u64>> for MultiBucketTimelineIndexIds {
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct MultiBatchId(pub Vec<(u64, u64)>);
 
+/// A group of sequential transactions of an account, i.e., transactions that were
+/// submitted together and are kept together (for broadcast and block inclusion).
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct TransactionGroup {
+    pub sender: AccountAddress,
+    pub first_sequence_number: u64,
+    pub last_sequence_number: u64,
+}
+
+impl TransactionGroup {
+    /// Returns true iff the given transaction is in the group
+    pub fn contains(&self, transaction: &SignedTransaction) -> bool {
+        transaction.sender() == self.sender
+            && (self.first_sequence_number..=self.last_sequence_number)
+                .contains(&transaction.sequence_number())
+    }
+
+    /// Returns the number of transactions in the group
+    pub fn num_transactions(&self) -> usize {
+        (self.last_sequence_number - self.first_sequence_number + 1) as usize
+    }
+}
+
 impl MultiBatchId {
     pub(crate) fn from_timeline_ids(
         old: &MultiBucketTimelineIndexIds,
@@ -341,7 +426,11 @@ impl Ord for MultiBatchId {
 
 #[cfg(test)]
 mod test {
-    use crate::shared_mempool::types::{MultiBatchId, MultiBucketTimelineIndexIds};
+    use crate::shared_mempool::types::{
+        BandwidthClass, BroadcastWindow, MultiBatchId, MultiBucketTimelineIndexIds,
+    };
+    use aptos_config::config::AdaptiveBroadcastConfig;
+    use std::time::Duration;
 
     #[test]
     fn test_multi_bucket_timeline_ids_update() {
@@ -360,6 +449,58 @@ mod test {
 
         assert!(left > right);
     }
+
+    #[test]
+    fn test_broadcast_window_bandwidth_class() {
+        let config = AdaptiveBroadcastConfig {
+            enabled: true,
+            fast_peer_max_rtt_ms: 50,
+            slow_peer_min_rtt_ms: 500,
+            slow_peer_min_error_rate_percent: 10,
+            window_scaling_factor: 2,
+        };
+
+        // Peers without any ACKs are normal
+        let mut window = BroadcastWindow::default();
+        assert_eq!(window.bandwidth_class(&config), BandwidthClass::Normal);
+
+        // Peers with low RTTs are fast
+        window.record_ack(Duration::from_millis(10));
+        assert_eq!(window.bandwidth_class(&config), BandwidthClass::Fast);
+
+        // Peers with high (smoothed) RTTs are slow
+        for _ in 0..20 {
+            window.record_ack(Duration::from_millis(1_000));
+        }
+        assert_eq!(window.bandwidth_class(&config), BandwidthClass::Slow);
+
+        // Peers recover once their RTTs drop again
+        for _ in 0..20 {
+            window.record_ack(Duration::from_millis(100));
+        }
+        assert_eq!(window.bandwidth_class(&config), BandwidthClass::Normal);
+
+        // Peers with frequent errors are slow (regardless of their RTTs)
+        window.record_error();
+        assert_eq!(window.bandwidth_class(&config), BandwidthClass::Slow);
+        for _ in 0..20 {
+            window.record_ack(Duration::from_millis(100));
+        }
+        assert_eq!(window.bandwidth_class(&config), BandwidthClass::Normal);
+    }
+
+    #[test]
+    fn test_bandwidth_class_windows() {
+        assert_eq!(BandwidthClass::Fast.batch_size(300, 2), 600);
+        assert_eq!(BandwidthClass::Normal.batch_size(300, 2), 300);
+        assert_eq!(BandwidthClass::Slow.batch_size(300, 2), 150);
+        assert_eq!(BandwidthClass::Slow.batch_size(1, 2), 1);
+
+        assert_eq!(BandwidthClass::Fast.interval_ms(10, 2), 5);
+        assert_eq!(BandwidthClass::Normal.interval_ms(10, 2), 10);
+        assert_eq!(BandwidthClass::Slow.interval_ms(10, 2), 20);
+        assert_eq!(BandwidthClass::Fast.interval_ms(1, 2), 1);
+    }
 }
 
 /// Txn broadcast-related info for a given remote peer.
@@ -371,6 +512,8 @@ pub struct BroadcastInfo {
     pub retry_batches: BTreeSet<MultiBatchId>,
     // Whether broadcasting to this peer is in backoff mode, e.g. broadcasting at longer intervals.
     pub backoff_mode: bool,
+    // The observed broadcast performance of this peer (used for adaptive broadcast windows).
+    pub window: BroadcastWindow,
 }
 
 impl BroadcastInfo {
@@ -379,6 +522,97 @@ impl BroadcastInfo {
             sent_batches: BTreeMap::new(),
             retry_batches: BTreeSet::new(),
             backoff_mode: false,
+            window: BroadcastWindow::default(),
+        }
+    }
+}
+
+/// The bandwidth class of a peer, as observed from its broadcast ACKs. The class
+/// determines the broadcast window (i.e., the batch size and interval) of the peer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BandwidthClass {
+    Fast,
+    Normal,
+    Slow,
+}
+
+impl BandwidthClass {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            BandwidthClass::Fast => "fast",
+            BandwidthClass::Normal => "normal",
+            BandwidthClass::Slow => "slow",
+        }
+    }
+
+    /// Returns the broadcast batch size for the class, given the default batch size
+    pub fn batch_size(&self, default_batch_size: usize, scaling_factor: u64) -> usize {
+        let scaling_factor = scaling_factor.max(1) as usize;
+        match self {
+            BandwidthClass::Fast => default_batch_size.saturating_mul(scaling_factor),
+            BandwidthClass::Normal => default_batch_size,
+            BandwidthClass::Slow => (default_batch_size / scaling_factor).max(1),
+        }
+    }
+
+    /// Returns the broadcast interval for the class, given the default interval
+    pub fn interval_ms(&self, default_interval_ms: u64, scaling_factor: u64) -> u64 {
+        let scaling_factor = scaling_factor.max(1);
+        match self {
+            BandwidthClass::Fast => (default_interval_ms / scaling_factor).max(1),
+            BandwidthClass::Normal => default_interval_ms,
+            BandwidthClass::Slow => default_interval_ms.saturating_mul(scaling_factor),
+        }
+    }
+}
+
+// The weight of a new observation in the moving averages of a broadcast window
+const BROADCAST_WINDOW_EWMA_WEIGHT: f64 = 0.2;
+
+/// The observed broadcast performance of a remote peer, tracked as exponentially
+/// weighted moving averages of the ACK round trip times and the broadcast errors.
+#[derive(Clone, Debug, Default)]
+pub struct BroadcastWindow {
+    // The smoothed ACK round trip time (if any ACKs were received).
+    smoothed_rtt: Option<Duration>,
+    // The smoothed fraction of broadcasts that expired or failed (between 0 and 1).
+    error_rate: f64,
+}
+
+impl BroadcastWindow {
+    /// Records a broadcast that was ACK'ed after the given round trip time
+    pub fn record_ack(&mut self, rtt: Duration) {
+        self.smoothed_rtt = Some(match self.smoothed_rtt {
+            Some(smoothed_rtt) => {
+                smoothed_rtt.mul_f64(1.0 - BROADCAST_WINDOW_EWMA_WEIGHT)
+                    + rtt.mul_f64(BROADCAST_WINDOW_EWMA_WEIGHT)
+            },
+            None => rtt,
+        });
+        self.error_rate *= 1.0 - BROADCAST_WINDOW_EWMA_WEIGHT;
+    }
+
+    /// Records a broadcast that expired (without an ACK) or failed to send
+    pub fn record_error(&mut self) {
+        self.error_rate =
+            self.error_rate * (1.0 - BROADCAST_WINDOW_EWMA_WEIGHT) + BROADCAST_WINDOW_EWMA_WEIGHT;
+    }
+
+    /// Returns the bandwidth class of the peer. Peers without any ACKs (yet) are
+    /// considered normal, unless their broadcasts keep failing.
+    pub fn bandwidth_class(&self, config: &AdaptiveBroadcastConfig) -> BandwidthClass {
+        if self.error_rate * 100.0 > config.slow_peer_min_error_rate_percent as f64 {
+            return BandwidthClass::Slow;
+        }
+
+        match self.smoothed_rtt {
+            Some(rtt) if rtt > Duration::from_millis(config.slow_peer_min_rtt_ms) => {
+                BandwidthClass::Slow
+            },
+            Some(rtt) if rtt < Duration::from_millis(config.fast_peer_max_rtt_ms) => {
+                BandwidthClass::Fast
+            },
+            _ => BandwidthClass::Normal,
         }
     }
 }
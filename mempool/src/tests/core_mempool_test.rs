@@ -3,7 +3,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::{CoreMempool, MempoolTransaction, SubmittedBy, TimelineState},
+    core_mempool::{
+        build_transaction_chains, pack_transaction_chains, CoreMempool, EvictionReason,
+        MempoolTransaction, SubmittedBy, TimelineState, TransactionChain,
+    },
+    shared_mempool::types::TransactionGroup,
     tests::common::{
         add_signed_txn, add_txn, add_txns_to_mempool, setup_mempool,
         setup_mempool_with_broadcast_buckets, txn_bytes_len, TestTransaction,
@@ -13,8 +17,11 @@ use aptos_config::config::NodeConfig;
 use aptos_consensus_types::common::{TransactionInProgress, TransactionSummary};
 use aptos_crypto::HashValue;
 use aptos_types::{
-    mempool_status::MempoolStatusCode, transaction::SignedTransaction, vm_status::DiscardedVMStatus,
+    mempool_status::{MempoolRejectionReason, MempoolStatus, MempoolStatusCode},
+    transaction::SignedTransaction,
+    vm_status::DiscardedVMStatus,
 };
+use futures::channel::oneshot;
 use itertools::Itertools;
 use maplit::btreemap;
 use std::time::{Duration, SystemTime};
@@ -299,6 +306,52 @@ fn test_system_ttl() {
     assert_eq!(vec![transaction.make_signed_transaction()], batch);
 }
 
+#[test]
+fn test_eviction_notifications() {
+    let (mut pool, _) = setup_mempool();
+    let subscribe = |pool: &mut CoreMempool, address: usize| {
+        let (callback, receiver) = oneshot::channel();
+        pool.subscribe_eviction(TestTransaction::get_address(address), 0, callback);
+        receiver
+    };
+
+    // Replace a transaction with a higher gas price one
+    let txns = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(0, 0, 1)]);
+    let mut replaced_receiver = subscribe(&mut pool, 0);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 5)).unwrap();
+    let eviction = replaced_receiver.try_recv().unwrap().unwrap();
+    assert_eq!(eviction.reason, EvictionReason::Replaced);
+    assert_eq!(eviction.hash, txns[0].committed_hash());
+
+    // Expire a transaction
+    let txn = TestTransaction::new(1, 0, 1).make_signed_transaction_with_expiration_time(0);
+    let expired_hash = txn.committed_hash();
+    pool.add_txn(txn, 1, 0, TimelineState::NotReady, false);
+    let mut expired_receiver = subscribe(&mut pool, 1);
+    pool.gc_by_expiration_time(Duration::from_secs(1));
+    let eviction = expired_receiver.try_recv().unwrap().unwrap();
+    assert_eq!(eviction.reason, EvictionReason::Expired);
+    assert_eq!(eviction.hash, expired_hash);
+
+    // Reject a transaction
+    let txns = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(2, 0, 1)]);
+    let mut rejected_receiver = subscribe(&mut pool, 2);
+    pool.reject_transaction(
+        &TestTransaction::get_address(2),
+        0,
+        &txns[0].committed_hash(),
+        &DiscardedVMStatus::MALFORMED,
+    );
+    let eviction = rejected_receiver.try_recv().unwrap().unwrap();
+    assert_eq!(eviction.reason, EvictionReason::Rejected);
+
+    // Commit a transaction (the subscribers are not notified)
+    add_txns_to_mempool(&mut pool, vec![TestTransaction::new(3, 0, 1)]);
+    let mut committed_receiver = subscribe(&mut pool, 3);
+    pool.commit_transaction(&TestTransaction::get_address(3), 0);
+    assert_eq!(committed_receiver.try_recv().unwrap(), None);
+}
+
 #[test]
 fn test_commit_callback() {
     // Consensus commit callback should unlock txns in parking lot.
@@ -391,6 +444,39 @@ fn test_timeline() {
     assert_eq!(0, pool.get_parking_lot_size());
 }
 
+#[test]
+fn test_parked_transactions_promoted_when_gap_filled() {
+    let mut pool = setup_mempool().0;
+    let txns = add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(1, 0, 1),
+        TestTransaction::new(1, 2, 1),
+        TestTransaction::new(1, 3, 1),
+        TestTransaction::new(1, 4, 1),
+    ]);
+    // Txns 2, 3 and 4 should be in parking lot.
+    assert_eq!(3, pool.get_parking_lot_size());
+
+    // Add txn 1 to fill the gap, and promote the whole chain.
+    add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 1, 1)]);
+    assert_eq!(0, pool.get_parking_lot_size());
+    let (timeline, _) = pool.read_timeline(&vec![0].into(), 10);
+    assert_eq!(view(timeline), vec![0, 1, 2, 3, 4]);
+
+    // Reject txn 2 to open a new gap. New txns after the gap should be parked.
+    pool.reject_transaction(
+        &TestTransaction::get_address(1),
+        2,
+        &txns[1].clone().committed_hash(),
+        &DiscardedVMStatus::MALFORMED,
+    );
+    add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 5, 1)]);
+    assert_eq!(1, pool.get_parking_lot_size());
+
+    // Add txn 2 again to fill the gap, and promote txn 5.
+    add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 2, 1)]);
+    assert_eq!(0, pool.get_parking_lot_size());
+}
+
 #[test]
 fn test_multi_bucket_timeline() {
     let mut pool = setup_mempool_with_broadcast_buckets(vec![0, 101, 201]).0;
@@ -588,6 +674,80 @@ fn test_capacity_bytes() {
     }
 }
 
+#[test]
+fn test_rejection_reasons() {
+    let mut config = NodeConfig::generate_random_config();
+    config.mempool.capacity = 2;
+    config.mempool.capacity_per_user = 1;
+    let mut pool = CoreMempool::new(&config);
+
+    // Add a transaction for account 0
+    add_txn(&mut pool, TestTransaction::new(0, 0, 2)).unwrap();
+
+    // Verify that a replacement with a lower gas price is underpriced
+    let status = add_txn_with_status(
+        &mut pool,
+        TestTransaction::new(0, 0, 1).make_signed_transaction(),
+    );
+    assert_eq!(status.code, MempoolStatusCode::InvalidUpdate);
+    assert_eq!(
+        status.rejection_reason,
+        Some(MempoolRejectionReason::ReplacementUnderpriced)
+    );
+
+    // Verify that a different transaction with the same sequence number is a duplicate
+    let txn = TestTransaction::new(0, 0, 2).make_signed_transaction_with_max_gas_amount(200);
+    let status = add_txn_with_status(&mut pool, txn);
+    assert_eq!(status.code, MempoolStatusCode::InvalidUpdate);
+    assert_eq!(
+        status.rejection_reason,
+        Some(MempoolRejectionReason::Duplicate)
+    );
+
+    // Verify that exceeding the account capacity is reported
+    let status = add_txn_with_status(
+        &mut pool,
+        TestTransaction::new(0, 1, 2).make_signed_transaction(),
+    );
+    assert_eq!(status.code, MempoolStatusCode::TooManyTransactions);
+    assert_eq!(
+        status.rejection_reason,
+        Some(MempoolRejectionReason::AccountQuotaExceeded)
+    );
+
+    // Fill up mempool and verify that ready transactions can't be admitted
+    add_txn(&mut pool, TestTransaction::new(1, 0, 2)).unwrap();
+    let status = add_txn_with_status(
+        &mut pool,
+        TestTransaction::new(2, 0, 2).make_signed_transaction(),
+    );
+    assert_eq!(status.code, MempoolStatusCode::MempoolIsFull);
+    assert_eq!(
+        status.rejection_reason,
+        Some(MempoolRejectionReason::FeeTooLowForLoad)
+    );
+
+    // Verify that non-ready transactions can't be parked
+    let status = add_txn_with_status(
+        &mut pool,
+        TestTransaction::new(2, 5, 2).make_signed_transaction(),
+    );
+    assert_eq!(status.code, MempoolStatusCode::MempoolIsFull);
+    assert_eq!(
+        status.rejection_reason,
+        Some(MempoolRejectionReason::ParkingLotFull)
+    );
+
+    // Verify that accepted transactions have no rejection reason
+    pool.commit_transaction(&TestTransaction::get_address(1), 0);
+    let status = add_txn_with_status(
+        &mut pool,
+        TestTransaction::new(2, 0, 2).make_signed_transaction(),
+    );
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+    assert_eq!(status.rejection_reason, None);
+}
+
 fn new_test_mempool_transaction(address: usize, sequence_number: u64) -> MempoolTransaction {
     let signed_txn = TestTransaction::new(address, sequence_number, 1).make_signed_transaction();
     MempoolTransaction::new(
@@ -959,3 +1119,337 @@ fn test_include_gas_upgraded() {
     });
     assert_eq!(batch.len(), 0);
 }
+
+#[test]
+fn test_capacity_report() {
+    let (mut pool, _) = setup_mempool_with_broadcast_buckets(vec![0, 100]);
+
+    // Add transactions to several buckets (one of which is parked)
+    add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(0, 0, 50),
+        TestTransaction::new(0, 1, 50),
+        TestTransaction::new(0, 2, 50),
+        TestTransaction::new(1, 0, 200),
+        TestTransaction::new(2, 5, 150),
+    ]);
+
+    // Commit a single transaction
+    pool.commit_transaction(&TestTransaction::get_address(0), 0);
+
+    // Verify the composition of the report
+    let report = pool.gen_capacity_report(1);
+    assert_eq!(report.utilization.num_txns, 4);
+    assert_eq!(report.utilization.num_parked_txns, 1);
+    assert_eq!(
+        report.utilization.size_bytes,
+        report.buckets.iter().map(|b| b.size_bytes).sum::<usize>()
+    );
+
+    let bucket_sizes: Vec<_> = report
+        .buckets
+        .iter()
+        .map(|b| (b.bucket_min.as_str(), b.num_txns, b.num_parked_txns))
+        .collect();
+    assert_eq!(bucket_sizes, vec![("0", 2, 0), ("100", 2, 1)]);
+
+    assert_eq!(report.accounts.num_accounts, 3);
+    assert_eq!(report.accounts.top_accounts.len(), 1);
+    assert_eq!(
+        report.accounts.top_accounts[0].address,
+        TestTransaction::get_address(0)
+    );
+    assert_eq!(report.accounts.top_accounts[0].num_txns, 2);
+    assert_eq!(report.accounts.top_accounts_txns_share, 0.5);
+
+    assert_eq!(report.ages.len(), 6);
+    assert_eq!(report.ages.iter().map(|a| a.num_txns).sum::<usize>(), 4);
+
+    // Verify the throughput and the eviction horizon
+    assert!(report.throughput.insertion_tps > report.throughput.commit_tps);
+    assert!(report.throughput.commit_tps > 0.0);
+    assert!(report.eviction_horizon.net_growth_tps > 0.0);
+    assert!(report.eviction_horizon.secs_until_full.is_some());
+    assert!(report.eviction_horizon.secs_to_drain.is_some());
+}
+
+#[test]
+fn test_estimate_gas_price() {
+    let (mut pool, _) = setup_mempool();
+
+    // Verify there is no estimate for an empty mempool
+    let estimate = pool.estimate_gas_price(50, 10).unwrap();
+    assert_eq!(estimate.gas_unit_price, None);
+
+    // Add ready transactions and a parked transaction (which is ignored)
+    add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(0, 0, 10),
+        TestTransaction::new(1, 0, 20),
+        TestTransaction::new(2, 0, 30),
+        TestTransaction::new(3, 5, 1000),
+    ]);
+    let estimate = pool.estimate_gas_price(50, 10).unwrap();
+    assert_eq!(estimate.gas_unit_price, Some(20));
+    assert_eq!(estimate.num_ready_txns, 3);
+    assert_eq!(estimate.num_committed_txns, 0);
+    assert_eq!(estimate.horizon_blocks, 0);
+
+    // Commit two blocks (each with a single transaction)
+    for account in [0, 1] {
+        let sender = TestTransaction::get_address(account);
+        let gas_unit_price = pool.get_gas_unit_price(&sender, 0).unwrap();
+        pool.commit_transaction(&sender, 0);
+        pool.record_committed_gas_prices(vec![gas_unit_price]);
+    }
+
+    // Verify the estimates only consider the committed blocks within the horizon
+    let estimate = pool.estimate_gas_price(0, 1).unwrap();
+    assert_eq!(estimate.gas_unit_price, Some(20));
+    assert_eq!(estimate.num_ready_txns, 1);
+    assert_eq!(estimate.num_committed_txns, 1);
+    assert_eq!(estimate.horizon_blocks, 1);
+    let estimate = pool.estimate_gas_price(0, 10).unwrap();
+    assert_eq!(estimate.gas_unit_price, Some(10));
+    assert_eq!(estimate.horizon_blocks, 2);
+    let estimate = pool.estimate_gas_price(100, 10).unwrap();
+    assert_eq!(estimate.gas_unit_price, Some(30));
+
+    // Verify that invalid percentiles are rejected
+    assert!(pool.estimate_gas_price(101, 10).is_err());
+}
+
+#[test]
+fn test_pack_transaction_chains() {
+    let sender_a = TestTransaction::get_address(0);
+    let sender_b = TestTransaction::get_address(1);
+    let sender_c = TestTransaction::get_address(2);
+    let candidates = vec![
+        (TransactionSummary::new(sender_b, 0), 15),
+        (TransactionSummary::new(sender_a, 3), 10),
+        (TransactionSummary::new(sender_c, 0), 12),
+        (TransactionSummary::new(sender_a, 4), 10),
+        (TransactionSummary::new(sender_a, 5), 10),
+    ];
+    let chains = build_transaction_chains(&candidates);
+    assert_eq!(chains, vec![
+        TransactionChain {
+            sender: sender_b,
+            sequence_numbers: vec![0],
+            aggregate_ranking_score: 15,
+        },
+        TransactionChain {
+            sender: sender_a,
+            sequence_numbers: vec![3, 4, 5],
+            aggregate_ranking_score: 30,
+        },
+        TransactionChain {
+            sender: sender_c,
+            sequence_numbers: vec![0],
+            aggregate_ranking_score: 12,
+        },
+    ]);
+
+    // Complete chains are packed first, by price density (so the long chain of low-fee
+    // transactions does not displace the higher fee transactions, despite its higher total)
+    assert_eq!(pack_transaction_chains(chains.clone(), 5), vec![
+        TransactionSummary::new(sender_b, 0),
+        TransactionSummary::new(sender_c, 0),
+        TransactionSummary::new(sender_a, 3),
+        TransactionSummary::new(sender_a, 4),
+        TransactionSummary::new(sender_a, 5),
+    ]);
+    assert_eq!(pack_transaction_chains(chains.clone(), 4), vec![
+        TransactionSummary::new(sender_b, 0),
+        TransactionSummary::new(sender_c, 0),
+        TransactionSummary::new(sender_a, 3),
+        TransactionSummary::new(sender_a, 4),
+    ]);
+    // The remaining space is filled with the prefixes of the chains that did not fit
+    assert_eq!(pack_transaction_chains(chains, 2), vec![
+        TransactionSummary::new(sender_b, 0),
+        TransactionSummary::new(sender_c, 0),
+    ]);
+}
+
+#[test]
+fn test_chain_aware_batch_packing() {
+    let transactions = vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 1, 30),
+        TestTransaction::new(1, 0, 20),
+        TestTransaction::new(2, 0, 12),
+    ];
+
+    // By default, the highest paying ready transactions are pulled independently
+    let (mut pool, _) = setup_mempool();
+    let txns = add_txns_to_mempool(&mut pool, transactions.clone());
+    let batch = pool.get_batch(3, 2048, true, false, btreemap![]);
+    assert_eq!(batch, vec![
+        txns[2].clone(),
+        txns[3].clone(),
+        txns[0].clone()
+    ]);
+
+    // With chain-aware packing, the complete chain with the higher fee per transaction is
+    // pulled (instead of only its low-fee prefix)
+    let mut config = NodeConfig::generate_random_config();
+    config.mempool.enable_chain_aware_batch_packing = true;
+    let mut pool = CoreMempool::new(&config);
+    let txns = add_txns_to_mempool(&mut pool, transactions);
+    let batch = pool.get_batch(3, 2048, true, false, btreemap![]);
+    assert_eq!(batch, vec![
+        txns[2].clone(),
+        txns[0].clone(),
+        txns[1].clone()
+    ]);
+}
+
+#[test]
+fn test_transaction_group_all_or_nothing() {
+    // Groups of non-sequential transactions are rejected
+    let (mut pool, _) = setup_mempool();
+    let (_, status) = add_txn_group_with_status(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 2, 1),
+    ]);
+    assert_eq!(status.code, MempoolStatusCode::InvalidTransactionGroup);
+    assert!(pool
+        .get_batch(10, 2048, true, false, btreemap![])
+        .is_empty());
+
+    // Groups with transactions of different accounts are rejected
+    let (_, status) = add_txn_group_with_status(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(1, 1, 1),
+    ]);
+    assert_eq!(status.code, MempoolStatusCode::InvalidTransactionGroup);
+    assert!(pool
+        .get_batch(10, 2048, true, false, btreemap![])
+        .is_empty());
+
+    // If any transaction of the group is rejected, none of them are added
+    let mut config = NodeConfig::generate_random_config();
+    config.mempool.capacity_per_user = 2;
+    let mut pool = CoreMempool::new(&config);
+    let (_, status) = add_txn_group_with_status(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 1, 1),
+        TestTransaction::new(0, 2, 1),
+    ]);
+    assert_eq!(status.code, MempoolStatusCode::TooManyTransactions);
+    assert!(pool
+        .get_batch(10, 2048, true, false, btreemap![])
+        .is_empty());
+    assert_eq!(pool.get_transaction_store().get_transactions().len(), 0);
+
+    // A valid group is added
+    let (txns, status) = add_txn_group_with_status(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 1, 1),
+    ]);
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+    assert_eq!(pool.get_batch(10, 2048, true, false, btreemap![]), txns);
+}
+
+#[test]
+fn test_transaction_group_batch_boundaries() {
+    let (mut pool, _) = setup_mempool();
+    let (group_txns, status) = add_txn_group_with_status(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 1, 1),
+        TestTransaction::new(0, 2, 1),
+    ]);
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+    let txns = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 0, 10)]);
+
+    // The group doesn't fit into the batch, so it is not split
+    let batch = pool.get_batch(2, 2048, true, false, btreemap![]);
+    assert_eq!(batch, vec![txns[0].clone()]);
+
+    // The whole group fits into the batch
+    let batch = pool.get_batch(4, 2048, true, false, btreemap![]);
+    let mut expected_batch = txns.clone();
+    expected_batch.extend(group_txns.clone());
+    assert_eq!(batch, expected_batch);
+
+    // The group isn't split by the byte limit either
+    let max_bytes = txn_bytes_len(TestTransaction::new(1, 0, 10))
+        + txn_bytes_len(TestTransaction::new(0, 0, 1)) * 2;
+    let batch = pool.get_batch(4, max_bytes, true, false, btreemap![]);
+    assert_eq!(batch, txns);
+
+    // The group is broadcast together
+    let (timeline, _) = pool.read_timeline(&vec![0].into(), 1);
+    assert_eq!(timeline, group_txns);
+}
+
+#[test]
+fn test_transaction_group_reject() {
+    let (mut pool, _) = setup_mempool();
+    let (txns, status) = add_txn_group_with_status(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 1, 1),
+        TestTransaction::new(0, 2, 1),
+    ]);
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+
+    // Rejecting a transaction of the group removes the whole group
+    pool.reject_transaction(
+        &TestTransaction::get_address(0),
+        1,
+        &txns[1].clone().committed_hash(),
+        &DiscardedVMStatus::MALFORMED,
+    );
+    for sequence_number in 0..3 {
+        assert!(pool
+            .get_transaction_store()
+            .get(&TestTransaction::get_address(0), sequence_number)
+            .is_none());
+    }
+}
+
+#[test]
+fn test_transaction_group_broadcast_metadata() {
+    let (mut pool, _) = setup_mempool();
+    let (group_txns, status) = add_txn_group_with_status(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 1, 1),
+    ]);
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+    let txns = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 0, 10)]);
+
+    // The group is only reported if it is completely contained in the batch
+    let expected_group = TransactionGroup {
+        sender: TestTransaction::get_address(0),
+        first_sequence_number: 0,
+        last_sequence_number: 1,
+    };
+    let mut batch = txns.clone();
+    batch.extend(group_txns.clone());
+    assert_eq!(pool.get_transaction_groups(&batch), vec![expected_group]);
+    assert!(pool.get_transaction_groups(&batch[..2]).is_empty());
+    assert!(pool.get_transaction_groups(&txns).is_empty());
+}
+
+/// Adds the given transactions to mempool as a group, and returns
+/// the signed transactions and the mempool status of the group.
+fn add_txn_group_with_status(
+    pool: &mut CoreMempool,
+    txns: Vec<TestTransaction>,
+) -> (Vec<SignedTransaction>, MempoolStatus) {
+    let txns: Vec<_> = txns
+        .into_iter()
+        .map(|txn| txn.make_signed_transaction())
+        .collect();
+    let txns_with_scores = txns
+        .iter()
+        .map(|txn| (txn.clone(), txn.gas_unit_price()))
+        .collect();
+    let status = pool.add_txn_group(txns_with_scores, 0, TimelineState::NotReady, false);
+    (txns, status)
+}
+
+/// Adds the given transaction to mempool and returns the mempool status
+fn add_txn_with_status(pool: &mut CoreMempool, txn: SignedTransaction) -> MempoolStatus {
+    let gas_unit_price = txn.gas_unit_price();
+    pool.add_txn(txn, gas_unit_price, 0, TimelineState::NotReady, false)
+}
@@ -148,6 +148,51 @@ async fn vfn_middle_man_test() {
         .await;
 }
 
+/// Tests that a node only requests the transactions missing from a digest announcement
+#[tokio::test]
+async fn test_digest_broadcast_requests_missing_txns() {
+    let mut node = MempoolTestFrameworkBuilder::single_vfn();
+    let (fn_peer_network_id, fn_metadata) =
+        pfn_vfn_mock_connection(ConnectionOrigin::Inbound, &ALL_PROTOCOLS);
+    node.connect_self(fn_peer_network_id.network_id(), fn_metadata);
+
+    // Receive the first transaction
+    node.receive_message(ProtocolId::MempoolDirectSend, fn_peer_network_id, TXN_1)
+        .await;
+    node.assert_only_txns_in_mempool(TXN_1);
+
+    // Only the second transaction should be requested from the announcement
+    node.receive_digests_and_respond(fn_peer_network_id, ALL_TXNS, TXN_2)
+        .await;
+    node.assert_only_txns_in_mempool(ALL_TXNS);
+
+    // Nothing should be requested when all transactions are already in mempool
+    node.receive_digests_and_respond(fn_peer_network_id, ALL_TXNS, &[])
+        .await;
+    node.assert_only_txns_in_mempool(ALL_TXNS);
+}
+
+/// Tests that a validator asks its peers to back off while consensus is back pressured
+#[tokio::test]
+async fn test_consensus_backpressure_backoff() {
+    let mut node = MempoolTestFrameworkBuilder::single_validator();
+    let (vfn_peer_network_id, vfn_metadata) =
+        vfn_validator_mock_connection(ConnectionOrigin::Inbound, &ALL_PROTOCOLS);
+    node.connect_self(vfn_peer_network_id.network_id(), vfn_metadata);
+
+    // While consensus is back pressured, transactions are accepted but the peer should back off
+    node.notify_consensus_backpressure(true).await;
+    node.receive_message_with_backoff(ProtocolId::MempoolDirectSend, vfn_peer_network_id, TXN_1)
+        .await;
+    node.assert_only_txns_in_mempool(TXN_1);
+
+    // Once the backpressure is lifted, the peer should no longer back off
+    node.notify_consensus_backpressure(false).await;
+    node.receive_message(ProtocolId::MempoolDirectSend, vfn_peer_network_id, TXN_2)
+        .await;
+    node.assert_only_txns_in_mempool(ALL_TXNS);
+}
+
 /// Tests when a node skips an ack
 #[tokio::test]
 async fn test_skip_ack_rebroadcast() {
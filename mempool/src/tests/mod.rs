@@ -13,7 +13,11 @@ mod multi_node_test;
 #[cfg(test)]
 mod node;
 #[cfg(test)]
+mod persistence_test;
+#[cfg(test)]
 mod shared_mempool_test;
+#[cfg(test)]
+mod submission_quotas_test;
 
 pub mod fuzzing;
 #[cfg(any(feature = "fuzzing", test))]
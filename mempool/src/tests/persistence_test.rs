@@ -0,0 +1,231 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    core_mempool::CoreMempool,
+    shared_mempool::persistence::MempoolPersistence,
+    tests::common::{add_txns_to_mempool, setup_mempool, txn_bytes_len, TestTransaction},
+};
+use aptos_config::config::MempoolPersistenceConfig;
+use aptos_infallible::Mutex;
+use aptos_temppath::TempPath;
+use aptos_types::transaction::SignedTransaction;
+use std::{path::Path, sync::Arc, thread, time::Duration};
+
+#[test]
+fn test_persist_and_load_transactions() {
+    // Create a mempool with two ready transactions and a parked transaction
+    let (mut mempool, _) = setup_mempool();
+    let transactions = add_txns_to_mempool(&mut mempool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(1, 0, 5),
+        TestTransaction::new(2, 5, 10), // Parked (sequence number gap)
+    ]);
+    let mempool = Arc::new(Mutex::new(mempool));
+
+    // Persist the ready and parked transactions
+    let storage_dir = create_storage_dir();
+    let persistence = create_persistence(
+        MempoolPersistenceConfig::default(),
+        storage_dir.path(),
+        mempool,
+    );
+    assert_eq!(persistence.persist_transactions().unwrap(), 3);
+
+    // Verify that all transactions are loaded (ordered by sender and sequence number)
+    assert_eq!(
+        persistence.load_persisted_transactions(),
+        sort_transactions(transactions.clone())
+    );
+
+    // Verify that the transactions are loaded again (until the next snapshot removes them)
+    assert_eq!(
+        persistence.load_persisted_transactions(),
+        sort_transactions(transactions)
+    );
+}
+
+#[test]
+fn test_persist_ready_transactions_only() {
+    // Create a mempool with two ready transactions and a parked transaction
+    let (mut mempool, _) = setup_mempool();
+    let transactions = add_txns_to_mempool(&mut mempool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(1, 0, 5),
+        TestTransaction::new(2, 5, 10), // Parked (sequence number gap)
+    ]);
+    let mempool = Arc::new(Mutex::new(mempool));
+
+    // Persist the transactions without the parked transactions
+    let storage_dir = create_storage_dir();
+    let config = MempoolPersistenceConfig {
+        persist_parked_transactions: false,
+        ..Default::default()
+    };
+    let persistence = create_persistence(config, storage_dir.path(), mempool);
+    assert_eq!(persistence.persist_transactions().unwrap(), 2);
+
+    // Verify that only the ready transactions are loaded
+    assert_eq!(
+        persistence.load_persisted_transactions(),
+        sort_transactions(transactions[..2].to_vec())
+    );
+}
+
+#[test]
+fn test_persist_max_bytes() {
+    // Create a mempool with two ready transactions
+    let (mut mempool, _) = setup_mempool();
+    let transactions = add_txns_to_mempool(&mut mempool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(1, 0, 5),
+    ]);
+    let mempool = Arc::new(Mutex::new(mempool));
+
+    // Persist the ready transactions with room for only a single transaction
+    let storage_dir = create_storage_dir();
+    let config = MempoolPersistenceConfig {
+        max_persisted_bytes: txn_bytes_len(TestTransaction::new(1, 0, 5)),
+        ..Default::default()
+    };
+    let persistence = create_persistence(config, storage_dir.path(), mempool);
+    assert_eq!(persistence.persist_transactions().unwrap(), 1);
+
+    // Verify that only the highest priority transaction is loaded
+    assert_eq!(persistence.load_persisted_transactions(), vec![
+        transactions[1].clone()
+    ]);
+}
+
+#[test]
+fn test_snapshots_remove_stale_transactions() {
+    // Create a mempool with two ready transactions and persist them
+    let (mut mempool, _) = setup_mempool();
+    let transactions = add_txns_to_mempool(&mut mempool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(1, 0, 5),
+    ]);
+    let mempool = Arc::new(Mutex::new(mempool));
+    let storage_dir = create_storage_dir();
+    let persistence = create_persistence(
+        MempoolPersistenceConfig::default(),
+        storage_dir.path(),
+        mempool.clone(),
+    );
+    assert_eq!(persistence.persist_transactions().unwrap(), 2);
+
+    // Commit the first transaction and take another snapshot
+    let committed_transaction = &transactions[0];
+    mempool.lock().commit_transaction(
+        &committed_transaction.sender(),
+        committed_transaction.sequence_number(),
+    );
+    assert_eq!(persistence.persist_transactions().unwrap(), 1);
+
+    // Verify that the committed transaction was removed from the DB
+    assert_eq!(persistence.load_persisted_transactions(), vec![
+        transactions[1].clone()
+    ]);
+
+    // Reopen the DB with an empty mempool, and verify that
+    // the first snapshot also removes the stale transactions.
+    drop(persistence);
+    let (empty_mempool, _) = setup_mempool();
+    let persistence = create_persistence(
+        MempoolPersistenceConfig::default(),
+        storage_dir.path(),
+        Arc::new(Mutex::new(empty_mempool)),
+    );
+    assert_eq!(persistence.load_persisted_transactions().len(), 1);
+    assert_eq!(persistence.persist_transactions().unwrap(), 0);
+    assert!(persistence.load_persisted_transactions().is_empty());
+}
+
+#[test]
+fn test_load_discards_old_transactions() {
+    // Create a mempool with a ready transaction and persist it
+    let (mut mempool, _) = setup_mempool();
+    add_txns_to_mempool(&mut mempool, vec![TestTransaction::new(0, 0, 1)]);
+    let mempool = Arc::new(Mutex::new(mempool));
+    let storage_dir = create_storage_dir();
+    let persistence = create_persistence(
+        MempoolPersistenceConfig::default(),
+        storage_dir.path(),
+        mempool.clone(),
+    );
+    assert_eq!(persistence.persist_transactions().unwrap(), 1);
+    drop(persistence);
+
+    // Verify that the transaction is discarded if it is too old
+    thread::sleep(Duration::from_millis(10));
+    let config = MempoolPersistenceConfig {
+        max_transaction_age_secs: 0,
+        ..Default::default()
+    };
+    let persistence = create_persistence(config, storage_dir.path(), mempool);
+    assert!(persistence.load_persisted_transactions().is_empty());
+}
+
+#[test]
+fn test_load_discards_corrupted_transactions() {
+    // Create a mempool with two ready transactions and persist them
+    let (mut mempool, _) = setup_mempool();
+    let transactions = add_txns_to_mempool(&mut mempool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(1, 0, 5),
+    ]);
+    let storage_dir = create_storage_dir();
+    let persistence = create_persistence(
+        MempoolPersistenceConfig::default(),
+        storage_dir.path(),
+        Arc::new(Mutex::new(mempool)),
+    );
+    assert_eq!(persistence.persist_transactions().unwrap(), 2);
+
+    // Corrupt the first transaction, and add a transaction that doesn't match its key
+    let corrupted_transaction = &transactions[0];
+    persistence.put_raw_value(
+        corrupted_transaction.sender(),
+        corrupted_transaction.sequence_number(),
+        vec![0xFF; 10],
+    );
+    persistence.put_raw_value(
+        TestTransaction::get_address(2),
+        0,
+        bcs::to_bytes(&(transactions[1].clone(), Duration::ZERO)).unwrap(),
+    );
+
+    // Verify that only the valid transaction is loaded
+    assert_eq!(persistence.load_persisted_transactions(), vec![
+        transactions[1].clone()
+    ]);
+}
+
+/// Creates a new mempool persistence handle
+fn create_persistence(
+    config: MempoolPersistenceConfig,
+    storage_dir: &Path,
+    mempool: Arc<Mutex<CoreMempool>>,
+) -> MempoolPersistence {
+    MempoolPersistence::new(
+        MempoolPersistenceConfig {
+            enabled: true,
+            ..config
+        },
+        storage_dir,
+        mempool,
+    )
+}
+
+/// Creates a temporary storage directory
+fn create_storage_dir() -> TempPath {
+    let storage_dir = TempPath::new();
+    storage_dir.create_as_dir().unwrap();
+    storage_dir
+}
+
+/// Sorts the given transactions by sender and sequence number (i.e., the load order)
+fn sort_transactions(mut transactions: Vec<SignedTransaction>) -> Vec<SignedTransaction> {
+    transactions.sort_by_key(|transaction| (transaction.sender(), transaction.sequence_number()));
+    transactions
+}
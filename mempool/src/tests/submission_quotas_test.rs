@@ -0,0 +1,155 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{SubmissionQuotaRejection, SubmissionQuotas, SubmissionSource};
+use aptos_config::config::{SubmissionQuota, SubmissionQuotasConfig};
+use aptos_time_service::TimeService;
+use maplit::btreemap;
+
+#[test]
+fn test_submission_quotas_disabled() {
+    // Create submission quotas that are disabled
+    let config = SubmissionQuotasConfig {
+        enabled: false,
+        default_quota: Some(SubmissionQuota::new(0, 1)),
+        ..Default::default()
+    };
+    let submission_quotas = SubmissionQuotas::new(&config, TimeService::mock());
+
+    // Verify that all submissions are accepted
+    for _ in 0..10 {
+        submission_quotas
+            .check_and_record_submission(&SubmissionSource::Anonymous)
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_submission_quotas_api_keys() {
+    // Create submission quotas with a default quota and two API key quotas
+    let config = SubmissionQuotasConfig {
+        enabled: true,
+        default_quota: Some(SubmissionQuota::new(1, 10)),
+        api_key_quotas: btreemap! {
+            "basic".into() => SubmissionQuota::new(2, 10),
+            "premium".into() => SubmissionQuota::new(5, 10),
+        },
+    };
+    let time_service = TimeService::mock();
+    let submission_quotas = SubmissionQuotas::new(&config, time_service.clone());
+
+    // Verify that each API key is limited by its own quota
+    let basic = SubmissionSource::ApiKey("basic".into());
+    let premium = SubmissionSource::ApiKey("premium".into());
+    verify_accepted_submissions(&submission_quotas, &basic, 2);
+    verify_accepted_submissions(&submission_quotas, &premium, 5);
+    verify_rejected_submission(
+        &submission_quotas,
+        &basic,
+        SubmissionQuotaRejection::ApiKeyQuotaExceeded,
+    );
+    verify_rejected_submission(
+        &submission_quotas,
+        &premium,
+        SubmissionQuotaRejection::ApiKeyQuotaExceeded,
+    );
+
+    // Verify that anonymous submissions and unknown API keys share the default quota
+    verify_accepted_submissions(&submission_quotas, &SubmissionSource::Anonymous, 1);
+    verify_rejected_submission(
+        &submission_quotas,
+        &SubmissionSource::ApiKey("unknown".into()),
+        SubmissionQuotaRejection::DefaultQuotaExceeded,
+    );
+
+    // Elapse the window and verify that the quotas are reset
+    time_service.into_mock().advance_secs(10);
+    verify_accepted_submissions(&submission_quotas, &basic, 2);
+    verify_accepted_submissions(&submission_quotas, &SubmissionSource::Anonymous, 1);
+
+    // Verify the usage summaries
+    let summaries = submission_quotas.get_summaries();
+    assert_eq!(summaries.len(), 3);
+    assert_eq!(summaries[0].api_key, None);
+    assert_eq!(summaries[0].total_accepted, 2);
+    assert_eq!(summaries[0].total_rejected, 1);
+    assert_eq!(summaries[1].api_key, Some("basic".into()));
+    assert_eq!(summaries[1].submissions_in_window, 2);
+    assert_eq!(summaries[1].total_accepted, 4);
+    assert_eq!(summaries[1].total_rejected, 1);
+    assert_eq!(summaries[2].api_key, Some("premium".into()));
+    assert_eq!(summaries[2].submissions_in_window, 0);
+    assert_eq!(summaries[2].total_accepted, 5);
+}
+
+#[test]
+fn test_submission_quotas_runtime_updates() {
+    // Create submission quotas without any quotas
+    let config = SubmissionQuotasConfig {
+        enabled: true,
+        ..Default::default()
+    };
+    let submission_quotas = SubmissionQuotas::new(&config, TimeService::mock());
+
+    // Verify that all submissions are accepted (the default quota is unlimited)
+    let api_key = SubmissionSource::ApiKey("api_key".into());
+    verify_accepted_submissions(&submission_quotas, &api_key, 10);
+
+    // Add a default quota and verify that it applies to the unknown API key
+    submission_quotas.set_quota(None, SubmissionQuota::new(10, 60));
+    verify_accepted_submissions(&submission_quotas, &api_key, 10);
+    verify_rejected_submission(
+        &submission_quotas,
+        &api_key,
+        SubmissionQuotaRejection::DefaultQuotaExceeded,
+    );
+
+    // Add a quota for the API key and verify that it is used
+    submission_quotas.set_quota(Some("api_key".into()), SubmissionQuota::new(3, 60));
+    verify_accepted_submissions(&submission_quotas, &api_key, 3);
+    verify_rejected_submission(
+        &submission_quotas,
+        &api_key,
+        SubmissionQuotaRejection::ApiKeyQuotaExceeded,
+    );
+
+    // Increase the quota for the API key and verify that the existing usage is preserved
+    submission_quotas.set_quota(Some("api_key".into()), SubmissionQuota::new(4, 60));
+    verify_accepted_submissions(&submission_quotas, &api_key, 1);
+    verify_rejected_submission(
+        &submission_quotas,
+        &api_key,
+        SubmissionQuotaRejection::ApiKeyQuotaExceeded,
+    );
+
+    // Remove both quotas and verify that all submissions are accepted again
+    submission_quotas.remove_quota(Some("api_key"));
+    submission_quotas.remove_quota(None);
+    verify_accepted_submissions(&submission_quotas, &api_key, 10);
+    assert_eq!(submission_quotas.get_summaries().len(), 1);
+}
+
+/// Verifies that the given number of submissions are accepted for the source
+fn verify_accepted_submissions(
+    submission_quotas: &SubmissionQuotas,
+    source: &SubmissionSource,
+    num_submissions: u64,
+) {
+    for _ in 0..num_submissions {
+        submission_quotas
+            .check_and_record_submission(source)
+            .unwrap();
+    }
+}
+
+/// Verifies that the next submission for the source is rejected with the expected rejection
+fn verify_rejected_submission(
+    submission_quotas: &SubmissionQuotas,
+    source: &SubmissionSource,
+    expected_rejection: SubmissionQuotaRejection,
+) {
+    let rejection = submission_quotas
+        .check_and_record_submission(source)
+        .unwrap_err();
+    assert_eq!(rejection, expected_rejection);
+}
@@ -4,9 +4,11 @@
 
 use crate::{
     core_mempool::CoreMempool,
+    network::TransactionDigest,
     shared_mempool::{start_shared_mempool, types::MultiBatchId},
     tests::{common, common::TestTransaction},
     MempoolClientRequest, MempoolClientSender, MempoolSyncMsg, QuorumStoreRequest,
+    SubmissionSource,
 };
 use aptos_channels::{aptos_channel, message_queues::QueueStyle};
 use aptos_config::{
@@ -149,7 +151,11 @@ impl MempoolNode {
             let (sender, receiver) = oneshot::channel();
 
             self.mempool_client_sender
-                .send(MempoolClientRequest::SubmitTransaction(txn, sender))
+                .send(MempoolClientRequest::SubmitTransaction(
+                    txn,
+                    SubmissionSource::Anonymous,
+                    sender,
+                ))
                 .await
                 .unwrap();
             let status = receiver.await.unwrap().unwrap();
@@ -157,6 +163,23 @@ impl MempoolNode {
         }
     }
 
+    /// Notifies the node of a change to the consensus backpressure
+    pub async fn notify_consensus_backpressure(&mut self, back_pressured: bool) {
+        self.consensus_to_mempool_sender
+            .send(QuorumStoreRequest::BackpressureNotification(back_pressured))
+            .await
+            .unwrap();
+
+        // Wait for an (empty) reject notification to be processed. Requests are
+        // handled in order, so the backpressure notification has been processed too.
+        let (callback, callback_rcv) = oneshot::channel();
+        self.consensus_to_mempool_sender
+            .send(QuorumStoreRequest::RejectNotification(vec![], callback))
+            .await
+            .unwrap();
+        callback_rcv.await.unwrap().unwrap();
+    }
+
     pub async fn commit_txns(&mut self, txns: &[TestTransaction]) {
         for txn in sign_transactions(txns) {
             self.mempool
@@ -250,6 +273,28 @@ impl MempoolNode {
         protocol_id: ProtocolId,
         remote_peer_network_id: PeerNetworkId,
         txns: &[TestTransaction],
+    ) {
+        self.receive_message_and_verify_response(protocol_id, remote_peer_network_id, txns, false)
+            .await
+    }
+
+    /// Receives a message from a peer and verifies that the node asks the peer to back off
+    pub async fn receive_message_with_backoff(
+        &mut self,
+        protocol_id: ProtocolId,
+        remote_peer_network_id: PeerNetworkId,
+        txns: &[TestTransaction],
+    ) {
+        self.receive_message_and_verify_response(protocol_id, remote_peer_network_id, txns, true)
+            .await
+    }
+
+    async fn receive_message_and_verify_response(
+        &mut self,
+        protocol_id: ProtocolId,
+        remote_peer_network_id: PeerNetworkId,
+        txns: &[TestTransaction],
+        expected_backoff: bool,
     ) {
         let network_id = remote_peer_network_id.network_id();
         let remote_peer_id = remote_peer_network_id.peer_id();
@@ -305,12 +350,113 @@ impl MempoolNode {
         {
             assert_eq!(batch_id, request_id);
             assert!(!retry);
-            assert!(!backoff);
+            assert_eq!(backoff, expected_backoff);
         } else {
             panic!("Expected a response!");
         }
     }
 
+    /// Sends a digest announcement for the given transactions to the node, verifies
+    /// that the node only requests the expected missing transactions, and that the
+    /// broadcast is acked once the missing transactions are received.
+    pub async fn receive_digests_and_respond(
+        &mut self,
+        remote_peer_network_id: PeerNetworkId,
+        announced_txns: &[TestTransaction],
+        expected_missing_txns: &[TestTransaction],
+    ) {
+        let protocol_id = ProtocolId::MempoolDigestDirectSend;
+        let batch_id = MultiBatchId::from_timeline_ids(&vec![1].into(), &vec![10].into());
+
+        // Send the digest announcement
+        let digests = sign_transactions(announced_txns)
+            .iter()
+            .map(TransactionDigest::new)
+            .collect();
+        self.receive_direct_send(
+            remote_peer_network_id,
+            protocol_id,
+            MempoolSyncMsg::BroadcastTransactionDigestsRequest {
+                request_id: batch_id.clone(),
+                digests,
+            },
+        );
+
+        // Verify the missing transactions are requested (if any), and send them
+        if !expected_missing_txns.is_empty() {
+            let missing_txns = sign_transactions(expected_missing_txns);
+            match self.get_next_direct_send(remote_peer_network_id).await {
+                MempoolSyncMsg::MissingTransactionsRequest { request_id, hashes } => {
+                    assert_eq!(batch_id, request_id);
+                    let expected_hashes: Vec<_> = missing_txns
+                        .iter()
+                        .map(|txn| TransactionDigest::new(txn).hash)
+                        .collect();
+                    assert_eq!(hashes, expected_hashes);
+                },
+                message => panic!(
+                    "Expected a missing transactions request! Got: {:?}",
+                    message
+                ),
+            }
+            self.receive_direct_send(
+                remote_peer_network_id,
+                protocol_id,
+                MempoolSyncMsg::BroadcastTransactionsRequest {
+                    request_id: batch_id.clone(),
+                    transactions: missing_txns,
+                },
+            );
+        }
+
+        // Verify the broadcast is acked
+        match self.get_next_direct_send(remote_peer_network_id).await {
+            MempoolSyncMsg::BroadcastTransactionsResponse {
+                request_id,
+                retry,
+                backoff,
+            } => {
+                assert_eq!(batch_id, request_id);
+                assert!(!retry);
+                assert!(!backoff);
+            },
+            message => panic!("Expected a response! Got: {:?}", message),
+        }
+    }
+
+    /// Delivers the given direct send message from the remote peer to the node
+    fn receive_direct_send(
+        &mut self,
+        remote_peer_network_id: PeerNetworkId,
+        protocol_id: ProtocolId,
+        message: MempoolSyncMsg,
+    ) {
+        let remote_peer_id = remote_peer_network_id.peer_id();
+        let notif = PeerManagerNotification::RecvMessage(remote_peer_id, Message {
+            protocol_id,
+            mdata: protocol_id.to_bytes(&message).unwrap().into(),
+        });
+        self.get_inbound_handle(remote_peer_network_id.network_id())
+            .inbound_message_sender
+            .push((remote_peer_id, protocol_id), notif)
+            .unwrap();
+    }
+
+    /// Returns the next direct send message sent by the node to the remote peer
+    async fn get_next_direct_send(
+        &mut self,
+        remote_peer_network_id: PeerNetworkId,
+    ) -> MempoolSyncMsg {
+        let network_id = remote_peer_network_id.network_id();
+        match self.get_outbound_handle(network_id).next().await.unwrap() {
+            PeerManagerRequest::SendDirectSend(peer_id, msg) => {
+                assert_eq!(peer_id, remote_peer_network_id.peer_id());
+                msg.protocol_id.from_bytes(&msg.mdata).unwrap()
+            },
+            _ => panic!("Should not be getting an RPC request"),
+        }
+    }
+
     pub async fn send_broadcast_and_receive_ack(
         &mut self,
         expected_peer_network_id: PeerNetworkId,
@@ -392,6 +538,7 @@ impl MempoolNode {
             MempoolSyncMsg::BroadcastTransactionsResponse { .. } => {
                 panic!("We aren't supposed to be getting as response here");
             },
+            message => panic!("Unexpected mempool message: {:?}", message),
         };
         let response = MempoolSyncMsg::BroadcastTransactionsResponse {
             request_id,
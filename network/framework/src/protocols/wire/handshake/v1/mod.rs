@@ -71,6 +71,9 @@ pub enum ProtocolId {
     JWKConsensusRpcCompressed = 24,
     JWKConsensusRpcBcs = 25,
     JWKConsensusRpcJson = 26,
+    ConsensusObserver = 27,
+    MempoolDigestDirectSend = 28,
+    MempoolGroupDirectSend = 29,
 }
 
 /// The encoding types for Protocols
@@ -111,6 +114,9 @@ impl ProtocolId {
             JWKConsensusRpcCompressed => "JWKConsensusRpcCompressed",
             JWKConsensusRpcBcs => "JWKConsensusRpcBcs",
             JWKConsensusRpcJson => "JWKConsensusRpcJson",
+            ConsensusObserver => "ConsensusObserver",
+            MempoolDigestDirectSend => "MempoolDigestDirectSend",
+            MempoolGroupDirectSend => "MempoolGroupDirectSend",
         }
     }
 
@@ -144,6 +150,9 @@ impl ProtocolId {
             ProtocolId::JWKConsensusRpcCompressed,
             ProtocolId::JWKConsensusRpcBcs,
             ProtocolId::JWKConsensusRpcJson,
+            ProtocolId::ConsensusObserver,
+            ProtocolId::MempoolDigestDirectSend,
+            ProtocolId::MempoolGroupDirectSend,
         ]
     }
 
@@ -159,7 +168,11 @@ impl ProtocolId {
             },
             ProtocolId::JWKConsensusDirectSendCompressed
             | ProtocolId::JWKConsensusRpcCompressed => Encoding::CompressedBcs(RECURSION_LIMIT),
-            ProtocolId::MempoolDirectSend => Encoding::CompressedBcs(USER_INPUT_RECURSION_LIMIT),
+            ProtocolId::MempoolDirectSend
+            | ProtocolId::MempoolDigestDirectSend
+            | ProtocolId::MempoolGroupDirectSend => {
+                Encoding::CompressedBcs(USER_INPUT_RECURSION_LIMIT)
+            },
             ProtocolId::MempoolRpc => Encoding::Bcs(USER_INPUT_RECURSION_LIMIT),
             _ => Encoding::Bcs(RECURSION_LIMIT),
         }
@@ -171,7 +184,9 @@ impl ProtocolId {
             ProtocolId::ConsensusDirectSendCompressed | ProtocolId::ConsensusRpcCompressed => {
                 CompressionClient::Consensus
             },
-            ProtocolId::MempoolDirectSend => CompressionClient::Mempool,
+            ProtocolId::MempoolDirectSend
+            | ProtocolId::MempoolDigestDirectSend
+            | ProtocolId::MempoolGroupDirectSend => CompressionClient::Mempool,
             ProtocolId::DKGDirectSendCompressed | ProtocolId::DKGRpcCompressed => {
                 CompressionClient::DKG
             },
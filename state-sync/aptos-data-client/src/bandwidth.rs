@@ -0,0 +1,364 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{metrics, peer_states::PeerStates, utils};
+use aptos_config::{config::AptosBandwidthSelectionConfig, network_id::PeerNetworkId};
+use aptos_storage_service_types::{
+    requests::StorageServiceRequest, responses::StorageServiceResponse,
+};
+use rand::Rng;
+use std::{collections::HashSet, time::Duration};
+
+// Useful metric labels
+const EXPLOIT_LABEL: &str = "exploit";
+const EXPLORE_LABEL: &str = "explore";
+
+/// The observed throughput and error rate of a single peer. Both
+/// are tracked as exponential moving averages, so that old samples
+/// are gradually forgotten (e.g., if the peer's network improves).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PeerBandwidthStats {
+    /// The number of samples (responses and errors) recorded for the peer
+    num_samples: u64,
+    /// The average throughput (bytes per second) of successful responses
+    throughput_bytes_per_sec: f64,
+    /// The average fraction of requests that failed
+    error_rate: f64,
+}
+
+impl PeerBandwidthStats {
+    /// Returns the number of samples recorded for the peer
+    pub fn get_num_samples(&self) -> u64 {
+        self.num_samples
+    }
+
+    /// Returns the average throughput (bytes per second) of the peer
+    pub fn get_throughput_bytes_per_sec(&self) -> f64 {
+        self.throughput_bytes_per_sec
+    }
+
+    /// Returns the average error rate of the peer
+    pub fn get_error_rate(&self) -> f64 {
+        self.error_rate
+    }
+
+    /// Returns the bandwidth score of the peer, i.e., the expected number of
+    /// useful bytes per second. The higher the score, the better the peer.
+    pub fn get_bandwidth_score(&self) -> f64 {
+        self.throughput_bytes_per_sec * (1.0 - self.error_rate)
+    }
+
+    /// Records a successful response of the given size and duration
+    pub(crate) fn record_response(
+        &mut self,
+        config: &AptosBandwidthSelectionConfig,
+        num_bytes: u64,
+        duration: Duration,
+    ) {
+        // Ignore samples that are too short to measure
+        let duration_secs = duration.as_secs_f64();
+        if duration_secs <= 0.0 {
+            return;
+        }
+
+        let throughput_bytes_per_sec = num_bytes as f64 / duration_secs;
+        let (throughput_bytes_per_sec, error_rate) = if self.num_samples == 0 {
+            (throughput_bytes_per_sec, 0.0)
+        } else {
+            let sample_weight = get_sample_weight(config);
+            (
+                moving_average(
+                    self.throughput_bytes_per_sec,
+                    throughput_bytes_per_sec,
+                    sample_weight,
+                ),
+                moving_average(self.error_rate, 0.0, sample_weight),
+            )
+        };
+
+        self.throughput_bytes_per_sec = throughput_bytes_per_sec;
+        self.error_rate = error_rate;
+        self.num_samples += 1;
+    }
+
+    /// Records a failed request (the throughput is left unchanged)
+    pub(crate) fn record_error(&mut self, config: &AptosBandwidthSelectionConfig) {
+        self.error_rate = if self.num_samples == 0 {
+            1.0
+        } else {
+            moving_average(self.error_rate, 1.0, get_sample_weight(config))
+        };
+        self.num_samples += 1;
+    }
+}
+
+/// Returns true iff the request should be used to measure peer bandwidth.
+/// Storage summary, protocol version, optimistic fetch and subscription requests
+/// are excluded, as their response times are not bound by the peer's bandwidth.
+pub(crate) fn is_bandwidth_sample_request(request: &StorageServiceRequest) -> bool {
+    let data_request = &request.data_request;
+    !(data_request.is_storage_summary_request()
+        || data_request.is_protocol_version_request()
+        || data_request.is_optimistic_fetch()
+        || data_request.is_subscription_request())
+}
+
+/// Returns the size (in bytes) of the response, as sent over the network
+pub(crate) fn get_response_size(response: &StorageServiceResponse) -> u64 {
+    match response {
+        StorageServiceResponse::CompressedResponse(_, compressed_data)
+        | StorageServiceResponse::CodecCompressedResponse(_, _, compressed_data) => {
+            compressed_data.len() as u64
+        },
+        StorageServiceResponse::RawResponse(data_response) => {
+            bcs::serialized_size(data_response).unwrap_or_default() as u64
+        },
+    }
+}
+
+/// Chooses peers from the given serviceable peers using an explore/exploit
+/// policy. When exploiting, peers with enough samples are selected randomly,
+/// weighted by their bandwidth scores. When exploring (or if no peer has
+/// enough samples), `choose_peers_without_scores` is used instead, preferring
+/// peers that have not been sampled enough yet.
+pub(crate) fn choose_peers_by_bandwidth(
+    config: &AptosBandwidthSelectionConfig,
+    peer_states: &PeerStates,
+    serviceable_peers: HashSet<PeerNetworkId>,
+    num_peers_to_choose: usize,
+    choose_peers_without_scores: impl Fn(HashSet<PeerNetworkId>, usize) -> HashSet<PeerNetworkId>,
+) -> HashSet<PeerNetworkId> {
+    // If no peers can be chosen, return an empty set
+    if num_peers_to_choose == 0 || serviceable_peers.is_empty() {
+        return HashSet::new();
+    }
+
+    // Split the peers by whether or not they have enough samples
+    let mut sampled_peers_and_scores = vec![];
+    let mut unsampled_peers = HashSet::new();
+    for peer in serviceable_peers.iter() {
+        match peer_states.get_bandwidth_stats(peer) {
+            Some(stats)
+                if stats.get_num_samples() >= config.min_samples_for_bandwidth_selection =>
+            {
+                sampled_peers_and_scores.push((*peer, stats.get_bandwidth_score()));
+            },
+            _ => {
+                unsampled_peers.insert(*peer);
+            },
+        }
+    }
+
+    // Decide whether to explore or exploit
+    let explore = sampled_peers_and_scores.is_empty()
+        || rand::thread_rng().gen_range(0..100) < config.exploration_percentage;
+    if explore {
+        metrics::increment_counter(&metrics::BANDWIDTH_PEER_SELECTIONS, EXPLORE_LABEL);
+
+        // Prefer the unsampled peers (if there are any)
+        let candidate_peers = if unsampled_peers.is_empty() {
+            serviceable_peers.clone()
+        } else {
+            unsampled_peers
+        };
+        let selected_peers = choose_peers_without_scores(candidate_peers, num_peers_to_choose);
+        return utils::extend_with_random_peers(
+            selected_peers,
+            serviceable_peers,
+            num_peers_to_choose,
+        );
+    }
+
+    // Otherwise, exploit the peers with the best bandwidth scores
+    metrics::increment_counter(&metrics::BANDWIDTH_PEER_SELECTIONS, EXPLOIT_LABEL);
+    let positive_peers_and_scores: Vec<_> = sampled_peers_and_scores
+        .into_iter()
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    let mut selected_peers =
+        utils::choose_random_peers_by_weight(num_peers_to_choose as u64, positive_peers_and_scores);
+
+    // Fill any remaining slots without using scores (e.g., if too few peers have positive scores)
+    if selected_peers.len() < num_peers_to_choose {
+        let num_remaining_peers = num_peers_to_choose - selected_peers.len();
+        let remaining_peers = serviceable_peers
+            .difference(&selected_peers)
+            .cloned()
+            .collect();
+        selected_peers.extend(choose_peers_without_scores(
+            remaining_peers,
+            num_remaining_peers,
+        ));
+    }
+    utils::extend_with_random_peers(selected_peers, serviceable_peers, num_peers_to_choose)
+}
+
+/// Returns the weight of each new sample (between 0 and 1)
+fn get_sample_weight(config: &AptosBandwidthSelectionConfig) -> f64 {
+    (config.sample_weight_percentage.clamp(1, 100) as f64) / 100.0
+}
+
+/// Returns the exponential moving average after adding the given sample
+fn moving_average(average: f64, sample: f64, sample_weight: f64) -> f64 {
+    (average * (1.0 - sample_weight)) + (sample * sample_weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_config::{config::AptosDataClientConfig, network_id::NetworkId};
+    use aptos_storage_service_types::responses::StorageServerSummary;
+    use aptos_types::PeerId;
+    use std::{collections::HashMap, sync::Arc};
+
+    #[test]
+    fn test_bandwidth_stats_moving_averages() {
+        let config = AptosBandwidthSelectionConfig {
+            sample_weight_percentage: 50,
+            ..Default::default()
+        };
+        let mut stats = PeerBandwidthStats::default();
+
+        // The first sample initializes the averages
+        stats.record_response(&config, 1000, Duration::from_secs(1));
+        assert_eq!(stats.get_num_samples(), 1);
+        assert_eq!(stats.get_throughput_bytes_per_sec(), 1000.0);
+        assert_eq!(stats.get_error_rate(), 0.0);
+        assert_eq!(stats.get_bandwidth_score(), 1000.0);
+
+        // Later samples are averaged
+        stats.record_response(&config, 3000, Duration::from_secs(1));
+        assert_eq!(stats.get_throughput_bytes_per_sec(), 2000.0);
+        stats.record_error(&config);
+        assert_eq!(stats.get_num_samples(), 3);
+        assert_eq!(stats.get_throughput_bytes_per_sec(), 2000.0);
+        assert_eq!(stats.get_error_rate(), 0.5);
+        assert_eq!(stats.get_bandwidth_score(), 1000.0);
+
+        // Zero duration samples are ignored
+        stats.record_response(&config, 1000, Duration::ZERO);
+        assert_eq!(stats.get_num_samples(), 3);
+
+        // A peer that only fails has a zero score
+        let mut stats = PeerBandwidthStats::default();
+        stats.record_error(&config);
+        assert_eq!(stats.get_error_rate(), 1.0);
+        assert_eq!(stats.get_bandwidth_score(), 0.0);
+    }
+
+    #[test]
+    fn test_choose_peers_by_bandwidth_exploit() {
+        // Disable exploration
+        let config = AptosBandwidthSelectionConfig {
+            exploration_percentage: 0,
+            min_samples_for_bandwidth_selection: 1,
+            ..Default::default()
+        };
+        let (peer_states, fast_peer, slow_peer, failing_peer) = create_peer_states(&config);
+        let serviceable_peers: HashSet<_> = [fast_peer, slow_peer, failing_peer].into();
+
+        // Verify that the fast peer is selected far more often than the slow peer
+        let mut selection_counts = HashMap::new();
+        for _ in 0..1000 {
+            let selected_peers = choose_peers_by_bandwidth(
+                &config,
+                &peer_states,
+                serviceable_peers.clone(),
+                1,
+                panicking_fallback,
+            );
+            for peer in selected_peers {
+                *selection_counts.entry(peer).or_insert(0) += 1;
+            }
+        }
+        assert!(selection_counts[&fast_peer] > 900);
+        assert!(selection_counts.get(&failing_peer).is_none());
+    }
+
+    #[test]
+    fn test_choose_peers_by_bandwidth_explore() {
+        // Always explore
+        let config = AptosBandwidthSelectionConfig {
+            exploration_percentage: 100,
+            min_samples_for_bandwidth_selection: 1,
+            ..Default::default()
+        };
+        let (peer_states, fast_peer, slow_peer, _) = create_peer_states(&config);
+        let unsampled_peer = create_peer(&peer_states);
+        let serviceable_peers: HashSet<_> = [fast_peer, slow_peer, unsampled_peer].into();
+
+        // Verify that the unsampled peer is explored
+        let selected_peers = choose_peers_by_bandwidth(
+            &config,
+            &peer_states,
+            serviceable_peers.clone(),
+            1,
+            |peers, _| peers,
+        );
+        assert_eq!(selected_peers, [unsampled_peer].into());
+
+        // Verify that all peers can be explored once they have been sampled
+        let selected_peers = choose_peers_by_bandwidth(
+            &config,
+            &peer_states,
+            [fast_peer, slow_peer].into(),
+            2,
+            |peers, _| peers,
+        );
+        assert_eq!(selected_peers, [fast_peer, slow_peer].into());
+    }
+
+    #[test]
+    fn test_choose_peers_by_bandwidth_fills_remaining_peers() {
+        let config = AptosBandwidthSelectionConfig {
+            exploration_percentage: 0,
+            min_samples_for_bandwidth_selection: 1,
+            ..Default::default()
+        };
+        let (peer_states, fast_peer, slow_peer, failing_peer) = create_peer_states(&config);
+        let unsampled_peer = create_peer(&peer_states);
+        let serviceable_peers: HashSet<_> =
+            [fast_peer, slow_peer, failing_peer, unsampled_peer].into();
+
+        // Verify that the peers without scores are used to fill the request
+        let selected_peers = choose_peers_by_bandwidth(
+            &config,
+            &peer_states,
+            serviceable_peers.clone(),
+            4,
+            |peers, _| peers,
+        );
+        assert_eq!(selected_peers, serviceable_peers);
+    }
+
+    /// Creates peer states with a fast, a slow and a failing peer
+    fn create_peer_states(
+        config: &AptosBandwidthSelectionConfig,
+    ) -> (PeerStates, PeerNetworkId, PeerNetworkId, PeerNetworkId) {
+        let data_client_config = AptosDataClientConfig {
+            bandwidth_selection_config: *config,
+            ..Default::default()
+        };
+        let peer_states = PeerStates::new(Arc::new(data_client_config));
+        let fast_peer = create_peer(&peer_states);
+        let slow_peer = create_peer(&peer_states);
+        let failing_peer = create_peer(&peer_states);
+
+        peer_states.record_bandwidth_sample(fast_peer, 100_000, Duration::from_secs(1));
+        peer_states.record_bandwidth_sample(slow_peer, 1_000, Duration::from_secs(1));
+        peer_states.record_bandwidth_error(failing_peer);
+
+        (peer_states, fast_peer, slow_peer, failing_peer)
+    }
+
+    /// Creates a new peer with a storage summary
+    fn create_peer(peer_states: &PeerStates) -> PeerNetworkId {
+        let peer = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+        peer_states.update_summary(peer, StorageServerSummary::default());
+        peer
+    }
+
+    fn panicking_fallback(_: HashSet<PeerNetworkId>, _: usize) -> HashSet<PeerNetworkId> {
+        panic!("Peers should be selected by bandwidth!")
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    bandwidth,
     error::Error,
     global_summary::GlobalDataSummary,
     interface::{
@@ -18,11 +19,14 @@ use crate::{
     priority,
     priority::PeerPriority,
     utils,
+    validation::{ResponseViolation, ValidateResponse},
 };
+use aptos_compression::codec::CompressionCodec;
 use aptos_config::{
     config::{AptosDataClientConfig, BaseConfig},
     network_id::PeerNetworkId,
 };
+use aptos_crypto::HashValue;
 use aptos_id_generator::{IdGenerator, U64IdGenerator};
 use aptos_infallible::Mutex;
 use aptos_logger::{info, sample, sample::SampleRate, trace, warn};
@@ -36,16 +40,19 @@ use aptos_storage_service_types::{
     requests::{
         DataRequest, EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest,
         NewTransactionsOrOutputsWithProofRequest, NewTransactionsWithProofRequest,
-        StateValuesWithProofRequest, StorageServiceRequest,
+        StateValuesWithIncrementalProofRequest, StateValuesWithProofRequest, StorageServiceRequest,
         SubscribeTransactionOutputsWithProofRequest,
         SubscribeTransactionsOrOutputsWithProofRequest, SubscribeTransactionsWithProofRequest,
         SubscriptionStreamMetadata, TransactionOutputsWithProofRequest,
         TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
     },
-    responses::{StorageServerSummary, StorageServiceResponse, TransactionOrOutputListWithProof},
-    Epoch, StorageServiceMessage,
+    responses::{
+        StateValueChunkWithIncrementalProof, StorageServerSummary, StorageServiceResponse,
+        TransactionOrOutputListWithProof,
+    },
+    Epoch, StorageServiceError, StorageServiceMessage,
 };
-use aptos_time_service::TimeService;
+use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::{
     epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
@@ -97,6 +104,8 @@ pub struct AptosDataClient {
     storage_service_client: StorageServiceClient<NetworkClient<StorageServiceMessage>>,
     /// The state of the active subscription stream.
     active_subscription_state: Arc<Mutex<Option<SubscriptionState>>>,
+    /// The last (highest) state value chunk we've received (used for incremental proofs)
+    known_state_value_chunk: Arc<Mutex<Option<KnownStateValueChunk>>>,
     /// All of the data-client specific data we have on each network peer.
     peer_states: Arc<PeerStates>,
     /// A cached, aggregate data summary of all unbanned peers' data summaries.
@@ -126,6 +135,7 @@ impl AptosDataClient {
             data_client_config: data_client_config.clone(),
             storage_service_client: storage_service_client.clone(),
             active_subscription_state: Arc::new(Mutex::new(None)),
+            known_state_value_chunk: Arc::new(Mutex::new(None)),
             peer_states: Arc::new(PeerStates::new(data_client_config.clone())),
             global_summary_cache: Arc::new(ArcSwap::from(Arc::new(GlobalDataSummary::empty()))),
             response_id_generator: Arc::new(U64IdGenerator::new()),
@@ -203,6 +213,37 @@ impl AptosDataClient {
         self.peer_states.update_summary(peer, summary)
     }
 
+    /// Returns the storage summary to use as the base of the next storage summary
+    /// update for the peer, or `None` if a full storage summary should be polled.
+    pub fn get_storage_summary_for_update(
+        &self,
+        peer: &PeerNetworkId,
+        full_storage_summary_refresh_interval: u64,
+    ) -> Option<StorageServerSummary> {
+        self.peer_states
+            .get_storage_summary_for_update(peer, full_storage_summary_refresh_interval)
+    }
+
+    /// Updates the state of the storage summary updates for the peer, after
+    /// an update was polled (successfully or not) from the peer.
+    pub fn update_peer_storage_summary_update_result(
+        &self,
+        peer: &PeerNetworkId,
+        success: bool,
+        max_storage_summary_update_failures: u64,
+    ) {
+        self.peer_states.update_storage_summary_update_result(
+            peer,
+            success,
+            max_storage_summary_update_failures,
+        )
+    }
+
+    /// Stops polling storage summary updates from the peer
+    pub fn disable_peer_storage_summary_updates(&self, peer: &PeerNetworkId) {
+        self.peer_states.disable_storage_summary_updates(peer)
+    }
+
     /// Recompute and update the global data summary cache
     pub fn update_global_summary_cache(&self) -> crate::error::Result<(), Error> {
         // Before calculating the summary, we should garbage collect
@@ -372,8 +413,8 @@ impl AptosDataClient {
     }
 
     /// Chooses several peers to service the specific data request.
-    /// Peers are selected first by priority, and then by latency
-    /// (within priority groups).
+    /// Peers are selected first by priority, and then by bandwidth
+    /// and latency (within priority groups).
     fn choose_peers_for_specific_data_request(
         &self,
         request: &StorageServiceRequest,
@@ -383,9 +424,10 @@ impl AptosDataClient {
         // Select peers by priority (starting with the highest priority first)
         let mut selected_peers = HashSet::new();
         for serviceable_peers in serviceable_peers_by_priorities {
-            // Select peers by distance and latency
+            // Select peers by bandwidth and latency
             let num_peers_remaining = num_peers_for_request.saturating_sub(selected_peers.len());
-            let peers = self.choose_random_peers_by_latency(serviceable_peers, num_peers_remaining);
+            let peers =
+                self.choose_peers_by_bandwidth_and_latency(serviceable_peers, num_peers_remaining);
 
             // Add the peers to the entire set
             selected_peers.extend(peers);
@@ -503,6 +545,28 @@ impl AptosDataClient {
         Ok(selected_peer)
     }
 
+    /// Chooses peers from the given set of serviceable peers using the observed peer
+    /// bandwidths (if enabled). Peers without enough bandwidth samples (and peers
+    /// chosen for exploration) are chosen randomly, weighted by latency.
+    fn choose_peers_by_bandwidth_and_latency(
+        &self,
+        serviceable_peers: HashSet<PeerNetworkId>,
+        num_peers_to_choose: usize,
+    ) -> HashSet<PeerNetworkId> {
+        let bandwidth_selection_config = &self.data_client_config.bandwidth_selection_config;
+        if bandwidth_selection_config.enable_bandwidth_selection {
+            bandwidth::choose_peers_by_bandwidth(
+                bandwidth_selection_config,
+                &self.peer_states,
+                serviceable_peers,
+                num_peers_to_choose,
+                |peers, num_peers| self.choose_random_peers_by_latency(peers, num_peers),
+            )
+        } else {
+            self.choose_random_peers_by_latency(serviceable_peers, num_peers_to_choose)
+        }
+    }
+
     /// Chooses peers randomly weighted by latency from the given set of serviceable peers
     fn choose_random_peers_by_latency(
         &self,
@@ -618,7 +682,7 @@ impl AptosDataClient {
         request_timeout_ms: u64,
     ) -> crate::error::Result<Response<T>>
     where
-        T: TryFrom<StorageServiceResponse, Error = E> + Send + Sync + 'static,
+        T: TryFrom<StorageServiceResponse, Error = E> + ValidateResponse + Send + Sync + 'static,
         E: Into<Error>,
     {
         // Select the peers to service the request
@@ -695,15 +759,20 @@ impl AptosDataClient {
         request_timeout_ms: u64,
     ) -> crate::error::Result<Response<T>>
     where
-        T: TryFrom<StorageServiceResponse, Error = E> + Send + 'static,
+        T: TryFrom<StorageServiceResponse, Error = E> + ValidateResponse + Send + 'static,
         E: Into<Error>,
     {
         // Start the timer for the request
         let timer = start_request_timer(&metrics::REQUEST_LATENCIES, &request.get_label(), peer);
 
-        // Get the response from the peer
+        // Get the response from the peer (negotiating the compression codec, if possible)
+        let peer_request = self.get_request_for_peer(&peer, &request);
+        let requested_accepted_compression = matches!(
+            peer_request.data_request,
+            DataRequest::GetWithAcceptedCompression(_, _)
+        );
         let response = self
-            .send_request_to_peer(peer, request.clone(), request_timeout_ms)
+            .send_request_to_peer(peer, peer_request.clone(), request_timeout_ms)
             .await;
 
         // If an error occurred, stop the timer (without updating the metrics)
@@ -715,27 +784,33 @@ impl AptosDataClient {
             },
             Err(error) => {
                 timer.stop_and_discard(); // Discard the timer without updating the metrics
+
+                // If the peer failed to respond to a request with the accepted
+                // compression codecs, only request (LZ4) compression from now on.
+                if requested_accepted_compression {
+                    self.peer_states.disable_compression_negotiation(&peer);
+                }
+
                 return Err(error);
             },
         };
 
-        // Ensure the response obeys the compression requirements
+        // Ensure the response obeys the compression requirements (i.e., the response is
+        // compressed with an accepted codec). Note: servers may skip compression (even
+        // if requested) when it isn't worthwhile, so uncompressed responses are always
+        // accepted.
         let (context, storage_response) = storage_response.into_parts();
-        if request.use_compression && !storage_response.is_compressed() {
-            return Err(Error::InvalidResponse(format!(
-                "Requested compressed data, but the response was uncompressed! Response: {:?}",
-                storage_response.get_label()
-            )));
-        } else if !request.use_compression && storage_response.is_compressed() {
+        if !peer_request.accepts_compression_codec(storage_response.get_compression_codec()) {
             return Err(Error::InvalidResponse(format!(
-                "Requested uncompressed data, but the response was compressed! Response: {:?}",
+                "The response was compressed with an unaccepted codec: {:?}! Response: {:?}",
+                storage_response.get_compression_codec(),
                 storage_response.get_label()
             )));
         }
 
         // Try to convert the storage service enum into the exact variant we're expecting.
         // We do this using spawn_blocking because it involves serde and compression.
-        tokio::task::spawn_blocking(move || {
+        let response = tokio::task::spawn_blocking(move || {
             match T::try_from(storage_response) {
                 Ok(new_payload) => Ok(Response::new(context, new_payload)),
                 // If the variant doesn't match what we're expecting, report the issue
@@ -748,7 +823,41 @@ impl AptosDataClient {
             }
         })
         .await
-        .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?
+        .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))??;
+
+        // Ensure the response obeys the structural invariants of the request
+        if let Err(violation) = response.payload.validate(&request.data_request) {
+            self.notify_response_violation(peer, &request, &violation);
+            response
+                .context
+                .response_callback
+                .notify_bad_response(ResponseError::InvalidData);
+            return Err(Error::InvalidResponseStructure(violation));
+        }
+
+        Ok(response)
+    }
+
+    /// Returns the request to send to the given peer. If enabled (and supported
+    /// by the peer), zstd is offered as the preferred compression codec (before LZ4).
+    fn get_request_for_peer(
+        &self,
+        peer: &PeerNetworkId,
+        request: &StorageServiceRequest,
+    ) -> StorageServiceRequest {
+        // Negotiate the compression codec (if possible)
+        let mut data_request = request.data_request.clone();
+        if request.use_compression
+            && self.data_client_config.enable_zstd_compression
+            && data_request.is_compression_negotiable()
+            && self.peer_states.supports_compression_negotiation(peer)
+        {
+            let accepted_codecs = vec![CompressionCodec::Zstd, CompressionCodec::Lz4];
+            data_request =
+                DataRequest::GetWithAcceptedCompression(accepted_codecs, Box::new(data_request));
+        }
+
+        StorageServiceRequest::new(data_request, request.use_compression)
     }
 
     /// Sends a request to a specific peer
@@ -773,6 +882,7 @@ impl AptosDataClient {
         self.update_sent_request_metrics(peer, &request);
 
         // Send the request and process the result
+        let request_start_time = self.time_service.now();
         let result = self
             .storage_service_client
             .send_request(
@@ -794,6 +904,29 @@ impl AptosDataClient {
                 // Update the received response metrics
                 self.update_received_response_metrics(peer, &request);
 
+                // Update the observed bandwidth of the peer
+                if bandwidth::is_bandwidth_sample_request(&request) {
+                    let request_duration = self
+                        .time_service
+                        .now()
+                        .saturating_duration_since(request_start_time);
+                    self.peer_states.record_bandwidth_sample(
+                        peer,
+                        bandwidth::get_response_size(&response),
+                        request_duration,
+                    );
+                }
+
+                // Update the storage summary response sizes (to compare full
+                // storage summaries with storage summary updates).
+                if request.data_request.is_storage_summary_request() {
+                    metrics::observe_value_with_label(
+                        &metrics::STORAGE_SUMMARY_RESPONSE_BYTES,
+                        &request.get_label(),
+                        bandwidth::get_response_size(&response) as f64,
+                    );
+                }
+
                 // For now, record all responses that at least pass the data
                 // client layer successfully. An alternative might also have the
                 // consumer notify both success and failure via the callback.
@@ -827,6 +960,9 @@ impl AptosDataClient {
                         },
                         _ => Error::UnexpectedErrorEncountered(rpc_error.to_string()),
                     },
+                    aptos_storage_service_client::Error::StorageServiceError(
+                        StorageServiceError::InvalidRequest(error),
+                    ) => Error::InvalidRequest(error),
                     aptos_storage_service_client::Error::StorageServiceError(err) => {
                         Error::UnexpectedErrorEncountered(err.to_string())
                     },
@@ -859,10 +995,34 @@ impl AptosDataClient {
         &self,
         _id: ResponseId,
         peer: PeerNetworkId,
-        _request: &StorageServiceRequest,
+        request: &StorageServiceRequest,
         error_type: ErrorType,
     ) {
         self.peer_states.update_score_error(peer, error_type);
+        if bandwidth::is_bandwidth_sample_request(request) {
+            self.peer_states.record_bandwidth_error(peer);
+        }
+    }
+
+    /// Updates the metrics and logs for a response that violates the
+    /// structural invariants of the request.
+    fn notify_response_violation(
+        &self,
+        peer: PeerNetworkId,
+        request: &StorageServiceRequest,
+        violation: &ResponseViolation,
+    ) {
+        warn!(
+            (LogSchema::new(LogEntry::StorageServiceResponse)
+                .event(LogEvent::ResponseViolation)
+                .request_type(&request.get_label())
+                .peer(&peer)
+                .message(&format!("Response violation: {}", violation)))
+        );
+
+        increment_request_counter(&metrics::RESPONSE_VIOLATIONS, violation.get_label(), peer);
+        self.peer_states
+            .increment_response_violation_counter(peer, violation);
     }
 
     /// Creates a storage service request using the given data request
@@ -873,7 +1033,7 @@ impl AptosDataClient {
         data_request: DataRequest,
     ) -> crate::error::Result<Response<T>>
     where
-        T: TryFrom<StorageServiceResponse, Error = E> + Send + Sync + 'static,
+        T: TryFrom<StorageServiceResponse, Error = E> + ValidateResponse + Send + Sync + 'static,
         E: Into<Error>,
     {
         let storage_request =
@@ -882,6 +1042,102 @@ impl AptosDataClient {
             .await
     }
 
+    /// Returns the known state value chunk iff the chunk can be used to request
+    /// an incremental proof for the given version and start index.
+    fn get_known_state_value_chunk_for_request(
+        &self,
+        version: Version,
+        start_index: u64,
+    ) -> Option<KnownStateValueChunk> {
+        if !self
+            .data_client_config
+            .enable_incremental_state_value_proofs
+        {
+            return None;
+        }
+
+        self.known_state_value_chunk
+            .lock()
+            .clone()
+            .filter(|known_chunk| {
+                known_chunk.version == version && known_chunk.last_index < start_index
+            })
+    }
+
+    /// Requests a state value chunk with an incremental proof (relative to the
+    /// given known chunk) and reconstructs the full state value chunk with proof.
+    async fn get_state_values_with_incremental_proof(
+        &self,
+        known_state_value_chunk: KnownStateValueChunk,
+        version: Version,
+        start_index: u64,
+        end_index: u64,
+        request_timeout_ms: u64,
+    ) -> crate::error::Result<Response<StateValueChunkWithProof>> {
+        // Send the request and wait for the response
+        let data_request = DataRequest::GetStateValuesWithIncrementalProof(
+            StateValuesWithIncrementalProofRequest {
+                version,
+                start_index,
+                end_index,
+                known_last_key: known_state_value_chunk.last_key,
+            },
+        );
+        let response: Response<StateValueChunkWithIncrementalProof> = self
+            .create_and_send_storage_request(request_timeout_ms, data_request)
+            .await?;
+
+        // Reconstruct the full proof using the known right siblings
+        let (context, state_value_chunk_with_incremental_proof) = response.into_parts();
+        let state_value_chunk_with_proof = match state_value_chunk_with_incremental_proof
+            .into_state_value_chunk_with_proof(&known_state_value_chunk.right_siblings)
+        {
+            Ok(state_value_chunk_with_proof) => state_value_chunk_with_proof,
+            Err(error) => {
+                context
+                    .response_callback
+                    .notify_bad_response(ResponseError::InvalidData);
+                return Err(Error::InvalidResponse(format!(
+                    "Failed to reconstruct the state value chunk proof! Error: {:?}",
+                    error
+                )));
+            },
+        };
+        self.update_known_state_value_chunk(version, &state_value_chunk_with_proof);
+
+        Ok(Response::new(context, state_value_chunk_with_proof))
+    }
+
+    /// Updates the known state value chunk if the given chunk is at a
+    /// different version, or extends beyond the currently known chunk.
+    fn update_known_state_value_chunk(
+        &self,
+        version: Version,
+        state_value_chunk_with_proof: &StateValueChunkWithProof,
+    ) {
+        if !self
+            .data_client_config
+            .enable_incremental_state_value_proofs
+        {
+            return;
+        }
+
+        let mut known_state_value_chunk = self.known_state_value_chunk.lock();
+        let should_update = match known_state_value_chunk.as_ref() {
+            Some(known_chunk) => {
+                known_chunk.version != version
+                    || known_chunk.last_index < state_value_chunk_with_proof.last_index
+            },
+            None => true,
+        };
+        if should_update {
+            *known_state_value_chunk = Some(KnownStateValueChunk::new(
+                version,
+                state_value_chunk_with_proof,
+            ));
+        }
+    }
+
     /// Updates the metrics for the responses received via the data client
     fn update_received_response_metrics(
         &self,
@@ -1004,13 +1260,33 @@ impl AptosDataClientInterface for AptosDataClient {
         end_index: u64,
         request_timeout_ms: u64,
     ) -> crate::error::Result<Response<StateValueChunkWithProof>> {
+        // If possible, request an incremental proof for the chunk
+        if let Some(known_state_value_chunk) =
+            self.get_known_state_value_chunk_for_request(version, start_index)
+        {
+            return self
+                .get_state_values_with_incremental_proof(
+                    known_state_value_chunk,
+                    version,
+                    start_index,
+                    end_index,
+                    request_timeout_ms,
+                )
+                .await;
+        }
+
+        // Otherwise, request the chunk with a full proof
         let data_request = DataRequest::GetStateValuesWithProof(StateValuesWithProofRequest {
             version,
             start_index,
             end_index,
         });
-        self.create_and_send_storage_request(request_timeout_ms, data_request)
-            .await
+        let response: Response<StateValueChunkWithProof> = self
+            .create_and_send_storage_request(request_timeout_ms, data_request)
+            .await?;
+        self.update_known_state_value_chunk(version, &response.payload);
+
+        Ok(response)
     }
 
     async fn get_transaction_outputs_with_proof(
@@ -1179,6 +1455,27 @@ impl SubscriptionState {
     }
 }
 
+/// A struct that holds the last (highest) state value chunk received by the
+/// client. This is used to request incremental proofs for subsequent chunks.
+#[derive(Clone, Debug)]
+struct KnownStateValueChunk {
+    version: Version,
+    last_index: u64,
+    last_key: HashValue,
+    right_siblings: Vec<HashValue>,
+}
+
+impl KnownStateValueChunk {
+    fn new(version: Version, state_value_chunk_with_proof: &StateValueChunkWithProof) -> Self {
+        Self {
+            version,
+            last_index: state_value_chunk_with_proof.last_index,
+            last_key: state_value_chunk_with_proof.last_key,
+            right_siblings: state_value_chunk_with_proof.proof.right_siblings().to_vec(),
+        }
+    }
+}
+
 /// Updates the metrics for the number of connected peers (priority and regular)
 fn update_priority_and_regular_peer_metrics(
     priority_peers: &HashSet<PeerNetworkId>,
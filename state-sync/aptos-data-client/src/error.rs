@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::validation::ResponseViolation;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -17,6 +18,8 @@ pub enum Error {
     InvalidRequest(String),
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
+    #[error("Invalid response (structural violation): {0}")]
+    InvalidResponseStructure(ResponseViolation),
     #[error("No connected peers: {0}")]
     NoConnectedPeers(String),
     #[error("The subscription stream is lagging behind the data advertisements: {0}")]
@@ -35,6 +38,7 @@ impl Error {
             Self::DataIsTooLarge(_) => "data_is_too_large",
             Self::InvalidRequest(_) => "invalid_request",
             Self::InvalidResponse(_) => "invalid_response",
+            Self::InvalidResponseStructure(_) => "invalid_response_structure",
             Self::NoConnectedPeers(_) => "no_connected_peers",
             Self::SubscriptionStreamIsLagging(_) => "subscription_stream_is_lagging",
             Self::TimeoutWaitingForResponse(_) => "timeout_waiting_for_response",
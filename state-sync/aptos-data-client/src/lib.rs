@@ -3,6 +3,7 @@
 
 #![forbid(unsafe_code)]
 
+pub mod bandwidth;
 pub mod client;
 pub mod error;
 pub mod global_summary;
@@ -14,6 +15,7 @@ pub mod peer_states;
 pub mod poller;
 pub mod priority;
 mod utils;
+pub mod validation;
 
 #[cfg(test)]
 mod tests;
@@ -63,6 +63,7 @@ pub enum LogEvent {
     PriorityPeerCategories,
     ResponseError,
     ResponseSuccess,
+    ResponseViolation,
     SendRequest,
     StorageReadFailed,
     UnexpectedError,
@@ -49,6 +49,26 @@ pub static ERROR_RESPONSES: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter for tracking responses that violate structural invariants
+pub static RESPONSE_VIOLATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_data_client_response_violations",
+        "Counters related to responses that violate structural invariants",
+        &["violation_type", "network"]
+    )
+    .unwrap()
+});
+
+/// Counter for tracking peer selections by the bandwidth selection policy
+pub static BANDWIDTH_PEER_SELECTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_data_client_bandwidth_peer_selections",
+        "Counters related to peer selections by bandwidth (i.e., explore or exploit)",
+        &["selection_type"]
+    )
+    .unwrap()
+});
+
 // Buckets for tracking the number of multi-fetches sent per request
 const MULTI_FETCH_BUCKETS: &[f64] = &[
     1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 15.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0,
@@ -65,6 +85,22 @@ pub static MULTI_FETCHES_PER_REQUEST: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(histogram_opts, &["label"]).unwrap()
 });
 
+// Buckets for tracking the sizes of storage summary responses (bytes)
+const STORAGE_SUMMARY_RESPONSE_BYTES_BUCKETS: &[f64] = &[
+    64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0, 4096.0, 8192.0, 16384.0, 32768.0, 65536.0,
+];
+
+/// Counter for tracking the sizes of storage summary responses (i.e., full
+/// storage summaries and storage summary updates), by request type.
+pub static STORAGE_SUMMARY_RESPONSE_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
+    let histogram_opts = histogram_opts!(
+        "aptos_data_client_storage_summary_response_bytes",
+        "Counters related to the sizes of storage summary responses",
+        STORAGE_SUMMARY_RESPONSE_BYTES_BUCKETS.to_vec()
+    );
+    register_histogram_vec!(histogram_opts, &["request_type"]).unwrap()
+});
+
 // Latency buckets for network latencies (seconds)
 const REQUEST_LATENCY_BUCKETS_SECS: &[f64] = &[
     0.05, 0.1, 0.2, 0.3, 0.5, 0.75, 1.0, 1.5, 2.0, 3.0, 5.0, 7.5, 10.0, 15.0, 20.0, 30.0, 40.0,
@@ -179,6 +215,16 @@ pub static RECEIVED_RESPONSES_BY_PEER_BUCKET: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Gauge for tracking the number of response violations by peer buckets
+pub static RESPONSE_VIOLATIONS_BY_PEER_BUCKET: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_data_client_response_violations_by_peer_bucket",
+        "Gauge related to the response violations by peer buckets",
+        &["peer_bucket_id", "violation_type"]
+    )
+    .unwrap()
+});
+
 /// An enum representing the various types of data that can be
 /// fetched via the data client.
 pub enum DataType {
@@ -221,6 +267,11 @@ pub fn increment_request_counter(
         .inc();
 }
 
+/// Increments the given counter with the provided label
+pub fn increment_counter(counter: &Lazy<IntCounterVec>, label: &str) {
+    counter.with_label_values(&[label]).inc();
+}
+
 /// Observes the value for the provided histogram and label
 pub fn observe_value_with_label(histogram: &Lazy<HistogramVec>, label: &str, value: f64) {
     histogram.with_label_values(&[label]).observe(value)
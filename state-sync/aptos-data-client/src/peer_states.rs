@@ -2,10 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    bandwidth::PeerBandwidthStats,
     global_summary::{AdvertisedData, GlobalDataSummary, OptimalChunkSizes},
     interface::ResponseError,
     logging::{LogEntry, LogEvent, LogSchema},
     metrics,
+    validation::ResponseViolation,
 };
 use aptos_config::{config::AptosDataClientConfig, network_id::PeerNetworkId};
 use aptos_logger::prelude::*;
@@ -65,11 +67,30 @@ pub struct PeerState {
     received_responses_by_type: Arc<DashMap<String, u64>>,
     /// The number of requests sent to this peer (by data request label)
     sent_requests_by_type: Arc<DashMap<String, u64>>,
+    /// The number of responses from this peer that violated structural
+    /// invariants (by violation label)
+    response_violations_by_type: Arc<DashMap<String, u64>>,
     /// The latest observed advertised data for this peer, or `None` if we
     /// haven't polled them yet.
     storage_summary: Option<StorageServerSummary>,
+    /// The number of storage summary updates polled from this peer since the
+    /// last full storage summary.
+    num_storage_summary_updates: u64,
+    /// The number of consecutive storage summary updates that failed for this peer.
+    num_failed_storage_summary_updates: u64,
+    /// True iff the next poll should fetch a full storage summary (e.g., to
+    /// back off after a failed storage summary update).
+    refresh_storage_summary: bool,
+    /// True iff the peer doesn't support storage summary updates (i.e., it
+    /// rejected them, or they failed too many times in a row).
+    storage_summary_updates_unsupported: bool,
+    /// True iff the peer failed to respond to a request with the accepted
+    /// compression codecs (e.g., because it doesn't support negotiation).
+    compression_negotiation_unsupported: bool,
     /// For now, a simplified port of the original state-sync v1 scoring system.
     score: f64,
+    /// The observed throughput and error rate of the peer (for peer selection)
+    bandwidth_stats: PeerBandwidthStats,
 }
 
 impl Default for PeerState {
@@ -77,8 +98,15 @@ impl Default for PeerState {
         Self {
             received_responses_by_type: Arc::new(DashMap::new()),
             sent_requests_by_type: Arc::new(DashMap::new()),
+            response_violations_by_type: Arc::new(DashMap::new()),
             storage_summary: None,
+            num_storage_summary_updates: 0,
+            num_failed_storage_summary_updates: 0,
+            refresh_storage_summary: false,
+            storage_summary_updates_unsupported: false,
+            compression_negotiation_unsupported: false,
             score: STARTING_SCORE,
+            bandwidth_stats: PeerBandwidthStats::default(),
         }
     }
 }
@@ -100,11 +128,24 @@ impl PeerState {
             .or_insert(1);
     }
 
+    /// Increments the response violation counter for the given label
+    fn increment_response_violation_counter(&mut self, violation_label: String) {
+        self.response_violations_by_type
+            .entry(violation_label)
+            .and_modify(|counter| *counter += 1)
+            .or_insert(1);
+    }
+
     /// Returns the peer's score
     pub fn get_score(&self) -> f64 {
         self.score
     }
 
+    /// Returns the bandwidth stats for the peer
+    pub fn get_bandwidth_stats(&self) -> &PeerBandwidthStats {
+        &self.bandwidth_stats
+    }
+
     /// Returns the storage summary for the peer
     pub fn get_storage_summary(&self) -> Option<StorageServerSummary> {
         self.storage_summary.clone()
@@ -129,6 +170,18 @@ impl PeerState {
         sorted_responses_by_type
     }
 
+    /// Returns a sorted copy of the response violations by type map
+    pub fn get_response_violations_by_type(&self) -> BTreeMap<String, u64> {
+        let mut sorted_violations_by_type = BTreeMap::new();
+        for response_violation in self.response_violations_by_type.iter() {
+            sorted_violations_by_type.insert(
+                response_violation.key().clone(),
+                *response_violation.value(),
+            );
+        }
+        sorted_violations_by_type
+    }
+
     /// Returns the storage summary iff the peer is not below the ignore threshold
     pub(crate) fn get_storage_summary_if_not_ignored(&self) -> Option<&StorageServerSummary> {
         if self.score <= IGNORE_PEER_THRESHOLD {
@@ -156,6 +209,54 @@ impl PeerState {
     fn update_storage_summary(&mut self, storage_summary: StorageServerSummary) {
         self.storage_summary = Some(storage_summary);
     }
+
+    /// Returns the storage summary to use as the base of the next storage
+    /// summary update, or `None` if a full storage summary should be polled
+    /// instead (i.e., if the peer doesn't support updates, we don't have a
+    /// summary for the peer, or the full refresh interval has elapsed).
+    fn get_storage_summary_for_update(
+        &mut self,
+        full_storage_summary_refresh_interval: u64,
+    ) -> Option<StorageServerSummary> {
+        if self.storage_summary_updates_unsupported {
+            return None;
+        }
+        let storage_summary = self.storage_summary.clone()?;
+
+        // Periodically poll a full storage summary as a safety net (and
+        // after a failed update, to back off from updates for one poll).
+        if self.refresh_storage_summary
+            || self.num_storage_summary_updates >= full_storage_summary_refresh_interval
+        {
+            self.refresh_storage_summary = false;
+            self.num_storage_summary_updates = 0;
+            return None;
+        }
+        self.num_storage_summary_updates += 1;
+
+        Some(storage_summary)
+    }
+
+    /// Updates the state of the storage summary updates after an update
+    /// was polled (successfully or not) from the peer. Failed updates are
+    /// followed by a full storage summary, and too many consecutive failures
+    /// disable updates for the peer altogether.
+    fn update_storage_summary_update_result(
+        &mut self,
+        success: bool,
+        max_storage_summary_update_failures: u64,
+    ) {
+        if success {
+            self.num_failed_storage_summary_updates = 0;
+            return;
+        }
+
+        self.num_failed_storage_summary_updates += 1;
+        self.refresh_storage_summary = true;
+        if self.num_failed_storage_summary_updates >= max_storage_summary_update_failures {
+            self.storage_summary_updates_unsupported = true;
+        }
+    }
 }
 
 /// Contains all of the unbanned peers' most recent [`StorageServerSummary`] data
@@ -235,6 +336,21 @@ impl PeerStates {
         }
     }
 
+    /// Increments the response violation counter for the given peer
+    pub fn increment_response_violation_counter(
+        &self,
+        peer: PeerNetworkId,
+        violation: &ResponseViolation,
+    ) {
+        // Get the violation label
+        let violation_label = violation.get_label().into();
+
+        // Update the peer's counter
+        if let Some(mut entry) = self.peer_to_state.get_mut(&peer) {
+            entry.increment_response_violation_counter(violation_label);
+        }
+    }
+
     /// Updates the logs and metrics for the peer request distributions
     pub fn update_peer_request_logs_and_metrics(&self) {
         // Periodically update the metrics
@@ -294,6 +410,34 @@ impl PeerStates {
         }
     }
 
+    /// Records a successful response (of the given size and
+    /// duration) in the bandwidth stats of the given peer.
+    pub fn record_bandwidth_sample(&self, peer: PeerNetworkId, num_bytes: u64, duration: Duration) {
+        if let Some(mut entry) = self.peer_to_state.get_mut(&peer) {
+            entry.bandwidth_stats.record_response(
+                &self.data_client_config.bandwidth_selection_config,
+                num_bytes,
+                duration,
+            );
+        }
+    }
+
+    /// Records a failed request in the bandwidth stats of the given peer
+    pub fn record_bandwidth_error(&self, peer: PeerNetworkId) {
+        if let Some(mut entry) = self.peer_to_state.get_mut(&peer) {
+            entry
+                .bandwidth_stats
+                .record_error(&self.data_client_config.bandwidth_selection_config);
+        }
+    }
+
+    /// Returns a copy of the bandwidth stats for the given peer
+    pub fn get_bandwidth_stats(&self, peer: &PeerNetworkId) -> Option<PeerBandwidthStats> {
+        self.peer_to_state
+            .get(peer)
+            .map(|entry| entry.bandwidth_stats.clone())
+    }
+
     /// Updates the storage summary for the given peer
     pub fn update_summary(&self, peer: PeerNetworkId, storage_summary: StorageServerSummary) {
         self.peer_to_state
@@ -302,6 +446,56 @@ impl PeerStates {
             .update_storage_summary(storage_summary);
     }
 
+    /// Returns the storage summary to use as the base of the next storage summary
+    /// update for the given peer, or `None` if a full storage summary should be polled.
+    pub fn get_storage_summary_for_update(
+        &self,
+        peer: &PeerNetworkId,
+        full_storage_summary_refresh_interval: u64,
+    ) -> Option<StorageServerSummary> {
+        self.peer_to_state.get_mut(peer).and_then(|mut entry| {
+            entry.get_storage_summary_for_update(full_storage_summary_refresh_interval)
+        })
+    }
+
+    /// Updates the state of the storage summary updates for the given peer,
+    /// after an update was polled (successfully or not) from the peer.
+    pub fn update_storage_summary_update_result(
+        &self,
+        peer: &PeerNetworkId,
+        success: bool,
+        max_storage_summary_update_failures: u64,
+    ) {
+        if let Some(mut entry) = self.peer_to_state.get_mut(peer) {
+            entry
+                .update_storage_summary_update_result(success, max_storage_summary_update_failures);
+        }
+    }
+
+    /// Stops polling storage summary updates from the given peer (e.g., because
+    /// the peer rejected the updates as unsupported requests).
+    pub fn disable_storage_summary_updates(&self, peer: &PeerNetworkId) {
+        if let Some(mut entry) = self.peer_to_state.get_mut(peer) {
+            entry.storage_summary_updates_unsupported = true;
+        }
+    }
+
+    /// Returns true iff the compression codec can be negotiated with the given peer
+    pub fn supports_compression_negotiation(&self, peer: &PeerNetworkId) -> bool {
+        self.peer_to_state
+            .get(peer)
+            .map(|entry| !entry.compression_negotiation_unsupported)
+            .unwrap_or(false)
+    }
+
+    /// Stops negotiating the compression codec with the given peer (e.g.,
+    /// because the peer doesn't support compression negotiation).
+    pub fn disable_compression_negotiation(&self, peer: &PeerNetworkId) {
+        if let Some(mut entry) = self.peer_to_state.get_mut(peer) {
+            entry.compression_negotiation_unsupported = true;
+        }
+    }
+
     /// Garbage collects the peer states to remove data for disconnected peers
     pub fn garbage_collect_peer_states(&self, connected_peers: HashSet<PeerNetworkId>) {
         self.peer_to_state
@@ -445,11 +639,16 @@ fn update_peer_request_logs(peer_to_state: Arc<DashMap<PeerNetworkId, PeerState>
         let peer_bucket_id = get_bucket_id_for_peer(peer);
         let sent_requests_by_type = peer_state_entry.get_sent_requests_by_type();
         let received_responses_by_type = peer_state_entry.get_received_responses_by_type();
+        let response_violations_by_type = peer_state_entry.get_response_violations_by_type();
 
         // Collect the request and response counts
         let peer_and_requests_string = format!(
-            "Peer: {:?}, Bucket ID: {:?}, Sent request counts: {:?}, Received response counts: {:?}",
-            peer, peer_bucket_id, sent_requests_by_type, received_responses_by_type
+            "Peer: {:?}, Bucket ID: {:?}, Sent request counts: {:?}, Received response counts: {:?}, Response violation counts: {:?}",
+            peer,
+            peer_bucket_id,
+            sent_requests_by_type,
+            received_responses_by_type,
+            response_violations_by_type
         );
         request_and_response_counts.push(peer_and_requests_string);
     }
@@ -469,12 +668,15 @@ fn update_peer_request_metrics(peer_to_state: Arc<DashMap<PeerNetworkId, PeerSta
     let mut sent_requests_by_peer_bucket: BTreeMap<u8, BTreeMap<String, u64>> = BTreeMap::new();
     let mut received_responses_by_peer_bucket: BTreeMap<u8, BTreeMap<String, u64>> =
         BTreeMap::new();
+    let mut response_violations_by_peer_bucket: BTreeMap<u8, BTreeMap<String, u64>> =
+        BTreeMap::new();
     for peer_state_entry in peer_to_state.iter() {
         // Get the peer and request data
         let peer = *peer_state_entry.key();
         let peer_bucket_id = get_bucket_id_for_peer(peer);
         let sent_requests_by_type = peer_state_entry.get_sent_requests_by_type();
         let received_responses_by_type = peer_state_entry.get_received_responses_by_type();
+        let response_violations_by_type = peer_state_entry.get_response_violations_by_type();
 
         // Aggregate the sent request counts by peer bucket
         let sent_requests_by_bucket = sent_requests_by_peer_bucket
@@ -495,6 +697,16 @@ fn update_peer_request_metrics(peer_to_state: Arc<DashMap<PeerNetworkId, PeerSta
                 .entry(response_label.clone())
                 .or_default() += count;
         }
+
+        // Aggregate the response violation counts by peer bucket
+        let response_violations_by_bucket = response_violations_by_peer_bucket
+            .entry(peer_bucket_id)
+            .or_default();
+        for (violation_label, count) in response_violations_by_type.iter() {
+            *response_violations_by_bucket
+                .entry(violation_label.clone())
+                .or_default() += count;
+        }
     }
 
     // Update the sent request metrics
@@ -520,4 +732,16 @@ fn update_peer_request_metrics(peer_to_state: Arc<DashMap<PeerNetworkId, PeerSta
             );
         }
     }
+
+    // Update the response violation metrics
+    for (peer_bucket_id, response_violations_by_type) in response_violations_by_peer_bucket.iter() {
+        for (violation_label, count) in response_violations_by_type.iter() {
+            metrics::set_gauge_for_bucket(
+                &metrics::RESPONSE_VIOLATIONS_BY_PEER_BUCKET,
+                &peer_bucket_id.to_string(),
+                violation_label,
+                *count,
+            );
+        }
+    }
 }
@@ -20,8 +20,8 @@ use aptos_logger::{debug, error, info, sample, sample::SampleRate, warn};
 use aptos_network::application::storage::PeersAndMetadata;
 use aptos_storage_interface::DbReader;
 use aptos_storage_service_types::{
-    requests::{DataRequest, StorageServiceRequest},
-    responses::StorageServerSummary,
+    requests::{DataRequest, StorageServerSummaryUpdateRequest, StorageServiceRequest},
+    responses::{StorageServerSummary, StorageServerSummaryDelta},
 };
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use dashmap::DashSet;
@@ -402,18 +402,14 @@ pub(crate) fn poll_peer(
     // Create the poller for the peer
     let runtime = data_summary_poller.runtime.clone();
     let poller = async move {
-        // Construct the request for polling
-        let data_request = DataRequest::GetStorageServerSummary;
-        let use_compression = data_summary_poller.data_client_config.use_compression;
-        let storage_request = StorageServiceRequest::new(data_request, use_compression);
-
-        // Fetch the storage summary for the peer and stop the timer
-        let request_timeout = data_summary_poller.data_client_config.response_timeout_ms;
-        let result: crate::error::Result<StorageServerSummary> = data_summary_poller
-            .data_client
-            .send_request_to_peer_and_decode(peer, storage_request, request_timeout)
-            .await
-            .map(Response::into_payload);
+        // Fetch the storage summary for the peer (either as an update of
+        // the known storage summary, or as a full storage summary).
+        let result = match get_storage_summary_for_update(&data_summary_poller, &peer) {
+            Some(known_storage_summary) => {
+                poll_storage_summary_update(&data_summary_poller, peer, known_storage_summary).await
+            },
+            None => poll_full_storage_summary(&data_summary_poller, peer).await,
+        };
 
         // Mark the in-flight poll as now complete
         data_summary_poller.in_flight_request_complete(&peer);
@@ -465,6 +461,120 @@ pub(crate) fn poll_peer(
     }
 }
 
+/// Returns the known storage summary of the peer to poll an update for,
+/// or `None` if a full storage summary should be polled instead.
+fn get_storage_summary_for_update(
+    data_summary_poller: &DataSummaryPoller,
+    peer: &PeerNetworkId,
+) -> Option<StorageServerSummary> {
+    let data_poller_config = data_summary_poller.data_client_config.data_poller_config;
+    if !data_poller_config.enable_storage_summary_updates {
+        return None;
+    }
+
+    data_summary_poller
+        .data_client
+        .get_storage_summary_for_update(
+            peer,
+            data_poller_config.full_storage_summary_refresh_interval,
+        )
+}
+
+/// Polls the full storage summary of the given peer
+async fn poll_full_storage_summary(
+    data_summary_poller: &DataSummaryPoller,
+    peer: PeerNetworkId,
+) -> crate::error::Result<StorageServerSummary> {
+    // Construct the request for polling
+    let data_request = DataRequest::GetStorageServerSummary;
+    let use_compression = data_summary_poller.data_client_config.use_compression;
+    let storage_request = StorageServiceRequest::new(data_request, use_compression);
+
+    // Fetch the storage summary for the peer
+    let request_timeout = data_summary_poller.data_client_config.response_timeout_ms;
+    data_summary_poller
+        .data_client
+        .send_request_to_peer_and_decode(peer, storage_request, request_timeout)
+        .await
+        .map(Response::into_payload)
+}
+
+/// Polls the changes to the known storage summary of the given peer, and
+/// applies them to reconstruct the peer's storage summary. If the peer rejects
+/// the request as invalid (i.e., it doesn't support storage summary updates),
+/// updates are disabled for the peer. Otherwise (e.g., on timeouts), the next
+/// poll falls back to a full storage summary, and updates are only disabled
+/// after too many consecutive failures (as older peers drop the requests).
+async fn poll_storage_summary_update(
+    data_summary_poller: &DataSummaryPoller,
+    peer: PeerNetworkId,
+    known_storage_summary: StorageServerSummary,
+) -> crate::error::Result<StorageServerSummary> {
+    // Construct the request for polling
+    let data_request = DataRequest::GetStorageServerSummaryUpdate(
+        StorageServerSummaryUpdateRequest::new(&known_storage_summary),
+    );
+    let use_compression = data_summary_poller.data_client_config.use_compression;
+    let storage_request = StorageServiceRequest::new(data_request, use_compression);
+
+    // Fetch the storage summary delta for the peer
+    let request_timeout = data_summary_poller.data_client_config.response_timeout_ms;
+    let result: crate::error::Result<StorageServerSummaryDelta> = data_summary_poller
+        .data_client
+        .send_request_to_peer_and_decode(peer, storage_request, request_timeout)
+        .await
+        .map(Response::into_payload);
+
+    // Apply the delta to the known storage summary
+    let max_storage_summary_update_failures = data_summary_poller
+        .data_client_config
+        .data_poller_config
+        .max_storage_summary_update_failures;
+    match result {
+        Ok(storage_summary_delta) => {
+            data_summary_poller
+                .data_client
+                .update_peer_storage_summary_update_result(
+                    &peer,
+                    true,
+                    max_storage_summary_update_failures,
+                );
+            Ok(storage_summary_delta.apply(&known_storage_summary))
+        },
+        Err(Error::InvalidRequest(error)) => {
+            warn!(
+                (LogSchema::new(LogEntry::StorageSummaryResponse)
+                    .event(LogEvent::PeerPollingError)
+                    .message("The peer doesn't support storage summary updates! Disabling them.")
+                    .peer(&peer))
+            );
+            data_summary_poller
+                .data_client
+                .disable_peer_storage_summary_updates(&peer);
+            Err(Error::InvalidRequest(error))
+        },
+        Err(error) => {
+            warn!(
+                (LogSchema::new(LogEntry::StorageSummaryResponse)
+                    .event(LogEvent::PeerPollingError)
+                    .message(
+                        "Failed to poll a storage summary update! Polling a full summary next."
+                    )
+                    .error(&error)
+                    .peer(&peer))
+            );
+            data_summary_poller
+                .data_client
+                .update_peer_storage_summary_update_result(
+                    &peer,
+                    false,
+                    max_storage_summary_update_failures,
+                );
+            Err(error)
+        },
+    }
+}
+
 /// Spawns the dedicated latency monitor
 fn start_latency_monitor(
     data_client_config: Arc<AptosDataClientConfig>,
@@ -98,7 +98,7 @@ async fn compression_mismatch_disabled() {
 }
 
 #[tokio::test]
-async fn compression_mismatch_enabled() {
+async fn compression_skipped_by_server() {
     // Create a base config for a validator
     let base_config = utils::create_validator_base_config();
 
@@ -159,19 +159,19 @@ async fn compression_mismatch_enabled() {
                         include_events: false,
                     })
                 ) {
-                    // Don't compress the response
+                    // Don't compress the response (e.g., the server skipped compression)
                     utils::handle_transactions_request(network_request, false);
                 }
             }
         });
 
-        // The client should receive a compressed response and return an error
+        // The client should accept the uncompressed response
         let request_timeout = data_client_config.response_timeout_ms;
         let response = client
             .get_transactions_with_proof(100, 50, 100, false, request_timeout)
             .await
-            .unwrap_err();
-        assert_matches!(response, Error::DataIsUnavailable(_));
+            .unwrap();
+        assert_eq!(response.payload, TransactionListWithProof::new_empty());
     }
 }
 
@@ -9,4 +9,5 @@ mod peers;
 mod poller;
 mod priority;
 mod utils;
+mod validation;
 mod weighted_selection;
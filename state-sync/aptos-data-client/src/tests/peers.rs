@@ -21,7 +21,7 @@ use aptos_storage_service_types::{
     StorageServiceError,
 };
 use aptos_types::transaction::TransactionListWithProof;
-use claims::{assert_err, assert_matches, assert_ok};
+use claims::{assert_err, assert_matches, assert_none, assert_ok, assert_some};
 use maplit::hashset;
 use rand::{rngs::OsRng, Rng};
 use std::{collections::HashSet, time::Duration};
@@ -572,6 +572,50 @@ async fn single_good_peer_across_priorities() {
     }
 }
 
+#[tokio::test]
+async fn storage_summary_update_failures() {
+    // Create the mock network and client
+    let data_client_config = AptosDataClientConfig::default();
+    let (mut mock_network, _, client, _) = MockNetwork::new(None, Some(data_client_config), None);
+
+    // Add two peers that advertise a storage summary
+    let (peer, _) = utils::add_peer_to_network(PeerPriority::HighPriority, &mut mock_network);
+    let (unsupported_peer, _) =
+        utils::add_peer_to_network(PeerPriority::HighPriority, &mut mock_network);
+    for peer in [peer, unsupported_peer] {
+        client.update_peer_storage_summary(peer, utils::create_storage_summary(100));
+    }
+
+    // Verify that a failed update is followed by a full storage summary (and then updates again)
+    let refresh_interval = 10;
+    let max_failures = 3;
+    assert_some!(client.get_storage_summary_for_update(&peer, refresh_interval));
+    client.update_peer_storage_summary_update_result(&peer, false, max_failures);
+    assert_none!(client.get_storage_summary_for_update(&peer, refresh_interval));
+    assert_some!(client.get_storage_summary_for_update(&peer, refresh_interval));
+
+    // Verify that a successful update resets the consecutive failures
+    client.update_peer_storage_summary_update_result(&peer, true, max_failures);
+    for _ in 0..max_failures - 1 {
+        client.update_peer_storage_summary_update_result(&peer, false, max_failures);
+        assert_none!(client.get_storage_summary_for_update(&peer, refresh_interval));
+        assert_some!(client.get_storage_summary_for_update(&peer, refresh_interval));
+    }
+
+    // Verify that too many consecutive failures disable updates for the peer
+    client.update_peer_storage_summary_update_result(&peer, false, max_failures);
+    for _ in 0..refresh_interval * 2 {
+        assert_none!(client.get_storage_summary_for_update(&peer, refresh_interval));
+    }
+
+    // Verify that updates are disabled immediately for a peer that doesn't support them
+    assert_some!(client.get_storage_summary_for_update(&unsupported_peer, refresh_interval));
+    client.disable_peer_storage_summary_updates(&unsupported_peer);
+    for _ in 0..refresh_interval * 2 {
+        assert_none!(client.get_storage_summary_for_update(&unsupported_peer, refresh_interval));
+    }
+}
+
 /// Emulates network latencies by sleeping for some amount of time.
 /// If no duration is specified, the sleep duration is randomly chosen.
 async fn emulate_network_latencies(sleep_duration_ms: Option<u64>) {
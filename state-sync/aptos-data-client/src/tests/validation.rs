@@ -0,0 +1,313 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::validation::{ResponseViolation, ValidateResponse};
+use aptos_crypto::HashValue;
+use aptos_storage_service_types::{
+    requests::{
+        DataRequest, EpochEndingLedgerInfoRequest, NewTransactionsWithProofRequest,
+        StateValuesWithProofRequest, TransactionsWithProofRequest,
+    },
+    responses::TransactionOrOutputListWithProof,
+};
+use aptos_types::{
+    aggregate_signature::AggregateSignature,
+    block_info::BlockInfo,
+    epoch_change::EpochChangeProof,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{SparseMerkleRangeProof, TransactionInfoListWithProof},
+    state_store::{
+        state_key::StateKey,
+        state_value::{StateValue, StateValueChunkWithProof},
+    },
+    transaction::{
+        ExecutionStatus, Transaction, TransactionInfo, TransactionListWithProof, Version,
+    },
+};
+
+#[test]
+fn test_validate_epoch_ending_ledger_infos() {
+    // Create a request for epochs 10 to 20
+    let data_request = DataRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+        start_epoch: 10,
+        expected_end_epoch: 20,
+    });
+
+    // Verify that contiguous epochs are valid
+    let epoch_change_proof = create_epoch_change_proof(&[10, 11, 12]);
+    assert_eq!(epoch_change_proof.validate(&data_request), Ok(()));
+
+    // Verify that an empty response is invalid
+    let epoch_change_proof = create_epoch_change_proof(&[]);
+    assert_eq!(
+        epoch_change_proof.validate(&data_request),
+        Err(ResponseViolation::EmptyEpochEndingLedgerInfos)
+    );
+
+    // Verify that epochs not starting at the requested epoch are invalid
+    let epoch_change_proof = create_epoch_change_proof(&[11, 12]);
+    assert_eq!(
+        epoch_change_proof.validate(&data_request),
+        Err(ResponseViolation::NonContiguousEpochs {
+            expected_epoch: 10,
+            found_epoch: 11,
+        })
+    );
+
+    // Verify that non-contiguous epochs are invalid
+    let epoch_change_proof = create_epoch_change_proof(&[10, 11, 11]);
+    assert_eq!(
+        epoch_change_proof.validate(&data_request),
+        Err(ResponseViolation::NonContiguousEpochs {
+            expected_epoch: 12,
+            found_epoch: 11,
+        })
+    );
+
+    // Verify that epochs beyond the expected end epoch are invalid
+    let data_request = DataRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+        start_epoch: 10,
+        expected_end_epoch: 11,
+    });
+    let epoch_change_proof = create_epoch_change_proof(&[10, 11, 12]);
+    assert_eq!(
+        epoch_change_proof.validate(&data_request),
+        Err(ResponseViolation::EpochExceedsExpectedEnd {
+            expected_end_epoch: 11,
+            found_epoch: 12,
+        })
+    );
+}
+
+#[test]
+fn test_validate_transactions() {
+    // Create a request for versions 100 to 109
+    let data_request = DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+        proof_version: 200,
+        start_version: 100,
+        end_version: 109,
+        include_events: false,
+    });
+
+    // Verify that empty and contiguous transaction lists are valid
+    let transaction_list = TransactionListWithProof::new_empty();
+    assert_eq!(transaction_list.validate(&data_request), Ok(()));
+    let transaction_list = create_transaction_list(Some(100), 10, 10);
+    assert_eq!(transaction_list.validate(&data_request), Ok(()));
+
+    // Verify that a list starting at the wrong version is invalid
+    let transaction_list = create_transaction_list(Some(101), 5, 5);
+    assert_eq!(
+        transaction_list.validate(&data_request),
+        Err(ResponseViolation::NonContiguousVersions {
+            expected_version: 100,
+            found_version: 101,
+        })
+    );
+
+    // Verify that a list without a first version is invalid
+    let transaction_list = create_transaction_list(None, 5, 5);
+    assert_eq!(
+        transaction_list.validate(&data_request),
+        Err(ResponseViolation::MissingFirstVersion { num_items: 5 })
+    );
+
+    // Verify that a list with too many transactions is invalid
+    let transaction_list = create_transaction_list(Some(100), 11, 11);
+    assert_eq!(
+        transaction_list.validate(&data_request),
+        Err(ResponseViolation::TooManyItems {
+            max_num_items: 10,
+            num_items: 11,
+        })
+    );
+
+    // Verify that a list with a mismatched proof length is invalid
+    let transaction_list = create_transaction_list(Some(100), 5, 4);
+    assert_eq!(
+        transaction_list.validate(&data_request),
+        Err(ResponseViolation::MismatchedProofLength {
+            num_items: 5,
+            num_proofs: 4,
+        })
+    );
+
+    // Verify that a list with a mismatched events length is invalid
+    let mut transaction_list = create_transaction_list(Some(100), 5, 5);
+    transaction_list.events = Some(vec![vec![]; 3]);
+    assert_eq!(
+        transaction_list.validate(&data_request),
+        Err(ResponseViolation::MismatchedEventsLength {
+            num_items: 5,
+            num_events: 3,
+        })
+    );
+}
+
+#[test]
+fn test_validate_new_transactions() {
+    // Create a request for new transactions after version 99
+    let data_request = DataRequest::GetNewTransactionsWithProof(NewTransactionsWithProofRequest {
+        known_version: 99,
+        known_epoch: 5,
+        include_events: false,
+    });
+
+    // Verify that transactions up to the ledger info version are valid
+    let transaction_list = create_transaction_list(Some(100), 10, 10);
+    let response = (transaction_list, create_ledger_info(5, 109));
+    assert_eq!(response.validate(&data_request), Ok(()));
+
+    // Verify that transactions beyond the ledger info version are invalid
+    let transaction_list = create_transaction_list(Some(100), 10, 10);
+    let response = (transaction_list, create_ledger_info(5, 105));
+    assert_eq!(
+        response.validate(&data_request),
+        Err(ResponseViolation::VersionExceedsLedgerInfo {
+            last_version: 109,
+            ledger_info_version: 105,
+        })
+    );
+
+    // Verify that transactions not following the known version are invalid
+    let transaction_list = create_transaction_list(Some(50), 10, 10);
+    let response = (transaction_list, create_ledger_info(5, 109));
+    assert_eq!(
+        response.validate(&data_request),
+        Err(ResponseViolation::NonContiguousVersions {
+            expected_version: 100,
+            found_version: 50,
+        })
+    );
+
+    // Verify that a response without transactions or outputs is invalid
+    let transaction_or_output_list: TransactionOrOutputListWithProof = (None, None);
+    let response = (transaction_or_output_list, create_ledger_info(5, 109));
+    assert_eq!(
+        response.validate(&data_request),
+        Err(ResponseViolation::InvalidTransactionOrOutputList {
+            has_transactions: false,
+            has_outputs: false,
+        })
+    );
+}
+
+#[test]
+fn test_validate_state_values() {
+    // Create a request for state indices 10 to 19
+    let data_request = DataRequest::GetStateValuesWithProof(StateValuesWithProofRequest {
+        version: 100,
+        start_index: 10,
+        end_index: 19,
+    });
+
+    // Verify that a chunk within the requested range is valid
+    let state_value_chunk = create_state_value_chunk(10, 19, 10);
+    assert_eq!(state_value_chunk.validate(&data_request), Ok(()));
+    let state_value_chunk = create_state_value_chunk(10, 14, 5);
+    assert_eq!(state_value_chunk.validate(&data_request), Ok(()));
+
+    // Verify that chunks outside the requested range are invalid
+    for (first_index, last_index) in [(11, 19), (10, 20), (10, 9)] {
+        let state_value_chunk = create_state_value_chunk(first_index, last_index, 10);
+        assert_eq!(
+            state_value_chunk.validate(&data_request),
+            Err(ResponseViolation::InvalidStateValueIndices {
+                expected_first_index: 10,
+                first_index,
+                last_index,
+            })
+        );
+    }
+
+    // Verify that a chunk with a mismatched number of values is invalid
+    let state_value_chunk = create_state_value_chunk(10, 19, 9);
+    assert_eq!(
+        state_value_chunk.validate(&data_request),
+        Err(ResponseViolation::MismatchedStateValueCount {
+            first_index: 10,
+            last_index: 19,
+            num_values: 9,
+        })
+    );
+}
+
+/// Creates an epoch change proof with epoch ending ledger infos for the given epochs
+fn create_epoch_change_proof(epochs: &[u64]) -> EpochChangeProof {
+    let ledger_infos = epochs
+        .iter()
+        .map(|epoch| create_ledger_info(*epoch, epoch * 100))
+        .collect();
+    EpochChangeProof::new(ledger_infos, false)
+}
+
+/// Creates a test ledger info at the given epoch and version
+fn create_ledger_info(epoch: u64, version: Version) -> LedgerInfoWithSignatures {
+    LedgerInfoWithSignatures::new(
+        LedgerInfo::new(
+            BlockInfo::new(
+                epoch,
+                0,
+                HashValue::zero(),
+                HashValue::zero(),
+                version,
+                0,
+                None,
+            ),
+            HashValue::zero(),
+        ),
+        AggregateSignature::empty(),
+    )
+}
+
+/// Creates a state value chunk with the given indices and number of values
+fn create_state_value_chunk(
+    first_index: u64,
+    last_index: u64,
+    num_values: u64,
+) -> StateValueChunkWithProof {
+    let raw_values = (0..num_values)
+        .map(|index| {
+            (
+                StateKey::raw(index.to_le_bytes().to_vec()),
+                StateValue::from(vec![]),
+            )
+        })
+        .collect();
+    StateValueChunkWithProof {
+        first_index,
+        last_index,
+        first_key: HashValue::zero(),
+        last_key: HashValue::zero(),
+        raw_values,
+        proof: SparseMerkleRangeProof::new(vec![]),
+        root_hash: HashValue::zero(),
+    }
+}
+
+/// Creates a transaction list with the given first version, number
+/// of transactions and number of transaction infos.
+fn create_transaction_list(
+    first_version: Option<Version>,
+    num_transactions: usize,
+    num_transaction_infos: usize,
+) -> TransactionListWithProof {
+    let transactions = (0..num_transactions)
+        .map(|_| Transaction::StateCheckpoint(HashValue::random()))
+        .collect();
+    let transaction_infos = (0..num_transaction_infos)
+        .map(|_| {
+            TransactionInfo::new(
+                HashValue::zero(),
+                HashValue::zero(),
+                HashValue::zero(),
+                None,
+                0,
+                ExecutionStatus::Success,
+            )
+        })
+        .collect();
+    let mut proof = TransactionInfoListWithProof::new_empty();
+    proof.transaction_infos = transaction_infos;
+    TransactionListWithProof::new(transactions, None, first_version, proof)
+}
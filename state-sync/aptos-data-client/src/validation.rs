@@ -0,0 +1,369 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structural validation of storage service responses. These checks are cheap
+//! (i.e., they don't verify any proofs), and catch malformed responses before
+//! they're handed to state sync (where they would otherwise fail deep inside
+//! the apply logic, far away from the peer that sent them).
+
+use aptos_storage_service_types::{
+    requests::DataRequest,
+    responses::{
+        StateValueChunkWithIncrementalProof, StorageServerSummary, StorageServerSummaryDelta,
+        TransactionOrOutputListWithProof,
+    },
+};
+use aptos_types::{
+    epoch_change::EpochChangeProof,
+    ledger_info::LedgerInfoWithSignatures,
+    state_store::state_value::StateValueChunkWithProof,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A structural invariant violated by a storage service response
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Eq, Serialize)]
+pub enum ResponseViolation {
+    #[error("The response contains no epoch ending ledger infos!")]
+    EmptyEpochEndingLedgerInfos,
+    #[error("Non-contiguous epochs! Expected epoch: {expected_epoch}, found: {found_epoch}")]
+    NonContiguousEpochs {
+        expected_epoch: u64,
+        found_epoch: u64,
+    },
+    #[error("The epoch {found_epoch} exceeds the expected end epoch {expected_end_epoch}!")]
+    EpochExceedsExpectedEnd {
+        expected_end_epoch: u64,
+        found_epoch: u64,
+    },
+    #[error("The response contains {num_items} items, but no first version!")]
+    MissingFirstVersion { num_items: u64 },
+    #[error("Non-contiguous versions! Expected first version: {expected_version}, found: {found_version}")]
+    NonContiguousVersions {
+        expected_version: Version,
+        found_version: Version,
+    },
+    #[error("Too many items! Requested at most: {max_num_items}, found: {num_items}")]
+    TooManyItems { max_num_items: u64, num_items: u64 },
+    #[error("The number of transaction infos ({num_proofs}) doesn't match the number of items ({num_items})!")]
+    MismatchedProofLength { num_items: u64, num_proofs: u64 },
+    #[error("The number of event lists ({num_events}) doesn't match the number of transactions ({num_items})!")]
+    MismatchedEventsLength { num_items: u64, num_events: u64 },
+    #[error(
+        "The last version {last_version} exceeds the ledger info version {ledger_info_version}!"
+    )]
+    VersionExceedsLedgerInfo {
+        last_version: Version,
+        ledger_info_version: Version,
+    },
+    #[error("Expected exactly one of a transaction or output list! Found transactions: {has_transactions}, outputs: {has_outputs}")]
+    InvalidTransactionOrOutputList {
+        has_transactions: bool,
+        has_outputs: bool,
+    },
+    #[error("Invalid state value indices! Expected first index: {expected_first_index}, found: [{first_index}, {last_index}]")]
+    InvalidStateValueIndices {
+        expected_first_index: u64,
+        first_index: u64,
+        last_index: u64,
+    },
+    #[error("The number of state values ({num_values}) doesn't match the index range [{first_index}, {last_index}]!")]
+    MismatchedStateValueCount {
+        first_index: u64,
+        last_index: u64,
+        num_values: u64,
+    },
+}
+
+impl ResponseViolation {
+    /// Returns a summary label for the violation
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Self::EmptyEpochEndingLedgerInfos => "empty_epoch_ending_ledger_infos",
+            Self::NonContiguousEpochs { .. } => "non_contiguous_epochs",
+            Self::EpochExceedsExpectedEnd { .. } => "epoch_exceeds_expected_end",
+            Self::MissingFirstVersion { .. } => "missing_first_version",
+            Self::NonContiguousVersions { .. } => "non_contiguous_versions",
+            Self::TooManyItems { .. } => "too_many_items",
+            Self::MismatchedProofLength { .. } => "mismatched_proof_length",
+            Self::MismatchedEventsLength { .. } => "mismatched_events_length",
+            Self::VersionExceedsLedgerInfo { .. } => "version_exceeds_ledger_info",
+            Self::InvalidTransactionOrOutputList { .. } => "invalid_transaction_or_output_list",
+            Self::InvalidStateValueIndices { .. } => "invalid_state_value_indices",
+            Self::MismatchedStateValueCount { .. } => "mismatched_state_value_count",
+        }
+    }
+}
+
+/// Validates the structural invariants of a (decoded) response payload, given
+/// the data request that the response was sent for. Note: this doesn't verify
+/// any proofs (this is left to state sync).
+pub trait ValidateResponse {
+    fn validate(&self, _data_request: &DataRequest) -> Result<(), ResponseViolation> {
+        Ok(()) // By default, there are no structural invariants to check
+    }
+}
+
+impl ValidateResponse for u64 {}
+
+impl ValidateResponse for StorageServerSummary {}
+
+impl ValidateResponse for StorageServerSummaryDelta {}
+
+impl ValidateResponse for EpochChangeProof {
+    fn validate(&self, data_request: &DataRequest) -> Result<(), ResponseViolation> {
+        let DataRequest::GetEpochEndingLedgerInfos(request) = data_request else {
+            return Ok(());
+        };
+
+        // The ledger infos must start at the requested epoch and be contiguous
+        if self.ledger_info_with_sigs.is_empty() {
+            return Err(ResponseViolation::EmptyEpochEndingLedgerInfos);
+        }
+        for (expected_epoch, ledger_info) in
+            (request.start_epoch..).zip(self.ledger_info_with_sigs.iter())
+        {
+            let found_epoch = ledger_info.ledger_info().epoch();
+            if found_epoch != expected_epoch {
+                return Err(ResponseViolation::NonContiguousEpochs {
+                    expected_epoch,
+                    found_epoch,
+                });
+            }
+            if found_epoch > request.expected_end_epoch {
+                return Err(ResponseViolation::EpochExceedsExpectedEnd {
+                    expected_end_epoch: request.expected_end_epoch,
+                    found_epoch,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ValidateResponse for StateValueChunkWithProof {
+    fn validate(&self, data_request: &DataRequest) -> Result<(), ResponseViolation> {
+        let (start_index, end_index) = match data_request {
+            DataRequest::GetStateValuesWithProof(request) => {
+                (request.start_index, request.end_index)
+            },
+            DataRequest::GetStateValuesWithIncrementalProof(request) => {
+                (request.start_index, request.end_index)
+            },
+            _ => return Ok(()),
+        };
+
+        // The chunk must start at the requested index and stay within the requested range
+        if self.first_index != start_index
+            || self.last_index < self.first_index
+            || self.last_index > end_index
+        {
+            return Err(ResponseViolation::InvalidStateValueIndices {
+                expected_first_index: start_index,
+                first_index: self.first_index,
+                last_index: self.last_index,
+            });
+        }
+
+        // The number of values must match the index range
+        let num_values = self.raw_values.len() as u64;
+        if num_values != self.last_index - self.first_index + 1 {
+            return Err(ResponseViolation::MismatchedStateValueCount {
+                first_index: self.first_index,
+                last_index: self.last_index,
+                num_values,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl ValidateResponse for StateValueChunkWithIncrementalProof {
+    fn validate(&self, data_request: &DataRequest) -> Result<(), ResponseViolation> {
+        self.state_value_chunk_with_proof.validate(data_request)
+    }
+}
+
+impl ValidateResponse for TransactionListWithProof {
+    fn validate(&self, data_request: &DataRequest) -> Result<(), ResponseViolation> {
+        let num_items = self.transactions.len() as u64;
+        if let Some(events) = &self.events {
+            let num_events = events.len() as u64;
+            if num_events != num_items {
+                return Err(ResponseViolation::MismatchedEventsLength {
+                    num_items,
+                    num_events,
+                });
+            }
+        }
+
+        validate_transaction_list(
+            data_request,
+            self.first_transaction_version,
+            num_items,
+            self.proof.transaction_infos.len() as u64,
+        )
+    }
+}
+
+impl ValidateResponse for TransactionOutputListWithProof {
+    fn validate(&self, data_request: &DataRequest) -> Result<(), ResponseViolation> {
+        validate_transaction_list(
+            data_request,
+            self.first_transaction_output_version,
+            self.transactions_and_outputs.len() as u64,
+            self.proof.transaction_infos.len() as u64,
+        )
+    }
+}
+
+impl ValidateResponse for TransactionOrOutputListWithProof {
+    fn validate(&self, data_request: &DataRequest) -> Result<(), ResponseViolation> {
+        match self {
+            (Some(transaction_list), None) => transaction_list.validate(data_request),
+            (None, Some(output_list)) => output_list.validate(data_request),
+            (transaction_list, output_list) => {
+                Err(ResponseViolation::InvalidTransactionOrOutputList {
+                    has_transactions: transaction_list.is_some(),
+                    has_outputs: output_list.is_some(),
+                })
+            },
+        }
+    }
+}
+
+impl<T: ValidateResponse + LastVersion> ValidateResponse for (T, LedgerInfoWithSignatures) {
+    fn validate(&self, data_request: &DataRequest) -> Result<(), ResponseViolation> {
+        let (data, ledger_info) = self;
+        data.validate(data_request)?;
+
+        // The data must not extend beyond the ledger info it is proven against
+        let ledger_info_version = ledger_info.ledger_info().version();
+        if let Some(last_version) = data.last_version() {
+            if last_version > ledger_info_version {
+                return Err(ResponseViolation::VersionExceedsLedgerInfo {
+                    last_version,
+                    ledger_info_version,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the last version contained in a transaction or output list (if any)
+pub trait LastVersion {
+    fn last_version(&self) -> Option<Version>;
+}
+
+impl LastVersion for TransactionListWithProof {
+    fn last_version(&self) -> Option<Version> {
+        get_last_version(self.first_transaction_version, self.transactions.len())
+    }
+}
+
+impl LastVersion for TransactionOutputListWithProof {
+    fn last_version(&self) -> Option<Version> {
+        get_last_version(
+            self.first_transaction_output_version,
+            self.transactions_and_outputs.len(),
+        )
+    }
+}
+
+impl LastVersion for TransactionOrOutputListWithProof {
+    fn last_version(&self) -> Option<Version> {
+        match self {
+            (Some(transaction_list), _) => transaction_list.last_version(),
+            (_, Some(output_list)) => output_list.last_version(),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Returns the last version of a list with the given first version and length
+fn get_last_version(first_version: Option<Version>, num_items: usize) -> Option<Version> {
+    let num_items = (num_items as u64).checked_sub(1)?;
+    first_version.and_then(|first_version| first_version.checked_add(num_items))
+}
+
+/// Returns the expected first version and the maximum number of items
+/// for a transaction (or output) list sent for the given data request.
+fn get_expected_versions(data_request: &DataRequest) -> (Option<Version>, Option<u64>) {
+    let requested_range = |start_version: Version, end_version: Version| {
+        let max_num_items = end_version.saturating_sub(start_version).saturating_add(1);
+        (Some(start_version), Some(max_num_items))
+    };
+    match data_request {
+        DataRequest::GetTransactionsWithProof(request) => {
+            requested_range(request.start_version, request.end_version)
+        },
+        DataRequest::GetTransactionOutputsWithProof(request) => {
+            requested_range(request.start_version, request.end_version)
+        },
+        DataRequest::GetTransactionsOrOutputsWithProof(request) => {
+            requested_range(request.start_version, request.end_version)
+        },
+        DataRequest::GetNewTransactionsWithProof(request) => {
+            (request.known_version.checked_add(1), None)
+        },
+        DataRequest::GetNewTransactionOutputsWithProof(request) => {
+            (request.known_version.checked_add(1), None)
+        },
+        DataRequest::GetNewTransactionsOrOutputsWithProof(request) => {
+            (request.known_version.checked_add(1), None)
+        },
+        _ => (None, None), // Subscriptions don't specify the versions explicitly
+    }
+}
+
+/// Validates the versions and proof length of a transaction (or output) list
+fn validate_transaction_list(
+    data_request: &DataRequest,
+    first_version: Option<Version>,
+    num_items: u64,
+    num_proofs: u64,
+) -> Result<(), ResponseViolation> {
+    // Every item must have a corresponding transaction info
+    if num_proofs != num_items {
+        return Err(ResponseViolation::MismatchedProofLength {
+            num_items,
+            num_proofs,
+        });
+    }
+
+    // Empty lists have no versions to check
+    if num_items == 0 {
+        return Ok(());
+    }
+
+    // The list must start at the expected version
+    let Some(found_version) = first_version else {
+        return Err(ResponseViolation::MissingFirstVersion { num_items });
+    };
+    let (expected_version, max_num_items) = get_expected_versions(data_request);
+    if let Some(expected_version) = expected_version {
+        if found_version != expected_version {
+            return Err(ResponseViolation::NonContiguousVersions {
+                expected_version,
+                found_version,
+            });
+        }
+    }
+
+    // The list must not contain more items than requested
+    if let Some(max_num_items) = max_num_items {
+        if num_items > max_num_items {
+            return Err(ResponseViolation::TooManyItems {
+                max_num_items,
+                num_items,
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -45,6 +45,10 @@ pub trait ConsensusNotificationSender: Send + Sync {
 
     /// Notify state sync to synchronize storage to the specified target.
     async fn sync_to_target(&self, target: LedgerInfoWithSignatures) -> Result<(), Error>;
+
+    /// Notify state sync that the consensus observer stopped executing blocks (e.g.,
+    /// because the observation stalled), so state sync should keep the node synced.
+    async fn notify_observer_fallback(&self) -> Result<(), Error>;
 }
 
 /// This method returns a (ConsensusNotifier, ConsensusNotificationListener) pair that can be used
@@ -160,6 +164,20 @@ impl ConsensusNotificationSender for ConsensusNotifier {
             Err(error) => Err(Error::UnexpectedErrorEncountered(format!("{:?}", error))),
         }
     }
+
+    async fn notify_observer_fallback(&self) -> Result<(), Error> {
+        // Send the notification to state sync (no response is required)
+        self.notification_sender
+            .clone()
+            .send(ConsensusNotification::ObserverFallback)
+            .await
+            .map_err(|error| {
+                Error::NotificationError(format!(
+                    "Failed to notify state sync of the observer fallback! Error: {:?}",
+                    error
+                ))
+            })
+    }
 }
 
 /// The state sync component responsible for handling consensus requests and
@@ -219,6 +237,7 @@ impl FusedStream for ConsensusNotificationListener {
 pub enum ConsensusNotification {
     NotifyCommit(ConsensusCommitNotification),
     SyncToTarget(ConsensusSyncNotification),
+    ObserverFallback,
 }
 
 /// A commit notification to notify state sync of new commits.
@@ -374,6 +393,30 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_observer_fallback_notification_arrives() {
+        // Create runtime and consensus notifier
+        let runtime = create_runtime();
+        let _enter = runtime.enter();
+        let (consensus_notifier, mut consensus_listener) =
+            crate::new_consensus_notifier_listener_pair(CONSENSUS_NOTIFICATION_TIMEOUT);
+
+        // Send an observer fallback notification (no response is expected)
+        let notify_result = block_on(consensus_notifier.notify_observer_fallback());
+        assert_ok!(notify_result);
+
+        // Verify the notification arrives at the receiver
+        assert_matches!(
+            consensus_listener.select_next_some().now_or_never(),
+            Some(ConsensusNotification::ObserverFallback)
+        );
+
+        // Drop the receiver and verify the notification fails
+        consensus_listener.notification_receiver.close();
+        let notify_result = block_on(consensus_notifier.notify_observer_fallback());
+        assert_matches!(notify_result, Err(Error::NotificationError(_)));
+    }
+
     #[test]
     fn test_consensus_notification_responses() {
         // Create runtime and consensus notifier
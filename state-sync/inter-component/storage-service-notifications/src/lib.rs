@@ -4,6 +4,8 @@
 #![forbid(unsafe_code)]
 
 use aptos_channels::{self, aptos_channel, message_queues::QueueStyle};
+use aptos_config::network_id::PeerNetworkId;
+use aptos_types::ledger_info::LedgerInfoWithSignatures;
 use async_trait::async_trait;
 use futures::{stream::FusedStream, Stream};
 use serde::{Deserialize, Serialize};
@@ -20,10 +22,16 @@ use thiserror::Error;
 // the next X-1 will execute with an unchanged DB (thus, becoming a no-op and wasting the CPU).
 const STORAGE_SERVICE_NOTIFICATION_CHANNEL_SIZE: usize = 1;
 
+// Note: we only keep the latest commit notification for each peer, because
+// the driver only cares about the highest ledger info advertised by the peer.
+const PEER_COMMIT_NOTIFICATION_CHANNEL_SIZE: usize = 1;
+
 #[derive(Clone, Debug, Deserialize, Error, PartialEq, Eq, Serialize)]
 pub enum Error {
     #[error("Commit notification failed: {0}")]
     CommitNotificationError(String),
+    #[error("Peer commit notification failed: {0}")]
+    PeerCommitNotificationError(String),
 }
 
 /// The interface between the state sync driver and the storage service, allowing the driver
@@ -142,10 +150,131 @@ impl fmt::Display for StorageServiceCommitNotification {
     }
 }
 
+/// This method returns a (PeerCommitNotifier, PeerCommitNotificationListener) pair
+/// that can be used to forward commit notifications received from peers (e.g.,
+/// validators and VFNs) by the storage service to the state sync driver.
+///
+/// Note: the storage service should take the notifier and the driver should take the listener.
+pub fn new_peer_commit_notifier_listener_pair(
+) -> (PeerCommitNotifier, PeerCommitNotificationListener) {
+    // Create a dedicated channel for notifications
+    let (notification_sender, notification_receiver) = aptos_channel::new(
+        QueueStyle::KLAST,
+        PEER_COMMIT_NOTIFICATION_CHANNEL_SIZE,
+        None,
+    );
+
+    // Create a notification sender and listener
+    let peer_commit_notifier = PeerCommitNotifier::new(notification_sender);
+    let peer_commit_listener = PeerCommitNotificationListener::new(notification_receiver);
+
+    (peer_commit_notifier, peer_commit_listener)
+}
+
+/// The storage service component responsible for forwarding peer commit notifications
+#[derive(Clone, Debug)]
+pub struct PeerCommitNotifier {
+    notification_sender: aptos_channel::Sender<PeerNetworkId, PeerCommitNotification>,
+}
+
+impl PeerCommitNotifier {
+    fn new(
+        notification_sender: aptos_channel::Sender<PeerNetworkId, PeerCommitNotification>,
+    ) -> Self {
+        Self {
+            notification_sender,
+        }
+    }
+
+    /// Forwards the ledger info committed by the specified peer to the driver
+    pub fn notify_peer_commit(
+        &self,
+        peer_network_id: PeerNetworkId,
+        ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        // Create a new peer commit notification
+        let peer_commit_notification = PeerCommitNotification {
+            peer_network_id,
+            ledger_info,
+        };
+
+        // Send the notification to the driver
+        if let Err(error) = self
+            .notification_sender
+            .push(peer_network_id, peer_commit_notification)
+        {
+            return Err(Error::PeerCommitNotificationError(format!(
+                "Failed to forward the peer commit notification! Error: {:?}",
+                error
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// The state sync driver component responsible for handling peer commit notifications
+#[derive(Debug)]
+pub struct PeerCommitNotificationListener {
+    notification_receiver: aptos_channel::Receiver<PeerNetworkId, PeerCommitNotification>,
+}
+
+impl PeerCommitNotificationListener {
+    fn new(
+        notification_receiver: aptos_channel::Receiver<PeerNetworkId, PeerCommitNotification>,
+    ) -> Self {
+        PeerCommitNotificationListener {
+            notification_receiver,
+        }
+    }
+}
+
+impl Stream for PeerCommitNotificationListener {
+    type Item = PeerCommitNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().notification_receiver).poll_next(cx)
+    }
+}
+
+impl FusedStream for PeerCommitNotificationListener {
+    fn is_terminated(&self) -> bool {
+        self.notification_receiver.is_terminated()
+    }
+}
+
+/// A notification for a new ledger info committed by a peer, forwarded
+/// by the storage service to the state sync driver.
+#[derive(Debug)]
+pub struct PeerCommitNotification {
+    pub peer_network_id: PeerNetworkId, // The peer that sent the notification
+    pub ledger_info: LedgerInfoWithSignatures, // The latest ledger info committed by the peer
+}
+
+impl fmt::Display for PeerCommitNotification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PeerCommitNotification [peer_network_id: {}, version: {}]",
+            self.peer_network_id,
+            self.ledger_info.ledger_info().version(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        new_storage_service_notifier_listener_pair, Error, StorageServiceNotificationSender,
+        new_peer_commit_notifier_listener_pair, new_storage_service_notifier_listener_pair, Error,
+        StorageServiceNotificationSender,
+    };
+    use aptos_config::network_id::{NetworkId, PeerNetworkId};
+    use aptos_crypto::HashValue;
+    use aptos_types::{
+        aggregate_signature::AggregateSignature,
+        block_info::BlockInfo,
+        ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+        PeerId,
     };
     use claims::assert_matches;
     use futures::StreamExt;
@@ -178,4 +307,44 @@ mod tests {
             .unwrap_err();
         assert_matches!(error, Error::CommitNotificationError(_));
     }
+
+    #[tokio::test]
+    async fn test_peer_commit_notification() {
+        // Create a peer commit notifier and listener pair
+        let (peer_commit_notifier, mut peer_commit_listener) =
+            new_peer_commit_notifier_listener_pair();
+
+        // Forward several notifications from the same peer
+        let peer_network_id = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+        for version in [10, 20, 30] {
+            peer_commit_notifier
+                .notify_peer_commit(peer_network_id, create_ledger_info(version))
+                .unwrap();
+        }
+
+        // Verify only the latest notification is received
+        let peer_commit_notification = peer_commit_listener.next().await.unwrap();
+        assert_eq!(peer_commit_notification.peer_network_id, peer_network_id);
+        assert_eq!(
+            peer_commit_notification.ledger_info.ledger_info().version(),
+            30
+        );
+
+        // Drop the receiver, send a notification and verify an error is returned
+        drop(peer_commit_listener);
+        let error = peer_commit_notifier
+            .notify_peer_commit(peer_network_id, create_ledger_info(40))
+            .unwrap_err();
+        assert_matches!(error, Error::PeerCommitNotificationError(_));
+    }
+
+    /// Creates a test ledger info at the given version
+    fn create_ledger_info(version: u64) -> LedgerInfoWithSignatures {
+        let block_info =
+            BlockInfo::new(0, 0, HashValue::zero(), HashValue::zero(), version, 0, None);
+        LedgerInfoWithSignatures::new(
+            LedgerInfo::new(block_info, HashValue::zero()),
+            AggregateSignature::empty(),
+        )
+    }
 }
@@ -33,9 +33,13 @@ use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
 use aptos_mempool_notifications::MempoolNotificationSender;
 use aptos_storage_interface::DbReader;
-use aptos_storage_service_notifications::StorageServiceNotificationSender;
+use aptos_storage_service_notifications::{
+    PeerCommitNotification, PeerCommitNotificationListener, StorageServiceNotificationSender,
+};
 use aptos_time_service::{TimeService, TimeServiceTrait};
-use aptos_types::{contract_event::ContractEvent, waypoint::Waypoint};
+use aptos_types::{
+    contract_event::ContractEvent, epoch_change::Verifier, transaction::Version, waypoint::Waypoint,
+};
 use futures::StreamExt;
 use std::{sync::Arc, time::Instant};
 use tokio::{
@@ -58,14 +62,24 @@ pub struct DriverConfiguration {
 
     // The trusted waypoint for the node
     pub waypoint: Waypoint,
+
+    // Whether the consensus observer is enabled (i.e., whether full
+    // nodes should accept notifications from the observer).
+    pub consensus_observer_enabled: bool,
 }
 
 impl DriverConfiguration {
-    pub fn new(config: StateSyncDriverConfig, role: RoleType, waypoint: Waypoint) -> Self {
+    pub fn new(
+        config: StateSyncDriverConfig,
+        role: RoleType,
+        waypoint: Waypoint,
+        consensus_observer_enabled: bool,
+    ) -> Self {
         Self {
             config,
             role,
             waypoint,
+            consensus_observer_enabled,
         }
     }
 }
@@ -91,6 +105,10 @@ pub struct StateSyncDriver<
     // The handler for notifications from consensus
     consensus_notification_handler: ConsensusNotificationHandler,
 
+    // Whether the consensus observer is executing (and committing) blocks. This is
+    // set once the observer's sync request is satisfied, and reset on its fallback.
+    consensus_observer_executing: bool,
+
     // The component that manages the continuous syncing of the node
     continuous_syncer: ContinuousSyncer<StorageSyncer, StreamingClient>,
 
@@ -109,6 +127,12 @@ pub struct StateSyncDriver<
     // The handler for notifications to mempool
     mempool_notification_handler: MempoolNotificationHandler<MempoolNotifier>,
 
+    // The listener for commit notifications sent by peers (e.g., validators and VFNs)
+    peer_commit_notification_listener: PeerCommitNotificationListener,
+
+    // The highest version advertised by a peer commit notification that triggered progress
+    highest_peer_commit_version: Option<Version>,
+
     // The timestamp at which the driver started executing
     start_time: Option<Instant>,
 
@@ -151,6 +175,7 @@ impl<
         event_subscription_service: Arc<Mutex<EventSubscriptionService>>,
         mempool_notification_handler: MempoolNotificationHandler<MempoolNotifier>,
         metadata_storage: MetadataStorage,
+        peer_commit_notification_listener: PeerCommitNotificationListener,
         storage_service_notification_handler: StorageServiceNotificationHandler<
             StorageServiceNotifier,
         >,
@@ -183,12 +208,15 @@ impl<
             client_notification_listener,
             commit_notification_listener,
             consensus_notification_handler,
+            consensus_observer_executing: false,
             continuous_syncer,
             aptos_data_client,
             driver_configuration,
             error_notification_listener,
             event_subscription_service,
             mempool_notification_handler,
+            peer_commit_notification_listener,
+            highest_peer_commit_version: None,
             start_time: None,
             storage,
             storage_service_notification_handler,
@@ -223,6 +251,9 @@ impl<
                 notification = self.error_notification_listener.select_next_some() => {
                     self.handle_error_notification(notification).await;
                 }
+                notification = self.peer_commit_notification_listener.select_next_some() => {
+                    self.handle_peer_commit_notification(notification).await;
+                }
                 _ = progress_check_interval.select_next_some() => {
                     self.drive_progress().await;
                 }
@@ -232,9 +263,12 @@ impl<
 
     /// Handles a notification sent by consensus
     async fn handle_consensus_notification(&mut self, notification: ConsensusNotification) {
-        // Verify the notification: full nodes shouldn't receive notifications
-        // and consensus should only send notifications after bootstrapping!
-        let result = if self.driver_configuration.role == RoleType::FullNode {
+        // Verify the notification: full nodes shouldn't receive notifications (unless
+        // they are sent by the consensus observer) and consensus should only send
+        // notifications after bootstrapping!
+        let result = if self.driver_configuration.role == RoleType::FullNode
+            && !self.is_consensus_observer()
+        {
             Err(Error::FullNodeConsensusNotification(format!(
                 "Received consensus notification: {:?}",
                 notification
@@ -263,6 +297,7 @@ impl<
                         .respond_to_sync_notification(sync_notification, Err(error.clone()))
                         .await;
                 },
+                ConsensusNotification::ObserverFallback => {}, // No response is required
             }
             warn!(LogSchema::new(LogEntry::ConsensusNotification)
                 .error(&error)
@@ -280,6 +315,10 @@ impl<
                 self.handle_consensus_sync_notification(sync_notification)
                     .await
             },
+            ConsensusNotification::ObserverFallback => {
+                self.handle_consensus_observer_fallback();
+                Ok(())
+            },
         };
 
         // Log any errors from notification handling
@@ -382,12 +421,36 @@ impl<
             metrics::DRIVER_CONSENSUS_SYNC_NOTIFICATION,
         );
 
+        // The consensus observer executes blocks once the sync request is satisfied,
+        // so continuous syncing must stop (and any pending data must be committed)
+        // before the sync request is initialized.
+        if self.is_consensus_observer() {
+            self.consensus_observer_executing = false;
+            self.continuous_syncer.reset_active_stream(None).await?;
+            self.wait_for_pending_storage_data().await;
+        }
+
         // Initialize a new sync request
         let latest_synced_ledger_info =
             utils::fetch_latest_synced_ledger_info(self.storage.clone())?;
         self.consensus_notification_handler
             .initialize_sync_request(sync_notification, latest_synced_ledger_info)
-            .await
+            .await?;
+
+        // If the sync request was already satisfied, hand control to the consensus observer
+        if self.is_consensus_observer() && !self.active_sync_request() {
+            self.storage_synchronizer.finish_chunk_executor();
+            self.consensus_observer_executing = true;
+        }
+        Ok(())
+    }
+
+    /// Handles a fallback notification sent by the consensus observer, i.e.,
+    /// the observer stopped executing blocks, so the node must keep syncing.
+    fn handle_consensus_observer_fallback(&mut self) {
+        info!(LogSchema::new(LogEntry::ConsensusNotification)
+            .message("Received a consensus observer fallback notification!"));
+        self.consensus_observer_executing = false;
     }
 
     /// Handles a client notification sent by the driver client
@@ -479,6 +542,89 @@ impl<
         };
     }
 
+    /// Handles a commit notification sent by a peer (e.g., a validator or VFN).
+    /// If the peer is ahead of us, we drive progress immediately (instead of
+    /// waiting for the next progress check).
+    async fn handle_peer_commit_notification(&mut self, notification: PeerCommitNotification) {
+        trace!(
+            LogSchema::new(LogEntry::PeerCommitNotification).message(&format!(
+                "Received a peer commit notification: {}",
+                notification
+            ))
+        );
+        metrics::increment_counter(
+            &metrics::DRIVER_COUNTERS,
+            metrics::DRIVER_PEER_COMMIT_NOTIFICATION,
+        );
+
+        // Check if the notification should trigger progress
+        match self.should_drive_progress_for_peer_commit(&notification) {
+            Ok(true) => {
+                self.highest_peer_commit_version =
+                    Some(notification.ledger_info.ledger_info().version());
+                metrics::increment_counter(
+                    &metrics::DRIVER_COUNTERS,
+                    metrics::DRIVER_PEER_COMMIT_NOTIFICATION_PROGRESS,
+                );
+                self.drive_progress().await;
+            },
+            Ok(false) => { /* There's nothing to do */ },
+            Err(error) => {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(DRIVER_ERROR_LOG_FREQ_SECS)),
+                    warn!(LogSchema::new(LogEntry::PeerCommitNotification)
+                        .error(&error)
+                        .message(&format!(
+                            "Failed to handle the commit notification from peer: {}",
+                            notification.peer_network_id
+                        )));
+                );
+            },
+        }
+    }
+
+    /// Returns true iff the given peer commit notification should trigger
+    /// progress, i.e., the notification contains a valid ledger info that is
+    /// ahead of us (and ahead of any previously handled notifications).
+    fn should_drive_progress_for_peer_commit(
+        &self,
+        notification: &PeerCommitNotification,
+    ) -> Result<bool, Error> {
+        // Validators follow consensus, and the bootstrapper drives
+        // progress using the advertised data of all peers.
+        if self.is_validator()
+            || !self.bootstrapper.is_bootstrapped()
+            || self.check_if_consensus_executing()
+        {
+            return Ok(false);
+        }
+
+        // Verify the notification is for a new version
+        let peer_version = notification.ledger_info.ledger_info().version();
+        if self.highest_peer_commit_version >= Some(peer_version) {
+            return Ok(false);
+        }
+        let synced_version = utils::fetch_latest_synced_version(self.storage.clone())?;
+        if synced_version >= peer_version {
+            return Ok(false);
+        }
+
+        // Verify the ledger info signatures. Note: ledger infos from
+        // future epochs can't be verified, so they are ignored (the
+        // data client will eventually see them in the peer summaries).
+        let epoch_state = utils::fetch_latest_epoch_state(self.storage.clone())?;
+        epoch_state
+            .verify(&notification.ledger_info)
+            .map_err(|error| {
+                Error::VerificationError(format!(
+                    "Failed to verify the peer commit notification! Error: {:?}",
+                    error
+                ))
+            })?;
+
+        Ok(true)
+    }
+
     /// Checks if the node has successfully reached the sync target
     async fn check_sync_request_progress(&mut self) -> Result<(), Error> {
         if !self.active_sync_request() {
@@ -504,15 +650,7 @@ impl<
 
         // Wait for the storage synchronizer to drain (if it hasn't already).
         // This prevents notifying consensus prematurely.
-        while self.storage_synchronizer.pending_storage_data() {
-            sample!(
-                SampleRate::Duration(Duration::from_secs(PENDING_DATA_LOG_FREQ_SECS)),
-                info!("Waiting for the storage synchronizer to handle pending data!")
-            );
-
-            // Yield to avoid starving the storage synchronizer threads.
-            yield_now().await;
-        }
+        self.wait_for_pending_storage_data().await;
 
         // Refresh the latest synced ledger info and handle the sync request
         let latest_synced_ledger_info =
@@ -526,23 +664,47 @@ impl<
         if !self.active_sync_request() {
             self.continuous_syncer.reset_active_stream(None).await?;
             self.storage_synchronizer.finish_chunk_executor(); // Consensus is now in control
+            if self.is_consensus_observer() {
+                self.consensus_observer_executing = true;
+            }
         }
         Ok(())
     }
 
+    /// Waits for the storage synchronizer to handle all pending data
+    async fn wait_for_pending_storage_data(&self) {
+        while self.storage_synchronizer.pending_storage_data() {
+            sample!(
+                SampleRate::Duration(Duration::from_secs(PENDING_DATA_LOG_FREQ_SECS)),
+                info!("Waiting for the storage synchronizer to handle pending data!")
+            );
+
+            // Yield to avoid starving the storage synchronizer threads.
+            yield_now().await;
+        }
+    }
+
     /// Returns true iff there's an active sync request from consensus
     fn active_sync_request(&self) -> bool {
         self.consensus_notification_handler.active_sync_request()
     }
 
+    /// Returns true iff this node is a full node that runs the consensus observer
+    fn is_consensus_observer(&self) -> bool {
+        self.driver_configuration.role == RoleType::FullNode
+            && self.driver_configuration.consensus_observer_enabled
+    }
+
     /// Returns true iff this node is a validator
     fn is_validator(&self) -> bool {
         self.driver_configuration.role == RoleType::Validator
     }
 
-    /// Returns true iff consensus is currently executing
+    /// Returns true iff consensus (or the consensus observer) is currently executing
     fn check_if_consensus_executing(&self) -> bool {
-        self.is_validator() && self.bootstrapper.is_bootstrapped() && !self.active_sync_request()
+        (self.is_validator() || self.consensus_observer_executing)
+            && self.bootstrapper.is_bootstrapped()
+            && !self.active_sync_request()
     }
 
     /// Checks if the connection deadline has passed. If so, validators with
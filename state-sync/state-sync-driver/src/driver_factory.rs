@@ -21,7 +21,9 @@ use aptos_executor_types::ChunkExecutorTrait;
 use aptos_infallible::Mutex;
 use aptos_mempool_notifications::MempoolNotificationSender;
 use aptos_storage_interface::DbReaderWriter;
-use aptos_storage_service_notifications::StorageServiceNotificationSender;
+use aptos_storage_service_notifications::{
+    PeerCommitNotificationListener, StorageServiceNotificationSender,
+};
 use aptos_time_service::TimeService;
 use aptos_types::{move_resource::MoveStorage, waypoint::Waypoint};
 use futures::{
@@ -53,6 +55,7 @@ impl DriverFactory {
         mempool_notification_sender: MempoolNotifier,
         storage_service_notification_sender: StorageServiceNotifier,
         metadata_storage: MetadataStorage,
+        peer_commit_notification_listener: PeerCommitNotificationListener,
         consensus_listener: ConsensusNotificationListener,
         event_subscription_service: EventSubscriptionService,
         aptos_data_client: AptosDataClient,
@@ -68,6 +71,7 @@ impl DriverFactory {
             mempool_notification_sender,
             storage_service_notification_sender,
             metadata_storage,
+            peer_commit_notification_listener,
             consensus_listener,
             event_subscription_service,
             aptos_data_client,
@@ -94,6 +98,7 @@ impl DriverFactory {
         mempool_notification_sender: MempoolNotifier,
         storage_service_notification_sender: StorageServiceNotifier,
         metadata_storage: MetadataStorage,
+        peer_commit_notification_listener: PeerCommitNotificationListener,
         consensus_listener: ConsensusNotificationListener,
         mut event_subscription_service: EventSubscriptionService,
         aptos_data_client: AptosDataClient,
@@ -157,6 +162,7 @@ impl DriverFactory {
             node_config.state_sync.state_sync_driver,
             node_config.base.role,
             waypoint,
+            node_config.consensus_observer.observer_enabled,
         );
 
         // Create the state sync driver
@@ -169,6 +175,7 @@ impl DriverFactory {
             event_subscription_service,
             mempool_notification_handler,
             metadata_storage,
+            peer_commit_notification_listener,
             storage_service_notification_handler,
             storage_synchronizer,
             aptos_data_client,
@@ -33,6 +33,7 @@ pub enum LogEntry {
     ConsensusNotification,
     Driver,
     NotificationHandler,
+    PeerCommitNotification,
     StorageSynchronizer,
     SynchronizerNotification,
 }
@@ -12,6 +12,9 @@ use std::time::Instant;
 pub const DRIVER_CLIENT_NOTIFICATION: &str = "driver_client_notification";
 pub const DRIVER_CONSENSUS_COMMIT_NOTIFICATION: &str = "driver_consensus_commit_notification";
 pub const DRIVER_CONSENSUS_SYNC_NOTIFICATION: &str = "driver_consensus_sync_notification";
+pub const DRIVER_PEER_COMMIT_NOTIFICATION: &str = "driver_peer_commit_notification";
+pub const DRIVER_PEER_COMMIT_NOTIFICATION_PROGRESS: &str =
+    "driver_peer_commit_notification_progress";
 
 /// Data notification metric labels
 pub const NOTIFICATION_CREATE_TO_APPLY: &str = "notification_create_to_apply";
@@ -272,6 +272,34 @@ async fn test_consensus_sync_request() {
     assert_err!(result);
 }
 
+#[tokio::test]
+#[timeout(120_000)]
+async fn test_consensus_observer_notifications() {
+    // Create a driver for a full node with the consensus observer enabled
+    let mut node_config = NodeConfig::default();
+    node_config.base.role = RoleType::FullNode;
+    node_config.consensus_observer.observer_enabled = true;
+    let (_full_node_driver, _, consensus_notifier, _, _, _, _, _) =
+        create_driver_for_tests(node_config, Waypoint::default(), None).await;
+
+    // Verify that the commit notification is accepted, but the node isn't bootstrapped
+    let error = consensus_notifier
+        .notify_new_commit(vec![create_transaction()], vec![])
+        .await
+        .unwrap_err();
+    assert!(format!("{:?}", error).contains("BootstrapNotComplete"));
+
+    // Verify that the fallback notification is delivered
+    consensus_notifier.notify_observer_fallback().await.unwrap();
+
+    // Verify that the sync request is accepted, but the node isn't bootstrapped
+    let error = consensus_notifier
+        .sync_to_target(create_ledger_info_at_version(0))
+        .await
+        .unwrap_err();
+    assert!(format!("{:?}", error).contains("BootstrapNotComplete"));
+}
+
 /// Creates a state sync driver for a validator node
 async fn create_validator_driver(
     event_key_subscriptions: Option<Vec<EventKey>>,
@@ -387,6 +415,10 @@ async fn create_driver_for_tests(
         None,
     );
 
+    // Create the peer commit notifier and listener
+    let (_peer_commit_notifier, peer_commit_listener) =
+        aptos_storage_service_notifications::new_peer_commit_notifier_listener_pair();
+
     // Create the metadata storage
     let metadata_storage = PersistentMetadataStorage::new(db_path.path());
 
@@ -401,6 +433,7 @@ async fn create_driver_for_tests(
             mempool_notifier,
             storage_service_notifier,
             metadata_storage,
+            peer_commit_listener,
             consensus_listener,
             event_subscription_service,
             aptos_data_client,
@@ -85,6 +85,8 @@ fn test_new_initialized_configs() {
     // Create the state sync driver factory
     let chunk_executor = Arc::new(ChunkExecutor::<AptosVM>::new(db_rw.clone()));
     let metadata_storage = PersistentMetadataStorage::new(tmp_dir.path());
+    let (_peer_commit_notifier, peer_commit_listener) =
+        aptos_storage_service_notifications::new_peer_commit_notifier_listener_pair();
     let _ = DriverFactory::create_and_spawn_driver(
         true,
         &node_config,
@@ -94,6 +96,7 @@ fn test_new_initialized_configs() {
         mempool_notifier,
         storage_service_notifier,
         metadata_storage,
+        peer_commit_listener,
         consensus_listener,
         event_subscription_service,
         aptos_data_client,
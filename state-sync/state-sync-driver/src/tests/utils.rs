@@ -90,6 +90,7 @@ pub fn create_full_node_driver_configuration() -> DriverConfiguration {
         config,
         role,
         waypoint,
+        consensus_observer_enabled: false,
     }
 }
 
@@ -16,16 +16,24 @@ use aptos_storage_service_types::{
 use std::{collections::HashSet, sync::Arc, time::Duration};
 use thiserror::Error;
 
+pub mod prefetcher;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Network error: {0}")]
     NetworkError(String),
 
+    #[error("Chunk prefetch error: {0}")]
+    PrefetchError(String),
+
     #[error("Network RPC error: {0}")]
     RpcError(#[from] RpcError),
 
     #[error("Error from remote storage service: {0}")]
     StorageServiceError(#[from] StorageServiceError),
+
+    #[error("Chunk verification error: {0}")]
+    VerificationError(String),
 }
 
 /// The interface for sending Storage Service requests and
@@ -60,6 +68,12 @@ impl<NetworkClient: NetworkClientInterface<StorageServiceMessage>>
                 "Got storage service request instead of response! Request: {:?}",
                 request
             ))),
+            StorageServiceMessage::CommitNotification(ledger_info) => {
+                Err(Error::NetworkError(format!(
+                    "Got commit notification instead of response! Ledger info: {:?}",
+                    ledger_info
+                )))
+            },
         }
     }
 
@@ -0,0 +1,377 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, StorageServiceClient};
+use aptos_config::network_id::PeerNetworkId;
+use aptos_network::application::interface::NetworkClientInterface;
+use aptos_storage_service_types::{
+    requests::StorageServiceRequest, responses::StorageServiceResponse, StorageServiceMessage,
+};
+use async_trait::async_trait;
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+use tokio::{sync::Semaphore, task::JoinHandle};
+
+/// The configuration of the verified chunk prefetcher
+#[derive(Clone, Copy, Debug)]
+pub struct PrefetcherConfig {
+    /// The maximum number of chunks fetched (or verified) ahead of the consumer
+    pub max_chunks_in_flight: usize,
+    /// The maximum number of chunks verified concurrently
+    pub num_verification_workers: usize,
+    /// The timeout of each chunk request
+    pub request_timeout: Duration,
+}
+
+impl Default for PrefetcherConfig {
+    fn default() -> Self {
+        Self {
+            max_chunks_in_flight: 8,
+            num_verification_workers: 4,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Sends the chunk requests of the prefetcher (e.g., to a single peer)
+#[async_trait]
+pub trait ChunkRequester: Send + Sync + 'static {
+    async fn request_chunk(
+        &self,
+        request: StorageServiceRequest,
+        timeout: Duration,
+    ) -> Result<StorageServiceResponse, Error>;
+}
+
+/// Verifies the proofs of the chunk responses. Verification is synchronous
+/// (and potentially expensive), so it is executed on the blocking thread pool.
+pub trait ChunkVerifier: Send + Sync + 'static {
+    type Chunk: Send + 'static;
+
+    /// Verifies the response to the given chunk request and returns the verified chunk
+    fn verify_chunk(
+        &self,
+        request: &StorageServiceRequest,
+        response: StorageServiceResponse,
+    ) -> Result<Self::Chunk, Error>;
+}
+
+/// A chunk requester that sends all requests to a single peer
+pub struct PeerChunkRequester<NetworkClient> {
+    storage_service_client: StorageServiceClient<NetworkClient>,
+    peer: PeerNetworkId,
+}
+
+impl<NetworkClient> PeerChunkRequester<NetworkClient> {
+    pub fn new(
+        storage_service_client: StorageServiceClient<NetworkClient>,
+        peer: PeerNetworkId,
+    ) -> Self {
+        Self {
+            storage_service_client,
+            peer,
+        }
+    }
+}
+
+#[async_trait]
+impl<NetworkClient: NetworkClientInterface<StorageServiceMessage> + 'static> ChunkRequester
+    for PeerChunkRequester<NetworkClient>
+{
+    async fn request_chunk(
+        &self,
+        request: StorageServiceRequest,
+        timeout: Duration,
+    ) -> Result<StorageServiceResponse, Error> {
+        self.storage_service_client
+            .send_request(self.peer, timeout, request)
+            .await
+    }
+}
+
+/// A prefetcher that requests the next chunks ahead of the consumer (e.g., the
+/// state sync applier). Each chunk is fetched and then verified in a background
+/// task, so that network latency and proof verification are off the critical
+/// path. Verified chunks are always delivered in the order of the requests.
+pub struct VerifiedChunkPrefetcher<Requester, Verifier: ChunkVerifier> {
+    config: PrefetcherConfig,
+    requester: Arc<Requester>,
+    verifier: Arc<Verifier>,
+    verification_permits: Arc<Semaphore>,
+
+    chunk_requests: Box<dyn Iterator<Item = StorageServiceRequest> + Send>,
+    pending_chunks: VecDeque<JoinHandle<Result<Verifier::Chunk, Error>>>,
+    failed: bool,
+}
+
+impl<Requester: ChunkRequester, Verifier: ChunkVerifier>
+    VerifiedChunkPrefetcher<Requester, Verifier>
+{
+    pub fn new(
+        config: PrefetcherConfig,
+        requester: Requester,
+        verifier: Verifier,
+        chunk_requests: impl Iterator<Item = StorageServiceRequest> + Send + 'static,
+    ) -> Self {
+        // A zero-sized config would never make progress
+        let config = PrefetcherConfig {
+            max_chunks_in_flight: config.max_chunks_in_flight.max(1),
+            num_verification_workers: config.num_verification_workers.max(1),
+            ..config
+        };
+        let verification_permits = Arc::new(Semaphore::new(config.num_verification_workers));
+
+        Self {
+            config,
+            requester: Arc::new(requester),
+            verifier: Arc::new(verifier),
+            verification_permits,
+            chunk_requests: Box::new(chunk_requests),
+            pending_chunks: VecDeque::new(),
+            failed: false,
+        }
+    }
+
+    /// Returns the next verified chunk (in request order), or None if all
+    /// chunks have been delivered. After the first error, no more chunks are
+    /// delivered (the consumer is expected to restart from the failed chunk).
+    pub async fn next_verified_chunk(&mut self) -> Option<Result<Verifier::Chunk, Error>> {
+        if self.failed {
+            return None;
+        }
+
+        // Top up the pipeline before waiting on the next chunk
+        self.prefetch_chunks();
+        let pending_chunk = self.pending_chunks.pop_front()?;
+        let result = pending_chunk.await.unwrap_or_else(|error| {
+            Err(Error::PrefetchError(format!(
+                "Failed to join the chunk prefetch task: {:?}",
+                error
+            )))
+        });
+
+        if result.is_err() {
+            self.abort_pending_chunks();
+        } else {
+            self.prefetch_chunks();
+        }
+        Some(result)
+    }
+
+    /// Returns the number of chunks currently fetched (or verified) ahead of the consumer
+    pub fn num_pending_chunks(&self) -> usize {
+        self.pending_chunks.len()
+    }
+
+    /// Spawns the fetch and verification tasks for the next chunks, until
+    /// the maximum number of chunks in flight is reached.
+    fn prefetch_chunks(&mut self) {
+        while self.pending_chunks.len() < self.config.max_chunks_in_flight {
+            let Some(chunk_request) = self.chunk_requests.next() else {
+                return;
+            };
+            let pending_chunk = tokio::spawn(fetch_and_verify_chunk(
+                self.requester.clone(),
+                self.verifier.clone(),
+                self.verification_permits.clone(),
+                chunk_request,
+                self.config.request_timeout,
+            ));
+            self.pending_chunks.push_back(pending_chunk);
+        }
+    }
+
+    /// Aborts all chunks in flight, and stops prefetching
+    fn abort_pending_chunks(&mut self) {
+        for pending_chunk in self.pending_chunks.drain(..) {
+            pending_chunk.abort();
+        }
+        self.failed = true;
+    }
+}
+
+impl<Requester, Verifier: ChunkVerifier> Drop for VerifiedChunkPrefetcher<Requester, Verifier> {
+    fn drop(&mut self) {
+        for pending_chunk in self.pending_chunks.iter() {
+            pending_chunk.abort();
+        }
+    }
+}
+
+/// Fetches the chunk and verifies it once a verification worker is available
+async fn fetch_and_verify_chunk<Requester: ChunkRequester, Verifier: ChunkVerifier>(
+    requester: Arc<Requester>,
+    verifier: Arc<Verifier>,
+    verification_permits: Arc<Semaphore>,
+    chunk_request: StorageServiceRequest,
+    request_timeout: Duration,
+) -> Result<Verifier::Chunk, Error> {
+    let response = requester
+        .request_chunk(chunk_request.clone(), request_timeout)
+        .await?;
+
+    let _permit = verification_permits
+        .acquire_owned()
+        .await
+        .map_err(|error| Error::PrefetchError(error.to_string()))?;
+    tokio::task::spawn_blocking(move || verifier.verify_chunk(&chunk_request, response))
+        .await
+        .map_err(|error| {
+            Error::PrefetchError(format!("Failed to join the verification task: {:?}", error))
+        })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_storage_service_types::{requests::DataRequest, responses::DataResponse};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A requester that responds to each chunk request (for the number of
+    /// states at the given version) after a delay, and tracks the concurrency.
+    #[derive(Default)]
+    struct MockRequester {
+        num_in_flight: AtomicUsize,
+        max_num_in_flight: AtomicUsize,
+        num_requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ChunkRequester for Arc<MockRequester> {
+        async fn request_chunk(
+            &self,
+            request: StorageServiceRequest,
+            _timeout: Duration,
+        ) -> Result<StorageServiceResponse, Error> {
+            self.num_requests.fetch_add(1, Ordering::SeqCst);
+            let num_in_flight = self.num_in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_num_in_flight
+                .fetch_max(num_in_flight, Ordering::SeqCst);
+
+            // Later chunks are served faster, so responses arrive out of order
+            let version = get_chunk_version(&request);
+            tokio::time::sleep(Duration::from_millis(50 - 5 * (version % 10))).await;
+
+            self.num_in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(
+                StorageServiceResponse::new(DataResponse::NumberOfStatesAtVersion(version), false)
+                    .unwrap(),
+            )
+        }
+    }
+
+    /// A verifier that rejects the chunk at the given version
+    struct MockVerifier {
+        invalid_version: Option<u64>,
+    }
+
+    impl ChunkVerifier for MockVerifier {
+        type Chunk = u64;
+
+        fn verify_chunk(
+            &self,
+            request: &StorageServiceRequest,
+            response: StorageServiceResponse,
+        ) -> Result<u64, Error> {
+            let version = get_chunk_version(request);
+            match response.get_data_response() {
+                Ok(DataResponse::NumberOfStatesAtVersion(response_version))
+                    if response_version == version && Some(version) != self.invalid_version =>
+                {
+                    Ok(version)
+                },
+                _ => Err(Error::VerificationError(format!(
+                    "Invalid chunk at version {}",
+                    version
+                ))),
+            }
+        }
+    }
+
+    fn get_chunk_version(request: &StorageServiceRequest) -> u64 {
+        match request.data_request {
+            DataRequest::GetNumberOfStatesAtVersion(version) => version,
+            _ => panic!("Unexpected request: {:?}", request),
+        }
+    }
+
+    fn create_prefetcher(
+        requester: Arc<MockRequester>,
+        max_chunks_in_flight: usize,
+        num_chunks: u64,
+        invalid_version: Option<u64>,
+    ) -> VerifiedChunkPrefetcher<Arc<MockRequester>, MockVerifier> {
+        let config = PrefetcherConfig {
+            max_chunks_in_flight,
+            ..Default::default()
+        };
+        let chunk_requests = (0..num_chunks).map(|version| {
+            StorageServiceRequest::new(DataRequest::GetNumberOfStatesAtVersion(version), false)
+        });
+        VerifiedChunkPrefetcher::new(
+            config,
+            requester,
+            MockVerifier { invalid_version },
+            chunk_requests,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_chunks_are_delivered_in_order() {
+        let requester = Arc::new(MockRequester::default());
+        let mut prefetcher = create_prefetcher(requester.clone(), 4, 20, None);
+
+        for version in 0..20 {
+            assert_eq!(
+                prefetcher.next_verified_chunk().await.unwrap().unwrap(),
+                version
+            );
+        }
+        assert!(prefetcher.next_verified_chunk().await.is_none());
+
+        // Chunks were fetched concurrently, but never more than the limit
+        assert_eq!(requester.num_requests.load(Ordering::SeqCst), 20);
+        assert!(requester.max_num_in_flight.load(Ordering::SeqCst) > 1);
+        assert!(requester.max_num_in_flight.load(Ordering::SeqCst) <= 4);
+    }
+
+    #[tokio::test]
+    async fn test_chunks_are_prefetched_ahead_of_consumer() {
+        let requester = Arc::new(MockRequester::default());
+        let mut prefetcher = create_prefetcher(requester.clone(), 4, 20, None);
+        assert_eq!(prefetcher.next_verified_chunk().await.unwrap().unwrap(), 0);
+
+        // While the consumer applies the chunk, the next chunks are ready-verified
+        assert_eq!(prefetcher.num_pending_chunks(), 4);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let start_time = std::time::Instant::now();
+        for version in 1..5 {
+            assert_eq!(
+                prefetcher.next_verified_chunk().await.unwrap().unwrap(),
+                version
+            );
+        }
+        assert!(start_time.elapsed() < Duration::from_millis(25));
+    }
+
+    #[tokio::test]
+    async fn test_prefetching_stops_on_verification_failure() {
+        let requester = Arc::new(MockRequester::default());
+        let mut prefetcher = create_prefetcher(requester.clone(), 4, 20, Some(3));
+
+        for version in 0..3 {
+            assert_eq!(
+                prefetcher.next_verified_chunk().await.unwrap().unwrap(),
+                version
+            );
+        }
+        assert!(matches!(
+            prefetcher.next_verified_chunk().await,
+            Some(Err(Error::VerificationError(_)))
+        ));
+
+        // No chunks are delivered after the failure
+        assert!(prefetcher.next_verified_chunk().await.is_none());
+        assert_eq!(prefetcher.num_pending_chunks(), 0);
+        assert!(requester.num_requests.load(Ordering::SeqCst) < 20);
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{error::Error, metrics};
+use aptos_config::network_id::{NetworkId, PeerNetworkId};
+use aptos_netcore::transport::ConnectionOrigin;
+use aptos_network::{
+    application::{
+        interface::{NetworkClient, NetworkClientInterface},
+        storage::PeersAndMetadata,
+    },
+    ProtocolId,
+};
+use aptos_storage_service_types::StorageServiceMessage;
+use aptos_types::ledger_info::LedgerInfoWithSignatures;
+
+/// Sends the given ledger info (i.e., the new highest synced ledger info)
+/// to all downstream peers, so that they can fetch the new data immediately
+/// (instead of waiting to poll our storage summary).
+pub fn broadcast_commit_notification(
+    network_client: &NetworkClient<StorageServiceMessage>,
+    ledger_info: LedgerInfoWithSignatures,
+) -> Result<(), Error> {
+    // Identify the downstream peers
+    let downstream_peers = get_downstream_peers(&network_client.get_peers_and_metadata())?;
+    if downstream_peers.is_empty() {
+        return Ok(()); // There's no one to notify
+    }
+
+    // Send the commit notification to the peers
+    network_client
+        .send_to_peers(
+            StorageServiceMessage::CommitNotification(ledger_info),
+            &downstream_peers,
+        )
+        .map_err(|error| {
+            Error::UnexpectedErrorEncountered(format!(
+                "Failed to send the commit notification to downstream peers! Error: {:?}",
+                error
+            ))
+        })?;
+
+    // Update the commit notification metrics
+    for peer_network_id in downstream_peers {
+        metrics::increment_commit_notifications_sent(peer_network_id.network_id());
+    }
+
+    Ok(())
+}
+
+/// Returns the connected downstream peers that support commit notifications.
+/// Downstream peers are those that connected to us (e.g., VFNs connecting to
+/// validators, and PFNs connecting to VFNs). Validators are never notified
+/// because they follow consensus directly.
+pub(crate) fn get_downstream_peers(
+    peers_and_metadata: &PeersAndMetadata,
+) -> Result<Vec<PeerNetworkId>, Error> {
+    let connected_peers_and_metadata = peers_and_metadata
+        .get_connected_peers_and_metadata()
+        .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+
+    let downstream_peers = connected_peers_and_metadata
+        .into_iter()
+        .filter(|(peer_network_id, peer_metadata)| {
+            peer_network_id.network_id() != NetworkId::Validator
+                && peer_metadata.get_connection_metadata().origin == ConnectionOrigin::Inbound
+                && peer_metadata.supports_protocol(ProtocolId::StateSyncDirectSend)
+        })
+        .map(|(peer_network_id, _)| peer_network_id)
+        .collect();
+    Ok(downstream_peers)
+}
@@ -0,0 +1,271 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{error::Error, metrics};
+use aptos_compression::codec::CompressionCodec;
+use aptos_config::{
+    config::AdaptiveCompressionConfig,
+    network_id::{NetworkId, PeerNetworkId},
+};
+use aptos_storage_service_types::responses::{DataResponse, StorageServiceResponse};
+use mini_moka::sync::Cache;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+// The maximum number of (peer, request type) compression stats to track
+const MAX_NUM_COMPRESSION_STATS: u64 = 10_000;
+
+// The weight of each new sample in the compression moving averages
+const MOVING_AVERAGE_WEIGHT: f64 = 0.2;
+
+// Useful labels for the compression decisions
+pub const COMPRESSION_BENEFICIAL: &str = "compression_beneficial";
+pub const COMPRESSION_CODEC_UNSUPPORTED: &str = "compression_codec_unsupported";
+pub const COMPRESSION_DISABLED: &str = "compression_disabled";
+pub const COMPRESSION_NOT_BENEFICIAL: &str = "compression_not_beneficial";
+pub const COMPRESSION_NOT_REQUESTED: &str = "compression_not_requested";
+pub const COMPRESSION_PROBE: &str = "compression_probe";
+pub const COMPRESSION_SERVER_SATURATED: &str = "compression_server_saturated";
+
+// Useful label for the negotiated compression codecs (if no codec is supported)
+pub const NO_SUPPORTED_CODEC: &str = "no_supported_codec";
+
+/// The measured cost and benefit of compressing responses (for a
+/// single peer and request type). All values are moving averages.
+#[derive(Clone, Debug, Default)]
+struct CompressionStats {
+    num_samples: u64,
+    num_skipped_since_sample: u64,
+    raw_bytes: f64,
+    compressed_bytes: f64,
+    compression_secs: f64,
+}
+
+impl CompressionStats {
+    /// Updates the moving averages with the given sample
+    fn add_sample(&mut self, raw_bytes: u64, compressed_bytes: u64, compression_secs: f64) {
+        let weight = if self.num_samples == 0 {
+            1.0 // The first sample initializes the averages
+        } else {
+            MOVING_AVERAGE_WEIGHT
+        };
+        self.raw_bytes += weight * (raw_bytes as f64 - self.raw_bytes);
+        self.compressed_bytes += weight * (compressed_bytes as f64 - self.compressed_bytes);
+        self.compression_secs += weight * (compression_secs - self.compression_secs);
+        self.num_samples += 1;
+        self.num_skipped_since_sample = 0;
+    }
+
+    /// Returns true iff the time saved sending the compressed data (over a
+    /// link with the given bandwidth) outweighs the time spent compressing it.
+    fn is_compression_beneficial(&self, bandwidth_bytes_per_sec: u64) -> bool {
+        let saved_bytes = (self.raw_bytes - self.compressed_bytes).max(0.0);
+        let saved_secs = saved_bytes / (bandwidth_bytes_per_sec.max(1) as f64);
+        saved_secs > self.compression_secs
+    }
+}
+
+/// Decides whether or not to compress responses for clients that requested
+/// compression. Compression is skipped for peers where the measured CPU cost
+/// of compressing responses outweighs the bandwidth savings (e.g., peers on
+/// fast local links), or when the server is saturated with compressions.
+///
+/// The compression codec is negotiated per response: the server uses the first
+/// codec accepted by the client (in order of preference) that it supports.
+///
+/// Whether or not a response is compressed (and with which codec) is carried by
+/// the response itself (see `StorageServiceResponse::get_compression_codec()`).
+pub struct CompressionManager {
+    config: AdaptiveCompressionConfig,
+
+    // The compression stats for each peer and request type
+    compression_stats: Cache<(PeerNetworkId, String), CompressionStats>,
+
+    // The number of compressions currently in progress
+    num_active_compressions: AtomicU64,
+}
+
+impl CompressionManager {
+    pub fn new(config: AdaptiveCompressionConfig) -> Self {
+        Self {
+            config,
+            compression_stats: Cache::new(MAX_NUM_COMPRESSION_STATS),
+            num_active_compressions: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates a new storage service response for the given peer. The response is
+    /// only compressed if requested (with a codec accepted by the client), and if
+    /// compression is worthwhile.
+    pub fn create_response(
+        &self,
+        peer_network_id: &PeerNetworkId,
+        data_response: DataResponse,
+        accepted_codecs: &[CompressionCodec],
+    ) -> Result<StorageServiceResponse, Error> {
+        // Negotiate the compression codec with the client
+        let compression_codec = self.negotiate_compression_codec(peer_network_id, accepted_codecs);
+
+        // Decide whether or not to compress the response. The stats are
+        // tracked per codec, as the cost of compression differs greatly.
+        let stats_label = match compression_codec {
+            Some(compression_codec) => {
+                format!(
+                    "{}_{}",
+                    data_response.get_label(),
+                    compression_codec.get_label()
+                )
+            },
+            None => data_response.get_label().to_string(),
+        };
+        let stats_key = (*peer_network_id, stats_label);
+        let (perform_compression, decision) = if !accepted_codecs.is_empty()
+            && compression_codec.is_none()
+        {
+            (false, COMPRESSION_CODEC_UNSUPPORTED)
+        } else {
+            self.should_compress_response(peer_network_id, &stats_key, compression_codec.is_some())
+        };
+        metrics::increment_counter(
+            &metrics::COMPRESSION_DECISIONS,
+            peer_network_id.network_id(),
+            decision.into(),
+        );
+
+        // If compression is not being performed, create the raw response
+        let compression_codec = match compression_codec {
+            Some(compression_codec) if perform_compression => compression_codec,
+            _ => {
+                return StorageServiceResponse::new(data_response, false)
+                    .map_err(|error| error.into())
+            },
+        };
+
+        // Otherwise, compress the response and measure the cost and benefit
+        let raw_bytes = bcs::serialized_size(&data_response)
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+        self.num_active_compressions.fetch_add(1, Ordering::Relaxed);
+        let compression_start = Instant::now();
+        let storage_response = StorageServiceResponse::new_with_compression_codec(
+            data_response,
+            Some(compression_codec),
+        );
+        let compression_secs = compression_start.elapsed().as_secs_f64();
+        self.num_active_compressions.fetch_sub(1, Ordering::Relaxed);
+        let storage_response = storage_response?;
+
+        // Update the compression stats and metrics
+        if let StorageServiceResponse::CompressedResponse(_, compressed_data)
+        | StorageServiceResponse::CodecCompressedResponse(_, _, compressed_data) =
+            &storage_response
+        {
+            let compressed_bytes = compressed_data.len() as u64;
+            let mut stats = self.compression_stats.get(&stats_key).unwrap_or_default();
+            stats.add_sample(raw_bytes as u64, compressed_bytes, compression_secs);
+            self.compression_stats.insert(stats_key, stats);
+
+            metrics::observe_compression_ratio(
+                peer_network_id.network_id(),
+                raw_bytes as f64 / (compressed_bytes.max(1) as f64),
+            );
+        }
+
+        Ok(storage_response)
+    }
+
+    /// Returns the first compression codec accepted by the client (i.e., the
+    /// client's most preferred codec) that is also supported by the server.
+    /// If the client doesn't accept any codecs, None is returned.
+    fn negotiate_compression_codec(
+        &self,
+        peer_network_id: &PeerNetworkId,
+        accepted_codecs: &[CompressionCodec],
+    ) -> Option<CompressionCodec> {
+        // If the client doesn't accept compressed responses, there's nothing to negotiate
+        if accepted_codecs.is_empty() {
+            return None;
+        }
+
+        // Select the most preferred codec supported by the server
+        let compression_codec = accepted_codecs
+            .iter()
+            .copied()
+            .find(|compression_codec| self.is_codec_supported(compression_codec));
+
+        // Update the negotiated codec metrics
+        let codec_label = compression_codec
+            .map(|compression_codec| compression_codec.get_label())
+            .unwrap_or(NO_SUPPORTED_CODEC);
+        metrics::increment_counter(
+            &metrics::NEGOTIATED_COMPRESSION_CODECS,
+            peer_network_id.network_id(),
+            codec_label.into(),
+        );
+
+        compression_codec
+    }
+
+    /// Returns true iff the server supports compressing responses with the given codec
+    fn is_codec_supported(&self, compression_codec: &CompressionCodec) -> bool {
+        match compression_codec {
+            CompressionCodec::Lz4 => true,
+            CompressionCodec::Zstd => self.config.enable_zstd_compression,
+        }
+    }
+
+    /// Returns true iff the response should be compressed,
+    /// as well as a label for the decision (for metrics).
+    fn should_compress_response(
+        &self,
+        peer_network_id: &PeerNetworkId,
+        stats_key: &(PeerNetworkId, String),
+        use_compression: bool,
+    ) -> (bool, &'static str) {
+        // If the client didn't request compression, we don't compress
+        if !use_compression {
+            return (false, COMPRESSION_NOT_REQUESTED);
+        }
+
+        // If adaptive compression is disabled, we always compress
+        if !self.config.enable_adaptive_compression {
+            return (true, COMPRESSION_DISABLED);
+        }
+
+        // If the server is saturated with compressions, skip compression
+        if self.num_active_compressions.load(Ordering::Relaxed)
+            >= self.config.max_concurrent_compressions
+        {
+            return (false, COMPRESSION_SERVER_SATURATED);
+        }
+
+        // If we don't have enough recent samples, compress to measure the cost and benefit
+        let stats = self.compression_stats.get(stats_key).unwrap_or_default();
+        if stats.num_samples < self.config.min_compression_samples
+            || stats.num_skipped_since_sample >= self.config.compression_probe_interval
+        {
+            return (true, COMPRESSION_PROBE);
+        }
+
+        // Otherwise, only compress if it is beneficial for the peer's link
+        let bandwidth_bytes_per_sec = self.get_link_bandwidth(peer_network_id.network_id());
+        if stats.is_compression_beneficial(bandwidth_bytes_per_sec) {
+            (true, COMPRESSION_BENEFICIAL)
+        } else {
+            let mut stats = stats;
+            stats.num_skipped_since_sample += 1;
+            self.compression_stats.insert(stats_key.clone(), stats);
+            (false, COMPRESSION_NOT_BENEFICIAL)
+        }
+    }
+
+    /// Returns the estimated link bandwidth (bytes per second) for peers on the given network
+    fn get_link_bandwidth(&self, network_id: NetworkId) -> u64 {
+        match network_id {
+            NetworkId::Public => self.config.public_network_bandwidth_bytes_per_sec,
+            NetworkId::Validator => self.config.validator_network_bandwidth_bytes_per_sec,
+            NetworkId::Vfn => self.config.vfn_network_bandwidth_bytes_per_sec,
+        }
+    }
+}
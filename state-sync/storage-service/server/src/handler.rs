@@ -2,16 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    compression::CompressionManager,
     error::Error,
+    integrity::DataIntegrityChecker,
     logging::{LogEntry, LogSchema},
     metrics,
     metrics::{
-        increment_counter, LRU_CACHE_HIT, LRU_CACHE_PROBE, OPTIMISTIC_FETCH_ADD, SUBSCRIPTION_ADD,
+        increment_counter, LRU_CACHE_ENTRIES, LRU_CACHE_HIT, LRU_CACHE_INVALIDATION,
+        LRU_CACHE_PROBE, LRU_CACHE_WEIGHTED_BYTES, OPTIMISTIC_FETCH_ADD, SUBSCRIPTION_ADD,
         SUBSCRIPTION_FAILURE, SUBSCRIPTION_NEW_STREAM,
     },
     moderator::RequestModerator,
     network::ResponseSender,
     optimistic_fetch::OptimisticFetchRequest,
+    pre_serialization::PreSerializedResponses,
     storage::StorageReaderInterface,
     subscription::{SubscriptionRequest, SubscriptionStreamRequests},
     utils,
@@ -21,12 +25,15 @@ use aptos_logger::{debug, sample, sample::SampleRate, trace, warn};
 use aptos_network::protocols::wire::handshake::v1::ProtocolId;
 use aptos_storage_service_types::{
     requests::{
-        DataRequest, EpochEndingLedgerInfoRequest, StateValuesWithProofRequest,
-        StorageServiceRequest, TransactionOutputsWithProofRequest,
-        TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
+        DataRequest, EpochEndingLedgerInfoRequest, LatestLedgerInfoWithEpochProofRequest,
+        StateValuesWithIncrementalProofRequest, StateValuesWithProofRequest,
+        StorageServerSummaryUpdateRequest, StorageServiceRequest,
+        TransactionOutputsWithProofRequest, TransactionsOrOutputsWithProofRequest,
+        TransactionsWithProofRequest,
     },
     responses::{
-        DataResponse, ServerProtocolVersion, StorageServerSummary, StorageServiceResponse,
+        DataResponse, ServerProtocolVersion, StateValueChunkWithIncrementalProof,
+        StorageServerSummary, StorageServerSummaryDelta, StorageServiceResponse,
     },
     StorageServiceError,
 };
@@ -50,6 +57,9 @@ pub struct Handler<T> {
     cached_storage_server_summary: Arc<ArcSwap<StorageServerSummary>>,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
+    data_integrity_checker: Option<Arc<DataIntegrityChecker>>,
+    pre_serialized_responses: Option<Arc<PreSerializedResponses>>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -61,6 +71,7 @@ impl<T: StorageReaderInterface> Handler<T> {
         cached_storage_server_summary: Arc<ArcSwap<StorageServerSummary>>,
         optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
         lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+        compression_manager: Arc<CompressionManager>,
         request_moderator: Arc<RequestModerator>,
         storage: T,
         subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -70,6 +81,9 @@ impl<T: StorageReaderInterface> Handler<T> {
             cached_storage_server_summary,
             optimistic_fetches,
             lru_response_cache,
+            compression_manager,
+            data_integrity_checker: None,
+            pre_serialized_responses: None,
             request_moderator,
             storage,
             subscriptions,
@@ -77,6 +91,26 @@ impl<T: StorageReaderInterface> Handler<T> {
         }
     }
 
+    /// Sets the data integrity checker used to re-verify responses before
+    /// they are served. Without a checker, responses are not re-verified.
+    pub fn with_data_integrity_checker(
+        mut self,
+        data_integrity_checker: Arc<DataIntegrityChecker>,
+    ) -> Self {
+        self.data_integrity_checker = Some(data_integrity_checker);
+        self
+    }
+
+    /// Sets the tracker of pre-serialized responses, so that cache hits
+    /// on pre-serialized responses (and the CPU they save) are measured.
+    pub fn with_pre_serialized_responses(
+        mut self,
+        pre_serialized_responses: Arc<PreSerializedResponses>,
+    ) -> Self {
+        self.pre_serialized_responses = Some(pre_serialized_responses);
+        self
+    }
+
     /// Handles the given storage service request and responds to the
     /// request directly.
     pub fn process_request_and_respond(
@@ -202,13 +236,27 @@ impl<T: StorageReaderInterface> Handler<T> {
         match &request.data_request {
             DataRequest::GetServerProtocolVersion => {
                 let data_response = self.get_server_protocol_version();
-                StorageServiceResponse::new(data_response, request.use_compression)
-                    .map_err(|error| error.into())
+                self.compression_manager.create_response(
+                    peer_network_id,
+                    data_response,
+                    request.get_accepted_compression_codecs(),
+                )
             },
             DataRequest::GetStorageServerSummary => {
                 let data_response = self.get_storage_server_summary();
-                StorageServiceResponse::new(data_response, request.use_compression)
-                    .map_err(|error| error.into())
+                self.compression_manager.create_response(
+                    peer_network_id,
+                    data_response,
+                    request.get_accepted_compression_codecs(),
+                )
+            },
+            DataRequest::GetStorageServerSummaryUpdate(summary_update_request) => {
+                let data_response = self.get_storage_server_summary_update(summary_update_request);
+                self.compression_manager.create_response(
+                    peer_network_id,
+                    data_response,
+                    request.get_accepted_compression_codecs(),
+                )
             },
             _ => self.process_cachable_request(peer_network_id, request),
         }
@@ -379,24 +427,57 @@ impl<T: StorageReaderInterface> Handler<T> {
             LRU_CACHE_PROBE.into(),
         );
 
-        // Check if the response is already in the cache
+        // Check if the response is already in the cache (and still valid)
         if let Some(response) = self.lru_response_cache.get(request) {
+            if self.is_cached_response_valid(request, &response) {
+                increment_counter(
+                    &metrics::LRU_CACHE_EVENT,
+                    peer_network_id.network_id(),
+                    LRU_CACHE_HIT.into(),
+                );
+                if let Some(pre_serialized_responses) = &self.pre_serialized_responses {
+                    pre_serialized_responses
+                        .record_cache_hit(peer_network_id.network_id(), request);
+                }
+                return Ok(response.clone());
+            }
+
+            // The response is stale, so we remove it from the cache
             increment_counter(
                 &metrics::LRU_CACHE_EVENT,
                 peer_network_id.network_id(),
-                LRU_CACHE_HIT.into(),
+                LRU_CACHE_INVALIDATION.into(),
             );
-            return Ok(response.clone());
+            self.lru_response_cache.invalidate(request);
+        }
+
+        // Only regular data requests can negotiate the compression codec
+        if let DataRequest::GetWithAcceptedCompression(_, inner_data_request) =
+            &request.data_request
+        {
+            if !inner_data_request.is_compression_negotiable() {
+                return Err(Error::InvalidRequest(format!(
+                    "The request cannot negotiate the compression codec: {:?}",
+                    inner_data_request
+                )));
+            }
         }
 
-        // Otherwise, fetch the data from storage and time the operation
-        let fetch_data_response = || match &request.data_request {
+        // Otherwise, fetch the data from storage and time the operation. Requests
+        // that negotiate the compression codec are served using the inner request.
+        let fetch_data_response = || match request.data_request.get_inner_data_request() {
             DataRequest::GetStateValuesWithProof(request) => {
                 self.get_state_value_chunk_with_proof(request)
             },
+            DataRequest::GetStateValuesWithIncrementalProof(request) => {
+                self.get_state_value_chunk_with_incremental_proof(request)
+            },
             DataRequest::GetEpochEndingLedgerInfos(request) => {
                 self.get_epoch_ending_ledger_infos(request)
             },
+            DataRequest::GetLatestLedgerInfoWithEpochProof(request) => {
+                self.get_latest_ledger_info_with_epoch_proof(request)
+            },
             DataRequest::GetNumberOfStatesAtVersion(version) => {
                 self.get_number_of_states_at_version(*version)
             },
@@ -422,10 +503,27 @@ impl<T: StorageReaderInterface> Handler<T> {
             None,
         )?;
 
+        // Re-verify the data response (if integrity checks are enabled)
+        if let Some(data_integrity_checker) = &self.data_integrity_checker {
+            let storage_server_summary = self.cached_storage_server_summary.load();
+            data_integrity_checker.check_response(
+                peer_network_id,
+                request,
+                &data_response,
+                storage_server_summary
+                    .data_summary
+                    .synced_ledger_info
+                    .as_ref(),
+            )?;
+        }
+
         // Create the storage response and time the operation
         let create_storage_response = || {
-            StorageServiceResponse::new(data_response, request.use_compression)
-                .map_err(|error| error.into())
+            self.compression_manager.create_response(
+                peer_network_id,
+                data_response,
+                request.get_accepted_compression_codecs(),
+            )
         };
         let storage_response = utils::execute_and_time_duration(
             &metrics::STORAGE_RESPONSE_CREATION_LATENCY,
@@ -438,11 +536,53 @@ impl<T: StorageReaderInterface> Handler<T> {
         // Create and cache the storage response
         self.lru_response_cache
             .insert(request.clone(), storage_response.clone());
+        metrics::set_gauge(
+            &metrics::LRU_CACHE_SIZE,
+            LRU_CACHE_ENTRIES,
+            self.lru_response_cache.entry_count(),
+        );
+        metrics::set_gauge(
+            &metrics::LRU_CACHE_SIZE,
+            LRU_CACHE_WEIGHTED_BYTES,
+            self.lru_response_cache.weighted_size(),
+        );
 
         // Return the storage response
         Ok(storage_response)
     }
 
+    /// Returns true iff the cached response for the given request is still
+    /// valid. Most cachable requests specify the versions to serve the data
+    /// at, so their responses never change. However, the latest ledger info
+    /// changes as new versions are committed, so the cached response is only
+    /// valid until the highest synced version moves on.
+    fn is_cached_response_valid(
+        &self,
+        request: &StorageServiceRequest,
+        response: &StorageServiceResponse,
+    ) -> bool {
+        if !matches!(
+            request.data_request.get_inner_data_request(),
+            DataRequest::GetLatestLedgerInfoWithEpochProof(_)
+        ) {
+            return true;
+        }
+
+        // Compare the cached ledger info to the highest synced ledger info
+        let storage_server_summary = self.cached_storage_server_summary.load();
+        let highest_synced_version = storage_server_summary
+            .data_summary
+            .synced_ledger_info
+            .as_ref()
+            .map(|ledger_info| ledger_info.ledger_info().version());
+        match response.get_data_response() {
+            Ok(DataResponse::LatestLedgerInfoWithEpochProof((_, ledger_info))) => {
+                Some(ledger_info.ledger_info().version()) == highest_synced_version
+            },
+            _ => false,
+        }
+    }
+
     fn get_state_value_chunk_with_proof(
         &self,
         request: &StateValuesWithProofRequest,
@@ -458,6 +598,27 @@ impl<T: StorageReaderInterface> Handler<T> {
         ))
     }
 
+    fn get_state_value_chunk_with_incremental_proof(
+        &self,
+        request: &StateValuesWithIncrementalProofRequest,
+    ) -> aptos_storage_service_types::Result<DataResponse, Error> {
+        let state_value_chunk_with_proof = self.storage.get_state_value_chunk_with_proof(
+            request.version,
+            request.start_index,
+            request.end_index,
+        )?;
+
+        // Remove the right siblings that the client already knows
+        let state_value_chunk_with_incremental_proof = StateValueChunkWithIncrementalProof::new(
+            state_value_chunk_with_proof,
+            request.known_last_key,
+        );
+
+        Ok(DataResponse::StateValueChunkWithIncrementalProof(
+            state_value_chunk_with_incremental_proof,
+        ))
+    }
+
     fn get_epoch_ending_ledger_infos(
         &self,
         request: &EpochEndingLedgerInfoRequest,
@@ -469,6 +630,19 @@ impl<T: StorageReaderInterface> Handler<T> {
         Ok(DataResponse::EpochEndingLedgerInfos(epoch_change_proof))
     }
 
+    fn get_latest_ledger_info_with_epoch_proof(
+        &self,
+        request: &LatestLedgerInfoWithEpochProofRequest,
+    ) -> aptos_storage_service_types::Result<DataResponse, Error> {
+        let proof_with_ledger_info = self
+            .storage
+            .get_latest_ledger_info_with_epoch_proof(request.trusted_epoch)?;
+
+        Ok(DataResponse::LatestLedgerInfoWithEpochProof(
+            proof_with_ledger_info,
+        ))
+    }
+
     fn get_number_of_states_at_version(
         &self,
         version: Version,
@@ -490,7 +664,17 @@ impl<T: StorageReaderInterface> Handler<T> {
         DataResponse::StorageServerSummary(storage_server_summary.as_ref().clone())
     }
 
-    fn get_transaction_outputs_with_proof(
+    fn get_storage_server_summary_update(
+        &self,
+        request: &StorageServerSummaryUpdateRequest,
+    ) -> DataResponse {
+        let storage_server_summary = self.cached_storage_server_summary.load().clone();
+        let storage_server_summary_delta =
+            StorageServerSummaryDelta::new(request, &storage_server_summary);
+        DataResponse::StorageServerSummaryUpdate(storage_server_summary_delta)
+    }
+
+    pub(crate) fn get_transaction_outputs_with_proof(
         &self,
         request: &TransactionOutputsWithProofRequest,
     ) -> aptos_storage_service_types::Result<DataResponse, Error> {
@@ -505,7 +689,7 @@ impl<T: StorageReaderInterface> Handler<T> {
         ))
     }
 
-    fn get_transactions_with_proof(
+    pub(crate) fn get_transactions_with_proof(
         &self,
         request: &TransactionsWithProofRequest,
     ) -> aptos_storage_service_types::Result<DataResponse, Error> {
@@ -519,7 +703,7 @@ impl<T: StorageReaderInterface> Handler<T> {
         Ok(DataResponse::TransactionsWithProof(transactions_with_proof))
     }
 
-    fn get_transactions_or_outputs_with_proof(
+    pub(crate) fn get_transactions_or_outputs_with_proof(
         &self,
         request: &TransactionsOrOutputsWithProofRequest,
     ) -> aptos_storage_service_types::Result<DataResponse, Error> {
@@ -578,10 +762,7 @@ fn log_storage_response(
         Ok(storage_response) => {
             // We expect peers to be polling our storage server summary frequently,
             // so only log this response periodically.
-            if matches!(
-                storage_request.data_request,
-                DataRequest::GetStorageServerSummary
-            ) {
+            if storage_request.data_request.is_storage_summary_request() {
                 sample!(
                     SampleRate::Duration(Duration::from_secs(SUMMARY_LOG_FREQUENCY_SECS)),
                     {
@@ -0,0 +1,157 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    logging::{LogEntry, LogSchema},
+    metrics,
+};
+use aptos_config::{config::DataIntegrityCheckConfig, network_id::PeerNetworkId};
+use aptos_logger::error;
+use aptos_storage_service_types::{
+    requests::{DataRequest, StorageServiceRequest},
+    responses::DataResponse,
+};
+use aptos_types::{
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::Version,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Useful labels for the data integrity check results
+pub const CHECK_FAILED: &str = "check_failed";
+pub const CHECK_PASSED: &str = "check_passed";
+pub const CHECK_SKIPPED: &str = "check_skipped";
+
+/// Re-verifies the proofs of (sampled) data responses against the latest
+/// synced ledger info before they are served. This guards against silent
+/// local DB corruption propagating to syncing peers: responses that fail
+/// verification are never sent (or cached), and the failures are logged
+/// and counted (so that operators can be alerted).
+///
+/// Note: only transaction data can be re-verified (the state value proofs
+/// depend on the state root hashes, which are not part of the ledger info).
+pub struct DataIntegrityChecker {
+    config: DataIntegrityCheckConfig,
+
+    // The number of verifiable responses seen so far (used for sampling)
+    num_verifiable_responses: AtomicU64,
+}
+
+impl DataIntegrityChecker {
+    pub fn new(config: DataIntegrityCheckConfig) -> Self {
+        Self {
+            config,
+            num_verifiable_responses: AtomicU64::new(0),
+        }
+    }
+
+    /// Checks the data response for the given request (if the response is
+    /// sampled). Returns an error iff the response fails verification.
+    pub fn check_response(
+        &self,
+        peer_network_id: &PeerNetworkId,
+        request: &StorageServiceRequest,
+        data_response: &DataResponse,
+        synced_ledger_info: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        // Check if integrity checks are enabled
+        if !self.config.enable_integrity_checks {
+            return Ok(());
+        }
+
+        // Check if the response is verifiable and sampled
+        let proof_version = match get_proof_version(&request.data_request) {
+            Some(proof_version) => proof_version,
+            None => return Ok(()),
+        };
+        let response_index = self
+            .num_verifiable_responses
+            .fetch_add(1, Ordering::Relaxed);
+        if response_index % self.config.sampling_interval.max(1) != 0 {
+            return Ok(());
+        }
+
+        // The response can only be verified against a ledger info at the proof version
+        let ledger_info = match synced_ledger_info {
+            Some(ledger_info) if ledger_info.ledger_info().version() == proof_version => {
+                ledger_info.ledger_info()
+            },
+            _ => {
+                update_integrity_check_metrics(peer_network_id, CHECK_SKIPPED);
+                return Ok(());
+            },
+        };
+
+        // Verify the response
+        match verify_data_response(data_response, ledger_info) {
+            Ok(()) => {
+                update_integrity_check_metrics(peer_network_id, CHECK_PASSED);
+                Ok(())
+            },
+            Err(error) => {
+                update_integrity_check_metrics(peer_network_id, CHECK_FAILED);
+                let error = Error::UnexpectedErrorEncountered(format!(
+                    "The data integrity check failed! The local DB may be corrupt. Error: {}",
+                    error
+                ));
+                error!(LogSchema::new(LogEntry::DataIntegrityCheck)
+                    .error(&error)
+                    .peer_network_id(peer_network_id)
+                    .request(request));
+                Err(error)
+            },
+        }
+    }
+}
+
+/// Returns the proof version of the given data request (if the
+/// response can be re-verified against a ledger info).
+fn get_proof_version(data_request: &DataRequest) -> Option<Version> {
+    match data_request {
+        DataRequest::GetTransactionsWithProof(request) => Some(request.proof_version),
+        DataRequest::GetTransactionOutputsWithProof(request) => Some(request.proof_version),
+        DataRequest::GetTransactionsOrOutputsWithProof(request) => Some(request.proof_version),
+        DataRequest::GetWithAcceptedCompression(_, data_request) => get_proof_version(data_request),
+        _ => None,
+    }
+}
+
+/// Verifies the proofs of the given data response against the ledger info
+fn verify_data_response(
+    data_response: &DataResponse,
+    ledger_info: &LedgerInfo,
+) -> Result<(), Error> {
+    match data_response {
+        DataResponse::TransactionsWithProof(transaction_list) => {
+            transaction_list.verify(ledger_info, transaction_list.first_transaction_version)?;
+        },
+        DataResponse::TransactionOutputsWithProof(output_list) => {
+            output_list.verify(ledger_info, output_list.first_transaction_output_version)?;
+        },
+        DataResponse::TransactionsOrOutputsWithProof((transaction_list, output_list)) => {
+            if let Some(transaction_list) = transaction_list {
+                transaction_list.verify(ledger_info, transaction_list.first_transaction_version)?;
+            }
+            if let Some(output_list) = output_list {
+                output_list.verify(ledger_info, output_list.first_transaction_output_version)?;
+            }
+        },
+        data_response => {
+            return Err(Error::UnexpectedErrorEncountered(format!(
+                "Unable to verify the data response type: {:?}",
+                data_response.get_label()
+            )));
+        },
+    }
+    Ok(())
+}
+
+/// Updates the data integrity check metrics with the given result
+fn update_integrity_check_metrics(peer_network_id: &PeerNetworkId, result: &str) {
+    metrics::increment_counter(
+        &metrics::DATA_INTEGRITY_CHECKS,
+        peer_network_id.network_id(),
+        result.into(),
+    );
+}
@@ -5,8 +5,11 @@
 #![forbid(unsafe_code)]
 
 use crate::{
+    compression::CompressionManager,
+    integrity::DataIntegrityChecker,
     logging::{LogEntry, LogSchema},
     network::StorageServiceNetworkEvents,
+    pre_serialization::{PreSerializedResponses, ResponsePreSerializer},
     subscription::SubscriptionStreamRequests,
 };
 use aptos_channels::{aptos_channel, message_queues::QueueStyle};
@@ -15,11 +18,12 @@ use aptos_config::{
     network_id::PeerNetworkId,
 };
 use aptos_logger::prelude::*;
-use aptos_network::application::storage::PeersAndMetadata;
+use aptos_network::application::{interface::NetworkClient, storage::PeersAndMetadata};
 use aptos_storage_service_notifications::StorageServiceNotificationListener;
 use aptos_storage_service_types::{
     requests::StorageServiceRequest,
     responses::{ProtocolMetadata, StorageServerSummary, StorageServiceResponse},
+    StorageServiceMessage,
 };
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use arc_swap::ArcSwap;
@@ -35,13 +39,17 @@ use storage::StorageReaderInterface;
 use thiserror::Error;
 use tokio::runtime::Handle;
 
+mod commit_notification;
+mod compression;
 mod error;
 mod handler;
+mod integrity;
 mod logging;
 pub mod metrics;
 mod moderator;
 pub mod network;
 mod optimistic_fetch;
+mod pre_serialization;
 pub mod storage;
 mod subscription;
 mod utils;
@@ -55,6 +63,9 @@ mod tests;
 // the next X-1 will execute with an unchanged version (thus, becoming a no-op and wasting the CPU).
 const CACHED_SUMMARY_UPDATE_CHANNEL_SIZE: usize = 1;
 
+// The frequency to log commit notification broadcast errors (secs)
+const COMMIT_NOTIFICATION_LOG_FREQ_SECS: u64 = 5;
+
 /// The server-side actor for the storage service. Handles inbound storage
 /// service requests from clients.
 pub struct StorageServiceServer<T> {
@@ -78,12 +89,24 @@ pub struct StorageServiceServer<T> {
     // A set of active subscriptions for peers waiting for new data
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
 
+    // A manager that decides whether or not to compress responses
+    compression_manager: Arc<CompressionManager>,
+
+    // A checker that re-verifies (sampled) responses before they are served
+    data_integrity_checker: Arc<DataIntegrityChecker>,
+
+    // The responses pre-serialized for the newest data (held in the LRU cache)
+    pre_serialized_responses: Arc<PreSerializedResponses>,
+
     // A moderator for incoming peer requests
     request_moderator: Arc<RequestModerator>,
 
     // The listener for notifications from state sync
     storage_service_listener: Option<StorageServiceNotificationListener>,
 
+    // The network client used to send commit notifications to downstream peers
+    network_client: Option<NetworkClient<StorageServiceMessage>>,
+
     // The runtime on which to spawn tasks
     runtime: Handle,
 }
@@ -97,6 +120,7 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
         peers_and_metadata: Arc<PeersAndMetadata>,
         network_requests: StorageServiceNetworkEvents,
         storage_service_listener: StorageServiceNotificationListener,
+        network_client: Option<NetworkClient<StorageServiceMessage>>,
     ) -> Self {
         // Extract the individual component configs
         let aptos_data_client_config = config.aptos_data_client;
@@ -106,8 +130,15 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
         let cached_storage_server_summary =
             Arc::new(ArcSwap::from(Arc::new(StorageServerSummary::default())));
         let optimistic_fetches = Arc::new(DashMap::new());
-        let lru_response_cache = Cache::new(storage_service_config.max_lru_cache_size);
+        let lru_response_cache = utils::create_lru_response_cache(&storage_service_config);
         let subscriptions = Arc::new(DashMap::new());
+        let compression_manager = Arc::new(CompressionManager::new(
+            storage_service_config.adaptive_compression,
+        ));
+        let data_integrity_checker = Arc::new(DataIntegrityChecker::new(
+            storage_service_config.data_integrity_check,
+        ));
+        let pre_serialized_responses = Arc::new(PreSerializedResponses::default());
         let request_moderator = Arc::new(RequestModerator::new(
             aptos_data_client_config,
             cached_storage_server_summary.clone(),
@@ -126,8 +157,12 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
             lru_response_cache,
             optimistic_fetches,
             subscriptions,
+            compression_manager,
+            data_integrity_checker,
+            pre_serialized_responses,
             request_moderator,
             storage_service_listener,
+            network_client,
             runtime,
         }
     }
@@ -141,14 +176,50 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
         let (cache_update_notifier_subscription, cache_update_listener_subscription) =
             aptos_channel::new(QueueStyle::LIFO, CACHED_SUMMARY_UPDATE_CHANNEL_SIZE, None);
 
-        // Spawn the refresher for the storage summary cache
-        let cache_update_notifiers = vec![
+        // Create a channel to notify the commit notification broadcaster (if enabled)
+        let mut cache_update_notifiers = vec![
             cache_update_notifier_optimistic_fetch.clone(),
             cache_update_notifier_subscription.clone(),
         ];
+        let mut cache_update_listener_commit_notification = None;
+        if self.storage_service_config.enable_commit_notifications && self.network_client.is_some()
+        {
+            let (cache_update_notifier, cache_update_listener) =
+                aptos_channel::new(QueueStyle::LIFO, CACHED_SUMMARY_UPDATE_CHANNEL_SIZE, None);
+            cache_update_notifiers.push(cache_update_notifier);
+            cache_update_listener_commit_notification = Some(cache_update_listener);
+        }
+
+        // Create a channel to notify the response pre-serializer (if enabled).
+        // Note: pre-serialized responses are not re-verified by the data
+        // integrity checker, so pre-serialization is skipped if checks are enabled.
+        let mut cache_update_listener_pre_serialization = None;
+        let config = self.storage_service_config;
+        if config.response_pre_serialization.enable_pre_serialization
+            && !config.data_integrity_check.enable_integrity_checks
+        {
+            let (cache_update_notifier, cache_update_listener) =
+                aptos_channel::new(QueueStyle::LIFO, CACHED_SUMMARY_UPDATE_CHANNEL_SIZE, None);
+            cache_update_notifiers.push(cache_update_notifier);
+            cache_update_listener_pre_serialization = Some(cache_update_listener);
+        }
+
+        // Spawn the refresher for the storage summary cache
         self.spawn_storage_summary_refresher(cache_update_notifiers)
             .await;
 
+        // Spawn the commit notification broadcaster (if enabled)
+        if let Some(cache_update_listener) = cache_update_listener_commit_notification {
+            self.spawn_commit_notification_broadcaster(cache_update_listener)
+                .await;
+        }
+
+        // Spawn the response pre-serializer (if enabled)
+        if let Some(cache_update_listener) = cache_update_listener_pre_serialization {
+            self.spawn_response_pre_serializer(cache_update_listener)
+                .await;
+        }
+
         // Spawn the optimistic fetch handler
         self.spawn_optimistic_fetch_handler(cache_update_listener_optimistic_fetch)
             .await;
@@ -218,6 +289,123 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
         });
     }
 
+    /// Spawns a non-terminating task that sends commit notifications to
+    /// downstream peers whenever the highest synced ledger info changes.
+    async fn spawn_commit_notification_broadcaster(
+        &mut self,
+        mut cached_summary_update_listener: aptos_channel::Receiver<
+            (),
+            CachedSummaryUpdateNotification,
+        >,
+    ) {
+        // Clone all required components for the task
+        let cached_storage_server_summary = self.cached_storage_server_summary.clone();
+        let network_client = self
+            .network_client
+            .clone()
+            .expect("The network client must be present!");
+
+        // Spawn the task
+        self.runtime.spawn(async move {
+            let mut highest_notified_version = None;
+            while let Some(notification) = cached_summary_update_listener.next().await {
+                trace!(
+                    LogSchema::new(LogEntry::ReceivedCacheUpdateNotification).message(&format!(
+                        "Received cache update notification for commit broadcaster! \
+                     Highest synced version: {:?}",
+                        notification.highest_synced_version
+                    ))
+                );
+
+                // Only notify peers if the highest synced ledger info has advanced
+                let storage_server_summary = cached_storage_server_summary.load().clone();
+                let data_summary = &storage_server_summary.data_summary;
+                let Some(ledger_info) = data_summary.synced_ledger_info.clone() else {
+                    continue;
+                };
+                let synced_version = ledger_info.ledger_info().version();
+                if highest_notified_version >= Some(synced_version) {
+                    continue;
+                }
+                highest_notified_version = Some(synced_version);
+
+                // Send the commit notification to all downstream peers
+                if let Err(error) =
+                    commit_notification::broadcast_commit_notification(&network_client, ledger_info)
+                {
+                    sample!(
+                        SampleRate::Duration(Duration::from_secs(
+                            COMMIT_NOTIFICATION_LOG_FREQ_SECS
+                        )),
+                        warn!(LogSchema::new(LogEntry::CommitNotificationBroadcast)
+                            .error(&error)
+                            .message("Failed to broadcast the commit notification!"))
+                    );
+                }
+            }
+        });
+    }
+
+    /// Spawns a non-terminating task that pre-serializes the responses
+    /// for the newest data whenever the highest synced version changes.
+    async fn spawn_response_pre_serializer(
+        &mut self,
+        mut cached_summary_update_listener: aptos_channel::Receiver<
+            (),
+            CachedSummaryUpdateNotification,
+        >,
+    ) {
+        // Create the response pre-serializer
+        let cached_storage_server_summary = self.cached_storage_server_summary.clone();
+        let handler = Handler::new(
+            cached_storage_server_summary.clone(),
+            self.optimistic_fetches.clone(),
+            self.lru_response_cache.clone(),
+            self.compression_manager.clone(),
+            self.request_moderator.clone(),
+            self.storage.clone(),
+            self.subscriptions.clone(),
+            self.time_service.clone(),
+        );
+        let response_pre_serializer = Arc::new(ResponsePreSerializer::new(
+            self.storage_service_config,
+            handler,
+            self.lru_response_cache.clone(),
+            self.pre_serialized_responses.clone(),
+        ));
+
+        // Spawn the task
+        let runtime = self.runtime.clone();
+        self.runtime.spawn(async move {
+            while let Some(notification) = cached_summary_update_listener.next().await {
+                trace!(
+                    LogSchema::new(LogEntry::ReceivedCacheUpdateNotification).message(&format!(
+                        "Received cache update notification for response pre-serializer! \
+                     Highest synced version: {:?}",
+                        notification.highest_synced_version
+                    ))
+                );
+
+                // Pre-serialize the responses on the blocking thread pool (serialization
+                // is CPU-bound). We wait for each update to complete before handling the
+                // next, so that the responses are always created in version order.
+                let response_pre_serializer = response_pre_serializer.clone();
+                let storage_server_summary = cached_storage_server_summary.load().clone();
+                let result = runtime
+                    .spawn_blocking(move || {
+                        response_pre_serializer
+                            .handle_storage_summary_update(&storage_server_summary)
+                    })
+                    .await;
+                if let Err(error) = result {
+                    error!(LogSchema::new(LogEntry::ResponsePreSerialization)
+                        .error(&Error::UnexpectedErrorEncountered(error.to_string()))
+                        .message("Failed to pre-serialize the responses for the newest data!"));
+                }
+            }
+        });
+    }
+
     /// Spawns a non-terminating task that handles optimistic fetches
     async fn spawn_optimistic_fetch_handler(
         &mut self,
@@ -232,6 +420,7 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
         let config = self.storage_service_config;
         let optimistic_fetches = self.optimistic_fetches.clone();
         let lru_response_cache = self.lru_response_cache.clone();
+        let compression_manager = self.compression_manager.clone();
         let request_moderator = self.request_moderator.clone();
         let storage = self.storage.clone();
         let subscriptions = self.subscriptions.clone();
@@ -256,6 +445,7 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
                                 config,
                                 optimistic_fetches.clone(),
                                 lru_response_cache.clone(),
+                                compression_manager.clone(),
                                 request_moderator.clone(),
                                 storage.clone(),
                                 subscriptions.clone(),
@@ -274,6 +464,7 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
                                 config,
                                 optimistic_fetches.clone(),
                                 lru_response_cache.clone(),
+                                compression_manager.clone(),
                                 request_moderator.clone(),
                                 storage.clone(),
                                 subscriptions.clone(),
@@ -299,6 +490,7 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
         let config = self.storage_service_config;
         let optimistic_fetches = self.optimistic_fetches.clone();
         let lru_response_cache = self.lru_response_cache.clone();
+        let compression_manager = self.compression_manager.clone();
         let request_moderator = self.request_moderator.clone();
         let storage = self.storage.clone();
         let subscriptions = self.subscriptions.clone();
@@ -323,6 +515,7 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
                                 config,
                                 optimistic_fetches.clone(),
                                 lru_response_cache.clone(),
+                                compression_manager.clone(),
                                 request_moderator.clone(),
                                 storage.clone(),
                                 subscriptions.clone(),
@@ -341,6 +534,7 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
                                 config,
                                 optimistic_fetches.clone(),
                                 lru_response_cache.clone(),
+                                compression_manager.clone(),
                                 request_moderator.clone(),
                                 storage.clone(),
                                 subscriptions.clone(),
@@ -397,6 +591,9 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
             let optimistic_fetches = self.optimistic_fetches.clone();
             let subscriptions = self.subscriptions.clone();
             let lru_response_cache = self.lru_response_cache.clone();
+            let compression_manager = self.compression_manager.clone();
+            let data_integrity_checker = self.data_integrity_checker.clone();
+            let pre_serialized_responses = self.pre_serialized_responses.clone();
             let request_moderator = self.request_moderator.clone();
             let time_service = self.time_service.clone();
             self.runtime.spawn_blocking(move || {
@@ -404,11 +601,14 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
                     cached_storage_server_summary,
                     optimistic_fetches,
                     lru_response_cache,
+                    compression_manager,
                     request_moderator,
                     storage,
                     subscriptions,
                     time_service,
                 )
+                .with_data_integrity_checker(data_integrity_checker)
+                .with_pre_serialized_responses(pre_serialized_responses)
                 .process_request_and_respond(
                     config,
                     network_request.peer_network_id,
@@ -451,6 +651,7 @@ async fn handle_active_optimistic_fetches<T: StorageReaderInterface>(
     config: StorageServiceConfig,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -462,6 +663,7 @@ async fn handle_active_optimistic_fetches<T: StorageReaderInterface>(
         config,
         optimistic_fetches,
         lru_response_cache,
+        compression_manager,
         request_moderator,
         storage,
         subscriptions,
@@ -483,6 +685,7 @@ async fn handle_active_subscriptions<T: StorageReaderInterface>(
     config: StorageServiceConfig,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -494,6 +697,7 @@ async fn handle_active_subscriptions<T: StorageReaderInterface>(
         config,
         optimistic_fetches,
         lru_response_cache,
+        compression_manager,
         request_moderator,
         storage,
         subscriptions,
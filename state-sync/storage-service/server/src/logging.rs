@@ -36,14 +36,18 @@ impl<'a> LogSchema<'a> {
 #[derive(Clone, Copy, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum LogEntry {
+    CommitNotificationBroadcast,
+    DataIntegrityCheck,
     OptimisticFetchRefresh,
     OptimisticFetchRequest,
     OptimisticFetchResponse,
+    PeerCommitNotification,
     ReceivedCacheUpdateNotification,
     ReceivedCommitNotification,
     ReceivedStorageRequest,
     RequestModeratorIgnoredPeer,
     RequestModeratorRefresh,
+    ResponsePreSerialization,
     SentStorageResponse,
     StorageServiceError,
     StorageSummaryRefresh,
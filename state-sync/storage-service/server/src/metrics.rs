@@ -11,8 +11,12 @@ use once_cell::sync::Lazy;
 use std::time::Instant;
 
 /// Useful metric constants for the storage service
+pub const LRU_CACHE_ENTRIES: &str = "lru_cache_entries";
 pub const LRU_CACHE_HIT: &str = "lru_cache_hit";
+pub const LRU_CACHE_INVALIDATION: &str = "lru_cache_invalidation";
+pub const LRU_CACHE_PRE_SERIALIZED_HIT: &str = "lru_cache_pre_serialized_hit";
 pub const LRU_CACHE_PROBE: &str = "lru_cache_probe";
+pub const LRU_CACHE_WEIGHTED_BYTES: &str = "lru_cache_weighted_bytes";
 pub const OPTIMISTIC_FETCH_ADD: &str = "optimistic_fetch_add";
 pub const OPTIMISTIC_FETCH_EXPIRE: &str = "optimistic_fetch_expire";
 pub const RESULT_SUCCESS: &str = "success";
@@ -28,6 +32,62 @@ const REQUEST_PROCESSING_LATENCY_BUCKETS_SECS: &[f64] = &[
     60.0, 120.0, 180.0, 240.0, 300.0,
 ];
 
+// Buckets for the achieved response compression ratios
+const COMPRESSION_RATIO_BUCKETS: &[f64] = &[
+    1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0, 4.0, 5.0, 7.5, 10.0, 20.0,
+];
+
+/// Counter for commit notifications sent to downstream peers
+pub static COMMIT_NOTIFICATIONS_SENT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_storage_service_server_commit_notifications_sent",
+        "Counters for commit notifications sent to downstream peers",
+        &["network_id"]
+    )
+    .unwrap()
+});
+
+/// Counter for the decisions made when compressing responses
+pub static COMPRESSION_DECISIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_storage_service_server_compression_decisions",
+        "Counters for the decisions made when compressing responses",
+        &["network_id", "decision"]
+    )
+    .unwrap()
+});
+
+/// Counter for the compression codecs negotiated with clients
+pub static NEGOTIATED_COMPRESSION_CODECS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_storage_service_server_negotiated_compression_codecs",
+        "Counters for the compression codecs negotiated with clients",
+        &["network_id", "codec"]
+    )
+    .unwrap()
+});
+
+/// The compression ratios achieved when compressing responses
+pub static COMPRESSION_RATIOS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_storage_service_server_compression_ratios",
+        "The compression ratios achieved when compressing responses",
+        &["network_id"],
+        COMPRESSION_RATIO_BUCKETS.to_vec(),
+    )
+    .unwrap()
+});
+
+/// Counter for the results of the data integrity checks on served responses
+pub static DATA_INTEGRITY_CHECKS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_storage_service_server_data_integrity_checks",
+        "Counters for the results of the data integrity checks on served responses",
+        &["network_id", "result"]
+    )
+    .unwrap()
+});
+
 /// Gauge for tracking the number of actively ignored peers
 pub static IGNORED_PEER_COUNT: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
@@ -48,6 +108,16 @@ pub static LRU_CACHE_EVENT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Gauge for tracking the size of the lru cache in the storage service (server-side)
+pub static LRU_CACHE_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_storage_service_server_lru_cache_size",
+        "Gauge for tracking the size of the lru cache in the storage server",
+        &["size_type"]
+    )
+    .unwrap()
+});
+
 /// Counter for the number of times a storage response overflowed the network
 /// frame limit size and had to be retried.
 pub static NETWORK_FRAME_OVERFLOW: Lazy<IntCounterVec> = Lazy::new(|| {
@@ -90,6 +160,26 @@ pub static OPTIMISTIC_FETCH_LATENCIES: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter for the events of responses pre-serialized for the newest data
+pub static PRE_SERIALIZATION_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_storage_service_server_pre_serialization_events",
+        "Counters for the events of responses pre-serialized for the newest data",
+        &["event"]
+    )
+    .unwrap()
+});
+
+/// Counter for the serialization CPU time (usecs) saved by serving pre-serialized responses
+pub static PRE_SERIALIZATION_SAVED_USECS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_storage_service_server_pre_serialization_saved_usecs",
+        "Counters for the serialization CPU time (usecs) saved by serving pre-serialized responses",
+        &["network_id"]
+    )
+    .unwrap()
+});
+
 /// Counter for pending network events to the storage service (server-side)
 pub static PENDING_STORAGE_SERVER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -213,6 +303,32 @@ pub static SUBSCRIPTION_LATENCIES: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Increments the commit notifications sent counter for the given network
+pub fn increment_commit_notifications_sent(network_id: NetworkId) {
+    COMMIT_NOTIFICATIONS_SENT
+        .with_label_values(&[network_id.as_str()])
+        .inc()
+}
+
+/// Observes the compression ratio achieved for a response sent on the given network
+pub fn observe_compression_ratio(network_id: NetworkId, compression_ratio: f64) {
+    COMPRESSION_RATIOS
+        .with_label_values(&[network_id.as_str()])
+        .observe(compression_ratio)
+}
+
+/// Increments the pre-serialization event counter for the given event
+pub fn increment_pre_serialization_event(event: &str) {
+    PRE_SERIALIZATION_EVENTS.with_label_values(&[event]).inc()
+}
+
+/// Increments the serialization CPU time saved by serving a pre-serialized response
+pub fn increment_pre_serialization_saved_usecs(network_id: NetworkId, saved_usecs: u64) {
+    PRE_SERIALIZATION_SAVED_USECS
+        .with_label_values(&[network_id.as_str()])
+        .inc_by(saved_usecs)
+}
+
 /// Increments the network frame overflow counter for the given response
 pub fn increment_network_frame_overflow(response_type: &str) {
     NETWORK_FRAME_OVERFLOW
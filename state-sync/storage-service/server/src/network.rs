@@ -2,12 +2,15 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::logging::{LogEntry, LogSchema};
 use aptos_config::network_id::{NetworkId, PeerNetworkId};
+use aptos_logger::{sample, sample::SampleRate, warn};
 use aptos_network::{
     application::interface::NetworkServiceEvents,
     protocols::network::{Event, RpcError},
     ProtocolId,
 };
+use aptos_storage_service_notifications::PeerCommitNotifier;
 use aptos_storage_service_types::{
     requests::StorageServiceRequest, responses::StorageServiceResponse, Result,
     StorageServiceMessage,
@@ -21,8 +24,12 @@ use futures::{
 use std::{
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
+// The frequency to log peer commit notification errors (secs)
+const PEER_COMMIT_NOTIFICATION_LOG_FREQ_SECS: u64 = 5;
+
 /// A simple wrapper for each network request
 pub struct NetworkRequest {
     pub peer_network_id: PeerNetworkId,
@@ -32,13 +39,17 @@ pub struct NetworkRequest {
 }
 
 /// A stream of requests from network. Each request also comes with a callback to
-/// send the response.
+/// send the response. Commit notifications sent by peers (via direct send) are
+/// forwarded to state sync (if a peer commit notifier is provided).
 pub struct StorageServiceNetworkEvents {
     network_request_stream: BoxStream<'static, NetworkRequest>,
 }
 
 impl StorageServiceNetworkEvents {
-    pub fn new(network_service_events: NetworkServiceEvents<StorageServiceMessage>) -> Self {
+    pub fn new(
+        network_service_events: NetworkServiceEvents<StorageServiceMessage>,
+        peer_commit_notifier: Option<PeerCommitNotifier>,
+    ) -> Self {
         // Transform the event streams to also include the network ID
         let network_events: Vec<_> = network_service_events
             .into_network_and_events()
@@ -49,8 +60,12 @@ impl StorageServiceNetworkEvents {
 
         // Transform each event to a network request
         let network_request_stream = network_events
-            .filter_map(|(network_id, event)| {
-                future::ready(Self::event_to_request(network_id, event))
+            .filter_map(move |(network_id, event)| {
+                future::ready(Self::event_to_request(
+                    network_id,
+                    event,
+                    peer_commit_notifier.as_ref(),
+                ))
             })
             .boxed();
 
@@ -59,10 +74,11 @@ impl StorageServiceNetworkEvents {
         }
     }
 
-    /// Filters out everything except Rpc requests
+    /// Filters out everything except Rpc requests (and forwards commit notifications)
     fn event_to_request(
         network_id: NetworkId,
         event: Event<StorageServiceMessage>,
+        peer_commit_notifier: Option<&PeerCommitNotifier>,
     ) -> Option<NetworkRequest> {
         match event {
             Event::RpcRequest(
@@ -80,7 +96,28 @@ impl StorageServiceNetworkEvents {
                     response_sender,
                 })
             },
-            _ => None, // We don't use direct send and don't care about connection events
+            Event::Message(peer_id, StorageServiceMessage::CommitNotification(ledger_info)) => {
+                if let Some(peer_commit_notifier) = peer_commit_notifier {
+                    let peer_network_id = PeerNetworkId::new(network_id, peer_id);
+                    if let Err(error) =
+                        peer_commit_notifier.notify_peer_commit(peer_network_id, ledger_info)
+                    {
+                        sample!(
+                            SampleRate::Duration(Duration::from_secs(
+                                PEER_COMMIT_NOTIFICATION_LOG_FREQ_SECS
+                            )),
+                            warn!(LogSchema::new(LogEntry::PeerCommitNotification)
+                                .peer_network_id(&peer_network_id)
+                                .message(&format!(
+                                    "Failed to forward the peer commit notification! Error: {:?}",
+                                    error
+                                )))
+                        );
+                    }
+                }
+                None
+            },
+            _ => None, // We don't care about other direct sends or connection events
         }
     }
 }
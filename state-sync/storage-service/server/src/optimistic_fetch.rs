@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    compression::CompressionManager,
     error::Error,
     metrics,
     metrics::{increment_counter, OPTIMISTIC_FETCH_EXPIRE},
@@ -188,6 +189,7 @@ pub(crate) async fn handle_active_optimistic_fetches<T: StorageReaderInterface>(
     config: StorageServiceConfig,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -203,6 +205,7 @@ pub(crate) async fn handle_active_optimistic_fetches<T: StorageReaderInterface>(
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage.clone(),
         subscriptions.clone(),
@@ -217,6 +220,7 @@ pub(crate) async fn handle_active_optimistic_fetches<T: StorageReaderInterface>(
         config,
         optimistic_fetches,
         lru_response_cache,
+        compression_manager,
         request_moderator,
         storage,
         subscriptions,
@@ -236,6 +240,7 @@ pub(crate) async fn handle_ready_optimistic_fetches<T: StorageReaderInterface>(
     config: StorageServiceConfig,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -258,6 +263,7 @@ pub(crate) async fn handle_ready_optimistic_fetches<T: StorageReaderInterface>(
             let cached_storage_server_summary = cached_storage_server_summary.clone();
             let optimistic_fetches = optimistic_fetches.clone();
             let lru_response_cache = lru_response_cache.clone();
+            let compression_manager = compression_manager.clone();
             let request_moderator = request_moderator.clone();
             let storage = storage.clone();
             let subscriptions = subscriptions.clone();
@@ -281,6 +287,7 @@ pub(crate) async fn handle_ready_optimistic_fetches<T: StorageReaderInterface>(
                         optimistic_fetches.clone(),
                         subscriptions.clone(),
                         lru_response_cache.clone(),
+                        compression_manager.clone(),
                         request_moderator.clone(),
                         storage.clone(),
                         time_service.clone(),
@@ -317,6 +324,7 @@ pub(crate) async fn get_peers_with_ready_optimistic_fetches<T: StorageReaderInte
     cached_storage_server_summary: Arc<ArcSwap<StorageServerSummary>>,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -341,6 +349,7 @@ pub(crate) async fn get_peers_with_ready_optimistic_fetches<T: StorageReaderInte
         optimistic_fetches.clone(),
         subscriptions,
         lru_response_cache,
+        compression_manager,
         request_moderator,
         storage,
         time_service,
@@ -370,6 +379,7 @@ async fn identify_expired_invalid_and_ready_fetches<T: StorageReaderInterface>(
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     time_service: TimeService,
@@ -411,6 +421,7 @@ async fn identify_expired_invalid_and_ready_fetches<T: StorageReaderInterface>(
             optimistic_fetches,
             subscriptions,
             lru_response_cache,
+            compression_manager,
             request_moderator,
             storage,
             time_service,
@@ -435,6 +446,7 @@ async fn identify_ready_and_invalid_optimistic_fetches<T: StorageReaderInterface
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     time_service: TimeService,
@@ -464,6 +476,7 @@ async fn identify_ready_and_invalid_optimistic_fetches<T: StorageReaderInterface
         let optimistic_fetches = optimistic_fetches.clone();
         let subscriptions = subscriptions.clone();
         let lru_response_cache = lru_response_cache.clone();
+        let compression_manager = compression_manager.clone();
         let request_moderator = request_moderator.clone();
         let storage = storage.clone();
         let time_service = time_service.clone();
@@ -484,6 +497,7 @@ async fn identify_ready_and_invalid_optimistic_fetches<T: StorageReaderInterface
                         subscriptions.clone(),
                         highest_known_epoch,
                         lru_response_cache.clone(),
+                        compression_manager.clone(),
                         request_moderator.clone(),
                         &peer_network_id,
                         storage.clone(),
@@ -0,0 +1,334 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    handler::Handler,
+    logging::{LogEntry, LogSchema},
+    metrics,
+    storage::StorageReaderInterface,
+};
+use aptos_config::{
+    config::{ResponsePreSerializationConfig, StorageServiceConfig},
+    network_id::NetworkId,
+};
+use aptos_infallible::Mutex;
+use aptos_logger::{sample, sample::SampleRate, warn};
+use aptos_storage_service_types::{
+    requests::{
+        DataRequest, StorageServiceRequest, TransactionOutputsWithProofRequest,
+        TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
+    },
+    responses::{DataSummary, StorageServerSummary, StorageServiceResponse},
+};
+use aptos_types::transaction::Version;
+use dashmap::DashMap;
+use mini_moka::sync::Cache;
+use std::{
+    cmp::min,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+// The frequency to log pre-serialization errors (secs)
+const PRE_SERIALIZATION_LOG_FREQ_SECS: u64 = 5;
+
+// Useful labels for the pre-serialization events
+pub const PRE_SERIALIZATION_CREATED: &str = "created";
+pub const PRE_SERIALIZATION_EXPIRED: &str = "expired";
+pub const PRE_SERIALIZATION_FAILED: &str = "failed";
+pub const PRE_SERIALIZATION_PRUNED: &str = "pruned";
+pub const PRE_SERIALIZATION_SKIPPED: &str = "skipped";
+
+/// A response that was pre-serialized (and inserted into the LRU response cache)
+#[derive(Clone, Copy, Debug)]
+struct PreSerializedResponse {
+    start_version: Version,   // The first version of the data in the response
+    serialization_usecs: u64, // The time taken to serialize (and compress) the response
+    served: bool,             // Whether the response has been served (from the cache)
+}
+
+/// Tracks the responses that were pre-serialized for the newest data, so
+/// that the serialization CPU saved by serving them can be measured, and
+/// so that they can be invalidated once the data is no longer hot (or has
+/// been pruned).
+#[derive(Default)]
+pub struct PreSerializedResponses {
+    // The highest synced version for which responses have been pre-serialized
+    highest_pre_serialized_version: Mutex<Option<Version>>,
+
+    // The pre-serialized responses, keyed by the request they were created for
+    responses: DashMap<StorageServiceRequest, PreSerializedResponse>,
+}
+
+impl PreSerializedResponses {
+    /// Records a cache hit for the given request (if the cached
+    /// response was pre-serialized) on the given network.
+    ///
+    /// Only the first hit on each pre-serialized response is counted: without
+    /// pre-serialization, the first request would have missed the cache (and
+    /// serialized the response), but all later requests would have hit anyway.
+    pub fn record_cache_hit(&self, network_id: NetworkId, request: &StorageServiceRequest) {
+        let Some(mut response) = self.responses.get_mut(request) else {
+            return;
+        };
+        if response.served {
+            return;
+        }
+        response.served = true;
+
+        metrics::increment_counter(
+            &metrics::LRU_CACHE_EVENT,
+            network_id,
+            metrics::LRU_CACHE_PRE_SERIALIZED_HIT.into(),
+        );
+        metrics::increment_pre_serialization_saved_usecs(network_id, response.serialization_usecs);
+    }
+
+    /// Updates the highest pre-serialized version to the given target version,
+    /// and returns the previous highest version (i.e., the version that peers
+    /// following the chain are expected to know). Returns None if there is no
+    /// new data to pre-serialize (or no previous version to serve it from).
+    fn update_highest_pre_serialized_version(&self, target_version: Version) -> Option<Version> {
+        let mut highest_pre_serialized_version = self.highest_pre_serialized_version.lock();
+        match *highest_pre_serialized_version {
+            Some(known_version) if known_version >= target_version => None,
+            known_version => {
+                *highest_pre_serialized_version = Some(target_version);
+                known_version
+            },
+        }
+    }
+
+    /// Removes the pre-serialized responses that are no longer hot (i.e., they
+    /// start before the lowest hot version), and invalidates the cached responses
+    /// for data that has since been pruned (i.e., it starts before the lowest
+    /// available version).
+    fn remove_stale_responses(
+        &self,
+        lowest_hot_version: Version,
+        lowest_available_version: Version,
+        lru_response_cache: &Cache<StorageServiceRequest, StorageServiceResponse>,
+    ) {
+        self.responses.retain(|request, response| {
+            if response.start_version < lowest_available_version {
+                lru_response_cache.invalidate(request);
+                metrics::increment_pre_serialization_event(PRE_SERIALIZATION_PRUNED);
+                false
+            } else if response.start_version < lowest_hot_version {
+                metrics::increment_pre_serialization_event(PRE_SERIALIZATION_EXPIRED);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    #[cfg(test)]
+    /// Returns the number of tracked pre-serialized responses
+    pub fn num_responses(&self) -> usize {
+        self.responses.len()
+    }
+
+    #[cfg(test)]
+    /// Returns the number of pre-serialized responses that have been served
+    pub fn num_served_responses(&self) -> usize {
+        self.responses
+            .iter()
+            .filter(|response| response.served)
+            .count()
+    }
+}
+
+/// Serializes (and compresses) the responses for newly committed transactions
+/// and outputs once, in the background, and inserts them into the LRU response
+/// cache. The responses are created for the requests that peers following the
+/// chain (i.e., peers that already synced the previous highest version) will
+/// make for the new data, so that all of them are served the cached bytes.
+pub struct ResponsePreSerializer<T> {
+    config: ResponsePreSerializationConfig,
+    storage_service_config: StorageServiceConfig,
+    handler: Handler<T>,
+    lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    pre_serialized_responses: Arc<PreSerializedResponses>,
+}
+
+impl<T: StorageReaderInterface> ResponsePreSerializer<T> {
+    pub fn new(
+        storage_service_config: StorageServiceConfig,
+        handler: Handler<T>,
+        lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+        pre_serialized_responses: Arc<PreSerializedResponses>,
+    ) -> Self {
+        Self {
+            config: storage_service_config.response_pre_serialization,
+            storage_service_config,
+            handler,
+            lru_response_cache,
+            pre_serialized_responses,
+        }
+    }
+
+    /// Handles an update to the storage server summary: the stale responses
+    /// are removed, and the responses for the newly committed data (if any)
+    /// are pre-serialized.
+    pub fn handle_storage_summary_update(&self, storage_server_summary: &StorageServerSummary) {
+        // Get the highest synced version
+        let data_summary = &storage_server_summary.data_summary;
+        let Some(target_version) = data_summary.get_synced_ledger_info_version() else {
+            return; // We haven't synced anything yet
+        };
+
+        // Remove the responses that are no longer hot (or that have been pruned)
+        let lowest_hot_version = target_version.saturating_sub(self.config.max_hot_versions);
+        self.pre_serialized_responses.remove_stale_responses(
+            lowest_hot_version,
+            get_lowest_available_version(data_summary),
+            &self.lru_response_cache,
+        );
+
+        // Identify the new data. If the node just started (or fell too far
+        // behind), there are no peers following the chain to serve yet.
+        let Some(known_version) = self
+            .pre_serialized_responses
+            .update_highest_pre_serialized_version(target_version)
+        else {
+            return;
+        };
+        if target_version - known_version > self.config.max_hot_versions {
+            return;
+        }
+
+        // Pre-serialize the responses for the new data
+        for request in self.get_hot_requests(known_version, target_version) {
+            if let Err(error) = self.pre_serialize_response(request) {
+                metrics::increment_pre_serialization_event(PRE_SERIALIZATION_FAILED);
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(PRE_SERIALIZATION_LOG_FREQ_SECS)),
+                    warn!(LogSchema::new(LogEntry::ResponsePreSerialization)
+                        .error(&error)
+                        .message("Failed to pre-serialize the response!"))
+                );
+            }
+        }
+    }
+
+    /// Returns the requests that peers (with the given known version) will
+    /// make for the new data at the target version. These match the requests
+    /// created for the optimistic fetches of the peers.
+    fn get_hot_requests(
+        &self,
+        known_version: Version,
+        target_version: Version,
+    ) -> Vec<StorageServiceRequest> {
+        let start_version = known_version + 1;
+        let transactions_end_version = min(
+            target_version,
+            known_version + self.storage_service_config.max_transaction_chunk_size,
+        );
+        let outputs_end_version = min(
+            target_version,
+            known_version
+                + self
+                    .storage_service_config
+                    .max_transaction_output_chunk_size,
+        );
+
+        let data_requests = [
+            DataRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
+                proof_version: target_version,
+                start_version,
+                end_version: outputs_end_version,
+            }),
+            DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+                proof_version: target_version,
+                start_version,
+                end_version: transactions_end_version,
+                include_events: false,
+            }),
+            DataRequest::GetTransactionsOrOutputsWithProof(TransactionsOrOutputsWithProofRequest {
+                proof_version: target_version,
+                start_version,
+                end_version: outputs_end_version,
+                include_events: false,
+                max_num_output_reductions: 0,
+            }),
+        ];
+        data_requests
+            .into_iter()
+            .map(|data_request| StorageServiceRequest::new(data_request, true))
+            .collect()
+    }
+
+    /// Pre-serializes the response for the given request, and inserts it into
+    /// the LRU response cache. The response is skipped if it's already cached
+    /// (e.g., because the optimistic fetch handlers served the request first),
+    /// as pre-serializing it would save nothing.
+    fn pre_serialize_response(&self, request: StorageServiceRequest) -> Result<(), Error> {
+        if self.lru_response_cache.contains_key(&request) {
+            metrics::increment_pre_serialization_event(PRE_SERIALIZATION_SKIPPED);
+            return Ok(());
+        }
+
+        // Fetch the data (this isn't counted as serialization CPU)
+        let (data_response, start_version) = match &request.data_request {
+            DataRequest::GetTransactionOutputsWithProof(request) => (
+                self.handler.get_transaction_outputs_with_proof(request)?,
+                request.start_version,
+            ),
+            DataRequest::GetTransactionsWithProof(request) => (
+                self.handler.get_transactions_with_proof(request)?,
+                request.start_version,
+            ),
+            DataRequest::GetTransactionsOrOutputsWithProof(request) => (
+                self.handler
+                    .get_transactions_or_outputs_with_proof(request)?,
+                request.start_version,
+            ),
+            data_request => {
+                return Err(Error::UnexpectedErrorEncountered(format!(
+                    "Unexpected request to pre-serialize: {:?}",
+                    data_request
+                )))
+            },
+        };
+
+        // Serialize (and compress) the response and time the operation
+        let serialization_start_time = Instant::now();
+        let storage_response = StorageServiceResponse::new(data_response, request.use_compression)?;
+        let serialization_usecs = serialization_start_time.elapsed().as_micros() as u64;
+
+        // Check again if the response was cached in the meantime (i.e., we
+        // raced with the request handlers), otherwise cache it and track it.
+        if self.lru_response_cache.contains_key(&request) {
+            metrics::increment_pre_serialization_event(PRE_SERIALIZATION_SKIPPED);
+            return Ok(());
+        }
+        self.lru_response_cache
+            .insert(request.clone(), storage_response);
+        self.pre_serialized_responses
+            .responses
+            .insert(request, PreSerializedResponse {
+                start_version,
+                serialization_usecs,
+                served: false,
+            });
+        metrics::increment_pre_serialization_event(PRE_SERIALIZATION_CREATED);
+
+        Ok(())
+    }
+}
+
+/// Returns the lowest version for which both transactions and outputs are
+/// still available (i.e., the data below this version may have been pruned).
+fn get_lowest_available_version(data_summary: &DataSummary) -> Version {
+    let lowest_transaction_version = data_summary
+        .transactions
+        .map(|range| range.lowest())
+        .unwrap_or_default();
+    let lowest_output_version = data_summary
+        .transaction_outputs
+        .map(|range| range.lowest())
+        .unwrap_or_default();
+    lowest_transaction_version.max(lowest_output_version)
+}
@@ -48,6 +48,16 @@ pub trait StorageReaderInterface: Clone + Send + 'static {
         expected_end_epoch: u64,
     ) -> aptos_storage_service_types::Result<EpochChangeProof, Error>;
 
+    /// Returns the latest ledger info together with an epoch change proof
+    /// that starts at `trusted_epoch` and ends at the epoch of the latest
+    /// ledger info. Unlike other requests, the proof is never truncated:
+    /// if it exceeds the maximum proof length (or network frame) an error
+    /// is returned.
+    fn get_latest_ledger_info_with_epoch_proof(
+        &self,
+        trusted_epoch: u64,
+    ) -> aptos_storage_service_types::Result<(EpochChangeProof, LedgerInfoWithSignatures), Error>;
+
     /// Returns a list of transaction outputs with a proof relative to the
     /// `proof_version`. The transaction output list is expected to start at
     /// `start_version` and end at `end_version` (inclusive). In some cases,
@@ -334,6 +344,80 @@ impl StorageReaderInterface for StorageReader {
         )))
     }
 
+    fn get_latest_ledger_info_with_epoch_proof(
+        &self,
+        trusted_epoch: u64,
+    ) -> aptos_storage_service_types::Result<(EpochChangeProof, LedgerInfoWithSignatures), Error>
+    {
+        // Fetch the latest ledger info
+        let latest_ledger_info_with_sigs = self
+            .storage
+            .get_latest_ledger_info()
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+        let latest_epoch = latest_ledger_info_with_sigs.ledger_info().epoch();
+
+        // Verify the proof length is valid and within the configured bounds
+        let proof_length = latest_epoch.checked_sub(trusted_epoch).ok_or_else(|| {
+            Error::InvalidRequest(format!(
+                "The trusted epoch ({}) is ahead of the latest epoch ({})!",
+                trusted_epoch, latest_epoch
+            ))
+        })?;
+        let max_proof_length = self.config.max_epoch_change_proof_length;
+        if proof_length > max_proof_length {
+            return Err(Error::InvalidRequest(format!(
+                "The epoch change proof length ({}) exceeds the maximum ({})! Trusted epoch: {}, \
+                latest epoch: {}",
+                proof_length, max_proof_length, trusted_epoch, latest_epoch
+            )));
+        }
+
+        // If the client already trusts the latest epoch, no epoch changes are required
+        if proof_length == 0 {
+            return Ok((
+                EpochChangeProof::new(vec![], false),
+                latest_ledger_info_with_sigs,
+            ));
+        }
+
+        // Fetch the epoch ending ledger infos from the trusted epoch to the latest epoch.
+        // Note: the DbReader interface returns the epochs up to: `latest_epoch - 1`.
+        let epoch_change_proof = self
+            .storage
+            .get_epoch_ending_ledger_infos(trusted_epoch, latest_epoch)
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+        if epoch_change_proof.more
+            || epoch_change_proof.ledger_info_with_sigs.len() as u64 != proof_length
+        {
+            return Err(Error::UnexpectedErrorEncountered(format!(
+                "Unable to fetch a complete epoch change proof! Trusted epoch: {}, latest epoch: {}, \
+                num ledger infos: {}",
+                trusted_epoch,
+                latest_epoch,
+                epoch_change_proof.ledger_info_with_sigs.len()
+            )));
+        }
+
+        // Verify the proof fits into a single network frame (it cannot be truncated)
+        let proof_with_ledger_info = (epoch_change_proof, latest_ledger_info_with_sigs);
+        let (overflow_frame, num_bytes) = check_overflow_network_frame(
+            &proof_with_ledger_info,
+            self.config.max_network_chunk_bytes,
+        )?;
+        if overflow_frame {
+            increment_network_frame_overflow(
+                DataResponse::LatestLedgerInfoWithEpochProof(proof_with_ledger_info).get_label(),
+            );
+            return Err(Error::UnexpectedErrorEncountered(format!(
+                "Unable to serve the get_latest_ledger_info_with_epoch_proof request! Trusted \
+                epoch: {:?}, num bytes: {:?}. The data cannot fit into a single network frame!",
+                trusted_epoch, num_bytes
+            )));
+        }
+
+        Ok(proof_with_ledger_info)
+    }
+
     fn get_transaction_outputs_with_proof(
         &self,
         proof_version: u64,
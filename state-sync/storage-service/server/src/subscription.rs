@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    compression::CompressionManager,
     error::Error,
     metrics,
     metrics::{increment_counter, SUBSCRIPTION_EXPIRE},
@@ -513,6 +514,7 @@ pub(crate) async fn handle_active_subscriptions<T: StorageReaderInterface>(
     config: StorageServiceConfig,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -531,6 +533,7 @@ pub(crate) async fn handle_active_subscriptions<T: StorageReaderInterface>(
             cached_storage_server_summary.clone(),
             optimistic_fetches.clone(),
             lru_response_cache.clone(),
+            compression_manager.clone(),
             request_moderator.clone(),
             storage.clone(),
             subscriptions.clone(),
@@ -550,6 +553,7 @@ pub(crate) async fn handle_active_subscriptions<T: StorageReaderInterface>(
             config,
             optimistic_fetches.clone(),
             lru_response_cache.clone(),
+            compression_manager.clone(),
             request_moderator.clone(),
             storage.clone(),
             subscriptions.clone(),
@@ -568,6 +572,7 @@ async fn handle_ready_subscriptions<T: StorageReaderInterface>(
     config: StorageServiceConfig,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -596,6 +601,7 @@ async fn handle_ready_subscriptions<T: StorageReaderInterface>(
             let cached_storage_server_summary = cached_storage_server_summary.clone();
             let optimistic_fetches = optimistic_fetches.clone();
             let lru_response_cache = lru_response_cache.clone();
+            let compression_manager = compression_manager.clone();
             let request_moderator = request_moderator.clone();
             let storage = storage.clone();
             let subscriptions = subscriptions.clone();
@@ -623,6 +629,7 @@ async fn handle_ready_subscriptions<T: StorageReaderInterface>(
                         optimistic_fetches,
                         subscriptions.clone(),
                         lru_response_cache,
+                        compression_manager,
                         request_moderator,
                         storage,
                         time_service.clone(),
@@ -675,6 +682,7 @@ pub(crate) async fn get_peers_with_ready_subscriptions<T: StorageReaderInterface
     cached_storage_server_summary: Arc<ArcSwap<StorageServerSummary>>,
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
@@ -701,6 +709,7 @@ pub(crate) async fn get_peers_with_ready_subscriptions<T: StorageReaderInterface
         optimistic_fetches.clone(),
         subscriptions.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage.clone(),
         time_service.clone(),
@@ -729,6 +738,7 @@ async fn identify_expired_invalid_and_ready_subscriptions<T: StorageReaderInterf
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     time_service: TimeService,
@@ -776,6 +786,7 @@ async fn identify_expired_invalid_and_ready_subscriptions<T: StorageReaderInterf
             optimistic_fetches,
             subscriptions,
             lru_response_cache,
+            compression_manager,
             request_moderator,
             storage,
             time_service,
@@ -802,6 +813,7 @@ async fn identify_ready_and_invalid_subscriptions<T: StorageReaderInterface>(
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     time_service: TimeService,
@@ -829,6 +841,7 @@ async fn identify_ready_and_invalid_subscriptions<T: StorageReaderInterface>(
         let optimistic_fetches = optimistic_fetches.clone();
         let subscriptions = subscriptions.clone();
         let lru_response_cache = lru_response_cache.clone();
+        let compression_manager = compression_manager.clone();
         let request_moderator = request_moderator.clone();
         let storage = storage.clone();
         let time_service = time_service.clone();
@@ -849,6 +862,7 @@ async fn identify_ready_and_invalid_subscriptions<T: StorageReaderInterface>(
                         subscriptions.clone(),
                         highest_known_epoch,
                         lru_response_cache.clone(),
+                        compression_manager.clone(),
                         request_moderator.clone(),
                         &peer_network_id,
                         storage.clone(),
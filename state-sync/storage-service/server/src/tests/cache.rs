@@ -4,7 +4,10 @@
 use crate::tests::{mock, mock::MockClient, utils};
 use aptos_config::config::StorageServiceConfig;
 use aptos_crypto::hash::HashValue;
-use aptos_storage_service_types::responses::{DataResponse, StorageServiceResponse};
+use aptos_storage_service_types::{
+    requests::{DataRequest, LatestLedgerInfoWithEpochProofRequest, StorageServiceRequest},
+    responses::{DataResponse, StorageServiceResponse},
+};
 use aptos_types::{
     proof::definition::SparseMerkleRangeProof, state_store::state_value::StateValueChunkWithProof,
 };
@@ -12,6 +15,7 @@ use mockall::{
     predicate::{always, eq},
     Sequence,
 };
+use std::sync::Arc;
 
 #[tokio::test]
 async fn test_cachable_requests_compression() {
@@ -155,6 +159,62 @@ async fn test_cachable_requests_data_versions() {
     }
 }
 
+#[tokio::test]
+async fn test_cachable_requests_latest_ledger_info() {
+    // Create test data
+    let epoch = 10;
+    let versions = [1000, 1001];
+
+    // Create the mock db reader (the latest ledger info should be fetched once per version)
+    let mut db_reader = mock::create_mock_db_reader();
+    let mut expectation_sequence = Sequence::new();
+    for version in versions {
+        let latest_ledger_info = utils::create_test_ledger_info_with_sigs(epoch, version);
+        db_reader
+            .expect_get_latest_ledger_info()
+            .times(1)
+            .return_once(move || Ok(latest_ledger_info))
+            .in_sequence(&mut expectation_sequence);
+    }
+
+    // Create the storage client and server
+    let (mut mock_client, mut service, _, _, _) = MockClient::new(Some(db_reader), None);
+    utils::update_storage_server_summary(&mut service, versions[0], epoch);
+    let cached_storage_server_summary = service.cached_storage_server_summary.clone();
+    tokio::spawn(service.start());
+
+    // Repeatedly fetch the latest ledger info and verify it is only
+    // refetched from storage once a new version is synced.
+    for version in versions {
+        // Update the highest synced version
+        let mut storage_server_summary = cached_storage_server_summary.load().as_ref().clone();
+        storage_server_summary.data_summary.synced_ledger_info =
+            Some(utils::create_epoch_ending_ledger_info(epoch, version));
+        cached_storage_server_summary.store(Arc::new(storage_server_summary));
+
+        for _ in 0..10 {
+            let data_request = DataRequest::GetLatestLedgerInfoWithEpochProof(
+                LatestLedgerInfoWithEpochProofRequest {
+                    trusted_epoch: epoch,
+                },
+            );
+            let storage_request = StorageServiceRequest::new(data_request, true);
+            let response = mock_client.process_request(storage_request).await.unwrap();
+
+            // Verify the response is correct
+            match response.get_data_response().unwrap() {
+                DataResponse::LatestLedgerInfoWithEpochProof((_, ledger_info)) => {
+                    assert_eq!(ledger_info.ledger_info().version(), version);
+                },
+                _ => panic!(
+                    "Expected latest ledger info with epoch proof but got: {:?}",
+                    response
+                ),
+            };
+        }
+    }
+}
+
 #[tokio::test]
 async fn test_cachable_requests_eviction() {
     // Create test data
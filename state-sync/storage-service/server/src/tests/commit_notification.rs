@@ -0,0 +1,194 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{commit_notification, metrics, network::StorageServiceNetworkEvents, tests::utils};
+use aptos_channels::{aptos_channel, message_queues::QueueStyle};
+use aptos_config::{
+    config::{PeerRole, StorageServiceConfig},
+    network_id::{NetworkId, PeerNetworkId},
+};
+use aptos_netcore::transport::ConnectionOrigin;
+use aptos_network::{
+    application::{interface::NetworkServiceEvents, storage::PeersAndMetadata},
+    peer_manager::PeerManagerNotification,
+    protocols::{
+        direct_send::Message,
+        network::{NetworkEvents, NewNetworkEvents},
+        rpc::InboundRpcRequest,
+        wire::handshake::v1::{MessagingProtocolVersion, ProtocolId, ProtocolIdSet},
+    },
+    transport::{ConnectionId, ConnectionMetadata},
+};
+use aptos_storage_service_types::{
+    requests::{DataRequest, StorageServiceRequest},
+    StorageServiceMessage,
+};
+use aptos_types::{network_address::NetworkAddress, PeerId};
+use futures::{channel::oneshot, StreamExt};
+use std::{collections::HashMap, str::FromStr};
+
+#[tokio::test]
+async fn test_commit_notification_forwarding() {
+    // Create the network events for a single network
+    let network_id = NetworkId::Public;
+    let queue_config = aptos_channel::Config::new(
+        StorageServiceConfig::default().max_network_channel_size as usize,
+    )
+    .queue_style(QueueStyle::FIFO)
+    .counters(&metrics::PENDING_STORAGE_SERVER_NETWORK_EVENTS);
+    let (peer_manager_notifier, peer_manager_notification_receiver) = queue_config.build();
+    let (_, connection_notification_receiver) = queue_config.build();
+    let network_events = NetworkEvents::new(
+        peer_manager_notification_receiver,
+        connection_notification_receiver,
+        None,
+    );
+    let network_service_events =
+        NetworkServiceEvents::new(HashMap::from([(network_id, network_events)]));
+
+    // Create the storage service network events (with a peer commit notifier)
+    let (peer_commit_notifier, mut peer_commit_listener) =
+        aptos_storage_service_notifications::new_peer_commit_notifier_listener_pair();
+    let mut storage_service_network_events =
+        StorageServiceNetworkEvents::new(network_service_events, Some(peer_commit_notifier));
+
+    // Send a commit notification from a peer
+    let peer_id = PeerId::random();
+    let ledger_info = utils::create_epoch_ending_ledger_info(10, 1000);
+    let protocol_id = ProtocolId::StateSyncDirectSend;
+    let message = Message {
+        protocol_id,
+        mdata: protocol_id
+            .to_bytes(&StorageServiceMessage::CommitNotification(
+                ledger_info.clone(),
+            ))
+            .unwrap()
+            .into(),
+    };
+    peer_manager_notifier
+        .push(
+            (peer_id, protocol_id),
+            PeerManagerNotification::RecvMessage(peer_id, message),
+        )
+        .unwrap();
+
+    // Send a storage request from the same peer
+    let protocol_id = ProtocolId::StorageServiceRpc;
+    let request = StorageServiceRequest::new(DataRequest::GetServerProtocolVersion, false);
+    let (res_tx, _res_rx) = oneshot::channel();
+    let inbound_rpc = InboundRpcRequest {
+        protocol_id,
+        data: protocol_id
+            .to_bytes(&StorageServiceMessage::Request(request.clone()))
+            .unwrap()
+            .into(),
+        res_tx,
+    };
+    peer_manager_notifier
+        .push(
+            (peer_id, protocol_id),
+            PeerManagerNotification::RecvRpc(peer_id, inbound_rpc),
+        )
+        .unwrap();
+
+    // Verify only the storage request is returned by the network events
+    let network_request = storage_service_network_events.next().await.unwrap();
+    assert_eq!(network_request.storage_service_request, request);
+
+    // Verify the commit notification was forwarded to state sync
+    let peer_commit_notification = peer_commit_listener.next().await.unwrap();
+    assert_eq!(
+        peer_commit_notification.peer_network_id,
+        PeerNetworkId::new(network_id, peer_id)
+    );
+    assert_eq!(peer_commit_notification.ledger_info, ledger_info);
+}
+
+#[test]
+fn test_get_downstream_peers() {
+    // Create the peers and metadata
+    let peers_and_metadata =
+        PeersAndMetadata::new(&[NetworkId::Validator, NetworkId::Vfn, NetworkId::Public]);
+
+    // Add a validator peer that connected to us
+    let validator_peer = add_connected_peer(
+        &peers_and_metadata,
+        NetworkId::Validator,
+        ConnectionOrigin::Inbound,
+        true,
+    );
+
+    // Add a VFN that connected to us
+    let vfn_peer = add_connected_peer(
+        &peers_and_metadata,
+        NetworkId::Vfn,
+        ConnectionOrigin::Inbound,
+        true,
+    );
+
+    // Add a public peer that we connected to
+    let upstream_peer = add_connected_peer(
+        &peers_and_metadata,
+        NetworkId::Public,
+        ConnectionOrigin::Outbound,
+        true,
+    );
+
+    // Add a public peer that connected to us, but doesn't support commit notifications
+    let old_peer = add_connected_peer(
+        &peers_and_metadata,
+        NetworkId::Public,
+        ConnectionOrigin::Inbound,
+        false,
+    );
+
+    // Add a public peer that connected to us
+    let pfn_peer = add_connected_peer(
+        &peers_and_metadata,
+        NetworkId::Public,
+        ConnectionOrigin::Inbound,
+        true,
+    );
+
+    // Verify that only the VFN and PFN are downstream peers
+    let downstream_peers = commit_notification::get_downstream_peers(&peers_and_metadata).unwrap();
+    assert_eq!(downstream_peers.len(), 2);
+    assert!(downstream_peers.contains(&vfn_peer));
+    assert!(downstream_peers.contains(&pfn_peer));
+    for peer in [validator_peer, upstream_peer, old_peer] {
+        assert!(!downstream_peers.contains(&peer));
+    }
+}
+
+/// Adds a new connected peer to the given peers and metadata
+fn add_connected_peer(
+    peers_and_metadata: &PeersAndMetadata,
+    network_id: NetworkId,
+    origin: ConnectionOrigin,
+    supports_commit_notifications: bool,
+) -> PeerNetworkId {
+    // Determine the supported protocols
+    let mut protocol_ids = vec![ProtocolId::StorageServiceRpc];
+    if supports_commit_notifications {
+        protocol_ids.push(ProtocolId::StateSyncDirectSend);
+    }
+
+    // Create the connection metadata
+    let peer_network_id = PeerNetworkId::new(network_id, PeerId::random());
+    let connection_metadata = ConnectionMetadata::new(
+        peer_network_id.peer_id(),
+        ConnectionId::from(0),
+        NetworkAddress::from_str("/ip4/127.0.0.1/tcp/8081").unwrap(),
+        origin,
+        MessagingProtocolVersion::V1,
+        ProtocolIdSet::from_iter(protocol_ids),
+        PeerRole::Unknown,
+    );
+
+    // Insert the connection metadata
+    peers_and_metadata
+        .insert_connection_metadata(peer_network_id, connection_metadata)
+        .unwrap();
+
+    peer_network_id
+}
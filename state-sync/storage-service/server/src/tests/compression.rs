@@ -0,0 +1,176 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::compression::CompressionManager;
+use aptos_compression::codec::CompressionCodec;
+use aptos_config::{
+    config::AdaptiveCompressionConfig,
+    network_id::{NetworkId, PeerNetworkId},
+};
+use aptos_storage_service_types::responses::DataResponse;
+use aptos_types::PeerId;
+
+#[test]
+fn test_adaptive_compression_disabled() {
+    // Create a compression manager with adaptive compression disabled
+    let compression_manager = CompressionManager::new(AdaptiveCompressionConfig::default());
+
+    // Verify that responses are always compressed (if requested)
+    let peer_network_id = PeerNetworkId::new(NetworkId::Vfn, PeerId::random());
+    for _ in 0..100 {
+        let response = compression_manager
+            .create_response(&peer_network_id, create_data_response(), &[
+                CompressionCodec::Lz4,
+            ])
+            .unwrap();
+        assert!(response.is_compressed());
+    }
+
+    // Verify that responses are never compressed (if not requested)
+    let response = compression_manager
+        .create_response(&peer_network_id, create_data_response(), &[])
+        .unwrap();
+    assert!(!response.is_compressed());
+}
+
+#[test]
+fn test_adaptive_compression_not_beneficial() {
+    // Create a compression manager with adaptive compression enabled
+    let min_compression_samples = 5;
+    let compression_probe_interval = 10;
+    let compression_manager = CompressionManager::new(AdaptiveCompressionConfig {
+        enable_adaptive_compression: true,
+        min_compression_samples,
+        compression_probe_interval,
+        vfn_network_bandwidth_bytes_per_sec: u64::MAX, // The link is extremely fast
+        ..Default::default()
+    });
+
+    // Verify that the first responses are compressed (to measure compression)
+    let peer_network_id = PeerNetworkId::new(NetworkId::Vfn, PeerId::random());
+    for _ in 0..min_compression_samples {
+        let response = compression_manager
+            .create_response(&peer_network_id, create_data_response(), &[
+                CompressionCodec::Lz4,
+            ])
+            .unwrap();
+        assert!(response.is_compressed());
+    }
+
+    // Verify that compression is now skipped, and the response data is unchanged
+    for _ in 0..compression_probe_interval {
+        let response = compression_manager
+            .create_response(&peer_network_id, create_data_response(), &[
+                CompressionCodec::Lz4,
+            ])
+            .unwrap();
+        assert!(!response.is_compressed());
+        assert_eq!(
+            response.get_data_response().unwrap(),
+            create_data_response()
+        );
+    }
+
+    // Verify that the next response is compressed (to refresh the measurements)
+    let response = compression_manager
+        .create_response(&peer_network_id, create_data_response(), &[
+            CompressionCodec::Lz4,
+        ])
+        .unwrap();
+    assert!(response.is_compressed());
+
+    // Verify that responses for other peers are still compressed
+    let other_peer_network_id = PeerNetworkId::new(NetworkId::Vfn, PeerId::random());
+    let response = compression_manager
+        .create_response(&other_peer_network_id, create_data_response(), &[
+            CompressionCodec::Lz4,
+        ])
+        .unwrap();
+    assert!(response.is_compressed());
+}
+
+#[test]
+fn test_adaptive_compression_server_saturated() {
+    // Create a compression manager that doesn't allow any concurrent compressions
+    let compression_manager = CompressionManager::new(AdaptiveCompressionConfig {
+        enable_adaptive_compression: true,
+        max_concurrent_compressions: 0,
+        ..Default::default()
+    });
+
+    // Verify that compression is always skipped
+    let peer_network_id = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+    for _ in 0..10 {
+        let response = compression_manager
+            .create_response(&peer_network_id, create_data_response(), &[
+                CompressionCodec::Lz4,
+            ])
+            .unwrap();
+        assert!(!response.is_compressed());
+    }
+}
+
+#[test]
+fn test_compression_codec_negotiation() {
+    // Create a compression manager with zstd compression enabled
+    let compression_manager = CompressionManager::new(AdaptiveCompressionConfig::default());
+
+    // Verify that the most preferred codec of the client is used
+    let peer_network_id = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+    for (accepted_codecs, expected_codec) in [
+        (vec![CompressionCodec::Lz4], CompressionCodec::Lz4),
+        (vec![CompressionCodec::Zstd], CompressionCodec::Zstd),
+        (
+            vec![CompressionCodec::Lz4, CompressionCodec::Zstd],
+            CompressionCodec::Lz4,
+        ),
+        (
+            vec![CompressionCodec::Zstd, CompressionCodec::Lz4],
+            CompressionCodec::Zstd,
+        ),
+    ] {
+        let response = compression_manager
+            .create_response(&peer_network_id, create_data_response(), &accepted_codecs)
+            .unwrap();
+        assert_eq!(response.get_compression_codec(), Some(expected_codec));
+        assert_eq!(
+            response.get_data_response().unwrap(),
+            create_data_response()
+        );
+    }
+
+    // Create a compression manager with zstd compression disabled
+    let compression_manager = CompressionManager::new(AdaptiveCompressionConfig {
+        enable_zstd_compression: false,
+        ..Default::default()
+    });
+
+    // Verify that LZ4 is used (if accepted by the client)
+    let response = compression_manager
+        .create_response(&peer_network_id, create_data_response(), &[
+            CompressionCodec::Zstd,
+            CompressionCodec::Lz4,
+        ])
+        .unwrap();
+    assert_eq!(
+        response.get_compression_codec(),
+        Some(CompressionCodec::Lz4)
+    );
+
+    // Verify that the response is not compressed if no codec is supported
+    let response = compression_manager
+        .create_response(&peer_network_id, create_data_response(), &[
+            CompressionCodec::Zstd,
+        ])
+        .unwrap();
+    assert!(!response.is_compressed());
+    assert_eq!(
+        response.get_data_response().unwrap(),
+        create_data_response()
+    );
+}
+
+/// Creates a small data response (that doesn't benefit from compression)
+fn create_data_response() -> DataResponse {
+    DataResponse::NumberOfStatesAtVersion(100)
+}
@@ -0,0 +1,170 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{error::Error, integrity::DataIntegrityChecker};
+use aptos_config::{
+    config::DataIntegrityCheckConfig,
+    network_id::{NetworkId, PeerNetworkId},
+};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_storage_service_types::{
+    requests::{DataRequest, StorageServiceRequest, TransactionsWithProofRequest},
+    responses::DataResponse,
+};
+use aptos_types::{
+    aggregate_signature::AggregateSignature,
+    block_info::BlockInfo,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{TransactionAccumulatorRangeProof, TransactionInfoListWithProof},
+    transaction::{
+        ExecutionStatus, Transaction, TransactionInfo, TransactionListWithProof, Version,
+    },
+    PeerId,
+};
+
+#[test]
+fn test_integrity_checks_disabled() {
+    // Create a data integrity checker with checks disabled
+    let data_integrity_checker = DataIntegrityChecker::new(DataIntegrityCheckConfig::default());
+
+    // Verify that corrupt responses are not detected
+    let (corrupt_response, ledger_info) = create_transactions_response(true);
+    let result = data_integrity_checker.check_response(
+        &create_peer_network_id(),
+        &create_transactions_request(0),
+        &corrupt_response,
+        Some(&ledger_info),
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_integrity_checks_sampling() {
+    // Create a data integrity checker that checks every other response
+    let data_integrity_checker = DataIntegrityChecker::new(DataIntegrityCheckConfig {
+        enable_integrity_checks: true,
+        sampling_interval: 2,
+    });
+
+    // Verify that valid responses pass the checks
+    let peer_network_id = create_peer_network_id();
+    let request = create_transactions_request(0);
+    let (valid_response, ledger_info) = create_transactions_response(false);
+    for _ in 0..4 {
+        let result = data_integrity_checker.check_response(
+            &peer_network_id,
+            &request,
+            &valid_response,
+            Some(&ledger_info),
+        );
+        assert!(result.is_ok());
+    }
+
+    // Verify that only the sampled corrupt responses are detected
+    let (corrupt_response, ledger_info) = create_transactions_response(true);
+    for response_index in 0..4 {
+        let result = data_integrity_checker.check_response(
+            &peer_network_id,
+            &request,
+            &corrupt_response,
+            Some(&ledger_info),
+        );
+        if response_index % 2 == 0 {
+            assert!(matches!(result, Err(Error::UnexpectedErrorEncountered(_))));
+        } else {
+            assert!(result.is_ok());
+        }
+    }
+}
+
+#[test]
+fn test_integrity_checks_skipped() {
+    // Create a data integrity checker that checks every response
+    let data_integrity_checker = DataIntegrityChecker::new(DataIntegrityCheckConfig {
+        enable_integrity_checks: true,
+        sampling_interval: 1,
+    });
+
+    // Verify that responses for a different proof version are not checked
+    let peer_network_id = create_peer_network_id();
+    let (corrupt_response, ledger_info) = create_transactions_response(true);
+    let result = data_integrity_checker.check_response(
+        &peer_network_id,
+        &create_transactions_request(10),
+        &corrupt_response,
+        Some(&ledger_info),
+    );
+    assert!(result.is_ok());
+
+    // Verify that responses are not checked without a synced ledger info
+    let result = data_integrity_checker.check_response(
+        &peer_network_id,
+        &create_transactions_request(0),
+        &corrupt_response,
+        None,
+    );
+    assert!(result.is_ok());
+
+    // Verify that corrupt responses are detected with a synced ledger info
+    let result = data_integrity_checker.check_response(
+        &peer_network_id,
+        &create_transactions_request(0),
+        &corrupt_response,
+        Some(&ledger_info),
+    );
+    assert!(result.is_err());
+}
+
+/// Creates a random peer on the public network
+fn create_peer_network_id() -> PeerNetworkId {
+    PeerNetworkId::new(NetworkId::Public, PeerId::random())
+}
+
+/// Creates a request for the transaction at version 0 (with the given proof version)
+fn create_transactions_request(proof_version: Version) -> StorageServiceRequest {
+    let data_request = DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+        proof_version,
+        start_version: 0,
+        end_version: 0,
+        include_events: false,
+    });
+    StorageServiceRequest::new(data_request, false)
+}
+
+/// Creates a response containing a single transaction (at version 0) and the
+/// ledger info that proves it. If `corrupt` is true, the transaction in the
+/// response doesn't match the proof.
+fn create_transactions_response(corrupt: bool) -> (DataResponse, LedgerInfoWithSignatures) {
+    // Create the transaction info and the ledger info that proves it
+    let transaction = Transaction::StateCheckpoint(HashValue::random());
+    let transaction_info = TransactionInfo::new(
+        transaction.hash(),
+        HashValue::zero(),
+        HashValue::zero(),
+        None,
+        0,
+        ExecutionStatus::Success,
+    );
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(0, 0, HashValue::zero(), transaction_info.hash(), 0, 0, None),
+        HashValue::zero(),
+    );
+    let ledger_info = LedgerInfoWithSignatures::new(ledger_info, AggregateSignature::empty());
+
+    // Create the transaction list (corrupting the transaction if required)
+    let transaction = if corrupt {
+        Transaction::StateCheckpoint(HashValue::random())
+    } else {
+        transaction
+    };
+    let proof =
+        TransactionInfoListWithProof::new(TransactionAccumulatorRangeProof::new_empty(), vec![
+            transaction_info,
+        ]);
+    let transaction_list = TransactionListWithProof::new(vec![transaction], None, Some(0), proof);
+
+    (
+        DataResponse::TransactionsWithProof(transaction_list),
+        ledger_info,
+    )
+}
@@ -0,0 +1,181 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::tests::{mock, mock::MockClient, utils};
+use aptos_config::config::StorageServiceConfig;
+use aptos_storage_service_types::{
+    requests::{DataRequest, LatestLedgerInfoWithEpochProofRequest, StorageServiceRequest},
+    responses::DataResponse,
+    Epoch, StorageServiceError,
+};
+use aptos_types::{epoch_change::EpochChangeProof, ledger_info::LedgerInfoWithSignatures};
+use claims::assert_matches;
+
+#[tokio::test]
+async fn test_get_latest_ledger_info_with_epoch_proof() {
+    // Test different proof lengths
+    let latest_epoch = 150;
+    for trusted_epoch in [50, 100, 149] {
+        // Create test data
+        let latest_ledger_info = utils::create_test_ledger_info_with_sigs(latest_epoch, 1000);
+        let epoch_change_proof = EpochChangeProof {
+            ledger_info_with_sigs: create_epoch_ending_ledger_infos(trusted_epoch, latest_epoch),
+            more: false,
+        };
+
+        // Create the mock db reader
+        let mut db_reader = mock::create_mock_db_reader();
+        expect_get_latest_ledger_info(&mut db_reader, latest_ledger_info.clone());
+        utils::expect_get_epoch_ending_ledger_infos(
+            &mut db_reader,
+            trusted_epoch,
+            latest_epoch,
+            epoch_change_proof.clone(),
+        );
+
+        // Create the storage client and server
+        let (mut mock_client, mut service, _, _, _) = MockClient::new(Some(db_reader), None);
+        utils::update_storage_server_summary(&mut service, 1000, latest_epoch);
+        tokio::spawn(service.start());
+
+        // Process a request to fetch the latest ledger info with an epoch proof
+        let response =
+            get_latest_ledger_info_with_epoch_proof(&mut mock_client, trusted_epoch, true)
+                .await
+                .unwrap();
+
+        // Verify the response is correct
+        match response {
+            DataResponse::LatestLedgerInfoWithEpochProof((
+                response_epoch_change_proof,
+                response_ledger_info,
+            )) => {
+                assert_eq!(response_epoch_change_proof, epoch_change_proof);
+                assert_eq!(response_ledger_info, latest_ledger_info);
+            },
+            _ => panic!(
+                "Expected latest ledger info with epoch proof but got: {:?}",
+                response
+            ),
+        };
+    }
+}
+
+#[tokio::test]
+async fn test_get_latest_ledger_info_with_epoch_proof_same_epoch() {
+    // Create test data
+    let latest_epoch = 10;
+    let latest_ledger_info = utils::create_test_ledger_info_with_sigs(latest_epoch, 500);
+
+    // Create the mock db reader (no epoch ending ledger infos should be fetched)
+    let mut db_reader = mock::create_mock_db_reader();
+    expect_get_latest_ledger_info(&mut db_reader, latest_ledger_info.clone());
+
+    // Create the storage client and server
+    let (mut mock_client, mut service, _, _, _) = MockClient::new(Some(db_reader), None);
+    utils::update_storage_server_summary(&mut service, 500, latest_epoch);
+    tokio::spawn(service.start());
+
+    // Process the request and verify the proof is empty
+    let response = get_latest_ledger_info_with_epoch_proof(&mut mock_client, latest_epoch, true)
+        .await
+        .unwrap();
+    match response {
+        DataResponse::LatestLedgerInfoWithEpochProof((epoch_change_proof, ledger_info)) => {
+            assert!(epoch_change_proof.ledger_info_with_sigs.is_empty());
+            assert!(!epoch_change_proof.more);
+            assert_eq!(ledger_info, latest_ledger_info);
+        },
+        _ => panic!(
+            "Expected latest ledger info with epoch proof but got: {:?}",
+            response
+        ),
+    };
+}
+
+#[tokio::test]
+async fn test_get_latest_ledger_info_with_epoch_proof_too_long() {
+    // Create a storage config with a small max proof length
+    let max_epoch_change_proof_length = 10;
+    let storage_config = StorageServiceConfig {
+        max_epoch_change_proof_length,
+        ..Default::default()
+    };
+
+    // Create test data
+    let latest_epoch = 100;
+    let trusted_epoch = latest_epoch - max_epoch_change_proof_length - 1;
+    let latest_ledger_info = utils::create_test_ledger_info_with_sigs(latest_epoch, 1000);
+
+    // Create the mock db reader (no epoch ending ledger infos should be fetched)
+    let mut db_reader = mock::create_mock_db_reader();
+    expect_get_latest_ledger_info(&mut db_reader, latest_ledger_info);
+
+    // Create the storage client and server
+    let (mut mock_client, mut service, _, _, _) =
+        MockClient::new(Some(db_reader), Some(storage_config));
+    utils::update_storage_server_summary(&mut service, 1000, latest_epoch);
+    tokio::spawn(service.start());
+
+    // Process the request and verify it is rejected
+    let response = get_latest_ledger_info_with_epoch_proof(&mut mock_client, trusted_epoch, true)
+        .await
+        .unwrap_err();
+    assert_matches!(response, StorageServiceError::InvalidRequest(_));
+}
+
+#[tokio::test]
+async fn test_get_latest_ledger_info_with_epoch_proof_not_serviceable() {
+    // Create the storage client and server (that cannot service the request)
+    let latest_epoch = 100;
+    let (mut mock_client, mut service, _, _, _) = MockClient::new(None, None);
+    utils::update_storage_server_summary(&mut service, 1000, latest_epoch);
+    tokio::spawn(service.start());
+
+    // Process a request with a trusted epoch ahead of the server
+    let response =
+        get_latest_ledger_info_with_epoch_proof(&mut mock_client, latest_epoch + 1, true)
+            .await
+            .unwrap_err();
+
+    // Verify the request is not serviceable
+    assert_matches!(response, StorageServiceError::InvalidRequest(_));
+}
+
+/// Creates a list of epoch ending ledger infos (from start to end, exclusive)
+fn create_epoch_ending_ledger_infos(
+    start_epoch: Epoch,
+    end_epoch: Epoch,
+) -> Vec<LedgerInfoWithSignatures> {
+    (start_epoch..end_epoch)
+        .map(|epoch| utils::create_epoch_ending_ledger_info(epoch, epoch * 10))
+        .collect()
+}
+
+/// Sets an expectation on the given mock db for a call to fetch the latest ledger info
+fn expect_get_latest_ledger_info(
+    mock_db: &mut mock::MockDatabaseReader,
+    latest_ledger_info: LedgerInfoWithSignatures,
+) {
+    mock_db
+        .expect_get_latest_ledger_info()
+        .times(1)
+        .returning(move || Ok(latest_ledger_info.clone()));
+}
+
+/// Sends a request for the latest ledger info with an epoch proof and returns the response
+async fn get_latest_ledger_info_with_epoch_proof(
+    mock_client: &mut MockClient,
+    trusted_epoch: Epoch,
+    use_compression: bool,
+) -> Result<DataResponse, StorageServiceError> {
+    let data_request =
+        DataRequest::GetLatestLedgerInfoWithEpochProof(LatestLedgerInfoWithEpochProofRequest {
+            trusted_epoch,
+        });
+    let storage_request = StorageServiceRequest::new(data_request, use_compression);
+    mock_client
+        .process_request(storage_request)
+        .await
+        .map(|response| response.get_data_response().unwrap())
+}
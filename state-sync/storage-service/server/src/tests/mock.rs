@@ -108,7 +108,7 @@ impl MockClient {
             peer_manager_notifiers.insert(network_id, peer_manager_notifier);
         }
         let storage_service_network_events =
-            StorageServiceNetworkEvents::new(NetworkServiceEvents::new(network_and_events));
+            StorageServiceNetworkEvents::new(NetworkServiceEvents::new(network_and_events), None);
 
         // Create the storage service notifier and listener
         let (storage_service_notifier, storage_service_listener) =
@@ -126,6 +126,7 @@ impl MockClient {
             peers_and_metadata.clone(),
             storage_service_network_events,
             storage_service_listener,
+            None,
         );
 
         // Return the client and service
@@ -2,14 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod cache;
+mod commit_notification;
+mod compression;
 mod epoch_ending;
+mod integrity;
+mod latest_ledger_info_with_epoch_proof;
 mod mock;
 mod new_transaction_outputs;
 mod new_transactions;
 mod new_transactions_or_outputs;
 mod number_of_states;
 mod optimistic_fetch;
+mod pre_serialization;
 mod protocol_version;
+mod request_fuzzing;
 mod request_moderator;
 mod state_values;
 mod storage_summary;
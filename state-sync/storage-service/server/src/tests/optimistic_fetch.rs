@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    compression::CompressionManager,
     moderator::RequestModerator,
     network::ResponseSender,
     optimistic_fetch,
@@ -68,6 +69,9 @@ async fn test_peers_with_ready_optimistic_fetches() {
     let cached_storage_server_summary =
         Arc::new(ArcSwap::from(Arc::new(StorageServerSummary::default())));
     let lru_response_cache = Cache::new(0);
+    let compression_manager = Arc::new(CompressionManager::new(
+        storage_service_config.adaptive_compression,
+    ));
     let request_moderator = Arc::new(RequestModerator::new(
         AptosDataClientConfig::default(),
         cached_storage_server_summary.clone(),
@@ -85,6 +89,7 @@ async fn test_peers_with_ready_optimistic_fetches() {
             cached_storage_server_summary.clone(),
             optimistic_fetches.clone(),
             lru_response_cache.clone(),
+            compression_manager.clone(),
             request_moderator.clone(),
             storage_reader.clone(),
             subscriptions.clone(),
@@ -106,6 +111,7 @@ async fn test_peers_with_ready_optimistic_fetches() {
             cached_storage_server_summary.clone(),
             optimistic_fetches.clone(),
             lru_response_cache.clone(),
+            compression_manager.clone(),
             request_moderator.clone(),
             storage_reader.clone(),
             subscriptions.clone(),
@@ -133,6 +139,7 @@ async fn test_peers_with_ready_optimistic_fetches() {
             cached_storage_server_summary,
             optimistic_fetches,
             lru_response_cache,
+            compression_manager,
             request_moderator,
             storage_reader,
             subscriptions,
@@ -173,6 +180,9 @@ async fn test_peers_with_ready_optimistic_fetches_update() {
     let cached_storage_server_summary =
         Arc::new(ArcSwap::from(Arc::new(StorageServerSummary::default())));
     let lru_response_cache = Cache::new(0);
+    let compression_manager = Arc::new(CompressionManager::new(
+        storage_service_config.adaptive_compression,
+    ));
     let request_moderator = Arc::new(RequestModerator::new(
         AptosDataClientConfig::default(),
         cached_storage_server_summary.clone(),
@@ -198,6 +208,7 @@ async fn test_peers_with_ready_optimistic_fetches_update() {
             cached_storage_server_summary.clone(),
             optimistic_fetches.clone(),
             lru_response_cache.clone(),
+            compression_manager.clone(),
             request_moderator.clone(),
             storage_reader.clone(),
             subscriptions.clone(),
@@ -225,6 +236,7 @@ async fn test_peers_with_ready_optimistic_fetches_update() {
         storage_service_config,
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -252,6 +264,7 @@ async fn test_peers_with_ready_optimistic_fetches_update() {
             cached_storage_server_summary.clone(),
             optimistic_fetches.clone(),
             lru_response_cache.clone(),
+            compression_manager.clone(),
             request_moderator.clone(),
             storage_reader.clone(),
             subscriptions.clone(),
@@ -283,6 +296,9 @@ async fn test_remove_expired_optimistic_fetches() {
     let cached_storage_server_summary =
         Arc::new(ArcSwap::from(Arc::new(StorageServerSummary::default())));
     let lru_response_cache = Cache::new(0);
+    let compression_manager = Arc::new(CompressionManager::new(
+        storage_service_config.adaptive_compression,
+    ));
     let request_moderator = Arc::new(RequestModerator::new(
         AptosDataClientConfig::default(),
         cached_storage_server_summary.clone(),
@@ -318,6 +334,7 @@ async fn test_remove_expired_optimistic_fetches() {
             cached_storage_server_summary.clone(),
             optimistic_fetches.clone(),
             lru_response_cache.clone(),
+            compression_manager.clone(),
             request_moderator.clone(),
             storage.clone(),
             subscriptions.clone(),
@@ -352,6 +369,7 @@ async fn test_remove_expired_optimistic_fetches() {
             cached_storage_server_summary.clone(),
             optimistic_fetches.clone(),
             lru_response_cache.clone(),
+            compression_manager.clone(),
             request_moderator.clone(),
             storage.clone(),
             subscriptions.clone(),
@@ -373,6 +391,7 @@ async fn test_remove_expired_optimistic_fetches() {
             cached_storage_server_summary.clone(),
             optimistic_fetches.clone(),
             lru_response_cache,
+            compression_manager,
             request_moderator,
             storage.clone(),
             subscriptions,
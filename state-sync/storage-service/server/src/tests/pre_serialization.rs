@@ -0,0 +1,287 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    handler::Handler,
+    pre_serialization::ResponsePreSerializer,
+    storage::StorageReader,
+    tests::{mock, mock::MockClient, utils},
+    StorageServiceServer,
+};
+use aptos_config::config::{ResponsePreSerializationConfig, StorageServiceConfig};
+use aptos_storage_service_types::{
+    requests::{DataRequest, StorageServiceRequest, TransactionsWithProofRequest},
+    responses::{CompleteDataRange, DataResponse, StorageServerSummary, StorageServiceResponse},
+};
+use mockall::predicate::eq;
+
+#[tokio::test]
+async fn test_pre_serialize_hot_responses() {
+    // Create test data
+    let known_version = 100;
+    let target_version = 150;
+    let start_version = known_version + 1;
+    let num_versions = target_version - known_version;
+    let transaction_list_with_proof = utils::create_transaction_list_with_proof(
+        start_version,
+        target_version,
+        target_version,
+        false,
+    );
+    let output_list_with_proof =
+        utils::create_output_list_with_proof(start_version, target_version, target_version);
+
+    // Create the mock db reader. The data should only be fetched once per hot request
+    // (the outputs are fetched for both the output and transaction or output requests).
+    let mut db_reader = mock::create_mock_db_reader();
+    utils::expect_get_transactions(
+        &mut db_reader,
+        start_version,
+        num_versions,
+        target_version,
+        false,
+        transaction_list_with_proof.clone(),
+    );
+    db_reader
+        .expect_get_transaction_outputs()
+        .times(2)
+        .with(eq(start_version), eq(num_versions), eq(target_version))
+        .returning(move |_, _, _| Ok(output_list_with_proof.clone()));
+
+    // Create the storage client and server
+    let storage_service_config = create_storage_service_config();
+    let (mut mock_client, mut service, _, _, _) =
+        MockClient::new(Some(db_reader), Some(storage_service_config));
+    let response_pre_serializer = create_response_pre_serializer(&service);
+
+    // Handle the first summary update and verify nothing is pre-serialized
+    utils::update_storage_server_summary(&mut service, known_version, 10);
+    response_pre_serializer
+        .handle_storage_summary_update(&service.cached_storage_server_summary.load());
+    assert_eq!(service.pre_serialized_responses.num_responses(), 0);
+
+    // Handle the summary update for the new data and verify the responses are cached
+    utils::update_storage_server_summary(&mut service, target_version, 10);
+    response_pre_serializer
+        .handle_storage_summary_update(&service.cached_storage_server_summary.load());
+    assert_eq!(service.pre_serialized_responses.num_responses(), 3);
+    let transactions_request = StorageServiceRequest::new(
+        DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+            proof_version: target_version,
+            start_version,
+            end_version: target_version,
+            include_events: false,
+        }),
+        true,
+    );
+    assert!(service
+        .lru_response_cache
+        .contains_key(&transactions_request));
+
+    // Handle a duplicate summary update and verify nothing is re-serialized
+    response_pre_serializer
+        .handle_storage_summary_update(&service.cached_storage_server_summary.load());
+    assert_eq!(service.pre_serialized_responses.num_responses(), 3);
+
+    // Fetch the new transactions (twice) and verify the pre-serialized response is served
+    let pre_serialized_responses = service.pre_serialized_responses.clone();
+    tokio::spawn(service.start());
+    for _ in 0..2 {
+        let response = utils::get_transactions_with_proof(
+            &mut mock_client,
+            start_version,
+            target_version,
+            target_version,
+            false,
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(response.is_compressed());
+        match response.get_data_response().unwrap() {
+            DataResponse::TransactionsWithProof(transactions_with_proof) => {
+                assert_eq!(transactions_with_proof, transaction_list_with_proof)
+            },
+            data_response => panic!(
+                "Expected transactions with proof but got: {:?}",
+                data_response
+            ),
+        }
+    }
+
+    // Verify the response is only counted as served once (the second
+    // request would have hit the cache even without pre-serialization).
+    assert_eq!(pre_serialized_responses.num_served_responses(), 1);
+}
+
+#[tokio::test]
+async fn test_pre_serialization_skips_cached_responses() {
+    // Create test data
+    let known_version = 100;
+    let target_version = 150;
+    let start_version = known_version + 1;
+    let num_versions = target_version - known_version;
+    let transaction_list_with_proof = utils::create_transaction_list_with_proof(
+        start_version,
+        target_version,
+        target_version,
+        false,
+    );
+    let output_list_with_proof =
+        utils::create_output_list_with_proof(start_version, target_version, target_version);
+
+    // Create the mock db reader. The transactions should never be fetched.
+    let mut db_reader = mock::create_mock_db_reader();
+    db_reader
+        .expect_get_transaction_outputs()
+        .times(2)
+        .with(eq(start_version), eq(num_versions), eq(target_version))
+        .returning(move |_, _, _| Ok(output_list_with_proof.clone()));
+
+    // Create the storage client and server
+    let storage_service_config = create_storage_service_config();
+    let (_, mut service, _, _, _) = MockClient::new(Some(db_reader), Some(storage_service_config));
+    let response_pre_serializer = create_response_pre_serializer(&service);
+
+    // Cache the response for the new transactions (e.g., as if an optimistic fetch was served)
+    let transactions_request = StorageServiceRequest::new(
+        DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+            proof_version: target_version,
+            start_version,
+            end_version: target_version,
+            include_events: false,
+        }),
+        true,
+    );
+    let transactions_response = StorageServiceResponse::new(
+        DataResponse::TransactionsWithProof(transaction_list_with_proof),
+        true,
+    )
+    .unwrap();
+    service
+        .lru_response_cache
+        .insert(transactions_request, transactions_response);
+
+    // Handle the summary updates and verify the cached response isn't pre-serialized
+    utils::update_storage_server_summary(&mut service, known_version, 10);
+    response_pre_serializer
+        .handle_storage_summary_update(&service.cached_storage_server_summary.load());
+    utils::update_storage_server_summary(&mut service, target_version, 10);
+    response_pre_serializer
+        .handle_storage_summary_update(&service.cached_storage_server_summary.load());
+    assert_eq!(service.pre_serialized_responses.num_responses(), 2);
+}
+
+#[tokio::test]
+async fn test_pre_serialized_responses_removal() {
+    // Create test data
+    let known_version = 1000;
+    let target_version = 1010;
+    let start_version = known_version + 1;
+    let num_versions = target_version - known_version;
+    let transaction_list_with_proof = utils::create_transaction_list_with_proof(
+        start_version,
+        target_version,
+        target_version,
+        false,
+    );
+    let output_list_with_proof =
+        utils::create_output_list_with_proof(start_version, target_version, target_version);
+
+    // Create the mock db reader
+    let mut db_reader = mock::create_mock_db_reader();
+    utils::expect_get_transactions(
+        &mut db_reader,
+        start_version,
+        num_versions,
+        target_version,
+        false,
+        transaction_list_with_proof,
+    );
+    db_reader
+        .expect_get_transaction_outputs()
+        .times(2)
+        .with(eq(start_version), eq(num_versions), eq(target_version))
+        .returning(move |_, _, _| Ok(output_list_with_proof.clone()));
+
+    // Create the storage server and pre-serialize the responses for the new data
+    let storage_service_config = create_storage_service_config();
+    let (_, mut service, _, _, _) = MockClient::new(Some(db_reader), Some(storage_service_config));
+    let response_pre_serializer = create_response_pre_serializer(&service);
+    for version in [known_version, target_version] {
+        utils::update_storage_server_summary(&mut service, version, 10);
+        response_pre_serializer
+            .handle_storage_summary_update(&service.cached_storage_server_summary.load());
+    }
+    assert_eq!(service.pre_serialized_responses.num_responses(), 3);
+    assert_eq!(service.lru_response_cache.iter().count(), 3);
+
+    // Prune the data and verify the responses are invalidated (the highest
+    // synced version doesn't change, so nothing new is pre-serialized).
+    let storage_server_summary = create_storage_server_summary(target_version, start_version + 5);
+    response_pre_serializer.handle_storage_summary_update(&storage_server_summary);
+    assert_eq!(service.pre_serialized_responses.num_responses(), 0);
+    assert_eq!(service.lru_response_cache.iter().count(), 0);
+
+    // Skip far ahead and verify nothing is pre-serialized (the new data isn't
+    // hot). Note: the mock db would panic on any unexpected data fetches.
+    let max_hot_versions = storage_service_config
+        .response_pre_serialization
+        .max_hot_versions;
+    let highest_version = target_version + max_hot_versions + 1;
+    utils::update_storage_server_summary(&mut service, highest_version, 10);
+    response_pre_serializer
+        .handle_storage_summary_update(&service.cached_storage_server_summary.load());
+    assert_eq!(service.pre_serialized_responses.num_responses(), 0);
+}
+
+/// Creates a storage service config with pre-serialization enabled
+fn create_storage_service_config() -> StorageServiceConfig {
+    StorageServiceConfig {
+        response_pre_serialization: ResponsePreSerializationConfig {
+            enable_pre_serialization: true,
+            max_hot_versions: 100,
+        },
+        ..Default::default()
+    }
+}
+
+/// Creates a response pre-serializer using the components of the given server
+fn create_response_pre_serializer(
+    service: &StorageServiceServer<StorageReader>,
+) -> ResponsePreSerializer<StorageReader> {
+    let handler = Handler::new(
+        service.cached_storage_server_summary.clone(),
+        service.optimistic_fetches.clone(),
+        service.lru_response_cache.clone(),
+        service.compression_manager.clone(),
+        service.request_moderator.clone(),
+        service.storage.clone(),
+        service.subscriptions.clone(),
+        service.time_service.clone(),
+    );
+    ResponsePreSerializer::new(
+        service.storage_service_config,
+        handler,
+        service.lru_response_cache.clone(),
+        service.pre_serialized_responses.clone(),
+    )
+}
+
+/// Creates a storage server summary with the given highest
+/// synced version and lowest (i.e., non-pruned) data version.
+fn create_storage_server_summary(
+    highest_synced_version: u64,
+    lowest_version: u64,
+) -> StorageServerSummary {
+    let mut storage_server_summary = StorageServerSummary::default();
+    let data_summary = &mut storage_server_summary.data_summary;
+    data_summary.synced_ledger_info = Some(utils::create_epoch_ending_ledger_info(
+        10,
+        highest_synced_version,
+    ));
+    let data_range = CompleteDataRange::new(lowest_version, highest_synced_version).unwrap();
+    data_summary.transactions = Some(data_range);
+    data_summary.transaction_outputs = Some(data_range);
+    storage_server_summary
+}
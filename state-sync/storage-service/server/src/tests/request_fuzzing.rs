@@ -0,0 +1,294 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::tests::{
+    mock,
+    mock::{MockClient, MockDatabaseReader},
+    utils,
+};
+use aptos_storage_interface::AptosDbError;
+use aptos_storage_service_types::{
+    requests::{
+        DataRequest, EpochEndingLedgerInfoRequest, LatestLedgerInfoWithEpochProofRequest,
+        StateValuesWithProofRequest, StorageServerSummaryUpdateRequest, StorageServiceRequest,
+        TransactionOutputsWithProofRequest, TransactionsOrOutputsWithProofRequest,
+        TransactionsWithProofRequest,
+    },
+    responses::{CompleteDataRange, ProtocolMetadata},
+    StorageServiceError,
+};
+use claims::{assert_err, assert_matches, assert_ok};
+use proptest::{
+    arbitrary::any,
+    collection::vec,
+    option,
+    prelude::*,
+    sample::{select, Index},
+};
+
+// The highest synced version and epoch advertised by the storage server
+const HIGHEST_SYNCED_VERSION: u64 = 1000;
+const HIGHEST_SYNCED_EPOCH: u64 = 10;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_adversarial_requests(
+        requests in vec((arb_data_request(), any::<bool>()), 1..16)
+    ) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(process_adversarial_requests(requests));
+    }
+
+    #[test]
+    fn test_corrupted_request_deserialization(
+        request in arb_data_request(),
+        use_compression in any::<bool>(),
+        corruptions in vec((any::<Index>(), any::<u8>()), 0..4),
+        truncation in option::of(any::<Index>()),
+    ) {
+        // Serialize the request and corrupt the bytes
+        let request = StorageServiceRequest::new(request, use_compression);
+        let mut request_bytes = bcs::to_bytes(&request).unwrap();
+        for (index, byte) in corruptions {
+            let index = index.index(request_bytes.len());
+            request_bytes[index] = byte;
+        }
+        if let Some(truncation) = truncation {
+            request_bytes.truncate(truncation.index(request_bytes.len()));
+        }
+
+        // Verify that deserialization doesn't panic, and that any
+        // successfully deserialized request is canonical.
+        if let Ok(request) = bcs::from_bytes::<StorageServiceRequest>(&request_bytes) {
+            prop_assert_eq!(bcs::to_bytes(&request).unwrap(), request_bytes);
+        }
+    }
+
+    #[test]
+    fn test_random_request_deserialization(request_bytes in vec(any::<u8>(), 0..256)) {
+        // Verify that deserialization doesn't panic
+        let _ = bcs::from_bytes::<StorageServiceRequest>(&request_bytes);
+    }
+}
+
+/// Sends the given requests to a storage server (backed by a database that
+/// fails all reads) and verifies that each request is handled gracefully,
+/// i.e., the server responds with a typed error and doesn't panic. Note: if
+/// the server panics, the response sender is dropped and the client fails.
+async fn process_adversarial_requests(requests: Vec<(DataRequest, bool)>) {
+    // Create the storage client and server
+    let (mut mock_client, mut service, _, _, _) =
+        MockClient::new(Some(create_failing_mock_db_reader()), None);
+    utils::update_storage_server_summary(
+        &mut service,
+        HIGHEST_SYNCED_VERSION,
+        HIGHEST_SYNCED_EPOCH,
+    );
+    tokio::spawn(service.start());
+
+    // Process each request and verify the response
+    for (data_request, use_compression) in requests {
+        let response =
+            utils::send_storage_request(&mut mock_client, use_compression, data_request.clone())
+                .await;
+        match &data_request {
+            DataRequest::GetServerProtocolVersion
+            | DataRequest::GetStorageServerSummary
+            | DataRequest::GetStorageServerSummaryUpdate(_) => {
+                // These requests don't touch the database and should always succeed
+                assert_ok!(response);
+            },
+            data_request if has_degenerate_range(data_request) => {
+                assert_matches!(response, Err(StorageServiceError::InvalidRequest(_)));
+            },
+            _ => {
+                // All database reads fail, so all other requests should fail
+                assert_err!(response);
+            },
+        }
+    }
+}
+
+/// Returns true iff the data request contains a degenerate range (i.e.,
+/// the start of the range is greater than the end).
+fn has_degenerate_range(data_request: &DataRequest) -> bool {
+    match data_request {
+        DataRequest::GetEpochEndingLedgerInfos(request) => {
+            request.start_epoch > request.expected_end_epoch
+        },
+        DataRequest::GetStateValuesWithProof(request) => request.start_index > request.end_index,
+        DataRequest::GetTransactionOutputsWithProof(request) => {
+            request.start_version > request.end_version
+        },
+        DataRequest::GetTransactionsWithProof(request) => {
+            request.start_version > request.end_version
+        },
+        DataRequest::GetTransactionsOrOutputsWithProof(request) => {
+            request.start_version > request.end_version
+        },
+        _ => false,
+    }
+}
+
+/// Creates a mock database reader that fails all data reads
+fn create_failing_mock_db_reader() -> MockDatabaseReader {
+    let mut db_reader = mock::create_mock_db_reader();
+    db_reader
+        .expect_get_epoch_ending_ledger_infos()
+        .returning(|_, _| Err(create_db_error()));
+    db_reader
+        .expect_get_latest_ledger_info()
+        .returning(|| Err(create_db_error()));
+    db_reader
+        .expect_get_state_leaf_count()
+        .returning(|_| Err(create_db_error()));
+    db_reader
+        .expect_get_state_value_chunk_with_proof()
+        .returning(|_, _, _| Err(create_db_error()));
+    db_reader
+        .expect_get_transaction_outputs()
+        .returning(|_, _, _| Err(create_db_error()));
+    db_reader
+        .expect_get_transactions()
+        .returning(|_, _, _, _| Err(create_db_error()));
+    db_reader
+}
+
+/// Creates a database error for the failing mock database reader
+fn create_db_error() -> AptosDbError {
+    AptosDbError::NotFound("Fuzzed data".into())
+}
+
+/// Returns a strategy for versions, epochs and indices that favours edge
+/// cases, e.g., values around the advertised data range and the u64 bounds.
+fn arb_u64() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        select(vec![
+            0,
+            1,
+            HIGHEST_SYNCED_EPOCH,
+            HIGHEST_SYNCED_VERSION,
+            HIGHEST_SYNCED_VERSION + 1,
+            u64::MAX - 1,
+            u64::MAX,
+        ]),
+        0..=(HIGHEST_SYNCED_VERSION + 1),
+        any::<u64>(),
+    ]
+}
+
+/// Returns a strategy for optional (non-degenerate) complete data ranges
+fn arb_data_range() -> impl Strategy<Value = Option<CompleteDataRange<u64>>> {
+    option::of(
+        (arb_u64(), arb_u64()).prop_filter_map("degenerate range", |(lowest, highest)| {
+            CompleteDataRange::new(lowest, highest).ok()
+        }),
+    )
+}
+
+/// Returns a strategy for adversarial (one-shot) data requests. Note: optimistic
+/// fetch and subscription requests are excluded, as the server may (correctly)
+/// never respond to them.
+fn arb_data_request() -> impl Strategy<Value = DataRequest> {
+    prop_oneof![
+        (arb_u64(), arb_u64()).prop_map(|(start_epoch, expected_end_epoch)| {
+            DataRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+                start_epoch,
+                expected_end_epoch,
+            })
+        }),
+        arb_u64().prop_map(|trusted_epoch| {
+            DataRequest::GetLatestLedgerInfoWithEpochProof(LatestLedgerInfoWithEpochProofRequest {
+                trusted_epoch,
+            })
+        }),
+        arb_u64().prop_map(DataRequest::GetNumberOfStatesAtVersion),
+        Just(DataRequest::GetServerProtocolVersion),
+        (arb_u64(), arb_u64(), arb_u64()).prop_map(|(version, start_index, end_index)| {
+            DataRequest::GetStateValuesWithProof(StateValuesWithProofRequest {
+                version,
+                start_index,
+                end_index,
+            })
+        }),
+        Just(DataRequest::GetStorageServerSummary),
+        (
+            arb_u64(),
+            arb_u64(),
+            arb_u64(),
+            arb_u64(),
+            option::of(arb_u64()),
+            arb_data_range(),
+            arb_data_range(),
+            arb_data_range(),
+            arb_data_range(),
+        )
+            .prop_map(
+                |(
+                    max_epoch_chunk_size,
+                    max_state_chunk_size,
+                    max_transaction_chunk_size,
+                    max_transaction_output_chunk_size,
+                    known_synced_ledger_info_version,
+                    known_epoch_ending_ledger_infos,
+                    known_states,
+                    known_transactions,
+                    known_transaction_outputs,
+                )| {
+                    DataRequest::GetStorageServerSummaryUpdate(StorageServerSummaryUpdateRequest {
+                        known_protocol_metadata: ProtocolMetadata {
+                            max_epoch_chunk_size,
+                            max_state_chunk_size,
+                            max_transaction_chunk_size,
+                            max_transaction_output_chunk_size,
+                        },
+                        known_synced_ledger_info_version,
+                        known_epoch_ending_ledger_infos,
+                        known_states,
+                        known_transactions,
+                        known_transaction_outputs,
+                    })
+                }
+            ),
+        (arb_u64(), arb_u64(), arb_u64()).prop_map(
+            |(proof_version, start_version, end_version)| {
+                DataRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
+                    proof_version,
+                    start_version,
+                    end_version,
+                })
+            }
+        ),
+        (arb_u64(), arb_u64(), arb_u64(), any::<bool>()).prop_map(
+            |(proof_version, start_version, end_version, include_events)| {
+                DataRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+                    proof_version,
+                    start_version,
+                    end_version,
+                    include_events,
+                })
+            }
+        ),
+        (arb_u64(), arb_u64(), arb_u64(), any::<bool>(), arb_u64()).prop_map(
+            |(
+                proof_version,
+                start_version,
+                end_version,
+                include_events,
+                max_num_output_reductions,
+            )| {
+                DataRequest::GetTransactionsOrOutputsWithProof(
+                    TransactionsOrOutputsWithProofRequest {
+                        proof_version,
+                        start_version,
+                        end_version,
+                        include_events,
+                        max_num_output_reductions,
+                    },
+                )
+            }
+        ),
+    ]
+}
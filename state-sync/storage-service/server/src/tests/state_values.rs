@@ -9,8 +9,8 @@ use crate::tests::{
 use aptos_config::config::StorageServiceConfig;
 use aptos_crypto::hash::HashValue;
 use aptos_storage_service_types::{
-    requests::{DataRequest, StateValuesWithProofRequest},
-    responses::{DataResponse, StorageServiceResponse},
+    requests::{DataRequest, StateValuesWithIncrementalProofRequest, StateValuesWithProofRequest},
+    responses::{DataResponse, StateValueChunkWithIncrementalProof, StorageServiceResponse},
     StorageServiceError,
 };
 use aptos_types::{
@@ -125,6 +125,80 @@ async fn test_get_states_with_proof_chunk_limit() {
     );
 }
 
+#[tokio::test]
+async fn test_get_states_with_incremental_proof() {
+    // Create two keys that share the top 2 right siblings (i.e., the
+    // common prefix of the keys is 001, which contains 2 zero bits).
+    let mut known_last_key_bytes = [0; HashValue::LENGTH];
+    known_last_key_bytes[0] = 0b0010_0000;
+    let known_last_key = HashValue::new(known_last_key_bytes);
+    let mut last_key_bytes = [0; HashValue::LENGTH];
+    last_key_bytes[0] = 0b0011_0000;
+    let last_key = HashValue::new(last_key_bytes);
+
+    // Create test data
+    let version = 101;
+    let start_index = 100;
+    let end_index = 199;
+    let right_siblings: Vec<_> = (0..5).map(|_| HashValue::random()).collect();
+    let state_value_chunk_with_proof = StateValueChunkWithProof {
+        first_index: start_index,
+        last_index: end_index,
+        first_key: HashValue::random(),
+        last_key,
+        raw_values: vec![],
+        proof: SparseMerkleRangeProof::new(right_siblings.clone()),
+        root_hash: HashValue::random(),
+    };
+
+    // Create the mock db reader
+    let mut db_reader = mock::create_mock_db_reader();
+    expect_get_state_values_with_proof(
+        &mut db_reader,
+        version,
+        start_index,
+        end_index - start_index + 1,
+        state_value_chunk_with_proof.clone(),
+    );
+
+    // Create the storage client and server
+    let (mut mock_client, mut service, _, _, _) = MockClient::new(Some(db_reader), None);
+    utils::update_storage_server_summary(&mut service, version, 10);
+    tokio::spawn(service.start());
+
+    // Process a request to fetch a states chunk with an incremental proof
+    let data_request =
+        DataRequest::GetStateValuesWithIncrementalProof(StateValuesWithIncrementalProofRequest {
+            version,
+            start_index,
+            end_index,
+            known_last_key,
+        });
+    let response = utils::send_storage_request(&mut mock_client, false, data_request)
+        .await
+        .unwrap();
+
+    // Verify that the shared right siblings were removed from the proof
+    let incremental_chunk = match response.get_data_response().unwrap() {
+        DataResponse::StateValueChunkWithIncrementalProof(incremental_chunk) => incremental_chunk,
+        data_response => panic!("Unexpected data response: {:?}", data_response),
+    };
+    assert_eq!(
+        incremental_chunk,
+        StateValueChunkWithIncrementalProof::new(
+            state_value_chunk_with_proof.clone(),
+            known_last_key
+        )
+    );
+    assert_eq!(incremental_chunk.num_shared_right_siblings, 2);
+    assert_eq!(
+        incremental_chunk
+            .into_state_value_chunk_with_proof(&right_siblings[3..])
+            .unwrap(),
+        state_value_chunk_with_proof
+    );
+}
+
 #[tokio::test]
 async fn test_get_states_with_proof_invalid() {
     // Create the storage client and server
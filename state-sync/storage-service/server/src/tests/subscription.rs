@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    compression::CompressionManager,
     error::Error,
     moderator::RequestModerator,
     network::ResponseSender,
@@ -77,6 +78,9 @@ async fn test_peers_with_ready_subscriptions() {
         Arc::new(ArcSwap::from(Arc::new(StorageServerSummary::default())));
     let optimistic_fetches = Arc::new(DashMap::new());
     let lru_response_cache = Cache::new(0);
+    let compression_manager = Arc::new(CompressionManager::new(
+        storage_service_config.adaptive_compression,
+    ));
     let request_moderator = Arc::new(RequestModerator::new(
         AptosDataClientConfig::default(),
         cached_storage_server_summary.clone(),
@@ -92,6 +96,7 @@ async fn test_peers_with_ready_subscriptions() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -112,6 +117,7 @@ async fn test_peers_with_ready_subscriptions() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -138,6 +144,7 @@ async fn test_peers_with_ready_subscriptions() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -164,6 +171,7 @@ async fn test_peers_with_ready_subscriptions() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -196,6 +204,9 @@ async fn test_remove_expired_subscriptions_no_new_data() {
         Arc::new(ArcSwap::from(Arc::new(StorageServerSummary::default())));
     let optimistic_fetches = Arc::new(DashMap::new());
     let lru_response_cache = Cache::new(0);
+    let compression_manager = Arc::new(CompressionManager::new(
+        storage_service_config.adaptive_compression,
+    ));
     let request_moderator = Arc::new(RequestModerator::new(
         AptosDataClientConfig::default(),
         cached_storage_server_summary.clone(),
@@ -229,6 +240,7 @@ async fn test_remove_expired_subscriptions_no_new_data() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -259,6 +271,7 @@ async fn test_remove_expired_subscriptions_no_new_data() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -279,6 +292,7 @@ async fn test_remove_expired_subscriptions_no_new_data() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -327,6 +341,9 @@ async fn test_remove_expired_subscriptions_blocked_stream() {
         Arc::new(ArcSwap::from(Arc::new(StorageServerSummary::default())));
     let optimistic_fetches = Arc::new(DashMap::new());
     let lru_response_cache = Cache::new(0);
+    let compression_manager = Arc::new(CompressionManager::new(
+        storage_service_config.adaptive_compression,
+    ));
     let request_moderator = Arc::new(RequestModerator::new(
         AptosDataClientConfig::default(),
         cached_storage_server_summary.clone(),
@@ -349,6 +366,7 @@ async fn test_remove_expired_subscriptions_blocked_stream() {
         storage_service_config,
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -384,6 +402,7 @@ async fn test_remove_expired_subscriptions_blocked_stream() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -427,6 +446,9 @@ async fn test_remove_expired_subscriptions_blocked_stream_index() {
         Arc::new(ArcSwap::from(Arc::new(StorageServerSummary::default())));
     let optimistic_fetches = Arc::new(DashMap::new());
     let lru_response_cache = Cache::new(0);
+    let compression_manager = Arc::new(CompressionManager::new(
+        storage_service_config.adaptive_compression,
+    ));
     let request_moderator = Arc::new(RequestModerator::new(
         AptosDataClientConfig::default(),
         cached_storage_server_summary.clone(),
@@ -450,6 +472,7 @@ async fn test_remove_expired_subscriptions_blocked_stream_index() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -472,6 +495,7 @@ async fn test_remove_expired_subscriptions_blocked_stream_index() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -511,6 +535,7 @@ async fn test_remove_expired_subscriptions_blocked_stream_index() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -540,6 +565,7 @@ async fn test_remove_expired_subscriptions_blocked_stream_index() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
@@ -559,6 +585,7 @@ async fn test_remove_expired_subscriptions_blocked_stream_index() {
         cached_storage_server_summary.clone(),
         optimistic_fetches.clone(),
         lru_response_cache.clone(),
+        compression_manager.clone(),
         request_moderator.clone(),
         storage_reader.clone(),
         subscriptions.clone(),
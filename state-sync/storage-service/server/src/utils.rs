@@ -2,11 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    error::Error, handler::Handler, metrics, moderator::RequestModerator, network::ResponseSender,
-    optimistic_fetch::OptimisticFetchRequest, storage::StorageReaderInterface,
-    subscription::SubscriptionStreamRequests,
+    compression::CompressionManager, error::Error, handler::Handler, metrics,
+    moderator::RequestModerator, network::ResponseSender, optimistic_fetch::OptimisticFetchRequest,
+    storage::StorageReaderInterface, subscription::SubscriptionStreamRequests,
 };
-use aptos_config::network_id::PeerNetworkId;
+use aptos_config::{config::StorageServiceConfig, network_id::PeerNetworkId};
 use aptos_metrics_core::HistogramVec;
 use aptos_storage_service_types::{
     requests::{DataRequest, EpochEndingLedgerInfoRequest, StorageServiceRequest},
@@ -20,6 +20,31 @@ use mini_moka::sync::Cache;
 use once_cell::sync::Lazy;
 use std::{sync::Arc, time::Instant};
 
+/// Creates the LRU response cache. The cache is bounded by both the
+/// maximum number of bytes and the maximum number of items: every entry
+/// weighs at least its share of the byte budget, so no more than the
+/// maximum number of items can ever fit.
+pub fn create_lru_response_cache(
+    storage_service_config: &StorageServiceConfig,
+) -> Cache<StorageServiceRequest, StorageServiceResponse> {
+    let max_lru_cache_bytes = storage_service_config.max_lru_cache_bytes;
+    let max_lru_cache_size = storage_service_config.max_lru_cache_size;
+    if max_lru_cache_size == 0 {
+        return Cache::new(0); // The cache is disabled
+    }
+
+    // Calculate the minimum weight of each entry
+    let min_entry_weight = (max_lru_cache_bytes / max_lru_cache_size).clamp(1, u32::MAX as u64);
+    Cache::builder()
+        .max_capacity(max_lru_cache_bytes)
+        .weigher(move |_, response: &StorageServiceResponse| {
+            get_response_size_bytes(response)
+                .max(min_entry_weight)
+                .min(u32::MAX as u64) as u32
+        })
+        .build()
+}
+
 /// Gets the epoch ending ledger info at the given epoch
 pub fn get_epoch_ending_ledger_info<T: StorageReaderInterface>(
     cached_storage_server_summary: Arc<ArcSwap<StorageServerSummary>>,
@@ -27,6 +52,7 @@ pub fn get_epoch_ending_ledger_info<T: StorageReaderInterface>(
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
     epoch: u64,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     peer_network_id: &PeerNetworkId,
     storage: T,
@@ -47,6 +73,7 @@ pub fn get_epoch_ending_ledger_info<T: StorageReaderInterface>(
         cached_storage_server_summary,
         optimistic_fetches,
         lru_response_cache,
+        compression_manager,
         request_moderator,
         storage,
         subscriptions,
@@ -88,6 +115,7 @@ pub fn notify_peer_of_new_data<T: StorageReaderInterface>(
     optimistic_fetches: Arc<DashMap<PeerNetworkId, OptimisticFetchRequest>>,
     subscriptions: Arc<DashMap<PeerNetworkId, SubscriptionStreamRequests>>,
     lru_response_cache: Cache<StorageServiceRequest, StorageServiceResponse>,
+    compression_manager: Arc<CompressionManager>,
     request_moderator: Arc<RequestModerator>,
     storage: T,
     time_service: TimeService,
@@ -97,11 +125,11 @@ pub fn notify_peer_of_new_data<T: StorageReaderInterface>(
     response_sender: ResponseSender,
 ) -> aptos_storage_service_types::Result<DataResponse, Error> {
     // Handle the storage service request to fetch the missing data
-    let use_compression = missing_data_request.use_compression;
     let handler = Handler::new(
         cached_storage_server_summary,
         optimistic_fetches,
         lru_response_cache,
+        compression_manager.clone(),
         request_moderator,
         storage,
         subscriptions,
@@ -161,16 +189,19 @@ pub fn notify_peer_of_new_data<T: StorageReaderInterface>(
     };
 
     // Create the storage service response
-    let storage_response =
-        match StorageServiceResponse::new(transformed_data_response.clone(), use_compression) {
-            Ok(storage_response) => storage_response,
-            Err(error) => {
-                return Err(Error::UnexpectedErrorEncountered(format!(
-                    "Failed to create transformed response! Error: {:?}",
-                    error
-                )));
-            },
-        };
+    let storage_response = match compression_manager.create_response(
+        peer_network_id,
+        transformed_data_response.clone(),
+        missing_data_request.get_accepted_compression_codecs(),
+    ) {
+        Ok(storage_response) => storage_response,
+        Err(error) => {
+            return Err(Error::UnexpectedErrorEncountered(format!(
+                "Failed to create transformed response! Error: {:?}",
+                error
+            )));
+        },
+    };
 
     // Send the response to the peer
     handler.send_response(missing_data_request, Ok(storage_response), response_sender);
@@ -178,6 +209,19 @@ pub fn notify_peer_of_new_data<T: StorageReaderInterface>(
     Ok(transformed_data_response)
 }
 
+/// Returns the (serialized and possibly compressed) size of the response
+pub fn get_response_size_bytes(response: &StorageServiceResponse) -> u64 {
+    match response {
+        StorageServiceResponse::CompressedResponse(_, compressed_data)
+        | StorageServiceResponse::CodecCompressedResponse(_, _, compressed_data) => {
+            compressed_data.len() as u64
+        },
+        StorageServiceResponse::RawResponse(data_response) => {
+            bcs::serialized_size(data_response).unwrap_or(0) as u64
+        },
+    }
+}
+
 /// An utility that calls and times the given function. The metric histogram
 /// is updated with the given labels (e.g., peer, request result and duration).
 /// If no start time is specified, the timer begins before calling the function.
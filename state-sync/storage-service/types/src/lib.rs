@@ -4,6 +4,7 @@
 
 #![forbid(unsafe_code)]
 
+use aptos_types::ledger_info::LedgerInfoWithSignatures;
 use requests::StorageServiceRequest;
 use responses::StorageServiceResponse;
 use serde::{Deserialize, Serialize};
@@ -46,4 +47,7 @@ pub enum StorageServiceMessage {
     /// A response from the storage service. If there was an error while handling
     /// the request, the service will return an [`StorageServiceError`] error.
     Response(Result<StorageServiceResponse>),
+    /// A notification of the latest ledger info committed by the sender (e.g.,
+    /// sent by validators and VFNs to downstream peers via direct send).
+    CommitNotification(LedgerInfoWithSignatures),
 }
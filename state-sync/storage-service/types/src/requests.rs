@@ -1,7 +1,12 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::COMPRESSION_SUFFIX_LABEL;
+use crate::{
+    responses::{CompleteDataRange, ProtocolMetadata, StorageServerSummary},
+    Epoch, COMPRESSION_SUFFIX_LABEL,
+};
+use aptos_compression::codec::CompressionCodec;
+use aptos_crypto::HashValue;
 use aptos_types::transaction::Version;
 use serde::{Deserialize, Serialize};
 
@@ -28,6 +33,27 @@ impl StorageServiceRequest {
         }
         label
     }
+
+    /// Returns the compression codecs accepted by the client (in order of
+    /// preference). Clients that only set `use_compression` accept LZ4.
+    pub fn get_accepted_compression_codecs(&self) -> &[CompressionCodec] {
+        if !self.use_compression {
+            return &[];
+        }
+        match &self.data_request {
+            DataRequest::GetWithAcceptedCompression(codecs, _) => codecs,
+            _ => &[CompressionCodec::Lz4],
+        }
+    }
+
+    /// Returns true iff the client accepts responses compressed with the
+    /// given codec (or uncompressed responses, if no codec is given).
+    pub fn accepts_compression_codec(&self, codec: Option<CompressionCodec>) -> bool {
+        match codec {
+            Some(codec) => self.get_accepted_compression_codecs().contains(&codec),
+            None => true,
+        }
+    }
 }
 
 /// A single data request.
@@ -47,6 +73,10 @@ pub enum DataRequest {
     SubscribeTransactionOutputsWithProof(SubscribeTransactionOutputsWithProofRequest), // Subscribes to transaction outputs with a proof
     SubscribeTransactionsOrOutputsWithProof(SubscribeTransactionsOrOutputsWithProofRequest), // Subscribes to transactions or outputs with a proof
     SubscribeTransactionsWithProof(SubscribeTransactionsWithProofRequest), // Subscribes to transactions with a proof
+    GetLatestLedgerInfoWithEpochProof(LatestLedgerInfoWithEpochProofRequest), // Fetches the latest ledger info with an epoch change proof
+    GetStorageServerSummaryUpdate(StorageServerSummaryUpdateRequest), // Fetches the changes to the storage server summary since the known summary
+    GetStateValuesWithIncrementalProof(StateValuesWithIncrementalProofRequest), // Fetches a list of states with a proof relative to a known chunk
+    GetWithAcceptedCompression(Vec<CompressionCodec>, Box<DataRequest>), // Fetches the data of the inner request, compressed with an accepted codec
 }
 
 impl DataRequest {
@@ -73,9 +103,38 @@ impl DataRequest {
                 "subscribe_transactions_or_outputs_with_proof"
             },
             Self::SubscribeTransactionsWithProof(_) => "subscribe_transactions_with_proof",
+            Self::GetLatestLedgerInfoWithEpochProof(_) => "get_latest_ledger_info_with_epoch_proof",
+            Self::GetStorageServerSummaryUpdate(_) => "get_storage_server_summary_update",
+            Self::GetStateValuesWithIncrementalProof(_) => {
+                "get_state_values_with_incremental_proof"
+            },
+            Self::GetWithAcceptedCompression(_, _) => "get_with_accepted_compression",
         }
     }
 
+    /// Returns the inner data request (if the request is wrapped
+    /// for accepted compression), or the request itself.
+    pub fn get_inner_data_request(&self) -> &DataRequest {
+        match self {
+            Self::GetWithAcceptedCompression(_, data_request) => {
+                data_request.get_inner_data_request()
+            },
+            _ => self,
+        }
+    }
+
+    /// Returns true iff the compression codec can be negotiated for the
+    /// request (i.e., the request can be wrapped with the accepted codecs).
+    /// Only regular data requests (that are served and cached immediately)
+    /// support negotiation. Other requests are compressed with LZ4.
+    pub fn is_compression_negotiable(&self) -> bool {
+        !(self.is_optimistic_fetch()
+            || self.is_protocol_version_request()
+            || self.is_storage_summary_request()
+            || self.is_subscription_request()
+            || matches!(self, Self::GetWithAcceptedCompression(_, _)))
+    }
+
     pub fn is_optimistic_fetch(&self) -> bool {
         matches!(self, &Self::GetNewTransactionOutputsWithProof(_))
             || matches!(self, &Self::GetNewTransactionsWithProof(_))
@@ -88,6 +147,7 @@ impl DataRequest {
 
     pub fn is_storage_summary_request(&self) -> bool {
         matches!(self, &Self::GetStorageServerSummary)
+            || matches!(self, &Self::GetStorageServerSummaryUpdate(_))
     }
 
     pub fn is_subscription_request(&self) -> bool {
@@ -104,6 +164,41 @@ pub struct EpochEndingLedgerInfoRequest {
     pub expected_end_epoch: u64, // The epoch to finish at
 }
 
+/// A storage service request for fetching the latest ledger info together
+/// with an epoch change proof that skips from the trusted epoch to the latest
+/// epoch. This allows light clients to sync trust in a single round trip.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct LatestLedgerInfoWithEpochProofRequest {
+    pub trusted_epoch: u64, // The epoch the client already trusts
+}
+
+/// A storage service request for fetching the changes to the storage server
+/// summary, relative to the summary already known by the client. The known
+/// synced ledger info is identified by its version (to keep requests small).
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct StorageServerSummaryUpdateRequest {
+    pub known_protocol_metadata: ProtocolMetadata, // The known protocol metadata
+    pub known_synced_ledger_info_version: Option<Version>, // The version of the known synced ledger info
+    pub known_epoch_ending_ledger_infos: Option<CompleteDataRange<Epoch>>, // The known epoch ending ledger info range
+    pub known_states: Option<CompleteDataRange<Version>>,                  // The known state range
+    pub known_transactions: Option<CompleteDataRange<Version>>, // The known transaction range
+    pub known_transaction_outputs: Option<CompleteDataRange<Version>>, // The known transaction output range
+}
+
+impl StorageServerSummaryUpdateRequest {
+    pub fn new(known_summary: &StorageServerSummary) -> Self {
+        let known_data_summary = &known_summary.data_summary;
+        Self {
+            known_protocol_metadata: known_summary.protocol_metadata.clone(),
+            known_synced_ledger_info_version: known_data_summary.get_synced_ledger_info_version(),
+            known_epoch_ending_ledger_infos: known_data_summary.epoch_ending_ledger_infos,
+            known_states: known_data_summary.states,
+            known_transactions: known_data_summary.transactions,
+            known_transaction_outputs: known_data_summary.transaction_outputs,
+        }
+    }
+}
+
 /// A storage service request for fetching a new transaction output list
 /// beyond the already known version and epoch.
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
@@ -130,6 +225,19 @@ pub struct StateValuesWithProofRequest {
     pub end_index: u64,   // The index to stop fetching state values (inclusive)
 }
 
+/// A storage service request for fetching a list of state values at a
+/// specified version, where the proof omits the right siblings that are
+/// shared with the proof of a state value chunk already known by the client
+/// (at the same version). This allows clients that stream state value chunks
+/// to avoid re-fetching the top of the proof for every chunk.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct StateValuesWithIncrementalProofRequest {
+    pub version: u64,              // The version to fetch the state values at
+    pub start_index: u64,          // The index to start fetching state values (inclusive)
+    pub end_index: u64,            // The index to stop fetching state values (inclusive)
+    pub known_last_key: HashValue, // The last hashed state key of the chunk known by the client
+}
+
 /// A storage service request for fetching a transaction output list with a
 /// corresponding proof.
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
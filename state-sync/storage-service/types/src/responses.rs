@@ -2,25 +2,32 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    requests::DataRequest::{
-        GetEpochEndingLedgerInfos, GetNewTransactionOutputsWithProof,
-        GetNewTransactionsOrOutputsWithProof, GetNewTransactionsWithProof,
-        GetNumberOfStatesAtVersion, GetServerProtocolVersion, GetStateValuesWithProof,
-        GetStorageServerSummary, GetTransactionOutputsWithProof, GetTransactionsOrOutputsWithProof,
-        GetTransactionsWithProof, SubscribeTransactionOutputsWithProof,
-        SubscribeTransactionsOrOutputsWithProof, SubscribeTransactionsWithProof,
+    requests::{
+        DataRequest::{
+            GetEpochEndingLedgerInfos, GetLatestLedgerInfoWithEpochProof,
+            GetNewTransactionOutputsWithProof, GetNewTransactionsOrOutputsWithProof,
+            GetNewTransactionsWithProof, GetNumberOfStatesAtVersion, GetServerProtocolVersion,
+            GetStateValuesWithIncrementalProof, GetStateValuesWithProof, GetStorageServerSummary,
+            GetStorageServerSummaryUpdate, GetTransactionOutputsWithProof,
+            GetTransactionsOrOutputsWithProof, GetTransactionsWithProof,
+            GetWithAcceptedCompression, SubscribeTransactionOutputsWithProof,
+            SubscribeTransactionsOrOutputsWithProof, SubscribeTransactionsWithProof,
+        },
+        StorageServerSummaryUpdateRequest,
     },
     responses::Error::DegenerateRangeError,
     Epoch, StorageServiceRequest, COMPRESSION_SUFFIX_LABEL,
 };
-use aptos_compression::{client::CompressionClient, CompressedData};
+use aptos_compression::{client::CompressionClient, codec::CompressionCodec, CompressedData};
 use aptos_config::config::{
     AptosDataClientConfig, StorageServiceConfig, MAX_APPLICATION_MESSAGE_SIZE,
 };
+use aptos_crypto::HashValue;
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::{
     epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
+    proof::SparseMerkleRangeProof,
     state_store::state_value::StateValueChunkWithProof,
     transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
 };
@@ -59,29 +66,50 @@ impl From<aptos_compression::Error> for Error {
 pub enum StorageServiceResponse {
     CompressedResponse(String, CompressedData), // Store the label and the data (e.g., for logging/metrics)
     RawResponse(DataResponse),
+    CodecCompressedResponse(String, CompressionCodec, CompressedData), // Store the label, codec and data (only used for non-LZ4 codecs)
 }
 
 impl StorageServiceResponse {
-    /// Creates a new response and performs compression if required
+    /// Creates a new response and performs (LZ4) compression if required
     pub fn new(data_response: DataResponse, perform_compression: bool) -> Result<Self, Error> {
-        if perform_compression {
-            // Serialize and compress the raw data
-            let raw_data = bcs::to_bytes(&data_response)
-                .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
-            let compressed_data = aptos_compression::compress(
-                raw_data,
-                CompressionClient::StateSync,
-                MAX_APPLICATION_MESSAGE_SIZE,
-            )?;
-
-            // Create the compressed response
-            let label = data_response.get_label().to_string() + COMPRESSION_SUFFIX_LABEL;
-            Ok(StorageServiceResponse::CompressedResponse(
+        let compression_codec = perform_compression.then_some(CompressionCodec::Lz4);
+        Self::new_with_compression_codec(data_response, compression_codec)
+    }
+
+    /// Creates a new response and performs compression
+    /// with the given codec (if one is specified).
+    pub fn new_with_compression_codec(
+        data_response: DataResponse,
+        compression_codec: Option<CompressionCodec>,
+    ) -> Result<Self, Error> {
+        let compression_codec = match compression_codec {
+            Some(compression_codec) => compression_codec,
+            None => return Ok(StorageServiceResponse::RawResponse(data_response)),
+        };
+
+        // Serialize and compress the raw data
+        let raw_data = bcs::to_bytes(&data_response)
+            .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+        let compressed_data = aptos_compression::compress_with_codec(
+            raw_data,
+            compression_codec,
+            CompressionClient::StateSync,
+            MAX_APPLICATION_MESSAGE_SIZE,
+        )?;
+
+        // Create the compressed response. LZ4 responses use the original
+        // variant, so that clients without codec support can decode them.
+        let label = data_response.get_label().to_string() + COMPRESSION_SUFFIX_LABEL;
+        match compression_codec {
+            CompressionCodec::Lz4 => Ok(StorageServiceResponse::CompressedResponse(
                 label,
                 compressed_data,
-            ))
-        } else {
-            Ok(StorageServiceResponse::RawResponse(data_response))
+            )),
+            compression_codec => Ok(StorageServiceResponse::CodecCompressedResponse(
+                label,
+                compression_codec,
+                compressed_data,
+            )),
         }
     }
 
@@ -99,6 +127,21 @@ impl StorageServiceResponse {
                 Ok(data_response)
             },
             StorageServiceResponse::RawResponse(data_response) => Ok(data_response.clone()),
+            StorageServiceResponse::CodecCompressedResponse(
+                _,
+                compression_codec,
+                compressed_data,
+            ) => {
+                let raw_data = aptos_compression::decompress_with_codec(
+                    compressed_data,
+                    *compression_codec,
+                    CompressionClient::StateSync,
+                    MAX_APPLICATION_MESSAGE_SIZE,
+                )?;
+                let data_response = bcs::from_bytes::<DataResponse>(&raw_data)
+                    .map_err(|error| Error::UnexpectedErrorEncountered(error.to_string()))?;
+                Ok(data_response)
+            },
         }
     }
 
@@ -109,12 +152,22 @@ impl StorageServiceResponse {
             StorageServiceResponse::RawResponse(data_response) => {
                 data_response.get_label().to_string()
             },
+            StorageServiceResponse::CodecCompressedResponse(label, _, _) => label.clone(),
+        }
+    }
+
+    /// Returns the codec used to compress the data response (if it is compressed)
+    pub fn get_compression_codec(&self) -> Option<CompressionCodec> {
+        match self {
+            Self::CompressedResponse(_, _) => Some(CompressionCodec::Lz4),
+            Self::RawResponse(_) => None,
+            Self::CodecCompressedResponse(_, compression_codec, _) => Some(*compression_codec),
         }
     }
 
     /// Returns true iff the data response is compressed
     pub fn is_compressed(&self) -> bool {
-        matches!(self, Self::CompressedResponse(_, _))
+        self.get_compression_codec().is_some()
     }
 }
 
@@ -139,6 +192,9 @@ pub enum DataResponse {
     TransactionsWithProof(TransactionListWithProof),
     NewTransactionsOrOutputsWithProof((TransactionOrOutputListWithProof, LedgerInfoWithSignatures)),
     TransactionsOrOutputsWithProof(TransactionOrOutputListWithProof),
+    LatestLedgerInfoWithEpochProof((EpochChangeProof, LedgerInfoWithSignatures)),
+    StorageServerSummaryUpdate(StorageServerSummaryDelta),
+    StateValueChunkWithIncrementalProof(StateValueChunkWithIncrementalProof),
 }
 
 impl DataResponse {
@@ -156,6 +212,11 @@ impl DataResponse {
             Self::TransactionsWithProof(_) => "transactions_with_proof",
             Self::NewTransactionsOrOutputsWithProof(_) => "new_transactions_or_outputs_with_proof",
             Self::TransactionsOrOutputsWithProof(_) => "transactions_or_outputs_with_proof",
+            Self::LatestLedgerInfoWithEpochProof(_) => "latest_ledger_info_with_epoch_proof",
+            Self::StorageServerSummaryUpdate(_) => "storage_server_summary_update",
+            Self::StateValueChunkWithIncrementalProof(_) => {
+                "state_value_chunk_with_incremental_proof"
+            },
         }
     }
 }
@@ -167,6 +228,9 @@ impl Display for DataResponse {
             DataResponse::StorageServerSummary(storage_summary) => {
                 format!("{:?}", storage_summary)
             },
+            DataResponse::StorageServerSummaryUpdate(storage_summary_delta) => {
+                format!("{:?}", storage_summary_delta)
+            },
             _ => "...".into(),
         };
         write!(
@@ -193,6 +257,21 @@ impl TryFrom<StorageServiceResponse> for StateValueChunkWithProof {
     }
 }
 
+impl TryFrom<StorageServiceResponse> for StateValueChunkWithIncrementalProof {
+    type Error = crate::responses::Error;
+
+    fn try_from(response: StorageServiceResponse) -> crate::Result<Self, Self::Error> {
+        let data_response = response.get_data_response()?;
+        match data_response {
+            DataResponse::StateValueChunkWithIncrementalProof(inner) => Ok(inner),
+            _ => Err(Error::UnexpectedResponseError(format!(
+                "expected state_value_chunk_with_incremental_proof, found {}",
+                data_response.get_label()
+            ))),
+        }
+    }
+}
+
 impl TryFrom<StorageServiceResponse> for EpochChangeProof {
     type Error = crate::responses::Error;
 
@@ -285,6 +364,21 @@ impl TryFrom<StorageServiceResponse> for StorageServerSummary {
     }
 }
 
+impl TryFrom<StorageServiceResponse> for StorageServerSummaryDelta {
+    type Error = crate::responses::Error;
+
+    fn try_from(response: StorageServiceResponse) -> crate::Result<Self, Self::Error> {
+        let data_response = response.get_data_response()?;
+        match data_response {
+            DataResponse::StorageServerSummaryUpdate(inner) => Ok(inner),
+            _ => Err(Error::UnexpectedResponseError(format!(
+                "expected storage_server_summary_update, found {}",
+                data_response.get_label()
+            ))),
+        }
+    }
+}
+
 impl TryFrom<StorageServiceResponse> for TransactionOutputListWithProof {
     type Error = crate::responses::Error;
 
@@ -347,6 +441,21 @@ impl TryFrom<StorageServiceResponse> for TransactionOrOutputListWithProof {
     }
 }
 
+impl TryFrom<StorageServiceResponse> for (EpochChangeProof, LedgerInfoWithSignatures) {
+    type Error = crate::responses::Error;
+
+    fn try_from(response: StorageServiceResponse) -> crate::Result<Self, Self::Error> {
+        let data_response = response.get_data_response()?;
+        match data_response {
+            DataResponse::LatestLedgerInfoWithEpochProof(inner) => Ok(inner),
+            _ => Err(Error::UnexpectedResponseError(format!(
+                "expected latest_ledger_info_with_epoch_proof, found {}",
+                data_response.get_label()
+            ))),
+        }
+    }
+}
+
 /// The protocol version run by this server. Clients request this first to
 /// identify what API calls and data requests the server supports.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -442,7 +551,9 @@ impl DataSummary {
         request: &StorageServiceRequest,
     ) -> bool {
         match &request.data_request {
-            GetServerProtocolVersion | GetStorageServerSummary => true,
+            GetServerProtocolVersion
+            | GetStorageServerSummary
+            | GetStorageServerSummaryUpdate(_) => true,
             GetEpochEndingLedgerInfos(request) => {
                 let desired_range =
                     match CompleteDataRange::new(request.start_epoch, request.expected_end_epoch) {
@@ -472,21 +583,9 @@ impl DataSummary {
                 .states
                 .map(|range| range.contains(*version))
                 .unwrap_or(false),
-            GetStateValuesWithProof(request) => {
-                let proof_version = request.version;
-
-                let can_serve_states = self
-                    .states
-                    .map(|range| range.contains(request.version))
-                    .unwrap_or(false);
-
-                let can_create_proof = self
-                    .synced_ledger_info
-                    .as_ref()
-                    .map(|li| li.ledger_info().version() >= proof_version)
-                    .unwrap_or(false);
-
-                can_serve_states && can_create_proof
+            GetStateValuesWithProof(request) => self.can_service_state_values(request.version),
+            GetStateValuesWithIncrementalProof(request) => {
+                self.can_service_state_values(request.version)
             },
             GetTransactionOutputsWithProof(request) => {
                 let desired_range =
@@ -568,9 +667,63 @@ impl DataSummary {
                 time_service,
                 self.synced_ledger_info.as_ref(),
             ),
+            GetLatestLedgerInfoWithEpochProof(request) => {
+                let synced_epoch = match self.synced_ledger_info.as_ref() {
+                    Some(synced_ledger_info) => synced_ledger_info.ledger_info().epoch(),
+                    None => return false,
+                };
+
+                // The trusted epoch cannot be ahead of the synced epoch
+                if request.trusted_epoch > synced_epoch {
+                    return false;
+                }
+
+                // If the client already trusts the synced epoch, no proof is required
+                if request.trusted_epoch == synced_epoch {
+                    return true;
+                }
+
+                // Otherwise, we must hold all epoch endings from the trusted epoch onwards
+                let desired_range = match CompleteDataRange::new(
+                    request.trusted_epoch,
+                    synced_epoch.saturating_sub(1),
+                ) {
+                    Ok(desired_range) => desired_range,
+                    Err(_) => return false,
+                };
+                self.epoch_ending_ledger_infos
+                    .map(|range| range.superset_of(&desired_range))
+                    .unwrap_or(false)
+            },
+            GetWithAcceptedCompression(_, data_request) => {
+                // Only regular data requests can negotiate the compression codec
+                if !data_request.is_compression_negotiable() {
+                    return false;
+                }
+                let request =
+                    StorageServiceRequest::new(*data_request.clone(), request.use_compression);
+                self.can_service(aptos_data_client_config, time_service, &request)
+            },
         }
     }
 
+    /// Returns true iff the state values at the given version can be served
+    /// (together with a proof relative to the synced ledger info).
+    fn can_service_state_values(&self, version: Version) -> bool {
+        let can_serve_states = self
+            .states
+            .map(|range| range.contains(version))
+            .unwrap_or(false);
+
+        let can_create_proof = self
+            .synced_ledger_info
+            .as_ref()
+            .map(|li| li.ledger_info().version() >= version)
+            .unwrap_or(false);
+
+        can_serve_states && can_create_proof
+    }
+
     /// Returns the version of the synced ledger info (if one exists)
     pub fn get_synced_ledger_info_version(&self) -> Option<u64> {
         self.synced_ledger_info
@@ -579,6 +732,171 @@ impl DataSummary {
     }
 }
 
+/// The changes to a storage server summary, relative to the summary known by
+/// the client (see `StorageServerSummaryUpdateRequest`). Each field is `None`
+/// if it is unchanged, and otherwise holds the new value. This avoids sending
+/// the full summary to clients that poll frequently.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StorageServerSummaryDelta {
+    pub protocol_metadata: Option<ProtocolMetadata>,
+    pub synced_ledger_info: Option<Option<LedgerInfoWithSignatures>>,
+    pub epoch_ending_ledger_infos: Option<Option<CompleteDataRange<Epoch>>>,
+    pub states: Option<Option<CompleteDataRange<Version>>>,
+    pub transactions: Option<Option<CompleteDataRange<Version>>>,
+    pub transaction_outputs: Option<Option<CompleteDataRange<Version>>>,
+}
+
+impl StorageServerSummaryDelta {
+    /// Returns the changes from the known summary (in the given request)
+    /// to the specified storage server summary.
+    pub fn new(
+        request: &StorageServerSummaryUpdateRequest,
+        storage_server_summary: &StorageServerSummary,
+    ) -> Self {
+        let data_summary = &storage_server_summary.data_summary;
+        Self {
+            protocol_metadata: changed_value(
+                &request.known_protocol_metadata,
+                &storage_server_summary.protocol_metadata,
+            ),
+            synced_ledger_info: if request.known_synced_ledger_info_version
+                == data_summary.get_synced_ledger_info_version()
+            {
+                None
+            } else {
+                Some(data_summary.synced_ledger_info.clone())
+            },
+            epoch_ending_ledger_infos: changed_value(
+                &request.known_epoch_ending_ledger_infos,
+                &data_summary.epoch_ending_ledger_infos,
+            ),
+            states: changed_value(&request.known_states, &data_summary.states),
+            transactions: changed_value(&request.known_transactions, &data_summary.transactions),
+            transaction_outputs: changed_value(
+                &request.known_transaction_outputs,
+                &data_summary.transaction_outputs,
+            ),
+        }
+    }
+
+    /// Returns true iff the summary is unchanged
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Applies the changes to the known summary and returns the updated summary
+    pub fn apply(&self, known_summary: &StorageServerSummary) -> StorageServerSummary {
+        let known_data_summary = &known_summary.data_summary;
+        StorageServerSummary {
+            protocol_metadata: self
+                .protocol_metadata
+                .clone()
+                .unwrap_or_else(|| known_summary.protocol_metadata.clone()),
+            data_summary: DataSummary {
+                synced_ledger_info: self
+                    .synced_ledger_info
+                    .clone()
+                    .unwrap_or_else(|| known_data_summary.synced_ledger_info.clone()),
+                epoch_ending_ledger_infos: self
+                    .epoch_ending_ledger_infos
+                    .unwrap_or(known_data_summary.epoch_ending_ledger_infos),
+                states: self.states.unwrap_or(known_data_summary.states),
+                transactions: self.transactions.unwrap_or(known_data_summary.transactions),
+                transaction_outputs: self
+                    .transaction_outputs
+                    .unwrap_or(known_data_summary.transaction_outputs),
+            },
+        }
+    }
+}
+
+/// A state value chunk with an incremental proof, i.e., a range proof that
+/// omits the right siblings shared with the range proof of a chunk already
+/// known by the client (see `StateValuesWithIncrementalProofRequest`). The
+/// omitted siblings are always the last (i.e., top-most) right siblings.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateValueChunkWithIncrementalProof {
+    pub state_value_chunk_with_proof: StateValueChunkWithProof, // The chunk (without the shared right siblings)
+    pub num_shared_right_siblings: u64, // The number of right siblings omitted from the proof
+}
+
+impl StateValueChunkWithIncrementalProof {
+    /// Creates an incremental proof for the given chunk, by removing the right
+    /// siblings shared with the range proof of the known last key.
+    pub fn new(
+        mut state_value_chunk_with_proof: StateValueChunkWithProof,
+        known_last_key: HashValue,
+    ) -> Self {
+        let right_siblings = state_value_chunk_with_proof.proof.right_siblings();
+        let num_shared_right_siblings =
+            get_num_shared_right_siblings(known_last_key, state_value_chunk_with_proof.last_key)
+                .min(right_siblings.len());
+        let num_new_right_siblings = right_siblings.len() - num_shared_right_siblings;
+        state_value_chunk_with_proof.proof =
+            SparseMerkleRangeProof::new(right_siblings[..num_new_right_siblings].to_vec());
+
+        Self {
+            state_value_chunk_with_proof,
+            num_shared_right_siblings: num_shared_right_siblings as u64,
+        }
+    }
+
+    /// Returns the state value chunk with the full range proof, using the
+    /// right siblings of the known chunk's range proof.
+    pub fn into_state_value_chunk_with_proof(
+        self,
+        known_right_siblings: &[HashValue],
+    ) -> Result<StateValueChunkWithProof, Error> {
+        let num_shared_right_siblings = self.num_shared_right_siblings as usize;
+        if num_shared_right_siblings > known_right_siblings.len() {
+            return Err(Error::UnexpectedResponseError(format!(
+                "Found more shared right siblings ({}) than known right siblings ({})!",
+                num_shared_right_siblings,
+                known_right_siblings.len()
+            )));
+        }
+
+        let mut state_value_chunk_with_proof = self.state_value_chunk_with_proof;
+        let shared_right_siblings =
+            &known_right_siblings[known_right_siblings.len() - num_shared_right_siblings..];
+        let right_siblings = state_value_chunk_with_proof
+            .proof
+            .right_siblings()
+            .iter()
+            .chain(shared_right_siblings)
+            .cloned()
+            .collect();
+        state_value_chunk_with_proof.proof = SparseMerkleRangeProof::new(right_siblings);
+        Ok(state_value_chunk_with_proof)
+    }
+}
+
+/// Returns the number of right siblings shared by the range proofs of the
+/// known last key and the given (greater) last key. The paths of both keys
+/// are identical up until the first differing bit, so all right siblings
+/// above that bit (i.e., for each zero bit in the common prefix) are shared.
+pub fn get_num_shared_right_siblings(known_last_key: HashValue, last_key: HashValue) -> usize {
+    if known_last_key >= last_key {
+        return 0; // The proofs are only shared for increasing keys
+    }
+
+    let common_prefix_bits_len = known_last_key.common_prefix_bits_len(last_key);
+    last_key
+        .iter_bits()
+        .take(common_prefix_bits_len)
+        .filter(|bit| !bit)
+        .count()
+}
+
+/// Returns the new value iff it differs from the known value
+fn changed_value<T: Clone + PartialEq>(known_value: &T, new_value: &T) -> Option<T> {
+    if known_value == new_value {
+        None
+    } else {
+        Some(new_value.clone())
+    }
+}
+
 /// Returns true iff an optimistic data request can be serviced
 /// by the peer with the given synced ledger info.
 fn can_service_optimistic_request(
@@ -3,16 +3,22 @@
 
 use crate::{
     requests::{
-        DataRequest, EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest,
-        NewTransactionsOrOutputsWithProofRequest, NewTransactionsWithProofRequest,
-        StateValuesWithProofRequest, SubscribeTransactionOutputsWithProofRequest,
+        DataRequest, EpochEndingLedgerInfoRequest, LatestLedgerInfoWithEpochProofRequest,
+        NewTransactionOutputsWithProofRequest, NewTransactionsOrOutputsWithProofRequest,
+        NewTransactionsWithProofRequest, StateValuesWithProofRequest,
+        StorageServerSummaryUpdateRequest, SubscribeTransactionOutputsWithProofRequest,
         SubscribeTransactionsOrOutputsWithProofRequest, SubscribeTransactionsWithProofRequest,
         SubscriptionStreamMetadata, TransactionOutputsWithProofRequest,
         TransactionsOrOutputsWithProofRequest, TransactionsWithProofRequest,
     },
-    responses::{CompleteDataRange, DataSummary, ProtocolMetadata},
+    responses::{
+        get_num_shared_right_siblings, CompleteDataRange, DataResponse, DataSummary,
+        ProtocolMetadata, StateValueChunkWithIncrementalProof, StorageServerSummary,
+        StorageServerSummaryDelta, StorageServiceResponse,
+    },
     Epoch, StorageServiceRequest,
 };
+use aptos_compression::codec::CompressionCodec;
 use aptos_config::config::AptosDataClientConfig;
 use aptos_crypto::hash::HashValue;
 use aptos_time_service::{TimeService, TimeServiceTrait};
@@ -20,6 +26,8 @@ use aptos_types::{
     aggregate_signature::AggregateSignature,
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::SparseMerkleRangeProof,
+    state_store::state_value::StateValueChunkWithProof,
     transaction::Version,
 };
 use claims::{assert_err, assert_ok};
@@ -89,6 +97,40 @@ fn test_data_summary_service_epoch_ending_ledger_infos() {
     }
 }
 
+#[test]
+fn test_data_summary_service_latest_ledger_info_with_epoch_proof() {
+    // Create a data client config and data summary
+    let data_client_config = AptosDataClientConfig::default();
+    let data_summary = DataSummary {
+        synced_ledger_info: Some(create_ledger_info_at_epoch(200)),
+        epoch_ending_ledger_infos: Some(create_data_range(100, 199)),
+        ..Default::default()
+    };
+
+    // Verify the different requests that can be serviced
+    for compression in [true, false] {
+        // Test the serviceable trusted epochs
+        for trusted_epoch in [100, 150, 199, 200] {
+            let request = create_epoch_proof_request(trusted_epoch, compression);
+            verify_serviceability(&data_client_config, &data_summary, None, request, true);
+        }
+
+        // Test the unserviceable trusted epochs (missing epoch endings or future epochs)
+        for trusted_epoch in [0, 99, 201, u64::MAX] {
+            let request = create_epoch_proof_request(trusted_epoch, compression);
+            verify_serviceability(&data_client_config, &data_summary, None, request, false);
+        }
+    }
+
+    // Verify that requests cannot be serviced without a synced ledger info
+    let data_summary = DataSummary {
+        epoch_ending_ledger_infos: Some(create_data_range(100, 199)),
+        ..Default::default()
+    };
+    let request = create_epoch_proof_request(150, true);
+    verify_serviceability(&data_client_config, &data_summary, None, request, false);
+}
+
 #[test]
 fn test_data_summary_service_optimistic_fetch() {
     // Create a data client config with the specified max optimistic fetch lag
@@ -492,6 +534,230 @@ fn test_protocol_metadata_service() {
     }
 }
 
+#[test]
+fn test_storage_server_summary_delta() {
+    // Create a storage server summary
+    let known_summary = StorageServerSummary {
+        protocol_metadata: ProtocolMetadata::default(),
+        data_summary: DataSummary {
+            synced_ledger_info: Some(create_ledger_info_at_version(1000)),
+            epoch_ending_ledger_infos: Some(create_data_range(0, 10)),
+            states: Some(create_data_range(500, 1000)),
+            transactions: Some(create_data_range(0, 1000)),
+            transaction_outputs: Some(create_data_range(0, 1000)),
+        },
+    };
+    let update_request = StorageServerSummaryUpdateRequest::new(&known_summary);
+
+    // Verify that an unchanged summary results in an empty delta
+    let delta = StorageServerSummaryDelta::new(&update_request, &known_summary);
+    assert!(delta.is_empty());
+    assert_eq!(delta.apply(&known_summary), known_summary);
+
+    // Update the summary (the ledger info, transactions and outputs advance,
+    // and the states are pruned).
+    let mut new_summary = known_summary.clone();
+    new_summary.data_summary.synced_ledger_info = Some(create_ledger_info_at_version(2000));
+    new_summary.data_summary.states = None;
+    new_summary.data_summary.transactions = Some(create_data_range(100, 2000));
+    new_summary.data_summary.transaction_outputs = Some(create_data_range(100, 2000));
+
+    // Verify that only the changed fields are included in the delta
+    let delta = StorageServerSummaryDelta::new(&update_request, &new_summary);
+    assert!(!delta.is_empty());
+    assert_eq!(delta.protocol_metadata, None);
+    assert_eq!(delta.epoch_ending_ledger_infos, None);
+    assert_eq!(delta.states, Some(None));
+    assert_eq!(delta.transactions, Some(Some(create_data_range(100, 2000))));
+
+    // Verify that applying the delta reconstructs the new summary
+    assert_eq!(delta.apply(&known_summary), new_summary);
+}
+
+#[test]
+fn test_state_value_chunk_with_incremental_proof() {
+    // Create two keys with a common prefix of 3 bits (i.e., 001) that
+    // contains 2 zero bits (i.e., the paths share 2 right siblings).
+    let mut known_last_key_bytes = [0; HashValue::LENGTH];
+    known_last_key_bytes[0] = 0b0010_0000;
+    let known_last_key = HashValue::new(known_last_key_bytes);
+    let mut last_key_bytes = [0; HashValue::LENGTH];
+    last_key_bytes[0] = 0b0011_0000;
+    let last_key = HashValue::new(last_key_bytes);
+
+    // Verify the number of shared right siblings
+    assert_eq!(get_num_shared_right_siblings(known_last_key, last_key), 2);
+    assert_eq!(get_num_shared_right_siblings(last_key, known_last_key), 0);
+    assert_eq!(get_num_shared_right_siblings(last_key, last_key), 0);
+
+    // Create a state value chunk with a full proof
+    let right_siblings: Vec<_> = (0..5).map(|_| HashValue::random()).collect();
+    let state_value_chunk_with_proof = StateValueChunkWithProof {
+        first_index: 100,
+        last_index: 200,
+        first_key: known_last_key,
+        last_key,
+        raw_values: vec![],
+        proof: SparseMerkleRangeProof::new(right_siblings.clone()),
+        root_hash: HashValue::random(),
+    };
+
+    // Verify that the shared right siblings are removed from the proof
+    let incremental_chunk = StateValueChunkWithIncrementalProof::new(
+        state_value_chunk_with_proof.clone(),
+        known_last_key,
+    );
+    assert_eq!(incremental_chunk.num_shared_right_siblings, 2);
+    assert_eq!(
+        incremental_chunk
+            .state_value_chunk_with_proof
+            .proof
+            .right_siblings(),
+        &right_siblings[..3]
+    );
+
+    // Verify that the full proof is reconstructed using the known right siblings
+    let known_right_siblings = vec![HashValue::random(), right_siblings[3], right_siblings[4]];
+    assert_eq!(
+        incremental_chunk
+            .clone()
+            .into_state_value_chunk_with_proof(&known_right_siblings)
+            .unwrap(),
+        state_value_chunk_with_proof
+    );
+
+    // Verify that the proof can't be reconstructed without enough known right siblings
+    assert_err!(incremental_chunk.into_state_value_chunk_with_proof(&right_siblings[4..]));
+
+    // Verify that nothing is removed from the proof if the known key isn't smaller
+    let incremental_chunk =
+        StateValueChunkWithIncrementalProof::new(state_value_chunk_with_proof.clone(), last_key);
+    assert_eq!(incremental_chunk.num_shared_right_siblings, 0);
+    assert_eq!(
+        incremental_chunk
+            .into_state_value_chunk_with_proof(&[])
+            .unwrap(),
+        state_value_chunk_with_proof
+    );
+}
+
+#[test]
+fn test_data_summary_service_accepted_compression() {
+    // Create a data client config and data summary
+    let data_client_config = AptosDataClientConfig::default();
+    let data_summary = DataSummary {
+        synced_ledger_info: Some(create_ledger_info_at_version(250)),
+        transactions: Some(create_data_range(100, 200)),
+        ..Default::default()
+    };
+
+    // Verify that requests with accepted compression codecs are
+    // serviceable iff the inner requests are serviceable.
+    for (start_version, end_version, expect_service) in [(100, 200, true), (50, 150, false)] {
+        let request = create_transactions_request(225, start_version, end_version, true);
+        let request = StorageServiceRequest::new(
+            DataRequest::GetWithAcceptedCompression(
+                vec![CompressionCodec::Zstd, CompressionCodec::Lz4],
+                Box::new(request.data_request),
+            ),
+            request.use_compression,
+        );
+        verify_serviceability(
+            &data_client_config,
+            &data_summary,
+            None,
+            request,
+            expect_service,
+        );
+    }
+
+    // Verify that optimistic fetch requests cannot negotiate the compression codec
+    let request = create_optimistic_fetch_request(150, true);
+    let request = StorageServiceRequest::new(
+        DataRequest::GetWithAcceptedCompression(
+            vec![CompressionCodec::Zstd],
+            Box::new(request.data_request),
+        ),
+        request.use_compression,
+    );
+    verify_serviceability(&data_client_config, &data_summary, None, request, false);
+}
+
+#[test]
+fn test_accepted_compression_codecs() {
+    // Verify the accepted codecs of requests without a codec list
+    let request = create_transactions_request(225, 100, 200, false);
+    assert!(request.get_accepted_compression_codecs().is_empty());
+    assert!(request.accepts_compression_codec(None));
+    assert!(!request.accepts_compression_codec(Some(CompressionCodec::Lz4)));
+    let request = create_transactions_request(225, 100, 200, true);
+    assert_eq!(request.get_accepted_compression_codecs(), &[
+        CompressionCodec::Lz4
+    ]);
+    assert!(request.accepts_compression_codec(Some(CompressionCodec::Lz4)));
+    assert!(!request.accepts_compression_codec(Some(CompressionCodec::Zstd)));
+
+    // Verify the accepted codecs of requests with a codec list
+    let accepted_codecs = vec![CompressionCodec::Zstd, CompressionCodec::Lz4];
+    for use_compression in [false, true] {
+        let inner_request = create_transactions_request(225, 100, 200, use_compression);
+        let request = StorageServiceRequest::new(
+            DataRequest::GetWithAcceptedCompression(
+                accepted_codecs.clone(),
+                Box::new(inner_request.data_request.clone()),
+            ),
+            use_compression,
+        );
+        assert_eq!(
+            request.data_request.get_inner_data_request(),
+            &inner_request.data_request
+        );
+        if use_compression {
+            assert_eq!(request.get_accepted_compression_codecs(), &accepted_codecs);
+            assert!(request.accepts_compression_codec(Some(CompressionCodec::Zstd)));
+        } else {
+            assert!(request.get_accepted_compression_codecs().is_empty());
+            assert!(!request.accepts_compression_codec(Some(CompressionCodec::Zstd)));
+        }
+    }
+}
+
+#[test]
+fn test_compressed_responses() {
+    // Create a data response
+    let data_response = DataResponse::NumberOfStatesAtVersion(100);
+
+    // Verify that responses can be created and decoded with each codec
+    for compression_codec in [
+        None,
+        Some(CompressionCodec::Lz4),
+        Some(CompressionCodec::Zstd),
+    ] {
+        let storage_response = StorageServiceResponse::new_with_compression_codec(
+            data_response.clone(),
+            compression_codec,
+        )
+        .unwrap();
+        assert_eq!(storage_response.get_compression_codec(), compression_codec);
+        assert_eq!(
+            storage_response.is_compressed(),
+            compression_codec.is_some()
+        );
+        assert_eq!(storage_response.get_data_response().unwrap(), data_response);
+    }
+
+    // Verify that LZ4 responses are backward compatible
+    let storage_response = StorageServiceResponse::new_with_compression_codec(
+        data_response.clone(),
+        Some(CompressionCodec::Lz4),
+    )
+    .unwrap();
+    assert_eq!(
+        storage_response,
+        StorageServiceResponse::new(data_response, true).unwrap()
+    );
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(1000))]
 
@@ -519,6 +785,29 @@ fn create_epoch_ending_request(
     StorageServiceRequest::new(data_request, use_compression)
 }
 
+/// Creates a request for the latest ledger info with an epoch change proof
+fn create_epoch_proof_request(
+    trusted_epoch: Epoch,
+    use_compression: bool,
+) -> StorageServiceRequest {
+    let data_request =
+        DataRequest::GetLatestLedgerInfoWithEpochProof(LatestLedgerInfoWithEpochProofRequest {
+            trusted_epoch,
+        });
+    StorageServiceRequest::new(data_request, use_compression)
+}
+
+/// Creates a new ledger info at the given epoch
+fn create_ledger_info_at_epoch(epoch: Epoch) -> LedgerInfoWithSignatures {
+    LedgerInfoWithSignatures::new(
+        LedgerInfo::new(
+            BlockInfo::new(epoch, 0, HashValue::zero(), HashValue::zero(), 0, 0, None),
+            HashValue::zero(),
+        ),
+        AggregateSignature::empty(),
+    )
+}
+
 /// Creates a new ledger info at the given version
 fn create_ledger_info_at_version(version: Version) -> LedgerInfoWithSignatures {
     create_ledger_info_at_version_and_timestamp(version, 0)
@@ -2072,6 +2072,19 @@ fn realistic_network_tuned_for_throughput_test() -> ForgeConfig {
             }));
     }
 
+    // Fail when a specific stage of the pipeline regresses, even if the overall throughput holds
+    let stage_latency_thresholds = LatencyBreakdownThreshold::new_with_breach_pct(
+        vec![
+            (LatencyBreakdownSlice::MempoolWait, 1.5),
+            (LatencyBreakdownSlice::QsBatchWait, 0.1),
+            (LatencyBreakdownSlice::ConsensusRoundTime, 0.4),
+            // blocks are sized to take 200-250ms of execution time (see MAX_TXNS_PER_BLOCK)
+            (LatencyBreakdownSlice::Execution, 0.4),
+            (LatencyBreakdownSlice::Commit, 0.3),
+        ],
+        5,
+    );
+
     if USE_CRAZY_MACHINES {
         forge_config = forge_config
             .with_validator_resource_override(NodeResourceOverride {
@@ -2086,7 +2099,8 @@ fn realistic_network_tuned_for_throughput_test() -> ForgeConfig {
                 SuccessCriteria::new(25000)
                     .add_no_restarts()
                     /* This test runs at high load, so we need more catchup time */
-                    .add_wait_for_catchup_s(120),
+                    .add_wait_for_catchup_s(120)
+                    .add_latency_breakdown_threshold(stage_latency_thresholds),
                 /* Doesn't work without event indices
                 .add_chain_progress(StateProgressThreshold {
                     max_no_progress_secs: 10.0,
@@ -2099,7 +2113,8 @@ fn realistic_network_tuned_for_throughput_test() -> ForgeConfig {
             SuccessCriteria::new(12000)
                 .add_no_restarts()
                 /* This test runs at high load, so we need more catchup time */
-                .add_wait_for_catchup_s(120),
+                .add_wait_for_catchup_s(120)
+                .add_latency_breakdown_threshold(stage_latency_thresholds),
             /* Doesn't work without event indices
                 .add_chain_progress(StateProgressThreshold {
                      max_no_progress_secs: 10.0,
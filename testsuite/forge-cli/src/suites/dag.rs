@@ -12,7 +12,10 @@ use aptos_sdk::types::on_chain_config::{
 };
 use aptos_testcases::{
     consensus_reliability_tests::ChangingWorkingQuorumTest,
-    dag_onchain_enable_test::DagOnChainEnableTest, two_traffics_test::TwoTrafficsTest,
+    dag_onchain_enable_test::DagOnChainEnableTest,
+    fault_schedule_test::{FaultSchedule, FaultScheduleTest},
+    network_chaos_recovery_test::{GroupChaos, NetworkChaosRecoveryTest},
+    two_traffics_test::TwoTrafficsTest,
 };
 use std::{num::NonZeroUsize, sync::Arc, time::Duration};
 
@@ -34,6 +37,9 @@ fn get_dag_on_realistic_env_test(
         "dag_realistic_env_max_load" => dag_realistic_env_max_load_test(duration, test_cmd, 20, 0),
         "dag_changing_working_quorum_test" => dag_changing_working_quorum_test(),
         "dag_reconfig_enable_test" => dag_reconfig_enable_test(),
+        "dag_partition_recovery_test" => dag_partition_recovery_test(duration),
+        "dag_asymmetric_loss_recovery_test" => dag_asymmetric_loss_recovery_test(duration),
+        "dag_fault_schedule_test" => dag_fault_schedule_test(duration),
         _ => return None, // The test name does not match a dag realistic-env test
     };
     Some(test)
@@ -230,3 +236,100 @@ fn dag_reconfig_enable_test() -> ForgeConfig {
                 }),
         )
 }
+
+/// Partitions a minority of the validators from the rest of the network for a while,
+/// and verifies that the chain makes progress again once the partition is healed.
+fn dag_partition_recovery_test(duration: Duration) -> ForgeConfig {
+    dag_chaos_recovery_test(duration, GroupChaos::Partition)
+}
+
+/// Drops most of the packets sent from a minority of the validators to the rest of the
+/// network (but not the other way around), and verifies that the chain makes progress
+/// again once the loss is removed.
+fn dag_asymmetric_loss_recovery_test(duration: Duration) -> ForgeConfig {
+    dag_chaos_recovery_test(duration, GroupChaos::AsymmetricLoss {
+        loss_percentage: 80,
+        correlation_percentage: 25,
+    })
+}
+
+fn dag_chaos_recovery_test(duration: Duration, chaos: GroupChaos) -> ForgeConfig {
+    ForgeConfig::default()
+        .with_initial_validator_count(NonZeroUsize::new(20).unwrap())
+        .add_network_test(NetworkChaosRecoveryTest {
+            // Keep the minority below a third, so the majority can still make progress
+            minority_percentage: 30,
+            chaos,
+            // Inject the chaos for (at most) half of the test
+            chaos_duration: (duration / 2).min(Duration::from_secs(300)),
+            max_recovery_duration: Duration::from_secs(120),
+        })
+        .with_validator_override_node_config_fn(Arc::new(|config, _| {
+            config.consensus.max_sending_block_txns = 4000;
+            config.consensus.max_sending_block_bytes = 6 * 1024 * 1024;
+            config.consensus.max_receiving_block_txns = 10000;
+            config.consensus.max_receiving_block_bytes = 7 * 1024 * 1024;
+        }))
+        .with_genesis_helm_config_fn(Arc::new(move |helm_values| {
+            let onchain_consensus_config = OnChainConsensusConfig::V3 {
+                alg: ConsensusAlgorithmConfig::DAG(DagConsensusConfigV1::default()),
+                vtxn: ValidatorTxnConfig::default_for_genesis(),
+            };
+
+            helm_values["chain"]["on_chain_consensus_config"] =
+                serde_yaml::to_value(onchain_consensus_config).expect("must serialize");
+            helm_values["chain"]["on_chain_execution_config"] =
+                serde_yaml::to_value(OnChainExecutionConfig::default_for_genesis())
+                    .expect("must serialize");
+        }))
+        .with_emit_job(EmitJobRequest::default().mode(EmitJobMode::ConstTps { tps: 1000 }))
+        .with_success_criteria(
+            SuccessCriteria::new(200)
+                .add_no_restarts()
+                .add_wait_for_catchup_s(120),
+        )
+}
+
+/// Composes a timed sequence of faults: a validator is killed (and later restarted),
+/// a minority of the validators is partitioned from the rest of the network, and a
+/// validator is restarted with a wiped consensus DB. The fault times are relative to
+/// the test duration, and all faults are healed before the success criteria are checked.
+fn dag_fault_schedule_test(duration: Duration) -> ForgeConfig {
+    let num_validators = 20;
+    let step = duration / 8;
+    let schedule = FaultSchedule::new()
+        .kill_validator_between(step, step * 3, 0)
+        .partition_groups(
+            step * 4,
+            step * 6,
+            // Keep the minority below a third, so the majority can still make progress
+            (1..6).collect(),
+            (6..num_validators).collect(),
+        )
+        .restart_with_wiped_consensus_db(step * 7, 6);
+
+    ForgeConfig::default()
+        .with_initial_validator_count(NonZeroUsize::new(num_validators).unwrap())
+        .add_network_test(FaultScheduleTest { schedule })
+        .with_validator_override_node_config_fn(Arc::new(|config, _| {
+            config.consensus.max_sending_block_txns = 4000;
+            config.consensus.max_sending_block_bytes = 6 * 1024 * 1024;
+            config.consensus.max_receiving_block_txns = 10000;
+            config.consensus.max_receiving_block_bytes = 7 * 1024 * 1024;
+        }))
+        .with_genesis_helm_config_fn(Arc::new(move |helm_values| {
+            let onchain_consensus_config = OnChainConsensusConfig::V3 {
+                alg: ConsensusAlgorithmConfig::DAG(DagConsensusConfigV1::default()),
+                vtxn: ValidatorTxnConfig::default_for_genesis(),
+            };
+
+            helm_values["chain"]["on_chain_consensus_config"] =
+                serde_yaml::to_value(onchain_consensus_config).expect("must serialize");
+            helm_values["chain"]["on_chain_execution_config"] =
+                serde_yaml::to_value(OnChainExecutionConfig::default_for_genesis())
+                    .expect("must serialize");
+        }))
+        .with_emit_job(EmitJobRequest::default().mode(EmitJobMode::ConstTps { tps: 1000 }))
+        // Note: restarts are expected, as the node exits after recovering from the wiped DB
+        .with_success_criteria(SuccessCriteria::new(200).add_wait_for_catchup_s(120))
+}
@@ -3,7 +3,8 @@
 
 use crate::{
     dump_string_to_file, K8sSwarm, Result, Swarm, SwarmChaos, SwarmCpuStress, SwarmNetEm,
-    SwarmNetworkBandwidth, SwarmNetworkDelay, SwarmNetworkLoss, SwarmNetworkPartition, KUBECTL_BIN,
+    SwarmNetworkBandwidth, SwarmNetworkDelay, SwarmNetworkGroupLoss, SwarmNetworkGroupPartition,
+    SwarmNetworkLoss, SwarmNetworkPartition, KUBECTL_BIN,
 };
 use anyhow::bail;
 use aptos_logger::info;
@@ -21,6 +22,11 @@ macro_rules! PARTITION_NETWORK_CHAOS_TEMPLATE {
         "chaos/network_partition.yaml"
     };
 }
+macro_rules! GROUP_PARTITION_NETWORK_CHAOS_TEMPLATE {
+    () => {
+        "chaos/network_group_partition.yaml"
+    };
+}
 macro_rules! BANDWIDTH_NETWORK_CHAOS_TEMPLATE {
     () => {
         "chaos/network_bandwidth.yaml"
@@ -33,6 +39,12 @@ macro_rules! NETWORK_LOSS_CHAOS_TEMPLATE {
     };
 }
 
+macro_rules! GROUP_NETWORK_LOSS_CHAOS_TEMPLATE {
+    () => {
+        "chaos/network_group_loss.yaml"
+    };
+}
+
 macro_rules! NETEM_CHAOS_TEMPLATE {
     () => {
         "chaos/netem.yaml"
@@ -130,6 +142,28 @@ impl K8sSwarm {
         ))
     }
 
+    fn create_network_group_partition_template(
+        &self,
+        swarm_network_group_partition: &SwarmNetworkGroupPartition,
+    ) -> Result<String> {
+        let mut network_chaos_specs = vec![];
+
+        for group_partition in &swarm_network_group_partition.group_network_partitions {
+            let source_instance_labels = self.get_instance_labels(&group_partition.source_nodes);
+            let target_instance_labels = self.get_instance_labels(&group_partition.target_nodes);
+
+            network_chaos_specs.push(format!(
+                include_str!(GROUP_PARTITION_NETWORK_CHAOS_TEMPLATE!()),
+                name = &group_partition.name,
+                namespace = self.kube_namespace,
+                instance_labels = &source_instance_labels,
+                target_instance_labels = &target_instance_labels,
+            ));
+        }
+
+        Ok(network_chaos_specs.join("\n---\n"))
+    }
+
     fn create_network_bandwidth_template(
         &self,
         swarm_network_bandwidth: &SwarmNetworkBandwidth,
@@ -161,6 +195,30 @@ impl K8sSwarm {
         ))
     }
 
+    fn create_network_group_loss_template(
+        &self,
+        swarm_network_group_loss: &SwarmNetworkGroupLoss,
+    ) -> Result<String> {
+        let mut network_chaos_specs = vec![];
+
+        for group_loss in &swarm_network_group_loss.group_network_losses {
+            let source_instance_labels = self.get_instance_labels(&group_loss.source_nodes);
+            let target_instance_labels = self.get_instance_labels(&group_loss.target_nodes);
+
+            network_chaos_specs.push(format!(
+                include_str!(GROUP_NETWORK_LOSS_CHAOS_TEMPLATE!()),
+                name = &group_loss.name,
+                namespace = self.kube_namespace,
+                loss_percentage = group_loss.loss_percentage,
+                correlation_percentage = group_loss.correlation_percentage,
+                instance_labels = &source_instance_labels,
+                target_instance_labels = &target_instance_labels,
+            ));
+        }
+
+        Ok(network_chaos_specs.join("\n---\n"))
+    }
+
     fn create_netem_template(&self, swarm_netem: &SwarmNetEm) -> Result<String> {
         let mut network_chaos_specs = vec![];
 
@@ -217,6 +275,8 @@ impl K8sSwarm {
             SwarmChaos::Loss(c) => self.create_network_loss_template(c),
             SwarmChaos::NetEm(c) => self.create_netem_template(c),
             SwarmChaos::CpuStress(c) => self.create_cpu_stress_template(c),
+            SwarmChaos::GroupPartition(c) => self.create_network_group_partition_template(c),
+            SwarmChaos::GroupLoss(c) => self.create_network_group_loss_template(c),
         }
     }
 
@@ -3,9 +3,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    backend::k8s::stateful_set, get_free_port, scale_stateful_set_replicas, FullNode,
-    HealthCheckError, Node, NodeExt, Result, Validator, Version, KUBECTL_BIN, LOCALHOST,
-    NODE_METRIC_PORT, REST_API_HAPROXY_SERVICE_PORT, REST_API_SERVICE_PORT,
+    backend::{k8s::stateful_set, CONSENSUS_DB_NAME},
+    get_free_port, scale_stateful_set_replicas, FullNode, HealthCheckError, Node, NodeExt, Result,
+    Validator, Version, KUBECTL_BIN, LOCALHOST, NODE_METRIC_PORT, REST_API_HAPROXY_SERVICE_PORT,
+    REST_API_SERVICE_PORT,
 };
 use anyhow::{anyhow, format_err};
 use aptos_config::config::NodeConfig;
@@ -201,6 +202,37 @@ impl Node for K8sNode {
         Ok(())
     }
 
+    async fn clear_consensus_db(&mut self) -> Result<()> {
+        // Remove the consensus DB files
+        let consensus_db_path = format!("{}/db/{}", APTOS_DATA_DIR, CONSENSUS_DB_NAME);
+        let delete_consensus_db_path = [
+            "-n",
+            self.namespace(),
+            "exec",
+            &format!("sts/{}", self.stateful_set_name()),
+            "--",
+            "rm",
+            "-rf",
+            &consensus_db_path,
+        ];
+        info!("{:?}", delete_consensus_db_path);
+        let cleanup_output = Command::new(KUBECTL_BIN)
+            .stdout(Stdio::inherit())
+            .args(delete_consensus_db_path)
+            .output()
+            .expect("failed to clear node consensus db");
+        assert!(
+            cleanup_output.status.success(),
+            "{}",
+            String::from_utf8(cleanup_output.stderr).unwrap()
+        );
+
+        // Stop the node to clear buffers (this must be done after removing the files)
+        self.stop().await?;
+
+        Ok(())
+    }
+
     fn config(&self) -> &NodeConfig {
         todo!()
     }
@@ -2,7 +2,10 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{FullNode, HealthCheckError, LocalVersion, Node, NodeExt, Validator, Version};
+use crate::{
+    backend::CONSENSUS_DB_NAME, FullNode, HealthCheckError, LocalVersion, Node, NodeExt, Validator,
+    Version,
+};
 use anyhow::{anyhow, ensure, Context, Result};
 use aptos_config::{
     config::{NodeConfig, SECURE_STORAGE_FILENAME},
@@ -355,6 +358,24 @@ impl Node for LocalNode {
         Ok(())
     }
 
+    async fn clear_consensus_db(&mut self) -> Result<()> {
+        // Stop the node (so that the consensus DB isn't in use)
+        self.stop();
+
+        // Remove the consensus DB files
+        let consensus_db_path = self.config().storage.dir().join(CONSENSUS_DB_NAME);
+        debug!(
+            "Deleting the consensus db path ({:?}) for node {:?}",
+            consensus_db_path.as_path(),
+            self.name
+        );
+        fs::remove_dir_all(consensus_db_path)
+            .map_err(anyhow::Error::from)
+            .context("Failed to delete consensus_db_path")?;
+
+        Ok(())
+    }
+
     async fn health_check(&mut self) -> Result<(), HealthCheckError> {
         self.health_check().await
     }
@@ -2,6 +2,9 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+// The name of the consensus DB directory (i.e., aptos_consensus::CONSENSUS_DB_NAME)
+const CONSENSUS_DB_NAME: &str = "consensus_db";
+
 mod local;
 pub use local::{LocalNode, *};
 
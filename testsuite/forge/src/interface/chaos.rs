@@ -12,6 +12,8 @@ pub enum SwarmChaos {
     Loss(SwarmNetworkLoss),
     NetEm(SwarmNetEm),
     CpuStress(SwarmCpuStress),
+    GroupPartition(SwarmNetworkGroupPartition),
+    GroupLoss(SwarmNetworkGroupLoss),
 }
 
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
@@ -46,6 +48,26 @@ impl Display for SwarmNetworkPartition {
     }
 }
 
+/// Partitions groups of nodes from each other (e.g., to split the validators
+/// into a majority and minority group)
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct SwarmNetworkGroupPartition {
+    pub group_network_partitions: Vec<GroupNetworkPartition>,
+}
+
+impl Display for SwarmNetworkGroupPartition {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Partition groups {:?}", self.group_network_partitions)
+    }
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct GroupNetworkPartition {
+    pub name: String,
+    pub source_nodes: Vec<PeerId>,
+    pub target_nodes: Vec<PeerId>,
+}
+
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
 pub struct SwarmNetworkBandwidth {
     pub group_network_bandwidths: Vec<GroupNetworkBandwidth>,
@@ -82,6 +104,28 @@ impl Display for SwarmNetworkLoss {
     }
 }
 
+/// Drops packets between groups of nodes. Unlike `SwarmNetworkLoss`, the loss is
+/// asymmetric: only the packets sent from the source nodes to the target nodes are lost.
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct SwarmNetworkGroupLoss {
+    pub group_network_losses: Vec<GroupNetworkLoss>,
+}
+
+impl Display for SwarmNetworkGroupLoss {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Loss between groups {:?}", self.group_network_losses)
+    }
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct GroupNetworkLoss {
+    pub name: String,
+    pub source_nodes: Vec<PeerId>,
+    pub target_nodes: Vec<PeerId>,
+    pub loss_percentage: u64,
+    pub correlation_percentage: u64,
+}
+
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
 pub struct SwarmNetEm {
     pub group_netems: Vec<GroupNetEm>,
@@ -67,6 +67,10 @@ pub trait Node: Send + Sync {
     /// Clears this Node's Storage. This stops the node as well
     async fn clear_storage(&mut self) -> Result<()>;
 
+    /// Clears this Node's consensus DB (the rest of the storage is kept).
+    /// This stops the node as well.
+    async fn clear_consensus_db(&mut self) -> Result<()>;
+
     async fn health_check(&mut self) -> Result<(), HealthCheckError>;
 
     fn counter(&self, counter: &str, port: u64) -> Result<f64>;
@@ -97,6 +97,17 @@ pub enum LatencyBreakdownSlice {
     ConsensusProposalToOrdered,
     ConsensusOrderedToCommit,
     ConsensusProposalToCommit,
+    // Per-stage slices, to catch regressions in a specific stage of the pipeline
+    /// Time from a txn entering mempool to it being pulled by consensus
+    MempoolWait,
+    /// Time spent waiting for quorum store batches before execution
+    QsBatchWait,
+    /// Average time between consecutive consensus rounds
+    ConsensusRoundTime,
+    /// Time spent executing a block
+    Execution,
+    /// Time spent committing blocks to storage
+    Commit,
 }
 
 #[derive(Clone, Debug)]
@@ -118,6 +129,16 @@ impl LatencyBreakdown {
     }
 }
 
+/// Returns the PromQL query for the average (over 1m) of the given histogram,
+/// aggregated across all validators.
+fn validator_histogram_avg_query(histogram: &str, labels: &str) -> String {
+    format!(
+        r#"sum(rate({histogram}_sum{{role=~"validator"{labels}}}[1m])) / sum(rate({histogram}_count{{role=~"validator"{labels}}}[1m]))"#,
+        histogram = histogram,
+        labels = labels,
+    )
+}
+
 pub async fn fetch_latency_breakdown(
     swarm: &dyn Swarm,
     start_time: u64,
@@ -127,79 +148,65 @@ pub async fn fetch_latency_breakdown(
     let start_time_adjusted = start_time + 60;
     let consensus_proposal_to_ordered_query = r#"quantile(0.67, rate(aptos_consensus_block_tracing_sum{role=~"validator", stage="ordered"}[1m]) / rate(aptos_consensus_block_tracing_count{role=~"validator", stage="ordered"}[1m]))"#;
     let consensus_proposal_to_commit_query = r#"quantile(0.67, rate(aptos_consensus_block_tracing_sum{role=~"validator", stage="committed"}[1m]) / rate(aptos_consensus_block_tracing_count{role=~"validator", stage="committed"}[1m]))"#;
-
-    let qs_batch_to_pos_query = r#"sum(rate(quorum_store_batch_to_PoS_duration_sum{role=~"validator"}[1m])) / sum(rate(quorum_store_batch_to_PoS_duration_count{role=~"validator"}[1m]))"#;
-    let qs_pos_to_proposal_query = r#"sum(rate(quorum_store_pos_to_pull_sum{role=~"validator"}[1m])) / sum(rate(quorum_store_pos_to_pull_count{role=~"validator"}[1m]))"#;
-
-    let consensus_proposal_to_ordered_samples = swarm
-        .query_range_metrics(
-            consensus_proposal_to_ordered_query,
-            start_time_adjusted as i64,
-            end_time as i64,
-            None,
-        )
-        .await?;
-
-    let consensus_proposal_to_commit_samples = swarm
-        .query_range_metrics(
-            consensus_proposal_to_commit_query,
-            start_time_adjusted as i64,
-            end_time as i64,
-            None,
-        )
-        .await?;
-
-    let consensus_ordered_to_commit_samples = swarm
-        .query_range_metrics(
-            &format!(
+    // The round time is the inverse of the round rate (DAG and Jolteon track rounds separately)
+    let consensus_round_time_query = r#"quantile(0.67, 1 / ((rate(aptos_consensus_dag_current_round{role=~"validator"}[1m]) > 0) or (rate(aptos_consensus_current_round{role=~"validator"}[1m]) > 0)))"#;
+
+    let queries = vec![
+        (
+            LatencyBreakdownSlice::QsBatchToPos,
+            validator_histogram_avg_query("quorum_store_batch_to_PoS_duration", ""),
+        ),
+        (
+            LatencyBreakdownSlice::QsPosToProposal,
+            validator_histogram_avg_query("quorum_store_pos_to_pull", ""),
+        ),
+        (
+            LatencyBreakdownSlice::ConsensusProposalToOrdered,
+            consensus_proposal_to_ordered_query.to_string(),
+        ),
+        (
+            LatencyBreakdownSlice::ConsensusOrderedToCommit,
+            format!(
                 "{} - {}",
                 consensus_proposal_to_commit_query, consensus_proposal_to_ordered_query
             ),
-            start_time_adjusted as i64,
-            end_time as i64,
-            None,
-        )
-        .await?;
-
-    let qs_batch_to_pos_samples = swarm
-        .query_range_metrics(
-            qs_batch_to_pos_query,
-            start_time_adjusted as i64,
-            end_time as i64,
-            None,
-        )
-        .await?;
-
-    let qs_pos_to_proposal_samples = swarm
-        .query_range_metrics(
-            qs_pos_to_proposal_query,
-            start_time_adjusted as i64,
-            end_time as i64,
-            None,
-        )
-        .await?;
+        ),
+        (
+            LatencyBreakdownSlice::ConsensusProposalToCommit,
+            consensus_proposal_to_commit_query.to_string(),
+        ),
+        (
+            LatencyBreakdownSlice::MempoolWait,
+            validator_histogram_avg_query(
+                "aptos_core_mempool_txn_commit_latency",
+                r#", stage="consensus_pulled""#,
+            ),
+        ),
+        (
+            LatencyBreakdownSlice::QsBatchWait,
+            validator_histogram_avg_query("aptos_consensus_batch_wait_duration", ""),
+        ),
+        (
+            LatencyBreakdownSlice::ConsensusRoundTime,
+            consensus_round_time_query.to_string(),
+        ),
+        (
+            LatencyBreakdownSlice::Execution,
+            validator_histogram_avg_query("aptos_executor_execute_block_seconds", ""),
+        ),
+        (
+            LatencyBreakdownSlice::Commit,
+            validator_histogram_avg_query("aptos_executor_commit_blocks_seconds", ""),
+        ),
+    ];
 
     let mut samples = BTreeMap::new();
-    samples.insert(
-        LatencyBreakdownSlice::QsBatchToPos,
-        MetricSamples::new(qs_batch_to_pos_samples),
-    );
-    samples.insert(
-        LatencyBreakdownSlice::QsPosToProposal,
-        MetricSamples::new(qs_pos_to_proposal_samples),
-    );
-    samples.insert(
-        LatencyBreakdownSlice::ConsensusProposalToOrdered,
-        MetricSamples::new(consensus_proposal_to_ordered_samples),
-    );
-    samples.insert(
-        LatencyBreakdownSlice::ConsensusOrderedToCommit,
-        MetricSamples::new(consensus_ordered_to_commit_samples),
-    );
-    samples.insert(
-        LatencyBreakdownSlice::ConsensusProposalToCommit,
-        MetricSamples::new(consensus_proposal_to_commit_samples),
-    );
+    for (slice, query) in queries {
+        let slice_samples = swarm
+            .query_range_metrics(&query, start_time_adjusted as i64, end_time as i64, None)
+            .await?;
+        samples.insert(slice, MetricSamples::new(slice_samples));
+    }
 
     Ok(LatencyBreakdown::new(samples))
 }
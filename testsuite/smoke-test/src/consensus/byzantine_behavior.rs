@@ -0,0 +1,109 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{smoke_test_environment::SwarmBuilder, txn_emitter::generate_traffic};
+use aptos_forge::{LocalSwarm, NodeExt, Swarm, SwarmExt, TransactionType};
+use aptos_types::PeerId;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+const MAX_WAIT_SECS: u64 = 60;
+const NUM_VALIDATORS: usize = 4;
+
+/// Creates a swarm where the first validator injects the byzantine behaviors (in every round)
+async fn create_swarm_with_byzantine_validator() -> LocalSwarm {
+    SwarmBuilder::new_local(NUM_VALIDATORS)
+        .with_aptos()
+        .with_init_config(Arc::new(|index, config, _| {
+            config.consensus.round_initial_timeout_ms = 1000;
+            config.consensus.round_timeout_backoff_exponent_base = 1.0;
+            if index == 0 {
+                let byzantine_behavior = &mut config.consensus.byzantine_behavior;
+                byzantine_behavior.equivocate_proposals = true;
+                byzantine_behavior.withhold_votes = true;
+                byzantine_behavior.timeout_vote_delay_ms = 500;
+            }
+        }))
+        .build()
+        .await
+}
+
+/// Verifies that the byzantine validator actually injected the byzantine behaviors, i.e., that
+/// the node was built with the byzantine behavior injection (the smoke-test feature).
+async fn assert_byzantine_behaviors_injected(swarm: &LocalSwarm) {
+    let byzantine_validator = swarm.validators().next().unwrap();
+    let num_injected = byzantine_validator
+        .get_metric_with_fields_i64(
+            "aptos_consensus_byzantine_behaviors_injected",
+            HashMap::new(),
+        )
+        .await
+        .unwrap()
+        .unwrap_or(0);
+    assert!(
+        num_injected > 0,
+        "No byzantine behaviors were injected. Was aptos-node built with the smoke-test feature?"
+    );
+}
+
+/// Verifies that all nodes agree on the hashes of the committed blocks
+async fn assert_no_forks(swarm: &LocalSwarm) {
+    let clients: Vec<_> = swarm.validators().map(|node| node.rest_client()).collect();
+    let mut min_block_height = u64::MAX;
+    for client in &clients {
+        let state = client.get_ledger_information().await.unwrap().into_inner();
+        min_block_height = min_block_height.min(state.block_height);
+    }
+
+    for height in 0..=min_block_height {
+        let mut block_hashes = vec![];
+        for client in &clients {
+            let block = client
+                .get_block_by_height(height, false)
+                .await
+                .unwrap()
+                .into_inner();
+            block_hashes.push(block.block_hash);
+        }
+        block_hashes.dedup();
+        assert_eq!(
+            block_hashes.len(),
+            1,
+            "Nodes disagree on the block at height {}: {:?}",
+            height,
+            block_hashes
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_byzantine_validator_safety_and_liveness() {
+    let mut swarm = create_swarm_with_byzantine_validator().await;
+    let honest_validators: Vec<PeerId> = swarm.validators().skip(1).map(|v| v.peer_id()).collect();
+
+    // The honest validators hold more than 2/3 of the voting power, so the chain must keep
+    // committing (the rounds of the byzantine proposer are expected to time out).
+    let txn_stat = generate_traffic(
+        &mut swarm,
+        &honest_validators,
+        Duration::from_secs(20),
+        100,
+        vec![vec![(
+            TransactionType::CoinTransfer {
+                invalid_transaction_ratio: 0,
+                sender_use_account_pool: false,
+            },
+            100,
+        )]],
+    )
+    .await
+    .unwrap();
+    println!("{:?}", txn_stat.rate());
+    assert_byzantine_behaviors_injected(&swarm).await;
+    assert!(txn_stat.committed > 30);
+
+    swarm
+        .wait_for_all_nodes_to_catchup(Duration::from_secs(MAX_WAIT_SECS))
+        .await
+        .unwrap();
+    assert_no_forks(&swarm).await;
+}
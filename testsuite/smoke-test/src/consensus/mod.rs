@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+mod byzantine_behavior;
 mod consensus_fault_tolerance;
 mod consensus_only;
 mod consensusdb_recovery;
@@ -0,0 +1,390 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::NetworkLoadTest;
+use anyhow::{bail, Context};
+use aptos_forge::{
+    GroupNetworkPartition, NetworkContext, NetworkTest, Result, Swarm, SwarmChaos,
+    SwarmNetworkGroupPartition, Test, TestReport, Validator,
+};
+use aptos_logger::info;
+use aptos_sdk::types::PeerId;
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+use tokio::runtime::Runtime;
+
+/// A single fault applied to the validators (identified by their index in the swarm)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FaultAction {
+    /// Stops the validator (it stays down until it is started again)
+    KillValidator(usize),
+    /// Starts the (stopped) validator again
+    StartValidator(usize),
+    /// Stops the validator, wipes its consensus DB and starts it again.
+    /// Note: the validator must be running when the fault is applied.
+    RestartWithWipedConsensusDb(usize),
+    /// Partitions the two validator groups from each other
+    PartitionGroups {
+        partition_id: usize,
+        group_a: Vec<usize>,
+        group_b: Vec<usize>,
+    },
+    /// Heals the partition with the given id
+    HealPartition { partition_id: usize },
+}
+
+impl FaultAction {
+    /// Returns the indices of the validators affected by the fault
+    fn get_validator_indices(&self) -> Vec<usize> {
+        match self {
+            FaultAction::KillValidator(index)
+            | FaultAction::StartValidator(index)
+            | FaultAction::RestartWithWipedConsensusDb(index) => vec![*index],
+            FaultAction::PartitionGroups {
+                group_a, group_b, ..
+            } => group_a.iter().chain(group_b.iter()).copied().collect(),
+            FaultAction::HealPartition { .. } => vec![],
+        }
+    }
+}
+
+/// A fault, together with the time (relative to the start of the test) it is applied at
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FaultStep {
+    pub time: Duration,
+    pub action: FaultAction,
+}
+
+/// A declarative schedule of timed faults. Suites compose the schedule
+/// using the builder methods, for example:
+///
+/// ```ignore
+/// FaultSchedule::new()
+///     .kill_validator_between(Duration::from_secs(120), Duration::from_secs(240), 0)
+///     .partition_groups(
+///         Duration::from_secs(300),
+///         Duration::from_secs(420),
+///         vec![1, 2, 3],
+///         vec![4, 5, 6, 7, 8, 9],
+///     )
+///     .restart_with_wiped_consensus_db(Duration::from_secs(480), 4)
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FaultSchedule {
+    steps: Vec<FaultStep>,
+    num_partitions: usize,
+}
+
+impl FaultSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the validator at the given time
+    pub fn kill_validator(self, at: Duration, index: usize) -> Self {
+        self.add_step(at, FaultAction::KillValidator(index))
+    }
+
+    /// Starts the (stopped) validator at the given time
+    pub fn start_validator(self, at: Duration, index: usize) -> Self {
+        self.add_step(at, FaultAction::StartValidator(index))
+    }
+
+    /// Stops the validator for the given time range
+    pub fn kill_validator_between(self, from: Duration, to: Duration, index: usize) -> Self {
+        self.kill_validator(from, index).start_validator(to, index)
+    }
+
+    /// Restarts the validator with a wiped consensus DB at the given time
+    pub fn restart_with_wiped_consensus_db(self, at: Duration, index: usize) -> Self {
+        self.add_step(at, FaultAction::RestartWithWipedConsensusDb(index))
+    }
+
+    /// Partitions the validator groups from each other for the given time range
+    pub fn partition_groups(
+        mut self,
+        from: Duration,
+        to: Duration,
+        group_a: Vec<usize>,
+        group_b: Vec<usize>,
+    ) -> Self {
+        let partition_id = self.num_partitions;
+        self.num_partitions += 1;
+        self.add_step(from, FaultAction::PartitionGroups {
+            partition_id,
+            group_a,
+            group_b,
+        })
+        .add_step(to, FaultAction::HealPartition { partition_id })
+    }
+
+    fn add_step(mut self, time: Duration, action: FaultAction) -> Self {
+        self.steps.push(FaultStep { time, action });
+        self
+    }
+
+    /// Returns the fault steps ordered by time. Steps
+    /// scheduled at the same time keep the order they were added in.
+    pub fn get_ordered_steps(&self) -> Vec<FaultStep> {
+        let mut steps = self.steps.clone();
+        steps.sort_by_key(|step| step.time);
+        steps
+    }
+
+    /// Verifies that the schedule can be applied to a swarm with
+    /// the given number of validators, within the given test duration.
+    pub fn validate(&self, num_validators: usize, duration: Duration) -> Result<()> {
+        for step in &self.steps {
+            // Verify the step is scheduled within the test
+            if step.time > duration {
+                bail!(
+                    "The fault step {:?} is scheduled after the end of the test ({}s)!",
+                    step,
+                    duration.as_secs()
+                );
+            }
+
+            // Verify the step only affects existing validators
+            let indices = step.action.get_validator_indices();
+            if let Some(index) = indices.iter().find(|index| **index >= num_validators) {
+                bail!(
+                    "The fault step {:?} affects a missing validator: {}. Number of validators: {}",
+                    step,
+                    index,
+                    num_validators
+                );
+            }
+
+            // Verify the partitioned groups are non-empty and disjoint
+            if let FaultAction::PartitionGroups {
+                group_a, group_b, ..
+            } = &step.action
+            {
+                let unique_indices: HashSet<_> = indices.iter().collect();
+                if group_a.is_empty() || group_b.is_empty() || unique_indices.len() != indices.len()
+                {
+                    bail!(
+                        "The partitioned groups must be non-empty and disjoint! Step: {:?}",
+                        step
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies the fault schedule to the validators (while the load is running). Once
+/// the schedule is complete, any remaining faults are healed (i.e., stopped
+/// validators are started and partitions are removed), so that the success
+/// criteria can verify that all nodes catch up again.
+pub struct FaultScheduleTest {
+    pub schedule: FaultSchedule,
+}
+
+impl Test for FaultScheduleTest {
+    fn name(&self) -> &'static str {
+        "network::fault-schedule-test"
+    }
+}
+
+impl NetworkLoadTest for FaultScheduleTest {
+    fn test(
+        &self,
+        swarm: &mut dyn Swarm,
+        report: &mut TestReport,
+        duration: Duration,
+    ) -> Result<()> {
+        let runtime = Runtime::new().unwrap();
+        let start = Instant::now();
+
+        // Verify the schedule before injecting any faults
+        let validators: Vec<_> = swarm.validators().map(|v| v.peer_id()).collect();
+        self.schedule.validate(validators.len(), duration)?;
+
+        // Apply each fault step at its scheduled time
+        let mut fault_state = FaultState::default();
+        for step in self.schedule.get_ordered_steps() {
+            std::thread::sleep(step.time.saturating_sub(start.elapsed()));
+
+            let msg = format!(
+                "Applying fault at {}s: {:?}",
+                start.elapsed().as_secs(),
+                step.action
+            );
+            info!("{}", msg);
+            report.report_text(msg);
+            runtime
+                .block_on(fault_state.apply(swarm, &validators, &step.action))
+                .context(format!("Failed to apply the fault step: {:?}", step))?;
+        }
+
+        // Keep the load running for the rest of the test
+        std::thread::sleep(duration.saturating_sub(start.elapsed()));
+
+        // Heal the remaining faults
+        runtime
+            .block_on(fault_state.heal(swarm))
+            .context("Failed to heal the remaining faults")?;
+        Ok(())
+    }
+}
+
+impl NetworkTest for FaultScheduleTest {
+    fn run(&self, ctx: &mut NetworkContext<'_>) -> Result<()> {
+        <dyn NetworkLoadTest>::run(self, ctx)
+    }
+}
+
+/// Tracks the faults that are currently applied to the swarm
+#[derive(Default)]
+struct FaultState {
+    stopped_validators: HashSet<PeerId>,
+    active_partitions: HashMap<usize, SwarmChaos>,
+}
+
+impl FaultState {
+    /// Applies the given fault to the swarm
+    async fn apply(
+        &mut self,
+        swarm: &mut dyn Swarm,
+        validators: &[PeerId],
+        action: &FaultAction,
+    ) -> Result<()> {
+        match action {
+            FaultAction::KillValidator(index) => {
+                let peer_id = validators[*index];
+                get_validator(swarm, peer_id)?.stop().await?;
+                self.stopped_validators.insert(peer_id);
+            },
+            FaultAction::StartValidator(index) => {
+                let peer_id = validators[*index];
+                get_validator(swarm, peer_id)?.start().await?;
+                self.stopped_validators.remove(&peer_id);
+            },
+            FaultAction::RestartWithWipedConsensusDb(index) => {
+                let peer_id = validators[*index];
+                let validator = get_validator(swarm, peer_id)?;
+                validator.clear_consensus_db().await?;
+                validator.start().await?;
+                self.stopped_validators.remove(&peer_id);
+            },
+            FaultAction::PartitionGroups {
+                partition_id,
+                group_a,
+                group_b,
+            } => {
+                let chaos = SwarmChaos::GroupPartition(SwarmNetworkGroupPartition {
+                    group_network_partitions: vec![GroupNetworkPartition {
+                        name: format!("forge-fault-partition-{}", partition_id),
+                        source_nodes: group_a.iter().map(|index| validators[*index]).collect(),
+                        target_nodes: group_b.iter().map(|index| validators[*index]).collect(),
+                    }],
+                });
+                swarm.inject_chaos(chaos.clone()).await?;
+                self.active_partitions.insert(*partition_id, chaos);
+            },
+            FaultAction::HealPartition { partition_id } => {
+                if let Some(chaos) = self.active_partitions.remove(partition_id) {
+                    swarm.remove_chaos(chaos).await?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Heals all remaining faults
+    async fn heal(&mut self, swarm: &mut dyn Swarm) -> Result<()> {
+        for peer_id in self.stopped_validators.drain() {
+            info!("Starting stopped validator: {}", peer_id);
+            get_validator(swarm, peer_id)?.start().await?;
+        }
+        for (_, chaos) in self.active_partitions.drain() {
+            info!("Removing active partition: {:?}", chaos);
+            swarm.remove_chaos(chaos).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the (mutable) validator with the given peer id
+fn get_validator(swarm: &mut dyn Swarm, peer_id: PeerId) -> Result<&mut dyn Validator> {
+    swarm
+        .validator_mut(peer_id)
+        .context(format!("Validator {} is missing from the swarm", peer_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FaultAction, FaultSchedule, FaultStep};
+    use std::time::Duration;
+
+    #[test]
+    fn test_fault_schedule_ordering() {
+        // Create a schedule with out of order steps
+        let schedule = FaultSchedule::new()
+            .kill_validator_between(Duration::from_secs(120), Duration::from_secs(240), 0)
+            .partition_groups(
+                Duration::from_secs(60),
+                Duration::from_secs(120),
+                vec![1],
+                vec![2, 3],
+            )
+            .restart_with_wiped_consensus_db(Duration::from_secs(30), 3);
+
+        // Verify that the steps are ordered by time (and insertion order)
+        let actions: Vec<_> = schedule
+            .get_ordered_steps()
+            .into_iter()
+            .map(|FaultStep { time, action }| (time.as_secs(), action))
+            .collect();
+        assert_eq!(actions, vec![
+            (30, FaultAction::RestartWithWipedConsensusDb(3)),
+            (60, FaultAction::PartitionGroups {
+                partition_id: 0,
+                group_a: vec![1],
+                group_b: vec![2, 3],
+            }),
+            (120, FaultAction::KillValidator(0)),
+            (120, FaultAction::HealPartition { partition_id: 0 }),
+            (240, FaultAction::StartValidator(0)),
+        ]);
+    }
+
+    #[test]
+    fn test_fault_schedule_validation() {
+        let duration = Duration::from_secs(300);
+
+        // Verify that a valid schedule passes validation
+        let schedule = FaultSchedule::new()
+            .kill_validator(Duration::from_secs(100), 3)
+            .partition_groups(
+                Duration::from_secs(100),
+                Duration::from_secs(300),
+                vec![0],
+                vec![1, 2],
+            );
+        assert!(schedule.validate(4, duration).is_ok());
+
+        // Verify that steps after the end of the test fail validation
+        let schedule = FaultSchedule::new().kill_validator(Duration::from_secs(301), 0);
+        assert!(schedule.validate(4, duration).is_err());
+
+        // Verify that steps affecting missing validators fail validation
+        let schedule = FaultSchedule::new().kill_validator(Duration::from_secs(100), 4);
+        assert!(schedule.validate(4, duration).is_err());
+
+        // Verify that empty or overlapping partition groups fail validation
+        for (group_a, group_b) in [(vec![], vec![1]), (vec![0, 1], vec![1, 2])] {
+            let schedule = FaultSchedule::new().partition_groups(
+                Duration::from_secs(100),
+                Duration::from_secs(200),
+                group_a,
+                group_b,
+            );
+            assert!(schedule.validate(4, duration).is_err());
+        }
+    }
+}
@@ -5,6 +5,7 @@
 pub mod compatibility_test;
 pub mod consensus_reliability_tests;
 pub mod dag_onchain_enable_test;
+pub mod fault_schedule_test;
 pub mod forge_setup_test;
 pub mod framework_upgrade;
 pub mod fullnode_reboot_stress_test;
@@ -12,6 +13,7 @@ pub mod load_vs_perf_benchmark;
 pub mod modifiers;
 pub mod multi_region_network_test;
 pub mod network_bandwidth_test;
+pub mod network_chaos_recovery_test;
 pub mod network_loss_test;
 pub mod network_partition_test;
 pub mod partial_nodes_down_test;
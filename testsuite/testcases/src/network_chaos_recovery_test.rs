@@ -0,0 +1,128 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::NetworkLoadTest;
+use anyhow::Context;
+use aptos_forge::{
+    GroupNetworkLoss, GroupNetworkPartition, NetworkContext, NetworkTest, Result, Swarm,
+    SwarmChaos, SwarmExt, SwarmNetworkGroupLoss, SwarmNetworkGroupPartition, Test, TestReport,
+};
+use aptos_logger::info;
+use aptos_sdk::types::PeerId;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+/// The chaos injected between the validator groups
+#[derive(Clone, Copy, Debug)]
+pub enum GroupChaos {
+    /// Drops all packets between the groups
+    Partition,
+    /// Drops a percentage of the packets sent from the minority group to the majority group
+    AsymmetricLoss {
+        loss_percentage: u64,
+        correlation_percentage: u64,
+    },
+}
+
+/// Splits the validators into a minority and a majority group, and injects chaos between
+/// the groups for the given duration. Once the chaos is removed, the test verifies that
+/// all nodes make progress again within the given bound.
+pub struct NetworkChaosRecoveryTest {
+    /// The percentage of validators in the minority group
+    pub minority_percentage: u64,
+    pub chaos: GroupChaos,
+    /// How long the chaos is injected for
+    pub chaos_duration: Duration,
+    /// How long all nodes have to make progress once the chaos is removed
+    pub max_recovery_duration: Duration,
+}
+
+impl NetworkChaosRecoveryTest {
+    /// Returns the swarm chaos to inject between the minority and majority groups
+    fn create_chaos(&self, minority: Vec<PeerId>, majority: Vec<PeerId>) -> SwarmChaos {
+        match self.chaos {
+            GroupChaos::Partition => SwarmChaos::GroupPartition(SwarmNetworkGroupPartition {
+                group_network_partitions: vec![GroupNetworkPartition {
+                    name: "forge-minority-partition".to_string(),
+                    source_nodes: minority,
+                    target_nodes: majority,
+                }],
+            }),
+            GroupChaos::AsymmetricLoss {
+                loss_percentage,
+                correlation_percentage,
+            } => SwarmChaos::GroupLoss(SwarmNetworkGroupLoss {
+                group_network_losses: vec![GroupNetworkLoss {
+                    name: "forge-minority-loss".to_string(),
+                    source_nodes: minority,
+                    target_nodes: majority,
+                    loss_percentage,
+                    correlation_percentage,
+                }],
+            }),
+        }
+    }
+}
+
+impl Test for NetworkChaosRecoveryTest {
+    fn name(&self) -> &'static str {
+        "network::chaos-recovery-test"
+    }
+}
+
+impl NetworkLoadTest for NetworkChaosRecoveryTest {
+    fn test(
+        &self,
+        swarm: &mut dyn Swarm,
+        report: &mut TestReport,
+        duration: Duration,
+    ) -> Result<()> {
+        let runtime = Runtime::new().unwrap();
+        let start = Instant::now();
+
+        // Split the validators into the minority and majority groups
+        let validators: Vec<_> = swarm.validators().map(|v| v.peer_id()).collect();
+        let num_minority = (validators.len() * self.minority_percentage as usize / 100).max(1);
+        let (minority, majority) = validators.split_at(num_minority);
+
+        // Inject the chaos between the groups
+        let chaos = self.create_chaos(minority.to_vec(), majority.to_vec());
+        runtime.block_on(swarm.inject_chaos(chaos.clone()))?;
+        let msg = format!(
+            "Injected {:?} between {} minority and {} majority validators for {}s",
+            self.chaos,
+            minority.len(),
+            majority.len(),
+            self.chaos_duration.as_secs()
+        );
+        info!("{}", msg);
+        report.report_text(msg);
+        std::thread::sleep(self.chaos_duration);
+
+        // Remove the chaos, and verify that all nodes make progress again
+        runtime.block_on(swarm.remove_chaos(chaos))?;
+        let recovery_start = Instant::now();
+        runtime
+            .block_on(swarm.wait_for_all_nodes_to_catchup_to_next(self.max_recovery_duration))
+            .context(format!(
+                "Nodes failed to make progress within {}s of removing the chaos",
+                self.max_recovery_duration.as_secs()
+            ))?;
+        let msg = format!(
+            "All nodes made progress {:.2}s after removing the chaos",
+            recovery_start.elapsed().as_secs_f64()
+        );
+        info!("{}", msg);
+        report.report_text(msg);
+
+        // Keep the load running for the rest of the test
+        std::thread::sleep(duration.saturating_sub(start.elapsed()));
+        Ok(())
+    }
+}
+
+impl NetworkTest for NetworkChaosRecoveryTest {
+    fn run(&self, ctx: &mut NetworkContext<'_>) -> Result<()> {
+        <dyn NetworkLoadTest>::run(self, ctx)
+    }
+}
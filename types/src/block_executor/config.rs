@@ -14,6 +14,25 @@ pub struct BlockExecutorLocalConfig {
     // If true, we will discard the failed blocks and continue with the next block.
     // (allow_fallback needs to be set)
     pub discard_failed_blocks: bool,
+    // If specified, a transaction incarnation whose execution takes longer than the given
+    // wall-clock budget (in milliseconds) halts the parallel execution, so that the block
+    // is re-executed sequentially (allow_fallback needs to be set).
+    pub txn_execution_time_budget_ms: Option<u64>,
+    // Thresholds for serializing the resource groups of committed transaction outputs.
+    pub resource_group_serialization: ResourceGroupSerializationConfig,
+}
+
+/// Thresholds for serializing resource groups when materializing transaction outputs.
+/// The serialized groups are byte-identical to a plain bcs serialization for any thresholds
+/// (so that the write sets are deterministic across validators with different configs).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResourceGroupSerializationConfig {
+    /// If set, groups with more resource bytes are split into chunks of (roughly)
+    /// the given number of bytes, which are serialized in parallel.
+    pub split_threshold_bytes: Option<usize>,
+    /// If set, groups with fewer resource bytes (that are written by the same
+    /// transaction) are merged and serialized into a single shared buffer.
+    pub merge_threshold_bytes: Option<usize>,
 }
 
 /// Configuration from on-chain configuration, that is
@@ -73,6 +92,8 @@ impl BlockExecutorConfig {
                 concurrency_level,
                 allow_fallback: true,
                 discard_failed_blocks: false,
+                txn_execution_time_budget_ms: None,
+                resource_group_serialization: ResourceGroupSerializationConfig::default(),
             },
             onchain: BlockExecutorConfigFromOnchain::new_no_block_limit(),
         }
@@ -87,6 +108,8 @@ impl BlockExecutorConfig {
                 concurrency_level,
                 allow_fallback: true,
                 discard_failed_blocks: false,
+                txn_execution_time_budget_ms: None,
+                resource_group_serialization: ResourceGroupSerializationConfig::default(),
             },
             onchain: BlockExecutorConfigFromOnchain::new_maybe_block_limit(maybe_block_gas_limit),
         }
@@ -20,6 +20,8 @@ pub struct MempoolStatus {
     pub code: MempoolStatusCode,
     /// optional message
     pub message: String,
+    /// optional structured reason for rejecting the transaction
+    pub rejection_reason: Option<MempoolRejectionReason>,
 }
 
 impl MempoolStatus {
@@ -27,6 +29,7 @@ impl MempoolStatus {
         Self {
             code,
             message: "".to_string(),
+            rejection_reason: None,
         }
     }
 
@@ -35,6 +38,12 @@ impl MempoolStatus {
         self.message = message;
         self
     }
+
+    /// Adds a rejection reason to the Mempool status.
+    pub fn with_rejection_reason(mut self, rejection_reason: MempoolRejectionReason) -> Self {
+        self.rejection_reason = Some(rejection_reason);
+        self
+    }
 }
 
 impl fmt::Display for MempoolStatus {
@@ -64,6 +73,10 @@ pub enum MempoolStatusCode {
     // transaction didn't pass vm_validation
     VmError = 5,
     UnknownStatus = 6,
+    // The submission source exceeded its submission quota
+    SubmissionQuotaExceeded = 7,
+    // The transaction group is invalid (e.g., the transactions are not sequential)
+    InvalidTransactionGroup = 8,
 }
 
 impl TryFrom<u64> for MempoolStatusCode {
@@ -78,6 +91,8 @@ impl TryFrom<u64> for MempoolStatusCode {
             4 => Ok(MempoolStatusCode::InvalidUpdate),
             5 => Ok(MempoolStatusCode::VmError),
             6 => Ok(MempoolStatusCode::UnknownStatus),
+            7 => Ok(MempoolStatusCode::SubmissionQuotaExceeded),
+            8 => Ok(MempoolStatusCode::InvalidTransactionGroup),
             _ => Err("invalid StatusCode"),
         }
     }
@@ -89,6 +104,30 @@ impl From<MempoolStatusCode> for u64 {
     }
 }
 
+/// A structured reason for why Mempool rejected a transaction. This allows
+/// clients to decide how (and if) a rejected transaction should be retried.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub enum MempoolRejectionReason {
+    // Mempool is full, and the transaction cannot displace any existing
+    // transactions (e.g., the fee is too low for the current load)
+    FeeTooLowForLoad,
+    // The account reached its max number of transactions in Mempool
+    AccountQuotaExceeded,
+    // Mempool is full, and the transaction would be parked (i.e., it is not ready)
+    ParkingLotFull,
+    // A different transaction with the same sequence number is already in Mempool
+    Duplicate,
+    // The transaction replaces one in Mempool, but doesn't increase the gas price
+    ReplacementUnderpriced,
+}
+
+impl fmt::Display for MempoolRejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 impl fmt::Display for MempoolStatusCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
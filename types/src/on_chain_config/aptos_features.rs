@@ -67,6 +67,10 @@ pub enum FeatureFlag {
     MAX_OBJECT_NESTING_CHECK = 53,
     KEYLESS_ACCOUNTS_WITH_PASSKEYS = 54,
     MULTISIG_V2_ENHANCEMENT = 55,
+    CONSENSUS_BATCH_RESPONSE_V2 = 56,
+    QUORUM_STORE_QUEUE_LENGTH_HINTS = 57,
+    CONSENSUS_BLOCK_RANGE_RETRIEVAL = 58,
+    CONSENSUS_EPOCH_STARTER_KIT = 59,
 }
 
 impl FeatureFlag {
@@ -120,6 +124,10 @@ impl FeatureFlag {
             FeatureFlag::MAX_OBJECT_NESTING_CHECK,
             FeatureFlag::KEYLESS_ACCOUNTS_WITH_PASSKEYS,
             FeatureFlag::MULTISIG_V2_ENHANCEMENT,
+            FeatureFlag::CONSENSUS_BATCH_RESPONSE_V2,
+            FeatureFlag::QUORUM_STORE_QUEUE_LENGTH_HINTS,
+            FeatureFlag::CONSENSUS_BLOCK_RANGE_RETRIEVAL,
+            FeatureFlag::CONSENSUS_EPOCH_STARTER_KIT,
         ]
     }
 }
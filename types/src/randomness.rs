@@ -123,7 +123,7 @@ impl OnChainConfig for PerBlockRandomness {
     const TYPE_IDENTIFIER: &'static str = "PerBlockRandomness";
 }
 
-#[derive(Clone, SilentDebug)]
+#[derive(SilentDebug)]
 pub struct RandKeys {
     // augmented secret / public key share of this validator, obtained from the DKG transcript of last epoch
     pub ask: ASK,